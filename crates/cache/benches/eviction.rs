@@ -0,0 +1,87 @@
+//! Compare eviction policies on a recorded trace
+//!
+//! ```sh
+//! CUENV_EVICTION_TRACE=trace.jsonl CUENV_EVICTION_BUDGET=1073741824 \
+//!     cargo bench -p cuenv-cache --bench eviction
+//! ```
+//!
+//! Without a trace a synthetic one is generated: a small set of expensive,
+//! frequently reused task outputs mixed with a long tail of cheap, large,
+//! one-off artifacts. Hit ratios are printed once; criterion then times the
+//! replay itself.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuenv_cache::eviction::{
+    compare_policies, create_eviction_policy, load_trace, replay_trace, TraceAccess,
+};
+use std::path::PathBuf;
+
+const MIB: u64 = 1024 * 1024;
+
+fn synthetic_trace() -> Vec<TraceAccess> {
+    let mut rng = fastrand::Rng::with_seed(0x5eed);
+    (0..20_000)
+        .map(|i| {
+            if rng.u8(..) < 160 {
+                let id = rng.u32(..64);
+                TraceAccess {
+                    key: format!("task-{id}"),
+                    size: (u64::from(id) % 8 + 1) * MIB,
+                    cost_ms: u64::from(id) * 500 + 1_000,
+                }
+            } else {
+                TraceAccess {
+                    key: format!("artifact-{i}"),
+                    size: rng.u64(16..64) * MIB,
+                    cost_ms: rng.u64(20..200),
+                }
+            }
+        })
+        .collect()
+}
+
+fn load() -> (Vec<TraceAccess>, u64) {
+    let trace = match std::env::var_os("CUENV_EVICTION_TRACE") {
+        Some(path) => load_trace(&PathBuf::from(path)).expect("failed to load eviction trace"),
+        None => synthetic_trace(),
+    };
+    let budget = std::env::var("CUENV_EVICTION_BUDGET")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(256 * MIB);
+    (trace, budget)
+}
+
+fn bench_eviction(c: &mut Criterion) {
+    let (trace, budget) = load();
+
+    println!(
+        "{:<6} {:>10} {:>10} {:>12} {:>10}",
+        "policy", "hit", "byte hit", "cost saved", "evictions"
+    );
+    for report in compare_policies(&trace, budget).expect("unknown eviction policy") {
+        println!(
+            "{:<6} {:>9.1}% {:>9.1}% {:>11.1}% {:>10}",
+            report.policy,
+            report.hit_ratio() * 100.0,
+            report.byte_hit_ratio() * 100.0,
+            report.cost_saved_ratio() * 100.0,
+            report.evictions
+        );
+    }
+
+    let mut group = c.benchmark_group("eviction_replay");
+    group.sample_size(10);
+    for name in ["lru", "lfu", "arc", "gdsf"] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let policy = create_eviction_policy(name, budget).expect("unknown eviction policy");
+                replay_trace(name, policy.as_ref(), &trace)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_eviction);
+criterion_main!(benches);
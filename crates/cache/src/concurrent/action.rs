@@ -5,14 +5,19 @@
 
 use super::ConcurrentCache;
 use crate::content_addressed_store::ContentAddressedStore;
+use crate::env_usage::{referenced_variables, EnvUsageCache};
+use crate::file_hashes::{FileHashCache, FileHashEntry};
+use crate::hashing::{split_package_input, PACKAGE_INPUT_PREFIX};
 use crate::keys::CacheKeyGenerator;
+use crate::manager::manifest::{ManifestEntry, RunLog};
+use crate::mode::VerifyMode;
 use crate::security::signing::{CacheSigner, SignedCacheEntry};
 use cuenv_core::{Error, Result};
 use cuenv_core::{TaskDefinition, TaskExecutionMode};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -33,6 +38,24 @@ pub struct ActionResult {
     pub executed_at: SystemTime,
     /// Duration of execution in milliseconds
     pub duration_ms: u64,
+    /// Environment selected with `-e` when the action ran
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// ULID of the task run that recorded the result
+    #[serde(default)]
+    pub run_id: Option<String>,
+    /// Until when a failed result may be reused; failures without it are
+    /// not cached at all
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+}
+
+impl ActionResult {
+    /// Whether the result may be reused at `now`: successes always are,
+    /// failures until they expire
+    pub fn is_reusable(&self, now: SystemTime) -> bool {
+        self.exit_code == 0 || self.expires_at.is_some_and(|expires_at| expires_at > now)
+    }
 }
 
 /// Action digest computation
@@ -53,10 +76,11 @@ pub struct ActionComponents {
     pub command: Option<String>,
     /// Working directory
     pub working_dir: PathBuf,
-    /// Environment variables that affect the action
-    pub env_vars: HashMap<String, String>,
+    /// Environment variables that affect the action; ordered, so the hash
+    /// is the same on every run
+    pub env_vars: BTreeMap<String, String>,
     /// Input file hashes (path -> hash)
-    pub input_files: HashMap<String, String>,
+    pub input_files: BTreeMap<String, String>,
     /// Task configuration hash
     pub config_hash: String,
 }
@@ -73,6 +97,12 @@ pub struct ActionCache {
     signer: Arc<CacheSigner>,
     /// Cache key generator with selective environment variable filtering
     key_generator: Arc<CacheKeyGenerator>,
+    /// Hashes of unchanged input files from earlier runs
+    file_hashes: FileHashCache,
+    /// Variables audited runs showed each task to use
+    env_usage: EnvUsageCache,
+    /// Entries stored or reused by each run, for manifests
+    run_log: RunLog,
 }
 
 impl ActionCache {
@@ -98,49 +128,112 @@ impl ActionCache {
             in_flight: Arc::new(DashMap::new()),
             signer,
             key_generator,
+            file_hashes: FileHashCache::new(cache_dir),
+            env_usage: EnvUsageCache::new(cache_dir),
+            run_log: RunLog::new(cache_dir),
         })
     }
 
     /// Compute action digest for a task
+    ///
+    /// Besides what the key filter keeps, the key covers `declared_vars`,
+    /// every variable the command references and those audited runs of the
+    /// task were seen to use.
+    ///
+    /// `pkg://` inputs are expanded in the directories of `package_dirs`
+    /// and keyed by package, so keys do not depend on where the workspace
+    /// is checked out.
     pub async fn compute_digest(
         &self,
         task_name: &str,
         task_definition: &TaskDefinition,
         working_dir: &Path,
         env_vars: HashMap<String, String>,
+        declared_vars: &BTreeSet<String>,
+        package_dirs: &HashMap<String, PathBuf>,
     ) -> Result<ActionDigest> {
-        // Filter environment variables using selective filtering
-        let filtered_env_vars = self.key_generator.filter_env_vars(task_name, &env_vars);
-
         let command = match &task_definition.execution_mode {
             TaskExecutionMode::Command { command } => Some(command.clone()),
             TaskExecutionMode::Script { content } => Some(content.clone()),
         };
 
+        // Filter environment variables using selective filtering, then add
+        // back the ones the task is known to depend on
+        let tracked: BTreeSet<String> = declared_vars
+            .iter()
+            .cloned()
+            .chain(
+                command
+                    .as_deref()
+                    .map(referenced_variables)
+                    .unwrap_or_default(),
+            )
+            .chain(self.env_usage.load(working_dir, task_name))
+            .collect();
+        let mut filtered_env_vars = self.key_generator.filter_env_vars(task_name, &env_vars);
+        filtered_env_vars.extend(tracked.iter().filter_map(|name| {
+            env_vars
+                .get(name)
+                .map(|value| (name.clone(), value.clone()))
+        }));
+
         let mut components = ActionComponents {
             task_name: task_name.to_string(),
             command,
             working_dir: working_dir.to_path_buf(),
-            env_vars: filtered_env_vars,
-            input_files: HashMap::new(),
+            env_vars: filtered_env_vars.into_iter().collect(),
+            input_files: BTreeMap::new(),
             config_hash: hash_task_definition(task_definition)?,
         };
 
-        // Hash input files
+        // Hash input files, reusing hashes of files that did not change
         if !task_definition.inputs.is_empty() {
-            for pattern in &task_definition.inputs {
-                let files = crate::hashing::expand_glob_pattern(pattern, working_dir)?;
+            let known = self.file_hashes.load();
+            let mut computed = Vec::new();
+            for input in &task_definition.inputs {
+                let (base_dir, pattern, key_prefix) = match split_package_input(input) {
+                    Some((package, pattern)) => {
+                        let dir = package_dirs.get(package).ok_or_else(|| {
+                            Error::configuration(format!(
+                                "Task '{task_name}' has input '{input}' of unknown package '{package}'"
+                            ))
+                        })?;
+                        (
+                            dir.as_path(),
+                            pattern,
+                            format!("{PACKAGE_INPUT_PREFIX}{package}/"),
+                        )
+                    }
+                    None => (working_dir, input.as_str(), String::new()),
+                };
+                let files = crate::hashing::expand_glob_pattern(pattern, base_dir)?;
                 for file in files {
-                    // Use streaming hash computation for large files
-                    let hash = compute_file_hash(&file).await?;
+                    let metadata = tokio::fs::metadata(&file)
+                        .await
+                        .map_err(|e| Error::file_system(&file, "read file metadata", e))?;
+                    let hash = match known.get(&file).filter(|entry| entry.matches(&metadata)) {
+                        Some(entry) => entry.hash.clone(),
+                        None => {
+                            // Use streaming hash computation for large files
+                            let hash = compute_file_hash(&file).await?;
+                            if let Some(entry) = FileHashEntry::new(&metadata, hash.clone()) {
+                                computed.push((file.clone(), entry));
+                            }
+                            hash
+                        }
+                    };
                     let relative_path = file
-                        .strip_prefix(working_dir)
+                        .strip_prefix(base_dir)
                         .unwrap_or(&file)
-                        .to_string_lossy()
-                        .to_string();
-                    components.input_files.insert(relative_path, hash);
+                        .to_string_lossy();
+                    components
+                        .input_files
+                        .insert(format!("{key_prefix}{relative_path}"), hash);
                 }
             }
+            if let Err(e) = self.file_hashes.store(computed) {
+                log::warn!("Failed to record input file hashes: {e}");
+            }
         }
 
         // Compute final digest
@@ -152,8 +245,38 @@ impl ActionCache {
         })
     }
 
+    /// The variables among `env_vars` the key filter keeps for `task_name`
+    pub fn filter_env_vars(
+        &self,
+        task_name: &str,
+        env_vars: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        self.key_generator.filter_env_vars(task_name, env_vars)
+    }
+
+    /// Remember variables an audited run of a task was seen to use
+    pub fn record_env_usage(
+        &self,
+        task_name: &str,
+        working_dir: &Path,
+        names: &BTreeSet<String>,
+    ) -> Result<()> {
+        self.env_usage.record(working_dir, task_name, names)
+    }
+
     /// Get cached action result from storage with signature verification
+    ///
+    /// Failures past their expiry are dropped instead.
     pub fn get_cached_action_result(&self, hash: &str) -> Option<ActionResult> {
+        let result = self.stored_action_result(hash)?;
+        if result.is_reusable(SystemTime::now()) {
+            return Some(result);
+        }
+        self.result_cache.remove(hash);
+        None
+    }
+
+    fn stored_action_result(&self, hash: &str) -> Option<ActionResult> {
         self.result_cache.get(hash).and_then(|cached| {
             // Deserialize signed cache entry from stdout field
             if let Some(stdout_bytes) = &cached.stdout {
@@ -196,6 +319,9 @@ impl ActionCache {
                 output_files: cached.output_files.clone(),
                 executed_at: cached.executed_at,
                 duration_ms: 0, // Not stored in CachedTaskResult
+                environment: None,
+                run_id: None,
+                expires_at: None,
             })
         })
     }
@@ -311,6 +437,13 @@ impl ActionCache {
             }
         };
 
+        // Failures are only cached when they are given an expiry
+        if !result.is_reusable(SystemTime::now()) {
+            self.in_flight.remove(&digest.hash);
+            notify.notify_waiters();
+            return Ok(result);
+        }
+
         // Cache the result with cryptographic signing
         let signed_result = self
             .signer
@@ -333,6 +466,9 @@ impl ActionCache {
 
         self.result_cache
             .insert(digest.hash.clone(), cached_result)?;
+        if let Err(e) = self.run_log.record(&ManifestEntry::new(digest, &result)) {
+            log::warn!("Failed to record cache entry in the run log: {e}");
+        }
 
         // Remove from in-flight and notify waiters
         self.in_flight.remove(&digest.hash);
@@ -341,6 +477,14 @@ impl ActionCache {
         Ok(result)
     }
 
+    /// Record that run `run_id` reused `result`, so its manifest lists it
+    pub fn record_reuse(&self, digest: &ActionDigest, result: &ActionResult, run_id: &str) {
+        let entry = ManifestEntry::reused(digest, result, run_id);
+        if let Err(e) = self.run_log.record(&entry) {
+            log::warn!("Failed to record reused cache entry in the run log: {e}");
+        }
+    }
+
     /// Store action outputs in CAS
     async fn store_outputs_in_cas(&self, mut result: ActionResult) -> Result<ActionResult> {
         // Store stdout if present
@@ -355,16 +499,69 @@ impl ActionCache {
             result.stderr_hash = Some(hash);
         }
 
-        // Store output files
-        let mut new_output_files = HashMap::new();
-        for (path, content_hash) in &result.output_files {
-            // In a real implementation, we'd read the file and store it
-            // For now, we'll assume the hash is already computed
-            new_output_files.insert(path.clone(), content_hash.clone());
+        // Output files were stored by `store_output_files` as the action
+        // ran, so `output_files` already holds their CAS hashes
+        Ok(result)
+    }
+
+    /// Store the files matching the output patterns of a task in the CAS
+    /// and return their CAS hashes by path relative to `working_dir`
+    ///
+    /// Files are streamed into the store, so large outputs are never held in
+    /// memory.
+    pub async fn store_output_files(
+        &self,
+        outputs: &[String],
+        working_dir: &Path,
+    ) -> Result<HashMap<String, String>> {
+        let mut output_files = HashMap::new();
+        for pattern in outputs {
+            for file in crate::hashing::expand_glob_pattern(pattern, working_dir)? {
+                let cas = Arc::clone(&self.cas);
+                let path = file.clone();
+                let hash = tokio::task::spawn_blocking(move || cas.store_file(&path))
+                    .await
+                    .map_err(|e| {
+                        Error::configuration(format!("Failed to store {}: {e}", file.display()))
+                    })??;
+                let relative_path = file
+                    .strip_prefix(working_dir)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .to_string();
+                output_files.insert(relative_path, hash);
+            }
         }
-        result.output_files = new_output_files;
+        Ok(output_files)
+    }
 
-        Ok(result)
+    /// Write the output files of a cached result back under `working_dir`,
+    /// checking each as `verify` says
+    ///
+    /// Fails on the first file that cannot be restored; its corrupted object
+    /// has been quarantined by then, see [`ContentAddressedStore::restore_file`].
+    pub async fn restore_output_files(
+        &self,
+        output_files: &HashMap<String, String>,
+        working_dir: &Path,
+        verify: VerifyMode,
+    ) -> Result<()> {
+        for (relative_path, hash) in output_files {
+            let cas = Arc::clone(&self.cas);
+            let dest = working_dir.join(relative_path);
+            let hash = hash.clone();
+            tokio::task::spawn_blocking(move || cas.restore_file(&hash, &dest, verify))
+                .await
+                .map_err(|e| {
+                    Error::configuration(format!("Failed to restore {relative_path}: {e}"))
+                })??;
+        }
+        Ok(())
+    }
+
+    /// Drop the cached result `hash`, so the action runs again
+    pub fn invalidate(&self, hash: &str) {
+        self.result_cache.remove(hash);
     }
 
     /// Get statistics
@@ -463,12 +660,26 @@ mod tests {
                 enabled: true,
                 key: None,
                 env_filter: None,
+                failure_ttl: None,
             },
             timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         };
 
         let digest = cache
-            .compute_digest("test", &task_definition, temp_dir.path(), HashMap::new())
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &HashMap::new(),
+            )
             .await
             .unwrap();
 
@@ -477,6 +688,181 @@ mod tests {
         assert_eq!(digest.components.command, Some("echo hello".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_digest_keys_package_inputs_by_package() {
+        let temp_dir = TempDir::new().unwrap();
+        let lib_dir = temp_dir.path().join("projects").join("lib");
+        std::fs::create_dir_all(lib_dir.join("src")).unwrap();
+        std::fs::write(lib_dir.join("src").join("lib.rs"), "fn a() {}").unwrap();
+        let cas = Arc::new(ContentAddressedStore::new(temp_dir.path().join("cas"), 4096).unwrap());
+        let cache = ActionCache::new(cas, 0, temp_dir.path()).unwrap();
+
+        let task_definition = TaskDefinition {
+            name: "test".to_string(),
+            description: None,
+            execution_mode: TaskExecutionMode::Command {
+                command: "echo hello".to_string(),
+            },
+            dependencies: vec![],
+            working_directory: temp_dir.path().to_path_buf(),
+            shell: "sh".to_string(),
+            inputs: vec!["pkg://projects:lib/src/**".to_string()],
+            outputs: vec![],
+            security: None,
+            cache: TaskCache {
+                enabled: true,
+                key: None,
+                env_filter: None,
+                failure_ttl: None,
+            },
+            timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
+        };
+        let package_dirs = HashMap::from([("projects:lib".to_string(), lib_dir.clone())]);
+        let before = cache
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &package_dirs,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            before.components.input_files.keys().collect::<Vec<_>>(),
+            ["pkg://projects:lib/src/lib.rs"]
+        );
+
+        std::fs::write(lib_dir.join("src").join("lib.rs"), "fn changed() {}").unwrap();
+        let after = cache
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &package_dirs,
+            )
+            .await
+            .unwrap();
+        assert_ne!(before.hash, after.hash);
+
+        let unknown = cache
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &HashMap::new(),
+            )
+            .await;
+        assert!(unknown.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_digest_tracks_task_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        let cas =
+            Arc::new(ContentAddressedStore::new(temp_dir.path().to_path_buf(), 4096).unwrap());
+        let cache = ActionCache::new(cas, 0, temp_dir.path()).unwrap();
+
+        let task_definition = TaskDefinition {
+            name: "migrate".to_string(),
+            description: None,
+            execution_mode: TaskExecutionMode::Command {
+                command: "psql \"$DATABASE_URL\" -f schema.sql".to_string(),
+            },
+            dependencies: vec![],
+            working_directory: temp_dir.path().to_path_buf(),
+            shell: "sh".to_string(),
+            inputs: vec![],
+            outputs: vec![],
+            security: None,
+            cache: TaskCache::default(),
+            timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
+        };
+        let digest_for = |database_url: &str, region: &str| {
+            let env_vars = HashMap::from([
+                ("DATABASE_URL".to_string(), database_url.to_string()),
+                ("REGION".to_string(), region.to_string()),
+            ]);
+            let cache = &cache;
+            let task_definition = &task_definition;
+            let temp_dir = &temp_dir;
+            async move {
+                cache
+                    .compute_digest(
+                        "migrate",
+                        task_definition,
+                        temp_dir.path(),
+                        env_vars,
+                        &BTreeSet::new(),
+                        &HashMap::new(),
+                    )
+                    .await
+                    .unwrap()
+                    .hash
+            }
+        };
+
+        let dev = digest_for("postgres://dev-db/app", "eu-west-1").await;
+        assert_ne!(dev, digest_for("postgres://prod-db/app", "eu-west-1").await);
+        // Unreferenced, unobserved variables stay out of the key
+        assert_eq!(dev, digest_for("postgres://dev-db/app", "us-east-1").await);
+
+        cache
+            .record_env_usage(
+                "migrate",
+                temp_dir.path(),
+                &BTreeSet::from(["REGION".to_string()]),
+            )
+            .unwrap();
+        assert_ne!(
+            digest_for("postgres://dev-db/app", "eu-west-1").await,
+            digest_for("postgres://dev-db/app", "us-east-1").await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_output_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let cas = Arc::new(ContentAddressedStore::new(temp_dir.path().join("cas"), 4096).unwrap());
+        let cache = ActionCache::new(Arc::clone(&cas), 0, temp_dir.path()).unwrap();
+
+        let work = temp_dir.path().join("work");
+        std::fs::create_dir_all(work.join("dist")).unwrap();
+        std::fs::write(work.join("dist/app.js"), "console.log(1)").unwrap();
+        std::fs::write(work.join("dist/app.wasm"), vec![7u8; 10_000]).unwrap();
+
+        let outputs = cache
+            .store_output_files(&["dist".to_string()], &work)
+            .await
+            .unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            cas.retrieve(&outputs["dist/app.js"]).unwrap(),
+            b"console.log(1)"
+        );
+        assert_eq!(
+            cas.retrieve(&outputs["dist/app.wasm"]).unwrap(),
+            vec![7u8; 10_000]
+        );
+    }
+
     #[tokio::test]
     async fn test_action_caching() {
         let temp_dir = TempDir::new().unwrap();
@@ -500,12 +886,26 @@ mod tests {
                 enabled: true,
                 key: None,
                 env_filter: None,
+                failure_ttl: None,
             },
             timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         };
 
         let digest = cache
-            .compute_digest("test", &task_definition, temp_dir.path(), HashMap::new())
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &HashMap::new(),
+            )
             .await
             .unwrap();
 
@@ -519,6 +919,9 @@ mod tests {
                     output_files: HashMap::new(),
                     executed_at: SystemTime::now(),
                     duration_ms: 10,
+                    environment: None,
+                    run_id: None,
+                    expires_at: None,
                 })
             })
             .await
@@ -536,6 +939,74 @@ mod tests {
         assert_eq!(stats.writes, 1);
     }
 
+    #[tokio::test]
+    async fn test_failures_are_cached_until_they_expire() {
+        let temp_dir = TempDir::new().unwrap();
+        let cas =
+            Arc::new(ContentAddressedStore::new(temp_dir.path().to_path_buf(), 4096).unwrap());
+        let cache = ActionCache::new(cas, 0, temp_dir.path()).unwrap();
+        let task_definition = TaskDefinition {
+            name: "test".to_string(),
+            description: None,
+            execution_mode: TaskExecutionMode::Command {
+                command: "false".to_string(),
+            },
+            dependencies: vec![],
+            working_directory: temp_dir.path().to_path_buf(),
+            shell: "sh".to_string(),
+            inputs: vec![],
+            outputs: vec![],
+            security: None,
+            cache: TaskCache::default(),
+            timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
+        };
+        let digest = cache
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let failure = |expires_at| ActionResult {
+            exit_code: 1,
+            stdout_hash: None,
+            stderr_hash: None,
+            output_files: HashMap::new(),
+            executed_at: SystemTime::now(),
+            duration_ms: 0,
+            environment: None,
+            run_id: None,
+            expires_at,
+        };
+
+        let run = |expires_at| {
+            cache.execute_action(&digest, move || async move { Ok(failure(expires_at)) })
+        };
+        run(None).await.unwrap();
+        assert!(cache.get_cached_result(&digest).await.is_none());
+
+        run(Some(SystemTime::now() + Duration::from_secs(600)))
+            .await
+            .unwrap();
+        assert_eq!(cache.get_cached_result(&digest).await.unwrap().exit_code, 1);
+
+        cache.invalidate(&digest.hash);
+        run(Some(SystemTime::now() - Duration::from_secs(1)))
+            .await
+            .unwrap();
+        assert!(cache.get_cached_result(&digest).await.is_none());
+    }
+
     #[tokio::test]
     async fn test_concurrent_action_execution() {
         let temp_dir = TempDir::new().unwrap();
@@ -559,12 +1030,26 @@ mod tests {
                 enabled: true,
                 key: None,
                 env_filter: None,
+                failure_ttl: None,
             },
             timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         };
 
         let digest = cache
-            .compute_digest("test", &task_definition, temp_dir.path(), HashMap::new())
+            .compute_digest(
+                "test",
+                &task_definition,
+                temp_dir.path(),
+                HashMap::new(),
+                &BTreeSet::new(),
+                &HashMap::new(),
+            )
             .await
             .unwrap();
 
@@ -585,6 +1070,9 @@ mod tests {
                         output_files: HashMap::new(),
                         executed_at: SystemTime::now(),
                         duration_ms: 100,
+                        environment: None,
+                        run_id: None,
+                        expires_at: None,
                     })
                 })
                 .await;
@@ -610,6 +1098,9 @@ mod tests {
                         output_files: HashMap::new(),
                         executed_at: SystemTime::now(),
                         duration_ms: 10,
+                        environment: None,
+                        run_id: None,
+                        expires_at: None,
                     })
                 })
                 .await;
@@ -1,5 +1,5 @@
 //! Cache configuration management with precedence and validation
-use super::{keys::CacheKeyFilterConfig, CacheMode};
+use super::{keys::CacheKeyFilterConfig, CacheMode, VerifyMode};
 use crate::errors::{Error, RecoveryHint, Result, SerializationOp};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -52,6 +52,8 @@ pub struct GlobalCacheConfig {
     pub inline_threshold: Option<usize>,
     /// Global environment variable filtering configuration
     pub env_filter: Option<CacheKeyFilterConfig>,
+    /// How outputs restored from the cache are verified
+    pub verify: Option<VerifyMode>,
 }
 
 impl Default for GlobalCacheConfig {
@@ -63,10 +65,18 @@ impl Default for GlobalCacheConfig {
             max_size: None,
             inline_threshold: None,
             env_filter: None,
+            verify: None,
         }
     }
 }
 
+impl GlobalCacheConfig {
+    /// How outputs restored from the cache are verified, `fast` unless set
+    pub fn verify_mode(&self) -> VerifyMode {
+        self.verify.unwrap_or_default()
+    }
+}
+
 // Re-export TaskCacheConfig from config crate
 pub use cuenv_config::TaskCacheConfig;
 
@@ -265,6 +275,10 @@ impl CacheConfigLoader {
             if let Some(threshold) = cache_obj.get("inline_threshold").and_then(|v| v.as_u64()) {
                 global.inline_threshold = Some(threshold as usize);
             }
+
+            if let Some(verify) = cache_obj.get("verify").and_then(|v| v.as_str()) {
+                global.verify = Some(VerifyMode::from(verify.to_string()));
+            }
         }
 
         Ok(Some(CacheConfiguration {
@@ -317,6 +331,12 @@ impl CacheConfigLoader {
             has_env_config = true;
         }
 
+        // Check for restore verification setting
+        if let Ok(verify_str) = std::env::var("CUENV_CACHE_VERIFY") {
+            global.verify = Some(VerifyMode::from(verify_str));
+            has_env_config = true;
+        }
+
         if has_env_config {
             Ok(Some(CacheConfiguration {
                 global,
@@ -392,6 +412,10 @@ impl CacheConfigLoader {
             global.env_filter = override_config.global.env_filter;
         }
 
+        if override_config.global.verify.is_some() {
+            global.verify = override_config.global.verify;
+        }
+
         // Task configs are additive (from CUE files, not config file/env)
         let mut task_configs = base.task_configs;
         task_configs.extend(override_config.task_configs);
@@ -529,6 +553,7 @@ mod tests {
             max_size: None,
             inline_threshold: None,
             env_filter: None,
+            verify: None,
         };
 
         // Test with task config enabled
@@ -555,6 +580,7 @@ mod tests {
             max_size: global_config.max_size,
             inline_threshold: global_config.inline_threshold,
             env_filter: global_config.env_filter.clone(),
+            verify: global_config.verify,
         };
         assert!(!CacheConfigResolver::should_cache_task(
             &global_disabled,
@@ -4,6 +4,7 @@
 //! are stored and retrieved by their content hash, ensuring deduplication
 //! and integrity.
 
+use crate::mode::VerifyMode;
 use cuenv_core::{Error, Result};
 use cuenv_utils::atomic_file::{write_atomic, write_atomic_string};
 use dashmap::DashMap;
@@ -11,7 +12,7 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
@@ -104,51 +105,82 @@ impl ContentAddressedStore {
         let hash = self.hash_content(&content);
         let size = content.len() as u64;
 
+        self.add_object(&hash, size, |path| write_atomic(path, &content))?;
+        Ok(hash)
+    }
+
+    /// Store the file at `path` and return its hash
+    ///
+    /// The file is hashed and copied a chunk at a time, so files of any size
+    /// are stored without being held in memory.
+    pub fn store_file(&self, path: &Path) -> Result<String> {
+        self.maybe_garbage_collect()?;
+
+        let size = fs::metadata(path)
+            .map_err(|e| Error::file_system(path, "read file metadata for CAS", e))?
+            .len();
+        let hash = file_content_hash(path, size)?;
+
+        self.add_object(&hash, size, |object_path| {
+            // Copy beside the object and rename, so a partial copy is never
+            // taken for the object
+            let partial = object_path.with_extension("partial");
+            fs::copy(path, &partial)
+                .map_err(|e| Error::file_system(&partial, "copy file into CAS", e))?;
+            fs::rename(&partial, object_path)
+                .map_err(|e| Error::file_system(object_path, "move file into CAS", e))
+        })?;
+        Ok(hash)
+    }
+
+    /// Where a download of the object `hash` collects before it is stored
+    pub fn download_path(&self, hash: &str) -> PathBuf {
+        self.base_dir.join("downloads").join(hash)
+    }
+
+    /// Add a reference to the object `hash`, writing it with `write` when
+    /// the store does not have it yet
+    fn add_object(
+        &self,
+        hash: &str,
+        size: u64,
+        write: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<()> {
         // Check if already exists
-        if let Some(mut entry) = self.index.get_mut(&hash) {
+        if let Some(mut entry) = self.index.get_mut(hash) {
             entry.ref_count += 1;
             drop(entry); // Release the lock before persisting
-            self.persist_index()?;
-            return Ok(hash);
+            return self.persist_index();
         }
 
-        // Determine storage strategy
-        let (inlined, _object_path) = if content.len() <= self.inline_threshold {
-            // Inline small objects
-            (true, None)
+        // Determine storage strategy: inline small objects, store large
+        // objects as files
+        let inlined = size <= self.inline_threshold as u64;
+        let object_path = if inlined {
+            self.get_inline_path(hash)
         } else {
-            // Store large objects as files
-            let object_path = self.get_object_path(&hash);
-            if let Some(parent) = object_path.parent() {
-                fs::create_dir_all(parent).map_err(|e| {
-                    Error::file_system(parent.to_path_buf(), "create CAS object directory", e)
-                })?;
-            }
-            write_atomic(&object_path, &content)?;
-            (false, Some(object_path))
+            self.get_object_path(hash)
         };
+        if let Some(parent) = object_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::file_system(parent.to_path_buf(), "create CAS object directory", e)
+            })?;
+        }
+        write(&object_path)?;
 
         // Create metadata
         let metadata = ObjectMetadata {
-            hash: hash.clone(),
+            hash: hash.to_string(),
             size,
             stored_at: SystemTime::now(),
             ref_count: 1,
             inlined,
         };
 
-        // Store inline content if applicable
-        if inlined {
-            let inline_path = self.get_inline_path(&hash);
-            write_atomic(&inline_path, &content)?;
-        }
-
         // Update index
-        self.index.insert(hash.clone(), metadata);
+        self.index.insert(hash.to_string(), metadata);
         self.total_bytes.fetch_add(size, Ordering::Relaxed);
-        self.persist_index()?;
-
-        Ok(hash)
+        self.persist_index()
     }
 
     /// Retrieve content by hash with integrity verification
@@ -188,16 +220,81 @@ impl ContentAddressedStore {
         Ok(content)
     }
 
-    /// Compute hash of content with length prefix to prevent collisions
-    fn hash_content(&self, content: &[u8]) -> String {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
+    /// Copy the object `hash` to `dest` and check the copy as `verify` says
+    ///
+    /// An object whose copy does not match what was recorded is quarantined
+    /// and an error returned, so the caller recomputes it instead of using
+    /// corrupted content.
+    pub fn restore_file(&self, hash: &str, dest: &Path, verify: VerifyMode) -> Result<()> {
+        let metadata = self
+            .get_metadata(hash)
+            .ok_or_else(|| Error::configuration(format!("Object not found in CAS: {hash}")))?;
+        let object_path = self.object_path(&metadata);
+        if !object_path.exists() {
+            self.quarantine(hash)?;
+            return Err(Error::configuration(format!(
+                "Cached output {} is missing from the store",
+                dest.display()
+            )));
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::file_system(parent, "create output directory", e))?;
+        }
+        // Copy beside the output and rename, so a partial copy never
+        // replaces it
+        let mut partial = dest.as_os_str().to_owned();
+        partial.push(".cuenv-restore");
+        let partial = PathBuf::from(partial);
+        if let Err(e) = fs::copy(&object_path, &partial) {
+            let _ = fs::remove_file(&partial);
+            return Err(Error::file_system(&object_path, "restore CAS object", e));
+        }
 
-        // Add length prefix to prevent length extension attacks and collisions
-        hasher.update((content.len() as u64).to_le_bytes());
-        hasher.update(content);
+        if let Err(problem) = verify_copy(&partial, &metadata, verify) {
+            let _ = fs::remove_file(&partial);
+            log::error!("CAS object {hash} failed {verify} verification: {problem}");
+            self.quarantine(hash)?;
+            return Err(Error::configuration(format!(
+                "Cached output {} is corrupted ({problem})",
+                dest.display()
+            )));
+        }
+
+        fs::rename(&partial, dest)
+            .map_err(|e| Error::file_system(dest, "move restored output into place", e))
+    }
+
+    /// Move the object `hash` out of the store into `quarantine/`, where it
+    /// is kept for inspection but never used again
+    pub fn quarantine(&self, hash: &str) -> Result<()> {
+        let Some((_, metadata)) = self.index.remove(hash) else {
+            return Ok(());
+        };
+        self.total_bytes.fetch_sub(metadata.size, Ordering::Relaxed);
+
+        let object_path = self.object_path(&metadata);
+        let quarantine_dir = self.base_dir.join("quarantine");
+        fs::create_dir_all(&quarantine_dir)
+            .map_err(|e| Error::file_system(&quarantine_dir, "create CAS quarantine", e))?;
+        let quarantined = quarantine_dir.join(hash);
+        match fs::rename(&object_path, &quarantined) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(Error::file_system(&object_path, "quarantine CAS object", e));
+            }
+            _ => {}
+        }
+        log::warn!(
+            "Quarantined corrupted CAS object {hash} in {}",
+            quarantined.display()
+        );
+        self.persist_index()
+    }
 
-        format!("{:x}", hasher.finalize())
+    /// Compute hash of content with length prefix to prevent collisions
+    fn hash_content(&self, content: &[u8]) -> String {
+        content_hash(content)
     }
 
     /// Check if an object exists
@@ -320,6 +417,15 @@ impl ContentAddressedStore {
         self.base_dir.join("inline").join(hash)
     }
 
+    /// Path an object is stored at, inlined or not
+    fn object_path(&self, metadata: &ObjectMetadata) -> PathBuf {
+        if metadata.inlined {
+            self.get_inline_path(&metadata.hash)
+        } else {
+            self.get_object_path(&metadata.hash)
+        }
+    }
+
     /// Remove an object from storage
     fn remove_object(&self, hash: &str) -> Result<()> {
         if let Some((_, metadata)) = self.index.remove(hash) {
@@ -393,6 +499,76 @@ impl ContentAddressedStore {
     }
 }
 
+/// Hash `content` is stored under in a [`ContentAddressedStore`]
+pub fn content_hash(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+
+    // Add length prefix to prevent length extension attacks and collisions
+    hasher.update((content.len() as u64).to_le_bytes());
+    hasher.update(content);
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Why the restored copy at `path` does not match the object it was made
+/// from, if it does not
+fn verify_copy(
+    path: &Path,
+    metadata: &ObjectMetadata,
+    verify: VerifyMode,
+) -> std::result::Result<(), String> {
+    if verify == VerifyMode::Off {
+        return Ok(());
+    }
+
+    let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size != metadata.size {
+        return Err(format!("{size} bytes instead of {} bytes", metadata.size));
+    }
+    if verify == VerifyMode::Full {
+        let hash = file_content_hash(path, size).map_err(|e| e.to_string())?;
+        if hash != metadata.hash {
+            return Err(format!("content hash {hash}"));
+        }
+    }
+    Ok(())
+}
+
+/// [`content_hash`] of the file at `path`, which holds `size` bytes,
+/// computed a chunk at a time
+fn file_content_hash(path: &Path, size: u64) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file =
+        fs::File::open(path).map_err(|e| Error::file_system(path, "open file for CAS", e))?;
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut hashed = 0u64;
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| Error::file_system(path, "read file for CAS", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        hashed += n as u64;
+    }
+    if hashed != size {
+        return Err(Error::file_system(
+            path,
+            "read file for CAS",
+            std::io::Error::other(format!(
+                "file changed size while hashing ({size} to {hashed} bytes)"
+            )),
+        ));
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -481,4 +657,52 @@ mod tests {
         assert_eq!(removed_count, 0); // Already removed by release
         assert_eq!(cas.total_bytes(), 0);
     }
+
+    #[test]
+    fn test_cas_store_file_matches_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let cas = ContentAddressedStore::new(temp_dir.path().join("cas"), 100).unwrap();
+
+        let content: Vec<u8> = (0..300_000u32).map(|i| (i % 256) as u8).collect();
+        let path = temp_dir.path().join("artifact.bin");
+        fs::write(&path, &content).unwrap();
+
+        let hash = cas.store_file(&path).unwrap();
+        assert_eq!(hash, content_hash(&content));
+        assert!(!cas.get_metadata(&hash).unwrap().inlined);
+        assert_eq!(cas.retrieve(&hash).unwrap(), content);
+
+        // Storing the same bytes again only adds a reference
+        assert_eq!(cas.store(Cursor::new(&content)).unwrap(), hash);
+        assert_eq!(cas.get_metadata(&hash).unwrap().ref_count, 2);
+        assert_eq!(cas.total_bytes(), content.len() as u64);
+    }
+
+    #[test]
+    fn test_restore_verifies_and_quarantines() {
+        let temp_dir = TempDir::new().unwrap();
+        let cas = ContentAddressedStore::new(temp_dir.path().join("cas"), 10).unwrap();
+        let work = temp_dir.path().join("work");
+
+        let hash = cas.store(Cursor::new(b"compiled output")).unwrap();
+        let dest = work.join("dist/app.js");
+        cas.restore_file(&hash, &dest, VerifyMode::Full).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"compiled output");
+
+        // Same size, different bytes: only a full check notices
+        fs::write(cas.get_object_path(&hash), b"compiled 0utput").unwrap();
+        cas.restore_file(&hash, &dest, VerifyMode::Fast).unwrap();
+        let err = cas
+            .restore_file(&hash, &dest, VerifyMode::Full)
+            .unwrap_err();
+        assert!(err.to_string().contains("corrupted"));
+        assert!(!cas.contains(&hash));
+        assert!(temp_dir.path().join("cas/quarantine").join(&hash).exists());
+
+        // A truncated object fails even the fast check
+        let hash = cas.store(Cursor::new(b"another output")).unwrap();
+        fs::write(cas.get_object_path(&hash), b"another").unwrap();
+        assert!(cas.restore_file(&hash, &dest, VerifyMode::Fast).is_err());
+        assert!(!cas.contains(&hash));
+    }
 }
@@ -1,7 +1,7 @@
 //! Cache builder and initialization
 
 use crate::errors::{CacheError, RecoveryHint, Result};
-use crate::eviction::create_eviction_policy;
+use crate::eviction::{create_eviction_policy, DEFAULT_EVICTION_POLICY};
 use crate::fast_path::FastPathCache;
 use crate::memory_manager::{MemoryManager, MemoryThresholds};
 use crate::traits::CacheConfig;
@@ -65,7 +65,10 @@ impl Cache {
 
         // Create eviction policy
         let eviction_policy = match create_eviction_policy(
-            config.eviction_policy.as_deref().unwrap_or("lru"),
+            config
+                .eviction_policy
+                .as_deref()
+                .unwrap_or(DEFAULT_EVICTION_POLICY),
             config.max_memory_size.unwrap_or(1024 * 1024 * 1024), // 1GB default
         ) {
             Ok(policy) => policy,
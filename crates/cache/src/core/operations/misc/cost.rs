@@ -0,0 +1,14 @@
+//! Recomputation cost tracking
+
+use crate::core::types::Cache;
+use std::time::Duration;
+
+impl Cache {
+    /// Record how long the value stored under `key` took to produce
+    ///
+    /// Cost-aware eviction keeps expensive entries over cheap ones of the
+    /// same size; call this after `put` with the task's run time.
+    pub fn record_cost(&self, key: &str, cost: Duration) {
+        self.inner.eviction_policy.on_cost(key, cost);
+    }
+}
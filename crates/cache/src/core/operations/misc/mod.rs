@@ -1,6 +1,7 @@
 //! Miscellaneous cache operations
 
 mod clear;
+mod cost;
 mod stats;
 
 use crate::core::paths::metadata_path;
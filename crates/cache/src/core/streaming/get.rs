@@ -2,6 +2,7 @@
 
 use crate::core::types::Cache;
 use crate::errors::{CacheError, RecoveryHint, Result};
+use crate::streaming::{GetOptions, TransferProgress};
 use futures::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use std::future::Future;
 use std::path::PathBuf;
@@ -15,24 +16,41 @@ impl Cache {
         key: &'a str,
         writer: W,
     ) -> Pin<Box<dyn Future<Output = Result<Option<u64>>> + Send + 'a>>
+    where
+        W: AsyncWrite + Send + 'a,
+    {
+        self.get_stream_with(key, writer, GetOptions::default())
+    }
+
+    /// Stream the value of `key` into `writer`, a chunk at a time, and
+    /// return how many bytes were written
+    ///
+    /// With `options.offset`, the value is streamed from that far in, so a
+    /// download that was cut off can be resumed.
+    pub fn get_stream_with<'a, W>(
+        &'a self,
+        key: &'a str,
+        writer: W,
+        options: GetOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<u64>>> + Send + 'a>>
     where
         W: AsyncWrite + Send + 'a,
     {
         Box::pin(async move {
-            let reader = match self.get_reader(key).await {
+            let mut reader = match self.get_reader(key).await {
                 Ok(Some(r)) => r,
                 Ok(None) => return Ok(None),
                 Err(e) => return Err(e),
             };
 
-            let _expected_size = reader.metadata().size_bytes;
+            let expected_size = reader.metadata().size_bytes;
+            reader.seek_to(options.offset).await?;
 
             // High-performance streaming copy
             // Note: Zero-copy implementation would be added here for Linux systems
             // using sendfile/splice system calls for optimal performance
 
             // Standard async copy
-            let mut reader = reader;
             const BUFFER_SIZE: usize = 64 * 1024; // 64KB buffer
             let mut buffer = vec![0u8; BUFFER_SIZE];
             let mut total_bytes = 0u64;
@@ -70,6 +88,12 @@ impl Cache {
                 }
 
                 total_bytes += n as u64;
+                if let Some(progress) = &options.progress {
+                    progress.report(TransferProgress {
+                        transferred: options.offset + total_bytes,
+                        total: Some(expected_size),
+                    });
+                }
             }
 
             match writer.flush().await {
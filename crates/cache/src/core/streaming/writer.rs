@@ -1,7 +1,7 @@
 //! Streaming write operations
 
 use crate::errors::{CacheError, RecoveryHint, Result};
-use crate::streaming::CacheWriter;
+use crate::streaming::{CacheWriter, PutOptions, TransferProgress};
 use crate::traits::CacheKey;
 use futures::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use parking_lot::RwLock;
@@ -14,7 +14,7 @@ use std::time::{Duration, Instant};
 
 use crate::core::internal::InMemoryEntry;
 use crate::core::operations::utils::mmap_file;
-use crate::core::paths::{hash_key, object_path};
+use crate::core::paths::{metadata_path, object_path};
 use crate::core::types::Cache;
 
 impl Cache {
@@ -43,10 +43,12 @@ impl Cache {
                 }
             }
 
-            match CacheWriter::new(&self.inner.base_dir, key, ttl).await {
-                Ok(writer) => Ok(writer),
-                Err(e) => Err(e),
-            }
+            CacheWriter::at(
+                object_path(&self.inner, key),
+                metadata_path(&self.inner, key),
+                ttl,
+            )
+            .await
         })
     }
 
@@ -56,54 +58,66 @@ impl Cache {
         reader: R,
         ttl: Option<Duration>,
     ) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>>
+    where
+        R: AsyncRead + Send + 'a,
+    {
+        self.put_stream_with(
+            key,
+            reader,
+            PutOptions {
+                ttl,
+                ..PutOptions::default()
+            },
+        )
+    }
+
+    /// Bytes an interrupted resumable put of `key` left behind; a resumed
+    /// put's reader starts this far into the value
+    pub async fn partial_len(&self, key: &str) -> u64 {
+        CacheWriter::partial_len_at(&object_path(&self.inner, key)).await
+    }
+
+    /// Stream `reader` into the cache under `key`, a chunk at a time, and
+    /// return the size of the value
+    ///
+    /// The value is hashed as it is written, so values of any size are never
+    /// held in memory. With `options.resume`, an earlier put cut off midway
+    /// is continued from [`Cache::partial_len`].
+    pub fn put_stream_with<'a, R>(
+        &'a self,
+        key: &'a str,
+        reader: R,
+        options: PutOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>>
     where
         R: AsyncRead + Send + 'a,
     {
         Box::pin(async move {
-            let mut writer = match self.get_writer(key, ttl).await {
-                Ok(w) => w,
-                Err(e) => return Err(e),
+            let mut writer = if options.resume {
+                key.validate()?;
+                CacheWriter::resume_at(
+                    object_path(&self.inner, key),
+                    metadata_path(&self.inner, key),
+                    options.ttl,
+                )
+                .await?
+            } else {
+                self.get_writer(key, options.ttl).await?
             };
 
-            // High-performance streaming copy
-            const BUFFER_SIZE: usize = 64 * 1024; // 64KB buffer
-            let mut buffer = vec![0u8; BUFFER_SIZE];
-            let mut total_bytes = 0u64;
-
-            tokio::pin!(reader);
-
-            loop {
-                let n = match reader.read(&mut buffer).await {
-                    Ok(0) => break, // EOF
-                    Ok(n) => n,
-                    Err(e) => {
-                        return Err(CacheError::Io {
-                            path: PathBuf::from(key),
-                            operation: "read from stream",
-                            source: std::io::Error::other(e),
-                            recovery_hint: RecoveryHint::Retry {
-                                after: Duration::from_millis(100),
-                            },
-                        });
-                    }
-                };
-
-                match writer.write_all(&buffer[..n]).await {
-                    Ok(()) => {}
-                    Err(e) => {
-                        return Err(CacheError::Io {
-                            path: PathBuf::from(key),
-                            operation: "write to cache stream",
-                            source: std::io::Error::other(e),
-                            recovery_hint: RecoveryHint::Retry {
-                                after: Duration::from_millis(100),
-                            },
-                        });
+            match copy_chunks(key, reader, &mut writer, &options).await {
+                Ok(()) => {}
+                Err(e) => {
+                    // A resumable put keeps what it wrote for the next attempt
+                    if options.resume {
+                        let _ = writer.flush().await;
+                    } else {
+                        writer.discard().await;
                     }
+                    return Err(e);
                 }
-
-                total_bytes += n as u64;
             }
+            let total_bytes = writer.bytes_written();
 
             // Finalize the write
             let metadata = match writer.finalize().await {
@@ -119,7 +133,6 @@ impl Cache {
                 .fetch_add(total_bytes, Ordering::Relaxed);
 
             // Add to memory cache for hot access
-            let _hash = hash_key(&self.inner, key);
             let data_path = object_path(&self.inner, key);
 
             // Try to memory-map for future reads
@@ -138,3 +151,56 @@ impl Cache {
         })
     }
 }
+
+/// Copy `reader` into `writer` a chunk at a time, reporting progress
+async fn copy_chunks<R: AsyncRead>(
+    key: &str,
+    reader: R,
+    writer: &mut CacheWriter,
+    options: &PutOptions,
+) -> Result<()> {
+    // High-performance streaming copy
+    const BUFFER_SIZE: usize = 64 * 1024; // 64KB buffer
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    tokio::pin!(reader);
+
+    loop {
+        let n = match reader.read(&mut buffer).await {
+            Ok(0) => break, // EOF
+            Ok(n) => n,
+            Err(e) => {
+                return Err(CacheError::Io {
+                    path: PathBuf::from(key),
+                    operation: "read from stream",
+                    source: std::io::Error::other(e),
+                    recovery_hint: RecoveryHint::Retry {
+                        after: Duration::from_millis(100),
+                    },
+                });
+            }
+        };
+
+        match writer.write_all(&buffer[..n]).await {
+            Ok(()) => {}
+            Err(e) => {
+                return Err(CacheError::Io {
+                    path: PathBuf::from(key),
+                    operation: "write to cache stream",
+                    source: std::io::Error::other(e),
+                    recovery_hint: RecoveryHint::Retry {
+                        after: Duration::from_millis(100),
+                    },
+                });
+            }
+        }
+
+        if let Some(progress) = &options.progress {
+            progress.report(TransferProgress {
+                transferred: writer.bytes_written(),
+                total: options.total,
+            });
+        }
+    }
+    Ok(())
+}
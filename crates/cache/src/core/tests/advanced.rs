@@ -108,3 +108,88 @@ async fn test_cache_consistency_simple() -> Result<()> {
 
     Ok(())
 }
+
+/// Reader whose stream breaks after its data, like a dropped connection
+struct CutOff(futures::io::Cursor<Vec<u8>>);
+
+impl futures::io::AsyncRead for CutOff {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        match std::pin::Pin::new(&mut self.0).poll_read(cx, buf) {
+            std::task::Poll::Ready(Ok(0)) => std::task::Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "cut off",
+            ))),
+            other => other,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_resumable_streams_report_progress() -> Result<()> {
+    use crate::streaming::{GetOptions, ProgressCallback, PutOptions, TransferProgress};
+    use std::sync::{Arc, Mutex};
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache = Cache::new(temp_dir.path().to_path_buf(), CacheConfig::default()).await?;
+    let value: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let resume = PutOptions {
+        resume: true,
+        ..PutOptions::default()
+    };
+
+    // The first attempt breaks off halfway and keeps what it wrote
+    let first = CutOff(futures::io::Cursor::new(value[..100_000].to_vec()));
+    assert!(cache
+        .put_stream_with("artifact", first, resume.clone())
+        .await
+        .is_err());
+    assert_eq!(cache.partial_len("artifact").await, 100_000);
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let progress = {
+        let reported = Arc::clone(&reported);
+        ProgressCallback::new(move |progress| reported.lock().unwrap().push(progress))
+    };
+    let rest = futures::io::Cursor::new(value[100_000..].to_vec());
+    let size = cache
+        .put_stream_with(
+            "artifact",
+            rest,
+            PutOptions {
+                total: Some(200_000),
+                progress: Some(progress),
+                ..resume
+            },
+        )
+        .await?;
+    assert_eq!(size, 200_000);
+    assert_eq!(cache.partial_len("artifact").await, 0);
+    assert_eq!(
+        reported.lock().unwrap().last(),
+        Some(&TransferProgress {
+            transferred: 200_000,
+            total: Some(200_000),
+        })
+    );
+
+    // A download cut off at 150000 bytes continues from there
+    let mut tail = Vec::new();
+    let written = cache
+        .get_stream_with(
+            "artifact",
+            &mut tail,
+            GetOptions {
+                offset: 150_000,
+                ..GetOptions::default()
+            },
+        )
+        .await?;
+    assert_eq!(written, Some(50_000));
+    assert_eq!(tail, value[150_000..]);
+
+    Ok(())
+}
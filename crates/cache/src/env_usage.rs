@@ -0,0 +1,144 @@
+//! Environment variables a task is known to depend on
+//!
+//! The key filter decides by name alone, so a task that reads `DATABASE_URL`
+//! would hit a result recorded against another environment unless a pattern
+//! happens to include it. Variables a task references as `$NAME` or
+//! `${NAME}` are always part of its key, and so are variables an audited run
+//! was seen to use: those whose value shows up in a file path or network
+//! address the task accessed. Observations are kept per task, so later runs
+//! without `--audit` key on them too.
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const USAGE_DIR: &str = "env-usage";
+
+/// Shorter values, like `1` or `dev`, would match unrelated paths
+const MIN_OBSERVED_VALUE_LEN: usize = 4;
+
+/// Names of the variables `script` expands, in `$NAME` or `${NAME...}` form
+pub fn referenced_variables(script: &str) -> BTreeSet<String> {
+    script
+        .split('$')
+        .skip(1)
+        .filter_map(|rest| {
+            let name = rest.strip_prefix('{').unwrap_or(rest);
+            let len = name
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(name.len());
+            let name = &name[..len];
+            name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Names of the variables in `vars` whose value appears in an `accessed` entry
+pub fn observed_variables(vars: &HashMap<String, String>, accessed: &[String]) -> BTreeSet<String> {
+    vars.iter()
+        .filter(|(_, value)| value.len() >= MIN_OBSERVED_VALUE_LEN)
+        .filter(|(_, value)| accessed.iter().any(|entry| entry.contains(value.as_str())))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Observed variables stored in the cache directory, one file per task
+#[derive(Debug, Clone)]
+pub struct EnvUsageCache {
+    dir: PathBuf,
+}
+
+impl EnvUsageCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join(USAGE_DIR),
+        }
+    }
+
+    /// Variables recorded for a task; nothing recorded is an empty set
+    pub fn load(&self, working_dir: &Path, task_name: &str) -> BTreeSet<String> {
+        fs::read_to_string(self.path(working_dir, task_name))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Add `names` to what is recorded for a task
+    ///
+    /// Entries are never dropped: one audited run only shows some of the
+    /// paths a task may touch.
+    pub fn record(
+        &self,
+        working_dir: &Path,
+        task_name: &str,
+        names: &BTreeSet<String>,
+    ) -> Result<()> {
+        let known = self.load(working_dir, task_name);
+        if names.is_subset(&known) {
+            return Ok(());
+        }
+
+        let merged: BTreeSet<_> = known.union(names).collect();
+        let content = serde_json::to_string(&merged).map_err(|e| Error::Json {
+            message: "Failed to serialize observed environment variables".to_string(),
+            source: e,
+        })?;
+        write_atomic_string(&self.path(working_dir, task_name), &content)
+    }
+
+    fn path(&self, working_dir: &Path, task_name: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(working_dir.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(task_name.as_bytes());
+        self.dir.join(format!("{:x}.json", hasher.finalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_referenced_variables() {
+        let names =
+            referenced_variables("psql $DATABASE_URL -c \"${QUERY:-select 1}\" $1 $$ cost$");
+
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            ["DATABASE_URL", "QUERY"]
+        );
+    }
+
+    #[test]
+    fn test_observed_variables_are_recorded_per_task() {
+        let temp_dir = TempDir::new().unwrap();
+        let usage = EnvUsageCache::new(temp_dir.path());
+        let vars = HashMap::from([
+            ("CONFIG_DIR".to_string(), "/etc/acme/prod".to_string()),
+            ("STAGE".to_string(), "dev".to_string()),
+            ("API_HOST".to_string(), "api.internal".to_string()),
+        ]);
+        let accessed = [
+            "/etc/acme/prod/app.toml".to_string(),
+            "/srv/dev/cache".to_string(),
+        ];
+
+        let observed = observed_variables(&vars, &accessed);
+        assert_eq!(observed, BTreeSet::from(["CONFIG_DIR".to_string()]));
+
+        let work = Path::new("/work");
+        usage.record(work, "deploy", &observed).unwrap();
+        usage
+            .record(work, "deploy", &BTreeSet::from(["API_HOST".to_string()]))
+            .unwrap();
+
+        assert_eq!(usage.load(work, "deploy").len(), 2);
+        assert!(usage.load(work, "build").is_empty());
+    }
+}
@@ -2,9 +2,15 @@
 
 use crate::errors::{CacheError, RecoveryHint, Result};
 
-use super::policies::{ArcPolicy, LfuPolicy, LruPolicy};
+use super::policies::{ArcPolicy, GdsfPolicy, LfuPolicy, LruPolicy};
 use super::traits::EvictionPolicy;
 
+/// Policy used when none is configured
+///
+/// GDSF only pays off for entries whose producer records their cost with
+/// `Cache::record_cost`; without costs it weighs every entry the same.
+pub const DEFAULT_EVICTION_POLICY: &str = "lru";
+
 /// Eviction policy factory
 pub fn create_eviction_policy(
     policy_type: &str,
@@ -14,10 +20,11 @@ pub fn create_eviction_policy(
         "lru" => Ok(Box::new(LruPolicy::new(max_memory))),
         "lfu" => Ok(Box::new(LfuPolicy::new(max_memory))),
         "arc" => Ok(Box::new(ArcPolicy::new(max_memory))),
+        "gdsf" => Ok(Box::new(GdsfPolicy::new(max_memory))),
         _ => Err(CacheError::Configuration {
             message: format!("Unknown eviction policy: {policy_type}"),
             recovery_hint: RecoveryHint::UseDefault {
-                value: "lru".to_string(),
+                value: DEFAULT_EVICTION_POLICY.to_string(),
             },
         }),
     }
@@ -1,15 +1,18 @@
 //! Eviction policies for cache memory management
 //!
-//! Implements LRU, LFU, and ARC eviction strategies with
-//! production-grade performance and correctness.
+//! Implements LRU, LFU, ARC and cost-aware GDSF eviction strategies with
+//! production-grade performance and correctness, plus a trace replay
+//! harness for comparing them.
 
 mod factory;
 mod policies;
+mod replay;
 mod traits;
 
 // Re-export public API
-pub use factory::create_eviction_policy;
-pub use policies::{ArcPolicy, LfuPolicy, LruPolicy};
+pub use factory::{create_eviction_policy, DEFAULT_EVICTION_POLICY};
+pub use policies::{ArcPolicy, GdsfPolicy, LfuPolicy, LruPolicy};
+pub use replay::{compare_policies, load_trace, replay_trace, ReplayReport, TraceAccess};
 pub use traits::EvictionPolicy;
 
 #[cfg(test)]
@@ -0,0 +1,139 @@
+//! GDSF (Greedy Dual Size Frequency) eviction policy implementation
+//!
+//! Each entry is weighted by how often it is hit, how expensive it was to
+//! produce and how many bytes it occupies:
+//!
+//! ```text
+//! priority = inflation + hits * cost / size
+//! ```
+//!
+//! The entry with the lowest priority is evicted first and its priority
+//! becomes the new inflation value, so entries that stop being used age out
+//! relative to newer ones instead of living forever on old hit counts.
+
+use crate::eviction::traits::EvictionPolicy;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Cost assumed for entries whose recomputation time was never recorded
+const DEFAULT_COST_MS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+struct GdsfEntry {
+    size: u64,
+    hits: u64,
+    cost_ms: f64,
+    priority: f64,
+}
+
+impl GdsfEntry {
+    fn reprioritize(&mut self, inflation: f64) {
+        self.priority = inflation + self.hits as f64 * self.cost_ms / self.size.max(1) as f64;
+    }
+}
+
+/// Cost-aware eviction policy favouring small, hot, expensive entries
+pub struct GdsfPolicy {
+    /// Per-key weighting
+    entries: DashMap<String, GdsfEntry>,
+    /// Aging value (`f64` bits), raised to each evicted entry's priority
+    inflation: AtomicU64,
+    /// Total memory usage
+    total_size: AtomicU64,
+    /// Maximum memory allowed
+    max_memory: u64,
+}
+
+impl GdsfPolicy {
+    pub fn new(max_memory: u64) -> Self {
+        Self {
+            entries: DashMap::new(),
+            inflation: AtomicU64::new(0f64.to_bits()),
+            total_size: AtomicU64::new(0),
+            max_memory,
+        }
+    }
+
+    /// Current aging value
+    pub fn inflation(&self) -> f64 {
+        f64::from_bits(self.inflation.load(Ordering::Acquire))
+    }
+
+    fn raise_inflation(&self, priority: f64) {
+        // `Err` only means the inflation is already at least this high
+        let _ = self
+            .inflation
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |bits| {
+                (priority > f64::from_bits(bits)).then(|| priority.to_bits())
+            });
+    }
+
+    fn update(&self, key: &str, apply: impl FnOnce(&mut GdsfEntry)) {
+        let inflation = self.inflation();
+        if let Some(mut entry) = self.entries.get_mut(key) {
+            apply(&mut entry);
+            entry.reprioritize(inflation);
+        }
+    }
+}
+
+impl EvictionPolicy for GdsfPolicy {
+    fn on_access(&self, key: &str, _size: u64) {
+        self.update(key, |entry| entry.hits += 1);
+    }
+
+    fn on_insert(&self, key: &str, size: u64) {
+        let mut entry = GdsfEntry {
+            size,
+            hits: 1,
+            cost_ms: DEFAULT_COST_MS,
+            priority: 0.0,
+        };
+        entry.reprioritize(self.inflation());
+
+        if let Some(old) = self.entries.insert(key.to_string(), entry) {
+            self.total_size.fetch_sub(old.size, Ordering::AcqRel);
+        }
+        self.total_size.fetch_add(size, Ordering::AcqRel);
+    }
+
+    fn on_remove(&self, key: &str, _size: u64) {
+        if let Some((_, entry)) = self.entries.remove(key) {
+            self.total_size.fetch_sub(entry.size, Ordering::AcqRel);
+        }
+    }
+
+    fn on_cost(&self, key: &str, cost: Duration) {
+        self.update(key, |entry| {
+            entry.cost_ms = (cost.as_secs_f64() * 1000.0).max(DEFAULT_COST_MS);
+        });
+    }
+
+    fn next_eviction(&self) -> Option<String> {
+        if self.memory_usage() <= self.max_memory {
+            return None;
+        }
+
+        // O(n) scan; ties go to the lexicographically smallest key so the
+        // choice does not depend on map iteration order
+        let (key, priority) = self
+            .entries
+            .iter()
+            .map(|r| (r.key().clone(), r.value().priority))
+            .min_by(|(a_key, a), (b_key, b)| a.total_cmp(b).then_with(|| a_key.cmp(b_key)))?;
+
+        self.raise_inflation(priority);
+        Some(key)
+    }
+
+    fn clear(&self) {
+        self.entries.clear();
+        self.total_size.store(0, Ordering::Release);
+        self.inflation.store(0f64.to_bits(), Ordering::Release);
+    }
+
+    fn memory_usage(&self) -> u64 {
+        self.total_size.load(Ordering::Acquire)
+    }
+}
@@ -1,9 +1,11 @@
 //! Eviction policy implementations
 
 mod arc;
+mod gdsf;
 mod lfu;
 mod lru;
 
 pub use arc::ArcPolicy;
+pub use gdsf::GdsfPolicy;
 pub use lfu::LfuPolicy;
 pub use lru::LruPolicy;
@@ -0,0 +1,153 @@
+//! Trace replay for comparing eviction policies
+//!
+//! A trace is a JSON Lines file with one cache lookup per line:
+//!
+//! ```json
+//! {"key":"build-3f2a","size":52428800,"costMs":41000}
+//! ```
+//!
+//! Every policy sees the same lookups against the same memory budget; a miss
+//! inserts the entry and pays its recomputation cost, a hit saves it.
+
+use crate::errors::{CacheError, RecoveryHint, Result, SerializationOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use super::factory::create_eviction_policy;
+use super::traits::EvictionPolicy;
+
+/// Policies compared by [`compare_policies`]
+const REPLAYED_POLICIES: [&str; 4] = ["lru", "lfu", "arc", "gdsf"];
+
+/// One recorded cache lookup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAccess {
+    pub key: String,
+    /// Size of the cached value in bytes
+    pub size: u64,
+    /// Time it takes to recompute the value on a miss
+    pub cost_ms: u64,
+}
+
+/// Outcome of replaying a trace through one policy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub policy: String,
+    pub requests: u64,
+    pub hits: u64,
+    pub requested_bytes: u64,
+    pub hit_bytes: u64,
+    pub total_cost_ms: u64,
+    /// Recomputation time avoided by hits
+    pub saved_cost_ms: u64,
+    pub evictions: u64,
+}
+
+impl ReplayReport {
+    pub fn hit_ratio(&self) -> f64 {
+        ratio(self.hits, self.requests)
+    }
+
+    pub fn byte_hit_ratio(&self) -> f64 {
+        ratio(self.hit_bytes, self.requested_bytes)
+    }
+
+    /// Share of recomputation time avoided; the number GDSF optimises
+    pub fn cost_saved_ratio(&self) -> f64 {
+        ratio(self.saved_cost_ms, self.total_cost_ms)
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Read a JSON Lines trace, skipping blank lines
+pub fn load_trace(path: &Path) -> Result<Vec<TraceAccess>> {
+    let content = std::fs::read_to_string(path).map_err(|e| CacheError::Io {
+        path: path.to_path_buf(),
+        operation: "read eviction trace",
+        source: e,
+        recovery_hint: RecoveryHint::CheckPermissions {
+            path: path.to_path_buf(),
+        },
+    })?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| CacheError::Serialization {
+                key: format!("{}:{}", path.display(), index + 1),
+                operation: SerializationOp::Decode,
+                source: Box::new(e),
+                recovery_hint: RecoveryHint::Manual {
+                    instructions: "Each trace line must be {\"key\", \"size\", \"costMs\"}"
+                        .to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Replay `trace` through `policy`, which must be sized for the budget
+pub fn replay_trace(
+    name: &str,
+    policy: &dyn EvictionPolicy,
+    trace: &[TraceAccess],
+) -> ReplayReport {
+    let mut resident: HashMap<&str, u64> = HashMap::new();
+    let mut report = ReplayReport {
+        policy: name.to_string(),
+        ..ReplayReport::default()
+    };
+
+    for access in trace {
+        report.requests += 1;
+        report.requested_bytes += access.size;
+        report.total_cost_ms += access.cost_ms;
+
+        if resident.contains_key(access.key.as_str()) {
+            report.hits += 1;
+            report.hit_bytes += access.size;
+            report.saved_cost_ms += access.cost_ms;
+            policy.on_access(&access.key, access.size);
+            continue;
+        }
+
+        resident.insert(&access.key, access.size);
+        policy.on_insert(&access.key, access.size);
+        policy.on_cost(&access.key, Duration::from_millis(access.cost_ms));
+
+        while let Some(victim) = policy.next_eviction() {
+            // A policy naming something it no longer tracks cannot make progress
+            let Some(size) = resident.remove(victim.as_str()) else {
+                break;
+            };
+            policy.on_remove(&victim, size);
+            report.evictions += 1;
+        }
+    }
+
+    report
+}
+
+/// Replay `trace` through every built-in policy with the same memory budget
+pub fn compare_policies(trace: &[TraceAccess], max_memory: u64) -> Result<Vec<ReplayReport>> {
+    REPLAYED_POLICIES
+        .iter()
+        .map(|name| {
+            let policy = create_eviction_policy(name, max_memory)?;
+            Ok(replay_trace(name, policy.as_ref(), trace))
+        })
+        .collect()
+}
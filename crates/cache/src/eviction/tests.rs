@@ -1,6 +1,7 @@
 //! Tests for eviction policies
 
 use super::*;
+use std::time::Duration;
 
 #[test]
 fn test_lru_eviction() {
@@ -66,3 +67,90 @@ fn test_arc_adaptation() {
     let evicted = policy.next_eviction().unwrap();
     assert!(evicted == "c" || evicted == "d");
 }
+
+#[test]
+fn test_gdsf_keeps_expensive_small_entries() {
+    let policy = GdsfPolicy::new(1000);
+
+    policy.on_insert("cheap-large", 600);
+    policy.on_cost("cheap-large", Duration::from_millis(10));
+    policy.on_insert("costly-small", 300);
+    policy.on_cost("costly-small", Duration::from_secs(30));
+    policy.on_insert("new", 200); // Total: 1100, over limit
+    policy.on_cost("new", Duration::from_secs(1));
+
+    // Lowest cost per byte goes first
+    assert_eq!(policy.next_eviction(), Some("cheap-large".to_string()));
+    assert!(policy.inflation() > 0.0);
+}
+
+#[test]
+fn test_gdsf_hits_and_aging() {
+    let policy = GdsfPolicy::new(1000);
+
+    policy.on_insert("a", 500);
+    policy.on_insert("b", 500);
+    policy.on_access("a", 500);
+    policy.on_insert("c", 500); // Total: 1500, over limit
+
+    // 'b' has fewer hits than 'a'; its tie with 'c' goes to the smaller key
+    assert_eq!(policy.next_eviction(), Some("b".to_string()));
+    policy.on_remove("b", 500);
+
+    // 'd' starts on top of the raised inflation value, so the equally
+    // valuable but older 'c' goes first
+    policy.on_insert("d", 500);
+    assert_eq!(policy.memory_usage(), 1500);
+    assert_eq!(policy.next_eviction(), Some("c".to_string()));
+
+    policy.clear();
+    assert_eq!(policy.memory_usage(), 0);
+    assert_eq!(policy.inflation(), 0.0);
+}
+
+#[test]
+fn test_replay_prefers_gdsf_on_costly_working_set() {
+    // A few expensive builds reused often, interleaved with a scan of large,
+    // cheap artifacts that are never read twice
+    let trace: Vec<TraceAccess> = (0..50)
+        .flat_map(|round| {
+            let hot = (0..3).map(|i| TraceAccess {
+                key: format!("build-{i}"),
+                size: 100,
+                cost_ms: 20_000,
+            });
+            let scan = (0..2).map(move |i| TraceAccess {
+                key: format!("artifact-{round}-{i}"),
+                size: 400,
+                cost_ms: 50,
+            });
+            hot.chain(scan).collect::<Vec<_>>()
+        })
+        .collect();
+
+    let reports = compare_policies(&trace, 1000).unwrap();
+    let by_name = |name: &str| reports.iter().find(|r| r.policy == name).unwrap();
+
+    let gdsf = by_name("gdsf");
+    assert_eq!(gdsf.requests, trace.len() as u64);
+    assert!(gdsf.cost_saved_ratio() > 0.9);
+    assert!(gdsf.cost_saved_ratio() >= by_name("lru").cost_saved_ratio());
+}
+
+#[test]
+fn test_load_trace() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace.jsonl");
+    std::fs::write(
+        &path,
+        "{\"key\":\"a\",\"size\":10,\"costMs\":5}\n\n{\"key\":\"b\",\"size\":20,\"costMs\":7}\n",
+    )
+    .unwrap();
+
+    let trace = load_trace(&path).unwrap();
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[1].cost_ms, 7);
+
+    std::fs::write(&path, "not json\n").unwrap();
+    assert!(load_trace(&path).is_err());
+}
@@ -1,5 +1,7 @@
 //! Core eviction policy trait definition
 
+use std::time::Duration;
+
 /// Eviction policy trait
 pub trait EvictionPolicy: Send + Sync {
     /// Record access to a key
@@ -11,6 +13,11 @@ pub trait EvictionPolicy: Send + Sync {
     /// Record removal of a key
     fn on_remove(&self, key: &str, size: u64);
 
+    /// Record how long the value for a key took to compute
+    ///
+    /// Only cost-aware policies use this; the others ignore it.
+    fn on_cost(&self, _key: &str, _cost: Duration) {}
+
     /// Get next key to evict
     fn next_eviction(&self) -> Option<String>;
 
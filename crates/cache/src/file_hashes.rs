@@ -0,0 +1,199 @@
+//! Persistent file hash cache
+//!
+//! Input files are recognised by their size and modification time. While
+//! both match what was recorded, the stored hash is reused instead of reading
+//! the file again. Idle-time maintenance refreshes entries for files that
+//! changed, so the next task run finds them up to date.
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, Metadata, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CACHE_FILE: &str = "file-hashes.json";
+const LOCK_FILE: &str = "file-hashes.lock";
+
+/// Files modified this recently are not recorded: a write within the same
+/// timestamp tick would go unnoticed
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Recorded hashes by absolute path
+pub type FileHashes = BTreeMap<PathBuf, FileHashEntry>;
+
+/// Hash of a file's content and the metadata it was taken from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHashEntry {
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch
+    pub modified_ns: u64,
+    /// SHA-256 of the content, hex encoded
+    pub hash: String,
+}
+
+impl FileHashEntry {
+    /// Record `hash` for a file described by `metadata`
+    ///
+    /// Returns `None` when the modification time is unavailable.
+    pub fn new(metadata: &Metadata, hash: String) -> Option<Self> {
+        Some(Self {
+            size: metadata.len(),
+            modified_ns: modified_ns(metadata)?,
+            hash,
+        })
+    }
+
+    /// Hash the file at `path`
+    pub fn compute(path: &Path) -> Result<Self> {
+        let metadata =
+            fs::metadata(path).map_err(|e| Error::file_system(path, "read file metadata", e))?;
+        let mut file = fs::File::open(path)
+            .map_err(|e| Error::file_system(path, "open file for hashing", e))?;
+
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 8192];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| Error::file_system(path, "read file chunk for hashing", e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Self::new(&metadata, format!("{:x}", hasher.finalize())).ok_or_else(|| {
+            Error::configuration(format!(
+                "Modification time unavailable for {}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Whether `metadata` still describes the file this entry was taken from
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        metadata.len() == self.size && modified_ns(metadata) == Some(self.modified_ns)
+    }
+
+    /// Whether the file was last modified long enough ago to be recorded
+    pub fn is_settled(&self) -> bool {
+        let modified = UNIX_EPOCH + Duration::from_nanos(self.modified_ns);
+        SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age >= SETTLE_TIME)
+    }
+}
+
+/// File hashes stored in the cache directory
+#[derive(Debug, Clone)]
+pub struct FileHashCache {
+    dir: PathBuf,
+}
+
+impl FileHashCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.to_path_buf(),
+        }
+    }
+
+    /// Recorded hashes; a missing or unreadable cache is empty
+    pub fn load(&self) -> FileHashes {
+        fs::read_to_string(self.dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record settled entries, replacing what was stored for their paths
+    pub fn store(&self, entries: impl IntoIterator<Item = (PathBuf, FileHashEntry)>) -> Result<()> {
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|(_, entry)| entry.is_settled())
+            .collect();
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.update(|hashes| hashes.extend(entries))
+    }
+
+    /// Change the stored hashes while holding the cache lock
+    ///
+    /// The lock is held only for the read-modify-write, so concurrent task
+    /// runs and maintenance never lose each other's updates.
+    pub fn update(&self, apply: impl FnOnce(&mut FileHashes)) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| Error::file_system(&self.dir, "create cache directory", e))?;
+        let lock_path = self.dir.join(LOCK_FILE);
+        let lock = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| Error::file_system(&lock_path, "open file hash lock", e))?;
+        lock.lock_exclusive()
+            .map_err(|e| Error::file_system(&lock_path, "lock file hash cache", e))?;
+
+        let mut hashes = self.load();
+        apply(&mut hashes);
+        let content = serde_json::to_string(&hashes).map_err(|e| Error::Json {
+            message: "Failed to serialize file hashes".to_string(),
+            source: e,
+        })?;
+        write_atomic_string(&self.dir.join(CACHE_FILE), &content)
+    }
+}
+
+fn modified_ns(metadata: &Metadata) -> Option<u64> {
+    let since_epoch = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_nanos()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn backdate(path: &Path) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_entries_round_trip_and_detect_changes() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("input.txt");
+        fs::write(&file, "hello").unwrap();
+        backdate(&file);
+
+        let cache = FileHashCache::new(&dir.path().join("cache"));
+        let entry = FileHashEntry::compute(&file).unwrap();
+        cache.store([(file.clone(), entry.clone())]).unwrap();
+
+        let stored = cache.load();
+        assert_eq!(stored.get(&file), Some(&entry));
+        assert!(entry.matches(&fs::metadata(&file).unwrap()));
+
+        fs::write(&file, "changed").unwrap();
+        assert!(!entry.matches(&fs::metadata(&file).unwrap()));
+    }
+
+    #[test]
+    fn test_recently_modified_files_are_not_stored() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("input.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let cache = FileHashCache::new(dir.path());
+        let entry = FileHashEntry::compute(&file).unwrap();
+        assert!(!entry.is_settled());
+        cache.store([(file, entry)]).unwrap();
+        assert!(cache.load().is_empty());
+    }
+}
@@ -236,6 +236,17 @@ impl HashEngine {
     }
 }
 
+/// Prefix of inputs naming files of another package, e.g. `pkg://projects:lib/**`
+pub const PACKAGE_INPUT_PREFIX: &str = "pkg://";
+
+/// Split a `pkg://` input into its package and the pattern within it
+///
+/// The pattern is empty when the input names the whole package.
+pub fn split_package_input(input: &str) -> Option<(&str, &str)> {
+    let reference = input.strip_prefix(PACKAGE_INPUT_PREFIX)?;
+    Some(reference.split_once('/').unwrap_or((reference, "")))
+}
+
 /// Expand a glob pattern to find matching files
 pub fn expand_glob_pattern(pattern: &str, base_dir: &Path) -> Result<Vec<PathBuf>> {
     // Check if it's a direct file path (no glob chars)
@@ -597,4 +608,17 @@ mod tests {
 
         assert_ne!(hash1, hash2, "Hash should depend on insertion order");
     }
+
+    #[test]
+    fn test_split_package_input() {
+        assert_eq!(
+            split_package_input("pkg://projects:lib/src/**"),
+            Some(("projects:lib", "src/**"))
+        );
+        assert_eq!(
+            split_package_input("pkg://projects:lib"),
+            Some(("projects:lib", ""))
+        );
+        assert_eq!(split_package_input("src/**"), None);
+    }
 }
@@ -54,6 +54,7 @@ mod tests {
             include: Some(vec!["PATH".to_string()]),
             exclude: Some(vec!["PS1".to_string()]),
             use_smart_defaults: Some(false),
+            env_all: None,
         };
 
         let config: CacheKeyFilterConfig = cue_config.into();
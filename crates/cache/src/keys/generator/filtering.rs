@@ -2,6 +2,7 @@
 
 use crate::keys::config::CacheKeyFilterConfig;
 use crate::keys::filter::{PatternMatcher, SmartDefaults};
+use cuenv_core::CUENV_GIT_VAR_PREFIX;
 use regex::Regex;
 use std::collections::HashMap;
 
@@ -35,7 +36,8 @@ impl FilterLogic {
             }
         }
 
-        // Check include patterns
+        // Check include patterns; this is the only way git metadata variables
+        // make it into a key
         let has_include_patterns = !config.include.is_empty();
         if has_include_patterns {
             for pattern in include_patterns {
@@ -59,7 +61,7 @@ impl FilterLogic {
         }
 
         // If no patterns and no smart defaults, include all variables
-        true
+        !var_name.starts_with(CUENV_GIT_VAR_PREFIX)
     }
 
     /// Check if variable matches smart default patterns
@@ -87,6 +87,37 @@ mod generator_tests {
         assert!(!filtered.contains_key("PWD"));
     }
 
+    #[test]
+    fn test_git_metadata_is_opt_in() {
+        let mut env_vars = HashMap::new();
+        env_vars.insert("PATH".to_string(), "/usr/bin".to_string());
+        env_vars.insert("CUENV_GIT_SHA".to_string(), "3f2a9c".to_string());
+        env_vars.insert("CUENV_GIT_DIRTY".to_string(), "false".to_string());
+
+        let defaults = CacheKeyGenerator::with_config(CacheKeyFilterConfig::default()).unwrap();
+        let filtered = defaults.filter_env_vars("test", &env_vars);
+        assert!(filtered.contains_key("PATH"));
+        assert!(!filtered.contains_key("CUENV_GIT_SHA"));
+
+        let everything = CacheKeyGenerator::with_config(CacheKeyFilterConfig {
+            use_smart_defaults: false,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!everything
+            .filter_env_vars("test", &env_vars)
+            .contains_key("CUENV_GIT_SHA"));
+
+        let opted_in = CacheKeyGenerator::with_config(CacheKeyFilterConfig {
+            include: vec!["CUENV_GIT_SHA".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let filtered = opted_in.filter_env_vars("test", &env_vars);
+        assert!(filtered.contains_key("CUENV_GIT_SHA"));
+        assert!(!filtered.contains_key("CUENV_GIT_DIRTY"));
+    }
+
     #[test]
     fn test_cache_key_generation() {
         let generator = CacheKeyGenerator::new().unwrap();
@@ -1,5 +1,6 @@
 //! Hash computation and path normalization for cache keys
 
+use cuenv_utils::portable_path::{normalize_portable_path, PathCasePolicy};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::Path;
@@ -54,59 +55,16 @@ impl HashComputer {
 
     /// Normalize working directory path for consistent cache keys across platforms
     pub fn normalize_working_dir(path: &Path) -> String {
-        // Normalize path separators to forward slashes for consistency
-        let path_str = path.to_string_lossy();
-        let mut normalized = path_str.replace('\\', "/");
-
-        // Remove trailing slashes and dots for consistency
-        while normalized.ends_with('/') || normalized.ends_with("/.") {
-            if normalized.ends_with("/.") {
-                normalized.truncate(normalized.len() - 2);
-            } else if normalized.ends_with('/') {
-                normalized.truncate(normalized.len() - 1);
-            }
-        }
-
-        // Handle path components like `/tmp/../project` by resolving them
-        // This is a simplified path resolution that doesn't access the filesystem
-        let mut components = Vec::new();
-        for component in normalized.split('/') {
-            match component {
-                "" | "." => continue, // Skip empty and current directory references
-                ".." => {
-                    if !components.is_empty() && components.last() != Some(&"..") {
-                        components.pop(); // Go up one directory
-                    } else if !normalized.starts_with('/') {
-                        // For relative paths, keep the ".."
-                        components.push(component);
-                    }
-                    // For absolute paths, ".." at root is ignored
-                }
-                _ => components.push(component),
-            }
-        }
-
-        let resolved = if normalized.starts_with('/') {
-            format!("/{}", components.join("/"))
-        } else {
-            components.join("/")
-        };
-
-        // Handle empty path case
-        if resolved.is_empty() || resolved == "/" {
-            return "/".to_string();
-        }
+        // Normalize separators, drive letters, UNC and verbatim prefixes so
+        // the same directory hashes identically on every platform
+        let normalized =
+            normalize_portable_path(&path.to_string_lossy(), PathCasePolicy::FoldWindows);
 
         // Convert relative paths to absolute-style paths for consistency
-        if !resolved.starts_with('/') && !resolved.contains(':') {
-            format!("/{resolved}")
-        } else if cfg!(windows) && resolved.len() > 1 && resolved.chars().nth(1) == Some(':') {
-            // Convert Windows drive letters to forward-slash format (C: -> /c)
-            let drive_letter = resolved.chars().next().unwrap().to_lowercase();
-            let rest = &resolved[2..];
-            format!("/{drive_letter}{rest}")
-        } else {
-            resolved
+        match normalized.as_str() {
+            "." => "/".to_string(),
+            _ if normalized.starts_with('/') => normalized,
+            _ => format!("/{normalized}"),
         }
     }
 }
@@ -178,4 +136,24 @@ mod tests {
         );
         assert_eq!(HashComputer::normalize_working_dir(Path::new("/")), "/");
     }
+
+    #[test]
+    fn test_normalize_working_dir_windows_forms() {
+        let expected = "/c/users/dev/project";
+        for path in [
+            r"C:\Users\Dev\project",
+            "c:/users/dev/project/",
+            r"\\?\C:\Users\Dev\project",
+        ] {
+            assert_eq!(
+                HashComputer::normalize_working_dir(Path::new(path)),
+                expected
+            );
+        }
+
+        assert_eq!(
+            HashComputer::normalize_working_dir(Path::new(r"\\build\cache\project")),
+            "//build/cache/project"
+        );
+    }
 }
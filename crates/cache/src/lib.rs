@@ -16,14 +16,17 @@ pub mod content_addressed_store;
 pub mod core;
 pub mod engine;
 pub mod entry;
+pub mod env_usage;
 pub mod errors;
 pub mod eviction;
 #[path = "fast-path/mod.rs"]
 pub mod fast_path;
+pub mod file_hashes;
 pub mod hashing;
 pub mod health;
 pub mod item;
 pub mod keys;
+pub mod maintenance;
 pub mod manager;
 pub mod memory_manager;
 pub mod metrics;
@@ -31,6 +34,7 @@ pub mod mode;
 pub mod monitored;
 pub mod monitoring;
 pub mod performance;
+pub mod remote;
 pub mod security;
 pub mod serialization;
 pub mod storage;
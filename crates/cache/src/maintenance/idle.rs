@@ -0,0 +1,76 @@
+//! Idleness detection
+//!
+//! The machine counts as idle when no shell prompt has been drawn for the
+//! quiet period, the load average is low and no cuenv instance is loading or
+//! unloading an environment.
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::sync::env::InstanceLock;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use sysinfo::System;
+
+const ACTIVITY_FILE: &str = "activity";
+
+/// Decides whether background work may run now
+#[derive(Debug, Clone)]
+pub struct IdleDetector {
+    activity_file: PathBuf,
+    quiet_period: Duration,
+    max_load_per_cpu: f64,
+}
+
+impl IdleDetector {
+    pub fn new(dir: &Path, quiet_period: Duration, max_load_per_cpu: f64) -> Self {
+        Self {
+            activity_file: dir.join(ACTIVITY_FILE),
+            quiet_period,
+            max_load_per_cpu,
+        }
+    }
+
+    /// Note interactive use, postponing work by the quiet period
+    pub fn record_activity(&self) -> Result<()> {
+        if let Some(dir) = self.activity_file.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| Error::file_system(dir, "create maintenance directory", e))?;
+        }
+        fs::write(&self.activity_file, b"")
+            .map_err(|e| Error::file_system(&self.activity_file, "record activity", e))
+    }
+
+    /// Time since activity was last recorded; `None` when it never was
+    pub fn quiet_for(&self) -> Option<Duration> {
+        let modified = fs::metadata(&self.activity_file).ok()?.modified().ok()?;
+        Some(
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn is_idle(&self) -> bool {
+        let quiet = self
+            .quiet_for()
+            .is_none_or(|quiet| quiet >= self.quiet_period);
+        quiet && load_per_cpu() <= self.max_load_per_cpu && !cuenv_busy()
+    }
+}
+
+/// One-minute load average divided by the number of CPUs
+///
+/// Platforms without a load average report zero.
+pub fn load_per_cpu() -> f64 {
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    System::load_average().one / cpus as f64
+}
+
+/// Whether another cuenv process holds the instance lock
+fn cuenv_busy() -> bool {
+    // The lock is released straight away so the other process is never held up
+    matches!(
+        InstanceLock::try_acquire(),
+        Err(Error::Configuration { .. })
+    )
+}
@@ -0,0 +1,62 @@
+//! Removal of stale task cache entries and hash manifests
+
+use super::{dir_entries, is_older_than, next_batch, JobStep, MaintenanceJob};
+use cuenv_core::{Error, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Removes entries of the given cache directories not touched for `max_age`
+#[derive(Debug, Clone)]
+pub struct CacheCleanupJob {
+    dirs: Vec<PathBuf>,
+    max_age: Duration,
+}
+
+impl CacheCleanupJob {
+    pub fn new(dirs: Vec<PathBuf>, max_age: Duration) -> Self {
+        Self { dirs, max_age }
+    }
+}
+
+impl MaintenanceJob for CacheCleanupJob {
+    fn name(&self) -> &'static str {
+        "cache-cleanup"
+    }
+
+    fn step(&self, cursor: Option<&str>, batch: usize) -> Result<JobStep> {
+        let entries = self.dirs.iter().flat_map(|dir| dir_entries(dir)).collect();
+        let (entries, next) = next_batch(entries, cursor, batch);
+
+        let mut step = JobStep {
+            next,
+            processed: entries.len(),
+            ..JobStep::default()
+        };
+        for (_, path) in entries {
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            let stale = metadata
+                .modified()
+                .is_ok_and(|modified| is_older_than(modified, self.max_age));
+            if !stale {
+                continue;
+            }
+
+            let removed = if metadata.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            match removed {
+                Ok(()) => step.removed += 1,
+                // Already removed by another cleanup
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(Error::file_system(path, "remove stale cache entry", e)),
+            }
+        }
+        Ok(step)
+    }
+}
@@ -0,0 +1,83 @@
+//! Refreshing recorded file hashes ahead of the next task run
+
+use super::{next_batch, JobStep, MaintenanceJob};
+use crate::file_hashes::{FileHashCache, FileHashEntry};
+use cuenv_core::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Rehashes recorded files that changed and forgets files that are gone
+#[derive(Debug, Clone)]
+pub struct FileHashRefreshJob {
+    cache: FileHashCache,
+}
+
+impl FileHashRefreshJob {
+    pub fn new(cache: FileHashCache) -> Self {
+        Self { cache }
+    }
+}
+
+impl MaintenanceJob for FileHashRefreshJob {
+    fn name(&self) -> &'static str {
+        "file-hash-refresh"
+    }
+
+    fn step(&self, cursor: Option<&str>, batch: usize) -> Result<JobStep> {
+        let recorded = self
+            .cache
+            .load()
+            .into_iter()
+            .map(|(path, entry)| (path.to_string_lossy().into_owned(), (path, entry)))
+            .collect();
+        let (entries, next) = next_batch(recorded, cursor, batch);
+
+        // New value per changed path: `None` when the file is gone
+        let mut changes: BTreeMap<PathBuf, (FileHashEntry, Option<FileHashEntry>)> =
+            BTreeMap::new();
+        let processed = entries.len();
+        for (_, (path, entry)) in entries {
+            match fs::metadata(&path) {
+                Ok(metadata) if entry.matches(&metadata) => {}
+                Ok(_) => {
+                    // Files that cannot be read now are retried on the next pass
+                    if let Ok(fresh) = FileHashEntry::compute(&path) {
+                        if fresh.is_settled() {
+                            changes.insert(path, (entry, Some(fresh)));
+                        }
+                    }
+                }
+                Err(_) => {
+                    changes.insert(path, (entry, None));
+                }
+            }
+        }
+
+        let removed = changes
+            .values()
+            .filter(|(_, fresh)| fresh.is_none())
+            .count();
+        let step = JobStep {
+            next,
+            processed,
+            removed,
+            refreshed: changes.len() - removed,
+        };
+        if !changes.is_empty() {
+            self.cache.update(|hashes| {
+                for (path, (seen, fresh)) in changes {
+                    // A task run may have recorded a newer hash in the meantime
+                    if hashes.get(&path) != Some(&seen) {
+                        continue;
+                    }
+                    match fresh {
+                        Some(fresh) => hashes.insert(path, fresh),
+                        None => hashes.remove(&path),
+                    };
+                }
+            })?;
+        }
+        Ok(step)
+    }
+}
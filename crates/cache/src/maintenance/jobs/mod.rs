@@ -0,0 +1,82 @@
+//! Incremental maintenance jobs
+//!
+//! A job walks its items in a stable order a batch at a time. The cursor it
+//! returns is saved between batches, so a run that stops because the machine
+//! got busy picks up where it left off next time.
+
+mod cache_cleanup;
+mod file_hash_refresh;
+mod state_prune;
+
+pub use cache_cleanup::CacheCleanupJob;
+pub use file_hash_refresh::FileHashRefreshJob;
+pub use state_prune::StatePruneJob;
+
+use cuenv_core::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A unit of maintenance work that can be done in small steps
+pub trait MaintenanceJob: Send + Sync {
+    /// Stable name, used to remember the job's progress
+    fn name(&self) -> &'static str;
+
+    /// Handle up to `batch` items after `cursor`
+    fn step(&self, cursor: Option<&str>, batch: usize) -> Result<JobStep>;
+}
+
+/// What a single step did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobStep {
+    /// Where the next step continues; `None` once the pass is finished
+    pub next: Option<String>,
+    /// Items looked at
+    pub processed: usize,
+    /// Items deleted
+    pub removed: usize,
+    /// Items brought up to date
+    pub refreshed: usize,
+}
+
+/// The next `batch` items after `cursor` in key order, and the cursor that
+/// follows them
+///
+/// The returned cursor is `None` when the batch reaches the end.
+pub(super) fn next_batch<T>(
+    mut items: Vec<(String, T)>,
+    cursor: Option<&str>,
+    batch: usize,
+) -> (Vec<(String, T)>, Option<String>) {
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    let remaining: Vec<_> = items
+        .into_iter()
+        .filter(|(key, _)| cursor.is_none_or(|cursor| key.as_str() > cursor))
+        .collect();
+    let more = remaining.len() > batch;
+    let taken: Vec<_> = remaining.into_iter().take(batch).collect();
+    let next = taken.last().filter(|_| more).map(|(key, _)| key.clone());
+    (taken, next)
+}
+
+/// Entries of `dir`, keyed by path; a missing directory has none
+pub(super) fn dir_entries(dir: &Path) -> Vec<(String, PathBuf)> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| {
+                    let path = entry.path();
+                    (path.to_string_lossy().into_owned(), path)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether `modified` lies further back than `max_age`
+pub(super) fn is_older_than(modified: SystemTime, max_age: Duration) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .is_ok_and(|age| age > max_age)
+}
@@ -0,0 +1,59 @@
+//! Removal of stale shell state
+
+use super::{next_batch, JobStep, MaintenanceJob};
+use cuenv_core::Result;
+use cuenv_utils::state_prune::{StaleState, StatePruner};
+
+type BeforeRemove = Box<dyn Fn(&StaleState) + Send + Sync>;
+
+/// Removes the state a [`StatePruner`] finds stale
+///
+/// State whose supervisor lock is held is in use and left alone.
+pub struct StatePruneJob {
+    pruner: StatePruner,
+    before_remove: Option<BeforeRemove>,
+}
+
+impl StatePruneJob {
+    pub fn new(pruner: StatePruner) -> Self {
+        Self {
+            pruner,
+            before_remove: None,
+        }
+    }
+
+    /// Call `hook` on each state directory about to be removed, e.g. to stop
+    /// services it still records
+    pub fn before_remove(mut self, hook: impl Fn(&StaleState) + Send + Sync + 'static) -> Self {
+        self.before_remove = Some(Box::new(hook));
+        self
+    }
+}
+
+impl MaintenanceJob for StatePruneJob {
+    fn name(&self) -> &'static str {
+        "state-prune"
+    }
+
+    fn step(&self, cursor: Option<&str>, batch: usize) -> Result<JobStep> {
+        let stale = self
+            .pruner
+            .find()
+            .into_iter()
+            .map(|state| (state.path.to_string_lossy().into_owned(), state))
+            .collect();
+        let (entries, next) = next_batch(stale, cursor, batch);
+        let stale: Vec<_> = entries.into_iter().map(|(_, state)| state).collect();
+
+        let removed = match &self.before_remove {
+            Some(hook) => self.pruner.prune_with(&stale, hook)?,
+            None => self.pruner.prune(&stale)?,
+        };
+        Ok(JobStep {
+            next,
+            processed: stale.len(),
+            removed,
+            ..JobStep::default()
+        })
+    }
+}
@@ -0,0 +1,229 @@
+//! Idle-time cache maintenance
+//!
+//! Stale cache cleanup, pruning of old shell state and refreshing the file
+//! hash cache are done in small batches while the machine is idle, so that
+//! interactive commands never pay for them. The shell hook can launch a run
+//! in the background; launches are rate limited and a lock keeps at most one
+//! run going at a time.
+
+mod idle;
+mod jobs;
+mod state;
+
+pub use idle::{load_per_cpu, IdleDetector};
+pub use jobs::{CacheCleanupJob, FileHashRefreshJob, JobStep, MaintenanceJob, StatePruneJob};
+
+use crate::file_hashes::FileHashCache;
+use cuenv_core::{Error, Result};
+use fs2::FileExt;
+use state::{now_secs, MaintenanceState};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Subdirectory of the cache directory holding maintenance state
+const MAINTENANCE_DIR: &str = "maintenance";
+/// Held for the whole of a run
+const RUN_LOCK: &str = "run.lock";
+/// Held while the saved state is read and written
+const STATE_LOCK: &str = "state.lock";
+/// How often a run waiting for the machine to go idle checks again
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Tuning for maintenance runs
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    /// Minimum time between two launched runs
+    pub min_interval: Duration,
+    /// How long no shell prompt must have been drawn before work starts
+    pub quiet_period: Duration,
+    /// Highest one-minute load average per CPU that still counts as idle
+    pub max_load_per_cpu: f64,
+    /// Longest a run may last, time spent waiting for idleness included
+    pub budget: Duration,
+    /// Items each job handles before idleness is checked again
+    pub batch_size: usize,
+    /// Age after which task cache entries and hash manifests are removed
+    pub cache_max_age: Duration,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(60 * 60),
+            quiet_period: Duration::from_secs(60),
+            max_load_per_cpu: 0.25,
+            budget: Duration::from_secs(30 * 60),
+            batch_size: 64,
+            cache_max_age: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// What a run did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    pub processed: usize,
+    pub removed: usize,
+    pub refreshed: usize,
+    /// Whether every job finished its pass; otherwise the next run resumes
+    pub completed: bool,
+}
+
+/// Runs maintenance jobs for a cache directory
+pub struct Maintenance {
+    dir: PathBuf,
+    config: MaintenanceConfig,
+    idle: IdleDetector,
+    jobs: Vec<Box<dyn MaintenanceJob>>,
+}
+
+impl Maintenance {
+    /// Maintenance with the built-in jobs, pruning shell state with `state`
+    pub fn new(cache_dir: &Path, state: StatePruneJob, config: MaintenanceConfig) -> Self {
+        let jobs: Vec<Box<dyn MaintenanceJob>> = vec![
+            Box::new(CacheCleanupJob::new(
+                vec![cache_dir.join("hashes"), cache_dir.join("tasks")],
+                config.cache_max_age,
+            )),
+            Box::new(state),
+            Box::new(FileHashRefreshJob::new(FileHashCache::new(cache_dir))),
+        ];
+        Self::with_jobs(cache_dir, config, jobs)
+    }
+
+    pub fn with_jobs(
+        cache_dir: &Path,
+        config: MaintenanceConfig,
+        jobs: Vec<Box<dyn MaintenanceJob>>,
+    ) -> Self {
+        let dir = cache_dir.join(MAINTENANCE_DIR);
+        let idle = IdleDetector::new(&dir, config.quiet_period, config.max_load_per_cpu);
+        Self {
+            dir,
+            config,
+            idle,
+            jobs,
+        }
+    }
+
+    pub fn idle_detector(&self) -> &IdleDetector {
+        &self.idle
+    }
+
+    /// Claim the next launch if one is due
+    ///
+    /// Returns `true` at most once per `min_interval`, however many shells
+    /// ask at the same time.
+    pub fn claim_launch(&self) -> Result<bool> {
+        let _lock = self.lock(STATE_LOCK, true)?;
+        let mut state = MaintenanceState::load(&self.dir);
+        let now = now_secs();
+        let due = state
+            .last_claimed
+            .is_none_or(|last| now.saturating_sub(last) >= self.config.min_interval.as_secs());
+        if due {
+            state.last_claimed = Some(now);
+            state.save(&self.dir)?;
+        }
+        Ok(due)
+    }
+
+    /// Run jobs until every pass is finished, the budget is spent or the
+    /// machine is busy
+    ///
+    /// With `wait_for_idle` a busy machine is waited out, within the budget.
+    /// Returns `None` when another run is already in progress.
+    pub fn run(&self, wait_for_idle: bool) -> Result<Option<MaintenanceReport>> {
+        let Some(_running) = self.lock(RUN_LOCK, false)? else {
+            return Ok(None);
+        };
+        let deadline = Instant::now() + self.config.budget;
+        let mut report = MaintenanceReport::default();
+
+        for job in &self.jobs {
+            let name = job.name();
+            if MaintenanceState::load(&self.dir).finished.contains(name) {
+                continue;
+            }
+
+            loop {
+                if Instant::now() >= deadline {
+                    return Ok(Some(report));
+                }
+                if !self.idle.is_idle() {
+                    if !wait_for_idle {
+                        return Ok(Some(report));
+                    }
+                    std::thread::sleep(IDLE_POLL_INTERVAL.min(deadline - Instant::now()));
+                    continue;
+                }
+
+                let cursor = MaintenanceState::load(&self.dir).cursors.remove(name);
+                let step = match job.step(cursor.as_deref(), self.config.batch_size) {
+                    Ok(step) => step,
+                    Err(e) => {
+                        // Retried from the same cursor on the next run
+                        log::warn!("Maintenance job {name} failed: {e}");
+                        return Ok(Some(report));
+                    }
+                };
+                report.processed += step.processed;
+                report.removed += step.removed;
+                report.refreshed += step.refreshed;
+
+                let done = step.next.is_none();
+                self.update_state(|state| match step.next {
+                    Some(next) => {
+                        state.cursors.insert(name.to_string(), next);
+                    }
+                    None => {
+                        state.cursors.remove(name);
+                        state.finished.insert(name.to_string());
+                    }
+                })?;
+                if done {
+                    break;
+                }
+            }
+        }
+
+        self.update_state(|state| {
+            state.finished.clear();
+            state.last_completed = Some(now_secs());
+        })?;
+        report.completed = true;
+        Ok(Some(report))
+    }
+
+    fn update_state(&self, apply: impl FnOnce(&mut MaintenanceState)) -> Result<()> {
+        let _lock = self.lock(STATE_LOCK, true)?;
+        let mut state = MaintenanceState::load(&self.dir);
+        apply(&mut state);
+        state.save(&self.dir)
+    }
+
+    /// Lock `name` in the maintenance directory; `None` when `wait` is false
+    /// and the lock is held elsewhere
+    fn lock(&self, name: &str, wait: bool) -> Result<Option<File>> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| Error::file_system(&self.dir, "create maintenance directory", e))?;
+        let path = self.dir.join(name);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| Error::file_system(&path, "open maintenance lock", e))?;
+        if wait {
+            file.lock_exclusive()
+                .map_err(|e| Error::file_system(&path, "lock maintenance state", e))?;
+            Ok(Some(file))
+        } else {
+            Ok(file.try_lock_exclusive().ok().map(|()| file))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,57 @@
+//! Progress persisted between maintenance runs
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const STATE_FILE: &str = "state.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct MaintenanceState {
+    /// When a run was last launched, in seconds since the Unix epoch
+    #[serde(default)]
+    pub last_claimed: Option<u64>,
+    /// When every job last finished a full pass
+    #[serde(default)]
+    pub last_completed: Option<u64>,
+    /// Where each unfinished job resumes
+    #[serde(default)]
+    pub cursors: BTreeMap<String, String>,
+    /// Jobs that finished the current pass
+    #[serde(default)]
+    pub finished: BTreeSet<String>,
+}
+
+impl MaintenanceState {
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(STATE_FILE)
+    }
+
+    /// Saved state; missing or unreadable state starts over
+    pub fn load(dir: &Path) -> Self {
+        fs::read_to_string(Self::path(dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)
+            .map_err(|e| Error::file_system(dir, "create maintenance directory", e))?;
+        let content = serde_json::to_string_pretty(self).map_err(|e| Error::Json {
+            message: "Failed to serialize maintenance state".to_string(),
+            source: e,
+        })?;
+        write_atomic_string(&Self::path(dir), &content)
+    }
+}
+
+pub(super) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,169 @@
+use super::jobs::next_batch;
+use super::*;
+use crate::file_hashes::FileHashEntry;
+use cuenv_utils::state_prune::{PrunePolicy, StatePruner};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tempfile::TempDir;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn backdate(path: &Path, age: Duration) {
+    let file = File::open(path).unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+fn always_idle() -> MaintenanceConfig {
+    MaintenanceConfig {
+        quiet_period: Duration::ZERO,
+        max_load_per_cpu: f64::MAX,
+        batch_size: 2,
+        ..MaintenanceConfig::default()
+    }
+}
+
+#[test]
+fn test_next_batch_walks_keys_in_order() {
+    let items = |keys: &[&str]| keys.iter().map(|k| (k.to_string(), ())).collect();
+
+    let (batch, next) = next_batch(items(&["c", "a", "b"]), None, 2);
+    assert_eq!(batch.len(), 2);
+    assert_eq!(next.as_deref(), Some("b"));
+
+    let (batch, next) = next_batch(items(&["c", "a", "b"]), next.as_deref(), 2);
+    assert_eq!(batch[0].0, "c");
+    assert_eq!(next, None);
+}
+
+#[test]
+fn test_cache_cleanup_removes_only_stale_entries() {
+    let cache = TempDir::new().unwrap();
+    let tasks = cache.path().join("tasks");
+    fs::create_dir_all(&tasks).unwrap();
+    for name in ["old-1", "old-2", "old-3", "new"] {
+        fs::write(tasks.join(name), name).unwrap();
+    }
+    for name in ["old-1", "old-2", "old-3"] {
+        backdate(&tasks.join(name), 8 * DAY);
+    }
+
+    let job = CacheCleanupJob::new(vec![tasks.clone()], 7 * DAY);
+    let maintenance = Maintenance::with_jobs(cache.path(), always_idle(), vec![Box::new(job)]);
+    let report = maintenance.run(false).unwrap().unwrap();
+
+    assert!(report.completed);
+    assert_eq!(report.processed, 4);
+    assert_eq!(report.removed, 3);
+    assert!(tasks.join("new").exists());
+}
+
+#[test]
+fn test_state_prune_skips_locked_directories() {
+    let root = TempDir::new().unwrap();
+    let (busy, unused) = (root.path().join("busy"), root.path().join("unused"));
+    for dir in [&busy, &unused] {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("supervisor.lock"), "").unwrap();
+        backdate(&dir.join("supervisor.lock"), 30 * DAY);
+        backdate(dir, 30 * DAY);
+    }
+    let held = File::options()
+        .write(true)
+        .open(busy.join("supervisor.lock"))
+        .unwrap();
+    held.lock_exclusive().unwrap();
+
+    let pruner = StatePruner::new(
+        root.path().to_path_buf(),
+        Vec::new(),
+        root.path().join("hooks_status.json"),
+        PrunePolicy {
+            max_age: Some(7 * DAY),
+            ..PrunePolicy::default()
+        },
+    );
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let job = {
+        let seen = Arc::clone(&seen);
+        StatePruneJob::new(pruner)
+            .before_remove(move |stale| seen.lock().unwrap().push(stale.path.clone()))
+    };
+
+    let step = job.step(None, 10).unwrap();
+    assert_eq!(step.removed, 1);
+    assert!(busy.exists());
+    assert!(!unused.exists());
+    assert_eq!(*seen.lock().unwrap(), [unused]);
+}
+
+#[test]
+fn test_file_hash_refresh_updates_changed_and_forgets_missing() {
+    let dir = TempDir::new().unwrap();
+    let (changed, deleted) = (dir.path().join("changed"), dir.path().join("deleted"));
+    fs::write(&changed, "before").unwrap();
+    fs::write(&deleted, "gone soon").unwrap();
+    backdate(&changed, DAY);
+    backdate(&deleted, DAY);
+
+    let cache = FileHashCache::new(&dir.path().join("cache"));
+    let before = FileHashEntry::compute(&changed).unwrap();
+    cache
+        .store([
+            (changed.clone(), before.clone()),
+            (deleted.clone(), FileHashEntry::compute(&deleted).unwrap()),
+        ])
+        .unwrap();
+
+    fs::write(&changed, "after").unwrap();
+    backdate(&changed, Duration::from_secs(60));
+    fs::remove_file(&deleted).unwrap();
+
+    let step = FileHashRefreshJob::new(cache.clone())
+        .step(None, 10)
+        .unwrap();
+    assert_eq!((step.refreshed, step.removed), (1, 1));
+
+    let hashes = cache.load();
+    assert_eq!(hashes.len(), 1);
+    assert_ne!(hashes[&changed].hash, before.hash);
+}
+
+#[test]
+fn test_launches_are_rate_limited() {
+    let cache = TempDir::new().unwrap();
+    let maintenance = Maintenance::with_jobs(cache.path(), always_idle(), Vec::new());
+
+    assert!(maintenance.claim_launch().unwrap());
+    assert!(!maintenance.claim_launch().unwrap());
+}
+
+#[test]
+fn test_busy_machine_stops_run_and_resumes_later() {
+    let cache = TempDir::new().unwrap();
+    let tasks = cache.path().join("tasks");
+    fs::create_dir_all(&tasks).unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(tasks.join(name), name).unwrap();
+    }
+    let job = || -> Vec<Box<dyn MaintenanceJob>> {
+        vec![Box::new(CacheCleanupJob::new(vec![tasks.clone()], DAY))]
+    };
+
+    // Recent activity keeps the machine busy for an hour
+    let busy = MaintenanceConfig {
+        quiet_period: Duration::from_secs(60 * 60),
+        ..always_idle()
+    };
+    let maintenance = Maintenance::with_jobs(cache.path(), busy, job());
+    maintenance.idle_detector().record_activity().unwrap();
+    let report = maintenance.run(false).unwrap().unwrap();
+    assert_eq!(report.processed, 0);
+    assert!(!report.completed);
+
+    let report = Maintenance::with_jobs(cache.path(), always_idle(), job())
+        .run(false)
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.processed, 3);
+    assert!(report.completed);
+}
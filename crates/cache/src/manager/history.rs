@@ -0,0 +1,428 @@
+//! Persistent cache statistics history
+//!
+//! Cache statistics only live in memory for the duration of a process. To make
+//! trends visible across runs, each task run appends a snapshot of its cache
+//! activity to a JSONL file in the cache directory. `cuenv cache stats
+//! --history` reads these snapshots back and aggregates them into buckets.
+
+use crate::concurrent::CacheStatSnapshot;
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File name of the history log inside the cache directory
+pub const HISTORY_FILE_NAME: &str = "stats-history.jsonl";
+
+/// Maximum number of snapshots kept when the log is compacted
+const MAX_ENTRIES: usize = 10_000;
+
+/// Size past which the log is compacted, room for about twice `MAX_ENTRIES`
+const COMPACT_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Bucket sizes used when aggregating history into a trend
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const MAX_BUCKETS: u64 = 60;
+
+/// A single persisted statistics snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsHistoryEntry {
+    /// Seconds since the Unix epoch when the snapshot was taken
+    pub timestamp: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub errors: u64,
+    pub bytes_saved: u64,
+}
+
+impl StatsHistoryEntry {
+    /// Build an entry from the activity between two in-memory snapshots,
+    /// stamped with the current time
+    pub fn since_previous(
+        current: &CacheStatSnapshot,
+        previous: Option<&CacheStatSnapshot>,
+    ) -> Self {
+        let delta = |now: u64, before: fn(&CacheStatSnapshot) -> u64| {
+            now.saturating_sub(previous.map(before).unwrap_or(0))
+        };
+        Self {
+            timestamp: unix_now(),
+            hits: delta(current.hits, |s| s.hits),
+            misses: delta(current.misses, |s| s.misses),
+            writes: delta(current.writes, |s| s.writes),
+            errors: delta(current.errors, |s| s.errors),
+            bytes_saved: delta(current.bytes_saved, |s| s.bytes_saved),
+        }
+    }
+
+    /// Whether the snapshot recorded any cache activity at all
+    pub fn is_empty(&self) -> bool {
+        self.hits == 0 && self.misses == 0 && self.writes == 0 && self.errors == 0
+    }
+}
+
+/// Aggregated statistics for one time bucket of a trend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendBucket {
+    /// Start of the bucket, in seconds since the Unix epoch
+    pub start: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub bytes_saved: u64,
+    /// Hit rate in percent, `None` when the bucket saw no lookups
+    pub hit_rate: Option<f64>,
+}
+
+/// Trend report over a window of history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsTrend {
+    pub since: u64,
+    pub until: u64,
+    pub bucket_seconds: u64,
+    pub runs: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub errors: u64,
+    pub bytes_saved: u64,
+    pub hit_rate: f64,
+    pub buckets: Vec<TrendBucket>,
+}
+
+/// Append-only store of statistics snapshots
+pub struct StatsHistory {
+    path: PathBuf,
+}
+
+impl StatsHistory {
+    /// Open the history stored under the given cache directory
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join(HISTORY_FILE_NAME),
+        }
+    }
+
+    /// Path of the underlying history file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a snapshot, skipping runs that never touched the cache
+    pub fn record(&self, entry: &StatsHistoryEntry) -> Result<()> {
+        if entry.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::file_system(parent, "create cache directory", e))?;
+        }
+
+        let line = serde_json::to_string(entry).map_err(|e| Error::Json {
+            message: "failed to serialize cache statistics snapshot".to_string(),
+            source: e,
+        })?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| Error::file_system(&self.path, "open stats history", e))?;
+        writeln!(file, "{line}")
+            .map_err(|e| Error::file_system(&self.path, "append stats history", e))?;
+
+        // Only the size is checked on each run; the log is read back when
+        // it has grown well past what compaction keeps
+        let size = file
+            .metadata()
+            .map_err(|e| Error::file_system(&self.path, "stat stats history", e))?
+            .len();
+        if size > COMPACT_BYTES {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Load all snapshots taken at or after `since`, oldest first
+    ///
+    /// Malformed lines (e.g. from a crash mid-write) are skipped.
+    pub fn load_since(&self, since: SystemTime) -> Result<Vec<StatsHistoryEntry>> {
+        let since = to_unix(since);
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::file_system(&self.path, "open stats history", e)),
+        };
+
+        let mut entries: Vec<StatsHistoryEntry> = BufReader::new(file)
+            .lines()
+            .map_while(|line| line.ok())
+            .filter_map(|line| serde_json::from_str::<StatsHistoryEntry>(&line).ok())
+            .filter(|entry| entry.timestamp >= since)
+            .collect();
+        entries.sort_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+
+    /// Rewrite the log keeping only the newest `MAX_ENTRIES` snapshots
+    fn compact(&self) -> Result<()> {
+        let entries = self.load_since(UNIX_EPOCH)?;
+        if entries.len() <= MAX_ENTRIES {
+            return Ok(());
+        }
+
+        let keep = &entries[entries.len() - MAX_ENTRIES..];
+        let contents = keep
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect::<String>();
+        cuenv_utils::atomic_file::write_atomic_string(&self.path, &contents)
+    }
+}
+
+/// Aggregate entries into a trend covering `[since, until]`
+///
+/// Windows of two days or more are bucketed per day, shorter ones per hour.
+pub fn summarize(
+    entries: &[StatsHistoryEntry],
+    since: SystemTime,
+    until: SystemTime,
+) -> StatsTrend {
+    let since = to_unix(since);
+    let until = to_unix(until).max(since);
+    let range = until - since;
+    let bucket_seconds = if range >= 2 * DAY { DAY } else { HOUR };
+    let bucket_count = range.div_ceil(bucket_seconds).clamp(1, MAX_BUCKETS);
+    let first_start = until.saturating_sub(bucket_count * bucket_seconds);
+
+    let mut buckets: Vec<TrendBucket> = (0..bucket_count)
+        .map(|i| TrendBucket {
+            start: first_start + i * bucket_seconds,
+            hits: 0,
+            misses: 0,
+            bytes_saved: 0,
+            hit_rate: None,
+        })
+        .collect();
+
+    let in_window = entries
+        .iter()
+        .filter(|e| e.timestamp >= since && e.timestamp <= until);
+    for entry in in_window.clone() {
+        let offset = entry.timestamp.saturating_sub(first_start) / bucket_seconds;
+        let index = (offset as usize).min(buckets.len() - 1);
+        let bucket = &mut buckets[index];
+        bucket.hits += entry.hits;
+        bucket.misses += entry.misses;
+        bucket.bytes_saved += entry.bytes_saved;
+    }
+    for bucket in &mut buckets {
+        bucket.hit_rate = percentage(bucket.hits, bucket.misses);
+    }
+
+    let (runs, hits, misses, writes, errors, bytes_saved) = in_window.fold(
+        (0, 0, 0, 0, 0, 0),
+        |(runs, hits, misses, writes, errors, bytes), e| {
+            (
+                runs + 1,
+                hits + e.hits,
+                misses + e.misses,
+                writes + e.writes,
+                errors + e.errors,
+                bytes + e.bytes_saved,
+            )
+        },
+    );
+
+    StatsTrend {
+        since,
+        until,
+        bucket_seconds,
+        runs,
+        hits,
+        misses,
+        writes,
+        errors,
+        bytes_saved,
+        hit_rate: percentage(hits, misses).unwrap_or(0.0),
+        buckets,
+    }
+}
+
+/// Render values as a unicode sparkline; `None` values render as a gap
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = values.iter().flatten().copied().fold(0.0_f64, f64::max);
+    values
+        .iter()
+        .map(|value| match value {
+            None => ' ',
+            Some(_) if max <= 0.0 => BARS[0],
+            Some(v) => {
+                let level = ((v / max) * (BARS.len() - 1) as f64).round() as usize;
+                BARS[level.min(BARS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+/// Look-back window helper: the instant `window` before now
+pub fn since_window(window: Duration) -> SystemTime {
+    SystemTime::now().checked_sub(window).unwrap_or(UNIX_EPOCH)
+}
+
+fn percentage(hits: u64, misses: u64) -> Option<f64> {
+    let total = hits + misses;
+    (total > 0).then(|| hits as f64 / total as f64 * 100.0)
+}
+
+fn unix_now() -> u64 {
+    to_unix(SystemTime::now())
+}
+
+fn to_unix(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(timestamp: u64, hits: u64, misses: u64) -> StatsHistoryEntry {
+        StatsHistoryEntry {
+            timestamp,
+            hits,
+            misses,
+            writes: misses,
+            errors: 0,
+            bytes_saved: hits * 100,
+        }
+    }
+
+    #[test]
+    fn test_record_and_load_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let history = StatsHistory::new(temp_dir.path());
+
+        history.record(&entry(1_000, 3, 1)).unwrap();
+        history.record(&entry(2_000, 1, 1)).unwrap();
+        // Empty runs are not persisted
+        history.record(&entry(3_000, 0, 0)).unwrap();
+
+        let all = history.load_since(UNIX_EPOCH).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let recent = history
+            .load_since(UNIX_EPOCH + Duration::from_secs(1_500))
+            .unwrap();
+        assert_eq!(recent, vec![entry(2_000, 1, 1)]);
+    }
+
+    #[test]
+    fn test_record_compacts_large_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let history = StatsHistory::new(temp_dir.path());
+        let line = serde_json::to_string(&entry(1_000, 1, 0)).unwrap() + "\n";
+        let fits = COMPACT_BYTES as usize / line.len();
+        fs::write(history.path(), line.repeat(fits - 1)).unwrap();
+
+        history.record(&entry(2_000, 1, 0)).unwrap();
+        assert_eq!(history.load_since(UNIX_EPOCH).unwrap().len(), fits);
+
+        fs::write(history.path(), line.repeat(fits)).unwrap();
+        history.record(&entry(2_000, 1, 0)).unwrap();
+        let kept = history.load_since(UNIX_EPOCH).unwrap();
+        assert_eq!(kept.len(), MAX_ENTRIES);
+        assert_eq!(kept.last(), Some(&entry(2_000, 1, 0)));
+    }
+
+    #[test]
+    fn test_since_previous_records_delta() {
+        let previous = CacheStatSnapshot {
+            hits: 2,
+            misses: 1,
+            writes: 1,
+            errors: 0,
+            bytes_saved: 200,
+        };
+        let current = CacheStatSnapshot {
+            hits: 5,
+            misses: 1,
+            writes: 2,
+            errors: 0,
+            bytes_saved: 500,
+        };
+
+        let entry = StatsHistoryEntry::since_previous(&current, Some(&previous));
+        assert_eq!((entry.hits, entry.misses, entry.writes), (3, 0, 1));
+        assert_eq!(entry.bytes_saved, 300);
+
+        let first = StatsHistoryEntry::since_previous(&current, None);
+        assert_eq!(first.hits, 5);
+    }
+
+    #[test]
+    fn test_load_skips_malformed_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let history = StatsHistory::new(temp_dir.path());
+        history.record(&entry(1_000, 1, 0)).unwrap();
+        fs::write(
+            history.path(),
+            format!(
+                "{}\nnot json\n",
+                serde_json::to_string(&entry(1_000, 1, 0)).unwrap()
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(history.load_since(UNIX_EPOCH).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_summarize_buckets_by_hour() {
+        let since = UNIX_EPOCH + Duration::from_secs(10 * HOUR);
+        let until = UNIX_EPOCH + Duration::from_secs(13 * HOUR);
+        let entries = vec![
+            entry(10 * HOUR + 5, 1, 1),
+            entry(12 * HOUR + 5, 4, 0),
+            entry(12 * HOUR + 10, 0, 4),
+        ];
+
+        let trend = summarize(&entries, since, until);
+        assert_eq!(trend.bucket_seconds, HOUR);
+        assert_eq!(trend.buckets.len(), 3);
+        assert_eq!(trend.runs, 3);
+        assert_eq!(trend.hits, 5);
+        assert_eq!(trend.misses, 5);
+        assert_eq!(trend.hit_rate, 50.0);
+        assert_eq!(trend.buckets[0].hit_rate, Some(50.0));
+        assert_eq!(trend.buckets[1].hit_rate, None);
+        assert_eq!(trend.buckets[2].hit_rate, Some(50.0));
+    }
+
+    #[test]
+    fn test_summarize_uses_daily_buckets_for_long_windows() {
+        let since = UNIX_EPOCH + Duration::from_secs(DAY);
+        let until = UNIX_EPOCH + Duration::from_secs(8 * DAY);
+        let trend = summarize(&[], since, until);
+        assert_eq!(trend.bucket_seconds, DAY);
+        assert_eq!(trend.buckets.len(), 7);
+        assert_eq!(trend.hit_rate, 0.0);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[Some(0.0), Some(50.0), Some(100.0)]), "▁▅█");
+        assert_eq!(sparkline(&[None, Some(0.0)]), " ▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
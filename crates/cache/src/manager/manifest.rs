@@ -0,0 +1,323 @@
+//! Signed manifests of the cache entries a run produced
+//!
+//! Every action result the action cache stores or a run reuses is appended
+//! to a run log in the cache directory, keyed by that run. `cuenv cache
+//! manifest` turns the entries of one run into a [`RunManifest`] signed with
+//! the cache's Ed25519 key, and `cuenv cache fetch --manifest` pulls exactly
+//! the objects it lists from a remote cache, e.g. to reuse what CI built.
+//! Manifests are signed, not encrypted, and are only accepted from a key the
+//! caller trusts.
+
+use crate::concurrent::action::{ActionDigest, ActionResult};
+use crate::security::signing::{verify_with_public_key, CacheSigner, SignedCacheEntry};
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the run log inside the cache directory
+pub const RUN_LOG_FILE_NAME: &str = "runs.jsonl";
+
+/// Maximum number of entries kept before the log is compacted
+const MAX_ENTRIES: usize = 10_000;
+
+/// A cache entry as recorded in the run log and listed in manifests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// Action digest the result is cached under
+    pub key: String,
+    pub task: String,
+    /// Run that recorded or reused the result
+    pub run_id: Option<String>,
+    /// Run that produced a result reused by `run_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reused_from: Option<String>,
+    /// Environment selected with `-e` when the action ran
+    pub environment: Option<String>,
+    pub exit_code: i32,
+    /// Seconds since the Unix epoch when the action ran
+    pub executed_at: u64,
+    /// CAS hashes of the captured output streams
+    pub stdout_hash: Option<String>,
+    pub stderr_hash: Option<String>,
+    /// Output files (path -> CAS hash)
+    pub output_files: BTreeMap<String, String>,
+}
+
+impl ManifestEntry {
+    /// Entry for `result`, cached under `digest`
+    pub fn new(digest: &ActionDigest, result: &ActionResult) -> Self {
+        Self {
+            key: digest.hash.clone(),
+            task: digest.components.task_name.clone(),
+            run_id: result.run_id.clone(),
+            reused_from: None,
+            environment: result.environment.clone(),
+            exit_code: result.exit_code,
+            executed_at: to_unix(result.executed_at),
+            stdout_hash: result.stdout_hash.clone(),
+            stderr_hash: result.stderr_hash.clone(),
+            output_files: result.output_files.clone().into_iter().collect(),
+        }
+    }
+
+    /// Entry for `result`, cached under `digest` by another run and reused
+    /// by `run_id`
+    pub fn reused(digest: &ActionDigest, result: &ActionResult, run_id: &str) -> Self {
+        Self {
+            run_id: Some(run_id.to_string()),
+            reused_from: result.run_id.clone(),
+            ..Self::new(digest, result)
+        }
+    }
+
+    /// CAS hashes of every object the entry references
+    pub fn objects(&self) -> impl Iterator<Item = &str> {
+        self.stdout_hash
+            .iter()
+            .chain(&self.stderr_hash)
+            .chain(self.output_files.values())
+            .map(String::as_str)
+    }
+}
+
+/// Cache entries of one run with their provenance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunManifest {
+    pub run_id: String,
+    /// Seconds since the Unix epoch when the manifest was created
+    pub created_at: u64,
+    /// Version of cuenv that created the manifest
+    pub cuenv_version: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// A manifest as written by `cuenv cache manifest`
+pub type SignedManifest = SignedCacheEntry<RunManifest>;
+
+impl RunManifest {
+    /// Manifest of `run_id`, or of the most recent run when `None`
+    pub fn for_run(log: &RunLog, run_id: Option<&str>) -> Result<Self> {
+        let entries = log.load()?;
+        let run_id = match run_id {
+            Some(run_id) => run_id.to_string(),
+            None => entries
+                .iter()
+                .rev()
+                .find_map(|entry| entry.run_id.clone())
+                .ok_or_else(|| Error::configuration("No cached runs recorded yet"))?,
+        };
+
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| entry.run_id.as_deref() == Some(run_id.as_str()))
+            .collect();
+        if entries.is_empty() {
+            return Err(Error::configuration(format!(
+                "Run '{run_id}' recorded no cache entries"
+            )));
+        }
+
+        Ok(Self {
+            run_id,
+            created_at: to_unix(SystemTime::now()),
+            cuenv_version: env!("CARGO_PKG_VERSION").to_string(),
+            entries,
+        })
+    }
+
+    /// Distinct CAS hashes of every object the manifest references
+    pub fn objects(&self) -> Vec<&str> {
+        let mut objects: Vec<_> = self
+            .entries
+            .iter()
+            .flat_map(ManifestEntry::objects)
+            .collect();
+        objects.sort_unstable();
+        objects.dedup();
+        objects
+    }
+
+    /// Sign the manifest with the cache's key
+    pub fn sign(&self, signer: &CacheSigner) -> Result<SignedManifest> {
+        signer
+            .sign(self)
+            .map_err(|e| Error::security(format!("failed to sign manifest: {e}")))
+    }
+
+    /// Parse a signed manifest and check its signature
+    ///
+    /// Anyone can sign a manifest, so it must have been signed with
+    /// `trusted_key`; without one the manifest is refused, naming the key it
+    /// was signed with.
+    pub fn verify(text: &str, trusted_key: Option<&[u8; 32]>) -> Result<Self> {
+        let signed: SignedManifest = serde_json::from_str(text).map_err(|e| Error::Json {
+            message: "failed to parse cache manifest".to_string(),
+            source: e,
+        })?;
+
+        let key: [u8; 32] = signed
+            .public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::security("manifest carries a malformed public key"))?;
+        match trusted_key {
+            Some(trusted) if *trusted == key => {}
+            Some(_) => {
+                return Err(Error::security(format!(
+                    "manifest was signed by untrusted key {}",
+                    hex::encode(key)
+                )));
+            }
+            None => {
+                return Err(Error::security(format!(
+                    "manifest is signed by key {key}, which is not trusted; pass --trust {key} if it is the key of the cache that wrote it",
+                    key = hex::encode(key)
+                )));
+            }
+        }
+
+        let valid = verify_with_public_key(&signed, &key)
+            .map_err(|e| Error::security(format!("invalid manifest signature: {e}")))?;
+        if !valid {
+            return Err(Error::security(
+                "manifest signature does not match its contents or has expired",
+            ));
+        }
+        Ok(signed.data)
+    }
+}
+
+/// Append-only log of the cache entries recorded by each run
+#[derive(Debug, Clone)]
+pub struct RunLog {
+    path: PathBuf,
+}
+
+impl RunLog {
+    /// Open the log stored under the given cache directory
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join(RUN_LOG_FILE_NAME),
+        }
+    }
+
+    /// Append an entry
+    pub fn record(&self, entry: &ManifestEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::file_system(parent, "create cache directory", e))?;
+        }
+
+        let line = serde_json::to_string(entry).map_err(|e| Error::Json {
+            message: "failed to serialize run log entry".to_string(),
+            source: e,
+        })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| Error::file_system(&self.path, "open run log", e))?;
+        writeln!(file, "{line}")
+            .map_err(|e| Error::file_system(&self.path, "append run log", e))?;
+
+        self.compact_if_needed()
+    }
+
+    /// Every entry, oldest first
+    ///
+    /// Malformed lines (e.g. from a crash mid-write) are skipped.
+    pub fn load(&self) -> Result<Vec<ManifestEntry>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::file_system(&self.path, "open run log", e)),
+        };
+
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(|line| line.ok())
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect())
+    }
+
+    /// Rewrite the log keeping only the newest `MAX_ENTRIES` entries
+    fn compact_if_needed(&self) -> Result<()> {
+        let entries = self.load()?;
+        if entries.len() <= MAX_ENTRIES {
+            return Ok(());
+        }
+
+        let contents = entries[entries.len() - MAX_ENTRIES..]
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect::<String>();
+        cuenv_utils::atomic_file::write_atomic_string(&self.path, &contents)
+    }
+}
+
+fn to_unix(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(key: &str, run_id: &str, output: &str) -> ManifestEntry {
+        ManifestEntry {
+            key: key.to_string(),
+            task: "build".to_string(),
+            run_id: Some(run_id.to_string()),
+            reused_from: None,
+            environment: None,
+            exit_code: 0,
+            executed_at: 1,
+            stdout_hash: Some("aa".to_string()),
+            stderr_hash: None,
+            output_files: BTreeMap::from([("dist/app".to_string(), output.to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_manifest_round_trip_is_verified() {
+        let dir = TempDir::new().unwrap();
+        let log = RunLog::new(dir.path());
+        log.record(&entry("k1", "run-1", "bb")).unwrap();
+        log.record(&entry("k2", "run-2", "cc")).unwrap();
+        log.record(&entry("k3", "run-2", "cc")).unwrap();
+
+        let manifest = RunManifest::for_run(&log, None).unwrap();
+        assert_eq!(manifest.run_id, "run-2");
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.objects(), vec!["aa", "cc"]);
+        assert!(RunManifest::for_run(&log, Some("run-3")).is_err());
+
+        let signer = CacheSigner::new(dir.path()).unwrap();
+        let signed = manifest.sign(&signer).unwrap();
+        let text = serde_json::to_string(&signed).unwrap();
+        assert!(RunManifest::verify(&text, None)
+            .unwrap_err()
+            .to_string()
+            .contains("not trusted"));
+        assert_eq!(
+            RunManifest::verify(&text, Some(signer.public_key())).unwrap(),
+            manifest
+        );
+        assert!(RunManifest::verify(&text, Some(&[7; 32])).is_err());
+
+        let mut tampered = signed;
+        tampered.data.entries[0].output_files.clear();
+        let text = serde_json::to_string(&tampered).unwrap();
+        assert!(RunManifest::verify(&text, Some(signer.public_key())).is_err());
+    }
+}
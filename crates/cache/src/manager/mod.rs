@@ -1,17 +1,22 @@
 //! Unified cache manager with security and remote cache support
 
 mod builder;
+pub mod history;
 mod keygen;
+pub mod manifest;
 mod migration;
 mod operations;
 mod statistics;
 
 pub use builder::CacheManagerBuilder;
+pub use history::{StatsHistory, StatsHistoryEntry, StatsTrend};
 pub use keygen::hash_task_config;
+pub use manifest::{ManifestEntry, RunLog, RunManifest, SignedManifest};
 pub use migration::CACHE_VERSION;
 pub use statistics::CacheStatistics;
 
 use crate::concurrent::action::ActionCache;
+use crate::concurrent::CacheStatSnapshot;
 use crate::config::CacheConfig;
 use crate::content_addressed_store::ContentAddressedStore;
 use crate::engine::CacheEngine;
@@ -19,6 +24,7 @@ use crate::keys::{CacheKeyFilterConfig, CacheKeyGenerator};
 use crate::types::CachedTaskResult;
 use cuenv_config::TaskConfig;
 use cuenv_core::Result;
+use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
@@ -38,6 +44,8 @@ pub struct CacheManager {
     key_gen_manager: keygen::KeyGenManager,
     /// Cache version for migration support
     _version: u32,
+    /// Action cache statistics at the time of the last history snapshot
+    last_recorded: Mutex<Option<CacheStatSnapshot>>,
 }
 
 impl CacheManager {
@@ -69,6 +77,7 @@ impl CacheManager {
             operations,
             key_gen_manager: components.key_gen_manager,
             _version: CACHE_VERSION,
+            last_recorded: Mutex::new(None),
         })
     }
 
@@ -97,6 +106,26 @@ impl CacheManager {
         self.operations.get_statistics()
     }
 
+    /// Persisted statistics history for this cache directory
+    pub fn history(&self) -> StatsHistory {
+        StatsHistory::new(&self.config.base_dir)
+    }
+
+    /// Cache entries recorded by each run
+    pub fn run_log(&self) -> RunLog {
+        RunLog::new(&self.config.base_dir)
+    }
+
+    /// Append the cache activity since the previous call to the statistics history
+    pub fn record_history(&self) -> Result<()> {
+        let current = self.operations.action_cache().stats();
+        let mut last = self.last_recorded.lock();
+        let entry = StatsHistoryEntry::since_previous(&current, last.as_ref());
+        self.history().record(&entry)?;
+        *last = Some(current);
+        Ok(())
+    }
+
     /// Get cached result for a task
     pub fn get_cached_result(&self, cache_key: &str) -> Option<CachedTaskResult> {
         self.operations.get_cached_result(cache_key)
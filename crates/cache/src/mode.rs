@@ -73,3 +73,44 @@ pub fn get_cache_mode() -> CacheMode {
     }
     CacheMode::ReadWrite
 }
+
+/// How task outputs restored from the cache are checked against the hashes
+/// recorded when they were stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyMode {
+    /// Restore without checking
+    Off,
+    /// Check each restored file has the recorded size (default)
+    #[default]
+    Fast,
+    /// Hash each restored file and compare it to the recorded hash
+    Full,
+}
+
+impl From<String> for VerifyMode {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "off" => VerifyMode::Off,
+            "fast" => VerifyMode::Fast,
+            "full" => VerifyMode::Full,
+            _ => {
+                log::warn!(
+                    "Unknown cache verify mode \"{value}\", falling back to fast verification"
+                );
+                VerifyMode::Fast
+            }
+        }
+    }
+}
+
+impl fmt::Display for VerifyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode_str = match self {
+            VerifyMode::Off => "off",
+            VerifyMode::Fast => "fast",
+            VerifyMode::Full => "full",
+        };
+        write!(f, "{mode_str}")
+    }
+}
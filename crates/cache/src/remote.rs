@@ -0,0 +1,238 @@
+//! Client for a remote cache shared between machines
+//!
+//! The remote serves CAS objects over plain HTTP at `<endpoint>/cas/<hash>`,
+//! a layout any static file server or object store can provide. Objects are
+//! content addressed, so every download is checked against its hash before
+//! it enters the local store. Objects are streamed to disk as they arrive,
+//! and a download that was cut off continues where it stopped. Failed
+//! requests are retried under the `remote_cache` retry policy. Requests
+//! carry the endpoint's credential as a bearer token, if it has one.
+
+use crate::content_addressed_store::{content_hash, ContentAddressedStore};
+use crate::manager::manifest::{RunLog, RunManifest};
+use crate::streaming::{ProgressCallback, TransferProgress};
+use cuenv_core::{Error, Result};
+use cuenv_utils::credentials::{Credential, CredentialLookup};
+use cuenv_utils::resilience::policy::{self, Subsystem};
+use reqwest::{header, StatusCode};
+use tokio::io::AsyncWriteExt;
+
+/// Environment variable naming the remote cache endpoint
+pub const REMOTE_CACHE_ENV_VAR: &str = "CUENV_REMOTE_CACHE";
+
+/// Environment variable holding the remote cache's token, ahead of the
+/// other credential sources
+pub const REMOTE_CACHE_TOKEN_ENV_VAR: &str = "CUENV_REMOTE_CACHE_TOKEN";
+
+/// Objects transferred by [`RemoteCache::fetch_manifest`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchSummary {
+    /// Objects downloaded from the remote
+    pub fetched: usize,
+    /// Objects the local store already had
+    pub present: usize,
+    /// Bytes downloaded
+    pub bytes: u64,
+}
+
+/// HTTP client for a remote cache
+#[derive(Debug, Clone)]
+pub struct RemoteCache {
+    endpoint: String,
+    client: reqwest::Client,
+    credential: Option<Credential>,
+    progress: Option<ProgressCallback>,
+}
+
+impl RemoteCache {
+    /// Client for the cache at `endpoint`, with its credential if one is found
+    pub fn new(endpoint: &str) -> Self {
+        let credential = CredentialLookup::for_url(endpoint)
+            .with_env_var(REMOTE_CACHE_TOKEN_ENV_VAR)
+            .resolve();
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            credential,
+            progress: None,
+        }
+    }
+
+    /// Report the progress of each object download to `progress`
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Client for the endpoint in `CUENV_REMOTE_CACHE`, if set
+    pub fn from_env() -> Option<Self> {
+        std::env::var(REMOTE_CACHE_ENV_VAR)
+            .ok()
+            .filter(|endpoint| !endpoint.trim().is_empty())
+            .map(|endpoint| Self::new(endpoint.trim()))
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Download the object stored under `hash`, checking its content
+    pub async fn fetch_object(&self, hash: &str) -> Result<Vec<u8>> {
+        policy::call(Subsystem::RemoteCache, || self.try_fetch_object(hash)).await
+    }
+
+    async fn try_fetch_object(&self, hash: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/cas/{hash}", self.endpoint);
+        let response = self
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| Error::network(&url, e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(status_error(&url, response.status()));
+        }
+        let content = response
+            .bytes()
+            .await
+            .map_err(|e| Error::network(&url, e.to_string()))?;
+
+        let actual = content_hash(&content);
+        if actual != hash {
+            return Err(Error::security(format!(
+                "object {hash} from {url} has hash {actual}"
+            )));
+        }
+        Ok(content.to_vec())
+    }
+
+    /// Download the object stored under `hash` into `cas`, a chunk at a
+    /// time, and return the bytes downloaded
+    ///
+    /// The object collects in [`ContentAddressedStore::download_path`]; when
+    /// an earlier download left part of it there, only the rest is requested,
+    /// so a retry after a dropped connection picks up where it stopped.
+    pub async fn fetch_object_into(&self, hash: &str, cas: &ContentAddressedStore) -> Result<u64> {
+        policy::call(Subsystem::RemoteCache, || {
+            self.try_fetch_object_into(hash, cas)
+        })
+        .await
+    }
+
+    async fn try_fetch_object_into(&self, hash: &str, cas: &ContentAddressedStore) -> Result<u64> {
+        let url = format!("{}/cas/{hash}", self.endpoint);
+        let path = cas.download_path(hash);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Error::file_system(parent, "create download directory", e))?;
+        }
+        let have = tokio::fs::metadata(&path).await.map_or(0, |m| m.len());
+
+        let mut request = self.get(&url);
+        if have > 0 {
+            request = request.header(header::RANGE, format!("bytes={have}-"));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| Error::network(&url, e.to_string()))?;
+        // A remote that ignores the range sends the whole object again
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        if !response.status().is_success() {
+            if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+                // What was kept does not fit the object; start over next time
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            return Err(status_error(&url, response.status()));
+        }
+        let mut transferred = if resumed { have } else { 0 };
+        let total = response.content_length().map(|len| transferred + len);
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&path)
+            .await
+            .map_err(|e| Error::file_system(&path, "open download", e))?;
+        let mut downloaded = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::network(&url, e.to_string()))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| Error::file_system(&path, "write download", e))?;
+            downloaded += chunk.len() as u64;
+            transferred += chunk.len() as u64;
+            if let Some(progress) = &self.progress {
+                progress.report(TransferProgress { transferred, total });
+            }
+        }
+        file.flush()
+            .await
+            .map_err(|e| Error::file_system(&path, "write download", e))?;
+        drop(file);
+
+        let stored = cas.store_file(&path);
+        let _ = tokio::fs::remove_file(&path).await;
+        let actual = stored?;
+        if actual != hash {
+            cas.release(&actual)?;
+            return Err(Error::security(format!(
+                "object {hash} from {url} has hash {actual}"
+            )));
+        }
+        Ok(downloaded)
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match &self.credential {
+            Some(credential) => request.bearer_auth(&credential.token),
+            None => request,
+        }
+    }
+
+    /// Pull every object `manifest` lists into `cas` and record its entries
+    /// in `log`, so the run is known locally
+    pub async fn fetch_manifest(
+        &self,
+        manifest: &RunManifest,
+        cas: &ContentAddressedStore,
+        log: &RunLog,
+    ) -> Result<FetchSummary> {
+        let mut summary = FetchSummary::default();
+        for hash in manifest.objects() {
+            if cas.contains(hash) {
+                summary.present += 1;
+                continue;
+            }
+            summary.bytes += self.fetch_object_into(hash, cas).await?;
+            summary.fetched += 1;
+        }
+
+        let known = log.load()?;
+        manifest
+            .entries
+            .iter()
+            .filter(|entry| !known.contains(entry))
+            .try_for_each(|entry| log.record(entry))?;
+        Ok(summary)
+    }
+}
+
+/// Error for an unsuccessful answer; only answers that a retry may change
+/// are network errors, which the retry policy retries
+fn status_error(url: &str, status: StatusCode) -> Error {
+    if status.is_client_error()
+        && status != StatusCode::TOO_MANY_REQUESTS
+        && status != StatusCode::REQUEST_TIMEOUT
+    {
+        Error::configuration(format!("Remote cache {url} answered {status}"))
+    } else {
+        Error::network(url, format!("remote cache answered {status}"))
+    }
+}
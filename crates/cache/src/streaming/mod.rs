@@ -9,6 +9,7 @@
 //! - Chunked transfer encoding for network operations
 //! - Memory-mapped streaming for hot data paths
 //! - Vectored I/O for scatter-gather operations
+//! - Resumable puts and gets with progress callbacks
 
 use futures::io::{AsyncRead, AsyncWrite};
 use std::future::Future;
@@ -18,6 +19,7 @@ use std::time::Duration;
 use crate::errors::Result;
 
 // Re-export all public types and traits
+pub use progress::{GetOptions, ProgressCallback, PutOptions, TransferProgress};
 pub use reader::CacheReader;
 pub use writer::CacheWriter;
 
@@ -25,6 +27,7 @@ pub use writer::CacheWriter;
 mod finalization;
 pub mod operations;
 mod path_utils;
+mod progress;
 mod reader;
 mod writer;
 
@@ -29,6 +29,12 @@ pub fn get_paths(cache_dir: &Path, hash: &str) -> (PathBuf, PathBuf) {
     (data_path, metadata_path)
 }
 
+/// Where a resumable writer keeps the value stored at `data_path` until
+/// it is finalized
+pub fn partial_path(data_path: &Path) -> PathBuf {
+    data_path.with_extension("partial")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
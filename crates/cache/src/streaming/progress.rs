@@ -0,0 +1,57 @@
+//! Progress reporting and options for streamed transfers
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far a streamed transfer has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferProgress {
+    /// Bytes transferred so far, counting those of a resumed earlier attempt
+    pub transferred: u64,
+    /// Size of the whole transfer, when known
+    pub total: Option<u64>,
+}
+
+/// Called each time a streamed transfer moves a chunk
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(TransferProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(TransferProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn report(&self, progress: TransferProgress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Options for [`Cache::put_stream_with`](crate::Cache::put_stream_with)
+#[derive(Debug, Clone, Default)]
+pub struct PutOptions {
+    pub ttl: Option<Duration>,
+    /// Size of the value, if known, for progress reports
+    pub total: Option<u64>,
+    /// Continue an earlier put of the key that was cut off, keeping what it
+    /// wrote; the reader then starts at
+    /// [`Cache::partial_len`](crate::Cache::partial_len). A resumable put
+    /// that fails keeps its partial value for the next attempt.
+    pub resume: bool,
+    pub progress: Option<ProgressCallback>,
+}
+
+/// Options for [`Cache::get_stream_with`](crate::Cache::get_stream_with)
+#[derive(Debug, Clone, Default)]
+pub struct GetOptions {
+    /// Bytes of the value to skip, e.g. those an interrupted download
+    /// already has
+    pub offset: u64,
+    pub progress: Option<ProgressCallback>,
+}
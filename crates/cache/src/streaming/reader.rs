@@ -16,7 +16,7 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use tokio::fs::File;
-use tokio::io::{AsyncRead as TokioAsyncRead, ReadBuf};
+use tokio::io::{AsyncRead as TokioAsyncRead, AsyncSeekExt, ReadBuf};
 
 pin_project! {
     /// Reader for streaming cached values
@@ -130,6 +130,40 @@ impl CacheReader {
         self.bytes_read
     }
 
+    /// Skip to `offset` bytes into the value, or to its end if it is
+    /// shorter
+    ///
+    /// Skipped bytes are not hashed, so [`CacheReader::verify_integrity`]
+    /// only holds for readers read from the start.
+    pub async fn seek_to(&mut self, offset: u64) -> Result<()> {
+        match &mut self.inner {
+            CacheReaderInner::File(reader) => {
+                reader
+                    .seek(io::SeekFrom::Start(offset))
+                    .await
+                    .map_err(|e| CacheError::Io {
+                        path: PathBuf::new(),
+                        operation: "seek in cache file",
+                        source: e,
+                        recovery_hint: RecoveryHint::Retry {
+                            after: Duration::from_millis(10),
+                        },
+                    })?;
+            }
+            CacheReaderInner::Memory(cursor) => {
+                let len = cursor.get_ref().len() as u64;
+                cursor.set_position(offset.min(len));
+            }
+            #[cfg(target_os = "linux")]
+            CacheReaderInner::Mmap(reader) => {
+                reader.position = usize::try_from(offset)
+                    .unwrap_or(usize::MAX)
+                    .min(reader.mmap.len());
+            }
+        }
+        Ok(())
+    }
+
     /// Verify the integrity of the data read
     pub fn verify_integrity(&self) -> bool {
         // Clone the hasher state to avoid consuming the original
@@ -14,10 +14,16 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::{Duration, SystemTime};
 use tokio::fs::File;
-use tokio::io::{AsyncWrite as TokioAsyncWrite, AsyncWriteExt as TokioAsyncWriteExt};
+use tokio::io::{
+    AsyncReadExt as TokioAsyncReadExt, AsyncWrite as TokioAsyncWrite,
+    AsyncWriteExt as TokioAsyncWriteExt,
+};
 
 use super::{finalization, path_utils};
 
+/// Bytes of a partial value hashed at a time when resuming
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
 pin_project! {
     /// Writer for streaming values into the cache
     pub struct CacheWriter {
@@ -36,31 +42,17 @@ pin_project! {
 impl CacheWriter {
     /// Create a new cache writer
     pub async fn new(cache_dir: &Path, key: &str, ttl: Option<Duration>) -> Result<Self> {
-        match key.validate() {
-            Ok(()) => {}
-            Err(e) => return Err(e),
-        }
-
-        let hash = path_utils::hash_key(key);
-        let (final_path, metadata_path) = path_utils::get_paths(cache_dir, &hash);
-
-        // Ensure parent directories exist
-        if let Some(parent) = final_path.parent() {
-            match tokio::fs::create_dir_all(parent).await {
-                Ok(()) => {}
-                Err(e) => {
-                    return Err(CacheError::Io {
-                        path: parent.to_path_buf(),
-                        operation: "create cache directory",
-                        source: e,
-                        recovery_hint: RecoveryHint::CheckPermissions {
-                            path: parent.to_path_buf(),
-                        },
-                    });
-                }
-            }
-        }
+        let (final_path, metadata_path) = key_paths(cache_dir, key)?;
+        Self::at(final_path, metadata_path, ttl).await
+    }
 
+    /// Create a writer that finalizes into `final_path` and `metadata_path`
+    pub(crate) async fn at(
+        final_path: PathBuf,
+        metadata_path: PathBuf,
+        ttl: Option<Duration>,
+    ) -> Result<Self> {
+        create_parent(&final_path).await?;
         let temp_path = final_path.with_extension(format!("tmp.{}", uuid::Uuid::new_v4()));
 
         let file = match File::create(&temp_path).await {
@@ -87,6 +79,98 @@ impl CacheWriter {
         })
     }
 
+    /// Create a writer that continues the value an earlier resumable writer
+    /// for `key` left behind, or starts one that can be resumed
+    ///
+    /// The bytes already written are hashed again a chunk at a time, so
+    /// resuming never holds the value in memory.
+    pub async fn resume(cache_dir: &Path, key: &str, ttl: Option<Duration>) -> Result<Self> {
+        let (final_path, metadata_path) = key_paths(cache_dir, key)?;
+        Self::resume_at(final_path, metadata_path, ttl).await
+    }
+
+    /// [`CacheWriter::resume`] for a value finalized into `final_path` and
+    /// `metadata_path`
+    pub(crate) async fn resume_at(
+        final_path: PathBuf,
+        metadata_path: PathBuf,
+        ttl: Option<Duration>,
+    ) -> Result<Self> {
+        create_parent(&final_path).await?;
+        let temp_path = path_utils::partial_path(&final_path);
+        let io_error = |operation, e| CacheError::Io {
+            path: temp_path.clone(),
+            operation,
+            source: e,
+            recovery_hint: RecoveryHint::CheckPermissions {
+                path: temp_path.clone(),
+            },
+        };
+
+        let mut hasher = Sha256::new();
+        let mut bytes_written = 0u64;
+        match File::open(&temp_path).await {
+            Ok(mut partial) => {
+                let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+                loop {
+                    let n = partial
+                        .read(&mut buffer)
+                        .await
+                        .map_err(|e| io_error("read partial cache file", e))?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                    bytes_written += n as u64;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(io_error("open partial cache file", e)),
+        }
+
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&temp_path)
+            .await
+            .map_err(|e| io_error("open partial cache file", e))?;
+
+        Ok(Self {
+            file: tokio::io::BufWriter::new(file),
+            temp_path,
+            final_path,
+            metadata_path,
+            hasher,
+            bytes_written,
+            ttl,
+            created_at: SystemTime::now(),
+        })
+    }
+
+    /// Bytes an interrupted resumable writer for `key` left behind
+    pub async fn partial_len(cache_dir: &Path, key: &str) -> u64 {
+        let (final_path, _) = path_utils::get_paths(cache_dir, &path_utils::hash_key(key));
+        Self::partial_len_at(&final_path).await
+    }
+
+    /// Bytes an interrupted resumable writer for the value finalized into
+    /// `final_path` left behind
+    pub(crate) async fn partial_len_at(final_path: &Path) -> u64 {
+        tokio::fs::metadata(path_utils::partial_path(final_path))
+            .await
+            .map_or(0, |metadata| metadata.len())
+    }
+
+    /// Bytes of the value written so far, including resumed ones
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Drop what was written, for a transfer that will not be resumed
+    pub async fn discard(self) {
+        let _ = tokio::fs::remove_file(&self.temp_path).await;
+    }
+
     /// Finalize the write operation
     pub async fn finalize(mut self) -> Result<CacheMetadata> {
         // Flush any buffered data
@@ -155,6 +239,32 @@ impl CacheWriter {
     }
 }
 
+/// Validate `key` and compute where its value is stored
+fn key_paths(cache_dir: &Path, key: &str) -> Result<(PathBuf, PathBuf)> {
+    key.validate()?;
+    Ok(path_utils::get_paths(cache_dir, &path_utils::hash_key(key)))
+}
+
+/// Create the directory a value is stored in
+async fn create_parent(final_path: &Path) -> Result<()> {
+    if let Some(parent) = final_path.parent() {
+        match tokio::fs::create_dir_all(parent).await {
+            Ok(()) => {}
+            Err(e) => {
+                return Err(CacheError::Io {
+                    path: parent.to_path_buf(),
+                    operation: "create cache directory",
+                    source: e,
+                    recovery_hint: RecoveryHint::CheckPermissions {
+                        path: parent.to_path_buf(),
+                    },
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 impl AsyncWrite for CacheWriter {
     fn poll_write(
         self: Pin<&mut Self>,
@@ -239,7 +239,7 @@ pub struct CacheConfig {
     /// Minimum size for compression in bytes (Phase 2)
     #[serde(default)]
     pub compression_min_size: Option<usize>,
-    /// Eviction policy (Phase 4) - "lru", "lfu", or "arc"
+    /// Eviction policy (Phase 4) - "lru", "gdsf", "lfu", or "arc"
     #[serde(default)]
     pub eviction_policy: Option<String>,
     /// Maximum memory size in bytes (Phase 4)
@@ -266,7 +266,7 @@ impl Default for CacheConfig {
             compression_enabled: true,
             compression_level: Some(3),       // Fast compression
             compression_min_size: Some(1024), // 1KB minimum
-            eviction_policy: Some("lru".to_string()),
+            eviction_policy: Some(crate::eviction::DEFAULT_EVICTION_POLICY.to_string()),
             max_memory_size: Some(1024 * 1024 * 1024), // 1GB
             max_disk_size: Some(10 * 1024 * 1024 * 1024), // 10GB
         }
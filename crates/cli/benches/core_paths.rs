@@ -0,0 +1,47 @@
+//! Time cuenv's core paths on a generated repository
+//!
+//! ```sh
+//! CUENV_BENCH_PACKAGES=20 CUENV_BENCH_TASKS=100 \
+//!     cargo bench -p cuenv --bench core_paths -- --save-baseline main
+//! ```
+//!
+//! The repository is the one `cuenv bench` generates: 10 packages of 50
+//! tasks and 200 variables, 5 input files per task, unless overridden by
+//! `CUENV_BENCH_PACKAGES`, `CUENV_BENCH_TASKS`, `CUENV_BENCH_VARIABLES` and
+//! `CUENV_BENCH_FILES`. Compare a change against a saved criterion baseline
+//! with `-- --baseline main`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuenv::commands::bench::paths::{BenchPath, Fixture};
+use cuenv::commands::bench::synthetic::RepoSize;
+
+fn size_from_env(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn core_paths(c: &mut Criterion) {
+    let size = RepoSize {
+        packages: size_from_env("CUENV_BENCH_PACKAGES", 10),
+        tasks: size_from_env("CUENV_BENCH_TASKS", 50),
+        variables: size_from_env("CUENV_BENCH_VARIABLES", 200),
+        files: size_from_env("CUENV_BENCH_FILES", 5),
+    };
+    let fixture = Fixture::generate(size).unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("core_paths");
+    group.sample_size(10);
+    for path in BenchPath::ALL {
+        group.bench_function(path.name(), |b| {
+            b.to_async(&runtime)
+                .iter(|| async { fixture.run(path).await.unwrap() });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, core_paths);
+criterion_main!(benches);
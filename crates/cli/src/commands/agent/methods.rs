@@ -0,0 +1,479 @@
+//! Methods of the agent and the session state they share
+
+use cuenv_config::{Config, ConfigLoader, TaskConfig};
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_env::manager::environment::SupervisorMode;
+use cuenv_env::EnvManager;
+use cuenv_task::{cancellation, similar_task_names, TaskExecutor};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+pub(super) const PARSE_ERROR: i64 = -32700;
+pub(super) const METHOD_NOT_FOUND: i64 = -32601;
+pub(super) const INVALID_PARAMS: i64 = -32602;
+/// A valid request cuenv could not carry out, e.g. because env.cue does not
+/// evaluate or no environment is loaded
+pub(super) const REQUEST_FAILED: i64 = -32000;
+
+/// Version of the agent's methods, raised when one changes incompatibly
+const PROTOCOL_VERSION: u32 = 1;
+
+pub(super) const RUN_TASK_METHOD: &str = "runTask";
+pub(super) const SHUTDOWN_METHOD: &str = "shutdown";
+
+/// Every method, as reported by `initialize`
+const METHODS: &[&str] = &[
+    "initialize",
+    "loadEnv",
+    "listTasks",
+    RUN_TASK_METHOD,
+    "cancelRun",
+    "cancelTask",
+    "status",
+    "explainVar",
+    SHUTDOWN_METHOD,
+];
+
+/// Error returned for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn failed(error: cuenv_core::Error) -> Self {
+        Self::new(REQUEST_FAILED, error.to_string())
+    }
+}
+
+type MethodResult = std::result::Result<Value, RpcError>;
+
+/// Parameters of `loadEnv`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoadEnvParams {
+    /// Directory holding env.cue; the agent's directory when absent
+    directory: Option<PathBuf>,
+    environment: Option<String>,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+/// Parameters of `runTask`
+#[derive(Debug, Deserialize)]
+struct RunTaskParams {
+    task: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// Parameters of `cancelRun`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelRunParams {
+    /// `runId` of the task's notifications
+    run_id: String,
+    /// Kill the task without giving it time to exit
+    #[serde(default)]
+    force: bool,
+}
+
+/// Parameters of `cancelTask`
+#[derive(Debug, Deserialize)]
+struct CancelTaskParams {
+    task: String,
+    #[serde(default)]
+    force: bool,
+}
+
+/// Parameters of `explainVar`
+#[derive(Debug, Deserialize)]
+struct ExplainVarParams {
+    name: String,
+}
+
+/// Environment loaded with `loadEnv`
+struct LoadedEnv {
+    directory: PathBuf,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    config: Config,
+}
+
+/// State of one client's session
+#[derive(Default)]
+pub(super) struct Session {
+    loaded: Option<LoadedEnv>,
+    /// Tasks started by `runTask` that have not finished
+    running: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl Session {
+    /// Answer a request for any method but `runTask`
+    pub async fn handle(&mut self, method: &str, params: Value) -> MethodResult {
+        match method {
+            "initialize" => Ok(json!({
+                "name": "cuenv",
+                "version": env!("CARGO_PKG_VERSION"),
+                "protocolVersion": PROTOCOL_VERSION,
+                "methods": METHODS,
+            })),
+            "loadEnv" => self.load_env(parse_params(params)?).await,
+            "listTasks" => self.list_tasks(),
+            "cancelRun" => cancel_run(parse_params(params)?),
+            "cancelTask" => cancel_task(parse_params(params)?),
+            "status" => Ok(self.status()),
+            "explainVar" => self.explain_var(parse_params(params)?),
+            SHUTDOWN_METHOD => Ok(Value::Null),
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                with_suggestions(
+                    format!("Unknown method '{other}'"),
+                    &similar_names(other, METHODS),
+                ),
+            )),
+        }
+    }
+
+    /// Check a `runTask` request against the loaded environment
+    pub fn prepare_run(&self, params: Value) -> std::result::Result<TaskRun, RpcError> {
+        let params: RunTaskParams = parse_params(params)?;
+        let loaded = self.loaded()?;
+        let tasks = loaded.config.get_tasks();
+        if !tasks.contains_key(&params.task)
+            && !loaded.config.get_task_nodes().contains_key(&params.task)
+        {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                with_suggestions(
+                    format!("Task '{}' not found", params.task),
+                    &similar_task_names(tasks, &params.task),
+                ),
+            ));
+        }
+        Ok(TaskRun {
+            task: params.task,
+            args: params.args,
+            directory: loaded.directory.clone(),
+            environment: loaded.environment.clone(),
+            capabilities: loaded.capabilities.clone(),
+            running: Arc::clone(&self.running),
+        })
+    }
+
+    fn loaded(&self) -> std::result::Result<&LoadedEnv, RpcError> {
+        self.loaded.as_ref().ok_or_else(|| {
+            RpcError::new(REQUEST_FAILED, "No environment loaded; call loadEnv first")
+        })
+    }
+
+    async fn load_env(&mut self, params: LoadEnvParams) -> MethodResult {
+        let directory = match params.directory {
+            Some(directory) => directory,
+            None => std::env::current_dir().map_err(|e| {
+                RpcError::failed(cuenv_core::Error::file_system(
+                    ".",
+                    "get current directory",
+                    e,
+                ))
+            })?,
+        };
+        let mut loader = ConfigLoader::new()
+            .directory(directory.clone())
+            .capabilities(params.capabilities.clone());
+        if let Some(environment) = &params.environment {
+            loader = loader.environment(environment.clone());
+        }
+        let config = loader.load().await.map_err(RpcError::failed)?;
+
+        let loaded = self.loaded.insert(LoadedEnv {
+            directory,
+            environment: params.environment,
+            capabilities: params.capabilities,
+            config,
+        });
+        let variables = loaded.config.get_env_vars().map_err(RpcError::failed)?;
+        Ok(json!({
+            "directory": loaded.directory,
+            "envFile": loaded.config.env_file,
+            "environment": loaded.environment,
+            "environments": loaded.config.get_environments(),
+            "capabilities": loaded.capabilities,
+            "variables": variables.into_iter().collect::<BTreeMap<_, _>>(),
+        }))
+    }
+
+    fn list_tasks(&self) -> MethodResult {
+        let loaded = self.loaded()?;
+        let mut tasks: Vec<(&String, &TaskConfig)> = loaded.config.get_tasks().iter().collect();
+        tasks.sort_by(|a, b| a.0.cmp(b.0));
+        Ok(Value::Array(
+            tasks
+                .into_iter()
+                .map(|(name, task)| task_summary(name, task))
+                .collect(),
+        ))
+    }
+
+    fn status(&self) -> Value {
+        let running: Vec<String> = self
+            .running
+            .lock()
+            .map(|running| running.iter().cloned().collect())
+            .unwrap_or_default();
+        let runs: Vec<Value> = cancellation::active_runs()
+            .into_iter()
+            .map(|(run_id, task)| json!({"runId": run_id, "task": task}))
+            .collect();
+        match &self.loaded {
+            Some(loaded) => json!({
+                "loaded": true,
+                "directory": loaded.directory,
+                "environment": loaded.environment,
+                "capabilities": loaded.capabilities,
+                "running": running,
+                "runs": runs,
+            }),
+            None => json!({"loaded": false, "running": running, "runs": runs}),
+        }
+    }
+
+    fn explain_var(&self, params: ExplainVarParams) -> MethodResult {
+        let loaded = self.loaded()?;
+        let variables = loaded.config.get_env_vars().map_err(RpcError::failed)?;
+        let Some(value) = variables.get(&params.name) else {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                with_suggestions(
+                    format!("Variable '{}' not found", params.name),
+                    &similar_names(&params.name, variables.keys()),
+                ),
+            ));
+        };
+        let origin = loaded
+            .config
+            .get_provenance()
+            .variables
+            .get(&params.name)
+            .cloned()
+            .unwrap_or_default();
+        Ok(json!({
+            "name": params.name,
+            "value": value,
+            "source": origin.describe(),
+            "origin": origin,
+            "metadata": loaded.config.get_metadata(&params.name),
+        }))
+    }
+}
+
+/// Stop one task run, gracefully unless `force` is set
+fn cancel_run(params: CancelRunParams) -> MethodResult {
+    if !cancellation::cancel_run(&params.run_id, params.force) {
+        return Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("No active run '{}'", params.run_id),
+        ));
+    }
+    Ok(json!({"cancelled": [params.run_id]}))
+}
+
+/// Stop every run of a task, gracefully unless `force` is set
+fn cancel_task(params: CancelTaskParams) -> MethodResult {
+    let cancelled = cancellation::cancel_task(&params.task, params.force);
+    Ok(json!({"cancelled": cancelled}))
+}
+
+/// What editors show of a task
+fn task_summary(name: &str, task: &TaskConfig) -> Value {
+    json!({
+        "name": name,
+        "description": task.description,
+        "dependencies": task.dependencies.clone().unwrap_or_default(),
+        "tags": task.tags.clone().unwrap_or_default(),
+        "labels": task.labels.clone().unwrap_or_default(),
+        "owner": task.owner,
+        "deprecated": task.deprecated,
+    })
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {e}")))
+}
+
+/// A `runTask` request that can run without holding the session
+pub(super) struct TaskRun {
+    task: String,
+    args: Vec<String>,
+    directory: PathBuf,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    running: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl TaskRun {
+    /// Run the task and its dependencies, with their output captured so it
+    /// is published as notifications rather than written to the agent's
+    /// stdout
+    pub async fn execute(self) -> MethodResult {
+        let started = self
+            .running
+            .lock()
+            .map(|mut running| running.insert(self.task.clone()))
+            .unwrap_or(true);
+        if !started {
+            return Err(RpcError::new(
+                REQUEST_FAILED,
+                format!("Task '{}' is already running", self.task),
+            ));
+        }
+
+        let result = self.run().await;
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&self.task);
+        }
+        let exit_code = result.map_err(RpcError::failed)?;
+        Ok(json!({"task": self.task, "exitCode": exit_code}))
+    }
+
+    async fn run(&self) -> cuenv_core::Result<i32> {
+        let mut env_manager = EnvManager::new();
+        env_manager
+            .load_env_with_options(
+                &self.directory,
+                self.environment.clone(),
+                self.capabilities.clone(),
+                None,
+                SupervisorMode::Foreground,
+            )
+            .await?;
+        let executor = TaskExecutor::new(env_manager, self.directory.clone()).await?;
+        executor
+            .execute_tasks_with_capture(std::slice::from_ref(&self.task), &self.args, false)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded_session() -> Session {
+        let mut parse_result = cuenv_config::ParseResult::default();
+        parse_result.variables.insert(
+            "DATABASE_URL".to_string(),
+            "postgres://localhost".to_string(),
+        );
+        parse_result.tasks.insert(
+            "build".to_string(),
+            TaskConfig {
+                description: Some("Build it".to_string()),
+                labels: Some(vec!["ci".to_string()]),
+                ..TaskConfig::default()
+            },
+        );
+        Session {
+            loaded: Some(LoadedEnv {
+                directory: PathBuf::from("/project"),
+                environment: None,
+                capabilities: Vec::new(),
+                config: Config::new(
+                    PathBuf::from("/project"),
+                    None,
+                    parse_result,
+                    cuenv_config::RuntimeOptions::default(),
+                ),
+            }),
+            running: Arc::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_methods_need_a_loaded_environment() {
+        let mut session = Session::default();
+        let error = session.handle("listTasks", Value::Null).await.unwrap_err();
+        assert_eq!(error.code, REQUEST_FAILED);
+        assert!(session.prepare_run(json!({"task": "build"})).is_err());
+        assert_eq!(
+            session.handle("status", Value::Null).await.unwrap()["loaded"],
+            false
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_and_explain_var() {
+        let mut session = loaded_session();
+        let tasks = session.handle("listTasks", Value::Null).await.unwrap();
+        assert_eq!(tasks[0]["name"], "build");
+        assert_eq!(tasks[0]["description"], "Build it");
+        assert_eq!(tasks[0]["labels"], json!(["ci"]));
+
+        let explained = session
+            .handle("explainVar", json!({"name": "DATABASE_URL"}))
+            .await
+            .unwrap();
+        assert_eq!(explained["value"], "postgres://localhost");
+        assert_eq!(explained["source"], "env.cue");
+
+        let error = session
+            .handle("explainVar", json!({"name": "DATABASE_ULR"}))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, INVALID_PARAMS);
+        assert!(error.message.contains("Did you mean 'DATABASE_URL'?"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests() {
+        let mut session = loaded_session();
+        let error = session.handle("explainVar", json!({})).await.unwrap_err();
+        assert_eq!(error.code, INVALID_PARAMS);
+
+        let error = session.prepare_run(json!({"task": "biuld"})).err().unwrap();
+        assert_eq!(error.code, INVALID_PARAMS);
+        assert!(error.message.contains("Did you mean 'build'?"));
+
+        let error = session.handle("loadEnvs", Value::Null).await.unwrap_err();
+        assert_eq!(error.code, METHOD_NOT_FOUND);
+        assert!(error.message.contains("'loadEnv'"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_runs() {
+        let mut session = Session::default();
+        let token = cancellation::register("01AGENTTESTCANCEL", "agent-cancel");
+        let status = session.handle("status", Value::Null).await.unwrap();
+        assert!(status["runs"]
+            .as_array()
+            .unwrap()
+            .contains(&json!({"runId": "01AGENTTESTCANCEL", "task": "agent-cancel"})));
+
+        let cancelled = session
+            .handle("cancelTask", json!({"task": "agent-cancel"}))
+            .await
+            .unwrap();
+        assert_eq!(cancelled["cancelled"], json!(["01AGENTTESTCANCEL"]));
+        assert!(token.is_cancelled());
+
+        drop(token);
+        let error = session
+            .handle("cancelRun", json!({"runId": "01AGENTTESTCANCEL"}))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, INVALID_PARAMS);
+    }
+}
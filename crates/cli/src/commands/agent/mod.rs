@@ -0,0 +1,268 @@
+//! `cuenv agent`: a long-running JSON-RPC service for editors
+//!
+//! Editors keep one agent running instead of scraping the output of cuenv
+//! commands. Requests and responses are newline-delimited JSON-RPC 2.0 over
+//! stdio, or over a Unix socket with `--socket`, where every connection has
+//! its own session. A session loads an environment with `loadEnv`, then
+//! lists its tasks, explains its variables and runs its tasks. `runTask`
+//! answers once the task finishes; meanwhile its output and state changes
+//! arrive as the `task/log`, `task/stateChanged` and `task/diagnostic`
+//! notifications of the task server protocol, and other requests are still
+//! answered. `cancelRun` and `cancelTask` stop running tasks, see
+//! `cuenv_task::cancellation`.
+
+mod methods;
+
+use cuenv_core::{Error, Result, SystemEvent};
+use cuenv_task::TaskNotification;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+use self::methods::{RpcError, Session, PARSE_ERROR, RUN_TASK_METHOD, SHUTDOWN_METHOD};
+
+/// Subscription that the agent's task notifications carry; the agent
+/// streams every task it runs, so there is only the one
+const AGENT_SUBSCRIPTION: u64 = 0;
+
+/// Serve the agent on stdio, or on a Unix socket when `socket` is set
+pub async fn execute(socket: Option<PathBuf>) -> Result<()> {
+    match socket {
+        None => serve_connection(tokio::io::stdin(), tokio::io::stdout()).await,
+        Some(socket_path) => serve_socket(socket_path).await,
+    }
+}
+
+async fn serve_socket(socket_path: PathBuf) -> Result<()> {
+    if socket_path.exists() {
+        std::fs::remove_file(&socket_path)
+            .map_err(|e| Error::file_system(socket_path.clone(), "remove existing socket", e))?;
+    }
+    let listener = UnixListener::bind(&socket_path).map_err(|e| {
+        Error::configuration(format!(
+            "Failed to bind to socket {}: {e}",
+            socket_path.display()
+        ))
+    })?;
+    eprintln!("cuenv agent listening on {}", socket_path.display());
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        let (reader, writer) = stream.into_split();
+                        if let Err(e) = serve_connection(reader, writer).await {
+                            tracing::error!(error = %e, "Agent connection error");
+                        }
+                    });
+                }
+                Err(e) => tracing::error!(error = %e, "Failed to accept agent connection"),
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    let _ = std::fs::remove_file(&socket_path);
+    Ok(())
+}
+
+/// Answer requests from one client until it disconnects or shuts the
+/// agent down
+///
+/// Responses and notifications share the connection, so all output goes
+/// through a single writer task.
+async fn serve_connection<R, W>(reader: R, writer: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (outgoing, receiver) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(write_lines(writer, receiver));
+    let forwarder = tokio::spawn(forward_task_events(
+        cuenv_core::events::global_event_bus().subscribe(),
+        outgoing.clone(),
+    ));
+    let session = Arc::new(Mutex::new(Session::default()));
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| Error::configuration(format!("Failed to read agent request: {e}")))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Invalid JSON: {e}"));
+                let _ = outgoing.send(response(Value::Null, Err(error)));
+                continue;
+            }
+        };
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        // Requests without an id are notifications and get no response
+        let id = request.get("id").cloned();
+
+        if method == RUN_TASK_METHOD {
+            // Runs are answered when they finish, without holding up the
+            // requests that follow
+            let run = session.lock().await.prepare_run(params);
+            let outgoing = outgoing.clone();
+            tokio::spawn(async move {
+                let result = match run {
+                    Ok(run) => run.execute().await,
+                    Err(e) => Err(e),
+                };
+                if let Some(id) = id {
+                    let _ = outgoing.send(response(id, result));
+                }
+            });
+            continue;
+        }
+
+        let result = session.lock().await.handle(&method, params).await;
+        if let Some(id) = id {
+            if outgoing.send(response(id, result)).is_err() {
+                break;
+            }
+        }
+        if method == SHUTDOWN_METHOD {
+            break;
+        }
+    }
+
+    // Stop forwarding notifications and let the writer drain
+    forwarder.abort();
+    drop(outgoing);
+    writer
+        .await
+        .map_err(|e| Error::configuration(format!("Agent connection writer failed: {e}")))?
+}
+
+/// JSON-RPC response to the request `id`
+fn response(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "error": {"code": error.code, "message": error.message},
+            "id": id,
+        }),
+    }
+}
+
+/// Send the task events published on the event bus as notifications
+async fn forward_task_events(
+    mut events: broadcast::Receiver<cuenv_core::events::EnhancedEvent>,
+    outgoing: mpsc::UnboundedSender<Value>,
+) {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                let SystemEvent::Task(event) = event.event else {
+                    continue;
+                };
+                if let Some(notification) = TaskNotification::from_event(event) {
+                    if outgoing
+                        .send(notification.to_json_rpc(AGENT_SUBSCRIPTION))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("Agent missed {missed} task events");
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Write newline-delimited messages to the client until all senders close
+async fn write_lines<W: AsyncWrite + Unpin>(
+    mut writer: W,
+    mut receiver: mpsc::UnboundedReceiver<Value>,
+) -> Result<()> {
+    while let Some(message) = receiver.recv().await {
+        writer
+            .write_all(format!("{message}\n").as_bytes())
+            .await
+            .map_err(|e| Error::configuration(format!("Failed to write agent message: {e}")))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| Error::configuration(format!("Failed to flush agent message: {e}")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufRead, Lines};
+
+    /// Next response, skipping notifications of tasks other tests run
+    async fn next_response<R: AsyncBufRead + Unpin>(lines: &mut Lines<R>) -> Value {
+        loop {
+            let line = lines.next_line().await.unwrap().unwrap();
+            let message: Value = serde_json::from_str(&line).unwrap();
+            if message.get("id").is_some() {
+                return message;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_answers_requests_in_order() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (server_reader, server_writer) = tokio::io::split(server);
+        let connection = tokio::spawn(serve_connection(server_reader, server_writer));
+
+        let (client_reader, mut client_writer) = tokio::io::split(client);
+        client_writer
+            .write_all(
+                concat!(
+                    "{\"jsonrpc\":\"2.0\",\"method\":\"status\",\"id\":1}\n",
+                    "not json\n",
+                    "{\"jsonrpc\":\"2.0\",\"method\":\"listTasks\",\"id\":2}\n",
+                    "{\"jsonrpc\":\"2.0\",\"method\":\"nope\",\"id\":3}\n",
+                    "{\"jsonrpc\":\"2.0\",\"method\":\"shutdown\",\"id\":4}\n",
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        let mut lines = BufReader::new(client_reader).lines();
+        let status = next_response(&mut lines).await;
+        assert_eq!(status["id"], 1);
+        assert_eq!(status["result"]["loaded"], false);
+        assert_eq!(
+            next_response(&mut lines).await["error"]["code"],
+            PARSE_ERROR
+        );
+        assert_eq!(
+            next_response(&mut lines).await["error"]["code"],
+            methods::REQUEST_FAILED
+        );
+        assert_eq!(
+            next_response(&mut lines).await["error"]["code"],
+            methods::METHOD_NOT_FOUND
+        );
+        assert_eq!(next_response(&mut lines).await["id"], 4);
+
+        connection.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+//! `cuenv bench`: time core paths against a generated repository
+//!
+//! Generates a synthetic CUE module of the requested size, then measures
+//! CUE evaluation, environment loading, cache key computation and DAG
+//! scheduling on it. Results can be saved as a named baseline and later
+//! runs compared against it, failing when a path got slower than the
+//! threshold allows. `cargo bench -p cuenv --bench core_paths` runs the
+//! same paths under criterion.
+
+pub mod paths;
+pub mod synthetic;
+
+use self::paths::{BenchPath, Fixture};
+use self::synthetic::RepoSize;
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// What to measure and what to compare it with
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub size: RepoSize,
+    /// Times each path is measured, after one warm-up run
+    pub iterations: usize,
+    /// Record the results as this baseline
+    pub save_baseline: Option<String>,
+    /// Compare the results with this baseline
+    pub baseline: Option<String>,
+    /// Slowdown of the median, in percent, counted as a regression
+    pub threshold: f64,
+    pub baseline_dir: PathBuf,
+}
+
+/// Medians of a run, as saved for later comparison
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Baseline {
+    size: RepoSize,
+    /// Median of each path, by path name, in nanoseconds
+    medians_ns: BTreeMap<String, u64>,
+}
+
+/// Timings of one path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Measurement {
+    min: Duration,
+    median: Duration,
+    max: Duration,
+}
+
+impl Measurement {
+    fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        samples.sort();
+        Some(Self {
+            min: *samples.first()?,
+            median: *samples.get(samples.len() / 2)?,
+            max: *samples.last()?,
+        })
+    }
+}
+
+pub async fn execute(options: BenchOptions) -> Result<()> {
+    if options.iterations == 0 {
+        return Err(Error::configuration("--iterations must be at least 1"));
+    }
+    let baseline = options
+        .baseline
+        .as_deref()
+        .map(|name| load_baseline(&options.baseline_dir, name))
+        .transpose()?;
+
+    eprintln!("Generating repository: {}", options.size);
+    let fixture = Fixture::generate(options.size)?;
+
+    let mut results = BTreeMap::new();
+    for path in BenchPath::ALL {
+        fixture.run(path).await?;
+        let mut samples = Vec::with_capacity(options.iterations);
+        for _ in 0..options.iterations {
+            let start = Instant::now();
+            fixture.run(path).await?;
+            samples.push(start.elapsed());
+        }
+        if let Some(measurement) = Measurement::from_samples(samples) {
+            results.insert(path.name(), measurement);
+        }
+    }
+
+    if let Some(baseline) = &baseline {
+        if baseline.size != *fixture.size() {
+            eprintln!(
+                "Warning: the baseline was measured on a different repository ({})",
+                baseline.size
+            );
+        }
+    }
+    let regressions = report(&results, baseline.as_ref(), options.threshold);
+
+    if let Some(name) = &options.save_baseline {
+        let saved = Baseline {
+            size: *fixture.size(),
+            medians_ns: results
+                .iter()
+                .map(|(path, m)| (path.to_string(), m.median.as_nanos() as u64))
+                .collect(),
+        };
+        let file = save_baseline(&options.baseline_dir, name, &saved)?;
+        eprintln!("Saved baseline '{name}' to {}", file.display());
+    }
+
+    if regressions.is_empty() {
+        return Ok(());
+    }
+    Err(Error::configuration(format!(
+        "Slower than baseline '{}' by more than {}%: {}",
+        options.baseline.unwrap_or_default(),
+        options.threshold,
+        regressions.join(", ")
+    )))
+}
+
+/// Print the results, returning the paths slower than `baseline` allows
+fn report(
+    results: &BTreeMap<&'static str, Measurement>,
+    baseline: Option<&Baseline>,
+    threshold: f64,
+) -> Vec<String> {
+    let mut regressions = Vec::new();
+    println!(
+        "{:<14} {:>10} {:>10} {:>10}{}",
+        "path",
+        "min",
+        "median",
+        "max",
+        if baseline.is_some() {
+            format!(" {:>10} {:>8}", "baseline", "change")
+        } else {
+            String::new()
+        }
+    );
+    for (path, measurement) in results {
+        let mut line = format!(
+            "{path:<14} {:>10} {:>10} {:>10}",
+            format_duration(measurement.min),
+            format_duration(measurement.median),
+            format_duration(measurement.max)
+        );
+        if let Some(before) = baseline.and_then(|b| b.medians_ns.get(*path)) {
+            let change = change_percent(*before, measurement.median);
+            line.push_str(&format!(
+                " {:>10} {:>+7.1}%",
+                format_duration(Duration::from_nanos(*before)),
+                change
+            ));
+            if change > threshold {
+                regressions.push(path.to_string());
+                line.push_str("  regressed");
+            }
+        }
+        println!("{line}");
+    }
+    regressions
+}
+
+/// How much slower `after` is than `before`, in percent
+fn change_percent(before_ns: u64, after: Duration) -> f64 {
+    if before_ns == 0 {
+        return 0.0;
+    }
+    (after.as_nanos() as f64 - before_ns as f64) / before_ns as f64 * 100.0
+}
+
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1_000_000.0;
+    if micros < 1_000.0 {
+        format!("{micros:.1}µs")
+    } else if micros < 1_000_000.0 {
+        format!("{:.2}ms", micros / 1_000.0)
+    } else {
+        format!("{:.3}s", micros / 1_000_000.0)
+    }
+}
+
+fn baseline_file(dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::configuration(format!(
+            "Invalid baseline name '{name}': use letters, digits, '-' and '_'"
+        )));
+    }
+    Ok(dir.join(format!("{name}.json")))
+}
+
+fn load_baseline(dir: &Path, name: &str) -> Result<Baseline> {
+    let file = baseline_file(dir, name)?;
+    let content = std::fs::read_to_string(&file)
+        .map_err(|e| Error::file_system(&file, "read benchmark baseline", e))?;
+    serde_json::from_str(&content).map_err(|e| Error::Json {
+        message: format!("invalid benchmark baseline {}", file.display()),
+        source: e,
+    })
+}
+
+fn save_baseline(dir: &Path, name: &str, baseline: &Baseline) -> Result<PathBuf> {
+    let file = baseline_file(dir, name)?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| Error::file_system(dir, "create benchmark baseline directory", e))?;
+    let json = serde_json::to_string_pretty(baseline).map_err(|e| Error::Json {
+        message: "failed to serialize benchmark baseline".to_string(),
+        source: e,
+    })?;
+    write_atomic_string(&file, &json)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measurement_from_samples() {
+        let samples = [30, 10, 20].map(Duration::from_millis).to_vec();
+        assert_eq!(
+            Measurement::from_samples(samples),
+            Some(Measurement {
+                min: Duration::from_millis(10),
+                median: Duration::from_millis(20),
+                max: Duration::from_millis(30),
+            })
+        );
+        assert_eq!(Measurement::from_samples(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_baseline_round_trip_and_regressions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let size = RepoSize {
+            packages: 1,
+            tasks: 1,
+            variables: 1,
+            files: 1,
+        };
+        let baseline = Baseline {
+            size,
+            medians_ns: BTreeMap::from([
+                ("cue-eval".to_string(), 1_000_000),
+                ("dag-schedule".to_string(), 1_000_000),
+            ]),
+        };
+        save_baseline(temp_dir.path(), "main", &baseline).unwrap();
+        assert_eq!(load_baseline(temp_dir.path(), "main").unwrap(), baseline);
+        assert!(load_baseline(temp_dir.path(), "../main").is_err());
+
+        let measurement = |millis| Measurement {
+            min: Duration::from_millis(millis),
+            median: Duration::from_millis(millis),
+            max: Duration::from_millis(millis),
+        };
+        let results = BTreeMap::from([
+            ("cue-eval", measurement(1)),
+            ("dag-schedule", measurement(2)),
+        ]);
+        assert_eq!(report(&results, Some(&baseline), 10.0), ["dag-schedule"]);
+    }
+}
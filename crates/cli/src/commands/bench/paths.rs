@@ -0,0 +1,149 @@
+//! The core paths measured, run against a generated repository
+
+use super::synthetic::{self, RepoSize};
+use cuenv_cache::concurrent::action::ActionCache;
+use cuenv_cache::ContentAddressedStore;
+use cuenv_config::{CueParser, ParseOptions, ParseResult, TaskConfig};
+use cuenv_core::constants::DEFAULT_PACKAGE_NAME;
+use cuenv_core::{Error, Result, TaskDefinition};
+use cuenv_env::manager::environment::SupervisorMode;
+use cuenv_env::EnvManager;
+use cuenv_task::{TaskBuilder, UnifiedTaskDAG};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// A core path of cuenv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BenchPath {
+    /// Evaluate every package through the CUE bridge
+    CueEval,
+    /// Load the first package's environment as `cuenv env` would
+    EnvLoad,
+    /// Compute the cache key of every task, with file hashes warm
+    CacheKey,
+    /// Build every package's task DAG and order it into levels
+    DagSchedule,
+}
+
+impl BenchPath {
+    pub const ALL: [Self; 4] = [
+        Self::CueEval,
+        Self::EnvLoad,
+        Self::CacheKey,
+        Self::DagSchedule,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::CueEval => "cue-eval",
+            Self::EnvLoad => "env-load",
+            Self::CacheKey => "cache-key",
+            Self::DagSchedule => "dag-schedule",
+        }
+    }
+}
+
+/// A generated repository and what the paths need from it, prepared once
+pub struct Fixture {
+    size: RepoSize,
+    packages: Vec<Package>,
+    action_cache: ActionCache,
+    _root: TempDir,
+}
+
+struct Package {
+    dir: PathBuf,
+    tasks: HashMap<String, TaskConfig>,
+    definitions: HashMap<String, TaskDefinition>,
+}
+
+impl Fixture {
+    /// Generate a repository of `size` in a temporary directory
+    pub fn generate(size: RepoSize) -> Result<Self> {
+        let root = tempfile::Builder::new()
+            .prefix("cuenv-bench-")
+            .tempdir()
+            .map_err(|e| Error::file_system(std::env::temp_dir(), "create bench repository", e))?;
+        let mut packages = Vec::with_capacity(size.packages);
+        for dir in synthetic::generate(root.path(), &size)? {
+            let tasks = evaluate(&dir)?.tasks;
+            let definitions = TaskBuilder::new_with_env(dir.clone(), HashMap::new())
+                .build_tasks(tasks.clone())?;
+            packages.push(Package {
+                dir,
+                tasks,
+                definitions,
+            });
+        }
+
+        let cache_dir = root.path().join(".cache");
+        let cas = Arc::new(ContentAddressedStore::new(cache_dir.join("cas"), 4096)?);
+        let action_cache = ActionCache::new(cas, 0, &cache_dir)?;
+        Ok(Self {
+            size,
+            packages,
+            action_cache,
+            _root: root,
+        })
+    }
+
+    pub fn size(&self) -> &RepoSize {
+        &self.size
+    }
+
+    /// Run `path` once
+    pub async fn run(&self, path: BenchPath) -> Result<()> {
+        match path {
+            BenchPath::CueEval => {
+                for package in &self.packages {
+                    evaluate(&package.dir)?;
+                }
+            }
+            BenchPath::EnvLoad => {
+                if let Some(package) = self.packages.first() {
+                    EnvManager::embedded()
+                        .load_env_with_options(
+                            &package.dir,
+                            None,
+                            Vec::new(),
+                            None,
+                            SupervisorMode::Synchronous,
+                        )
+                        .await?;
+                }
+            }
+            BenchPath::CacheKey => {
+                for package in &self.packages {
+                    for (name, definition) in &package.definitions {
+                        self.action_cache
+                            .compute_digest(
+                                name,
+                                definition,
+                                &package.dir,
+                                HashMap::new(),
+                                &BTreeSet::new(),
+                                &HashMap::new(),
+                            )
+                            .await?;
+                    }
+                }
+            }
+            BenchPath::DagSchedule => {
+                for package in &self.packages {
+                    let names: Vec<String> = package.tasks.keys().cloned().collect();
+                    UnifiedTaskDAG::builder()
+                        .with_task_configs(package.tasks.clone())
+                        .build_for_tasks(&names)?
+                        .get_execution_levels()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn evaluate(dir: &Path) -> Result<ParseResult> {
+    CueParser::eval_package_with_options(dir, DEFAULT_PACKAGE_NAME, &ParseOptions::default())
+}
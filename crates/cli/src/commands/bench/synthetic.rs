@@ -0,0 +1,143 @@
+//! Synthetic repositories to benchmark against
+//!
+//! A CUE module with `packages` packages under `packages/`, each defining
+//! `variables` variables and `tasks` tasks. Every task reads `files` input
+//! files of its own and depends on the task before it and the one halfway
+//! back, so the DAG has both chains and fan-in.
+
+use cuenv_core::constants::{DEFAULT_PACKAGE_NAME, ENV_CUE_FILENAME};
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How large a generated repository is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoSize {
+    pub packages: usize,
+    /// Tasks per package
+    pub tasks: usize,
+    /// Variables per package
+    pub variables: usize,
+    /// Input files per task
+    pub files: usize,
+}
+
+impl std::fmt::Display for RepoSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} packages, {} tasks and {} variables each, {} input files per task",
+            self.packages, self.tasks, self.variables, self.files
+        )
+    }
+}
+
+/// Write a repository of `size` under `root`, returning its package
+/// directories
+pub fn generate(root: &Path, size: &RepoSize) -> Result<Vec<PathBuf>> {
+    write(
+        &root.join("cue.mod").join("module.cue"),
+        "module: \"bench.cuenv.dev/synthetic\"\nlanguage: version: \"v0.9.0\"\n",
+    )?;
+
+    let mut packages = Vec::with_capacity(size.packages);
+    for package in 0..size.packages {
+        let dir = root.join("packages").join(format!("pkg_{package}"));
+        write(&dir.join(ENV_CUE_FILENAME), &env_cue(package, size))?;
+        for task in 0..size.tasks {
+            for file in 0..size.files {
+                write(
+                    &dir.join("src")
+                        .join(format!("task_{task}"))
+                        .join(format!("file_{file}.txt")),
+                    &format!("package {package} task {task} file {file}\n"),
+                )?;
+            }
+        }
+        packages.push(dir);
+    }
+    Ok(packages)
+}
+
+/// Name of a generated task
+pub fn task_name(task: usize) -> String {
+    format!("task_{task}")
+}
+
+/// Tasks a generated task depends on
+pub fn task_dependencies(task: usize) -> Vec<usize> {
+    let mut dependencies: Vec<usize> = [task.checked_sub(1), (task > 1).then_some(task / 2)]
+        .into_iter()
+        .flatten()
+        .collect();
+    dependencies.dedup();
+    dependencies
+}
+
+fn env_cue(package: usize, size: &RepoSize) -> String {
+    let mut cue = format!("package {DEFAULT_PACKAGE_NAME}\n\nenv: {{\n");
+    for variable in 0..size.variables {
+        let _ = writeln!(
+            cue,
+            "\tVAR_{variable}: \"package-{package}-value-{variable}\""
+        );
+    }
+    cue.push_str("}\n\ntasks: {\n");
+    for task in 0..size.tasks {
+        let dependencies: Vec<String> = task_dependencies(task)
+            .into_iter()
+            .map(|dependency| format!("\"{}\"", task_name(dependency)))
+            .collect();
+        let _ = writeln!(
+            cue,
+            "\t\"{}\": {{\n\t\tcommand: \"true\"\n\t\tdependencies: [{}]\n\t\tinputs: [\"src/task_{task}/**\"]\n\t}}",
+            task_name(task),
+            dependencies.join(", ")
+        );
+    }
+    cue.push_str("}\n");
+    cue
+}
+
+fn write(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| Error::file_system(parent, "create directory", e))?;
+    }
+    fs::write(path, content).map_err(|e| Error::file_system(path, "write file", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_writes_packages_and_inputs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let size = RepoSize {
+            packages: 2,
+            tasks: 3,
+            variables: 2,
+            files: 2,
+        };
+        let packages = generate(temp_dir.path(), &size).unwrap();
+
+        assert_eq!(packages.len(), 2);
+        assert!(temp_dir.path().join("cue.mod/module.cue").is_file());
+        assert!(packages[1].join("src/task_2/file_1.txt").is_file());
+        let cue = fs::read_to_string(packages[0].join("env.cue")).unwrap();
+        assert!(cue.contains("\tVAR_1: \"package-0-value-1\"\n"));
+        assert!(cue
+            .contains("\t\"task_2\": {\n\t\tcommand: \"true\"\n\t\tdependencies: [\"task_1\"]\n"));
+    }
+
+    #[test]
+    fn test_task_dependencies_chain_and_fan_in() {
+        assert_eq!(task_dependencies(0), Vec::<usize>::new());
+        assert_eq!(task_dependencies(1), [0]);
+        assert_eq!(task_dependencies(2), [1]);
+        assert_eq!(task_dependencies(6), [5, 3]);
+    }
+}
@@ -0,0 +1,64 @@
+use cuenv_cache::manager::history::{since_window, sparkline, summarize, StatsTrend};
+use cuenv_cache::CacheManager;
+use cuenv_core::{Error, Result};
+use std::time::SystemTime;
+
+/// Print the persisted statistics trend for the given look-back window
+pub fn execute(manager: &CacheManager, since: &str, format: &str) -> Result<()> {
+    let window = cuenv_utils::parse_duration(since)?;
+    let start = since_window(window);
+    let entries = manager.history().load_since(start)?;
+    let trend = summarize(&entries, start, SystemTime::now());
+
+    match format {
+        "json" => {
+            let json = serde_json::to_string_pretty(&trend).map_err(|e| Error::Json {
+                message: "failed to serialize cache statistics history".to_string(),
+                source: e,
+            })?;
+            println!("{json}");
+        }
+        "human" => print_human(&trend, since),
+        other => {
+            return Err(Error::configuration(format!(
+                "Invalid format '{other}'. Must be one of: human, json"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn print_human(trend: &StatsTrend, since: &str) {
+    println!("Cache Statistics (last {since}, {} runs):", trend.runs);
+    if trend.runs == 0 {
+        println!("  No cache activity recorded in this window");
+        return;
+    }
+
+    let hit_rates: Vec<Option<f64>> = trend.buckets.iter().map(|b| b.hit_rate).collect();
+    let bytes_saved: Vec<Option<f64>> = trend
+        .buckets
+        .iter()
+        .map(|b| Some(b.bytes_saved as f64))
+        .collect();
+    let bucket = if trend.bucket_seconds >= 86_400 {
+        "day"
+    } else {
+        "hour"
+    };
+
+    println!("  Hits: {}", trend.hits);
+    println!("  Misses: {}", trend.misses);
+    println!("  Writes: {}", trend.writes);
+    println!("  Errors: {}", trend.errors);
+    println!(
+        "  Hit rate: {:.1}%  {}  (per {bucket})",
+        trend.hit_rate,
+        sparkline(&hit_rates)
+    );
+    println!(
+        "  Bytes saved: {:.2} MB  {}  (per {bucket})",
+        trend.bytes_saved as f64 / 1_048_576.0,
+        sparkline(&bytes_saved)
+    );
+}
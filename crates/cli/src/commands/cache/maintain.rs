@@ -0,0 +1,120 @@
+use cuenv_cache::maintenance::{Maintenance, MaintenanceConfig, StatePruneJob};
+use cuenv_core::{Result, CUENV_IDLE_MAINTENANCE_VAR};
+use cuenv_utils::state_prune::{PrunePolicy, StatePruner};
+use cuenv_utils::xdg::XdgPaths;
+use std::env;
+use std::process::{Command, Stdio};
+
+fn maintenance(config: MaintenanceConfig) -> Maintenance {
+    let state = StatePruneJob::new(StatePruner::for_user(PrunePolicy::default()))
+        .before_remove(crate::commands::env::stop_compose_services_blocking);
+    Maintenance::new(&XdgPaths::cache_dir(), state, config)
+}
+
+/// Run maintenance in the foreground
+pub fn execute(wait: bool) -> Result<()> {
+    let Some(report) = maintenance(MaintenanceConfig::default()).run(wait)? else {
+        println!("Maintenance is already running");
+        return Ok(());
+    };
+
+    let summary = format!(
+        "{} checked, {} removed, {} refreshed",
+        report.processed, report.removed, report.refreshed
+    );
+    if report.completed {
+        println!("✓ Maintenance finished: {summary}");
+    } else {
+        println!("Maintenance paused while the machine is busy: {summary}");
+    }
+    Ok(())
+}
+
+/// Called by the shell hook on every prompt when `CUENV_IDLE_MAINTENANCE` is
+/// set: records the activity and, when a run is due, launches one in the
+/// background
+///
+/// The variable is either a truthy value or the minimum interval between
+/// runs, e.g. `30m`. Failures are logged only, so the prompt is never held up.
+/// Returns whether idle maintenance is on, in which case it prunes stale
+/// state too.
+pub fn on_prompt() -> bool {
+    let Some(config) = env::var(CUENV_IDLE_MAINTENANCE_VAR)
+        .ok()
+        .and_then(|value| config_from_var(&value))
+    else {
+        return false;
+    };
+
+    let maintenance = maintenance(config);
+    let launched = maintenance
+        .idle_detector()
+        .record_activity()
+        .and_then(|()| maintenance.claim_launch());
+    match launched {
+        Ok(true) => {
+            if let Err(e) = spawn_background_run() {
+                tracing::debug!("Failed to launch cache maintenance: {e}");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => tracing::debug!("Cache maintenance check failed: {e}"),
+    }
+    true
+}
+
+fn config_from_var(value: &str) -> Option<MaintenanceConfig> {
+    let defaults = MaintenanceConfig::default();
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "0" | "false" | "off" | "no" => None,
+        "1" | "true" | "on" | "yes" => Some(defaults),
+        interval => match cuenv_utils::parse_duration(interval) {
+            Ok(min_interval) => Some(MaintenanceConfig {
+                min_interval,
+                ..defaults
+            }),
+            Err(e) => {
+                tracing::debug!("Ignoring {CUENV_IDLE_MAINTENANCE_VAR}: {e}");
+                None
+            }
+        },
+    }
+}
+
+fn spawn_background_run() -> std::io::Result<()> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(["cache", "maintain", "--wait"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Keep the run alive when the shell's foreground job is interrupted
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    command.spawn().map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_config_from_var() {
+        assert!(config_from_var("off").is_none());
+        assert!(config_from_var("bogus").is_none());
+        assert_eq!(
+            config_from_var("true").map(|c| c.min_interval),
+            Some(MaintenanceConfig::default().min_interval)
+        );
+        assert_eq!(
+            config_from_var("30m").map(|c| c.min_interval),
+            Some(Duration::from_secs(30 * 60))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use cuenv_cache::manager::RunManifest;
+use cuenv_cache::remote::{RemoteCache, REMOTE_CACHE_ENV_VAR};
+use cuenv_cache::security::signing::CacheSigner;
+use cuenv_cache::CacheManager;
+use cuenv_core::{Error, Result};
+use std::path::Path;
+
+/// Print the signed manifest of a run
+pub fn write(manager: &CacheManager, run: Option<&str>) -> Result<()> {
+    let base_dir = &manager.config().base_dir;
+    let manifest = RunManifest::for_run(&manager.run_log(), run)?;
+    let signer = CacheSigner::new(base_dir).map_err(|e| {
+        Error::file_system(
+            base_dir,
+            "load cache signing key",
+            std::io::Error::other(e.to_string()),
+        )
+    })?;
+    let signed = manifest.sign(&signer)?;
+
+    let json = serde_json::to_string_pretty(&signed).map_err(|e| Error::Json {
+        message: "failed to serialize cache manifest".to_string(),
+        source: e,
+    })?;
+    println!("{json}");
+    eprintln!(
+        "Manifest of run {} ({} entries), signed with key {}",
+        manifest.run_id,
+        manifest.entries.len(),
+        hex::encode(signer.public_key())
+    );
+    Ok(())
+}
+
+/// Pull the objects listed in a manifest from the remote cache
+pub async fn fetch(
+    manager: &CacheManager,
+    path: &Path,
+    remote: Option<&str>,
+    trust: Option<&str>,
+) -> Result<()> {
+    let remote = remote
+        .map(RemoteCache::new)
+        .or_else(RemoteCache::from_env)
+        .ok_or_else(|| {
+            Error::configuration(format!(
+                "No remote cache configured. Pass --remote or set {REMOTE_CACHE_ENV_VAR}"
+            ))
+        })?;
+    let trusted_key = trust.map(parse_key).transpose()?;
+
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::file_system(path, "read cache manifest", e))?;
+    let manifest = RunManifest::verify(&text, trusted_key.as_ref())?;
+    let summary = remote
+        .fetch_manifest(&manifest, &manager.content_store(), &manager.run_log())
+        .await?;
+
+    println!(
+        "✓ Fetched run {} from {}: {} objects downloaded ({:.2} MB), {} already present",
+        manifest.run_id,
+        remote.endpoint(),
+        summary.fetched,
+        summary.bytes as f64 / 1_048_576.0,
+        summary.present
+    );
+    Ok(())
+}
+
+fn parse_key(key: &str) -> Result<[u8; 32]> {
+    hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            Error::configuration(format!(
+                "Invalid public key '{key}'. Expected 64 hexadecimal characters"
+            ))
+        })
+}
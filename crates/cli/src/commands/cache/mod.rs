@@ -1,19 +1,80 @@
 use clap::Subcommand;
 use cuenv_cache::{CacheConfig, CacheManager};
 use cuenv_core::Result;
+use std::path::PathBuf;
+
+mod history;
+mod maintain;
+mod manifest;
+mod relocate;
+
+pub use maintain::on_prompt as maintenance_on_prompt;
 
 #[derive(Subcommand)]
 pub enum CacheCommands {
     /// Clear all cache entries
     Clear,
     /// Show cache statistics
-    Stats,
+    Stats {
+        /// Show persisted statistics history and trends instead of the current process
+        #[arg(long)]
+        history: bool,
+
+        /// Look-back window for --history (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "7d", requires = "history")]
+        since: String,
+
+        /// Output format for --history (human or json)
+        #[arg(long, default_value = "human", requires = "history")]
+        format: String,
+    },
     /// Clean up stale cache entries
     Cleanup {
         /// Maximum age of cache entries to keep (in hours)
         #[arg(long, default_value = "168")]
         max_age_hours: u64,
     },
+    /// Clean up stale entries, prune old shell state and refresh file hashes
+    /// while the machine is idle
+    Maintain {
+        /// Wait for the machine to go idle instead of stopping when it is busy
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Move the cache to where cuenv now resolves it, e.g. after setting
+    /// `cacheDir` or `CUENV_CACHE_DIR`
+    Move {
+        /// Destination (defaults to the current cache directory)
+        to: Option<PathBuf>,
+
+        /// Directory to move from (defaults to the default cache directory)
+        #[arg(long)]
+        from: Option<PathBuf>,
+
+        /// Move the per-directory state instead of the cache
+        #[arg(long)]
+        state: bool,
+    },
+    /// Print a signed manifest of the cache entries a run recorded
+    Manifest {
+        /// Run to describe (defaults to the most recent run)
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Pull the objects listed in a manifest from a remote cache
+    Fetch {
+        /// Manifest written by `cuenv cache manifest`
+        #[arg(long)]
+        manifest: PathBuf,
+
+        /// Remote cache endpoint (defaults to CUENV_REMOTE_CACHE)
+        #[arg(long)]
+        remote: Option<String>,
+
+        /// Public key (hex) the manifest must be signed with; required
+        #[arg(long)]
+        trust: Option<String>,
+    },
 }
 
 impl CacheCommands {
@@ -26,9 +87,16 @@ impl CacheCommands {
                 println!("✓ Cache cleared successfully");
                 Ok(())
             }
-            CacheCommands::Stats => {
+            CacheCommands::Stats {
+                history,
+                since,
+                format,
+            } => {
                 let config = CacheConfig::default();
                 let manager = CacheManager::new(config).await?;
+                if history {
+                    return history::execute(&manager, &since, &format);
+                }
                 let stats = manager.get_statistics();
                 println!("Cache Statistics:");
                 println!("  Hits: {}", stats.hits);
@@ -54,6 +122,26 @@ impl CacheCommands {
                 println!("✓ Cleaned up stale cache entries");
                 Ok(())
             }
+            CacheCommands::Move { to, from, state } => relocate::execute(to, from, state),
+            CacheCommands::Manifest { run } => {
+                let manager = CacheManager::new(CacheConfig::default()).await?;
+                manifest::write(&manager, run.as_deref())
+            }
+            CacheCommands::Fetch {
+                manifest,
+                remote,
+                trust,
+            } => {
+                let manager = CacheManager::new(CacheConfig::default()).await?;
+                manifest::fetch(&manager, &manifest, remote.as_deref(), trust.as_deref()).await
+            }
+            CacheCommands::Maintain { wait } => {
+                tokio::task::spawn_blocking(move || maintain::execute(wait))
+                    .await
+                    .map_err(|e| {
+                        cuenv_core::Error::configuration(format!("Maintenance task failed: {e}"))
+                    })?
+            }
         }
     }
 }
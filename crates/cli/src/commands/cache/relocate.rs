@@ -0,0 +1,152 @@
+//! `cuenv cache move`: carry the cache or state over to a new location
+//!
+//! By default this moves everything from the default location to wherever
+//! cuenv resolves the directory to now, so relocating it with `cacheDir`,
+//! `stateDir`, `CUENV_CACHE_DIR` or `CUENV_STATE_DIR` keeps existing entries.
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::paths::{get_cuenv_temp_dir, get_state_root};
+use cuenv_utils::xdg::XdgPaths;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Move the cache, or the state with `state`, from `from` to `to`
+pub fn execute(to: Option<PathBuf>, from: Option<PathBuf>, state: bool) -> Result<()> {
+    let (default_from, default_to) = if state {
+        (get_cuenv_temp_dir().join("state"), get_state_root())
+    } else {
+        (XdgPaths::default_cache_dir(), XdgPaths::cache_dir())
+    };
+    let from = from.unwrap_or(default_from);
+    let to = to.unwrap_or(default_to);
+
+    let moved = move_dir(&from, &to)?;
+    println!(
+        "✓ Moved {moved} entries from {} to {}",
+        from.display(),
+        to.display()
+    );
+    Ok(())
+}
+
+/// Move every entry of `from` into `to`, creating `to` if needed, and
+/// remove `from` once empty
+///
+/// Entries are renamed when both sides share a file system and copied
+/// otherwise. Nothing is moved when an entry already exists in `to`, so no
+/// data is overwritten.
+fn move_dir(from: &Path, to: &Path) -> Result<usize> {
+    if !from.is_dir() {
+        return Err(Error::configuration(format!(
+            "Nothing to move: {} is not a directory",
+            from.display()
+        )));
+    }
+    fs::create_dir_all(to).map_err(|e| Error::file_system(to, "create directory", e))?;
+
+    let canonical = |path: &Path| {
+        path.canonicalize()
+            .map_err(|e| Error::file_system(path, "resolve path", e))
+    };
+    let (source, target) = (canonical(from)?, canonical(to)?);
+    if source == target {
+        return Err(Error::configuration(format!(
+            "{} is already the current location",
+            to.display()
+        )));
+    }
+    if target.starts_with(&source) {
+        return Err(Error::configuration(format!(
+            "Cannot move {} into itself",
+            from.display()
+        )));
+    }
+
+    let entries = fs::read_dir(from)
+        .map_err(|e| Error::file_system(from, "read directory", e))?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(|e| Error::file_system(from, "read directory", e))?;
+
+    let conflicts: Vec<_> = entries
+        .iter()
+        .filter(|name| to.join(name).exists())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(Error::configuration(format!(
+            "{} already contains {}; move or remove them first",
+            to.display(),
+            conflicts.join(", ")
+        )));
+    }
+
+    for name in &entries {
+        move_entry(&from.join(name), &to.join(name))?;
+    }
+    fs::remove_dir(from).map_err(|e| Error::file_system(from, "remove directory", e))?;
+    Ok(entries.len())
+}
+
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    // Most likely a different file system
+    copy_recursive(from, to)?;
+    let removed = if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    };
+    removed.map_err(|e| Error::file_system(from, "remove moved entry", e))
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| Error::file_system(from, "copy file", e));
+    }
+    fs::create_dir_all(to).map_err(|e| Error::file_system(to, "create directory", e))?;
+    for entry in fs::read_dir(from).map_err(|e| Error::file_system(from, "read directory", e))? {
+        let entry = entry.map_err(|e| Error::file_system(from, "read directory", e))?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_move_dir() {
+        let temp = TempDir::new().unwrap();
+        let from = temp.path().join("cache");
+        let to = temp.path().join("project/.cuenv/cache");
+        fs::create_dir_all(from.join("actions")).unwrap();
+        fs::write(from.join("actions/entry"), "cached").unwrap();
+        fs::write(from.join("stats-history.jsonl"), "{}").unwrap();
+
+        assert_eq!(move_dir(&from, &to).unwrap(), 2);
+        assert!(!from.exists());
+        assert_eq!(
+            fs::read_to_string(to.join("actions/entry")).unwrap(),
+            "cached"
+        );
+
+        // Existing entries are never overwritten
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join("stats-history.jsonl"), "newer").unwrap();
+        assert!(move_dir(&from, &to).is_err());
+        assert_eq!(
+            fs::read_to_string(to.join("stats-history.jsonl")).unwrap(),
+            "{}"
+        );
+
+        assert!(move_dir(&to, &to).is_err());
+        assert!(move_dir(&to, &to.join("nested")).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! `cuenv ci`: the environment for pipeline steps
+//!
+//! `export` resolves every variable of env.cue without asking anything and
+//! without running hooks, and fails when a secret can't be resolved instead
+//! of passing its reference on. It writes an export script, or appends to
+//! GitHub Actions' `$GITHUB_ENV` file, masking secrets in the job log. Its
+//! report says how each variable was resolved, never with a value.
+
+use clap::Subcommand;
+use cuenv_config::Config;
+use cuenv_core::{Error, Result};
+use cuenv_env::manager::secrets::{is_secret_reference, resolve_secret};
+use cuenv_shell::mod_shell::escape_bash_like;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand)]
+pub enum CiCommands {
+    /// Resolve the environment for a pipeline step and export it
+    Export {
+        /// Output format (default: shell, options: shell, github)
+        #[arg(short, long, default_value = "shell")]
+        format: String,
+
+        /// File to write to instead of stdout; github appends to $GITHUB_ENV by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write a JSON report of how each variable was resolved to this file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+}
+
+impl CiCommands {
+    pub async fn execute(self, config: &Config) -> Result<()> {
+        match self {
+            CiCommands::Export {
+                format,
+                output,
+                report,
+            } => export(config, &format, output, report.as_deref()),
+        }
+    }
+}
+
+/// How a variable was resolved
+#[derive(Debug, Serialize)]
+struct Resolution {
+    name: String,
+    secret: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    environment: Option<&'a str>,
+    capabilities: &'a [String],
+    succeeded: bool,
+    variables: &'a [Resolution],
+}
+
+fn export(
+    config: &Config,
+    format: &str,
+    output: Option<PathBuf>,
+    report: Option<&Path>,
+) -> Result<()> {
+    if !["shell", "github"].contains(&format) {
+        return Err(Error::configuration(format!(
+            "Invalid format '{format}' for ci export. Must be one of: shell, github"
+        )));
+    }
+    let output = match (format, output) {
+        ("github", None) => Some(
+            std::env::var_os("GITHUB_ENV")
+                .map(PathBuf::from)
+                .ok_or_else(|| {
+                    Error::configuration(
+                        "GITHUB_ENV is not set; pass --output for the github format",
+                    )
+                })?,
+        ),
+        (_, output) => output,
+    };
+
+    let (values, resolutions) = resolve(config)?;
+    let failed: Vec<&Resolution> = resolutions.iter().filter(|r| r.error.is_some()).collect();
+    if let Some(path) = report {
+        let report = Report {
+            environment: config.runtime.environment.as_deref(),
+            capabilities: &config.runtime.capabilities,
+            succeeded: failed.is_empty(),
+            variables: &resolutions,
+        };
+        let json = serde_json::to_string_pretty(&report).map_err(|e| Error::Json {
+            message: "failed to encode the resolution report".to_string(),
+            source: e,
+        })?;
+        cuenv_utils::atomic_file::write_atomic_string(path, &(json + "\n"))?;
+    }
+    if !failed.is_empty() {
+        let listed: Vec<String> = failed
+            .iter()
+            .map(|r| format!("{}: {}", r.name, r.error.as_deref().unwrap_or_default()))
+            .collect();
+        return Err(Error::configuration(format!(
+            "Secrets could not be resolved, so nothing was exported:\n  {}",
+            listed.join("\n  ")
+        )));
+    }
+
+    let secrets: Vec<&str> = resolutions
+        .iter()
+        .filter(|r| r.secret)
+        .map(|r| values[&r.name].as_str())
+        .collect();
+    let content = match format {
+        "github" => {
+            // Workflow commands on stdout mask the values in the job log
+            for secret in &secrets {
+                for line in secret.lines().filter(|line| !line.trim().is_empty()) {
+                    println!("::add-mask::{line}");
+                }
+            }
+            github_env(&values)
+        }
+        _ => shell_script(&values),
+    };
+    match output {
+        Some(path) => write_private(&path, &content, format == "github")?,
+        None => print!("{content}"),
+    }
+    eprintln!(
+        "Exported {} variables ({} secrets)",
+        values.len(),
+        secrets.len()
+    );
+    Ok(())
+}
+
+/// The variables with their secrets resolved, and how each was
+fn resolve(config: &Config) -> Result<(BTreeMap<String, String>, Vec<Resolution>)> {
+    let mut values = BTreeMap::new();
+    let mut resolutions = Vec::new();
+    let variables: BTreeMap<String, String> = config.get_env_vars()?.into_iter().collect();
+    for (name, value) in variables {
+        if !is_variable_name(&name) {
+            return Err(Error::configuration(format!(
+                "'{name}' can't be exported: variable names are letters, digits and underscores"
+            )));
+        }
+        let secret = is_secret_reference(&value);
+        let resolved = if secret {
+            resolve_secret(&value).and_then(|resolved| {
+                if resolved.is_empty() {
+                    Err(Error::configuration("resolved to an empty value"))
+                } else {
+                    Ok(resolved)
+                }
+            })
+        } else {
+            Ok(value)
+        };
+        let error = match resolved {
+            Ok(resolved) => {
+                values.insert(name.clone(), resolved);
+                None
+            }
+            Err(e) => Some(e.to_string()),
+        };
+        resolutions.push(Resolution {
+            name,
+            secret,
+            error,
+        });
+    }
+    Ok((values, resolutions))
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn shell_script(values: &BTreeMap<String, String>) -> String {
+    values
+        .iter()
+        .map(|(name, value)| format!("export {name}={}\n", escape_bash_like(value)))
+        .collect()
+}
+
+/// `$GITHUB_ENV` lines, every value in a heredoc whose delimiter it doesn't
+/// contain, so no value can add variables of its own
+fn github_env(values: &BTreeMap<String, String>) -> String {
+    let mut content = String::new();
+    for (name, value) in values {
+        let delimiter = loop {
+            let delimiter = format!("CUENV_EOF_{}", uuid::Uuid::new_v4().simple());
+            if !value.contains(&delimiter) {
+                break delimiter;
+            }
+        };
+        content.push_str(&format!("{name}<<{delimiter}\n{value}\n{delimiter}\n"));
+    }
+    content
+}
+
+/// Write `content` to `path`, readable only by its owner when created
+fn write_private(path: &Path, content: &str, append: bool) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true);
+    if append {
+        options.append(true);
+    } else {
+        options.write(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| Error::file_system(path, "write ci export", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_formats() {
+        let values = BTreeMap::from([
+            ("A".to_string(), "1".to_string()),
+            ("B".to_string(), "x\nEVIL=1".to_string()),
+        ]);
+        assert_eq!(shell_script(&values), "export A=1\nexport B='x\nEVIL=1'\n");
+
+        let github = github_env(&values);
+        let lines: Vec<&str> = github.lines().collect();
+        assert_eq!(lines.len(), 7);
+        let delimiter = lines[0].strip_prefix("A<<").unwrap();
+        assert_eq!(lines[1..3], ["1", delimiter]);
+        assert!(lines[3].starts_with("B<<"));
+        assert_eq!(lines[4..6], ["x", "EVIL=1"]);
+    }
+
+    #[test]
+    fn test_variable_names() {
+        assert!(is_variable_name("_PATH2"));
+        assert!(!is_variable_name("2FA"));
+        assert!(!is_variable_name("A-B"));
+        assert!(!is_variable_name(""));
+    }
+}
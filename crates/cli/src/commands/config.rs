@@ -0,0 +1,98 @@
+//! `cuenv config`: the evaluated configuration itself
+//!
+//! `export-json` saves what the CUE bridge evaluates `env.cue` to as
+//! `env.json`. cuenv builds without the bridge load environments and list
+//! tasks from that file instead of evaluating. `event-schema` prints the
+//! JSON Schema of the events `--output-format json` writes. `lint` lists
+//! fields of `env.cue` the cuenv schema doesn't know, likely typos.
+
+use clap::Subcommand;
+use cuenv_config::{snapshot_path, strict_schema_error, Config, CueParser, EvalBackend};
+use cuenv_core::events::schema::json_schema;
+use cuenv_core::{Error, Result, CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME, ENV_CUE_FILENAME};
+use cuenv_utils::atomic_file::write_atomic_string;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Save the evaluated env.cue as env.json, for cuenv builds without the CUE bridge
+    #[command(name = "export-json")]
+    ExportJson {
+        /// File to write instead of env.json next to env.cue ('-' for stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the JSON Schema of the events written with --output-format json
+    #[command(name = "event-schema")]
+    EventSchema,
+
+    /// List fields of env.cue the cuenv schema doesn't know
+    Lint {
+        /// Fail when there are any, as `strictSchema: true` makes loading do
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+impl ConfigCommands {
+    pub async fn execute(self, config: &Config) -> Result<()> {
+        match self {
+            ConfigCommands::ExportJson { output } => {
+                export_json(&config.working_dir, output.as_deref())
+            }
+            ConfigCommands::EventSchema => {
+                let schema =
+                    serde_json::to_string_pretty(&json_schema()).map_err(|e| Error::Json {
+                        message: "failed to encode the event schema".to_string(),
+                        source: e,
+                    })?;
+                println!("{schema}");
+                Ok(())
+            }
+            ConfigCommands::Lint { strict } => lint(&config.working_dir, strict),
+        }
+    }
+}
+
+fn export_json(dir: &Path, output: Option<&Path>) -> Result<()> {
+    if EvalBackend::current()? == EvalBackend::Snapshot {
+        return Err(Error::configuration(format!(
+            "Exporting evaluates {ENV_CUE_FILENAME}, which needs the CUE bridge; \
+             unset CUENV_EVAL_BACKEND"
+        )));
+    }
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+    let json = CueParser::evaluate_json(dir, &package_name)?;
+    // Refuse to save evaluation errors, which would only surface when read
+    CueParser::parse_json(dir, &json)?;
+
+    match output {
+        Some(path) if path == Path::new("-") => println!("{json}"),
+        _ => {
+            let file = output.map_or_else(|| snapshot_path(dir), Path::to_path_buf);
+            write_atomic_string(&file, &json)?;
+            eprintln!("Exported {ENV_CUE_FILENAME} to {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+fn lint(dir: &Path, strict: bool) -> Result<()> {
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+    let issues = CueParser::lint_schema(dir, &package_name)?;
+    if strict {
+        if let Some(error) = strict_schema_error(&issues) {
+            return Err(error);
+        }
+    }
+    if issues.is_empty() {
+        println!("No unknown fields in {ENV_CUE_FILENAME}");
+    }
+    for issue in &issues {
+        eprintln!("warning: {issue}");
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! `cuenv docker-args` and `cuenv compose-env`: the environment in the forms
+//! container builds take it
+//!
+//! Build arguments end up in the image's history, so secrets are never
+//! turned into them; pass those with BuildKit's `--secret` instead. A
+//! compose `.env` file leaves secrets out unless `--secrets` says otherwise.
+
+use super::env::print::name_filter;
+use cuenv_config::Config;
+use cuenv_core::{Error, Result};
+use cuenv_env::manager::secrets::is_secret_reference;
+use cuenv_shell::mod_shell::escape_bash_like;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+/// What happens to secrets in a compose `.env` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretPolicy {
+    /// Left out
+    Omit,
+    /// Written as `NAME=${NAME}`, so compose takes the value from the
+    /// environment it runs in
+    Reference,
+    /// Written with a masked value
+    Mask,
+}
+
+impl std::str::FromStr for SecretPolicy {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "omit" => Ok(Self::Omit),
+            "reference" => Ok(Self::Reference),
+            "mask" => Ok(Self::Mask),
+            other => Err(Error::configuration(format!(
+                "Invalid secrets policy '{other}'. Must be one of: omit, reference, mask"
+            ))),
+        }
+    }
+}
+
+/// Variables whose names match `allow`, all when it is empty, split into
+/// plain values and the names of secrets
+fn variables(config: &Config, allow: &[String]) -> Result<(BTreeMap<String, String>, Vec<String>)> {
+    let filter = name_filter(allow)?;
+    let mut values = BTreeMap::new();
+    let mut secrets = Vec::new();
+    for (name, value) in config.get_env_vars()? {
+        if !filter.as_ref().is_none_or(|set| set.is_match(&name)) {
+            continue;
+        }
+        if is_secret_reference(&value) || config.get_metadata(&name).is_some_and(|meta| meta.lazy) {
+            secrets.push(name);
+        } else {
+            values.insert(name, value);
+        }
+    }
+    secrets.sort();
+    Ok((values, secrets))
+}
+
+/// Print `--build-arg NAME=VALUE` for each allowed variable that is not a
+/// secret, quoted for a shell or as a JSON array of arguments
+pub fn docker_args(config: &Config, allow: &[String], format: &str) -> Result<()> {
+    if !["shell", "json"].contains(&format) {
+        return Err(Error::configuration(format!(
+            "Invalid format '{format}' for docker-args. Must be one of: shell, json"
+        )));
+    }
+    let (values, secrets) = variables(config, allow)?;
+    if !secrets.is_empty() {
+        eprintln!(
+            "Not passing secrets as build arguments: {}; use `docker build --secret id=NAME,env=NAME` instead",
+            secrets.join(", ")
+        );
+    }
+
+    let args = build_args(&values);
+    if format == "json" {
+        println!("{}", serde_json::json!(args));
+    } else {
+        let quoted: Vec<String> = args.iter().map(|arg| escape_bash_like(arg)).collect();
+        println!("{}", quoted.join(" "));
+    }
+    Ok(())
+}
+
+fn build_args(values: &BTreeMap<String, String>) -> Vec<String> {
+    values
+        .iter()
+        .flat_map(|(name, value)| ["--build-arg".to_string(), format!("{name}={value}")])
+        .collect()
+}
+
+/// Write the allowed variables as a compose `.env` file to `output`, or
+/// print it
+pub fn compose_env(
+    config: &Config,
+    allow: &[String],
+    secrets: SecretPolicy,
+    output: Option<&Path>,
+) -> Result<()> {
+    let (values, secret_names) = variables(config, allow)?;
+    let content = dotenv(&values, &secret_names, secrets);
+    let Some(path) = output else {
+        print!("{content}");
+        return Ok(());
+    };
+
+    // The file may hold values worth keeping to its owner
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| Error::file_system(path, "write compose env file", e))?;
+    // Every line after the header is a variable
+    let written = content.lines().skip(1).count();
+    println!("Wrote {written} variables to {}", path.display());
+    Ok(())
+}
+
+fn dotenv(values: &BTreeMap<String, String>, secrets: &[String], policy: SecretPolicy) -> String {
+    let mut lines = vec!["# Generated by cuenv compose-env; changes are overwritten".to_string()];
+    lines.extend(
+        values
+            .iter()
+            .map(|(name, value)| format!("{name}={}", quote(value))),
+    );
+    lines.extend(secrets.iter().filter_map(|name| match policy {
+        SecretPolicy::Omit => None,
+        SecretPolicy::Reference => Some(format!("{name}=${{{name}}}")),
+        SecretPolicy::Mask => Some(format!("{name}={}", cuenv_core::redaction::MASK)),
+    }));
+    lines.join("\n") + "\n"
+}
+
+/// A value as compose reads it back unchanged, without interpolating it
+fn quote(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:@,+%".contains(c);
+    if !value.is_empty() && value.chars().all(plain) {
+        return value.to_string();
+    }
+    // Single quotes are taken literally
+    if !value.contains(['\'', '\n']) {
+        return format!("'{value}'");
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '$' => quoted.push_str("$$"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_args() {
+        let values = BTreeMap::from([
+            ("A".to_string(), "1".to_string()),
+            ("B".to_string(), "two words".to_string()),
+        ]);
+        assert_eq!(
+            build_args(&values),
+            ["--build-arg", "A=1", "--build-arg", "B=two words"]
+        );
+    }
+
+    #[test]
+    fn test_dotenv_quoting_and_secrets() {
+        let values = BTreeMap::from([
+            ("HOST".to_string(), "db.local:5432".to_string()),
+            ("GREETING".to_string(), "hi $USER".to_string()),
+            ("QUOTE".to_string(), "it's \"$5\"".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]);
+        let secrets = ["TOKEN".to_string()];
+        assert_eq!(
+            dotenv(&values, &secrets, SecretPolicy::Reference),
+            "# Generated by cuenv compose-env; changes are overwritten\n\
+             EMPTY=''\n\
+             GREETING='hi $USER'\n\
+             HOST=db.local:5432\n\
+             QUOTE=\"it's \\\"$$5\\\"\"\n\
+             TOKEN=${TOKEN}\n"
+        );
+        assert!(!dotenv(&values, &secrets, SecretPolicy::Omit).contains("TOKEN"));
+        assert!(dotenv(&values, &secrets, SecretPolicy::Mask).contains("TOKEN=***"));
+    }
+}
@@ -20,6 +20,9 @@ pub async fn execute(config: Arc<Config>, max_depth: usize, load: bool, dump: bo
                     println!("═══════════════════════════════════════════════");
                     println!("Package: {}", package.name);
                     println!("Path: {}", package.path.display());
+                    if let Some(kind) = package.inferred_from {
+                        println!("Inferred from the {kind} (no env.cue)");
+                    }
 
                     if let Some(ref result) = package.parse_result {
                         println!("\nEnvironment Variables:");
@@ -49,7 +52,14 @@ pub async fn execute(config: Arc<Config>, max_depth: usize, load: bool, dump: bo
                 // Normal mode: just list discovered packages
                 println!("Discovered {} CUE packages:", packages.len());
                 for package in packages {
-                    println!("  • {} ({})", package.name, package.path.display());
+                    match package.inferred_from {
+                        Some(kind) => println!(
+                            "  • {} ({}, inferred from the {kind})",
+                            package.name,
+                            package.path.display()
+                        ),
+                        None => println!("  • {} ({})", package.name, package.path.display()),
+                    }
                     if load {
                         if let Some(ref result) = package.parse_result {
                             println!("    - {} variables", result.variables.len());
@@ -0,0 +1,389 @@
+//! Packages inferred from package manager workspaces
+//!
+//! Members of a pnpm workspace (`pnpm-workspace.yaml`) or a Cargo workspace
+//! (`[workspace] members` of `Cargo.toml`) at the module root that have no
+//! env.cue yet are discovered as packages too, with `build` and `test` tasks
+//! running the package manager, so a large repository can adopt cuenv one
+//! package at a time.
+
+use cuenv_config::TaskConfig;
+use cuenv_core::{Error, Result};
+use cuenv_utils::IgnoreRules;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const PNPM_WORKSPACE_FILE: &str = "pnpm-workspace.yaml";
+const CARGO_MANIFEST_FILE: &str = "Cargo.toml";
+const PACKAGE_JSON_FILE: &str = "package.json";
+
+/// Tasks inferred for every member, where its manifest has them
+const INFERRED_TASKS: &[&str] = &["build", "test"];
+
+/// The package manager a workspace belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceKind {
+    Pnpm,
+    Cargo,
+}
+
+impl fmt::Display for WorkspaceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pnpm => write!(f, "pnpm workspace"),
+            Self::Cargo => write!(f, "Cargo workspace"),
+        }
+    }
+}
+
+/// A workspace member and the tasks inferred from its manifest
+#[derive(Debug, Clone)]
+pub struct WorkspaceMember {
+    pub path: PathBuf,
+    pub kind: WorkspaceKind,
+    pub tasks: HashMap<String, TaskConfig>,
+}
+
+/// Members of the workspaces declared at `module_root`, sorted by path
+///
+/// Directories the module root's `.cuenvignore` matches are skipped, as
+/// they are when looking for env.cue files.
+pub fn workspace_members(module_root: &Path, max_depth: usize) -> Result<Vec<WorkspaceMember>> {
+    let mut workspaces = Vec::new();
+    if let Some(patterns) = read_pnpm_workspace(module_root)? {
+        workspaces.push((WorkspaceKind::Pnpm, patterns));
+    }
+    if let Some(patterns) = read_cargo_workspace(module_root)? {
+        workspaces.push((WorkspaceKind::Cargo, patterns));
+    }
+    if workspaces.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ignore = IgnoreRules::load(module_root)?;
+    let cue_mod = module_root.join("cue.mod");
+    let directories: Vec<PathBuf> = WalkDir::new(module_root)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry
+                .path()
+                .strip_prefix(module_root)
+                .unwrap_or(entry.path());
+            entry.file_type().is_dir()
+                && entry.path() != cue_mod
+                && !ignore.is_ignored(relative, true)
+        })
+        .filter_map(|e| e.ok())
+        .map(|entry| entry.into_path())
+        .collect();
+
+    let mut members: Vec<WorkspaceMember> = Vec::new();
+    for (kind, patterns) in workspaces {
+        for dir in &directories {
+            let relative = dir.strip_prefix(module_root).unwrap_or(dir);
+            if !patterns.matches(relative) {
+                continue;
+            }
+            let Some(tasks) = infer_tasks(kind, dir) else {
+                continue;
+            };
+            // A directory in both workspaces keeps the tasks found first
+            match members.iter_mut().find(|member| &member.path == dir) {
+                Some(member) => {
+                    for (name, task) in tasks {
+                        member.tasks.entry(name).or_insert(task);
+                    }
+                }
+                None => members.push(WorkspaceMember {
+                    path: dir.clone(),
+                    kind,
+                    tasks,
+                }),
+            }
+        }
+    }
+    members.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(members)
+}
+
+/// Member patterns of a workspace, relative to its root
+#[derive(Debug)]
+struct MemberPatterns {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl MemberPatterns {
+    fn new(file: &Path, include: &[String], exclude: &[String]) -> Result<Self> {
+        let build = |patterns: &[String]| {
+            let mut set = GlobSetBuilder::new();
+            for pattern in patterns {
+                let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+                let glob = GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        Error::configuration(format!(
+                            "Invalid workspace member pattern '{pattern}' in {}: {e}",
+                            file.display()
+                        ))
+                    })?;
+                set.add(glob);
+            }
+            set.build().map_err(|e| {
+                Error::configuration(format!(
+                    "Invalid workspace members in {}: {e}",
+                    file.display()
+                ))
+            })
+        };
+        Ok(Self {
+            include: build(include)?,
+            exclude: build(exclude)?,
+        })
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        self.include.is_match(relative) && !self.exclude.is_match(relative)
+    }
+}
+
+/// The `packages` of `pnpm-workspace.yaml`, where `!` marks exclusions
+fn read_pnpm_workspace(root: &Path) -> Result<Option<MemberPatterns>> {
+    let path = root.join(PNPM_WORKSPACE_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| Error::file_system(&path, "read pnpm workspace", e))?;
+    let (exclude, include): (Vec<String>, Vec<String>) = pnpm_packages(&content)
+        .into_iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let exclude: Vec<String> = exclude
+        .iter()
+        .map(|pattern| pattern[1..].to_string())
+        .collect();
+    MemberPatterns::new(&path, &include, &exclude).map(Some)
+}
+
+/// Items of the top-level `packages` list of a pnpm workspace file
+///
+/// The file is only ever a list of globs, so this reads the block or flow
+/// sequence under `packages:` rather than YAML in general.
+fn pnpm_packages(content: &str) -> Vec<String> {
+    let unquote = |item: &str| {
+        item.trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string()
+    };
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or_default();
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = false;
+            if let Some(value) = trimmed.strip_prefix("packages:") {
+                let value = value.trim();
+                if let Some(flow) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                    packages.extend(flow.split(',').map(unquote).filter(|p| !p.is_empty()));
+                } else {
+                    in_packages = value.is_empty();
+                }
+            }
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                packages.push(unquote(item));
+            }
+        }
+    }
+    packages
+}
+
+/// The `members` and `exclude` of the `[workspace]` of `Cargo.toml`
+fn read_cargo_workspace(root: &Path) -> Result<Option<MemberPatterns>> {
+    let path = root.join(CARGO_MANIFEST_FILE);
+    let Some(manifest) = read_toml(&path)? else {
+        return Ok(None);
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Ok(None);
+    };
+    let strings = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    MemberPatterns::new(&path, &strings("members"), &strings("exclude")).map(Some)
+}
+
+fn read_toml(path: &Path) -> Result<Option<toml::Value>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| Error::file_system(path, "read manifest", e))?;
+    toml::from_str(&content)
+        .map(Some)
+        .map_err(|e| Error::configuration(format!("Failed to parse {}: {e}", path.display())))
+}
+
+/// Tasks of a member, or `None` if its manifest is missing or unreadable
+fn infer_tasks(kind: WorkspaceKind, dir: &Path) -> Option<HashMap<String, TaskConfig>> {
+    let inferred = match kind {
+        WorkspaceKind::Pnpm => pnpm_tasks(dir),
+        WorkspaceKind::Cargo => cargo_tasks(dir),
+    };
+    match inferred {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            tracing::warn!("Skipping {kind} member {}: {e}", dir.display());
+            None
+        }
+    }
+}
+
+/// `pnpm run` of the member's scripts named like the inferred tasks
+fn pnpm_tasks(dir: &Path) -> Result<Option<HashMap<String, TaskConfig>>> {
+    let path = dir.join(PACKAGE_JSON_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| Error::file_system(&path, "read manifest", e))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| Error::configuration(format!("Failed to parse {}: {e}", path.display())))?;
+    let scripts = manifest.get("scripts");
+    let tasks = INFERRED_TASKS
+        .iter()
+        .filter(|name| scripts.and_then(|s| s.get(**name)).is_some())
+        .map(|name| {
+            let command = format!("pnpm run {name}");
+            (name.to_string(), inferred_task(command, PACKAGE_JSON_FILE))
+        })
+        .collect();
+    Ok(Some(tasks))
+}
+
+/// `cargo build` and `cargo test` of the member's crate
+fn cargo_tasks(dir: &Path) -> Result<Option<HashMap<String, TaskConfig>>> {
+    let path = dir.join(CARGO_MANIFEST_FILE);
+    let Some(manifest) = read_toml(&path)? else {
+        return Ok(None);
+    };
+    let Some(name) = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(toml::Value::as_str)
+    else {
+        return Ok(None);
+    };
+    let tasks = INFERRED_TASKS
+        .iter()
+        .map(|task| {
+            let command = format!("cargo {task} -p {name}");
+            (
+                task.to_string(),
+                inferred_task(command, CARGO_MANIFEST_FILE),
+            )
+        })
+        .collect();
+    Ok(Some(tasks))
+}
+
+fn inferred_task(command: String, manifest: &str) -> TaskConfig {
+    TaskConfig {
+        description: Some(format!("`{command}`, inferred from {manifest}")),
+        command: Some(command),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pnpm_packages() {
+        let content = "# workspace\npackages:\n  - 'apps/*'\n  - \"packages/**\" # libs\n  - '!**/test/**'\ncatalog:\n  react: ^18\n";
+        assert_eq!(
+            pnpm_packages(content),
+            vec!["apps/*", "packages/**", "!**/test/**"]
+        );
+        assert_eq!(
+            pnpm_packages("packages: ['apps/*', libs/*]\n"),
+            vec!["apps/*", "libs/*"]
+        );
+    }
+
+    #[test]
+    fn test_workspace_members() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\n  - '!apps/legacy'\n",
+        );
+        write(
+            "apps/web/package.json",
+            r#"{"scripts": {"build": "vite build", "dev": "vite"}}"#,
+        );
+        write(
+            "apps/legacy/package.json",
+            r#"{"scripts": {"build": "make"}}"#,
+        );
+        write("apps/notes/README.md", "");
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/scratch\"]\n",
+        );
+        write(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"acme-core\"\n",
+        );
+        write(
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"scratch\"\n",
+        );
+
+        let members = workspace_members(root, 32).unwrap();
+        let paths: Vec<_> = members.iter().map(|m| m.path.clone()).collect();
+        assert_eq!(paths, vec![root.join("apps/web"), root.join("crates/core")]);
+
+        assert_eq!(members[0].kind, WorkspaceKind::Pnpm);
+        assert_eq!(members[0].tasks.len(), 1);
+        assert_eq!(
+            members[0].tasks["build"].command.as_deref(),
+            Some("pnpm run build")
+        );
+        assert_eq!(members[1].kind, WorkspaceKind::Cargo);
+        assert_eq!(
+            members[1].tasks["test"].command.as_deref(),
+            Some("cargo test -p acme-core")
+        );
+    }
+}
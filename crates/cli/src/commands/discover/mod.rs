@@ -1,5 +1,7 @@
+use self::inference::WorkspaceKind;
 use cuenv_config::{CueParser, ParseOptions, ParseResult};
 use cuenv_core::{Error, Result};
+use cuenv_utils::IgnoreRules;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -14,6 +16,8 @@ pub struct DiscoveredPackage {
     pub _relative_path: PathBuf,
     /// The parsed CUE package (if loaded)
     pub parse_result: Option<ParseResult>,
+    /// The workspace a package without env.cue was inferred from
+    pub inferred_from: Option<WorkspaceKind>,
 }
 
 /// Discovery configuration and state
@@ -62,10 +66,15 @@ impl PackageDiscovery {
     }
 
     /// Discover all env.cue files from the module root
+    ///
+    /// Directories matched by the module root's `.cuenvignore`, or by the
+    /// defaults such as `node_modules`, are not descended into.
     pub fn discover_env_files(&mut self, start_path: &Path) -> Result<Vec<PathBuf>> {
         // Find the module root first
         let module_root = Self::find_module_root(start_path)?;
         self.module_root = Some(module_root.clone());
+        let ignore = IgnoreRules::load(&module_root)?;
+        let cue_mod = module_root.join("cue.mod");
 
         let mut env_files = Vec::new();
 
@@ -74,15 +83,19 @@ impl PackageDiscovery {
             .max_depth(self.max_depth)
             .follow_links(false)
             .into_iter()
+            .filter_entry(|entry| {
+                let relative = entry
+                    .path()
+                    .strip_prefix(&module_root)
+                    .unwrap_or(entry.path());
+                entry.path() != cue_mod
+                    && (entry.depth() == 0
+                        || !ignore.is_ignored(relative, entry.file_type().is_dir()))
+            })
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
 
-            // Skip the cue.mod directory itself
-            if path.starts_with(module_root.join("cue.mod")) {
-                continue;
-            }
-
             // Check if this is an env.cue file
             if path.is_file() && path.file_name() == Some(std::ffi::OsStr::new("env.cue")) {
                 env_files.push(path.to_path_buf());
@@ -97,20 +110,24 @@ impl PackageDiscovery {
 
     /// Convert a path to a hierarchical package name
     pub fn format_package_name(&self, env_file_path: &Path) -> Result<String> {
-        let module_root = self
-            .module_root
-            .as_ref()
-            .ok_or_else(|| Error::configuration("Module root not set"))?;
-
         // Get the directory containing the env.cue file
         let package_dir = env_file_path
             .parent()
             .ok_or_else(|| Error::configuration("Invalid env.cue path"))?;
+        self.package_name_of_dir(package_dir)
+    }
+
+    /// Hierarchical name of the package in `package_dir`
+    fn package_name_of_dir(&self, package_dir: &Path) -> Result<String> {
+        let module_root = self
+            .module_root
+            .as_ref()
+            .ok_or_else(|| Error::configuration("Module root not set"))?;
 
         // Get the relative path from module root
         let relative_path = package_dir
             .strip_prefix(module_root)
-            .map_err(|_| Error::configuration("env.cue file not under module root"))?;
+            .map_err(|_| Error::configuration("Package directory not under module root"))?;
 
         // Convert path components to colon-separated name
         if relative_path.as_os_str().is_empty() {
@@ -129,6 +146,9 @@ impl PackageDiscovery {
     }
 
     /// Discover all packages and optionally load them
+    ///
+    /// Members of a pnpm or Cargo workspace at the module root without an
+    /// env.cue follow, with the tasks inferred from their manifests.
     pub async fn discover(
         &mut self,
         start_path: &Path,
@@ -136,15 +156,31 @@ impl PackageDiscovery {
     ) -> Result<Vec<DiscoveredPackage>> {
         // Discover all env.cue files
         let env_files = self.discover_env_files(start_path)?;
+        let package_dirs = env_files
+            .iter()
+            .map(|env_file| {
+                env_file
+                    .parent()
+                    .ok_or_else(|| Error::configuration("Invalid env.cue path"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Load the packages together, in a single call to the CUE bridge
+        let mut parse_results = if load_packages {
+            CueParser::eval_packages_with_options(
+                &package_dirs,
+                cuenv_core::constants::DEFAULT_PACKAGE_NAME,
+                &ParseOptions::default(),
+            )
+        } else {
+            Vec::new()
+        }
+        .into_iter();
 
         let mut packages = Vec::new();
 
-        for env_file in env_files {
-            let package_dir = env_file
-                .parent()
-                .ok_or_else(|| Error::configuration("Invalid env.cue path"))?;
-
-            let name = self.format_package_name(&env_file)?;
+        for (env_file, package_dir) in env_files.iter().zip(&package_dirs) {
+            let name = self.format_package_name(env_file)?;
 
             let relative_path = if let Some(ref module_root) = self.module_root {
                 package_dir
@@ -155,25 +191,13 @@ impl PackageDiscovery {
                 PathBuf::new()
             };
 
-            let parse_result = if load_packages {
-                // Load the package using existing CUE parser
-                match CueParser::eval_package_with_options(
-                    package_dir,
-                    cuenv_core::constants::DEFAULT_PACKAGE_NAME,
-                    &ParseOptions::default(),
-                ) {
-                    Ok(result) => Some(result),
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to load package at {}: {}",
-                            package_dir.display(),
-                            e
-                        );
-                        None
-                    }
+            let parse_result = match parse_results.next() {
+                Some(Ok(result)) => Some(result),
+                Some(Err(e)) => {
+                    tracing::warn!("Failed to load package at {}: {}", package_dir.display(), e);
+                    None
                 }
-            } else {
-                None
+                None => None,
             };
 
             packages.push(DiscoveredPackage {
@@ -181,9 +205,32 @@ impl PackageDiscovery {
                 path: package_dir.to_path_buf(),
                 _relative_path: relative_path,
                 parse_result,
+                inferred_from: None,
             });
         }
 
+        if let Some(module_root) = self.module_root.clone() {
+            for member in inference::workspace_members(&module_root, self.max_depth)? {
+                if packages.iter().any(|package| package.path == member.path) {
+                    continue;
+                }
+                packages.push(DiscoveredPackage {
+                    name: self.package_name_of_dir(&member.path)?,
+                    _relative_path: member
+                        .path
+                        .strip_prefix(&module_root)
+                        .unwrap_or(&member.path)
+                        .to_path_buf(),
+                    path: member.path,
+                    parse_result: Some(ParseResult {
+                        tasks: member.tasks,
+                        ..Default::default()
+                    }),
+                    inferred_from: Some(member.kind),
+                });
+            }
+        }
+
         Ok(packages)
     }
 
@@ -199,6 +246,9 @@ impl PackageDiscovery {
             .into_iter()
             .find(|p| p.name == package_name)
             .ok_or_else(|| Error::configuration(format!("Package '{package_name}' not found")))?;
+        if package.inferred_from.is_some() {
+            return Ok(package);
+        }
 
         // Load the package
         let parse_result = CueParser::eval_package_with_options(
@@ -212,6 +262,7 @@ impl PackageDiscovery {
             path: package.path,
             _relative_path: package._relative_path,
             parse_result: Some(parse_result),
+            inferred_from: None,
         })
     }
 }
@@ -305,6 +356,15 @@ mod tests {
         fs::create_dir(&backend_dir).unwrap();
         fs::write(backend_dir.join("env.cue"), "package cuenv\n").unwrap();
 
+        // Vendored and ignored trees are skipped
+        let vendored = temp_dir.path().join("node_modules/pkg");
+        fs::create_dir_all(&vendored).unwrap();
+        fs::write(vendored.join("env.cue"), "package cuenv\n").unwrap();
+        let generated = temp_dir.path().join("generated");
+        fs::create_dir(&generated).unwrap();
+        fs::write(generated.join("env.cue"), "package cuenv\n").unwrap();
+        fs::write(temp_dir.path().join(".cuenvignore"), "generated/\n").unwrap();
+
         let mut discovery = PackageDiscovery::new(32);
         let env_files = discovery.discover_env_files(temp_dir.path()).unwrap();
 
@@ -312,4 +372,5 @@ mod tests {
     }
 }
 mod execute;
+mod inference;
 pub use execute::execute;
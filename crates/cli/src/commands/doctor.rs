@@ -0,0 +1,94 @@
+//! `cuenv doctor`: how the subsystems that talk to the outside are doing
+//!
+//! Lists the retry policy of each network-facing subsystem, with the
+//! overrides of the global config file applied, and the circuit breaker
+//! state a cuenv process last recorded for it. An open circuit means the
+//! subsystem failed repeatedly and calls to it are being rejected for a while.
+
+use chrono::{DateTime, Local};
+use cuenv_core::{Error, Result};
+use cuenv_utils::resilience::policy::{
+    circuits_file, config_file, load_policies, recorded_states, RecordedCircuit,
+};
+use cuenv_utils::resilience::{Policy, Subsystem};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+pub async fn execute(format: String) -> Result<()> {
+    let config = config_file();
+    let policies = load_policies(&config)?;
+    let circuits = recorded_states(&circuits_file());
+
+    match format.as_str() {
+        "json" => {
+            let subsystems: Vec<_> = policies
+                .iter()
+                .map(|(subsystem, policy)| {
+                    json!({
+                        "subsystem": subsystem.name(),
+                        "maxRetries": policy.retry.max_retries,
+                        "baseDelayMs": policy.retry.base_delay.as_millis() as u64,
+                        "maxDelayMs": policy.retry.max_delay.as_millis() as u64,
+                        "failureThreshold": policy.circuit.failure_threshold,
+                        "breakDurationSecs": policy.circuit.break_duration.as_secs(),
+                        "circuit": circuits.get(subsystem.name()),
+                    })
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&json!({ "subsystems": subsystems })).map_err(
+                |e| Error::Json {
+                    message: "failed to serialize doctor report".to_string(),
+                    source: e,
+                },
+            )?;
+            println!("{json}");
+        }
+        "human" => {
+            println!("Retry policies ({})", config.display());
+            print_policies(&policies, &circuits);
+        }
+        other => {
+            return Err(Error::configuration(format!(
+                "Invalid format '{other}'. Must be one of: human, json"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn print_policies(
+    policies: &BTreeMap<Subsystem, Policy>,
+    circuits: &BTreeMap<String, RecordedCircuit>,
+) {
+    for (subsystem, policy) in policies {
+        let circuit = match circuits.get(subsystem.name()) {
+            Some(circuit) => format!(
+                "circuit {} since {}",
+                circuit.state,
+                format_time(circuit.changed_at)
+            ),
+            None => "circuit closed".to_string(),
+        };
+        println!(
+            "  {:<13} {} retries, {:?} to {:?} backoff, opens after {} failures for {:?}; {circuit}",
+            subsystem.name(),
+            policy.retry.max_retries,
+            policy.retry.base_delay,
+            policy.retry.max_delay,
+            policy.circuit.failure_threshold,
+            policy.circuit.break_duration,
+        );
+    }
+}
+
+fn format_time(changed_at: u64) -> String {
+    i64::try_from(changed_at)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string())
+}
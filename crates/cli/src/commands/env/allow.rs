@@ -1,6 +1,7 @@
 use crate::directory::DirectoryManager;
 use cuenv_core::{Result, ENV_CUE_FILENAME};
 use cuenv_env::EnvManager;
+use cuenv_utils::RuleAction;
 use std::{env, path::PathBuf};
 
 pub async fn execute(directory: PathBuf) -> Result<()> {
@@ -30,3 +31,47 @@ pub async fn execute(directory: PathBuf) -> Result<()> {
 
     Ok(())
 }
+
+pub fn execute_pattern(pattern: &str) -> Result<()> {
+    DirectoryManager::new().add_pattern(pattern, RuleAction::Allow)?;
+    println!("✓ Allowed pattern: {pattern}");
+    Ok(())
+}
+
+pub fn execute_list() -> Result<()> {
+    let (directories, rules) = DirectoryManager::new().list()?;
+
+    if directories.is_empty() && rules.is_empty() {
+        println!("No allowed directories or pattern rules");
+        return Ok(());
+    }
+
+    if !directories.is_empty() {
+        println!("Allowed directories:");
+        for directory in directories {
+            println!("  {directory}");
+        }
+    }
+
+    if !rules.is_empty() {
+        println!("Pattern rules:");
+        for rule in rules {
+            let action = match rule.action {
+                RuleAction::Allow => "allow",
+                RuleAction::Deny => "deny ",
+            };
+            println!("  {action} {}", rule.pattern);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute_remove(entry: &str) -> Result<()> {
+    if DirectoryManager::new().remove_entry(entry)? {
+        println!("✓ Removed: {entry}");
+    } else {
+        println!("No allowed directory or pattern rule matches '{entry}'");
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::directory::DirectoryManager;
 use cuenv_core::Result;
+use cuenv_utils::RuleAction;
 use std::{env, path::PathBuf};
 
 pub async fn execute(directory: PathBuf) -> Result<()> {
@@ -15,3 +16,9 @@ pub async fn execute(directory: PathBuf) -> Result<()> {
     println!("✓ Denied directory: {}", abs_dir.display());
     Ok(())
 }
+
+pub fn execute_pattern(pattern: &str) -> Result<()> {
+    DirectoryManager::new().add_pattern(pattern, RuleAction::Deny)?;
+    println!("✓ Denied pattern: {pattern}");
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use cuenv_config::{Config, Origin};
+use cuenv_core::{Error, Result};
+use std::collections::{BTreeMap, HashMap};
+
+/// Show where each variable, task and capability is defined
+pub fn execute(config: &Config, name: Option<&str>, format: &str) -> Result<()> {
+    let provenance = config.get_provenance();
+    let capabilities = config
+        .get_commands()
+        .values()
+        .flat_map(|command| command.capabilities.iter().flatten());
+    let sections = [
+        (
+            "variables",
+            sources(config.get_env_vars()?.keys(), &provenance.variables),
+        ),
+        (
+            "tasks",
+            sources(config.get_task_nodes().keys(), &provenance.tasks),
+        ),
+        (
+            "capabilities",
+            sources(capabilities, &provenance.capabilities),
+        ),
+    ]
+    .map(|(section, entries)| {
+        let entries: BTreeMap<String, Origin> = entries
+            .into_iter()
+            .filter(|(key, _)| name.is_none_or(|name| key == name))
+            .collect();
+        (section, entries)
+    });
+
+    if let Some(name) = name {
+        if sections.iter().all(|(_, entries)| entries.is_empty()) {
+            return Err(Error::configuration(format!(
+                "'{name}' is not a variable, task or capability of this environment"
+            )));
+        }
+    }
+
+    match format {
+        "json" => {
+            let json = serde_json::json!({
+                "profiles": provenance.profiles,
+                "environments": provenance.environments,
+                "variables": sections[0].1,
+                "tasks": sections[1].1,
+                "capabilities": sections[2].1,
+            });
+            println!("{json}");
+        }
+        "human" => {
+            if provenance.profiles.is_empty() {
+                println!("Profiles: none");
+            } else {
+                println!(
+                    "Profiles (lowest precedence first): {}",
+                    provenance.profiles.join(", ")
+                );
+            }
+            if !provenance.environments.is_empty() {
+                println!(
+                    "Environments (lowest precedence first): {}",
+                    provenance.environments.join(", ")
+                );
+            }
+            for (section, entries) in &sections {
+                if entries.is_empty() {
+                    continue;
+                }
+                let width = entries.keys().map(String::len).max().unwrap_or_default();
+                println!();
+                println!("{}{}:", section[..1].to_uppercase(), &section[1..]);
+                for (key, origin) in entries {
+                    println!("  {key:<width$}  {}", origin.describe());
+                }
+            }
+        }
+        other => {
+            return Err(Error::configuration(format!(
+                "Invalid format '{other}' for explain. Must be one of: human, json"
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Origin of every name; names without provenance come from the package
+fn sources<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    origins: &HashMap<String, Origin>,
+) -> BTreeMap<String, Origin> {
+    names
+        .into_iter()
+        .map(|name| {
+            let origin = origins.get(name).cloned().unwrap_or_default();
+            (name.clone(), origin)
+        })
+        .collect()
+}
@@ -0,0 +1,154 @@
+use crate::platform::{PlatformOps, Shell};
+use chrono::{DateTime, Local};
+use cuenv_core::{Error, Result};
+use cuenv_env::state::history::{plan_rollback, EnvHistory, EnvHistoryEntry};
+use cuenv_env::StateManager;
+use cuenv_shell::ShellType;
+use std::env;
+use std::path::PathBuf;
+
+// Import the platform-specific implementation
+#[cfg(unix)]
+use crate::platform::UnixPlatform as Platform;
+#[cfg(windows)]
+use crate::platform::WindowsPlatform as Platform;
+
+/// List the environments applied in the loaded directory, latest first
+pub fn execute_history() -> Result<()> {
+    let dir = history_dir()?;
+    let history = EnvHistory::load(&dir);
+    let entries = history.entries();
+    if entries.is_empty() {
+        println!("No environment history for {}", dir.display());
+        return Ok(());
+    }
+
+    if let Some(index) = history.first_broken() {
+        eprintln!(
+            "Warning: environment history was modified at entry #{}",
+            entries.len() - 1 - index
+        );
+    }
+
+    println!(
+        "{:>3}  {:<19}  {:<12}  CHANGES",
+        "#", "RECORDED", "ENVIRONMENT"
+    );
+    for (steps, entry) in entries.iter().rev().enumerate() {
+        let previous = history.back(steps + 1);
+        println!(
+            "{steps:>3}  {:<19}  {:<12}  {}",
+            format_time(entry.recorded_at),
+            entry.environment.as_deref().unwrap_or("-"),
+            describe_changes(previous, entry)
+        );
+    }
+    Ok(())
+}
+
+/// Print shell commands that restore the environment `steps` back
+pub fn execute_rollback(steps: usize, shell: Option<String>) -> Result<()> {
+    if steps == 0 {
+        return Err(Error::configuration(
+            "Entry #0 is the current environment; roll back at least 1 step",
+        ));
+    }
+
+    let dir = history_dir()?;
+    let history = EnvHistory::load(&dir);
+    if let Some(index) = history.first_broken() {
+        return Err(Error::configuration(format!(
+            "Environment history was modified at entry #{}; refusing to roll back",
+            history.entries().len() - 1 - index
+        )));
+    }
+    let (Some(latest), Some(target)) = (history.back(0), history.back(steps)) else {
+        return Err(Error::configuration(format!(
+            "Only {} environment(s) recorded for {}",
+            history.entries().len(),
+            dir.display()
+        )));
+    };
+
+    let current = env::vars().collect();
+    let rollback = plan_rollback(target, latest, &current);
+    let shell_impl = shell_type(shell).as_shell();
+
+    eprintln!(
+        "# cuenv: Restoring the environment recorded {}",
+        format_time(target.recorded_at)
+    );
+    for key in &rollback.unset {
+        println!("{}", shell_impl.unset(key));
+    }
+    for (key, value) in &rollback.set {
+        println!("{}", shell_impl.export(key, value));
+    }
+    for key in &rollback.unrestorable {
+        eprintln!("# cuenv: Not restoring secret {key}: only its digest is recorded");
+    }
+    Ok(())
+}
+
+/// The loaded directory, or the current one when nothing is loaded
+fn history_dir() -> Result<PathBuf> {
+    match StateManager::current_dir() {
+        Some(dir) => Ok(dir),
+        None => env::current_dir().map_err(|e| Error::file_system(".", "get current directory", e)),
+    }
+}
+
+fn shell_type(shell: Option<String>) -> ShellType {
+    match shell {
+        Some(s) => ShellType::from_name(&s),
+        None => match Platform::get_current_shell() {
+            Ok(Shell::Bash) => ShellType::Bash,
+            Ok(Shell::Zsh) => ShellType::Zsh,
+            Ok(Shell::Fish) => ShellType::Fish,
+            Ok(Shell::Pwsh) => ShellType::PowerShell,
+            Ok(Shell::Cmd) => ShellType::Cmd,
+            _ => ShellType::Bash,
+        },
+    }
+}
+
+fn format_time(recorded_at: u64) -> String {
+    i64::try_from(recorded_at)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Variables added (`+`), changed (`~`) and removed (`-`) since `previous`
+fn describe_changes(previous: Option<&EnvHistoryEntry>, entry: &EnvHistoryEntry) -> String {
+    let Some(previous) = previous else {
+        return format!("{} variables", entry.variables.len());
+    };
+
+    let changed =
+        entry
+            .variables
+            .iter()
+            .filter_map(|(key, value)| match previous.variables.get(key) {
+                None => Some(format!("+{key}")),
+                Some(old) if old != value => Some(format!("~{key}")),
+                Some(_) => None,
+            });
+    let removed = previous
+        .variables
+        .keys()
+        .filter(|key| !entry.variables.contains_key(*key))
+        .map(|key| format!("-{key}"));
+
+    let changes: Vec<_> = changed.chain(removed).collect();
+    if changes.is_empty() {
+        "environment only".to_string()
+    } else {
+        changes.join(" ")
+    }
+}
@@ -0,0 +1,61 @@
+use cuenv_core::{Error, Result, CUENV_IDLE_MAINTENANCE_VAR};
+use cuenv_utils::hook_latency::{HookLatencyLog, HookLatencySummary, SLOW_HOOK_THRESHOLD};
+use std::time::Duration;
+
+/// Report shell hook latency over the last `last` invocations
+pub fn execute(last: usize, format: &str) -> Result<()> {
+    let summary = HookLatencySummary::of(&HookLatencyLog::for_user().load(), last);
+
+    match format {
+        "json" => {
+            let json = match &summary {
+                Some(summary) => serde_json::json!({
+                    "count": summary.count,
+                    "p50_us": summary.p50.as_micros() as u64,
+                    "p95_us": summary.p95.as_micros() as u64,
+                    "max_us": summary.max.as_micros() as u64,
+                    "slowest_directory": summary.slowest_directory,
+                    "slow": summary.is_slow(),
+                }),
+                None => serde_json::json!({}),
+            };
+            println!("{json}");
+        }
+        "human" => match summary {
+            Some(summary) => print_human(&summary),
+            None => println!("No shell hook invocations recorded yet"),
+        },
+        other => {
+            return Err(Error::configuration(format!(
+                "Invalid format '{other}' for --hook-latency. Must be one of: human, json"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn print_human(summary: &HookLatencySummary) {
+    println!("Shell hook latency (last {} invocations)", summary.count);
+    println!("  p50: {:.1}ms", millis(summary.p50));
+    println!("  p95: {:.1}ms", millis(summary.p95));
+    println!("  max: {:.1}ms", millis(summary.max));
+    if let Some(dir) = &summary.slowest_directory {
+        println!("  slowest directory: {}", dir.display());
+    }
+
+    if summary.is_slow() {
+        println!();
+        println!(
+            "⚠ The hook's p95 is above {}ms, so prompts feel sluggish. To speed it up:",
+            SLOW_HOOK_THRESHOLD.as_millis()
+        );
+        println!("  - move slow commands into onEnter hooks; they run in the background");
+        println!(
+            "  - set {CUENV_IDLE_MAINTENANCE_VAR}=1 so cache upkeep happens while the machine is idle"
+        );
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,95 @@
+use cuenv_config::{CueParser, ParseOptions};
+use cuenv_core::{Error, Result, CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME};
+use cuenv_env::deprecation::{deprecated_variables, find_references};
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// Files larger than this are not scanned
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Directories that never contain project sources
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor"];
+
+pub async fn execute(directory: PathBuf) -> Result<()> {
+    let directory = directory
+        .canonicalize()
+        .map_err(|e| Error::file_system(&directory, "resolve directory", e))?;
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+
+    let result =
+        CueParser::eval_package_with_options(&directory, &package_name, &ParseOptions::default())?;
+    let variables = deprecated_variables(&result.metadata);
+
+    if variables.is_empty() {
+        println!("No deprecated or renamed variables declared");
+        return Ok(());
+    }
+
+    let mut findings = 0;
+    for entry in WalkDir::new(&directory)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_skipped(entry))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let Some(text) = read_text(entry.path()) else {
+            continue;
+        };
+        let is_cue = entry.path().extension().is_some_and(|ext| ext == "cue");
+
+        for reference in find_references(&text, &variables) {
+            let line = text.lines().nth(reference.line - 1).unwrap_or_default();
+            // The declaration of the deprecated variable itself is not a usage
+            if is_cue && is_declaration(line, &reference.variable.name) {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .strip_prefix(&directory)
+                .unwrap_or(entry.path());
+            println!(
+                "{}:{}: {}",
+                path.display(),
+                reference.line,
+                reference.variable.message
+            );
+            findings += 1;
+        }
+    }
+
+    if findings == 0 {
+        println!(
+            "✓ No usages of {} deprecated variable(s) found",
+            variables.len()
+        );
+        return Ok(());
+    }
+
+    eprintln!("\n{findings} usage(s) of deprecated variables found");
+    std::process::exit(1);
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.file_type().is_dir() && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let metadata = path.metadata().ok()?;
+    if metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+fn is_declaration(line: &str, name: &str) -> bool {
+    let trimmed = line.trim_start();
+    let label = trimmed
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_prefix(name))
+        .and_then(|rest| rest.strip_prefix('"'))
+        .or_else(|| trimmed.strip_prefix(name));
+    label.is_some_and(|rest| rest.trim_start().starts_with(':'))
+}
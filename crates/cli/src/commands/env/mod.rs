@@ -1,11 +1,21 @@
 use clap::Subcommand;
+use cuenv_config::Config;
 use std::path::PathBuf;
 
 mod allow;
 mod deny;
+mod explain;
 mod export;
+mod history;
+mod hook_latency;
+mod lint;
+pub(super) mod print;
+mod providers;
 mod prune;
 mod status;
+mod switch;
+
+pub use prune::{on_prompt as prune_on_prompt, stop_compose_services_blocking};
 
 #[derive(Subcommand)]
 pub enum EnvCommands {
@@ -13,12 +23,28 @@ pub enum EnvCommands {
     Allow {
         #[arg(default_value = ".")]
         directory: PathBuf,
+
+        /// Allow every directory matching a glob pattern (e.g. `~/work/**`)
+        #[arg(long, value_name = "GLOB", conflicts_with_all = ["list", "rm"])]
+        pattern: Option<String>,
+
+        /// List allowed directories and pattern rules
+        #[arg(long, conflicts_with = "rm")]
+        list: bool,
+
+        /// Remove an allowed directory or pattern rule
+        #[arg(long, value_name = "ENTRY")]
+        rm: Option<String>,
     },
 
     /// Deny cuenv from loading environments in a directory
     Deny {
         #[arg(default_value = ".")]
         directory: PathBuf,
+
+        /// Deny every directory matching a glob pattern, overriding allow rules
+        #[arg(long, value_name = "GLOB")]
+        pattern: Option<String>,
     },
 
     /// Display current environment status and changes
@@ -34,6 +60,62 @@ pub enum EnvCommands {
         /// Show verbose output (for starship format)
         #[arg(short, long)]
         verbose: bool,
+
+        /// Show how long the shell hook takes (p50/p95) instead
+        #[arg(long)]
+        hook_latency: bool,
+
+        /// Number of recent hook invocations to summarize
+        #[arg(long, default_value = "50", requires = "hook_latency")]
+        last: usize,
+    },
+
+    /// Switch the named environment of the current shell, from the next prompt
+    Use {
+        /// Environment to use, e.g. staging
+        #[arg(conflicts_with_all = ["list", "clear"])]
+        name: Option<String>,
+
+        /// List the environments and the one in use
+        #[arg(long)]
+        list: bool,
+
+        /// Go back to the default environment
+        #[arg(long, conflicts_with = "list")]
+        clear: bool,
+    },
+
+    /// Show which profile or file defines each variable, task and capability
+    Explain {
+        /// Only explain this variable, task or capability
+        name: Option<String>,
+
+        /// Output format (default: human, options: human, json)
+        #[arg(short, long, default_value = "human")]
+        format: String,
+    },
+
+    /// Print the variables of the environment with their origin, optionally as of a git revision
+    Print {
+        /// Evaluate env.cue as it was at this tag, branch or commit
+        #[arg(long, value_name = "REV")]
+        as_of: Option<String>,
+
+        /// Output format (default: human, options: human, table, json)
+        #[arg(short, long, default_value = "human")]
+        format: String,
+
+        /// Only print variables whose name matches this glob, e.g. 'AWS_*' (can be repeated)
+        #[arg(long = "filter", value_name = "GLOB")]
+        filters: Vec<String>,
+
+        /// Only print variables whose value the environment changed
+        #[arg(long)]
+        only_changed: bool,
+
+        /// Include variables inherited from the parent environment
+        #[arg(long)]
+        all: bool,
     },
 
     /// Export environment variables for the current directory
@@ -47,22 +129,102 @@ pub enum EnvCommands {
         all: bool,
     },
 
+    /// Show the environments recently applied in this directory
+    History,
+
+    /// Print shell commands that restore an earlier environment
+    Rollback {
+        /// How many environments back, 1 being the one before the current
+        steps: usize,
+
+        /// Shell format (defaults to current shell)
+        #[arg(short, long)]
+        shell: Option<String>,
+    },
+
     /// Prune stale environment state
-    Prune,
+    Prune {
+        /// List what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Age after which state of a directory not visited is stale (default: 7d)
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+    },
+
+    /// List external secret providers discovered on PATH
+    Providers,
+
+    /// Report usages of deprecated or renamed variables in project files
+    Lint {
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+    },
 }
 
 impl EnvCommands {
-    pub async fn execute(self) -> cuenv_core::Result<()> {
+    pub async fn execute(self, config: &Config) -> cuenv_core::Result<()> {
         match self {
-            EnvCommands::Allow { directory } => allow::execute(directory).await,
-            EnvCommands::Deny { directory } => deny::execute(directory).await,
+            EnvCommands::Allow {
+                directory,
+                pattern,
+                list,
+                rm,
+            } => match (pattern, list, rm) {
+                (Some(pattern), _, _) => allow::execute_pattern(&pattern),
+                (None, true, _) => allow::execute_list(),
+                (None, false, Some(entry)) => allow::execute_remove(&entry),
+                (None, false, None) => allow::execute(directory).await,
+            },
+            EnvCommands::Deny { directory, pattern } => match pattern {
+                Some(pattern) => deny::execute_pattern(&pattern),
+                None => deny::execute(directory).await,
+            },
+            EnvCommands::Status {
+                hook_latency: true,
+                format,
+                last,
+                ..
+            } => hook_latency::execute(last, &format),
             EnvCommands::Status {
                 hooks,
                 format,
                 verbose,
+                ..
             } => status::execute(hooks, format, verbose).await,
+            EnvCommands::Use { name, clear, .. } if name.is_some() || clear => {
+                switch::execute(config, name.as_deref(), clear)
+            }
+            EnvCommands::Use { .. } => switch::execute_list(config),
+            EnvCommands::Explain { name, format } => {
+                explain::execute(config, name.as_deref(), &format)
+            }
+            EnvCommands::Print {
+                as_of,
+                format,
+                filters,
+                only_changed,
+                all,
+            } => {
+                let options = print::PrintOptions {
+                    as_of,
+                    format,
+                    filters,
+                    only_changed,
+                    all,
+                };
+                print::execute(config, options).await
+            }
             EnvCommands::Export { shell, all } => export::execute(shell, all).await,
-            EnvCommands::Prune => prune::execute().await,
+            EnvCommands::History => history::execute_history(),
+            EnvCommands::Rollback { steps, shell } => history::execute_rollback(steps, shell),
+            EnvCommands::Prune {
+                dry_run,
+                older_than,
+            } => prune::execute(dry_run, older_than.as_deref()).await,
+            EnvCommands::Providers => providers::execute().await,
+            EnvCommands::Lint { directory } => lint::execute(directory).await,
         }
     }
 }
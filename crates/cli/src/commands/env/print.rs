@@ -0,0 +1,251 @@
+//! `cuenv env print`: the variables of the environment and where they
+//! come from
+//!
+//! Each variable has an origin: `cue` for values env.cue sets, `secret` for
+//! secrets, whose values are masked, `hook` for values `source` hooks
+//! contributed to the loaded environment, and `inherited` for the rest of
+//! the process environment, listed with `--all`.
+
+use cuenv_config::{Config, ConfigLoader};
+use cuenv_core::redaction::{redact, MASK};
+use cuenv_core::{Error, Result};
+use cuenv_env::manager::secrets::is_secret_reference;
+use cuenv_env::StateManager;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// What to print and how
+pub struct PrintOptions {
+    pub as_of: Option<String>,
+    pub format: String,
+    /// Glob patterns of the names to print, all when empty
+    pub filters: Vec<String>,
+    /// Only variables whose value the environment changed
+    pub only_changed: bool,
+    /// Include variables inherited from the parent environment
+    pub all: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum VarOrigin {
+    Cue,
+    Secret,
+    Hook,
+    Inherited,
+}
+
+impl VarOrigin {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Cue => "cue",
+            Self::Secret => "secret",
+            Self::Hook => "hook",
+            Self::Inherited => "inherited",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    name: String,
+    value: String,
+    origin: VarOrigin,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capability: Option<String>,
+    /// Whether the value differs from the one before the environment
+    changed: bool,
+}
+
+/// Print the environment's variables, evaluated at `as_of` when given
+pub async fn execute(config: &Config, options: PrintOptions) -> Result<()> {
+    let format = options.format.as_str();
+    if !["human", "table", "json"].contains(&format) {
+        return Err(Error::configuration(format!(
+            "Invalid format '{format}' for print. Must be one of: human, table, json"
+        )));
+    }
+    let filter = name_filter(&options.filters)?;
+
+    let (variables, evaluated) = match &options.as_of {
+        Some(rev) => {
+            let evaluated = ConfigLoader::new()
+                .directory(config.working_dir.clone())
+                .runtime(config.runtime.clone())
+                .as_of(rev.clone())
+                .load()
+                .await?;
+            (evaluated.get_env_vars()?, Some(evaluated))
+        }
+        None => (config.get_env_vars()?, None),
+    };
+    let config = evaluated.as_ref().unwrap_or(config);
+
+    // The loaded environment of this directory knows what hooks added and
+    // what the variables were before; otherwise that is the process's
+    let loaded = StateManager::current_dir()
+        .filter(|dir| options.as_of.is_none() && *dir == config.working_dir)
+        .and_then(|_| StateManager::get_diff().ok().flatten());
+    let process: HashMap<String, String> = std::env::vars().collect();
+    let before = loaded.as_ref().map_or(&process, |diff| &diff.prev);
+
+    let mut rows: BTreeMap<String, Row> = BTreeMap::new();
+    for (name, value) in &variables {
+        let metadata = config.get_metadata(name);
+        let secret = is_secret_reference(value) || metadata.is_some_and(|meta| meta.lazy);
+        rows.insert(
+            name.clone(),
+            Row {
+                name: name.clone(),
+                value: value.clone(),
+                origin: if secret {
+                    VarOrigin::Secret
+                } else {
+                    VarOrigin::Cue
+                },
+                capability: metadata.and_then(|meta| meta.capability.clone()),
+                changed: before.get(name) != Some(value),
+            },
+        );
+    }
+    if let Some(diff) = &loaded {
+        for (name, value) in diff.added_or_changed() {
+            rows.entry(name.to_string()).or_insert_with(|| Row {
+                name: name.to_string(),
+                value: value.to_string(),
+                origin: VarOrigin::Hook,
+                capability: None,
+                changed: true,
+            });
+        }
+    }
+    if options.all {
+        for (name, value) in &process {
+            rows.entry(name.clone()).or_insert_with(|| Row {
+                name: name.clone(),
+                value: value.clone(),
+                origin: VarOrigin::Inherited,
+                capability: None,
+                changed: false,
+            });
+        }
+    }
+
+    let rows: Vec<Row> = rows
+        .into_values()
+        .filter(|row| filter.as_ref().is_none_or(|set| set.is_match(&row.name)))
+        .filter(|row| !options.only_changed || row.changed)
+        .map(|mut row| {
+            row.value = if row.origin == VarOrigin::Secret {
+                MASK.to_string()
+            } else {
+                redact(&row.value).into_owned()
+            };
+            row
+        })
+        .collect();
+
+    match format {
+        "json" => println!("{}", serde_json::json!(rows)),
+        "table" => print!("{}", table(&rows)),
+        _ => rows
+            .iter()
+            .for_each(|row| println!("{}={}", row.name, row.value)),
+    }
+    Ok(())
+}
+
+pub(crate) fn name_filter(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| Error::configuration(format!("Invalid filter '{pattern}': {e}")))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| Error::configuration(format!("Invalid filter: {e}")))
+}
+
+/// Rows as aligned columns under a header
+fn table(rows: &[Row]) -> String {
+    let header = ["NAME", "VALUE", "ORIGIN", "CAPABILITY"];
+    let cells: Vec<[&str; 4]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.name.as_str(),
+                row.value.as_str(),
+                row.origin.as_str(),
+                row.capability.as_deref().unwrap_or("-"),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            cells
+                .iter()
+                .map(|cells| cells[column].chars().count())
+                .chain([header[column].len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut table = String::new();
+    for line in std::iter::once(header).chain(cells) {
+        let columns: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        table.push_str(columns.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let rows = [
+            Row {
+                name: "AWS_REGION".to_string(),
+                value: "eu-west-1".to_string(),
+                origin: VarOrigin::Cue,
+                capability: Some("aws".to_string()),
+                changed: true,
+            },
+            Row {
+                name: "DB".to_string(),
+                value: MASK.to_string(),
+                origin: VarOrigin::Secret,
+                capability: None,
+                changed: true,
+            },
+        ];
+        assert_eq!(
+            table(&rows),
+            "NAME        VALUE      ORIGIN  CAPABILITY\n\
+             AWS_REGION  eu-west-1  cue     aws\n\
+             DB          ***        secret  -\n"
+        );
+    }
+
+    #[test]
+    fn test_name_filter() {
+        let filter = name_filter(&["AWS_*".to_string()]).unwrap().unwrap();
+        assert!(filter.is_match("AWS_REGION"));
+        assert!(!filter.is_match("GCP_PROJECT"));
+        assert!(name_filter(&[]).unwrap().is_none());
+        assert!(name_filter(&["[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use cuenv_core::constants::SECRET_PROVIDER_EXECUTABLE_PREFIX;
+use cuenv_core::Result;
+use cuenv_env::manager::secrets::{discover_providers, PROTOCOL_VERSION};
+
+pub async fn execute() -> Result<()> {
+    let providers = discover_providers();
+    if providers.is_empty() {
+        println!(
+            "No secret providers found on PATH (looking for {SECRET_PROVIDER_EXECUTABLE_PREFIX}<name> executables)"
+        );
+        return Ok(());
+    }
+
+    println!("Secret providers (protocol v{PROTOCOL_VERSION}):");
+    for name in providers {
+        println!("  {name}  → cuenv-provider://{name}/<reference>");
+    }
+    Ok(())
+}
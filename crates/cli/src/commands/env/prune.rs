@@ -1,8 +1,11 @@
 use crate::platform::{PlatformOps, Shell};
-use cuenv_core::Result;
+use cuenv_core::{Result, CUENV_AUTO_PRUNE_VAR};
+use cuenv_env::manager::environment::compose;
 use cuenv_env::StateManager;
 use cuenv_shell::ShellType;
+use cuenv_utils::state_prune::{PrunePolicy, StaleState, StatePruner, DEFAULT_AUTO_PRUNE_INTERVAL};
 use std::env;
+use std::time::Duration;
 
 // Import the platform-specific implementation
 #[cfg(unix)]
@@ -10,7 +13,24 @@ use crate::platform::UnixPlatform as Platform;
 #[cfg(windows)]
 use crate::platform::WindowsPlatform as Platform;
 
-pub async fn execute() -> Result<()> {
+pub async fn execute(dry_run: bool, older_than: Option<&str>) -> Result<()> {
+    let mut policy = PrunePolicy::default();
+    if let Some(older_than) = older_than {
+        policy.max_age = Some(cuenv_utils::parse_duration(older_than)?);
+    }
+    let pruner = StatePruner::for_user(policy);
+    let stale = StateManager::stale_state(&pruner);
+
+    if dry_run {
+        if stale.is_empty() {
+            println!("No stale state found");
+        }
+        for state in &stale {
+            println!("Would remove {state}");
+        }
+        return Ok(());
+    }
+
     // Get the diff before unloading to generate cleanup shell commands
     if let Ok(Some(diff)) = StateManager::get_diff() {
         // Detect shell type
@@ -47,6 +67,93 @@ pub async fn execute() -> Result<()> {
 
     // Unload any stale state
     StateManager::unload().await?;
-    eprintln!("✓ Pruned stale environment state");
+
+    stop_compose_services(&stale).await;
+    let removed = pruner.prune(&stale)?;
+    for state in &stale {
+        tracing::debug!("Pruned {state}");
+    }
+    eprintln!("✓ Pruned stale environment state ({removed} stale item(s) removed)");
     Ok(())
 }
+
+/// Called by the shell hook on every prompt: prunes stale state once per
+/// interval, which `CUENV_AUTO_PRUNE` sets (e.g. `12h`) or turns off
+///
+/// Failures are logged only, so the prompt is never held up.
+pub async fn on_prompt() {
+    let Some(interval) = interval_from_var(env::var(CUENV_AUTO_PRUNE_VAR).ok().as_deref()) else {
+        return;
+    };
+
+    let pruner = StatePruner::for_user(PrunePolicy::default());
+    let result = match pruner.claim_auto_prune(interval) {
+        Ok(true) => {
+            let stale = StateManager::stale_state(&pruner);
+            stop_compose_services(&stale).await;
+            pruner.prune(&stale)
+        }
+        Ok(false) => Ok(0),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(0) => {}
+        Ok(removed) => tracing::debug!("Pruned {removed} stale state item(s)"),
+        Err(e) => tracing::debug!("Automatic state pruning failed: {e}"),
+    }
+}
+
+/// Tear down compose services recorded in stale state before it is removed
+async fn stop_compose_services(stale: &[StaleState]) {
+    for state in stale.iter().filter(|state| state.path.is_dir()) {
+        stop_state_compose_services(state).await;
+    }
+}
+
+/// [`stop_compose_services`] for one state directory, from a blocking
+/// maintenance run
+pub fn stop_compose_services_blocking(state: &StaleState) {
+    tokio::runtime::Handle::current().block_on(stop_state_compose_services(state));
+}
+
+async fn stop_state_compose_services(state: &StaleState) {
+    if let Err(e) = compose::teardown(&state.path, true).await {
+        tracing::warn!(
+            "Failed to stop compose services of {}: {e}",
+            state.path.display()
+        );
+    }
+}
+
+fn interval_from_var(value: Option<&str>) -> Option<Duration> {
+    let Some(value) = value else {
+        return Some(DEFAULT_AUTO_PRUNE_INTERVAL);
+    };
+    match value.trim().to_ascii_lowercase().as_str() {
+        "0" | "false" | "off" | "no" => None,
+        "" | "1" | "true" | "on" | "yes" => Some(DEFAULT_AUTO_PRUNE_INTERVAL),
+        interval => match cuenv_utils::parse_duration(interval) {
+            Ok(interval) => Some(interval),
+            Err(e) => {
+                tracing::debug!("Ignoring {CUENV_AUTO_PRUNE_VAR}: {e}");
+                None
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_from_var() {
+        assert_eq!(interval_from_var(None), Some(DEFAULT_AUTO_PRUNE_INTERVAL));
+        assert_eq!(interval_from_var(Some("off")), None);
+        assert_eq!(interval_from_var(Some("bogus")), None);
+        assert_eq!(
+            interval_from_var(Some("12h")),
+            Some(Duration::from_secs(12 * 60 * 60))
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use cuenv_core::Result;
+use cuenv_env::manager::environment::compose::{self, ServiceStatus};
 use cuenv_env::EnvManager;
 use cuenv_utils::hooks_status::{
     calculate_elapsed, should_show_completed_status, HookState, HooksStatusManager,
@@ -45,6 +46,18 @@ pub async fn execute(hooks: bool, format: String, verbose: bool) -> Result<()> {
                 println!(); // Add spacing
             }
 
+            match compose::status(&current_dir).await {
+                Ok(services) if services.is_empty() => {}
+                Ok(services) => {
+                    format_compose_output(&services);
+                    println!();
+                }
+                Err(e) => {
+                    println!("Compose services unavailable: {e}");
+                    println!();
+                }
+            }
+
             // Also show environment diff unless hooks flag is set
             if !hooks {
                 println!("Environment Status");
@@ -116,6 +129,19 @@ fn format_starship_output(status: &cuenv_utils::hooks_status::HooksStatus, verbo
     }
 }
 
+fn format_compose_output(services: &[ServiceStatus]) {
+    println!("Compose Services");
+    println!("================");
+    for service in services {
+        let state = if service.running {
+            "running"
+        } else {
+            "stopped"
+        };
+        println!("  - {} ({state})", service.name);
+    }
+}
+
 fn format_json_output(status: &cuenv_utils::hooks_status::HooksStatus) {
     // Output raw JSON for machine consumption
     if let Ok(json) = serde_json::to_string_pretty(status) {
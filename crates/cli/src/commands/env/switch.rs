@@ -0,0 +1,65 @@
+//! `cuenv env use`: choose the named environment of the current shell
+//!
+//! The choice applies from the next prompt, when the shell hook reloads the
+//! environment with it, and lasts until the shell exits or another is chosen.
+
+use cuenv_config::Config;
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_core::{Error, Result};
+use cuenv_env::state::session::ShellSession;
+
+/// Choose `name` for the shell, or the default environment with `clear`
+pub fn execute(config: &Config, name: Option<&str>, clear: bool) -> Result<()> {
+    let session = ShellSession::current().ok_or_else(|| {
+        Error::configuration(
+            "This shell has no cuenv session; install the shell hook with \
+             `cuenv shell init <shell>`, or pass `-e <environment>` to a single command",
+        )
+    })?;
+
+    let Some(name) = name.filter(|_| !clear) else {
+        session.use_environment(None)?;
+        println!("Using the default environment in this shell from the next prompt");
+        return Ok(());
+    };
+    let environments = config.get_environments();
+    if !environments.iter().any(|environment| environment == name) {
+        let message = if environments.is_empty() {
+            format!("Unknown environment '{name}': env.cue declares no environments")
+        } else {
+            with_suggestions(
+                format!("Unknown environment '{name}'"),
+                &similar_names(name, &environments),
+            )
+        };
+        return Err(Error::configuration(message));
+    }
+    session.use_environment(Some(name))?;
+    println!("Using environment '{name}' in this shell from the next prompt");
+    Ok(())
+}
+
+/// List the environments env.cue declares, marking the one in use
+pub fn execute_list(config: &Config) -> Result<()> {
+    let active = ShellSession::current().and_then(|session| session.environment());
+    let environments = config.get_environments();
+    if environments.is_empty() {
+        println!("env.cue declares no environments");
+    }
+    for environment in &environments {
+        let marker = if active.as_deref() == Some(environment) {
+            '*'
+        } else {
+            ' '
+        };
+        println!("{marker} {environment}");
+    }
+    match active {
+        Some(name) if !environments.contains(&name) => {
+            println!("In use: '{name}', which env.cue does not declare");
+        }
+        Some(_) => {}
+        None => println!("In use: the default environment"),
+    }
+    Ok(())
+}
@@ -1,17 +1,21 @@
 use cuenv_config::Config;
 use cuenv_core::{Result, CUENV_CAPABILITIES_VAR, CUENV_ENV_VAR};
+use cuenv_env::deprecation::command_references;
 use cuenv_env::manager::environment::SupervisorMode;
 use cuenv_env::EnvManager;
+use cuenv_security::AccessRestrictions;
 use std::env;
+use std::path::Path;
 use std::sync::Arc;
 
 pub async fn execute(
-    _config: Arc<Config>,
+    config: Arc<Config>,
     environment: Option<String>,
     capabilities: Vec<String>,
     command: String,
     args: Vec<String>,
-    _audit: bool,
+    audit: bool,
+    restrict: bool,
 ) -> Result<()> {
     let current_dir = env::current_dir()
         .map_err(|e| cuenv_core::Error::file_system(".", "get current directory", e))?;
@@ -40,9 +44,63 @@ pub async fn execute(
         )
         .await?;
 
-    // Execute the command in the prepared environment
-    // Use run_command_with_current_env to include variables set by preload hooks
-    let exit_code = env_manager.run_command_with_current_env(&command, &args)?;
+    // Warn about deprecated variables the command visibly relies on
+    let deprecated = env_manager.deprecated_variables();
+    for variable in command_references(&command, &args, &deprecated) {
+        eprintln!("⚠ {}", variable.message);
+    }
+
+    if audit {
+        let (exit_code, report) = env_manager.audit_command(&command, &args)?;
+        report.print_summary();
+        println!("\n📝 Suggested configuration:\n");
+        println!("{}", report.suggested_capability_block(&command));
+        std::process::exit(exit_code);
+    }
+
+    let exit_code = if restrict {
+        let restrictions = restrictions_for_command(&config, &current_dir, &command);
+        env_manager.run_command_with_restrictions(&command, &args, &restrictions)?
+    } else {
+        // Execute the command in the prepared environment
+        // Use run_command_with_current_env to include variables set by preload hooks
+        env_manager.run_command_with_current_env(&command, &args)?
+    };
 
     std::process::exit(exit_code);
 }
+
+/// Build sandbox restrictions for an ad-hoc command
+///
+/// Global security defaults are combined with the security settings of the
+/// capabilities that list the command. Disk and network access are
+/// restricted unless the merged configuration explicitly turns them off.
+fn restrictions_for_command(
+    config: &Config,
+    current_dir: &Path,
+    command: &str,
+) -> AccessRestrictions {
+    let mut security = config.security.clone();
+    let command_name = Path::new(command)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| command.to_string());
+    if let Some(command_security) = config
+        .get_commands()
+        .get(&command_name)
+        .and_then(|command| command.security.as_ref())
+    {
+        security.merge(command_security);
+    }
+
+    let mut restrictions = AccessRestrictions::from_security_config(&security);
+    restrictions.restrict_disk = security.restrict_disk.unwrap_or(true);
+    restrictions.restrict_network = security.restrict_network.unwrap_or(true);
+
+    if restrictions.restrict_disk {
+        restrictions.allow_system_paths();
+        restrictions.add_read_only_path(current_dir);
+    }
+
+    restrictions
+}
@@ -0,0 +1,40 @@
+use clap::Subcommand;
+use cuenv_core::{Error, Result};
+use cuenv_env::manager::environment::refresh_hook_cache;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum HooksCommands {
+    /// Discard the cached hook environment so hooks run again on next load
+    Refresh {
+        /// Directory whose hook cache to discard (defaults to current directory)
+        directory: Option<PathBuf>,
+    },
+}
+
+impl HooksCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            HooksCommands::Refresh { directory } => {
+                let directory = match directory {
+                    Some(dir) => dir,
+                    None => std::env::current_dir()
+                        .map_err(|e| Error::file_system(".", "get current directory", e))?,
+                };
+                let directory = directory
+                    .canonicalize()
+                    .map_err(|e| Error::file_system(&directory, "resolve directory", e))?;
+
+                let removed = refresh_hook_cache(&directory)?;
+                if removed == 0 {
+                    println!("No cached hook environment for {}", directory.display());
+                } else {
+                    println!(
+                        "✓ Discarded {removed} cached hook environment(s); hooks will run on next load"
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! `cuenv internal ffi-check`: exercise the Go bridge and report what breaks
+//!
+//! Each check evaluates a throwaway package through the same entry point as
+//! real configurations, so a failure here points at the bridge rather than
+//! at a project's CUE.
+
+use cuenv_config::{bridge_info, CueParser, ParseOptions, ParseResult, BRIDGE_ABI_VERSION};
+use cuenv_core::{Error, Result, DEFAULT_PACKAGE_NAME, ENV_CUE_FILENAME};
+use std::time::Instant;
+
+/// Variables in the large-output check
+const LARGE_VARIABLE_COUNT: usize = 5_000;
+/// Size of the single large value in the large-output check
+const LARGE_VALUE_BYTES: usize = 256 * 1024;
+/// Resident memory growth over the repeated calls treated as a leak
+const LEAK_THRESHOLD_KB: u64 = 64 * 1024;
+
+const UNICODE_VALUE: &str = "héllo wörld – 日本語 – 🚀";
+
+/// A named check, returning what it verified
+type Check = (&'static str, Box<dyn Fn() -> Result<String>>);
+
+pub fn execute(iterations: usize, threads: usize) -> Result<()> {
+    println!("Bridge");
+    match bridge_info() {
+        Ok(info) => {
+            println!(
+                "  ABI version: {} (this build expects {BRIDGE_ABI_VERSION})",
+                info.abi_version
+            );
+            println!("  Go:          {}", info.go_version);
+            println!("  CUE:         {}", info.cue_version);
+            println!("  Platform:    {}/{}", info.os, info.arch);
+            if !info.is_compatible() {
+                println!("  ✗ ABI mismatch: rebuild the bridge with `cargo clean -p cuenv-libcue-ffi-bridge && cargo build`");
+            }
+        }
+        Err(e) => println!("  ✗ Could not read bridge info: {e}"),
+    }
+
+    println!("\nChecks");
+    let checks: [Check; 5] = [
+        ("basic evaluation", Box::new(check_basic)),
+        ("unicode round trip", Box::new(check_unicode)),
+        ("large output", Box::new(check_large)),
+        (
+            "concurrent calls",
+            Box::new(move || check_concurrent(threads)),
+        ),
+        ("repeated calls", Box::new(move || check_memory(iterations))),
+    ];
+    let failed = checks
+        .iter()
+        .filter(|(name, check)| {
+            let started = Instant::now();
+            let outcome = check();
+            let elapsed = started.elapsed().as_millis();
+            match &outcome {
+                Ok(detail) => println!("  ✓ {name} ({elapsed}ms): {detail}"),
+                Err(e) => println!("  ✗ {name} ({elapsed}ms): {e}"),
+            }
+            outcome.is_err()
+        })
+        .count();
+
+    if failed > 0 {
+        return Err(Error::ffi(
+            "ffi-check",
+            format!("{failed} of {} checks failed", checks.len()),
+        ));
+    }
+    println!("\n✓ The CUE bridge is healthy");
+    Ok(())
+}
+
+/// Evaluate `source` as the env.cue of a throwaway package
+fn evaluate(source: &str) -> Result<ParseResult> {
+    let dir = tempfile::tempdir()
+        .map_err(|e| Error::configuration(format!("Failed to create temp directory: {e}")))?;
+    let env_file = dir.path().join(ENV_CUE_FILENAME);
+    std::fs::write(&env_file, source)
+        .map_err(|e| Error::file_system(&env_file, "write check package", e))?;
+    CueParser::eval_package_with_options(dir.path(), DEFAULT_PACKAGE_NAME, &ParseOptions::default())
+}
+
+fn expect_variable(result: &ParseResult, name: &str, expected: &str) -> Result<()> {
+    match result.variables.get(name) {
+        Some(value) if value == expected => Ok(()),
+        Some(value) => Err(Error::ffi(
+            "ffi-check",
+            format!("{name} came back as {value:?}, expected {expected:?}"),
+        )),
+        None => Err(Error::ffi("ffi-check", format!("{name} is missing"))),
+    }
+}
+
+fn basic_source() -> String {
+    format!("package {DEFAULT_PACKAGE_NAME}\n\nenv: {{\n\tGREETING: \"hello\"\n\tPORT: 8080\n}}\n")
+}
+
+fn check_basic() -> Result<String> {
+    let result = evaluate(&basic_source())?;
+    expect_variable(&result, "GREETING", "hello")?;
+    expect_variable(&result, "PORT", "8080")?;
+    Ok("2 variables".to_string())
+}
+
+fn check_unicode() -> Result<String> {
+    let source = format!(
+        "package {DEFAULT_PACKAGE_NAME}\n\n// {UNICODE_VALUE}\nenv: {{\n\tUNICODE: \"{UNICODE_VALUE}\"\n}}\n"
+    );
+    let result = evaluate(&source)?;
+    expect_variable(&result, "UNICODE", UNICODE_VALUE)?;
+    Ok(format!("{} bytes", UNICODE_VALUE.len()))
+}
+
+fn check_large() -> Result<String> {
+    let large_value = "x".repeat(LARGE_VALUE_BYTES);
+    let variables: String = (0..LARGE_VARIABLE_COUNT)
+        .map(|i| format!("\tVAR_{i}: \"value-{i}\"\n"))
+        .collect();
+    let source = format!(
+        "package {DEFAULT_PACKAGE_NAME}\n\nenv: {{\n{variables}\tLARGE: \"{large_value}\"\n}}\n"
+    );
+    let result = evaluate(&source)?;
+    if result.variables.len() != LARGE_VARIABLE_COUNT + 1 {
+        return Err(Error::ffi(
+            "ffi-check",
+            format!(
+                "{} variables came back, expected {}",
+                result.variables.len(),
+                LARGE_VARIABLE_COUNT + 1
+            ),
+        ));
+    }
+    expect_variable(&result, "LARGE", &large_value)?;
+    Ok(format!(
+        "{LARGE_VARIABLE_COUNT} variables and a {} KiB value",
+        LARGE_VALUE_BYTES / 1024
+    ))
+}
+
+fn check_concurrent(threads: usize) -> Result<String> {
+    let source = basic_source();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| scope.spawn(|| evaluate(&source)))
+            .collect();
+        handles.into_iter().try_for_each(|handle| {
+            let result = handle
+                .join()
+                .map_err(|_| Error::ffi("ffi-check", "a bridge call panicked"))??;
+            expect_variable(&result, "GREETING", "hello")
+        })
+    })?;
+    Ok(format!("{threads} threads"))
+}
+
+fn check_memory(iterations: usize) -> Result<String> {
+    let source = basic_source();
+    // Let the Go runtime reach its steady-state heap first
+    for _ in 0..10 {
+        evaluate(&source)?;
+    }
+    let before = resident_memory_kb();
+    for _ in 0..iterations {
+        evaluate(&source)?;
+    }
+    match (before, resident_memory_kb()) {
+        (Some(before), Some(after)) => memory_verdict(before, after, iterations),
+        _ => Ok(format!(
+            "{iterations} calls, resident memory is not measurable on this platform"
+        )),
+    }
+}
+
+/// Judge resident memory growth over `iterations` calls
+fn memory_verdict(before_kb: u64, after_kb: u64, iterations: usize) -> Result<String> {
+    let growth = after_kb.saturating_sub(before_kb);
+    let summary = format!("resident memory grew by {growth} KiB over {iterations} calls");
+    if growth > LEAK_THRESHOLD_KB {
+        return Err(Error::ffi(
+            "ffi-check",
+            format!("{summary}, the bridge is likely leaking"),
+        ));
+    }
+    Ok(summary)
+}
+
+/// Resident set size of this process, where the platform reports it
+fn resident_memory_kb() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_verdict() {
+        assert!(memory_verdict(100_000, 101_000, 200).is_ok());
+        // Shrinking is fine too
+        assert!(memory_verdict(100_000, 90_000, 200).is_ok());
+        let err = memory_verdict(100_000, 100_000 + LEAK_THRESHOLD_KB + 1, 200).unwrap_err();
+        assert!(err.to_string().contains("leaking"));
+    }
+}
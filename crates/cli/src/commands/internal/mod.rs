@@ -1,7 +1,10 @@
 use clap::Subcommand;
+use cuenv_core::suggestions::{did_you_mean, similar_names};
 use cuenv_core::{Error, Result};
 use std::path::PathBuf;
 
+mod ffi_check;
+
 #[derive(Subcommand)]
 pub enum InternalCommands {
     /// Task Server Protocol implementation for devenv integration
@@ -28,6 +31,20 @@ pub enum InternalCommands {
         #[arg(long)]
         export_json: bool,
     },
+    /// Run a detached task run to completion, recording how it ended
+    SuperviseRun {
+        /// ID of the run
+        run_id: String,
+    },
+    /// Exercise the CUE bridge and report its build, failures and leaks
+    FfiCheck {
+        /// Calls made while tracking resident memory
+        #[arg(long, default_value_t = 200)]
+        iterations: usize,
+        /// Threads calling the bridge at once
+        #[arg(long, default_value_t = 8)]
+        threads: usize,
+    },
 }
 
 impl InternalCommands {
@@ -53,6 +70,13 @@ impl InternalCommands {
                 )
                 .await
             }
+            InternalCommands::SuperviseRun { run_id } => {
+                crate::commands::task::supervise_detached_run(&run_id)
+            }
+            InternalCommands::FfiCheck {
+                iterations,
+                threads,
+            } => ffi_check::execute(iterations, threads),
         }
     }
 }
@@ -162,6 +186,10 @@ async fn handle_task_protocol(
             }
         } else {
             eprintln!("Task '{task_name}' not found");
+            let names = all_tasks.iter().map(|task| &task.name);
+            if let Some(hint) = did_you_mean(&similar_names(task_name, names)) {
+                eprintln!("{hint}");
+            }
             eprintln!("Available tasks:");
             for task in &all_tasks {
                 eprintln!("  - {}", task.name);
@@ -206,6 +234,10 @@ async fn handle_task_protocol(
             task_nodes: indexmap::IndexMap::new(), // Empty for internal commands
             hooks: HashMap::new(),
             config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
         };
 
         let config = Arc::new(Config::new(
@@ -365,6 +397,7 @@ mod tests {
                 assert!(socket.is_none());
                 assert!(!export_json);
             }
+            _ => panic!("expected TaskProtocol"),
         }
     }
 
@@ -673,6 +706,7 @@ mod tests {
                     assert!(socket.is_some());
                     assert!(export_json);
                 }
+                _ => panic!("expected TaskProtocol"),
             }
         }
 
@@ -700,6 +734,7 @@ mod tests {
                     assert!(*serve);
                     assert!(*export_json);
                 }
+                _ => panic!("expected TaskProtocol"),
             }
         }
     }
@@ -0,0 +1,62 @@
+//! `cuenv lock`: pin what the environment resolves to in `cuenv.lock`
+//!
+//! The package is evaluated once, then resolved for its base variables and
+//! for each environment, so the lockfile covers every secret reference
+//! whichever environment is loaded later. Locking runs the secret providers
+//! of those references to learn their versions and revisions; the values are
+//! dropped.
+
+use cuenv_config::{Config, CueParser, ParseOptions};
+use cuenv_core::{Result, CUENV_LOCKFILE_NAME, CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME};
+use cuenv_env::lock::{secret_references, Lockfile};
+use std::sync::Arc;
+
+pub async fn execute(config: Arc<Config>, check: bool) -> Result<()> {
+    let directory = &config.working_dir;
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+
+    let package = CueParser::evaluate_package(directory, &package_name)?;
+    let base = package.parse_result(&ParseOptions::default())?;
+    let mut results = Vec::with_capacity(base.environments.len());
+    for environment in &base.environments {
+        let options = ParseOptions {
+            environment: Some(environment.clone()),
+            capabilities: Vec::new(),
+        };
+        results.push(package.parse_result(&options)?);
+    }
+    let references = secret_references(std::iter::once(&base).chain(&results));
+
+    let current = Lockfile::generate(directory, &references, &base.hooks)?;
+
+    if !check {
+        current.save(directory)?;
+        println!(
+            "Locked {} secret(s), {} provider(s), {} tool(s) and {} hook(s) in {CUENV_LOCKFILE_NAME}",
+            current.secrets.len(),
+            current.providers.len(),
+            current.tools.len(),
+            current.hooks.len()
+        );
+        return Ok(());
+    }
+
+    let Some(locked) = Lockfile::load(directory)? else {
+        eprintln!("No {CUENV_LOCKFILE_NAME} found. Run 'cuenv lock' to create it");
+        std::process::exit(1);
+    };
+    let drift = locked.drift(&current, true);
+    if drift.is_empty() {
+        println!("✓ Environment matches {CUENV_LOCKFILE_NAME}");
+        return Ok(());
+    }
+    for change in &drift {
+        println!("{change}");
+    }
+    eprintln!(
+        "\n{} difference(s) from {CUENV_LOCKFILE_NAME}. Run 'cuenv lock' to update it",
+        drift.len()
+    );
+    std::process::exit(1);
+}
@@ -0,0 +1,98 @@
+//! `cuenv login`: store the token of a backend
+//!
+//! The token goes to the OS keychain, or to the credentials file where
+//! there is none, and is then used for the remote cache, HTTP publishing,
+//! hook URLs or the secret provider the backend names. It is read from
+//! standard input: typed without echo at a terminal, or piped.
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::credentials::{self, CredentialLookup};
+use std::io::{self, BufRead, Read, Write};
+
+pub async fn execute(backend: String, check: bool) -> Result<()> {
+    // `https://cache.example.com/v1` stands for `cache.example.com`
+    let backend = if backend.contains("://") {
+        credentials::backend_of_url(&backend)
+    } else {
+        backend
+    };
+
+    if check {
+        return match CredentialLookup::new(&backend).resolve() {
+            Some(credential) => {
+                println!("{backend}: token from {}", credential.source);
+                Ok(())
+            }
+            None => Err(Error::configuration(format!(
+                "No credential for {backend}. Run `cuenv login {backend}`"
+            ))),
+        };
+    }
+
+    let token = read_token(&backend)?;
+    if token.is_empty() {
+        return Err(Error::configuration("No token given"));
+    }
+    let source = credentials::store(&backend, &token)?;
+    eprintln!("✓ Stored the token for {backend} in {source}");
+    Ok(())
+}
+
+fn read_token(backend: &str) -> Result<String> {
+    let read_error = |e: io::Error| Error::configuration(format!("Failed to read token: {e}"));
+    let mut token = String::new();
+    if atty::is(atty::Stream::Stdin) {
+        eprint!("Token for {backend}: ");
+        io::stderr().flush().map_err(read_error)?;
+        let echo = EchoOff::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut token)
+            .map_err(read_error)?;
+        drop(echo);
+        eprintln!();
+    } else {
+        io::stdin().read_to_string(&mut token).map_err(read_error)?;
+    }
+    Ok(token.trim().to_string())
+}
+
+/// Keeps the terminal from echoing what is typed while it lives
+struct EchoOff {
+    #[cfg(unix)]
+    saved: Option<libc::termios>,
+}
+
+impl EchoOff {
+    #[cfg(unix)]
+    fn new() -> Self {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: tcgetattr fills the struct when it returns 0
+        let saved = unsafe {
+            (libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) == 0)
+                .then(|| termios.assume_init())
+        };
+        if let Some(saved) = saved {
+            let mut silent = saved;
+            silent.c_lflag &= !libc::ECHO;
+            // SAFETY: `silent` is a valid termios copied from the terminal
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &silent) };
+        }
+        Self { saved }
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(saved) = &self.saved {
+            // SAFETY: restores the settings read in `new`
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, saved) };
+        }
+    }
+}
@@ -1,20 +1,44 @@
 use clap::Subcommand;
 use std::path::PathBuf;
 
+pub mod agent;
+pub mod bench;
 pub mod cache;
+pub mod ci;
+pub mod config;
+pub mod container;
 pub mod discover;
+pub mod doctor;
 pub mod env;
 pub mod exec;
+pub mod hooks;
 pub mod init;
 pub mod internal;
+pub mod lock;
+pub mod login;
 pub mod mcp;
+pub mod new;
+pub mod run_config;
+pub mod scheduler;
+pub mod security;
+pub mod self_update;
+pub mod serve;
+pub mod setup;
 pub mod shell;
+pub mod state;
 pub mod task;
+pub mod version;
 
 use self::cache::CacheCommands;
+use self::ci::CiCommands;
+use self::config::ConfigCommands;
 use self::env::EnvCommands;
+use self::hooks::HooksCommands;
 use self::internal::InternalCommands;
+use self::scheduler::SchedulerCommands;
+use self::security::SecurityCommands;
 use self::shell::ShellCommands;
+use self::state::StateCommands;
 
 #[derive(Subcommand)]
 pub enum Commands {
@@ -44,7 +68,7 @@ pub enum Commands {
         #[arg(short, long)]
         verbose: bool,
 
-        /// Output format for task execution (tui, simple, or spinner)
+        /// Output format for task execution (tui, simple, spinner, or tmux)
         #[arg(long, value_name = "FORMAT", default_value = "spinner")]
         output: String,
 
@@ -60,6 +84,57 @@ pub enum Commands {
         /// Character set for tree format: unicode (default), ascii
         #[arg(long, value_name = "CHARSET", default_value = "unicode")]
         charset: String,
+
+        /// Exit with code 3 when tasks had to run instead of being served from the cache
+        #[arg(long)]
+        exit_zero_on_cache_hit_only: bool,
+
+        /// Run tasks that ask for confirmation without asking
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Write the diagnostics found by problem matchers to this file as JSON
+        #[arg(long, value_name = "FILE")]
+        diagnostics_json: Option<PathBuf>,
+
+        /// Fail cross-package runs whose packages define a variable with different values
+        #[arg(long)]
+        strict_conflicts: bool,
+
+        /// Prefix each line of task output with the time since the run started (simple output)
+        #[arg(long)]
+        timestamps: bool,
+
+        /// Print each task's duration and the slowest tasks when the run ends (simple output)
+        #[arg(long)]
+        summary: bool,
+
+        /// Write a report of the run: junit=<path> for JUnit XML, md=<path> for Markdown
+        #[arg(long, value_name = "FORMAT=PATH")]
+        report: Vec<String>,
+
+        /// Run this command instead of a task's own, as task=command (can be repeated)
+        #[arg(long = "stub", value_name = "TASK=COMMAND")]
+        stubs: Vec<String>,
+    },
+
+    /// Run a named configuration from `runConfigs`, or list them
+    #[command(name = "run-config")]
+    RunConfig {
+        /// Run configuration to run (lists all if not provided)
+        name: Option<String>,
+
+        /// Output format for task execution (tui, simple, spinner, or tmux)
+        #[arg(long, value_name = "FORMAT", default_value = "spinner")]
+        output: String,
+
+        /// Run in audit mode to see file and network access without restrictions
+        #[arg(long)]
+        audit: bool,
+
+        /// Run tasks that ask for confirmation without asking
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
 
     /// Manage environment configuration
@@ -69,6 +144,34 @@ pub enum Commands {
         command: EnvCommands,
     },
 
+    /// Print the environment as `docker build` arguments, leaving out secrets
+    #[command(name = "docker-args")]
+    DockerArgs {
+        /// Only variables whose names match this glob (can be specified multiple times)
+        #[arg(long, value_name = "GLOB")]
+        allow: Vec<String>,
+
+        /// Output format: shell (quoted, for `eval`) or json (an array of arguments)
+        #[arg(long, default_value = "shell")]
+        format: String,
+    },
+
+    /// Write the environment as a docker compose `.env` file
+    #[command(name = "compose-env")]
+    ComposeEnv {
+        /// Only variables whose names match this glob (can be specified multiple times)
+        #[arg(long, value_name = "GLOB")]
+        allow: Vec<String>,
+
+        /// What to do with secrets: omit, reference (NAME=${NAME}) or mask
+        #[arg(long, default_value = "omit")]
+        secrets: String,
+
+        /// File to write instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
     /// Initialize a new env.cue file with example configuration
     Init {
         /// Force overwrite existing file
@@ -76,6 +179,59 @@ pub enum Commands {
         force: bool,
     },
 
+    /// Create a directory from a template, or list the templates
+    New {
+        /// Template to instantiate (lists all if not provided)
+        template: Option<String>,
+
+        /// Name of what is created, and its directory unless --dir is given
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Directory to create
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Registry to use instead of the configured ones: a path or a git URL, with an optional #ref
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Template variable, as KEY=VALUE (can be specified multiple times)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Do not run the template's post-create hooks
+        #[arg(long)]
+        no_hooks: bool,
+
+        /// Run the post-create hooks of a template from a git registry without asking
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Install the shell hook and completions into your shell's rc file
+    Setup {
+        /// Shell to set up (defaults to the current shell)
+        #[arg(long)]
+        shell: Option<String>,
+
+        /// Rc file to edit instead of the shell's default
+        #[arg(long)]
+        rc_file: Option<PathBuf>,
+
+        /// Edit the rc file without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Only install the hook, not completions
+        #[arg(long)]
+        no_completions: bool,
+
+        /// Remove the block a previous setup added
+        #[arg(long, conflicts_with_all = ["yes", "no_completions"])]
+        remove: bool,
+    },
+
     /// Discover all CUE packages in the repository
     Discover {
         /// Maximum depth to search for env.cue files
@@ -89,18 +245,101 @@ pub enum Commands {
         dump: bool,
     },
 
+    /// Show retry policies and circuit breaker states of network-facing subsystems
+    Doctor {
+        /// Output format (human or json)
+        #[arg(long, default_value = "human")]
+        format: String,
+    },
+
+    /// Pin secret provider versions, secret revisions, tools and hook inputs in cuenv.lock
+    Lock {
+        /// Compare with cuenv.lock instead of writing it, failing on drift
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Store the token of a remote cache, endpoint or secret provider in the OS keychain
+    Login {
+        /// Host (or URL) of the service, or the name of a secret provider
+        backend: String,
+
+        /// Show where the backend's credential is found instead of storing one
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Work with the evaluated configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Resolve the environment for CI pipeline steps
+    Ci {
+        #[command(subcommand)]
+        command: CiCommands,
+    },
+
     /// Manage the task and environment cache
     Cache {
         #[command(subcommand)]
         command: CacheCommands,
     },
 
+    /// Manage onEnter hooks and their cached environments
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+
+    /// Export or import approvals, settings and the cache, e.g. into container images
+    State {
+        #[command(subcommand)]
+        command: StateCommands,
+    },
+
+    /// Run tasks on their `schedule`
+    Scheduler {
+        #[command(subcommand)]
+        command: SchedulerCommands,
+    },
+
+    /// Inspect sandbox protections and allow the access audited tasks used
+    Security {
+        #[command(subcommand)]
+        command: SecurityCommands,
+    },
+
     /// Configure shell integration for automatic environment loading
     Shell {
         #[command(subcommand)]
         command: ShellCommands,
     },
 
+    /// Update cuenv to the latest release of the update channel
+    #[command(name = "self-update")]
+    SelfUpdate {
+        /// Channel to update from instead of the configured one
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Only check whether an update is available
+        #[arg(long)]
+        check: bool,
+
+        /// Reinstall the latest release when it is the installed version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the version of cuenv
+    Version {
+        /// Also print the versions of the embedded CUE evaluator and Go bridge
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
     /// Generate shell completion scripts
     Completion {
         /// Shell to generate completion for
@@ -126,6 +365,10 @@ pub enum Commands {
         /// Run in audit mode to see file and network access without restrictions
         #[arg(long)]
         audit: bool,
+
+        /// Sandbox the command using its capabilities' security settings
+        #[arg(long, conflicts_with = "audit")]
+        restrict: bool,
     },
 
     // Internal commands
@@ -141,6 +384,46 @@ pub enum Commands {
     #[command(name = "_complete_hosts", hide = true)]
     CompleteHosts,
 
+    /// Benchmark core paths against a generated repository (for contributors)
+    #[command(hide = true)]
+    Bench {
+        /// Packages in the generated repository
+        #[arg(long, default_value_t = 10)]
+        packages: usize,
+
+        /// Tasks per package
+        #[arg(long, default_value_t = 50)]
+        tasks: usize,
+
+        /// Variables per package
+        #[arg(long, default_value_t = 200)]
+        variables: usize,
+
+        /// Input files per task
+        #[arg(long, default_value_t = 5)]
+        files: usize,
+
+        /// Times each path is measured
+        #[arg(long, default_value_t = 10)]
+        iterations: usize,
+
+        /// Save the results as this baseline
+        #[arg(long)]
+        save_baseline: Option<String>,
+
+        /// Compare the results with this baseline, failing on regressions
+        #[arg(long)]
+        baseline: Option<String>,
+
+        /// Slowdown of a median, in percent, counted as a regression
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+
+        /// Directory baselines are kept in
+        #[arg(long, default_value = "target/cuenv-bench")]
+        baseline_dir: PathBuf,
+    },
+
     /// Internal task server protocol implementation (experimental)
     #[command(name = "internal", hide = true)]
     Internal {
@@ -148,6 +431,13 @@ pub enum Commands {
         command: InternalCommands,
     },
 
+    /// Serve JSON-RPC for editor integrations, on stdio unless a socket is given
+    Agent {
+        /// Unix socket to listen on instead of stdio
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+
     /// Start MCP (Model Context Protocol) server for Claude Code integration
     Mcp {
         /// Transport type (stdio, tcp, unix)
@@ -167,6 +457,48 @@ pub enum Commands {
         allow_exec: bool,
     },
 
+    /// Serve a web dashboard of task runs, optionally running a task
+    Serve {
+        /// Serve the web dashboard
+        #[arg(long)]
+        web: bool,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on (0 picks a free one)
+        #[arg(long, default_value = "7878")]
+        port: u16,
+
+        /// Access token to require instead of a random one (or set CUENV_WEB_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Environment to use (e.g., dev, staging, production)
+        #[arg(short = 'e', long = "env")]
+        environment: Option<String>,
+
+        /// Capabilities to enable (can be specified multiple times)
+        #[arg(short = 'c', long = "capability")]
+        capabilities: Vec<String>,
+
+        /// Run in audit mode to see file and network access without restrictions
+        #[arg(long)]
+        audit: bool,
+
+        /// Stop serving once the task finishes instead of waiting for Ctrl-C
+        #[arg(long, requires = "task")]
+        exit: bool,
+
+        /// Task or group to run while serving
+        task: Option<String>,
+
+        /// Arguments to pass to the task
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
     /// Internal preload supervisor (hidden from user)
     #[command(name = "supervisor", hide = true)]
     Supervisor {
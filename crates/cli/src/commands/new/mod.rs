@@ -0,0 +1,205 @@
+//! `cuenv new`: scaffold a directory from a template
+//!
+//! Templates are looked up by name in the registries described in
+//! [`registry`], their files are copied with `{{ variable }}` placeholders
+//! replaced, and their post-create hooks run in the new directory. The hooks
+//! are listed before any runs, and those of a template cloned from a git
+//! registry only run once confirmed, or with `--yes`.
+
+mod registry;
+mod template;
+
+use self::registry::{registries, Registry};
+use self::template::{Template, NAME_VARIABLE};
+use cuenv_config::Config;
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_core::{Error, Result};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+pub struct NewOptions {
+    pub template: Option<String>,
+    pub name: Option<String>,
+    pub dir: Option<PathBuf>,
+    pub from: Option<String>,
+    pub vars: Vec<String>,
+    pub no_hooks: bool,
+    pub yes: bool,
+}
+
+pub async fn execute(config: Arc<Config>, options: NewOptions) -> Result<()> {
+    let registries = registries(options.from.as_deref())?;
+    let Some(template_name) = options.template else {
+        return list(&registries);
+    };
+    let (template, remote) = find(&registries, &template_name)?;
+
+    let dir = options
+        .dir
+        .map(|dir| config.working_dir.join(dir))
+        .or_else(|| {
+            options
+                .name
+                .as_ref()
+                .map(|name| config.working_dir.join(name))
+        })
+        .ok_or_else(|| Error::configuration("Give the project a --name or a --dir to create"))?;
+    let name = match options.name {
+        Some(name) => name,
+        None => dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::configuration(format!("Cannot name {}", dir.display())))?,
+    };
+    let is_empty = dir
+        .read_dir()
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true);
+    if !is_empty {
+        return Err(Error::configuration(format!(
+            "{} already exists and is not empty",
+            dir.display()
+        )));
+    }
+
+    let given = parse_vars(&options.vars)?;
+    let values = template.resolve(&name, &given)?;
+    let written = template.instantiate(&dir, &values)?;
+    println!(
+        "✓ Created {} from '{}' ({written} files)",
+        dir.display(),
+        template.name
+    );
+
+    let hooks = template.hooks(&values);
+    if !hooks.is_empty() && !options.no_hooks {
+        println!("Post-create hooks:");
+        for hook in &hooks {
+            println!("  {} {}", hook.command, hook.args.join(" "));
+        }
+    }
+    let run_hooks = !options.no_hooks
+        && (hooks.is_empty() || !remote || options.yes || confirm_hooks(&template.name)?);
+    if !run_hooks {
+        if !hooks.is_empty() {
+            println!("Skipped {} post-create hooks", hooks.len());
+        }
+    } else {
+        for hook in hooks {
+            println!("→ {} {}", hook.command, hook.args.join(" "));
+            let status = Command::new(&hook.command)
+                .args(&hook.args)
+                .current_dir(&dir)
+                .status()
+                .map_err(|e| {
+                    Error::command_execution(&hook.command, hook.args.clone(), e.to_string(), None)
+                })?;
+            if !status.success() {
+                return Err(Error::command_execution(
+                    hook.command,
+                    hook.args,
+                    "post-create hook failed; the files are in place",
+                    status.code(),
+                ));
+            }
+        }
+    }
+
+    println!("\nNext steps:");
+    println!("  cd {}", dir.display());
+    println!("  cuenv env allow .");
+    Ok(())
+}
+
+/// The template called `name` and whether it comes from a git registry
+fn find(registries: &[Registry], name: &str) -> Result<(Template, bool)> {
+    registries
+        .iter()
+        .find_map(|registry| {
+            registry
+                .find(name)
+                .map(|template| template.map(|template| (template, registry.is_remote())))
+                .transpose()
+        })
+        .unwrap_or_else(|| {
+            let names: Vec<String> = registries
+                .iter()
+                .filter_map(|registry| registry.templates().ok())
+                .flatten()
+                .map(|template| template.name)
+                .collect();
+            Err(Error::configuration(format!(
+                "{}\nRun 'cuenv new' to list templates",
+                with_suggestions(
+                    format!("Template '{name}' not found"),
+                    &similar_names(name, names)
+                )
+            )))
+        })
+}
+
+/// Ask before running the hooks listed above, refusing to guess when nobody
+/// can answer
+fn confirm_hooks(template: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::configuration(format!(
+            "Template '{template}' comes from a git registry and has post-create hooks\nNot running interactively; pass --yes to run them or --no-hooks to skip them"
+        )));
+    }
+    print!("Run the post-create hooks of '{template}'? [y/N] ");
+    io::stdout()
+        .flush()
+        .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn list(registries: &[Registry]) -> Result<()> {
+    if registries.is_empty() {
+        println!("No template registries. Pass --from <path or git URL>, or list registries under \"templates\" in ~/.config/cuenv/config.json");
+        return Ok(());
+    }
+    for registry in registries {
+        println!("{}", registry.source);
+        let templates = registry.templates()?;
+        if templates.is_empty() {
+            println!("  (no templates)");
+        }
+        for template in templates {
+            let variables: Vec<&str> = template.variables.keys().map(String::as_str).collect();
+            println!(
+                "  {:<24} {}",
+                template.name,
+                template.description.as_deref().unwrap_or_default()
+            );
+            if !variables.is_empty() {
+                println!("  {:<24} variables: {}", "", variables.join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `KEY=VALUE` pairs from `--var`
+fn parse_vars(vars: &[String]) -> Result<BTreeMap<String, String>> {
+    vars.iter()
+        .map(|var| {
+            var.split_once('=')
+                .filter(|(key, _)| !key.is_empty() && *key != NAME_VARIABLE)
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| {
+                    Error::configuration(format!(
+                        "Invalid --var '{var}': expected KEY=VALUE, with --name for the name"
+                    ))
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,207 @@
+//! Where templates come from
+//!
+//! A registry is a directory holding one template per subdirectory, either
+//! on disk or at the root of a git repository. Registries are searched in
+//! order: `--from` if given, then those listed in the `templates` section of
+//! `~/.config/cuenv/config.json`, then the local registry in the data
+//! directory:
+//!
+//! ```json
+//! {
+//!   "templates": {
+//!     "registries": ["https://github.com/acme/cuenv-templates.git#v2", "/srv/templates"]
+//!   }
+//! }
+//! ```
+//!
+//! A `#ref` suffix on a git URL picks a branch or tag; git registries are
+//! cloned shallowly into a temporary directory for the run.
+
+use super::template::{Template, TEMPLATE_MANIFEST_FILENAME};
+use cuenv_core::{Error, Result};
+use cuenv_utils::xdg::XdgPaths;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// The `templates` section of the global config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplatesConfig {
+    #[serde(default)]
+    registries: Vec<String>,
+}
+
+/// A directory of templates, kept alive for as long as it is cloned
+#[derive(Debug)]
+pub struct Registry {
+    /// Path or URL the registry was given as
+    pub source: String,
+    root: PathBuf,
+    _checkout: Option<TempDir>,
+}
+
+impl Registry {
+    /// Open a local directory or clone a git URL
+    pub fn open(source: &str) -> Result<Self> {
+        let local = PathBuf::from(shellexpand::tilde(source).as_ref());
+        if local.is_dir() {
+            return Ok(Self {
+                source: source.to_string(),
+                root: local,
+                _checkout: None,
+            });
+        }
+        let checkout = clone(source)?;
+        Ok(Self {
+            source: source.to_string(),
+            root: checkout.path().to_path_buf(),
+            _checkout: Some(checkout),
+        })
+    }
+
+    /// The local registry in the data directory, if it exists
+    pub fn local() -> Option<Self> {
+        let root = XdgPaths::data_dir().join("templates");
+        root.is_dir().then(|| Self {
+            source: root.display().to_string(),
+            root,
+            _checkout: None,
+        })
+    }
+
+    /// Templates of this registry, sorted by name
+    pub fn templates(&self) -> Result<Vec<Template>> {
+        let entries = fs::read_dir(&self.root)
+            .map_err(|e| Error::file_system(&self.root, "read template registry", e))?;
+        let mut templates = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(TEMPLATE_MANIFEST_FILENAME).is_file())
+            .map(|path| Template::load(&path))
+            .collect::<Result<Vec<_>>>()?;
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Whether the registry was cloned from a git URL rather than read from disk
+    pub fn is_remote(&self) -> bool {
+        self._checkout.is_some()
+    }
+
+    pub fn find(&self, name: &str) -> Result<Option<Template>> {
+        let dir = self.root.join(name);
+        if dir.join(TEMPLATE_MANIFEST_FILENAME).is_file() {
+            Template::load(&dir).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Registries to search, `from` replacing the configured ones
+pub fn registries(from: Option<&str>) -> Result<Vec<Registry>> {
+    if let Some(source) = from {
+        return Ok(vec![Registry::open(source)?]);
+    }
+    let configured = configured_registries(&XdgPaths::config_dir().join("config.json"))?
+        .iter()
+        .map(|source| Registry::open(source))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(configured.into_iter().chain(Registry::local()).collect())
+}
+
+fn configured_registries(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| Error::file_system(path, "read config", e))?;
+    let file: serde_json::Value = serde_json::from_str(&content).map_err(|e| Error::Json {
+        message: format!("Failed to parse {}: {e}", path.display()),
+        source: e,
+    })?;
+    match file.get("templates") {
+        Some(section) => TemplatesConfig::deserialize(section)
+            .map(|config| config.registries)
+            .map_err(|e| Error::Json {
+                message: format!("Invalid templates section in {}: {e}", path.display()),
+                source: e,
+            }),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Shallow clone of `url`, at the ref after a `#` if there is one
+fn clone(source: &str) -> Result<TempDir> {
+    let (url, reference) = match source.rsplit_once('#') {
+        Some((url, reference)) => (url, Some(reference)),
+        None => (source, None),
+    };
+    let checkout = tempfile::Builder::new()
+        .prefix("cuenv-templates-")
+        .tempdir()
+        .map_err(|e| Error::file_system(std::env::temp_dir(), "create template checkout", e))?;
+
+    let mut args = vec!["clone", "--quiet", "--depth", "1"];
+    if let Some(reference) = reference {
+        args.extend(["--branch", reference]);
+    }
+    args.push(url);
+    let args: Vec<String> = args.into_iter().map(str::to_string).collect();
+    let output = Command::new("git")
+        .args(&args)
+        .arg(checkout.path())
+        .output()
+        .map_err(|e| Error::command_execution("git", args.clone(), e.to_string(), None))?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            "git",
+            args,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            output.status.code(),
+        ));
+    }
+    Ok(checkout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lists_template_directories() {
+        let root = TempDir::new().unwrap();
+        for name in ["web", "rust-service"] {
+            let dir = root.path().join(name);
+            fs::create_dir_all(dir.join("files")).unwrap();
+            fs::write(
+                dir.join(TEMPLATE_MANIFEST_FILENAME),
+                format!(r#"{{"description": "{name} template"}}"#),
+            )
+            .unwrap();
+        }
+        // Not a template
+        fs::create_dir_all(root.path().join("docs")).unwrap();
+
+        let registry = Registry::open(&root.path().display().to_string()).unwrap();
+        let names: Vec<String> = registry
+            .templates()
+            .unwrap()
+            .into_iter()
+            .map(|template| template.name)
+            .collect();
+        assert_eq!(names, ["rust-service", "web"]);
+        assert!(registry.find("web").unwrap().is_some());
+        assert!(registry.find("docs").unwrap().is_none());
+
+        let config = root.path().join("config.json");
+        fs::write(
+            &config,
+            r#"{"templates": {"registries": ["/srv/templates"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(configured_registries(&config).unwrap(), ["/srv/templates"]);
+    }
+}
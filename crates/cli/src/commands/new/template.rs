@@ -0,0 +1,324 @@
+//! A template: a manifest and the files it scaffolds
+//!
+//! ```text
+//! rust-service/
+//!   template.json      description, variables and post-create hooks
+//!   files/             copied into the new directory
+//!     env.cue
+//!     src/{{name}}.rs
+//! ```
+//!
+//! `{{ variable }}` in file contents and paths is replaced by its value.
+//! Only `name` and the variables the manifest declares are replaced, so
+//! other uses of braces, such as GitHub Actions expressions, pass through.
+//! Files that are not UTF-8 are copied as they are.
+
+use cuenv_core::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const TEMPLATE_MANIFEST_FILENAME: &str = "template.json";
+/// Directory of a template holding the files to scaffold
+const FILES_DIR: &str = "files";
+/// Variable every template has: the name of what is created
+pub const NAME_VARIABLE: &str = "name";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariableSpec {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Value when none is given; the variable is required without one
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// Command run in the new directory once the files are in place
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostCreateHook {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, VariableSpec>,
+    #[serde(default)]
+    post_create: Vec<PostCreateHook>,
+}
+
+#[derive(Debug)]
+pub struct Template {
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: BTreeMap<String, VariableSpec>,
+    pub post_create: Vec<PostCreateHook>,
+    dir: PathBuf,
+}
+
+impl Template {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(TEMPLATE_MANIFEST_FILENAME);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| Error::file_system(&path, "read template manifest", e))?;
+        let manifest: Manifest = serde_json::from_str(&content).map_err(|e| Error::Json {
+            message: format!("Invalid template manifest {}: {e}", path.display()),
+            source: e,
+        })?;
+        Ok(Self {
+            name: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            description: manifest.description,
+            variables: manifest.variables,
+            post_create: manifest.post_create,
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Values of all variables: `name`, the given ones and the defaults
+    /// of the rest, which may refer to `name`
+    pub fn resolve(
+        &self,
+        name: &str,
+        given: &BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>> {
+        if let Some(unknown) = given
+            .keys()
+            .find(|key| *key != NAME_VARIABLE && !self.variables.contains_key(*key))
+        {
+            return Err(Error::configuration(format!(
+                "Template '{}' has no variable '{unknown}'",
+                self.name
+            )));
+        }
+        let named = BTreeMap::from([(NAME_VARIABLE.to_string(), name.to_string())]);
+        let missing: Vec<String> = self
+            .variables
+            .iter()
+            .filter(|(key, spec)| spec.default.is_none() && !given.contains_key(*key))
+            .map(|(key, spec)| match &spec.description {
+                Some(description) => format!("  {key}: {description}"),
+                None => format!("  {key}"),
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::configuration(format!(
+                "Template '{}' needs values for, with --var KEY=VALUE:\n{}",
+                self.name,
+                missing.join("\n")
+            )));
+        }
+
+        let values = self.variables.iter().map(|(key, spec)| {
+            let value = match (given.get(key), &spec.default) {
+                (Some(value), _) => value.clone(),
+                (None, default) => render(default.as_deref().unwrap_or_default(), &named),
+            };
+            (key.clone(), value)
+        });
+        Ok(named.clone().into_iter().chain(values).collect())
+    }
+
+    /// Write the template's files into `dest`, returning how many were
+    /// written
+    pub fn instantiate(&self, dest: &Path, values: &BTreeMap<String, String>) -> Result<usize> {
+        let files_dir = self.dir.join(FILES_DIR);
+        if !files_dir.is_dir() {
+            return Err(Error::configuration(format!(
+                "Template '{}' has no {FILES_DIR} directory",
+                self.name
+            )));
+        }
+
+        // Check every entry first so nothing is written for a bad one
+        let entries = WalkDir::new(&files_dir)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.map_err(|e| {
+                    Error::configuration(format!("Failed to read template '{}': {e}", self.name))
+                })?;
+                let relative = entry
+                    .path()
+                    .strip_prefix(&files_dir)
+                    .unwrap_or(entry.path());
+                if !entry.file_type().is_dir() && !entry.file_type().is_file() {
+                    return Err(Error::configuration(format!(
+                        "Template '{}' contains {}, which is not a file or directory",
+                        self.name,
+                        relative.display()
+                    )));
+                }
+                let target = dest.join(render_path(relative, values)?);
+                Ok((entry, target))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        fs::create_dir_all(dest).map_err(|e| Error::file_system(dest, "create directory", e))?;
+        let mut written = 0;
+        for (entry, target) in entries {
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)
+                    .map_err(|e| Error::file_system(&target, "create directory", e))?;
+            } else {
+                let bytes = fs::read(entry.path())
+                    .map_err(|e| Error::file_system(entry.path(), "read template file", e))?;
+                let contents = match String::from_utf8(bytes) {
+                    Ok(text) => render(&text, values).into_bytes(),
+                    Err(binary) => binary.into_bytes(),
+                };
+                fs::write(&target, contents)
+                    .map_err(|e| Error::file_system(&target, "write file", e))?;
+                // Keep scripts executable
+                let permissions = entry
+                    .metadata()
+                    .map_err(|e| {
+                        Error::configuration(format!(
+                            "Failed to read template '{}': {e}",
+                            self.name
+                        ))
+                    })?
+                    .permissions();
+                fs::set_permissions(&target, permissions)
+                    .map_err(|e| Error::file_system(&target, "set permissions", e))?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Post-create hooks with their arguments rendered
+    pub fn hooks(&self, values: &BTreeMap<String, String>) -> Vec<PostCreateHook> {
+        self.post_create
+            .iter()
+            .map(|hook| PostCreateHook {
+                command: render(&hook.command, values),
+                args: hook.args.iter().map(|arg| render(arg, values)).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Replace `{{ key }}` for each key of `values`, leaving other braces alone
+fn render(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| values.get(after[..end].trim()).map(|value| (end, value)));
+        match value {
+            Some((end, value)) => {
+                rendered.push_str(&rest[..start]);
+                rendered.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                rendered.push_str(&rest[..start + 2]);
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Render a path of the template, refusing values that would take it out
+/// of the new directory
+fn render_path(path: &Path, values: &BTreeMap<String, String>) -> Result<PathBuf> {
+    let rendered = PathBuf::from(render(&path.to_string_lossy(), values));
+    if rendered
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        Ok(rendered)
+    } else {
+        Err(Error::configuration(format!(
+            "Template path {} renders to {}, outside the new directory",
+            path.display(),
+            rendered.display()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn template(root: &Path) -> Template {
+        let dir = root.join("rust-service");
+        fs::create_dir_all(dir.join("files/src")).unwrap();
+        fs::write(
+            dir.join(TEMPLATE_MANIFEST_FILENAME),
+            r#"{
+                "description": "Rust service",
+                "variables": {
+                    "owner": { "description": "Owning team" },
+                    "crate": { "default": "{{ name }}-service" }
+                },
+                "postCreate": [{ "command": "git", "args": ["init", "{{name}}"] }]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("files/env.cue"),
+            "package cuenv\n\nenv: OWNER: \"{{ owner }}\"\n// ${{ secrets.TOKEN }}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("files/src/{{crate}}.rs"), "// {{name}}\n").unwrap();
+        fs::write(dir.join("files/logo.bin"), [0xff, 0xfe, b'{', b'{']).unwrap();
+        Template::load(&dir).unwrap()
+    }
+
+    #[test]
+    fn test_template_instantiates_with_variables() {
+        let root = TempDir::new().unwrap();
+        let template = template(root.path());
+        assert_eq!(template.name, "rust-service");
+
+        let err = template.resolve("billing", &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("owner: Owning team"));
+        let typo = BTreeMap::from([("ownr".to_string(), "payments".to_string())]);
+        assert!(template.resolve("billing", &typo).is_err());
+
+        let given = BTreeMap::from([("owner".to_string(), "payments".to_string())]);
+        let values = template.resolve("billing", &given).unwrap();
+        assert_eq!(values["crate"], "billing-service");
+
+        let dest = root.path().join("billing");
+        assert_eq!(template.instantiate(&dest, &values).unwrap(), 3);
+        assert_eq!(
+            fs::read_to_string(dest.join("env.cue")).unwrap(),
+            "package cuenv\n\nenv: OWNER: \"payments\"\n// ${{ secrets.TOKEN }}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("src/billing-service.rs")).unwrap(),
+            "// billing\n"
+        );
+        assert_eq!(
+            fs::read(dest.join("logo.bin")).unwrap(),
+            [0xff, 0xfe, b'{', b'{']
+        );
+        assert_eq!(template.hooks(&values)[0].args, ["init", "billing"]);
+
+        // Values cannot escape the new directory, and nothing is written
+        let escaping = template.resolve("../escape", &given).unwrap();
+        let dest = root.path().join("escape");
+        assert!(template.instantiate(&dest, &escaping).is_err());
+        assert!(!dest.exists());
+    }
+}
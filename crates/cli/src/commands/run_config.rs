@@ -0,0 +1,79 @@
+//! `cuenv run-config`: run a named bundle of environment, capabilities and
+//! tasks declared under `runConfigs`
+
+use cuenv_config::Config;
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_core::{Error, Result};
+use std::sync::Arc;
+
+pub async fn execute(
+    config: Arc<Config>,
+    name: Option<String>,
+    output_format: String,
+    audit: bool,
+) -> Result<()> {
+    let run_configs = config.get_run_configs();
+    let Some(name) = name else {
+        list(&config);
+        return Ok(());
+    };
+    let Some(run_config) = run_configs.get(&name) else {
+        let mut available: Vec<_> = run_configs.keys().map(String::as_str).collect();
+        available.sort_unstable();
+        let not_found = with_suggestions(
+            format!("Run configuration '{name}' not found"),
+            &similar_names(&name, &available),
+        );
+        return Err(Error::configuration(format!(
+            "{not_found}\nAvailable: {}",
+            if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            }
+        )));
+    };
+
+    let environment = run_config.environment.as_deref().unwrap_or("default");
+    eprintln!(
+        "Running '{name}': {} in {environment}",
+        run_config.tasks.join(", ")
+    );
+    let status = crate::commands::task::run_tasks(
+        run_config.environment.clone(),
+        run_config.capabilities.clone(),
+        &run_config.tasks,
+        &[],
+        audit,
+        &output_format,
+    )
+    .await?;
+    if status != 0 {
+        std::process::exit(status);
+    }
+    Ok(())
+}
+
+fn list(config: &Config) {
+    let mut run_configs: Vec<_> = config.get_run_configs().iter().collect();
+    if run_configs.is_empty() {
+        println!("No run configurations defined. Add them under `runConfigs` in env.cue.");
+        return;
+    }
+    run_configs.sort_unstable_by_key(|(name, _)| name.as_str());
+
+    println!("Available run configurations:");
+    for (name, run_config) in run_configs {
+        match &run_config.description {
+            Some(description) => println!("  {name} - {description}"),
+            None => println!("  {name}"),
+        }
+        println!("    tasks: {}", run_config.tasks.join(", "));
+        if let Some(environment) = &run_config.environment {
+            println!("    environment: {environment}");
+        }
+        if !run_config.capabilities.is_empty() {
+            println!("    capabilities: {}", run_config.capabilities.join(", "));
+        }
+    }
+}
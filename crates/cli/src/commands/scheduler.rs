@@ -0,0 +1,151 @@
+//! `cuenv scheduler`: run tasks with a `schedule` when they are due
+
+use chrono::{DateTime, Local, Utc};
+use clap::Subcommand;
+use cuenv_config::Config;
+use cuenv_core::{Error, Result};
+use cuenv_task::scheduler::{scheduled_tasks, DueRun, ScheduleState, ScheduledTask, Scheduler};
+use cuenv_utils::paths::get_scheduler_state_path;
+use std::time::Duration;
+
+/// Longest the scheduler sleeps before checking the clock again, so a
+/// suspended machine or a changed clock is noticed
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+#[derive(Subcommand)]
+pub enum SchedulerCommands {
+    /// Run scheduled tasks as they become due, until interrupted
+    Run {
+        /// Environment to use (e.g., dev, staging, production)
+        #[arg(short = 'e', long = "env")]
+        environment: Option<String>,
+
+        /// Capabilities to enable (can be specified multiple times)
+        #[arg(short = 'c', long = "capability")]
+        capabilities: Vec<String>,
+
+        /// Run the tasks that are due, including missed ones, then exit
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// List scheduled tasks and when they next run
+    List,
+}
+
+impl SchedulerCommands {
+    pub async fn execute(self, config: &Config) -> Result<()> {
+        let tasks = scheduled_tasks(config.get_tasks())?;
+        let project_dir = std::env::current_dir()
+            .map_err(|e| Error::file_system(".", "get current directory", e))?;
+        let state = ScheduleState::load(get_scheduler_state_path(&project_dir));
+        match self {
+            SchedulerCommands::Run {
+                environment,
+                capabilities,
+                once,
+            } => {
+                let scheduler = Scheduler::new(tasks, state, Utc::now());
+                run(scheduler, environment, capabilities, once).await
+            }
+            SchedulerCommands::List => {
+                list(&tasks, &state);
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn run(
+    mut scheduler: Scheduler,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    once: bool,
+) -> Result<()> {
+    if scheduler.tasks().is_empty() {
+        println!("No scheduled tasks. Add a `schedule` to tasks in env.cue.");
+        return Ok(());
+    }
+    // Tasks seen for the first time start from now
+    scheduler.save()?;
+    if !once {
+        eprintln!(
+            "Scheduler running {} task(s), press Ctrl-C to stop",
+            scheduler.tasks().len()
+        );
+    }
+
+    loop {
+        for due in scheduler.due(&Local::now()) {
+            run_due(&due, environment.clone(), capabilities.clone()).await;
+            scheduler.record(&due);
+            scheduler.save()?;
+        }
+        if once {
+            return Ok(());
+        }
+
+        let sleep = scheduler
+            .next_run(&Local::now())
+            .and_then(|next| (next - Local::now()).to_std().ok())
+            .map_or(MAX_SLEEP, |until| until.min(MAX_SLEEP));
+        tokio::select! {
+            _ = tokio::time::sleep(sleep) => {}
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("Scheduler stopped");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Run a due task as many times as its catch-up policy says, reporting
+/// failures without stopping the scheduler
+async fn run_due(due: &DueRun, environment: Option<String>, capabilities: Vec<String>) {
+    let due_at = due.due.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    if due.missed > 0 {
+        eprintln!(
+            "'{}' missed {} run(s), last due {due_at}: running it {} time(s)",
+            due.task, due.missed, due.runs
+        );
+    }
+    for _ in 0..due.runs {
+        eprintln!("Running '{}' (due {due_at})", due.task);
+        let task_names = std::slice::from_ref(&due.task);
+        match crate::commands::task::run_tasks(
+            environment.clone(),
+            capabilities.clone(),
+            task_names,
+            &[],
+            false,
+            "simple",
+        )
+        .await
+        {
+            Ok(0) => {}
+            Ok(status) => eprintln!("'{}' failed with exit code {status}", due.task),
+            Err(e) => eprintln!("'{}' failed: {e}", due.task),
+        }
+    }
+}
+
+fn list(tasks: &[ScheduledTask], state: &ScheduleState) {
+    if tasks.is_empty() {
+        println!("No scheduled tasks. Add a `schedule` to tasks in env.cue.");
+        return;
+    }
+    let now = Local::now();
+    let local = |time: DateTime<Local>| time.format("%Y-%m-%d %H:%M").to_string();
+
+    println!("Scheduled tasks:");
+    for task in tasks {
+        println!("  {} - {}", task.name, task.expression);
+        let next = task
+            .next_run(&now)
+            .map_or_else(|| "never".to_string(), local);
+        println!("    next run: {next}");
+        if let Some(last) = state.last_due(&task.name) {
+            println!("    last due: {}", local(last.with_timezone(&Local)));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+mod allow;
+
+use clap::Subcommand;
+use cuenv_config::Config;
+use cuenv_core::{Error, Result};
+use cuenv_security::selftest::{self, ProbeStatus, SelfTestReport};
+
+#[derive(Subcommand)]
+pub enum SecurityCommands {
+    /// Attempt forbidden operations inside the sandbox and report which
+    /// protections are active on this platform
+    Selftest {
+        /// Output format (human or json)
+        #[arg(long, default_value = "human")]
+        format: String,
+    },
+
+    /// Review the access an audited run of a task used and allow it in env.cue
+    Allow {
+        /// Task whose last `cuenv task <name> --audit` run to review
+        task: String,
+
+        /// Allow everything the audit observed without asking
+        #[arg(long)]
+        write: bool,
+    },
+}
+
+impl SecurityCommands {
+    pub async fn execute(self, config: &Config) -> Result<()> {
+        match self {
+            SecurityCommands::Allow { task, write } => allow::execute(config, &task, write),
+            SecurityCommands::Selftest { format } => {
+                let report = tokio::task::spawn_blocking(selftest::run)
+                    .await
+                    .map_err(|e| Error::configuration(format!("Self-test failed: {e}")))??;
+                print_report(&report, &format)?;
+
+                let bypassed: Vec<_> = report.bypassed().map(|probe| probe.feature).collect();
+                if bypassed.is_empty() {
+                    Ok(())
+                } else {
+                    Err(Error::security(format!(
+                        "sandbox bypassed: {}",
+                        bypassed.join(", ")
+                    )))
+                }
+            }
+        }
+    }
+}
+
+fn print_report(report: &SelfTestReport, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let json = serde_json::to_string_pretty(report).map_err(|e| Error::Json {
+                message: "failed to serialize security self-test report".to_string(),
+                source: e,
+            })?;
+            println!("{json}");
+        }
+        "human" => print_human(report),
+        other => {
+            return Err(Error::configuration(format!(
+                "Invalid format '{other}'. Must be one of: human, json"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn print_human(report: &SelfTestReport) {
+    println!("Security self-test ({})", report.platform);
+    if let Some(kernel) = &report.kernel {
+        println!("  Kernel: {kernel}");
+    }
+    match report.landlock_abi {
+        Some(abi) => println!("  Landlock ABI: {abi}"),
+        None => println!("  Landlock: not available"),
+    }
+    println!("  Enforcement: {}", report.enforcement);
+    println!();
+
+    for probe in &report.probes {
+        let marker = match probe.status {
+            ProbeStatus::Active => "✓",
+            ProbeStatus::Bypassed => "✗",
+            ProbeStatus::Unsupported => "-",
+            ProbeStatus::Inconclusive => "?",
+        };
+        println!(
+            "  {marker} {:<11} {:<13} {}",
+            probe.feature, probe.status, probe.operation
+        );
+        println!("      {}", probe.detail);
+    }
+}
@@ -0,0 +1,224 @@
+//! `cuenv security allow`: grant a task the access its audit observed
+//!
+//! `cuenv task <name> --audit` keeps the report of the run. This groups its
+//! accesses, files by directory, and asks about each group on a terminal;
+//! `--write` accepts them all. The accepted paths and hosts are added to the
+//! task's `security` block in env.cue, and disk or network access becomes
+//! restricted once everything of its kind the task used is allowed.
+
+use cuenv_config::editor::grant_task_security_in_file;
+use cuenv_config::{Config, SecurityConfig};
+use cuenv_core::{Error, Result, CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME, ENV_CUE_FILENAME};
+use cuenv_security::{AccessGroup, AccessKind, AuditReport};
+use cuenv_utils::paths::get_audit_report_path;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+/// Review the audited access of `task`, writing what is accepted to env.cue
+pub fn execute(config: &Config, task: &str, write: bool) -> Result<()> {
+    let existing = config
+        .get_task(task)
+        .ok_or_else(|| Error::configuration(format!("Task '{task}' not found")))?
+        .security
+        .clone()
+        .unwrap_or_default();
+    let report_path = get_audit_report_path(&config.working_dir, task);
+    let json = std::fs::read_to_string(&report_path).map_err(|_| {
+        Error::configuration(format!(
+            "No audit of task '{task}' found; run `cuenv task {task} --audit` first"
+        ))
+    })?;
+    let report = AuditReport::from_json(&json)?;
+
+    let groups = report.access_groups();
+    let (allowed, pending): (Vec<&AccessGroup>, Vec<&AccessGroup>) = groups
+        .iter()
+        .partition(|group| already_allowed(&existing, group));
+    let mut accepted = allowed;
+
+    if pending.is_empty() {
+        println!("Everything the audit of '{task}' observed is already allowed");
+    } else if write {
+        for group in &pending {
+            print_group(group);
+        }
+        accepted.extend(&pending);
+    } else if io::stdin().is_terminal() {
+        println!(
+            "The audit of '{task}' observed {} accesses to review",
+            pending.len()
+        );
+        for (index, group) in pending.iter().enumerate() {
+            print_group(group);
+            match ask(index + 1, pending.len())? {
+                Answer::Yes => accepted.push(*group),
+                Answer::No => {}
+                Answer::All => {
+                    accepted.extend(&pending[index..]);
+                    break;
+                }
+                Answer::Quit => {
+                    println!("Nothing written");
+                    return Ok(());
+                }
+            }
+        }
+    } else {
+        for group in &pending {
+            print_group(group);
+        }
+        println!("Not running interactively; pass --write to allow all of these");
+        return Ok(());
+    }
+
+    let grant = without_existing(report.accepted_security_config(&accepted), &existing);
+    if grant == SecurityConfig::default() {
+        println!("Nothing to write");
+        return Ok(());
+    }
+    let env_file = config.working_dir.join(ENV_CUE_FILENAME);
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+    grant_task_security_in_file(&env_file, task, &grant, &package_name)?;
+
+    let count = |values: &Option<Vec<String>>| values.as_ref().map_or(0, Vec::len);
+    println!(
+        "✓ Allowed {} paths and {} hosts for '{task}' in {ENV_CUE_FILENAME}",
+        count(&grant.read_only_paths),
+        count(&grant.allowed_hosts)
+    );
+    for (restricted, what) in [
+        (grant.restrict_disk, "Disk"),
+        (grant.restrict_network, "Network"),
+    ] {
+        if restricted == Some(true) {
+            println!("  {what} access of '{task}' is now restricted to what is allowed");
+        }
+    }
+    Ok(())
+}
+
+/// Whether the task's security already allows `group`
+fn already_allowed(security: &SecurityConfig, group: &AccessGroup) -> bool {
+    match group.kind {
+        AccessKind::Path => security
+            .read_only_paths
+            .iter()
+            .chain(&security.read_write_paths)
+            .flatten()
+            .any(|path| Path::new(&group.allow).starts_with(path)),
+        AccessKind::Host => security
+            .allowed_hosts
+            .iter()
+            .flatten()
+            .any(|host| *host == group.allow),
+    }
+}
+
+/// `grant` without the restrictions and values `existing` already has
+fn without_existing(mut grant: SecurityConfig, existing: &SecurityConfig) -> SecurityConfig {
+    fn retain_new(values: &mut Option<Vec<String>>, existing: &Option<Vec<String>>) {
+        if let Some(list) = values {
+            list.retain(|value| !existing.iter().flatten().any(|have| have == value));
+        }
+        if values.as_ref().is_some_and(Vec::is_empty) {
+            *values = None;
+        }
+    }
+    retain_new(&mut grant.read_only_paths, &existing.read_only_paths);
+    retain_new(&mut grant.read_only_paths, &existing.read_write_paths);
+    retain_new(&mut grant.allowed_hosts, &existing.allowed_hosts);
+    if existing.restrict_disk == Some(true) {
+        grant.restrict_disk = None;
+    }
+    if existing.restrict_network == Some(true) {
+        grant.restrict_network = None;
+    }
+    grant
+}
+
+fn print_group(group: &AccessGroup) {
+    match group.kind {
+        AccessKind::Host => println!("\n  Host {}", group.allow),
+        AccessKind::Path if group.observed.len() > 1 => {
+            println!(
+                "\n  Path {} ({} files read)",
+                group.allow,
+                group.observed.len()
+            );
+            for path in &group.observed {
+                println!("      {path}");
+            }
+        }
+        AccessKind::Path => println!("\n  Path {}", group.allow),
+    }
+}
+
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+fn ask(index: usize, total: usize) -> Result<Answer> {
+    loop {
+        print!("  Allow? [{index}/{total}] (y)es, (n)o, (a)ll remaining, (q)uit: ");
+        io::stdout()
+            .flush()
+            .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+        let mut line = String::new();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+        if read == 0 {
+            return Ok(Answer::Quit);
+        }
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" | "" => return Ok(Answer::No),
+            "a" | "all" => return Ok(Answer::All),
+            "q" | "quit" => return Ok(Answer::Quit),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_access_is_not_granted_again() {
+        let existing = SecurityConfig {
+            restrict_network: Some(true),
+            read_write_paths: Some(vec!["/home/dev/.cache".to_string()]),
+            allowed_hosts: Some(vec!["a.example.com".to_string()]),
+            ..Default::default()
+        };
+        let cached = AccessGroup {
+            kind: AccessKind::Path,
+            allow: "/home/dev/.cache/pip".to_string(),
+            observed: vec![],
+        };
+        assert!(already_allowed(&existing, &cached));
+
+        let grant = SecurityConfig {
+            restrict_disk: Some(true),
+            restrict_network: Some(true),
+            read_only_paths: Some(vec!["/home/dev/.cache".to_string()]),
+            allowed_hosts: Some(vec![
+                "a.example.com".to_string(),
+                "b.example.com".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let grant = without_existing(grant, &existing);
+        assert_eq!(grant.restrict_disk, Some(true));
+        assert_eq!(grant.restrict_network, None);
+        assert_eq!(grant.read_only_paths, None);
+        assert_eq!(grant.allowed_hosts, Some(vec!["b.example.com".to_string()]));
+    }
+}
@@ -0,0 +1,229 @@
+//! Update settings and the release feed
+//!
+//! Settings come from the `update` section of the system config file
+//! `/etc/cuenv/config.json`, which an organisation can use to pin a channel
+//! and its own feed, overridden by `~/.config/cuenv/config.json`:
+//!
+//! ```json
+//! {
+//!   "update": {
+//!     "channel": "stable",
+//!     "feed": "https://releases.example.com/cuenv/update-feed.json",
+//!     "publicKey": "RWQ..."
+//!   }
+//! }
+//! ```
+//!
+//! The feed lists the latest release of each channel and where to download
+//! its binary and minisign signature for each target:
+//!
+//! ```json
+//! {
+//!   "channels": {
+//!     "stable": {
+//!       "version": "0.5.0",
+//!       "targets": {
+//!         "x86_64-linux": {
+//!           "url": "https://.../cuenv-x86_64-linux",
+//!           "signature": "https://.../cuenv-x86_64-linux.minisig"
+//!         }
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::xdg::XdgPaths;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CHANNEL: &str = "stable";
+pub const DEFAULT_FEED: &str =
+    "https://github.com/rawkode/cuenv/releases/latest/download/update-feed.json";
+const SYSTEM_CONFIG: &str = "/etc/cuenv/config.json";
+
+/// The `update` section of a config file
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateSettings {
+    pub channel: Option<String>,
+    pub feed: Option<String>,
+    /// Minisign public key that releases are signed with
+    pub public_key: Option<String>,
+}
+
+impl UpdateSettings {
+    /// Settings of the system config file overridden by the user's
+    pub fn load() -> Result<Self> {
+        let system = read_section(Path::new(SYSTEM_CONFIG))?;
+        let user = read_section(&XdgPaths::config_dir().join("config.json"))?;
+        Ok(system.overridden_by(user))
+    }
+
+    fn overridden_by(self, other: Self) -> Self {
+        Self {
+            channel: other.channel.or(self.channel),
+            feed: other.feed.or(self.feed),
+            public_key: other.public_key.or(self.public_key),
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        self.channel.as_deref().unwrap_or(DEFAULT_CHANNEL)
+    }
+
+    pub fn feed(&self) -> &str {
+        self.feed.as_deref().unwrap_or(DEFAULT_FEED)
+    }
+
+    /// The configured key, or the one this binary was built with
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key
+            .as_deref()
+            .or(option_env!("CUENV_UPDATE_PUBLIC_KEY"))
+    }
+}
+
+fn read_section(path: &Path) -> Result<UpdateSettings> {
+    if !path.exists() {
+        return Ok(UpdateSettings::default());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| Error::file_system(path, "read config", e))?;
+    let file: serde_json::Value = serde_json::from_str(&content).map_err(|e| Error::Json {
+        message: format!("Failed to parse {}: {e}", path.display()),
+        source: e,
+    })?;
+    match file.get("update") {
+        Some(section) => UpdateSettings::deserialize(section).map_err(|e| Error::Json {
+            message: format!("Invalid update section in {}: {e}", path.display()),
+            source: e,
+        }),
+        None => Ok(UpdateSettings::default()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Feed {
+    channels: HashMap<String, Release>,
+}
+
+/// The latest release of a channel
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub version: String,
+    targets: HashMap<String, Asset>,
+}
+
+/// Binary of a release for one target
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub url: String,
+    /// URL of the minisign signature of the binary
+    pub signature: String,
+}
+
+impl Feed {
+    pub async fn fetch(url: &str) -> Result<Self> {
+        let response = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::network(url, e.to_string()))?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::network(url, e.to_string()))?;
+        serde_json::from_str(&body).map_err(|e| Error::Json {
+            message: format!("Invalid update feed at {url}: {e}"),
+            source: e,
+        })
+    }
+
+    pub fn release(&self, channel: &str) -> Result<&Release> {
+        self.channels.get(channel).ok_or_else(|| {
+            let mut known: Vec<&str> = self.channels.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            Error::configuration(format!(
+                "Unknown update channel '{channel}' (available: {})",
+                known.join(", ")
+            ))
+        })
+    }
+}
+
+impl Release {
+    pub fn version(&self) -> Result<semver::Version> {
+        semver::Version::parse(self.version.trim_start_matches('v')).map_err(|e| {
+            Error::configuration(format!("Invalid release version '{}': {e}", self.version))
+        })
+    }
+
+    /// Binary for the platform this was built for
+    pub fn asset(&self) -> Result<&Asset> {
+        let target = target();
+        self.targets.get(&target).ok_or_else(|| {
+            Error::configuration(format!(
+                "Release {} has no binary for {target}",
+                self.version
+            ))
+        })
+    }
+}
+
+/// Feed key of this platform, such as `aarch64-macos`
+pub fn target() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// Where the download of `version` is kept next to the executable
+pub fn download_path(exe: &Path, version: &str) -> PathBuf {
+    exe.with_file_name(format!(".cuenv-{version}.download"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_settings_override_system_settings() {
+        let system = UpdateSettings {
+            channel: Some("lts".to_string()),
+            feed: Some("https://releases.example.com/feed.json".to_string()),
+            public_key: None,
+        };
+        let user = UpdateSettings {
+            channel: Some("nightly".to_string()),
+            ..UpdateSettings::default()
+        };
+        let merged = system.overridden_by(user);
+        assert_eq!(merged.channel(), "nightly");
+        assert_eq!(merged.feed(), "https://releases.example.com/feed.json");
+        assert_eq!(UpdateSettings::default().channel(), DEFAULT_CHANNEL);
+    }
+
+    #[test]
+    fn test_release_for_channel_and_target() {
+        let feed: Feed = serde_json::from_value(serde_json::json!({
+            "channels": {
+                "stable": {
+                    "version": "v1.2.3",
+                    "targets": {
+                        target(): { "url": "https://x/cuenv", "signature": "https://x/cuenv.minisig" }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let release = feed.release("stable").unwrap();
+        assert_eq!(release.version().unwrap(), semver::Version::new(1, 2, 3));
+        assert_eq!(release.asset().unwrap().url, "https://x/cuenv");
+        assert!(feed
+            .release("beta")
+            .unwrap_err()
+            .to_string()
+            .contains("available: stable"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Verifying minisign signatures of release binaries
+//!
+//! A public key is `Ed`, an 8 byte key ID and the 32 byte Ed25519 key, in
+//! base64. A `.minisig` file holds an untrusted comment, the signature (the
+//! algorithm, the key ID and 64 bytes), a trusted comment and a signature
+//! over the signature and the trusted comment. Signatures made with `ED`
+//! sign the BLAKE2b-512 hash of the file, those made with `Ed` the file
+//! itself.
+//!
+//! Release signatures name what they sign in the trusted comment, as
+//! `key:value` fields separated by tabs or spaces, e.g.
+//! `timestamp:1700000000 file:cuenv-x86_64-linux version:1.2.3`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use cuenv_core::{Error, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+
+const KEY_ID_LEN: usize = 8;
+
+/// A minisign public key
+#[derive(Debug, Clone)]
+pub struct PublicKey {
+    key_id: [u8; KEY_ID_LEN],
+    key: VerifyingKey,
+}
+
+impl PublicKey {
+    /// Parse the base64 key, or the contents of a `.pub` file
+    pub fn parse(text: &str) -> Result<Self> {
+        let bytes = decode(last_line(text), "public key")?;
+        let (algorithm, rest) = bytes.split_at_checked(2).unwrap_or((&[], &[]));
+        if algorithm != b"Ed" || rest.len() != KEY_ID_LEN + 32 {
+            return Err(invalid("public key"));
+        }
+        let (key_id, key) = rest.split_at(KEY_ID_LEN);
+        Ok(Self {
+            key_id: key_id.try_into().map_err(|_| invalid("public key"))?,
+            key: VerifyingKey::from_bytes(key.try_into().map_err(|_| invalid("public key"))?)
+                .map_err(|_| invalid("public key"))?,
+        })
+    }
+
+    /// Check that `signature`, a `.minisig` file, signs `data` with this key,
+    /// returning its trusted comment
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<String> {
+        let mut lines = signature.lines().map(str::trim);
+        let (Some(_untrusted), Some(encoded), Some(trusted), Some(global)) =
+            (lines.next(), lines.next(), lines.next(), lines.next())
+        else {
+            return Err(invalid("signature"));
+        };
+        let trusted = trusted
+            .strip_prefix("trusted comment: ")
+            .ok_or_else(|| invalid("signature"))?;
+
+        let bytes = decode(encoded, "signature")?;
+        if bytes.len() != 2 + KEY_ID_LEN + 64 {
+            return Err(invalid("signature"));
+        }
+        let (algorithm, rest) = bytes.split_at(2);
+        let (key_id, signature) = rest.split_at(KEY_ID_LEN);
+        if key_id != self.key_id {
+            return Err(Error::configuration(format!(
+                "Signature was made with key {}, not the trusted key {}",
+                hex(key_id),
+                hex(&self.key_id)
+            )));
+        }
+        let signed = match algorithm {
+            b"ED" => Blake2b512::digest(data).to_vec(),
+            b"Ed" => data.to_vec(),
+            _ => return Err(invalid("signature")),
+        };
+        self.check(&signed, signature)?;
+
+        // The trusted comment is signed too, so it cannot be swapped
+        let comment_signed = [signature, trusted.as_bytes()].concat();
+        self.check(&comment_signed, &decode(global, "signature")?)?;
+        Ok(trusted.to_string())
+    }
+
+    fn check(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        let signature = Signature::from_slice(signature).map_err(|_| invalid("signature"))?;
+        self.key
+            .verify_strict(message, &signature)
+            .map_err(|_| Error::configuration("Signature verification failed"))
+    }
+}
+
+/// The value of the field `key` of a trusted comment
+pub fn comment_field<'a>(comment: &'a str, key: &str) -> Option<&'a str> {
+    comment
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix(key)?.strip_prefix(':'))
+}
+
+/// The key or signature line of a file with comment lines
+fn last_line(text: &str) -> &str {
+    text.lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+        .unwrap_or_default()
+}
+
+fn decode(text: &str, what: &str) -> Result<Vec<u8>> {
+    STANDARD.decode(text.trim()).map_err(|_| invalid(what))
+}
+
+fn invalid(what: &str) -> Error {
+    Error::configuration(format!("Invalid minisign {what}"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .rev()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn public_key() -> String {
+        let key = [
+            b"Ed".as_slice(),
+            &KEY_ID,
+            signing_key().verifying_key().as_bytes(),
+        ]
+        .concat();
+        format!(
+            "untrusted comment: minisign public key\n{}\n",
+            STANDARD.encode(key)
+        )
+    }
+
+    fn sign(data: &[u8], trusted: &str) -> String {
+        let key = signing_key();
+        let signature = key.sign(&Blake2b512::digest(data)).to_bytes();
+        let global = key.sign(&[signature.as_slice(), trusted.as_bytes()].concat());
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {trusted}\n{}\n",
+            STANDARD.encode([b"ED".as_slice(), &KEY_ID, &signature].concat()),
+            STANDARD.encode(global.to_bytes())
+        )
+    }
+
+    #[test]
+    fn test_verify_prehashed_signature() {
+        let key = PublicKey::parse(&public_key()).unwrap();
+        let signature = sign(b"cuenv 1.2.3", "timestamp:1700000000\tfile:cuenv");
+        assert_eq!(
+            key.verify(b"cuenv 1.2.3", &signature).unwrap(),
+            "timestamp:1700000000\tfile:cuenv"
+        );
+
+        assert!(key.verify(b"cuenv 6.6.6", &signature).is_err());
+        let swapped_comment = signature.replace("file:cuenv", "file:other");
+        assert!(key.verify(b"cuenv 1.2.3", &swapped_comment).is_err());
+
+        let other = PublicKey {
+            key_id: [9; 8],
+            ..key
+        };
+        assert!(other
+            .verify(b"cuenv 1.2.3", &signature)
+            .unwrap_err()
+            .to_string()
+            .contains("not the trusted key"));
+        assert!(PublicKey::parse("RWQ=").is_err());
+    }
+
+    #[test]
+    fn test_comment_fields() {
+        let comment = "timestamp:1700000000\tfile:cuenv-x86_64-linux version:1.2.3";
+        assert_eq!(comment_field(comment, "file"), Some("cuenv-x86_64-linux"));
+        assert_eq!(comment_field(comment, "version"), Some("1.2.3"));
+        assert_eq!(comment_field(comment, "ver"), None);
+        assert_eq!(comment_field(comment, "hashed"), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! `cuenv self-update`: replace the running binary with the latest release
+//!
+//! The release of the configured channel is looked up in the update feed,
+//! its binary downloaded next to the executable, resuming an interrupted
+//! download, and its minisign signature checked against the trusted key.
+//! The feed is not signed, so the signature's trusted comment must name the
+//! version and file the feed offers, and no older version is installed.
+//! The current executable is then moved aside and the new one renamed into
+//! its place. If the new binary does not run, the old one is put back.
+
+mod feed;
+mod minisign;
+
+use cuenv_core::{Error, Result};
+use cuenv_utils::network::download::download_resumable;
+use cuenv_utils::resilience::Subsystem;
+use feed::{Feed, UpdateSettings};
+use minisign::{comment_field, PublicKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directories of installs that a package manager owns
+const MANAGED_PATHS: &[(&str, &str)] = &[
+    ("/nix/store/", "Nix"),
+    ("/Cellar/", "Homebrew"),
+    ("/homebrew/", "Homebrew"),
+];
+
+pub struct SelfUpdateOptions {
+    /// Channel to use instead of the configured one
+    pub channel: Option<String>,
+    /// Only report whether an update is available
+    pub check: bool,
+    /// Reinstall the release when it is the installed version
+    pub force: bool,
+}
+
+pub async fn execute(options: SelfUpdateOptions) -> Result<()> {
+    let settings = UpdateSettings::load()?;
+    let channel = options.channel.as_deref().unwrap_or(settings.channel());
+    let feed = Feed::fetch(settings.feed()).await?;
+    let release = feed.release(channel)?;
+    let latest = release.version()?;
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))
+        .map_err(|e| Error::configuration(format!("Invalid version of this binary: {e}")))?;
+
+    if latest < current {
+        return Err(Error::configuration(format!(
+            "Refusing to downgrade cuenv {current} to {latest}, the release the {channel} \
+             channel offers"
+        )));
+    }
+    if latest == current && !options.force {
+        println!("cuenv {current} is up to date ({channel} channel)");
+        return Ok(());
+    }
+    if options.check {
+        println!("cuenv {latest} is available ({channel} channel), you have {current}");
+        return Ok(());
+    }
+
+    let public_key = settings.public_key().ok_or_else(|| {
+        Error::configuration(
+            "No public key to verify releases with; set update.publicKey in the config file",
+        )
+    })?;
+    let public_key = PublicKey::parse(public_key)?;
+    let exe = current_exe()?;
+    let asset = release.asset()?;
+
+    println!("Downloading cuenv {latest} for {}", feed::target());
+    let download = feed::download_path(&exe, &release.version);
+    let signature_path = PathBuf::from(format!("{}.minisig", download.display()));
+    download_resumable(&asset.url, &download, None, Subsystem::Updates).await?;
+    download_resumable(&asset.signature, &signature_path, None, Subsystem::Updates).await?;
+
+    let result = verify(&public_key, &download, &signature_path)
+        .and_then(|comment| {
+            tracing::info!("Verified signature: {comment}");
+            check_signed_release(&comment, &latest, &artifact_name(&asset.url))
+        })
+        .and_then(|()| install(&download, &exe));
+    let _ = fs::remove_file(&download);
+    let _ = fs::remove_file(&signature_path);
+    result?;
+
+    println!("Updated cuenv {current} -> {latest}");
+    Ok(())
+}
+
+/// The running executable, refusing installs a package manager owns
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(|e| Error::file_system(PathBuf::from("cuenv"), "locate executable", e))?;
+    let path = exe.to_string_lossy();
+    if let Some((_, manager)) = MANAGED_PATHS.iter().find(|(dir, _)| path.contains(dir)) {
+        return Err(Error::configuration(format!(
+            "cuenv at {path} is managed by {manager}; update it with {manager} instead"
+        )));
+    }
+    Ok(exe)
+}
+
+fn verify(key: &PublicKey, binary: &Path, signature: &Path) -> Result<String> {
+    let data = fs::read(binary).map_err(|e| Error::file_system(binary, "read download", e))?;
+    let signature = fs::read_to_string(signature)
+        .map_err(|e| Error::file_system(signature, "read signature", e))?;
+    key.verify(&data, &signature)
+}
+
+/// Check that the trusted comment of a signature names `version` and `file`,
+/// so a signed binary of another release is not installed in their place
+fn check_signed_release(comment: &str, version: &semver::Version, file: &str) -> Result<()> {
+    let signed_version = comment_field(comment, "version")
+        .and_then(|signed| semver::Version::parse(signed.trim_start_matches('v')).ok());
+    if signed_version.as_ref() != Some(version) {
+        return Err(Error::configuration(format!(
+            "The signature is not for cuenv {version} (trusted comment: {comment})"
+        )));
+    }
+    if comment_field(comment, "file") != Some(file) {
+        return Err(Error::configuration(format!(
+            "The signature is not for {file} (trusted comment: {comment})"
+        )));
+    }
+    Ok(())
+}
+
+/// File name of the binary at `url`
+fn artifact_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default().to_string()
+}
+
+/// Replace `exe` with `new`, restoring `exe` if the new binary does not run
+fn install(new: &Path, exe: &Path) -> Result<()> {
+    make_executable(new)?;
+    let backup = exe.with_extension("old");
+    fs::rename(exe, &backup).map_err(|e| Error::file_system(exe, "back up executable", e))?;
+
+    let installed = fs::rename(new, exe)
+        .map_err(|e| Error::file_system(exe, "install executable", e))
+        .and_then(|()| check_runs(exe));
+    match installed {
+        Ok(()) => {
+            let _ = fs::remove_file(&backup);
+            Ok(())
+        }
+        Err(e) => {
+            fs::rename(&backup, exe)
+                .map_err(|restore| Error::file_system(exe, "restore executable", restore))?;
+            Err(e)
+        }
+    }
+}
+
+fn check_runs(exe: &Path) -> Result<()> {
+    let output = Command::new(exe).arg("--version").output().map_err(|e| {
+        Error::command_execution(exe.display().to_string(), vec![], e.to_string(), None)
+    })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::command_execution(
+            exe.display().to_string(),
+            vec!["--version".to_string()],
+            "new binary failed to run, the previous version was restored",
+            output.status.code(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| Error::file_system(path, "make executable", e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_must_name_the_offered_release() {
+        let version = semver::Version::new(1, 2, 3);
+        let file = artifact_name("https://x/v1.2.3/cuenv-x86_64-linux?download=1");
+        assert_eq!(file, "cuenv-x86_64-linux");
+
+        let signed = "timestamp:1700000000\tfile:cuenv-x86_64-linux\tversion:1.2.3";
+        assert!(check_signed_release(signed, &version, &file).is_ok());
+        // An older release, validly signed, offered as the new one
+        let older = "timestamp:1600000000\tfile:cuenv-x86_64-linux\tversion:1.0.0";
+        assert!(check_signed_release(older, &version, &file).is_err());
+        let other_file = "timestamp:1700000000\tfile:cuenv-aarch64-macos\tversion:1.2.3";
+        assert!(check_signed_release(other_file, &version, &file).is_err());
+        let unnamed = "timestamp:1700000000\tfile:cuenv-x86_64-linux";
+        assert!(check_signed_release(unnamed, &version, &file).is_err());
+    }
+}
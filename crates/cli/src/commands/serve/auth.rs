@@ -0,0 +1,120 @@
+//! Access token guarding the dashboard
+//!
+//! Every request must carry the token, either as `Authorization: Bearer
+//! <token>` or as a `token` query parameter. The query form is what the
+//! printed URL uses, and the only one `EventSource` can send.
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// Secret a client must present to use the dashboard
+#[derive(Clone)]
+pub struct AccessToken(String);
+
+impl AccessToken {
+    pub fn new(token: String) -> Self {
+        Self(token)
+    }
+
+    /// A random token for this server's lifetime
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().simple().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Compare in constant time, so response timing leaks nothing about the
+    /// token
+    pub fn matches(&self, candidate: &str) -> bool {
+        let (expected, candidate) = (self.0.as_bytes(), candidate.as_bytes());
+        expected.len() == candidate.len()
+            && expected
+                .iter()
+                .zip(candidate)
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken(..)")
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Token presented with a request, if any
+fn presented_token(request: &Request) -> Option<String> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    bearer.or_else(|| {
+        Query::<TokenQuery>::try_from_uri(request.uri())
+            .ok()
+            .and_then(|Query(query)| query.token)
+    })
+}
+
+/// Middleware rejecting requests without the dashboard's token
+pub async fn require_token(
+    State(token): State<Arc<AccessToken>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match presented_token(&request) {
+        Some(candidate) if token.matches(&candidate) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            "missing or invalid dashboard token",
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(uri: &str, authorization: Option<&str>) -> Request {
+        let builder = Request::builder().uri(uri);
+        let builder = match authorization {
+            Some(value) => builder.header(header::AUTHORIZATION, value),
+            None => builder,
+        };
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_token_from_header_or_query() {
+        let token = AccessToken::new("s3cret".to_string());
+        let presented = |uri, authorization| presented_token(&request(uri, authorization));
+
+        assert_eq!(
+            presented("/", Some("Bearer s3cret")).as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(presented("/?token=s3cret", None).as_deref(), Some("s3cret"));
+        assert_eq!(presented("/", Some("Basic s3cret")), None);
+        assert_eq!(presented("/api/state", None), None);
+
+        assert!(token.matches("s3cret"));
+        assert!(!token.matches("s3cre"));
+        assert!(!token.matches("s3creT"));
+        assert_eq!(AccessToken::generate().as_str().len(), 32);
+    }
+}
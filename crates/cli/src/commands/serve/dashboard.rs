@@ -0,0 +1,466 @@
+//! Live dashboard state built from the event stream
+//!
+//! The dashboard subscribes to the global event bus and folds task, cache and
+//! pipeline events into a snapshot the web UI polls, while forwarding each
+//! change to connected browsers as a lightweight update.
+
+use async_trait::async_trait;
+use cuenv_config::TaskConfig;
+use cuenv_core::events::{EnhancedEvent, EventSubscriber};
+use cuenv_core::{CacheEvent, Diagnostic, SystemEvent, TaskEvent};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, RwLock};
+
+/// Log lines kept for browsers that connect mid-run
+const MAX_LOG_LINES: usize = 5_000;
+
+/// Runs kept in the session history
+const MAX_RUNS: usize = 100;
+
+/// Updates buffered per browser before it has to resynchronise
+const UPDATE_CAPACITY: usize = 1_024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Running,
+    Publishing,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// A task in the DAG and how far it got in the current run
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskView {
+    pub status: TaskStatus,
+    /// ULID of the task's current run
+    pub run_id: Option<String>,
+    pub description: Option<String>,
+    pub dependencies: Vec<String>,
+    /// Milliseconds since the Unix epoch
+    pub started_at: Option<u64>,
+    pub duration_ms: Option<u64>,
+    pub progress: Option<String>,
+    pub error: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Problems the task's problem matchers found in this run
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl TaskView {
+    fn new(description: Option<String>, dependencies: Vec<String>) -> Self {
+        Self {
+            status: TaskStatus::Pending,
+            run_id: None,
+            description,
+            dependencies,
+            started_at: None,
+            duration_ms: None,
+            progress: None,
+            error: None,
+            exit_code: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(
+            self.description.take(),
+            std::mem::take(&mut self.dependencies),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    /// Position of the line in the session, so clients can page from it
+    pub seq: u64,
+    pub timestamp: u64,
+    pub task: String,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// Cache activity seen in this session
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheCounters {
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub bytes_written: u64,
+    pub evictions: u64,
+}
+
+/// A run started by this server
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    pub task: String,
+    pub started_at: u64,
+    pub duration_ms: Option<u64>,
+    /// Exit code of the run, once finished
+    pub exit_code: Option<i32>,
+}
+
+/// Everything the dashboard shows except the logs
+#[derive(Debug, Default, Serialize)]
+pub struct DashboardState {
+    pub tasks: BTreeMap<String, TaskView>,
+    pub cache: CacheCounters,
+    pub runs: VecDeque<RunRecord>,
+    #[serde(skip)]
+    logs: VecDeque<LogLine>,
+    #[serde(skip)]
+    next_seq: u64,
+}
+
+impl DashboardState {
+    /// State listing the configured tasks, none of them run yet
+    pub fn new(tasks: &HashMap<String, TaskConfig>) -> Self {
+        let tasks = tasks
+            .iter()
+            .map(|(name, task)| {
+                let view = TaskView::new(
+                    task.description.clone(),
+                    task.dependencies.clone().unwrap_or_default(),
+                );
+                (name.clone(), view)
+            })
+            .collect();
+        Self {
+            tasks,
+            ..Default::default()
+        }
+    }
+
+    /// Fold an event into the state, returning the log line it produced
+    pub fn apply(&mut self, event: &SystemEvent, timestamp: u64) -> Option<LogLine> {
+        match event {
+            SystemEvent::Task(event) => self.apply_task(event, timestamp),
+            SystemEvent::Cache(event) => {
+                self.apply_cache(event);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn apply_task(&mut self, event: &TaskEvent, timestamp: u64) -> Option<LogLine> {
+        match event {
+            TaskEvent::TaskStarted { task_name, task_id } => {
+                let task = self.task(task_name);
+                task.reset();
+                task.status = TaskStatus::Running;
+                task.run_id = Some(task_id.clone());
+                task.started_at = Some(timestamp);
+            }
+            TaskEvent::TaskCompleted {
+                task_name,
+                duration_ms,
+                ..
+            } => {
+                let task = self.task(task_name);
+                task.status = TaskStatus::Succeeded;
+                task.duration_ms = Some(*duration_ms);
+                task.exit_code = Some(0);
+            }
+            TaskEvent::TaskFailed {
+                task_name,
+                error,
+                exit_status,
+                ..
+            } => {
+                let task = self.task(task_name);
+                task.status = TaskStatus::Failed;
+                task.duration_ms = task.started_at.map(|start| timestamp.saturating_sub(start));
+                task.error = Some(error.clone());
+                task.exit_code = exit_status.map(|status| status.code());
+            }
+            TaskEvent::TaskProgress {
+                task_name, message, ..
+            } => self.task(task_name).progress = Some(message.clone()),
+            TaskEvent::TaskPublishing { task_name, .. } => {
+                self.task(task_name).status = TaskStatus::Publishing
+            }
+            TaskEvent::TaskDiagnostic {
+                task_name,
+                diagnostic,
+                ..
+            } => self.task(task_name).diagnostics.push(diagnostic.clone()),
+            TaskEvent::TaskDeprecated {
+                task_name, message, ..
+            } => return Some(self.log(task_name, LogStream::Stderr, message, timestamp)),
+            TaskEvent::TaskStubbed {
+                task_name, command, ..
+            } => {
+                let line = format!("stubbed: {command}");
+                return Some(self.log(task_name, LogStream::Stderr, &line, timestamp));
+            }
+            TaskEvent::TaskSkipped {
+                task_name, reason, ..
+            } => {
+                let task = self.task(task_name);
+                task.status = TaskStatus::Skipped;
+                task.progress = Some(reason.clone());
+            }
+            TaskEvent::TaskOutput {
+                task_name, output, ..
+            } => return Some(self.log(task_name, LogStream::Stdout, output, timestamp)),
+            TaskEvent::TaskError {
+                task_name, error, ..
+            } => return Some(self.log(task_name, LogStream::Stderr, error, timestamp)),
+        }
+        None
+    }
+
+    fn apply_cache(&mut self, event: &CacheEvent) {
+        let cache = &mut self.cache;
+        match event {
+            CacheEvent::CacheHit { .. } => cache.hits += 1,
+            CacheEvent::CacheMiss { .. } => cache.misses += 1,
+            CacheEvent::CacheWrite { size_bytes, .. } => {
+                cache.writes += 1;
+                cache.bytes_written += size_bytes;
+            }
+            CacheEvent::CacheEvict { .. } => cache.evictions += 1,
+        }
+    }
+
+    /// The task named in an event, added on first sight when it is not part
+    /// of the configured DAG (e.g. a cross-package dependency)
+    fn task(&mut self, name: &str) -> &mut TaskView {
+        self.tasks
+            .entry(name.to_string())
+            .or_insert_with(|| TaskView::new(None, Vec::new()))
+    }
+
+    fn log(&mut self, task: &str, stream: LogStream, line: &str, timestamp: u64) -> LogLine {
+        let line = LogLine {
+            seq: self.next_seq,
+            timestamp,
+            task: task.to_string(),
+            stream,
+            line: line.trim_end_matches(['\r', '\n']).to_string(),
+        };
+        self.next_seq += 1;
+        if self.logs.len() == MAX_LOG_LINES {
+            self.logs.pop_front();
+        }
+        self.logs.push_back(line.clone());
+        line
+    }
+
+    /// Log lines after `after`, optionally only those of one task
+    pub fn logs(&self, after: Option<u64>, task: Option<&str>) -> Vec<LogLine> {
+        self.logs
+            .iter()
+            .filter(|line| after.is_none_or(|after| line.seq > after))
+            .filter(|line| task.is_none_or(|task| line.task == task))
+            .cloned()
+            .collect()
+    }
+
+    /// Record the start of a run, resetting the tasks of the previous one
+    pub fn start_run(&mut self, task: &str, timestamp: u64) {
+        self.tasks.values_mut().for_each(TaskView::reset);
+        if self.runs.len() == MAX_RUNS {
+            self.runs.pop_front();
+        }
+        self.runs.push_back(RunRecord {
+            task: task.to_string(),
+            started_at: timestamp,
+            duration_ms: None,
+            exit_code: None,
+        });
+    }
+
+    /// Record how the latest run ended
+    pub fn finish_run(&mut self, exit_code: i32, timestamp: u64) {
+        if let Some(run) = self.runs.back_mut() {
+            run.duration_ms = Some(timestamp.saturating_sub(run.started_at));
+            run.exit_code = Some(exit_code);
+        }
+    }
+}
+
+/// Change pushed to connected browsers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Update {
+    /// A new log line
+    Log(LogLine),
+    /// The snapshot changed and should be fetched again
+    State,
+}
+
+/// Shared dashboard, fed by the event bus and read by the web server
+pub struct Dashboard {
+    state: RwLock<DashboardState>,
+    updates: broadcast::Sender<Update>,
+}
+
+impl Dashboard {
+    pub fn new(state: DashboardState) -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_CAPACITY);
+        Self {
+            state: RwLock::new(state),
+            updates,
+        }
+    }
+
+    pub fn state(&self) -> &RwLock<DashboardState> {
+        &self.state
+    }
+
+    /// Receive every change from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Update> {
+        self.updates.subscribe()
+    }
+
+    pub async fn start_run(&self, task: &str) {
+        self.state
+            .write()
+            .await
+            .start_run(task, unix_millis(SystemTime::now()));
+        self.notify(Update::State);
+    }
+
+    pub async fn finish_run(&self, exit_code: i32) {
+        self.state
+            .write()
+            .await
+            .finish_run(exit_code, unix_millis(SystemTime::now()));
+        self.notify(Update::State);
+    }
+
+    fn notify(&self, update: Update) {
+        // Nobody may be watching yet
+        let _ = self.updates.send(update);
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for Dashboard {
+    async fn handle_event(
+        &self,
+        event: &EnhancedEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let timestamp = unix_millis(event.timestamp);
+        let line = self.state.write().await.apply(&event.event, timestamp);
+        self.notify(line.map_or(Update::State, Update::Log));
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "web_dashboard"
+    }
+
+    fn is_interested(&self, event: &SystemEvent) -> bool {
+        matches!(event, SystemEvent::Task(_) | SystemEvent::Cache(_))
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_core::ExitStatus;
+
+    fn task_event(event: TaskEvent) -> SystemEvent {
+        SystemEvent::Task(event)
+    }
+
+    fn state() -> DashboardState {
+        let tasks: HashMap<String, TaskConfig> = serde_json::from_value(serde_json::json!({
+            "build": {"description": "Compile"},
+            "test": {"dependencies": ["build"]}
+        }))
+        .unwrap();
+        DashboardState::new(&tasks)
+    }
+
+    #[test]
+    fn test_task_events_update_the_dag() {
+        let mut state = state();
+        assert_eq!(state.tasks["test"].dependencies, ["build"]);
+
+        let started = TaskEvent::TaskStarted {
+            task_name: "build".into(),
+            task_id: "1".into(),
+        };
+        state.apply(&task_event(started), 1_000);
+        assert_eq!(state.tasks["build"].status, TaskStatus::Running);
+
+        let completed = TaskEvent::TaskCompleted {
+            task_name: "build".into(),
+            task_id: "1".into(),
+            duration_ms: 250,
+        };
+        state.apply(&task_event(completed), 1_250);
+        let failed = TaskEvent::TaskFailed {
+            task_name: "test".into(),
+            task_id: "2".into(),
+            error: "2 tests failed".into(),
+            exit_status: Some(ExitStatus::Code(101)),
+        };
+        state.apply(&task_event(failed), 2_000);
+
+        let build = &state.tasks["build"];
+        assert_eq!(build.status, TaskStatus::Succeeded);
+        assert_eq!(build.run_id.as_deref(), Some("1"));
+        assert_eq!(build.duration_ms, Some(250));
+        assert_eq!(build.description.as_deref(), Some("Compile"));
+        let test = &state.tasks["test"];
+        assert_eq!(test.status, TaskStatus::Failed);
+        assert_eq!(test.exit_code, Some(101));
+
+        state.start_run("test", 3_000);
+        assert_eq!(state.tasks["build"].status, TaskStatus::Pending);
+        assert_eq!(state.tasks["test"].dependencies, ["build"]);
+    }
+
+    #[test]
+    fn test_output_and_cache_events() {
+        let mut state = state();
+        for (task, output) in [
+            ("build", "compiling\n"),
+            ("test", "running"),
+            ("build", "done"),
+        ] {
+            let event = TaskEvent::TaskOutput {
+                task_name: task.into(),
+                task_id: "1".into(),
+                output: output.into(),
+            };
+            assert!(state.apply(&task_event(event), 0).is_some());
+        }
+        state.apply(
+            &SystemEvent::Cache(CacheEvent::CacheHit { key: "k".into() }),
+            0,
+        );
+
+        let lines: Vec<_> = state
+            .logs(Some(0), Some("build"))
+            .into_iter()
+            .map(|line| line.line)
+            .collect();
+        assert_eq!(lines, ["done"]);
+        assert_eq!(state.logs(None, None)[0].line, "compiling");
+        assert_eq!(state.cache.hits, 1);
+    }
+}
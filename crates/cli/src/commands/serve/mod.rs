@@ -0,0 +1,98 @@
+//! `cuenv serve --web`: a web dashboard for task runs
+//!
+//! The dashboard shows the task DAG with live status, task logs, cache
+//! statistics and the runs started by the server. It is fed by the global
+//! event bus, so it sees everything the tasks of this process publish. Every
+//! request needs the access token printed at startup.
+
+mod auth;
+mod dashboard;
+mod routes;
+
+use self::auth::AccessToken;
+use self::dashboard::{Dashboard, DashboardState};
+use cuenv_cache::{CacheConfig, CacheManager};
+use cuenv_config::Config;
+use cuenv_core::events::register_global_subscriber;
+use cuenv_core::{Error, Result, CUENV_WEB_TOKEN_VAR};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Options of `cuenv serve`
+pub struct ServeOptions {
+    pub web: bool,
+    pub host: String,
+    pub port: u16,
+    pub token: Option<String>,
+    pub environment: Option<String>,
+    pub capabilities: Vec<String>,
+    pub audit: bool,
+    /// Stop serving once the task finishes
+    pub exit: bool,
+    pub task: Option<String>,
+    pub args: Vec<String>,
+}
+
+pub async fn execute(config: Arc<Config>, options: ServeOptions) -> Result<()> {
+    if !options.web {
+        return Err(Error::configuration(
+            "Nothing to serve: pass --web to start the web dashboard",
+        ));
+    }
+
+    let token = options
+        .token
+        .or_else(|| std::env::var(CUENV_WEB_TOKEN_VAR).ok())
+        .map(AccessToken::new)
+        .unwrap_or_else(AccessToken::generate);
+    let dashboard = Arc::new(Dashboard::new(DashboardState::new(config.get_tasks())));
+    register_global_subscriber(dashboard.clone()).await;
+    let history = CacheManager::new(CacheConfig::default()).await?.history();
+
+    let address = format!("{}:{}", options.host, options.port);
+    let listener = TcpListener::bind(&address)
+        .await
+        .map_err(|e| Error::configuration(format!("Failed to listen on {address}: {e}")))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| Error::configuration(format!("Failed to read listening address: {e}")))?;
+    eprintln!("Dashboard: http://{address}/?token={}", token.as_str());
+
+    let app = routes::router(dashboard.clone(), history, token);
+    let server = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            eprintln!("Dashboard server failed: {e}");
+        }
+    });
+
+    let mut exit_code = 0;
+    if let Some(task) = options.task {
+        dashboard.start_run(&task).await;
+        exit_code = crate::commands::task::run_tasks(
+            options.environment,
+            options.capabilities,
+            std::slice::from_ref(&task),
+            &options.args,
+            options.audit,
+            "simple",
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            1
+        });
+        dashboard.finish_run(exit_code).await;
+    }
+
+    if !options.exit {
+        eprintln!("Dashboard still serving at http://{address}/, press Ctrl-C to stop");
+        let _ = tokio::signal::ctrl_c().await;
+    }
+    // Open event streams never finish, so there is nothing to drain
+    server.abort();
+
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! HTTP routes of the web dashboard
+//!
+//! - `GET /` - the dashboard page
+//! - `GET /api/state` - tasks with their dependencies and status, cache
+//!   counters and the runs of this session
+//! - `GET /api/logs?after=<seq>&task=<name>` - buffered log lines
+//! - `GET /api/events` - server-sent stream of log lines and state changes
+//! - `GET /api/cache/history?since=<duration>` - persisted cache statistics
+
+use super::auth::{require_token, AccessToken};
+use super::dashboard::{Dashboard, Update};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use cuenv_cache::manager::history::{since_window, summarize};
+use cuenv_cache::manager::StatsHistory;
+use futures::Stream;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Default look-back window of the cache history
+const DEFAULT_HISTORY_WINDOW: &str = "7d";
+
+#[derive(Clone)]
+struct AppState {
+    dashboard: Arc<Dashboard>,
+    history: Arc<StatsHistory>,
+}
+
+/// Router serving `dashboard`, requiring `token` on every route
+pub fn router(dashboard: Arc<Dashboard>, history: StatsHistory, token: AccessToken) -> Router {
+    let state = AppState {
+        dashboard,
+        history: Arc::new(history),
+    };
+    Router::new()
+        .route("/", get(index))
+        .route("/api/state", get(snapshot))
+        .route("/api/logs", get(logs))
+        .route("/api/events", get(events))
+        .route("/api/cache/history", get(cache_history))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::new(token),
+            require_token,
+        ))
+        .with_state(state)
+}
+
+async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn snapshot(State(app): State<AppState>) -> Response {
+    Json(&*app.dashboard.state().read().await).into_response()
+}
+
+#[derive(Deserialize)]
+struct LogsQuery {
+    after: Option<u64>,
+    task: Option<String>,
+}
+
+async fn logs(State(app): State<AppState>, Query(query): Query<LogsQuery>) -> Response {
+    let state = app.dashboard.state().read().await;
+    Json(state.logs(query.after, query.task.as_deref())).into_response()
+}
+
+async fn events(
+    State(app): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    // A browser that fell behind resynchronises from the next snapshot
+    let updates = BroadcastStream::new(app.dashboard.subscribe())
+        .map(|update| Event::default().json_data(update.unwrap_or(Update::State)));
+    Sse::new(updates).keep_alive(KeepAlive::default())
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    since: Option<String>,
+}
+
+async fn cache_history(State(app): State<AppState>, Query(query): Query<HistoryQuery>) -> Response {
+    let since = query.since.as_deref().unwrap_or(DEFAULT_HISTORY_WINDOW);
+    let trend = cuenv_utils::parse_duration(since).and_then(|window| {
+        let start = since_window(window);
+        let entries = app.history.load_since(start)?;
+        Ok(summarize(&entries, start, SystemTime::now()))
+    });
+    match trend {
+        Ok(trend) => Json(trend).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,199 @@
+//! `cuenv setup`: first-run installation of the shell hook and completions
+
+mod rc;
+
+use crate::platform::{PlatformOps, Shell};
+use cuenv_core::{Error, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::platform::UnixPlatform as Platform;
+#[cfg(windows)]
+use crate::platform::WindowsPlatform as Platform;
+
+/// How long the verification shell may take to start
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Add (or with `remove`, take out) the cuenv block in the shell's rc file
+pub async fn execute(
+    shell: Option<String>,
+    rc_file: Option<PathBuf>,
+    yes: bool,
+    no_completions: bool,
+    remove: bool,
+) -> Result<()> {
+    let shell = match shell {
+        Some(name) => name.parse::<Shell>().map_err(Error::configuration)?,
+        None => Platform::get_current_shell().map_err(Error::configuration)?,
+    };
+    if !shell.is_unix() {
+        return Err(Error::configuration(format!(
+            "cuenv setup supports bash, zsh and fish; for {} see the shell integration guide",
+            shell.as_str()
+        )));
+    }
+
+    let default_rc = rc::default_rc_file(shell);
+    let path = rc_file
+        .or_else(|| default_rc.clone())
+        .ok_or_else(|| Error::configuration("Could not determine your home directory"))?;
+    let original = match std::fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(Error::file_system(&path, "read shell rc file", e)),
+    };
+    let content = original.as_deref().unwrap_or_default();
+
+    if remove {
+        let updated = rc::remove_block(content);
+        if updated == content {
+            println!("No cuenv block found in {}", path.display());
+        } else {
+            write_rc(&path, &updated)?;
+            println!("Removed the cuenv block from {}", path.display());
+        }
+        return Ok(());
+    }
+
+    let hook = !rc::has_manual_hook(content);
+    if !hook {
+        println!(
+            "{} already loads the cuenv hook; leaving that line alone",
+            path.display()
+        );
+    }
+    let lines = rc::block_lines(shell, hook, !no_completions);
+    let updated = if lines.is_empty() {
+        rc::remove_block(content)
+    } else {
+        rc::upsert_block(content, &lines)
+    };
+    if updated == content {
+        println!("{} is already set up for cuenv", path.display());
+        return Ok(());
+    }
+
+    if !yes {
+        println!("cuenv will add the following to {}:\n", path.display());
+        for line in &lines {
+            println!("    {line}");
+        }
+        println!();
+        if !confirm("Proceed?")? {
+            println!("Nothing changed");
+            return Ok(());
+        }
+    }
+
+    if let Some(original) = &original {
+        let backup = backup_path(&path);
+        std::fs::write(&backup, original)
+            .map_err(|e| Error::file_system(&backup, "back up shell rc file", e))?;
+    }
+    write_rc(&path, &updated)?;
+
+    if let Err(e) = verify(shell, &path, default_rc.as_deref() == Some(&path)).await {
+        match &original {
+            Some(original) => write_rc(&path, original)?,
+            None => std::fs::remove_file(&path)
+                .map_err(|e| Error::file_system(&path, "remove shell rc file", e))?,
+        }
+        return Err(Error::configuration(format!(
+            "A new {} did not load the cuenv hook ({e}); {} was restored. \
+             Make sure `cuenv` is on the PATH that {} starts with",
+            shell.as_str(),
+            path.display(),
+            shell.as_str()
+        )));
+    }
+
+    println!("Updated {}", path.display());
+    if original.is_some() {
+        println!("Previous version saved to {}", backup_path(&path).display());
+    }
+    println!(
+        "Open a new terminal, or run `source {}`, to start using cuenv",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Ask a yes/no question, refusing to guess when nobody can answer
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::configuration(
+            "Not running interactively; pass --yes to modify the rc file",
+        ));
+    }
+    print!("{question} [y/N] ");
+    io::stdout()
+        .flush()
+        .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".cuenv-backup");
+    path.with_file_name(name)
+}
+
+fn write_rc(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| Error::file_system(parent, "create directory", e))?;
+    }
+    std::fs::write(path, content).map_err(|e| Error::file_system(path, "write shell rc file", e))
+}
+
+/// Start an interactive shell that reads `path` and check the hook is defined
+///
+/// Shells read their default rc file on their own; any other file is
+/// sourced explicitly so the check covers what was just written.
+async fn verify(shell: Shell, path: &Path, is_default: bool) -> std::result::Result<(), String> {
+    let mut command = Command::new(shell.as_str());
+    let check = match shell {
+        Shell::Fish => "functions -q _cuenv_hook",
+        _ => "type _cuenv_hook >/dev/null 2>&1",
+    };
+    match shell {
+        Shell::Bash => {
+            command.arg("--rcfile").arg(path).args(["-i", "-c", check]);
+        }
+        _ if is_default => {
+            command.args(["-i", "-c", check]);
+        }
+        _ => {
+            command.env("CUENV_SETUP_RC", path).args([
+                "-i",
+                "-c",
+                &format!("source \"$CUENV_SETUP_RC\"; {check}"),
+            ]);
+        }
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let status = tokio::time::timeout(VERIFY_TIMEOUT, command.status())
+        .await
+        .map_err(|_| format!("timed out after {}s", VERIFY_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("failed to start {}: {e}", shell.as_str()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("the _cuenv_hook function was not defined".to_string())
+    }
+}
@@ -0,0 +1,147 @@
+//! The managed cuenv block in a shell rc file
+//!
+//! Everything `cuenv setup` writes lives between [`BEGIN_MARKER`] and
+//! [`END_MARKER`], so running setup again replaces the block in place and
+//! `--remove` takes out exactly what was added.
+
+use crate::platform::Shell;
+use std::ops::Range;
+use std::path::PathBuf;
+
+pub(super) const BEGIN_MARKER: &str = "# >>> cuenv >>>";
+pub(super) const END_MARKER: &str = "# <<< cuenv <<<";
+
+/// Rc file the shell reads at interactive startup
+pub(super) fn default_rc_file(shell: Shell) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    match shell {
+        // Terminal.app starts login shells, which skip ~/.bashrc
+        Shell::Bash if cfg!(target_os = "macos") => Some(home.join(".bash_profile")),
+        Shell::Bash => Some(home.join(".bashrc")),
+        Shell::Zsh => {
+            let dir = std::env::var_os("ZDOTDIR").map_or(home, PathBuf::from);
+            Some(dir.join(".zshrc"))
+        }
+        Shell::Fish => {
+            let dir = std::env::var_os("XDG_CONFIG_HOME")
+                .map_or_else(|| home.join(".config"), PathBuf::from);
+            Some(dir.join("fish").join("config.fish"))
+        }
+        Shell::Pwsh | Shell::Cmd => None,
+    }
+}
+
+/// Lines to place inside the managed block
+pub(super) fn block_lines(shell: Shell, hook: bool, completions: bool) -> Vec<String> {
+    let name = shell.as_str();
+    let load = |command: &str| match shell {
+        Shell::Fish => format!("cuenv {command} {name} | source"),
+        _ => format!("eval \"$(cuenv {command} {name})\""),
+    };
+    [(hook, "shell init"), (completions, "completion")]
+        .into_iter()
+        .filter(|(wanted, _)| *wanted)
+        .map(|(_, command)| load(command))
+        .collect()
+}
+
+/// Whether the hook is already loaded by a line outside the managed block
+pub(super) fn has_manual_hook(content: &str) -> bool {
+    remove_block(content)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| line.contains("cuenv shell init") || line.contains("cuenv init"))
+}
+
+/// `content` with the managed block holding `lines`, added at the end if absent
+pub(super) fn upsert_block(content: &str, lines: &[String]) -> String {
+    let block = std::iter::once(BEGIN_MARKER.to_string())
+        .chain(std::iter::once(
+            "# Added by `cuenv setup`; remove with `cuenv setup --remove`".to_string(),
+        ))
+        .chain(lines.iter().cloned())
+        .chain(std::iter::once(format!("{END_MARKER}\n")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match block_range(content) {
+        Some(range) => {
+            let mut updated = content.to_string();
+            updated.replace_range(range, &block);
+            updated
+        }
+        None if content.is_empty() => block,
+        None if content.ends_with('\n') => format!("{content}\n{block}"),
+        None => format!("{content}\n\n{block}"),
+    }
+}
+
+/// `content` without the managed block and the blank line that separated it
+pub(super) fn remove_block(content: &str) -> String {
+    match block_range(content) {
+        Some(range) => {
+            let before = &content[..range.start];
+            let before = before
+                .strip_suffix('\n')
+                .filter(|rest| rest.is_empty() || rest.ends_with('\n'))
+                .unwrap_or(before);
+            format!("{before}{}", &content[range.end..])
+        }
+        None => content.to_string(),
+    }
+}
+
+/// Byte range of the managed block, including its final newline
+fn block_range(content: &str) -> Option<Range<usize>> {
+    let start = content.find(BEGIN_MARKER)?;
+    let end = start + content[start..].find(END_MARKER)? + END_MARKER.len();
+    let end = if content[end..].starts_with('\n') {
+        end + 1
+    } else {
+        end
+    };
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_is_appended_replaced_and_removed() {
+        let original = "export PATH=$HOME/bin:$PATH\n";
+        let lines = block_lines(Shell::Bash, true, true);
+        assert_eq!(
+            lines,
+            [
+                "eval \"$(cuenv shell init bash)\"",
+                "eval \"$(cuenv completion bash)\""
+            ]
+        );
+
+        let installed = upsert_block(original, &lines);
+        assert!(installed.starts_with(original));
+        assert!(installed.ends_with(&format!("{}\n{END_MARKER}\n", lines[1])));
+        assert_eq!(upsert_block(&installed, &lines), installed);
+
+        let hook_only = upsert_block(&installed, &block_lines(Shell::Bash, true, false));
+        assert_eq!(hook_only.matches(BEGIN_MARKER).count(), 1);
+        assert!(!hook_only.contains("cuenv completion"));
+
+        assert_eq!(remove_block(&installed), original);
+        assert_eq!(remove_block(&upsert_block("", &lines)), "");
+    }
+
+    #[test]
+    fn test_manual_hook_outside_block() {
+        let fish = block_lines(Shell::Fish, true, false);
+        assert_eq!(fish, ["cuenv shell init fish | source"]);
+        assert!(!has_manual_hook(&upsert_block(
+            "set -x EDITOR vim\n",
+            &fish
+        )));
+        assert!(has_manual_hook("cuenv shell init fish | source\n"));
+        assert!(!has_manual_hook("# eval \"$(cuenv shell init zsh)\"\n"));
+    }
+}
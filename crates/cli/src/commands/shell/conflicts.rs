@@ -0,0 +1,122 @@
+//! Variables changed by hand while an environment was loaded
+//!
+//! Unloading restores variables to their values before the load, which
+//! would throw away values set in the shell since. On a terminal the hook
+//! asks whether to keep them; otherwise `CUENV_UNLOAD_CONFLICTS` decides,
+//! keeping them unless it is `restore`.
+
+use cuenv_core::{Error, Result, CUENV_UNLOAD_CONFLICTS_VAR};
+use cuenv_env::Divergence;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+/// What to do with the changed variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Ask,
+    Preserve,
+    Restore,
+}
+
+impl Policy {
+    fn from_env() -> Result<Self> {
+        match std::env::var(CUENV_UNLOAD_CONFLICTS_VAR).as_deref() {
+            Err(_) | Ok("") | Ok("ask") => Ok(Self::Ask),
+            Ok("preserve") => Ok(Self::Preserve),
+            Ok("restore") => Ok(Self::Restore),
+            Ok(other) => Err(Error::configuration(format!(
+                "Invalid {CUENV_UNLOAD_CONFLICTS_VAR} '{other}'. Expected ask, preserve or restore"
+            ))),
+        }
+    }
+}
+
+/// The variables among `divergences` to leave as they are when unloading,
+/// asking when `interactive`
+pub fn resolve(
+    divergences: &[Divergence],
+    interactive: bool,
+    notices: &mut Vec<String>,
+) -> Result<HashSet<String>> {
+    if divergences.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let keys = divergences
+        .iter()
+        .map(|d| d.key.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let preserve = match Policy::from_env()? {
+        Policy::Ask if interactive => ask(&keys, divergences)?,
+        Policy::Ask | Policy::Preserve => {
+            notices.push(format!(
+                "# cuenv: Kept {keys}, changed since the environment was loaded"
+            ));
+            true
+        }
+        Policy::Restore => false,
+    };
+    Ok(if preserve {
+        divergences.iter().map(|d| d.key.clone()).collect()
+    } else {
+        HashSet::new()
+    })
+}
+
+/// Ask until answered, returning whether to preserve the variables
+fn ask(keys: &str, divergences: &[Divergence]) -> Result<bool> {
+    eprintln!("# cuenv: {keys} changed since the environment was loaded");
+    loop {
+        eprint!("[p]reserve your values, [r]estore them, or show a [d]iff? [P/r/d] ");
+        io::stderr()
+            .flush()
+            .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+        let mut line = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+        match line.trim().to_lowercase().as_str() {
+            "r" | "restore" => return Ok(false),
+            "d" | "diff" => divergences
+                .iter()
+                .for_each(|d| eprintln!("{}", describe(d))),
+            _ => return Ok(true),
+        }
+    }
+}
+
+/// A variable's loaded, current and restored values, one line
+fn describe(divergence: &Divergence) -> String {
+    let value = |value: &Option<String>| match value {
+        Some(value) => format!("{value:?}"),
+        None => "unset".to_string(),
+    };
+    format!(
+        "  {}: loaded {}, now {}, restores to {}",
+        divergence.key,
+        value(&divergence.expected),
+        value(&divergence.current),
+        value(&divergence.original)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let divergence = Divergence {
+            key: "EDITOR".to_string(),
+            expected: Some("nano".to_string()),
+            current: Some("emacs".to_string()),
+            original: None,
+        };
+        assert_eq!(
+            describe(&divergence),
+            r#"  EDITOR: loaded "nano", now "emacs", restores to unset"#
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! The shell hook, run before every prompt
+//!
+//! The async zsh and fish hooks run it in the background with `--deferred`,
+//! so it writes its output to a file instead. The file appears complete or
+//! not at all, and the shell sources it once it exists.
+
+use super::conflicts;
+use crate::directory::DirectoryManager;
+use crate::platform::{PlatformOps, Shell};
+use cuenv_core::{Result, CUENV_ENV_VAR, CUENV_SCOPED_VAR, CUENV_SESSION_VAR, ENV_CUE_FILENAME};
+use cuenv_env::manager::environment::{compose, SupervisorMode};
+use cuenv_env::state::session::ShellSession;
+use cuenv_env::{EnvManager, StateManager};
+use cuenv_shell::ShellType;
+use cuenv_utils::hook_latency::{HookLatencyLog, HookLatencySample};
+use cuenv_utils::sync::SyncEnv;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::time::Instant;
+
+#[cfg(unix)]
+use crate::platform::UnixPlatform as Platform;
+#[cfg(windows)]
+use crate::platform::WindowsPlatform as Platform;
+
+/// What the hook prints: shell commands on stdout, notices on stderr
+#[derive(Default)]
+struct HookOutput {
+    commands: Vec<String>,
+    notices: Vec<String>,
+}
+
+impl HookOutput {
+    fn print(&self) {
+        self.notices.iter().for_each(|notice| eprintln!("{notice}"));
+        self.commands
+            .iter()
+            .for_each(|command| println!("{command}"));
+    }
+
+    /// Write the output as a script to `path`, in a single rename
+    fn write_deferred(&self, shell: &dyn cuenv_shell::Shell, path: &Path) -> Result<()> {
+        let script: String = self
+            .notices
+            .iter()
+            .map(|notice| format!("printf '%s\\n' {} >&2", shell.escape(notice)))
+            .chain(self.commands.iter().cloned())
+            .map(|line| line + "\n")
+            .collect();
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut temp = tempfile::NamedTempFile::new_in(dir)
+            .map_err(|e| cuenv_core::Error::file_system(dir, "create deferred hook output", e))?;
+        temp.write_all(script.as_bytes()).map_err(|e| {
+            cuenv_core::Error::file_system(temp.path(), "write deferred hook output", e)
+        })?;
+        temp.persist(path).map_err(|e| {
+            cuenv_core::Error::file_system(path, "write deferred hook output", e.error)
+        })?;
+        Ok(())
+    }
+}
+
+/// Run the hook and record how long it took; with `deferred`, write its
+/// output to that file instead of printing it
+pub async fn execute(shell: Option<String>, deferred: Option<&Path>) -> Result<()> {
+    let shell_impl = shell_type(shell).as_shell();
+    // Scoped subshells keep the environment they were started with
+    let output = if env::var_os(CUENV_SCOPED_VAR).is_some() {
+        HookOutput::default()
+    } else {
+        let started = Instant::now();
+        let current_dir = env::current_dir()?;
+        // Only a hook printing straight to the shell can ask questions
+        let interactive =
+            deferred.is_none() && io::stdin().is_terminal() && io::stderr().is_terminal();
+        let result = run(shell_impl.as_ref(), &current_dir, interactive).await;
+        record_latency(&current_dir, started);
+        result?
+    };
+    match deferred {
+        Some(path) => output.write_deferred(shell_impl.as_ref(), path),
+        None => {
+            output.print();
+            Ok(())
+        }
+    }
+}
+
+fn record_latency(directory: &Path, started: Instant) {
+    let sample = HookLatencySample::new(directory, started.elapsed());
+    if let Err(e) = HookLatencyLog::for_user().record(sample) {
+        tracing::debug!("Failed to record shell hook latency: {e}");
+    }
+}
+
+/// Name the state records for a load without a named environment
+const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// The environment the loaded state was loaded with
+fn loaded_environment() -> String {
+    StateManager::get_state()
+        .ok()
+        .flatten()
+        .and_then(|state| state.environment)
+        .unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string())
+}
+
+fn shell_type(shell: Option<String>) -> ShellType {
+    match shell {
+        Some(s) => ShellType::from_name(&s),
+        None => {
+            if let Some(arg0) = env::args().next() {
+                ShellType::detect_from_arg(&arg0)
+            } else {
+                match Platform::get_current_shell() {
+                    Ok(Shell::Bash) => ShellType::Bash,
+                    Ok(Shell::Zsh) => ShellType::Zsh,
+                    Ok(Shell::Fish) => ShellType::Fish,
+                    Ok(Shell::Pwsh) => ShellType::PowerShell,
+                    Ok(Shell::Cmd) => ShellType::Cmd,
+                    _ => ShellType::Bash,
+                }
+            }
+        }
+    }
+}
+
+async fn run(
+    shell_impl: &dyn cuenv_shell::Shell,
+    current_dir: &Path,
+    interactive: bool,
+) -> Result<HookOutput> {
+    if !crate::commands::cache::maintenance_on_prompt() {
+        crate::commands::env::prune_on_prompt().await;
+    }
+
+    // Set environment variable to indicate we're in shell hook mode
+    SyncEnv::set_var("CUENV_SHELL_HOOK", "1")?;
+
+    let mut output = HookOutput::default();
+
+    // Give the shell a session, for `cuenv env use` to choose its environment
+    let session = ShellSession::current().unwrap_or_else(|| {
+        let session = ShellSession::start();
+        output
+            .commands
+            .push(shell_impl.export(CUENV_SESSION_VAR, session.id()));
+        session
+    });
+    let selected = session.environment();
+
+    // Check if we need to unload (directory changed)
+    let should_unload = StateManager::should_unload(current_dir);
+    // or reload (`cuenv env use` chose another environment)
+    let switched = !should_unload
+        && StateManager::current_dir().as_deref() == Some(current_dir)
+        && loaded_environment() != selected.as_deref().unwrap_or(DEFAULT_ENVIRONMENT);
+
+    // Also check for orphaned state (state cleared but env vars remain)
+    let is_loaded = StateManager::is_loaded();
+    let has_orphaned_vars = !is_loaded
+        && (std::env::var("TEST_BG_VAR").is_ok()
+            || std::env::var("TEST_TIMESTAMP").is_ok()
+            || std::env::var("CUENV_ENV").is_ok());
+
+    if should_unload || switched || has_orphaned_vars {
+        if should_unload || switched {
+            output.notices.push(match (switched, &selected) {
+                (false, _) => "# cuenv: Unloading environment (directory changed)".to_string(),
+                (true, Some(name)) => format!("# cuenv: Switching to environment '{name}'"),
+                (true, None) => "# cuenv: Switching to the default environment".to_string(),
+            });
+            // Use the diff for proper unloading, leaving alone what the
+            // user chose to keep of the variables changed since the load
+            let divergences = StateManager::modified_since_load().unwrap_or_default();
+            let preserved = conflicts::resolve(&divergences, interactive, &mut output.notices)?;
+            if let Ok(Some(diff)) = StateManager::get_diff() {
+                for key in diff.removed() {
+                    if preserved.contains(key) {
+                        continue;
+                    }
+                    output.commands.push(shell_impl.unset(key));
+                }
+                for (key, _) in diff.added_or_changed() {
+                    if preserved.contains(key) {
+                        continue;
+                    }
+                    if diff.prev.contains_key(key) {
+                        if let Some(orig_value) = diff.prev.get(key) {
+                            output.commands.push(shell_impl.export(key, orig_value));
+                        }
+                    } else {
+                        output.commands.push(shell_impl.unset(key));
+                    }
+                }
+            }
+            let unloaded_dir = StateManager::current_dir();
+            StateManager::unload().await.map_err(|e| {
+                cuenv_core::Error::configuration(format!("Failed to unload state: {e}"))
+            })?;
+            if let Some(dir) = unloaded_dir {
+                compose::on_unload(&dir).await;
+            }
+        } else if has_orphaned_vars {
+            output
+                .notices
+                .push("# cuenv: Cleaning up orphaned environment variables".to_string());
+            // Manually clean up known orphaned variables
+            let known_vars = ["TEST_BG_VAR", "TEST_TIMESTAMP", "CUENV_ENV"];
+            for var in &known_vars {
+                if std::env::var(var).is_ok() {
+                    output.commands.push(shell_impl.unset(var));
+                }
+            }
+        }
+    }
+
+    // Then check if current directory has an environment to load
+    if current_dir.join(ENV_CUE_FILENAME).exists() {
+        let dir_manager = DirectoryManager::new();
+        if dir_manager
+            .is_directory_allowed(current_dir)
+            .unwrap_or(false)
+        {
+            // Check for completed background hooks ONLY if directory is allowed
+            if let Some(completed_env) =
+                cuenv_env::manager::environment::hooks::load_captured_environment()
+            {
+                // Apply newly available environment
+                for (key, value) in completed_env {
+                    output.commands.push(shell_impl.export(&key, &value));
+                }
+
+                // Show subtle notification
+                output
+                    .notices
+                    .push("# cuenv: ✓ Background hooks completed, environment updated".to_string());
+            }
+
+            // Task-only and comment-only edits leave the environment loaded
+            if StateManager::should_load(current_dir)
+                || StateManager::watch_change().needs_env_reload()
+            {
+                // The state records the environment the load finds in CUENV_ENV
+                match &selected {
+                    Some(name) => SyncEnv::set_var(CUENV_ENV_VAR, name)?,
+                    None => SyncEnv::remove_var(CUENV_ENV_VAR)?,
+                }
+                let mut env_manager = EnvManager::new();
+                if let Err(e) = env_manager
+                    .load_env_with_options(
+                        current_dir,
+                        selected,
+                        Vec::new(),
+                        None,
+                        SupervisorMode::Background,
+                    )
+                    .await
+                {
+                    output
+                        .notices
+                        .push(format!("# cuenv: failed to load environment: {e}"));
+                } else if let Ok(Some(diff)) = StateManager::get_diff() {
+                    for (key, value) in diff.added_or_changed() {
+                        output.commands.push(shell_impl.export(key, value));
+                    }
+                    for key in diff.removed() {
+                        output.commands.push(shell_impl.unset(key));
+                    }
+                }
+            }
+        } else {
+            output.notices.push(
+                "# cuenv: Directory not allowed. Run 'cuenv env allow' to allow this directory."
+                    .to_string(),
+            );
+        }
+    }
+    Ok(output)
+}
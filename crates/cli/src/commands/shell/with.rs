@@ -0,0 +1,62 @@
+use cuenv_core::{Result, CUENV_CAPABILITIES_VAR, CUENV_ENV_VAR, CUENV_SCOPED_VAR, DEFAULT_SHELL};
+use cuenv_env::{manager::environment::SupervisorMode, EnvManager};
+use cuenv_utils::sync::env::SyncEnv;
+use std::env;
+use std::path::PathBuf;
+
+/// Run `command` (or an interactive shell) with an environment applied
+///
+/// Nothing is persisted: the calling shell keeps whatever environment it had
+/// loaded, and the spawned process is marked so its own shell hook does not
+/// replace the scoped environment with the directory default.
+pub async fn execute(
+    directory: Option<PathBuf>,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    command: Vec<String>,
+) -> Result<()> {
+    let dir = match directory {
+        Some(d) => d,
+        None => env::current_dir()
+            .map_err(|e| cuenv_core::Error::file_system(".", "get current directory", e))?,
+    };
+
+    let env_name = environment.or_else(|| env::var(CUENV_ENV_VAR).ok());
+    let mut caps = capabilities;
+    if caps.is_empty() {
+        if let Ok(env_caps) = env::var(CUENV_CAPABILITIES_VAR) {
+            caps = env_caps
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+    }
+
+    let (program, args) = match command.split_first() {
+        Some((program, args)) => (program.clone(), args.to_vec()),
+        None => (
+            env::var("SHELL").unwrap_or_else(|_| DEFAULT_SHELL.to_string()),
+            Vec::new(),
+        ),
+    };
+
+    let mut env_manager = EnvManager::scoped();
+    env_manager
+        .load_env_with_options(
+            &dir,
+            env_name.clone(),
+            caps,
+            Some(&program),
+            SupervisorMode::Synchronous,
+        )
+        .await?;
+
+    SyncEnv::set_var(CUENV_SCOPED_VAR, "1")?;
+    if let Some(name) = env_name {
+        SyncEnv::set_var(CUENV_ENV_VAR, name)?;
+    }
+
+    let exit_code = env_manager.run_command_with_current_env(&program, &args)?;
+    std::process::exit(exit_code);
+}
@@ -0,0 +1,75 @@
+//! `cuenv state`: move cuenv's state between machines
+//!
+//! Exporting in a development container's image build and importing in the
+//! image lets fresh containers start with approvals, settings and a warm
+//! cache, see [`cuenv_utils::state_bundle`].
+
+use clap::Subcommand;
+use cuenv_core::{Error, Result};
+use cuenv_utils::state_bundle::{Manifest, Section, StateBundle, StateDirs};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum StateCommands {
+    /// Bundle approvals, global settings and the cache into a file
+    Export {
+        /// File to write, a zstd-compressed tar archive
+        #[arg(long, value_name = "FILE")]
+        bundle: PathBuf,
+
+        /// Only bundle this part of the state: approvals, overrides or cache (can be repeated)
+        #[arg(long = "section", value_parser = ["approvals", "overrides", "cache"])]
+        sections: Vec<String>,
+    },
+
+    /// Unpack a bundle, merging its approvals with the ones already here
+    Import {
+        /// Bundle written by `cuenv state export`
+        #[arg(long, value_name = "FILE")]
+        bundle: PathBuf,
+    },
+}
+
+impl StateCommands {
+    pub async fn execute(self) -> Result<()> {
+        let state = StateBundle::new(StateDirs::current());
+        match self {
+            StateCommands::Export { bundle, sections } => {
+                let sections: Vec<Section> = if sections.is_empty() {
+                    Section::ALL.to_vec()
+                } else {
+                    sections
+                        .iter()
+                        .filter_map(|name| Section::from_name(name))
+                        .collect()
+                };
+                let path = bundle.clone();
+                let manifest = blocking(move || state.export(&path, &sections)).await?;
+                println!("✓ Bundled {} into {}", summary(&manifest), bundle.display());
+            }
+            StateCommands::Import { bundle } => {
+                let path = bundle.clone();
+                let manifest = blocking(move || state.import(&path)).await?;
+                println!(
+                    "✓ Imported {} from {}",
+                    summary(&manifest),
+                    bundle.display()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn summary(manifest: &Manifest) -> String {
+    let sections: Vec<&str> = manifest.sections.iter().map(|s| s.name()).collect();
+    format!("{} ({} files)", sections.join(", "), manifest.files)
+}
+
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| Error::configuration(format!("State bundling failed: {e}")))?
+}
@@ -0,0 +1,61 @@
+//! Asking before tasks that declare `confirm` run
+//!
+//! On a terminal each such task, dependencies included, is asked about in
+//! turn. Without one, as in CI, they only run with `--yes`; the executor
+//! refuses any task that was not confirmed.
+
+use cuenv_core::{Error, Result};
+use cuenv_task::TaskExecutor;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `--yes`, for the rest of the process
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Confirm every task without asking
+pub fn assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+/// Confirm the tasks running for `task_names` that ask for it, returning
+/// false when the user declined one
+pub fn confirm_tasks(executor: &TaskExecutor, task_names: &[String]) -> Result<bool> {
+    let pending = executor.pending_confirmations(task_names)?;
+    if pending.is_empty() {
+        return Ok(true);
+    }
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        for (task, question) in &pending {
+            eprintln!("Confirmed '{task}' with --yes: {question}");
+        }
+    } else {
+        if !io::stdin().is_terminal() {
+            let (task, question) = &pending[0];
+            return Err(Error::configuration(format!(
+                "Task '{task}' asks for confirmation: {question}\nNot running interactively; pass --yes to run it"
+            )));
+        }
+        for (task, question) in &pending {
+            if !ask(task, question)? {
+                eprintln!("Not running '{task}'");
+                return Ok(false);
+            }
+        }
+    }
+    executor.confirm(pending.into_iter().map(|(task, _)| task));
+    Ok(true)
+}
+
+fn ask(task: &str, question: &str) -> Result<bool> {
+    eprint!("⚠ {task}: {question} [y/N] ");
+    io::stderr()
+        .flush()
+        .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,358 @@
+//! Detached task runs
+//!
+//! `cuenv task run --detach` records the run under `runs/<run-id>` in the
+//! state directory and starts `cuenv internal supervise-run` in a session of
+//! its own, so the run outlives the terminal it was started from. The
+//! supervisor runs `cuenv task run` with the same selection, writing its
+//! output to `output.log`, and records how it ended in `run.json`.
+//! `cuenv task attach <run-id>` follows the log until the run ends, and
+//! `cuenv task wait <run-id>` exits with the run's exit code. Run IDs can be
+//! shortened to any unique prefix.
+
+use clap::Parser;
+use cuenv_core::{Error, ExitStatus, Result, RunId};
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::xdg::XdgPaths;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Reserved task name that triggers the attach command
+pub const ATTACH_TASK_COMMAND: &str = "attach";
+
+/// Reserved task name that triggers the wait command
+pub const WAIT_TASK_COMMAND: &str = "wait";
+
+const RECORD_FILE: &str = "run.json";
+const LOG_FILE: &str = "output.log";
+
+/// How often the log and record are checked while following a run
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A detached run, as recorded in `run.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DetachedRun {
+    id: String,
+    /// Directory the run was started in
+    dir: PathBuf,
+    /// Arguments of the `cuenv` the supervisor runs
+    args: Vec<String>,
+    /// Seconds since the Unix epoch
+    started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    supervisor_pid: Option<u32>,
+    /// How the run ended, once it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit_status: Option<ExitStatus>,
+}
+
+impl DetachedRun {
+    fn load(runs: &Path, id: &str) -> Result<Self> {
+        let file = runs.join(id).join(RECORD_FILE);
+        let content = fs::read_to_string(&file)
+            .map_err(|e| Error::file_system(&file, "read detached run", e))?;
+        serde_json::from_str(&content).map_err(|e| Error::Json {
+            message: format!("invalid detached run {}", file.display()),
+            source: e,
+        })
+    }
+
+    fn save(&self, runs: &Path) -> Result<()> {
+        let dir = runs.join(&self.id);
+        fs::create_dir_all(&dir)
+            .map_err(|e| Error::file_system(&dir, "create detached run directory", e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| Error::Json {
+            message: "failed to serialize detached run".to_string(),
+            source: e,
+        })?;
+        write_atomic_string(&dir.join(RECORD_FILE), &json)
+    }
+
+    /// How the run ended, or an error when its supervisor is gone without
+    /// recording it
+    fn finished(&self) -> Result<Option<ExitStatus>> {
+        if self.exit_status.is_some() {
+            return Ok(self.exit_status);
+        }
+        if self.supervisor_pid.is_some_and(|pid| !process_alive(pid)) {
+            return Err(Error::configuration(format!(
+                "Run {} stopped without recording how it ended",
+                self.id
+            )));
+        }
+        Ok(None)
+    }
+}
+
+fn runs_dir() -> PathBuf {
+    XdgPaths::state_dir().join("runs")
+}
+
+/// Start `cuenv` with `args` in `dir` under a detached supervisor, returning
+/// the run ID
+pub fn start(dir: &Path, args: Vec<String>) -> Result<String> {
+    let runs = runs_dir();
+    let run = DetachedRun {
+        id: RunId::new().to_string(),
+        dir: dir.to_path_buf(),
+        args,
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        supervisor_pid: None,
+        exit_status: None,
+    };
+    run.save(&runs)?;
+
+    let exe = std::env::current_exe()
+        .map_err(|e| Error::configuration(format!("Failed to find the cuenv executable: {e}")))?;
+    let mut command = Command::new(exe);
+    command
+        .args(["internal", "supervise-run", &run.id])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach_from_terminal(&mut command);
+    command
+        .spawn()
+        .map_err(|e| Error::configuration(format!("Failed to start the run supervisor: {e}")))?;
+    Ok(run.id)
+}
+
+/// Run the detached run `id` to completion, recording how it ended
+pub fn supervise(id: &str) -> Result<()> {
+    let runs = runs_dir();
+    let mut run = DetachedRun::load(&runs, id)?;
+    run.supervisor_pid = Some(std::process::id());
+    run.save(&runs)?;
+
+    let log_path = runs.join(id).join(LOG_FILE);
+    let log =
+        File::create(&log_path).map_err(|e| Error::file_system(&log_path, "create run log", e))?;
+    let stderr = log
+        .try_clone()
+        .map_err(|e| Error::file_system(&log_path, "open run log", e))?;
+    let exe = std::env::current_exe()
+        .map_err(|e| Error::configuration(format!("Failed to find the cuenv executable: {e}")))?;
+    let status = Command::new(exe)
+        .args(&run.args)
+        .current_dir(&run.dir)
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(stderr)
+        .status();
+
+    run.exit_status = Some(match status {
+        Ok(status) => ExitStatus::from(status),
+        Err(e) => {
+            if let Ok(mut log) = fs::OpenOptions::new().append(true).open(&log_path) {
+                let _ = writeln!(log, "Failed to start the run: {e}");
+            }
+            ExitStatus::FAILURE
+        }
+    });
+    run.save(&runs)
+}
+
+/// Flags accepted by `cuenv task attach`
+#[derive(Parser, Debug)]
+#[command(
+    name = "cuenv task attach",
+    about = "Follow the output of a detached run until it ends"
+)]
+struct TaskAttachArgs {
+    /// ID of the run, or a unique prefix of it
+    run_id: String,
+}
+
+/// Flags accepted by `cuenv task wait`
+#[derive(Parser, Debug)]
+#[command(
+    name = "cuenv task wait",
+    about = "Wait for a detached run and exit with its exit code"
+)]
+struct TaskWaitArgs {
+    /// ID of the run, or a unique prefix of it
+    run_id: String,
+}
+
+/// Parse the trailing arguments of `cuenv task attach` and follow the run
+pub async fn attach(args: Vec<String>) -> Result<()> {
+    let Some(args) = parse_args::<TaskAttachArgs>(ATTACH_TASK_COMMAND, args)? else {
+        return Ok(());
+    };
+    let runs = runs_dir();
+    let id = resolve_id(&runs, &args.run_id)?;
+    let log_path = runs.join(&id).join(LOG_FILE);
+
+    let mut position = 0;
+    loop {
+        // Read the record first, so output written before it ended is shown
+        let finished = DetachedRun::load(&runs, &id)?.finished()?;
+        position = print_new_output(&log_path, position)?;
+        if let Some(status) = finished {
+            eprintln!("Run {id} finished with {status}");
+            exit_with(status);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Parse the trailing arguments of `cuenv task wait` and wait for the run
+pub async fn wait(args: Vec<String>) -> Result<()> {
+    let Some(args) = parse_args::<TaskWaitArgs>(WAIT_TASK_COMMAND, args)? else {
+        return Ok(());
+    };
+    let runs = runs_dir();
+    let id = resolve_id(&runs, &args.run_id)?;
+    loop {
+        if let Some(status) = DetachedRun::load(&runs, &id)?.finished()? {
+            exit_with(status);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Parse the trailing arguments of `cuenv task <command>`, or print its
+/// help and return `None`
+fn parse_args<T: Parser>(command: &str, args: Vec<String>) -> Result<Option<T>> {
+    match T::try_parse_from(std::iter::once(format!("cuenv task {command}")).chain(args)) {
+        Ok(args) => Ok(Some(args)),
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            Ok(None)
+        }
+        Err(e) => Err(Error::configuration(e.to_string())),
+    }
+}
+
+/// The run `id` names, as a whole or as a unique prefix
+fn resolve_id(runs: &Path, id: &str) -> Result<String> {
+    let id = id.to_ascii_uppercase();
+    let mut matches: Vec<String> = fs::read_dir(runs)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.starts_with(&id))
+                .collect()
+        })
+        .unwrap_or_default();
+    match matches.len() {
+        0 => Err(Error::configuration(format!("No detached run '{id}'"))),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            matches.sort();
+            Err(Error::configuration(format!(
+                "Run ID '{id}' is ambiguous: {}",
+                matches.join(", ")
+            )))
+        }
+    }
+}
+
+/// Print what was written to the log past `position`, returning the new end
+fn print_new_output(log_path: &Path, position: u64) -> Result<u64> {
+    let mut log = match File::open(log_path) {
+        Ok(log) => log,
+        // The supervisor has not started yet
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(position),
+        Err(e) => return Err(Error::file_system(log_path, "open run log", e)),
+    };
+    log.seek(SeekFrom::Start(position))
+        .map_err(|e| Error::file_system(log_path, "read run log", e))?;
+    let mut output = Vec::new();
+    log.read_to_end(&mut output)
+        .map_err(|e| Error::file_system(log_path, "read run log", e))?;
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(&output);
+    let _ = stdout.flush();
+    Ok(position + output.len() as u64)
+}
+
+fn exit_with(status: ExitStatus) -> ! {
+    std::process::exit(status.code())
+}
+
+/// Start `command` in a session of its own, so closing the terminal does
+/// not stop it
+fn detach_from_terminal(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid is async-signal-safe
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str) -> DetachedRun {
+        DetachedRun {
+            id: id.to_string(),
+            dir: PathBuf::from("/project"),
+            args: vec!["task".to_string(), "run".to_string(), "build".to_string()],
+            started_at: 0,
+            supervisor_pid: None,
+            exit_status: None,
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip_and_prefixes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let runs = temp_dir.path();
+        let mut finished = run("01J9ZQ3A");
+        finished.exit_status = Some(ExitStatus::Code(2));
+        finished.save(runs).unwrap();
+        run("01J9ZQ4B").save(runs).unwrap();
+
+        assert_eq!(DetachedRun::load(runs, "01J9ZQ3A").unwrap(), finished);
+        assert_eq!(resolve_id(runs, "01j9zq3").unwrap(), "01J9ZQ3A");
+        assert!(resolve_id(runs, "01J9ZQ").is_err());
+        assert!(resolve_id(runs, "ZZ").is_err());
+        assert_eq!(finished.finished().unwrap(), Some(ExitStatus::Code(2)));
+        assert_eq!(run("01J9ZQ4B").finished().unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_without_its_supervisor_is_an_error() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        let mut orphaned = run("01J9ZQ5C");
+        orphaned.supervisor_pid = Some(pid);
+        assert!(orphaned.finished().is_err());
+        orphaned.supervisor_pid = Some(std::process::id());
+        assert_eq!(orphaned.finished().unwrap(), None);
+    }
+}
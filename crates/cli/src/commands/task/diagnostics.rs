@@ -0,0 +1,220 @@
+//! Reporting the diagnostics found by tasks' problem matchers
+//!
+//! Diagnostics are collected from the event bus while tasks run. Afterwards
+//! they are summarized on stderr, printed as workflow commands when running
+//! in GitHub Actions so they show up as annotations, and written as JSON to
+//! the file given with `--diagnostics-json`.
+
+use cuenv_core::{Diagnostic, Error, Result, Severity, SystemEvent, TaskEvent};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+/// `--diagnostics-json`, for the rest of the process
+static JSON_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Write the diagnostics of the run as JSON to `path`
+pub fn write_json_to(path: Option<PathBuf>) {
+    if let Some(path) = path {
+        let _ = JSON_PATH.set(path);
+    }
+}
+
+/// A diagnostic and the task run it was found in
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunDiagnostic {
+    run_id: String,
+    #[serde(flatten)]
+    diagnostic: Diagnostic,
+}
+
+/// Collects diagnostics published while tasks run
+pub struct DiagnosticCollector {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<Vec<RunDiagnostic>>,
+}
+
+impl DiagnosticCollector {
+    /// Start collecting; only diagnostics published from now on are seen
+    pub fn start() -> Self {
+        let mut events = cuenv_core::events::global_event_bus().subscribe();
+        let (stop, mut stopped) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let mut diagnostics = Vec::new();
+            loop {
+                tokio::select! {
+                    biased;
+                    event = events.recv() => match event {
+                        Ok(event) => diagnostics.extend(diagnostic(event.event)),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("Missed {missed} events collecting diagnostics");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = &mut stopped => {
+                        // Everything published before stopping is queued already
+                        while let Ok(event) = events.try_recv() {
+                            diagnostics.extend(diagnostic(event.event));
+                        }
+                        break;
+                    }
+                }
+            }
+            diagnostics
+        });
+        Self { stop, handle }
+    }
+
+    /// Stop collecting and report what was found
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let diagnostics = self.handle.await.unwrap_or_default();
+        report(&diagnostics);
+    }
+}
+
+fn diagnostic(event: SystemEvent) -> Option<RunDiagnostic> {
+    match event {
+        SystemEvent::Task(TaskEvent::TaskDiagnostic {
+            task_id,
+            diagnostic,
+            ..
+        }) => Some(RunDiagnostic {
+            run_id: task_id,
+            diagnostic,
+        }),
+        _ => None,
+    }
+}
+
+fn report(found: &[RunDiagnostic]) {
+    let diagnostics: Vec<Diagnostic> = found.iter().map(|d| d.diagnostic.clone()).collect();
+    if !diagnostics.is_empty() {
+        eprintln!("{}", summary(&diagnostics));
+        if std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true") {
+            diagnostics
+                .iter()
+                .for_each(|diagnostic| println!("{}", github_annotation(diagnostic)));
+        }
+    }
+    if let Some(path) = JSON_PATH.get() {
+        if let Err(e) = write_json(path, found) {
+            eprintln!("Warning: {e}");
+        }
+    }
+}
+
+/// Counts by severity followed by each diagnostic and its task
+fn summary(diagnostics: &[Diagnostic]) -> String {
+    let count = |severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    let counts = [Severity::Error, Severity::Warning, Severity::Note]
+        .into_iter()
+        .map(|severity| (count(severity), severity))
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, severity)| format!("{count} {severity}{}", plural(count)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let lines: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| format!("  [{}] {diagnostic}", diagnostic.task))
+        .collect();
+    format!("\nDiagnostics: {counts}\n{}", lines.join("\n"))
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// A GitHub Actions workflow command annotating the diagnostic's location
+fn github_annotation(diagnostic: &Diagnostic) -> String {
+    let command = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "notice",
+    };
+    let title = match &diagnostic.code {
+        Some(code) => format!("{} ({code})", diagnostic.task),
+        None => diagnostic.task.clone(),
+    };
+    let properties: Vec<String> = [
+        ("file", diagnostic.file.clone()),
+        ("line", diagnostic.line.map(|line| line.to_string())),
+        ("col", diagnostic.column.map(|column| column.to_string())),
+        ("title", Some(title)),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| format!("{name}={}", escape_property(&value))))
+    .collect();
+    format!(
+        "::{command} {}::{}",
+        properties.join(","),
+        escape_data(&diagnostic.message)
+    )
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn write_json(path: &Path, diagnostics: &[RunDiagnostic]) -> Result<()> {
+    let json = serde_json::to_string_pretty(diagnostics)?;
+    std::fs::write(path, json).map_err(|e| Error::file_system(path, "write diagnostics", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(severity: Severity, file: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            task: "lint".to_string(),
+            severity,
+            message: "100% wrong\nreally".to_string(),
+            file: file.map(str::to_string),
+            line: Some(3),
+            column: Some(10),
+            code: Some("no-unused-vars".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        assert_eq!(
+            github_annotation(&diagnostic(Severity::Error, Some("src/a,b.js"))),
+            "::error file=src/a%2Cb.js,line=3,col=10,title=lint (no-unused-vars)::100%25 wrong%0Areally"
+        );
+        assert!(github_annotation(&diagnostic(Severity::Note, None))
+            .starts_with("::notice line=3,col=10,title="));
+    }
+
+    #[test]
+    fn test_summary_counts_by_severity() {
+        let diagnostics = [
+            diagnostic(Severity::Error, Some("a.js")),
+            diagnostic(Severity::Warning, Some("a.js")),
+            diagnostic(Severity::Warning, None),
+        ];
+        let summary = summary(&diagnostics);
+        assert!(summary.starts_with("\nDiagnostics: 1 error, 2 warnings\n"));
+        assert!(summary.contains("  [lint] a.js:3:10: warning[no-unused-vars]: 100% wrong"));
+    }
+}
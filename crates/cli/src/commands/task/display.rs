@@ -65,10 +65,7 @@ pub fn display_task_tree(nodes: &IndexMap<String, TaskNode>, verbose: bool, use_
                     println!("  • {name}");
                 }
             }
-            TaskNode::Group {
-                description: _,
-                tasks,
-            } => {
+            TaskNode::Group { tasks, .. } => {
                 if verbose {
                     // Verbose mode: show tree structure
                     display_group_collection(
@@ -278,6 +275,7 @@ fn display_tree_children_collection(
                     TaskNode::Group {
                         description,
                         tasks: subtasks,
+                        ..
                     } => {
                         display_group_collection(
                             &name,
@@ -339,7 +337,9 @@ fn display_tree_children(
                 );
                 println!("{task_line}");
             }
-            TaskNode::Group { description, tasks } => {
+            TaskNode::Group {
+                description, tasks, ..
+            } => {
                 display_group_collection(
                     name,
                     description.as_deref(),
@@ -436,11 +436,35 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: None,
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         }))
     }
 
     fn create_test_group(tasks: TaskCollection, description: Option<String>) -> TaskNode {
-        TaskNode::Group { description, tasks }
+        TaskNode::Group {
+            description,
+            setup: None,
+            teardown: None,
+            tasks,
+        }
     }
 
     #[test]
@@ -0,0 +1,31 @@
+//! Exit code of `cuenv task`
+//!
+//! Tasks, groups and monorepo runs all exit with the status of the first
+//! task that failed: its exit code, or 128 + N for a task killed by signal
+//! N. A run cancelled with Ctrl-C exits with 130, and an error that keeps
+//! cuenv from running the tasks at all, or declining to confirm one, exits
+//! with 1.
+
+use cuenv_task::TaskExecutor;
+
+/// Exit code of a successful run that had to execute tasks, under
+/// `--exit-zero-on-cache-hit-only`
+pub const CACHE_MISS_EXIT_CODE: i32 = 3;
+
+/// Adjustments to the exit code of a run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExitPolicy {
+    /// Succeed only when every task was served from the cache
+    pub zero_on_cache_hit_only: bool,
+}
+
+impl ExitPolicy {
+    /// Exit code for a run of `executor` that ended with `status`
+    pub fn exit_code(self, status: i32, executor: &TaskExecutor) -> i32 {
+        if status == 0 && self.zero_on_cache_hit_only && !executor.all_cached() {
+            eprintln!("Tasks were executed instead of being served from the cache");
+            return CACHE_MISS_EXIT_CODE;
+        }
+        status
+    }
+}
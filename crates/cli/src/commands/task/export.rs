@@ -0,0 +1,133 @@
+//! `cuenv task export`: resolved task definitions for other build systems
+
+use clap::Parser;
+use cuenv_config::{Config, ConfigLoader};
+use cuenv_core::{Error, Result, CUENV_ENV_VAR};
+use cuenv_task::export::TaskExport;
+use cuenv_task::{TaskBuilder, UnifiedTaskDAG};
+use std::path::PathBuf;
+
+/// Reserved task name that triggers the export command
+pub const EXPORT_TASK_COMMAND: &str = "export";
+
+/// Flags accepted by `cuenv task export`
+#[derive(Parser, Debug)]
+#[command(
+    name = "cuenv task export",
+    about = "Export fully resolved task definitions"
+)]
+struct TaskExportArgs {
+    /// Tasks or groups to export, with everything they depend on (default: all)
+    tasks: Vec<String>,
+
+    /// Output format
+    #[arg(long, default_value = "json", value_parser = ["json", "starlark"])]
+    format: String,
+
+    /// Environment to resolve variables for (e.g., dev, staging, production)
+    #[arg(short = 'e', long = "env")]
+    environment: Option<String>,
+
+    /// Write the export to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Parse the trailing arguments of `cuenv task export` and print the export
+pub async fn execute(
+    config: &Config,
+    environment: Option<String>,
+    args: Vec<String>,
+) -> Result<()> {
+    let args = match TaskExportArgs::try_parse_from(
+        std::iter::once(format!("cuenv task {EXPORT_TASK_COMMAND}")).chain(args),
+    ) {
+        Ok(args) => args,
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            return Ok(());
+        }
+        Err(e) => return Err(Error::configuration(e.to_string())),
+    };
+
+    // Variables depend on the environment, so select it before resolving
+    let environment = args
+        .environment
+        .or(environment)
+        .or_else(|| std::env::var(CUENV_ENV_VAR).ok());
+    let config = match environment {
+        Some(environment) => {
+            ConfigLoader::new()
+                .runtime(config.runtime.clone())
+                .environment(environment)
+                .load()
+                .await?
+        }
+        None => config.clone(),
+    };
+
+    let task_names = if args.tasks.is_empty() {
+        all_task_names(&config)
+    } else {
+        args.tasks
+    };
+    if task_names.is_empty() {
+        return Err(Error::configuration("No tasks defined to export"));
+    }
+
+    let root = config
+        .env_file
+        .as_ref()
+        .and_then(|file| file.parent())
+        .map_or_else(|| config.working_dir.clone(), |dir| dir.to_path_buf());
+    let strict_variables = config
+        .parse_result
+        .config
+        .as_ref()
+        .and_then(|settings| settings.strict_variables)
+        .unwrap_or(false);
+    let definitions = TaskBuilder::new(root.clone())
+        .with_strict_variables(strict_variables)
+        .build_tasks_with_nodes(config.get_tasks().clone(), config.get_task_nodes().clone())?;
+    let dag = UnifiedTaskDAG::builder()
+        .with_task_configs(config.get_tasks().clone())
+        .with_task_nodes(config.get_task_nodes().clone())
+        .with_task_definitions(definitions)
+        .build_for_tasks(&task_names)?;
+
+    let env = config.get_env_vars()?.into_iter().collect();
+    let export = TaskExport::from_dag(&dag, &root, config.runtime.environment.clone(), env)?;
+    let content = match args.format.as_str() {
+        "starlark" => export.to_starlark(),
+        _ => export.to_json()? + "\n",
+    };
+
+    match args.output {
+        Some(path) => std::fs::write(&path, content)
+            .map_err(|e| Error::file_system(path, "write task export", e)),
+        None => {
+            print!("{content}");
+            Ok(())
+        }
+    }
+}
+
+/// Top-level tasks and groups, in a stable order
+fn all_task_names(config: &Config) -> Vec<String> {
+    let mut names: Vec<String> = config
+        .get_tasks()
+        .keys()
+        .filter(|name| !name.contains('.'))
+        .cloned()
+        .collect();
+    names.sort();
+    names.extend(
+        config
+            .get_task_nodes()
+            .keys()
+            .filter(|name| !names.contains(name))
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+    names
+}
@@ -2,7 +2,12 @@
 //!
 //! This module provides integration between the task executor and the TUI formatters.
 
-use cuenv_core::Result;
+use super::diagnostics::DiagnosticCollector;
+use super::exit_policy::ExitPolicy;
+use super::report::RunReporter;
+use super::template::{self, Rendering};
+use super::tmux;
+use cuenv_core::{ExitStatus, Result};
 use cuenv_task::TaskExecutor;
 use cuenv_tui::app::TuiApp;
 use cuenv_tui::event_bus::EventBus;
@@ -12,6 +17,9 @@ use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::time::{interval, Duration};
 
+/// File the `--trace-output` Chrome trace is written to
+const CHROME_TRACE_FILE: &str = "cuenv-trace.json";
+
 /// Execute tasks with the appropriate output formatter
 pub async fn execute_with_formatter(
     executor: &TaskExecutor,
@@ -20,7 +28,16 @@ pub async fn execute_with_formatter(
     audit: bool,
     output_format: &str,
     trace_output: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<i32> {
+    // Ask before the output takes over the terminal
+    if !super::confirm::confirm_tasks(executor, &[task_name.to_string()])? {
+        return Ok(1);
+    }
+
+    let diagnostics = DiagnosticCollector::start();
+    let report = RunReporter::start();
+
     // Set up signal handling for Ctrl-C
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
 
@@ -33,18 +50,10 @@ pub async fn execute_with_formatter(
         }
     });
 
-    match output_format {
+    let result = match output_format {
         "spinner" => execute_with_spinner(executor, task_name, args, audit, &mut shutdown_rx).await,
         "simple" | "tree" => {
-            execute_with_simple(
-                executor,
-                task_name,
-                args,
-                audit,
-                trace_output,
-                &mut shutdown_rx,
-            )
-            .await
+            execute_with_simple(executor, task_name, args, audit, &mut shutdown_rx).await
         }
         "tui" => {
             // Check if we're in a TTY environment
@@ -58,19 +67,70 @@ pub async fn execute_with_formatter(
                 execute_with_tui(executor, task_name, args, audit, &mut shutdown_rx).await
             }
         }
-        _ => {
-            // Fall back to simple output for unknown formats
-            eprintln!("Unknown output format '{output_format}', using simple output");
-            execute_with_simple(
+        "tmux" => {
+            if !tmux::inside_tmux() {
+                eprintln!(
+                    "tmux output requires running inside tmux. Falling back to spinner mode."
+                );
+                execute_with_spinner(executor, task_name, args, audit, &mut shutdown_rx).await
+            } else {
+                tmux::execute_in_panes(
+                    executor,
+                    task_name,
+                    args,
+                    audit,
+                    exit_policy,
+                    &mut shutdown_rx,
+                )
+                .await
+            }
+        }
+        "template" | "json" => {
+            let rendering = if output_format == "json" {
+                Rendering::Json
+            } else {
+                Rendering::Template
+            };
+            template::execute(
                 executor,
                 task_name,
                 args,
                 audit,
-                trace_output,
+                rendering,
                 &mut shutdown_rx,
             )
             .await
         }
+        _ => {
+            // Fall back to simple output for unknown formats
+            eprintln!("Unknown output format '{output_format}', using simple output");
+            execute_with_simple(executor, task_name, args, audit, &mut shutdown_rx).await
+        }
+    };
+
+    diagnostics.finish().await;
+    if let Some(report) = report {
+        report.finish(task_name, executor).await;
+    }
+    if trace_output {
+        write_chrome_trace();
+    }
+
+    result.map(|status| exit_policy.exit_code(status, executor))
+}
+
+/// Write the unified timeline (hooks, environment, secrets, tasks) for chrome://tracing
+fn write_chrome_trace() {
+    let events = cuenv_core::events::global_timeline().to_chrome_trace();
+    let written = serde_json::to_string_pretty(&events)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(CHROME_TRACE_FILE, json).map_err(|e| e.to_string()));
+
+    match written {
+        Ok(()) => {
+            eprintln!("Chrome trace written to: {CHROME_TRACE_FILE} (open in chrome://tracing)")
+        }
+        Err(e) => eprintln!("Warning: failed to write Chrome trace: {e}"),
     }
 }
 
@@ -161,6 +221,12 @@ async fn execute_with_spinner(
                                 error,
                                 duration_ms: 0,
                             }),
+                            cuenv_core::TaskEvent::TaskSkipped { task_name, .. } => {
+                                Some(cuenv_tui::TaskEvent::Cancelled { task_name })
+                            }
+                            cuenv_core::TaskEvent::TaskPublishing { task_name, .. } => {
+                                Some(cuenv_tui::TaskEvent::Publishing { task_name })
+                            }
                             _ => None,
                         };
 
@@ -201,7 +267,7 @@ async fn execute_with_spinner(
         _ = shutdown_rx.recv() => {
             task_registry.update_task_state(task_name, TaskState::Cancelled).await;
             eprintln!("Task execution cancelled");
-            Ok(130) // Standard exit code for SIGINT
+            Ok(ExitStatus::INTERRUPTED.code())
         }
     };
 
@@ -245,10 +311,19 @@ async fn execute_with_tui(
     // Get the task registry from the event bus
     let task_registry = event_bus.registry();
 
-    // Register the task to be executed
-    task_registry
-        .register_task(task_name.to_string(), vec![])
-        .await;
+    // Register every node that will run, including group setup and teardown
+    let dag = executor.build_unified_dag(&[task_name.to_string()])?;
+    for task in dag.get_flattened_tasks().iter().filter(|t| !t.is_barrier) {
+        let dependencies = task
+            .dependencies
+            .iter()
+            .filter(|dep| !dep.contains("__"))
+            .cloned()
+            .collect();
+        task_registry
+            .register_task(task.id.clone(), dependencies)
+            .await;
+    }
 
     // Create a bridge to forward core events to TUI event bus
     let tui_event_bus = event_bus.clone();
@@ -316,6 +391,16 @@ async fn execute_with_tui(
                                     })
                                     .await;
                             }
+                            cuenv_core::TaskEvent::TaskSkipped { task_name, .. } => {
+                                tui_event_bus
+                                    .publish(cuenv_tui::events::TaskEvent::Cancelled { task_name })
+                                    .await;
+                            }
+                            cuenv_core::TaskEvent::TaskPublishing { task_name, .. } => {
+                                tui_event_bus
+                                    .publish(cuenv_tui::events::TaskEvent::Publishing { task_name })
+                                    .await;
+                            }
                             // Forward other events if needed
                             _ => {}
                         }
@@ -357,7 +442,7 @@ async fn execute_with_tui(
     // Get the task result
     let task_result = match task_handle.await {
         Ok(result) => result,
-        Err(_) => Ok(1), // Task was cancelled or panicked
+        Err(_) => Ok(ExitStatus::FAILURE.code()), // Task was cancelled or panicked
     };
 
     // Check for TUI errors
@@ -374,12 +459,13 @@ async fn execute_with_simple(
     task_name: &str,
     args: &[String],
     audit: bool,
-    trace_output: bool,
     shutdown_rx: &mut mpsc::Receiver<()>,
 ) -> Result<i32> {
-    if trace_output {
-        eprintln!("Note: Chrome trace output is not yet implemented");
-    }
+    // Timestamped lines are printed from the events of captured output
+    let executor = &executor
+        .clone()
+        .with_captured_output(super::timing::timestamps());
+    let timing = super::timing::TimingReporter::start();
 
     // Build unified DAG to show all tasks that will be executed (including dependencies)
     let dag = executor.build_unified_dag(&[task_name.to_string()])?;
@@ -424,7 +510,7 @@ async fn execute_with_simple(
         } => result,
         _ = shutdown_rx.recv() => {
             eprintln!("\n⚠️  Task cancelled by user");
-            Ok(130) // Standard exit code for SIGINT
+            Ok(ExitStatus::INTERRUPTED.code())
         }
     };
 
@@ -432,7 +518,7 @@ async fn execute_with_simple(
         Ok(0) => {
             println!("✓ Task completed successfully");
         }
-        Ok(130) => {
+        Ok(code) if code == ExitStatus::INTERRUPTED.code() => {
             // Don't print extra message for cancellation
         }
         Ok(code) => {
@@ -443,5 +529,9 @@ async fn execute_with_simple(
         }
     }
 
+    if let Some(timing) = timing {
+        timing.finish().await;
+    }
+
     result
 }
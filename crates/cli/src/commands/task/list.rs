@@ -0,0 +1,149 @@
+//! `cuenv task list`: tasks selected by their tags, owner or deprecation
+
+use clap::Parser;
+use cuenv_config::{Config, TaskConfig};
+use cuenv_core::{Error, Result};
+
+/// Reserved task name that triggers the listing command
+pub const LIST_TASK_COMMAND: &str = "list";
+
+/// Flags accepted by `cuenv task list`
+#[derive(Parser, Debug)]
+#[command(
+    name = "cuenv task list",
+    about = "List tasks, optionally selected by metadata"
+)]
+struct TaskListArgs {
+    /// Only tasks with this tag, e.g. from `@tag("slow")` (repeatable; all must match)
+    #[arg(short, long = "tag")]
+    tags: Vec<String>,
+
+    /// Only tasks owned by this team or person
+    #[arg(long)]
+    owner: Option<String>,
+
+    /// Only deprecated tasks
+    #[arg(long)]
+    deprecated: bool,
+
+    /// Show task descriptions
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+impl TaskListArgs {
+    fn is_filtered(&self) -> bool {
+        !self.tags.is_empty() || self.owner.is_some() || self.deprecated
+    }
+
+    fn matches(&self, task: &TaskConfig) -> bool {
+        self.tags.iter().all(|tag| task.has_tag(tag))
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| task.owner.as_ref() == Some(owner))
+            && (!self.deprecated || task.deprecated.is_some())
+    }
+}
+
+/// Parse the trailing arguments of `cuenv task list` and list the tasks
+pub async fn execute(config: std::sync::Arc<Config>, args: Vec<String>) -> Result<()> {
+    let args = match TaskListArgs::try_parse_from(
+        std::iter::once(format!("cuenv task {LIST_TASK_COMMAND}")).chain(args),
+    ) {
+        Ok(args) => args,
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            return Ok(());
+        }
+        Err(e) => return Err(Error::configuration(e.to_string())),
+    };
+
+    if !args.is_filtered() {
+        return super::list_tasks(config, args.verbose, None).await;
+    }
+
+    let mut tasks: Vec<(&String, &TaskConfig)> = config
+        .get_tasks()
+        .iter()
+        .filter(|(_, task)| args.matches(task))
+        .collect();
+    if tasks.is_empty() {
+        println!("No matching tasks");
+        return Ok(());
+    }
+    tasks.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (name, task) in tasks {
+        println!("{}", format_task(name, task, args.verbose));
+    }
+    Ok(())
+}
+
+/// One line for `task`: its name, metadata and, when verbose, description
+fn format_task(name: &str, task: &TaskConfig, verbose: bool) -> String {
+    let mut line = name.to_string();
+    if let Some(tags) = task.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        line.push_str(&format!(" [{}]", tags.join(", ")));
+    }
+    if let Some(owner) = &task.owner {
+        line.push_str(&format!(" (owner: {owner})"));
+    }
+    if let Some(description) = task.description.as_ref().filter(|_| verbose) {
+        line.push_str(&format!(" – {description}"));
+    }
+    if let Some(note) = &task.deprecated {
+        match note.as_str() {
+            "" => line.push_str(" (deprecated)"),
+            note => line.push_str(&format!(" (deprecated: {note})")),
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(flags: &[&str]) -> TaskListArgs {
+        TaskListArgs::try_parse_from(
+            std::iter::once("cuenv task list").chain(flags.iter().copied()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_selects_by_tags_owner_and_deprecation() {
+        let task = TaskConfig {
+            tags: Some(vec!["slow".to_string(), "ci".to_string()]),
+            owner: Some("team-x".to_string()),
+            ..TaskConfig::default()
+        };
+
+        assert!(!args(&[]).is_filtered());
+        assert!(args(&["--tag", "slow"]).matches(&task));
+        assert!(args(&["--tag", "slow", "--tag", "ci", "--owner", "team-x"]).matches(&task));
+        assert!(!args(&["--tag", "slow", "--tag", "fast"]).matches(&task));
+        assert!(!args(&["--owner", "team-y"]).matches(&task));
+        assert!(!args(&["--deprecated"]).matches(&task));
+    }
+
+    #[test]
+    fn test_format_task_shows_metadata() {
+        let task = TaskConfig {
+            description: Some("Build everything".to_string()),
+            tags: Some(vec!["slow".to_string()]),
+            owner: Some("team-x".to_string()),
+            deprecated: Some("use build2".to_string()),
+            ..TaskConfig::default()
+        };
+        assert_eq!(
+            format_task("build", &task, false),
+            "build [slow] (owner: team-x) (deprecated: use build2)"
+        );
+        assert_eq!(
+            format_task("build", &task, true),
+            "build [slow] (owner: team-x) – Build everything (deprecated: use build2)"
+        );
+    }
+}
@@ -1,17 +1,39 @@
+mod confirm;
+mod detach;
+mod diagnostics;
 mod display;
+mod exit_policy;
+mod export;
 mod formatter;
 mod graph;
+mod list;
+mod new;
+mod report;
+mod resolve;
+mod run;
+mod template;
+mod timing;
+mod tmux;
 
 use clap::Subcommand;
-use cuenv_config::{Config, TaskNode};
+use cuenv_config::{Config, TaskConfig, TaskNode};
+use cuenv_core::suggestions::did_you_mean;
 use cuenv_core::{Result, CUENV_CAPABILITIES_VAR, CUENV_ENV_VAR};
 use cuenv_env::manager::environment::SupervisorMode;
 use cuenv_env::EnvManager;
-use cuenv_task::TaskExecutor;
+use cuenv_task::{similar_task_names, TaskExecutor};
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 use std::sync::Arc;
 
+pub use self::confirm::{assume_yes, confirm_tasks};
+pub use self::detach::supervise as supervise_detached_run;
+pub use self::diagnostics::write_json_to as write_diagnostics_json_to;
 use self::display::{display_group_contents, display_task_tree};
+pub use self::exit_policy::ExitPolicy;
+pub use self::report::configure as configure_reports;
+pub use self::timing::configure as configure_timing;
 
 /// Execute the simplified task command
 #[allow(clippy::too_many_arguments)]
@@ -27,6 +49,7 @@ pub async fn execute_task_command(
     trace_output: bool,
     graph: Option<String>,
     charset: String,
+    exit_policy: ExitPolicy,
 ) -> Result<()> {
     // If --graph flag is set, show the dependency graph instead of executing
     if graph.is_some() {
@@ -38,9 +61,51 @@ pub async fn execute_task_command(
             // No arguments: list all tasks
             list_tasks(config, verbose, None).await
         }
+        // `task new` scaffolds a task unless the project defines its own `new`
+        Some(name)
+            if name == new::NEW_TASK_COMMAND
+                && !config.get_tasks().contains_key(new::NEW_TASK_COMMAND) =>
+        {
+            new::execute(&config, args).await
+        }
+        // `task list` likewise, unless the project defines its own `list`
+        Some(name)
+            if name == list::LIST_TASK_COMMAND
+                && !config.get_tasks().contains_key(list::LIST_TASK_COMMAND) =>
+        {
+            list::execute(config, args).await
+        }
+        // `task run` likewise, unless the project defines its own `run`
+        Some(name)
+            if name == run::RUN_TASK_COMMAND
+                && !config.get_tasks().contains_key(run::RUN_TASK_COMMAND) =>
+        {
+            run::execute(&config, environment, capabilities, audit, args).await
+        }
+        // `task attach` and `task wait` likewise, for detached runs
+        Some(name)
+            if name == detach::ATTACH_TASK_COMMAND
+                && !config.get_tasks().contains_key(detach::ATTACH_TASK_COMMAND) =>
+        {
+            detach::attach(args).await
+        }
+        Some(name)
+            if name == detach::WAIT_TASK_COMMAND
+                && !config.get_tasks().contains_key(detach::WAIT_TASK_COMMAND) =>
+        {
+            detach::wait(args).await
+        }
+        // `task export` likewise, unless the project defines its own `export`
+        Some(name)
+            if name == export::EXPORT_TASK_COMMAND
+                && !config.get_tasks().contains_key(export::EXPORT_TASK_COMMAND) =>
+        {
+            export::execute(&config, environment, args).await
+        }
         Some(name) => {
             // Check if it's a task or a group
             let tasks = config.get_tasks();
+            let name = resolve::resolve_task(tasks, name, &args)?;
 
             // First check if it's a direct task
             if tasks.contains_key(&name) {
@@ -54,6 +119,7 @@ pub async fn execute_task_command(
                     audit,
                     output_format.clone(),
                     trace_output,
+                    exit_policy,
                 )
                 .await
             } else if args.is_empty() {
@@ -69,13 +135,13 @@ pub async fn execute_task_command(
                             cuenv_config::TaskCollection::Sequential(_) => {
                                 // Sequential collection: execute all tasks in order
                                 execute_task_group(
-                                    config.clone(),
                                     environment,
                                     capabilities,
                                     name,
                                     audit,
                                     output_format,
                                     trace_output,
+                                    exit_policy,
                                 )
                                 .await
                             }
@@ -83,13 +149,13 @@ pub async fn execute_task_command(
                                 // Parallel collection: can execute as group or list tasks
                                 // For now, execute as a group (dependency-based execution)
                                 execute_task_group(
-                                    config.clone(),
                                     environment,
                                     capabilities,
                                     name,
                                     audit,
                                     output_format,
                                     trace_output,
+                                    exit_policy,
                                 )
                                 .await
                             }
@@ -101,6 +167,7 @@ pub async fn execute_task_command(
                 } else {
                     // Not found as task or group
                     eprintln!("Task or group '{name}' not found");
+                    print_suggestions(tasks, &name);
                     eprintln!("Run 'cuenv task' to see available tasks");
                     std::process::exit(1)
                 }
@@ -120,6 +187,7 @@ pub async fn execute_task_command(
                         audit,
                         output_format.clone(),
                         trace_output,
+                        exit_policy,
                     )
                     .await
                 } else {
@@ -134,10 +202,12 @@ pub async fn execute_task_command(
                             audit,
                             output_format,
                             trace_output,
+                            exit_policy,
                         )
                         .await
                     } else {
                         eprintln!("Task '{name}' not found");
+                        print_suggestions(tasks, &name);
                         eprintln!("Run 'cuenv task' to see available tasks");
                         std::process::exit(1)
                     }
@@ -182,7 +252,7 @@ pub enum TaskCommands {
         #[arg(long)]
         audit: bool,
 
-        /// Output format for task execution (tui, simple, or spinner)
+        /// Output format for task execution (tui, simple, spinner, tmux, template or json)
         #[arg(long, value_name = "FORMAT", default_value = "spinner")]
         output: String,
 
@@ -215,7 +285,9 @@ async fn list_tasks(
     if let Some(ref group) = group_filter {
         if let Some(node) = task_nodes.get(group) {
             match node {
-                TaskNode::Group { description, tasks } => {
+                TaskNode::Group {
+                    description, tasks, ..
+                } => {
                     display_group_contents(
                         group,
                         description.as_deref(),
@@ -255,32 +327,11 @@ async fn execute_task(
     audit: bool,
     output_format: String,
     trace_output: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<()> {
     let current_dir = env::current_dir()
         .map_err(|e| cuenv_core::Error::file_system(".", "get current directory", e))?;
-    let mut env_manager = EnvManager::new();
-
-    let env_name = environment.or_else(|| env::var(CUENV_ENV_VAR).ok());
-    let mut caps = capabilities;
-    if caps.is_empty() {
-        if let Ok(env_caps) = env::var(CUENV_CAPABILITIES_VAR) {
-            caps = env_caps
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-        }
-    }
-
-    env_manager
-        .load_env_with_options(
-            &current_dir,
-            env_name,
-            caps,
-            None,
-            SupervisorMode::Foreground,
-        )
-        .await?;
+    let env_manager = load_env_manager(&current_dir, environment, capabilities).await?;
 
     // Check if this might be a group/subtask pattern (e.g., "fmt" with first arg "check")
     // First try the task as-is, then try as group.subtask if not found
@@ -325,6 +376,7 @@ async fn execute_task(
             &actual_task_name,
             &actual_args,
             audit,
+            exit_policy,
         )
         .await?;
         std::process::exit(status);
@@ -339,8 +391,12 @@ async fn execute_task(
             audit,
             &output_format,
             trace_output,
+            exit_policy,
         )
         .await?;
+        if audit {
+            eprintln!("Allow the access it used with: cuenv security allow {actual_task_name}");
+        }
         std::process::exit(status);
     } else {
         // Check if this might be a task group
@@ -361,20 +417,109 @@ async fn execute_task(
             eprintln!("Run 'cuenv task {task_name} <task>' to execute a task");
         } else {
             eprintln!("Task '{task_name}' not found");
+            print_suggestions(env_manager.get_tasks(), &task_name);
             eprintln!("Run 'cuenv task list' to see available tasks");
         }
         std::process::exit(1);
     }
 }
 
+/// Suggest the tasks a name that was not found may have meant
+fn print_suggestions(tasks: &HashMap<String, TaskConfig>, name: &str) {
+    if let Some(hint) = did_you_mean(&similar_task_names(tasks, name)) {
+        eprintln!("{hint}");
+    }
+}
+
+/// Load the environment tasks run in, falling back to `CUENV_ENV` and
+/// `CUENV_CAPABILITIES` when no environment or capabilities are given
+async fn load_env_manager(
+    current_dir: &Path,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+) -> Result<EnvManager> {
+    let mut env_manager = EnvManager::new();
+
+    let env_name = environment.or_else(|| env::var(CUENV_ENV_VAR).ok());
+    let mut caps = capabilities;
+    if caps.is_empty() {
+        if let Ok(env_caps) = env::var(CUENV_CAPABILITIES_VAR) {
+            caps = env_caps
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+    }
+
+    env_manager
+        .load_env_with_options(
+            current_dir,
+            env_name,
+            caps,
+            None,
+            SupervisorMode::Foreground,
+        )
+        .await?;
+    Ok(env_manager)
+}
+
+/// Run tasks or groups one after the other and return the exit code of the
+/// first that fails, or 0
+///
+/// Unlike `cuenv task`, this does not exit the process, for commands that
+/// run several tasks or keep going once they finish, such as
+/// `cuenv run-config` and `cuenv serve --web`.
+pub(crate) async fn run_tasks(
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    task_names: &[String],
+    args: &[String],
+    audit: bool,
+    output_format: &str,
+) -> Result<i32> {
+    let current_dir = env::current_dir()
+        .map_err(|e| cuenv_core::Error::file_system(".", "get current directory", e))?;
+    let env_manager = load_env_manager(&current_dir, environment, capabilities).await?;
+    let executor = TaskExecutor::new(env_manager, current_dir.clone()).await?;
+
+    for task_name in task_names {
+        let status = if task_name.contains(':') && crate::monorepo::is_monorepo(&current_dir) {
+            crate::monorepo::execute_monorepo_task(
+                &current_dir,
+                task_name,
+                args,
+                audit,
+                ExitPolicy::default(),
+            )
+            .await?
+        } else {
+            formatter::execute_with_formatter(
+                &executor,
+                task_name,
+                args,
+                audit,
+                output_format,
+                false,
+                ExitPolicy::default(),
+            )
+            .await?
+        };
+        if status != 0 {
+            return Ok(status);
+        }
+    }
+    Ok(0)
+}
+
 async fn execute_task_group(
-    config: std::sync::Arc<cuenv_config::Config>,
     environment: Option<String>,
     capabilities: Vec<String>,
     group_name: String,
     audit: bool,
     output_format: String,
     trace_output: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<()> {
     let current_dir = env::current_dir()
         .map_err(|e| cuenv_core::Error::file_system(".", "get current directory", e))?;
@@ -400,7 +545,7 @@ async fn execute_task_group(
         .await?;
 
     // Get the group's collection type for display
-    let task_nodes = config.get_task_nodes();
+    let task_nodes = env_manager.get_task_nodes();
     let collection_type = if let Some(TaskNode::Group { tasks, .. }) = task_nodes.get(&group_name) {
         match tasks {
             cuenv_config::TaskCollection::Sequential(_) => "sequential",
@@ -425,6 +570,7 @@ async fn execute_task_group(
         audit,
         &output_format,
         trace_output,
+        exit_policy,
     )
     .await?;
 
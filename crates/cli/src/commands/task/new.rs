@@ -0,0 +1,160 @@
+//! `cuenv task new`: scaffold a task into the current package
+
+use clap::Parser;
+use cuenv_config::editor::{add_task_to_file, TaskScaffold};
+use cuenv_config::Config;
+use cuenv_core::{Error, Result, CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME, ENV_CUE_FILENAME};
+use std::io::{self, BufRead, Write};
+
+/// Reserved task name that triggers the scaffolding command
+pub const NEW_TASK_COMMAND: &str = "new";
+
+/// Flags accepted by `cuenv task new`
+#[derive(Parser, Debug)]
+#[command(name = "cuenv task new", about = "Add a new task to env.cue")]
+struct TaskNewArgs {
+    /// Name of the task to create (prompted for when omitted)
+    name: Option<String>,
+
+    /// Human readable description
+    #[arg(short, long)]
+    description: Option<String>,
+
+    /// Shell command the task runs (prompted for when omitted)
+    #[arg(long)]
+    command: Option<String>,
+
+    /// Input file patterns (repeatable)
+    #[arg(short, long = "input")]
+    inputs: Vec<String>,
+
+    /// Output file patterns (repeatable)
+    #[arg(short, long = "output")]
+    outputs: Vec<String>,
+
+    /// Enable caching for the task
+    #[arg(long, conflicts_with = "no_cache")]
+    cache: bool,
+
+    /// Disable caching for the task
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Add a restrictive security block inferred from inputs and outputs
+    #[arg(long)]
+    secure: bool,
+
+    /// Never prompt; fail if required values are missing
+    #[arg(long)]
+    non_interactive: bool,
+}
+
+/// Parse the trailing arguments of `cuenv task new` and add the task
+pub async fn execute(config: &Config, args: Vec<String>) -> Result<()> {
+    let args = match TaskNewArgs::try_parse_from(
+        std::iter::once(format!("cuenv task {NEW_TASK_COMMAND}")).chain(args),
+    ) {
+        Ok(args) => args,
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            return Ok(());
+        }
+        Err(e) => return Err(Error::configuration(e.to_string())),
+    };
+
+    let env_file = config.working_dir.join(ENV_CUE_FILENAME);
+    if !env_file.exists() {
+        return Err(Error::configuration(format!(
+            "No {ENV_CUE_FILENAME} found in {}. Run 'cuenv init' first.",
+            config.working_dir.display()
+        )));
+    }
+
+    let interactive = !args.non_interactive && atty::is(atty::Stream::Stdin);
+    let scaffold = build_scaffold(args, interactive)?;
+    let package_name =
+        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+
+    add_task_to_file(&env_file, &scaffold, &package_name)?;
+
+    println!("✓ Added task '{}' to {ENV_CUE_FILENAME}", scaffold.name);
+    println!("  Run it with: cuenv task {}", scaffold.name);
+    Ok(())
+}
+
+fn build_scaffold(args: TaskNewArgs, interactive: bool) -> Result<TaskScaffold> {
+    let name = required(args.name, "Task name", interactive)?;
+    let description = match args.description {
+        Some(description) => Some(description),
+        None if interactive => prompt("Description (optional)")?,
+        None => None,
+    };
+    let command = required(args.command, "Command", interactive)?;
+
+    let inputs = if args.inputs.is_empty() && interactive {
+        prompt_list("Input patterns, comma separated (optional)")?
+    } else {
+        args.inputs
+    };
+    let outputs = if args.outputs.is_empty() && interactive {
+        prompt_list("Output patterns, comma separated (optional)")?
+    } else {
+        args.outputs
+    };
+
+    let cache = match (args.cache, args.no_cache) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+
+    Ok(TaskScaffold {
+        name,
+        description,
+        command,
+        inputs,
+        outputs,
+        cache,
+        security: args.secure,
+    })
+}
+
+fn required(value: Option<String>, label: &str, interactive: bool) -> Result<String> {
+    match value {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        _ if interactive => {
+            prompt(label)?.ok_or_else(|| Error::configuration(format!("{label} is required")))
+        }
+        _ => Err(Error::configuration(format!(
+            "{label} is required (pass it as a flag or run interactively)"
+        ))),
+    }
+}
+
+fn prompt(label: &str) -> Result<Option<String>> {
+    print!("{label}: ");
+    io::stdout()
+        .flush()
+        .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+
+    let trimmed = line.trim();
+    Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+}
+
+fn prompt_list(label: &str) -> Result<Vec<String>> {
+    Ok(prompt(label)?
+        .map(|line| {
+            line.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default())
+}
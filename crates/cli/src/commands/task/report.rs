@@ -0,0 +1,502 @@
+//! Summary artifacts of a run, for CI
+//!
+//! With `--report junit=<path>,md=<path>` the tasks of a run are followed on
+//! the event bus, and once it ends they are written out as JUnit XML for the
+//! test tabs of CI systems and as Markdown for pull request comments: each
+//! task's status, duration and whether the cache served it, with the last
+//! lines of output of the tasks that failed. Task output is published while
+//! it is shown, so excerpts are there whatever the output format.
+
+use cuenv_core::events::EnhancedEvent;
+use cuenv_core::{Error, Result, SystemEvent, TaskEvent};
+use cuenv_task::TaskExecutor;
+use regex::Regex;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+/// `--report`, for the rest of the process
+static TARGETS: OnceLock<Vec<ReportTarget>> = OnceLock::new();
+
+/// Lines of output kept for the excerpt of a failed task
+const EXCERPT_LINES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Junit,
+    Markdown,
+}
+
+/// A file to write the report to, and its format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTarget {
+    format: ReportFormat,
+    path: PathBuf,
+}
+
+/// Write reports of runs to the files of `--report` values such as
+/// `junit=report.xml,md=summary.md`
+pub fn configure(specs: &[String]) -> Result<()> {
+    let targets = specs
+        .iter()
+        .flat_map(|spec| spec.split(','))
+        .filter(|target| !target.trim().is_empty())
+        .map(parse_target)
+        .collect::<Result<Vec<_>>>()?;
+    if !targets.is_empty() {
+        cuenv_task::publish_piped_output(true);
+        let _ = TARGETS.set(targets);
+    }
+    Ok(())
+}
+
+fn parse_target(target: &str) -> Result<ReportTarget> {
+    let invalid = || {
+        Error::configuration(format!(
+            "Invalid --report '{target}'. Expected junit=<path> or md=<path>"
+        ))
+    };
+    let (format, path) = target.trim().split_once('=').ok_or_else(invalid)?;
+    let format = match format {
+        "junit" => ReportFormat::Junit,
+        "md" | "markdown" => ReportFormat::Markdown,
+        _ => return Err(invalid()),
+    };
+    if path.is_empty() {
+        return Err(invalid());
+    }
+    Ok(ReportTarget {
+        format,
+        path: PathBuf::from(path),
+    })
+}
+
+/// How a task ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// A task of the run as the report shows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TaskResult {
+    name: String,
+    status: Status,
+    duration: Duration,
+    cached: bool,
+    /// Why it failed or was skipped
+    message: Option<String>,
+    /// Last lines of its output, for failed tasks
+    excerpt: Vec<String>,
+}
+
+/// Follows a run and writes its report
+pub struct RunReporter {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<RunRecord>,
+}
+
+impl RunReporter {
+    /// Start following the tasks run from now on, unless no `--report` was
+    /// given
+    pub fn start() -> Option<Self> {
+        TARGETS.get()?;
+        let mut events = cuenv_core::events::global_event_bus().subscribe();
+        let mut record = RunRecord::new(SystemTime::now());
+        let (stop, mut stopped) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    event = events.recv() => match event {
+                        Ok(event) => record.record(event),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("Missed {missed} events of the run report");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = &mut stopped => {
+                        // Everything published before stopping is queued already
+                        while let Ok(event) = events.try_recv() {
+                            record.record(event);
+                        }
+                        break;
+                    }
+                }
+            }
+            record
+        });
+        Some(Self { stop, handle })
+    }
+
+    /// Stop following the run of `root` and write its reports
+    pub async fn finish(self, root: &str, executor: &TaskExecutor) {
+        let _ = self.stop.send(());
+        let Ok(record) = self.handle.await else {
+            return;
+        };
+        let wall_time = SystemTime::now()
+            .duration_since(record.start)
+            .unwrap_or_default();
+        let results = record.results(|task| executor.is_cached(task));
+        for target in TARGETS.get().into_iter().flatten() {
+            let report = match target.format {
+                ReportFormat::Junit => junit(root, &results, wall_time),
+                ReportFormat::Markdown => markdown(root, &results, wall_time),
+            };
+            match write(&target.path, &report) {
+                Ok(()) => eprintln!("Report written to {}", target.path.display()),
+                Err(e) => eprintln!("Warning: {e}"),
+            }
+        }
+    }
+}
+
+/// A task run as far as its events went
+struct Entry {
+    name: String,
+    started: Option<SystemTime>,
+    ended: Option<(Status, Duration, Option<String>)>,
+    output: VecDeque<String>,
+}
+
+/// Task events of a run, by run id in the order tasks first appeared
+struct RunRecord {
+    start: SystemTime,
+    entries: Vec<Entry>,
+    by_run: HashMap<String, usize>,
+}
+
+impl RunRecord {
+    fn new(start: SystemTime) -> Self {
+        Self {
+            start,
+            entries: Vec::new(),
+            by_run: HashMap::new(),
+        }
+    }
+
+    fn entry(&mut self, task_name: String, run_id: String) -> &mut Entry {
+        let entries = &mut self.entries;
+        let index = *self.by_run.entry(run_id).or_insert_with(|| {
+            entries.push(Entry {
+                name: task_name,
+                started: None,
+                ended: None,
+                output: VecDeque::new(),
+            });
+            entries.len() - 1
+        });
+        &mut self.entries[index]
+    }
+
+    fn record(&mut self, event: EnhancedEvent) {
+        let SystemEvent::Task(task_event) = event.event else {
+            return;
+        };
+        let at = event.timestamp;
+        match task_event {
+            TaskEvent::TaskStarted { task_name, task_id } => {
+                self.entry(task_name, task_id).started = Some(at);
+            }
+            TaskEvent::TaskOutput {
+                task_name,
+                task_id,
+                output: line,
+            }
+            | TaskEvent::TaskError {
+                task_name,
+                task_id,
+                error: line,
+            } => {
+                let output = &mut self.entry(task_name, task_id).output;
+                if output.len() == EXCERPT_LINES {
+                    output.pop_front();
+                }
+                output.push_back(line);
+            }
+            TaskEvent::TaskCompleted {
+                task_name,
+                task_id,
+                duration_ms,
+            } => {
+                self.entry(task_name, task_id).ended =
+                    Some((Status::Passed, Duration::from_millis(duration_ms), None));
+            }
+            TaskEvent::TaskFailed {
+                task_name,
+                task_id,
+                error,
+                ..
+            } => {
+                let entry = self.entry(task_name, task_id);
+                let duration = entry
+                    .started
+                    .and_then(|started| at.duration_since(started).ok())
+                    .unwrap_or_default();
+                entry.ended = Some((Status::Failed, duration, Some(error)));
+            }
+            TaskEvent::TaskSkipped {
+                task_name,
+                task_id,
+                reason,
+            } => {
+                self.entry(task_name, task_id).ended =
+                    Some((Status::Skipped, Duration::ZERO, Some(reason)));
+            }
+            _ => {}
+        }
+    }
+
+    /// What the report shows of each task; tasks that never ended failed
+    fn results(self, is_cached: impl Fn(&str) -> bool) -> Vec<TaskResult> {
+        self.entries
+            .into_iter()
+            .map(|entry| {
+                let (status, duration, message) = entry.ended.unwrap_or((
+                    Status::Failed,
+                    Duration::ZERO,
+                    Some("did not finish".to_string()),
+                ));
+                let excerpt = if status == Status::Failed {
+                    entry.output.into_iter().collect()
+                } else {
+                    Vec::new()
+                };
+                TaskResult {
+                    cached: status == Status::Passed && is_cached(&entry.name),
+                    name: entry.name,
+                    status,
+                    duration,
+                    message,
+                    excerpt,
+                }
+            })
+            .collect()
+    }
+}
+
+fn count(results: &[TaskResult], status: Status) -> usize {
+    results
+        .iter()
+        .filter(|result| result.status == status)
+        .count()
+}
+
+/// JUnit XML with a test suite for the run and a test case per task
+fn junit(root: &str, results: &[TaskResult], wall_time: Duration) -> String {
+    let counts = format!(
+        r#"tests="{}" failures="{}" skipped="{}" time="{:.3}""#,
+        results.len(),
+        count(results, Status::Failed),
+        count(results, Status::Skipped),
+        wall_time.as_secs_f64()
+    );
+    let mut xml = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        format!(r#"<testsuites name="cuenv" {counts}>"#),
+        format!(r#"  <testsuite name="{}" {counts}>"#, xml_escape(root)),
+    ];
+    for result in results {
+        xml.push(format!(
+            r#"    <testcase name="{}" classname="cuenv.{}" time="{:.3}">"#,
+            xml_escape(&result.name),
+            xml_escape(root),
+            result.duration.as_secs_f64()
+        ));
+        xml.push(format!(
+            r#"      <properties><property name="cached" value="{}"/></properties>"#,
+            result.cached
+        ));
+        let message = xml_escape(result.message.as_deref().unwrap_or_default());
+        match result.status {
+            Status::Passed => {}
+            Status::Skipped => xml.push(format!(r#"      <skipped message="{message}"/>"#)),
+            Status::Failed => xml.push(format!(
+                r#"      <failure message="{message}">{}</failure>"#,
+                xml_escape(&result.excerpt.join("\n"))
+            )),
+        }
+        xml.push("    </testcase>".to_string());
+    }
+    xml.push("  </testsuite>".to_string());
+    xml.push("</testsuites>".to_string());
+    xml.join("\n") + "\n"
+}
+
+/// Markdown with a table of the tasks and the output of those that failed
+fn markdown(root: &str, results: &[TaskResult], wall_time: Duration) -> String {
+    let failed = count(results, Status::Failed);
+    let passed = count(results, Status::Passed);
+    let skipped = count(results, Status::Skipped);
+    let mark = if failed > 0 { "✗" } else { "✓" };
+    let mut md = vec![
+        format!(
+            "## {mark} `cuenv task {root}`: {passed} passed, {failed} failed, {skipped} skipped in {:.1}s",
+            wall_time.as_secs_f64()
+        ),
+        String::new(),
+        "| Task | Status | Duration | Cache |".to_string(),
+        "| --- | --- | --- | --- |".to_string(),
+    ];
+    for result in results {
+        let status = match result.status {
+            Status::Passed => "✓ passed".to_string(),
+            Status::Failed => "✗ failed".to_string(),
+            Status::Skipped => match &result.message {
+                Some(reason) => format!("⊘ skipped: {}", table_escape(reason)),
+                None => "⊘ skipped".to_string(),
+            },
+        };
+        md.push(format!(
+            "| `{}` | {status} | {:.1}s | {} |",
+            result.name,
+            result.duration.as_secs_f64(),
+            if result.cached { "hit" } else { "" }
+        ));
+    }
+    for result in results.iter().filter(|r| r.status == Status::Failed) {
+        let message = result.message.as_deref().unwrap_or("failed");
+        md.push(String::new());
+        md.push(format!(
+            "<details><summary><code>{}</code>: {}</summary>",
+            html_escape(&result.name),
+            html_escape(message)
+        ));
+        md.push(String::new());
+        md.push("```text".to_string());
+        md.extend(result.excerpt.iter().map(|line| strip_ansi(line)));
+        md.push("```".to_string());
+        md.push(String::new());
+        md.push("</details>".to_string());
+    }
+    md.join("\n") + "\n"
+}
+
+fn write(path: &Path, report: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| Error::file_system(parent, "create report directory", e))?;
+    }
+    std::fs::write(path, report).map_err(|e| Error::file_system(path, "write run report", e))
+}
+
+/// Terminal escape sequences, which task output is often colored with
+fn strip_ansi(text: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").expect("ANSI escape pattern is valid")
+    });
+    ansi.replace_all(text, "").into_owned()
+}
+
+/// Text safe in XML attributes and content; control characters XML can't
+/// hold are dropped
+fn xml_escape(text: &str) -> String {
+    strip_ansi(text)
+        .chars()
+        .filter(|&c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+fn html_escape(text: &str) -> String {
+    strip_ansi(text)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn table_escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<TaskResult> {
+        vec![
+            TaskResult {
+                name: "ci.lint".to_string(),
+                status: Status::Passed,
+                duration: Duration::from_millis(1200),
+                cached: true,
+                message: None,
+                excerpt: Vec::new(),
+            },
+            TaskResult {
+                name: "ci.test".to_string(),
+                status: Status::Failed,
+                duration: Duration::from_millis(3400),
+                cached: false,
+                message: Some("Task exited with exit code 1".to_string()),
+                excerpt: vec!["\x1b[31mFAIL\x1b[0m a < b".to_string()],
+            },
+            TaskResult {
+                name: "ci.deploy".to_string(),
+                status: Status::Skipped,
+                duration: Duration::ZERO,
+                cached: false,
+                message: Some("an earlier task failed".to_string()),
+                excerpt: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_report_targets() {
+        assert_eq!(
+            parse_target("junit=out/report.xml").unwrap(),
+            ReportTarget {
+                format: ReportFormat::Junit,
+                path: PathBuf::from("out/report.xml"),
+            }
+        );
+        assert_eq!(
+            parse_target("md=s.md").unwrap().format,
+            ReportFormat::Markdown
+        );
+        assert!(parse_target("html=r.html").is_err());
+        assert!(parse_target("junit").is_err());
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let xml = junit("ci", &results(), Duration::from_secs(5));
+        assert!(xml
+            .contains(r#"<testsuite name="ci" tests="3" failures="1" skipped="1" time="5.000">"#));
+        assert!(xml.contains(r#"<testcase name="ci.lint" classname="cuenv.ci" time="1.200">"#));
+        assert!(xml.contains(r#"<property name="cached" value="true"/>"#));
+        assert!(xml.contains(
+            r#"<failure message="Task exited with exit code 1">FAIL a &lt; b</failure>"#
+        ));
+        assert!(xml.contains(r#"<skipped message="an earlier task failed"/>"#));
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let md = markdown("ci", &results(), Duration::from_secs(5));
+        assert!(md.starts_with("## ✗ `cuenv task ci`: 1 passed, 1 failed, 1 skipped in 5.0s\n"));
+        assert!(md.contains("| `ci.lint` | ✓ passed | 1.2s | hit |"));
+        assert!(md.contains("| `ci.deploy` | ⊘ skipped: an earlier task failed | 0.0s |  |"));
+        assert!(
+            md.contains("<summary><code>ci.test</code>: Task exited with exit code 1</summary>")
+        );
+        assert!(md.contains("```text\nFAIL a < b\n```"));
+    }
+}
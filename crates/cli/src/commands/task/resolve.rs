@@ -0,0 +1,75 @@
+//! Resolving aliases and partial task names given to `cuenv task`
+
+use cuenv_config::TaskConfig;
+use cuenv_core::{Error, Result};
+use cuenv_task::{resolve_task_name, TaskResolution};
+use std::collections::HashMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Full name of the task `name` refers to
+///
+/// Names of tasks and groups, and `group subtask` pairs, are returned
+/// unchanged. Otherwise aliases and trailing parts of task names are
+/// resolved; when several tasks match, the user picks one interactively.
+/// A name nothing matches is returned unchanged as well.
+pub fn resolve_task(
+    tasks: &HashMap<String, TaskConfig>,
+    name: String,
+    args: &[String],
+) -> Result<String> {
+    if names_task_or_group(tasks, &name, args) {
+        return Ok(name);
+    }
+
+    match resolve_task_name(tasks, &name) {
+        TaskResolution::Found(resolved) => {
+            tracing::debug!("Resolved task '{name}' to '{resolved}'");
+            Ok(resolved)
+        }
+        TaskResolution::Ambiguous(candidates) => choose_candidate(&name, candidates),
+        TaskResolution::NotFound => Ok(name),
+    }
+}
+
+fn names_task_or_group(tasks: &HashMap<String, TaskConfig>, name: &str, args: &[String]) -> bool {
+    let prefix = format!("{name}.");
+    tasks.contains_key(name)
+        || tasks.keys().any(|key| key.starts_with(&prefix))
+        || args
+            .first()
+            .is_some_and(|subtask| tasks.contains_key(&format!("{prefix}{subtask}")))
+}
+
+fn choose_candidate(name: &str, candidates: Vec<String>) -> Result<String> {
+    let listing = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| format!("  {}) {candidate}", index + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if !io::stdin().is_terminal() {
+        return Err(Error::configuration(format!(
+            "Task '{name}' is ambiguous; use one of the full names:\n{listing}"
+        )));
+    }
+
+    eprintln!("Task '{name}' matches several tasks:\n{listing}");
+    eprint!("Run which task? [1-{}] ", candidates.len());
+    io::stderr()
+        .flush()
+        .map_err(|e| Error::configuration(format!("Failed to write prompt: {e}")))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| Error::configuration(format!("Failed to read input: {e}")))?;
+
+    line.trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|choice| choice.checked_sub(1))
+        .and_then(|index| candidates.get(index).cloned())
+        .ok_or_else(|| Error::configuration(format!("No task selected for '{name}'")))
+}
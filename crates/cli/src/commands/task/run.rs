@@ -0,0 +1,202 @@
+//! `cuenv task run`: run the tasks selected by label or by what changed
+
+use clap::Parser;
+use cuenv_config::Config;
+use cuenv_core::{Error, Result};
+use cuenv_task::selection::{changed_files, TaskSelection};
+use cuenv_task::TaskExecutor;
+use std::collections::HashMap;
+
+/// Reserved task name that triggers the selection command
+pub const RUN_TASK_COMMAND: &str = "run";
+
+/// Flags accepted by `cuenv task run`
+#[derive(Parser, Debug)]
+#[command(
+    name = "cuenv task run",
+    about = "Run the tasks selected by label or by what changed"
+)]
+struct TaskRunArgs {
+    /// Tasks or groups to select from (default: all tasks)
+    tasks: Vec<String>,
+
+    /// Only tasks with this label (repeatable; a task must have all of them)
+    #[arg(short, long = "label")]
+    labels: Vec<String>,
+
+    /// Leave out tasks with this label (repeatable)
+    #[arg(long = "exclude-label")]
+    exclude_labels: Vec<String>,
+
+    /// Only tasks affected by changes since this git revision, e.g. origin/main
+    #[arg(long, value_name = "REF")]
+    affected: Option<String>,
+
+    /// Environment to use (e.g., dev, staging, production)
+    #[arg(short = 'e', long = "env")]
+    environment: Option<String>,
+
+    /// Capabilities to enable (can be specified multiple times)
+    #[arg(short = 'c', long = "capability")]
+    capabilities: Vec<String>,
+
+    /// Run in audit mode to see file and network access without restrictions
+    #[arg(long)]
+    audit: bool,
+
+    /// Print the selected tasks without running them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Run in the background and print the run ID; see `cuenv task attach`
+    /// and `cuenv task wait`
+    #[arg(long)]
+    detach: bool,
+}
+
+/// Parse the trailing arguments of `cuenv task run` and run the selection
+pub async fn execute(
+    config: &Config,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    audit: bool,
+    args: Vec<String>,
+) -> Result<()> {
+    let args = match TaskRunArgs::try_parse_from(
+        std::iter::once(format!("cuenv task {RUN_TASK_COMMAND}")).chain(args),
+    ) {
+        Ok(args) => args,
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            return Ok(());
+        }
+        Err(e) => return Err(Error::configuration(e.to_string())),
+    };
+
+    let current_dir =
+        std::env::current_dir().map_err(|e| Error::file_system(".", "get current directory", e))?;
+    let selection = TaskSelection {
+        labels: args.labels,
+        exclude_labels: args.exclude_labels,
+        changed_files: args
+            .affected
+            .as_deref()
+            .map(|base| changed_files(&current_dir, base))
+            .transpose()?,
+    };
+    if selection.is_empty() && args.tasks.is_empty() {
+        return Err(Error::configuration(
+            "Select tasks by name, --label, --exclude-label or --affected",
+        ));
+    }
+
+    let tasks = config.get_tasks();
+    let candidates = candidates(tasks, &args.tasks)?;
+    let selected = selection.select(tasks, candidates.iter().map(String::as_str))?;
+    if selected.is_empty() {
+        println!("No tasks selected");
+        return Ok(());
+    }
+    if args.dry_run {
+        for task in &selected {
+            println!("{task}");
+        }
+        return Ok(());
+    }
+
+    let environment = args.environment.or(environment);
+    let capabilities = if args.capabilities.is_empty() {
+        capabilities
+    } else {
+        args.capabilities
+    };
+    let audit = args.audit || audit;
+    let env_manager =
+        super::load_env_manager(&current_dir, environment.clone(), capabilities.clone()).await?;
+    let executor = TaskExecutor::new(env_manager, current_dir.clone()).await?;
+    if !super::confirm_tasks(&executor, &selected)? {
+        std::process::exit(1);
+    }
+
+    if args.detach {
+        // Confirmed above, so the run does not ask again
+        let mut run_args = vec!["task".to_string(), "--yes".to_string()];
+        run_args.extend(
+            environment
+                .into_iter()
+                .flat_map(|env| ["-e".to_string(), env]),
+        );
+        run_args.extend(
+            capabilities
+                .into_iter()
+                .flat_map(|cap| ["-c".to_string(), cap]),
+        );
+        if audit {
+            run_args.push("--audit".to_string());
+        }
+        run_args.push(RUN_TASK_COMMAND.to_string());
+        run_args.extend(selected);
+        let id = super::detach::start(&current_dir, run_args)?;
+        eprintln!("Started detached run; follow it with 'cuenv task attach {id}'");
+        println!("{id}");
+        return Ok(());
+    }
+
+    eprintln!(
+        "Running {} selected task(s): {}",
+        selected.len(),
+        selected.join(", ")
+    );
+    let status = executor
+        .execute_tasks_unified(&selected, &[], audit)
+        .await?;
+    if status != 0 {
+        std::process::exit(status);
+    }
+    Ok(())
+}
+
+/// The tasks named, with groups expanded to their tasks, or every task
+fn candidates<T>(tasks: &HashMap<String, T>, names: &[String]) -> Result<Vec<String>> {
+    if names.is_empty() {
+        return Ok(tasks.keys().cloned().collect());
+    }
+    let mut candidates = Vec::new();
+    for name in names {
+        let prefix = format!("{name}.");
+        let members: Vec<String> = tasks
+            .keys()
+            .filter(|task| *task == name || task.starts_with(&prefix))
+            .cloned()
+            .collect();
+        if members.is_empty() {
+            return Err(Error::configuration(format!(
+                "Task or group '{name}' not found"
+            )));
+        }
+        candidates.extend(members);
+    }
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_expand_groups() {
+        let tasks = HashMap::from([
+            ("build".to_string(), ()),
+            ("test.unit".to_string(), ()),
+            ("test.e2e".to_string(), ()),
+        ]);
+        let mut all = candidates(&tasks, &[]).unwrap();
+        all.sort();
+        assert_eq!(all, ["build", "test.e2e", "test.unit"]);
+
+        let mut group = candidates(&tasks, &["test".to_string()]).unwrap();
+        group.sort();
+        assert_eq!(group, ["test.e2e", "test.unit"]);
+        assert!(candidates(&tasks, &["deploy".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! The `template` and `json` output formats
+//!
+//! Both print the events of a run the way the project configures: `template`
+//! puts each event through the template `config: outputTemplates` has for
+//! its type, skipping types without one, and `json` prints every event as a
+//! line of JSON with the fields of `config: outputJson`. Task output is
+//! captured, so it arrives as `taskOutput` and `taskError` events.
+
+use cuenv_core::events::{EnhancedEvent, EventTemplates};
+use cuenv_core::{ExitStatus, Result, SystemEvent, TaskEvent};
+use cuenv_task::TaskExecutor;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// How events are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rendering {
+    Template,
+    Json,
+}
+
+/// Run a task, printing the events of the run with the project's templates
+pub async fn execute(
+    executor: &TaskExecutor,
+    task_name: &str,
+    args: &[String],
+    audit: bool,
+    rendering: Rendering,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) -> Result<i32> {
+    let executor = executor.clone().with_captured_output(true);
+    let templates = executor.env_manager().event_templates().clone();
+
+    let mut events = cuenv_core::events::global_event_bus().subscribe();
+    let (stop, mut stopped) = oneshot::channel::<()>();
+    let printer = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                event = events.recv() => match event {
+                    Ok(event) => print_event(&templates, rendering, &event),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Missed {missed} events rendering output");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = &mut stopped => {
+                    // Everything published before stopping is queued already
+                    while let Ok(event) = events.try_recv() {
+                        print_event(&templates, rendering, &event);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    let tasks = [task_name.to_string()];
+    let result = tokio::select! {
+        result = executor.execute_tasks_unified(&tasks, args, audit) => result,
+        _ = shutdown_rx.recv() => Ok(ExitStatus::INTERRUPTED.code()),
+    };
+
+    let _ = stop.send(());
+    let _ = printer.await;
+    result
+}
+
+fn print_event(templates: &EventTemplates, rendering: Rendering, event: &EnhancedEvent) {
+    match rendering {
+        Rendering::Json => println!("{}", templates.render_json(event)),
+        Rendering::Template => {
+            let Some(line) = templates.render_line(event) else {
+                return;
+            };
+            if matches!(event.event, SystemEvent::Task(TaskEvent::TaskError { .. })) {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Timestamps and durations in simple output
+//!
+//! With `--timestamps` every line tasks print is prefixed with the time
+//! since the run started and the task it came from. With `--summary` the
+//! duration of each task is listed once the run ends, followed by the
+//! slowest ones. Both follow the task events published on the event bus.
+
+use cuenv_core::events::EnhancedEvent;
+use cuenv_core::{SystemEvent, TaskEvent};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+/// `--timestamps`, for the rest of the process
+static TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// `--summary`, for the rest of the process
+static SUMMARY: AtomicBool = AtomicBool::new(false);
+
+/// Tasks listed as the slowest in the summary
+const SLOWEST_SHOWN: usize = 5;
+
+/// Prefix task output with timestamps and summarize durations after runs
+pub fn configure(timestamps: bool, summary: bool) {
+    TIMESTAMPS.store(timestamps, Ordering::Relaxed);
+    SUMMARY.store(summary, Ordering::Relaxed);
+}
+
+/// Whether task output is prefixed with timestamps, which needs it captured
+pub fn timestamps() -> bool {
+    TIMESTAMPS.load(Ordering::Relaxed)
+}
+
+/// How a task ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TaskTiming {
+    task: String,
+    duration: Duration,
+    outcome: Outcome,
+}
+
+/// Follows a run to print timestamped output and its summary
+pub struct TimingReporter {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<Timeline>,
+}
+
+impl TimingReporter {
+    /// Start following the tasks run from now on, unless neither
+    /// `--timestamps` nor `--summary` was given
+    pub fn start() -> Option<Self> {
+        let summary = SUMMARY.load(Ordering::Relaxed);
+        if !timestamps() && !summary {
+            return None;
+        }
+
+        let mut events = cuenv_core::events::global_event_bus().subscribe();
+        let mut timeline = Timeline::new(SystemTime::now(), timestamps());
+        let (stop, mut stopped) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    event = events.recv() => match event {
+                        Ok(event) => timeline.record(event),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("Missed {missed} events timing tasks");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = &mut stopped => {
+                        // Everything published before stopping is queued already
+                        while let Ok(event) = events.try_recv() {
+                            timeline.record(event);
+                        }
+                        break;
+                    }
+                }
+            }
+            timeline
+        });
+        Some(Self { stop, handle })
+    }
+
+    /// Stop following the run and print its summary if asked for
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let Ok(timeline) = self.handle.await else {
+            return;
+        };
+        if SUMMARY.load(Ordering::Relaxed) {
+            let wall_time = SystemTime::now()
+                .duration_since(timeline.start)
+                .unwrap_or_default();
+            eprintln!("{}", summary(&timeline.timings, wall_time));
+        }
+    }
+}
+
+/// Task events of a run, in the order they were published
+struct Timeline {
+    start: SystemTime,
+    print_output: bool,
+    /// When each running task started, by run id
+    started: HashMap<String, SystemTime>,
+    timings: Vec<TaskTiming>,
+}
+
+impl Timeline {
+    fn new(start: SystemTime, print_output: bool) -> Self {
+        Self {
+            start,
+            print_output,
+            started: HashMap::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, event: EnhancedEvent) {
+        let SystemEvent::Task(task_event) = event.event else {
+            return;
+        };
+        let at = event.timestamp;
+        match task_event {
+            TaskEvent::TaskStarted { task_id, .. } => {
+                self.started.insert(task_id, at);
+            }
+            TaskEvent::TaskOutput {
+                task_name, output, ..
+            } if self.print_output => {
+                println!("{}", timestamped(self.elapsed(at), &task_name, &output));
+            }
+            TaskEvent::TaskError {
+                task_name, error, ..
+            } if self.print_output => {
+                eprintln!("{}", timestamped(self.elapsed(at), &task_name, &error));
+            }
+            TaskEvent::TaskCompleted {
+                task_name,
+                task_id,
+                duration_ms,
+            } => {
+                self.started.remove(&task_id);
+                self.finished(
+                    task_name,
+                    Duration::from_millis(duration_ms),
+                    Outcome::Succeeded,
+                );
+            }
+            TaskEvent::TaskFailed {
+                task_name, task_id, ..
+            } => {
+                let duration = self
+                    .started
+                    .remove(&task_id)
+                    .and_then(|started| at.duration_since(started).ok())
+                    .unwrap_or_default();
+                self.finished(task_name, duration, Outcome::Failed);
+            }
+            TaskEvent::TaskSkipped {
+                task_name, task_id, ..
+            } => {
+                self.started.remove(&task_id);
+                self.finished(task_name, Duration::ZERO, Outcome::Skipped);
+            }
+            _ => {}
+        }
+    }
+
+    fn elapsed(&self, at: SystemTime) -> Duration {
+        at.duration_since(self.start).unwrap_or_default()
+    }
+
+    fn finished(&mut self, task: String, duration: Duration, outcome: Outcome) {
+        self.timings.push(TaskTiming {
+            task,
+            duration,
+            outcome,
+        });
+    }
+}
+
+/// A line of task output prefixed with the time since the run started
+fn timestamped(elapsed: Duration, task: &str, line: &str) -> String {
+    format!("[{:>9}] {task} | {line}", format!("+{}", seconds(elapsed)))
+}
+
+/// Duration of each task in the order they finished, then the slowest
+fn summary(timings: &[TaskTiming], wall_time: Duration) -> String {
+    let mut lines = vec![format!("\nTask durations (total {}):", seconds(wall_time))];
+    let width = timings.iter().map(|t| t.task.len()).max().unwrap_or(0);
+    lines.extend(timings.iter().map(|timing| {
+        let note = match timing.outcome {
+            Outcome::Succeeded => "",
+            Outcome::Failed => "  failed",
+            Outcome::Skipped => "  skipped",
+        };
+        format!(
+            "  {:<width$}  {:>9}{note}",
+            timing.task,
+            seconds(timing.duration)
+        )
+    }));
+
+    let mut slowest: Vec<&TaskTiming> = timings
+        .iter()
+        .filter(|timing| timing.outcome != Outcome::Skipped)
+        .collect();
+    slowest.sort_by_key(|timing| std::cmp::Reverse(timing.duration));
+    if slowest.len() > 1 {
+        let shown: Vec<String> = slowest
+            .iter()
+            .take(SLOWEST_SHOWN)
+            .map(|timing| format!("{} ({})", timing.task, seconds(timing.duration)))
+            .collect();
+        lines.push(format!("Slowest: {}", shown.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(task: &str, millis: u64, outcome: Outcome) -> TaskTiming {
+        TaskTiming {
+            task: task.to_string(),
+            duration: Duration::from_millis(millis),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_timestamped_line() {
+        assert_eq!(
+            timestamped(Duration::from_millis(1234), "build", "Compiling"),
+            "[  +1.234s] build | Compiling"
+        );
+    }
+
+    #[test]
+    fn test_summary_lists_slowest_first() {
+        let timings = [
+            timing("lint", 200, Outcome::Succeeded),
+            timing("build", 4500, Outcome::Succeeded),
+            timing("docs", 0, Outcome::Skipped),
+            timing("test", 1200, Outcome::Failed),
+        ];
+        let summary = summary(&timings, Duration::from_millis(5000));
+
+        assert!(summary.starts_with("\nTask durations (total 5.000s):\n"));
+        assert!(summary.contains("  build     4.500s\n"));
+        assert!(summary.contains("  test      1.200s  failed\n"));
+        assert!(summary.contains("  docs      0.000s  skipped\n"));
+        assert!(summary.ends_with("Slowest: build (4.500s), test (1.200s), lint (0.200s)"));
+    }
+}
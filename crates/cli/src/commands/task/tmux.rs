@@ -0,0 +1,226 @@
+//! Task output in tmux panes
+//!
+//! With `--output tmux`, each member of a parallel group runs in its own
+//! pane of a new tmux window, as a separate `cuenv task` with simple output,
+//! so its output keeps tmux's native scrollback. Other tasks get a single
+//! pane. Each pane writes its exit status to a file this process waits for,
+//! and the run fails if any pane failed.
+
+use super::exit_policy::ExitPolicy;
+use cuenv_core::{Error, ExitStatus, Result};
+use cuenv_task::TaskExecutor;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Run in each pane as `sh -c PANE_SCRIPT sh <status file> <command...>`;
+/// the pane stays open after the command exits until Enter is pressed
+const PANE_SCRIPT: &str = r#"status_file=$1; shift
+"$@"; code=$?
+echo "$code" > "$status_file.tmp" && mv "$status_file.tmp" "$status_file"
+printf '\n[exited with status %s, press Enter to close]' "$code"
+read -r _"#;
+
+/// A pane running one task
+struct Pane {
+    id: String,
+    task_name: String,
+    status_file: PathBuf,
+}
+
+/// Whether cuenv runs inside a tmux session
+pub fn inside_tmux() -> bool {
+    std::env::var_os("TMUX").is_some()
+}
+
+/// Execute with one tmux pane per parallel task
+pub async fn execute_in_panes(
+    executor: &TaskExecutor,
+    task_name: &str,
+    args: &[String],
+    audit: bool,
+    exit_policy: ExitPolicy,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) -> Result<i32> {
+    let working_dir =
+        std::env::current_dir().map_err(|e| Error::file_system(".", "get current directory", e))?;
+    let cuenv = std::env::current_exe()
+        .map_err(|e| Error::file_system("cuenv", "locate current executable", e))?;
+    let status_dir = tempfile::tempdir()
+        .map_err(|e| Error::file_system(std::env::temp_dir(), "create status directory", e))?;
+
+    let mut panes: Vec<Pane> = Vec::new();
+    for (index, unit) in executor.parallel_units(task_name).into_iter().enumerate() {
+        let status_file = status_dir.path().join(format!("{index}.status"));
+        let mut command = vec![cuenv.to_string_lossy().into_owned()];
+        command.extend(task_args(executor, &unit, args, audit, exit_policy));
+
+        let first = panes.first().map(|pane| pane.id.as_str());
+        let id = open_pane(first, task_name, &working_dir, &status_file, &command)?;
+        panes.push(Pane {
+            id,
+            task_name: unit,
+            status_file,
+        });
+    }
+    eprintln!(
+        "Running {} task(s) in tmux window 'cuenv:{task_name}'",
+        panes.len()
+    );
+
+    tokio::select! {
+        status = wait_for_panes(&panes) => Ok(status),
+        _ = shutdown_rx.recv() => {
+            for pane in &panes {
+                let _ = tmux(&["kill-pane", "-t", &pane.id]);
+            }
+            eprintln!("Task execution cancelled");
+            Ok(ExitStatus::INTERRUPTED.code())
+        }
+    }
+}
+
+/// Arguments for the `cuenv` run in a pane, with the loaded environment,
+/// capabilities and exit policy of this run
+fn task_args(
+    executor: &TaskExecutor,
+    task_name: &str,
+    args: &[String],
+    audit: bool,
+    exit_policy: ExitPolicy,
+) -> Vec<String> {
+    let env_manager = executor.env_manager();
+    let mut command = vec![
+        "task".to_string(),
+        "--output".to_string(),
+        "simple".to_string(),
+    ];
+    if let Some(environment) = env_manager.environment() {
+        command.extend(["--env".to_string(), environment.to_string()]);
+    }
+    for capability in env_manager.capabilities() {
+        command.extend(["--capability".to_string(), capability.clone()]);
+    }
+    if audit {
+        command.push("--audit".to_string());
+    }
+    if exit_policy.zero_on_cache_hit_only {
+        command.push("--exit-zero-on-cache-hit-only".to_string());
+    }
+    command.push(task_name.to_string());
+    command.extend(args.iter().cloned());
+    command
+}
+
+/// Open a pane for `command`: a new window for the first task, a split of
+/// it for the others, or another window once the first has no room left
+fn open_pane(
+    first: Option<&str>,
+    task_name: &str,
+    working_dir: &Path,
+    status_file: &Path,
+    command: &[String],
+) -> Result<String> {
+    let working_dir = working_dir.to_string_lossy();
+    let status_file = status_file.to_string_lossy();
+    let window_name = format!("cuenv:{task_name}");
+    let script = ["sh", "-c", PANE_SCRIPT, "sh", status_file.as_ref()]
+        .into_iter()
+        .chain(command.iter().map(String::as_str));
+
+    let new_window = |detached: bool| {
+        let flags = ["new-window", "-P", "-F", "#{pane_id}", "-n", &window_name];
+        let detach = detached.then_some("-d");
+        let args: Vec<&str> = flags
+            .into_iter()
+            .chain(detach)
+            .chain(["-c", working_dir.as_ref()])
+            .chain(script.clone())
+            .collect();
+        tmux(&args)
+    };
+
+    let Some(first) = first else {
+        return new_window(false);
+    };
+    let split: Vec<&str> = ["split-window", "-d", "-P", "-F", "#{pane_id}", "-t", first]
+        .into_iter()
+        .chain(["-c", working_dir.as_ref()])
+        .chain(script.clone())
+        .collect();
+    match tmux(&split) {
+        Ok(id) => {
+            let _ = tmux(&["select-layout", "-t", first, "tiled"]);
+            Ok(id)
+        }
+        Err(_) => new_window(true),
+    }
+}
+
+/// Wait until every pane reported its status; the first failure wins
+async fn wait_for_panes(panes: &[Pane]) -> i32 {
+    let mut statuses: Vec<Option<i32>> = vec![None; panes.len()];
+    while statuses.iter().any(Option::is_none) {
+        for (pane, status) in panes.iter().zip(statuses.iter_mut()) {
+            if status.is_none() {
+                *status = pane_status(pane);
+                if let Some(code) = status {
+                    report(&pane.task_name, *code);
+                }
+            }
+        }
+        sleep(STATUS_POLL_INTERVAL).await;
+    }
+
+    statuses
+        .into_iter()
+        .flatten()
+        .find(|code| *code != 0)
+        .unwrap_or(0)
+}
+
+/// Exit status of a finished pane; a pane closed before reporting failed
+fn pane_status(pane: &Pane) -> Option<i32> {
+    match std::fs::read_to_string(&pane.status_file) {
+        Ok(content) => Some(content.trim().parse().unwrap_or(1)),
+        Err(_) if !pane_exists(&pane.id) => Some(1),
+        Err(_) => None,
+    }
+}
+
+fn pane_exists(id: &str) -> bool {
+    tmux(&["list-panes", "-a", "-F", "#{pane_id}"])
+        .is_ok_and(|panes| panes.lines().any(|pane| pane == id))
+}
+
+fn report(task_name: &str, code: i32) {
+    if code == 0 {
+        eprintln!("✓ {task_name} completed");
+    } else {
+        eprintln!("✗ {task_name} failed with exit code: {code}");
+    }
+}
+
+/// Run a tmux command and return its trimmed output
+fn tmux(args: &[&str]) -> Result<String> {
+    let output = Command::new("tmux")
+        .args(args)
+        .output()
+        .map_err(|e| Error::command_execution("tmux", args_vec(args), e.to_string(), None))?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            "tmux",
+            args_vec(args),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            output.status.code(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn args_vec(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
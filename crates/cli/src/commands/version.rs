@@ -0,0 +1,28 @@
+//! `cuenv version`: the version of cuenv and, verbosely, of the CUE
+//! evaluator its bridge embeds
+//!
+//! Runs without loading the project, so it works where the project's
+//! `config.cueVersion` rejects this build.
+
+use cuenv_config::{bridge_info, BRIDGE_ABI_VERSION};
+use cuenv_core::Result;
+
+pub fn execute(verbose: bool) -> Result<()> {
+    println!("cuenv {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return Ok(());
+    }
+    match bridge_info() {
+        Ok(info) => {
+            println!("  CUE:         {}", info.cue_version);
+            println!("  Go:          {}", info.go_version);
+            println!(
+                "  Bridge ABI:  {} (this build expects {BRIDGE_ABI_VERSION})",
+                info.abi_version
+            );
+            println!("  Platform:    {}/{}", info.os, info.arch);
+        }
+        Err(e) => println!("  CUE:         not available ({e})"),
+    }
+    Ok(())
+}
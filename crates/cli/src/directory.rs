@@ -1,10 +1,14 @@
 use cuenv_core::{Error, Result};
-use cuenv_utils::XdgPaths;
+use cuenv_utils::{AllowRule, AllowRules, RuleAction, RuleDecision, XdgPaths};
 use sha2::{Digest, Sha256};
 use std::env;
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+/// Reports an unreadable rules file once per process
+static RULES_WARNING: Once = Once::new();
 
 pub struct DirectoryManager;
 
@@ -30,11 +34,26 @@ impl DirectoryManager {
             .canonicalize()
             .map_err(|e| Error::file_system(dir.to_path_buf(), "canonicalize path", e))?;
 
+        // A deny rule wins over the allow list, so an entry would do nothing
+        if let Some(rule) = self.load_rules().matching(&canonical_dir, RuleAction::Deny) {
+            return Err(Error::configuration(format!(
+                "{} is denied by the pattern rule '{}'; remove it with `cuenv env allow --rm '{}'` first",
+                canonical_dir.display(),
+                rule.pattern,
+                rule.pattern
+            )));
+        }
+
         // Check if already allowed
         if self.is_directory_allowed(&canonical_dir)? {
             return Ok(()); // Already allowed
         }
 
+        self.append_entry(&allowed_file, &canonical_dir)
+    }
+
+    /// Add `canonical_dir` to the allow list, pinned to the hash of its env.cue
+    fn append_entry(&self, allowed_file: &Path, canonical_dir: &Path) -> Result<()> {
         // Calculate hash of env.cue if it exists
         let env_cue = canonical_dir.join("env.cue");
         let hash = if env_cue.exists() {
@@ -47,8 +66,8 @@ impl DirectoryManager {
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(&allowed_file)
-            .map_err(|e| Error::file_system(allowed_file.clone(), "open allowed file", e))?;
+            .open(allowed_file)
+            .map_err(|e| Error::file_system(allowed_file, "open allowed file", e))?;
 
         if hash.is_empty() {
             writeln!(file, "{}", canonical_dir.display())
@@ -82,7 +101,7 @@ impl DirectoryManager {
             let line =
                 line.map_err(|e| Error::file_system(allowed_file.clone(), "read allowed file", e))?;
             let line = line.trim();
-            if !line.is_empty() && line != canonical_dir.to_string_lossy() {
+            if !line.is_empty() && Self::entry_path(line) != canonical_dir.to_string_lossy() {
                 allowed_dirs.push(line.to_string());
             }
         }
@@ -97,56 +116,132 @@ impl DirectoryManager {
     pub fn is_directory_allowed(&self, dir: &Path) -> Result<bool> {
         let allowed_file = self.get_allowed_file()?;
 
-        if !allowed_file.exists() {
-            return Ok(false);
-        }
-
         // Get canonical path
         let canonical_dir = dir
             .canonicalize()
             .map_err(|e| Error::file_system(dir.to_path_buf(), "canonicalize path", e))?;
 
-        // Read allowed directories
-        let file = fs::File::open(&allowed_file)
-            .map_err(|e| Error::file_system(allowed_file.clone(), "open allowed file", e))?;
-        let reader = BufReader::new(file);
+        // Deny rules win over everything. A directory an allow rule matches is
+        // pinned to its env.cue the first time it is seen, so a later change
+        // to env.cue needs allowing again, as it does for exact entries
+        let decision = self.load_rules().evaluate(&canonical_dir);
+        if decision == RuleDecision::Denied {
+            return Ok(false);
+        }
 
-        for line in reader.lines() {
-            let line =
-                line.map_err(|e| Error::file_system(allowed_file.clone(), "read allowed file", e))?;
-            let line = line.trim();
+        let entries = if allowed_file.exists() {
+            fs::read_to_string(&allowed_file)
+                .map_err(|e| Error::file_system(allowed_file.clone(), "read allowed file", e))?
+        } else {
+            String::new()
+        };
+        let env_cue = canonical_dir.join("env.cue");
+        let env_cue_hash = if env_cue.exists() {
+            Some(self.calculate_file_hash(&env_cue)?)
+        } else {
+            None
+        };
 
-            // Parse line which can be either "path" or "path:hash"
-            let (allowed_path, allowed_hash) = if let Some(colon_pos) = line.rfind(':') {
-                (
-                    line[..colon_pos].to_string(),
-                    Some(line[colon_pos + 1..].to_string()),
-                )
-            } else {
-                (line.to_string(), None)
-            };
-
-            if allowed_path == canonical_dir.to_string_lossy() {
-                // Path matches, now check hash if present
-                if let Some(expected_hash) = allowed_hash {
-                    let env_cue = canonical_dir.join("env.cue");
-                    if env_cue.exists() {
-                        let actual_hash = self.calculate_file_hash(&env_cue)?;
-                        if actual_hash == expected_hash {
-                            return Ok(true);
-                        }
-                        // Hash doesn't match, continue checking other entries
-                    } else {
-                        // env.cue doesn't exist but hash was expected, continue checking
-                    }
-                } else {
-                    // No hash requirement, directory is allowed
-                    return Ok(true);
-                }
+        match allow_list_decision(&entries, &canonical_dir, env_cue_hash.as_deref()) {
+            Some(allowed) => Ok(allowed),
+            None if decision == RuleDecision::Allowed => {
+                self.append_entry(&allowed_file, &canonical_dir)?;
+                Ok(true)
             }
+            None => Ok(false),
+        }
+    }
+
+    /// The pattern rules, or none when the rules file can't be read; the
+    /// exact allow list still applies then
+    fn load_rules(&self) -> AllowRules {
+        let path = AllowRules::default_path();
+        AllowRules::load(&path).unwrap_or_else(|e| {
+            RULES_WARNING.call_once(|| {
+                eprintln!(
+                    "⚠ Ignoring pattern rules in {}: {e}; only exactly allowed directories are trusted",
+                    path.display()
+                );
+            });
+            AllowRules::default()
+        })
+    }
+
+    /// Add a glob-pattern rule such as `~/work/**`
+    pub fn add_pattern(&self, pattern: &str, action: RuleAction) -> Result<()> {
+        let path = AllowRules::default_path();
+        let mut rules = AllowRules::load(&path)?;
+        rules.add(pattern, action)?;
+        rules.save(&path)
+    }
+
+    /// List exactly allowed directories and pattern rules
+    pub fn list(&self) -> Result<(Vec<String>, Vec<AllowRule>)> {
+        let allowed_file = self.get_allowed_file()?;
+        let directories = if allowed_file.exists() {
+            fs::read_to_string(&allowed_file)
+                .map_err(|e| Error::file_system(allowed_file.clone(), "read allowed file", e))?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| Self::entry_path(line).to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let rules = AllowRules::load(&AllowRules::default_path())?.rules;
+        Ok((directories, rules))
+    }
+
+    /// Remove a pattern rule or an exactly allowed directory
+    ///
+    /// Returns whether anything was removed.
+    pub fn remove_entry(&self, entry: &str) -> Result<bool> {
+        let rules_path = AllowRules::default_path();
+        let mut rules = AllowRules::load(&rules_path)?;
+        if rules.remove(entry) {
+            rules.save(&rules_path)?;
+            return Ok(true);
         }
 
-        Ok(false)
+        let allowed_file = self.get_allowed_file()?;
+        if !allowed_file.exists() {
+            return Ok(false);
+        }
+
+        // Directories may no longer exist, so match the stored path literally
+        let target = Path::new(entry)
+            .canonicalize()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_else(|_| entry.trim_end_matches('/').to_string());
+
+        let content = fs::read_to_string(&allowed_file)
+            .map_err(|e| Error::file_system(allowed_file.clone(), "read allowed file", e))?;
+        let (removed, remaining): (Vec<&str>, Vec<&str>) = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .partition(|line| Self::entry_path(line) == target);
+
+        let removed = !removed.is_empty();
+        if removed {
+            fs::write(&allowed_file, remaining.join("\n") + "\n")
+                .map_err(|e| Error::file_system(allowed_file, "write allowed file", e))?;
+        }
+        Ok(removed)
+    }
+
+    /// Strip the optional `:hash` suffix from an allow list line
+    fn entry_path(line: &str) -> &str {
+        match line.rsplit_once(':') {
+            Some((path, hash))
+                if hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) =>
+            {
+                path
+            }
+            _ => line,
+        }
     }
 
     fn get_allowed_file(&self) -> Result<PathBuf> {
@@ -186,6 +281,36 @@ impl DirectoryManager {
     }
 }
 
+/// What the allow list `entries` says about `canonical_dir`, `None` when it
+/// has no entry for it
+///
+/// Lines are `path` or `path:hash`; an entry with a hash only allows the
+/// directory while its env.cue still has that hash.
+fn allow_list_decision(
+    entries: &str,
+    canonical_dir: &Path,
+    env_cue_hash: Option<&str>,
+) -> Option<bool> {
+    let dir = canonical_dir.to_string_lossy();
+    let mut listed = false;
+    for line in entries.lines().map(str::trim) {
+        // Parse line which can be either "path" or "path:hash"
+        let (path, hash) = match line.rfind(':') {
+            Some(colon_pos) => (&line[..colon_pos], Some(&line[colon_pos + 1..])),
+            None => (line, None),
+        };
+        if path != dir {
+            continue;
+        }
+        listed = true;
+        match hash {
+            Some(expected) if env_cue_hash != Some(expected) => {}
+            _ => return Some(true),
+        }
+    }
+    listed.then_some(false)
+}
+
 impl Default for DirectoryManager {
     fn default() -> Self {
         Self::new()
@@ -217,4 +342,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_allow_list_decision() {
+        let dir = Path::new("/work/app");
+        let entries = "/work/other\n/work/app:abc\n";
+
+        assert_eq!(allow_list_decision(entries, dir, Some("abc")), Some(true));
+        // A changed or removed env.cue is no longer trusted
+        assert_eq!(allow_list_decision(entries, dir, Some("def")), Some(false));
+        assert_eq!(allow_list_decision(entries, dir, None), Some(false));
+        assert_eq!(allow_list_decision(entries, Path::new("/work"), None), None);
+        assert_eq!(
+            allow_list_decision("/work/app\n", dir, Some("def")),
+            Some(true)
+        );
+    }
 }
@@ -18,7 +18,24 @@ impl Commands {
                 trace_output,
                 graph,
                 charset,
+                exit_zero_on_cache_hit_only,
+                yes,
+                diagnostics_json,
+                strict_conflicts,
+                timestamps,
+                summary,
+                report,
+                stubs,
             } => {
+                crate::commands::task::assume_yes(yes);
+                crate::commands::task::write_diagnostics_json_to(diagnostics_json);
+                crate::monorepo::strict_conflicts(strict_conflicts);
+                crate::commands::task::configure_timing(timestamps, summary);
+                crate::commands::task::configure_reports(&report)?;
+                cuenv_task::stub_tasks(&stubs)?;
+                let exit_policy = crate::commands::task::ExitPolicy {
+                    zero_on_cache_hit_only: exit_zero_on_cache_hit_only,
+                };
                 crate::commands::task::execute_task_command(
                     Arc::clone(&config),
                     task_or_group,
@@ -31,20 +48,120 @@ impl Commands {
                     trace_output,
                     graph,
                     charset,
+                    exit_policy,
                 )
                 .await
             }
-            Commands::Env { command } => command.execute().await,
+            Commands::RunConfig {
+                name,
+                output,
+                audit,
+                yes,
+            } => {
+                crate::commands::task::assume_yes(yes);
+                crate::commands::run_config::execute(config, name, output, audit).await
+            }
+            Commands::Env { command } => command.execute(&config).await,
             Commands::Shell { command } => command.execute().await,
             Commands::Cache { command } => command.execute().await,
+            Commands::Hooks { command } => command.execute().await,
+            Commands::State { command } => command.execute().await,
+            Commands::Config { command } => command.execute(&config).await,
+            Commands::Ci { command } => command.execute(&config).await,
+            Commands::Scheduler { command } => command.execute(&config).await,
+            Commands::Security { command } => command.execute(&config).await,
             Commands::Internal { command } => command.execute().await,
+            Commands::Bench {
+                packages,
+                tasks,
+                variables,
+                files,
+                iterations,
+                save_baseline,
+                baseline,
+                threshold,
+                baseline_dir,
+            } => {
+                crate::commands::bench::execute(crate::commands::bench::BenchOptions {
+                    size: crate::commands::bench::synthetic::RepoSize {
+                        packages,
+                        tasks,
+                        variables,
+                        files,
+                    },
+                    iterations,
+                    save_baseline,
+                    baseline,
+                    threshold,
+                    baseline_dir,
+                })
+                .await
+            }
 
+            Commands::DockerArgs { allow, format } => {
+                crate::commands::container::docker_args(&config, &allow, &format)
+            }
+            Commands::ComposeEnv {
+                allow,
+                secrets,
+                output,
+            } => crate::commands::container::compose_env(
+                &config,
+                &allow,
+                secrets.parse()?,
+                output.as_deref(),
+            ),
             Commands::Init { force } => crate::commands::init::execute(config, force).await,
+            Commands::New {
+                template,
+                name,
+                dir,
+                from,
+                vars,
+                no_hooks,
+                yes,
+            } => {
+                let options = crate::commands::new::NewOptions {
+                    template,
+                    name,
+                    dir,
+                    from,
+                    vars,
+                    no_hooks,
+                    yes,
+                };
+                crate::commands::new::execute(config, options).await
+            }
+            Commands::Setup {
+                shell,
+                rc_file,
+                yes,
+                no_completions,
+                remove,
+            } => crate::commands::setup::execute(shell, rc_file, yes, no_completions, remove).await,
             Commands::Discover {
                 max_depth,
                 load,
                 dump,
             } => crate::commands::discover::execute(config, max_depth, load, dump).await,
+            Commands::Doctor { format } => crate::commands::doctor::execute(format).await,
+            Commands::Lock { check } => crate::commands::lock::execute(config, check).await,
+            Commands::Login { backend, check } => {
+                crate::commands::login::execute(backend, check).await
+            }
+            Commands::SelfUpdate {
+                channel,
+                check,
+                force,
+            } => {
+                let options = crate::commands::self_update::SelfUpdateOptions {
+                    channel,
+                    check,
+                    force,
+                };
+                crate::commands::self_update::execute(options).await
+            }
+            Commands::Version { verbose } => crate::commands::version::execute(verbose),
             Commands::Completion { shell } => crate::completion::generate_completion(&shell),
             Commands::Exec {
                 environment,
@@ -52,6 +169,7 @@ impl Commands {
                 command,
                 args,
                 audit,
+                restrict,
             } => {
                 crate::commands::exec::execute(
                     config,
@@ -60,18 +178,46 @@ impl Commands {
                     command,
                     args,
                     audit,
+                    restrict,
                 )
                 .await
             }
             Commands::CompleteTasks => complete_tasks(config).await,
             Commands::CompleteEnvironments => complete_environments(config).await,
             Commands::CompleteHosts => complete_hosts().await,
+            Commands::Agent { socket } => crate::commands::agent::execute(socket).await,
             Commands::Mcp {
                 transport,
                 port,
                 socket,
                 allow_exec,
             } => crate::commands::mcp::execute(config, transport, port, socket, allow_exec).await,
+            Commands::Serve {
+                web,
+                host,
+                port,
+                token,
+                environment,
+                capabilities,
+                audit,
+                exit,
+                task,
+                args,
+            } => {
+                let options = crate::commands::serve::ServeOptions {
+                    web,
+                    host,
+                    port,
+                    token,
+                    environment,
+                    capabilities,
+                    audit,
+                    exit,
+                    task,
+                    args,
+                };
+                crate::commands::serve::execute(config, options).await
+            }
             Commands::Supervisor { hooks } => {
                 // Parse hooks from JSON
                 let hooks: Vec<cuenv_config::Hook> = serde_json::from_str(&hooks).map_err(|e| {
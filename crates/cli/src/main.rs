@@ -1,7 +1,8 @@
 use clap::Parser;
 use cuenv_cache::CacheMode;
 use cuenv_config::{ConfigLoader, RuntimeOptions};
-use std::env;
+use cuenv_utils::sync::SyncEnv;
+use cuenv_utils::xdg::XdgPaths;
 
 mod commands;
 mod completion;
@@ -37,8 +38,8 @@ struct Cli {
     #[arg(long, global = true)]
     audit: bool,
 
-    /// Output format for task execution (tui, spinner, simple, tree)
-    #[arg(long, value_parser = ["tui", "spinner", "simple", "tree"])]
+    /// Output format for task execution (tui, spinner, simple, tree, tmux, template, json)
+    #[arg(long, value_parser = ["tui", "spinner", "simple", "tree", "tmux", "template", "json"])]
     output_format: Option<String>,
 
     /// Enable Chrome trace output
@@ -74,11 +75,11 @@ async fn main() -> eyre::Result<()> {
             "write" => CacheMode::Write,
             _ => CacheMode::ReadWrite,
         };
-        env::set_var("CUENV_CACHE_MODE", mode.to_string());
+        SyncEnv::set_var("CUENV_CACHE_MODE", mode.to_string())?;
     }
 
     if let Some(enabled) = cli.cache_enabled {
-        env::set_var("CUENV_CACHE_ENABLED", enabled.to_string());
+        SyncEnv::set_var("CUENV_CACHE_ENABLED", enabled.to_string())?;
     }
 
     // Determine the command to execute
@@ -92,12 +93,24 @@ async fn main() -> eyre::Result<()> {
         }
     };
 
+    // Reports the build, which the project may reject, so it loads nothing
+    if let Commands::Version { verbose } = command {
+        return commands::version::execute(verbose).map_err(Into::into);
+    }
+
+    // Forward lifecycle events to the system log when asked to
+    #[cfg(unix)]
+    if let Some(sink) = cuenv_core::events::SyslogSubscriber::from_env()? {
+        cuenv_core::register_global_subscriber(std::sync::Arc::new(sink)).await;
+    }
+
     // Load configuration once at startup
     let config = ConfigLoader::new()
         .runtime(runtime)
         .load()
         .await?
         .into_arc();
+    XdgPaths::set_project_overrides(config.dir_overrides());
 
     // Execute the command with configuration
     command.execute(config).await.map_err(Into::into)
@@ -1,11 +1,22 @@
 use crate::commands::discover::PackageDiscovery;
+use crate::commands::task::{confirm_tasks, ExitPolicy};
 use cuenv_core::{Error, Result};
 use cuenv_env::EnvManager;
 use cuenv_task::{
     parse_reference, CrossPackageReference, DiscoveredPackage, MonorepoTaskRegistry,
-    ParseResult as TaskParseResult, TaskExecutor,
+    ParseResult as TaskParseResult, TaskExecutor, TaskResolution,
 };
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `--strict-conflicts`, for the rest of the process
+static STRICT_CONFLICTS: AtomicBool = AtomicBool::new(false);
+
+/// Fail cross-package runs whose packages define a variable differently,
+/// instead of warning about it
+pub fn strict_conflicts(strict: bool) {
+    STRICT_CONFLICTS.store(strict, Ordering::Relaxed);
+}
 
 /// Execute a task in a monorepo context
 pub async fn execute_monorepo_task(
@@ -13,6 +24,7 @@ pub async fn execute_monorepo_task(
     task_ref: &str,
     task_args: &[String],
     audit: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<i32> {
     // Check if this is a cross-package reference
     let parsed_ref = parse_reference(task_ref)?;
@@ -20,11 +32,11 @@ pub async fn execute_monorepo_task(
     match parsed_ref {
         CrossPackageReference::LocalTask { task } => {
             // Local task - use regular execution
-            execute_local_task(current_dir, &task, task_args, audit).await
+            execute_local_task(current_dir, &task, task_args, audit, exit_policy).await
         }
         _ => {
             // Cross-package task - need to discover and execute
-            execute_cross_package_task(current_dir, task_ref, task_args, audit).await
+            execute_cross_package_task(current_dir, task_ref, task_args, audit, exit_policy).await
         }
     }
 }
@@ -35,6 +47,7 @@ async fn execute_local_task(
     task_name: &str,
     task_args: &[String],
     audit: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<i32> {
     // First, check if we're in a monorepo context
     if let Ok(module_root) = PackageDiscovery::find_module_root(current_dir) {
@@ -79,6 +92,7 @@ async fn execute_local_task(
                                 &full_task_name,
                                 task_args,
                                 audit,
+                                exit_policy,
                             )
                             .await;
                         } else {
@@ -101,20 +115,27 @@ async fn execute_local_task(
     env_manager.load_env(current_dir).await?;
 
     let executor = TaskExecutor::new(env_manager, current_dir.to_path_buf()).await?;
+    if !confirm_tasks(&executor, &[task_name.to_string()])? {
+        return Ok(1);
+    }
 
-    if audit {
-        executor.execute_task_with_audit(task_name, task_args).await
+    let status = if audit {
+        executor
+            .execute_task_with_audit(task_name, task_args)
+            .await?
     } else {
-        executor.execute_task(task_name, task_args).await
-    }
+        executor.execute_task(task_name, task_args).await?
+    };
+    Ok(exit_policy.exit_code(status, &executor))
 }
 
 /// Execute a cross-package task
 async fn execute_cross_package_task(
     current_dir: &Path,
     task_ref: &str,
-    _task_args: &[String],
-    _audit: bool,
+    task_args: &[String],
+    audit: bool,
+    exit_policy: ExitPolicy,
 ) -> Result<i32> {
     // Find the module root
     let mut discovery = PackageDiscovery::new(32);
@@ -136,6 +157,7 @@ async fn execute_cross_package_task(
             path: cli_pkg.path,
             parse_result: cli_pkg.parse_result.map(|config_result| TaskParseResult {
                 tasks: config_result.tasks,
+                variables: config_result.variables,
             }),
         })
         .collect();
@@ -145,14 +167,48 @@ async fn execute_cross_package_task(
 
     // Validate all dependencies
     registry.validate_all_dependencies()?;
+    if let TaskResolution::Found(task_name) = registry.resolve_task(task_ref) {
+        check_env_conflicts(&registry, &task_name)?;
+    }
 
     // Create executor with the monorepo registry
-    let mut executor = TaskExecutor::new_with_registry(registry).await?;
+    let executor = TaskExecutor::new_with_registry(registry).await?;
+    if !confirm_tasks(&executor, &[task_ref.to_string()])? {
+        return Ok(1);
+    }
 
-    // Execute the task
-    executor.execute(task_ref).await?;
+    // Execute the task, exiting with the status of the first failure
+    let status = if audit {
+        executor
+            .execute_task_with_audit(task_ref, task_args)
+            .await?
+    } else {
+        executor.execute_task(task_ref, task_args).await?
+    };
+    Ok(exit_policy.exit_code(status, &executor))
+}
 
-    Ok(0)
+/// Report variables the packages taking part in running `task_name`
+/// define with different values
+fn check_env_conflicts(registry: &MonorepoTaskRegistry, task_name: &str) -> Result<()> {
+    let env = registry.workspace_env(task_name)?;
+    let conflicts = env.conflicts();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    let report = conflicts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if STRICT_CONFLICTS.load(Ordering::Relaxed) {
+        return Err(Error::configuration(format!(
+            "Packages define environment variables differently:\n{report}"
+        )));
+    }
+    eprintln!("⚠️  Warning: packages define environment variables differently:\n{report}");
+    Ok(())
 }
 
 /// Check if we're in a monorepo context
@@ -1,4 +1,5 @@
 use cuenv_env::state::StateManager;
+use cuenv_utils::sync::SyncEnv;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -27,7 +28,7 @@ impl TestIsolation {
         }
 
         // Set the test prefix
-        std::env::set_var("CUENV_PREFIX", &prefix);
+        SyncEnv::set_var("CUENV_PREFIX", &prefix).expect("Failed to set CUENV_PREFIX");
 
         Self {
             prefix,
@@ -55,15 +56,15 @@ impl TestIsolation {
         ];
 
         for var in &prefixed_vars {
-            std::env::remove_var(var);
+            let _ = SyncEnv::remove_var(var);
         }
 
         // Restore original values
         for (var, original_value) in self.original_vars.clone() {
-            match original_value {
-                Some(value) => std::env::set_var(var, value),
-                None => std::env::remove_var(var),
-            }
+            let _ = match original_value {
+                Some(value) => SyncEnv::set_var(var, value),
+                None => SyncEnv::remove_var(var),
+            };
         }
     }
 }
@@ -81,15 +82,15 @@ impl Drop for TestIsolation {
         ];
 
         for var in &prefixed_vars {
-            std::env::remove_var(var);
+            let _ = SyncEnv::remove_var(var);
         }
 
         // Restore original CUENV_PREFIX
         if let Some(original) = self.original_vars.get("CUENV_PREFIX") {
-            match original {
-                Some(value) => std::env::set_var("CUENV_PREFIX", value),
-                None => std::env::remove_var("CUENV_PREFIX"),
-            }
+            let _ = match original {
+                Some(value) => SyncEnv::set_var("CUENV_PREFIX", value),
+                None => SyncEnv::remove_var("CUENV_PREFIX"),
+            };
         }
     }
 }
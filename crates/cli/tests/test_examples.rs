@@ -285,14 +285,13 @@ PORT: "8080"
 "#;
     std::fs::write(temp_dir.path().join("env.cue"), env_content).unwrap();
 
-    // Set a variable that should NOT be passed to the child
-    std::env::set_var("TEST_PARENT_VAR", "should_not_exist");
-
     // Run a command that checks for our variables
     #[cfg(unix)]
     let output = Command::new(get_cuenv_binary())
         .current_dir(temp_dir.path())
         .env("CUENV_PACKAGE", "examples")
+        // Set a variable that should NOT be passed to the child
+        .env("TEST_PARENT_VAR", "should_not_exist")
         .arg("exec")
         .arg("sh")
         .arg("-c")
@@ -304,6 +303,8 @@ PORT: "8080"
     let output = Command::new(get_cuenv_binary())
         .current_dir(temp_dir.path())
         .env("CUENV_PACKAGE", "examples")
+        // Set a variable that should NOT be passed to the child
+        .env("TEST_PARENT_VAR", "should_not_exist")
         .arg("exec")
         .arg("cmd")
         .arg("/C")
@@ -316,9 +317,6 @@ PORT: "8080"
         "Command failed: {}",
         String::from_utf8_lossy(&output.stderr)
     );
-
-    // Clean up
-    std::env::remove_var("TEST_PARENT_VAR");
 }
 
 #[test]
@@ -0,0 +1,95 @@
+//! Time turning the bridge's JSON into parse results
+//!
+//! ```sh
+//! cargo bench -p cuenv-config --bench parse
+//! ```
+//!
+//! The payload is synthetic, shaped like a large monorepo package: thousands
+//! of variables, a few environments overriding some of them, and a thousand
+//! tasks. Save a criterion baseline with `-- --save-baseline before` to
+//! compare changes to the pipeline.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use cuenv_config::{CueParser, ParseOptions};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+const VARIABLES: usize = 5_000;
+const TASKS: usize = 1_000;
+const ENVIRONMENTS: [&str; 3] = ["dev", "staging", "production"];
+
+fn payload() -> String {
+    let mut env: Map<String, Value> = (0..VARIABLES)
+        .map(|i| {
+            (
+                format!("VAR_{i}"),
+                json!(format!("value-{i}-{}", "x".repeat(32))),
+            )
+        })
+        .collect();
+    let environments: Map<String, Value> = ENVIRONMENTS
+        .iter()
+        .map(|name| {
+            let overrides: Map<String, Value> = (0..VARIABLES / 10)
+                .map(|i| (format!("VAR_{i}"), json!(format!("{name}-{i}"))))
+                .collect();
+            ((*name).to_string(), Value::Object(overrides))
+        })
+        .collect();
+    env.insert("environment".to_string(), Value::Object(environments));
+
+    let tasks: Map<String, Value> = (0..TASKS)
+        .map(|i| {
+            let task = json!({
+                "description": format!("Task number {i}"),
+                "command": format!("make target-{i}"),
+                "dependencies": if i == 0 { vec![] } else { vec![format!("task_{}", i - 1)] },
+                "inputs": [format!("src/{i}/**/*.rs")],
+                "outputs": [format!("target/{i}")],
+            });
+            (format!("task_{i}"), task)
+        })
+        .collect();
+
+    json!({
+        "env": env,
+        "tasks": tasks,
+        "hooks": {"onEnter": [{"command": "echo", "args": ["hello"]}]},
+    })
+    .to_string()
+}
+
+fn parse(c: &mut Criterion) {
+    let json = payload();
+    let dir = Path::new(".");
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("base", |b| {
+        b.iter(|| {
+            CueParser::parse_json(dir, &json)
+                .and_then(|package| package.into_parse_result(&ParseOptions::default()))
+                .unwrap()
+        });
+    });
+    group.bench_with_input(
+        BenchmarkId::new("every_environment", ENVIRONMENTS.len()),
+        &json,
+        |b, json| {
+            b.iter(|| {
+                let package = CueParser::parse_json(dir, json).unwrap();
+                for name in ENVIRONMENTS {
+                    let options = ParseOptions {
+                        environment: Some(name.to_string()),
+                        capabilities: Vec::new(),
+                    };
+                    package.parse_result(&options).unwrap();
+                }
+            });
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -5,12 +5,14 @@
 //! to perform their own file I/O or parsing.
 
 use crate::{
-    CommandConfig, ConfigSettings, Hook, ParseResult, SecurityConfig, TaskConfig, VariableMetadata,
+    CommandConfig, ConfigSettings, Hook, ParseResult, Provenance, RunConfig, SecurityConfig,
+    TaskConfig, VariableMetadata,
 };
 use cuenv_core::{Error, Result};
+use cuenv_utils::xdg::DirOverrides;
 use indexmap::IndexMap;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Runtime options for the application
@@ -130,15 +132,7 @@ impl Config {
             env_file,
             parse_result,
             runtime,
-            security: SecurityConfig {
-                restrict_disk: None,
-                restrict_network: None,
-                read_only_paths: None,
-                read_write_paths: None,
-                deny_paths: None,
-                allowed_hosts: None,
-                infer_from_inputs_outputs: None,
-            },
+            security: SecurityConfig::default(),
             monorepo: None,
             original_env: std::env::vars().collect(),
         }
@@ -183,6 +177,11 @@ impl Config {
         &self.parse_result.commands
     }
 
+    /// Get named run configurations
+    pub fn get_run_configs(&self) -> &HashMap<String, RunConfig> {
+        &self.parse_result.run_configs
+    }
+
     /// Get hooks for a specific type
     pub fn get_hooks(&self, hook_type: &str) -> Vec<&Hook> {
         self.parse_result
@@ -192,6 +191,11 @@ impl Config {
             .unwrap_or_default()
     }
 
+    /// Get where profile-contributed variables, tasks and capabilities came from
+    pub fn get_provenance(&self) -> &Provenance {
+        &self.parse_result.provenance
+    }
+
     /// Get variable metadata
     pub fn get_metadata(&self, var_name: &str) -> Option<&VariableMetadata> {
         self.parse_result.metadata.get(var_name)
@@ -204,10 +208,9 @@ impl Config {
         false
     }
 
-    /// Get the list of available environments
+    /// Get the list of available environments, sorted
     pub fn get_environments(&self) -> Vec<String> {
-        // TODO: Extract from ParseResult when environment support is added
-        vec![]
+        self.parse_result.environments.clone()
     }
 
     /// Check if running in monorepo mode
@@ -220,6 +223,21 @@ impl Config {
         self.monorepo.as_ref().map(|m| &m.root_dir)
     }
 
+    /// The cache and state directories the project's config relocates to,
+    /// relative to the directory of its env.cue
+    pub fn dir_overrides(&self) -> DirOverrides {
+        let project_dir = self
+            .env_file
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(&self.working_dir);
+        self.parse_result
+            .config
+            .as_ref()
+            .map(|settings| settings.dir_overrides(project_dir))
+            .unwrap_or_default()
+    }
+
     /// Create an Arc wrapper for thread-safe sharing
     pub fn into_arc(self) -> Arc<Self> {
         Arc::new(self)
@@ -245,15 +263,7 @@ impl ConfigBuilder {
             env_file: None,
             parse_result: None,
             runtime: RuntimeOptions::default(),
-            security: SecurityConfig {
-                restrict_disk: None,
-                restrict_network: None,
-                read_only_paths: None,
-                read_write_paths: None,
-                deny_paths: None,
-                allowed_hosts: None,
-                infer_from_inputs_outputs: None,
-            },
+            security: SecurityConfig::default(),
             monorepo: None,
         }
     }
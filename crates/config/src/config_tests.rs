@@ -20,12 +20,14 @@ mod tests {
             "TEST_VAR".to_string(),
             VariableMetadata {
                 capability: Some("basic".to_string()),
+                ..Default::default()
             },
         );
         metadata.insert(
             "SECRET_VAR".to_string(),
             VariableMetadata {
                 capability: Some("secrets".to_string()),
+                ..Default::default()
             },
         );
 
@@ -37,6 +39,10 @@ mod tests {
             task_nodes: IndexMap::new(),
             hooks: HashMap::new(),
             config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
         }
     }
 
@@ -150,6 +156,9 @@ mod tests {
             deny_paths: Some(vec!["/secret".to_string()]),
             allowed_hosts: Some(vec!["github.com".to_string()]),
             infer_from_inputs_outputs: Some(false),
+            seccomp_profile: None,
+            seccomp_audit: None,
+            enforcement: None,
         };
 
         assert_eq!(config.security.restrict_disk, Some(true));
@@ -200,12 +209,16 @@ mod tests {
         use crate::Hook;
 
         let hook = Hook {
+            name: None,
+            depends_on: None,
             command: "echo".to_string(),
             args: Some(vec!["test".to_string()]),
             dir: None,
             inputs: None,
             source: None,
             preload: None,
+            fetch: None,
+            compose: None,
         };
 
         parse_result.hooks.insert("onEnter".to_string(), vec![hook]);
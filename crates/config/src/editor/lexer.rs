@@ -0,0 +1,285 @@
+//! Minimal structural scanner for CUE source
+//!
+//! This is not a full CUE parser. It understands just enough of the
+//! syntax (comments, the different string forms, brackets and field
+//! labels) to locate struct boundaries so edits can be spliced into a file
+//! without disturbing the surrounding text.
+
+/// A structural element found while scanning CUE source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// A field label such as `tasks:` or `"my-task":`
+    Label {
+        name: String,
+        offset: usize,
+        depth: usize,
+    },
+    /// An opening `{`; `depth` is the nesting level outside the brace
+    Open { offset: usize, depth: usize },
+    /// A closing `}`; `depth` is the nesting level outside the brace
+    Close { offset: usize, depth: usize },
+    /// An opening `[`; `depth` is the nesting level outside the bracket
+    OpenList { offset: usize, depth: usize },
+    /// A closing `]`; `depth` is the nesting level outside the bracket
+    CloseList { offset: usize, depth: usize },
+}
+
+/// Scan CUE source into labels, struct braces and list brackets
+///
+/// Depth counts every kind of bracket so labels inside lists or call
+/// arguments are never mistaken for fields of the enclosing struct.
+pub fn scan(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos = line_end(bytes, pos);
+            }
+            b'"' | b'\'' | b'#' if starts_string(bytes, pos) => {
+                let start = pos;
+                pos = string_end(bytes, pos);
+                if let Some(after) = label_colon(bytes, pos) {
+                    tokens.push(Token::Label {
+                        name: unquote(&source[start..pos]),
+                        offset: start,
+                        depth,
+                    });
+                    pos = after;
+                }
+            }
+            b'{' => {
+                tokens.push(Token::Open { offset: pos, depth });
+                depth += 1;
+                pos += 1;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                tokens.push(Token::Close { offset: pos, depth });
+                pos += 1;
+            }
+            b'[' => {
+                tokens.push(Token::OpenList { offset: pos, depth });
+                depth += 1;
+                pos += 1;
+            }
+            b']' => {
+                depth = depth.saturating_sub(1);
+                tokens.push(Token::CloseList { offset: pos, depth });
+                pos += 1;
+            }
+            b'(' => {
+                depth += 1;
+                pos += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                pos += 1;
+            }
+            byte if is_ident_start(byte) => {
+                let start = pos;
+                while pos < bytes.len() && is_ident_continue(bytes[pos]) {
+                    pos += 1;
+                }
+                if let Some(after) = label_colon(bytes, pos) {
+                    tokens.push(Token::Label {
+                        name: source[start..pos].to_string(),
+                        offset: start,
+                        depth,
+                    });
+                    pos = after;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+
+    tokens
+}
+
+/// Whether `name` can be written as a bare CUE identifier label
+pub fn is_identifier(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    matches!(bytes.next(), Some(b) if b.is_ascii_alphabetic() || b == b'_')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+fn is_ident_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || matches!(byte, b'_' | b'$' | b'#')
+}
+
+fn is_ident_continue(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'$' | b'#')
+}
+
+fn line_end(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |n| pos + n)
+}
+
+/// `#` only starts a string when it prefixes a quote (`#"raw"#`)
+fn starts_string(bytes: &[u8], pos: usize) -> bool {
+    let hashes = bytes[pos..].iter().take_while(|&&b| b == b'#').count();
+    matches!(bytes.get(pos + hashes), Some(b'"') | Some(b'\''))
+}
+
+/// Return the offset just past the string literal starting at `pos`
+fn string_end(bytes: &[u8], pos: usize) -> usize {
+    let hashes = bytes[pos..].iter().take_while(|&&b| b == b'#').count();
+    let quote = bytes[pos + hashes];
+    let open = pos + hashes;
+    let multiline = bytes.get(open..open + 3) == Some(&[quote, quote, quote][..]);
+    let delimiter_len = if multiline { 3 } else { 1 };
+
+    let mut cursor = open + delimiter_len;
+    while cursor < bytes.len() {
+        match bytes[cursor] {
+            b'\\' if hashes == 0 => cursor += 2,
+            b'\n' if !multiline => return cursor,
+            byte if byte == quote => {
+                let closes_quote =
+                    (0..delimiter_len).all(|i| bytes.get(cursor + i) == Some(&quote));
+                let closing_hashes = bytes[cursor + delimiter_len..]
+                    .iter()
+                    .take(hashes)
+                    .take_while(|&&b| b == b'#')
+                    .count();
+                if closes_quote && closing_hashes == hashes {
+                    return cursor + delimiter_len + hashes;
+                }
+                cursor += 1;
+            }
+            _ => cursor += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// If a label colon follows `pos` (allowing `?`/`!` markers), return the
+/// offset after it
+fn label_colon(bytes: &[u8], pos: usize) -> Option<usize> {
+    let mut cursor = pos;
+    if matches!(bytes.get(cursor), Some(b'?') | Some(b'!')) {
+        cursor += 1;
+    }
+    while matches!(bytes.get(cursor), Some(b' ') | Some(b'\t')) {
+        cursor += 1;
+    }
+    (bytes.get(cursor) == Some(&b':') && bytes.get(cursor + 1) != Some(&b':')).then_some(cursor + 1)
+}
+
+fn unquote(literal: &str) -> String {
+    let trimmed = literal.trim_matches('#');
+    serde_json::from_str(trimmed).unwrap_or_else(|_| trimmed.trim_matches('"').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(source: &str) -> Vec<(String, usize)> {
+        scan(source)
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Label { name, depth, .. } => Some((name, depth)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_labels_and_depth() {
+        let source = r#"package cuenv
+
+env: {
+	FOO: "bar"
+}
+tasks: {
+	"my-task": {
+		command: "echo {not a brace}"
+	}
+}
+"#;
+        assert_eq!(
+            labels(source),
+            vec![
+                ("env".to_string(), 0),
+                ("FOO".to_string(), 1),
+                ("tasks".to_string(), 0),
+                ("my-task".to_string(), 1),
+                ("command".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_skips_comments_and_strings() {
+        let source = "// tasks: {\nx: \"\"\"\n\tfake: {\n\t\"\"\"\ny: #\"raw \" }\"#\n";
+        assert_eq!(
+            labels(source),
+            vec![("x".to_string(), 0), ("y".to_string(), 0)]
+        );
+        assert!(scan(source)
+            .iter()
+            .all(|token| matches!(token, Token::Label { .. })));
+    }
+
+    #[test]
+    fn test_scan_ignores_values_and_optional_markers() {
+        let source = "a?: b.c & {d!: 1}\nlist: [{e: 2}]\n";
+        assert_eq!(
+            labels(source),
+            vec![
+                ("a".to_string(), 0),
+                ("d".to_string(), 1),
+                ("list".to_string(), 0),
+                ("e".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_lists() {
+        let source = "a: [\"]\", [1]]\n";
+        let lists: Vec<Token> = scan(source)
+            .into_iter()
+            .filter(|token| !matches!(token, Token::Label { .. }))
+            .collect();
+        assert_eq!(
+            lists,
+            vec![
+                Token::OpenList {
+                    offset: 3,
+                    depth: 0
+                },
+                Token::OpenList {
+                    offset: 9,
+                    depth: 1
+                },
+                Token::CloseList {
+                    offset: 11,
+                    depth: 1
+                },
+                Token::CloseList {
+                    offset: 12,
+                    depth: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_identifier() {
+        assert!(is_identifier("build"));
+        assert!(is_identifier("_private2"));
+        assert!(!is_identifier("my-task"));
+        assert!(!is_identifier("2fast"));
+        assert!(!is_identifier(""));
+    }
+}
@@ -0,0 +1,295 @@
+//! Structured editing of CUE packages
+//!
+//! Edits are applied as text patches located with a lightweight structural
+//! scan, so comments and formatting elsewhere in the file are preserved.
+//! Every edit is evaluated through the CUE bridge in a staging directory
+//! before the real file is touched.
+
+mod lexer;
+mod scaffold;
+mod security;
+
+pub use scaffold::TaskScaffold;
+pub use security::{grant_task_security, grant_task_security_in_file};
+
+use crate::parser::{CueParser, ParseOptions, ParseResult};
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use lexer::Token;
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_INDENT_UNIT: &str = "\t";
+const STAGING_PREFIX: &str = ".cuenv-edit-";
+
+/// Insert a new task into the top-level `tasks` struct of `source`
+///
+/// A `tasks` block is appended when the file does not declare one. Fails if
+/// a task with the same name already exists in the block.
+pub fn insert_task(source: &str, scaffold: &TaskScaffold) -> Result<String> {
+    let tokens = lexer::scan(source);
+
+    let Some(block) = find_tasks_block(&tokens) else {
+        let separator = if source.is_empty() || source.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return Ok(format!(
+            "{source}{separator}\ntasks: {{\n{}\n}}\n",
+            scaffold.render(DEFAULT_INDENT_UNIT, DEFAULT_INDENT_UNIT)
+        ));
+    };
+
+    if find_field(&tokens, &block, &scaffold.name).is_some() {
+        return Err(Error::configuration(format!(
+            "Task '{}' already exists",
+            scaffold.name
+        )));
+    }
+
+    Ok(insert_field(source, &tokens, &block, |indent, unit| {
+        scaffold.render(indent, unit)
+    }))
+}
+
+/// Evaluate `dir` as if `file_name` contained `content`, without modifying
+/// the real file
+///
+/// The package is copied into a hidden staging directory inside `dir` so
+/// module imports and parent-directory files resolve exactly as they do for
+/// the original package.
+pub fn validate_package_edit(
+    dir: &Path,
+    file_name: &str,
+    content: &str,
+    package_name: &str,
+) -> Result<ParseResult> {
+    let staging = tempfile::Builder::new()
+        .prefix(STAGING_PREFIX)
+        .tempdir_in(dir)
+        .map_err(|e| Error::file_system(dir, "create staging directory", e))?;
+
+    let entries = fs::read_dir(dir).map_err(|e| Error::file_system(dir, "read directory", e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::file_system(dir, "read directory entry", e))?;
+        let path = entry.path();
+        let is_cue = path.extension().is_some_and(|ext| ext == "cue");
+        if is_cue && path.is_file() && entry.file_name() != file_name {
+            let target = staging.path().join(entry.file_name());
+            fs::copy(&path, &target).map_err(|e| Error::file_system(&path, "copy", e))?;
+        }
+    }
+
+    write_atomic_string(&staging.path().join(file_name), content)?;
+
+    CueParser::eval_package_with_options(staging.path(), package_name, &ParseOptions::default())
+}
+
+/// Insert a task into `file`, validate the result through the CUE bridge,
+/// and only then write it back
+pub fn add_task_to_file(file: &Path, scaffold: &TaskScaffold, package_name: &str) -> Result<()> {
+    let source = fs::read_to_string(file).map_err(|e| Error::file_system(file, "read", e))?;
+    let patched = insert_task(&source, scaffold)?;
+
+    let (dir, file_name) = split_path(file)?;
+    let result = validate_package_edit(dir, file_name, &patched, package_name)?;
+    if !result.tasks.contains_key(&scaffold.name) {
+        return Err(Error::configuration(format!(
+            "Task '{}' was not present after evaluating the edited package",
+            scaffold.name
+        )));
+    }
+
+    write_atomic_string(file, &patched)
+}
+
+/// The directory and name of `file`
+fn split_path(file: &Path) -> Result<(&Path, &str)> {
+    let invalid = || Error::configuration(format!("Invalid path: {}", file.display()));
+    let dir = file.parent().ok_or_else(invalid)?;
+    let file_name = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(invalid)?;
+    Ok((dir, file_name))
+}
+
+/// The braces of a struct literal; `depth` is the nesting level outside them
+struct Block {
+    open: usize,
+    close: usize,
+    depth: usize,
+}
+
+/// Locate the braces of the struct assigned to the top-level `tasks` field
+fn find_tasks_block(tokens: &[Token]) -> Option<Block> {
+    let label_index = tokens.iter().position(
+        |token| matches!(token, Token::Label { name, depth: 0, .. } if name == "tasks"),
+    )?;
+    find_value_block(tokens, label_index)
+}
+
+/// Index of the label of the field `name` directly inside `block`
+fn find_field(tokens: &[Token], block: &Block, name: &str) -> Option<usize> {
+    tokens.iter().position(|token| {
+        matches!(token, Token::Label { name: label, offset, depth }
+            if *depth == block.depth + 1 && *offset > block.open && *offset < block.close && label == name)
+    })
+}
+
+/// Locate the struct literal in the value of the label at `label_index`,
+/// such as the `{}` of `tasks: env.#Tasks & {}`
+fn find_value_block(tokens: &[Token], label_index: usize) -> Option<Block> {
+    let Token::Label { depth, .. } = tokens[label_index] else {
+        return None;
+    };
+
+    let mut open = None;
+    for token in &tokens[label_index + 1..] {
+        match (token, open) {
+            (Token::Open { offset, depth: d }, None) if *d == depth => open = Some(*offset),
+            (Token::Close { offset, depth: d }, Some(open)) if *d == depth => {
+                return Some(Block {
+                    open,
+                    close: *offset,
+                    depth,
+                })
+            }
+            // The next field before any struct: the value is not a struct literal
+            (Token::Label { depth: d, .. }, None) if *d == depth => return None,
+            (Token::Close { depth: d, .. }, None) if *d < depth => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Add a field to `block`, rendered with its indentation and nesting step
+fn insert_field(
+    source: &str,
+    tokens: &[Token],
+    block: &Block,
+    render: impl Fn(&str, &str) -> String,
+) -> String {
+    let close_line_start = line_start(source, block.close);
+    let closing_indent = line_indent(source, block.close);
+    let unit = detect_indent_unit(source, tokens, block, closing_indent);
+
+    if source[close_line_start..block.close].trim().is_empty() {
+        // `}` sits on its own line: insert the field just above it
+        format!(
+            "{}{}\n{}",
+            &source[..close_line_start],
+            render(&format!("{closing_indent}{unit}"), &unit),
+            &source[close_line_start..]
+        )
+    } else {
+        // Single-line block such as `tasks: {}`: break it open
+        format!(
+            "{}\n{}\n{closing_indent}{}",
+            source[..block.close].trim_end(),
+            render(&format!("{closing_indent}{unit}"), &unit),
+            &source[block.close..]
+        )
+    }
+}
+
+/// Work out the indentation step used by children of `block`
+fn detect_indent_unit(
+    source: &str,
+    tokens: &[Token],
+    block: &Block,
+    closing_indent: &str,
+) -> String {
+    tokens
+        .iter()
+        .find_map(|token| match token {
+            Token::Label { offset, depth, .. }
+                if *depth == block.depth + 1 && *offset > block.open && *offset < block.close =>
+            {
+                let child_indent = &source[line_start(source, *offset)..*offset];
+                child_indent
+                    .strip_prefix(closing_indent)
+                    .filter(|unit| !unit.is_empty() && unit.trim().is_empty())
+                    .map(str::to_string)
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_INDENT_UNIT.to_string())
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |n| n + 1)
+}
+
+/// The whitespace the line holding `offset` starts with
+fn line_indent(source: &str, offset: usize) -> &str {
+    let start = line_start(source, offset);
+    let line = &source[start..offset];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaffold(name: &str) -> TaskScaffold {
+        TaskScaffold {
+            name: name.to_string(),
+            command: "echo hi".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_into_existing_block() {
+        let source =
+            "package cuenv\n\ntasks: {\n  build: {\n    command: \"make\"\n  }\n}\n\nenv: {}\n";
+        let patched = insert_task(source, &scaffold("test")).unwrap();
+
+        assert_eq!(
+            patched,
+            "package cuenv\n\ntasks: {\n  build: {\n    command: \"make\"\n  }\n  test: {\n    command: \"echo hi\"\n  }\n}\n\nenv: {}\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_with_schema_conjunction() {
+        let source = "package cuenv\n\ntasks: env.#Tasks & {\n\t\"a-b\": {command: \"x\"}\n}\n";
+        let patched = insert_task(source, &scaffold("new-task")).unwrap();
+
+        assert!(patched.ends_with("\t\"new-task\": {\n\t\tcommand: \"echo hi\"\n\t}\n}\n"));
+    }
+
+    #[test]
+    fn test_insert_into_empty_inline_block() {
+        let source = "package cuenv\ntasks: {}\n";
+        let patched = insert_task(source, &scaffold("lint")).unwrap();
+
+        assert_eq!(
+            patched,
+            "package cuenv\ntasks: {\n\tlint: {\n\t\tcommand: \"echo hi\"\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_append_block_when_missing() {
+        let source = "package cuenv\n\nenv: {\n\tFOO: \"tasks: {\"\n}";
+        let patched = insert_task(source, &scaffold("lint")).unwrap();
+
+        assert!(patched.starts_with(source));
+        assert!(patched.ends_with("\n\ntasks: {\n\tlint: {\n\t\tcommand: \"echo hi\"\n\t}\n}\n"));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_task() {
+        let source = "tasks: {\n\t\"lint\": {command: \"x\"}\n}\n";
+        assert!(insert_task(source, &scaffold("lint")).is_err());
+
+        // Nested fields with the same name are not task definitions
+        let nested = "tasks: {\n\tci: {\n\t\tlint: {command: \"x\"}\n\t}\n}\n";
+        assert!(insert_task(nested, &scaffold("lint")).is_ok());
+    }
+}
@@ -0,0 +1,119 @@
+//! Rendering of new task definitions as CUE source
+
+use super::lexer::is_identifier;
+
+/// Description of a task to append to a CUE package
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskScaffold {
+    pub name: String,
+    pub description: Option<String>,
+    pub command: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    /// Explicit cache setting; `None` leaves the project default in place
+    pub cache: Option<bool>,
+    /// Emit a restrictive `security` block derived from inputs/outputs
+    pub security: bool,
+}
+
+impl TaskScaffold {
+    /// Render the task as a CUE field, indenting every line with `indent`
+    /// and nesting with `unit`
+    pub fn render(&self, indent: &str, unit: &str) -> String {
+        let inner = format!("{indent}{unit}");
+        let mut lines = vec![format!("{indent}{}: {{", label(&self.name))];
+
+        if let Some(description) = &self.description {
+            lines.push(format!("{inner}description: {}", quote(description)));
+        }
+        lines.push(format!("{inner}command: {}", quote(&self.command)));
+        if !self.inputs.is_empty() {
+            lines.push(format!("{inner}inputs: {}", list(&self.inputs)));
+        }
+        if !self.outputs.is_empty() {
+            lines.push(format!("{inner}outputs: {}", list(&self.outputs)));
+        }
+        if let Some(cache) = self.cache {
+            lines.push(format!("{inner}cache: {cache}"));
+        }
+        if self.security {
+            lines.push(format!("{inner}security: {{"));
+            lines.push(format!("{inner}{unit}restrictDisk: true"));
+            lines.push(format!("{inner}{unit}restrictNetwork: true"));
+            lines.push(format!("{inner}{unit}inferFromInputsOutputs: true"));
+            lines.push(format!("{inner}{unit}allowedHosts: []"));
+            lines.push(format!("{inner}}}"));
+        }
+
+        lines.push(format!("{indent}}}"));
+        lines.join("\n")
+    }
+}
+
+/// Render a field label, quoting it when it is not a plain identifier
+pub fn label(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+/// Render a CUE string literal
+///
+/// JSON string escapes are a subset of CUE's, so serde's encoder is safe.
+pub(super) fn quote(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+pub(super) fn list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|value| quote(value)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_full_task() {
+        let scaffold = TaskScaffold {
+            name: "build-app".to_string(),
+            description: Some("Build \"the\" app".to_string()),
+            command: "cargo build".to_string(),
+            inputs: vec!["src/**".to_string()],
+            outputs: vec!["target/app".to_string()],
+            cache: Some(true),
+            security: true,
+        };
+
+        let expected = r#"	"build-app": {
+		description: "Build \"the\" app"
+		command: "cargo build"
+		inputs: ["src/**"]
+		outputs: ["target/app"]
+		cache: true
+		security: {
+			restrictDisk: true
+			restrictNetwork: true
+			inferFromInputsOutputs: true
+			allowedHosts: []
+		}
+	}"#;
+        assert_eq!(scaffold.render("\t", "\t"), expected);
+    }
+
+    #[test]
+    fn test_render_minimal_task() {
+        let scaffold = TaskScaffold {
+            name: "lint".to_string(),
+            command: "npm run lint".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            scaffold.render("", "  "),
+            "lint: {\n  command: \"npm run lint\"\n}"
+        );
+    }
+}
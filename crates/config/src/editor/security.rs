@@ -0,0 +1,325 @@
+//! Granting a task sandbox access, such as the access an audit observed
+//!
+//! The task's `security` block is patched in place: paths and hosts are
+//! appended to its lists, restrictions are switched on, and the block or its
+//! fields are added when missing. Everything else in it is left alone.
+
+use super::lexer::{self, Token};
+use super::scaffold::{list, quote};
+use super::{
+    find_field, find_tasks_block, find_value_block, insert_field, line_indent, line_start,
+    split_path, validate_package_edit, Block,
+};
+use crate::SecurityConfig;
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use std::fs;
+use std::path::Path;
+
+/// Add the access of `grant` to the `security` block of `task` in `source`
+///
+/// Only `readOnlyPaths`, `readWritePaths` and `allowedHosts` are added to,
+/// and `restrictDisk` and `restrictNetwork` switched on; other fields of
+/// `grant` are ignored. Values the lists already hold are the caller's to
+/// leave out.
+pub fn grant_task_security(source: &str, task: &str, grant: &SecurityConfig) -> Result<String> {
+    let mut patched = ensure_security_block(source, task)?;
+    for (field, enabled) in [
+        ("restrictDisk", grant.restrict_disk),
+        ("restrictNetwork", grant.restrict_network),
+    ] {
+        if enabled == Some(true) {
+            patched = enable_flag(&patched, task, field)?;
+        }
+    }
+    for (field, values) in [
+        ("readOnlyPaths", &grant.read_only_paths),
+        ("readWritePaths", &grant.read_write_paths),
+        ("allowedHosts", &grant.allowed_hosts),
+    ] {
+        if let Some(values) = values.as_ref().filter(|values| !values.is_empty()) {
+            patched = extend_list(&patched, task, field, values)?;
+        }
+    }
+    Ok(patched)
+}
+
+/// Grant `task` in `file` the access of `grant`, validate the result through
+/// the CUE bridge, and only then write it back
+pub fn grant_task_security_in_file(
+    file: &Path,
+    task: &str,
+    grant: &SecurityConfig,
+    package_name: &str,
+) -> Result<()> {
+    let source = fs::read_to_string(file).map_err(|e| Error::file_system(file, "read", e))?;
+    let patched = grant_task_security(&source, task, grant)?;
+
+    let (dir, file_name) = split_path(file)?;
+    let result = validate_package_edit(dir, file_name, &patched, package_name)?;
+    let security = result
+        .tasks
+        .get(task)
+        .and_then(|config| config.security.clone())
+        .unwrap_or_default();
+    let granted = |wanted: &Option<Vec<String>>, got: &Option<Vec<String>>| {
+        wanted
+            .iter()
+            .flatten()
+            .all(|value| got.iter().flatten().any(|have| have == value))
+    };
+    if !granted(&grant.read_only_paths, &security.read_only_paths)
+        || !granted(&grant.read_write_paths, &security.read_write_paths)
+        || !granted(&grant.allowed_hosts, &security.allowed_hosts)
+    {
+        return Err(Error::configuration(format!(
+            "The access granted to task '{task}' was not present after evaluating the edited package"
+        )));
+    }
+
+    write_atomic_string(file, &patched)
+}
+
+/// The struct literal of the task `task`, those of groups such as
+/// `ci.lint` nested in the group's
+fn task_block(tokens: &[Token], task: &str) -> Result<Block> {
+    let mut block = find_tasks_block(tokens)
+        .ok_or_else(|| Error::configuration("The file declares no tasks block"))?;
+    for name in task.split('.') {
+        let label = find_field(tokens, &block, name).ok_or_else(|| {
+            Error::configuration(format!(
+                "Task '{task}' is not declared in the tasks block of this file"
+            ))
+        })?;
+        block = find_value_block(tokens, label).ok_or_else(|| {
+            Error::configuration(format!("Task '{task}' is not a struct literal"))
+        })?;
+    }
+    Ok(block)
+}
+
+/// The struct literal of the `security` field of `task`, if it has one
+fn security_block(tokens: &[Token], task: &str) -> Result<Option<Block>> {
+    let task_block = task_block(tokens, task)?;
+    let Some(label) = find_field(tokens, &task_block, "security") else {
+        return Ok(None);
+    };
+    find_value_block(tokens, label).map(Some).ok_or_else(|| {
+        Error::configuration(format!(
+            "The security of task '{task}' has no struct literal to add to"
+        ))
+    })
+}
+
+/// Expect the `security` block of `task`, having added it
+fn expect_security_block(tokens: &[Token], task: &str) -> Result<Block> {
+    security_block(tokens, task)?
+        .ok_or_else(|| Error::configuration(format!("Task '{task}' has no security block")))
+}
+
+fn ensure_security_block(source: &str, task: &str) -> Result<String> {
+    let tokens = lexer::scan(source);
+    if security_block(&tokens, task)?.is_some() {
+        return Ok(source.to_string());
+    }
+    let block = task_block(&tokens, task)?;
+    Ok(insert_field(source, &tokens, &block, |indent, _| {
+        format!("{indent}security: {{\n{indent}}}")
+    }))
+}
+
+/// Set the flag `field` of the security block to true, unless it is set to
+/// anything other than `false`
+fn enable_flag(source: &str, task: &str, field: &str) -> Result<String> {
+    let tokens = lexer::scan(source);
+    let block = expect_security_block(&tokens, task)?;
+    let Some(label) = find_field(&tokens, &block, field) else {
+        return Ok(insert_field(source, &tokens, &block, |indent, _| {
+            format!("{indent}{field}: true")
+        }));
+    };
+    let Token::Label { offset, .. } = tokens[label] else {
+        return Ok(source.to_string());
+    };
+    let value_start = source[offset..]
+        .find(':')
+        .map_or(offset, |n| offset + n + 1);
+    let line_end = source[value_start..]
+        .find('\n')
+        .map_or(source.len(), |n| value_start + n);
+    let value = &source[value_start..line_end];
+    let value = value.split("//").next().unwrap_or(value);
+    let value = value.trim().trim_end_matches(',').trim_end();
+    if value != "false" {
+        return Ok(source.to_string());
+    }
+    let at = value_start + source[value_start..line_end].find("false").unwrap_or(0);
+    Ok(format!(
+        "{}true{}",
+        &source[..at],
+        &source[at + "false".len()..]
+    ))
+}
+
+/// Append `values` to the list `field` of the security block
+fn extend_list(source: &str, task: &str, field: &str, values: &[String]) -> Result<String> {
+    let tokens = lexer::scan(source);
+    let block = expect_security_block(&tokens, task)?;
+    let Some(label) = find_field(&tokens, &block, field) else {
+        return Ok(insert_field(source, &tokens, &block, |indent, _| {
+            format!("{indent}{field}: {}", list(values))
+        }));
+    };
+
+    let not_a_list =
+        || Error::configuration(format!("'{field}' of task '{task}' is not a list literal"));
+    let (open, depth) = match tokens.get(label + 1) {
+        Some(Token::OpenList { offset, depth }) if *depth == block.depth + 1 => (*offset, *depth),
+        _ => return Err(not_a_list()),
+    };
+    let Token::Label {
+        offset: label_offset,
+        ..
+    } = tokens[label]
+    else {
+        return Err(not_a_list());
+    };
+    // Only `field: [`, so a conjunction such as `base + [...]` is left alone
+    if !source[label_offset..open].trim_end().ends_with(':') {
+        return Err(not_a_list());
+    }
+    let close = tokens[label + 1..]
+        .iter()
+        .find_map(|token| match token {
+            Token::CloseList { offset, depth: d } if *d == depth => Some(*offset),
+            _ => None,
+        })
+        .ok_or_else(not_a_list)?;
+
+    let items: Vec<String> = values.iter().map(|value| quote(value)).collect();
+    let inner = &source[open + 1..close];
+    if inner.trim().is_empty() {
+        return Ok(format!(
+            "{}{}{}",
+            &source[..open],
+            list(values),
+            &source[close + 1..]
+        ));
+    }
+    let close_line_start = line_start(source, close);
+    if source[close_line_start..close].trim().is_empty() {
+        // One item per line: add ours in the same style, above the `]`
+        let last_item = source[..close].trim_end().len();
+        let item_indent = line_indent(source, last_item);
+        let comma = if source[..last_item].ends_with(',') {
+            ","
+        } else {
+            ""
+        };
+        let lines: String = items
+            .iter()
+            .map(|item| format!("{item_indent}{item}{comma}\n"))
+            .collect();
+        return Ok(format!(
+            "{}{lines}{}",
+            &source[..close_line_start],
+            &source[close_line_start..]
+        ));
+    }
+    let before = source[..close].trim_end();
+    let separator = if before.ends_with(',') { " " } else { ", " };
+    Ok(format!(
+        "{before}{separator}{}{}",
+        items.join(", "),
+        &source[close..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant() -> SecurityConfig {
+        SecurityConfig {
+            restrict_disk: Some(true),
+            restrict_network: Some(true),
+            read_only_paths: Some(vec!["/home/dev/.aws".to_string()]),
+            allowed_hosts: Some(vec!["api.example.com".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_grant_adds_security_block() {
+        let source = "tasks: {\n\tdeploy: {\n\t\tcommand: \"aws s3 sync\"\n\t}\n}\n";
+        let patched = grant_task_security(source, "deploy", &grant()).unwrap();
+        assert_eq!(
+            patched,
+            "tasks: {\n\tdeploy: {\n\t\tcommand: \"aws s3 sync\"\n\t\tsecurity: {\n\
+             \t\t\trestrictDisk: true\n\t\t\trestrictNetwork: true\n\
+             \t\t\treadOnlyPaths: [\"/home/dev/.aws\"]\n\
+             \t\t\tallowedHosts: [\"api.example.com\"]\n\t\t}\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_grant_extends_existing_lists() {
+        let source = r#"tasks: {
+  deploy: {
+    command: "deploy" // ships it
+    security: {
+      restrictDisk: false // for now
+      readOnlyPaths: [
+        "/etc/deploy",
+      ]
+      allowedHosts: ["a.example.com"]
+    }
+  }
+}
+"#;
+        let patched = grant_task_security(source, "deploy", &grant()).unwrap();
+        assert_eq!(
+            patched,
+            r#"tasks: {
+  deploy: {
+    command: "deploy" // ships it
+    security: {
+      restrictDisk: true // for now
+      readOnlyPaths: [
+        "/etc/deploy",
+        "/home/dev/.aws",
+      ]
+      allowedHosts: ["a.example.com", "api.example.com"]
+      restrictNetwork: true
+    }
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_grant_breaks_open_inline_blocks() {
+        let source = "tasks: {\n\tfetch: {command: \"curl\", security: {allowedHosts: []}}\n}\n";
+        let patched = grant_task_security(source, "fetch", &grant()).unwrap();
+        assert!(patched.contains("allowedHosts: [\"api.example.com\"]"));
+        assert!(patched.contains("\n\t\treadOnlyPaths: [\"/home/dev/.aws\"]\n"));
+        assert_eq!(patched.matches('{').count(), patched.matches('}').count());
+    }
+
+    #[test]
+    fn test_grant_to_task_of_group() {
+        let source = "tasks: {\n\tci: {\n\t\tlint: {\n\t\t\tcommand: \"lint\"\n\t\t}\n\t}\n}\n";
+        let patched = grant_task_security(source, "ci.lint", &grant()).unwrap();
+        assert!(patched.contains("\t\t\tsecurity: {\n\t\t\t\trestrictDisk: true\n"));
+    }
+
+    #[test]
+    fn test_grant_rejects_what_it_cannot_patch() {
+        let source =
+            "tasks: {\n\tbuild: {\n\t\tsecurity: {readOnlyPaths: base + [\"/x\"]}\n\t}\n}\n";
+        assert!(grant_task_security(source, "build", &grant()).is_err());
+        assert!(grant_task_security(source, "missing", &grant()).is_err());
+        assert!(grant_task_security("env: {}\n", "build", &grant()).is_err());
+    }
+}
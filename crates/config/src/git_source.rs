@@ -0,0 +1,237 @@
+//! Loading configuration from a git revision
+//!
+//! `--as-of <rev>` evaluates the env.cue files of a past commit without
+//! touching the working tree, index or checked out branch. The commit's tree
+//! is streamed out of the object database with `git archive` and unpacked
+//! into a private temporary directory, because the CUE bridge loads packages
+//! and `cue.mod` from a directory on disk. The whole tree is extracted so imports and parent directories
+//! resolve as they did at that commit, and it is removed again when the
+//! [`GitSnapshot`] is dropped.
+
+use cuenv_core::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// The tree of one commit, extracted for evaluation
+#[derive(Debug)]
+pub struct GitSnapshot {
+    root: TempDir,
+    /// Top level of the repository's working tree
+    repo_root: PathBuf,
+    /// Full hash of the commit
+    commit: String,
+    /// Path of the requested directory within the repository
+    relative_dir: PathBuf,
+}
+
+impl GitSnapshot {
+    /// Extract `rev` of the repository containing `dir`
+    ///
+    /// `rev` is anything `git rev-parse` accepts, such as a tag, branch or
+    /// abbreviated hash.
+    pub fn checkout(dir: &Path, rev: &str) -> Result<Self> {
+        // git reports the root with symlinks resolved, so compare canonical paths
+        let toplevel = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?);
+        let repo_root = toplevel
+            .canonicalize()
+            .map_err(|e| Error::file_system(&toplevel, "resolve repository root", e))?;
+        let commit = git(
+            dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{rev}^{{commit}}"),
+            ],
+        )
+        .map_err(|_| Error::configuration(format!("'{rev}' is not a commit of this repository")))?;
+
+        let canonical_dir = dir
+            .canonicalize()
+            .map_err(|e| Error::file_system(dir, "resolve directory", e))?;
+        let relative_dir = canonical_dir
+            .strip_prefix(&repo_root)
+            .map(Path::to_path_buf)
+            .map_err(|_| {
+                Error::configuration(format!(
+                    "{} is not inside the repository at {}",
+                    canonical_dir.display(),
+                    repo_root.display()
+                ))
+            })?;
+
+        let root = tempfile::Builder::new()
+            .prefix("cuenv-as-of-")
+            .tempdir()
+            .map_err(|e| {
+                Error::file_system(std::env::temp_dir(), "create snapshot directory", e)
+            })?;
+        extract_tree(&repo_root, &commit, root.path())?;
+
+        Ok(Self {
+            root,
+            repo_root,
+            commit,
+            relative_dir,
+        })
+    }
+
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// The requested directory as it was at the commit
+    pub fn dir(&self) -> PathBuf {
+        self.root.path().join(&self.relative_dir)
+    }
+
+    /// Where a path inside the snapshot lives in the working tree; `None`
+    /// for paths outside the snapshot
+    pub fn working_tree_path(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(self.root.path())
+            .ok()
+            .map(|relative| self.repo_root.join(relative))
+    }
+}
+
+/// Run git in `dir`, returning its trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| {
+            Error::command_execution(
+                "git",
+                args.iter().map(|arg| arg.to_string()).collect(),
+                e.to_string(),
+                None,
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            "git",
+            args.iter().map(|arg| arg.to_string()).collect(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            output.status.code(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Unpack the tree of `commit` into `dest` as `git archive` streams it
+fn extract_tree(repo_root: &Path, commit: &str, dest: &Path) -> Result<()> {
+    let args = vec![
+        "archive".to_string(),
+        "--format=tar".to_string(),
+        commit.to_string(),
+    ];
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::command_execution("git", args.clone(), e.to_string(), None))?;
+    let stdout = archive
+        .stdout
+        .take()
+        .ok_or_else(|| Error::configuration("git archive produced no output"))?;
+
+    // Unpacking refuses entries that would land outside `dest`
+    let unpacked = tar::Archive::new(stdout).unpack(dest);
+    let archived = archive
+        .wait_with_output()
+        .map_err(|e| Error::command_execution("git", args.clone(), e.to_string(), None))?;
+
+    // A failed unpack closes the pipe early, so its error explains git's
+    unpacked.map_err(|e| Error::file_system(dest, "extract revision", e))?;
+    if !archived.status.success() {
+        return Err(Error::command_execution(
+            "git",
+            args,
+            String::from_utf8_lossy(&archived.stderr).trim().to_string(),
+            archived.status.code(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_snapshot_holds_the_tree_of_the_revision() {
+        let repo = TempDir::new().unwrap();
+        let app = repo.path().join("app");
+        fs::create_dir_all(&app).unwrap();
+        run_git(repo.path(), &["init", "-q"]);
+
+        fs::write(app.join("env.cue"), "package cuenv\n\nVERSION: \"1\"\n").unwrap();
+        run_git(repo.path(), &["add", "."]);
+        run_git(repo.path(), &["commit", "-q", "-m", "v1"]);
+        run_git(repo.path(), &["tag", "v1"]);
+
+        fs::write(app.join("env.cue"), "package cuenv\n\nVERSION: \"2\"\n").unwrap();
+        fs::write(app.join("local.txt"), "not committed").unwrap();
+
+        let snapshot = GitSnapshot::checkout(&app, "v1").unwrap();
+        assert_eq!(snapshot.commit().len(), 40);
+        assert!(fs::read_to_string(snapshot.dir().join("env.cue"))
+            .unwrap()
+            .contains("VERSION: \"1\""));
+        assert!(!snapshot.dir().join("local.txt").exists());
+        assert_eq!(
+            snapshot.working_tree_path(&snapshot.dir().join("env.cue")),
+            Some(app.canonicalize().unwrap().join("env.cue"))
+        );
+        assert_eq!(snapshot.working_tree_path(&app), None);
+
+        // The working tree is left alone
+        assert!(fs::read_to_string(app.join("env.cue"))
+            .unwrap()
+            .contains("VERSION: \"2\""));
+
+        let root = snapshot.root.path().to_path_buf();
+        drop(snapshot);
+        assert!(!root.exists());
+
+        let err = GitSnapshot::checkout(&app, "v9").unwrap_err();
+        assert!(err.to_string().contains("'v9' is not a commit"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_snapshot_through_a_symlinked_directory() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        let app = repo.join("app");
+        fs::create_dir_all(&app).unwrap();
+        run_git(&repo, &["init", "-q"]);
+        fs::write(app.join("env.cue"), "package cuenv\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        let link = temp.path().join("link");
+        std::os::unix::fs::symlink(&repo, &link).unwrap();
+        let snapshot = GitSnapshot::checkout(&link.join("app"), "HEAD").unwrap();
+        assert!(snapshot.dir().join("env.cue").exists());
+        assert!(snapshot.dir().ends_with("app"));
+    }
+}
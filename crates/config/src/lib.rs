@@ -4,6 +4,8 @@
 
 pub mod cache;
 pub mod config;
+pub mod editor;
+pub mod git_source;
 pub mod loader;
 pub mod parser;
 
@@ -12,5 +14,6 @@ mod config_tests;
 
 pub use cache::*;
 pub use config::*;
+pub use git_source::GitSnapshot;
 pub use loader::*;
 pub use parser::*;
@@ -6,6 +6,7 @@
 
 use crate::{
     config::{Config, ConfigBuilder, MonorepoContext, RuntimeOptions},
+    git_source::GitSnapshot,
     CueParser, ParseOptions, ParseResult, SecurityConfig,
 };
 use cuenv_core::{
@@ -23,6 +24,8 @@ pub struct ConfigLoader {
     directory: Option<PathBuf>,
     /// Whether to discover monorepo packages
     discover_monorepo: bool,
+    /// Git revision to evaluate instead of the working tree
+    as_of: Option<String>,
 }
 
 impl ConfigLoader {
@@ -32,6 +35,7 @@ impl ConfigLoader {
             runtime: RuntimeOptions::default(),
             directory: None,
             discover_monorepo: true,
+            as_of: None,
         }
     }
 
@@ -71,6 +75,14 @@ impl ConfigLoader {
         self
     }
 
+    /// Evaluate the env.cue files as they were at a git revision
+    ///
+    /// The working tree is not touched; monorepo discovery is skipped.
+    pub fn as_of(mut self, rev: String) -> Self {
+        self.as_of = Some(rev);
+        self
+    }
+
     /// Load the configuration
     pub async fn load(self) -> Result<Config> {
         // Determine working directory
@@ -80,12 +92,20 @@ impl ConfigLoader {
             .or_else(|| std::env::current_dir().ok())
             .ok_or_else(|| Error::configuration("Failed to determine working directory"))?;
 
-        // Find env.cue file
-        let env_file = self.find_env_file(&working_dir)?;
-
-        // Parse CUE configuration if env file exists
-        let parse_result = if let Some(ref env_path) = env_file {
-            self.parse_cue_file(env_path)?
+        // Find and parse env.cue, in a snapshot of the revision if one is set
+        let (env_file, parse_result) = match &self.as_of {
+            Some(rev) => self.load_revision(&working_dir, rev)?,
+            None => {
+                let env_file = self.find_env_file(&working_dir)?;
+                let parse_result = env_file
+                    .as_deref()
+                    .map(|env_path| self.parse_cue_file(env_path))
+                    .transpose()?;
+                (env_file, parse_result)
+            }
+        };
+        let parse_result = if let Some(parse_result) = parse_result {
+            parse_result
         } else {
             // Create empty parse result for directories without env.cue
             ParseResult {
@@ -96,6 +116,10 @@ impl ConfigLoader {
                 task_nodes: indexmap::IndexMap::new(),
                 hooks: HashMap::new(),
                 config: None,
+                run_configs: HashMap::new(),
+                provenance: Default::default(),
+                imports: Vec::new(),
+                environments: Vec::new(),
             }
         };
 
@@ -109,7 +133,7 @@ impl ConfigLoader {
         let security = self.extract_security_config(&parse_result);
 
         // Detect monorepo context if enabled
-        let monorepo = if self.discover_monorepo {
+        let monorepo = if self.discover_monorepo && self.as_of.is_none() {
             self.detect_monorepo_context(&working_dir).await?
         } else {
             None
@@ -159,6 +183,28 @@ impl ConfigLoader {
         Ok(None)
     }
 
+    /// Find and parse env.cue in a snapshot of `rev`, returning where the
+    /// file lives in the working tree
+    fn load_revision(
+        &self,
+        working_dir: &Path,
+        rev: &str,
+    ) -> Result<(Option<PathBuf>, Option<ParseResult>)> {
+        let snapshot = GitSnapshot::checkout(working_dir, rev)?;
+
+        let Some(env_path) = self
+            .find_env_file(&snapshot.dir())?
+            .filter(|env_path| snapshot.working_tree_path(env_path).is_some())
+        else {
+            return Err(Error::configuration(format!(
+                "No {ENV_CUE_FILENAME} for {} at revision '{rev}'",
+                working_dir.display()
+            )));
+        };
+        let parse_result = self.parse_cue_file(&env_path)?;
+        Ok((snapshot.working_tree_path(&env_path), Some(parse_result)))
+    }
+
     /// Parse a CUE file and return the result
     fn parse_cue_file(&self, env_file: &Path) -> Result<ParseResult> {
         let dir = env_file
@@ -181,20 +227,12 @@ impl ConfigLoader {
     }
 
     /// Extract security configuration from parse result
-    fn extract_security_config(&self, _parse_result: &ParseResult) -> SecurityConfig {
-        // TODO: Extract security configuration from parse result
-        // For now, return default configuration
-        // Return empty security config for now
-        // TODO: Extract from parse result when SecurityConfig implements Default
-        SecurityConfig {
-            restrict_disk: None,
-            restrict_network: None,
-            read_only_paths: None,
-            read_write_paths: None,
-            deny_paths: None,
-            allowed_hosts: None,
-            infer_from_inputs_outputs: None,
-        }
+    fn extract_security_config(&self, parse_result: &ParseResult) -> SecurityConfig {
+        parse_result
+            .config
+            .as_ref()
+            .and_then(|config| config.security.clone())
+            .unwrap_or_default()
     }
 
     /// Detect monorepo context
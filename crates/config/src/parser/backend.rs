@@ -0,0 +1,127 @@
+//! What evaluates packages
+//!
+//! The Go bridge evaluates `env.cue` wherever it is linked in. Builds
+//! without it, such as minimal static binaries, read `env.json` instead:
+//! the bridge's output for the package, saved earlier by
+//! `cuenv config export-json`. `CUENV_EVAL_BACKEND=bridge|snapshot` picks
+//! one explicitly. Everything after evaluation is the same for both, so
+//! environments load and tasks list from a snapshot until `env.cue` changes.
+
+use cuenv_core::constants::{CUENV_EVAL_BACKEND_VAR, ENV_CUE_FILENAME, ENV_JSON_FILENAME};
+use cuenv_core::errors::{Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where evaluated packages come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalBackend {
+    /// Evaluate `env.cue` through the linked Go bridge
+    Bridge,
+    /// Read the `env.json` exported next to `env.cue`
+    Snapshot,
+}
+
+impl EvalBackend {
+    /// Whether this build links the Go bridge
+    pub const BRIDGE_AVAILABLE: bool = cfg!(feature = "cue-bridge");
+
+    /// The backend `CUENV_EVAL_BACKEND` asks for, or the bridge if linked in
+    pub fn current() -> Result<Self> {
+        match std::env::var(CUENV_EVAL_BACKEND_VAR) {
+            Ok(value) if !value.is_empty() => value.parse(),
+            _ if Self::BRIDGE_AVAILABLE => Ok(Self::Bridge),
+            _ => Ok(Self::Snapshot),
+        }
+    }
+}
+
+impl FromStr for EvalBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bridge" => Ok(Self::Bridge),
+            "snapshot" => Ok(Self::Snapshot),
+            other => Err(Error::configuration(format!(
+                "Invalid {CUENV_EVAL_BACKEND_VAR} '{other}'. Must be one of: bridge, snapshot"
+            ))),
+        }
+    }
+}
+
+/// Where the evaluation of the package in `dir` is exported to
+pub fn snapshot_path(dir: &Path) -> PathBuf {
+    dir.join(ENV_JSON_FILENAME)
+}
+
+/// The exported evaluation of the package in `dir`
+pub fn read_snapshot(dir: &Path) -> Result<String> {
+    let file = snapshot_path(dir);
+    let json = fs::read_to_string(&file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            Error::configuration(format!(
+                "No {ENV_JSON_FILENAME} in {} and no CUE bridge to evaluate {ENV_CUE_FILENAME}; \
+                 export it with `cuenv config export-json` where cuenv can",
+                dir.display()
+            ))
+        } else {
+            Error::file_system(&file, "read exported package", e)
+        }
+    })?;
+    if is_stale(dir, &file) {
+        log::warn!(
+            "{} is older than {ENV_CUE_FILENAME}; export it again to pick up changes",
+            file.display()
+        );
+    }
+    Ok(json)
+}
+
+/// Whether `env.cue` changed since `snapshot` was exported
+fn is_stale(dir: &Path, snapshot: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(&dir.join(ENV_CUE_FILENAME)), modified(snapshot)) {
+        (Some(source), Some(exported)) => source > exported,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CueParser;
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!(
+            "bridge".parse::<EvalBackend>().unwrap(),
+            EvalBackend::Bridge
+        );
+        assert_eq!(
+            "snapshot".parse::<EvalBackend>().unwrap(),
+            EvalBackend::Snapshot
+        );
+        assert!("json".parse::<EvalBackend>().is_err());
+    }
+
+    #[test]
+    fn test_read_snapshot() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let err = read_snapshot(temp_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("cuenv config export-json"));
+
+        fs::write(
+            snapshot_path(temp_dir.path()),
+            r#"{"env": {"PORT": "3000"}, "tasks": {"build": {"command": "make"}}}"#,
+        )
+        .unwrap();
+        let json = read_snapshot(temp_dir.path()).unwrap();
+        let result = CueParser::parse_json(temp_dir.path(), &json)
+            .unwrap()
+            .into_parse_result(&Default::default())
+            .unwrap();
+        assert_eq!(result.variables["PORT"], "3000");
+        assert!(result.tasks.contains_key("build"));
+    }
+}
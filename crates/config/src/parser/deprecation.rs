@@ -0,0 +1,195 @@
+//! Deprecated and renamed environment variables
+//!
+//! A variable may be declared as a struct instead of a plain value:
+//!
+//! ```cue
+//! env: {
+//!     DATABASE_URL: "postgres://localhost/app"
+//!     DB_URL: {renamedTo: "DATABASE_URL"}
+//!     LEGACY_MODE: {value: "1", deprecated: "no longer read by the server"}
+//! }
+//! ```
+//!
+//! Renamed variables take their value from the new name unless they set
+//! one explicitly, so existing consumers keep working during a migration.
+//!
+//! The CUE attributes `@deprecated("...")`, `@owner("...")` and `@tag(...)`
+//! reach us as the same kind of declaration, with `owner` and `tags` next
+//! to the value:
+//!
+//! ```cue
+//! env: DB_URL: "postgres://localhost/app" @deprecated("use DATABASE_URL") @owner("team-data")
+//! ```
+
+use super::types::VariableMetadata;
+use std::collections::HashMap;
+
+const VALUE_KEY: &str = "value";
+const DEPRECATED_KEY: &str = "deprecated";
+const RENAMED_TO_KEY: &str = "renamedTo";
+const OWNER_KEY: &str = "owner";
+const TAGS_KEY: &str = "tags";
+
+/// Split a variable declaration into its value and metadata
+///
+/// Returns `None` when `value` is not a declaration with deprecation,
+/// owner or tags, in which case it should be used unchanged.
+pub(crate) fn split_declaration(
+    value: &serde_json::Value,
+) -> Option<(Option<serde_json::Value>, VariableMetadata)> {
+    let object = value.as_object()?;
+    let string = |key: &str| object.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let metadata = VariableMetadata {
+        deprecated: string(DEPRECATED_KEY),
+        renamed_to: string(RENAMED_TO_KEY),
+        owner: string(OWNER_KEY),
+        tags: object
+            .get(TAGS_KEY)
+            .and_then(|v| v.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tag.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    if !metadata.is_deprecated() && metadata.owner.is_none() && metadata.tags.is_empty() {
+        return None;
+    }
+
+    Some((object.get(VALUE_KEY).cloned(), metadata))
+}
+
+/// Fill in renamed variables from their new names
+///
+/// With `export_aliases` disabled, renamed variables without an explicit
+/// value are left out so only the new name is exported.
+pub(crate) fn apply_renames(
+    variables: &mut HashMap<String, String>,
+    metadata: &HashMap<String, VariableMetadata>,
+    export_aliases: bool,
+) {
+    for (old_name, meta) in metadata {
+        let Some(new_name) = &meta.renamed_to else {
+            continue;
+        };
+        if !export_aliases || variables.contains_key(old_name) {
+            continue;
+        }
+        if let Some(value) = variables.get(new_name).cloned() {
+            variables.insert(old_name.clone(), value);
+        }
+    }
+}
+
+impl VariableMetadata {
+    /// Whether this variable is deprecated or renamed
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated.is_some() || self.renamed_to.is_some()
+    }
+
+    /// A human readable explanation of why `name` should no longer be used
+    pub fn deprecation_message(&self, name: &str) -> Option<String> {
+        match (&self.renamed_to, &self.deprecated) {
+            (Some(new_name), Some(note)) => {
+                Some(format!("{name} was renamed to {new_name} ({note})"))
+            }
+            (Some(new_name), None) => Some(format!("{name} was renamed to {new_name}")),
+            (None, Some(note)) if note.is_empty() => Some(format!("{name} is deprecated")),
+            (None, Some(note)) => Some(format!("{name} is deprecated: {note}")),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_split_declaration() {
+        assert!(split_declaration(&json!("plain")).is_none());
+        assert!(split_declaration(&json!({"value": "x"})).is_none());
+
+        let (value, meta) =
+            split_declaration(&json!({"value": "1", "deprecated": "use NEW"})).unwrap();
+        assert_eq!(value, Some(json!("1")));
+        assert_eq!(meta.deprecated.as_deref(), Some("use NEW"));
+        assert_eq!(meta.renamed_to, None);
+
+        let (value, meta) = split_declaration(&json!({"renamedTo": "NEW"})).unwrap();
+        assert_eq!(value, None);
+        assert_eq!(meta.renamed_to.as_deref(), Some("NEW"));
+
+        // As exported from `@owner("team-x") @tag("slow")`
+        let (value, meta) =
+            split_declaration(&json!({"value": "1", "owner": "team-x", "tags": ["slow", "ci"]}))
+                .unwrap();
+        assert_eq!(value, Some(json!("1")));
+        assert!(!meta.is_deprecated());
+        assert_eq!(meta.owner.as_deref(), Some("team-x"));
+        assert_eq!(meta.tags, ["slow", "ci"]);
+    }
+
+    #[test]
+    fn test_apply_renames() {
+        let metadata = HashMap::from([(
+            "OLD".to_string(),
+            VariableMetadata {
+                renamed_to: Some("NEW".to_string()),
+                ..Default::default()
+            },
+        )]);
+
+        let mut variables = HashMap::from([("NEW".to_string(), "value".to_string())]);
+        apply_renames(&mut variables, &metadata, true);
+        assert_eq!(variables.get("OLD").map(String::as_str), Some("value"));
+
+        let mut variables = HashMap::from([("NEW".to_string(), "value".to_string())]);
+        apply_renames(&mut variables, &metadata, false);
+        assert!(!variables.contains_key("OLD"));
+
+        // An explicit value on the old name wins
+        let mut variables = HashMap::from([
+            ("NEW".to_string(), "value".to_string()),
+            ("OLD".to_string(), "legacy".to_string()),
+        ]);
+        apply_renames(&mut variables, &metadata, true);
+        assert_eq!(variables.get("OLD").map(String::as_str), Some("legacy"));
+    }
+
+    #[test]
+    fn test_deprecation_message() {
+        let renamed = VariableMetadata {
+            renamed_to: Some("DATABASE_URL".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            renamed.deprecation_message("DB_URL").as_deref(),
+            Some("DB_URL was renamed to DATABASE_URL")
+        );
+
+        let deprecated = VariableMetadata {
+            deprecated: Some("use DATABASE_URL".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            deprecated.deprecation_message("DB").as_deref(),
+            Some("DB is deprecated: use DATABASE_URL")
+        );
+        let bare = VariableMetadata {
+            deprecated: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            bare.deprecation_message("DB").as_deref(),
+            Some("DB is deprecated")
+        );
+        assert!(VariableMetadata::default()
+            .deprecation_message("X")
+            .is_none());
+    }
+}
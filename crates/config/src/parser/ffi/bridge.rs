@@ -2,18 +2,25 @@
 //!
 //! Provides the main interface for evaluating CUE packages through FFI.
 
-use super::memory::CStringPtr;
-use crate::parser::processing::{build_parse_result, ParseOptions, ParseResult};
-use crate::parser::types::{CueParseResult, RawCueResult};
-use crate::parser::validation::{
-    create_ffi_string, validate_directory_path, validate_package_name,
-};
+use crate::parser::backend::{read_snapshot, EvalBackend};
+use crate::parser::deprecation::split_declaration;
+use crate::parser::groups::flatten_groups;
+use crate::parser::imports::take_imports;
+use crate::parser::inheritance::take_parents;
+use crate::parser::lazy::split_lazy_declaration;
+use crate::parser::processing::{EvaluatedPackage, ParseOptions, ParseResult};
+use crate::parser::profiles::apply_profiles;
+use crate::parser::strict::{parse_issues, schema_source, strict_schema_error, SchemaIssue};
+use crate::parser::types::{CueParseResult, HookValue, RawCueResult};
+use crate::parser::validation::{validate_directory_path, validate_package_name};
 use cuenv_core::errors::{Error, Result};
 use cuenv_utils::resilience::suggest_recovery;
 use std::collections::HashMap;
-use std::ffi::CStr;
 use std::path::Path;
 
+/// Keys of `env` that configure cuenv rather than declare variables
+const RESERVED_ENV_KEYS: [&str; 4] = ["environment", "capabilities", "hooks", "tasks"];
+
 pub struct CueParser;
 
 impl CueParser {
@@ -33,47 +40,153 @@ impl CueParser {
         package_name: &str,
         options: &ParseOptions,
     ) -> Result<ParseResult> {
-        // Validate inputs
-        validate_package_name(package_name)?;
-        let dir_str = validate_directory_path(dir)?;
+        Self::evaluate_package(dir, package_name)?.into_parse_result(options)
+    }
 
-        // Create FFI strings
-        let c_dir = create_ffi_string(&dir_str, "invalid directory path")?;
-        let c_package = create_ffi_string(package_name, "invalid package name")?;
+    /// Evaluate the package once, to build results for one or more sets of
+    /// options from
+    pub fn evaluate_package(dir: &Path, package_name: &str) -> Result<EvaluatedPackage> {
+        let json = Self::evaluate_json(dir, package_name)?;
+        Self::parse_json(dir, &json)
+            .and_then(|package| Self::enforce_strict_schema(dir, package_name, package))
+    }
 
-        // Call CUE evaluation
-        let result_ptr = call_cue_eval_package(&c_dir, &c_package);
+    /// Evaluate the package in each of `dirs`, with results in the same order
+    ///
+    /// The bridge evaluates them in parallel in a single call, far faster
+    /// than a call each when loading a monorepo. Should that call fail as a
+    /// whole, or with the snapshot backend, each is evaluated on its own.
+    pub fn evaluate_packages(dirs: &[&Path], package_name: &str) -> Vec<Result<EvaluatedPackage>> {
+        if dirs.len() > 1 && matches!(EvalBackend::current(), Ok(EvalBackend::Bridge)) {
+            match Self::evaluate_json_batch(dirs, package_name) {
+                Ok(results) => {
+                    return dirs
+                        .iter()
+                        .zip(results)
+                        .map(|(dir, json)| {
+                            Self::parse_json(dir, &json).and_then(|package| {
+                                Self::enforce_strict_schema(dir, package_name, package)
+                            })
+                        })
+                        .collect();
+                }
+                Err(e) => {
+                    log::warn!("Batch evaluation failed, evaluating packages one by one: {e}")
+                }
+            }
+        }
+        dirs.iter()
+            .map(|dir| Self::evaluate_package(dir, package_name))
+            .collect()
+    }
 
-        // Wrap the result pointer for automatic cleanup
-        // Safety: result_ptr is either null or a valid pointer returned from cue_eval_package
-        let result_wrapper = unsafe { CStringPtr::new(result_ptr) };
+    /// [`Self::evaluate_packages`] with results built for `options`
+    pub fn eval_packages_with_options(
+        dirs: &[&Path],
+        package_name: &str,
+        options: &ParseOptions,
+    ) -> Vec<Result<ParseResult>> {
+        Self::evaluate_packages(dirs, package_name)
+            .into_iter()
+            .map(|package| package.and_then(|package| package.into_parse_result(options)))
+            .collect()
+    }
 
-        if result_wrapper.is_null() {
-            return Err(Error::cue_parse(dir, "CUE parser returned null pointer"));
+    fn evaluate_json_batch(dirs: &[&Path], package_name: &str) -> Result<Vec<String>> {
+        validate_package_name(package_name)?;
+        let dirs_str = dirs
+            .iter()
+            .map(|dir| validate_directory_path(dir))
+            .collect::<Result<Vec<_>>>()?;
+        let results = super::native::eval_packages_json(&dirs_str, package_name)?;
+        if results.len() != dirs.len() {
+            return Err(Error::ffi(
+                "cue_eval_packages",
+                format!("expected {} results, got {}", dirs.len(), results.len()),
+            ));
         }
+        Ok(results)
+    }
+
+    /// Fields of the package in `dir` that the cuenv schema doesn't know
+    ///
+    /// This always uses the bridge, which checks the package against the
+    /// schema's closed definitions.
+    pub fn lint_schema(dir: &Path, package_name: &str) -> Result<Vec<SchemaIssue>> {
+        validate_package_name(package_name)?;
+        let dir_str = validate_directory_path(dir)?;
+        let json = super::native::lint_package_json(&dir_str, package_name, &schema_source())?;
+        parse_issues(dir, &json)
+    }
 
-        // Safety: We've verified the pointer is not null
-        let result_str = unsafe { result_wrapper.to_str()? };
+    /// `package`, unless it turns on strict schema mode and has fields the
+    /// schema doesn't know
+    ///
+    /// Snapshots can't be checked; they were checked when exported.
+    fn enforce_strict_schema(
+        dir: &Path,
+        package_name: &str,
+        package: EvaluatedPackage,
+    ) -> Result<EvaluatedPackage> {
+        if !package.strict_schema() || EvalBackend::current()? != EvalBackend::Bridge {
+            return Ok(package);
+        }
+        match strict_schema_error(&Self::lint_schema(dir, package_name)?) {
+            Some(error) => Err(error),
+            None => Ok(package),
+        }
+    }
 
-        let parse_result = if result_str.is_empty() {
-            ParseResult::default()
-        } else {
-            // Parse and validate JSON response
-            let json_value = parse_json_response(result_str)?;
-            check_for_error_response(&json_value, dir)?;
+    /// The package's evaluation as JSON, from the backend in use
+    ///
+    /// This is what `cuenv config export-json` saves as `env.json`.
+    pub fn evaluate_json(dir: &Path, package_name: &str) -> Result<String> {
+        validate_package_name(package_name)?;
+        let dir_str = validate_directory_path(dir)?;
+        match EvalBackend::current()? {
+            EvalBackend::Bridge => super::native::eval_package_json(&dir_str, package_name),
+            EvalBackend::Snapshot => read_snapshot(dir),
+        }
+    }
 
-            // Deserialize and build final result
-            let cue_result = deserialize_cue_result(json_value)?;
-            build_parse_result(cue_result, options)?
-        };
+    /// A package other than an env.cue package, such as a security policy,
+    /// as a JSON value
+    ///
+    /// Such files have no exported snapshot, so this always uses the bridge.
+    pub fn evaluate_value(dir: &Path, package_name: &str) -> Result<serde_json::Value> {
+        validate_package_name(package_name)?;
+        let dir_str = validate_directory_path(dir)?;
+        let json = super::native::eval_package_json(&dir_str, package_name)?;
+        let value = parse_json_response(&json)?;
+        check_for_error_response(&value, dir)?;
+        Ok(value)
+    }
 
-        // The CStringPtr will be automatically freed when it goes out of scope
-        Ok(parse_result)
+    /// Parse the JSON the bridge returned for the package in `dir`
+    ///
+    /// The JSON is deserialized straight into the result structures in a
+    /// single pass; an empty string is an empty package.
+    pub fn parse_json(dir: &Path, json: &str) -> Result<EvaluatedPackage> {
+        if json.is_empty() {
+            return Ok(EvaluatedPackage::default());
+        }
+        deserialize_cue_result(json, dir).map(EvaluatedPackage::new)
     }
 
     pub fn value_to_string(val: &serde_json::Value) -> Option<String> {
         match val {
-            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                log::warn!("Skipping non-primitive value");
+                None
+            }
+            _ => Self::into_string(val.clone()),
+        }
+    }
+
+    /// [`Self::value_to_string`] taking the value, so strings are moved
+    pub fn into_string(val: serde_json::Value) -> Option<String> {
+        match val {
+            serde_json::Value::String(s) => Some(s),
             serde_json::Value::Number(n) => Some(n.to_string()),
             serde_json::Value::Bool(b) => Some(b.to_string()),
             serde_json::Value::Null => None,
@@ -91,15 +204,6 @@ impl Default for CueParser {
     }
 }
 
-fn call_cue_eval_package(dir_path: &CStr, package_name: &CStr) -> *mut std::os::raw::c_char {
-    // Safety: cue_eval_package is an external C function that:
-    // - Takes two non-null C string pointers as arguments
-    // - Returns a heap-allocated C string that must be freed with cue_free_string
-    // - Returns null on allocation failure
-    // We ensure the input pointers are valid for the duration of the call
-    unsafe { super::cue_eval_package(dir_path.as_ptr(), package_name.as_ptr()) }
-}
-
 fn parse_json_response(json_str: &str) -> Result<serde_json::Value> {
     serde_json::from_str(json_str).map_err(|e| {
         let error = Error::Json {
@@ -115,19 +219,22 @@ fn parse_json_response(json_str: &str) -> Result<serde_json::Value> {
 fn check_for_error_response(json_value: &serde_json::Value, dir: &Path) -> Result<()> {
     if let serde_json::Value::Object(ref map) = json_value {
         if let Some(serde_json::Value::String(error)) = map.get("error") {
-            let cue_error = Error::cue_parse(dir, error.clone());
-
-            // Provide specific recovery suggestions based on error content
-            let recovery_hint = get_recovery_hint(error);
-
-            log::error!("CUE parsing error: {error}");
-            log::error!("Recovery suggestion: {recovery_hint}");
-            return Err(cue_error);
+            return Err(error_response(dir, error));
         }
     }
     Ok(())
 }
 
+/// The error for an `{"error": ...}` response
+fn error_response(dir: &Path, error: &str) -> Error {
+    // Provide specific recovery suggestions based on error content
+    let recovery_hint = get_recovery_hint(error);
+
+    log::error!("CUE parsing error: {error}");
+    log::error!("Recovery suggestion: {recovery_hint}");
+    Error::cue_parse(dir, error.to_string())
+}
+
 fn get_recovery_hint(error: &str) -> &'static str {
     if error.contains("cannot find package") {
         "Ensure your .cue files have 'package cuenv' at the top"
@@ -142,15 +249,19 @@ fn get_recovery_hint(error: &str) -> &'static str {
     }
 }
 
-fn deserialize_cue_result(json_value: serde_json::Value) -> Result<CueParseResult> {
-    // Check what's at the top level for error reporting if needed
-
-    // Try to deserialize as raw format first (simplified bridge)
-    if let Ok(raw) = serde_json::from_value::<RawCueResult>(json_value.clone()) {
+fn deserialize_cue_result(json: &str, dir: &Path) -> Result<CueParseResult> {
+    // The raw format of the simplified bridge is read straight from the
+    // string. An error response reads as one too, with only the error set.
+    if let Ok(mut raw) = serde_json::from_str::<RawCueResult>(json) {
+        if let Some(serde_json::Value::String(error)) = raw._other.remove("error") {
+            return Err(error_response(dir, &error));
+        }
         return convert_raw_to_cue_result(raw);
     }
 
     // Fallback to old format for compatibility
+    let json_value = parse_json_response(json)?;
+    check_for_error_response(&json_value, dir)?;
     serde_json::from_value(json_value).map_err(|e| {
         let error = Error::Json {
             message: "failed to parse CUE result structure".to_string(),
@@ -162,95 +273,140 @@ fn deserialize_cue_result(json_value: serde_json::Value) -> Result<CueParseResul
     })
 }
 
-fn convert_raw_to_cue_result(raw: RawCueResult) -> Result<CueParseResult> {
-    use crate::parser::types::{CommandConfig, HookValue, HooksConfig};
+fn convert_raw_to_cue_result(mut raw: RawCueResult) -> Result<CueParseResult> {
+    use crate::parser::types::{CommandConfig, HooksConfig};
+
+    let provenance = apply_profiles(&mut raw);
 
     let mut variables = HashMap::new();
-    let metadata = HashMap::new();
+    let mut metadata = HashMap::new();
     let mut commands = HashMap::new();
 
-    // Extract variables from env field (excluding special keys)
-    for (key, value) in raw.env.variables {
-        if !["environment", "capabilities", "hooks", "tasks"].contains(&key.as_str()) {
-            variables.insert(key.clone(), value);
-            // TODO: Extract @capability attributes if needed
+    // Bulk imports and the environment an environment inherits from are
+    // declared among the variables, but are not one
+    let environment_parents = take_parents(&mut raw.env.environment)?;
+    let imports = take_imports(&mut raw.env.variables)?;
+    let mut environment_imports = HashMap::new();
+    for (name, vars) in &mut raw.env.environment {
+        let env_imports = take_imports(vars)?;
+        if !env_imports.is_empty() {
+            environment_imports.insert(name.clone(), env_imports);
         }
     }
 
-    // Extract environment-specific overrides
-    let environments = raw.env.environment;
+    // Extract variables from env field, with groups flattened to prefixed
+    // variables. Special keys are dropped first, as their lower-case names
+    // would otherwise read as groups
+    raw.env
+        .variables
+        .retain(|key, _| !RESERVED_ENV_KEYS.contains(&key.as_str()));
+    let (env_variables, mut group_metadata) = flatten_groups(raw.env.variables);
+    for (key, value) in env_variables {
+        // TODO: Extract @capability attributes if needed
+        if let Some((value, meta)) = split_lazy_declaration(&value) {
+            variables.insert(key.clone(), value);
+            metadata.insert(key, meta);
+            continue;
+        }
+        match split_declaration(&value) {
+            Some((value, meta)) => {
+                if let Some(value) = value {
+                    variables.insert(key.clone(), value);
+                }
+                metadata.insert(key, meta);
+            }
+            None => {
+                variables.insert(key, value);
+            }
+        }
+    }
 
-    // Build command-to-capabilities mapping
-    for (cap_name, cap) in &raw.env.capabilities {
-        for cmd in &cap.commands {
-            commands
-                .entry(cmd.clone())
-                .or_insert_with(|| CommandConfig {
-                    capabilities: Some(vec![]),
+    // Extract environment-specific overrides, unwrapping deprecation declarations.
+    // A variable declared lazy in any environment is lazy in all of them.
+    let environments = raw
+        .env
+        .environment
+        .into_iter()
+        .map(|(name, vars)| {
+            let (vars, env_group_metadata) = flatten_groups(vars);
+            group_metadata.extend(env_group_metadata);
+            let vars = vars
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    if let Some((value, meta)) = split_lazy_declaration(&value) {
+                        metadata.entry(key.clone()).or_default().lazy = meta.lazy;
+                        return Some((key, value));
+                    }
+                    match split_declaration(&value) {
+                        Some((value, _)) => value.map(|value| (key, value)),
+                        None => Some((key, value)),
+                    }
                 })
-                .capabilities
-                .as_mut()
-                .unwrap()
-                .push(cap_name.clone());
-        }
+                .collect();
+            (name, vars)
+        })
+        .collect();
+
+    // Members of groups with a capability are tagged with it
+    for (key, meta) in group_metadata {
+        metadata.entry(key).or_default().capability = meta.capability;
     }
 
-    // Also process top-level capabilities
-    for (cap_name, cap) in &raw.capabilities {
+    // Build command-to-capabilities mapping, including top-level capabilities
+    for (cap_name, cap) in raw.env.capabilities.iter().chain(&raw.capabilities) {
         for cmd in &cap.commands {
-            commands
+            let command = commands
                 .entry(cmd.clone())
                 .or_insert_with(|| CommandConfig {
                     capabilities: Some(vec![]),
-                })
+                    security: None,
+                });
+            command
                 .capabilities
-                .as_mut()
-                .unwrap()
+                .get_or_insert_with(Vec::new)
                 .push(cap_name.clone());
+            if let Some(security) = &cap.security {
+                command
+                    .security
+                    .get_or_insert_with(Default::default)
+                    .merge(security);
+            }
         }
     }
 
     // Convert hooks
     let hooks = raw.hooks.map(|h| HooksConfig {
-        on_enter: h.on_enter.map(|v| {
-            if v.is_array() {
-                HookValue::Multiple(
-                    v.as_array()
-                        .unwrap()
-                        .iter()
-                        .filter_map(|hook| serde_json::from_value(hook.clone()).ok())
-                        .collect(),
-                )
-            } else {
-                serde_json::from_value(v)
-                    .map(|hook| HookValue::Single(Box::new(hook)))
-                    .unwrap_or(HookValue::Multiple(vec![]))
-            }
-        }),
-        on_exit: h.on_exit.map(|v| {
-            if v.is_array() {
-                HookValue::Multiple(
-                    v.as_array()
-                        .unwrap()
-                        .iter()
-                        .filter_map(|hook| serde_json::from_value(hook.clone()).ok())
-                        .collect(),
-                )
-            } else {
-                serde_json::from_value(v)
-                    .map(|hook| HookValue::Single(Box::new(hook)))
-                    .unwrap_or(HookValue::Multiple(vec![]))
-            }
-        }),
+        on_enter: h.on_enter.map(hook_value),
+        on_exit: h.on_exit.map(hook_value),
     });
 
     Ok(CueParseResult {
         variables,
         metadata,
         environments,
+        environment_parents,
+        imports,
+        environment_imports,
         commands,
         tasks: raw.tasks,
         hooks,
         config: raw.config,
+        run_configs: raw.run_configs,
+        provenance,
     })
 }
+
+/// A hook or list of hooks, skipping entries that are not hooks
+fn hook_value(value: serde_json::Value) -> HookValue {
+    match value {
+        serde_json::Value::Array(hooks) => HookValue::Multiple(
+            hooks
+                .into_iter()
+                .filter_map(|hook| serde_json::from_value(hook).ok())
+                .collect(),
+        ),
+        value => serde_json::from_value(value)
+            .map(|hook| HookValue::Single(Box::new(hook)))
+            .unwrap_or(HookValue::Multiple(vec![])),
+    }
+}
@@ -0,0 +1,62 @@
+//! Build information compiled into the Go bridge
+
+use crate::parser::types::CueVersionRange;
+use cuenv_core::errors::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Version of the bridge's exported interface this build expects
+pub const BRIDGE_ABI_VERSION: u32 = 3;
+
+/// What the linked Go bridge was built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeInfo {
+    pub abi_version: u32,
+    pub go_version: String,
+    pub cue_version: String,
+    pub os: String,
+    pub arch: String,
+}
+
+impl BridgeInfo {
+    /// Whether the bridge exports the interface this build calls
+    pub fn is_compatible(&self) -> bool {
+        self.abi_version == BRIDGE_ABI_VERSION
+    }
+}
+
+/// Ask the linked bridge for its build information
+pub fn bridge_info() -> Result<BridgeInfo> {
+    let json = super::native::bridge_info_json()?;
+    serde_json::from_str(&json).map_err(|e| Error::Json {
+        message: "failed to parse bridge info".to_string(),
+        source: e,
+    })
+}
+
+/// Fail when the CUE version the bridge embeds is outside the project's
+/// `config.cueVersion`
+///
+/// Builds without the bridge, and bridges built without a released CUE
+/// version, are not checked.
+pub fn check_cue_version(range: &CueVersionRange) -> Result<()> {
+    static CUE_VERSION: OnceLock<Option<String>> = OnceLock::new();
+    let version = CUE_VERSION.get_or_init(|| bridge_info().ok().map(|info| info.cue_version));
+    let Some(version) = version else {
+        return Ok(());
+    };
+    match range.allows(version) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Error::configuration(format!(
+            "This project supports CUE {range} (config.cueVersion), but this cuenv evaluates \
+             with CUE {version}. Install a cuenv built with a supported CUE version; \
+             `cuenv version --verbose` shows the version of a build"
+        ))),
+        Err(e) => {
+            range.requirement().map_err(Error::configuration)?;
+            log::warn!("Not checking config.cueVersion: {e}");
+            Ok(())
+        }
+    }
+}
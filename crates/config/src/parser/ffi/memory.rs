@@ -56,7 +56,7 @@ impl Drop for CStringPtr {
             // returned from cue_eval_package. The FFI contract guarantees that
             // this is safe to call exactly once per returned pointer.
             unsafe {
-                super::cue_free_string(self.ptr);
+                super::native::cue_free_string(self.ptr);
             }
         }
     }
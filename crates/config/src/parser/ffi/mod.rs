@@ -1,18 +1,19 @@
 //! FFI bridge to CUE evaluation library
 //!
 //! This module handles the low-level interaction with the Go-based CUE library
-//! through C FFI, including memory management and string conversion.
+//! through C FFI, including memory management and string conversion. Builds
+//! without the `cue-bridge` feature leave the library out and only read
+//! exported `env.json` snapshots.
 
 mod bridge;
+mod info;
+#[cfg(feature = "cue-bridge")]
 mod memory;
+#[cfg(feature = "cue-bridge")]
+mod native;
+#[cfg(not(feature = "cue-bridge"))]
+#[path = "unavailable.rs"]
+mod native;
 
 pub use bridge::CueParser;
-
-#[link(name = "cue_bridge")]
-extern "C" {
-    fn cue_eval_package(
-        dir_path: *const std::os::raw::c_char,
-        package_name: *const std::os::raw::c_char,
-    ) -> *mut std::os::raw::c_char;
-    fn cue_free_string(s: *mut std::os::raw::c_char);
-}
+pub use info::{bridge_info, check_cue_version, BridgeInfo, BRIDGE_ABI_VERSION};
@@ -0,0 +1,197 @@
+//! Evaluation through the linked Go bridge
+
+use super::memory::CStringPtr;
+use cuenv_core::constants::CUENV_MODULE_PATH_VAR;
+use cuenv_core::errors::{Error, Result};
+use std::ffi::{CStr, CString};
+
+#[link(name = "cue_bridge")]
+extern "C" {
+    fn cue_eval_package_with_modules(
+        dir_path: *const std::os::raw::c_char,
+        package_name: *const std::os::raw::c_char,
+        module_paths: *const std::os::raw::c_char,
+    ) -> *mut std::os::raw::c_char;
+    fn cue_eval_packages(
+        dir_paths: *const std::os::raw::c_char,
+        package_name: *const std::os::raw::c_char,
+        module_paths: *const std::os::raw::c_char,
+    ) -> *mut std::os::raw::c_char;
+    fn cue_lint_package(
+        dir_path: *const std::os::raw::c_char,
+        package_name: *const std::os::raw::c_char,
+        module_paths: *const std::os::raw::c_char,
+        schema_source: *const std::os::raw::c_char,
+    ) -> *mut std::os::raw::c_char;
+    fn cue_bridge_info() -> *mut std::os::raw::c_char;
+    pub(super) fn cue_free_string(s: *mut std::os::raw::c_char);
+}
+
+/// The bridge's JSON for the package `package_name` in `dir`
+pub(super) fn eval_package_json(dir: &str, package_name: &str) -> Result<String> {
+    // Create FFI strings
+    let c_dir = create_ffi_string(dir, "invalid directory path")?;
+    let c_package = create_ffi_string(package_name, "invalid package name")?;
+    let c_modules = create_ffi_string(&module_paths()?, "invalid module path")?;
+
+    // Call CUE evaluation
+    let result_ptr = call_cue_eval_package(&c_dir, &c_package, &c_modules);
+
+    // Wrap the result pointer for automatic cleanup
+    // Safety: result_ptr is either null or a valid pointer returned from cue_eval_package
+    let result_wrapper = unsafe { CStringPtr::new(result_ptr) };
+
+    if result_wrapper.is_null() {
+        return Err(Error::cue_parse(dir, "CUE parser returned null pointer"));
+    }
+
+    // Safety: We've verified the pointer is not null
+    // The CStringPtr will be automatically freed when it goes out of scope
+    unsafe { result_wrapper.to_str() }.map(str::to_string)
+}
+
+/// The bridge's JSON for the package `package_name` in each of `dirs`, in
+/// order, from a single call that evaluates them in parallel
+pub(super) fn eval_packages_json(dirs: &[String], package_name: &str) -> Result<Vec<String>> {
+    /// An array of results, or an error object when the call failed as a whole
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Response {
+        Results(Vec<String>),
+        Failed { error: String },
+    }
+
+    let dirs = serde_json::to_string(dirs).map_err(|e| Error::Json {
+        message: "failed to encode directory paths".to_string(),
+        source: e,
+    })?;
+    let c_dirs = create_ffi_string(&dirs, "invalid directory path")?;
+    let c_package = create_ffi_string(package_name, "invalid package name")?;
+    let c_modules = create_ffi_string(&module_paths()?, "invalid module path")?;
+
+    // Safety: cue_eval_packages takes three non-null C string pointers, valid
+    // for the duration of the call, and returns null or a heap-allocated C
+    // string that CStringPtr frees with cue_free_string
+    let result = unsafe {
+        CStringPtr::new(cue_eval_packages(
+            c_dirs.as_ptr(),
+            c_package.as_ptr(),
+            c_modules.as_ptr(),
+        ))
+    };
+    if result.is_null() {
+        return Err(Error::ffi(
+            "cue_eval_packages",
+            "bridge returned null pointer",
+        ));
+    }
+
+    // Safety: We've verified the pointer is not null
+    let json = unsafe { result.to_str() }?;
+    match serde_json::from_str(json) {
+        Ok(Response::Results(results)) => Ok(results),
+        Ok(Response::Failed { error }) => Err(Error::ffi("cue_eval_packages", error)),
+        Err(e) => Err(Error::Json {
+            message: "failed to parse batch result from CUE parser".to_string(),
+            source: e,
+        }),
+    }
+}
+
+/// The bridge's JSON listing the fields of the package `package_name` in
+/// `dir` that the `#Cuenv` definition of `schema` does not allow
+pub(super) fn lint_package_json(dir: &str, package_name: &str, schema: &str) -> Result<String> {
+    let c_dir = create_ffi_string(dir, "invalid directory path")?;
+    let c_package = create_ffi_string(package_name, "invalid package name")?;
+    let c_modules = create_ffi_string(&module_paths()?, "invalid module path")?;
+    let c_schema = create_ffi_string(schema, "invalid schema")?;
+
+    // Safety: cue_lint_package takes four non-null C string pointers, valid
+    // for the duration of the call, and returns null or a heap-allocated C
+    // string that CStringPtr frees with cue_free_string
+    let result = unsafe {
+        CStringPtr::new(cue_lint_package(
+            c_dir.as_ptr(),
+            c_package.as_ptr(),
+            c_modules.as_ptr(),
+            c_schema.as_ptr(),
+        ))
+    };
+    if result.is_null() {
+        return Err(Error::ffi(
+            "cue_lint_package",
+            "bridge returned null pointer",
+        ));
+    }
+
+    // Safety: We've verified the pointer is not null
+    unsafe { result.to_str() }.map(str::to_string)
+}
+
+/// The bridge's build information as JSON
+pub(super) fn bridge_info_json() -> Result<String> {
+    // Safety: cue_bridge_info takes no arguments and returns either null or a
+    // heap-allocated C string that CStringPtr frees with cue_free_string
+    let info = unsafe { CStringPtr::new(cue_bridge_info()) };
+    if info.is_null() {
+        return Err(Error::ffi(
+            "cue_bridge_info",
+            "bridge returned null pointer",
+        ));
+    }
+    // Safety: We've verified the pointer is not null
+    unsafe { info.to_str() }.map(str::to_string)
+}
+
+fn call_cue_eval_package(
+    dir_path: &CStr,
+    package_name: &CStr,
+    module_paths: &CStr,
+) -> *mut std::os::raw::c_char {
+    // Safety: cue_eval_package_with_modules is an external C function that:
+    // - Takes three non-null C string pointers as arguments
+    // - Returns a heap-allocated C string that must be freed with cue_free_string
+    // - Returns null on allocation failure
+    // We ensure the input pointers are valid for the duration of the call
+    unsafe {
+        cue_eval_package_with_modules(
+            dir_path.as_ptr(),
+            package_name.as_ptr(),
+            module_paths.as_ptr(),
+        )
+    }
+}
+
+/// Module directories from `CUENV_MODULE_PATH`, as the JSON array the bridge
+/// expects. Paths are made absolute because the bridge evaluates from the
+/// package directory.
+fn module_paths() -> Result<String> {
+    let paths = std::env::var_os(CUENV_MODULE_PATH_VAR)
+        .map(|value| {
+            std::env::split_paths(&value)
+                .filter(|path| !path.as_os_str().is_empty())
+                .map(|path| {
+                    std::path::absolute(&path)
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .map_err(|e| Error::file_system(path, "resolve module path", e))
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    serde_json::to_string(&paths).map_err(|e| Error::Json {
+        message: "failed to encode module paths".to_string(),
+        source: e,
+    })
+}
+
+/// Creates a CString for FFI, ensuring no null bytes
+fn create_ffi_string(value: &str, context: &str) -> Result<CString> {
+    CString::new(value).map_err(|e| {
+        Error::ffi(
+            "cue_eval_package",
+            format!("{context} - contains null byte: {e}"),
+        )
+    })
+}
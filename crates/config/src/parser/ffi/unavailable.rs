@@ -0,0 +1,26 @@
+//! Stand-in for the Go bridge in builds without it
+
+use cuenv_core::errors::{Error, Result};
+
+pub(super) fn eval_package_json(_dir: &str, _package_name: &str) -> Result<String> {
+    Err(unavailable())
+}
+
+pub(super) fn eval_packages_json(_dirs: &[String], _package_name: &str) -> Result<Vec<String>> {
+    Err(unavailable())
+}
+
+pub(super) fn lint_package_json(_dir: &str, _package_name: &str, _schema: &str) -> Result<String> {
+    Err(unavailable())
+}
+
+pub(super) fn bridge_info_json() -> Result<String> {
+    Err(unavailable())
+}
+
+fn unavailable() -> Error {
+    Error::configuration(
+        "This cuenv was built without the CUE bridge and cannot evaluate env.cue; \
+         unset CUENV_EVAL_BACKEND to read env.json exported by `cuenv config export-json`",
+    )
+}
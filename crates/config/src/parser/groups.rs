@@ -0,0 +1,223 @@
+//! Grouped environment variables
+//!
+//! Variables sharing a prefix can be nested under a lower-case group name.
+//! Groups flatten to prefixed variables and may nest further:
+//!
+//! ```cue
+//! env: {
+//!     aws: {
+//!         capability: "aws"
+//!         REGION:     "us-east-1"
+//!         s3: BUCKET: "assets"    // AWS_S3_BUCKET
+//!     }
+//!     vault: {
+//!         prefix:    "SECRETS"
+//!         separator: "__"
+//!         provider:  "vault"
+//!         TOKEN: {ref: "deploy/token"}    // SECRETS__TOKEN
+//!     }
+//! }
+//! ```
+//!
+//! `prefix` defaults to the group's name in upper case and `separator` to
+//! `_`. `capability` tags every member, so members are only loaded with
+//! that capability, and `provider` turns members declared as `{ref: ...}`
+//! into references to that secret provider. Nested groups inherit all four,
+//! overriding any they set, with their prefix appended to the parent's.
+
+use super::types::VariableMetadata;
+use cuenv_core::constants::CUENV_PROVIDER_PREFIX;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+const SEPARATOR_KEY: &str = "separator";
+const PREFIX_KEY: &str = "prefix";
+const CAPABILITY_KEY: &str = "capability";
+const PROVIDER_KEY: &str = "provider";
+const REF_KEY: &str = "ref";
+const DEFAULT_SEPARATOR: &str = "_";
+
+/// Settings a group passes on to its members
+#[derive(Debug, Clone, Default)]
+struct GroupDefaults {
+    prefix: String,
+    separator: String,
+    capability: Option<String>,
+    provider: Option<String>,
+}
+
+impl GroupDefaults {
+    /// Defaults of the group `name` declared by `group` inside `self`
+    fn nested(&self, name: &str, group: &Map<String, Value>) -> Self {
+        let setting = |key: &str| group.get(key).and_then(Value::as_str).map(str::to_string);
+        let separator = setting(SEPARATOR_KEY).unwrap_or_else(|| self.separator.clone());
+        let own_prefix = setting(PREFIX_KEY).unwrap_or_else(|| name.to_ascii_uppercase());
+        let prefix = if self.prefix.is_empty() {
+            own_prefix
+        } else {
+            format!("{}{separator}{own_prefix}", self.prefix)
+        };
+        Self {
+            prefix,
+            separator,
+            capability: setting(CAPABILITY_KEY).or_else(|| self.capability.clone()),
+            provider: setting(PROVIDER_KEY).or_else(|| self.provider.clone()),
+        }
+    }
+}
+
+/// Whether `key`, when declared as a struct, names a group of variables
+/// rather than a variable
+fn is_group_name(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_lowercase())
+}
+
+fn is_setting(key: &str) -> bool {
+    [SEPARATOR_KEY, PREFIX_KEY, CAPABILITY_KEY, PROVIDER_KEY].contains(&key)
+}
+
+/// Replace groups in `variables` with their prefixed members
+///
+/// Returns the capability each member inherited from its groups, to be
+/// merged into the variables' metadata. Variables outside groups are
+/// returned unchanged.
+pub(crate) fn flatten_groups(
+    variables: HashMap<String, Value>,
+) -> (HashMap<String, Value>, HashMap<String, VariableMetadata>) {
+    let mut flat = HashMap::with_capacity(variables.len());
+    let mut metadata = HashMap::new();
+    let root = GroupDefaults {
+        separator: DEFAULT_SEPARATOR.to_string(),
+        ..Default::default()
+    };
+
+    for (key, value) in variables {
+        match value {
+            Value::Object(group) if is_group_name(&key) => {
+                flatten_group(&root.nested(&key, &group), group, &mut flat, &mut metadata);
+            }
+            value => {
+                flat.insert(key, value);
+            }
+        }
+    }
+
+    (flat, metadata)
+}
+
+fn flatten_group(
+    defaults: &GroupDefaults,
+    group: Map<String, Value>,
+    flat: &mut HashMap<String, Value>,
+    metadata: &mut HashMap<String, VariableMetadata>,
+) {
+    for (key, value) in group {
+        if is_setting(&key) {
+            continue;
+        }
+        let value = match value {
+            Value::Object(nested) if is_group_name(&key) => {
+                flatten_group(&defaults.nested(&key, &nested), nested, flat, metadata);
+                continue;
+            }
+            value => value,
+        };
+
+        let name = format!("{}{}{key}", defaults.prefix, defaults.separator);
+        if let Some(capability) = &defaults.capability {
+            metadata.insert(
+                name.clone(),
+                VariableMetadata {
+                    capability: Some(capability.clone()),
+                    ..Default::default()
+                },
+            );
+        }
+        flat.insert(name, provider_reference(defaults, value));
+    }
+}
+
+/// Member declared as `{ref: ...}` of a group with a provider, as a
+/// reference to that provider; other values are kept
+fn provider_reference(defaults: &GroupDefaults, value: Value) -> Value {
+    let Some(provider) = &defaults.provider else {
+        return value;
+    };
+    match value {
+        Value::Object(mut declaration)
+            if declaration.get(REF_KEY).is_some_and(Value::is_string) =>
+        {
+            let reference = declaration
+                .remove(REF_KEY)
+                .and_then(|reference| reference.as_str().map(str::to_string))
+                .unwrap_or_default();
+            let reference = Value::String(format!("{CUENV_PROVIDER_PREFIX}{provider}/{reference}"));
+            if declaration.is_empty() {
+                return reference;
+            }
+            // Keep the rest of the declaration, e.g. `lazy: true`
+            declaration.insert("value".to_string(), reference);
+            Value::Object(declaration)
+        }
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn variables(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_groups_flatten_to_prefixed_variables() {
+        let (flat, metadata) = flatten_groups(variables(json!({
+            "PORT": "8080",
+            "aws": {
+                "capability": "aws",
+                "REGION": "us-east-1",
+                "s3": {"BUCKET": "assets"},
+                "ci": {"capability": "ci", "ROLE": "deployer"}
+            },
+            "vault": {
+                "prefix": "SECRETS",
+                "separator": "__",
+                "provider": "vault",
+                "TOKEN": {"ref": "deploy/token"},
+                "LAZY_TOKEN": {"ref": "deploy/lazy", "lazy": true},
+                "PLAIN": "value"
+            }
+        })));
+
+        assert_eq!(
+            flat,
+            variables(json!({
+                "PORT": "8080",
+                "AWS_REGION": "us-east-1",
+                "AWS_S3_BUCKET": "assets",
+                "AWS_CI_ROLE": "deployer",
+                "SECRETS__TOKEN": "cuenv-provider://vault/deploy/token",
+                "SECRETS__LAZY_TOKEN": {"value": "cuenv-provider://vault/deploy/lazy", "lazy": true},
+                "SECRETS__PLAIN": "value"
+            }))
+        );
+
+        let capability = |name: &str| metadata.get(name).and_then(|m| m.capability.as_deref());
+        assert_eq!(capability("AWS_REGION"), Some("aws"));
+        assert_eq!(capability("AWS_S3_BUCKET"), Some("aws"));
+        assert_eq!(capability("AWS_CI_ROLE"), Some("ci"));
+        assert_eq!(capability("SECRETS__TOKEN"), None);
+        assert_eq!(capability("PORT"), None);
+    }
+
+    #[test]
+    fn test_upper_case_declarations_are_not_groups() {
+        let declaration = json!({"renamedTo": "DATABASE_URL"});
+        let (flat, metadata) = flatten_groups(variables(json!({ "DB_URL": declaration })));
+        assert_eq!(flat.get("DB_URL"), Some(&declaration));
+        assert!(metadata.is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Bulk imports declared with `valueFrom`
+//!
+//! Every parameter under an AWS SSM path, or every key of an Azure App
+//! Configuration store, becomes a variable when the environment loads:
+//!
+//! ```cue
+//! env: {
+//!     valueFrom: [
+//!         {awsSsmPath: "/myapp/dev/", region: "eu-west-1"},
+//!         {azureAppConfig: {store: "myapp-config", prefix: "myapp:", label: "dev"}, ttl: 60},
+//!     ]
+//!     LOG_LEVEL: "debug"
+//!     environment: production: valueFrom: awsSsmPath: "/myapp/prod/"
+//! }
+//! ```
+//!
+//! `valueFrom` takes one import or a list. An environment's imports come
+//! after the base ones, and variables declared in CUE take precedence over
+//! imported ones.
+
+use super::types::EnvImport;
+use cuenv_core::{Error, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub(crate) const VALUE_FROM_KEY: &str = "valueFrom";
+
+/// Remove `valueFrom` from the variables it was declared among, returning
+/// the imports it declared
+pub(crate) fn take_imports(variables: &mut HashMap<String, Value>) -> Result<Vec<EnvImport>> {
+    let imports = match variables.remove(VALUE_FROM_KEY) {
+        None => return Ok(Vec::new()),
+        Some(Value::Array(imports)) => imports,
+        Some(import) => vec![import],
+    };
+    imports
+        .into_iter()
+        .map(|import| {
+            let import: EnvImport = serde_json::from_value(import).map_err(|e| Error::Json {
+                message: format!("Invalid {VALUE_FROM_KEY}: {e}"),
+                source: e,
+            })?;
+            import.validate().map_err(Error::configuration)?;
+            Ok(import)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_value_from_accepts_one_import_or_a_list() {
+        let mut variables: HashMap<String, Value> = serde_json::from_value(json!({
+            "PORT": "8080",
+            "valueFrom": {"awsSsmPath": "/myapp/dev/", "ttl": 60}
+        }))
+        .unwrap();
+        let imports = take_imports(&mut variables).unwrap();
+        assert_eq!(imports[0].aws_ssm_path.as_deref(), Some("/myapp/dev/"));
+        assert_eq!(imports[0].ttl, Some(60));
+        assert!(!variables.contains_key(VALUE_FROM_KEY));
+
+        let mut variables = HashMap::from([(
+            VALUE_FROM_KEY.to_string(),
+            json!([
+                {"awsSsmPath": "/myapp/dev/"},
+                {"azureAppConfig": {"store": "myapp-config", "prefix": "myapp:"}}
+            ]),
+        )]);
+        let imports = take_imports(&mut variables).unwrap();
+        assert_eq!(imports.len(), 2);
+        assert_eq!(
+            imports[1].azure_app_config.as_ref().unwrap().store,
+            "myapp-config"
+        );
+
+        for invalid in [
+            json!({"awsSsmPath": "myapp/dev"}),
+            json!({"region": "eu-west-1"}),
+            json!({"awsSsmPath": "/a/", "azureAppConfig": {"store": "b"}}),
+            json!({"awsSsmPath": "/a/", "pageSize": 10}),
+        ] {
+            let mut variables = HashMap::from([(VALUE_FROM_KEY.to_string(), invalid)]);
+            assert!(take_imports(&mut variables).is_err());
+        }
+    }
+}
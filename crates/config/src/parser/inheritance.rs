@@ -0,0 +1,152 @@
+//! Environments that build on other environments
+//!
+//! ```cue
+//! env: {
+//!     LOG_LEVEL: "info"
+//!     environment: {
+//!         dev: {DATABASE_URL: "postgres://dev.internal/app", LOG_LEVEL: "debug"}
+//!         staging: {
+//!             inherits:     "dev"
+//!             DATABASE_URL: "postgres://staging.internal/app"
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! An environment starts from the variables and imports of the environment
+//! it inherits from, which starts from its own parent, and so on. The
+//! nearest environment wins: staging's values override dev's, which
+//! override the base variables. Imports are concatenated, most distant
+//! ancestor first.
+
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_core::{Error, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub(crate) const INHERITS_KEY: &str = "inherits";
+
+/// Remove `inherits` from every environment, returning each environment's
+/// parent
+///
+/// Fails when a parent is not an environment or when environments inherit
+/// from each other in a loop.
+pub(crate) fn take_parents(
+    environments: &mut HashMap<String, HashMap<String, Value>>,
+) -> Result<HashMap<String, String>> {
+    let mut parents = HashMap::new();
+    for (name, vars) in environments.iter_mut() {
+        match vars.remove(INHERITS_KEY) {
+            None => {}
+            Some(Value::String(parent)) => {
+                parents.insert(name.clone(), parent);
+            }
+            Some(other) => {
+                return Err(Error::configuration(format!(
+                    "Environment '{name}': {INHERITS_KEY} must name an environment, got {other}"
+                )))
+            }
+        }
+    }
+
+    for (name, parent) in &parents {
+        if !environments.contains_key(parent) {
+            let suggestions = similar_names(parent, environments.keys());
+            return Err(Error::configuration(with_suggestions(
+                format!("Environment '{name}' inherits from unknown environment '{parent}'"),
+                &suggestions,
+            )));
+        }
+    }
+
+    for name in parents.keys() {
+        let mut path = vec![name.as_str()];
+        let mut current = name.as_str();
+        while let Some(parent) = parents.get(current) {
+            if path.contains(&parent.as_str()) {
+                path.push(parent);
+                return Err(Error::configuration(format!(
+                    "Environment '{name}' inherits from itself: {}",
+                    path.join(" -> ")
+                )));
+            }
+            path.push(parent);
+            current = parent;
+        }
+    }
+
+    Ok(parents)
+}
+
+/// `name` and the environments it inherits from, most distant ancestor
+/// first, so later entries take precedence
+pub(crate) fn inheritance_chain(name: &str, parents: &HashMap<String, String>) -> Vec<String> {
+    let mut chain = vec![name.to_string()];
+    let mut current = name;
+    // take_parents rejected loops, the length bound only guards against
+    // maps built elsewhere
+    while let Some(parent) = parents.get(current) {
+        if chain.len() > parents.len() {
+            break;
+        }
+        chain.push(parent.clone());
+        current = parent;
+    }
+    chain.reverse();
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn environments(value: Value) -> HashMap<String, HashMap<String, Value>> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_chain_lists_ancestors_first() {
+        let mut envs = environments(json!({
+            "dev": {"LOG": "debug"},
+            "staging": {"inherits": "dev", "LOG": "info"},
+            "perf": {"inherits": "staging"},
+            "production": {}
+        }));
+        let parents = take_parents(&mut envs).unwrap();
+
+        assert!(!envs["staging"].contains_key(INHERITS_KEY));
+        assert_eq!(
+            inheritance_chain("perf", &parents),
+            vec!["dev", "staging", "perf"]
+        );
+        assert_eq!(
+            inheritance_chain("production", &parents),
+            vec!["production"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_parents_are_rejected() {
+        let mut envs = environments(json!({
+            "dev": {},
+            "staging": {"inherits": "deev"}
+        }));
+        let err = take_parents(&mut envs).unwrap_err().to_string();
+        assert!(err.contains("unknown environment 'deev'"));
+        assert!(err.contains("dev"));
+
+        let mut envs = environments(json!({
+            "a": {"inherits": "b"},
+            "b": {"inherits": "a"}
+        }));
+        let err = take_parents(&mut envs).unwrap_err().to_string();
+        assert!(err.contains("inherits from itself"));
+
+        let mut envs = environments(json!({"a": {"inherits": "a"}}));
+        assert!(take_parents(&mut envs).is_err());
+
+        let mut envs = environments(json!({"a": {"inherits": 1}}));
+        assert!(take_parents(&mut envs).is_err());
+    }
+}
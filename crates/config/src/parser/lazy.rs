@@ -0,0 +1,82 @@
+//! Lazily resolved secrets
+//!
+//! A secret declared with `lazy: true` is not part of the shell environment.
+//! It is resolved only when a task references it or a command runs with it:
+//!
+//! ```cue
+//! env: {
+//!     DB_PASSWORD: schema.#OnePasswordRef & {ref: "op://prod/db/password", lazy: true}
+//!     DEPLOY_TOKEN: {value: "cuenv-provider://vault/deploy/token", lazy: true}
+//! }
+//! ```
+
+use super::deprecation::split_declaration;
+use super::types::VariableMetadata;
+use cuenv_core::constants::CUENV_RESOLVER_PREFIX;
+use serde_json::{json, Value};
+
+const LAZY_KEY: &str = "lazy";
+const VALUE_KEY: &str = "value";
+const RESOLVER_KEY: &str = "resolver";
+
+/// Split a `lazy: true` declaration into its secret reference and metadata
+///
+/// The reference is the declaration's `value`, or a resolver reference
+/// built from its `resolver`. Returns `None` for anything else.
+pub(crate) fn split_lazy_declaration(value: &Value) -> Option<(Value, VariableMetadata)> {
+    let object = value.as_object()?;
+    if object.get(LAZY_KEY).and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+
+    let reference = match (object.get(VALUE_KEY), object.get(RESOLVER_KEY)) {
+        (Some(Value::String(reference)), _) => reference.clone(),
+        (_, Some(resolver)) => {
+            let config = json!({
+                "cmd": resolver.get("command")?,
+                "args": resolver.get("args").cloned().unwrap_or_else(|| json!([])),
+            });
+            format!("{CUENV_RESOLVER_PREFIX}{config}")
+        }
+        _ => return None,
+    };
+
+    // Lazy secrets may be deprecated, owned and tagged too
+    let metadata = VariableMetadata {
+        lazy: true,
+        ..split_declaration(value)
+            .map(|(_, meta)| meta)
+            .unwrap_or_default()
+    };
+    Some((Value::String(reference), metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lazy_declaration() {
+        let (value, meta) = split_lazy_declaration(&json!({
+            "value": "cuenv-provider://vault/deploy/token",
+            "lazy": true
+        }))
+        .unwrap();
+        assert_eq!(value, "cuenv-provider://vault/deploy/token");
+        assert!(meta.lazy);
+
+        let (value, _) = split_lazy_declaration(&json!({
+            "ref": "op://prod/db/password",
+            "resolver": {"command": "op", "args": ["read", "op://prod/db/password"]},
+            "lazy": true
+        }))
+        .unwrap();
+        assert_eq!(
+            value,
+            r#"cuenv-resolver://{"args":["read","op://prod/db/password"],"cmd":"op"}"#
+        );
+
+        assert!(split_lazy_declaration(&json!({"value": "x", "lazy": false})).is_none());
+        assert!(split_lazy_declaration(&json!("plain")).is_none());
+    }
+}
@@ -3,21 +3,35 @@
 //! This module provides functionality to parse CUE files and extract
 //! environment variables, metadata, commands, tasks, and hooks.
 
+mod backend;
+mod deprecation;
 mod ffi;
+mod groups;
+mod imports;
+mod inheritance;
+mod lazy;
 mod processing;
+mod profiles;
+mod strict;
 mod types;
 mod validation;
 
-pub use ffi::CueParser;
-pub use processing::{ParseOptions, ParseResult};
+pub use backend::{read_snapshot, snapshot_path, EvalBackend};
+pub use ffi::{bridge_info, check_cue_version, BridgeInfo, CueParser, BRIDGE_ABI_VERSION};
+pub use processing::{EvaluatedPackage, ParseOptions, ParseResult};
+pub use strict::{strict_schema_error, SchemaIssue, SourceLocation};
 pub use types::{
-    CacheEnvConfig, CommandConfig, ConfigSettings, Hook, HookConfig, HookConstraint, HookType,
-    HookValue, SecurityConfig, TaskCacheConfig, TaskCollection, TaskConfig, TaskNode,
-    VariableMetadata,
+    AzureAppConfigImport, CacheEnvConfig, CatchUpPolicy, CommandConfig, ComposeHook,
+    ConfigSettings, ConfirmConfig, CueVersionRange, EnvImport, FetchHook, Hook, HookConfig,
+    HookConstraint, HookType, HookValue, HttpPublishConfig, OciPublishConfig, Origin,
+    OutputValueConfig, ProblemMatcherConfig, Provenance, PublishConfig, PublishTargetConfig,
+    RunAsConfig, RunConfig, ScheduleConfig, SecurityConfig, TaskCacheConfig, TaskCollection,
+    TaskConfig, TaskNode, TaskOutputsConfig, VariableMetadata, WaitForConfig,
 };
 
-#[cfg(test)]
+// Both evaluate env.cue through the bridge
+#[cfg(all(test, feature = "cue-bridge"))]
 mod tests;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "cue-bridge"))]
 mod field_ordering_test;
@@ -1,11 +1,14 @@
 //! Processing logic for CUE parse results
 
+use crate::parser::deprecation::apply_renames;
 use crate::parser::ffi::CueParser;
+use crate::parser::inheritance::inheritance_chain;
 use crate::parser::types::{
-    CommandConfig, ConfigSettings, CueParseResult, Hook, HookValue, HooksConfig, TaskCollection,
-    TaskConfig, TaskNode, VariableMetadata,
+    CommandConfig, ConfigSettings, CueParseResult, EnvImport, Hook, HookValue, HooksConfig,
+    Provenance, RunConfig, TaskCollection, TaskConfig, TaskNode, VariableMetadata,
 };
 use cuenv_core::errors::Result;
+use cuenv_core::suggestions::{similar_names, with_suggestions};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -25,14 +28,74 @@ pub struct ParseResult {
     pub task_nodes: IndexMap<String, TaskNode>, // Preserve task structure
     pub hooks: HashMap<String, Vec<Hook>>,
     pub config: Option<ConfigSettings>,
+    /// Named run configurations, see `cuenv run-config`
+    #[serde(default)]
+    pub run_configs: HashMap<String, RunConfig>,
+    /// Where profile-contributed settings came from
+    #[serde(default)]
+    pub provenance: Provenance,
+    /// Stores to import variables from, in order, for the selected
+    /// environment
+    #[serde(default)]
+    pub imports: Vec<EnvImport>,
+    /// Every environment the package declares, sorted
+    #[serde(default)]
+    pub environments: Vec<String>,
+}
+
+/// A package as the bridge evaluated it, before an environment and
+/// capabilities are selected
+///
+/// Building a [`ParseResult`] for several sets of options from one
+/// evaluation saves a bridge call for each of them.
+#[derive(Debug, Clone, Default)]
+pub struct EvaluatedPackage {
+    result: CueParseResult,
+}
+
+impl EvaluatedPackage {
+    pub(crate) fn new(result: CueParseResult) -> Self {
+        Self { result }
+    }
+
+    /// Whether `config` turns on strict schema mode
+    pub(crate) fn strict_schema(&self) -> bool {
+        self.result
+            .config
+            .as_ref()
+            .and_then(|config| config.strict_schema)
+            .unwrap_or(false)
+    }
+
+    /// Commands and the capabilities they need, whatever the options
+    pub fn commands(&self) -> &HashMap<String, CommandConfig> {
+        &self.result.commands
+    }
+
+    /// The result for `options`, leaving the package to build others from
+    pub fn parse_result(&self, options: &ParseOptions) -> Result<ParseResult> {
+        build_parse_result(self.result.clone(), options)
+    }
+
+    /// The result for `options`
+    pub fn into_parse_result(self, options: &ParseOptions) -> Result<ParseResult> {
+        build_parse_result(self.result, options)
+    }
 }
 
 /// Builds the final parse result from CUE data
+///
+/// Variables, tasks and hooks are moved out of `cue_result` rather than
+/// copied.
 pub fn build_parse_result(
     mut cue_result: CueParseResult,
     options: &ParseOptions,
 ) -> Result<ParseResult> {
-    let final_vars = build_filtered_variables(&cue_result, options);
+    let environments = selected_environments(&cue_result, options);
+    let mut environment_names: Vec<String> = cue_result.environments.keys().cloned().collect();
+    environment_names.sort();
+    let (final_vars, environment_origins) =
+        build_filtered_variables(&mut cue_result, &environments, options);
     let hooks = extract_hooks(cue_result.hooks);
     let (tasks, task_nodes) = process_tasks_with_structure(cue_result.tasks);
 
@@ -41,7 +104,27 @@ pub fn build_parse_result(
         config
             .validate()
             .map_err(|e| cuenv_core::Error::configuration(&e))?;
+        if let Some(ref range) = config.cue_version {
+            super::ffi::check_cue_version(range)?;
+        }
+    }
+    validate_run_configs(&cue_result.run_configs, &tasks, &task_nodes)?;
+
+    let mut imports = std::mem::take(&mut cue_result.imports);
+    for name in &environments {
+        imports.extend(
+            cue_result
+                .environment_imports
+                .remove(name)
+                .unwrap_or_default(),
+        );
+    }
+
+    let mut provenance = cue_result.provenance;
+    for (key, environment) in environment_origins {
+        provenance.variables.entry(key).or_default().environment = Some(environment);
     }
+    provenance.environments = environments;
 
     Ok(ParseResult {
         variables: final_vars,
@@ -51,9 +134,39 @@ pub fn build_parse_result(
         task_nodes,
         hooks,
         config: cue_result.config,
+        run_configs: cue_result.run_configs,
+        provenance,
+        imports,
+        environments: environment_names,
     })
 }
 
+/// Check that every run configuration names tasks, and that tasks of this
+/// package it names exist. Cross-package references (`pkg:task`) are
+/// resolved when the configuration runs.
+fn validate_run_configs(
+    run_configs: &HashMap<String, RunConfig>,
+    tasks: &HashMap<String, TaskConfig>,
+    task_nodes: &IndexMap<String, TaskNode>,
+) -> Result<()> {
+    for (name, run_config) in run_configs {
+        if run_config.tasks.is_empty() {
+            return Err(cuenv_core::Error::configuration(format!(
+                "Run configuration '{name}' has no tasks"
+            )));
+        }
+        let unknown = run_config.tasks.iter().find(|task| {
+            !task.contains(':') && !tasks.contains_key(*task) && !task_nodes.contains_key(*task)
+        });
+        if let Some(task) = unknown {
+            return Err(cuenv_core::Error::configuration(format!(
+                "Run configuration '{name}' refers to unknown task '{task}'"
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Determines if a variable should be included based on capabilities
 fn should_include_variable(
     key: &str,
@@ -76,16 +189,16 @@ fn should_include_variable(
 
 /// Processes variables from JSON values to strings
 fn process_variables(
-    variables: &HashMap<String, serde_json::Value>,
+    variables: HashMap<String, serde_json::Value>,
     metadata: &HashMap<String, VariableMetadata>,
     capabilities: &[String],
 ) -> HashMap<String, String> {
     let mut result = HashMap::with_capacity(variables.len());
 
     for (key, val) in variables {
-        if should_include_variable(key, metadata, capabilities) {
-            if let Some(str_val) = CueParser::value_to_string(val) {
-                result.insert(key.clone(), str_val);
+        if should_include_variable(&key, metadata, capabilities) {
+            if let Some(str_val) = CueParser::into_string(val) {
+                result.insert(key, str_val);
             }
         }
     }
@@ -93,30 +206,66 @@ fn process_variables(
     result
 }
 
-/// Builds filtered variables with environment overrides
+/// The selected environment and those it inherits from, lowest precedence
+/// first, or nothing when no known environment is selected
+fn selected_environments(cue_result: &CueParseResult, options: &ParseOptions) -> Vec<String> {
+    let Some(env_name) = &options.environment else {
+        return Vec::new();
+    };
+    if cue_result.environments.contains_key(env_name) {
+        return inheritance_chain(env_name, &cue_result.environment_parents);
+    }
+    if !cue_result.environments.is_empty() {
+        let suggestions = similar_names(env_name, cue_result.environments.keys());
+        log::warn!(
+            "{}",
+            with_suggestions(
+                format!("Environment '{env_name}' not found, using the base variables"),
+                &suggestions
+            )
+        );
+    }
+    Vec::new()
+}
+
+/// Builds filtered variables with the overrides of `environments` applied
+/// in order, returning also the environment each overridden variable came
+/// from
 fn build_filtered_variables(
-    cue_result: &CueParseResult,
+    cue_result: &mut CueParseResult,
+    environments: &[String],
     options: &ParseOptions,
-) -> HashMap<String, String> {
+) -> (HashMap<String, String>, HashMap<String, String>) {
     // Start with base variables
     let mut final_vars = process_variables(
-        &cue_result.variables,
+        std::mem::take(&mut cue_result.variables),
         &cue_result.metadata,
         &options.capabilities,
     );
+    let mut origins = HashMap::new();
 
-    // Apply environment-specific overrides
-    if let Some(env_name) = &options.environment {
-        if let Some(env_vars) = cue_result.environments.get(env_name) {
-            let env_overrides =
-                process_variables(env_vars, &cue_result.metadata, &options.capabilities);
-
-            // Merge environment overrides into base variables
-            final_vars.extend(env_overrides);
+    // Apply environment-specific overrides, nearest environment last
+    for name in environments {
+        let Some(env_vars) = cue_result.environments.remove(name) else {
+            continue;
+        };
+        let env_overrides =
+            process_variables(env_vars, &cue_result.metadata, &options.capabilities);
+        for key in env_overrides.keys() {
+            origins.insert(key.clone(), name.clone());
         }
+        final_vars.extend(env_overrides);
     }
 
-    final_vars
+    // Renamed variables mirror their replacement unless aliases are disabled
+    let export_aliases = cue_result
+        .config
+        .as_ref()
+        .and_then(|config| config.export_deprecated_aliases)
+        .unwrap_or(true);
+    apply_renames(&mut final_vars, &cue_result.metadata, export_aliases);
+
+    (final_vars, origins)
 }
 
 /// Extracts hooks from the configuration
@@ -152,17 +301,17 @@ fn process_tasks_with_structure(
     let mut task_nodes = IndexMap::new();
 
     for (name, value) in raw_tasks {
-        // Try to deserialize as TaskNode
-        if let Ok(node) = serde_json::from_value::<TaskNode>(value.clone()) {
-            // Store the node structure
-            task_nodes.insert(name.clone(), node.clone());
-            // Also flatten for backwards compatibility
+        // Deserializing from a reference leaves the value uncopied for the
+        // fallback
+        if let Ok(node) = TaskNode::deserialize(&value) {
+            // Flatten for backwards compatibility, then keep the structure
             flatten_task_node(&name, &node, &mut flat_tasks, vec![]);
-        } else if let Ok(task) = serde_json::from_value::<TaskConfig>(value.clone()) {
+            task_nodes.insert(name, node);
+        } else if let Ok(task) = TaskConfig::deserialize(&value) {
             // Fallback to direct TaskConfig (for backwards compatibility)
             flat_tasks.insert(name.clone(), task.clone());
             // Also store as a simple Task node
-            task_nodes.insert(name.clone(), TaskNode::Task(Box::new(task)));
+            task_nodes.insert(name, TaskNode::Task(Box::new(task)));
         }
     }
 
@@ -222,9 +371,10 @@ mod tests {
             "AWS_KEY".to_string(),
             VariableMetadata {
                 capability: Some("aws".to_string()),
+                ..Default::default()
             },
         );
-        metadata.insert("DB_URL".to_string(), VariableMetadata { capability: None });
+        metadata.insert("DB_URL".to_string(), VariableMetadata::default());
 
         // Variable with no metadata should always be included
         assert!(should_include_variable("UNKNOWN", &metadata, &[]));
@@ -255,4 +405,22 @@ mod tests {
             &["gcp".to_string()]
         )); // Non-matching capability
     }
+
+    #[test]
+    fn test_validate_run_configs() {
+        let tasks = HashMap::from([("test".to_string(), TaskConfig::default())]);
+        let run_config = |tasks: &[&str]| RunConfig {
+            tasks: tasks.iter().map(|task| task.to_string()).collect(),
+            ..Default::default()
+        };
+        let validate = |run_config: RunConfig| {
+            let run_configs = HashMap::from([("ci".to_string(), run_config)]);
+            validate_run_configs(&run_configs, &tasks, &IndexMap::new())
+        };
+
+        assert!(validate(run_config(&["test", "web:build"])).is_ok());
+        let err = validate(run_config(&["test", "lint"])).unwrap_err();
+        assert!(err.to_string().contains("unknown task 'lint'"));
+        assert!(validate(run_config(&[])).is_err());
+    }
 }
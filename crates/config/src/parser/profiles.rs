@@ -0,0 +1,169 @@
+//! Profiles: shared organization defaults imported from CUE modules
+//!
+//! ```cue
+//! import rust "corp.example/cuenv/profiles:rust"
+//!
+//! profiles: rust: rust
+//! ```
+//!
+//! A profile may define `env` (including per-environment overrides),
+//! `tasks` and `capabilities`. Profiles apply in declaration order, each
+//! replacing whatever earlier profiles defined under the same name, and the
+//! package's own definitions replace those of every profile. A variable
+//! replaced this way also drops the per-environment values of the layers
+//! below, so the winning definition is the only one that counts.
+
+use super::types::{Origin, Provenance, RawCueResult, RawEnv, RawProfile};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+/// Merge `raw.profiles` into the package's own settings
+pub(crate) fn apply_profiles(raw: &mut RawCueResult) -> Provenance {
+    let profiles = std::mem::take(&mut raw.profiles);
+    if profiles.is_empty() {
+        return Provenance::default();
+    }
+
+    let mut provenance = Provenance {
+        profiles: profiles.keys().cloned().collect(),
+        ..Default::default()
+    };
+    let package = RawProfile {
+        env: std::mem::take(&mut raw.env),
+        tasks: std::mem::take(&mut raw.tasks),
+        capabilities: std::mem::take(&mut raw.capabilities),
+    };
+
+    let mut env = RawEnv::default();
+    let mut tasks = IndexMap::new();
+    let mut capabilities = HashMap::new();
+    let layers = profiles
+        .into_iter()
+        .map(|(name, profile)| (Some(name), profile))
+        .chain(std::iter::once((None, package)));
+
+    for (profile, layer) in layers {
+        let profile = profile.as_deref();
+
+        for (key, value) in layer.env.variables {
+            record(&mut provenance.variables, &key, profile);
+            for overrides in env.environment.values_mut() {
+                overrides.remove(&key);
+            }
+            env.variables.insert(key, value);
+        }
+        for (name, overrides) in layer.env.environment {
+            env.environment.entry(name).or_default().extend(overrides);
+        }
+
+        for (key, value) in layer.tasks {
+            record(&mut provenance.tasks, &key, profile);
+            tasks.insert(key, value);
+        }
+
+        for (key, value) in layer.env.capabilities.into_iter().chain(layer.capabilities) {
+            record(&mut provenance.capabilities, &key, profile);
+            capabilities.insert(key, value);
+        }
+    }
+
+    raw.env = env;
+    raw.tasks = tasks;
+    raw.capabilities = capabilities;
+    provenance
+}
+
+/// Note that `profile` (or the package, for `None`) now defines `key`
+///
+/// `origins` holds an entry exactly when a profile defined the key before,
+/// so names only the package defines are never recorded.
+fn record(origins: &mut HashMap<String, Origin>, key: &str, profile: Option<&str>) {
+    let overrides = origins
+        .remove(key)
+        .map(|previous| {
+            let mut overrides = previous.overrides;
+            overrides.extend(previous.profile);
+            overrides
+        })
+        .unwrap_or_default();
+
+    if profile.is_some() || !overrides.is_empty() {
+        origins.insert(
+            key.to_string(),
+            Origin {
+                profile: profile.map(str::to_string),
+                overrides,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw(value: serde_json::Value) -> RawCueResult {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_package_overrides_profiles_in_order() {
+        let mut raw = raw(json!({
+            "profiles": {
+                "base": {
+                    "env": {
+                        "LOG": "info",
+                        "REGION": "eu",
+                        "environment": {"production": {"LOG": "warn", "REGION": "us"}}
+                    },
+                    "tasks": {"lint": {"command": "base-lint"}}
+                },
+                "rust": {
+                    "env": {"REGION": "ap"},
+                    "tasks": {"lint": {"command": "cargo clippy"}, "test": {"command": "cargo test"}},
+                    "capabilities": {"cargo": {"commands": ["cargo"]}}
+                }
+            },
+            "env": {"LOG": "debug", "APP": "demo"},
+            "tasks": {"test": {"command": "cargo nextest run"}}
+        }));
+
+        let provenance = apply_profiles(&mut raw);
+
+        assert_eq!(provenance.profiles, ["base", "rust"]);
+        assert_eq!(raw.env.variables["LOG"], "debug");
+        assert_eq!(raw.env.variables["REGION"], "ap");
+        // Both LOG and REGION were redefined above `base`, so its overrides go
+        assert!(raw.env.environment["production"].is_empty());
+        assert_eq!(raw.tasks["lint"]["command"], "cargo clippy");
+        assert_eq!(raw.tasks["test"]["command"], "cargo nextest run");
+        assert!(raw.capabilities.contains_key("cargo"));
+
+        assert_eq!(
+            provenance.variables["LOG"].describe(),
+            "env.cue (overrides base)"
+        );
+        assert_eq!(
+            provenance.variables["REGION"].describe(),
+            "profile rust (overrides base)"
+        );
+        assert!(!provenance.variables.contains_key("APP"));
+        assert_eq!(
+            provenance.tasks["test"].describe(),
+            "env.cue (overrides rust)"
+        );
+        assert_eq!(provenance.capabilities["cargo"].describe(), "profile rust");
+    }
+
+    #[test]
+    fn test_no_profiles_leaves_package_untouched() {
+        let mut raw = raw(json!({
+            "env": {"APP": "demo", "environment": {"dev": {"APP": "dev"}}}
+        }));
+
+        assert_eq!(apply_profiles(&mut raw), Provenance::default());
+        assert_eq!(raw.env.environment["dev"]["APP"], "dev");
+    }
+}
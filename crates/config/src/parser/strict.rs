@@ -0,0 +1,197 @@
+//! Strict schema mode: fields of env.cue that cuenv's schema doesn't know
+//!
+//! CUE accepts any field in a package, so a typo such as `dependecies`
+//! evaluates fine and is then ignored. Checking the package against the
+//! closed `#Cuenv` definition of the schema this cuenv was built with finds
+//! such fields, with where they are declared. The check needs the CUE
+//! bridge; `cuenv config lint` runs it, and so does loading a package whose
+//! `config` sets `strictSchema: true`.
+
+use cuenv_core::suggestions::{similar_names, with_suggestions};
+use cuenv_core::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Files of the schema, in the `schema` package
+const SCHEMA_FILES: &[&str] = &[
+    include_str!("../../../../schema/capabilities.cue"),
+    include_str!("../../../../schema/config.cue"),
+    include_str!("../../../../schema/cuenv.cue"),
+    include_str!("../../../../schema/env.cue"),
+    include_str!("../../../../schema/gcp.cue"),
+    include_str!("../../../../schema/hooks.cue"),
+    include_str!("../../../../schema/imports.cue"),
+    include_str!("../../../../schema/nix.cue"),
+    include_str!("../../../../schema/onepassword.cue"),
+    include_str!("../../../../schema/policy.cue"),
+    include_str!("../../../../schema/secrets.cue"),
+    include_str!("../../../../schema/security.cue"),
+    include_str!("../../../../schema/tasks.cue"),
+];
+
+/// The schema as the source of a single file, for the bridge to compile
+pub(crate) fn schema_source() -> String {
+    let mut source = String::from("package schema\n");
+    for file in SCHEMA_FILES {
+        for line in file.lines().filter(|line| !line.starts_with("package ")) {
+            source.push_str(line);
+            source.push('\n');
+        }
+    }
+    source
+}
+
+/// Every field name the schema declares, for suggestions
+fn schema_fields() -> BTreeSet<&'static str> {
+    SCHEMA_FILES
+        .iter()
+        .flat_map(|file| file.lines())
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let end = line.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+            let rest = line[end..].trim_start_matches(['?', '!']);
+            (end > 0 && rest.starts_with(':') && !line.starts_with("package")).then(|| &line[..end])
+        })
+        .collect()
+}
+
+/// A field of the package the schema does not allow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaIssue {
+    /// Path of the field, e.g. `tasks.build.dependecies`
+    pub path: String,
+    /// Where the field is declared, relative to the package directory
+    pub locations: Vec<SourceLocation>,
+    /// Fields of the schema the name is probably a typo of
+    pub suggestions: Vec<String>,
+}
+
+/// A position in a CUE file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.locations.first() {
+            write!(
+                f,
+                "{}:{}:{}: ",
+                location.file.display(),
+                location.line,
+                location.column
+            )?;
+        }
+        let message = format!("'{}' is not a field of the cuenv schema", self.path);
+        write!(f, "{}", with_suggestions(message, &self.suggestions))
+    }
+}
+
+/// The issues of the bridge's response for the package in `dir`
+pub(crate) fn parse_issues(dir: &Path, json: &str) -> Result<Vec<SchemaIssue>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Response {
+        Issues { issues: Vec<RawIssue> },
+        Failed { error: String },
+    }
+    #[derive(Deserialize)]
+    struct RawIssue {
+        path: String,
+        locations: Vec<SourceLocation>,
+    }
+
+    let issues = match serde_json::from_str(json) {
+        Ok(Response::Issues { issues }) => issues,
+        Ok(Response::Failed { error }) => return Err(Error::cue_parse(dir, error)),
+        Err(e) => {
+            return Err(Error::Json {
+                message: "failed to parse lint result from CUE parser".to_string(),
+                source: e,
+            })
+        }
+    };
+
+    let fields = schema_fields();
+    let mut seen = BTreeSet::new();
+    let mut result = Vec::new();
+    for issue in issues {
+        // A field can conflict with several definitions of its parent
+        if !seen.insert(issue.path.clone()) {
+            continue;
+        }
+        let name = issue.path.rsplit('.').next().unwrap_or(&issue.path);
+        result.push(SchemaIssue {
+            suggestions: similar_names(name.trim_matches('"'), &fields),
+            locations: issue
+                .locations
+                .into_iter()
+                .map(|location| SourceLocation {
+                    file: location
+                        .file
+                        .strip_prefix(dir)
+                        .map(Path::to_path_buf)
+                        .unwrap_or(location.file),
+                    ..location
+                })
+                .collect(),
+            path: issue.path,
+        });
+    }
+    Ok(result)
+}
+
+/// An error listing `issues`, if there are any
+pub fn strict_schema_error(issues: &[SchemaIssue]) -> Option<Error> {
+    if issues.is_empty() {
+        return None;
+    }
+    let listed: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    Some(Error::configuration(format!(
+        "env.cue has fields the cuenv schema does not know (strict schema mode):\n  {}",
+        listed.join("\n  ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_source_is_one_package() {
+        let source = schema_source();
+        assert_eq!(
+            source
+                .lines()
+                .filter(|line| line.starts_with("package "))
+                .count(),
+            1
+        );
+        assert!(source.contains("#Cuenv:"));
+    }
+
+    #[test]
+    fn test_parse_issues() {
+        let json = r#"{"issues": [
+            {"path": "tasks.build.dependecies", "message": "field not allowed",
+             "locations": [{"file": "/project/env.cue", "line": 7, "column": 3}]},
+            {"path": "tasks.build.dependecies", "message": "field not allowed", "locations": []}
+        ]}"#;
+        let issues = parse_issues(Path::new("/project"), json).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "env.cue:7:3: 'tasks.build.dependecies' is not a field of the cuenv schema. \
+             Did you mean one of: 'dependencies', 'dependsOn', 'deprecated'?"
+        );
+        assert!(strict_schema_error(&issues).is_some());
+        assert!(strict_schema_error(&[]).is_none());
+
+        assert!(parse_issues(Path::new("/project"), r#"{"error": "bad"}"#).is_err());
+    }
+}
@@ -2,9 +2,10 @@
 
 use super::*;
 use cuenv_core::constants::{CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME};
+use cuenv_utils::sync::ScopedEnv;
 use serial_test::serial;
-use std::env;
 use std::fs;
+use std::path::Path;
 use tempfile::TempDir;
 
 fn create_test_env(content: &str) -> TempDir {
@@ -26,9 +27,9 @@ fn create_test_env(content: &str) -> TempDir {
 #[test]
 #[serial]
 fn test_only_configured_package_allowed() {
-    // Set test package name
-    let original = env::var(CUENV_PACKAGE_VAR).ok();
-    env::set_var(CUENV_PACKAGE_VAR, "testpkg");
+    // Set test package name, restored when the scope ends
+    let mut scope = ScopedEnv::new();
+    scope.set(CUENV_PACKAGE_VAR, "testpkg").unwrap();
 
     // Test that non-configured packages are rejected
     let content = r#"
@@ -56,13 +57,6 @@ fn test_only_configured_package_allowed() {
     let temp_dir = create_test_env(content);
     let result = CueParser::eval_package(temp_dir.path(), "testpkg");
     assert!(result.is_ok());
-
-    // Restore original value
-    if let Some(val) = original {
-        env::set_var(CUENV_PACKAGE_VAR, val);
-    } else {
-        env::remove_var(CUENV_PACKAGE_VAR);
-    }
 }
 
 #[test]
@@ -219,6 +213,73 @@ fn test_parse_with_environments() {
     assert_eq!(result.variables.get("PORT").unwrap(), "3000"); // Not overridden
 }
 
+#[test]
+#[serial]
+fn test_parse_with_inherited_environments() {
+    let content = r#"
+    package cuenv
+
+    env: {
+        LOG_LEVEL: "info"
+        REGION:    "eu"
+
+        environment: {
+            dev: {
+                DATABASE_URL: "postgres://dev.internal/app"
+                LOG_LEVEL:    "debug"
+            }
+            staging: {
+                inherits:     "dev"
+                DATABASE_URL: "postgres://staging.internal/app"
+            }
+        }
+    }
+    "#;
+    let temp_dir = create_test_env(content);
+
+    let options = ParseOptions {
+        environment: Some("staging".to_string()),
+        capabilities: Vec::new(),
+    };
+    let result =
+        CueParser::eval_package_with_options(temp_dir.path(), DEFAULT_PACKAGE_NAME, &options)
+            .unwrap();
+    assert_eq!(
+        result.variables.get("DATABASE_URL").unwrap(),
+        "postgres://staging.internal/app"
+    );
+    assert_eq!(result.variables.get("LOG_LEVEL").unwrap(), "debug");
+    assert_eq!(result.variables.get("REGION").unwrap(), "eu");
+    assert!(!result.variables.contains_key("inherits"));
+    assert_eq!(result.provenance.environments, ["dev", "staging"]);
+    assert_eq!(
+        result.provenance.variables["LOG_LEVEL"].describe(),
+        "env.cue, environment dev"
+    );
+}
+
+#[test]
+#[serial]
+fn test_inheriting_unknown_environment_fails() {
+    let content = r#"
+    package cuenv
+
+    env: environment: {
+        dev: LOG_LEVEL: "debug"
+        staging: inherits: "deev"
+    }
+    "#;
+    let temp_dir = create_test_env(content);
+
+    let err = CueParser::eval_package_with_options(
+        temp_dir.path(),
+        DEFAULT_PACKAGE_NAME,
+        &ParseOptions::default(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown environment 'deev'"));
+}
+
 #[test]
 #[serial]
 fn test_parse_with_capabilities() {
@@ -361,6 +422,47 @@ fn test_parse_with_env_and_capabilities() {
     assert_eq!(result.variables.get("API_KEY").unwrap(), "secret123")
 }
 
+#[test]
+#[serial]
+fn test_parse_variable_groups() {
+    let content = r#"
+    package cuenv
+
+    env: {
+        PORT: "8080"
+        aws: {
+            capability: "aws"
+            REGION:     "us-east-1"
+            s3: BUCKET: "assets"
+        }
+
+        environment: production: aws: REGION: "eu-west-1"
+    }
+    "#;
+    let temp_dir = create_test_env(content);
+
+    let options = ParseOptions {
+        environment: Some("production".to_string()),
+        capabilities: vec!["aws".to_string()],
+    };
+    let result =
+        CueParser::eval_package_with_options(temp_dir.path(), DEFAULT_PACKAGE_NAME, &options)
+            .unwrap();
+    assert_eq!(result.variables.get("AWS_REGION").unwrap(), "eu-west-1");
+    assert_eq!(result.variables.get("AWS_S3_BUCKET").unwrap(), "assets");
+
+    // Members of a group with a capability need that capability
+    let options = ParseOptions {
+        environment: None,
+        capabilities: vec!["gcp".to_string()],
+    };
+    let result =
+        CueParser::eval_package_with_options(temp_dir.path(), DEFAULT_PACKAGE_NAME, &options)
+            .unwrap();
+    assert_eq!(result.variables.len(), 1);
+    assert_eq!(result.variables.get("PORT").unwrap(), "8080");
+}
+
 #[test]
 #[serial]
 fn test_empty_cue_file() {
@@ -904,3 +1006,62 @@ fn test_parse_nested_tasks() {
         Some("Apply code formatting changes")
     );
 }
+
+#[test]
+fn test_parse_json_builds_results_for_each_environment() {
+    let json = r#"{
+        "env": {
+            "DATABASE_URL": "postgres://localhost/dev",
+            "PORT": 8080,
+            "environment": {"production": {"DATABASE_URL": "postgres://db/prod"}}
+        },
+        "tasks": {"build": {"command": "cargo build"}},
+        "hooks": {"onEnter": [{"command": "echo", "args": ["hi"]}]}
+    }"#;
+    let package = CueParser::parse_json(Path::new("."), json).unwrap();
+
+    let base = package.parse_result(&ParseOptions::default()).unwrap();
+    assert_eq!(base.variables["DATABASE_URL"], "postgres://localhost/dev");
+    assert_eq!(base.variables["PORT"], "8080");
+    assert_eq!(base.environments, ["production"]);
+    assert!(base.tasks.contains_key("build") && base.task_nodes.contains_key("build"));
+    assert_eq!(base.hooks["onEnter"].len(), 1);
+
+    let production = package
+        .into_parse_result(&ParseOptions {
+            environment: Some("production".to_string()),
+            capabilities: Vec::new(),
+        })
+        .unwrap();
+    assert_eq!(production.variables["DATABASE_URL"], "postgres://db/prod");
+}
+
+#[test]
+fn test_parse_json_reports_bridge_errors() {
+    let err = CueParser::parse_json(Path::new("."), r#"{"error": "cannot find package"}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("cannot find package"),
+        "unexpected error: {err}"
+    );
+    assert!(CueParser::parse_json(Path::new("."), "not json").is_err());
+}
+
+#[test]
+fn test_parse_json_reserved_keys_are_not_groups() {
+    let json = r#"{
+        "env": {
+            "PORT": 8080,
+            "hooks": {"onEnter": {"command": "echo"}},
+            "tasks": {"build": {"command": "cargo build"}},
+            "aws": {"REGION": "us-east-1"}
+        }
+    }"#;
+    let package = CueParser::parse_json(Path::new("."), json).unwrap();
+
+    let result = package.parse_result(&ParseOptions::default()).unwrap();
+    let mut names: Vec<_> = result.variables.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["AWS_REGION", "PORT"]);
+}
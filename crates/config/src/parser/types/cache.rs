@@ -57,4 +57,7 @@ pub struct CacheEnvConfig {
     pub exclude: Option<Vec<String>>,
     /// Whether to use smart defaults for common build tools
     pub use_smart_defaults: Option<bool>,
+    /// Key on every variable of the loaded environment, so switching
+    /// environments always invalidates the task
+    pub env_all: Option<bool>,
 }
@@ -1,8 +1,12 @@
 //! Command configuration types
 
+use super::SecurityConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandConfig {
     pub capabilities: Option<Vec<String>>,
+    /// Access granted by the command's capabilities, merged across all of them
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
 }
@@ -1,4 +1,11 @@
+use super::SecurityConfig;
+use cuenv_core::events::EventTemplates;
+use cuenv_utils::xdg::DirOverrides;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct ConfigSettings {
@@ -22,17 +29,167 @@ pub struct ConfigSettings {
 
     #[serde(rename = "defaultCapabilities")]
     pub default_capabilities: Option<Vec<String>>,
+
+    /// Keep exporting renamed variables under their old name (default: true)
+    #[serde(rename = "exportDeprecatedAliases")]
+    pub export_deprecated_aliases: Option<bool>,
+
+    /// Fail task builds on `${VAR}` references to undefined variables
+    #[serde(
+        rename = "strictVariables",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub strict_variables: Option<bool>,
+
+    /// What to do when the environment drifted from `cuenv.lock`: "warn"
+    /// (default) or "fail"
+    #[serde(rename = "lockDrift", default, skip_serializing_if = "Option::is_none")]
+    pub lock_drift: Option<String>,
+
+    /// Most onEnter hooks running at once (default: one per CPU)
+    #[serde(
+        rename = "hookParallelism",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hook_parallelism: Option<usize>,
+
+    /// Regular expressions of secrets to mask in all output, on top of the
+    /// values of resolved secrets
+    #[serde(
+        rename = "redactPatterns",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub redact_patterns: Option<Vec<String>>,
+
+    /// Format strings of the `template` output format, by event type
+    #[serde(
+        rename = "outputTemplates",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_templates: Option<BTreeMap<String, String>>,
+
+    /// Fields of the lines of the `json` output format and the paths of the
+    /// event they are taken from, such as `".task_name"`
+    #[serde(
+        rename = "outputJson",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_json: Option<BTreeMap<String, String>>,
+
+    /// Security defaults applied to sandboxed `cuenv exec` commands
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
+
+    /// Where to keep cuenv's cache, relative to the project
+    #[serde(rename = "cacheDir", default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<String>,
+
+    /// Where to keep cuenv's per-directory state, relative to the project
+    #[serde(rename = "stateDir", default, skip_serializing_if = "Option::is_none")]
+    pub state_dir: Option<String>,
+
+    /// CUE versions the project may be evaluated with
+    #[serde(
+        rename = "cueVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cue_version: Option<CueVersionRange>,
+
+    /// Fail loading env.cue when it has fields the cuenv schema doesn't know
+    #[serde(
+        rename = "strictSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub strict_schema: Option<bool>,
+}
+
+/// Oldest and newest CUE versions a project supports, both inclusive
+///
+/// Versions may leave out their minor and patch number, so a `max` of
+/// `v0.11` allows every v0.11 release.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub struct CueVersionRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+}
+
+impl CueVersionRange {
+    /// The range as a semver requirement
+    pub fn requirement(&self) -> Result<VersionReq, String> {
+        let bound = |op: &str, version: &Option<String>| {
+            version
+                .as_deref()
+                .map(|version| format!("{op}{}", version.strip_prefix('v').unwrap_or(version)))
+        };
+        let comparators: Vec<String> = [bound(">=", &self.min), bound("<=", &self.max)]
+            .into_iter()
+            .flatten()
+            .collect();
+        if comparators.is_empty() {
+            return Ok(VersionReq::STAR);
+        }
+        VersionReq::parse(&comparators.join(", "))
+            .map_err(|e| format!("Invalid cueVersion {self}: {e}"))
+    }
+
+    /// Whether `version`, as the bridge reports it, is in the range
+    ///
+    /// Pre-releases count as the release they precede.
+    pub fn allows(&self, version: &str) -> Result<bool, String> {
+        let requirement = self.requirement()?;
+        let version = Version::parse(version.strip_prefix('v').unwrap_or(version))
+            .map_err(|e| format!("Invalid CUE version '{version}': {e}"))?;
+        Ok(requirement.matches(&Version::new(version.major, version.minor, version.patch)))
+    }
+}
+
+impl fmt::Display for CueVersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => write!(f, "{min} to {max}"),
+            (Some(min), None) => write!(f, "{min} or newer"),
+            (None, Some(max)) => write!(f, "{max} or older"),
+            (None, None) => write!(f, "any version"),
+        }
+    }
 }
 
 impl ConfigSettings {
+    /// The cache and state directories this project relocates to, with
+    /// relative paths resolved against `project_dir`
+    pub fn dir_overrides(&self, project_dir: &Path) -> DirOverrides {
+        let resolve = |dir: &Option<String>| dir.as_deref().map(|dir| project_dir.join(dir));
+        DirOverrides {
+            cache_dir: resolve(&self.cache_dir),
+            state_dir: resolve(&self.state_dir),
+        }
+    }
+
+    /// The `outputTemplates` and `outputJson` of this project
+    pub fn event_templates(&self) -> cuenv_core::Result<EventTemplates> {
+        EventTemplates::new(
+            self.output_templates.as_ref().unwrap_or(&BTreeMap::new()),
+            self.output_json.as_ref(),
+        )
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         // Validate output format
         if let Some(ref format) = self.output_format {
             match format.as_str() {
-                "tui" | "spinner" | "simple" | "tree" => {}
+                "tui" | "spinner" | "simple" | "tree" | "tmux" | "template" | "json" => {}
                 _ => {
                     return Err(format!(
-                    "Invalid output format: '{format}'. Must be one of: tui, spinner, simple, tree"
+                    "Invalid output format: '{format}'. Must be one of: tui, spinner, simple, tree, tmux, template, json"
                 ))
                 }
             }
@@ -50,6 +207,59 @@ impl ConfigSettings {
             }
         }
 
+        if let Some(ref drift) = self.lock_drift {
+            if !matches!(drift.as_str(), "warn" | "fail") {
+                return Err(format!(
+                    "Invalid lockDrift: '{drift}'. Must be one of: warn, fail"
+                ));
+            }
+        }
+
+        if let Some(ref range) = self.cue_version {
+            range.requirement()?;
+        }
+
+        if self.hook_parallelism == Some(0) {
+            return Err("Invalid hookParallelism: must be at least 1".to_string());
+        }
+
+        self.event_templates().map_err(|e| match e {
+            cuenv_core::Error::Configuration { message } => message,
+            e => e.to_string(),
+        })?;
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(min: Option<&str>, max: Option<&str>) -> CueVersionRange {
+        CueVersionRange {
+            min: min.map(str::to_string),
+            max: max.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_cue_version_range() {
+        let range = range(Some("v0.9.0"), Some("v0.11"));
+        assert!(range.allows("v0.9.0").unwrap());
+        assert!(range.allows("v0.11.3").unwrap());
+        assert!(range.allows("v0.11.0-alpha.1").unwrap());
+        assert!(!range.allows("v0.8.2").unwrap());
+        assert!(!range.allows("v0.12.0").unwrap());
+        assert!(range.allows("(devel)").is_err());
+    }
+
+    #[test]
+    fn test_invalid_cue_version_range() {
+        let settings = ConfigSettings {
+            cue_version: Some(range(Some("latest"), None)),
+            ..Default::default()
+        };
+        assert!(settings.validate().unwrap_err().contains("cueVersion"));
+    }
+}
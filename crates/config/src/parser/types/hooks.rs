@@ -30,9 +30,17 @@ pub enum HookConstraint {
     },
 }
 
-/// Hook is now just an ExecHook - a simple command execution primitive
+/// A hook runs a command (an ExecHook), fetches an archive when `fetch` is
+/// set, or manages Docker Compose services when `compose` is set
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hook {
+    /// Name other hooks refer to in `dependsOn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Hooks that must finish before this one starts
+    #[serde(rename = "dependsOn", default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+    #[serde(default)]
     pub command: String,
     #[serde(default)]
     pub args: Option<Vec<String>>,
@@ -44,6 +52,48 @@ pub struct Hook {
     pub source: Option<bool>,
     #[serde(default)]
     pub preload: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch: Option<FetchHook>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compose: Option<ComposeHook>,
+}
+
+/// Download of a tool archive, verified against its checksum and extracted
+/// into the project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchHook {
+    pub url: String,
+    /// Expected SHA-256 of the download, hex encoded
+    pub sha256: String,
+    /// Directory the archive is extracted into, relative to the project
+    pub extract_to: String,
+    /// Proxy for this download, instead of the HTTP(S)_PROXY variables
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Docker Compose services started when the directory's environment loads
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComposeHook {
+    /// Compose file relative to the project; the runtime's default lookup
+    /// when unset
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Services to manage, all of the file's services when empty
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// Start the services when the environment loads (default: true)
+    #[serde(default = "default_true")]
+    pub up_on_enter: bool,
+    /// Stop and remove the services when the environment unloads
+    #[serde(default)]
+    pub down_on_exit: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Legacy hook config for backward compatibility
@@ -0,0 +1,58 @@
+//! Bulk imports of variables from configuration stores
+
+use serde::{Deserialize, Serialize};
+
+/// Variables pulled in bulk from a parameter or configuration store when
+/// the environment loads, declared under `env.valueFrom`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EnvImport {
+    /// Every parameter under this AWS SSM Parameter Store path, e.g.
+    /// `/myapp/dev/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aws_ssm_path: Option<String>,
+    /// AWS region of the parameters, the AWS CLI's default otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Every key of an Azure App Configuration store under a prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure_app_config: Option<AzureAppConfigImport>,
+    /// Seconds the imported values are cached for; 0 fetches them on every
+    /// load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AzureAppConfigImport {
+    /// Name of the App Configuration store
+    pub store: String,
+    /// Only keys starting with this, which is removed from their names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Only keys with this label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl EnvImport {
+    /// Check that the import names exactly one store
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.aws_ssm_path, &self.azure_app_config) {
+            (Some(path), None) if !path.starts_with('/') => {
+                Err(format!("valueFrom awsSsmPath '{path}' must start with '/'"))
+            }
+            (Some(_), None) => Ok(()),
+            (None, Some(azure)) if self.region.is_some() => Err(format!(
+                "valueFrom for Azure App Configuration store '{}' cannot set a region",
+                azure.store
+            )),
+            (None, Some(azure)) if azure.store.is_empty() => {
+                Err("valueFrom azureAppConfig needs a store".to_string())
+            }
+            (None, Some(_)) => Ok(()),
+            _ => Err("valueFrom needs exactly one of awsSsmPath or azureAppConfig".to_string()),
+        }
+    }
+}
@@ -7,23 +7,53 @@ mod cache;
 mod commands;
 mod config;
 mod hooks;
+mod imports;
+mod outputs;
+mod problem_matcher;
+mod provenance;
+mod publish;
 mod raw;
 mod result;
+mod run_config;
+mod schedule;
 mod security;
 mod tasks;
 
 pub use cache::{CacheEnvConfig, TaskCacheConfig};
 pub use commands::CommandConfig;
-pub use config::ConfigSettings;
-pub use hooks::{Hook, HookConfig, HookConstraint, HookType, HookValue};
-pub(crate) use raw::RawCueResult;
+pub use config::{ConfigSettings, CueVersionRange};
+pub use hooks::{ComposeHook, FetchHook, Hook, HookConfig, HookConstraint, HookType, HookValue};
+pub use imports::{AzureAppConfigImport, EnvImport};
+pub use outputs::{OutputValueConfig, TaskOutputsConfig};
+pub use problem_matcher::ProblemMatcherConfig;
+pub use provenance::{Origin, Provenance};
+pub use publish::{HttpPublishConfig, OciPublishConfig, PublishConfig, PublishTargetConfig};
+pub(crate) use raw::{RawCueResult, RawEnv, RawProfile};
 pub(crate) use result::{CueParseResult, HooksConfig};
-pub use security::SecurityConfig;
-pub use tasks::{TaskCollection, TaskConfig, TaskNode};
+pub use run_config::RunConfig;
+pub use schedule::{CatchUpPolicy, ScheduleConfig};
+pub use security::{RunAsConfig, SecurityConfig};
+pub use tasks::{ConfirmConfig, TaskCollection, TaskConfig, TaskNode, WaitForConfig};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VariableMetadata {
     pub capability: Option<String>,
+    /// Deprecation note, e.g. `"use DATABASE_URL"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Name of the variable that replaces this one
+    #[serde(default, rename = "renamedTo", skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<String>,
+    /// Secret kept out of the shell and resolved only when a task or
+    /// command needs it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lazy: bool,
+    /// Team or person responsible for the variable, e.g. from `@owner("team-x")`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Free-form tags, e.g. from `@tag("slow")`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
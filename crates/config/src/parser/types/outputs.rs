@@ -0,0 +1,51 @@
+//! Task output configuration types
+
+use cuenv_core::TaskOutputType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `outputs` of a task: the files it writes, e.g. `["dist/"]`, or named
+/// values read from files it writes, e.g.
+/// `{ version: string @fromFile("VERSION") }`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TaskOutputsConfig {
+    Files(Vec<String>),
+    Values(BTreeMap<String, OutputValueConfig>),
+}
+
+/// A named output value; `name: string @fromFile("path")` in CUE
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OutputValueConfig {
+    /// File the value is read from, relative to the task's working directory
+    pub from_file: String,
+    #[serde(rename = "type", default)]
+    pub value_type: TaskOutputType,
+}
+
+impl TaskOutputsConfig {
+    /// Files the task writes, including those its values are read from
+    pub fn files(&self) -> Vec<String> {
+        match self {
+            Self::Files(files) => files.clone(),
+            Self::Values(values) => values.values().map(|v| v.from_file.clone()).collect(),
+        }
+    }
+
+    /// Named values the task outputs
+    pub fn values(&self) -> impl Iterator<Item = (&String, &OutputValueConfig)> {
+        match self {
+            Self::Files(_) => None,
+            Self::Values(values) => Some(values.iter()),
+        }
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl From<Vec<String>> for TaskOutputsConfig {
+    fn from(files: Vec<String>) -> Self {
+        Self::Files(files)
+    }
+}
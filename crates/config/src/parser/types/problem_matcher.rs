@@ -0,0 +1,42 @@
+//! Problem matcher configuration types
+
+use cuenv_core::Severity;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// One `problemMatcher` entry of a task: a built-in matcher by name, e.g.
+/// `"rustc"`, or a regex with named groups
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProblemMatcherConfig {
+    Builtin(String),
+    Pattern {
+        /// Regex with a `message` group and optionally `file`, `line`,
+        /// `column`, `severity` and `code`
+        pattern: String,
+        /// Severity of matches without a `severity` group, `error` unless set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        severity: Option<Severity>,
+    },
+}
+
+/// `problemMatcher` takes one matcher or a list
+pub(super) fn deserialize_problem_matchers<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<ProblemMatcherConfig>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(ProblemMatcherConfig),
+        Many(Vec<ProblemMatcherConfig>),
+    }
+
+    Ok(
+        Option::<OneOrMany>::deserialize(deserializer)?.map(|matchers| match matchers {
+            OneOrMany::One(matcher) => vec![matcher],
+            OneOrMany::Many(matchers) => matchers,
+        }),
+    )
+}
@@ -0,0 +1,55 @@
+//! Where configuration contributed by profiles and environments came from
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Source of a variable, task or capability
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Origin {
+    /// Profile that defined it, or `None` for the package itself
+    pub profile: Option<String>,
+    /// Environment of the inheritance chain whose value was used, or `None`
+    /// for the base variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Profiles whose definitions it replaced, in the order they were applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<String>,
+}
+
+impl Origin {
+    /// Human-readable source, e.g. `profile rust (overrides base)` or
+    /// `env.cue, environment dev`
+    pub fn describe(&self) -> String {
+        let mut source = match &self.profile {
+            Some(profile) => format!("profile {profile}"),
+            None => "env.cue".to_string(),
+        };
+        if let Some(environment) = &self.environment {
+            source = format!("{source}, environment {environment}");
+        }
+        if self.overrides.is_empty() {
+            source
+        } else {
+            format!("{source} (overrides {})", self.overrides.join(", "))
+        }
+    }
+}
+
+/// Provenance of everything a profile defined, the package overrode or the
+/// selected environment set
+///
+/// Names absent from these maps were defined by the package's base
+/// variables alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Applied profiles, lowest precedence first
+    pub profiles: Vec<String>,
+    /// Selected environment and those it inherits from, lowest precedence
+    /// first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environments: Vec<String>,
+    pub variables: HashMap<String, Origin>,
+    pub tasks: HashMap<String, Origin>,
+    pub capabilities: HashMap<String, Origin>,
+}
@@ -0,0 +1,45 @@
+//! Artifact publishing configuration types
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One `publish` entry of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishConfig {
+    /// Files or globs to publish; defaults to the task's outputs
+    pub files: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub target: PublishTargetConfig,
+}
+
+/// Destination of a `publish` entry, keyed by its kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishTargetConfig {
+    /// Directory to copy into, relative to the task's working directory
+    Dir(String),
+    Oci(OciPublishConfig),
+    Http(HttpPublishConfig),
+}
+
+/// OCI artifact pushed with `oras`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OciPublishConfig {
+    #[serde(rename = "ref")]
+    pub reference: String,
+    #[serde(rename = "mediaType")]
+    pub media_type: Option<String>,
+}
+
+/// Generic HTTP endpoint receiving one request per file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpPublishConfig {
+    pub url: String,
+    /// `PUT` (default) or `POST`
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Environment variable holding a bearer token
+    #[serde(rename = "tokenEnv")]
+    pub token_env: Option<String>,
+}
@@ -1,6 +1,6 @@
 //! Raw types for direct CUE JSON deserialization
 
-use super::ConfigSettings;
+use super::{ConfigSettings, RunConfig, SecurityConfig};
 use indexmap::IndexMap;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -18,11 +18,28 @@ pub(crate) struct RawCueResult {
     pub capabilities: HashMap<String, RawCapability>,
     #[serde(default)]
     pub config: Option<ConfigSettings>,
+    /// Shared defaults, applied in order before the package's own settings
+    #[serde(default)]
+    pub profiles: IndexMap<String, RawProfile>,
+    /// Named bundles of environment, capabilities and tasks
+    #[serde(default, rename = "runConfigs")]
+    pub run_configs: HashMap<String, RunConfig>,
     // Catch-all for other fields including sayHello at top level
     #[serde(flatten)]
     pub _other: HashMap<String, serde_json::Value>,
 }
 
+/// Variables, tasks and capabilities contributed by a profile
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RawProfile {
+    #[serde(default)]
+    pub env: RawEnv,
+    #[serde(default)]
+    pub tasks: IndexMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub capabilities: HashMap<String, RawCapability>,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub(crate) struct RawEnv {
     #[serde(default)]
@@ -37,6 +54,8 @@ pub(crate) struct RawEnv {
 pub(crate) struct RawCapability {
     #[serde(default)]
     pub commands: Vec<String>,
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,23 +1,37 @@
 //! Result types for CUE parsing
 
-use super::{CommandConfig, ConfigSettings, HookValue, VariableMetadata};
+use super::{
+    CommandConfig, ConfigSettings, EnvImport, HookValue, Provenance, RunConfig, VariableMetadata,
+};
 use indexmap::IndexMap;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub(crate) struct CueParseResult {
     pub variables: HashMap<String, serde_json::Value>,
     pub metadata: HashMap<String, VariableMetadata>,
     pub environments: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// Environment each environment inherits from, if any
+    #[serde(default)]
+    pub environment_parents: HashMap<String, String>,
+    /// Bulk imports of every environment, then those of each environment
+    #[serde(default)]
+    pub imports: Vec<EnvImport>,
+    #[serde(default)]
+    pub environment_imports: HashMap<String, Vec<EnvImport>>,
     pub commands: HashMap<String, CommandConfig>,
     #[serde(default)]
     pub tasks: IndexMap<String, serde_json::Value>,
     pub hooks: Option<HooksConfig>,
     pub config: Option<ConfigSettings>,
+    #[serde(default)]
+    pub run_configs: HashMap<String, RunConfig>,
+    #[serde(default)]
+    pub provenance: Provenance,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub(crate) struct HooksConfig {
     #[serde(rename = "onEnter")]
     pub on_enter: Option<HookValue>,
@@ -0,0 +1,18 @@
+//! Named run configurations
+
+use serde::{Deserialize, Serialize};
+
+/// A named bundle of environment, capabilities and tasks, run with
+/// `cuenv run-config <name>`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub description: Option<String>,
+    /// Environment to load, e.g. `production`
+    pub environment: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Tasks or groups to run, one after the other, stopping at the first
+    /// failure
+    #[serde(default)]
+    pub tasks: Vec<String>,
+}
@@ -0,0 +1,82 @@
+//! Task schedule configuration types
+
+use serde::{Deserialize, Serialize};
+
+/// `schedule` of a task run by `cuenv scheduler run`: a cron expression,
+/// e.g. `"0 9 * * 1-5"`, or one with its catch-up policy and jitter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ScheduleConfig {
+    Cron(String),
+    Detailed {
+        cron: String,
+        /// What to do about runs missed while the scheduler was not running
+        #[serde(rename = "catchUp", default)]
+        catch_up: CatchUpPolicy,
+        /// Longest delay added to each run, e.g. `"5m"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        jitter: Option<String>,
+    },
+}
+
+/// Runs to make for the times a task was due while the scheduler was not
+/// running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUpPolicy {
+    /// Run the task once, however many times it was missed
+    #[default]
+    Once,
+    /// Run the task for every time it was missed
+    All,
+    /// Wait for the next time the task is due
+    Skip,
+}
+
+impl ScheduleConfig {
+    /// The cron expression
+    pub fn cron(&self) -> &str {
+        match self {
+            Self::Cron(cron) | Self::Detailed { cron, .. } => cron,
+        }
+    }
+
+    pub fn catch_up(&self) -> CatchUpPolicy {
+        match self {
+            Self::Cron(_) => CatchUpPolicy::default(),
+            Self::Detailed { catch_up, .. } => *catch_up,
+        }
+    }
+
+    /// The jitter, as written
+    pub fn jitter(&self) -> Option<&str> {
+        match self {
+            Self::Cron(_) => None,
+            Self::Detailed { jitter, .. } => jitter.as_deref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_forms() {
+        let simple: ScheduleConfig =
+            serde_json::from_value(serde_json::json!("0 9 * * 1-5")).unwrap();
+        assert_eq!(simple.cron(), "0 9 * * 1-5");
+        assert_eq!(simple.catch_up(), CatchUpPolicy::Once);
+        assert_eq!(simple.jitter(), None);
+
+        let detailed: ScheduleConfig = serde_json::from_value(serde_json::json!({
+            "cron": "@daily",
+            "catchUp": "skip",
+            "jitter": "10m"
+        }))
+        .unwrap();
+        assert_eq!(detailed.cron(), "@daily");
+        assert_eq!(detailed.catch_up(), CatchUpPolicy::Skip);
+        assert_eq!(detailed.jitter(), Some("10m"));
+    }
+}
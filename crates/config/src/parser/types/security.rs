@@ -1,8 +1,9 @@
 //! Security configuration types
 
+use cuenv_core::EnforcementLevel;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct SecurityConfig {
     #[serde(rename = "restrictDisk")]
     pub restrict_disk: Option<bool>,
@@ -19,4 +20,146 @@ pub struct SecurityConfig {
     /// Automatically infer disk restrictions from task inputs/outputs
     #[serde(rename = "inferFromInputsOutputs")]
     pub infer_from_inputs_outputs: Option<bool>,
+    /// Seccomp profile: `"default"` or a path to a JSON profile (Linux only)
+    #[serde(rename = "seccompProfile")]
+    pub seccomp_profile: Option<String>,
+    /// Log seccomp violations instead of blocking them
+    #[serde(rename = "seccompAudit")]
+    pub seccomp_audit: Option<bool>,
+    /// Whether restrictions the platform can't apply fail the task
+    pub enforcement: Option<EnforcementLevel>,
+}
+
+impl SecurityConfig {
+    /// Layer `other` on top of this configuration
+    ///
+    /// A restriction enabled by either side stays enabled, and allow/deny
+    /// lists are combined without duplicates.
+    pub fn merge(&mut self, other: &SecurityConfig) {
+        fn merge_flag(base: &mut Option<bool>, other: Option<bool>) {
+            *base = match (*base, other) {
+                (Some(a), Some(b)) => Some(a || b),
+                (a, b) => a.or(b),
+            };
+        }
+
+        fn merge_list(base: &mut Option<Vec<String>>, other: &Option<Vec<String>>) {
+            let Some(other) = other else {
+                return;
+            };
+            let list = base.get_or_insert_with(Vec::new);
+            for item in other {
+                if !list.contains(item) {
+                    list.push(item.clone());
+                }
+            }
+        }
+
+        merge_flag(&mut self.restrict_disk, other.restrict_disk);
+        merge_flag(&mut self.restrict_network, other.restrict_network);
+        merge_flag(
+            &mut self.infer_from_inputs_outputs,
+            other.infer_from_inputs_outputs,
+        );
+        merge_list(&mut self.read_only_paths, &other.read_only_paths);
+        merge_list(&mut self.read_write_paths, &other.read_write_paths);
+        merge_list(&mut self.deny_paths, &other.deny_paths);
+        merge_list(&mut self.allowed_hosts, &other.allowed_hosts);
+
+        if other.seccomp_profile.is_some() {
+            self.seccomp_profile.clone_from(&other.seccomp_profile);
+        }
+        // Enforcement wins: audit-only mode needs both sides to ask for it
+        self.seccomp_audit = match (self.seccomp_audit, other.seccomp_audit) {
+            (Some(a), Some(b)) => Some(a && b),
+            (a, b) => a.or(b),
+        };
+        // As does the stricter enforcement level
+        self.enforcement = match (self.enforcement, other.enforcement) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Identity a task runs as after dropping privileges
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunAsConfig {
+    /// User name or numeric uid
+    pub user: String,
+    /// Group name or numeric gid; defaults to the user's primary group
+    pub group: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_config_merge() {
+        let mut base = SecurityConfig {
+            restrict_disk: Some(true),
+            restrict_network: Some(false),
+            read_only_paths: Some(vec!["/data".to_string()]),
+            ..Default::default()
+        };
+        base.merge(&SecurityConfig {
+            restrict_network: Some(true),
+            read_only_paths: Some(vec!["/data".to_string(), "/srv".to_string()]),
+            allowed_hosts: Some(vec!["443".to_string()]),
+            ..Default::default()
+        });
+
+        assert_eq!(base.restrict_disk, Some(true));
+        assert_eq!(base.restrict_network, Some(true));
+        assert_eq!(base.deny_paths, None);
+        assert_eq!(
+            base.read_only_paths,
+            Some(vec!["/data".to_string(), "/srv".to_string()])
+        );
+        assert_eq!(base.allowed_hosts, Some(vec!["443".to_string()]));
+    }
+
+    #[test]
+    fn test_security_config_merge_seccomp() {
+        let mut base = SecurityConfig {
+            seccomp_profile: Some("default".to_string()),
+            seccomp_audit: Some(true),
+            ..Default::default()
+        };
+        base.merge(&SecurityConfig {
+            seccomp_audit: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(base.seccomp_profile.as_deref(), Some("default"));
+        assert_eq!(base.seccomp_audit, Some(false));
+
+        base.merge(&SecurityConfig {
+            seccomp_profile: Some("seccomp.json".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(base.seccomp_profile.as_deref(), Some("seccomp.json"));
+    }
+
+    #[test]
+    fn test_security_config_merge_enforcement() {
+        let mut base = SecurityConfig::default();
+        base.merge(&SecurityConfig {
+            enforcement: Some(EnforcementLevel::Warn),
+            ..Default::default()
+        });
+        assert_eq!(base.enforcement, Some(EnforcementLevel::Warn));
+
+        base.merge(&SecurityConfig {
+            enforcement: Some(EnforcementLevel::Off),
+            ..Default::default()
+        });
+        assert_eq!(base.enforcement, Some(EnforcementLevel::Warn));
+
+        base.merge(&SecurityConfig {
+            enforcement: Some(EnforcementLevel::Enforce),
+            ..Default::default()
+        });
+        assert_eq!(base.enforcement, Some(EnforcementLevel::Enforce));
+    }
 }
@@ -1,6 +1,10 @@
 //! Task configuration types
 
-use super::{CacheEnvConfig, SecurityConfig, TaskCacheConfig};
+use super::{
+    CacheEnvConfig, ProblemMatcherConfig, PublishConfig, RunAsConfig, ScheduleConfig,
+    SecurityConfig, TaskCacheConfig, TaskOutputsConfig,
+};
+use cuenv_core::TaskIoPriority;
 use indexmap::IndexMap;
 use serde::{de::MapAccess, de::Visitor, Deserialize, Deserializer, Serialize};
 use std::fmt;
@@ -66,6 +70,12 @@ pub enum TaskNode {
     Group {
         #[serde(skip_serializing_if = "Option::is_none")]
         description: Option<String>,
+        /// Task run once before any member of the group
+        #[serde(skip_serializing_if = "Option::is_none")]
+        setup: Option<String>,
+        /// Task run once after every member of the group
+        #[serde(skip_serializing_if = "Option::is_none")]
+        teardown: Option<String>,
         tasks: TaskCollection,
     },
 }
@@ -91,11 +101,12 @@ impl<'de> Deserialize<'de> for TaskNode {
             } else {
                 // Check if it has a tasks field
                 if let Some(tasks_value) = map.get("tasks") {
-                    // It's a Group - extract description and tasks
-                    let description = map
-                        .get("description")
-                        .and_then(|v| v.as_str())
-                        .map(String::from);
+                    // It's a Group - extract description, hooks and tasks
+                    let string_field =
+                        |name: &str| map.get(name).and_then(|v| v.as_str()).map(String::from);
+                    let description = string_field("description");
+                    let setup = string_field("setup");
+                    let teardown = string_field("teardown");
 
                     let tasks = match tasks_value {
                         serde_json::Value::Array(arr) => {
@@ -127,7 +138,12 @@ impl<'de> Deserialize<'de> for TaskNode {
                         }
                     };
 
-                    Ok(TaskNode::Group { description, tasks })
+                    Ok(TaskNode::Group {
+                        description,
+                        setup,
+                        teardown,
+                        tasks,
+                    })
                 } else {
                     // Check if it has non-task fields (old format compatibility)
                     let task_fields = vec![
@@ -145,6 +161,19 @@ impl<'de> Deserialize<'de> for TaskNode {
                         "cache_env",
                         "timeout",
                         "args",
+                        "runAs",
+                        "allowUndefined",
+                        "publish",
+                        "alias",
+                        "deprecated",
+                        "owner",
+                        "tags",
+                        "labels",
+                        "umask",
+                        "nice",
+                        "ioPriority",
+                        "waitFor",
+                        "cacheFailures",
                     ];
 
                     let has_non_task_fields =
@@ -169,6 +198,8 @@ impl<'de> Deserialize<'de> for TaskNode {
 
                         Ok(TaskNode::Group {
                             description,
+                            setup: None,
+                            teardown: None,
                             tasks: TaskCollection::Parallel(tasks),
                         })
                     } else {
@@ -195,7 +226,8 @@ pub struct TaskConfig {
     pub working_dir: Option<String>,
     pub shell: Option<String>,
     pub inputs: Option<Vec<String>>,
-    pub outputs: Option<Vec<String>>,
+    /// Files the task writes, or named values read from them
+    pub outputs: Option<TaskOutputsConfig>,
     pub security: Option<SecurityConfig>,
     /// Cache configuration for this task (simple boolean or advanced config)
     #[serde(default, deserialize_with = "deserialize_cache_config")]
@@ -208,6 +240,153 @@ pub struct TaskConfig {
     pub cache_env: Option<CacheEnvConfig>,
     /// Timeout for task execution in seconds
     pub timeout: Option<u32>,
+    /// Drop privileges to another user before running the task (Unix only)
+    #[serde(rename = "runAs", default, skip_serializing_if = "Option::is_none")]
+    pub run_as: Option<RunAsConfig>,
+    /// Let `${VAR}` references to undefined variables expand to an empty
+    /// string, even when `strictVariables` is on
+    #[serde(
+        rename = "allowUndefined",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub allow_undefined: Option<bool>,
+    /// Where to publish artifacts after a successful run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish: Option<Vec<PublishConfig>>,
+    /// Other names the task can be run by, e.g. `alias: ["t"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Vec<String>>,
+    /// Split the task's tests across parallel shards; needs `testList`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shardable: Option<bool>,
+    /// Command printing the task's tests, one per line. Each shard runs the
+    /// task's command with its tests appended as arguments.
+    #[serde(rename = "testList", default, skip_serializing_if = "Option::is_none")]
+    pub test_list: Option<String>,
+    /// Number of shards, defaulting to the number of CPUs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shards: Option<usize>,
+    /// Question the user must answer yes to, or `--yes` given, before the
+    /// task runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<ConfirmConfig>,
+    /// Matchers reading diagnostics from the task's output, e.g.
+    /// `problemMatcher: "rustc"`
+    #[serde(
+        rename = "problemMatcher",
+        default,
+        deserialize_with = "super::problem_matcher::deserialize_problem_matchers",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub problem_matcher: Option<Vec<ProblemMatcherConfig>>,
+    /// When `cuenv scheduler run` runs the task, e.g. `"0 9 * * 1-5"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleConfig>,
+    /// Deprecation note; the task still runs, with a warning. Also set by
+    /// `@deprecated("use build2")`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Team or person responsible for the task, e.g. from `@owner("team-x")`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Free-form tags to list tasks by, e.g. from `@tag("slow")`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Labels to select tasks by, e.g. `cuenv task run --label integration`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// File mode creation mask of the task process in octal, e.g. `"027"`
+    /// (Unix only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub umask: Option<String>,
+    /// Niceness of the task process, from -20 to 19; on Windows mapped to a
+    /// priority class
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// IO scheduling class and level of the task process (Linux only)
+    #[serde(
+        rename = "ioPriority",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub io_priority: Option<TaskIoPriority>,
+    /// Services to wait for before the command starts, e.g.
+    /// `waitFor: [{tcp: "localhost:5432", timeout: "30s"}]`
+    #[serde(rename = "waitFor", default, skip_serializing_if = "Option::is_none")]
+    pub wait_for: Option<Vec<WaitForConfig>>,
+    /// How long a failed run is reused while its inputs don't change, e.g.
+    /// `cacheFailures: "10m"`; failures aren't cached without it
+    #[serde(
+        rename = "cacheFailures",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cache_failures: Option<String>,
+}
+
+impl TaskConfig {
+    /// Whether the task is tagged `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().flatten().any(|t| t == tag)
+    }
+
+    /// Whether the task is labelled `label`; tags count as labels
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().flatten().any(|l| l == label) || self.has_tag(label)
+    }
+
+    /// Warning for running the task `name`, if it is deprecated
+    pub fn deprecation_message(&self, name: &str) -> Option<String> {
+        match self.deprecated.as_deref()? {
+            "" => Some(format!("Task '{name}' is deprecated")),
+            note => Some(format!("Task '{name}' is deprecated: {note}")),
+        }
+    }
+}
+
+/// A service a task waits for, set by exactly one of `tcp` and `http`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaitForConfig {
+    /// `host:port` to connect to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<String>,
+    /// URL to request until it answers with a success status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<String>,
+    /// How long to wait, e.g. `"30s"`; 30 seconds by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+}
+
+/// Confirmation a destructive task asks for, in every environment or only
+/// in the listed ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConfirmConfig {
+    Always(String),
+    Environments {
+        message: String,
+        /// Environments to confirm in; every environment when empty
+        #[serde(default)]
+        environments: Vec<String>,
+    },
+}
+
+impl ConfirmConfig {
+    /// Question to ask when `environment` is loaded, if the task asks one
+    /// there
+    pub fn prompt(&self, environment: Option<&str>) -> Option<&str> {
+        match self {
+            Self::Always(message) => Some(message),
+            Self::Environments {
+                message,
+                environments,
+            } => (environments.is_empty()
+                || environment.is_some_and(|env| environments.iter().any(|e| e == env)))
+            .then_some(message.as_str()),
+        }
+    }
 }
 
 /// Custom deserializer for cache configuration to support both simple and advanced forms
@@ -269,3 +448,73 @@ where
     // Handle any value type including null/missing
     deserializer.deserialize_any(CacheConfigVisitor)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_setup_and_teardown() {
+        let node: TaskNode = serde_json::from_value(serde_json::json!({
+            "description": "Integration tests",
+            "setup": "db.start",
+            "teardown": "db.stop",
+            "tasks": {
+                "api": { "command": "cargo test --test api" }
+            }
+        }))
+        .unwrap();
+
+        let TaskNode::Group {
+            setup,
+            teardown,
+            tasks,
+            ..
+        } = node
+        else {
+            panic!("expected a group");
+        };
+        assert_eq!(setup.as_deref(), Some("db.start"));
+        assert_eq!(teardown.as_deref(), Some("db.stop"));
+        assert_eq!(tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_confirm_only_in_listed_environments() {
+        let task: TaskConfig = serde_json::from_value(serde_json::json!({
+            "command": "./drop-db.sh",
+            "confirm": { "message": "Drop the database?", "environments": ["production"] }
+        }))
+        .unwrap();
+        let confirm = task.confirm.unwrap();
+        assert_eq!(
+            confirm.prompt(Some("production")),
+            Some("Drop the database?")
+        );
+        assert_eq!(confirm.prompt(Some("dev")), None);
+        assert_eq!(confirm.prompt(None), None);
+
+        let always = ConfirmConfig::Always("Continue?".to_string());
+        assert_eq!(always.prompt(None), Some("Continue?"));
+    }
+
+    #[test]
+    fn test_task_metadata_attributes() {
+        // As exported from `@deprecated("use build2") @tag("slow") @owner("team-x")`
+        let task: TaskConfig = serde_json::from_value(serde_json::json!({
+            "deprecated": "use build2",
+            "owner": "team-x",
+            "tags": ["slow"],
+            "command": "make"
+        }))
+        .unwrap();
+        assert!(task.has_tag("slow"));
+        assert!(!task.has_tag("fast"));
+        assert_eq!(task.owner.as_deref(), Some("team-x"));
+        assert_eq!(
+            task.deprecation_message("build").as_deref(),
+            Some("Task 'build' is deprecated: use build2")
+        );
+        assert!(TaskConfig::default().deprecation_message("build").is_none());
+    }
+}
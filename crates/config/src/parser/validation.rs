@@ -2,7 +2,6 @@
 
 use cuenv_core::constants::{CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME};
 use cuenv_core::errors::{Error, Result};
-use std::ffi::CString;
 use std::path::Path;
 
 /// Validates that the package name is allowed
@@ -38,26 +37,16 @@ pub fn validate_directory_path(dir: &Path) -> Result<String> {
     Ok(dir_str.to_string())
 }
 
-/// Creates a CString for FFI, ensuring no null bytes
-pub fn create_ffi_string(value: &str, context: &str) -> Result<CString> {
-    CString::new(value).map_err(|e| {
-        Error::ffi(
-            "cue_eval_package",
-            format!("{context} - contains null byte: {e}"),
-        )
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
+    use cuenv_utils::sync::ScopedEnv;
 
     #[test]
     fn test_validate_package_name() {
-        // Set up test environment
-        let original = env::var(CUENV_PACKAGE_VAR).ok();
-        env::set_var(CUENV_PACKAGE_VAR, "testpkg");
+        // Set up test environment, restored when the scope ends
+        let mut scope = ScopedEnv::new();
+        scope.set(CUENV_PACKAGE_VAR, "testpkg").unwrap();
 
         // Empty package name should fail
         assert!(validate_package_name("").is_err());
@@ -67,31 +56,19 @@ mod tests {
 
         // Configured package should succeed
         assert!(validate_package_name("testpkg").is_ok());
-
-        // Restore original value
-        if let Some(val) = original {
-            env::set_var(CUENV_PACKAGE_VAR, val);
-        } else {
-            env::remove_var(CUENV_PACKAGE_VAR);
-        }
     }
 
     #[test]
     fn test_validate_package_name_default() {
         // Remove the env var to test default
-        let original = env::var(CUENV_PACKAGE_VAR).ok();
-        env::remove_var(CUENV_PACKAGE_VAR);
+        let mut scope = ScopedEnv::new();
+        scope.remove(CUENV_PACKAGE_VAR).unwrap();
 
         // Default package should succeed
         assert!(validate_package_name(DEFAULT_PACKAGE_NAME).is_ok());
 
         // Non-default package should fail
         assert!(validate_package_name("notdefault").is_err());
-
-        // Restore original value
-        if let Some(val) = original {
-            env::set_var(CUENV_PACKAGE_VAR, val);
-        }
     }
 
     #[test]
@@ -1,16 +1,62 @@
 /// Constants used throughout the cuenv codebase
 // CUE package constants
 pub const ENV_CUE_FILENAME: &str = "env.cue";
+// Evaluated env.cue, read instead of evaluating where the CUE bridge is missing
+pub const ENV_JSON_FILENAME: &str = "env.json";
+pub const CUENV_EVAL_BACKEND_VAR: &str = "CUENV_EVAL_BACKEND";
+// Paths to skip when discovering packages and walking hook inputs
+pub const CUENV_IGNORE_FILENAME: &str = ".cuenvignore";
 pub const CUENV_PACKAGE_VAR: &str = "CUENV_PACKAGE";
+// Directories of CUE modules, e.g. shared profiles, importable without vendoring
+pub const CUENV_MODULE_PATH_VAR: &str = "CUENV_MODULE_PATH";
 pub const DEFAULT_PACKAGE_NAME: &str = "cuenv";
+// What an environment resolved to when it was locked, next to env.cue
+pub const CUENV_LOCKFILE_NAME: &str = "cuenv.lock";
 
 // Resolver prefix
 pub const CUENV_RESOLVER_PREFIX: &str = "cuenv-resolver://";
 
+// External secret provider reference prefix, executable naming and token variable
+pub const CUENV_PROVIDER_PREFIX: &str = "cuenv-provider://";
+pub const SECRET_PROVIDER_EXECUTABLE_PREFIX: &str = "cuenv-secret-";
+pub const SECRET_PROVIDER_TOKEN_VAR: &str = "CUENV_PROVIDER_TOKEN";
+
 // Environment variable names
 pub const CUENV_ENV_VAR: &str = "CUENV_ENV";
 pub const CUENV_CAPABILITIES_VAR: &str = "CUENV_CAPABILITIES";
 pub const CUENV_LOG_VAR: &str = "CUENV_LOG";
+// Set inside `cuenv shell with` subshells so the shell hook leaves them alone
+pub const CUENV_SCOPED_VAR: &str = "CUENV_SCOPED";
+// Identifies a shell for `cuenv env use`; the shell hook sets it on the first prompt
+pub const CUENV_SESSION_VAR: &str = "CUENV_SESSION";
+// Tasks to run with stub commands, one `task=command` per line
+pub const CUENV_TASK_STUBS_VAR: &str = "CUENV_TASK_STUBS";
+// Opts the shell hook into launching idle-time cache maintenance
+pub const CUENV_IDLE_MAINTENANCE_VAR: &str = "CUENV_IDLE_MAINTENANCE";
+// How often the shell hook prunes stale state, or "off"
+pub const CUENV_AUTO_PRUNE_VAR: &str = "CUENV_AUTO_PRUNE";
+// Relocate cuenv's cache and per-directory state, overriding the project's
+// `cacheDir` and `stateDir` settings
+pub const CUENV_CACHE_DIR_VAR: &str = "CUENV_CACHE_DIR";
+pub const CUENV_STATE_DIR_VAR: &str = "CUENV_STATE_DIR";
+// Access token `cuenv serve --web` requires, instead of a random one
+pub const CUENV_WEB_TOKEN_VAR: &str = "CUENV_WEB_TOKEN";
+// What unloading does with variables changed since the load: ask, preserve
+// or restore
+pub const CUENV_UNLOAD_CONFLICTS_VAR: &str = "CUENV_UNLOAD_CONFLICTS";
+// Writes lifecycle events to journald or syslog: journald, syslog or auto
+pub const CUENV_SYSLOG_VAR: &str = "CUENV_SYSLOG";
+// What happens to escape sequences in task output written to a terminal,
+// and to files and pipes: preserve, colors or strip
+pub const CUENV_ANSI_TERMINAL_VAR: &str = "CUENV_ANSI_TERMINAL";
+pub const CUENV_ANSI_FILES_VAR: &str = "CUENV_ANSI_FILES";
+
+// Built-in git metadata variables
+pub const CUENV_GIT_VAR_PREFIX: &str = "CUENV_GIT_";
+pub const CUENV_GIT_SHA_VAR: &str = "CUENV_GIT_SHA";
+pub const CUENV_GIT_BRANCH_VAR: &str = "CUENV_GIT_BRANCH";
+pub const CUENV_GIT_DIRTY_VAR: &str = "CUENV_GIT_DIRTY";
+pub const CUENV_GIT_TAG_VAR: &str = "CUENV_GIT_TAG";
 
 // Default shell
 pub const DEFAULT_SHELL: &str = "bash";
@@ -18,6 +64,17 @@ pub const DEFAULT_SHELL: &str = "bash";
 // System paths to filter in audit mode
 pub const AUDIT_IGNORED_PATH_PREFIXES: &[&str] = &["/proc/", "/sys/", "/dev/", "/tmp/"];
 
+// System directories sandboxed commands may always read and execute from
+pub const SYSTEM_READ_ONLY_PATHS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/lib",
+    "/lib64",
+    "/etc",
+    "/nix/store",
+];
+
 // Common system files
 pub const LD_SO_CACHE: &str = "/etc/ld.so.cache";
 
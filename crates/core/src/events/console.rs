@@ -3,6 +3,7 @@
 use crate::events::{
     CacheEvent, EnhancedEvent, EventSubscriber, PipelineEvent, SystemEvent, TaskEvent,
 };
+use crate::output::{policy, sanitize, AnsiPolicy, OutputSink};
 use async_trait::async_trait;
 use std::io::{self, IsTerminal};
 use tracing::debug;
@@ -125,6 +126,32 @@ impl ConsoleSubscriber {
                     None
                 }
             }
+            TaskEvent::TaskPublishing { task_name, .. } => {
+                Some(self.colorize(&format!("📦 Publishing artifacts of '{task_name}'"), "cyan"))
+            }
+            TaskEvent::TaskDiagnostic {
+                task_name,
+                diagnostic,
+                ..
+            } => {
+                if matches!(
+                    self.verbosity,
+                    ConsoleVerbosity::Verbose | ConsoleVerbosity::Debug
+                ) {
+                    Some(format!("🔎 {task_name}: {diagnostic}"))
+                } else {
+                    None
+                }
+            }
+            TaskEvent::TaskDeprecated { message, .. } => {
+                Some(self.colorize(&format!("⚠ {message}"), "yellow"))
+            }
+            TaskEvent::TaskStubbed {
+                task_name, command, ..
+            } => Some(self.colorize(
+                &format!("⤷ Task '{task_name}' is stubbed: {command}"),
+                "yellow",
+            )),
             TaskEvent::TaskSkipped {
                 task_name, reason, ..
             } => {
@@ -141,6 +168,7 @@ impl ConsoleSubscriber {
                 task_name, output, ..
             } => {
                 if matches!(self.verbosity, ConsoleVerbosity::Debug) {
+                    let output = sanitize(output, self.ansi_policy());
                     Some(format!("📤 {task_name}: {output}"))
                 } else {
                     None
@@ -148,7 +176,10 @@ impl ConsoleSubscriber {
             }
             TaskEvent::TaskError {
                 task_name, error, ..
-            } => Some(self.colorize(&format!("🚨 {task_name}: {error}"), "red")),
+            } => {
+                let error = sanitize(error, self.ansi_policy());
+                Some(self.colorize(&format!("🚨 {task_name}: {error}"), "red"))
+            }
         }
     }
 
@@ -261,6 +292,18 @@ impl ConsoleSubscriber {
                     format!("Environment variable {key} changed")
                 }
             }
+            crate::events::EnvEvent::HookStarted { command } => {
+                format!("Running hook {command}")
+            }
+            crate::events::EnvEvent::HookCompleted {
+                command,
+                duration_ms,
+            } => {
+                format!("Hook {command} completed in {duration_ms}ms")
+            }
+            crate::events::EnvEvent::HookFailed { command, error } => {
+                format!("Hook {command} failed: {error}")
+            }
         }
     }
 
@@ -311,6 +354,19 @@ impl ConsoleSubscriber {
         format!("{color_code}{text}\x1b[0m")
     }
 
+    /// What task output keeps of its escape sequences where it is written
+    fn ansi_policy(&self) -> AnsiPolicy {
+        let terminal = match self.writer {
+            ConsoleWriter::Stderr => io::stderr().is_terminal(),
+            ConsoleWriter::Stdout => io::stdout().is_terminal(),
+        };
+        policy(if terminal {
+            OutputSink::Terminal
+        } else {
+            OutputSink::File
+        })
+    }
+
     /// Write output to the configured destination
     fn write_output(&self, content: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         match self.writer {
@@ -406,6 +462,7 @@ mod tests {
                 task_id: "test-1".to_string(),
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: std::collections::HashMap::new(),
         };
@@ -424,6 +481,7 @@ mod tests {
             task_name: "test".to_string(),
             task_id: "test-1".to_string(),
             error: "failed".to_string(),
+            exit_status: None,
         });
 
         let progress_event = SystemEvent::Task(TaskEvent::TaskProgress {
@@ -2,8 +2,9 @@
 
 use crate::events::subscriber::{EnhancedEvent, EventSubscriber};
 use crate::events::types::SystemEvent;
+use crate::redaction::global_redactor;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error};
@@ -16,6 +17,9 @@ pub struct EventEmitter {
     subscribers: RwLock<Vec<Arc<dyn EventSubscriber>>>,
     /// Event correlation context
     correlation_context: RwLock<HashMap<String, String>>,
+    /// Sequence number of the last event; held while sending so numbers
+    /// follow the order of the channel
+    sequence: Mutex<u64>,
 }
 
 impl EventEmitter {
@@ -26,6 +30,7 @@ impl EventEmitter {
             sender,
             subscribers: RwLock::new(Vec::new()),
             correlation_context: RwLock::new(HashMap::new()),
+            sequence: Mutex::new(0),
         }
     }
 
@@ -69,6 +74,8 @@ impl EventEmitter {
     }
 
     /// Emit an event with custom metadata
+    ///
+    /// Secrets are masked before anything sees the event.
     pub async fn emit_with_metadata(&self, event: SystemEvent, metadata: HashMap<String, String>) {
         let correlation_context = self.correlation_context.read().await;
         let correlation_id = correlation_context.get("correlation_id").cloned();
@@ -76,18 +83,29 @@ impl EventEmitter {
         let mut combined_metadata = correlation_context.clone();
         combined_metadata.extend(metadata);
 
-        let enhanced_event = EnhancedEvent {
-            event,
-            timestamp: SystemTime::now(),
-            correlation_id,
-            metadata: combined_metadata,
+        let redactor = global_redactor();
+        let event = redactor.redact_serde(event);
+        let combined_metadata = redactor.redact_serde(combined_metadata);
+
+        let enhanced_event = {
+            let mut sequence = self
+                .sequence
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            *sequence += 1;
+            let enhanced_event = EnhancedEvent {
+                event,
+                timestamp: SystemTime::now(),
+                sequence: *sequence,
+                correlation_id,
+                metadata: combined_metadata,
+            };
+            if let Err(e) = self.sender.send(enhanced_event.clone()) {
+                debug!("Failed to send event to broadcast channel: {}", e);
+            }
+            enhanced_event
         };
 
-        // Send to broadcast channel (for potential future use)
-        if let Err(e) = self.sender.send(enhanced_event.clone()) {
-            debug!("Failed to send event to broadcast channel: {}", e);
-        }
-
         // Notify subscribers directly with parallel processing
         self.notify_subscribers(&enhanced_event).await;
     }
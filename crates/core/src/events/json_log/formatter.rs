@@ -1,7 +1,10 @@
 //! JSON formatting for events
 
 use super::error::JsonLogError;
+use crate::events::schema::EVENT_SCHEMA_VERSION;
 use crate::events::EnhancedEvent;
+use crate::output::{policy, sanitize, AnsiPolicy, OutputSink};
+use serde_json::Value;
 
 /// Format an event as JSON
 pub async fn format_event(
@@ -9,11 +12,14 @@ pub async fn format_event(
     include_metadata: bool,
 ) -> Result<String, JsonLogError> {
     let mut json_obj = serde_json::json!({
+        "schemaVersion": EVENT_SCHEMA_VERSION,
         "timestamp": event.timestamp.duration_since(std::time::UNIX_EPOCH)
             .map_err(|e| JsonLogError::SerializationError(e.to_string()))?
             .as_millis(),
+        "sequence": event.sequence,
         "event": event.event,
     });
+    sanitize_strings(&mut json_obj["event"], policy(OutputSink::File));
 
     if include_metadata {
         if let Some(correlation_id) = &event.correlation_id {
@@ -33,3 +39,21 @@ pub async fn format_event(
 
     serde_json::to_string(&json_obj).map_err(|e| JsonLogError::SerializationError(e.to_string()))
 }
+
+/// Task output and error messages keep only what the policy of files allows
+fn sanitize_strings(value: &mut Value, policy: AnsiPolicy) {
+    match value {
+        Value::String(text) => {
+            if let std::borrow::Cow::Owned(sanitized) = sanitize(text, policy) {
+                *text = sanitized;
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| sanitize_strings(value, policy)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|value| sanitize_strings(value, policy)),
+        _ => {}
+    }
+}
@@ -31,6 +31,7 @@ async fn test_json_log_event_writing() {
             duration_ms: 1000,
         }),
         timestamp: SystemTime::now(),
+        sequence: 1,
         correlation_id: Some("test-correlation".to_string()),
         metadata: {
             let mut map = HashMap::new();
@@ -80,6 +81,7 @@ async fn test_json_log_rotation() {
                 duration_ms: 1000,
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -110,8 +112,10 @@ async fn test_json_log_format_event() {
             task_name: "failing_task".to_string(),
             task_id: "fail-1".to_string(),
             error: "Something went wrong".to_string(),
+            exit_status: None,
         }),
         timestamp: SystemTime::now(),
+        sequence: 1,
         correlation_id: Some("correlation-123".to_string()),
         metadata: {
             let mut map = HashMap::new();
@@ -125,6 +129,7 @@ async fn test_json_log_format_event() {
     // Should be valid JSON
     let parsed: serde_json::Value = serde_json::from_str(&formatted).unwrap();
     assert!(parsed["timestamp"].is_number());
+    assert!(parsed["sequence"] == 1);
     assert!(parsed["event"]["Task"]["TaskFailed"]["task_name"] == "failing_task");
     assert!(parsed["correlation_id"] == "correlation-123");
     assert!(parsed["metadata"]["user"] == "test_user");
@@ -138,6 +143,7 @@ async fn test_json_log_format_without_metadata() {
             task_id: "simple-1".to_string(),
         }),
         timestamp: SystemTime::now(),
+        sequence: 1,
         correlation_id: Some("should-not-appear".to_string()),
         metadata: {
             let mut map = HashMap::new();
@@ -155,3 +161,26 @@ async fn test_json_log_format_without_metadata() {
     assert!(parsed.get("correlation_id").is_none());
     assert!(parsed.get("metadata").is_none());
 }
+
+#[tokio::test]
+async fn test_json_log_strips_escape_sequences() {
+    let event = EnhancedEvent {
+        event: SystemEvent::Task(TaskEvent::TaskOutput {
+            task_name: "build".to_string(),
+            task_id: "build-1".to_string(),
+            output: "\u{1b}[32mok\u{1b}[0m \u{fffd}".to_string(),
+        }),
+        timestamp: SystemTime::now(),
+        sequence: 1,
+        correlation_id: None,
+        metadata: HashMap::new(),
+    };
+
+    let formatted = format_event(&event, false).await.unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_str(&formatted).unwrap();
+    assert_eq!(
+        parsed["event"]["Task"]["TaskOutput"]["output"],
+        "ok \u{fffd}"
+    );
+}
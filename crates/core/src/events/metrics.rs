@@ -510,6 +510,7 @@ mod tests {
                 duration_ms: 1500,
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -532,8 +533,10 @@ mod tests {
                 task_name: "failing_task".to_string(),
                 task_id: "fail-1".to_string(),
                 error: "Test error".to_string(),
+                exit_status: None,
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -556,6 +559,7 @@ mod tests {
                 key: "test-key".to_string(),
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -566,6 +570,7 @@ mod tests {
                 key: "test-key-2".to_string(),
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -590,6 +595,7 @@ mod tests {
                 failed_tasks: 1,
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -613,6 +619,7 @@ mod tests {
                 duration_ms: 1000,
             }),
             timestamp: SystemTime::now(),
+            sequence: 1,
             correlation_id: None,
             metadata: HashMap::new(),
         };
@@ -643,6 +650,7 @@ mod tests {
                     duration_ms: 1000 + (i as u64 * 500), // 1000, 1500, 2000 ms
                 }),
                 timestamp: SystemTime::now(),
+                sequence: 1,
                 correlation_id: None,
                 metadata: HashMap::new(),
             };
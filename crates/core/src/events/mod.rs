@@ -9,7 +9,13 @@ pub mod emitter;
 pub mod global;
 pub mod json_log;
 pub mod metrics;
+pub mod schema;
 pub mod subscriber;
+#[cfg(unix)]
+pub mod syslog;
+pub mod task_run;
+pub mod template;
+pub mod timeline;
 pub mod types;
 pub mod utils;
 
@@ -17,6 +23,8 @@ pub mod utils;
 pub use console::ConsoleSubscriber;
 pub use json_log::JsonLogSubscriber;
 pub use metrics::MetricsSubscriber;
+#[cfg(unix)]
+pub use syslog::SyslogSubscriber;
 
 // Re-export core types
 pub use emitter::{EventBus, EventEmitter};
@@ -24,7 +32,11 @@ pub use global::{
     emit_global_event, emit_global_event_with_metadata, global_event_bus, global_event_emitter,
     initialize_global_events, publish_global_event, register_global_subscriber,
 };
+pub use schema::EVENT_SCHEMA_VERSION;
 pub use subscriber::{EnhancedEvent, EventSubscriber};
+pub use task_run::TaskRunEvents;
+pub use template::EventTemplates;
+pub use timeline::{global_timeline, SpanGuard, SpanKind, SpanStatus, Timeline, TimelineSpan};
 pub use types::{
     CacheEvent, DependencyEvent, EnvEvent, EventSystemError, PipelineEvent, SystemEvent, TaskEvent,
 };
@@ -0,0 +1,643 @@
+//! Versioned schema of the events written as JSON
+//!
+//! `--output-format json` and the JSON event log write one event per line,
+//! carrying the fields of [`event_fields`](super::template::event_fields)
+//! and `schemaVersion`. Within a schema version fields are only added;
+//! removing, renaming or retyping one bumps [`EVENT_SCHEMA_VERSION`], so
+//! integrators can rely on the stream across upgrades. `cuenv config
+//! event-schema` prints the schema as JSON Schema.
+
+use serde_json::{json, Map, Value};
+
+/// Version of the event schema, the `schemaVersion` of every event
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Type of an event field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    String,
+    Integer,
+    Boolean,
+    /// `{"kind": "code" | "signal", "value": <integer>}`
+    ExitStatus,
+    /// A problem matcher's diagnostic
+    Diagnostic,
+}
+
+/// A field of an event type; optional fields may be null
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub field_type: FieldType,
+    pub required: bool,
+}
+
+/// The fields of one event type, such as `taskCompleted`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventSchema {
+    pub event_type: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [FieldSchema],
+}
+
+const fn required(name: &'static str, field_type: FieldType) -> FieldSchema {
+    FieldSchema {
+        name,
+        field_type,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, field_type: FieldType) -> FieldSchema {
+    FieldSchema {
+        name,
+        field_type,
+        required: false,
+    }
+}
+
+const TASK_NAME: FieldSchema = required("task_name", FieldType::String);
+const TASK_ID: FieldSchema = required("task_id", FieldType::String);
+
+/// Every event type written
+pub const EVENT_SCHEMAS: &[EventSchema] = &[
+    EventSchema {
+        event_type: "taskStarted",
+        description: "A task run started",
+        fields: &[TASK_NAME, TASK_ID],
+    },
+    EventSchema {
+        event_type: "taskCompleted",
+        description: "A task run succeeded",
+        fields: &[
+            TASK_NAME,
+            TASK_ID,
+            required("duration_ms", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "taskFailed",
+        description: "A task run failed",
+        fields: &[
+            TASK_NAME,
+            TASK_ID,
+            required("error", FieldType::String),
+            optional("exit_status", FieldType::ExitStatus),
+        ],
+    },
+    EventSchema {
+        event_type: "taskProgress",
+        description: "Progress of a task run",
+        fields: &[TASK_NAME, TASK_ID, required("message", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "taskOutput",
+        description: "A line a task wrote to stdout",
+        fields: &[TASK_NAME, TASK_ID, required("output", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "taskError",
+        description: "A line a task wrote to stderr",
+        fields: &[TASK_NAME, TASK_ID, required("error", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "taskDiagnostic",
+        description: "A problem matcher found a diagnostic in a task's output",
+        fields: &[
+            TASK_NAME,
+            TASK_ID,
+            required("diagnostic", FieldType::Diagnostic),
+        ],
+    },
+    EventSchema {
+        event_type: "taskPublishing",
+        description: "A task succeeded and is publishing its artifacts",
+        fields: &[TASK_NAME, TASK_ID],
+    },
+    EventSchema {
+        event_type: "taskDeprecated",
+        description: "A deprecated task is about to run",
+        fields: &[TASK_NAME, TASK_ID, required("message", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "taskStubbed",
+        description: "A stubbed task runs its stub instead of its own command",
+        fields: &[TASK_NAME, TASK_ID, required("command", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "taskSkipped",
+        description: "A task was not run",
+        fields: &[TASK_NAME, TASK_ID, required("reason", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "pipelineStarted",
+        description: "A run of several tasks started",
+        fields: &[
+            required("total_tasks", FieldType::Integer),
+            required("total_levels", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "levelStarted",
+        description: "A level of tasks without dependencies among them started",
+        fields: &[
+            required("level", FieldType::Integer),
+            required("tasks_in_level", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "levelCompleted",
+        description: "A level of tasks finished",
+        fields: &[
+            required("level", FieldType::Integer),
+            required("successful_tasks", FieldType::Integer),
+            required("failed_tasks", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "pipelineCompleted",
+        description: "A run of several tasks finished",
+        fields: &[
+            required("total_duration_ms", FieldType::Integer),
+            required("successful_tasks", FieldType::Integer),
+            required("failed_tasks", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "cacheHit",
+        description: "A task's result was found in the cache",
+        fields: &[required("key", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "cacheMiss",
+        description: "A task's result was not in the cache",
+        fields: &[required("key", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "cacheWrite",
+        description: "A task's result was cached",
+        fields: &[
+            required("key", FieldType::String),
+            required("size_bytes", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "cacheEvict",
+        description: "A cache entry was evicted",
+        fields: &[
+            required("key", FieldType::String),
+            required("reason", FieldType::String),
+        ],
+    },
+    EventSchema {
+        event_type: "envLoading",
+        description: "Loading an environment started",
+        fields: &[required("path", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "envLoaded",
+        description: "An environment was loaded",
+        fields: &[
+            required("path", FieldType::String),
+            required("var_count", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "envLoadFailed",
+        description: "Loading an environment failed",
+        fields: &[
+            required("path", FieldType::String),
+            required("error", FieldType::String),
+        ],
+    },
+    EventSchema {
+        event_type: "envVarChanged",
+        description: "An environment variable changed",
+        fields: &[
+            required("key", FieldType::String),
+            required("is_secret", FieldType::Boolean),
+        ],
+    },
+    EventSchema {
+        event_type: "hookStarted",
+        description: "A hook of an environment started",
+        fields: &[required("command", FieldType::String)],
+    },
+    EventSchema {
+        event_type: "hookCompleted",
+        description: "A hook of an environment succeeded",
+        fields: &[
+            required("command", FieldType::String),
+            required("duration_ms", FieldType::Integer),
+        ],
+    },
+    EventSchema {
+        event_type: "hookFailed",
+        description: "A hook of an environment failed",
+        fields: &[
+            required("command", FieldType::String),
+            required("error", FieldType::String),
+        ],
+    },
+    EventSchema {
+        event_type: "dependencyResolved",
+        description: "A task dependency was resolved",
+        fields: &[
+            TASK_NAME,
+            required("dependency_name", FieldType::String),
+            optional("package_name", FieldType::String),
+        ],
+    },
+    EventSchema {
+        event_type: "dependencyResolutionFailed",
+        description: "A task dependency could not be resolved",
+        fields: &[
+            TASK_NAME,
+            required("dependency_name", FieldType::String),
+            required("error", FieldType::String),
+        ],
+    },
+];
+
+/// The schema of `event_type`
+pub fn event_schema(event_type: &str) -> Option<&'static EventSchema> {
+    EVENT_SCHEMAS
+        .iter()
+        .find(|schema| schema.event_type == event_type)
+}
+
+/// Check an event line against the schema, naming the first mismatch
+///
+/// Fields the schema does not know are accepted, as later releases of the
+/// same schema version may add them.
+pub fn validate(event: &Value) -> Result<(), String> {
+    let version = event.get("schemaVersion").and_then(Value::as_u64);
+    if version != Some(u64::from(EVENT_SCHEMA_VERSION)) {
+        return Err(format!(
+            "expected schemaVersion {EVENT_SCHEMA_VERSION}, found {version:?}"
+        ));
+    }
+    let event_type = event
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or("missing type")?;
+    let schema = event_schema(event_type).ok_or(format!("unknown type '{event_type}'"))?;
+    for (name, field_type) in [
+        ("timestamp", FieldType::String),
+        ("sequence", FieldType::Integer),
+    ]
+    .into_iter()
+    .chain(
+        schema
+            .fields
+            .iter()
+            .map(|field| (field.name, field.field_type)),
+    ) {
+        let required = schema
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .is_none_or(|field| field.required);
+        match event.get(name) {
+            None | Some(Value::Null) if !required => {}
+            None => return Err(format!("{event_type} is missing {name}")),
+            Some(value) if !has_type(value, field_type) => {
+                return Err(format!("{event_type} field {name} is not a {field_type:?}"))
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, field_type: FieldType) -> bool {
+    match field_type {
+        FieldType::String => value.is_string(),
+        FieldType::Integer => value.is_u64() || value.is_i64(),
+        FieldType::Boolean => value.is_boolean(),
+        FieldType::ExitStatus => {
+            matches!(
+                value.get("kind").and_then(Value::as_str),
+                Some("code" | "signal")
+            ) && value.get("value").is_some_and(Value::is_i64)
+        }
+        FieldType::Diagnostic => ["task", "severity", "message"]
+            .iter()
+            .all(|field| value.get(field).is_some_and(Value::is_string)),
+    }
+}
+
+/// The schema as a JSON Schema document
+pub fn json_schema() -> Value {
+    let variants: Vec<Value> = EVENT_SCHEMAS
+        .iter()
+        .map(|schema| {
+            let mut properties = Map::new();
+            properties.insert(
+                "schemaVersion".to_string(),
+                json!({ "const": EVENT_SCHEMA_VERSION }),
+            );
+            properties.insert("type".to_string(), json!({ "const": schema.event_type }));
+            properties.insert(
+                "timestamp".to_string(),
+                json!({ "type": "string", "format": "date-time" }),
+            );
+            properties.insert(
+                "sequence".to_string(),
+                json!({ "type": "integer", "minimum": 0 }),
+            );
+            let mut required = vec!["schemaVersion", "type", "timestamp", "sequence"];
+            for field in schema.fields {
+                let mut property = field_json_schema(field.field_type);
+                if !field.required {
+                    property = json!({ "anyOf": [property, { "type": "null" }] });
+                }
+                properties.insert(field.name.to_string(), property);
+                if field.required {
+                    required.push(field.name);
+                }
+            }
+            json!({
+                "title": schema.event_type,
+                "description": schema.description,
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "cuenv event",
+        "description": format!(
+            "An event of schema version {EVENT_SCHEMA_VERSION}, one per line of cuenv's JSON output"
+        ),
+        "oneOf": variants,
+        "$defs": {
+            "exitStatus": {
+                "type": "object",
+                "properties": {
+                    "kind": { "enum": ["code", "signal"] },
+                    "value": { "type": "integer" },
+                },
+                "required": ["kind", "value"],
+            },
+            "diagnostic": {
+                "type": "object",
+                "properties": {
+                    "task": { "type": "string" },
+                    "severity": { "enum": ["error", "warning", "note"] },
+                    "message": { "type": "string" },
+                    "file": { "type": "string" },
+                    "line": { "type": "integer", "minimum": 0 },
+                    "column": { "type": "integer", "minimum": 0 },
+                    "code": { "type": "string" },
+                },
+                "required": ["task", "severity", "message"],
+            },
+        },
+    })
+}
+
+fn field_json_schema(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::String => json!({ "type": "string" }),
+        FieldType::Integer => json!({ "type": "integer" }),
+        FieldType::Boolean => json!({ "type": "boolean" }),
+        FieldType::ExitStatus => json!({ "$ref": "#/$defs/exitStatus" }),
+        FieldType::Diagnostic => json!({ "$ref": "#/$defs/diagnostic" }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::template::event_fields;
+    use crate::events::{
+        CacheEvent, DependencyEvent, EnhancedEvent, EnvEvent, PipelineEvent, SystemEvent, TaskEvent,
+    };
+    use crate::types::{Diagnostic, ExitStatus, Severity};
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    /// Fields of schema version 1, as `type.field: Type`; none may change
+    /// while the version stays 1
+    const VERSION_1: &str = "\
+        taskStarted.task_name: String, taskStarted.task_id: String, \
+        taskCompleted.duration_ms: Integer, taskFailed.error: String, \
+        taskFailed.exit_status: ExitStatus, taskOutput.output: String, \
+        taskError.error: String, taskDiagnostic.diagnostic: Diagnostic, \
+        taskSkipped.reason: String, pipelineCompleted.total_duration_ms: Integer, \
+        cacheHit.key: String, cacheWrite.size_bytes: Integer, envLoaded.var_count: Integer, \
+        hookCompleted.duration_ms: Integer, hookFailed.error: String";
+
+    fn samples() -> Vec<SystemEvent> {
+        let (task_name, task_id) = ("build".to_string(), "01J".to_string());
+        let task = |event: fn(String, String) -> TaskEvent| {
+            SystemEvent::Task(event(task_name.clone(), task_id.clone()))
+        };
+        vec![
+            task(|task_name, task_id| TaskEvent::TaskStarted { task_name, task_id }),
+            task(|task_name, task_id| TaskEvent::TaskCompleted {
+                task_name,
+                task_id,
+                duration_ms: 12,
+            }),
+            task(|task_name, task_id| TaskEvent::TaskFailed {
+                task_name,
+                task_id,
+                error: "exit code 2".to_string(),
+                exit_status: Some(ExitStatus::Code(2)),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskFailed {
+                task_name,
+                task_id,
+                error: "timed out".to_string(),
+                exit_status: None,
+            }),
+            task(|task_name, task_id| TaskEvent::TaskProgress {
+                task_name,
+                task_id,
+                message: "half".to_string(),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskOutput {
+                task_name,
+                task_id,
+                output: "ok".to_string(),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskError {
+                task_name,
+                task_id,
+                error: "warn".to_string(),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskDiagnostic {
+                task_name: task_name.clone(),
+                task_id,
+                diagnostic: Diagnostic {
+                    task: task_name,
+                    severity: Severity::Error,
+                    message: "mismatched types".to_string(),
+                    file: Some("src/main.rs".to_string()),
+                    line: Some(3),
+                    column: None,
+                    code: None,
+                },
+            }),
+            task(|task_name, task_id| TaskEvent::TaskPublishing { task_name, task_id }),
+            task(|task_name, task_id| TaskEvent::TaskDeprecated {
+                task_name,
+                task_id,
+                message: "use compile".to_string(),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskStubbed {
+                task_name,
+                task_id,
+                command: "true".to_string(),
+            }),
+            task(|task_name, task_id| TaskEvent::TaskSkipped {
+                task_name,
+                task_id,
+                reason: "cached".to_string(),
+            }),
+            SystemEvent::Pipeline(PipelineEvent::PipelineStarted {
+                total_tasks: 3,
+                total_levels: 2,
+            }),
+            SystemEvent::Pipeline(PipelineEvent::LevelStarted {
+                level: 0,
+                tasks_in_level: 2,
+            }),
+            SystemEvent::Pipeline(PipelineEvent::LevelCompleted {
+                level: 0,
+                successful_tasks: 2,
+                failed_tasks: 0,
+            }),
+            SystemEvent::Pipeline(PipelineEvent::PipelineCompleted {
+                total_duration_ms: 40,
+                successful_tasks: 3,
+                failed_tasks: 0,
+            }),
+            SystemEvent::Cache(CacheEvent::CacheHit {
+                key: "k".to_string(),
+            }),
+            SystemEvent::Cache(CacheEvent::CacheMiss {
+                key: "k".to_string(),
+            }),
+            SystemEvent::Cache(CacheEvent::CacheWrite {
+                key: "k".to_string(),
+                size_bytes: 10,
+            }),
+            SystemEvent::Cache(CacheEvent::CacheEvict {
+                key: "k".to_string(),
+                reason: "size".to_string(),
+            }),
+            SystemEvent::Env(EnvEvent::EnvLoading {
+                path: "/p".to_string(),
+            }),
+            SystemEvent::Env(EnvEvent::EnvLoaded {
+                path: "/p".to_string(),
+                var_count: 4,
+            }),
+            SystemEvent::Env(EnvEvent::EnvLoadFailed {
+                path: "/p".to_string(),
+                error: "syntax".to_string(),
+            }),
+            SystemEvent::Env(EnvEvent::EnvVarChanged {
+                key: "API_KEY".to_string(),
+                is_secret: true,
+            }),
+            SystemEvent::Env(EnvEvent::HookStarted {
+                command: "nix".to_string(),
+            }),
+            SystemEvent::Env(EnvEvent::HookCompleted {
+                command: "nix".to_string(),
+                duration_ms: 900,
+            }),
+            SystemEvent::Env(EnvEvent::HookFailed {
+                command: "nix".to_string(),
+                error: "exit code 1".to_string(),
+            }),
+            SystemEvent::Dependency(DependencyEvent::DependencyResolved {
+                task_name: "build".to_string(),
+                dependency_name: "lint".to_string(),
+                package_name: None,
+            }),
+            SystemEvent::Dependency(DependencyEvent::DependencyResolutionFailed {
+                task_name: "build".to_string(),
+                dependency_name: "missing".to_string(),
+                error: "not found".to_string(),
+            }),
+        ]
+    }
+
+    fn line(event: SystemEvent) -> Value {
+        event_fields(&EnhancedEvent {
+            event,
+            timestamp: SystemTime::UNIX_EPOCH,
+            sequence: 1,
+            correlation_id: None,
+            metadata: HashMap::new(),
+        })
+    }
+
+    #[test]
+    fn test_every_event_matches_schema() {
+        let mut covered = Vec::new();
+        for event in samples() {
+            let line = line(event);
+            validate(&line).unwrap_or_else(|e| panic!("{e}: {line}"));
+            covered.push(line["type"].as_str().unwrap().to_string());
+        }
+        for schema in EVENT_SCHEMAS {
+            assert!(
+                covered
+                    .iter()
+                    .any(|event_type| event_type == schema.event_type),
+                "no sample of {}",
+                schema.event_type
+            );
+        }
+
+        let mut wrong = line(SystemEvent::Cache(CacheEvent::CacheWrite {
+            key: "k".to_string(),
+            size_bytes: 10,
+        }));
+        wrong["size_bytes"] = json!("10");
+        assert!(validate(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_version_1_fields_are_stable() {
+        assert_eq!(EVENT_SCHEMA_VERSION, 1, "update the frozen fields");
+        for entry in VERSION_1.split(", ") {
+            let (path, field_type) = entry.split_once(": ").unwrap();
+            let (event_type, name) = path.trim().split_once('.').unwrap();
+            let field = event_schema(event_type)
+                .and_then(|schema| schema.fields.iter().find(|field| field.name == name))
+                .unwrap_or_else(|| panic!("{path} was removed"));
+            assert_eq!(format!("{:?}", field.field_type), field_type, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_json_schema_lists_every_type() {
+        let schema = json_schema();
+        let variants = schema["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), EVENT_SCHEMAS.len());
+        let failed = &variants[2];
+        assert_eq!(failed["properties"]["type"]["const"], "taskFailed");
+        assert_eq!(
+            failed["required"],
+            json!([
+                "schemaVersion",
+                "type",
+                "timestamp",
+                "sequence",
+                "task_name",
+                "task_id",
+                "error"
+            ])
+        );
+    }
+}
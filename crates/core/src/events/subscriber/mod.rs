@@ -11,6 +11,9 @@ pub struct EnhancedEvent {
     pub event: SystemEvent,
     /// Timestamp when the event occurred
     pub timestamp: SystemTime,
+    /// Position of the event among those published on its bus; receivers
+    /// see events in this order
+    pub sequence: u64,
     /// Optional correlation ID for tracing related events
     pub correlation_id: Option<String>,
     /// Additional metadata
@@ -0,0 +1,358 @@
+//! System log sink for lifecycle events
+//!
+//! With `CUENV_SYSLOG` set, task runs, environment loads and hooks are
+//! written to the system log so machines managed as a fleet can be watched
+//! centrally: to journald with one field per event field (`CUENV_EVENT`,
+//! `CUENV_TASK_NAME`, `CUENV_ERROR`, ...), or to syslog as a message
+//! followed by `key=value` pairs. Task output is not forwarded.
+
+use crate::constants::CUENV_SYSLOG_VAR;
+use crate::errors::{Error, Result};
+use crate::events::template::event_fields;
+use crate::events::{EnhancedEvent, EnvEvent, EventSubscriber, SystemEvent, TaskEvent};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+
+/// Socket journald reads native protocol datagrams from
+pub const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Socket of the local syslog daemon
+#[cfg(target_os = "macos")]
+pub const SYSLOG_SOCKET: &str = "/var/run/syslog";
+#[cfg(not(target_os = "macos"))]
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+
+const IDENTIFIER: &str = "cuenv";
+
+/// `user` facility, for the priority of syslog messages
+const FACILITY_USER: u8 = 1;
+
+/// Severities of the events written
+const PRIORITY_ERR: u8 = 3;
+const PRIORITY_WARNING: u8 = 4;
+const PRIORITY_INFO: u8 = 6;
+const PRIORITY_DEBUG: u8 = 7;
+
+/// Which log events go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogTarget {
+    Journald,
+    Syslog,
+}
+
+/// Writes lifecycle events to journald or syslog
+#[derive(Debug)]
+pub struct SyslogSubscriber {
+    target: SyslogTarget,
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl SyslogSubscriber {
+    /// A subscriber writing to the standard socket of `target`
+    pub fn new(target: SyslogTarget) -> io::Result<Self> {
+        let path = match target {
+            SyslogTarget::Journald => JOURNALD_SOCKET,
+            SyslogTarget::Syslog => SYSLOG_SOCKET,
+        };
+        Self::with_socket(target, path)
+    }
+
+    /// A subscriber writing to the socket at `path`
+    pub fn with_socket(target: SyslogTarget, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            target,
+            socket: UnixDatagram::unbound()?,
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// The subscriber `CUENV_SYSLOG` asks for, if any
+    ///
+    /// `journald` and `syslog` pick the log; `auto` (or `1`) prefers
+    /// journald where it runs.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(value) = std::env::var(CUENV_SYSLOG_VAR) else {
+            return Ok(None);
+        };
+        let target = match value.trim() {
+            "" | "0" | "off" => return Ok(None),
+            "journald" => SyslogTarget::Journald,
+            "syslog" => SyslogTarget::Syslog,
+            "auto" | "1" => {
+                if Path::new(JOURNALD_SOCKET).exists() {
+                    SyslogTarget::Journald
+                } else {
+                    SyslogTarget::Syslog
+                }
+            }
+            other => {
+                return Err(Error::configuration(format!(
+                    "Invalid {CUENV_SYSLOG_VAR} '{other}'. Expected journald, syslog, auto or off"
+                )))
+            }
+        };
+        Self::new(target)
+            .map(Some)
+            .map_err(|e| Error::configuration(format!("Failed to open the system log socket: {e}")))
+    }
+
+    fn send(&self, payload: &[u8]) -> io::Result<()> {
+        self.socket.send_to(payload, &self.path).map(|_| ())
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for SyslogSubscriber {
+    async fn handle_event(
+        &self,
+        event: &EnhancedEvent,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some((priority, message)) = describe(&event.event) else {
+            return Ok(());
+        };
+        let fields = structured_fields(event);
+        let payload = match self.target {
+            SyslogTarget::Journald => journald_payload(priority, &message, &fields),
+            SyslogTarget::Syslog => syslog_line(priority, &message, &fields).into_bytes(),
+        };
+        // The log is best effort: a missing daemon must not fail runs
+        if let Err(e) = self.send(&payload) {
+            tracing::debug!("Failed to write event to {}: {e}", self.path.display());
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    fn is_interested(&self, event: &SystemEvent) -> bool {
+        describe(event).is_some()
+    }
+}
+
+/// Priority and message of the events written, `None` for the rest
+fn describe(event: &SystemEvent) -> Option<(u8, String)> {
+    Some(match event {
+        SystemEvent::Task(task) => match task {
+            TaskEvent::TaskStarted { task_name, .. } => {
+                (PRIORITY_INFO, format!("task {task_name} started"))
+            }
+            TaskEvent::TaskCompleted {
+                task_name,
+                duration_ms,
+                ..
+            } => (
+                PRIORITY_INFO,
+                format!("task {task_name} completed in {duration_ms}ms"),
+            ),
+            TaskEvent::TaskFailed {
+                task_name, error, ..
+            } => (PRIORITY_ERR, format!("task {task_name} failed: {error}")),
+            TaskEvent::TaskSkipped {
+                task_name, reason, ..
+            } => (PRIORITY_INFO, format!("task {task_name} skipped: {reason}")),
+            _ => return None,
+        },
+        SystemEvent::Env(env) => match env {
+            EnvEvent::EnvLoading { path } => {
+                (PRIORITY_DEBUG, format!("loading environment from {path}"))
+            }
+            EnvEvent::EnvLoaded { path, var_count } => (
+                PRIORITY_INFO,
+                format!("loaded {var_count} variables from {path}"),
+            ),
+            EnvEvent::EnvLoadFailed { path, error } => (
+                PRIORITY_ERR,
+                format!("failed to load environment from {path}: {error}"),
+            ),
+            EnvEvent::HookStarted { command } => {
+                (PRIORITY_DEBUG, format!("hook {command} started"))
+            }
+            EnvEvent::HookCompleted {
+                command,
+                duration_ms,
+            } => (
+                PRIORITY_INFO,
+                format!("hook {command} completed in {duration_ms}ms"),
+            ),
+            EnvEvent::HookFailed { command, error } => {
+                (PRIORITY_WARNING, format!("hook {command} failed: {error}"))
+            }
+            EnvEvent::EnvVarChanged { .. } => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// Fields of the event as upper case names and text values, such as
+/// `("EVENT", "taskFailed")` and `("EXIT_STATUS", "{\"code\":1}")`
+fn structured_fields(event: &EnhancedEvent) -> Vec<(String, String)> {
+    let Value::Object(fields) = event_fields(event) else {
+        return Vec::new();
+    };
+    let mut structured: Vec<(String, String)> = fields
+        .into_iter()
+        .filter(|(name, _)| !["schemaVersion", "timestamp", "sequence"].contains(&name.as_str()))
+        .filter_map(|(name, value)| {
+            let name = if name == "type" {
+                "EVENT".to_string()
+            } else {
+                field_name(&name)?
+            };
+            let value = match value {
+                Value::Null => return None,
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            Some((name, value))
+        })
+        .collect();
+    if let Some(correlation_id) = &event.correlation_id {
+        structured.push(("CORRELATION_ID".to_string(), correlation_id.clone()));
+    }
+    structured
+}
+
+/// `task_name` as `TASK_NAME`; journald takes letters, digits and `_`
+fn field_name(name: &str) -> Option<String> {
+    let upper: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    (!upper.is_empty()).then_some(upper)
+}
+
+/// A datagram of journald's native protocol
+///
+/// Values spanning lines are written with their length, as the protocol
+/// requires.
+fn journald_payload(priority: u8, message: &str, fields: &[(String, String)]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    let mut field = |name: &str, value: &str| {
+        payload.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            payload.push(b'=');
+        }
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(b'\n');
+    };
+    field("MESSAGE", message);
+    field("PRIORITY", &priority.to_string());
+    field("SYSLOG_IDENTIFIER", IDENTIFIER);
+    for (name, value) in fields {
+        field(&format!("CUENV_{name}"), value);
+    }
+    payload
+}
+
+/// An RFC 3164 line as local syslog daemons accept it, the daemon adding
+/// the time and host
+fn syslog_line(priority: u8, message: &str, fields: &[(String, String)]) -> String {
+    let pairs: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| {
+            let name = name.to_ascii_lowercase();
+            if value.is_empty() || value.contains([' ', '"', '=']) || value.contains('\n') {
+                format!("{name}={value:?}")
+            } else {
+                format!("{name}={value}")
+            }
+        })
+        .collect();
+    format!(
+        "<{}>{IDENTIFIER}[{}]: {} {}",
+        FACILITY_USER * 8 + priority,
+        std::process::id(),
+        message.replace('\n', " "),
+        pairs.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ExitStatus;
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    fn failed_event() -> EnhancedEvent {
+        EnhancedEvent {
+            event: SystemEvent::Task(TaskEvent::TaskFailed {
+                task_name: "build".to_string(),
+                task_id: "01J".to_string(),
+                error: "exit code 2\nsee log".to_string(),
+                exit_status: Some(ExitStatus::Code(2)),
+            }),
+            timestamp: SystemTime::UNIX_EPOCH,
+            sequence: 3,
+            correlation_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_journald_payload() {
+        let event = failed_event();
+        let (priority, message) = describe(&event.event).unwrap();
+        let payload = journald_payload(priority, &message, &structured_fields(&event));
+
+        let text = String::from_utf8_lossy(&payload);
+        assert!(text.starts_with("MESSAGE\n"));
+        assert!(text.contains("PRIORITY=3\nSYSLOG_IDENTIFIER=cuenv\n"));
+        assert!(text.contains("CUENV_EVENT=taskFailed\n"));
+        assert!(text.contains("CUENV_TASK_NAME=build\n"));
+        let error = b"CUENV_ERROR\n";
+        let at = payload
+            .windows(error.len())
+            .position(|window| window == error)
+            .unwrap();
+        let length = &payload[at + error.len()..at + error.len() + 8];
+        assert_eq!(u64::from_le_bytes(length.try_into().unwrap()), 19);
+    }
+
+    #[test]
+    fn test_syslog_line() {
+        let event = failed_event();
+        let (priority, message) = describe(&event.event).unwrap();
+        let line = syslog_line(priority, &message, &structured_fields(&event));
+        assert!(line.starts_with(&format!("<11>cuenv[{}]: ", std::process::id())));
+        assert!(line.contains("task build failed: exit code 2 see log"));
+        assert!(line.contains(" event=taskFailed"));
+        assert!(line.contains(r#" error="exit code 2\nsee log""#));
+
+        let output = SystemEvent::Task(TaskEvent::TaskOutput {
+            task_name: "build".to_string(),
+            task_id: "01J".to_string(),
+            output: "compiling".to_string(),
+        });
+        assert!(describe(&output).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_writes_to_socket() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("journal.sock");
+        let daemon = UnixDatagram::bind(&path).unwrap();
+
+        let subscriber = SyslogSubscriber::with_socket(SyslogTarget::Journald, &path).unwrap();
+        subscriber.handle_event(&failed_event()).await.unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let received = daemon.recv(&mut buffer).unwrap();
+        assert!(String::from_utf8_lossy(&buffer[..received]).contains("CUENV_TASK_ID=01J"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Events of one task run, published in order
+//!
+//! Each invocation of a task publishes its events through a
+//! [`TaskRunEvents`], which stamps them with the run's [`RunId`] as
+//! `task_id`. A run starts with `TaskStarted` and ends with exactly one
+//! `TaskCompleted` or `TaskFailed`. Ending a run consumes the publisher, so
+//! nothing is published for a run once it ended, and as each publish
+//! completes before the next starts, subscribers and receivers see a run's
+//! events in the order they were published. A task that is skipped without
+//! running publishes only `TaskSkipped`.
+
+use crate::events::emitter::EventEmitter;
+use crate::events::global::global_event_bus;
+use crate::events::types::{SystemEvent, TaskEvent};
+use crate::types::{Diagnostic, ExitStatus, RunId};
+use std::sync::Arc;
+
+/// Publisher of the events of one task run
+pub struct TaskRunEvents {
+    bus: Arc<EventEmitter>,
+    task_name: String,
+    run_id: String,
+}
+
+impl TaskRunEvents {
+    /// Start a run of `task_name` on the global bus, publishing `TaskStarted`
+    pub async fn start(task_name: &str) -> Self {
+        Self::start_on(global_event_bus(), task_name).await
+    }
+
+    /// Start a run of `task_name` on `bus`, publishing `TaskStarted`
+    pub async fn start_on(bus: Arc<EventEmitter>, task_name: &str) -> Self {
+        let run = Self {
+            bus,
+            task_name: task_name.to_string(),
+            run_id: RunId::new().to_string(),
+        };
+        run.publish(|task_name, task_id| TaskEvent::TaskStarted { task_name, task_id })
+            .await;
+        run
+    }
+
+    /// Publish that `task_name` was not run
+    pub async fn skipped(task_name: &str, reason: &str) {
+        let bus = global_event_bus();
+        bus.publish(SystemEvent::Task(TaskEvent::TaskSkipped {
+            task_name: task_name.to_string(),
+            task_id: RunId::new().to_string(),
+            reason: reason.to_string(),
+        }))
+        .await;
+    }
+
+    /// Publish that the deprecated `task_name` is about to run
+    pub async fn deprecated(task_name: &str, message: &str) {
+        let bus = global_event_bus();
+        bus.publish(SystemEvent::Task(TaskEvent::TaskDeprecated {
+            task_name: task_name.to_string(),
+            task_id: RunId::new().to_string(),
+            message: message.to_string(),
+        }))
+        .await;
+    }
+
+    /// Publish that this run executes `command`, a stub, instead of the
+    /// task's own command
+    pub async fn stubbed(&self, command: &str) {
+        self.publish(|task_name, task_id| TaskEvent::TaskStubbed {
+            task_name,
+            task_id,
+            command: command.to_string(),
+        })
+        .await;
+    }
+
+    pub fn task_name(&self) -> &str {
+        &self.task_name
+    }
+
+    /// ULID of this run, carried by all of its events as `task_id`
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub async fn progress(&self, message: String) {
+        self.publish(|task_name, task_id| TaskEvent::TaskProgress {
+            task_name,
+            task_id,
+            message,
+        })
+        .await;
+    }
+
+    /// Standard output of the task
+    pub async fn output(&self, output: String) {
+        self.publish(|task_name, task_id| TaskEvent::TaskOutput {
+            task_name,
+            task_id,
+            output,
+        })
+        .await;
+    }
+
+    /// Standard error of the task
+    pub async fn error(&self, error: String) {
+        self.publish(|task_name, task_id| TaskEvent::TaskError {
+            task_name,
+            task_id,
+            error,
+        })
+        .await;
+    }
+
+    pub async fn diagnostic(&self, diagnostic: Diagnostic) {
+        self.publish(|task_name, task_id| TaskEvent::TaskDiagnostic {
+            task_name,
+            task_id,
+            diagnostic,
+        })
+        .await;
+    }
+
+    pub async fn publishing(&self) {
+        self.publish(|task_name, task_id| TaskEvent::TaskPublishing { task_name, task_id })
+            .await;
+    }
+
+    /// End the run successfully
+    pub async fn completed(self, duration_ms: u64) {
+        self.publish(|task_name, task_id| TaskEvent::TaskCompleted {
+            task_name,
+            task_id,
+            duration_ms,
+        })
+        .await;
+    }
+
+    /// End the run as failed
+    pub async fn failed(self, error: String, exit_status: Option<ExitStatus>) {
+        self.publish(|task_name, task_id| TaskEvent::TaskFailed {
+            task_name,
+            task_id,
+            error,
+            exit_status,
+        })
+        .await;
+    }
+
+    async fn publish(&self, event: impl FnOnce(String, String) -> TaskEvent) {
+        self.bus
+            .publish(SystemEvent::Task(event(
+                self.task_name.clone(),
+                self.run_id.clone(),
+            )))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_events_share_the_run_id_in_order() {
+        let bus = Arc::new(EventEmitter::new(16));
+        let mut receiver = bus.subscribe();
+
+        let first = TaskRunEvents::start_on(bus.clone(), "build").await;
+        let second = TaskRunEvents::start_on(bus.clone(), "build").await;
+        first.output("compiling".to_string()).await;
+        second.failed("boom".to_string(), None).await;
+        let first_id = first.run_id().to_string();
+        first.completed(5).await;
+
+        let events: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].sequence < pair[1].sequence));
+        let first_run: Vec<&TaskEvent> = events
+            .iter()
+            .filter_map(|event| match &event.event {
+                SystemEvent::Task(task_event) => Some(task_event),
+                _ => None,
+            })
+            .filter(|event| {
+                matches!(event,
+                    TaskEvent::TaskStarted { task_id, .. }
+                    | TaskEvent::TaskOutput { task_id, .. }
+                    | TaskEvent::TaskCompleted { task_id, .. }
+                    | TaskEvent::TaskFailed { task_id, .. } if *task_id == first_id)
+            })
+            .collect();
+        assert!(matches!(
+            first_run[..],
+            [
+                TaskEvent::TaskStarted { .. },
+                TaskEvent::TaskOutput { .. },
+                TaskEvent::TaskCompleted { .. }
+            ]
+        ));
+        assert_eq!(events.len(), 5);
+    }
+}
@@ -0,0 +1,320 @@
+//! User-defined rendering of events
+//!
+//! `config: outputTemplates` gives a format string per event type for the
+//! `template` output format, and `config: outputJson` the fields of each
+//! line of the `json` output format, so logs can be shaped for the system
+//! aggregating them. Both read an event's fields: `type` (such as
+//! `taskOutput`), `timestamp`, `sequence` and those of the event itself,
+//! such as `task_name`, `output` or `exit_status.code`.
+
+use super::schema::EVENT_SCHEMA_VERSION;
+use super::EnhancedEvent;
+use crate::errors::{Error, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Templates of the event types printed unless configured otherwise, so
+/// task output is never dropped
+const DEFAULT_TEMPLATES: &[(&str, &str)] = &[("taskOutput", "{output}"), ("taskError", "{error}")];
+
+/// Path of a field, such as `exit_status.code`; empty for the whole event
+type FieldPath = Vec<String>;
+
+/// A format string such as `[{task_name}] {output}`
+///
+/// `{{` and `}}` stand for literal braces. Missing fields render empty,
+/// strings as they are and anything else as JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(FieldPath),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Error::configuration(format!("Invalid output template '{source}': {reason}"))
+        };
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(invalid("unclosed '{'")),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(
+                        parse_field(field.trim()).map_err(|reason| invalid(&reason))?,
+                    ));
+                }
+                '}' => return Err(invalid("unmatched '}', write '}}' for a brace")),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// The template filled in with the `fields` of an event
+    pub fn render(&self, fields: &Value) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Field(path) => match lookup(fields, path) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::String(text)) => rendered.push_str(text),
+                    Some(value) => rendered.push_str(&value.to_string()),
+                },
+            }
+        }
+        rendered
+    }
+}
+
+/// Fields of a JSON line and the paths they are taken from, jq style
+/// (`.task_name`, `.exit_status.code`, `.` for the whole event)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    fields: Vec<(String, FieldPath)>,
+}
+
+impl Projection {
+    pub fn parse(fields: &BTreeMap<String, String>) -> Result<Self> {
+        let fields = fields
+            .iter()
+            .map(|(name, path)| {
+                let parsed = path
+                    .trim()
+                    .strip_prefix('.')
+                    .ok_or_else(|| "paths start with '.'".to_string())
+                    .and_then(|path| {
+                        if path.is_empty() {
+                            Ok(Vec::new())
+                        } else {
+                            parse_field(path)
+                        }
+                    });
+                parsed.map(|path| (name.clone(), path)).map_err(|reason| {
+                    Error::configuration(format!(
+                        "Invalid outputJson path '{path}' of '{name}': {reason}"
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { fields })
+    }
+
+    /// The configured fields taken from those of an event, null if missing
+    pub fn project(&self, fields: &Value) -> Value {
+        let projected = self
+            .fields
+            .iter()
+            .map(|(name, path)| {
+                let value = lookup(fields, path).cloned().unwrap_or(Value::Null);
+                (name.clone(), value)
+            })
+            .collect();
+        Value::Object(projected)
+    }
+}
+
+/// Output templates and JSON projection of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventTemplates {
+    templates: HashMap<String, Template>,
+    projection: Option<Projection>,
+}
+
+impl EventTemplates {
+    /// Templates by event type, and the fields of JSON lines if not whole
+    /// events
+    pub fn new(
+        templates: &BTreeMap<String, String>,
+        projection: Option<&BTreeMap<String, String>>,
+    ) -> Result<Self> {
+        let mut parsed = DEFAULT_TEMPLATES
+            .iter()
+            .map(|(event_type, source)| Ok((event_type.to_string(), Template::parse(source)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        for (event_type, source) in templates {
+            parsed.insert(event_type.clone(), Template::parse(source)?);
+        }
+        Ok(Self {
+            templates: parsed,
+            projection: projection.map(Projection::parse).transpose()?,
+        })
+    }
+
+    /// The event through its type's template, if it has one
+    pub fn render_line(&self, event: &EnhancedEvent) -> Option<String> {
+        let fields = event_fields(event);
+        let event_type = fields.get("type").and_then(Value::as_str)?;
+        let template = self.templates.get(event_type)?;
+        Some(template.render(&fields))
+    }
+
+    /// The event as one line of JSON
+    pub fn render_json(&self, event: &EnhancedEvent) -> String {
+        let fields = event_fields(event);
+        match &self.projection {
+            Some(projection) => projection.project(&fields).to_string(),
+            None => fields.to_string(),
+        }
+    }
+}
+
+/// The fields templates read from an event
+///
+/// Events serialize as `{"Task": {"TaskOutput": {...}}}`; this flattens
+/// that to the fields of the innermost object next to `schemaVersion`,
+/// `type`, `timestamp` and `sequence`, as the [event schema](super::schema)
+/// describes.
+pub fn event_fields(event: &EnhancedEvent) -> Value {
+    let mut fields = Map::new();
+    fields.insert(
+        "schemaVersion".to_string(),
+        Value::from(EVENT_SCHEMA_VERSION),
+    );
+    let timestamp = DateTime::<Utc>::from(event.timestamp);
+    fields.insert(
+        "timestamp".to_string(),
+        Value::String(timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    fields.insert("sequence".to_string(), Value::from(event.sequence));
+
+    let variant = match serde_json::to_value(&event.event) {
+        Ok(Value::Object(category)) => category.into_iter().next().map(|(_, variant)| variant),
+        _ => None,
+    };
+    match variant {
+        Some(Value::Object(variant)) => {
+            if let Some((name, Value::Object(event_fields))) = variant.into_iter().next() {
+                fields.insert("type".to_string(), Value::String(camel_case(&name)));
+                fields.extend(event_fields);
+            }
+        }
+        Some(Value::String(name)) => {
+            fields.insert("type".to_string(), Value::String(camel_case(&name)));
+        }
+        _ => {}
+    }
+    Value::Object(fields)
+}
+
+fn parse_field(field: &str) -> std::result::Result<FieldPath, String> {
+    let path: FieldPath = field.split('.').map(str::to_string).collect();
+    let valid = path.iter().all(|segment| {
+        !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    if valid {
+        Ok(path)
+    } else {
+        Err(format!("'{field}' is not a field name"))
+    }
+}
+
+fn lookup<'a>(fields: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(fields, |value, segment| value.get(segment))
+}
+
+/// `TaskOutput` as `taskOutput`
+fn camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{SystemEvent, TaskEvent};
+    use std::time::SystemTime;
+
+    fn output_event() -> EnhancedEvent {
+        EnhancedEvent {
+            event: SystemEvent::Task(TaskEvent::TaskOutput {
+                task_name: "build".to_string(),
+                task_id: "01J".to_string(),
+                output: "compiling".to_string(),
+            }),
+            timestamp: SystemTime::UNIX_EPOCH,
+            sequence: 7,
+            correlation_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_template_renders_fields_and_escapes() {
+        let template =
+            Template::parse("{{{type}}} [{task_name}] {output} {missing.field}").unwrap();
+        let fields = event_fields(&output_event());
+        assert_eq!(template.render(&fields), "{taskOutput} [build] compiling ");
+
+        assert!(Template::parse("{task_name").is_err());
+        assert!(Template::parse("a } b").is_err());
+        assert!(Template::parse("{task name}").is_err());
+    }
+
+    #[test]
+    fn test_event_templates_lines_and_json() {
+        let templates = BTreeMap::from([(
+            "taskOutput".to_string(),
+            "{timestamp} {task_name}: {output}".to_string(),
+        )]);
+        let projection = BTreeMap::from([
+            ("msg".to_string(), ".output".to_string()),
+            ("seq".to_string(), ".sequence".to_string()),
+            ("code".to_string(), ".exit_status.code".to_string()),
+        ]);
+        let event = output_event();
+
+        let rendered = EventTemplates::new(&templates, Some(&projection)).unwrap();
+        assert_eq!(
+            rendered.render_line(&event).unwrap(),
+            "1970-01-01T00:00:00.000Z build: compiling"
+        );
+        assert_eq!(
+            rendered.render_json(&event),
+            r#"{"code":null,"msg":"compiling","seq":7}"#
+        );
+
+        let defaults = EventTemplates::new(&BTreeMap::new(), None).unwrap();
+        assert_eq!(defaults.render_line(&event).unwrap(), "compiling");
+        assert!(defaults
+            .render_json(&event)
+            .contains(r#""type":"taskOutput""#));
+
+        let bad = BTreeMap::from([("msg".to_string(), "output".to_string())]);
+        assert!(EventTemplates::new(&BTreeMap::new(), Some(&bad)).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+//! Chrome trace export
+
+use super::span::{SpanKind, SpanStatus, TimelineSpan};
+use crate::redaction::redact;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A complete ("X") or begin ("B") event in Chrome trace format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChromeTraceEvent {
+    pub name: String,
+    pub cat: String,
+    pub ph: String,
+    /// Start in microseconds
+    pub ts: u64,
+    /// Duration in microseconds, for complete events
+    pub dur: Option<u64>,
+    pub pid: u32,
+    /// Track the event is drawn on
+    pub tid: String,
+    pub args: HashMap<String, serde_json::Value>,
+}
+
+impl ChromeTraceEvent {
+    /// Convert a span
+    ///
+    /// Tasks run in parallel, so each gets its own track; the sequential
+    /// stages share one track per kind. Secrets in names and details are
+    /// masked.
+    pub fn from_span(span: &TimelineSpan) -> Self {
+        let mut args = HashMap::from([(
+            "status".to_string(),
+            serde_json::to_value(span.status).unwrap_or_default(),
+        )]);
+        if let Some(parent) = span.parent {
+            args.insert("parent".to_string(), parent.into());
+        }
+        if let Some(detail) = &span.detail {
+            args.insert("detail".to_string(), redact(detail).into());
+        }
+
+        let complete = span.status != SpanStatus::Running;
+        let name = redact(&span.name).into_owned();
+        Self {
+            cat: span.kind.label().to_string(),
+            ph: if complete { "X" } else { "B" }.to_string(),
+            ts: span.start.as_micros() as u64,
+            dur: span.duration.map(|d| d.as_micros() as u64),
+            pid: std::process::id(),
+            tid: match span.kind {
+                SpanKind::Task => name.clone(),
+                kind => kind.label().to_string(),
+            },
+            name,
+            args,
+        }
+    }
+}
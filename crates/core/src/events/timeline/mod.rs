@@ -0,0 +1,206 @@
+//! Unified timeline of hooks, environment loading, secrets and tasks
+//!
+//! Every stage that can make entering a directory or running a task slow
+//! records a span here. The TUI and the Chrome trace exporter both read the
+//! same spans, so they always agree on where the time went.
+
+mod chrome;
+mod span;
+
+pub use chrome::ChromeTraceEvent;
+pub use span::{SpanKind, SpanStatus, TimelineSpan};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Spans kept; long-lived processes such as the agent drop the oldest
+const MAX_SPANS: usize = 10_000;
+
+#[derive(Debug)]
+struct TimelineState {
+    /// Spans in id order with no gaps, so a span is found from its id
+    spans: VecDeque<TimelineSpan>,
+    next_id: u64,
+    capacity: usize,
+}
+
+impl TimelineState {
+    fn get_mut(&mut self, id: u64) -> Option<&mut TimelineSpan> {
+        let first = self.spans.front()?.id;
+        let index = usize::try_from(id.checked_sub(first)?).ok()?;
+        self.spans.get_mut(index)
+    }
+}
+
+/// Shared, cheaply cloneable span recorder
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    origin: Instant,
+    state: Arc<Mutex<TimelineState>>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SPANS)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            origin: Instant::now(),
+            state: Arc::new(Mutex::new(TimelineState {
+                spans: VecDeque::new(),
+                next_id: 1,
+                capacity,
+            })),
+        }
+    }
+
+    /// Instant all span offsets are measured from
+    pub fn origin(&self) -> Instant {
+        self.origin
+    }
+
+    /// Open a top-level span; it closes when the guard is dropped
+    pub fn start(&self, kind: SpanKind, name: impl Into<String>) -> SpanGuard {
+        self.open(kind, name.into(), None)
+    }
+
+    /// Snapshot of the kept spans, ordered by start time
+    pub fn spans(&self) -> Vec<TimelineSpan> {
+        let mut spans: Vec<TimelineSpan> = self.lock().spans.iter().cloned().collect();
+        spans.sort_by_key(|span| (span.start, span.id));
+        spans
+    }
+
+    /// Drop all recorded spans
+    pub fn clear(&self) {
+        self.lock().spans.clear();
+    }
+
+    /// Spans in Chrome trace format (`chrome://tracing`, Perfetto)
+    pub fn to_chrome_trace(&self) -> Vec<ChromeTraceEvent> {
+        self.spans()
+            .iter()
+            .map(ChromeTraceEvent::from_span)
+            .collect()
+    }
+
+    /// Time since the origin, for measuring running spans
+    pub fn elapsed(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn open(&self, kind: SpanKind, name: String, parent: Option<u64>) -> SpanGuard {
+        let start = self.elapsed();
+        let id = {
+            let mut state = self.lock();
+            let id = state.next_id;
+            state.next_id += 1;
+            if state.spans.len() >= state.capacity {
+                state.spans.pop_front();
+            }
+            state.spans.push_back(TimelineSpan {
+                id,
+                parent,
+                kind,
+                name,
+                start,
+                duration: None,
+                status: SpanStatus::Running,
+                detail: None,
+            });
+            id
+        };
+
+        SpanGuard {
+            timeline: self.clone(),
+            id,
+            kind,
+            closed: false,
+        }
+    }
+
+    fn close(&self, id: u64, status: SpanStatus, detail: Option<String>) {
+        let end = self.elapsed();
+        let mut state = self.lock();
+        if let Some(span) = state.get_mut(id) {
+            span.duration = Some(end.saturating_sub(span.start));
+            span.status = status;
+            if detail.is_some() {
+                span.detail = detail;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TimelineState> {
+        // A poisoned timeline only loses diagnostics, never correctness
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An open span; closed as succeeded on drop unless finished explicitly
+#[derive(Debug)]
+pub struct SpanGuard {
+    timeline: Timeline,
+    id: u64,
+    kind: SpanKind,
+    closed: bool,
+}
+
+impl SpanGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Open a nested span of the same kind
+    pub fn child(&self, name: impl Into<String>) -> SpanGuard {
+        self.child_of_kind(self.kind, name)
+    }
+
+    /// Open a nested span of a different kind (e.g. a hook inside env loading)
+    pub fn child_of_kind(&self, kind: SpanKind, name: impl Into<String>) -> SpanGuard {
+        self.timeline.open(kind, name.into(), Some(self.id))
+    }
+
+    /// Close the span as failed with a short reason
+    pub fn fail(mut self, reason: impl Into<String>) {
+        self.closed = true;
+        self.timeline
+            .close(self.id, SpanStatus::Failed, Some(reason.into()));
+    }
+
+    /// Close the span according to `result`
+    pub fn finish<T, E: std::fmt::Display>(self, result: &std::result::Result<T, E>) {
+        match result {
+            Ok(_) => drop(self),
+            Err(e) => self.fail(e.to_string()),
+        }
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if !self.closed {
+            self.timeline.close(self.id, SpanStatus::Succeeded, None);
+        }
+    }
+}
+
+static GLOBAL_TIMELINE: OnceLock<Timeline> = OnceLock::new();
+
+/// The process-wide timeline every crate records into
+pub fn global_timeline() -> Timeline {
+    GLOBAL_TIMELINE.get_or_init(Timeline::new).clone()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,63 @@
+//! Timeline span types
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What a span measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanKind {
+    /// A hook command (onEnter, onExit, preload)
+    Hook,
+    /// A stage of environment loading (CUE evaluation, applying variables)
+    Env,
+    /// Secret resolution
+    Secrets,
+    /// Task execution
+    Task,
+}
+
+impl SpanKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Hook => "hook",
+            Self::Env => "env",
+            Self::Secrets => "secrets",
+            Self::Task => "task",
+        }
+    }
+}
+
+/// Outcome of a span
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A single timed stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineSpan {
+    pub id: u64,
+    /// Enclosing span, if any
+    pub parent: Option<u64>,
+    pub kind: SpanKind,
+    pub name: String,
+    /// Offset from the timeline origin
+    pub start: Duration,
+    /// `None` while the span is still running
+    pub duration: Option<Duration>,
+    pub status: SpanStatus,
+    /// Failure reason or other short annotation
+    pub detail: Option<String>,
+}
+
+impl TimelineSpan {
+    /// Duration so far, measuring running spans up to `now`
+    pub fn elapsed(&self, now: Duration) -> Duration {
+        self.duration
+            .unwrap_or_else(|| now.saturating_sub(self.start))
+    }
+}
@@ -0,0 +1,66 @@
+//! Tests for the timeline
+
+use super::*;
+
+#[test]
+fn test_spans_nest_and_close() {
+    let timeline = Timeline::new();
+
+    let load = timeline.start(SpanKind::Env, "load environment");
+    let load_id = load.id();
+    let hook = load.child_of_kind(SpanKind::Hook, "nix develop");
+    assert_eq!(timeline.spans()[1].status, SpanStatus::Running);
+    drop(hook);
+
+    let secrets = load.child_of_kind(SpanKind::Secrets, "resolve secrets");
+    secrets.finish(&Err::<(), _>("vault unreachable"));
+    drop(load);
+
+    let spans = timeline.spans();
+    assert_eq!(spans.len(), 3);
+    assert_eq!(spans[0].parent, None);
+    assert_eq!(spans[1].kind, SpanKind::Hook);
+    assert_eq!(spans[1].parent, Some(load_id));
+    assert_eq!(spans[1].status, SpanStatus::Succeeded);
+    assert_eq!(spans[2].status, SpanStatus::Failed);
+    assert_eq!(spans[2].detail.as_deref(), Some("vault unreachable"));
+    assert!(spans.iter().all(|span| span.duration.is_some()));
+
+    timeline.clear();
+    assert!(timeline.spans().is_empty());
+}
+
+#[test]
+fn test_oldest_spans_are_dropped() {
+    let timeline = Timeline::with_capacity(2);
+
+    let first = timeline.start(SpanKind::Task, "first");
+    drop(timeline.start(SpanKind::Task, "second"));
+    let third = timeline.start(SpanKind::Task, "third");
+    // Closing a dropped span is a no-op
+    drop(first);
+    third.fail("exit code 1");
+
+    let spans = timeline.spans();
+    let names: Vec<&str> = spans.iter().map(|span| span.name.as_str()).collect();
+    assert_eq!(names, ["second", "third"]);
+    assert_eq!(spans[1].status, SpanStatus::Failed);
+}
+
+#[test]
+fn test_chrome_trace_tracks() {
+    let timeline = Timeline::new();
+    drop(timeline.start(SpanKind::Hook, "devenv"));
+    drop(timeline.start(SpanKind::Task, "build"));
+    let running = timeline.start(SpanKind::Task, "test");
+
+    let events = timeline.to_chrome_trace();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].tid, "hook");
+    assert_eq!(events[0].cat, "hook");
+    assert_eq!(events[0].ph, "X");
+    assert_eq!(events[1].tid, "build");
+    assert_eq!(events[2].ph, "B");
+    assert_eq!(events[2].dur, None);
+    drop(running);
+}
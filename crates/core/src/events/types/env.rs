@@ -13,4 +13,10 @@ pub enum EnvEvent {
     EnvLoadFailed { path: String, error: String },
     /// Environment variable changed
     EnvVarChanged { key: String, is_secret: bool },
+    /// A hook of the environment started
+    HookStarted { command: String },
+    /// A hook of the environment finished successfully
+    HookCompleted { command: String, duration_ms: u64 },
+    /// A hook of the environment failed
+    HookFailed { command: String, error: String },
 }
@@ -1,8 +1,13 @@
 //! Task execution events
 
+use crate::types::{Diagnostic, ExitStatus};
 use serde::{Deserialize, Serialize};
 
 /// Task execution events
+///
+/// `task_id` is the ULID of the run the event belongs to; see
+/// [`TaskRunEvents`](crate::events::TaskRunEvents) for the order a run's
+/// events are published in.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskEvent {
     /// A task has started execution
@@ -18,6 +23,9 @@ pub enum TaskEvent {
         task_name: String,
         task_id: String,
         error: String,
+        /// How the task's process ended, when it ran to completion
+        #[serde(default)]
+        exit_status: Option<ExitStatus>,
     },
     /// Task progress update
     TaskProgress {
@@ -37,6 +45,26 @@ pub enum TaskEvent {
         task_id: String,
         error: String,
     },
+    /// A problem matcher found a diagnostic in a task's output
+    TaskDiagnostic {
+        task_name: String,
+        task_id: String,
+        diagnostic: Diagnostic,
+    },
+    /// A task succeeded and is publishing its artifacts
+    TaskPublishing { task_name: String, task_id: String },
+    /// A deprecated task is about to run
+    TaskDeprecated {
+        task_name: String,
+        task_id: String,
+        message: String,
+    },
+    /// A stubbed task runs its stub instead of its own command
+    TaskStubbed {
+        task_name: String,
+        task_id: String,
+        command: String,
+    },
     /// Task skipped due to cache or conditions
     TaskSkipped {
         task_name: String,
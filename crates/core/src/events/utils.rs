@@ -25,6 +25,7 @@ pub fn task_failed(task_name: &str, task_id: &str, error: &str) -> SystemEvent {
         task_name: task_name.to_string(),
         task_id: task_id.to_string(),
         error: error.to_string(),
+        exit_status: None,
     })
 }
 
@@ -13,6 +13,10 @@
 //!   the type level.
 //! - **`constants`**: A collection of shared, static constants such as environment
 //!   variable names and file paths.
+//! - **`suggestions`**: "Did you mean" suggestions for names that were not found.
+//! - **`redaction`**: Masking of secret values in everything cuenv writes.
+//! - **`output`**: Decoding of task output and the escape sequences each sink
+//!   keeps.
 
 // The `mod` statements declare the sub-modules within the `core` module.
 // The `pub` keyword makes them accessible from other parts of the crate that
@@ -20,6 +24,9 @@
 pub mod constants;
 pub mod errors;
 pub mod events;
+pub mod output;
+pub mod redaction;
+pub mod suggestions;
 pub mod types;
 
 // The `pub use` statements re-export the most important items from the sub-modules
@@ -0,0 +1,246 @@
+//! Decoding and sanitising of task output
+//!
+//! Tasks write bytes, not text: output is decoded a line at a time with
+//! invalid UTF-8 replaced by U+FFFD, so a stray byte never ends a stream
+//! early. What happens to terminal escape sequences depends on the sink
+//! the text is written to, see [`policy`]: they are passed to the terminal
+//! as they are, and stripped from files and the TUI, which draws the text
+//! itself.
+
+use crate::constants::{CUENV_ANSI_FILES_VAR, CUENV_ANSI_TERMINAL_VAR};
+use crate::errors::{Error, Result};
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// What happens to escape sequences and control characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiPolicy {
+    /// Kept as they are
+    Preserve,
+    /// Colors and text styles are kept, everything else is dropped, so
+    /// output can't move the cursor, clear the screen or set the title
+    Colors,
+    /// Everything is dropped, and of a line a carriage return redraws only
+    /// what was written last is kept, as a terminal would show it
+    Strip,
+}
+
+impl FromStr for AnsiPolicy {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "preserve" => Ok(Self::Preserve),
+            "colors" => Ok(Self::Colors),
+            "strip" => Ok(Self::Strip),
+            other => Err(Error::configuration(format!(
+                "Invalid ANSI policy '{other}'. Must be one of: preserve, colors, strip"
+            ))),
+        }
+    }
+}
+
+/// Where text is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSink {
+    /// A terminal, `CUENV_ANSI_TERMINAL`, preserve by default
+    Terminal,
+    /// A file or pipe, `CUENV_ANSI_FILES`, strip by default
+    File,
+    /// The TUI's panes, always strip
+    Tui,
+}
+
+/// The policy of `sink`, read from the environment once
+pub fn policy(sink: OutputSink) -> AnsiPolicy {
+    static POLICIES: OnceLock<(AnsiPolicy, AnsiPolicy)> = OnceLock::new();
+    let (terminal, files) = *POLICIES.get_or_init(|| {
+        (
+            configured(CUENV_ANSI_TERMINAL_VAR, AnsiPolicy::Preserve),
+            configured(CUENV_ANSI_FILES_VAR, AnsiPolicy::Strip),
+        )
+    });
+    match sink {
+        OutputSink::Terminal => terminal,
+        OutputSink::File => files,
+        OutputSink::Tui => AnsiPolicy::Strip,
+    }
+}
+
+fn configured(var: &str, default: AnsiPolicy) -> AnsiPolicy {
+    let Ok(value) = std::env::var(var) else {
+        return default;
+    };
+    value.parse().unwrap_or_else(|e| {
+        tracing::warn!("{var}: {e}");
+        default
+    })
+}
+
+/// A line read up to and including its `\n`, without its line ending
+pub fn decode_line(bytes: &[u8]) -> String {
+    let line = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+/// `text` with escape sequences and control characters handled by `policy`
+///
+/// Tabs are always kept.
+pub fn sanitize(text: &str, policy: AnsiPolicy) -> Cow<'_, str> {
+    if policy == AnsiPolicy::Preserve || !text.chars().any(|c| c.is_control() && c != '\t') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut sanitized = String::with_capacity(text.len());
+    let mut redrawn = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                Some('[') => {
+                    let sequence = control_sequence(&mut chars);
+                    if policy == AnsiPolicy::Colors && is_style(&sequence) {
+                        sanitized.push_str("\u{1b}[");
+                        sanitized.push_str(&sequence);
+                    }
+                }
+                Some(']' | 'P' | 'X' | '^' | '_') => skip_string(&mut chars),
+                Some(' '..='/') => {
+                    // nF escape: intermediates up to a final byte
+                    while chars.next_if(|c| matches!(c, ' '..='/')).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\u{9b}' => {
+                control_sequence(&mut chars);
+            }
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => skip_string(&mut chars),
+            '\r' if policy == AnsiPolicy::Strip => redrawn = true,
+            '\r' | '\t' => sanitized.push(c),
+            c if c.is_control() => {}
+            c => {
+                if std::mem::take(&mut redrawn) {
+                    sanitized.clear();
+                }
+                sanitized.push(c);
+            }
+        }
+    }
+    Cow::Owned(sanitized)
+}
+
+/// The parameters, intermediates and final byte of a CSI sequence
+fn control_sequence(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut sequence = String::new();
+    while let Some(c) = chars.next_if(|c| matches!(c, '0'..='?' | ' '..='/')) {
+        sequence.push(c);
+    }
+    if let Some(c) = chars.next_if(|c| matches!(c, '@'..='~')) {
+        sequence.push(c);
+    }
+    sequence
+}
+
+/// Select Graphic Rendition: colors and text styles
+fn is_style(sequence: &str) -> bool {
+    sequence.strip_suffix('m').is_some_and(|params| {
+        params
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ';' || c == ':')
+    })
+}
+
+/// Skip an OSC, DCS or similar string up to its terminator, BEL or ST
+fn skip_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{7}' | '\u{9c}' => return,
+            '\u{1b}' if chars.next_if_eq(&'\\').is_some() => return,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_decode_line() {
+        assert_eq!(decode_line(b"ok\r\n"), "ok");
+        assert_eq!(decode_line(b"ok"), "ok");
+        assert_eq!(
+            decode_line(b"caf\xc3\xa9 \xff\xfe\n"),
+            "café \u{fffd}\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_policies() {
+        let line = "\u{1b}]0;title\u{7}\u{1b}[1;31merror\u{1b}[0m: \u{1b}[2Kbad\u{7}\ttype";
+        assert_eq!(sanitize(line, AnsiPolicy::Preserve), line);
+        assert_eq!(
+            sanitize(line, AnsiPolicy::Colors),
+            "\u{1b}[1;31merror\u{1b}[0m: bad\ttype"
+        );
+        assert_eq!(sanitize(line, AnsiPolicy::Strip), "error: bad\ttype");
+        assert!(matches!(
+            sanitize("plain", AnsiPolicy::Strip),
+            Cow::Borrowed("plain")
+        ));
+    }
+
+    #[test]
+    fn test_strip_keeps_last_redraw() {
+        assert_eq!(sanitize(" 10%\r 50%\r100%", AnsiPolicy::Strip), "100%");
+        assert_eq!(sanitize("done\r", AnsiPolicy::Strip), "done");
+        assert_eq!(sanitize("a\rb", AnsiPolicy::Colors), "a\rb");
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!("colors".parse::<AnsiPolicy>().unwrap(), AnsiPolicy::Colors);
+        assert!("none".parse::<AnsiPolicy>().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_decoding_any_bytes_is_text(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let line = decode_line(&bytes);
+            if let Ok(text) = std::str::from_utf8(&bytes) {
+                let text = text.strip_suffix('\n').unwrap_or(text);
+                prop_assert_eq!(line.as_str(), text.strip_suffix('\r').unwrap_or(text));
+            }
+        }
+
+        #[test]
+        fn proptest_strip_leaves_no_controls(text in "(\\PC|\\p{Cc}|\u{1b}\\[[0-9;?]*[ -/]?[@-~]?|\u{1b}\\][^\u{7}]*\u{7}?)*") {
+            let stripped = sanitize(&text, AnsiPolicy::Strip);
+            prop_assert!(!stripped.chars().any(|c| c.is_control() && c != '\t'));
+        }
+
+        #[test]
+        fn proptest_colors_keeps_only_styles(text in "(\\PC|\\p{Cc}|\u{1b}\\[[0-9;?]*[ -/]?[@-~]?)*") {
+            let filtered = sanitize(&text, AnsiPolicy::Colors);
+            for sequence in filtered.split('\u{1b}').skip(1) {
+                let end = sequence.find('m');
+                prop_assert!(sequence.starts_with('[') && end.is_some());
+                prop_assert!(is_style(&sequence[1..=end.unwrap_or_default()]));
+            }
+            let unexpected = filtered.chars().filter(|&c| c.is_control() && !matches!(c, '\t' | '\r' | '\x1b'));
+            prop_assert_eq!(unexpected.count(), 0);
+        }
+
+        #[test]
+        fn proptest_sanitize_is_idempotent(text in "(\\PC|\\p{Cc}|\u{1b}\\[[0-9;]*m)*") {
+            for policy in [AnsiPolicy::Colors, AnsiPolicy::Strip] {
+                let once = sanitize(&text, policy).into_owned();
+                prop_assert_eq!(sanitize(&once, policy), once.as_str());
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Masking of secrets in everything cuenv writes
+//!
+//! Secret values are registered as they are resolved, and the patterns of
+//! `config.redactPatterns` when an environment loads. Sinks pass text
+//! through [`redact`] before writing it: events are masked as they are
+//! published, so the TUI, output formatters, the JSON log and JSON events
+//! never see a secret, and task and command output passed through to the
+//! terminal, Chrome traces and the environment pane are masked as written.
+
+use crate::errors::{Error, Result};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// What a secret is replaced with
+pub const MASK: &str = "***";
+
+/// Shorter values are not masked, as they would match unrelated output
+const MIN_SECRET_LEN: usize = 4;
+
+/// Secret values and patterns to mask
+#[derive(Debug, Default)]
+pub struct Redactor {
+    state: RwLock<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Longest first, so a secret containing another is masked whole
+    values: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mask `value` from now on
+    ///
+    /// Each line of a multi-line value is masked on its own too, as output
+    /// is mostly read a line at a time.
+    pub fn register_secret(&self, value: &str) {
+        let candidates = std::iter::once(value).chain(value.lines().map(str::trim));
+        let mut state = self.write();
+        for candidate in candidates {
+            if candidate.len() < MIN_SECRET_LEN || state.values.iter().any(|v| v == candidate) {
+                continue;
+            }
+            state.values.push(candidate.to_string());
+        }
+        state.values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    }
+
+    /// Mask matches of the regular expression `pattern` from now on
+    pub fn register_pattern(&self, pattern: &str) -> Result<()> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            Error::configuration(format!("Invalid redaction pattern '{pattern}': {e}"))
+        })?;
+        let mut state = self.write();
+        if !state.patterns.iter().any(|p| p.as_str() == pattern) {
+            state.patterns.push(regex);
+        }
+        Ok(())
+    }
+
+    /// Whether nothing is masked
+    pub fn is_empty(&self) -> bool {
+        let state = self.read();
+        state.values.is_empty() && state.patterns.is_empty()
+    }
+
+    /// `text` with every secret replaced by [`MASK`]
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let state = self.read();
+        let mut redacted = Cow::Borrowed(text);
+        for value in &state.values {
+            if redacted.contains(value.as_str()) {
+                redacted = Cow::Owned(redacted.replace(value.as_str(), MASK));
+            }
+        }
+        for pattern in &state.patterns {
+            if let Cow::Owned(replaced) = pattern.replace_all(&redacted, MASK) {
+                redacted = Cow::Owned(replaced);
+            }
+        }
+        redacted
+    }
+
+    /// Mask every string in `value`, returning whether any was changed
+    pub fn redact_json(&self, value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::String(text) => match self.redact(text) {
+                Cow::Owned(redacted) => {
+                    *text = redacted;
+                    true
+                }
+                Cow::Borrowed(_) => false,
+            },
+            serde_json::Value::Array(items) => items
+                .iter_mut()
+                .fold(false, |changed, item| self.redact_json(item) | changed),
+            serde_json::Value::Object(fields) => fields
+                .values_mut()
+                .fold(false, |changed, field| self.redact_json(field) | changed),
+            _ => false,
+        }
+    }
+
+    /// `value` with every string it serializes to masked
+    pub fn redact_serde<T: Serialize + DeserializeOwned>(&self, value: T) -> T {
+        if self.is_empty() {
+            return value;
+        }
+        let Ok(mut json) = serde_json::to_value(&value) else {
+            return value;
+        };
+        if !self.redact_json(&mut json) {
+            return value;
+        }
+        serde_json::from_value(json).unwrap_or(value)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+static GLOBAL_REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// The redactor every sink of this process masks through
+pub fn global_redactor() -> &'static Redactor {
+    GLOBAL_REDACTOR.get_or_init(Redactor::new)
+}
+
+/// `text` with every registered secret masked
+pub fn redact(text: &str) -> Cow<'_, str> {
+    global_redactor().redact(text)
+}
+
+/// Writer masking what passes through it with the global redactor
+///
+/// Output is held back until a line is complete, so a secret split across
+/// writes is still masked. The rest is written on flush.
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    fn write_redacted(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = String::from_utf8_lossy(bytes);
+        self.inner.write_all(redact(&text).as_bytes())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') {
+            let lines: Vec<u8> = self.pending.drain(..=end).collect();
+            self.write_redacted(&lines)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            self.write_redacted(&rest)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_values_and_patterns() {
+        let redactor = Redactor::new();
+        redactor.register_secret("hunter2-extended");
+        redactor.register_secret("hunter2");
+        redactor.register_secret("abc");
+        redactor.register_pattern(r"ghp_[A-Za-z0-9]{8}").unwrap();
+        assert!(redactor.register_pattern("(").is_err());
+
+        assert_eq!(
+            redactor.redact("pass=hunter2-extended token=ghp_abcd1234 short=abc"),
+            "pass=*** token=*** short=abc"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_redact_multiline_secret_by_line() {
+        let redactor = Redactor::new();
+        redactor.register_secret("-----BEGIN KEY-----\nMIIEvQIBADANBg\n-----END KEY-----");
+        assert_eq!(redactor.redact("line: MIIEvQIBADANBg"), "line: ***");
+    }
+
+    #[test]
+    fn test_redact_serde_masks_nested_strings() {
+        let redactor = Redactor::new();
+        redactor.register_secret("s3cr3t");
+        let value = serde_json::json!({"output": ["a s3cr3t b"], "code": 1});
+        assert_eq!(
+            redactor.redact_serde(value),
+            serde_json::json!({"output": ["a *** b"], "code": 1})
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! "Did you mean" suggestions for names that were not found
+//!
+//! Tasks, environments, run configurations and the like are looked up by
+//! name, and a typo in one should point at the name that was meant. Names
+//! are ranked by Jaro-Winkler similarity, which favours names sharing a
+//! prefix, as mistyped names usually do.
+
+/// Lowest similarity a name needs to be suggested
+const MIN_SIMILARITY: f64 = 0.8;
+
+/// Most names suggested at once
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Jaro-Winkler similarity of two strings, from 0 (nothing in common) to 1
+/// (equal)
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let jaro = jaro(&a, &b);
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if let Some(j) = (start..end).find(|&j| !b_matched[j] && b[j] == *ca) {
+            b_matched[j] = true;
+            a_matches.push(*ca);
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, matched)| **matched)
+        .map(|(c, _)| c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| x != y)
+        .count()
+        / 2;
+
+    let m = a_matches.len() as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+/// Names among `candidates` that `name` is probably a typo of, most similar
+/// first
+pub fn similar_names<I, S>(name: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut scored: Vec<(f64, String)> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.as_ref() != name)
+        .map(|candidate| {
+            (
+                jaro_winkler(name, candidate.as_ref()),
+                candidate.as_ref().to_string(),
+            )
+        })
+        .filter(|(score, _)| *score >= MIN_SIMILARITY)
+        .collect();
+    scored.sort_by(|(score_a, a), (score_b, b)| score_b.total_cmp(score_a).then_with(|| a.cmp(b)));
+    scored.dedup_by(|(_, a), (_, b)| a == b);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Sentence suggesting `suggestions`, if there are any
+pub fn did_you_mean(suggestions: &[String]) -> Option<String> {
+    match suggestions {
+        [] => None,
+        [only] => Some(format!("Did you mean '{only}'?")),
+        several => Some(format!(
+            "Did you mean one of: {}?",
+            several
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// `message`, followed by the sentence suggesting `suggestions`
+pub fn with_suggestions(message: impl Into<String>, suggestions: &[String]) -> String {
+    let message = message.into();
+    match did_you_mean(suggestions) {
+        Some(hint) => format!("{message}. {hint}"),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jaro_winkler() {
+        assert_eq!(jaro_winkler("build", "build"), 1.0);
+        assert_eq!(jaro_winkler("build", ""), 0.0);
+        assert!((jaro_winkler("martha", "marhta") - 0.961).abs() < 0.001);
+        assert!((jaro_winkler("dixon", "dicksonx") - 0.813).abs() < 0.001);
+        assert!(jaro_winkler("test", "tset") > jaro_winkler("test", "deploy"));
+    }
+
+    #[test]
+    fn test_similar_names_ranks_close_matches() {
+        let names = ["build", "build.docs", "test", "deploy", "lint.check"];
+        assert_eq!(similar_names("biuld", names), vec!["build"]);
+        assert_eq!(similar_names("lint.chek", names), vec!["lint.check"]);
+        assert_eq!(
+            similar_names("tst", ["test", "tests"]),
+            vec!["test", "tests"]
+        );
+        assert_eq!(similar_names("tset", names), vec!["test"]);
+        assert!(similar_names("xyz", names).is_empty());
+        // The name itself is not a suggestion
+        assert_eq!(similar_names("test", ["test", "tests"]), vec!["tests"]);
+    }
+
+    #[test]
+    fn test_did_you_mean_sentence() {
+        assert_eq!(did_you_mean(&[]), None);
+        assert_eq!(
+            did_you_mean(&["build".to_string()]).as_deref(),
+            Some("Did you mean 'build'?")
+        );
+        assert_eq!(
+            with_suggestions(
+                "Environment 'prod' not found",
+                &similar_names("prod", ["production", "dev"])
+            ),
+            "Environment 'prod' not found. Did you mean 'production'?"
+        );
+        assert_eq!(
+            with_suggestions("Task 'zzz' not found", &[]),
+            "Task 'zzz' not found"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Diagnostics read from task output
+//!
+//! Tasks that declare a `problemMatcher` have each line of their output
+//! matched against it, and every problem a compiler or linter reports
+//! becomes a [`Diagnostic`]. The CLI summarizes them after a run, writes
+//! them as JSON or GitHub annotations, and the task server protocol streams
+//! them to subscribers.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    /// Severity as tools print it, e.g. `error`, `Warning` or `info`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" | "fatal error" | "fatal" => Some(Self::Error),
+            "warning" | "warn" => Some(Self::Warning),
+            "note" | "info" | "help" | "hint" => Some(Self::Note),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A problem a task reported in its output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// Task whose output reported it
+    pub task: String,
+    pub severity: Severity,
+    pub message: String,
+    /// File as the tool printed it, usually relative to the task's
+    /// working directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// Error code or lint name, e.g. `E0308` or `no-unused-vars`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// `file:line:column`, with as much of it as is known
+    pub fn location(&self) -> Option<String> {
+        let file = self.file.as_deref()?;
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{file}:{line}:{column}"),
+            (Some(line), None) => format!("{file}:{line}"),
+            _ => file.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "{location}: ")?;
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic {
+            task: "build".to_string(),
+            severity: Severity::Error,
+            message: "mismatched types".to_string(),
+            file: Some("src/main.rs".to_string()),
+            line: Some(4),
+            column: Some(5),
+            code: Some("E0308".to_string()),
+        };
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/main.rs:4:5: error[E0308]: mismatched types"
+        );
+
+        let diagnostic = Diagnostic {
+            file: None,
+            code: None,
+            ..diagnostic
+        };
+        assert_eq!(diagnostic.to_string(), "error: mismatched types");
+        assert_eq!(Severity::parse("Warning"), Some(Severity::Warning));
+        assert_eq!(Severity::parse("fatal error"), Some(Severity::Error));
+    }
+}
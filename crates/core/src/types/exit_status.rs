@@ -0,0 +1,120 @@
+//! Exit status of executed processes
+//!
+//! Every execution path (single tasks, groups, monorepo runs and protocol
+//! runs) reports how a process ended as an [`ExitStatus`], and turns it
+//! into an exit code the same way: a process killed by signal N exits with
+//! 128 + N, as shells report it.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Offset added to a signal number to form the exit code
+const SIGNAL_EXIT_OFFSET: i32 = 128;
+
+/// Signal sent by Ctrl-C
+const SIGINT: i32 = 2;
+
+/// How a process ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum ExitStatus {
+    /// The process exited with this code
+    Code(i32),
+    /// The process was terminated by this signal
+    Signal(i32),
+}
+
+impl ExitStatus {
+    pub const SUCCESS: Self = Self::Code(0);
+    /// A run that failed without a process exit status of its own
+    pub const FAILURE: Self = Self::Code(1);
+    /// A run cancelled with Ctrl-C
+    pub const INTERRUPTED: Self = Self::Signal(SIGINT);
+
+    /// The exit code reporting this status, 128 + N for signal N
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Code(code) => code,
+            Self::Signal(signal) => SIGNAL_EXIT_OFFSET + signal,
+        }
+    }
+
+    pub fn success(self) -> bool {
+        self == Self::SUCCESS
+    }
+
+    /// The terminating signal, if any
+    pub fn signal(self) -> Option<i32> {
+        match self {
+            Self::Code(_) => None,
+            Self::Signal(signal) => Some(signal),
+        }
+    }
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return Self::Signal(signal);
+            }
+        }
+        status.code().map_or(Self::FAILURE, Self::Code)
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Code(code) => write!(f, "exit code {code}"),
+            Self::Signal(signal) => match signal_name(*signal) {
+                Some(name) => write!(f, "signal {signal} ({name})"),
+                None => write!(f, "signal {signal}"),
+            },
+        }
+    }
+}
+
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        6 => "SIGABRT",
+        9 => "SIGKILL",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_signal_statuses_map_to_shell_exit_codes() {
+        let status: ExitStatus = Command::new("sh")
+            .args(["-c", "kill -TERM $$"])
+            .status()
+            .unwrap()
+            .into();
+        assert_eq!(status, ExitStatus::Signal(15));
+        assert_eq!(status.code(), 143);
+        assert_eq!(status.to_string(), "signal 15 (SIGTERM)");
+
+        let status: ExitStatus = Command::new("sh")
+            .args(["-c", "exit 3"])
+            .status()
+            .unwrap()
+            .into();
+        assert_eq!(status, ExitStatus::Code(3));
+        assert!(!status.success());
+        assert_eq!(ExitStatus::INTERRUPTED.code(), 130);
+    }
+}
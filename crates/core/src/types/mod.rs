@@ -8,16 +8,22 @@
 //!
 //! - **`capabilities`**: Capability and permission management types
 //! - **`commands`**: Command execution and argument handling types  
+//! - **`diagnostics`**: Problems tasks report in their output
 //! - **`environment`**: Environment variable management types
+//! - **`exit_status`**: How executed processes ended
 //! - **`files`**: File path and validation types
+//! - **`run_id`**: Identifiers of task runs
 //! - **`security`**: Secret handling and security configuration types
 //! - **`shared`**: Common types used across multiple domains
 //! - **`tasks`**: Task execution pipeline and configuration types
 
 pub mod capabilities;
 pub mod commands;
+pub mod diagnostics;
 pub mod environment;
+pub mod exit_status;
 pub mod files;
+pub mod run_id;
 pub mod security;
 pub mod shared;
 pub mod tasks;
@@ -25,8 +31,11 @@ pub mod tasks;
 // Re-export all public types for convenient access
 pub use capabilities::*;
 pub use commands::*;
+pub use diagnostics::*;
 pub use environment::*;
+pub use exit_status::*;
 pub use files::*;
+pub use run_id::*;
 pub use security::*;
 pub use shared::*;
 pub use tasks::*;
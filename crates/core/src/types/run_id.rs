@@ -0,0 +1,89 @@
+//! Identifiers of task runs
+//!
+//! Every invocation of a task gets a [`RunId`], a ULID: 48 bits of
+//! milliseconds since the Unix epoch followed by 80 random bits, written as
+//! 26 Crockford base32 characters. IDs sort by the time they were made, and
+//! IDs made by one process sort in the order they were made, even within the
+//! same millisecond.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RANDOM_BITS: u32 = 80;
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The last ID made by this process
+static LAST: Mutex<u128> = Mutex::new(0);
+
+/// Identifier of one invocation of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RunId(u128);
+
+impl RunId {
+    pub fn new() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let random = uuid::Uuid::new_v4().as_u128() & ((1 << RANDOM_BITS) - 1);
+        let candidate = (millis << RANDOM_BITS) | random;
+
+        let mut last = LAST.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Within a millisecond, or if the clock went back, count up from the
+        // last ID so the order is kept
+        let id = if candidate > *last {
+            candidate
+        } else {
+            *last + 1
+        };
+        *last = id;
+        Self(id)
+    }
+
+    /// Milliseconds since the Unix epoch when the ID was made
+    pub fn timestamp_ms(self) -> u64 {
+        (self.0 >> RANDOM_BITS) as u64
+    }
+}
+
+impl Default for RunId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RunId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 26 characters of 5 bits cover 130 bits; the first takes the top 3
+        let encoded: String = (0..26)
+            .rev()
+            .map(|digit| CROCKFORD[((self.0 >> (digit * 5)) & 0x1f) as usize] as char)
+            .collect();
+        f.write_str(&encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_ids_sort_in_creation_order() {
+        let ids: Vec<RunId> = (0..1000).map(|_| RunId::new()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let encoded: Vec<String> = ids.iter().map(RunId::to_string).collect();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(encoded
+            .iter()
+            .all(|id| id.len() == 26 && id.chars().all(|c| CROCKFORD.contains(&(c as u8)))));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        assert!(now.abs_diff(ids[0].timestamp_ms()) < 60_000);
+        assert_eq!(RunId(0).to_string(), "0".repeat(26));
+    }
+}
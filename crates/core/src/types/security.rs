@@ -1,8 +1,10 @@
 //! Security-related types for secret handling and access control
 
 use crate::errors::{Error, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Type-safe wrapper for secret references
@@ -107,3 +109,53 @@ impl SecretValues {
 }
 
 // Drop is automatically handled by ZeroizeOnDrop derive
+
+/// How strictly a task's security restrictions are enforced
+///
+/// Sandboxing depends on what the kernel and platform support. With
+/// `enforce`, a task whose restrictions cannot be applied fails instead of
+/// running unprotected; `warn` runs it with whatever protection is available
+/// and `off` skips the restrictions altogether.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforcementLevel {
+    Off,
+    Warn,
+    #[default]
+    Enforce,
+}
+
+impl EnforcementLevel {
+    /// The level as written in configuration
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        }
+    }
+}
+
+impl fmt::Display for EnforcementLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EnforcementLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "enforce" => Ok(Self::Enforce),
+            other => Err(Error::configuration(format!(
+                "invalid security enforcement level '{other}': expected off, warn or enforce"
+            ))),
+        }
+    }
+}
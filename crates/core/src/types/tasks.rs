@@ -1,12 +1,19 @@
 //! Task-related types for execution pipeline management
 
+use super::diagnostics::Severity;
+use super::security::EnforcementLevel;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::PathBuf;
 use std::time::Duration;
 
 /// Default task timeout in seconds (1 hour)
 pub const DEFAULT_TASK_TIMEOUT_SECS: u64 = 3600;
 
+/// Default time a task waits for each of its `waitFor` services, in seconds
+pub const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 30;
+
 /// Task execution mode - either command or script
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskExecutionMode {
@@ -61,6 +68,160 @@ pub struct TaskSecurity {
     pub write_only_paths: Vec<PathBuf>,
     /// Allowed network hosts (for fine-grained control)
     pub allowed_hosts: Vec<String>,
+    /// Seccomp profile: `"default"` or an absolute path to a profile file
+    #[serde(default)]
+    pub seccomp_profile: Option<String>,
+    /// Log seccomp violations instead of blocking them
+    #[serde(default)]
+    pub seccomp_audit: bool,
+    /// What happens when a restriction cannot be applied on this platform
+    #[serde(default)]
+    pub enforcement: EnforcementLevel,
+}
+
+/// Identity a task process runs as after dropping privileges (Unix only)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRunAs {
+    /// User name or numeric uid
+    pub user: String,
+    /// Group name or numeric gid (defaults to the user's primary group)
+    pub group: Option<String>,
+}
+
+/// IO scheduling class of a task process (Linux only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    /// Served before everyone else; needs root
+    Realtime,
+    BestEffort,
+    /// Only served when no other process needs the disk
+    Idle,
+}
+
+/// IO priority of a task process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskIoPriority {
+    pub class: IoClass,
+    /// From 0 (highest) to 7 within the class, 4 by default; the idle
+    /// class has no levels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
+}
+
+/// Scheduling and file creation settings applied to a task process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskProcess {
+    /// File mode creation mask, e.g. `0o027`
+    pub umask: Option<u32>,
+    /// Niceness from -20 (favoured) to 19; on Windows mapped to a priority
+    /// class
+    pub nice: Option<i32>,
+    pub io_priority: Option<TaskIoPriority>,
+}
+
+/// Service that must be up before a task's command starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskWaitTarget {
+    /// `host:port` accepting TCP connections
+    Tcp(String),
+    /// URL answering GET requests with a success status
+    Http(String),
+}
+
+/// A `waitFor` entry of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskWait {
+    pub target: TaskWaitTarget,
+    pub timeout: Duration,
+}
+
+/// Artifacts a task publishes after a successful run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskPublish {
+    /// Files or globs to publish, relative to the working directory
+    /// (empty means the task's outputs)
+    pub files: Vec<String>,
+    /// Where the files go
+    pub target: TaskPublishTarget,
+}
+
+/// Destination of published artifacts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskPublishTarget {
+    /// Copy into a directory, relative to the working directory
+    Directory(PathBuf),
+    /// Push as an OCI artifact with `oras`
+    Oci {
+        reference: String,
+        media_type: Option<String>,
+    },
+    /// Upload each file to `<url>/<relative path>`
+    Http {
+        url: String,
+        method: String,
+        headers: BTreeMap<String, String>,
+        /// Environment variable holding a bearer token
+        token_env: Option<String>,
+    },
+}
+
+/// How a task's output is read for diagnostics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskProblemMatcher {
+    /// A matcher cuenv ships, e.g. `rustc` or `eslint`
+    Builtin(String),
+    /// A regex with named groups `message` and optionally `file`, `line`,
+    /// `column`, `severity` and `code`
+    Pattern {
+        pattern: String,
+        /// Severity of matches without a `severity` group
+        severity: Severity,
+    },
+}
+
+/// Type of a task output value, checked when a run of the task produces it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskOutputType {
+    #[default]
+    String,
+    Int,
+    Number,
+    Bool,
+}
+
+impl TaskOutputType {
+    /// Whether `value` is a value of this type
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            Self::Bool => matches!(value, "true" | "false"),
+        }
+    }
+}
+
+impl fmt::Display for TaskOutputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Int => "int",
+            Self::Number => "number",
+            Self::Bool => "bool",
+        })
+    }
+}
+
+/// A named value a task outputs, read from a file the task writes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskOutputValue {
+    pub name: String,
+    /// File the value is read from, relative to the task's working directory
+    pub from_file: String,
+    pub value_type: TaskOutputType,
 }
 
 /// Resolved cache configuration
@@ -73,6 +234,9 @@ pub struct TaskCache {
     pub key: Option<String>,
     /// Environment variable filtering for cache key computation
     pub env_filter: Option<CacheEnvFilter>,
+    /// How long a failed run is reused, failures aren't cached without it
+    #[serde(default)]
+    pub failure_ttl: Option<Duration>,
 }
 
 /// Cache environment variable filtering
@@ -84,6 +248,9 @@ pub struct CacheEnvFilter {
     pub exclude: Vec<String>,
     /// Use smart defaults for common tools
     pub smart_defaults: bool,
+    /// Include every variable of the loaded environment
+    #[serde(default)]
+    pub env_all: bool,
 }
 
 /// Immutable, validated task definition ready for execution
@@ -111,6 +278,25 @@ pub struct TaskDefinition {
     pub cache: TaskCache,
     /// Timeout for execution
     pub timeout: Duration,
+    /// Identity to run the task as
+    #[serde(default)]
+    pub run_as: Option<TaskRunAs>,
+    /// umask, niceness and IO priority of the task process
+    #[serde(default)]
+    pub process: TaskProcess,
+    /// Services to wait for before the command starts, in order
+    #[serde(default)]
+    pub wait_for: Vec<TaskWait>,
+    /// Artifacts to publish once the task succeeds
+    #[serde(default)]
+    pub publish: Vec<TaskPublish>,
+    /// Matchers reading diagnostics from the task's output
+    #[serde(default)]
+    pub problem_matchers: Vec<TaskProblemMatcher>,
+    /// Values the task outputs, which downstream tasks reference as
+    /// `${tasks.<name>.outputs.<value>}`
+    #[serde(default)]
+    pub output_values: Vec<TaskOutputValue>,
 }
 
 impl TaskDefinition {
@@ -132,6 +318,12 @@ impl TaskDefinition {
             security: None,
             cache: TaskCache::default(),
             timeout: Duration::from_secs(DEFAULT_TASK_TIMEOUT_SECS),
+            run_as: None,
+            process: TaskProcess::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         }
     }
 
@@ -1,10 +1,15 @@
 use anyhow::{Context, Result};
-use dirs;
+use cuenv_utils::xdg::XdgPaths;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, trace};
 
+/// Directory holding the cached environments, inside cuenv's cache directory
+fn environments_dir() -> PathBuf {
+    XdgPaths::cache_dir().join("environments")
+}
+
 /// Environment cache for storing evaluated hook outputs
 pub struct EnvCache {
     cache_dir: PathBuf,
@@ -15,12 +20,7 @@ pub struct EnvCache {
 impl EnvCache {
     /// Create a new environment cache for the given project directory
     pub fn new(project_dir: &Path) -> Result<Self> {
-        // Use dirs crate for platform-specific cache location
-        let cache_base = dirs::cache_dir()
-            .or_else(|| dirs::home_dir().map(|h| h.join(".cache")))
-            .context("Could not determine cache directory")?;
-
-        let cache_dir = cache_base.join("cuenv").join("environments");
+        let cache_dir = environments_dir();
         std::fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
 
         // Generate cache key from project path and important files
@@ -187,11 +187,7 @@ impl EnvCache {
 
     /// Clear all caches
     pub fn clear_all() -> Result<()> {
-        let cache_base = dirs::cache_dir()
-            .or_else(|| dirs::home_dir().map(|h| h.join(".cache")))
-            .context("Could not determine cache directory")?;
-
-        let cache_dir = cache_base.join("cuenv").join("environments");
+        let cache_dir = environments_dir();
 
         if cache_dir.exists() {
             std::fs::remove_dir_all(&cache_dir).context("Failed to remove cache directory")?;
@@ -203,11 +199,7 @@ impl EnvCache {
 
     /// Get information about cached environments
     pub fn list_caches() -> Result<Vec<PathBuf>> {
-        let cache_base = dirs::cache_dir()
-            .or_else(|| dirs::home_dir().map(|h| h.join(".cache")))
-            .context("Could not determine cache directory")?;
-
-        let cache_dir = cache_base.join("cuenv").join("environments");
+        let cache_dir = environments_dir();
 
         if !cache_dir.exists() {
             return Ok(Vec::new());
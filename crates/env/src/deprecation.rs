@@ -0,0 +1,187 @@
+//! Detection of deprecated environment variable usage
+//!
+//! Child processes read their environment directly, so access cannot be
+//! observed in general. Instead, commands run through `cuenv exec` are
+//! checked for references to deprecated names in their arguments and, when
+//! the command is a script, in the script itself. `cuenv env lint` applies
+//! the same scanner to project files.
+
+use cuenv_config::VariableMetadata;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Scripts larger than this are not scanned for references
+const MAX_SCRIPT_SIZE: u64 = 1024 * 1024;
+
+/// A variable that should no longer be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedVariable {
+    pub name: String,
+    pub message: String,
+}
+
+/// A reference to a deprecated variable found in some text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableReference<'a> {
+    /// 1-based line number
+    pub line: usize,
+    pub variable: &'a DeprecatedVariable,
+}
+
+/// Collect deprecated and renamed variables from load metadata, sorted by name
+pub fn deprecated_variables(
+    metadata: &HashMap<String, VariableMetadata>,
+) -> Vec<DeprecatedVariable> {
+    let mut variables: Vec<DeprecatedVariable> = metadata
+        .iter()
+        .filter_map(|(name, meta)| {
+            meta.deprecation_message(name)
+                .map(|message| DeprecatedVariable {
+                    name: name.clone(),
+                    message,
+                })
+        })
+        .collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+    variables
+}
+
+/// Find whole-word references to deprecated variables in `text`
+///
+/// A reference is the variable name delimited by characters that cannot
+/// be part of an identifier, which covers `$NAME`, `${NAME}`, `%NAME%`,
+/// `getenv("NAME")` and `process.env.NAME` alike.
+pub fn find_references<'a>(
+    text: &str,
+    variables: &'a [DeprecatedVariable],
+) -> Vec<VariableReference<'a>> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            variables
+                .iter()
+                .filter(move |variable| contains_word(line, &variable.name))
+                .map(move |variable| VariableReference {
+                    line: index + 1,
+                    variable,
+                })
+        })
+        .collect()
+}
+
+/// Deprecated variables referenced by a command about to be executed
+///
+/// Scans the command line and, if the command is a readable text file
+/// (a script), its contents.
+pub fn command_references<'a>(
+    command: &str,
+    args: &[String],
+    variables: &'a [DeprecatedVariable],
+) -> Vec<&'a DeprecatedVariable> {
+    if variables.is_empty() {
+        return Vec::new();
+    }
+
+    let mut text = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some(script) = read_script(command) {
+        text.push('\n');
+        text.push_str(&script);
+    }
+
+    let mut found: Vec<&DeprecatedVariable> = find_references(&text, variables)
+        .into_iter()
+        .map(|reference| reference.variable)
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found.dedup();
+    found
+}
+
+fn read_script(command: &str) -> Option<String> {
+    let path = if command.contains(std::path::MAIN_SEPARATOR) || command.contains('/') {
+        Path::new(command).to_path_buf()
+    } else {
+        which::which(command).ok()?
+    };
+
+    let metadata = fs::metadata(&path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_SCRIPT_SIZE {
+        return None;
+    }
+
+    // Binaries are not valid UTF-8 and are skipped here
+    fs::read_to_string(path).ok()
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    line.match_indices(word).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + word.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn variables() -> Vec<DeprecatedVariable> {
+        let metadata = HashMap::from([
+            (
+                "DB_URL".to_string(),
+                VariableMetadata {
+                    renamed_to: Some("DATABASE_URL".to_string()),
+                    ..Default::default()
+                },
+            ),
+            ("PORT".to_string(), VariableMetadata::default()),
+        ]);
+        deprecated_variables(&metadata)
+    }
+
+    #[test]
+    fn test_deprecated_variables_only_includes_deprecated() {
+        let vars = variables();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[0].name, "DB_URL");
+        assert_eq!(vars[0].message, "DB_URL was renamed to DATABASE_URL");
+    }
+
+    #[test]
+    fn test_find_references_whole_words() {
+        let vars = variables();
+        let text = "echo $DB_URL\nexport MY_DB_URL=1\nurl = os.getenv(\"DB_URL\")\n${DB_URL_2}";
+        let lines: Vec<usize> = find_references(text, &vars)
+            .iter()
+            .map(|reference| reference.line)
+            .collect();
+        assert_eq!(lines, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_command_references_scans_scripts() {
+        let vars = variables();
+        assert_eq!(
+            command_references(
+                "sh",
+                &["-c".to_string(), "echo ${DB_URL}".to_string()],
+                &vars
+            )
+            .len(),
+            1
+        );
+
+        let mut script = tempfile::NamedTempFile::new().unwrap();
+        writeln!(script, "#!/bin/sh\npsql \"$DB_URL\"").unwrap();
+        let path = script.path().to_string_lossy().to_string();
+        assert_eq!(command_references(&path, &[], &vars).len(), 1);
+
+        assert!(command_references("echo", &["DATABASE_URL".to_string()], &vars).is_empty());
+    }
+}
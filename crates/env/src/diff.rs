@@ -1,4 +1,5 @@
 use anyhow::Result;
+use cuenv_utils::portable_path::{path_lists_equivalent, PathCasePolicy};
 use cuenv_utils::sync::env::SyncEnv;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -24,6 +25,30 @@ const IGNORED_VARS: &[&str] = &[
     "CUENV_DIFF",
 ];
 
+/// Whether a variable holds a list of directories (`PATH`, `LD_LIBRARY_PATH`, ...)
+fn is_path_list_var(key: &str) -> bool {
+    key.to_ascii_uppercase().ends_with("PATH")
+}
+
+/// Compare two values of the same variable, treating path lists that only
+/// differ in separators, trailing slashes or Windows casing as equal
+fn values_equivalent(key: &str, prev: &str, next: &str) -> bool {
+    prev == next
+        || (is_path_list_var(key) && path_lists_equivalent(prev, next, PathCasePolicy::FoldWindows))
+}
+
+/// A variable changed again after a diff was applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub key: String,
+    /// Value the diff set, `None` when it removed the variable
+    pub expected: Option<String>,
+    /// Value now, `None` when unset
+    pub current: Option<String>,
+    /// Value before the diff, which reversing it restores
+    pub original: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct EnvDiff {
     /// Environment variables before the change
@@ -58,7 +83,7 @@ impl EnvDiff {
                     // Variable was added
                     result.insert(key.as_str(), value.as_str());
                 }
-                Some(prev_value) if prev_value != value => {
+                Some(prev_value) if !values_equivalent(key, prev_value, value) => {
                     // Variable was changed
                     result.insert(key.as_str(), value.as_str());
                 }
@@ -114,6 +139,32 @@ impl EnvDiff {
         self.added_or_changed().is_empty() && self.removed().is_empty()
     }
 
+    /// Variables this diff changed whose values in `current` are no longer
+    /// the ones it set, by key
+    pub fn divergences(&self, current: &HashMap<String, String>) -> Vec<Divergence> {
+        let changed = self.added_or_changed().into_keys();
+        let mut divergences: Vec<Divergence> = changed
+            .chain(self.removed())
+            .filter_map(|key| {
+                let expected = self.next.get(key);
+                let now = current.get(key);
+                let same = match (expected, now) {
+                    (Some(expected), Some(now)) => values_equivalent(key, expected, now),
+                    (None, None) => true,
+                    _ => false,
+                };
+                (!same).then(|| Divergence {
+                    key: key.to_string(),
+                    expected: expected.cloned(),
+                    current: now.cloned(),
+                    original: self.prev.get(key).cloned(),
+                })
+            })
+            .collect();
+        divergences.sort_by(|a, b| a.key.cmp(&b.key));
+        divergences
+    }
+
     /// Merge another diff into this one
     /// The resulting diff represents going from self.prev to other.next
     pub fn merge(&self, other: &Self) -> Self {
@@ -212,4 +263,50 @@ mod tests {
         let diff = EnvDiff::new(env1, env2);
         assert!(!diff.is_empty());
     }
+
+    #[test]
+    fn test_divergences() {
+        let prev = HashMap::from([
+            ("EDITOR".to_string(), "vi".to_string()),
+            ("GONE".to_string(), "old".to_string()),
+        ]);
+        let next = HashMap::from([
+            ("EDITOR".to_string(), "nano".to_string()),
+            ("API_URL".to_string(), "http://localhost".to_string()),
+            ("KEPT".to_string(), "value".to_string()),
+        ]);
+        let diff = EnvDiff::new(prev, next);
+
+        let current = HashMap::from([
+            ("EDITOR".to_string(), "emacs".to_string()),
+            ("GONE".to_string(), "again".to_string()),
+            ("KEPT".to_string(), "value".to_string()),
+        ]);
+        let divergences = diff.divergences(&current);
+
+        let keys: Vec<&str> = divergences.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(keys, ["API_URL", "EDITOR", "GONE"]);
+        assert_eq!(divergences[0].current, None);
+        assert_eq!(divergences[1].expected.as_deref(), Some("nano"));
+        assert_eq!(divergences[1].original.as_deref(), Some("vi"));
+        assert_eq!(divergences[2].expected, None);
+        assert!(diff.divergences(&diff.next).is_empty());
+    }
+
+    #[test]
+    fn test_path_lists_compare_portably() {
+        let mut prev = HashMap::new();
+        prev.insert("PATH".to_string(), r"C:\Tools\bin;C:\Windows".to_string());
+        prev.insert("PYTHONPATH".to_string(), "/opt/lib".to_string());
+
+        let mut next = HashMap::new();
+        next.insert("PATH".to_string(), "c:/tools/bin/;c:/windows".to_string());
+        next.insert("PYTHONPATH".to_string(), "/opt/lib:/opt/extra".to_string());
+
+        let diff = EnvDiff::new(prev, next);
+        let changes = diff.added_or_changed();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.get("PYTHONPATH"), Some(&"/opt/lib:/opt/extra"));
+    }
 }
@@ -0,0 +1,238 @@
+//! Working tree status from the index
+//!
+//! Each tracked file's size and modification time are compared with what the
+//! index recorded. A file that was touched without changing its content
+//! counts as modified until git refreshes the index, e.g. on `git status`.
+//! The index itself is then compared with HEAD's tree, so staged changes,
+//! additions and removals count too.
+
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use super::objects::{parse_hex, Objects, Oid, MODE_TYPE_MASK, OID_LEN};
+use super::Repository;
+
+const SIGNATURE: &[u8] = b"DIRC";
+const HEADER_LEN: usize = 12;
+/// Stat fields, object hash and flags, up to the path
+const ENTRY_FIXED_LEN: usize = 62;
+
+const FLAG_ASSUME_VALID: u16 = 0x8000;
+const FLAG_EXTENDED: u16 = 0x4000;
+const FLAG_STAGE_MASK: u16 = 0x3000;
+/// Name lengths of 0xfff or more are stored as 0xfff
+const FLAG_NAME_MASK: u16 = 0x0fff;
+const EXTENDED_SKIP_WORKTREE: u16 = 0x4000;
+
+const MODE_GITLINK: u32 = 0o160000;
+
+/// Extension caching the trees the index would be written as
+const CACHE_TREE: &[u8] = b"TREE";
+
+struct Index {
+    entries: Vec<IndexEntry>,
+    /// Tree matching the whole index, when the cache is up to date
+    tree: Option<Oid>,
+}
+
+struct IndexEntry {
+    path: String,
+    oid: Oid,
+    mtime_secs: u32,
+    mtime_nanos: u32,
+    mode: u32,
+    size: u32,
+    skip: bool,
+    conflicted: bool,
+}
+
+/// Whether any tracked file differs from the index, or the index from the
+/// tree of the `head` commit
+///
+/// An index or tree that cannot be read counts as dirty, since a clean tree
+/// cannot be confirmed.
+pub(super) fn is_dirty(repo: &Repository, head: &str) -> bool {
+    let Ok(data) = std::fs::read(repo.git_dir.join("index")) else {
+        // No index yet: nothing has been staged or checked out
+        return false;
+    };
+
+    match parse(&data) {
+        Some(index) => {
+            index
+                .entries
+                .iter()
+                .any(|entry| is_modified(&repo.work_dir, entry))
+                || is_staged(repo, head, &index)
+        }
+        None => {
+            tracing::debug!("Unreadable git index in {}", repo.git_dir.display());
+            true
+        }
+    }
+}
+
+/// Whether the index differs from the tree of `head`
+fn is_staged(repo: &Repository, head: &str, index: &Index) -> bool {
+    let objects = Objects::new(&repo.common_dir);
+    let Some(tree) = parse_hex(head).and_then(|commit| objects.commit_tree(&commit)) else {
+        tracing::debug!(
+            "Unreadable HEAD commit {head} in {}",
+            repo.git_dir.display()
+        );
+        return true;
+    };
+    if index.tree == Some(tree) {
+        return false;
+    }
+
+    let Some(files) = objects.tree_files(&tree) else {
+        tracing::debug!("Unreadable HEAD tree in {}", repo.git_dir.display());
+        return true;
+    };
+    index.entries.len() != files.len()
+        || index
+            .entries
+            .iter()
+            .any(|entry| files.get(&entry.path) != Some(&(entry.mode, entry.oid)))
+}
+
+fn is_modified(work_dir: &Path, entry: &IndexEntry) -> bool {
+    if entry.conflicted {
+        return true;
+    }
+    if entry.skip || entry.mode & MODE_TYPE_MASK == MODE_GITLINK {
+        return false;
+    }
+
+    let Ok(metadata) = std::fs::symlink_metadata(work_dir.join(&entry.path)) else {
+        return true;
+    };
+    if metadata.len() as u32 != entry.size {
+        return true;
+    }
+
+    let Some(mtime) = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+    else {
+        return true;
+    };
+    // Some filesystems and git builds record whole seconds only
+    mtime.as_secs() as u32 != entry.mtime_secs
+        || (entry.mtime_nanos != 0 && mtime.subsec_nanos() != entry.mtime_nanos)
+}
+
+fn parse(data: &[u8]) -> Option<Index> {
+    if data.get(..4)? != SIGNATURE {
+        return None;
+    }
+    let version = read_u32(data, 4)?;
+    let count = read_u32(data, 8)? as usize;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+
+    let mut entries = Vec::with_capacity(count);
+    let mut offset = HEADER_LEN;
+    let mut previous_path: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        let flags = read_u16(data, offset + ENTRY_FIXED_LEN - 2)?;
+        let mut path_start = offset + ENTRY_FIXED_LEN;
+        let extended = if version >= 3 && flags & FLAG_EXTENDED != 0 {
+            path_start += 2;
+            read_u16(data, offset + ENTRY_FIXED_LEN)?
+        } else {
+            0
+        };
+
+        let (path, next) = if version == 4 {
+            // Path is stored as "drop N bytes of the previous path" + suffix
+            let (strip, suffix_start) = read_varint(data, path_start)?;
+            let suffix_len = data.get(suffix_start..)?.iter().position(|&b| b == 0)?;
+            let keep = previous_path.len().checked_sub(strip)?;
+            let mut path = previous_path[..keep].to_vec();
+            path.extend_from_slice(&data[suffix_start..suffix_start + suffix_len]);
+            (path, suffix_start + suffix_len + 1)
+        } else {
+            let name_len = match flags & FLAG_NAME_MASK {
+                FLAG_NAME_MASK => data.get(path_start..)?.iter().position(|&b| b == 0)?,
+                len => len as usize,
+            };
+            let path = data.get(path_start..path_start + name_len)?.to_vec();
+            // Entries are NUL padded to a multiple of eight bytes
+            let entry_len = (path_start - offset + name_len + 8) & !7;
+            (path, offset + entry_len)
+        };
+
+        entries.push(IndexEntry {
+            path: String::from_utf8_lossy(&path).into_owned(),
+            oid: data
+                .get(offset + 40..offset + 40 + OID_LEN)?
+                .try_into()
+                .ok()?,
+            mtime_secs: read_u32(data, offset + 8)?,
+            mtime_nanos: read_u32(data, offset + 12)?,
+            mode: read_u32(data, offset + 24)?,
+            size: read_u32(data, offset + 36)?,
+            skip: flags & FLAG_ASSUME_VALID != 0 || extended & EXTENDED_SKIP_WORKTREE != 0,
+            conflicted: flags & FLAG_STAGE_MASK != 0,
+        });
+        previous_path = path;
+        offset = next;
+    }
+
+    Some(Index {
+        entries,
+        tree: cached_tree(data, offset),
+    })
+}
+
+/// Root of the cache tree extension, unless a change invalidated it
+fn cached_tree(data: &[u8], mut offset: usize) -> Option<Oid> {
+    // Extensions follow the entries, up to the trailing checksum
+    let end = data.len().checked_sub(OID_LEN)?;
+    while offset + 8 <= end {
+        let size = read_u32(data, offset + 4)? as usize;
+        let body = data.get(offset + 8..offset + 8 + size)?;
+        if &data[offset..offset + 4] == CACHE_TREE {
+            // "<path>\0<entry count> <subtree count>\n<id>", the root first
+            // with an empty path; an invalidated tree has a count of -1
+            let header_len = body.iter().position(|&b| b == b'\n')?;
+            let header = std::str::from_utf8(body.get(1..header_len)?).ok()?;
+            if !body.starts_with(b"\0") || header.starts_with('-') {
+                return None;
+            }
+            return body
+                .get(header_len + 1..header_len + 1 + OID_LEN)?
+                .try_into()
+                .ok();
+        }
+        offset += 8 + size;
+    }
+    None
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Git's offset varint: big-endian 7-bit groups, each continuation adding one
+fn read_varint(data: &[u8], mut offset: usize) -> Option<(usize, usize)> {
+    let mut byte = *data.get(offset)?;
+    let mut value = (byte & 0x7f) as usize;
+    while byte & 0x80 != 0 {
+        offset += 1;
+        byte = *data.get(offset)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+    }
+    Some((value, offset + 1))
+}
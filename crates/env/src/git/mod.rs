@@ -0,0 +1,173 @@
+//! Built-in git metadata variables
+//!
+//! `CUENV_GIT_SHA`, `CUENV_GIT_BRANCH`, `CUENV_GIT_DIRTY` and `CUENV_GIT_TAG`
+//! are read straight from the `.git` directory rather than by running `git`,
+//! so they do not depend on a git binary being on `PATH`. Only the variables
+//! asked for are read, and HEAD is read again on every lookup so values
+//! follow checkouts made while cuenv is running; the tag of a commit is
+//! remembered per repository and HEAD.
+
+mod index;
+mod objects;
+mod refs;
+
+use cuenv_core::{
+    CUENV_GIT_BRANCH_VAR, CUENV_GIT_DIRTY_VAR, CUENV_GIT_SHA_VAR, CUENV_GIT_TAG_VAR,
+    CUENV_GIT_VAR_PREFIX,
+};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Repository state exposed as variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitMetadata {
+    /// Full hash of the checked out commit
+    pub sha: String,
+    /// Checked out branch; `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// Whether tracked files differ from the index, or the index from HEAD
+    pub dirty: bool,
+    /// Tag pointing at the checked out commit
+    pub tag: Option<String>,
+}
+
+impl GitMetadata {
+    /// Read metadata for the repository containing `dir`
+    ///
+    /// Returns `None` outside a repository and before the first commit.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let repo = Repository::discover(dir)?;
+        let (sha, branch) = read_head(&repo)?;
+
+        Some(Self {
+            tag: tag_at(&repo, &sha),
+            dirty: index::is_dirty(&repo, &sha),
+            sha,
+            branch,
+        })
+    }
+
+    /// The metadata as environment variables; unset values are left out
+    pub fn variables(&self) -> HashMap<String, String> {
+        let mut vars = HashMap::from([
+            (CUENV_GIT_SHA_VAR.to_string(), self.sha.clone()),
+            (CUENV_GIT_DIRTY_VAR.to_string(), self.dirty.to_string()),
+        ]);
+        if let Some(branch) = &self.branch {
+            vars.insert(CUENV_GIT_BRANCH_VAR.to_string(), branch.clone());
+        }
+        if let Some(tag) = &self.tag {
+            vars.insert(CUENV_GIT_TAG_VAR.to_string(), tag.clone());
+        }
+        vars
+    }
+}
+
+/// Tag of each checked out commit, by repository and HEAD
+static TAGS: Lazy<DashMap<(PathBuf, String), Option<String>>> = Lazy::new(DashMap::new);
+
+/// Values of the built-in git variables among `names` for the repository
+/// containing `dir`
+///
+/// Other names are ignored, and nothing is read when none are asked for.
+/// Unset values, such as the branch of a detached HEAD, are left out.
+pub fn git_variables<'a>(
+    dir: &Path,
+    names: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, String> {
+    let wanted: BTreeSet<&str> = names
+        .into_iter()
+        .filter(|name| name.starts_with(CUENV_GIT_VAR_PREFIX))
+        .collect();
+    let mut vars = HashMap::new();
+    if wanted.is_empty() {
+        return vars;
+    }
+    let Some(repo) = Repository::discover(dir) else {
+        return vars;
+    };
+    let Some((sha, branch)) = read_head(&repo) else {
+        return vars;
+    };
+
+    for name in wanted {
+        let value = match name {
+            CUENV_GIT_SHA_VAR => Some(sha.clone()),
+            CUENV_GIT_BRANCH_VAR => branch.clone(),
+            CUENV_GIT_DIRTY_VAR => Some(index::is_dirty(&repo, &sha).to_string()),
+            CUENV_GIT_TAG_VAR => TAGS
+                .entry((repo.work_dir.clone(), sha.clone()))
+                .or_insert_with(|| tag_at(&repo, &sha))
+                .clone(),
+            _ => None,
+        };
+        if let Some(value) = value {
+            vars.insert(name.to_string(), value);
+        }
+    }
+    vars
+}
+
+/// Value of a single built-in git variable; the repository is only read for
+/// `CUENV_GIT_*` names
+pub fn lookup_git_variable(dir: &Path, name: &str) -> Option<String> {
+    git_variables(dir, [name]).remove(name)
+}
+
+/// The checked out commit and branch; `None` before the first commit
+fn read_head(repo: &Repository) -> Option<(String, Option<String>)> {
+    match refs::read_head(repo)? {
+        refs::Head::Branch(name) => {
+            let sha = refs::resolve_ref(repo, &name)?;
+            let branch = name.strip_prefix("refs/heads/").map(str::to_string);
+            Some((sha, branch))
+        }
+        refs::Head::Detached(sha) => Some((sha, None)),
+    }
+}
+
+fn tag_at(repo: &Repository, sha: &str) -> Option<String> {
+    refs::tags_at(repo, sha).into_iter().max()
+}
+
+/// Location of a repository on disk
+#[derive(Debug)]
+struct Repository {
+    /// Root of the working tree
+    work_dir: PathBuf,
+    /// Per-worktree git directory (HEAD, index)
+    git_dir: PathBuf,
+    /// Directory shared by all worktrees (refs, objects)
+    common_dir: PathBuf,
+}
+
+impl Repository {
+    fn discover(dir: &Path) -> Option<Self> {
+        dir.ancestors().find_map(|candidate| {
+            let dot_git = candidate.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else {
+                // Worktrees and submodules use a `gitdir: <path>` file
+                let content = std::fs::read_to_string(&dot_git).ok()?;
+                let target = content.strip_prefix("gitdir:")?.trim();
+                candidate.join(target)
+            };
+
+            let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+                .map(|content| git_dir.join(content.trim()))
+                .unwrap_or_else(|_| git_dir.clone());
+
+            Some(Self {
+                work_dir: candidate.to_path_buf(),
+                git_dir,
+                common_dir,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
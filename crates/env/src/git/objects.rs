@@ -0,0 +1,347 @@
+//! Commit and tree objects, loose or packed
+//!
+//! Only SHA-1 repositories with version 2 pack indexes are read. Deltified
+//! pack entries are rebuilt from their bases.
+
+use flate2::read::ZlibDecoder;
+use once_cell::unsync::OnceCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub(super) const OID_LEN: usize = 20;
+pub(super) type Oid = [u8; OID_LEN];
+
+const IDX_SIGNATURE: &[u8] = b"\xfftOc";
+const IDX_FANOUT_LEN: usize = 256 * 4;
+const IDX_LARGE_OFFSET: u32 = 0x8000_0000;
+
+const PACK_OFS_DELTA: u8 = 6;
+const PACK_REF_DELTA: u8 = 7;
+/// Git never writes longer delta chains
+const MAX_DELTA_DEPTH: usize = 4095;
+
+pub(super) const MODE_TYPE_MASK: u32 = 0o170000;
+const MODE_TREE: u32 = 0o040000;
+const MODE_FILE: u32 = 0o100000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+pub(super) struct Object {
+    pub kind: Kind,
+    pub data: Vec<u8>,
+}
+
+/// A repository's object database
+pub(super) struct Objects {
+    dir: PathBuf,
+    /// Pack files and their indexes, read on the first packed lookup
+    packs: OnceCell<Vec<(PathBuf, Vec<u8>)>>,
+}
+
+impl Objects {
+    pub(super) fn new(common_dir: &Path) -> Self {
+        Self {
+            dir: common_dir.join("objects"),
+            packs: OnceCell::new(),
+        }
+    }
+
+    pub(super) fn read(&self, oid: &Oid) -> Option<Object> {
+        self.find(oid, 0)
+    }
+
+    /// Tree of a commit
+    pub(super) fn commit_tree(&self, commit: &Oid) -> Option<Oid> {
+        let object = self
+            .read(commit)
+            .filter(|object| object.kind == Kind::Commit)?;
+        let first_line = object.data.split(|&b| b == b'\n').next()?;
+        parse_hex(std::str::from_utf8(first_line.strip_prefix(b"tree ")?).ok()?)
+    }
+
+    /// Mode and id of every file, symlink and submodule under a tree, by path
+    pub(super) fn tree_files(&self, tree: &Oid) -> Option<HashMap<String, (u32, Oid)>> {
+        let mut files = HashMap::new();
+        self.collect_tree(tree, "", &mut files)?;
+        Some(files)
+    }
+
+    fn collect_tree(
+        &self,
+        tree: &Oid,
+        prefix: &str,
+        files: &mut HashMap<String, (u32, Oid)>,
+    ) -> Option<()> {
+        let object = self.read(tree).filter(|object| object.kind == Kind::Tree)?;
+        // Entries are "<octal mode> <name>\0<raw id>"
+        let mut rest = object.data.as_slice();
+        while !rest.is_empty() {
+            let space = rest.iter().position(|&b| b == b' ')?;
+            let nul = rest.iter().position(|&b| b == 0)?;
+            let mode = u32::from_str_radix(std::str::from_utf8(&rest[..space]).ok()?, 8).ok()?;
+            let name = String::from_utf8_lossy(rest.get(space + 1..nul)?);
+            let oid: Oid = rest.get(nul + 1..nul + 1 + OID_LEN)?.try_into().ok()?;
+            rest = &rest[nul + 1 + OID_LEN..];
+
+            let path = if prefix.is_empty() {
+                name.into_owned()
+            } else {
+                format!("{prefix}/{name}")
+            };
+            match mode & MODE_TYPE_MASK {
+                MODE_TREE => self.collect_tree(&oid, &path, files)?,
+                // Old trees may record group permissions; the index never does
+                MODE_FILE => {
+                    let executable = mode & 0o100 != 0;
+                    let mode = if executable { 0o100755 } else { 0o100644 };
+                    files.insert(path, (mode, oid));
+                }
+                _ => {
+                    files.insert(path, (mode, oid));
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn find(&self, oid: &Oid, depth: usize) -> Option<Object> {
+        self.find_loose(oid).or_else(|| {
+            self.packs().iter().find_map(|(pack, index)| {
+                let offset = pack_offset(index, oid)?;
+                self.read_packed(pack, offset, depth)
+            })
+        })
+    }
+
+    fn find_loose(&self, oid: &Oid) -> Option<Object> {
+        let hex = to_hex(oid);
+        let file = File::open(self.dir.join(&hex[..2]).join(&hex[2..])).ok()?;
+        let mut content = Vec::new();
+        ZlibDecoder::new(file).read_to_end(&mut content).ok()?;
+
+        // Header is "<kind> <size>\0"
+        let nul = content.iter().position(|&b| b == 0)?;
+        let kind = match content.split(|&b| b == b' ').next()? {
+            b"commit" => Kind::Commit,
+            b"tree" => Kind::Tree,
+            b"blob" => Kind::Blob,
+            b"tag" => Kind::Tag,
+            _ => return None,
+        };
+        content.drain(..=nul);
+        Some(Object {
+            kind,
+            data: content,
+        })
+    }
+
+    fn packs(&self) -> &[(PathBuf, Vec<u8>)] {
+        self.packs.get_or_init(|| {
+            let Ok(entries) = std::fs::read_dir(self.dir.join("pack")) else {
+                return Vec::new();
+            };
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "idx"))
+                .filter_map(|path| {
+                    let index = std::fs::read(&path).ok()?;
+                    Some((path.with_extension("pack"), index))
+                })
+                .collect()
+        })
+    }
+
+    fn read_packed(&self, pack: &Path, offset: u64, depth: usize) -> Option<Object> {
+        if depth > MAX_DELTA_DEPTH {
+            return None;
+        }
+        let mut file = File::open(pack).ok()?;
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut reader = BufReader::new(file);
+
+        // Kind in bits 4-6 of the first byte, then the inflated size as a
+        // little-endian varint starting with the low four bits
+        let mut byte = read_byte(&mut reader)?;
+        let kind = (byte >> 4) & 0x7;
+        let mut size = u64::from(byte & 0x0f);
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_byte(&mut reader)?;
+            size |= u64::from(byte & 0x7f).checked_shl(shift)?;
+            shift += 7;
+        }
+
+        let kind = match kind {
+            1 => Kind::Commit,
+            2 => Kind::Tree,
+            3 => Kind::Blob,
+            4 => Kind::Tag,
+            PACK_OFS_DELTA => {
+                // Distance back to the base, in git's offset varint
+                let mut byte = read_byte(&mut reader)?;
+                let mut distance = u64::from(byte & 0x7f);
+                while byte & 0x80 != 0 {
+                    byte = read_byte(&mut reader)?;
+                    distance = distance.checked_add(1)?.checked_shl(7)? | u64::from(byte & 0x7f);
+                }
+                let delta = inflate(reader, size)?;
+                let base = self.read_packed(pack, offset.checked_sub(distance)?, depth + 1)?;
+                return apply_delta(base, &delta);
+            }
+            PACK_REF_DELTA => {
+                let mut base = [0; OID_LEN];
+                reader.read_exact(&mut base).ok()?;
+                let delta = inflate(reader, size)?;
+                let base = self.find(&base, depth + 1)?;
+                return apply_delta(base, &delta);
+            }
+            _ => return None,
+        };
+        Some(Object {
+            kind,
+            data: inflate(reader, size)?,
+        })
+    }
+}
+
+/// Offset of an object in the pack a version 2 index describes
+fn pack_offset(index: &[u8], oid: &Oid) -> Option<u64> {
+    if index.get(..4)? != IDX_SIGNATURE || read_u32(index, 4)? != 2 {
+        return None;
+    }
+    // Cumulative object counts by first byte of the id
+    let fanout = |byte: usize| Some(read_u32(index, 8 + byte * 4)? as usize);
+    let count = fanout(255)?;
+    let mut low = match oid[0] {
+        0 => 0,
+        first => fanout(first as usize - 1)?,
+    };
+    let mut high = fanout(oid[0] as usize)?;
+
+    let ids = 8 + IDX_FANOUT_LEN;
+    let offsets = ids + count * (OID_LEN + 4);
+    while low < high {
+        let middle = (low + high) / 2;
+        let id = index.get(ids + middle * OID_LEN..ids + (middle + 1) * OID_LEN)?;
+        match id.cmp(oid.as_slice()) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => {
+                let offset = read_u32(index, offsets + middle * 4)?;
+                if offset & IDX_LARGE_OFFSET == 0 {
+                    return Some(u64::from(offset));
+                }
+                // Packs over 2 GiB keep large offsets in a table of their own
+                let large = offsets + count * 4 + (offset & !IDX_LARGE_OFFSET) as usize * 8;
+                let bytes = index.get(large..large + 8)?;
+                return Some(u64::from_be_bytes(bytes.try_into().ok()?));
+            }
+        }
+    }
+    None
+}
+
+/// Rebuild an object from its base and a delta of copy and insert instructions
+fn apply_delta(base: Object, delta: &[u8]) -> Option<Object> {
+    let (base_len, offset) = delta_size(delta, 0)?;
+    let (target_len, mut offset) = delta_size(delta, offset)?;
+    if base_len != base.data.len() {
+        return None;
+    }
+
+    let mut data = Vec::new();
+    while let Some(&op) = delta.get(offset) {
+        offset += 1;
+        if op & 0x80 != 0 {
+            // Copy from the base; the low bits say which offset and size
+            // bytes follow, little-endian
+            let mut start = 0;
+            let mut len = 0;
+            for bit in 0..7 {
+                if op & (1 << bit) != 0 {
+                    let byte = *delta.get(offset)? as usize;
+                    offset += 1;
+                    if bit < 4 {
+                        start |= byte << (8 * bit);
+                    } else {
+                        len |= byte << (8 * (bit - 4));
+                    }
+                }
+            }
+            if len == 0 {
+                len = 0x10000;
+            }
+            data.extend_from_slice(base.data.get(start..start.checked_add(len)?)?);
+        } else if op != 0 {
+            let len = op as usize;
+            data.extend_from_slice(delta.get(offset..offset + len)?);
+            offset += len;
+        } else {
+            return None;
+        }
+    }
+
+    (data.len() == target_len).then_some(Object {
+        kind: base.kind,
+        data,
+    })
+}
+
+/// Little-endian varint at the start of a delta
+fn delta_size(delta: &[u8], mut offset: usize) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    let mut shift = 0u32;
+    loop {
+        let byte = *delta.get(offset)?;
+        offset += 1;
+        value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some((value, offset));
+        }
+        shift += 7;
+    }
+}
+
+fn inflate(reader: impl Read, size: u64) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(reader)
+        .take(size)
+        .read_to_end(&mut data)
+        .ok()?;
+    (data.len() as u64 == size).then_some(data)
+}
+
+fn read_byte(reader: &mut impl Read) -> Option<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte).ok()?;
+    Some(byte[0])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+pub(super) fn parse_hex(hex: &str) -> Option<Oid> {
+    if hex.len() != OID_LEN * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut oid = [0; OID_LEN];
+    for (byte, pair) in oid.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(oid)
+}
+
+fn to_hex(oid: &Oid) -> String {
+    oid.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,117 @@
+//! HEAD, branch and tag resolution
+
+use super::objects::{parse_hex, Kind, Objects};
+use super::Repository;
+
+/// Symbolic refs are followed at most this many times
+const MAX_REF_DEPTH: usize = 5;
+
+pub(super) enum Head {
+    /// Full ref name, e.g. `refs/heads/main`
+    Branch(String),
+    Detached(String),
+}
+
+pub(super) fn read_head(repo: &Repository) -> Option<Head> {
+    let content = std::fs::read_to_string(repo.git_dir.join("HEAD")).ok()?;
+    let content = content.trim();
+    Some(match content.strip_prefix("ref:") {
+        Some(name) => Head::Branch(name.trim().to_string()),
+        None => Head::Detached(content.to_string()),
+    })
+}
+
+/// Commit hash a ref points to, following symbolic refs
+pub(super) fn resolve_ref(repo: &Repository, name: &str) -> Option<String> {
+    let mut name = name.to_string();
+    for _ in 0..MAX_REF_DEPTH {
+        let loose = [&repo.git_dir, &repo.common_dir]
+            .iter()
+            .find_map(|dir| std::fs::read_to_string(dir.join(&name)).ok());
+        let value = match loose {
+            Some(content) => content.trim().to_string(),
+            None => {
+                return packed_refs(repo)
+                    .into_iter()
+                    .find(|packed| packed.name == name)
+                    .map(|packed| packed.sha)
+            }
+        };
+        match value.strip_prefix("ref:") {
+            Some(target) => name = target.trim().to_string(),
+            None => return Some(value),
+        }
+    }
+    None
+}
+
+/// Names of tags, lightweight or annotated, that point at `sha`
+pub(super) fn tags_at(repo: &Repository, sha: &str) -> Vec<String> {
+    let packed = packed_refs(repo)
+        .into_iter()
+        .filter(|packed| packed.name.starts_with("refs/tags/"))
+        .filter(|packed| packed.peeled.as_deref().unwrap_or(&packed.sha) == sha)
+        .map(|packed| packed.name);
+
+    let objects = Objects::new(&repo.common_dir);
+    let tags_dir = repo.common_dir.join("refs/tags");
+    let loose = walkdir::WalkDir::new(&tags_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            std::fs::read_to_string(entry.path())
+                .map(|target| peel_tag(&objects, target.trim()) == sha)
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(&repo.common_dir).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        });
+
+    packed
+        .chain(loose)
+        .filter_map(|name| name.strip_prefix("refs/tags/").map(str::to_string))
+        .collect()
+}
+
+struct PackedRef {
+    name: String,
+    sha: String,
+    /// Commit an annotated tag points to (`^` lines)
+    peeled: Option<String>,
+}
+
+fn packed_refs(repo: &Repository) -> Vec<PackedRef> {
+    let Ok(content) = std::fs::read_to_string(repo.common_dir.join("packed-refs")) else {
+        return Vec::new();
+    };
+
+    let mut refs: Vec<PackedRef> = Vec::new();
+    for line in content.lines().filter(|line| !line.starts_with('#')) {
+        if let Some(peeled) = line.strip_prefix('^') {
+            if let Some(last) = refs.last_mut() {
+                last.peeled = Some(peeled.trim().to_string());
+            }
+        } else if let Some((sha, name)) = line.split_once(' ') {
+            refs.push(PackedRef {
+                name: name.trim().to_string(),
+                sha: sha.to_string(),
+                peeled: None,
+            });
+        }
+    }
+    refs
+}
+
+/// Commit an annotated tag object points to; other hashes are returned as-is
+fn peel_tag(objects: &Objects, sha: &str) -> String {
+    parse_hex(sha)
+        .and_then(|oid| objects.read(&oid))
+        .filter(|object| object.kind == Kind::Tag)
+        .and_then(|object| {
+            let target = std::str::from_utf8(&object.data).ok()?.lines().next()?;
+            target.strip_prefix("object ").map(str::to_string)
+        })
+        .unwrap_or_else(|| sha.to_string())
+}
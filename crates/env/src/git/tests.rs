@@ -0,0 +1,173 @@
+//! Tests for git metadata, checked against repositories made by `git` itself
+
+use super::*;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Run git in `dir`; `None` when git is not installed
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "user.name=cuenv",
+            "-c",
+            "user.email=cuenv@example.com",
+        ])
+        .args(["-c", "commit.gpgsign=false", "-c", "tag.gpgsign=false"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    assert!(output.status.success(), "git {args:?} failed");
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn repository() -> Option<TempDir> {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main"])?;
+    std::fs::write(dir.path().join("README.md"), "hello\n").unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/lib.rs"), "fn main() {}\n").unwrap();
+    git(dir.path(), &["add", "."])?;
+    git(dir.path(), &["commit", "-q", "-m", "initial"])?;
+    Some(dir)
+}
+
+#[test]
+fn test_reads_branch_sha_and_dirty_state() {
+    let Some(repo) = repository() else {
+        return;
+    };
+    let nested = repo.path().join("src");
+
+    let metadata = GitMetadata::read(&nested).unwrap();
+    assert_eq!(
+        metadata.sha,
+        git(repo.path(), &["rev-parse", "HEAD"]).unwrap()
+    );
+    assert_eq!(metadata.branch.as_deref(), Some("main"));
+    assert!(!metadata.dirty);
+    assert_eq!(metadata.tag, None);
+
+    std::fs::write(repo.path().join("src/lib.rs"), "fn main() { todo!() }\n").unwrap();
+    assert!(GitMetadata::read(&nested).unwrap().dirty);
+
+    std::fs::remove_file(repo.path().join("README.md")).unwrap();
+    git(repo.path(), &["checkout", "-q", "--", "."]).unwrap();
+    assert!(!GitMetadata::read(&nested).unwrap().dirty);
+}
+
+#[test]
+fn test_staged_changes_are_dirty() {
+    let Some(repo) = repository() else {
+        return;
+    };
+    let dirty = || GitMetadata::read(repo.path()).unwrap().dirty;
+
+    // The worktree matches the index, which no longer matches HEAD
+    std::fs::write(repo.path().join("src/lib.rs"), "fn main() { todo!() }\n").unwrap();
+    git(repo.path(), &["add", "src/lib.rs"]).unwrap();
+    assert!(dirty());
+    git(repo.path(), &["reset", "-q", "--hard"]).unwrap();
+    assert!(!dirty());
+
+    std::fs::write(repo.path().join("NEW.md"), "new\n").unwrap();
+    git(repo.path(), &["add", "NEW.md"]).unwrap();
+    assert!(dirty());
+    git(repo.path(), &["rm", "-q", "--cached", "NEW.md"]).unwrap();
+    assert!(!dirty());
+
+    git(repo.path(), &["rm", "-q", "--cached", "README.md"]).unwrap();
+    assert!(dirty());
+}
+
+#[test]
+fn test_index_compared_with_packed_head() {
+    let Some(repo) = repository() else {
+        return;
+    };
+    // Enough similar trees for the pack to hold deltas; the newest tree is
+    // stored whole, so an older commit is checked out
+    for file in 0..50 {
+        std::fs::write(repo.path().join(format!("src/{file}.rs")), "// file\n").unwrap();
+    }
+    for round in 0..5 {
+        let path = repo.path().join(format!("src/{round}.rs"));
+        std::fs::write(path, format!("// round {round}\n")).unwrap();
+        git(repo.path(), &["add", "."]).unwrap();
+        git(repo.path(), &["commit", "-q", "-m", "round"]).unwrap();
+    }
+    git(repo.path(), &["gc", "-q", "--aggressive"]).unwrap();
+    git(repo.path(), &["checkout", "-q", "--detach", "HEAD~2"]).unwrap();
+    let dirty = || GitMetadata::read(repo.path()).unwrap().dirty;
+
+    // Staging a change and then the original content again leaves the index
+    // as it was but invalidates its cached tree, so HEAD's tree is read
+    std::fs::write(repo.path().join("src/7.rs"), "// changed\n").unwrap();
+    git(repo.path(), &["add", "src/7.rs"]).unwrap();
+    assert!(dirty());
+    std::fs::write(repo.path().join("src/7.rs"), "// file\n").unwrap();
+    git(repo.path(), &["add", "src/7.rs"]).unwrap();
+    assert!(!dirty());
+}
+
+#[test]
+fn test_reads_tags_and_detached_head() {
+    let Some(repo) = repository() else {
+        return;
+    };
+    git(repo.path(), &["tag", "v1.0.0"]).unwrap();
+    git(repo.path(), &["tag", "-a", "v1.1.0", "-m", "release"]).unwrap();
+
+    let metadata = GitMetadata::read(repo.path()).unwrap();
+    assert_eq!(metadata.tag.as_deref(), Some("v1.1.0"));
+
+    // Packed refs and peeled annotated tags resolve the same way
+    git(repo.path(), &["pack-refs", "--all"]).unwrap();
+    git(repo.path(), &["checkout", "-q", "--detach"]).unwrap();
+    let packed = GitMetadata::read(repo.path()).unwrap();
+    assert_eq!(packed.sha, metadata.sha);
+    assert_eq!(packed.branch, None);
+    assert_eq!(packed.tag.as_deref(), Some("v1.1.0"));
+
+    let vars = packed.variables();
+    assert_eq!(
+        vars.get(CUENV_GIT_DIRTY_VAR).map(String::as_str),
+        Some("false")
+    );
+    assert!(!vars.contains_key(CUENV_GIT_BRANCH_VAR));
+}
+
+#[test]
+fn test_outside_repository() {
+    let dir = TempDir::new().unwrap();
+    assert_eq!(GitMetadata::read(dir.path()), None);
+    assert_eq!(lookup_git_variable(dir.path(), CUENV_GIT_SHA_VAR), None);
+    assert_eq!(lookup_git_variable(dir.path(), "HOME"), None);
+}
+
+#[test]
+fn test_variables_follow_head() {
+    let Some(repo) = repository() else {
+        return;
+    };
+    let first = git(repo.path(), &["rev-parse", "HEAD"]).unwrap();
+    let vars = git_variables(repo.path(), [CUENV_GIT_SHA_VAR, "HOME"]);
+    assert_eq!(
+        vars,
+        HashMap::from([(CUENV_GIT_SHA_VAR.to_string(), first)])
+    );
+
+    git(repo.path(), &["checkout", "-q", "-b", "feature"]).unwrap();
+    std::fs::write(repo.path().join("README.md"), "changed\n").unwrap();
+    git(repo.path(), &["commit", "-q", "-am", "second"]).unwrap();
+    assert_eq!(
+        lookup_git_variable(repo.path(), CUENV_GIT_SHA_VAR),
+        git(repo.path(), &["rev-parse", "HEAD"])
+    );
+    assert_eq!(
+        lookup_git_variable(repo.path(), CUENV_GIT_BRANCH_VAR).as_deref(),
+        Some("feature")
+    );
+    assert!(git_variables(repo.path(), ["HOME"]).is_empty());
+}
@@ -4,7 +4,10 @@
 //! and caching of environment state.
 
 pub mod cache;
+pub mod deprecation;
 pub mod diff;
+pub mod git;
+pub mod lock;
 pub mod manager;
 pub mod source_parser;
 pub mod state;
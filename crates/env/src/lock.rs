@@ -0,0 +1,547 @@
+//! `cuenv.lock`: what an environment resolved to when it was locked
+//!
+//! `cuenv lock` writes, next to `env.cue`:
+//!
+//! - the version each secret provider reports in its handshake
+//! - the revision of each secret reference, for providers that version secrets
+//! - the executable each hook command and provider resolves to on `PATH`,
+//!   with its SHA-256
+//! - the input hash of each hook, as used for the hook cache
+//!
+//! Loading an environment with a lockfile compares its secret references,
+//! tools and hooks with the locked ones. Provider versions and revisions are
+//! only known once a secret is resolved, so they are compared then. Drift is
+//! a warning, or an error with `config: lockDrift: "fail"`.
+
+use crate::manager::environment::supervisor::calculate_input_hash;
+use crate::manager::secrets::{
+    is_secret_reference, provider_timeout, resolve_with_provider, ProviderReference,
+    ProviderResolution,
+};
+use cuenv_config::{Hook, ParseResult};
+use cuenv_core::constants::CUENV_LOCKFILE_NAME;
+use cuenv_core::{Error, Result};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Version of the lockfile format written by this build of cuenv
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Contents of `cuenv.lock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    /// By provider name
+    #[serde(default)]
+    pub providers: BTreeMap<String, LockedProvider>,
+    /// By secret reference
+    #[serde(default)]
+    pub secrets: BTreeMap<String, LockedSecret>,
+    /// By command name
+    #[serde(default)]
+    pub tools: BTreeMap<String, LockedTool>,
+    /// By hook type and position, e.g. `onEnter[0]`
+    #[serde(default)]
+    pub hooks: BTreeMap<String, LockedHook>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            providers: BTreeMap::new(),
+            secrets: BTreeMap::new(),
+            tools: BTreeMap::new(),
+            hooks: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedProvider {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedSecret {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedTool {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedHook {
+    pub command: String,
+    pub input_hash: String,
+}
+
+/// What to do when an environment drifted from its lockfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DriftMode {
+    #[default]
+    Warn,
+    Fail,
+}
+
+impl DriftMode {
+    /// The mode set by `config: lockDrift` of `parse_result`
+    pub fn of(parse_result: &ParseResult) -> Self {
+        match parse_result
+            .config
+            .as_ref()
+            .and_then(|config| config.lock_drift.as_deref())
+        {
+            Some("fail") => Self::Fail,
+            _ => Self::Warn,
+        }
+    }
+}
+
+/// Path of the lockfile of the package in `dir`
+pub fn lockfile_path(dir: &Path) -> PathBuf {
+    dir.join(CUENV_LOCKFILE_NAME)
+}
+
+/// Secret references among the variables of `parse_results`
+pub fn secret_references<'a>(
+    parse_results: impl IntoIterator<Item = &'a ParseResult>,
+) -> BTreeSet<String> {
+    parse_results
+        .into_iter()
+        .flat_map(|result| result.variables.values())
+        .filter(|value| is_secret_reference(value))
+        .cloned()
+        .collect()
+}
+
+impl Lockfile {
+    /// Read the lockfile of the package in `dir`, if it has one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = lockfile_path(dir);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::file_system(&path, "read lockfile", e)),
+        };
+        let lockfile: Self = serde_json::from_str(&content).map_err(|e| Error::Json {
+            message: format!("invalid lockfile {}", path.display()),
+            source: e,
+        })?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(Error::configuration(format!(
+                "{} has version {}, this cuenv reads up to version {LOCKFILE_VERSION}",
+                path.display(),
+                lockfile.version
+            )));
+        }
+        Ok(Some(lockfile))
+    }
+
+    /// Write the lockfile of the package in `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = lockfile_path(dir);
+        let content = serde_json::to_string_pretty(self).map_err(|e| Error::Json {
+            message: "failed to serialize lockfile".to_string(),
+            source: e,
+        })?;
+        std::fs::write(&path, format!("{content}\n"))
+            .map_err(|e| Error::file_system(&path, "write lockfile", e))
+    }
+
+    /// Lock `references` and `hooks` of the package in `dir` as they resolve
+    /// now, running the providers of the references
+    pub fn generate(
+        dir: &Path,
+        references: &BTreeSet<String>,
+        hooks: &HashMap<String, Vec<Hook>>,
+    ) -> Result<Self> {
+        Self::snapshot(dir, references, hooks, |reference| {
+            resolve_with_provider(reference, provider_timeout()).map(Some)
+        })
+    }
+
+    /// Like [`Lockfile::generate`], without running providers: provider
+    /// versions and revisions are left unknown
+    fn unresolved(
+        dir: &Path,
+        references: &BTreeSet<String>,
+        hooks: &HashMap<String, Vec<Hook>>,
+    ) -> Result<Self> {
+        Self::snapshot(dir, references, hooks, |_| Ok(None))
+    }
+
+    fn snapshot(
+        dir: &Path,
+        references: &BTreeSet<String>,
+        hooks: &HashMap<String, Vec<Hook>>,
+        resolve: impl Fn(&ProviderReference) -> Result<Option<ProviderResolution>>,
+    ) -> Result<Self> {
+        let mut lockfile = Self::default();
+
+        for value in references {
+            let resolution = match ProviderReference::parse(value) {
+                Some(reference) => {
+                    let executable = reference.executable_name();
+                    if let Ok(tool) = which::which(&executable) {
+                        lockfile.tools.insert(executable, lock_tool(&tool)?);
+                    }
+                    let resolution = resolve(&reference)?;
+                    lockfile.providers.insert(
+                        reference.provider,
+                        LockedProvider {
+                            version: resolution
+                                .as_ref()
+                                .and_then(|resolution| resolution.provider_version.clone()),
+                        },
+                    );
+                    resolution
+                }
+                None => None,
+            };
+            lockfile.secrets.insert(
+                value.clone(),
+                LockedSecret {
+                    revision: resolution.and_then(|resolution| resolution.revision),
+                },
+            );
+        }
+
+        for (hook_type, hooks) in hooks {
+            for (index, hook) in hooks.iter().enumerate() {
+                if hook.fetch.is_some() || hook.command.is_empty() {
+                    continue;
+                }
+                if let Ok(tool) = which::which(&hook.command) {
+                    lockfile
+                        .tools
+                        .insert(hook.command.clone(), lock_tool(&tool)?);
+                }
+                lockfile.hooks.insert(
+                    format!("{hook_type}[{index}]"),
+                    LockedHook {
+                        command: hook.command.clone(),
+                        input_hash: calculate_input_hash(std::slice::from_ref(hook), dir)?,
+                    },
+                );
+            }
+        }
+
+        Ok(lockfile)
+    }
+
+    /// How `current` differs from this lockfile
+    ///
+    /// Entries missing from `current` are only reported when it covers the
+    /// whole package, `complete`, rather than one environment of it. Unknown
+    /// provider versions and revisions are never reported.
+    pub fn drift(&self, current: &Self, complete: bool) -> Vec<Drift> {
+        let mut drift = Vec::new();
+        compare(
+            "provider",
+            &self.providers,
+            &current.providers,
+            complete,
+            |locked, current| current.version.is_none() || current.version == locked.version,
+            &mut drift,
+        );
+        compare(
+            "secret",
+            &self.secrets,
+            &current.secrets,
+            complete,
+            |locked, current| current.revision.is_none() || current.revision == locked.revision,
+            &mut drift,
+        );
+        compare(
+            "tool",
+            &self.tools,
+            &current.tools,
+            complete,
+            PartialEq::eq,
+            &mut drift,
+        );
+        compare(
+            "hook",
+            &self.hooks,
+            &current.hooks,
+            complete,
+            PartialEq::eq,
+            &mut drift,
+        );
+        drift
+    }
+}
+
+/// One way an environment differs from its lockfile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub kind: &'static str,
+    pub name: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Not in the lockfile
+    Added,
+    /// In the lockfile but no longer used
+    Removed,
+    Changed {
+        locked: String,
+        current: String,
+    },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { kind, name, change } = self;
+        match change {
+            Change::Added => write!(f, "{kind} '{name}' is not locked"),
+            Change::Removed => write!(f, "{kind} '{name}' is locked but no longer used"),
+            Change::Changed { locked, current } => {
+                write!(f, "{kind} '{name}' changed from {locked} to {current}")
+            }
+        }
+    }
+}
+
+/// How a locked entry shows in drift messages
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for LockedProvider {
+    fn describe(&self) -> String {
+        format!("version {}", self.version.as_deref().unwrap_or("unknown"))
+    }
+}
+
+impl Describe for LockedSecret {
+    fn describe(&self) -> String {
+        format!("revision {}", self.revision.as_deref().unwrap_or("unknown"))
+    }
+}
+
+impl Describe for LockedTool {
+    fn describe(&self) -> String {
+        format!("{} ({})", self.path.display(), short_hash(&self.sha256))
+    }
+}
+
+impl Describe for LockedHook {
+    fn describe(&self) -> String {
+        format!("{} ({})", self.command, short_hash(&self.input_hash))
+    }
+}
+
+fn short_hash(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+fn compare<T: Describe>(
+    kind: &'static str,
+    locked: &BTreeMap<String, T>,
+    current: &BTreeMap<String, T>,
+    complete: bool,
+    same: impl Fn(&T, &T) -> bool,
+    drift: &mut Vec<Drift>,
+) {
+    for (name, entry) in current {
+        let change = match locked.get(name) {
+            None => Change::Added,
+            Some(locked) if !same(locked, entry) => Change::Changed {
+                locked: locked.describe(),
+                current: entry.describe(),
+            },
+            Some(_) => continue,
+        };
+        drift.push(Drift {
+            kind,
+            name: name.clone(),
+            change,
+        });
+    }
+    if complete {
+        drift.extend(
+            locked
+                .keys()
+                .filter(|name| !current.contains_key(*name))
+                .map(|name| Drift {
+                    kind,
+                    name: name.clone(),
+                    change: Change::Removed,
+                }),
+        );
+    }
+}
+
+/// Path and SHA-256 of the executable at `path`
+fn lock_tool(path: &Path) -> Result<LockedTool> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut file =
+        std::fs::File::open(&path).map_err(|e| Error::file_system(&path, "open tool", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| Error::file_system(&path, "hash tool", e))?;
+    Ok(LockedTool {
+        sha256: format!("{:x}", hasher.finalize()),
+        path,
+    })
+}
+
+/// The lockfile of the loaded environment, checked when secrets resolve
+struct ActiveLock {
+    lockfile: Lockfile,
+    mode: DriftMode,
+}
+
+static ACTIVE: RwLock<Option<ActiveLock>> = RwLock::new(None);
+
+/// Compare the environment loaded from `dir` with its lockfile, if it has one
+///
+/// Fails on drift with `lockDrift: "fail"`, otherwise prints a warning for
+/// each difference. The lockfile stays active so secrets resolved later are
+/// checked against it too.
+pub fn verify(dir: &Path, parse_result: &ParseResult) -> Result<()> {
+    let Some(lockfile) = Lockfile::load(dir)? else {
+        *ACTIVE.write() = None;
+        return Ok(());
+    };
+    let mode = DriftMode::of(parse_result);
+
+    let current =
+        Lockfile::unresolved(dir, &secret_references([parse_result]), &parse_result.hooks)?;
+    let drift = lockfile.drift(&current, false);
+    *ACTIVE.write() = Some(ActiveLock { lockfile, mode });
+    report(&drift, mode)
+}
+
+/// Compare what a provider reported for `value` with the active lockfile
+pub(crate) fn check_resolution(value: &str, resolution: &ProviderResolution) -> Result<()> {
+    let active = ACTIVE.read();
+    let Some(ActiveLock { lockfile, mode }) = active.as_ref() else {
+        return Ok(());
+    };
+    let Some(reference) = ProviderReference::parse(value) else {
+        return Ok(());
+    };
+
+    let mut current = Lockfile::default();
+    current.providers.insert(
+        reference.provider,
+        LockedProvider {
+            version: resolution.provider_version.clone(),
+        },
+    );
+    current.secrets.insert(
+        value.to_string(),
+        LockedSecret {
+            revision: resolution.revision.clone(),
+        },
+    );
+    report(&lockfile.drift(&current, false), *mode)
+}
+
+fn report(drift: &[Drift], mode: DriftMode) -> Result<()> {
+    if drift.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<String> = drift.iter().map(ToString::to_string).collect();
+    match mode {
+        DriftMode::Fail => Err(Error::configuration(format!(
+            "Environment drifted from {CUENV_LOCKFILE_NAME}: {}. Run 'cuenv lock' to update it",
+            messages.join("; ")
+        ))),
+        DriftMode::Warn => {
+            for message in messages {
+                eprintln!("# cuenv: {CUENV_LOCKFILE_NAME}: {message}");
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockfile(revision: Option<&str>, input_hash: &str) -> Lockfile {
+        let mut lockfile = Lockfile::default();
+        lockfile.secrets.insert(
+            "cuenv-provider://vault/db".to_string(),
+            LockedSecret {
+                revision: revision.map(str::to_string),
+            },
+        );
+        lockfile.hooks.insert(
+            "onEnter[0]".to_string(),
+            LockedHook {
+                command: "nix".to_string(),
+                input_hash: input_hash.to_string(),
+            },
+        );
+        lockfile
+    }
+
+    #[test]
+    fn test_drift_reports_changes() {
+        let locked = lockfile(Some("7"), "aaaa");
+
+        assert!(locked.drift(&lockfile(Some("7"), "aaaa"), true).is_empty());
+        // Revisions are unknown until a secret is resolved
+        assert!(locked.drift(&lockfile(None, "aaaa"), true).is_empty());
+
+        let drift = locked.drift(&lockfile(Some("8"), "bbbb"), true);
+        let messages: Vec<String> = drift.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "secret 'cuenv-provider://vault/db' changed from revision 7 to revision 8",
+                "hook 'onEnter[0]' changed from nix (aaaa) to nix (bbbb)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_removed_entries_only_drift_when_complete() {
+        let locked = lockfile(Some("7"), "aaaa");
+        let mut current = lockfile(Some("7"), "aaaa");
+        current.secrets.clear();
+        current.secrets.insert(
+            "cuenv-provider://vault/api".to_string(),
+            LockedSecret { revision: None },
+        );
+
+        let drift = locked.drift(&current, false);
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].change, Change::Added);
+
+        let drift = locked.drift(&current, true);
+        assert_eq!(drift.len(), 2);
+        assert_eq!(drift[1].name, "cuenv-provider://vault/db");
+        assert_eq!(drift[1].change, Change::Removed);
+    }
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Lockfile::load(dir.path()).unwrap().is_none());
+
+        let locked = lockfile(Some("7"), "aaaa");
+        locked.save(dir.path()).unwrap();
+        assert_eq!(Lockfile::load(dir.path()).unwrap(), Some(locked));
+
+        std::fs::write(lockfile_path(dir.path()), r#"{"version": 99}"#).unwrap();
+        assert!(Lockfile::load(dir.path()).is_err());
+    }
+}
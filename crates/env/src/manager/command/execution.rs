@@ -1,12 +1,13 @@
-use cuenv_core::{Error, Result};
-use std::collections::{HashMap, HashSet};
+use cuenv_core::events::{global_timeline, SpanKind};
+use cuenv_core::redaction::RedactingWriter;
+use cuenv_core::{Error, ExitStatus, Result};
+use std::collections::HashMap;
 use std::io::{self, BufReader};
 use std::process::{Command, Stdio};
-use std::sync::{Arc, RwLock};
 
 use super::output::wait_for_output_threads;
-use crate::manager::secrets::resolve_secret;
-use crate::manager::stubs::{OutputFilter, Platform};
+use crate::manager::secrets::{is_secret_reference, resolve_secret};
+use crate::manager::stubs::Platform;
 
 /// Setup environment variables for command execution
 pub fn setup_command_environment(
@@ -23,7 +24,14 @@ pub fn setup_command_environment(
     // Resolve secrets in the merged environment
     let mut resolved_env = HashMap::new();
     for (key, value) in base_env {
-        let resolved_value = match resolve_secret(&value) {
+        let span = is_secret_reference(&value)
+            .then(|| global_timeline().start(SpanKind::Secrets, format!("resolve {key}")));
+        let resolved = resolve_secret(&value);
+        if let Some(span) = span {
+            span.finish(&resolved);
+        }
+
+        let resolved_value = match resolved {
             Ok(v) => v,
             Err(e) => {
                 tracing::warn!("Failed to resolve secret for {}: {}", key, e);
@@ -132,13 +140,9 @@ fn execute_command_with_options(
                 ));
             }
         };
-        Ok(status.code().unwrap_or(1))
+        Ok(ExitStatus::from(status).code())
     } else {
-        // For regular commands: handle output filtering
-        let secret_set = HashSet::new();
-        let secrets = Arc::new(RwLock::new(secret_set));
-
-        // Set up filtered output streams
+        // For regular commands: mask secrets in the output
         let stdout = match child.stdout.take() {
             Some(s) => s,
             None => {
@@ -162,17 +166,14 @@ fn execute_command_with_options(
             }
         };
 
-        let stdout_secrets = Arc::clone(&secrets);
-        let stderr_secrets = Arc::clone(&secrets);
-
-        // Spawn threads to handle output filtering
+        // Spawn threads masking secrets in the output
         let stdout_thread = std::thread::spawn(move || {
-            let mut filter = OutputFilter::new(io::stdout(), stdout_secrets);
+            let mut filter = RedactingWriter::new(io::stdout());
             io::copy(&mut BufReader::new(stdout), &mut filter)
         });
 
         let stderr_thread = std::thread::spawn(move || {
-            let mut filter = OutputFilter::new(io::stderr(), stderr_secrets);
+            let mut filter = RedactingWriter::new(io::stderr());
             io::copy(&mut BufReader::new(stderr), &mut filter)
         });
 
@@ -192,6 +193,6 @@ fn execute_command_with_options(
         // Wait for output threads to complete
         wait_for_output_threads(stdout_thread, stderr_thread, command, args, status.code())?;
 
-        Ok(status.code().unwrap_or(1))
+        Ok(ExitStatus::from(status).code())
     }
 }
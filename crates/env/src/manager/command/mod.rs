@@ -1,10 +1,10 @@
 mod execution;
 mod output;
 
-use cuenv_core::Result;
+use cuenv_core::{ExitStatus, Result};
 use std::collections::HashMap;
 
-use crate::manager::stubs::AccessRestrictions;
+use cuenv_security::{AccessRestrictions, AuditReport};
 use execution::{execute_command, execute_command_direct, setup_command_environment};
 use output::wait_for_output_threads;
 
@@ -32,6 +32,24 @@ pub fn run_command_direct(
     execute_command_direct(command, args, final_env)
 }
 
+/// Run a command under strace, collecting the files and hosts it accesses
+pub fn audit_command(
+    command: &str,
+    args: &[String],
+    sourced_env: &HashMap<String, String>,
+    cue_vars: &HashMap<String, String>,
+    original_env: &HashMap<String, String>,
+) -> Result<(i32, AuditReport)> {
+    let final_env = setup_command_environment(sourced_env, cue_vars, original_env);
+
+    let mut cmd = std::process::Command::new(command);
+    cmd.args(args).env_clear().envs(&final_env);
+
+    AccessRestrictions::default()
+        .run_with_audit(&mut cmd)
+        .map(|(status, report)| (status.code(), report))
+}
+
 /// Run a command with access restrictions in a hermetic environment
 pub fn run_command_with_restrictions(
     command: &str,
@@ -69,14 +87,8 @@ pub fn run_command_with_restrictions(
     };
 
     // Handle output streams
-    use std::collections::HashSet;
+    use cuenv_core::redaction::RedactingWriter;
     use std::io::{self, BufReader};
-    use std::sync::{Arc, RwLock};
-
-    use crate::manager::stubs::OutputFilter;
-
-    let secret_set = HashSet::new();
-    let secrets = Arc::new(RwLock::new(secret_set));
 
     let stdout = match child.stdout.take() {
         Some(s) => s,
@@ -101,17 +113,14 @@ pub fn run_command_with_restrictions(
         }
     };
 
-    let stdout_secrets = Arc::clone(&secrets);
-    let stderr_secrets = Arc::clone(&secrets);
-
-    // Spawn threads to handle output filtering
+    // Spawn threads masking secrets in the output
     let stdout_thread = std::thread::spawn(move || {
-        let mut filter = OutputFilter::new(io::stdout(), stdout_secrets);
+        let mut filter = RedactingWriter::new(io::stdout());
         io::copy(&mut BufReader::new(stdout), &mut filter)
     });
 
     let stderr_thread = std::thread::spawn(move || {
-        let mut filter = OutputFilter::new(io::stderr(), stderr_secrets);
+        let mut filter = RedactingWriter::new(io::stderr());
         io::copy(&mut BufReader::new(stderr), &mut filter)
     });
 
@@ -131,5 +140,5 @@ pub fn run_command_with_restrictions(
     // Wait for output threads to complete
     wait_for_output_threads(stdout_thread, stderr_thread, command, args, status.code())?;
 
-    Ok(status.code().unwrap_or(1))
+    Ok(ExitStatus::from(status).code())
 }
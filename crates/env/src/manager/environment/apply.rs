@@ -1,70 +1,74 @@
-use cuenv_config::ParseOptions;
+use cuenv_core::redaction::global_redactor;
 use cuenv_core::{Error, Result};
 use cuenv_utils::sync::env::SyncEnv;
-use cuenv_utils::FileTimes;
 use std::collections::HashMap;
 use std::path::Path;
 
 use crate::diff::EnvDiff;
+use crate::git::lookup_git_variable;
+use crate::state::history::EnvHistory;
 use crate::state::StateManager;
+use crate::watcher::WatchSet;
 
-/// Apply merged environment variables (sourced + CUE)
+/// Variables of a loaded environment
+pub struct LoadedVariables {
+    /// Sourced and CUE variables, shell-expanded when applied
+    pub merged: HashMap<String, String>,
+    /// Variables imported from configuration stores, set as they are since
+    /// the stores hold literal values
+    pub imported: HashMap<String, String>,
+}
+
+/// Where a loaded environment is applied besides the manager's variables
+#[derive(Debug, Clone, Copy)]
+pub struct ApplyTargets {
+    /// Record the environment in the shell state
+    pub persist_state: bool,
+    /// Set the variables in this process's environment
+    pub export_to_process: bool,
+}
+
+/// Apply merged environment variables (sourced + CUE) and imported ones
 pub async fn apply_merged_environment(
     dir: &Path,
-    variables: HashMap<String, String>,
-    _options: &ParseOptions,
+    variables: LoadedVariables,
+    watches: WatchSet,
     has_sourced_env: bool,
     original_env: &HashMap<String, String>,
     cue_vars: &mut HashMap<String, String>,
+    targets: ApplyTargets,
 ) -> Result<()> {
     // Build the new environment
     let mut new_env = original_env.clone();
     cue_vars.clear();
 
-    for (key, value) in variables {
-        // Skip shell expansion for nix-sourced variables that contain unexpandable references
-        // These will be expanded by the shell when the command runs
-        let final_value = if has_sourced_env && value.contains("$NIX_BUILD_TOP") {
-            // Don't expand nix-specific variables, they'll be set by the shell
-            value.clone()
-        } else {
-            // Try to expand other variables
-            match shellexpand::full(&value) {
-                Ok(expanded) => expanded.to_string(),
-                Err(e) => {
-                    // If expansion fails and it's a nix variable, just use it as-is
-                    if has_sourced_env && value.contains('$') {
-                        tracing::debug!(
-                            "Skipping expansion for {key}={value} (will be expanded at runtime)"
-                        );
-                        value.clone()
-                    } else {
-                        return Err(Error::shell_expansion(
-                            &value,
-                            format!("Failed to expand value for {key}: {e}"),
-                        ));
-                    }
-                }
-            }
-        };
-
-        tracing::debug!("Setting {key}={final_value}");
+    let values = variables
+        .merged
+        .into_iter()
+        .map(|(key, value)| {
+            expand_value(dir, &key, value, has_sourced_env).map(|expanded| (key, expanded))
+        })
+        .chain(variables.imported.into_iter().map(Ok));
+    for value in values {
+        let (key, final_value) = value?;
+        tracing::debug!("Setting {key}={}", global_redactor().redact(&final_value));
         new_env.insert(key.clone(), final_value.clone());
         cue_vars.insert(key.clone(), final_value.clone());
-        SyncEnv::set_var(key, final_value).map_err(|e| Error::Configuration {
-            message: format!("Failed to set environment variable: {e}"),
-        })?;
+        if targets.export_to_process {
+            SyncEnv::set_var(key, final_value).map_err(|e| Error::Configuration {
+                message: format!("Failed to set environment variable: {e}"),
+            })?;
+        }
+    }
+
+    if !targets.persist_state {
+        return Ok(());
     }
 
     // Create environment diff
     let diff = EnvDiff::new(original_env.clone(), new_env);
 
-    // Create file watches
-    let mut watches = FileTimes::new();
     let env_cue = dir.join("env.cue");
-    if env_cue.exists() {
-        watches.watch(&env_cue);
-    }
 
     // Save state with all required parameters
     let environment = SyncEnv::var("CUENV_ENV")
@@ -85,5 +89,53 @@ pub async fn apply_merged_environment(
     )
     .await?;
 
+    // Keep the applied variables for `cuenv env history` and `rollback`
+    let applied: HashMap<String, String> = diff
+        .added_or_changed()
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    if let Err(e) = EnvHistory::load(dir).record(environment.as_deref(), &applied) {
+        tracing::warn!("Failed to record environment history: {e}");
+    }
+
     Ok(())
 }
+
+/// Expand variables in `value` the way a shell would
+fn expand_value(dir: &Path, key: &str, value: String, has_sourced_env: bool) -> Result<String> {
+    // Skip shell expansion for nix-sourced variables that contain unexpandable references
+    // These will be expanded by the shell when the command runs
+    if has_sourced_env && value.contains("$NIX_BUILD_TOP") {
+        // Don't expand nix-specific variables, they'll be set by the shell
+        return Ok(value);
+    }
+
+    // Try to expand other variables; built-in git variables are only
+    // read from the repository when a value refers to one
+    let expanded = shellexpand::full_with_context(
+        &value,
+        || dirs::home_dir().map(|home| home.to_string_lossy().into_owned()),
+        |name| match lookup_git_variable(dir, name) {
+            Some(git_value) => Ok(Some(git_value)),
+            None => std::env::var(name).map(Some),
+        },
+    );
+    match expanded {
+        Ok(expanded) => Ok(expanded.to_string()),
+        Err(e) => {
+            // If expansion fails and it's a nix variable, just use it as-is
+            if has_sourced_env && value.contains('$') {
+                tracing::debug!(
+                    "Skipping expansion for {key}={value} (will be expanded at runtime)"
+                );
+                Ok(value)
+            } else {
+                Err(Error::shell_expansion(
+                    &value,
+                    format!("Failed to expand value for {key}: {e}"),
+                ))
+            }
+        }
+    }
+}
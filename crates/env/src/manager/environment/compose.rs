@@ -0,0 +1,310 @@
+//! Docker Compose hooks
+//!
+//! A compose hook brings up services of a compose file when the directory's
+//! environment loads. The services started are recorded in the directory's
+//! state, so they can be reported by `cuenv env status` and torn down when
+//! the environment unloads (`downOnExit`) or its state is pruned, with the
+//! same runtime that started them.
+
+use cuenv_config::ComposeHook;
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// File in a directory's state listing the compose services started for it
+const COMPOSE_STATE_FILE: &str = "compose.json";
+
+/// Container runtime that runs compose files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ComposeRuntime {
+    /// `docker compose`
+    Docker,
+    /// Standalone `docker-compose`
+    DockerCompose,
+    /// `podman compose`
+    Podman,
+    /// Standalone `podman-compose`
+    PodmanCompose,
+}
+
+impl ComposeRuntime {
+    /// Runtimes in the order they are looked for
+    const ALL: [Self; 4] = [
+        Self::Docker,
+        Self::DockerCompose,
+        Self::Podman,
+        Self::PodmanCompose,
+    ];
+
+    /// The first runtime installed, Docker before Podman and plugins before
+    /// standalone tools
+    pub async fn detect() -> Result<Self> {
+        for runtime in Self::ALL {
+            let available = runtime
+                .command()
+                .arg("version")
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .await
+                .is_ok_and(|status| status.success());
+            if available {
+                return Ok(runtime);
+            }
+        }
+        Err(Error::configuration(
+            "Compose hooks need docker compose, docker-compose, podman compose or podman-compose",
+        ))
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::DockerCompose => "docker-compose",
+            Self::Podman => "podman",
+            Self::PodmanCompose => "podman-compose",
+        }
+    }
+
+    fn command(self) -> Command {
+        let mut command = Command::new(self.program());
+        if matches!(self, Self::Docker | Self::Podman) {
+            command.arg("compose");
+        }
+        command
+    }
+}
+
+impl fmt::Display for ComposeRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Docker => write!(f, "docker compose"),
+            Self::Podman => write!(f, "podman compose"),
+            other => write!(f, "{}", other.program()),
+        }
+    }
+}
+
+/// Services of one compose file started for a directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComposeServices {
+    pub runtime: ComposeRuntime,
+    /// Directory compose runs in
+    pub directory: PathBuf,
+    /// Compose file, when not the runtime's default
+    pub file: Option<PathBuf>,
+    /// Services managed, all of the file's services when empty
+    pub services: Vec<String>,
+    pub down_on_exit: bool,
+}
+
+impl ComposeServices {
+    /// Start the services in the background
+    async fn up(&self) -> Result<()> {
+        let mut args = vec!["up".to_string(), "-d".to_string()];
+        args.extend(self.services.iter().cloned());
+        self.compose(args).await.map(drop)
+    }
+
+    /// Stop and remove the services
+    async fn down(&self) -> Result<()> {
+        let args = if self.services.is_empty() {
+            vec!["down".to_string()]
+        } else {
+            ["rm", "--stop", "--force"]
+                .into_iter()
+                .map(String::from)
+                .chain(self.services.iter().cloned())
+                .collect()
+        };
+        self.compose(args).await.map(drop)
+    }
+
+    /// Names of the services that are running
+    async fn running(&self) -> Result<Vec<String>> {
+        let args = ["ps", "--services", "--filter", "status=running"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let stdout = self.compose(args).await?;
+        Ok(stdout
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn compose(&self, args: Vec<String>) -> Result<String> {
+        let mut command = self.runtime.command();
+        command.current_dir(&self.directory);
+        if let Some(file) = &self.file {
+            command.arg("-f").arg(file);
+        }
+        let output = command.args(&args).output().await.map_err(|e| {
+            Error::command_execution(self.runtime.to_string(), args.clone(), e.to_string(), None)
+        })?;
+        if !output.status.success() {
+            return Err(Error::command_execution(
+                self.runtime.to_string(),
+                args,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                output.status.code(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// State of one service for `cuenv env status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub running: bool,
+}
+
+/// Bring up the services of `hooks` for `dir` and record them in its state
+pub async fn run_compose_hooks(dir: &Path, hooks: &[ComposeHook]) -> Result<()> {
+    let hooks: Vec<&ComposeHook> = hooks.iter().filter(|hook| hook.up_on_enter).collect();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let runtime = ComposeRuntime::detect().await?;
+    let mut started = Vec::with_capacity(hooks.len());
+    for hook in hooks {
+        let services = ComposeServices {
+            runtime,
+            directory: dir.to_path_buf(),
+            file: hook.file.as_ref().map(|file| dir.join(file)),
+            services: hook.services.clone(),
+            down_on_exit: hook.down_on_exit,
+        };
+        eprintln!("# cuenv: Starting compose services with {runtime}");
+        services.up().await?;
+        started.push(services);
+    }
+    save(&cuenv_utils::paths::get_state_dir(dir), &started)
+}
+
+/// Tear down the services recorded in `state_dir`
+///
+/// On unload only services with `downOnExit` are torn down and the others
+/// stay recorded; when pruning (`all`) every service is. Returns how many
+/// compose files were torn down.
+pub async fn teardown(state_dir: &Path, all: bool) -> Result<usize> {
+    let recorded = load(state_dir)?;
+    if recorded.is_empty() {
+        return Ok(0);
+    }
+
+    let (down, kept): (Vec<_>, Vec<_>) = recorded
+        .into_iter()
+        .partition(|services| all || services.down_on_exit);
+    for services in &down {
+        services.down().await?;
+    }
+    save(state_dir, &kept)?;
+    Ok(down.len())
+}
+
+/// Tear down the `downOnExit` services of `dir` as its environment unloads
+///
+/// Failures are reported but never stop the unload.
+pub async fn on_unload(dir: &Path) {
+    match teardown(&cuenv_utils::paths::get_state_dir(dir), false).await {
+        Ok(0) => {}
+        Ok(count) => eprintln!("# cuenv: Stopped compose services ({count} file(s))"),
+        Err(e) => eprintln!("# cuenv: Failed to stop compose services: {e}"),
+    }
+}
+
+/// Running state of every service recorded for `dir`
+pub async fn status(dir: &Path) -> Result<Vec<ServiceStatus>> {
+    let mut statuses = Vec::new();
+    for services in load(&cuenv_utils::paths::get_state_dir(dir))? {
+        let running = services.running().await?;
+        let names = if services.services.is_empty() {
+            running.clone()
+        } else {
+            services.services.clone()
+        };
+        statuses.extend(names.into_iter().map(|name| ServiceStatus {
+            running: running.contains(&name),
+            name,
+        }));
+    }
+    Ok(statuses)
+}
+
+fn load(state_dir: &Path) -> Result<Vec<ComposeServices>> {
+    let path = state_dir.join(COMPOSE_STATE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| {
+            Error::configuration(format!("Invalid compose state {}: {e}", path.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(Error::file_system(&path, "read compose state", e)),
+    }
+}
+
+fn save(state_dir: &Path, services: &[ComposeServices]) -> Result<()> {
+    let path = state_dir.join(COMPOSE_STATE_FILE);
+    if services.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(Error::file_system(&path, "remove compose state", e))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    fs::create_dir_all(state_dir)
+        .map_err(|e| Error::file_system(state_dir, "create state directory", e))?;
+    let content = serde_json::to_string_pretty(services)
+        .map_err(|e| Error::configuration(format!("Failed to serialize compose state: {e}")))?;
+    fs::write(&path, content).map_err(|e| Error::file_system(&path, "write compose state", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn services(down_on_exit: bool) -> ComposeServices {
+        ComposeServices {
+            runtime: ComposeRuntime::Docker,
+            directory: PathBuf::from("/project"),
+            file: Some(PathBuf::from("/project/docker-compose.yml")),
+            services: vec!["db".to_string()],
+            down_on_exit,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unload_keeps_services_without_down_on_exit() {
+        let state_dir = TempDir::new().unwrap();
+        save(state_dir.path(), &[services(false)]).unwrap();
+
+        assert_eq!(teardown(state_dir.path(), false).await.unwrap(), 0);
+        assert_eq!(load(state_dir.path()).unwrap(), vec![services(false)]);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let state_dir = TempDir::new().unwrap();
+        assert!(load(state_dir.path()).unwrap().is_empty());
+
+        save(state_dir.path(), &[services(true)]).unwrap();
+        let content = fs::read_to_string(state_dir.path().join(COMPOSE_STATE_FILE)).unwrap();
+        assert!(content.contains("\"runtime\": \"docker\""));
+        assert_eq!(load(state_dir.path()).unwrap(), vec![services(true)]);
+
+        save(state_dir.path(), &[]).unwrap();
+        assert!(!state_dir.path().join(COMPOSE_STATE_FILE).exists());
+    }
+}
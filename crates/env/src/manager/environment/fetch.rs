@@ -0,0 +1,234 @@
+//! Fetch hooks
+//!
+//! A fetch hook downloads a tool archive into the project's `.cuenv/fetch`
+//! cache, checks it against the configured SHA-256 and extracts it into
+//! `extractTo`. A marker left in `extractTo` makes later loads skip both the
+//! download and the extraction. Files that are not archives are copied into
+//! `extractTo` as executables, for tools released as a single binary.
+
+use cuenv_config::FetchHook;
+use cuenv_core::{Error, Result};
+use cuenv_utils::network::download::download_resumable;
+use cuenv_utils::paths::get_fetch_cache_dir;
+use cuenv_utils::resilience::Subsystem;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, BufReader};
+use std::path::{Component, Path};
+use tokio::process::Command;
+
+/// Archive suffixes `tar` extracts, detecting the compression itself
+const TAR_SUFFIXES: [&str; 8] = [
+    ".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2", ".tbz2", ".tar.zst",
+];
+
+/// Make the archive of `fetch` available in its `extractTo` directory
+pub async fn run_fetch_hook(dir: &Path, fetch: &FetchHook) -> Result<()> {
+    let expected = fetch.sha256.to_ascii_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::configuration(format!(
+            "Fetch hook for {} needs a hex encoded SHA-256, got '{}'",
+            fetch.url, fetch.sha256
+        )));
+    }
+
+    if !is_relative_dir(&fetch.extract_to) {
+        return Err(Error::configuration(format!(
+            "Fetch hook for {} must extract to a directory inside the project, got '{}'",
+            fetch.url, fetch.extract_to
+        )));
+    }
+
+    let target = dir.join(&fetch.extract_to);
+    let marker = target.join(format!(".cuenv-fetch-{expected}"));
+    if marker.exists() {
+        tracing::debug!("{} is already extracted to {}", fetch.url, target.display());
+        return Ok(());
+    }
+
+    let cache_dir = get_fetch_cache_dir(dir).join(&expected);
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| Error::file_system(&cache_dir, "create fetch cache", e))?;
+    let file_name = file_name(&fetch.url);
+    let archive = cache_dir.join(file_name);
+    if !archive.exists() {
+        eprintln!("# cuenv: Fetching {}", fetch.url);
+        download_resumable(
+            &fetch.url,
+            &archive,
+            fetch.proxy.as_deref(),
+            Subsystem::Hooks,
+        )
+        .await?;
+    }
+
+    let actual = file_sha256(&archive)?;
+    if actual != expected {
+        let _ = fs::remove_file(&archive);
+        return Err(Error::configuration(format!(
+            "Checksum mismatch for {}: expected {expected}, got {actual}",
+            fetch.url
+        )));
+    }
+
+    fs::create_dir_all(&target).map_err(|e| Error::file_system(&target, "create directory", e))?;
+    extract(&archive, file_name, &target).await?;
+    fs::write(&marker, &fetch.url).map_err(|e| Error::file_system(&marker, "write marker", e))
+}
+
+/// Whether `path` names a directory below the project: not empty, not
+/// absolute and without `.` or `..`
+fn is_relative_dir(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Last path segment of `url`, without query or fragment
+fn file_name(url: &str) -> &str {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download")
+}
+
+/// Hash of a file, streamed so large archives are never held in memory
+fn file_sha256(path: &Path) -> Result<String> {
+    let file = fs::File::open(path).map_err(|e| Error::file_system(path, "open download", e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut BufReader::new(file), &mut hasher)
+        .map_err(|e| Error::file_system(path, "read download", e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn extract(archive: &Path, file_name: &str, target: &Path) -> Result<()> {
+    let archive_arg = archive.to_string_lossy().into_owned();
+    let target_arg = target.to_string_lossy().into_owned();
+    let (program, args) = if file_name.ends_with(".zip") {
+        (
+            "unzip",
+            vec![
+                "-q".to_string(),
+                "-o".to_string(),
+                archive_arg,
+                "-d".to_string(),
+                target_arg,
+            ],
+        )
+    } else if TAR_SUFFIXES
+        .iter()
+        .any(|suffix| file_name.ends_with(suffix))
+    {
+        (
+            "tar",
+            vec!["-xf".to_string(), archive_arg, "-C".to_string(), target_arg],
+        )
+    } else {
+        return install_binary(archive, &target.join(file_name));
+    };
+
+    let output = Command::new(program)
+        .args(&args)
+        .output()
+        .await
+        .map_err(|e| Error::command_execution(program, args.clone(), e.to_string(), None))?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            program,
+            args,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            output.status.code(),
+        ));
+    }
+    Ok(())
+}
+
+fn install_binary(source: &Path, dest: &Path) -> Result<()> {
+    fs::copy(source, dest).map_err(|e| Error::file_system(dest, "copy download", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dest, fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::file_system(dest, "make executable", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fetch_hook(sha256: &str) -> FetchHook {
+        FetchHook {
+            url: "https://example.com/releases/tool?version=1".to_string(),
+            sha256: sha256.to_string(),
+            extract_to: ".tools/bin".to_string(),
+            proxy: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_download_is_verified_and_installed() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = b"#!/bin/sh\necho tool\n";
+        let sha256 = format!("{:x}", Sha256::digest(content));
+        let cache_dir = get_fetch_cache_dir(temp_dir.path()).join(&sha256);
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("tool"), content).unwrap();
+
+        run_fetch_hook(temp_dir.path(), &fetch_hook(&sha256))
+            .await
+            .unwrap();
+        let installed = temp_dir.path().join(".tools/bin/tool");
+        assert_eq!(fs::read(&installed).unwrap(), content);
+
+        // The marker skips the cache entirely from now on
+        fs::remove_dir_all(&cache_dir).unwrap();
+        run_fetch_hook(temp_dir.path(), &fetch_hook(&sha256))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_discards_download() {
+        let temp_dir = TempDir::new().unwrap();
+        let sha256 = "0".repeat(64);
+        let cache_dir = get_fetch_cache_dir(temp_dir.path()).join(&sha256);
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("tool"), b"tampered").unwrap();
+
+        let err = run_fetch_hook(temp_dir.path(), &fetch_hook(&sha256))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(!cache_dir.join("tool").exists());
+        assert!(!temp_dir.path().join(".tools/bin/tool").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extract_to_must_stay_inside_the_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        let sha256 = "0".repeat(64);
+
+        for extract_to in ["/usr/local/bin", "../bin", ".tools/../../bin", "./bin", ""] {
+            let fetch = FetchHook {
+                extract_to: extract_to.to_string(),
+                ..fetch_hook(&sha256)
+            };
+            let err = run_fetch_hook(&project, &fetch).await.unwrap_err();
+            assert!(
+                err.to_string().contains("inside the project"),
+                "{extract_to}: {err}"
+            );
+        }
+        // Refused before anything is downloaded or created
+        assert!(!get_fetch_cache_dir(&project).exists());
+        assert!(!temp_dir.path().join("bin").exists());
+    }
+}
@@ -1,31 +1,55 @@
-use cuenv_config::{Hook, HookConfig, HookType};
+use cuenv_config::{ComposeHook, Hook, HookConfig, HookType};
 use std::collections::HashMap;
 use std::path::Path;
 
+use super::compose::run_compose_hooks;
+use super::fetch::run_fetch_hook;
 use super::supervisor;
 use crate::manager::hooks;
 use supervisor::{Supervisor, SupervisorMode};
 
 /// Process all hooks using the new supervisor-based model.
+///
+/// `parallelism` bounds how many independent hooks run at once.
 pub async fn process_all_hooks(
     dir: &Path,
     hook_list: &HashMap<String, Vec<Hook>>,
     mode: SupervisorMode,
+    parallelism: Option<usize>,
 ) -> cuenv_core::Result<HashMap<String, String>> {
-    let mut on_enter_hooks = Vec::new();
-
-    // Collect all onEnter hooks
-    if let Some(hooks) = hook_list.get("onEnter") {
-        on_enter_hooks.extend(hooks.clone());
+    // Collect all onEnter hooks, separating fetch and compose hooks from exec hooks
+    let (fetch_hooks, on_enter_hooks): (Vec<Hook>, Vec<Hook>) = hook_list
+        .get("onEnter")
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|hook| hook.fetch.is_some());
+    let (compose_hooks, on_enter_hooks): (Vec<Hook>, Vec<Hook>) = on_enter_hooks
+        .into_iter()
+        .partition(|hook| hook.compose.is_some());
+
+    // Fetch hooks run first so exec hooks can use what they install
+    for fetch in fetch_hooks.iter().filter_map(|hook| hook.fetch.as_ref()) {
+        run_fetch_hook(dir, fetch).await?;
     }
 
+    // Services come up before exec hooks, which may talk to them
+    let compose: Vec<ComposeHook> = compose_hooks
+        .into_iter()
+        .filter_map(|hook| hook.compose)
+        .collect();
+    run_compose_hooks(dir, &compose).await?;
+
     // If there are no hooks, we're done.
     if on_enter_hooks.is_empty() {
         return Ok(HashMap::new());
     }
 
     // Run all onEnter hooks through the supervisor in the specified mode.
-    let supervisor = Supervisor::new_for_directory(dir, on_enter_hooks, mode)?;
+    let mut supervisor = Supervisor::new_for_directory(dir, on_enter_hooks, mode)?;
+    if let Some(parallelism) = parallelism {
+        supervisor = supervisor.with_parallelism(parallelism);
+    }
     supervisor.run().await?;
 
     // Read the captured environment from the directory-specific cache
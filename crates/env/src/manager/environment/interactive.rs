@@ -14,6 +14,8 @@ pub enum ControlFlow {
     Continue,
     /// The operation should be moved to the background.
     Background,
+    /// The user aborted; the caller decides how to exit, with this code.
+    Abort(i32),
 }
 
 /// Handles interactive terminal operations, such as monitoring for user input
@@ -115,7 +117,7 @@ impl InteractiveHandler {
                         ..
                     })) => {
                         eprintln!("\r\x1b[K# cuenv: Aborting...");
-                        std::process::exit(1);
+                        return ControlFlow::Abort(1);
                     }
                     Ok(Event::Key(KeyEvent {
                         code: KeyCode::Char('c'),
@@ -123,7 +125,7 @@ impl InteractiveHandler {
                         ..
                     })) => {
                         eprintln!("\r\x1b[K# cuenv: Interrupted!");
-                        std::process::exit(130); // Standard exit code for SIGINT
+                        return ControlFlow::Abort(130); // Standard exit code for SIGINT
                     }
                     _ => {}
                 }
@@ -4,15 +4,19 @@ use cuenv_config::{
 };
 use cuenv_core::{
     constants::{CUENV_PACKAGE_VAR, DEFAULT_PACKAGE_NAME},
-    Error, Result,
+    events::{global_timeline, EventTemplates, SpanKind},
+    redaction::global_redactor,
+    Result,
 };
 use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::path::Path;
 
-use super::apply::apply_merged_environment;
+use super::apply::{apply_merged_environment, ApplyTargets, LoadedVariables};
 use super::hooks::process_all_hooks;
 use super::supervisor::SupervisorMode;
+use crate::manager::secrets::import_variables;
+use crate::watcher::WatchSet;
 
 /// Context for loading environment with all the mutable maps
 pub struct LoadEnvironmentContext<'a> {
@@ -22,7 +26,14 @@ pub struct LoadEnvironmentContext<'a> {
     pub hooks: &'a mut HashMap<String, HookConfig>,
     pub cue_vars: &'a mut HashMap<String, String>,
     pub cue_vars_metadata: &'a mut HashMap<String, VariableMetadata>,
+    pub deferred_secrets: &'a mut HashMap<String, String>,
     pub sourced_env: &'a mut HashMap<String, String>,
+    pub strict_variables: &'a mut bool,
+    pub event_templates: &'a mut EventTemplates,
+    /// Record the result in the shell state so the hook can unload it later
+    pub persist_state: bool,
+    /// Set the loaded variables in this process's environment
+    pub export_to_process: bool,
 }
 
 /// Load environment with given options
@@ -35,83 +46,153 @@ pub async fn load_env_with_options(
     context: &mut LoadEnvironmentContext<'_>,
     mode: SupervisorMode,
 ) -> Result<()> {
-    // Get the package name from environment or use default
-    let package_name =
-        std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
-
-    // First pass: load package to get command mappings
-    let temp_options = ParseOptions {
-        environment: environment.clone(),
-        capabilities: Vec::new(), // Empty for now to get all commands
-    };
-
-    let parse_result = CueParser::eval_package_with_options(dir, &package_name, &temp_options)?;
-    context.commands.extend(parse_result.commands.clone());
-    context.tasks.extend(parse_result.tasks.clone());
-    context.task_nodes.extend(parse_result.task_nodes.clone());
-
-    // Convert Vec<Hook> to HookConfig for compatibility with TUI architecture
-    convert_hooks_to_config(&parse_result.hooks, context.hooks);
-
-    // If no capabilities were specified, try to infer from the command
-    infer_capabilities(command, context.commands, &mut capabilities);
-
-    // Second pass: load with actual capabilities
-    let options = ParseOptions {
-        environment,
-        capabilities,
-    };
-
-    tracing::info!(
-        path = %dir.display(),
-        environment = ?options.environment,
-        capabilities = ?options.capabilities,
-        "Loading CUE package"
-    );
-
-    // First, parse CUE package to get hooks and initial environment
-    let parse_result = match CueParser::eval_package_with_options(dir, &package_name, &options) {
-        Ok(result) => result,
-        Err(e) => {
-            return Err(Error::cue_parse_with_source(
-                dir,
-                format!("Failed to evaluate CUE package: {}", dir.display()),
-                e,
-            ));
+    let span =
+        global_timeline().start(SpanKind::Env, format!("load environment {}", dir.display()));
+    let result = async {
+        let eval_span = span.child("evaluate CUE");
+
+        // Get the package name from environment or use default
+        let package_name =
+            std::env::var(CUENV_PACKAGE_VAR).unwrap_or_else(|_| DEFAULT_PACKAGE_NAME.to_string());
+
+        // Evaluate once; commands do not depend on the selected capabilities
+        let package = match CueParser::evaluate_package(dir, &package_name) {
+            Ok(package) => package,
+            Err(e) => {
+                eval_span.fail(e.to_string());
+                return Err(e);
+            }
+        };
+
+        // If no capabilities were specified, try to infer from the command
+        infer_capabilities(command, package.commands(), &mut capabilities);
+
+        let options = ParseOptions {
+            environment,
+            capabilities,
+        };
+
+        tracing::info!(
+            path = %dir.display(),
+            environment = ?options.environment,
+            capabilities = ?options.capabilities,
+            "Loading CUE package"
+        );
+
+        // Build the result for the environment and capabilities
+        let mut parse_result = match package.into_parse_result(&options) {
+            Ok(result) => result,
+            Err(e) => {
+                eval_span.fail(e.to_string());
+                return Err(e);
+            }
+        };
+
+        // Mask secrets matching the configured patterns from here on
+        if let Some(patterns) = parse_result
+            .config
+            .as_ref()
+            .and_then(|config| config.redact_patterns.as_ref())
+        {
+            for pattern in patterns {
+                global_redactor().register_pattern(pattern)?;
+            }
+        }
+
+        // Convert Vec<Hook> to HookConfig for compatibility with TUI architecture
+        convert_hooks_to_config(&parse_result.hooks, context.hooks);
+        *context.strict_variables = parse_result
+            .config
+            .as_ref()
+            .and_then(|config| config.strict_variables)
+            .unwrap_or(false);
+        if let Some(config) = &parse_result.config {
+            *context.event_templates = config.event_templates()?;
         }
-    };
-
-    // Store commands, tasks and hooks
-    context.commands.extend(parse_result.commands.clone());
-    context.tasks.extend(parse_result.tasks.clone());
-    context.task_nodes.extend(parse_result.task_nodes.clone());
-    convert_hooks_to_config(&parse_result.hooks, context.hooks);
-
-    // Process all hooks using the new supervisor-based model
-    let sourced_env_vars = process_all_hooks(dir, &parse_result.hooks, mode).await?;
-
-    // Store the sourced environment
-    let has_sourced_env = !sourced_env_vars.is_empty();
-    *context.sourced_env = sourced_env_vars.clone();
-
-    // Merge CUE variables with sourced variables (CUE takes precedence)
-    let mut merged_variables = sourced_env_vars;
-    merged_variables.extend(parse_result.variables);
-
-    // Store variable metadata
-    context.cue_vars_metadata.clear();
-    context.cue_vars_metadata.extend(parse_result.metadata);
-
-    // Apply the merged environment
-    apply_merged_environment(
-        dir,
-        merged_variables,
-        &options,
-        has_sourced_env,
-        original_env,
-        context.cue_vars,
-    )
-    .await
+
+        // Hashed by the watches, so they are taken once those are built
+        let watches = WatchSet::new(dir, &package_name, &options, &parse_result);
+        context
+            .commands
+            .extend(std::mem::take(&mut parse_result.commands));
+        context
+            .tasks
+            .extend(std::mem::take(&mut parse_result.tasks));
+        context
+            .task_nodes
+            .extend(std::mem::take(&mut parse_result.task_nodes));
+
+        // Check the environment against cuenv.lock before its hooks run
+        crate::lock::verify(dir, &parse_result)?;
+        let watches = watches?;
+        drop(eval_span);
+
+        // Process all hooks using the new supervisor-based model
+        let hooks_span = span.child_of_kind(SpanKind::Hook, "hooks");
+        let hook_parallelism = parse_result
+            .config
+            .as_ref()
+            .and_then(|config| config.hook_parallelism);
+        let sourced_env_vars =
+            process_all_hooks(dir, &parse_result.hooks, mode, hook_parallelism).await;
+        hooks_span.finish(&sourced_env_vars);
+        let sourced_env_vars = sourced_env_vars?;
+
+        // Store the sourced environment
+        let has_sourced_env = !sourced_env_vars.is_empty();
+        *context.sourced_env = sourced_env_vars.clone();
+
+        // Lazy secrets stay out of the environment until something needs them
+        let (deferred, variables): (HashMap<_, _>, HashMap<_, _>) =
+            parse_result.variables.into_iter().partition(|(key, _)| {
+                parse_result
+                    .metadata
+                    .get(key)
+                    .is_some_and(|metadata| metadata.lazy)
+            });
+        *context.deferred_secrets = deferred;
+
+        // Variables imported from configuration stores take precedence over
+        // sourced ones, and variables declared in CUE over both
+        let mut imported = import_variables(&parse_result.imports).await?;
+        imported.retain(|key, _| {
+            !variables.contains_key(key) && !context.deferred_secrets.contains_key(key)
+        });
+
+        // Merge CUE variables with sourced variables (CUE takes precedence)
+        let mut merged_variables = sourced_env_vars;
+        merged_variables.retain(|key, _| !imported.contains_key(key));
+        merged_variables.extend(variables);
+
+        // Store variable metadata
+        context.cue_vars_metadata.clear();
+        context.cue_vars_metadata.extend(parse_result.metadata);
+
+        // Apply the merged environment
+        let apply_span = span.child("apply environment");
+        let result = apply_merged_environment(
+            dir,
+            LoadedVariables {
+                merged: merged_variables,
+                imported,
+            },
+            watches,
+            has_sourced_env,
+            original_env,
+            context.cue_vars,
+            ApplyTargets {
+                persist_state: context.persist_state,
+                export_to_process: context.export_to_process,
+            },
+        )
+        .await;
+        apply_span.finish(&result);
+        result
+    }
+    .await;
+
+    span.finish(&result);
+    result
 }
 
 fn convert_hooks_to_config(
@@ -1,4 +1,6 @@
 mod apply;
+pub mod compose;
+mod fetch;
 pub mod hooks;
 pub mod interactive;
 pub mod loading;
@@ -9,5 +11,5 @@ mod unload;
 pub use hooks::execute_on_enter_hooks;
 pub use loading::{load_env_with_options, LoadEnvironmentContext};
 pub use preload::PreloadHookManager;
-pub use supervisor::SupervisorMode;
+pub use supervisor::{refresh_hook_cache, SupervisorMode};
 pub use unload::unload_env;
@@ -1,6 +1,7 @@
 use cuenv_config::Hook;
 use cuenv_core::Result;
 use cuenv_utils::hooks_status::HooksStatusManager;
+use cuenv_utils::sync::SyncEnv;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
@@ -293,7 +294,7 @@ async fn execute_hook_async(hook: &Hook) -> Result<()> {
                 // Note: This only affects the cuenv process, not the parent shell
                 // The shell hook mechanism will handle propagating these to the shell
                 for (key, value) in filtered {
-                    std::env::set_var(&key, &value);
+                    SyncEnv::set_var(&key, &value)?;
                     tracing::debug!("Set env var from preload source hook: {}={}", key, value);
                 }
             }
@@ -334,23 +335,31 @@ mod tests {
 
     fn create_test_hook(command: &str, args: Option<Vec<String>>, preload: bool) -> Hook {
         Hook {
+            name: None,
+            depends_on: None,
             command: command.to_string(),
             args,
             dir: None,
             inputs: None,
             source: None,
             preload: Some(preload),
+            fetch: None,
+            compose: None,
         }
     }
 
     fn create_source_hook(command: &str, args: Option<Vec<String>>, preload: bool) -> Hook {
         Hook {
+            name: None,
+            depends_on: None,
             command: command.to_string(),
             args,
             dir: None,
             inputs: None,
             source: Some(true),
             preload: Some(preload),
+            fetch: None,
+            compose: None,
         }
     }
 
@@ -667,8 +676,9 @@ mod tests {
     async fn test_environment_variable_conflicts() {
         let manager = PreloadHookManager::new();
 
-        // Set initial environment variable
-        std::env::set_var("CUENV_TEST_CONFLICT", "initial");
+        // Set initial environment variable, restored when the test ends
+        let mut scope = cuenv_utils::sync::ScopedEnv::new();
+        scope.set("CUENV_TEST_CONFLICT", "initial").unwrap();
 
         let temp_dir = TempDir::new().unwrap();
         let script_path = temp_dir.path().join("conflict_script.sh");
@@ -698,9 +708,6 @@ mod tests {
         // Wait for completion
         let wait_result = manager.wait_for_completion().await;
         assert!(wait_result.is_ok());
-
-        // Clean up
-        std::env::remove_var("CUENV_TEST_CONFLICT");
     }
 
     #[tokio::test]
@@ -2,6 +2,7 @@
 
 use cuenv_config::Hook;
 use cuenv_core::Result;
+use cuenv_utils::IgnoreRules;
 use globset::Glob;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -115,11 +116,32 @@ pub fn apply_cached_environment(cache_dir: &Path, cached: CapturedEnvironment) -
     Ok(())
 }
 
+/// Files that determine the environment produced by nix and devenv hooks
+///
+/// Their contents are part of the cache key of every source hook, so the
+/// environment is rebuilt whenever a lockfile changes even if the hook does
+/// not declare any inputs.
+pub const HOOK_LOCKFILES: &[&str] = &[
+    "flake.lock",
+    "flake.nix",
+    "devenv.lock",
+    "devenv.nix",
+    "devenv.yaml",
+    "shell.nix",
+    "default.nix",
+];
+
 /// Calculate a hash of all hook inputs (commands, args, env vars, working dirs)
-pub fn calculate_input_hash(hooks: &[Hook]) -> Result<String> {
+///
+/// Declared inputs and well-known lockfiles are resolved against the hook's
+/// working directory, falling back to `directory`, and their contents are
+/// hashed so edits invalidate the cached environment. Glob inputs skip
+/// the trees `directory`'s `.cuenvignore` and the defaults ignore.
+pub fn calculate_input_hash(hooks: &[Hook], directory: &Path) -> Result<String> {
     use sha2::{Digest, Sha256};
 
     let mut hasher = Sha256::new();
+    let ignore = IgnoreRules::load(directory)?;
 
     for hook in hooks {
         // Hash the hook command and args
@@ -130,21 +152,25 @@ pub fn calculate_input_hash(hooks: &[Hook]) -> Result<String> {
             }
         }
 
+        // Dependencies decide the merge order of sourced environments
+        for dependency in hook.depends_on.iter().flatten() {
+            hasher.update(dependency.as_bytes());
+        }
+
         // Hash the working directory if set
         if let Some(dir) = &hook.dir {
             hasher.update(dir.as_bytes());
         }
+        let hook_dir = hook
+            .dir
+            .as_ref()
+            .map_or_else(|| directory.to_path_buf(), |dir| directory.join(dir));
 
-        // Hash inputs if provided
-        if let Some(inputs) = &hook.inputs {
-            for input in inputs {
-                hasher.update(input.as_bytes());
-            }
-        }
-
-        // Hash inputs directory patterns if provided
+        // Hash declared inputs: glob matches by path and mtime, files by content
         if let Some(inputs) = &hook.inputs {
             for pattern in inputs {
+                hasher.update(pattern.as_bytes());
+
                 // Check if this looks like a glob pattern
                 if pattern.contains('*') || pattern.contains('?') {
                     // Use glob pattern to find matching files
@@ -154,11 +180,23 @@ pub fn calculate_input_hash(hooks: &[Hook]) -> Result<String> {
                         })?
                         .compile_matcher();
 
-                    // Walk the current directory and hash all matching paths
-                    for entry in WalkDir::new(".").max_depth(5).into_iter().flatten() {
+                    // Walk the hook directory and hash all matching paths
+                    for entry in WalkDir::new(&hook_dir)
+                        .max_depth(5)
+                        .sort_by_file_name()
+                        .into_iter()
+                        .filter_entry(|entry| {
+                            let path = entry.path();
+                            let relative = path.strip_prefix(directory).unwrap_or(path);
+                            entry.depth() == 0
+                                || !ignore.is_ignored(relative, entry.file_type().is_dir())
+                        })
+                        .flatten()
+                    {
                         let path = entry.path();
-                        if glob.is_match(path) {
-                            hasher.update(path.to_string_lossy().as_bytes());
+                        let relative = path.strip_prefix(&hook_dir).unwrap_or(path);
+                        if glob.is_match(relative) {
+                            hasher.update(relative.to_string_lossy().as_bytes());
                             // Also hash the modification time if available
                             if let Ok(metadata) = entry.metadata() {
                                 if let Ok(modified) = metadata.modified() {
@@ -169,9 +207,18 @@ pub fn calculate_input_hash(hooks: &[Hook]) -> Result<String> {
                             }
                         }
                     }
-                } else {
-                    // Just hash the plain input
-                    hasher.update(pattern.as_bytes());
+                } else if let Ok(content) = fs::read(hook_dir.join(pattern)) {
+                    hasher.update(content);
+                }
+            }
+        }
+
+        // Source hooks produce the environment, so key them on lockfiles too
+        if hook.source.unwrap_or(false) {
+            for lockfile in HOOK_LOCKFILES {
+                if let Ok(content) = fs::read(hook_dir.join(lockfile)) {
+                    hasher.update(lockfile.as_bytes());
+                    hasher.update(content);
                 }
             }
         }
@@ -182,3 +229,35 @@ pub fn calculate_input_hash(hooks: &[Hook]) -> Result<String> {
 
     Ok(format!("{:x}", hasher.finalize()))
 }
+
+/// Remove cached hook environments so the next load re-runs the hooks
+///
+/// Returns the number of cache entries removed.
+pub fn clear_cached_environments(cache_dir: &Path) -> Result<usize> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return Ok(0);
+    };
+
+    let mut removed = 0;
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if !is_cached_environment(&path) {
+            continue;
+        }
+        fs::remove_file(&path)
+            .map_err(|e| cuenv_core::Error::file_system(&path, "remove cached environment", e))?;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
+/// Cached environments are stored as `<sha256>.json` plus `latest_env.json`
+fn is_cached_environment(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let Some(stem) = name.strip_suffix(".json") else {
+        return false;
+    };
+    stem == "latest_env" || (stem.len() == 64 && stem.bytes().all(|b| b.is_ascii_hexdigit()))
+}
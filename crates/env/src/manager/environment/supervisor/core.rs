@@ -2,18 +2,24 @@
 
 use crate::manager::environment::interactive::{ControlFlow, InteractiveHandler};
 use cuenv_config::Hook;
+use cuenv_core::events::{publish_global_event, EnvEvent, SystemEvent};
 use cuenv_core::Result;
 use cuenv_utils::directory_lock::DirectoryLock;
 use cuenv_utils::hooks_status::{HookState, HooksStatusManager};
 use std::collections::HashMap;
 use std::fs;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use super::cache;
 use super::execution::execute_hook_with_timeout;
-use super::utils::{get_cache_dir, is_process_running};
+use super::schedule::{label, HookGraph};
+use super::utils::is_process_running;
+
+/// Longest a single hook may run
+const HOOK_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// The mode in which the supervisor should run.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +46,10 @@ pub struct Supervisor {
     _lock: Option<DirectoryLock>,
     /// Directory for caching
     cache_dir: PathBuf,
+    /// Project directory the hooks belong to, used to resolve hook inputs
+    directory: PathBuf,
+    /// Most hooks running at once
+    parallelism: usize,
 }
 
 impl Supervisor {
@@ -49,7 +59,10 @@ impl Supervisor {
             cuenv_core::Error::configuration(format!("Failed to create status manager: {e}"))
         })?;
 
-        let cache_dir = get_cache_dir()?;
+        // Cached like a directory's supervisor, so `cuenv hooks refresh`
+        // reaches it without touching other projects
+        let directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let cache_dir = cuenv_utils::paths::get_state_dir(&directory);
         fs::create_dir_all(&cache_dir)
             .map_err(|e| cuenv_core::Error::file_system(&cache_dir, "create directory", e))?;
 
@@ -70,6 +83,8 @@ impl Supervisor {
             interactive_handler,
             _lock: None, // Legacy mode doesn't use locking
             cache_dir,
+            directory,
+            parallelism: default_parallelism(),
         })
     }
 
@@ -127,9 +142,17 @@ impl Supervisor {
             interactive_handler,
             _lock: lock,
             cache_dir,
+            directory: directory.to_path_buf(),
+            parallelism: default_parallelism(),
         })
     }
 
+    /// Run at most `parallelism` independent hooks at once
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
     /// Run the supervisor
     pub async fn run(mut self) -> Result<()> {
         match self.mode {
@@ -179,7 +202,7 @@ impl Supervisor {
         }
 
         // Check if we need to run based on inputs
-        let input_hash = cache::calculate_input_hash(&self.hooks)?;
+        let input_hash = cache::calculate_input_hash(&self.hooks, &self.directory)?;
         if let Ok(cached_env) = cache::load_cached_environment(&self.cache_dir, &input_hash) {
             // Inputs haven't changed, use cached environment
             eprintln!("# cuenv: Using cached environment (inputs unchanged)");
@@ -187,73 +210,15 @@ impl Supervisor {
             return Ok(());
         }
 
+        let schedule = self.schedule(false)?;
         eprintln!("# cuenv: Running {} hook(s)...", self.hooks.len());
-
-        // Clear any stale status from previous runs before initializing new hooks
-        self.status_manager.clear_status().map_err(|e| {
-            cuenv_core::Error::configuration(format!("Failed to clear status: {e}"))
-        })?;
-
-        // Initialize status tracking with all hooks
-        let hook_names: Vec<String> = self
-            .hooks
-            .iter()
-            .map(|h| {
-                if let Some(args) = &h.args {
-                    format!("{} {:?}", h.command, args)
-                } else {
-                    h.command.clone()
-                }
-            })
-            .collect();
-
-        self.status_manager
-            .initialize_hooks(hook_names)
-            .map_err(|e| {
-                cuenv_core::Error::configuration(format!("Failed to initialize hooks: {e}"))
-            })?;
-
-        let mut handles = Vec::new();
-
-        for hook in self.hooks.iter().cloned() {
-            let status_manager = self.status_manager.clone();
-            let hook_key = if let Some(args) = &hook.args {
-                format!("{} {:?}", hook.command, args)
-            } else {
-                hook.command.clone()
-            };
-
-            let handle = tokio::spawn(async move {
-                // Start with no PID, will be updated when process spawns
-                let _ = status_manager.mark_hook_started(&hook_key, 0);
-                let result = execute_hook_with_timeout(&hook, Duration::from_secs(60), false).await;
-                match result {
-                    Ok((output, pid)) => {
-                        // Update with actual PID if we got one
-                        if let Some(actual_pid) = pid {
-                            let _ = status_manager.mark_hook_started(&hook_key, actual_pid);
-                        }
-                        let _ = status_manager.mark_hook_completed(&hook_key);
-                        output
-                    }
-                    Err(e) => {
-                        let _ = status_manager.mark_hook_failed(&hook_key, e.to_string());
-                        None
-                    }
-                }
-            });
-            handles.push(handle);
-        }
+        self.initialize_status()?;
+        let handle = tokio::spawn(schedule);
 
         let mut message_shown = false;
         let start_time = std::time::Instant::now();
 
-        loop {
-            let all_finished = handles.iter().all(|h| h.is_finished());
-            if all_finished {
-                break;
-            }
-
+        while !handle.is_finished() {
             // Show message after 1 second
             if !message_shown && start_time.elapsed() > Duration::from_secs(1) {
                 eprintln!("# cuenv: Press 'b' to background, 'q' to quit");
@@ -263,33 +228,31 @@ impl Supervisor {
             // Always check for input after message is shown
             if message_shown {
                 if let Some(interactive_handler) = &mut self.interactive_handler {
-                    if interactive_handler
+                    let flow = interactive_handler
                         .monitor_with_timeout(Duration::from_millis(200))
-                        .await
-                        == ControlFlow::Background
-                    {
+                        .await;
+                    if let ControlFlow::Abort(exit_code) = flow {
+                        handle.abort();
+                        let _ = self.status_manager.clear_status();
+                        return Err(cuenv_core::Error::command_execution(
+                            "hooks",
+                            Vec::new(),
+                            "aborted by user",
+                            Some(exit_code),
+                        ));
+                    }
+                    if flow == ControlFlow::Background {
                         // Spawn a background task to monitor hook completion
                         eprintln!(
                             "# cuenv: Continuing {} hook(s) in background...",
-                            handles.len()
+                            self.hooks.len()
                         );
 
-                        let _status_manager = self.status_manager.clone();
-                        let hooks = self.hooks.clone();
                         let cache_dir = self.cache_dir.clone();
-
                         tokio::spawn(async move {
-                            // Wait for all handles to complete
-                            let mut captured_env = HashMap::new();
-                            for handle in handles {
-                                if let Ok(Some(env)) = handle.await {
-                                    captured_env.extend(env);
-                                }
-                            }
-
                             // Save captured environment if any
-                            if !captured_env.is_empty() {
-                                if let Ok(input_hash) = cache::calculate_input_hash(&hooks) {
+                            if let Ok(captured_env) = handle.await {
+                                if !captured_env.is_empty() {
                                     let _ = cache::save_cached_environment(
                                         &cache_dir,
                                         &input_hash,
@@ -311,19 +274,10 @@ impl Supervisor {
             }
         }
 
-        let mut captured_env = HashMap::new();
-        for handle in handles {
-            if let Ok(Some(env)) = handle.await {
-                captured_env.extend(env);
-            }
-        }
-
-        if !captured_env.is_empty() {
-            let input_hash = cache::calculate_input_hash(&self.hooks)?;
-            cache::save_cached_environment(&self.cache_dir, &input_hash, captured_env)?;
-        }
-
-        self.status_manager.clear_status()?;
+        let captured_env = handle
+            .await
+            .map_err(|e| cuenv_core::Error::configuration(format!("Hook scheduler failed: {e}")))?;
+        self.finish(&input_hash, captured_env)?;
         eprintln!("# cuenv: ✓ All hooks completed");
         Ok(())
     }
@@ -338,79 +292,17 @@ impl Supervisor {
         }
 
         // Check if we need to run based on inputs
-        let input_hash = cache::calculate_input_hash(&self.hooks)?;
+        let input_hash = cache::calculate_input_hash(&self.hooks, &self.directory)?;
         if let Ok(cached_env) = cache::load_cached_environment(&self.cache_dir, &input_hash) {
             // Inputs haven't changed, use cached environment
             cache::apply_cached_environment(&self.cache_dir, cached_env)?;
             return Ok(());
         }
 
-        self.status_manager.clear_status().map_err(|e| {
-            cuenv_core::Error::configuration(format!("Failed to clear status: {e}"))
-        })?;
-
-        let hook_names: Vec<String> = self
-            .hooks
-            .iter()
-            .map(|h| {
-                if let Some(args) = &h.args {
-                    format!("{} {:?}", h.command, args)
-                } else {
-                    h.command.clone()
-                }
-            })
-            .collect();
-
-        self.status_manager
-            .initialize_hooks(hook_names)
-            .map_err(|e| {
-                cuenv_core::Error::configuration(format!("Failed to initialize hooks: {e}"))
-            })?;
-
-        let mut captured_env = HashMap::new();
-        let mut handles = Vec::new();
-
-        for hook in self.hooks.iter().cloned() {
-            let status_manager = self.status_manager.clone();
-            let hook_key = if let Some(args) = &hook.args {
-                format!("{} {:?}", hook.command, args)
-            } else {
-                hook.command.clone()
-            };
-
-            let handle = tokio::spawn(async move {
-                let _ = status_manager.mark_hook_started(&hook_key, 0);
-                let result = execute_hook_with_timeout(&hook, Duration::from_secs(60), true).await;
-                match result {
-                    Ok((output, pid)) => {
-                        if let Some(actual_pid) = pid {
-                            let _ = status_manager.mark_hook_started(&hook_key, actual_pid);
-                        }
-                        let _ = status_manager.mark_hook_completed(&hook_key);
-                        output
-                    }
-                    Err(e) => {
-                        let _ = status_manager.mark_hook_failed(&hook_key, e.to_string());
-                        None
-                    }
-                }
-            });
-            handles.push(handle);
-        }
-
-        for handle in handles {
-            if let Ok(Some(env)) = handle.await {
-                captured_env.extend(env);
-            }
-        }
-
-        if !captured_env.is_empty() {
-            let input_hash = cache::calculate_input_hash(&self.hooks)?;
-            cache::save_cached_environment(&self.cache_dir, &input_hash, captured_env)?;
-        }
-
-        self.status_manager.clear_status()?;
-        Ok(())
+        let schedule = self.schedule(true)?;
+        self.initialize_status()?;
+        let captured_env = schedule.await;
+        self.finish(&input_hash, captured_env)
     }
 
     async fn run_background(&self) -> Result<()> {
@@ -423,111 +315,151 @@ impl Supervisor {
         }
 
         // Check if we need to run based on inputs
-        let input_hash = cache::calculate_input_hash(&self.hooks)?;
+        let input_hash = cache::calculate_input_hash(&self.hooks, &self.directory)?;
         if let Ok(cached_env) = cache::load_cached_environment(&self.cache_dir, &input_hash) {
             // Inputs haven't changed, use cached environment
             cache::apply_cached_environment(&self.cache_dir, cached_env)?;
             return Ok(());
         }
 
+        let schedule = self.schedule(false)?;
         eprintln!(
             "# cuenv: Starting {} hook(s) in background...",
             self.hooks.len()
         );
+        self.initialize_status()?;
+        let captured_env = schedule.await;
+        self.finish(&input_hash, captured_env)?;
+        eprintln!("# cuenv: ✓ All hooks completed");
+        Ok(())
+    }
 
+    /// Reset status tracking to the hooks about to run
+    fn initialize_status(&self) -> Result<()> {
         // Clear any stale status from previous runs
         self.status_manager.clear_status().map_err(|e| {
             cuenv_core::Error::configuration(format!("Failed to clear status: {e}"))
         })?;
 
-        // Initialize status tracking
-        let hook_names: Vec<String> = self
-            .hooks
-            .iter()
-            .map(|h| {
-                if let Some(args) = &h.args {
-                    format!("{} {:?}", h.command, args)
-                } else {
-                    h.command.clone()
-                }
-            })
-            .collect();
-
+        let hook_names: Vec<String> = self.hooks.iter().map(status_key).collect();
         self.status_manager
             .initialize_hooks(hook_names)
             .map_err(|e| {
                 cuenv_core::Error::configuration(format!("Failed to initialize hooks: {e}"))
-            })?;
-
-        // Collect environment from source hooks
-        let mut captured_env = HashMap::new();
+            })
+    }
 
-        // Execute all hooks
-        let mut handles = Vec::new();
-        for hook in self.hooks.iter().cloned() {
-            let hook_key = if let Some(args) = &hook.args {
-                format!("{} {:?}", hook.command, args)
-            } else {
-                hook.command.clone()
-            };
-
-            let status_manager = self.status_manager.clone();
-            let hook_clone = hook.clone();
-            let hook_key_clone = hook_key.clone();
-
-            eprintln!("# cuenv: Running hook: {}", hook.command);
-
-            // Mark hook as started
-            let pid = std::process::id();
-            status_manager
-                .mark_hook_started(&hook_key, pid)
-                .map_err(|e| {
-                    cuenv_core::Error::configuration(format!("Failed to update status: {e}"))
-                })?;
-
-            let handle = tokio::spawn(async move {
-                let result =
-                    execute_hook_with_timeout(&hook_clone, Duration::from_secs(60), false).await;
-
-                match result {
-                    Ok((output, pid)) => {
-                        // Update with actual PID if we got one
-                        if let Some(actual_pid) = pid {
-                            let _ = status_manager.mark_hook_started(&hook_key_clone, actual_pid);
+    /// Run the hooks in dependency order, resolving to the sourced environment
+    ///
+    /// The dependency graph is checked before anything runs, so a bad
+    /// `dependsOn` fails here rather than part way through.
+    fn schedule(
+        &self,
+        silent: bool,
+    ) -> Result<impl Future<Output = HashMap<String, String>> + Send + 'static> {
+        let graph = HookGraph::new(&self.hooks)?;
+        let hooks = self.hooks.clone();
+        let parallelism = self.parallelism;
+        let announce = self.mode == SupervisorMode::Background;
+        let status_manager = Arc::clone(&self.status_manager);
+        let skipped = Arc::clone(&self.status_manager);
+
+        Ok(async move {
+            graph
+                .run(
+                    &hooks,
+                    parallelism,
+                    move |hook, env| {
+                        let status_manager = Arc::clone(&status_manager);
+                        async move { run_hook(hook, env, &status_manager, silent, announce).await }
+                    },
+                    move |hook, dependency| {
+                        let reason = format!("dependency {} failed", label(dependency));
+                        if announce {
+                            eprintln!("# cuenv: Skipping hook {}: {reason}", label(hook));
                         }
-                        eprintln!("# cuenv: Hook completed: {}", hook_clone.command);
-                        let _ = status_manager.mark_hook_completed(&hook_key_clone);
-
-                        // Return environment if this was a source hook
-                        output
-                    }
-                    Err(e) => {
-                        eprintln!("# cuenv: Hook failed: {}: {}", hook_clone.command, e);
-                        let _ = status_manager.mark_hook_failed(&hook_key_clone, e.to_string());
-                        None
-                    }
-                }
-            });
+                        let _ = skipped.mark_hook_failed(&status_key(hook), reason);
+                    },
+                )
+                .await
+        })
+    }
 
-            handles.push(handle);
+    /// Save the captured environment and clear status after a completed run
+    fn finish(&self, input_hash: &str, captured_env: HashMap<String, String>) -> Result<()> {
+        if !captured_env.is_empty() {
+            cache::save_cached_environment(&self.cache_dir, input_hash, captured_env)?;
         }
 
-        // Wait for all hooks to complete
-        for handle in handles {
-            if let Ok(Some(env)) = handle.await {
-                captured_env.extend(env);
+        self.status_manager.clear_status()?;
+        Ok(())
+    }
+}
+
+/// Run one hook, tracking its progress in the status file
+///
+/// Fails when the hook could not be run, so hooks depending on it are
+/// skipped.
+async fn run_hook(
+    hook: Hook,
+    env: HashMap<String, String>,
+    status_manager: &HooksStatusManager,
+    silent: bool,
+    announce: bool,
+) -> Result<HashMap<String, String>> {
+    let hook_key = status_key(&hook);
+    if announce {
+        eprintln!("# cuenv: Running hook: {}", hook.command);
+    }
+    let _ = status_manager.mark_hook_started(&hook_key, std::process::id());
+    publish_global_event(SystemEvent::Env(EnvEvent::HookStarted {
+        command: hook.command.clone(),
+    }))
+    .await;
+    let started = Instant::now();
+
+    match execute_hook_with_timeout(&hook, &env, HOOK_TIMEOUT, silent).await {
+        Ok((output, pid)) => {
+            publish_global_event(SystemEvent::Env(EnvEvent::HookCompleted {
+                command: hook.command.clone(),
+                duration_ms: started.elapsed().as_millis() as u64,
+            }))
+            .await;
+            // Update with actual PID if we got one
+            if let Some(actual_pid) = pid {
+                let _ = status_manager.mark_hook_started(&hook_key, actual_pid);
+            }
+            if announce {
+                eprintln!("# cuenv: Hook completed: {}", hook.command);
             }
+            let _ = status_manager.mark_hook_completed(&hook_key);
+            Ok(output.unwrap_or_default())
         }
-
-        // Save captured environment if any
-        if !captured_env.is_empty() {
-            let input_hash = cache::calculate_input_hash(&self.hooks)?;
-            cache::save_cached_environment(&self.cache_dir, &input_hash, captured_env)?;
+        Err(e) => {
+            publish_global_event(SystemEvent::Env(EnvEvent::HookFailed {
+                command: hook.command.clone(),
+                error: e.to_string(),
+            }))
+            .await;
+            if announce {
+                eprintln!("# cuenv: Hook failed: {}: {}", hook.command, e);
+            }
+            let _ = status_manager.mark_hook_failed(&hook_key, e.to_string());
+            Err(e)
         }
+    }
+}
 
-        // Clear status after successful completion
-        self.status_manager.clear_status()?;
-        eprintln!("# cuenv: ✓ All hooks completed");
-        Ok(())
+/// Hooks run at once unless configured: one per CPU
+fn default_parallelism() -> usize {
+    std::thread::available_parallelism().map_or(1, usize::from)
+}
+
+/// Key a hook is tracked under in the status file
+fn status_key(hook: &Hook) -> String {
+    if let Some(args) = &hook.args {
+        format!("{} {:?}", hook.command, args)
+    } else {
+        hook.command.clone()
     }
 }
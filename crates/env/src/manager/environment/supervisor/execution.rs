@@ -1,6 +1,7 @@
 //! Hook execution functionality
 
 use cuenv_config::Hook;
+use cuenv_core::events::{global_timeline, SpanKind};
 use cuenv_core::Result;
 use std::collections::HashMap;
 use std::process::Stdio;
@@ -9,22 +10,40 @@ use tokio::process::Command;
 use tokio::time::timeout;
 
 /// Execute a hook with timeout and capture environment if needed
-/// Returns the output and the actual process PID
+///
+/// `env` is set on top of the inherited environment, so a hook sees what
+/// the hooks it depends on sourced. Returns the output and the actual
+/// process PID.
 pub async fn execute_hook_with_timeout(
     hook: &Hook,
+    env: &HashMap<String, String>,
     timeout_duration: Duration,
     silent: bool,
 ) -> Result<(Option<HashMap<String, String>>, Option<u32>)> {
+    let span = global_timeline().start(SpanKind::Hook, hook_label(hook));
+
     // For source hooks, we need to evaluate the output as shell script
-    if hook.source.unwrap_or(false) {
-        execute_source_hook(hook, timeout_duration, silent).await
+    let result = if hook.source.unwrap_or(false) {
+        execute_source_hook(hook, env, timeout_duration, silent).await
     } else {
-        execute_regular_hook(hook, timeout_duration, silent).await
-    }
+        execute_regular_hook(hook, env, timeout_duration, silent).await
+    };
+
+    span.finish(&result);
+    result
+}
+
+/// Command line shown for a hook on the timeline
+fn hook_label(hook: &Hook) -> String {
+    std::iter::once(hook.command.as_str())
+        .chain(hook.args.iter().flatten().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 async fn execute_source_hook(
     hook: &Hook,
+    env: &HashMap<String, String>,
     timeout_duration: Duration,
     silent: bool,
 ) -> Result<(Option<HashMap<String, String>>, Option<u32>)> {
@@ -70,7 +89,7 @@ comm -z -13 "$TEMP_BEFORE" "$TEMP_AFTER"
         cmd.current_dir(dir);
     }
 
-    // No environment variables in Hook struct
+    cmd.envs(env);
 
     let child = cmd.spawn().map_err(|e| {
         cuenv_core::Error::configuration(format!("Failed to spawn hook process: {e}"))
@@ -127,6 +146,7 @@ comm -z -13 "$TEMP_BEFORE" "$TEMP_AFTER"
 
 async fn execute_regular_hook(
     hook: &Hook,
+    env: &HashMap<String, String>,
     timeout_duration: Duration,
     silent: bool,
 ) -> Result<(Option<HashMap<String, String>>, Option<u32>)> {
@@ -145,7 +165,7 @@ async fn execute_regular_hook(
         cmd.stderr(Stdio::null());
     }
 
-    // No environment variables in Hook struct
+    cmd.envs(env);
 
     let child = cmd.spawn().map_err(|e| {
         cuenv_core::Error::configuration(format!("Failed to spawn hook process: {e}"))
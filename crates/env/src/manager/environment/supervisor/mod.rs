@@ -3,11 +3,21 @@
 mod cache;
 mod core;
 mod execution;
+mod schedule;
 mod utils;
 
 #[cfg(test)]
 mod tests;
 
-pub use cache::CapturedEnvironment;
+pub use cache::{calculate_input_hash, CapturedEnvironment, HOOK_LOCKFILES};
 pub use core::{Supervisor, SupervisorMode};
 pub use utils::get_cache_dir;
+
+/// Drop cached hook environments for `directory`, forcing hooks to re-run
+///
+/// Only the directory's own state is touched; other projects keep theirs.
+/// Returns the number of cache entries removed.
+pub fn refresh_hook_cache(directory: &std::path::Path) -> cuenv_core::Result<usize> {
+    let state_dir = cuenv_utils::paths::get_state_dir(directory);
+    cache::clear_cached_environments(&state_dir)
+}
@@ -0,0 +1,198 @@
+//! Dependency-ordered hook scheduling
+//!
+//! Hooks name themselves with `name` and list the hooks that must finish
+//! first in `dependsOn`. A hook starts once its dependencies are done, with
+//! at most `parallelism` hooks running at a time. Each hook sees the
+//! environment sourced by the hooks it depends on, and sourced environments
+//! are merged in dependency order with declaration order breaking ties, so
+//! the result does not depend on which hook happens to finish first.
+
+use cuenv_config::Hook;
+use cuenv_core::{Error, Result};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+
+/// How a scheduled hook ended
+enum Outcome {
+    Sourced(HashMap<String, String>),
+    Failed,
+}
+
+/// Dependencies between hooks, validated up front
+#[derive(Debug)]
+pub struct HookGraph {
+    /// Indices of the hooks each hook depends on
+    dependencies: Vec<Vec<usize>>,
+    /// Indices of the hooks depending on each hook
+    dependents: Vec<Vec<usize>>,
+    /// Hooks in dependency order, declaration order breaking ties
+    order: Vec<usize>,
+}
+
+impl HookGraph {
+    /// Resolve `dependsOn` names, failing on duplicate names, unknown
+    /// dependencies and cycles
+    pub fn new(hooks: &[Hook]) -> Result<Self> {
+        let mut names = HashMap::new();
+        for (index, hook) in hooks.iter().enumerate() {
+            if let Some(name) = &hook.name {
+                if names.insert(name.as_str(), index).is_some() {
+                    return Err(Error::configuration(format!(
+                        "Duplicate hook name '{name}'"
+                    )));
+                }
+            }
+        }
+
+        let mut dependencies = Vec::with_capacity(hooks.len());
+        let mut dependents = vec![Vec::new(); hooks.len()];
+        for (index, hook) in hooks.iter().enumerate() {
+            let mut resolved = hook
+                .depends_on
+                .iter()
+                .flatten()
+                .map(|name| {
+                    names.get(name.as_str()).copied().ok_or_else(|| {
+                        Error::configuration(format!(
+                            "Hook '{}' depends on unknown hook '{name}'",
+                            label(hook)
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            resolved.sort_unstable();
+            resolved.dedup();
+            for &dependency in &resolved {
+                dependents[dependency].push(index);
+            }
+            dependencies.push(resolved);
+        }
+
+        // Kahn's algorithm, always taking the earliest declared ready hook
+        let mut waiting: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut ready: BTreeSet<usize> = (0..hooks.len()).filter(|&i| waiting[i] == 0).collect();
+        let mut order = Vec::with_capacity(hooks.len());
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for &dependent in &dependents[index] {
+                waiting[dependent] -= 1;
+                if waiting[dependent] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+        if order.len() < hooks.len() {
+            let cycle: Vec<String> = (0..hooks.len())
+                .filter(|&i| waiting[i] > 0)
+                .map(|i| label(&hooks[i]))
+                .collect();
+            return Err(Error::configuration(format!(
+                "Hook dependencies form a cycle between: {}",
+                cycle.join(", ")
+            )));
+        }
+
+        Ok(Self {
+            dependencies,
+            dependents,
+            order,
+        })
+    }
+
+    /// Run `hooks` in dependency order, at most `parallelism` at a time
+    ///
+    /// `run` gets each hook with the environment sourced by its dependencies
+    /// and returns what the hook sourced. Hooks depending on a failed hook
+    /// are not run; `skip` gets them with the dependency that failed.
+    /// Returns the merged sourced environment.
+    pub async fn run<F, Fut, S>(
+        &self,
+        hooks: &[Hook],
+        parallelism: usize,
+        mut run: F,
+        mut skip: S,
+    ) -> HashMap<String, String>
+    where
+        F: FnMut(Hook, HashMap<String, String>) -> Fut,
+        Fut: Future<Output = Result<HashMap<String, String>>>,
+        S: FnMut(&Hook, &Hook),
+    {
+        let limit = parallelism.max(1);
+        let mut outcomes: Vec<Option<Outcome>> = hooks.iter().map(|_| None).collect();
+        let mut waiting: Vec<usize> = self.dependencies.iter().map(Vec::len).collect();
+        let mut ready: BTreeSet<usize> = (0..hooks.len()).filter(|&i| waiting[i] == 0).collect();
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            while running.len() < limit {
+                let Some(index) = ready.pop_first() else {
+                    break;
+                };
+                let failed = self.dependencies[index]
+                    .iter()
+                    .find(|&&dependency| matches!(outcomes[dependency], Some(Outcome::Failed)));
+                if let Some(&failed) = failed {
+                    skip(&hooks[index], &hooks[failed]);
+                    outcomes[index] = Some(Outcome::Failed);
+                    self.release(index, &mut waiting, &mut ready);
+                    continue;
+                }
+
+                let hook = run(hooks[index].clone(), self.inherited_env(index, &outcomes));
+                running.push(async move { (index, hook.await) });
+            }
+
+            let Some((index, result)) = running.next().await else {
+                break;
+            };
+            outcomes[index] = Some(match result {
+                Ok(env) => Outcome::Sourced(env),
+                Err(_) => Outcome::Failed,
+            });
+            self.release(index, &mut waiting, &mut ready);
+        }
+
+        let mut merged = HashMap::new();
+        for &index in &self.order {
+            if let Some(Outcome::Sourced(env)) = outcomes[index].take() {
+                merged.extend(env);
+            }
+        }
+        merged
+    }
+
+    /// Mark `index` done, readying dependents with nothing left to wait for
+    fn release(&self, index: usize, waiting: &mut [usize], ready: &mut BTreeSet<usize>) {
+        for &dependent in &self.dependents[index] {
+            waiting[dependent] -= 1;
+            if waiting[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+
+    /// Environment sourced by everything `index` depends on, directly or not
+    fn inherited_env(&self, index: usize, outcomes: &[Option<Outcome>]) -> HashMap<String, String> {
+        let mut ancestor = vec![false; self.dependencies.len()];
+        let mut stack = self.dependencies[index].clone();
+        while let Some(dependency) = stack.pop() {
+            if !std::mem::replace(&mut ancestor[dependency], true) {
+                stack.extend(&self.dependencies[dependency]);
+            }
+        }
+
+        let mut env = HashMap::new();
+        for &i in self.order.iter().filter(|&&i| ancestor[i]) {
+            if let Some(Outcome::Sourced(sourced)) = &outcomes[i] {
+                env.extend(sourced.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+        }
+        env
+    }
+}
+
+/// Name of a hook in messages: its `name`, or else its command
+pub fn label(hook: &Hook) -> String {
+    hook.name.clone().unwrap_or_else(|| hook.command.clone())
+}
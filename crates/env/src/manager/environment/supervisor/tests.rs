@@ -1,19 +1,29 @@
-use super::cache::{calculate_input_hash, CapturedEnvironment};
+use super::cache::{
+    calculate_input_hash, clear_cached_environments, save_cached_environment, CapturedEnvironment,
+};
 use super::core::{Supervisor, SupervisorMode};
 use super::execution::execute_hook_with_timeout;
+use super::schedule::HookGraph;
 use super::utils::get_cache_dir;
 use cuenv_config::Hook;
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
 
 fn create_test_hook(command: &str, args: Vec<String>, preload: bool, source: bool) -> Hook {
     Hook {
+        name: None,
+        depends_on: None,
         command: command.to_string(),
         args: Some(args),
         dir: None,
         preload: Some(preload),
+        fetch: None,
+        compose: None,
         source: Some(source),
         inputs: None,
     }
@@ -21,15 +31,27 @@ fn create_test_hook(command: &str, args: Vec<String>, preload: bool, source: boo
 
 fn create_test_hook_with_inputs(command: &str, args: Vec<String>, inputs: Vec<String>) -> Hook {
     Hook {
+        name: None,
+        depends_on: None,
         command: command.to_string(),
         args: Some(args),
         dir: None,
         preload: Some(true),
+        fetch: None,
+        compose: None,
         source: Some(false),
         inputs: Some(inputs),
     }
 }
 
+fn create_named_hook(name: &str, depends_on: &[&str]) -> Hook {
+    Hook {
+        name: Some(name.to_string()),
+        depends_on: Some(depends_on.iter().map(ToString::to_string).collect()),
+        ..create_test_hook(name, vec![], false, true)
+    }
+}
+
 #[tokio::test]
 async fn test_input_hash_consistency() {
     let hooks = vec![create_test_hook(
@@ -39,8 +61,8 @@ async fn test_input_hash_consistency() {
         false,
     )];
 
-    let hash1 = calculate_input_hash(&hooks).unwrap();
-    let hash2 = calculate_input_hash(&hooks).unwrap();
+    let hash1 = calculate_input_hash(&hooks, Path::new(".")).unwrap();
+    let hash2 = calculate_input_hash(&hooks, Path::new(".")).unwrap();
 
     assert_eq!(hash1, hash2, "Hash should be consistent for same inputs");
 }
@@ -60,8 +82,8 @@ async fn test_input_hash_different_commands() {
         false,
     )];
 
-    let hash1 = calculate_input_hash(&hooks1).unwrap();
-    let hash2 = calculate_input_hash(&hooks2).unwrap();
+    let hash1 = calculate_input_hash(&hooks1, Path::new(".")).unwrap();
+    let hash2 = calculate_input_hash(&hooks2, Path::new(".")).unwrap();
 
     assert_ne!(hash1, hash2, "Hash should differ for different commands");
 }
@@ -78,17 +100,69 @@ async fn test_input_hash_with_file_changes() {
         vec![file_path.to_string_lossy().to_string()],
     )];
 
-    let hash1 = calculate_input_hash(&hooks).unwrap();
+    let hash1 = calculate_input_hash(&hooks, Path::new(".")).unwrap();
 
     // Modify the file
     std::thread::sleep(std::time::Duration::from_millis(10));
     fs::write(&file_path, "modified content").unwrap();
 
-    let hash2 = calculate_input_hash(&hooks).unwrap();
+    let hash2 = calculate_input_hash(&hooks, Path::new(".")).unwrap();
 
-    // Hashes should be the same since we're not monitoring file contents in inputs
-    // (inputs just tracks paths, not contents)
-    assert_eq!(hash1, hash2);
+    // Declared input files are keyed on their contents
+    assert_ne!(hash1, hash2);
+}
+
+#[tokio::test]
+async fn test_input_hash_tracks_lockfiles_for_source_hooks() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_hooks = vec![create_test_hook("nix", vec![], false, true)];
+    let plain_hooks = vec![create_test_hook("nix", vec![], false, false)];
+
+    let source_before = calculate_input_hash(&source_hooks, temp_dir.path()).unwrap();
+    let plain_before = calculate_input_hash(&plain_hooks, temp_dir.path()).unwrap();
+
+    fs::write(temp_dir.path().join("flake.lock"), "{\"version\": 7}").unwrap();
+
+    assert_ne!(
+        source_before,
+        calculate_input_hash(&source_hooks, temp_dir.path()).unwrap()
+    );
+    assert_eq!(
+        plain_before,
+        calculate_input_hash(&plain_hooks, temp_dir.path()).unwrap()
+    );
+}
+
+#[test]
+fn test_clear_cached_environments() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_vars = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+    save_cached_environment(temp_dir.path(), &"a".repeat(64), env_vars).unwrap();
+    fs::write(temp_dir.path().join("hooks_status.json"), "{}").unwrap();
+
+    assert_eq!(clear_cached_environments(temp_dir.path()).unwrap(), 2);
+    assert!(temp_dir.path().join("hooks_status.json").exists());
+    assert_eq!(clear_cached_environments(temp_dir.path()).unwrap(), 0);
+}
+
+#[test]
+fn test_refresh_hook_cache_leaves_other_projects() {
+    let project = TempDir::new().unwrap();
+    let other = TempDir::new().unwrap();
+    let env_vars = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+    for dir in [project.path(), other.path()] {
+        let state_dir = cuenv_utils::paths::get_state_dir(dir);
+        fs::create_dir_all(&state_dir).unwrap();
+        save_cached_environment(&state_dir, &"a".repeat(64), env_vars.clone()).unwrap();
+    }
+
+    assert_eq!(super::refresh_hook_cache(project.path()).unwrap(), 2);
+    let other_state = cuenv_utils::paths::get_state_dir(other.path());
+    assert!(other_state
+        .join(format!("{}.json", "a".repeat(64)))
+        .exists());
+    fs::remove_dir_all(other_state).unwrap();
+    fs::remove_dir_all(cuenv_utils::paths::get_state_dir(project.path())).unwrap();
 }
 
 #[tokio::test]
@@ -115,7 +189,8 @@ async fn test_captured_environment_serialization() {
 #[tokio::test]
 async fn test_execute_hook_non_source() {
     let hook = create_test_hook("echo", vec!["hello".to_string()], false, false);
-    let result = execute_hook_with_timeout(&hook, Duration::from_secs(5), true).await;
+    let result =
+        execute_hook_with_timeout(&hook, &HashMap::new(), Duration::from_secs(5), true).await;
 
     assert!(result.is_ok());
     assert_eq!(
@@ -128,14 +203,16 @@ async fn test_execute_hook_non_source() {
 #[tokio::test]
 async fn test_execute_hook_failure() {
     let hook = create_test_hook("false", vec![], false, false);
-    let result = execute_hook_with_timeout(&hook, Duration::from_secs(5), true).await;
+    let result =
+        execute_hook_with_timeout(&hook, &HashMap::new(), Duration::from_secs(5), true).await;
     assert!(result.is_ok(), "Should handle command failure gracefully");
 }
 
 #[tokio::test]
 async fn test_execute_hook_timeout() {
     let hook = create_test_hook("sleep", vec!["10".to_string()], false, false);
-    let result = execute_hook_with_timeout(&hook, Duration::from_millis(100), true).await;
+    let result =
+        execute_hook_with_timeout(&hook, &HashMap::new(), Duration::from_millis(100), true).await;
 
     // Should not error, just return None for timed out hooks
     assert!(result.is_ok());
@@ -165,15 +242,20 @@ echo 'export TEST_VAR2="value2"'
     }
 
     let hook = Hook {
+        name: None,
+        depends_on: None,
         command: script_path.to_string_lossy().to_string(),
         args: None,
         dir: None,
         source: Some(true),
         preload: Some(false),
+        fetch: None,
+        compose: None,
         inputs: None,
     };
 
-    let result = execute_hook_with_timeout(&hook, Duration::from_secs(5), true).await;
+    let result =
+        execute_hook_with_timeout(&hook, &HashMap::new(), Duration::from_secs(5), true).await;
 
     assert!(result.is_ok());
     let env_vars = result.unwrap().0.unwrap();
@@ -296,3 +378,113 @@ async fn test_save_and_load_cached_environment() {
         Some(&"cached_value".to_string())
     );
 }
+
+#[test]
+fn test_hook_graph_rejects_invalid_dependencies() {
+    let unknown = HookGraph::new(&[create_named_hook("tf", &["nix"])]).unwrap_err();
+    assert!(unknown.to_string().contains("unknown hook 'nix'"));
+
+    let cycle = HookGraph::new(&[
+        create_named_hook("a", &["b"]),
+        create_named_hook("b", &["a"]),
+        create_named_hook("c", &[]),
+    ])
+    .unwrap_err();
+    assert!(cycle.to_string().contains("cycle between: a, b"));
+
+    let duplicate =
+        HookGraph::new(&[create_named_hook("a", &[]), create_named_hook("a", &[])]).unwrap_err();
+    assert!(duplicate.to_string().contains("Duplicate hook name 'a'"));
+}
+
+#[tokio::test]
+async fn test_hooks_run_after_dependencies_with_bounded_parallelism() {
+    // terraform needs what nix sourced; lint and docs depend on nothing
+    let hooks = vec![
+        create_named_hook("terraform", &["nix"]),
+        create_named_hook("nix", &[]),
+        create_named_hook("lint", &[]),
+        create_named_hook("docs", &[]),
+    ];
+    let graph = HookGraph::new(&hooks).unwrap();
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let env = graph
+        .run(
+            &hooks,
+            2,
+            |hook, inherited| {
+                let (running, peak, started) = (running.clone(), peak.clone(), started.clone());
+                async move {
+                    let name = hook.name.unwrap();
+                    started.lock().unwrap().push(name.clone());
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+
+                    let mut sourced = HashMap::from([("LAST".to_string(), name.clone())]);
+                    if name == "terraform" {
+                        assert_eq!(inherited.get("NIX").map(String::as_str), Some("1"));
+                        sourced.insert("TF_OUTPUT".to_string(), "ok".to_string());
+                    } else {
+                        assert!(inherited.is_empty());
+                    }
+                    if name == "nix" {
+                        sourced.insert("NIX".to_string(), "1".to_string());
+                    }
+                    Ok(sourced)
+                }
+            },
+            |hook, _| panic!("{} skipped", hook.command),
+        )
+        .await;
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    let started = started.lock().unwrap();
+    let position = |name: &str| started.iter().position(|n| n == name).unwrap();
+    assert!(position("nix") < position("terraform"));
+
+    // Merged in dependency order, declaration order breaking ties
+    assert_eq!(env.get("LAST").map(String::as_str), Some("docs"));
+    assert_eq!(env.get("NIX").map(String::as_str), Some("1"));
+    assert_eq!(env.get("TF_OUTPUT").map(String::as_str), Some("ok"));
+}
+
+#[tokio::test]
+async fn test_hooks_depending_on_failed_hook_are_skipped() {
+    let hooks = vec![
+        create_named_hook("nix", &[]),
+        create_named_hook("terraform", &["nix"]),
+        create_named_hook("outputs", &["terraform"]),
+        create_named_hook("lint", &[]),
+    ];
+    let graph = HookGraph::new(&hooks).unwrap();
+
+    let mut skipped = Vec::new();
+    let env = graph
+        .run(
+            &hooks,
+            4,
+            |hook, _| async move {
+                match hook.command.as_str() {
+                    "nix" => Err(cuenv_core::Error::configuration("nix failed")),
+                    "lint" => Ok(HashMap::from([("LINT".to_string(), "1".to_string())])),
+                    other => panic!("{other} should not run"),
+                }
+            },
+            |hook, dependency| skipped.push((hook.command.clone(), dependency.command.clone())),
+        )
+        .await;
+
+    assert_eq!(
+        skipped,
+        vec![
+            ("terraform".to_string(), "nix".to_string()),
+            ("outputs".to_string(), "terraform".to_string()),
+        ]
+    );
+    assert_eq!(env, HashMap::from([("LINT".to_string(), "1".to_string())]));
+}
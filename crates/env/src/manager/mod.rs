@@ -1,5 +1,7 @@
 use cuenv_config::{CommandConfig, HookConfig, TaskConfig, TaskNode};
+use cuenv_core::events::{publish_global_event, EnvEvent, EventTemplates, SystemEvent};
 use cuenv_core::{Error, Result};
+use cuenv_security::AuditReport;
 use cuenv_utils::sync::env::SyncEnv;
 use indexmap::IndexMap;
 use std::collections::HashMap;
@@ -9,11 +11,12 @@ mod command;
 pub mod environment;
 mod export;
 mod hooks;
-mod secrets;
+pub mod secrets;
 pub mod stubs;
 mod task;
 
-pub use stubs::{AccessRestrictions, Shell};
+pub use cuenv_security::AccessRestrictions;
+pub use stubs::Shell;
 pub use task::TaskSource;
 
 use self::environment::SupervisorMode;
@@ -24,10 +27,23 @@ pub struct EnvManager {
     sourced_env: HashMap<String, String>, // Environment from hooks (nix, devenv, etc.)
     cue_vars: HashMap<String, String>,
     cue_vars_metadata: HashMap<String, cuenv_config::VariableMetadata>,
+    /// Lazy secrets by name, still unresolved and kept out of the shell
+    deferred_secrets: HashMap<String, String>,
     commands: HashMap<String, CommandConfig>,
     tasks: HashMap<String, TaskConfig>,
     task_nodes: IndexMap<String, TaskNode>, // Preserve task structure and insertion order
     hooks: HashMap<String, HookConfig>,
+    /// `config: strictVariables` of the loaded package
+    strict_variables: bool,
+    /// `config: outputTemplates` and `outputJson` of the loaded package
+    event_templates: EventTemplates,
+    /// Environment selected with `-e`, if any
+    environment: Option<String>,
+    /// Capabilities enabled when loading
+    capabilities: Vec<String>,
+    persist_state: bool,
+    /// Set loaded variables in this process's environment
+    export_to_process: bool,
 }
 
 impl EnvManager {
@@ -37,10 +53,40 @@ impl EnvManager {
             sourced_env: HashMap::with_capacity(100),
             cue_vars: HashMap::with_capacity(50),
             cue_vars_metadata: HashMap::with_capacity(50),
+            deferred_secrets: HashMap::new(),
             commands: HashMap::with_capacity(20),
             tasks: HashMap::with_capacity(20),
             task_nodes: IndexMap::with_capacity(20),
             hooks: HashMap::with_capacity(4),
+            strict_variables: false,
+            event_templates: EventTemplates::default(),
+            environment: None,
+            capabilities: Vec::new(),
+            persist_state: true,
+            export_to_process: true,
+        }
+    }
+
+    /// Manager whose environment only lives in the commands it spawns
+    ///
+    /// Loading never records shell state, so the parent shell's loaded
+    /// environment and the shell hook are unaffected.
+    pub fn scoped() -> Self {
+        Self {
+            persist_state: false,
+            ..Self::new()
+        }
+    }
+
+    /// Manager for embedding cuenv in another program
+    ///
+    /// Like [`EnvManager::scoped`], but loaded variables are also kept out of
+    /// this process's environment: they only reach the commands and tasks
+    /// the manager runs.
+    pub fn embedded() -> Self {
+        Self {
+            export_to_process: false,
+            ..Self::scoped()
         }
     }
 }
@@ -66,6 +112,8 @@ impl EnvManager {
         mode: SupervisorMode,
     ) -> Result<()> {
         self.save_original_env()?;
+        self.environment = environment.clone();
+        self.capabilities = capabilities.clone();
 
         let mut context = environment::LoadEnvironmentContext {
             commands: &mut self.commands,
@@ -74,10 +122,20 @@ impl EnvManager {
             hooks: &mut self.hooks,
             cue_vars: &mut self.cue_vars,
             cue_vars_metadata: &mut self.cue_vars_metadata,
+            deferred_secrets: &mut self.deferred_secrets,
             sourced_env: &mut self.sourced_env,
+            strict_variables: &mut self.strict_variables,
+            event_templates: &mut self.event_templates,
+            persist_state: self.persist_state,
+            export_to_process: self.export_to_process,
         };
 
-        environment::load_env_with_options(
+        let path = dir.display().to_string();
+        publish_global_event(SystemEvent::Env(EnvEvent::EnvLoading {
+            path: path.clone(),
+        }))
+        .await;
+        let loaded = environment::load_env_with_options(
             dir,
             environment,
             capabilities,
@@ -86,7 +144,19 @@ impl EnvManager {
             &mut context,
             mode,
         )
-        .await?;
+        .await;
+        let event = match &loaded {
+            Ok(()) => EnvEvent::EnvLoaded {
+                path,
+                var_count: self.cue_vars.len(),
+            },
+            Err(e) => EnvEvent::EnvLoadFailed {
+                path,
+                error: e.to_string(),
+            },
+        };
+        publish_global_event(SystemEvent::Env(event)).await;
+        loaded?;
 
         // Execute remaining onEnter hooks after environment variables are set
         environment::execute_on_enter_hooks(&self.hooks)?;
@@ -99,7 +169,9 @@ impl EnvManager {
             &self.hooks,
             &mut self.cue_vars,
             &mut self.cue_vars_metadata,
-        )
+        )?;
+        self.deferred_secrets.clear();
+        Ok(())
     }
 
     fn save_original_env(&mut self) -> Result<()> {
@@ -125,7 +197,7 @@ impl EnvManager {
             command,
             args,
             &self.sourced_env,
-            &self.cue_vars,
+            &self.command_vars(),
             &self.original_env,
         )
     }
@@ -140,7 +212,7 @@ impl EnvManager {
             command,
             args,
             &self.sourced_env,
-            &self.cue_vars,
+            &self.command_vars(),
             &current_env, // Use current process env instead of original_env
         )
     }
@@ -157,7 +229,18 @@ impl EnvManager {
             args,
             restrictions,
             &self.sourced_env,
-            &self.cue_vars,
+            &self.command_vars(),
+            &self.original_env,
+        )
+    }
+
+    /// Run a command under audit monitoring, reporting the access it performs
+    pub fn audit_command(&self, command: &str, args: &[String]) -> Result<(i32, AuditReport)> {
+        command::audit_command(
+            command,
+            args,
+            &self.sourced_env,
+            &self.command_vars(),
             &self.original_env,
         )
     }
@@ -180,11 +263,59 @@ impl EnvManager {
         &self.tasks
     }
 
+    /// Whether task builds must reject references to undefined variables
+    pub fn strict_variables(&self) -> bool {
+        self.strict_variables
+    }
+
+    /// How the `template` and `json` output formats render events
+    pub fn event_templates(&self) -> &EventTemplates {
+        &self.event_templates
+    }
+
+    /// Lazy secrets of the loaded environment, by name, still unresolved
+    pub fn deferred_secrets(&self) -> &HashMap<String, String> {
+        &self.deferred_secrets
+    }
+
+    /// Resolve the lazy secrets among `names`; other names are ignored
+    pub fn resolve_deferred<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HashMap<String, String>> {
+        secrets::resolve_deferred(&self.deferred_secrets, names)
+    }
+
+    /// Variables for commands run through cuenv: lazy secrets are included,
+    /// since running a command is what they wait for
+    fn command_vars(&self) -> HashMap<String, String> {
+        self.cue_vars
+            .iter()
+            .chain(&self.deferred_secrets)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Environment selected with `-e` when loading, if any
+    pub fn environment(&self) -> Option<&str> {
+        self.environment.as_deref()
+    }
+
+    /// Capabilities enabled when loading
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     /// Get CUE environment variables
     pub fn get_cue_vars(&self) -> &HashMap<String, String> {
         &self.cue_vars
     }
 
+    /// Get variables declared as deprecated or renamed in the loaded package
+    pub fn deprecated_variables(&self) -> Vec<crate::deprecation::DeprecatedVariable> {
+        crate::deprecation::deprecated_variables(&self.cue_vars_metadata)
+    }
+
     /// Get the capabilities for a specific command
     pub fn get_command_capabilities(&self, command: &str) -> Vec<String> {
         // Extract the base command from the full command string
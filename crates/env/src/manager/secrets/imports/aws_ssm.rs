@@ -0,0 +1,131 @@
+//! AWS Systems Manager Parameter Store, read through the `aws` CLI
+
+use super::{BulkProvider, Page};
+use cuenv_core::{Error, Result};
+use serde::Deserialize;
+
+/// Parameters per page
+const PAGE_SIZE: &str = "50";
+
+/// Every parameter under a path, decrypted
+pub(super) struct AwsSsm {
+    pub path: String,
+    pub region: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ParametersPage {
+    #[serde(default)]
+    parameters: Vec<Parameter>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Parameter {
+    name: String,
+    #[serde(rename = "Type", default)]
+    kind: String,
+    value: String,
+}
+
+impl BulkProvider for AwsSsm {
+    fn describe(&self) -> String {
+        format!("AWS SSM parameters under {}", self.path)
+    }
+
+    fn page_command(&self, token: Option<&str>) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = [
+            "ssm",
+            "get-parameters-by-path",
+            "--path",
+            &self.path,
+            "--recursive",
+            "--with-decryption",
+            "--output",
+            "json",
+            "--max-items",
+            PAGE_SIZE,
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        if let Some(region) = &self.region {
+            args.extend(["--region".to_string(), region.clone()]);
+        }
+        if let Some(token) = token {
+            args.extend(["--starting-token".to_string(), token.to_string()]);
+        }
+        ("aws", args)
+    }
+
+    fn parse_page(&self, output: &[u8]) -> Result<Page> {
+        let page: ParametersPage = serde_json::from_slice(output).map_err(|e| Error::Json {
+            message: format!("Unexpected output reading {}: {e}", self.describe()),
+            source: e,
+        })?;
+        Ok(Page {
+            encrypted: page
+                .parameters
+                .iter()
+                .any(|parameter| parameter.kind == "SecureString"),
+            entries: page
+                .parameters
+                .into_iter()
+                .map(|parameter| (parameter.name, parameter.value))
+                .collect(),
+            next_token: page.next_token,
+        })
+    }
+
+    fn relative_key<'a>(&self, key: &'a str) -> &'a str {
+        key.strip_prefix(self.path.trim_end_matches('/'))
+            .unwrap_or(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_follow_the_starting_token() {
+        let ssm = AwsSsm {
+            path: "/myapp/dev/".to_string(),
+            region: Some("eu-west-1".to_string()),
+        };
+        let (program, args) = ssm.page_command(Some("abc"));
+        assert_eq!(program, "aws");
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--region", "eu-west-1"]));
+        assert!(args.ends_with(&["--starting-token".to_string(), "abc".to_string()]));
+
+        let page = ssm
+            .parse_page(
+                br#"{"Parameters": [{"Name": "/myapp/dev/db/host", "Type": "String", "Value": "db"}],
+                    "NextToken": "def"}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            page,
+            Page {
+                entries: vec![("/myapp/dev/db/host".to_string(), "db".to_string())],
+                encrypted: false,
+                next_token: Some("def".to_string()),
+            }
+        );
+        let secure = ssm
+            .parse_page(
+                br#"{"Parameters": [{"Name": "/myapp/dev/db/password", "Type": "SecureString", "Value": "s3cret"}]}"#,
+            )
+            .unwrap();
+        assert!(secure.encrypted);
+        assert_eq!(ssm.relative_key("/myapp/dev/db/host"), "/db/host");
+        assert_eq!(
+            ssm.parse_page(br#"{"Parameters": []}"#).unwrap(),
+            Page::default()
+        );
+    }
+}
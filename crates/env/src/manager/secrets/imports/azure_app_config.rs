@@ -0,0 +1,77 @@
+//! Azure App Configuration, read through the `az` CLI
+//!
+//! `az appconfig kv list --all` follows the store's continuation links
+//! itself, so a store is read as a single page.
+
+use super::{BulkProvider, Page};
+use cuenv_config::AzureAppConfigImport;
+use cuenv_core::{Error, Result};
+use serde::Deserialize;
+
+/// Every key of a store under a prefix, with a label if one is given
+pub(super) struct AzureAppConfig {
+    pub import: AzureAppConfigImport,
+}
+
+#[derive(Deserialize)]
+struct KeyValue {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+impl BulkProvider for AzureAppConfig {
+    fn describe(&self) -> String {
+        let prefix = self.import.prefix.as_deref().unwrap_or_default();
+        format!(
+            "Azure App Configuration keys {prefix}* of {}",
+            self.import.store
+        )
+    }
+
+    fn page_command(&self, _token: Option<&str>) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = [
+            "appconfig",
+            "kv",
+            "list",
+            "--name",
+            &self.import.store,
+            "--all",
+            "--fields",
+            "key",
+            "value",
+            "--output",
+            "json",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        if let Some(prefix) = &self.import.prefix {
+            args.extend(["--key".to_string(), format!("{prefix}*")]);
+        }
+        if let Some(label) = &self.import.label {
+            args.extend(["--label".to_string(), label.clone()]);
+        }
+        ("az", args)
+    }
+
+    fn parse_page(&self, output: &[u8]) -> Result<Page> {
+        let values: Vec<KeyValue> = serde_json::from_slice(output).map_err(|e| Error::Json {
+            message: format!("Unexpected output reading {}: {e}", self.describe()),
+            source: e,
+        })?;
+        Ok(Page {
+            entries: values
+                .into_iter()
+                .map(|kv| (kv.key, kv.value.unwrap_or_default()))
+                .collect(),
+            encrypted: false,
+            next_token: None,
+        })
+    }
+
+    fn relative_key<'a>(&self, key: &'a str) -> &'a str {
+        let prefix = self.import.prefix.as_deref().unwrap_or_default();
+        key.strip_prefix(prefix).unwrap_or(key)
+    }
+}
@@ -0,0 +1,358 @@
+//! Bulk imports of variables from configuration stores
+//!
+//! Each store declared with `env.valueFrom` is read through its CLI a page
+//! at a time. Pages are rate limited across all imports of a load, and
+//! throttled requests are retried under the `secrets` retry policy. Keys
+//! become variable names relative to the imported path or prefix, e.g.
+//! `/myapp/dev/db/password` imported from `/myapp/dev/` is `DB_PASSWORD`.
+//!
+//! Imported values are registered with the redactor. They are cached in the
+//! cache directory, readable only by the user, for the import's `ttl` (five
+//! minutes unless set), so entering a directory again does not call the
+//! store each time. Imports holding decrypted values are never cached, and
+//! expired cache files are deleted.
+
+mod aws_ssm;
+mod azure_app_config;
+
+use cuenv_config::EnvImport;
+use cuenv_core::events::{global_timeline, SpanKind};
+use cuenv_core::redaction::global_redactor;
+use cuenv_core::{Error, Result};
+use cuenv_utils::network::rate_limit::{RateLimitConfig, RateLimiter};
+use cuenv_utils::resilience::policy::{self, Subsystem};
+use cuenv_utils::xdg::XdgPaths;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+/// Pages read per second, across all imports
+const PAGES_PER_SECOND: usize = 5;
+
+/// A page of entries of a store and the token of the next page, if any
+#[derive(Debug, Default, PartialEq)]
+struct Page {
+    entries: Vec<(String, String)>,
+    /// Whether an entry was stored encrypted
+    encrypted: bool,
+    next_token: Option<String>,
+}
+
+/// A store variables are imported from in bulk
+trait BulkProvider: Send + Sync {
+    /// What is imported, for messages
+    fn describe(&self) -> String;
+
+    /// Program and arguments reading the page after `token`
+    fn page_command(&self, token: Option<&str>) -> (&'static str, Vec<String>);
+
+    fn parse_page(&self, output: &[u8]) -> Result<Page>;
+
+    /// Key of an entry relative to what is imported
+    fn relative_key<'a>(&self, key: &'a str) -> &'a str;
+}
+
+fn provider(import: &EnvImport) -> Result<Box<dyn BulkProvider>> {
+    match (&import.aws_ssm_path, &import.azure_app_config) {
+        (Some(path), None) => Ok(Box::new(aws_ssm::AwsSsm {
+            path: path.clone(),
+            region: import.region.clone(),
+        })),
+        (None, Some(azure)) => Ok(Box::new(azure_app_config::AzureAppConfig {
+            import: azure.clone(),
+        })),
+        _ => Err(Error::configuration(
+            "valueFrom needs exactly one of awsSsmPath or azureAppConfig",
+        )),
+    }
+}
+
+/// Variables of `imports`, later imports replacing earlier ones
+pub async fn import_variables(imports: &[EnvImport]) -> Result<HashMap<String, String>> {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        max_operations: PAGES_PER_SECOND,
+        window_duration: Duration::from_secs(1),
+        sliding_window: true,
+        burst_size: None,
+    });
+    let mut variables = HashMap::new();
+    for import in imports {
+        variables.extend(import_one(import, &limiter).await?);
+    }
+    for value in variables.values() {
+        global_redactor().register_secret(value);
+    }
+    Ok(variables)
+}
+
+async fn import_one(import: &EnvImport, limiter: &RateLimiter) -> Result<HashMap<String, String>> {
+    let ttl = import.ttl.map_or(DEFAULT_TTL, Duration::from_secs);
+    let cache = ImportCache::new(&XdgPaths::cache_dir().join("imports"), import)?;
+    if let Some(variables) = cache.load(ttl) {
+        return Ok(variables);
+    }
+
+    let provider = provider(import)?;
+    let span =
+        global_timeline().start(SpanKind::Secrets, format!("import {}", provider.describe()));
+    let fetched = fetch_all(provider.as_ref(), limiter).await;
+    span.finish(&fetched);
+    let fetched = fetched?;
+    let variables = to_variables(provider.as_ref(), fetched.entries);
+
+    // Decrypted values are not written to disk
+    if !ttl.is_zero() && !fetched.encrypted {
+        if let Err(e) = cache.store(&variables, ttl) {
+            tracing::warn!("Failed to cache {}: {e}", provider.describe());
+        }
+    }
+    Ok(variables)
+}
+
+/// Entries of every page of the store, as one page
+async fn fetch_all(provider: &dyn BulkProvider, limiter: &RateLimiter) -> Result<Page> {
+    let mut all = Page::default();
+    let mut token: Option<String> = None;
+    loop {
+        let _permit = limiter.acquire().await?;
+        let current = token.as_deref();
+        let page = policy::call(Subsystem::Secrets, || fetch_page(provider, current)).await?;
+        all.entries.extend(page.entries);
+        all.encrypted |= page.encrypted;
+        match page.next_token {
+            Some(next) if token.as_deref() == Some(next.as_str()) => {
+                return Err(Error::configuration(format!(
+                    "{} returned the same page token twice",
+                    provider.describe()
+                )));
+            }
+            Some(next) => token = Some(next),
+            None => return Ok(all),
+        }
+    }
+}
+
+async fn fetch_page(provider: &dyn BulkProvider, token: Option<&str>) -> Result<Page> {
+    let (program, args) = provider.page_command(token);
+    let output = tokio::process::Command::new(program)
+        .args(&args)
+        .output()
+        .await
+        .map_err(|e| {
+            Error::configuration(format!(
+                "Failed to run '{program}' to import {}: {e}",
+                provider.describe()
+            ))
+        })?;
+    if output.status.success() {
+        return provider.parse_page(&output.stdout);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    // Throttling is a network error so the page is retried
+    if ["Throttling", "TooManyRequests", "Rate exceeded", "429"]
+        .iter()
+        .any(|marker| stderr.contains(marker))
+    {
+        return Err(Error::network(provider.describe(), stderr));
+    }
+    Err(Error::command_execution(
+        program,
+        args,
+        stderr,
+        output.status.code(),
+    ))
+}
+
+/// Entries as variables, skipping keys that make no valid name
+fn to_variables(
+    provider: &dyn BulkProvider,
+    entries: Vec<(String, String)>,
+) -> HashMap<String, String> {
+    entries
+        .into_iter()
+        .filter_map(
+            |(key, value)| match variable_name(provider.relative_key(&key)) {
+                Some(name) => Some((name, value)),
+                None => {
+                    tracing::warn!(
+                        "Skipping '{key}' of {}: not a valid variable name",
+                        provider.describe()
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Variable name of a key: letters in upper case and anything else that is
+/// not a digit as `_`, e.g. `db/password` is `DB_PASSWORD`
+fn variable_name(key: &str) -> Option<String> {
+    let name: String = key
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        .then_some(name)
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedImport {
+    /// Seconds since the epoch
+    fetched_at: u64,
+    /// Seconds since the epoch after which the file is deleted
+    #[serde(default)]
+    expires_at: u64,
+    variables: HashMap<String, String>,
+}
+
+/// Cached variables of an import, keyed by everything the import declares
+struct ImportCache {
+    path: PathBuf,
+}
+
+impl ImportCache {
+    fn new(dir: &Path, import: &EnvImport) -> Result<Self> {
+        let key = Sha256::digest(serde_json::to_vec(import)?);
+        Ok(Self {
+            path: dir.join(format!("{key:x}.json")),
+        })
+    }
+
+    /// Variables cached less than `ttl` ago, deleting the file once it is
+    /// older
+    fn load(&self, ttl: Duration) -> Option<HashMap<String, String>> {
+        let content = std::fs::read(&self.path).ok()?;
+        let cached: CachedImport = serde_json::from_slice(&content).ok()?;
+        if now().saturating_sub(cached.fetched_at) < ttl.as_secs() {
+            return Some(cached.variables);
+        }
+        let _ = std::fs::remove_file(&self.path);
+        None
+    }
+
+    /// Cache `variables` for `ttl`, deleting the expired files of other
+    /// imports
+    fn store(&self, variables: &HashMap<String, String>, ttl: Duration) -> Result<()> {
+        let dir = self.path.parent().unwrap_or(&self.path);
+        std::fs::create_dir_all(dir)
+            .map_err(|e| Error::file_system(dir, "create import cache directory", e))?;
+        remove_expired(dir);
+        let fetched_at = now();
+        let cached = CachedImport {
+            fetched_at,
+            expires_at: fetched_at.saturating_add(ttl.as_secs()),
+            variables: variables.clone(),
+        };
+        // Temporary files are only readable by the user
+        let mut temp = tempfile::NamedTempFile::new_in(dir)
+            .map_err(|e| Error::file_system(dir, "create import cache file", e))?;
+        temp.write_all(&serde_json::to_vec(&cached)?)
+            .map_err(|e| Error::file_system(temp.path(), "write import cache", e))?;
+        temp.persist(&self.path)
+            .map_err(|e| Error::file_system(&self.path, "write import cache", e.error))?;
+        Ok(())
+    }
+}
+
+/// Delete cache files that expired or cannot be read
+fn remove_expired(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"));
+    for path in paths {
+        let expired = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<CachedImport>(&content).ok())
+            .is_none_or(|cached| cached.expires_at <= now());
+        if expired {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_become_variable_names() {
+        let ssm = aws_ssm::AwsSsm {
+            path: "/myapp/dev/".to_string(),
+            region: None,
+        };
+        let variables = to_variables(
+            &ssm,
+            vec![
+                ("/myapp/dev/db/password".to_string(), "s3cret".to_string()),
+                ("/myapp/dev/api-url".to_string(), "https://api".to_string()),
+                ("/myapp/dev/2fa".to_string(), "skipped".to_string()),
+            ],
+        );
+        assert_eq!(
+            variables,
+            HashMap::from([
+                ("DB_PASSWORD".to_string(), "s3cret".to_string()),
+                ("API_URL".to_string(), "https://api".to_string()),
+            ])
+        );
+        assert_eq!(
+            variable_name("feature.flags:beta"),
+            Some("FEATURE_FLAGS_BETA".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cached_imports_expire() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let import = EnvImport {
+            aws_ssm_path: Some("/myapp/dev/".to_string()),
+            ..Default::default()
+        };
+        let cache = ImportCache::new(dir.path(), &import).unwrap();
+        assert!(cache.load(DEFAULT_TTL).is_none());
+
+        let variables = HashMap::from([("DB_HOST".to_string(), "db".to_string())]);
+        cache.store(&variables, DEFAULT_TTL).unwrap();
+        assert_eq!(cache.load(DEFAULT_TTL), Some(variables.clone()));
+        assert!(cache.load(Duration::ZERO).is_none());
+        // The expired file is gone
+        assert!(!cache.path.exists());
+
+        // Another path is cached separately
+        let other = EnvImport {
+            aws_ssm_path: Some("/myapp/prod/".to_string()),
+            ..Default::default()
+        };
+        let other = ImportCache::new(dir.path(), &other).unwrap();
+        assert!(other.load(DEFAULT_TTL).is_none());
+
+        // Storing one import deletes what expired of the others
+        other.store(&variables, Duration::ZERO).unwrap();
+        cache.store(&variables, DEFAULT_TTL).unwrap();
+        assert!(!other.path.exists());
+        assert!(cache.path.exists());
+    }
+}
@@ -0,0 +1,100 @@
+use cuenv_core::events::{global_timeline, SpanKind};
+use cuenv_core::redaction::global_redactor;
+use cuenv_core::{constants::CUENV_RESOLVER_PREFIX, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+mod imports;
+mod provider;
+
+pub use imports::import_variables;
+pub use provider::{discover_providers, ProviderReference, ProviderResolution, PROTOCOL_VERSION};
+pub(crate) use provider::{provider_timeout, resolve_with_provider};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ResolverConfig {
+    cmd: String,
+    args: Vec<String>,
+}
+
+/// Whether `value` refers to a secret that must be resolved
+pub fn is_secret_reference(value: &str) -> bool {
+    value.starts_with(CUENV_RESOLVER_PREFIX) || ProviderReference::parse(value).is_some()
+}
+
+/// Resolve secret values that may contain special resolver references
+///
+/// Resolved secrets are registered with the global redactor, so nothing
+/// cuenv writes shows them.
+pub fn resolve_secret(value: &str) -> Result<String> {
+    if !is_secret_reference(value) {
+        return Ok(value.to_string());
+    }
+    let resolved = resolve_reference(value)?;
+    if resolved != value {
+        global_redactor().register_secret(&resolved);
+    }
+    Ok(resolved)
+}
+
+fn resolve_reference(value: &str) -> Result<String> {
+    if let Some(reference) = ProviderReference::parse(value) {
+        let resolution = resolve_with_provider(&reference, provider_timeout())?;
+        crate::lock::check_resolution(value, &resolution)?;
+        return Ok(resolution.value);
+    }
+
+    if let Some(json_str) = value.strip_prefix(CUENV_RESOLVER_PREFIX) {
+        if let Ok(config) = serde_json::from_str::<ResolverConfig>(json_str) {
+            // Execute the resolver command
+            let output = std::process::Command::new(&config.cmd)
+                .args(&config.args)
+                .output()
+                .map_err(|e| {
+                    Error::configuration(format!(
+                        "Failed to execute resolver command '{}': {}",
+                        config.cmd, e
+                    ))
+                })?;
+
+            if output.status.success() {
+                let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                Ok(result)
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(Error::configuration(format!(
+                    "Resolver command '{}' failed: {}",
+                    config.cmd, stderr
+                )))
+            }
+        } else {
+            // If it's not valid JSON, just return the original value
+            Ok(value.to_string())
+        }
+    } else {
+        // Not a resolver reference, return as-is
+        Ok(value.to_string())
+    }
+}
+
+/// Resolve the lazy secrets among `names`, skipping names that are not one
+///
+/// Unlike eager resolution for commands, a failure is an error: running a
+/// task with the unresolved reference would only fail later, less clearly.
+pub fn resolve_deferred<'a>(
+    deferred: &HashMap<String, String>,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<HashMap<String, String>> {
+    names
+        .into_iter()
+        .filter_map(|name| deferred.get_key_value(name))
+        .map(|(name, reference)| {
+            let span = global_timeline().start(SpanKind::Secrets, format!("resolve {name}"));
+            let resolved = resolve_secret(reference);
+            span.finish(&resolved);
+            resolved
+                .map(|value| (name.clone(), value))
+                .map_err(|e| Error::secret_resolution(name, e.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,443 @@
+//! External-process secret providers
+//!
+//! Any executable named `cuenv-secret-<name>` on `PATH` can serve secrets for
+//! references of the form `cuenv-provider://<name>/<reference>`. Providers speak
+//! newline-delimited JSON over stdio:
+//!
+//! 1. cuenv sends `{"type":"handshake","protocolVersion":1}`
+//! 2. the provider answers `{"type":"handshake","protocolVersion":1}`, and
+//!    may add its own `"providerVersion"`
+//! 3. cuenv sends `{"type":"resolve","id":1,"reference":"<reference>"}`
+//! 4. the provider answers `{"type":"resolved","id":1,"value":"..."}`, with a
+//!    `"revision"` when it versions secrets, or
+//!    `{"type":"error","id":1,"message":"..."}`
+//!
+//! Provider versions and revisions are what `cuenv.lock` pins.
+//!
+//! cuenv closes stdin once it has its answer; providers should exit on EOF.
+//!
+//! A token stored for the provider's name with `cuenv login <name>` is
+//! passed in `CUENV_PROVIDER_TOKEN`.
+
+use cuenv_core::constants::{
+    CUENV_PROVIDER_PREFIX, SECRET_PROVIDER_EXECUTABLE_PREFIX, SECRET_PROVIDER_TOKEN_VAR,
+};
+use cuenv_core::{Error, Result};
+use cuenv_utils::credentials::CredentialLookup;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Version of the provider protocol spoken by this build of cuenv
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Default time a provider has to complete the handshake and resolve a reference
+pub const DEFAULT_PROVIDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Environment variable overriding the provider timeout (in seconds)
+const PROVIDER_TIMEOUT_VAR: &str = "CUENV_SECRET_PROVIDER_TIMEOUT";
+
+/// A parsed `cuenv-provider://<name>/<reference>` value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderReference {
+    pub provider: String,
+    pub reference: String,
+}
+
+impl ProviderReference {
+    /// Parse a provider reference, returning `None` if the value is not one
+    pub fn parse(value: &str) -> Option<Self> {
+        let rest = value.strip_prefix(CUENV_PROVIDER_PREFIX)?;
+        let (provider, reference) = rest.split_once('/')?;
+        if provider.is_empty()
+            || reference.is_empty()
+            || !provider
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return None;
+        }
+        Some(Self {
+            provider: provider.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+
+    /// Name of the executable serving this reference
+    pub fn executable_name(&self) -> String {
+        format!("{SECRET_PROVIDER_EXECUTABLE_PREFIX}{}", self.provider)
+    }
+}
+
+/// Messages sent from cuenv to a provider
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Request<'a> {
+    #[serde(rename_all = "camelCase")]
+    Handshake {
+        protocol_version: u32,
+    },
+    Resolve {
+        id: u64,
+        reference: &'a str,
+    },
+}
+
+/// Messages sent from a provider back to cuenv
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Response {
+    #[serde(rename_all = "camelCase")]
+    Handshake {
+        protocol_version: u32,
+        #[serde(default)]
+        provider_version: Option<String>,
+    },
+    Resolved {
+        id: u64,
+        value: String,
+        #[serde(default)]
+        revision: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// A secret served by a provider, with what the provider reported about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderResolution {
+    pub value: String,
+    /// Version the provider reported in its handshake
+    pub provider_version: Option<String>,
+    /// Revision of the secret, from providers that version secrets
+    pub revision: Option<String>,
+}
+
+/// Find every `cuenv-secret-*` executable on `PATH`, returning provider names
+pub fn discover_providers() -> Vec<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+
+    let names: BTreeSet<String> = std::env::split_paths(&path)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_executable(&entry.path()))
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix(SECRET_PROVIDER_EXECUTABLE_PREFIX))
+                .map(|name| name.trim_end_matches(".exe").to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    names.into_iter().collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// Locate the executable for a provider on `PATH`
+fn locate_provider(reference: &ProviderReference) -> Result<PathBuf> {
+    let executable = reference.executable_name();
+    which::which(&executable).map_err(|_| {
+        let available = discover_providers();
+        let hint = if available.is_empty() {
+            "no secret providers were found on PATH".to_string()
+        } else {
+            format!("available providers: {}", available.join(", "))
+        };
+        Error::secret_resolution(
+            format!("{CUENV_PROVIDER_PREFIX}{}", reference.provider),
+            format!("provider executable '{executable}' not found on PATH ({hint})"),
+        )
+    })
+}
+
+/// Timeout applied to provider calls, honouring `CUENV_SECRET_PROVIDER_TIMEOUT`
+pub fn provider_timeout() -> Duration {
+    std::env::var(PROVIDER_TIMEOUT_VAR)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_PROVIDER_TIMEOUT)
+}
+
+/// Resolve a provider reference by running its executable
+pub fn resolve_with_provider(
+    reference: &ProviderReference,
+    timeout: Duration,
+) -> Result<ProviderResolution> {
+    let executable = locate_provider(reference)?;
+    let display = format!("{CUENV_PROVIDER_PREFIX}{}", reference.provider);
+
+    let mut command = Command::new(&executable);
+    if let Some(credential) = CredentialLookup::new(&reference.provider).resolve() {
+        command.env(SECRET_PROVIDER_TOKEN_VAR, credential.token);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| {
+            Error::secret_resolution(
+                &display,
+                format!("failed to start provider '{}': {e}", executable.display()),
+            )
+        })?;
+
+    let result = run_session(&mut child, reference, timeout)
+        .map_err(|message| Error::secret_resolution(&display, message));
+
+    // Providers are expected to exit once stdin closes; make sure they do
+    if !matches!(child.try_wait(), Ok(Some(_))) {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+
+    result
+}
+
+/// Drive the handshake and a single resolve request over the child's stdio
+fn run_session(
+    child: &mut Child,
+    reference: &ProviderReference,
+    timeout: Duration,
+) -> std::result::Result<ProviderResolution, String> {
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "provider stdin unavailable".to_string())?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "provider stdout unavailable".to_string())?;
+
+    let responses = spawn_reader(stdout);
+    let deadline = Instant::now() + timeout;
+
+    send(
+        &mut stdin,
+        &Request::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+        },
+    )?;
+    let provider_version = match receive(&responses, deadline, timeout)? {
+        Response::Handshake {
+            protocol_version,
+            provider_version,
+        } if protocol_version == PROTOCOL_VERSION => provider_version,
+        Response::Handshake {
+            protocol_version, ..
+        } => {
+            return Err(format!(
+                "provider speaks protocol version {protocol_version}, cuenv requires {PROTOCOL_VERSION}"
+            ))
+        }
+        Response::Error { message, .. } => return Err(format!("handshake failed: {message}")),
+        other => return Err(format!("unexpected handshake response: {other:?}")),
+    };
+
+    let id = 1;
+    send(
+        &mut stdin,
+        &Request::Resolve {
+            id,
+            reference: &reference.reference,
+        },
+    )?;
+    let resolution = match receive(&responses, deadline, timeout)? {
+        Response::Resolved {
+            id: got,
+            value,
+            revision,
+        } if got == id => ProviderResolution {
+            value,
+            provider_version,
+            revision,
+        },
+        Response::Resolved { id: got, .. } => {
+            return Err(format!("provider answered request {got}, expected {id}"))
+        }
+        Response::Error { message, .. } => return Err(message),
+        other => return Err(format!("unexpected resolve response: {other:?}")),
+    };
+
+    // Closing stdin signals the provider to exit
+    drop(stdin);
+    Ok(resolution)
+}
+
+fn send(stdin: &mut ChildStdin, request: &Request<'_>) -> std::result::Result<(), String> {
+    let line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    writeln!(stdin, "{line}")
+        .and_then(|()| stdin.flush())
+        .map_err(|e| format!("failed to write to provider: {e}"))
+}
+
+/// Read provider stdout on a separate thread so calls can time out
+fn spawn_reader(stdout: ChildStdout) -> mpsc::Receiver<std::io::Result<String>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+fn receive(
+    responses: &mpsc::Receiver<std::io::Result<String>>,
+    deadline: Instant,
+    timeout: Duration,
+) -> std::result::Result<Response, String> {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let line = match responses.recv_timeout(remaining) {
+            Ok(Ok(line)) => line,
+            Ok(Err(e)) => return Err(format!("failed to read from provider: {e}")),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(format!("provider timed out after {}s", timeout.as_secs()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err("provider exited without responding".to_string())
+            }
+        };
+
+        // Ignore blank lines so providers may pad their output
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        return serde_json::from_str(&line)
+            .map_err(|e| format!("provider sent invalid JSON ({e}): {line}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_provider_reference() {
+        let parsed = ProviderReference::parse("cuenv-provider://vault/secret/data/db#password")
+            .expect("valid reference");
+        assert_eq!(parsed.provider, "vault");
+        assert_eq!(parsed.reference, "secret/data/db#password");
+        assert_eq!(parsed.executable_name(), "cuenv-secret-vault");
+
+        assert!(ProviderReference::parse("plain-value").is_none());
+        assert!(ProviderReference::parse("cuenv-provider://vault").is_none());
+        assert!(ProviderReference::parse("cuenv-provider:///ref").is_none());
+        assert!(ProviderReference::parse("cuenv-provider://../bin/sh/x").is_none());
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let handshake = serde_json::to_string(&Request::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+        })
+        .unwrap();
+        assert_eq!(handshake, r#"{"type":"handshake","protocolVersion":1}"#);
+
+        let resolve = serde_json::to_string(&Request::Resolve {
+            id: 7,
+            reference: "a/b",
+        })
+        .unwrap();
+        assert_eq!(resolve, r#"{"type":"resolve","id":7,"reference":"a/b"}"#);
+    }
+
+    #[test]
+    fn test_response_deserialization() {
+        let resolved: Response =
+            serde_json::from_str(r#"{"type":"resolved","id":1,"value":"s3cr3t"}"#).unwrap();
+        assert!(
+            matches!(resolved, Response::Resolved { id: 1, ref value, revision: None } if value == "s3cr3t")
+        );
+
+        let versioned: Response =
+            serde_json::from_str(r#"{"type":"resolved","id":1,"value":"s3cr3t","revision":"7"}"#)
+                .unwrap();
+        assert!(
+            matches!(versioned, Response::Resolved { revision: Some(ref revision), .. } if revision == "7")
+        );
+
+        let error: Response =
+            serde_json::from_str(r#"{"type":"error","message":"denied"}"#).unwrap();
+        assert!(matches!(error, Response::Error { ref message } if message == "denied"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_session_against_script_provider() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("cuenv-secret-test");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             read handshake\n\
+             echo '{\"type\":\"handshake\",\"protocolVersion\":1,\"providerVersion\":\"2.1.0\"}'\n\
+             read request\n\
+             echo '{\"type\":\"resolved\",\"id\":1,\"value\":\"from-provider\",\"revision\":\"3\"}'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut child = Command::new(&script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let reference = ProviderReference::parse("cuenv-provider://test/anything").unwrap();
+        let resolution = run_session(&mut child, &reference, Duration::from_secs(5)).unwrap();
+        let _ = child.wait();
+        assert_eq!(
+            resolution,
+            ProviderResolution {
+                value: "from-provider".to_string(),
+                provider_version: Some("2.1.0".to_string()),
+                revision: Some("3".to_string()),
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_session_times_out() {
+        let mut child = Command::new("sleep")
+            .arg("5")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let reference = ProviderReference::parse("cuenv-provider://slow/ref").unwrap();
+        let err = run_session(&mut child, &reference, Duration::from_millis(100)).unwrap_err();
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(err.contains("timed out"), "unexpected error: {err}");
+    }
+}
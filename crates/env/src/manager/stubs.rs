@@ -1,32 +1,6 @@
 // Temporary stubs until dependencies are properly resolved
 use cuenv_core::Result;
 use std::collections::HashMap;
-use std::process::Command;
-
-// Temporary stub for AccessRestrictions until security crate is fixed
-#[derive(Default)]
-pub struct AccessRestrictions {
-    pub file_restrictions: bool,
-    pub network_restrictions: bool,
-}
-
-impl AccessRestrictions {
-    pub fn new(file_restrictions: bool, network_restrictions: bool) -> Self {
-        Self {
-            file_restrictions,
-            network_restrictions,
-        }
-    }
-
-    pub fn has_any_restrictions(&self) -> bool {
-        self.file_restrictions || self.network_restrictions
-    }
-
-    pub fn apply_to_command(&self, _cmd: &mut Command) -> Result<()> {
-        // Stub - would normally apply Landlock restrictions
-        Ok(())
-    }
-}
 
 // Stubs for missing types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -99,26 +73,3 @@ impl Platform {
         "HOME"
     }
 }
-
-pub struct OutputFilter<W> {
-    writer: W,
-}
-
-impl<W: std::io::Write> OutputFilter<W> {
-    pub fn new(
-        writer: W,
-        _secrets: std::sync::Arc<std::sync::RwLock<std::collections::HashSet<String>>>,
-    ) -> Self {
-        Self { writer }
-    }
-}
-
-impl<W: std::io::Write> std::io::Write for OutputFilter<W> {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.writer.write(buf)
-    }
-
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.writer.flush()
-    }
-}
@@ -55,3 +55,28 @@ export 123INVALID=value
     assert!(!vars.contains_key(""));
     assert!(!vars.contains_key("123INVALID"));
 }
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_deferred_only_resolves_lazy_secrets() {
+    use crate::manager::secrets::resolve_deferred;
+    use std::collections::HashMap;
+
+    let deferred = HashMap::from([(
+        "DEPLOY_TOKEN".to_string(),
+        r#"cuenv-resolver://{"cmd":"echo","args":["s3cr3t"]}"#.to_string(),
+    )]);
+
+    let resolved = resolve_deferred(&deferred, ["DEPLOY_TOKEN", "HOME"]).unwrap();
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(
+        resolved.get("DEPLOY_TOKEN").map(String::as_str),
+        Some("s3cr3t")
+    );
+
+    let failing = HashMap::from([(
+        "BROKEN".to_string(),
+        r#"cuenv-resolver://{"cmd":"false","args":[]}"#.to_string(),
+    )]);
+    assert!(resolve_deferred(&failing, ["BROKEN"]).is_err());
+}
@@ -0,0 +1,285 @@
+//! Bounded history of the environments applied in a directory
+//!
+//! Each time cuenv applies an environment, the variables it set are appended
+//! to the directory's state, keeping the last [`MAX_HISTORY_ENTRIES`]. Every
+//! entry carries the hash of the one before it, so an edited history shows up
+//! as a broken chain. Secret references are kept only as a SHA-256 digest:
+//! a rollback can tell whether the current value is the recorded one, but
+//! cannot bring back another.
+
+use anyhow::{Context, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::paths::get_env_history_path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::manager::secrets::is_secret_reference;
+
+/// Entries kept per directory; older ones are dropped
+pub const MAX_HISTORY_ENTRIES: usize = 20;
+
+/// A recorded variable value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum SnapshotValue {
+    Plain(String),
+    /// SHA-256 digest of a secret reference
+    Secret(String),
+}
+
+impl SnapshotValue {
+    fn capture(value: &str) -> Self {
+        if is_secret_reference(value) {
+            Self::Secret(digest(value))
+        } else {
+            Self::Plain(value.to_string())
+        }
+    }
+
+    /// Whether `value` is the recorded value
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::Plain(plain) => plain == value,
+            Self::Secret(hash) => *hash == digest(value),
+        }
+    }
+}
+
+/// One applied environment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvHistoryEntry {
+    /// Seconds since the Unix epoch
+    pub recorded_at: u64,
+    pub environment: Option<String>,
+    pub variables: BTreeMap<String, SnapshotValue>,
+    /// Hash of the entry before this one
+    pub previous_hash: Option<String>,
+    pub hash: String,
+}
+
+impl EnvHistoryEntry {
+    fn compute_hash(&self) -> String {
+        let content = serde_json::json!([
+            self.recorded_at,
+            self.environment,
+            self.variables,
+            self.previous_hash,
+        ]);
+        digest(&content.to_string())
+    }
+}
+
+/// Changes that bring the shell back to a recorded environment
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Rollback {
+    pub set: BTreeMap<String, String>,
+    pub unset: Vec<String>,
+    /// Secrets whose current value is not the recorded one
+    pub unrestorable: Vec<String>,
+}
+
+/// Environment history of one directory, oldest entry first
+#[derive(Debug)]
+pub struct EnvHistory {
+    path: PathBuf,
+    entries: Vec<EnvHistoryEntry>,
+}
+
+impl EnvHistory {
+    /// History of `dir`; a missing or unreadable file is an empty history
+    pub fn load(dir: &Path) -> Self {
+        Self::from_path(get_env_history_path(dir))
+    }
+
+    fn from_path(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[EnvHistoryEntry] {
+        &self.entries
+    }
+
+    /// The entry `steps` back from the latest, which is 0
+    pub fn back(&self, steps: usize) -> Option<&EnvHistoryEntry> {
+        self.entries.iter().rev().nth(steps)
+    }
+
+    /// Index of the first entry that does not match its hash or does not
+    /// follow the entry before it
+    pub fn first_broken(&self) -> Option<usize> {
+        self.entries.iter().enumerate().position(|(index, entry)| {
+            let follows =
+                index == 0 || entry.previous_hash.as_ref() == Some(&self.entries[index - 1].hash);
+            !follows || entry.hash != entry.compute_hash()
+        })
+    }
+
+    /// Append an applied environment, unless it equals the latest entry
+    pub fn record(
+        &mut self,
+        environment: Option<&str>,
+        variables: &HashMap<String, String>,
+    ) -> Result<()> {
+        let variables: BTreeMap<_, _> = variables
+            .iter()
+            .map(|(key, value)| (key.clone(), SnapshotValue::capture(value)))
+            .collect();
+        let environment = environment.map(str::to_string);
+        if self.entries.last().is_some_and(|latest| {
+            latest.variables == variables && latest.environment == environment
+        }) {
+            return Ok(());
+        }
+
+        let mut entry = EnvHistoryEntry {
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            environment,
+            variables,
+            previous_hash: self.entries.last().map(|latest| latest.hash.clone()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        self.entries.push(entry);
+
+        let excess = self.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        self.entries.drain(..excess);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.entries)
+            .context("Failed to encode environment history")?;
+        write_atomic_string(&self.path, &content)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Changes from `current` back to `target`, given the `latest` applied
+/// environment; variables only the latest one set are unset
+pub fn plan_rollback(
+    target: &EnvHistoryEntry,
+    latest: &EnvHistoryEntry,
+    current: &HashMap<String, String>,
+) -> Rollback {
+    let mut rollback = Rollback::default();
+    for (key, recorded) in &target.variables {
+        let current_value = current.get(key);
+        match recorded {
+            _ if current_value.is_some_and(|value| recorded.matches(value)) => {}
+            SnapshotValue::Plain(value) => {
+                rollback.set.insert(key.clone(), value.clone());
+            }
+            SnapshotValue::Secret(_) => rollback.unrestorable.push(key.clone()),
+        }
+    }
+    rollback.unset = latest
+        .variables
+        .keys()
+        .filter(|key| !target.variables.contains_key(*key) && current.contains_key(*key))
+        .cloned()
+        .collect();
+    rollback
+}
+
+fn digest(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vars(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_chained() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("env_history.json");
+        let mut history = EnvHistory::from_path(path.clone());
+
+        for n in 0..MAX_HISTORY_ENTRIES + 2 {
+            history
+                .record(Some("dev"), &vars(&[("N", &n.to_string())]))
+                .unwrap();
+        }
+        history.record(Some("dev"), &vars(&[("N", "21")])).unwrap();
+
+        let history = EnvHistory::from_path(path.clone());
+        assert_eq!(history.entries().len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(
+            history.back(0).unwrap().variables["N"],
+            SnapshotValue::Plain("21".to_string())
+        );
+        assert_eq!(history.first_broken(), None);
+
+        let mut tampered = history.entries().to_vec();
+        tampered[5].variables.insert(
+            "N".to_string(),
+            SnapshotValue::Plain("tampered".to_string()),
+        );
+        fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+        assert_eq!(EnvHistory::from_path(path).first_broken(), Some(5));
+    }
+
+    #[test]
+    fn test_rollback_plan() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut history = EnvHistory::from_path(temp_dir.path().join("env_history.json"));
+        history
+            .record(
+                None,
+                &vars(&[
+                    ("API_URL", "https://v1"),
+                    ("TOKEN", "cuenv-provider://vault/api/token"),
+                ]),
+            )
+            .unwrap();
+        history
+            .record(
+                None,
+                &vars(&[
+                    ("API_URL", "https://v2"),
+                    ("TOKEN", "cuenv-provider://vault/api/token2"),
+                    ("DEBUG", "1"),
+                ]),
+            )
+            .unwrap();
+
+        assert!(matches!(
+            history.back(1).unwrap().variables["TOKEN"],
+            SnapshotValue::Secret(_)
+        ));
+
+        let current = vars(&[
+            ("API_URL", "https://v2"),
+            ("TOKEN", "cuenv-provider://vault/api/token2"),
+            ("DEBUG", "1"),
+        ]);
+        let rollback = plan_rollback(history.back(1).unwrap(), history.back(0).unwrap(), &current);
+        assert_eq!(
+            rollback,
+            Rollback {
+                set: BTreeMap::from([("API_URL".to_string(), "https://v1".to_string())]),
+                unset: vec!["DEBUG".to_string()],
+                unrestorable: vec!["TOKEN".to_string()],
+            }
+        );
+    }
+}
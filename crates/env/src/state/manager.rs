@@ -1,9 +1,11 @@
-use crate::diff::EnvDiff;
+use crate::diff::{Divergence, EnvDiff};
+use crate::watcher::{ChangeKind, WatchSet};
 use anyhow::{Context, Result};
 use cuenv_security::audit_logger;
 use cuenv_utils::compression;
+use cuenv_utils::paths::get_state_dir;
+use cuenv_utils::state_prune::{StaleState, StatePruner};
 use cuenv_utils::sync::SyncEnv;
-use cuenv_utils::FileTimes;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -246,7 +248,7 @@ impl StateManager {
     fn store_metadata(
         transaction: &mut StateTransaction,
         diff: &EnvDiff,
-        watches: &FileTimes,
+        watches: &WatchSet,
     ) -> Result<()> {
         // Store the diff
         Self::encode_and_store(
@@ -274,7 +276,7 @@ impl StateManager {
         environment: Option<&str>,
         capabilities: &[String],
         diff: &EnvDiff,
-        watches: &FileTimes,
+        watches: &WatchSet,
     ) -> Result<()> {
         // Create a transaction with snapshot of current state
         let mut transaction = StateTransaction::new(&Self::state_var_names())?;
@@ -364,8 +366,18 @@ impl StateManager {
         Self::decode_from_var(&Self::env_var_name("CUENV_DIFF"), "Failed to decode diff")
     }
 
+    /// Variables the loaded environment set that were changed since, such
+    /// as by hand in the shell
+    pub fn modified_since_load() -> Result<Vec<Divergence>> {
+        let Some(diff) = Self::get_diff()? else {
+            return Ok(Vec::new());
+        };
+        let current = SyncEnv::vars()?.into_iter().collect();
+        Ok(diff.divergences(&current))
+    }
+
     /// Get the file watches
-    pub fn get_watches() -> Result<Option<FileTimes>> {
+    pub fn get_watches() -> Result<Option<WatchSet>> {
         // Don't acquire lock here to avoid deadlock when called from within locked methods
         Self::decode_from_var(
             &Self::env_var_name("CUENV_WATCHES"),
@@ -373,14 +385,30 @@ impl StateManager {
         )
     }
 
-    /// Check if watched files have changed
-    pub fn files_changed() -> bool {
-        let _guard = STATE_LOCK.read().ok();
-        if let Ok(Some(watches)) = Self::get_watches() {
-            watches.has_changed()
-        } else {
-            false
+    /// Classify changes to the watched files of the loaded environment
+    ///
+    /// Changes that leave the environment as it is become the baseline of
+    /// the next check, so they are only evaluated once, and touched files
+    /// are only hashed once.
+    pub fn watch_change() -> ChangeKind {
+        let _guard = STATE_LOCK.write().ok();
+        let Ok(Some(mut watches)) = Self::get_watches() else {
+            return ChangeKind::Unchanged;
+        };
+        let loaded = watches.clone();
+        let kind = watches.check();
+        if !kind.needs_env_reload() && watches != loaded {
+            let stored = compression::encode(&watches)
+                .context("Failed to encode file watches")
+                .and_then(|encoded| {
+                    SyncEnv::set_var(Self::env_var_name("CUENV_WATCHES"), encoded)
+                        .context("Failed to store file watches")
+                });
+            if let Err(e) = stored {
+                tracing::debug!("{e:#}");
+            }
         }
+        kind
     }
 
     /// Check if we should load environment for a directory
@@ -406,6 +434,21 @@ impl StateManager {
         }
     }
 
+    /// Stale on-disk state found by `pruner`, leaving the state of the
+    /// loaded directory alone
+    pub fn stale_state(pruner: &StatePruner) -> Vec<StaleState> {
+        let loaded = Self::current_dir().map(|dir| get_state_dir(&dir));
+        pruner
+            .find()
+            .into_iter()
+            .filter(|stale| {
+                loaded
+                    .as_ref()
+                    .is_none_or(|state_dir| !stale.path.starts_with(state_dir))
+            })
+            .collect()
+    }
+
     /// Get a consistent snapshot of the state
     /// Returns (is_loaded, current_dir, state) atomically
     pub fn get_state_snapshot() -> (bool, Option<PathBuf>, Option<CuenvState>) {
@@ -465,7 +508,7 @@ mod tests {
         let diff = EnvDiff::new(prev, next);
 
         // Create watches
-        let watches = FileTimes::new();
+        let watches = WatchSet::default();
 
         // Load state
         StateManager::load(
@@ -615,7 +658,7 @@ mod tests {
 
         // Create a diff that will fail to encode (simulate error)
         let diff = EnvDiff::new(HashMap::new(), HashMap::new());
-        let watches = FileTimes::new();
+        let watches = WatchSet::default();
 
         // Set some initial state
         SyncEnv::set_var("CUENV_DIR", "should-be-preserved").unwrap();
@@ -1,3 +1,5 @@
+pub mod history;
 pub mod manager;
+pub mod session;
 
 pub use manager::*;
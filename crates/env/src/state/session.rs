@@ -0,0 +1,96 @@
+//! The named environment chosen for a shell with `cuenv env use`
+//!
+//! A command can't change the shell it runs in, so the choice is written to
+//! a file named after the shell's session, which the shell hook reads on the
+//! next prompt. The hook gives each shell its session, exporting
+//! `CUENV_SESSION` on the first prompt; subshells share their parent's.
+
+use cuenv_core::{Error, Result, CUENV_SESSION_VAR};
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::paths::get_session_environment_path;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The shell session of a hook or command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellSession {
+    id: String,
+}
+
+impl ShellSession {
+    /// The session of the shell this process runs in, if its hook set one
+    pub fn current() -> Option<Self> {
+        std::env::var(CUENV_SESSION_VAR)
+            .ok()
+            .and_then(|id| Self::from_id(&id))
+    }
+
+    /// A session for a shell that has none yet
+    pub fn start() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        Self {
+            id: format!("{}-{nanos:x}", std::process::id()),
+        }
+    }
+
+    /// The session `id` names, unless it can't be a file name
+    pub fn from_id(id: &str) -> Option<Self> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| Self { id: id.to_string() })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn path(&self) -> PathBuf {
+        get_session_environment_path(&self.id)
+    }
+
+    /// The environment chosen for the session, if any
+    pub fn environment(&self) -> Option<String> {
+        let name = fs::read_to_string(self.path()).ok()?;
+        Some(name.trim().to_string()).filter(|name| !name.is_empty())
+    }
+
+    /// Choose `environment` for the session, or go back to the default one
+    pub fn use_environment(&self, environment: Option<&str>) -> Result<()> {
+        let path = self.path();
+        match environment {
+            Some(name) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)
+                        .map_err(|e| Error::file_system(dir, "create session directory", e))?;
+                }
+                write_atomic_string(&path, &format!("{name}\n"))
+            }
+            None => match fs::remove_file(&path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    Err(Error::file_system(&path, "remove session environment", e))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_ids() {
+        let session = ShellSession::start();
+        assert_eq!(ShellSession::from_id(session.id()), Some(session));
+        assert!(ShellSession::from_id("../state").is_none());
+        assert!(ShellSession::from_id("").is_none());
+    }
+}
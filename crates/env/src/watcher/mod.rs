@@ -1,3 +1,12 @@
+//! Watching the files a loaded environment came from
+//!
+//! [`WatchSet`] decides what the shell hook has to reload after a change;
+//! [`FileWatcher`] checks hook caches against their inputs.
+
+mod watch_set;
+
+pub use watch_set::{ChangeKind, WatchSet};
+
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
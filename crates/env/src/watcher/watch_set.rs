@@ -0,0 +1,439 @@
+//! The files an environment was loaded from, and what a change to them needs
+//!
+//! A [`WatchSet`] covers the CUE files of a package and its `cue.mod`, plus
+//! the declared inputs of its hooks, as glob patterns expanded on every
+//! check so added files are noticed as well as edited and removed ones.
+//! Files are compared by content, so touching one is not a change; a file
+//! is only hashed again when its size or modification time moved.
+//!
+//! When only CUE files changed, the package is evaluated again and the
+//! result compared with the one that was loaded, split into what shapes the
+//! environment and what only describes tasks. Editing a task description or
+//! a comment then leaves the loaded environment alone; changed hook inputs
+//! always need a reload, because the hooks have to run again.
+
+use cuenv_config::{CueParser, Hook, ParseOptions, ParseResult};
+use cuenv_core::{Error, Result};
+use cuenv_utils::IgnoreRules;
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::debug;
+use walkdir::WalkDir;
+
+/// Patterns of the package's own files, relative to its directory
+const PACKAGE_PATTERNS: &[&str] = &["*.cue", "cue.mod/**/*.cue"];
+
+/// Fields of the evaluated package that only describe tasks
+const TASK_FIELDS: &[&str] = &["tasks", "task_nodes", "run_configs"];
+
+/// What a change to the watched files needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// No watched file changed
+    Unchanged,
+    /// Files changed but evaluate to the same package
+    CommentOnly,
+    /// Only tasks changed; the loaded environment is still current
+    TaskOnly,
+    /// Variables, hooks or hook inputs changed; the environment is reloaded
+    Env,
+}
+
+impl ChangeKind {
+    pub fn needs_env_reload(self) -> bool {
+        self == Self::Env
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct WatchPattern {
+    base: PathBuf,
+    pattern: String,
+    hook_input: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct WatchedFile {
+    hash: String,
+    hook_input: bool,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified: Option<SystemTime>,
+}
+
+/// Watched files of a loaded package, with the evaluation they produced
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchSet {
+    dir: PathBuf,
+    package: String,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    patterns: Vec<WatchPattern>,
+    files: BTreeMap<PathBuf, WatchedFile>,
+    env_hash: String,
+    task_hash: String,
+}
+
+impl WatchSet {
+    /// Watch the package in `dir` that evaluated to `result` under `options`
+    pub fn new(
+        dir: &Path,
+        package: &str,
+        options: &ParseOptions,
+        result: &ParseResult,
+    ) -> Result<Self> {
+        let package_patterns = PACKAGE_PATTERNS.iter().map(|pattern| WatchPattern {
+            base: dir.to_path_buf(),
+            pattern: (*pattern).to_string(),
+            hook_input: false,
+        });
+        let patterns: Vec<WatchPattern> = package_patterns
+            .chain(hook_input_patterns(dir, &result.hooks))
+            .collect();
+        if let Some(invalid) = patterns
+            .iter()
+            .find(|pattern| is_glob(&pattern.pattern) && matcher(&pattern.pattern).is_none())
+        {
+            return Err(Error::configuration(format!(
+                "Invalid watch pattern '{}'",
+                invalid.pattern
+            )));
+        }
+
+        let (env_hash, task_hash) = evaluation_hashes(result)?;
+        let mut watches = Self {
+            dir: dir.to_path_buf(),
+            package: package.to_string(),
+            environment: options.environment.clone(),
+            capabilities: options.capabilities.clone(),
+            patterns,
+            files: BTreeMap::new(),
+            env_hash,
+            task_hash,
+        };
+        watches.files = watches.scan();
+        debug!(
+            "Watching {} files for {}",
+            watches.files.len(),
+            dir.display()
+        );
+        Ok(watches)
+    }
+
+    /// Paths of the watched files, as of the last check
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Files added, edited or removed since the last check
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        changed(&self.files, &self.scan())
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Classify the changes since the last check, evaluating the package
+    /// again if CUE files changed
+    pub fn check(&mut self) -> ChangeKind {
+        let options = ParseOptions {
+            environment: self.environment.clone(),
+            capabilities: self.capabilities.clone(),
+        };
+        let (dir, package) = (self.dir.clone(), self.package.clone());
+        self.check_with(|| CueParser::eval_package_with_options(&dir, &package, &options))
+    }
+
+    /// [`Self::check`] with the evaluation supplied by the caller
+    ///
+    /// Unless the environment needs a reload, the new state becomes the
+    /// baseline of the next check.
+    pub fn check_with(&mut self, evaluate: impl FnOnce() -> Result<ParseResult>) -> ChangeKind {
+        let files = self.scan();
+        let changed: Vec<(PathBuf, bool)> = changed(&self.files, &files)
+            .map(|(path, hook_input)| (path.clone(), hook_input))
+            .collect();
+        if changed.is_empty() {
+            // Keep the times of touched files so they are not hashed again
+            self.files = files;
+            return ChangeKind::Unchanged;
+        }
+        if let Some((path, _)) = changed.iter().find(|(_, hook_input)| *hook_input) {
+            debug!("Hook input {} changed", path.display());
+            return ChangeKind::Env;
+        }
+
+        let hashes = evaluate().and_then(|result| evaluation_hashes(&result));
+        let kind = match &hashes {
+            Ok((env_hash, _)) if *env_hash != self.env_hash => ChangeKind::Env,
+            Ok((_, task_hash)) if *task_hash != self.task_hash => ChangeKind::TaskOnly,
+            Ok(_) => ChangeKind::CommentOnly,
+            // Let the reload report the error
+            Err(_) => ChangeKind::Env,
+        };
+        debug!("{} watched files changed: {kind:?}", changed.len());
+        if let (false, Ok((_, task_hash))) = (kind.needs_env_reload(), hashes) {
+            self.files = files;
+            self.task_hash = task_hash;
+        }
+        kind
+    }
+
+    /// Expand the patterns and hash the files they match, reusing the hash
+    /// of the last check for files whose size and modification time held
+    fn scan(&self) -> BTreeMap<PathBuf, WatchedFile> {
+        let ignore = IgnoreRules::load(&self.dir).unwrap_or_default();
+        self.patterns
+            .iter()
+            .flat_map(|pattern| {
+                expand(&pattern.base, &pattern.pattern, &self.dir, &ignore)
+                    .into_iter()
+                    .map(move |path| (path, pattern.hook_input))
+            })
+            .filter_map(|(path, hook_input)| {
+                let metadata = fs::metadata(&path).ok()?;
+                let (size, modified) = (metadata.len(), metadata.modified().ok());
+                let hash = match self.files.get(&path) {
+                    Some(known)
+                        if modified.is_some()
+                            && known.modified == modified
+                            && known.size == size =>
+                    {
+                        known.hash.clone()
+                    }
+                    _ => format!("{:x}", Sha256::digest(fs::read(&path).ok()?)),
+                };
+                Some((
+                    path,
+                    WatchedFile {
+                        hash,
+                        hook_input,
+                        size,
+                        modified,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Paths that differ between two scans, with whether they are hook inputs
+fn changed<'a>(
+    before: &'a BTreeMap<PathBuf, WatchedFile>,
+    after: &'a BTreeMap<PathBuf, WatchedFile>,
+) -> impl Iterator<Item = (&'a PathBuf, bool)> {
+    let edited_or_removed = before
+        .iter()
+        .filter(|(path, file)| after.get(*path).map(|after| &after.hash) != Some(&file.hash))
+        .map(|(path, file)| (path, file.hook_input));
+    let added = after
+        .iter()
+        .filter(|(path, _)| !before.contains_key(*path))
+        .map(|(path, file)| (path, file.hook_input));
+    edited_or_removed.chain(added)
+}
+
+/// Declared hook inputs, relative to the hook's directory or the package
+fn hook_input_patterns<'a>(
+    dir: &'a Path,
+    hooks: &'a HashMap<String, Vec<Hook>>,
+) -> impl Iterator<Item = WatchPattern> + 'a {
+    hooks.values().flatten().flat_map(move |hook| {
+        let base = hook
+            .dir
+            .as_ref()
+            .map_or_else(|| dir.to_path_buf(), |hook_dir| dir.join(hook_dir));
+        hook.inputs.iter().flatten().map(move |input| WatchPattern {
+            base: base.clone(),
+            pattern: input.clone(),
+            hook_input: true,
+        })
+    })
+}
+
+/// Hashes of the environment-shaping and task-only parts of an evaluation
+fn evaluation_hashes(result: &ParseResult) -> Result<(String, String)> {
+    let value = serde_json::to_value(result)?;
+    let (tasks, env): (Vec<_>, Vec<_>) = value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .partition(|(field, _)| TASK_FIELDS.contains(&field.as_str()));
+    let hash = |fields: Vec<(&String, &serde_json::Value)>| -> Result<String> {
+        let json = serde_json::to_vec(&fields)?;
+        Ok(format!("{:x}", Sha256::digest(json)))
+    };
+    Ok((hash(env)?, hash(tasks)?))
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+fn matcher(pattern: &str) -> Option<GlobMatcher> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+/// Files under `base` matching `pattern`, skipping what `root`'s ignore
+/// rules skip. Only the directories the pattern can reach are walked.
+fn expand(base: &Path, pattern: &str, root: &Path, ignore: &IgnoreRules) -> Vec<PathBuf> {
+    if !is_glob(pattern) {
+        let path = base.join(pattern);
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+    let Some(matcher) = matcher(pattern) else {
+        return Vec::new();
+    };
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components
+        .iter()
+        .take_while(|component| !is_glob(component))
+        .count();
+    let start = base.join(components[..literal].join("/"));
+    let rest = &components[literal..];
+    let max_depth = if rest.contains(&"**") {
+        usize::MAX
+    } else {
+        rest.len()
+    };
+
+    WalkDir::new(&start)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            entry.depth() == 0 || !ignore.is_ignored(relative, entry.file_type().is_dir())
+        })
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| matcher.is_match(path.strip_prefix(base).unwrap_or(path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_config::TaskConfig;
+    use tempfile::TempDir;
+
+    fn evaluation(value: &str, task_description: &str) -> ParseResult {
+        let mut result = ParseResult::default();
+        result
+            .variables
+            .insert("VALUE".to_string(), value.to_string());
+        result.tasks.insert(
+            "build".to_string(),
+            TaskConfig {
+                description: Some(task_description.to_string()),
+                ..TaskConfig::default()
+            },
+        );
+        result.hooks.insert(
+            "onEnter".to_string(),
+            vec![Hook {
+                name: None,
+                depends_on: None,
+                command: "nix".to_string(),
+                args: None,
+                dir: None,
+                inputs: Some(vec!["flake.lock".to_string(), "nix/*.nix".to_string()]),
+                source: Some(true),
+                preload: None,
+                fetch: None,
+                compose: None,
+            }],
+        );
+        result
+    }
+
+    #[test]
+    fn test_changes_are_classified() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("cue.mod")).unwrap();
+        fs::create_dir_all(dir.join("nix")).unwrap();
+        fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+        fs::write(dir.join("env.cue"), "package cuenv\n").unwrap();
+        fs::write(dir.join("cue.mod/module.cue"), "module: \"x\"\n").unwrap();
+        fs::write(dir.join("flake.lock"), "{}").unwrap();
+        fs::write(dir.join("nix/shell.nix"), "{}").unwrap();
+        fs::write(dir.join("node_modules/pkg/ignored.cue"), "").unwrap();
+
+        let loaded = evaluation("1", "Build");
+        let mut watches = WatchSet::new(dir, "cuenv", &ParseOptions::default(), &loaded).unwrap();
+        let mut files: Vec<&Path> = watches.files().collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                dir.join("cue.mod/module.cue"),
+                dir.join("env.cue"),
+                dir.join("flake.lock"),
+                dir.join("nix/shell.nix"),
+            ]
+        );
+        assert_eq!(watches.check_with(|| unreachable!()), ChangeKind::Unchanged);
+
+        // Same contents written again
+        fs::write(dir.join("env.cue"), "package cuenv\n").unwrap();
+        assert_eq!(watches.check_with(|| unreachable!()), ChangeKind::Unchanged);
+
+        fs::write(dir.join("env.cue"), "package cuenv\n// note\n").unwrap();
+        let same = evaluation("1", "Build");
+        assert_eq!(watches.check_with(|| Ok(same)), ChangeKind::CommentOnly);
+
+        fs::write(dir.join("tasks.cue"), "package cuenv\n").unwrap();
+        let described = evaluation("1", "Build the project");
+        assert_eq!(watches.check_with(|| Ok(described)), ChangeKind::TaskOnly);
+        // The task change became the baseline
+        assert!(watches.changed_files().is_empty());
+
+        fs::write(dir.join("env.cue"), "package cuenv\nenv: VALUE: \"2\"\n").unwrap();
+        let changed = evaluation("2", "Build the project");
+        assert_eq!(watches.check_with(|| Ok(changed)), ChangeKind::Env);
+
+        let mut watches = WatchSet::new(dir, "cuenv", &ParseOptions::default(), &loaded).unwrap();
+        fs::write(dir.join("nix/extra.nix"), "{}").unwrap();
+        assert_eq!(watches.check_with(|| unreachable!()), ChangeKind::Env);
+        assert_eq!(watches.changed_files(), [dir.join("nix/extra.nix")]);
+    }
+
+    #[test]
+    fn test_files_are_only_hashed_again_when_their_times_move() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("env.cue"), "package cuenv\n").unwrap();
+        let loaded = ParseResult::default();
+        let mut watches = WatchSet::new(dir, "cuenv", &ParseOptions::default(), &loaded).unwrap();
+
+        // A hash that no longer matches the contents is trusted while the
+        // size and modification time hold
+        let path = dir.join("env.cue");
+        watches.files.get_mut(&path).unwrap().hash = "stale".to_string();
+        assert_eq!(watches.scan()[&path].hash, "stale");
+
+        watches.files.get_mut(&path).unwrap().size += 1;
+        assert_ne!(watches.scan()[&path].hash, "stale");
+        assert_eq!(watches.check_with(|| Ok(loaded)), ChangeKind::CommentOnly);
+
+        // Touched files keep their new times without counting as changed
+        watches.files.get_mut(&path).unwrap().modified = None;
+        assert_eq!(watches.check_with(|| unreachable!()), ChangeKind::Unchanged);
+        assert!(watches.files[&path].modified.is_some());
+    }
+}
@@ -5,9 +5,9 @@ use cuenv_core::types::{CommandArguments, EnvironmentVariables};
 // use cuenv_security::SecurityValidator;
 use cuenv_security::{audit_logger, AuditLogger};
 use cuenv_task::CommandExecutor;
+use cuenv_utils::credentials::CredentialLookup;
 use cuenv_utils::network::rate_limit::RateLimitManager;
-use cuenv_utils::network::retry::RetryConfig;
-use cuenv_utils::resilience::{CircuitBreaker, CircuitBreakerConfig};
+use cuenv_utils::resilience::policy::{self, Subsystem};
 use lru::LruCache;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
@@ -43,8 +43,6 @@ pub struct HookManager<E: CommandExecutor + Send + Sync> {
     cache_ttl: Duration,
     rate_limiter: Option<Arc<RateLimitManager>>,
     audit_logger: Option<Arc<AuditLogger>>,
-    _circuit_breaker: Arc<CircuitBreaker>,
-    _retry_config: RetryConfig,
     status_manager: Option<Arc<HooksStatusManager>>,
 }
 
@@ -62,16 +60,6 @@ impl<E: CommandExecutor + Send + Sync> HookManager<E> {
 
         let semaphore = Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_HOOKS));
 
-        let circuit_breaker_config = CircuitBreakerConfig {
-            failure_threshold: 5,
-            success_threshold: 2,
-            timeout: Duration::from_secs(300),       // 5 minutes
-            break_duration: Duration::from_secs(60), // 1 minute
-            half_open_max_calls: 3,
-        };
-
-        let retry_config = RetryConfig::network();
-
         Ok(Self {
             executor,
             http_client,
@@ -80,8 +68,6 @@ impl<E: CommandExecutor + Send + Sync> HookManager<E> {
             cache_ttl: DEFAULT_CACHE_TTL,
             rate_limiter: None,
             audit_logger: audit_logger(),
-            _circuit_breaker: Arc::new(CircuitBreaker::new(circuit_breaker_config)),
-            _retry_config: retry_config,
             status_manager: None,
         })
     }
@@ -318,26 +304,13 @@ impl<E: CommandExecutor + Send + Sync> HookManager<E> {
             }
         }
 
-        // Fetch from URL
+        // Fetch from URL, retrying under the hooks retry policy
         log::debug!("Fetching content from URL: {url}");
 
-        let response = self
-            .http_client
-            .get(parsed_url.as_str())
-            .send()
+        let content = policy::call(Subsystem::Hooks, || self.download(parsed_url.as_str()))
             .await
             .map_err(|e| anyhow!("Failed to fetch URL: {e}"))?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(anyhow!("HTTP request failed with status: {status}"));
-        }
-
-        let content = response
-            .text()
-            .await
-            .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
-
         // Update cache
         {
             let mut cache = self.cache.write().await;
@@ -353,6 +326,39 @@ impl<E: CommandExecutor + Send + Sync> HookManager<E> {
         Ok(content)
     }
 
+    /// Fetch `url`, with the credential stored for its host if there is one
+    async fn download(&self, url: &str) -> cuenv_core::Result<String> {
+        let mut request = self.http_client.get(url);
+        if let Some(credential) = CredentialLookup::for_url(url).resolve() {
+            request = request.bearer_auth(credential.token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| cuenv_core::Error::network(url, e.to_string()))?;
+
+        let status = response.status();
+        if status.is_client_error()
+            && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+            && status != reqwest::StatusCode::REQUEST_TIMEOUT
+        {
+            // Not worth retrying
+            return Err(cuenv_core::Error::configuration(format!(
+                "HTTP request failed with status: {status}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(cuenv_core::Error::network(
+                url,
+                format!("HTTP request failed with status: {status}"),
+            ));
+        }
+
+        response.text().await.map_err(|e| {
+            cuenv_core::Error::network(url, format!("Failed to read response body: {e}"))
+        })
+    }
+
     fn create_isolated_environment(
         &self,
         env_vars: &HashMap<String, String>,
@@ -7,16 +7,45 @@ import "C"
 import (
 	"encoding/json"
 	"fmt"
+	"io/fs"
 	"os"
+	"path/filepath"
+	"runtime"
+	"runtime/debug"
 	"strings"
+	"sync"
 	"unsafe"
 
 	"cuelang.org/go/cue"
-	"cuelang.org/go/cue/build"
 	"cuelang.org/go/cue/cuecontext"
+	cueerrors "cuelang.org/go/cue/errors"
 	"cuelang.org/go/cue/load"
 )
 
+// bridgeABIVersion is bumped whenever an exported function changes its
+// signature or the shape of the JSON it returns; the Rust side checks it
+const bridgeABIVersion = 3
+
+//export cue_bridge_info
+func cue_bridge_info() *C.char {
+	cueVersion := "unknown"
+	if info, ok := debug.ReadBuildInfo(); ok {
+		for _, dep := range info.Deps {
+			if dep.Path == "cuelang.org/go" {
+				cueVersion = dep.Version
+			}
+		}
+	}
+	infoBytes, _ := json.Marshal(map[string]interface{}{
+		"abiVersion": bridgeABIVersion,
+		"goVersion":  runtime.Version(),
+		"cueVersion": cueVersion,
+		"os":         runtime.GOOS,
+		"arch":       runtime.GOARCH,
+	})
+	return C.CString(string(infoBytes))
+}
+
 //export cue_free_string
 func cue_free_string(s *C.char) {
 	C.free(unsafe.Pointer(s))
@@ -24,6 +53,15 @@ func cue_free_string(s *C.char) {
 
 //export cue_eval_package
 func cue_eval_package(dirPath *C.char, packageName *C.char) *C.char {
+	return cue_eval_package_with_modules(dirPath, packageName, nil)
+}
+
+// cue_eval_package_with_modules evaluates a package like cue_eval_package,
+// additionally making the CUE modules in modulePaths (a JSON array of
+// directories) importable by their module path
+//
+//export cue_eval_package_with_modules
+func cue_eval_package_with_modules(dirPath *C.char, packageName *C.char, modulePaths *C.char) *C.char {
 	// Add recover to catch any panics
 	var result *C.char
 	defer func() {
@@ -37,6 +75,16 @@ func cue_eval_package(dirPath *C.char, packageName *C.char) *C.char {
 	goDir := C.GoString(dirPath)
 	goPackageName := C.GoString(packageName)
 
+	var moduleDirs []string
+	if modulePaths != nil {
+		if err := json.Unmarshal([]byte(C.GoString(modulePaths)), &moduleDirs); err != nil {
+			errMsg := map[string]string{"error": fmt.Sprintf("Invalid module paths: %v", err)}
+			errBytes, _ := json.Marshal(errMsg)
+			result = C.CString(string(errBytes))
+			return result
+		}
+	}
+
 	// Validate inputs
 	if goDir == "" {
 		errMsg := map[string]string{"error": "Directory path cannot be empty"}
@@ -69,55 +117,331 @@ func cue_eval_package(dirPath *C.char, packageName *C.char) *C.char {
 		return result
 	}
 
+	jsonStr, err := evaluate(".", goPackageName, moduleDirs)
+	if err != nil {
+		result = C.CString(errorJSON(err.Error()))
+		return result
+	}
+
+	result = C.CString(jsonStr)
+	return result
+}
+
+// cue_eval_packages evaluates the package packageName in each directory of
+// dirPaths (a JSON array) in parallel, with the modules of modulePaths like
+// cue_eval_package_with_modules. It returns a JSON array holding, in the
+// order of dirPaths, each package's JSON result as a string.
+//
+//export cue_eval_packages
+func cue_eval_packages(dirPaths *C.char, packageName *C.char, modulePaths *C.char) *C.char {
+	var result *C.char
+	defer func() {
+		if r := recover(); r != nil {
+			result = C.CString(errorJSON(fmt.Sprintf("Internal error: %v", r)))
+		}
+	}()
+
+	goPackageName := C.GoString(packageName)
+	if goPackageName == "" {
+		result = C.CString(errorJSON("Package name cannot be empty"))
+		return result
+	}
+	var dirs, moduleDirs []string
+	if err := json.Unmarshal([]byte(C.GoString(dirPaths)), &dirs); err != nil {
+		result = C.CString(errorJSON(fmt.Sprintf("Invalid directory paths: %v", err)))
+		return result
+	}
+	if modulePaths != nil {
+		if err := json.Unmarshal([]byte(C.GoString(modulePaths)), &moduleDirs); err != nil {
+			result = C.CString(errorJSON(fmt.Sprintf("Invalid module paths: %v", err)))
+			return result
+		}
+	}
+
+	results := make([]string, len(dirs))
+	slots := make(chan struct{}, runtime.GOMAXPROCS(0))
+	var wg sync.WaitGroup
+	for i, dir := range dirs {
+		wg.Add(1)
+		go func(i int, dir string) {
+			defer wg.Done()
+			slots <- struct{}{}
+			defer func() { <-slots }()
+			results[i] = evaluateOne(dir, goPackageName, moduleDirs)
+		}(i, dir)
+	}
+	wg.Wait()
+
+	resultBytes, _ := json.Marshal(results)
+	result = C.CString(string(resultBytes))
+	return result
+}
+
+// evaluateOne is one result of cue_eval_packages: the package's JSON, or an
+// error object as cue_eval_package returns it
+func evaluateOne(dir string, packageName string, moduleDirs []string) (result string) {
+	defer func() {
+		if r := recover(); r != nil {
+			result = errorJSON(fmt.Sprintf("Internal error: %v", r))
+		}
+	}()
+	if dir == "" {
+		return errorJSON("Directory path cannot be empty")
+	}
+	if info, err := os.Stat(dir); err != nil || !info.IsDir() {
+		return errorJSON(fmt.Sprintf("Failed to change directory to %s: not a directory", dir))
+	}
+	jsonStr, err := evaluate(dir, packageName, moduleDirs)
+	if err != nil {
+		return errorJSON(err.Error())
+	}
+	return jsonStr
+}
+
+// evaluate loads the package packageName in dir, like
+// "cue export .:package-name", and builds its ordered JSON. It leaves the
+// working directory alone, so packages can be evaluated concurrently.
+func evaluate(dir string, packageName string, moduleDirs []string) (string, error) {
+	_, v, err := build(dir, packageName, moduleDirs)
+	if err != nil {
+		return "", err
+	}
+
+	// Build JSON manually by iterating through CUE fields in order
+	// This completely bypasses Go's map randomization
+	jsonStr, err := buildOrderedJSONString(v)
+	if err != nil {
+		return "", fmt.Errorf("Failed to build ordered JSON: %v", err)
+	}
+	return jsonStr, nil
+}
+
+// build loads the package packageName in dir into a new context and builds
+// its value
+func build(dir string, packageName string, moduleDirs []string) (*cue.Context, cue.Value, error) {
+	absDir, err := filepath.Abs(dir)
+	if err != nil {
+		return nil, cue.Value{}, fmt.Errorf("Failed to resolve directory %s: %v", dir, err)
+	}
+
 	// Create CUE context
 	ctx := cuecontext.New()
 
-	// Load the specific CUE package by name
-	// This matches the behavior of "cue export .:package-name"
-	var instances []*build.Instance
-	packagePath := ".:" + goPackageName
-	instances = load.Instances([]string{packagePath}, nil)
+	loadConfig, err := moduleLoadConfig(ctx, absDir, moduleDirs)
+	if err != nil {
+		return nil, cue.Value{}, fmt.Errorf("Failed to load CUE modules: %v", err)
+	}
+	loadConfig.Dir = absDir
+	instances := load.Instances([]string{".:" + packageName}, loadConfig)
 
 	if len(instances) == 0 {
-		errMsg := map[string]string{"error": "No CUE instances found"}
-		errBytes, _ := json.Marshal(errMsg)
-		result = C.CString(string(errBytes))
-		return result
+		return nil, cue.Value{}, fmt.Errorf("No CUE instances found")
 	}
 
 	inst := instances[0]
 	if inst.Err != nil {
-		errMsg := map[string]string{"error": fmt.Sprintf("Failed to load CUE instance: %v", inst.Err)}
-		errBytes, _ := json.Marshal(errMsg)
-		result = C.CString(string(errBytes))
-		return result
+		return nil, cue.Value{}, fmt.Errorf("Failed to load CUE instance: %v", inst.Err)
 	}
 
 	// Build the CUE value
 	v := ctx.BuildInstance(inst)
 	if v.Err() != nil {
-		errMsg := map[string]string{"error": fmt.Sprintf("Failed to build CUE value: %v", v.Err())}
-		errBytes, _ := json.Marshal(errMsg)
-		result = C.CString(string(errBytes))
+		return nil, cue.Value{}, fmt.Errorf("Failed to build CUE value: %v", v.Err())
+	}
+	return ctx, v, nil
+}
+
+// cue_lint_package checks the package packageName in dirPath, with the
+// modules of modulePaths like cue_eval_package_with_modules, against the
+// #Cuenv definition of schemaSource, the source of cuenv's schema. It
+// returns {"issues": [...]} listing each field the closed schema does not
+// allow, with its path and positions in the package, or an error object.
+//
+//export cue_lint_package
+func cue_lint_package(dirPath *C.char, packageName *C.char, modulePaths *C.char, schemaSource *C.char) *C.char {
+	var result *C.char
+	defer func() {
+		if r := recover(); r != nil {
+			result = C.CString(errorJSON(fmt.Sprintf("Internal error: %v", r)))
+		}
+	}()
+
+	goDir := C.GoString(dirPath)
+	goPackageName := C.GoString(packageName)
+	if goDir == "" || goPackageName == "" {
+		result = C.CString(errorJSON("Directory path and package name cannot be empty"))
 		return result
 	}
+	var moduleDirs []string
+	if modulePaths != nil {
+		if err := json.Unmarshal([]byte(C.GoString(modulePaths)), &moduleDirs); err != nil {
+			result = C.CString(errorJSON(fmt.Sprintf("Invalid module paths: %v", err)))
+			return result
+		}
+	}
 
-	// Build JSON manually by iterating through CUE fields in order
-	// This completely bypasses Go's map randomization
-	jsonStr, err := buildOrderedJSONString(v)
+	issues, err := lint(goDir, goPackageName, moduleDirs, C.GoString(schemaSource))
 	if err != nil {
-		errMsg := map[string]string{"error": fmt.Sprintf("Failed to build ordered JSON: %v", err)}
-		errBytes, _ := json.Marshal(errMsg)
-		result = C.CString(string(errBytes))
+		result = C.CString(errorJSON(err.Error()))
 		return result
 	}
-	
-	jsonBytes := []byte(jsonStr)
-
-	result = C.CString(string(jsonBytes))
+	resultBytes, _ := json.Marshal(map[string]interface{}{"issues": issues})
+	result = C.CString(string(resultBytes))
 	return result
 }
 
+// lintIssue is a field of a package that the schema does not allow
+type lintIssue struct {
+	Path      string         `json:"path"`
+	Message   string         `json:"message"`
+	Locations []lintLocation `json:"locations"`
+}
+
+type lintLocation struct {
+	File   string `json:"file"`
+	Line   int    `json:"line"`
+	Column int    `json:"column"`
+}
+
+// schemaFilename names the schema's source in positions, so they can be
+// told apart from the package's
+const schemaFilename = "cuenv-schema.cue"
+
+func lint(dir string, packageName string, moduleDirs []string, schemaSource string) ([]lintIssue, error) {
+	ctx, v, err := build(dir, packageName, moduleDirs)
+	if err != nil {
+		return nil, err
+	}
+	schema := ctx.CompileString(schemaSource, cue.Filename(schemaFilename))
+	if schema.Err() != nil {
+		return nil, fmt.Errorf("Failed to compile the cuenv schema: %v", schema.Err())
+	}
+	definition := schema.LookupPath(cue.ParsePath("#Cuenv"))
+	if !definition.Exists() {
+		return nil, fmt.Errorf("The cuenv schema has no #Cuenv definition")
+	}
+
+	// Definitions are closed, so unifying reports every field the schema
+	// does not declare; other conflicts are left to evaluation
+	issues := []lintIssue{}
+	for _, e := range cueerrors.Errors(definition.Unify(v).Validate()) {
+		format, args := e.Msg()
+		message := fmt.Sprintf(format, args...)
+		if !strings.Contains(message, "field not allowed") {
+			continue
+		}
+		issue := lintIssue{
+			Path:      strings.Join(e.Path(), "."),
+			Message:   message,
+			Locations: []lintLocation{},
+		}
+		for _, pos := range cueerrors.Positions(e) {
+			if !pos.IsValid() || pos.Filename() == schemaFilename {
+				continue
+			}
+			issue.Locations = append(issue.Locations, lintLocation{
+				File:   pos.Filename(),
+				Line:   pos.Line(),
+				Column: pos.Column(),
+			})
+		}
+		issues = append(issues, issue)
+	}
+	return issues, nil
+}
+
+// errorJSON is the {"error": message} object returned for failures
+func errorJSON(message string) string {
+	errBytes, _ := json.Marshal(map[string]string{"error": message})
+	return string(errBytes)
+}
+
+// moduleLoadConfig overlays each module directory onto cue.mod/pkg of the
+// module of dir, as if it had been vendored there, so packages such as
+// shared profiles can be imported without copying them into every project.
+// Returns the default configuration when there are no modules.
+func moduleLoadConfig(ctx *cue.Context, dir string, moduleDirs []string) (*load.Config, error) {
+	if len(moduleDirs) == 0 {
+		return &load.Config{}, nil
+	}
+
+	moduleRoot := findModuleRoot(dir)
+	overlay := map[string]load.Source{}
+
+	// Imports need a module; give projects without one an anonymous module
+	if moduleRoot == "" {
+		moduleRoot = dir
+		overlay[filepath.Join(moduleRoot, "cue.mod", "module.cue")] = load.FromString("module: \"cuenv.local\"\n")
+	}
+
+	for _, dir := range moduleDirs {
+		modulePath, err := readModulePath(ctx, dir)
+		if err != nil {
+			return nil, err
+		}
+		target := filepath.Join(moduleRoot, "cue.mod", "pkg", filepath.FromSlash(modulePath))
+
+		err = filepath.WalkDir(dir, func(path string, entry fs.DirEntry, err error) error {
+			if err != nil {
+				return err
+			}
+			if entry.IsDir() {
+				if entry.Name() == "cue.mod" {
+					return filepath.SkipDir
+				}
+				return nil
+			}
+			if filepath.Ext(path) != ".cue" {
+				return nil
+			}
+			rel, err := filepath.Rel(dir, path)
+			if err != nil {
+				return err
+			}
+			content, err := os.ReadFile(path)
+			if err != nil {
+				return err
+			}
+			overlay[filepath.Join(target, rel)] = load.FromBytes(content)
+			return nil
+		})
+		if err != nil {
+			return nil, fmt.Errorf("failed to read module %s: %v", dir, err)
+		}
+	}
+
+	return &load.Config{ModuleRoot: moduleRoot, Overlay: overlay}, nil
+}
+
+// findModuleRoot returns the closest directory at or above dir containing
+// cue.mod, or "" if there is none
+func findModuleRoot(dir string) string {
+	for {
+		if info, err := os.Stat(filepath.Join(dir, "cue.mod")); err == nil && info.IsDir() {
+			return dir
+		}
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return ""
+		}
+		dir = parent
+	}
+}
+
+// readModulePath returns the module path declared in dir/cue.mod/module.cue
+func readModulePath(ctx *cue.Context, dir string) (string, error) {
+	moduleFile := filepath.Join(dir, "cue.mod", "module.cue")
+	content, err := os.ReadFile(moduleFile)
+	if err != nil {
+		return "", fmt.Errorf("%s is not a CUE module: %v", dir, err)
+	}
+	modulePath, err := ctx.CompileBytes(content).LookupPath(cue.ParsePath("module")).String()
+	if err != nil {
+		return "", fmt.Errorf("%s has no module path: %v", moduleFile, err)
+	}
+	return modulePath, nil
+}
+
 // buildOrderedJSONString manually builds a JSON string from CUE value preserving field order
 func buildOrderedJSONString(v cue.Value) (string, error) {
 	switch v.Kind() {
@@ -140,8 +464,17 @@ func buildOrderedJSONString(v cue.Value) (string, error) {
 				return "", fmt.Errorf("failed to marshal field name %s: %v", fieldName, err)
 			}
 			
-			// Recursively build value JSON
-			valueJSON, err := buildOrderedJSONString(fieldValue)
+			// Recursively build value JSON, except for task output values
+			// read from a file, which are not concrete
+			var valueJSON string
+			if attr := fieldValue.Attribute("fromFile"); attr.Err() == nil {
+				valueJSON, err = buildFromFileJSONString(attr, fieldValue)
+			} else {
+				valueJSON, err = buildOrderedJSONString(fieldValue)
+				if err == nil {
+					valueJSON, err = withMetadataAttributes(fieldValue, valueJSON)
+				}
+			}
 			if err != nil {
 				return "", fmt.Errorf("failed to build JSON for field %s: %v", fieldName, err)
 			}
@@ -187,4 +520,108 @@ func buildOrderedJSONString(v cue.Value) (string, error) {
 	}
 }
 
-func main() {}
\ No newline at end of file
+func main() {}
+// buildFromFileJSONString exports a field such as
+// `version: string @fromFile("VERSION")` as the file to read the value from
+// and the value's type: {"fromFile":"VERSION","type":"string"}
+func buildFromFileJSONString(attr cue.Attribute, v cue.Value) (string, error) {
+	path, err := attr.String(0)
+	if err != nil {
+		return "", fmt.Errorf("invalid @fromFile attribute: %v", err)
+	}
+	pathJSON, err := json.Marshal(path)
+	if err != nil {
+		return "", fmt.Errorf("failed to marshal @fromFile path %s: %v", path, err)
+	}
+
+	valueType := "string"
+	switch v.IncompleteKind() {
+	case cue.IntKind:
+		valueType = "int"
+	case cue.FloatKind, cue.NumberKind:
+		valueType = "number"
+	case cue.BoolKind:
+		valueType = "bool"
+	}
+
+	return `{"fromFile":` + string(pathJSON) + `,"type":"` + valueType + `"}`, nil
+}
+
+// withMetadataAttributes exports the @deprecated, @owner and @tag attributes
+// of a field as fields of its value, so `build: {...} @tag("slow")` reads
+// as `build: {tags: ["slow"], ...}`. Fields the value already declares win.
+// Values that are not structs become declarations: `{"value": ..., ...}`.
+func withMetadataAttributes(v cue.Value, valueJSON string) (string, error) {
+	var deprecated, owner *string
+	var tags []string
+	for _, attr := range v.Attributes(cue.FieldAttr) {
+		switch attr.Name() {
+		case "deprecated":
+			note := ""
+			if attr.NumArgs() > 0 {
+				note, _ = attr.String(0)
+			}
+			deprecated = &note
+		case "owner":
+			name, err := attr.String(0)
+			if err != nil {
+				return "", fmt.Errorf("invalid @owner attribute: %v", err)
+			}
+			owner = &name
+		case "tag":
+			for i := 0; i < attr.NumArgs(); i++ {
+				tag, err := attr.String(i)
+				if err != nil {
+					return "", fmt.Errorf("invalid @tag attribute: %v", err)
+				}
+				tags = append(tags, tag)
+			}
+		}
+	}
+	if deprecated == nil && owner == nil && len(tags) == 0 {
+		return valueJSON, nil
+	}
+
+	isStruct := v.Kind() == cue.StructKind
+	declares := func(field string) bool {
+		return isStruct && v.LookupPath(cue.MakePath(cue.Str(field))).Exists()
+	}
+	var parts []string
+	add := func(field string, value interface{}) error {
+		if declares(field) {
+			return nil
+		}
+		valueBytes, err := json.Marshal(value)
+		if err != nil {
+			return fmt.Errorf("failed to marshal @%s attribute: %v", field, err)
+		}
+		parts = append(parts, `"`+field+`":`+string(valueBytes))
+		return nil
+	}
+	if deprecated != nil {
+		if err := add("deprecated", *deprecated); err != nil {
+			return "", err
+		}
+	}
+	if owner != nil {
+		if err := add("owner", *owner); err != nil {
+			return "", err
+		}
+	}
+	if len(tags) > 0 {
+		if err := add("tags", tags); err != nil {
+			return "", err
+		}
+	}
+	if len(parts) == 0 {
+		return valueJSON, nil
+	}
+
+	if !isStruct {
+		return `{"value":` + valueJSON + "," + strings.Join(parts, ",") + "}", nil
+	}
+	if valueJSON == "{}" {
+		return "{" + strings.Join(parts, ",") + "}", nil
+	}
+	return "{" + strings.Join(parts, ",") + "," + valueJSON[1:], nil
+}
@@ -83,6 +83,53 @@ func TestCueFreeString(t *testing.T) {
 	cue_free_string(testStr)
 }
 
+func TestCueBridgeInfo(t *testing.T) {
+	result := cue_bridge_info()
+	defer cue_free_string(result)
+
+	var info map[string]interface{}
+	if err := json.Unmarshal([]byte(C.GoString(result)), &info); err != nil {
+		t.Fatalf("Failed to parse bridge info: %v", err)
+	}
+	if info["abiVersion"] != float64(bridgeABIVersion) {
+		t.Errorf("Expected abiVersion %d, got %v", bridgeABIVersion, info["abiVersion"])
+	}
+	if info["goVersion"] == "" {
+		t.Error("Expected goVersion to be set")
+	}
+}
+
+func TestLint_UnknownFields(t *testing.T) {
+	schema := `package schema
+
+#Cuenv: {
+	env?: [string]: string
+	tasks?: [string]: {
+		command?:      string
+		dependencies?: [...string]
+	}
+}`
+	tempDir, cleanup := createTestCueDir(t, "cuenv", `
+env: PORT: "3000"
+tasks: build: {
+	command:     "make"
+	dependecies: ["lint"]
+}`)
+	defer cleanup()
+
+	issues, err := lint(tempDir, "cuenv", nil, schema)
+	if err != nil {
+		t.Fatalf("Lint failed: %v", err)
+	}
+	if len(issues) != 1 || issues[0].Path != "tasks.build.dependecies" {
+		t.Fatalf("Expected an issue for tasks.build.dependecies, got %+v", issues)
+	}
+	locations := issues[0].Locations
+	if len(locations) == 0 || !strings.HasSuffix(locations[0].File, "env.cue") || locations[0].Line != 7 {
+		t.Errorf("Expected the issue at env.cue:7, got %+v", locations)
+	}
+}
+
 func TestCueEvalPackage_ValidInput(t *testing.T) {
 	cueContent := `
 env: {
@@ -482,4 +529,147 @@ tasks: {
 	if !t.Failed() {
 		t.Logf("✓ Consistency test passed across %d iterations", len(allResults))
 	}
-}
\ No newline at end of file
+}
+func TestFromFileOutputs(t *testing.T) {
+	cueContent := `
+tasks: {
+	build: {
+		command: "make"
+		outputs: {
+			version: string @fromFile("VERSION")
+			size:    int @fromFile("dist/size")
+		}
+	}
+}`
+
+	tempDir, cleanup := createTestCueDir(t, "cuenv", cueContent)
+	defer cleanup()
+
+	result := callCueEvalPackage(tempDir, "cuenv")
+	expected := `"outputs":{"version":{"fromFile":"VERSION","type":"string"},"size":{"fromFile":"dist/size","type":"int"}}`
+	if !strings.Contains(result, expected) {
+		t.Fatalf("Expected %s in result: %s", expected, result)
+	}
+}
+
+func TestMetadataAttributes(t *testing.T) {
+	cueContent := `
+env: {
+	DB_URL: "postgres://localhost" @deprecated("use DATABASE_URL") @owner("team-data")
+}
+tasks: {
+	build: {
+		command: "make"
+	} @deprecated("use build2") @tag("slow", "ci") @owner("team-x")
+	lint: {
+		command:    "lint"
+		deprecated: "declared"
+	} @deprecated("attribute")
+}`
+
+	tempDir, cleanup := createTestCueDir(t, "cuenv", cueContent)
+	defer cleanup()
+
+	result := callCueEvalPackage(tempDir, "cuenv")
+	for _, expected := range []string{
+		`"DB_URL":{"value":"postgres://localhost","deprecated":"use DATABASE_URL","owner":"team-data"}`,
+		`"build":{"deprecated":"use build2","owner":"team-x","tags":["slow","ci"],"command":"make"}`,
+		`"lint":{"command":"lint","deprecated":"declared"}`,
+	} {
+		if !strings.Contains(result, expected) {
+			t.Errorf("Expected %s in result: %s", expected, result)
+		}
+	}
+}
+
+func TestCueEvalPackageWithModules(t *testing.T) {
+	moduleDir, err := os.MkdirTemp("", "cuenv-module-*")
+	if err != nil {
+		t.Fatalf("Failed to create module dir: %v", err)
+	}
+	defer os.RemoveAll(moduleDir)
+
+	files := map[string]string{
+		"cue.mod/module.cue":     `module: "corp.example/cuenv"` + "\n",
+		"profiles/rust/rust.cue": "package rust\n\nenv: CARGO_TERM_COLOR: \"always\"\n",
+	}
+	for name, content := range files {
+		path := filepath.Join(moduleDir, name)
+		if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+			t.Fatalf("Failed to create %s: %v", name, err)
+		}
+		if err := os.WriteFile(path, []byte(content), 0644); err != nil {
+			t.Fatalf("Failed to write %s: %v", name, err)
+		}
+	}
+
+	tempDir, cleanup := createTestCueDir(t, "cuenv", `
+import rust "corp.example/cuenv/profiles/rust"
+
+profiles: rust: rust
+`)
+	defer cleanup()
+
+	modulePaths, _ := json.Marshal([]string{moduleDir})
+	cDirPath := C.CString(tempDir)
+	cPackageName := C.CString("cuenv")
+	cModulePaths := C.CString(string(modulePaths))
+	defer C.free(unsafe.Pointer(cDirPath))
+	defer C.free(unsafe.Pointer(cPackageName))
+	defer C.free(unsafe.Pointer(cModulePaths))
+
+	result := cue_eval_package_with_modules(cDirPath, cPackageName, cModulePaths)
+	defer cue_free_string(result)
+
+	var data struct {
+		Profiles map[string]TestCueData `json:"profiles"`
+	}
+	if err := json.Unmarshal([]byte(C.GoString(result)), &data); err != nil {
+		t.Fatalf("Failed to parse JSON result: %v\nResult: %s", err, C.GoString(result))
+	}
+	if got := data.Profiles["rust"].Env["CARGO_TERM_COLOR"]; got != "always" {
+		t.Errorf("Expected profile variable from module, got %v", got)
+	}
+}
+
+func TestCueEvalPackages(t *testing.T) {
+	first, cleanupFirst := createTestCueDir(t, "cuenv", `env: NAME: "first"`)
+	defer cleanupFirst()
+	second, cleanupSecond := createTestCueDir(t, "cuenv", `env: NAME: "second"`)
+	defer cleanupSecond()
+
+	dirPaths, _ := json.Marshal([]string{first, "/nonexistent/path", second})
+	cDirPaths := C.CString(string(dirPaths))
+	cPackageName := C.CString("cuenv")
+	defer C.free(unsafe.Pointer(cDirPaths))
+	defer C.free(unsafe.Pointer(cPackageName))
+
+	result := cue_eval_packages(cDirPaths, cPackageName, nil)
+	defer cue_free_string(result)
+
+	var results []string
+	if err := json.Unmarshal([]byte(C.GoString(result)), &results); err != nil {
+		t.Fatalf("Failed to parse JSON result: %v\nResult: %s", err, C.GoString(result))
+	}
+	if len(results) != 3 {
+		t.Fatalf("Expected 3 results, got %d", len(results))
+	}
+
+	for i, want := range map[int]string{0: "first", 2: "second"} {
+		var data TestCueData
+		if err := json.Unmarshal([]byte(results[i]), &data); err != nil {
+			t.Fatalf("Failed to parse result %d: %v", i, err)
+		}
+		if data.Env["NAME"] != want {
+			t.Errorf("Expected NAME %q in result %d, got %v", want, i, data.Env["NAME"])
+		}
+	}
+
+	var errorResponse map[string]string
+	if err := json.Unmarshal([]byte(results[1]), &errorResponse); err != nil {
+		t.Fatalf("Failed to parse error result: %v", err)
+	}
+	if !strings.Contains(errorResponse["error"], "/nonexistent/path") {
+		t.Errorf("Expected error for the missing directory, got: %s", results[1])
+	}
+}
@@ -79,9 +79,31 @@ typedef struct { void *data; GoInt len; GoInt cap; } GoSlice;
 extern "C" {
 #endif
 
+extern char* cue_bridge_info(void);
 extern void cue_free_string(char* s);
 extern char* cue_eval_package(char* dirPath, char* packageName);
 
+// cue_eval_package_with_modules evaluates a package like cue_eval_package,
+// additionally making the CUE modules in modulePaths (a JSON array of
+// directories) importable by their module path
+//
+extern char* cue_eval_package_with_modules(char* dirPath, char* packageName, char* modulePaths);
+
+// cue_eval_packages evaluates the package packageName in each directory of
+// dirPaths (a JSON array) in parallel, with the modules of modulePaths like
+// cue_eval_package_with_modules. It returns a JSON array holding, in the
+// order of dirPaths, each package's JSON result as a string.
+//
+extern char* cue_eval_packages(char* dirPaths, char* packageName, char* modulePaths);
+
+// cue_lint_package checks the package packageName in dirPath, with the
+// modules of modulePaths like cue_eval_package_with_modules, against the
+// #Cuenv definition of schemaSource, the source of cuenv's schema. It
+// returns {"issues": [...]} listing each field the closed schema does not
+// allow, with its path and positions in the package, or an error object.
+//
+extern char* cue_lint_package(char* dirPath, char* packageName, char* modulePaths, char* schemaSource);
+
 #ifdef __cplusplus
 }
 #endif
@@ -0,0 +1,130 @@
+//! Resolved environments and the tasks they run
+
+use cuenv_config::TaskConfig;
+use cuenv_core::Result;
+use cuenv_env::EnvManager;
+use cuenv_task::TaskExecutor;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A project's resolved environment
+#[derive(Clone)]
+pub struct Environment {
+    dir: PathBuf,
+    manager: EnvManager,
+    audit: bool,
+}
+
+impl Environment {
+    pub(crate) fn new(dir: PathBuf, manager: EnvManager, audit: bool) -> Self {
+        Self {
+            dir,
+            manager,
+            audit,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Variables of the environment: those of `env.cue`, shell-expanded, and
+    /// those sourced by hooks
+    ///
+    /// Lazy secrets are not resolved and left out.
+    pub fn variables(&self) -> &HashMap<String, String> {
+        self.manager.get_cue_vars()
+    }
+
+    /// The selected environment, e.g. `staging`
+    pub fn name(&self) -> Option<&str> {
+        self.manager.environment()
+    }
+
+    pub fn capabilities(&self) -> &[String] {
+        self.manager.capabilities()
+    }
+
+    /// Task definitions by name
+    pub fn task_configs(&self) -> &HashMap<String, TaskConfig> {
+        self.manager.get_tasks()
+    }
+
+    /// Prepare to run tasks in this environment
+    pub async fn tasks(&self) -> Result<TaskRunner> {
+        Ok(TaskRunner {
+            executor: TaskExecutor::new(self.manager.clone(), self.dir.clone()).await?,
+            audit: self.audit,
+        })
+    }
+}
+
+/// Runs tasks of an [`Environment`], with their dependencies
+pub struct TaskRunner {
+    executor: TaskExecutor,
+    audit: bool,
+}
+
+impl TaskRunner {
+    /// Tasks that would run for `tasks` and ask for confirmation first, with
+    /// their questions
+    ///
+    /// Running refuses these until they are [confirmed](Self::confirm).
+    pub fn pending_confirmations(&self, tasks: &[&str]) -> Result<Vec<(String, String)>> {
+        self.executor.pending_confirmations(&owned(tasks))
+    }
+
+    /// Let `tasks` run although they ask for confirmation
+    pub fn confirm<I, S>(&self, tasks: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.executor.confirm(tasks.into_iter().map(Into::into));
+    }
+
+    /// Run `tasks` and what they depend on
+    pub async fn run(&self, tasks: &[&str]) -> Result<TaskOutcome> {
+        self.run_with_args(tasks, &[]).await
+    }
+
+    /// Run `tasks` and what they depend on, passing `args` to the tasks
+    /// named
+    pub async fn run_with_args(&self, tasks: &[&str], args: &[String]) -> Result<TaskOutcome> {
+        let exit_code = self
+            .executor
+            .execute_tasks_unified(&owned(tasks), args, self.audit)
+            .await?;
+        Ok(TaskOutcome {
+            exit_code,
+            all_cached: self.executor.all_cached(),
+        })
+    }
+}
+
+/// How a task run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskOutcome {
+    exit_code: i32,
+    all_cached: bool,
+}
+
+impl TaskOutcome {
+    /// Exit code of the first task that failed, or 0
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// Whether every task was served from the cache
+    pub fn all_cached(&self) -> bool {
+        self.all_cached
+    }
+}
+
+fn owned(tasks: &[&str]) -> Vec<String> {
+    tasks.iter().map(ToString::to_string).collect()
+}
@@ -0,0 +1,38 @@
+//! Embed cuenv in other Rust programs
+//!
+//! A [`Project`] is a directory with an `env.cue`, opened with the
+//! environment and capabilities to use. From it, [`Project::load_config`]
+//! evaluates the configuration and [`Project::load_environment`] resolves
+//! the variables, running the project's hooks. The resulting
+//! [`Environment`] runs tasks.
+//!
+//! Nothing here touches the calling process: variables are not set in its
+//! environment, no shell state is recorded, nothing reads from the terminal
+//! and failures come back as [`Error`]s instead of exiting.
+//!
+//! ```no_run
+//! # async fn example() -> cuenv_sdk::Result<()> {
+//! use cuenv_sdk::Project;
+//!
+//! let project = Project::builder("path/to/project")
+//!     .environment("staging")
+//!     .capability("aws")
+//!     .build();
+//!
+//! let env = project.load_environment().await?;
+//! println!("DATABASE_URL={:?}", env.variables().get("DATABASE_URL"));
+//!
+//! let outcome = env.tasks().await?.run(&["build"]).await?;
+//! assert!(outcome.success());
+//! # Ok(())
+//! # }
+//! ```
+
+mod environment;
+mod project;
+
+pub use environment::{Environment, TaskOutcome, TaskRunner};
+pub use project::{Project, ProjectBuilder};
+
+pub use cuenv_config::{Config, Hook, ParseResult, TaskConfig, TaskNode, VariableMetadata};
+pub use cuenv_core::{Error, Result};
@@ -0,0 +1,152 @@
+//! Projects and how to open them
+
+use crate::Environment;
+use cuenv_config::{Config, ConfigLoader, RuntimeOptions};
+use cuenv_core::Result;
+use cuenv_env::manager::environment::SupervisorMode;
+use cuenv_env::EnvManager;
+use std::path::{Path, PathBuf};
+
+/// A directory with an `env.cue`, and the options to load it with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    dir: PathBuf,
+    environment: Option<String>,
+    capabilities: Vec<String>,
+    audit: bool,
+    monorepo: bool,
+}
+
+impl Project {
+    /// Start opening the project in `dir`
+    pub fn builder(dir: impl Into<PathBuf>) -> ProjectBuilder {
+        ProjectBuilder {
+            project: Self {
+                dir: dir.into(),
+                environment: None,
+                capabilities: Vec::new(),
+                audit: false,
+                monorepo: true,
+            },
+        }
+    }
+
+    /// The project in `dir`, with the default environment and capabilities
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self::builder(dir).build()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn environment(&self) -> Option<&str> {
+        self.environment.as_deref()
+    }
+
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    /// Evaluate the project's configuration: variables, tasks, hooks and
+    /// settings, without running anything
+    pub async fn load_config(&self) -> Result<Config> {
+        ConfigLoader::new()
+            .directory(self.dir.clone())
+            .runtime(RuntimeOptions {
+                environment: self.environment.clone(),
+                capabilities: self.capabilities.clone(),
+                audit_mode: self.audit,
+                ..RuntimeOptions::default()
+            })
+            .discover_monorepo(self.monorepo)
+            .load()
+            .await
+    }
+
+    /// Resolve the project's environment, running its `onEnter` hooks
+    ///
+    /// Hooks run to completion before this returns.
+    pub async fn load_environment(&self) -> Result<Environment> {
+        let mut manager = EnvManager::embedded();
+        manager
+            .load_env_with_options(
+                &self.dir,
+                self.environment.clone(),
+                self.capabilities.clone(),
+                None,
+                SupervisorMode::Synchronous,
+            )
+            .await?;
+        Ok(Environment::new(self.dir.clone(), manager, self.audit))
+    }
+}
+
+/// Options for opening a [`Project`]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ProjectBuilder {
+    project: Project,
+}
+
+impl ProjectBuilder {
+    /// Environment to apply, e.g. `staging`
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.project.environment = Some(environment.into());
+        self
+    }
+
+    /// Enable a capability; may be called several times
+    pub fn capability(mut self, capability: impl Into<String>) -> Self {
+        self.project.capabilities.push(capability.into());
+        self
+    }
+
+    /// Enable capabilities
+    pub fn capabilities<I, S>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.project
+            .capabilities
+            .extend(capabilities.into_iter().map(Into::into));
+        self
+    }
+
+    /// Run tasks in audit mode, reporting file and network access instead of
+    /// restricting it
+    pub fn audit(mut self, audit: bool) -> Self {
+        self.project.audit = audit;
+        self
+    }
+
+    /// Look for the packages of a surrounding monorepo (default: true)
+    pub fn monorepo(mut self, monorepo: bool) -> Self {
+        self.project.monorepo = monorepo;
+        self
+    }
+
+    pub fn build(self) -> Project {
+        self.project
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_collects_options() {
+        let project = Project::builder("/project")
+            .environment("staging")
+            .capability("aws")
+            .capabilities(["gcp", "k8s"])
+            .build();
+
+        assert_eq!(project.dir(), Path::new("/project"));
+        assert_eq!(project.environment(), Some("staging"));
+        assert_eq!(project.capabilities(), ["aws", "gcp", "k8s"]);
+        assert_eq!(Project::open("/project").environment(), None);
+    }
+}
@@ -1,5 +1,8 @@
-use cuenv_core::constants::{AUDIT_IGNORED_PATH_PREFIXES, AUDIT_LOG_PATH, LD_SO_CACHE};
-use cuenv_core::{Error, Result};
+use crate::enforcement::{effective_level, protection_unavailable, EnforcementLevel};
+use cuenv_core::constants::{
+    AUDIT_IGNORED_PATH_PREFIXES, AUDIT_LOG_PATH, LD_SO_CACHE, SYSTEM_READ_ONLY_PATHS,
+};
+use cuenv_core::{Error, ExitStatus, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::process::Command;
@@ -98,15 +101,32 @@ pub struct AccessRestrictions {
     pub allowed_hosts: Vec<String>,
     /// Audit mode - collect access information instead of restricting
     pub audit_mode: bool,
+    /// What happens when the platform can't apply the restrictions
+    pub enforcement: EnforcementLevel,
 }
 
 impl AccessRestrictions {
     /// Check if Landlock is supported on the current system
     #[cfg(target_os = "linux")]
     pub fn is_landlock_supported() -> bool {
-        // For now, we'll check by trying to create a ruleset
-        use landlock::Ruleset;
-        Ruleset::default().create().is_ok()
+        Self::landlock_abi_version().is_some()
+    }
+
+    /// Landlock ABI version of the running kernel, if it supports Landlock
+    #[cfg(target_os = "linux")]
+    pub fn landlock_abi_version() -> Option<i64> {
+        const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+        // SAFETY: with a null attribute and the version flag the kernel only
+        // reports the highest ABI it supports and creates nothing.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0_usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then_some(version)
     }
 
     #[cfg(not(target_os = "linux"))]
@@ -123,6 +143,7 @@ impl AccessRestrictions {
             deny_paths: Vec::new(),
             allowed_hosts: Vec::new(),
             audit_mode: false,
+            enforcement: EnforcementLevel::default(),
         }
     }
 
@@ -143,6 +164,7 @@ impl AccessRestrictions {
             deny_paths,
             allowed_hosts,
             audit_mode: false,
+            enforcement: EnforcementLevel::default(),
         }
     }
 
@@ -161,13 +183,23 @@ impl AccessRestrictions {
         self.deny_paths.push(path.into());
     }
 
+    /// Allow reading and executing from standard system directories
+    pub fn allow_system_paths(&mut self) {
+        for path in SYSTEM_READ_ONLY_PATHS {
+            let path = PathBuf::from(path);
+            if !self.read_only_paths.contains(&path) {
+                self.read_only_paths.push(path);
+            }
+        }
+    }
+
     /// Enable audit mode
     pub fn enable_audit_mode(&mut self) {
         self.audit_mode = true;
     }
 
     /// Run command with audit monitoring using strace
-    pub fn run_with_audit(&self, cmd: &mut Command) -> Result<(i32, AuditReport)> {
+    pub fn run_with_audit(&self, cmd: &mut Command) -> Result<(ExitStatus, AuditReport)> {
         if !cfg!(target_os = "linux") {
             return Err(Error::configuration(
                 "Audit mode is only supported on Linux systems".to_string(),
@@ -218,7 +250,7 @@ impl AccessRestrictions {
         // Clean up the audit log
         let _ = std::fs::remove_file(AUDIT_LOG_PATH);
 
-        Ok((output.status.code().unwrap_or(1), audit_report))
+        Ok((output.status.into(), audit_report))
     }
 
     /// Parse strace output to generate audit report
@@ -282,6 +314,7 @@ impl AccessRestrictions {
                 .unwrap_or_default(),
             allowed_hosts: security.allowed_hosts.as_ref().cloned().unwrap_or_default(),
             audit_mode: false,
+            enforcement: security.enforcement.unwrap_or_default(),
         }
     }
 
@@ -305,7 +338,7 @@ impl AccessRestrictions {
 
             // Add outputs as read-write paths
             if let Some(outputs) = &task_config.outputs {
-                for output in outputs {
+                for output in outputs.files() {
                     restrictions.add_read_write_path(PathBuf::from(output));
                 }
             }
@@ -322,15 +355,25 @@ impl AccessRestrictions {
     /// Apply restrictions to a command before execution
     /// This is the main entry point for applying platform-specific restrictions
     pub fn apply_to_command(&self, cmd: &mut Command) -> Result<()> {
-        if !self.has_any_restrictions() {
+        self.apply_with_enforcement(cmd, effective_level(self.enforcement)?)
+    }
+
+    /// Apply restrictions at `enforcement`, ignoring any override from the
+    /// environment
+    pub(crate) fn apply_with_enforcement(
+        &self,
+        cmd: &mut Command,
+        enforcement: EnforcementLevel,
+    ) -> Result<()> {
+        if !self.has_any_restrictions() || enforcement == EnforcementLevel::Off {
             return Ok(());
         }
         // Apply platform-specific restrictions
         #[cfg(target_os = "linux")]
-        self.apply_landlock_restrictions(cmd)?;
+        self.apply_landlock_restrictions(cmd, enforcement)?;
 
         #[cfg(not(target_os = "linux"))]
-        self.apply_fallback_restrictions(cmd)?;
+        self.apply_fallback_restrictions(cmd, enforcement)?;
 
         Ok(())
     }
@@ -342,16 +385,46 @@ impl AccessRestrictions {
 
     /// Apply Landlock-based restrictions on Linux
     #[cfg(target_os = "linux")]
-    fn apply_landlock_restrictions(&self, cmd: &mut Command) -> Result<()> {
+    fn apply_landlock_restrictions(
+        &self,
+        cmd: &mut Command,
+        enforcement: EnforcementLevel,
+    ) -> Result<()> {
         use landlock::{
             Access, AccessFs, AccessNet, NetPort, PathBeneath, PathFd, Ruleset, RulesetAttr,
             RulesetCreatedAttr, RulesetStatus, ABI,
         };
         use std::os::unix::process::CommandExt;
 
+        // Check kernel support up front: errors from the child only reach
+        // the parent as an errno
+        let Some(abi_version) = Self::landlock_abi_version() else {
+            return protection_unavailable(
+                enforcement,
+                Error::unsupported(
+                    "Landlock",
+                    "the running kernel does not support Landlock (Linux 5.13+ required)",
+                ),
+            );
+        };
+        let network_supported = abi_version >= 4;
+        if self.restrict_network && !network_supported {
+            protection_unavailable(
+                enforcement,
+                Error::unsupported(
+                    "restrictNetwork",
+                    "Landlock network rules need Linux 6.7 or newer",
+                ),
+            )?;
+        }
+        let restrict_network = self.restrict_network && network_supported;
+        if !self.restrict_disk && !restrict_network {
+            return Ok(());
+        }
+
         // Clone the necessary data for the pre_exec closure
         let restrict_disk = self.restrict_disk;
-        let restrict_network = self.restrict_network;
+        let enforce = enforcement == EnforcementLevel::Enforce;
         let read_only_paths = self.read_only_paths.clone();
         let read_write_paths = self.read_write_paths.clone();
         let allowed_hosts = self.allowed_hosts.clone();
@@ -461,6 +534,11 @@ impl AccessRestrictions {
                 })?;
 
                 if status.ruleset == RulesetStatus::NotEnforced {
+                    if enforce {
+                        return Err(std::io::Error::other(
+                            "Landlock restrictions are not enforced by the running kernel",
+                        ));
+                    }
                     eprintln!("⚠️  Warning: Landlock is not supported by the running kernel. Security restrictions will not be enforced.");
                     log::warn!("Landlock is not supported by the running kernel - security restrictions disabled");
                 }
@@ -474,11 +552,18 @@ impl AccessRestrictions {
 
     /// Apply fallback restrictions on non-Linux platforms
     #[cfg(not(target_os = "linux"))]
-    fn apply_fallback_restrictions(&self, _cmd: &mut Command) -> Result<()> {
+    fn apply_fallback_restrictions(
+        &self,
+        _cmd: &mut Command,
+        enforcement: EnforcementLevel,
+    ) -> Result<()> {
         if self.has_any_restrictions() {
-            return Err(Error::configuration(
-                "Access restrictions are only supported on Linux with Landlock. Please use a Linux system with kernel 5.13+ for sandboxing support.".to_string()
-            ));
+            return protection_unavailable(
+                enforcement,
+                Error::configuration(
+                    "Access restrictions are only supported on Linux with Landlock. Please use a Linux system with kernel 5.13+ for sandboxing support.".to_string()
+                ),
+            );
         }
         Ok(())
     }
@@ -0,0 +1,251 @@
+//! Suggested security configuration derived from audit reports
+
+use crate::AuditReport;
+use cuenv_config::SecurityConfig;
+use cuenv_core::constants::SYSTEM_READ_ONLY_PATHS;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// What an [`AccessGroup`] grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Path,
+    Host,
+}
+
+/// Accesses observed during an audit, to be allowed together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessGroup {
+    pub kind: AccessKind,
+    /// The path or host to allow: a directory when several files in it were read
+    pub allow: String,
+    /// What was observed
+    pub observed: Vec<String>,
+}
+
+impl AuditReport {
+    /// Security configuration granting the access observed during the audit
+    ///
+    /// Paths inside standard system directories are left out because
+    /// sandboxed commands may always read them.
+    pub fn suggested_security_config(&self) -> SecurityConfig {
+        let paths = sorted_unique(
+            self.accessed_files
+                .iter()
+                .filter(|path| !is_system_path(path)),
+        );
+        let hosts = sorted_unique(self.network_connections.iter());
+
+        SecurityConfig {
+            restrict_disk: Some(true),
+            restrict_network: Some(true),
+            read_only_paths: (!paths.is_empty()).then_some(paths),
+            allowed_hosts: (!hosts.is_empty()).then_some(hosts),
+            ..Default::default()
+        }
+    }
+
+    /// The accesses of the audit to review, files grouped by directory
+    ///
+    /// System paths are left out, as for
+    /// [`suggested_security_config`](Self::suggested_security_config).
+    pub fn access_groups(&self) -> Vec<AccessGroup> {
+        let mut directories: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for path in sorted_unique(
+            self.accessed_files
+                .iter()
+                .filter(|path| !is_system_path(path)),
+        ) {
+            let directory = Path::new(&path)
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .filter(|parent| !parent.is_empty() && parent != "/")
+                .unwrap_or_else(|| path.clone());
+            directories.entry(directory).or_default().push(path);
+        }
+
+        let paths = directories
+            .into_iter()
+            .map(|(directory, observed)| AccessGroup {
+                kind: AccessKind::Path,
+                allow: if observed.len() > 1 {
+                    directory
+                } else {
+                    observed[0].clone()
+                },
+                observed,
+            });
+        let hosts = sorted_unique(self.network_connections.iter())
+            .into_iter()
+            .map(|host| AccessGroup {
+                kind: AccessKind::Host,
+                allow: host.clone(),
+                observed: vec![host],
+            });
+        paths.chain(hosts).collect()
+    }
+
+    /// Security configuration allowing the `accepted` groups of
+    /// [`access_groups`](Self::access_groups)
+    ///
+    /// Disk or network access is only restricted when every group of its
+    /// kind was accepted, so what the task was seen to need keeps working.
+    pub fn accepted_security_config(&self, accepted: &[&AccessGroup]) -> SecurityConfig {
+        let all_accepted = |kind: AccessKind| {
+            self.access_groups()
+                .iter()
+                .filter(|group| group.kind == kind)
+                .all(|group| accepted.contains(&group))
+        };
+        let allowed = |kind: AccessKind| {
+            let values: Vec<String> = accepted
+                .iter()
+                .filter(|group| group.kind == kind)
+                .map(|group| group.allow.clone())
+                .collect();
+            (!values.is_empty()).then_some(values)
+        };
+
+        SecurityConfig {
+            restrict_disk: all_accepted(AccessKind::Path).then_some(true),
+            restrict_network: all_accepted(AccessKind::Host).then_some(true),
+            read_only_paths: allowed(AccessKind::Path),
+            allowed_hosts: allowed(AccessKind::Host),
+            ..Default::default()
+        }
+    }
+
+    /// A `capabilities` block for `command` ready to paste into env.cue
+    pub fn suggested_capability_block(&self, command: &str) -> String {
+        let name = Path::new(command)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| command.to_string());
+        let security = self.suggested_security_config();
+
+        let mut lines = vec![
+            "capabilities: {".to_string(),
+            format!("\t{}: {{", quote(&name)),
+            format!("\t\tcommands: [{}]", quote(&name)),
+            "\t\tsecurity: {".to_string(),
+            "\t\t\trestrictDisk: true".to_string(),
+            "\t\t\trestrictNetwork: true".to_string(),
+        ];
+        push_list(&mut lines, "readOnlyPaths", &security.read_only_paths);
+        push_list(&mut lines, "allowedHosts", &security.allowed_hosts);
+        lines.extend(["\t\t}", "\t}", "}"].map(String::from));
+
+        lines.join("\n")
+    }
+}
+
+fn is_system_path(path: &str) -> bool {
+    SYSTEM_READ_ONLY_PATHS
+        .iter()
+        .any(|prefix| Path::new(path).starts_with(prefix))
+}
+
+fn sorted_unique<'a>(items: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut items: Vec<String> = items.cloned().collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+fn push_list(lines: &mut Vec<String>, field: &str, values: &Option<Vec<String>>) {
+    let Some(values) = values else {
+        return;
+    };
+    lines.push(format!("\t\t\t{field}: ["));
+    lines.extend(
+        values
+            .iter()
+            .map(|value| format!("\t\t\t\t{},", quote(value))),
+    );
+    lines.push("\t\t\t]".to_string());
+}
+
+/// CUE string literals share JSON's escaping rules
+fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> AuditReport {
+        AuditReport {
+            accessed_files: vec![
+                "/usr/lib/libc.so.6".to_string(),
+                "/home/dev/.aws/config".to_string(),
+                "/home/dev/.aws/config".to_string(),
+            ],
+            network_connections: vec!["52.94.0.1".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_suggested_security_config_skips_system_paths() {
+        let security = report().suggested_security_config();
+        assert_eq!(security.restrict_disk, Some(true));
+        assert_eq!(
+            security.read_only_paths,
+            Some(vec!["/home/dev/.aws/config".to_string()])
+        );
+        assert_eq!(security.allowed_hosts, Some(vec!["52.94.0.1".to_string()]));
+    }
+
+    #[test]
+    fn test_access_groups() {
+        let mut report = report();
+        report
+            .accessed_files
+            .push("/home/dev/.aws/credentials".to_string());
+        report.accessed_files.push("/tmp/out.log".to_string());
+
+        let groups = report.access_groups();
+        let allowed: Vec<(&str, AccessKind)> = groups
+            .iter()
+            .map(|group| (group.allow.as_str(), group.kind))
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                ("/home/dev/.aws", AccessKind::Path),
+                ("/tmp/out.log", AccessKind::Path),
+                ("52.94.0.1", AccessKind::Host),
+            ]
+        );
+        assert_eq!(groups[0].observed.len(), 2);
+
+        let security = report.accepted_security_config(&[&groups[0], &groups[2]]);
+        assert_eq!(security.restrict_disk, None);
+        assert_eq!(security.restrict_network, Some(true));
+        assert_eq!(
+            security.read_only_paths,
+            Some(vec!["/home/dev/.aws".to_string()])
+        );
+        let all: Vec<&AccessGroup> = groups.iter().collect();
+        assert_eq!(
+            report.accepted_security_config(&all).restrict_disk,
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_suggested_capability_block() {
+        let block = report().suggested_capability_block("/usr/local/bin/aws");
+        assert!(block.starts_with("capabilities: {\n\t\"aws\": {\n\t\tcommands: [\"aws\"]"));
+        assert!(block.contains("\t\t\treadOnlyPaths: [\n\t\t\t\t\"/home/dev/.aws/config\",\n"));
+        assert!(block.contains("allowedHosts"));
+
+        let empty = AuditReport {
+            accessed_files: vec![],
+            network_connections: vec![],
+        };
+        assert!(!empty
+            .suggested_capability_block("true")
+            .contains("readOnlyPaths"));
+    }
+}
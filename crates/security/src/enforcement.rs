@@ -0,0 +1,83 @@
+//! Enforcement levels for security restrictions
+//!
+//! Tasks pick a level with `security.enforcement`. Setting
+//! `CUENV_SECURITY_ENFORCEMENT` raises it for every task, e.g. to insist on
+//! enforcement on CI runners. It never lowers a task's level, so the
+//! environment cannot switch off restrictions a task asks for.
+
+use cuenv_core::{Error, Result};
+
+pub use cuenv_core::EnforcementLevel;
+
+/// Environment variable overriding the configured enforcement level
+pub const ENFORCEMENT_ENV_VAR: &str = "CUENV_SECURITY_ENFORCEMENT";
+
+/// The level in effect for restrictions configured with `configured`
+pub fn effective_level(configured: EnforcementLevel) -> Result<EnforcementLevel> {
+    level_from(
+        std::env::var(ENFORCEMENT_ENV_VAR).ok().as_deref(),
+        configured,
+    )
+}
+
+fn level_from(value: Option<&str>, configured: EnforcementLevel) -> Result<EnforcementLevel> {
+    let requested: EnforcementLevel = match value.map(str::trim) {
+        Some(value) if !value.is_empty() => value.parse()?,
+        _ => return Ok(configured),
+    };
+    if requested < configured {
+        eprintln!(
+            "⚠️  Warning: ignoring {ENFORCEMENT_ENV_VAR}={requested}, which is lower than the configured level '{configured}'"
+        );
+        log::warn!(
+            "Ignoring {ENFORCEMENT_ENV_VAR}={requested} below configured level {configured}"
+        );
+    }
+    Ok(requested.max(configured))
+}
+
+/// Handle a protection the platform cannot apply
+///
+/// Fails with `error` when enforcing, otherwise carries on without the
+/// protection, warning about it unless enforcement is off.
+pub fn protection_unavailable(level: EnforcementLevel, error: Error) -> Result<()> {
+    match level {
+        EnforcementLevel::Enforce => Err(error),
+        EnforcementLevel::Warn => {
+            eprintln!("⚠️  Warning: {error}. Running without this protection.");
+            log::warn!("Running without unavailable protection: {error}");
+            Ok(())
+        }
+        EnforcementLevel::Off => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_only_raises_configured_level() {
+        let configured = EnforcementLevel::Warn;
+        assert_eq!(level_from(None, configured).unwrap(), configured);
+        assert_eq!(level_from(Some(""), configured).unwrap(), configured);
+        assert_eq!(
+            level_from(Some("enforce"), configured).unwrap(),
+            EnforcementLevel::Enforce
+        );
+        assert_eq!(level_from(Some(" off "), configured).unwrap(), configured);
+        assert_eq!(
+            level_from(Some("warn"), EnforcementLevel::Off).unwrap(),
+            EnforcementLevel::Warn
+        );
+        assert!(level_from(Some("strict"), configured).is_err());
+    }
+
+    #[test]
+    fn test_unavailable_protection_only_fails_when_enforcing() {
+        let error = || Error::unsupported("seccompProfile", "not on this platform");
+        assert!(protection_unavailable(EnforcementLevel::Enforce, error()).is_err());
+        assert!(protection_unavailable(EnforcementLevel::Warn, error()).is_ok());
+        assert!(protection_unavailable(EnforcementLevel::Off, error()).is_ok());
+    }
+}
@@ -5,13 +5,25 @@
 //! - Audit logging
 //! - File system access controls
 //! - Network access controls
+//! - Seccomp syscall filtering
+//! - Enforcement levels and a self-test of the protections the platform provides
+//! - An organisation-wide policy no task can opt out of
 
 pub mod access_restrictions;
 pub mod access_restrictions_builder;
 pub mod audit;
+mod audit_suggestion;
+pub mod enforcement;
+pub mod policy;
+pub mod seccomp;
+pub mod selftest;
 pub mod validator;
 
 pub use access_restrictions::*;
 pub use access_restrictions_builder::*;
 pub use audit::*;
+pub use audit_suggestion::{AccessGroup, AccessKind};
+pub use enforcement::EnforcementLevel;
+pub use policy::{PolicyViolation, SecurityPolicy};
+pub use seccomp::{SeccompFilter, SeccompMode, SeccompProfile, DEFAULT_SECCOMP_PROFILE};
 pub use validator::SecurityValidator;
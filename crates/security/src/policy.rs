@@ -0,0 +1,403 @@
+//! Organisation-wide security policy
+//!
+//! A policy sets rules no task can opt out of: hosts no task may be allowed
+//! to reach, directories whose tasks must run with disk restrictions, shells
+//! tasks may not use and a ceiling on task timeouts. It is read from
+//! `/etc/cuenv/policy.cue` and `.cuenv/policy.cue` in the project root
+//! (package `policy`), or `policy.json` next to them where the CUE bridge is
+//! missing, and both apply when both exist:
+//!
+//! ```cue
+//! package policy
+//!
+//! forbiddenHosts: ["pastebin.com", "*.ngrok.io"]
+//! restrictDisk: ["deploy", "/srv/releases"]
+//! bannedShells: ["pwsh"]
+//! maxTimeout: "30m"
+//! ```
+//!
+//! Task definitions are checked when they are built; each violation is
+//! reported with the file of the rule it breaks.
+
+use cuenv_config::CueParser;
+use cuenv_core::{EnforcementLevel, Error, Result, TaskDefinition};
+use cuenv_utils::duration::parse_duration;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory of the machine-wide policy
+pub const SYSTEM_POLICY_DIR: &str = "/etc/cuenv";
+
+/// Directory of the project policy, relative to the project root
+pub const PROJECT_POLICY_DIR: &str = ".cuenv";
+
+/// CUE package of policy files
+pub const POLICY_PACKAGE: &str = "policy";
+
+/// A policy file as written
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    forbidden_hosts: Vec<String>,
+    #[serde(default)]
+    restrict_disk: Vec<PathBuf>,
+    #[serde(default)]
+    banned_shells: Vec<String>,
+    #[serde(default)]
+    max_timeout: Option<String>,
+}
+
+/// A rule and the file it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule<T> {
+    value: T,
+    source: PathBuf,
+}
+
+impl<T> Rule<T> {
+    fn new(value: T, source: &Path) -> Self {
+        Self {
+            value,
+            source: source.to_path_buf(),
+        }
+    }
+}
+
+/// The rules of every policy file that applies to a project
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    forbidden_hosts: Vec<Rule<String>>,
+    restrict_disk: Vec<Rule<PathBuf>>,
+    banned_shells: Vec<Rule<String>>,
+    max_timeout: Option<Rule<Duration>>,
+}
+
+/// A rule a task breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub task: String,
+    pub reason: String,
+    pub source: PathBuf,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task '{}' {} (policy {})",
+            self.task,
+            self.reason,
+            self.source.display()
+        )
+    }
+}
+
+impl SecurityPolicy {
+    /// The system policy and that of the project at `project_root`
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let mut policy = Self::default();
+        for dir in [
+            PathBuf::from(SYSTEM_POLICY_DIR),
+            project_root.join(PROJECT_POLICY_DIR),
+        ] {
+            policy.load_dir(&dir, project_root)?;
+        }
+        Ok(policy)
+    }
+
+    /// Whether no rules apply
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn load_dir(&mut self, dir: &Path, project_root: &Path) -> Result<()> {
+        let cue = dir.join("policy.cue");
+        let json = dir.join("policy.json");
+        if cue.is_file() {
+            let value = CueParser::evaluate_value(dir, POLICY_PACKAGE).map_err(|e| {
+                Error::configuration(format!(
+                    "Failed to evaluate security policy {}: {e}. Without the CUE bridge, write it as {}",
+                    cue.display(),
+                    json.display()
+                ))
+            })?;
+            self.add(&cue, value, project_root)
+        } else if json.is_file() {
+            let content = std::fs::read_to_string(&json)
+                .map_err(|e| Error::file_system(&json, "read security policy", e))?;
+            let value = serde_json::from_str(&content).map_err(|e| {
+                Error::configuration(format!(
+                    "Failed to parse security policy {}: {e}",
+                    json.display()
+                ))
+            })?;
+            self.add(&json, value, project_root)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add the rules of the policy file at `source`
+    ///
+    /// Relative `restrictDisk` paths are relative to the project root.
+    fn add(&mut self, source: &Path, value: serde_json::Value, project_root: &Path) -> Result<()> {
+        let invalid = |reason: String| {
+            Error::configuration(format!(
+                "Invalid security policy {}: {reason}",
+                source.display()
+            ))
+        };
+        let file = PolicyFile::deserialize(value).map_err(|e| invalid(e.to_string()))?;
+        self.forbidden_hosts.extend(
+            file.forbidden_hosts
+                .into_iter()
+                .map(|host| Rule::new(host, source)),
+        );
+        self.restrict_disk
+            .extend(file.restrict_disk.into_iter().map(|path| {
+                let path = if path.is_absolute() {
+                    path
+                } else {
+                    project_root.join(path)
+                };
+                Rule::new(path, source)
+            }));
+        self.banned_shells.extend(
+            file.banned_shells
+                .into_iter()
+                .map(|shell| Rule::new(shell, source)),
+        );
+        if let Some(max_timeout) = file.max_timeout {
+            let max_timeout = parse_duration(&max_timeout)
+                .map_err(|e| invalid(format!("maxTimeout '{max_timeout}': {e}")))?;
+            // The strictest ceiling wins
+            if self
+                .max_timeout
+                .as_ref()
+                .is_none_or(|current| max_timeout < current.value)
+            {
+                self.max_timeout = Some(Rule::new(max_timeout, source));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cap the timeout of a task that did not set one at the policy's ceiling
+    pub fn apply_default_timeout(&self, definition: &mut TaskDefinition) {
+        if let Some(max) = &self.max_timeout {
+            definition.timeout = definition.timeout.min(max.value);
+        }
+    }
+
+    /// The rules `definition` breaks
+    pub fn check(&self, definition: &TaskDefinition) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let mut violation = |reason: String, source: &Path| {
+            violations.push(PolicyViolation {
+                task: definition.name.clone(),
+                reason,
+                source: source.to_path_buf(),
+            })
+        };
+
+        let allowed_hosts = definition
+            .security
+            .iter()
+            .flat_map(|security| &security.allowed_hosts);
+        for host in allowed_hosts {
+            for forbidden in &self.forbidden_hosts {
+                if hosts_overlap(host, &forbidden.value) {
+                    violation(
+                        format!("allows host '{host}', which is forbidden"),
+                        &forbidden.source,
+                    );
+                }
+            }
+        }
+
+        let restricts_disk = definition.security.as_ref().is_some_and(|security| {
+            security.restrict_disk && security.enforcement != EnforcementLevel::Off
+        });
+        if !restricts_disk {
+            for path in &self.restrict_disk {
+                if definition.working_directory.starts_with(&path.value) {
+                    violation(
+                        format!(
+                            "runs in {} without restrictDisk, which is mandatory there",
+                            path.value.display()
+                        ),
+                        &path.source,
+                    );
+                }
+            }
+        }
+
+        let shell = Path::new(&definition.shell)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&definition.shell);
+        for banned in &self.banned_shells {
+            if banned.value == definition.shell || banned.value == shell {
+                violation(format!("uses the banned shell '{shell}'"), &banned.source);
+            }
+        }
+
+        if let Some(max) = &self.max_timeout {
+            if definition.timeout > max.value {
+                violation(
+                    format!(
+                        "has a timeout of {}s, above the maximum of {}s",
+                        definition.timeout.as_secs(),
+                        max.value.as_secs()
+                    ),
+                    &max.source,
+                );
+            }
+        }
+        violations
+    }
+}
+
+/// Whether an allowed host pattern reaches a forbidden one, e.g.
+/// `api.example.com` and `example.com` or `*.example.com`
+fn hosts_overlap(allowed: &str, forbidden: &str) -> bool {
+    let bare = |host: &str| {
+        let host = host.rsplit_once(':').map_or(host, |(name, _)| name);
+        host.trim_start_matches("*.").to_ascii_lowercase()
+    };
+    let (allowed_name, forbidden_name) = (bare(allowed), bare(forbidden));
+    let within = |host: &str, domain: &str| host == domain || host.ends_with(&format!(".{domain}"));
+    within(&allowed_name, &forbidden_name)
+        || (allowed.starts_with("*.") && within(&forbidden_name, &allowed_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_core::{TaskExecutionMode, TaskSecurity};
+    use serde_json::json;
+
+    fn definition(name: &str, working_directory: &str) -> TaskDefinition {
+        TaskDefinition {
+            name: name.to_string(),
+            description: None,
+            execution_mode: TaskExecutionMode::Command {
+                command: "make".to_string(),
+            },
+            dependencies: Vec::new(),
+            working_directory: PathBuf::from(working_directory),
+            shell: "/bin/bash".to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            security: None,
+            cache: Default::default(),
+            timeout: Duration::from_secs(3600),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
+        }
+    }
+
+    fn security(restrict_disk: bool, allowed_hosts: &[&str]) -> Option<TaskSecurity> {
+        Some(TaskSecurity {
+            restrict_disk,
+            restrict_network: !allowed_hosts.is_empty(),
+            read_only_paths: Vec::new(),
+            write_only_paths: Vec::new(),
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
+        })
+    }
+
+    fn policy() -> SecurityPolicy {
+        let mut policy = SecurityPolicy::default();
+        policy
+            .add(
+                Path::new("/etc/cuenv/policy.cue"),
+                json!({"forbiddenHosts": ["*.ngrok.io"], "bannedShells": ["bash"], "maxTimeout": "1h"}),
+                Path::new("/repo"),
+            )
+            .unwrap();
+        policy
+            .add(
+                Path::new("/repo/.cuenv/policy.json"),
+                json!({"restrictDisk": ["deploy"], "maxTimeout": "30m"}),
+                Path::new("/repo"),
+            )
+            .unwrap();
+        policy
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let policy = policy();
+        assert_eq!(policy.max_timeout.as_ref().unwrap().value.as_secs(), 1800);
+
+        let mut task = definition("ship", "/repo/deploy/prod");
+        task.security = security(false, &["tunnel.ngrok.io", "github.com"]);
+        let reasons: Vec<_> = policy
+            .check(&task)
+            .into_iter()
+            .map(|v| (v.reason, v.source))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    "allows host 'tunnel.ngrok.io', which is forbidden".to_string(),
+                    PathBuf::from("/etc/cuenv/policy.cue")
+                ),
+                (
+                    "runs in /repo/deploy without restrictDisk, which is mandatory there"
+                        .to_string(),
+                    PathBuf::from("/repo/.cuenv/policy.json")
+                ),
+                (
+                    "uses the banned shell 'bash'".to_string(),
+                    PathBuf::from("/etc/cuenv/policy.cue")
+                ),
+                (
+                    "has a timeout of 3600s, above the maximum of 1800s".to_string(),
+                    PathBuf::from("/repo/.cuenv/policy.json")
+                ),
+            ]
+        );
+
+        let mut compliant = definition("build", "/repo/deploy");
+        compliant.shell = "sh".to_string();
+        compliant.security = security(true, &[]);
+        policy.apply_default_timeout(&mut compliant);
+        assert!(policy.check(&compliant).is_empty());
+    }
+
+    #[test]
+    fn test_policy_file_errors() {
+        let mut policy = SecurityPolicy::default();
+        let root = Path::new("/repo");
+        let source = Path::new("policy.json");
+        assert!(policy
+            .add(source, json!({"forbiddenHost": ["x"]}), root)
+            .is_err());
+        assert!(policy
+            .add(source, json!({"maxTimeout": "soon"}), root)
+            .is_err());
+        assert!(policy.is_empty());
+    }
+
+    #[test]
+    fn test_hosts_overlap() {
+        assert!(hosts_overlap("example.com", "example.com"));
+        assert!(hosts_overlap("api.example.com:443", "example.com"));
+        assert!(hosts_overlap("*.example.com", "api.example.com"));
+        assert!(!hosts_overlap("notexample.com", "example.com"));
+        assert!(!hosts_overlap("example.com", "api.example.com"));
+    }
+}
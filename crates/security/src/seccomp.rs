@@ -0,0 +1,377 @@
+//! Seccomp syscall filtering for tasks (Linux only)
+//!
+//! A filter is a small classic BPF program installed in the child process
+//! right before `exec`. Blocked syscalls fail with `EPERM` when enforcing;
+//! in audit mode they still succeed but the kernel logs them as `type=1326`
+//! records (visible in `dmesg` or `journalctl -k`), so a profile can be
+//! trialled before it starts breaking builds.
+//!
+//! Besides the built-in `"default"` profile, a task may point at a JSON file
+//! listing syscall names or raw numbers:
+//!
+//! ```json
+//! {"blockedSyscalls": ["ptrace", "mount", "keyctl"]}
+//! ```
+
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Name of the built-in profile
+pub const DEFAULT_SECCOMP_PROFILE: &str = "default";
+
+/// Syscalls blocked by the default profile: debugging other processes,
+/// mounting, kernel module and kexec loading, and kernel keyring access
+const DEFAULT_BLOCKED_SYSCALLS: &[&str] = &[
+    "ptrace",
+    "process_vm_readv",
+    "process_vm_writev",
+    "mount",
+    "umount2",
+    "pivot_root",
+    "chroot",
+    "setns",
+    "open_by_handle_at",
+    "init_module",
+    "finit_module",
+    "delete_module",
+    "kexec_load",
+    "kexec_file_load",
+    "reboot",
+    "swapon",
+    "swapoff",
+    "acct",
+    "quotactl",
+    "syslog",
+    "bpf",
+    "perf_event_open",
+    "userfaultfd",
+    "add_key",
+    "keyctl",
+    "request_key",
+];
+
+/// What happens when a task makes a blocked syscall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompMode {
+    /// Fail the syscall with `EPERM`
+    Enforce,
+    /// Allow the syscall but log it to the kernel audit log
+    Audit,
+}
+
+/// A list of syscalls to block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeccompProfile {
+    /// Syscall names (e.g. `"ptrace"`) or numbers for the host architecture
+    pub blocked_syscalls: Vec<String>,
+}
+
+impl SeccompProfile {
+    /// The built-in profile
+    pub fn builtin() -> Self {
+        Self {
+            blocked_syscalls: DEFAULT_BLOCKED_SYSCALLS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+
+    /// Load `"default"` or a JSON profile file
+    pub fn load(profile: &str) -> Result<Self> {
+        if profile == DEFAULT_SECCOMP_PROFILE {
+            return Ok(Self::builtin());
+        }
+
+        let path = Path::new(profile);
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::file_system(path, "read seccomp profile", e))?;
+        serde_json::from_str(&content).map_err(|e| Error::Json {
+            message: format!("invalid seccomp profile {}", path.display()),
+            source: e,
+        })
+    }
+}
+
+/// A compiled seccomp filter ready to attach to a command
+#[derive(Debug, Clone)]
+pub struct SeccompFilter {
+    syscalls: Vec<u32>,
+    mode: SeccompMode,
+}
+
+impl SeccompFilter {
+    /// Resolve the profile's syscalls for the host architecture
+    pub fn new(profile: &SeccompProfile, mode: SeccompMode) -> Result<Self> {
+        let mut syscalls = profile
+            .blocked_syscalls
+            .iter()
+            .map(|name| {
+                name.parse::<u32>()
+                    .ok()
+                    .or_else(|| syscall_number(name))
+                    .ok_or_else(|| {
+                        Error::configuration(format!(
+                            "Unknown syscall '{name}' in seccomp profile; use its number instead"
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        syscalls.sort_unstable();
+        syscalls.dedup();
+
+        Ok(Self { syscalls, mode })
+    }
+
+    /// Load a profile by name or path and compile it
+    pub fn load(profile: &str, mode: SeccompMode) -> Result<Self> {
+        Self::new(&SeccompProfile::load(profile)?, mode)
+    }
+
+    /// Blocked syscall numbers, sorted
+    pub fn syscalls(&self) -> &[u32] {
+        &self.syscalls
+    }
+
+    pub fn mode(&self) -> SeccompMode {
+        self.mode
+    }
+
+    /// Install the filter in the child process before `exec`
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub fn apply_to_command(&self, cmd: &mut Command) -> Result<()> {
+        use std::os::unix::process::CommandExt;
+
+        let program = bpf::program(&self.syscalls, self.mode);
+        tracing::debug!(
+            syscalls = self.syscalls.len(),
+            mode = ?self.mode,
+            "Applying seccomp filter"
+        );
+
+        // SAFETY: the program is built before fork and only borrowed in the
+        // child; prctl is async-signal-safe.
+        unsafe {
+            cmd.pre_exec(move || {
+                let fprog = libc::sock_fprog {
+                    len: program.len() as libc::c_ushort,
+                    filter: program.as_ptr().cast_mut(),
+                };
+                // Required to install a filter without CAP_SYS_ADMIN
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &fprog as *const libc::sock_fprog,
+                ) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Install the filter in the child process before `exec`
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    pub fn apply_to_command(&self, _cmd: &mut Command) -> Result<()> {
+        Err(Error::unsupported(
+            "seccompProfile",
+            "seccomp filtering is only supported on Linux (x86_64 and aarch64)",
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn syscall_number(name: &str) -> Option<u32> {
+    let number = match name {
+        "ptrace" => libc::SYS_ptrace,
+        "process_vm_readv" => libc::SYS_process_vm_readv,
+        "process_vm_writev" => libc::SYS_process_vm_writev,
+        "mount" => libc::SYS_mount,
+        "umount2" => libc::SYS_umount2,
+        "pivot_root" => libc::SYS_pivot_root,
+        "chroot" => libc::SYS_chroot,
+        "setns" => libc::SYS_setns,
+        "unshare" => libc::SYS_unshare,
+        "open_by_handle_at" => libc::SYS_open_by_handle_at,
+        "init_module" => libc::SYS_init_module,
+        "finit_module" => libc::SYS_finit_module,
+        "delete_module" => libc::SYS_delete_module,
+        "kexec_load" => libc::SYS_kexec_load,
+        "kexec_file_load" => libc::SYS_kexec_file_load,
+        "reboot" => libc::SYS_reboot,
+        "swapon" => libc::SYS_swapon,
+        "swapoff" => libc::SYS_swapoff,
+        "acct" => libc::SYS_acct,
+        "quotactl" => libc::SYS_quotactl,
+        "syslog" => libc::SYS_syslog,
+        "bpf" => libc::SYS_bpf,
+        "perf_event_open" => libc::SYS_perf_event_open,
+        "userfaultfd" => libc::SYS_userfaultfd,
+        "add_key" => libc::SYS_add_key,
+        "keyctl" => libc::SYS_keyctl,
+        "request_key" => libc::SYS_request_key,
+        "personality" => libc::SYS_personality,
+        "settimeofday" => libc::SYS_settimeofday,
+        "clock_settime" => libc::SYS_clock_settime,
+        _ => return None,
+    };
+    u32::try_from(number).ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn syscall_number(_name: &str) -> Option<u32> {
+    None
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod bpf {
+    use super::SeccompMode;
+    use libc::{sock_filter, BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xC000_003E;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+    /// Offsets into `struct seccomp_data`
+    const NR_OFFSET: u32 = 0;
+    const ARCH_OFFSET: u32 = 4;
+
+    /// Syscall numbers at or above this bit use the x32 ABI on x86_64
+    #[cfg(target_arch = "x86_64")]
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    fn stmt(code: u32, k: u32) -> sock_filter {
+        jump(code, k, 0, 0)
+    }
+
+    fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    /// Build the filter program
+    ///
+    /// Syscalls made through a foreign ABI (32-bit compat, x32) can't be
+    /// matched by number, so they receive the blocking action wholesale.
+    pub(super) fn program(syscalls: &[u32], mode: SeccompMode) -> Vec<sock_filter> {
+        let action = match mode {
+            SeccompMode::Enforce => libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+            SeccompMode::Audit => libc::SECCOMP_RET_LOG,
+        };
+
+        let mut program = vec![
+            stmt(BPF_LD | BPF_W | BPF_ABS, ARCH_OFFSET),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET | BPF_K, action),
+            stmt(BPF_LD | BPF_W | BPF_ABS, NR_OFFSET),
+        ];
+
+        #[cfg(target_arch = "x86_64")]
+        program.extend([
+            jump(BPF_JMP | libc::BPF_JGE | BPF_K, X32_SYSCALL_BIT, 0, 1),
+            stmt(BPF_RET | BPF_K, action),
+        ]);
+
+        program.extend(syscalls.iter().flat_map(|&nr| {
+            [
+                jump(BPF_JMP | BPF_JEQ | BPF_K, nr, 0, 1),
+                stmt(BPF_RET | BPF_K, action),
+            ]
+        }));
+        program.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+        program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_builtin_and_file_profiles() {
+        assert_eq!(
+            SeccompProfile::load(DEFAULT_SECCOMP_PROFILE).unwrap(),
+            SeccompProfile::builtin()
+        );
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("seccomp.json");
+        fs::write(&path, r#"{"blockedSyscalls": ["ptrace", "321"]}"#).unwrap();
+        let profile = SeccompProfile::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(profile.blocked_syscalls, vec!["ptrace", "321"]);
+
+        fs::write(&path, "{}").unwrap();
+        assert!(SeccompProfile::load(path.to_str().unwrap()).is_err());
+        assert!(SeccompProfile::load("/nonexistent/seccomp.json").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_filter_resolves_names_and_numbers() {
+        let filter = SeccompFilter::new(&SeccompProfile::builtin(), SeccompMode::Enforce).unwrap();
+        assert!(filter.syscalls().contains(&(libc::SYS_ptrace as u32)));
+        assert_eq!(filter.syscalls().len(), DEFAULT_BLOCKED_SYSCALLS.len());
+
+        let profile = SeccompProfile {
+            blocked_syscalls: vec!["4242".to_string(), "4242".to_string()],
+        };
+        let filter = SeccompFilter::new(&profile, SeccompMode::Audit).unwrap();
+        assert_eq!(filter.syscalls(), &[4242]);
+        assert_eq!(filter.mode(), SeccompMode::Audit);
+
+        let unknown = SeccompProfile {
+            blocked_syscalls: vec!["not_a_syscall".to_string()],
+        };
+        assert!(SeccompFilter::new(&unknown, SeccompMode::Enforce).is_err());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_filter_blocks_syscalls_in_child() {
+        let temp = TempDir::new().unwrap();
+        let profile = SeccompProfile {
+            blocked_syscalls: vec![libc::SYS_mkdir.to_string(), libc::SYS_mkdirat.to_string()],
+        };
+
+        let run_mkdir = |mode: SeccompMode, dir: &str| {
+            let mut cmd = Command::new("mkdir");
+            cmd.arg(temp.path().join(dir));
+            SeccompFilter::new(&profile, mode)
+                .unwrap()
+                .apply_to_command(&mut cmd)
+                .unwrap();
+            cmd.output().unwrap().status.success()
+        };
+
+        assert!(!run_mkdir(SeccompMode::Enforce, "enforced"));
+        assert!(!temp.path().join("enforced").exists());
+
+        assert!(run_mkdir(SeccompMode::Audit, "audited"));
+        assert!(temp.path().join("audited").exists());
+    }
+}
@@ -0,0 +1,235 @@
+//! Probes for Landlock and seccomp
+//!
+//! Operations run inside `pre_exec`, between `fork` and `exec`, so they only
+//! make raw syscalls on data prepared beforehand.
+
+use super::{ProbeResult, ProbeStatus};
+use crate::access_restrictions::AccessRestrictions;
+use crate::enforcement::EnforcementLevel;
+use crate::seccomp::{SeccompFilter, SeccompMode, DEFAULT_SECCOMP_PROFILE};
+use cuenv_core::Result;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::net::{Ipv4Addr, TcpListener};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+/// Byte read through `process_vm_readv` by the seccomp probe
+static PROBE_BYTE: u8 = 1;
+
+/// Writing a file in a directory the sandbox doesn't grant write access to
+pub(super) fn filesystem() -> ProbeResult {
+    const FEATURE: &str = "filesystem";
+    const OPERATION: &str = "write outside the allowed paths";
+
+    let dir = std::env::temp_dir().join(format!("cuenv-selftest-{}", uuid::Uuid::new_v4()));
+    if let Err(e) = std::fs::create_dir(&dir) {
+        return ProbeResult::new(
+            FEATURE,
+            OPERATION,
+            ProbeStatus::Inconclusive,
+            format!("cannot create {}: {e}", dir.display()),
+        );
+    }
+    let target = CString::new(dir.join("escape").as_os_str().as_bytes())
+        .expect("temporary paths contain no NUL bytes");
+
+    let result = probe(
+        FEATURE,
+        OPERATION,
+        |cmd| {
+            let mut restrictions = AccessRestrictions::new(true, false);
+            restrictions.add_read_only_path("/");
+            restrictions.apply_with_enforcement(cmd, EnforcementLevel::Enforce)
+        },
+        move || {
+            // SAFETY: `target` is a valid C string owned by the closure
+            let fd = unsafe {
+                libc::open(
+                    target.as_ptr(),
+                    libc::O_WRONLY | libc::O_CREAT | libc::O_CLOEXEC,
+                    0o600,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: `fd` was just opened by this process
+            unsafe { libc::close(fd) };
+            Ok(())
+        },
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// Connecting to a local TCP port that is not allowed
+pub(super) fn network() -> ProbeResult {
+    const FEATURE: &str = "network";
+    const OPERATION: &str = "connect to a port that is not allowed";
+
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            return ProbeResult::new(
+                FEATURE,
+                OPERATION,
+                ProbeStatus::Inconclusive,
+                format!("cannot listen on localhost: {e}"),
+            )
+        }
+    };
+    let port = listener.local_addr().map(|addr| addr.port()).unwrap_or(0);
+    let address = libc::sockaddr_in {
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: port.to_be(),
+        sin_addr: libc::in_addr {
+            s_addr: u32::from(Ipv4Addr::LOCALHOST).to_be(),
+        },
+        sin_zero: [0; 8],
+    };
+
+    probe(
+        FEATURE,
+        OPERATION,
+        |cmd| {
+            AccessRestrictions::new(false, true)
+                .apply_with_enforcement(cmd, EnforcementLevel::Enforce)
+        },
+        move || {
+            // SAFETY: plain socket syscalls on a local address and a socket
+            // this process owns
+            unsafe {
+                let fd = libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let connected = libc::connect(
+                    fd,
+                    (&address as *const libc::sockaddr_in).cast(),
+                    std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                );
+                let result = if connected == 0 {
+                    Ok(())
+                } else {
+                    Err(io::Error::last_os_error())
+                };
+                libc::close(fd);
+                result
+            }
+        },
+    )
+}
+
+/// Reading process memory, which the default seccomp profile blocks along
+/// with `ptrace`
+pub(super) fn seccomp() -> ProbeResult {
+    probe(
+        "seccomp",
+        "call a syscall the default profile blocks",
+        |cmd| {
+            SeccompFilter::load(DEFAULT_SECCOMP_PROFILE, SeccompMode::Enforce)?
+                .apply_to_command(cmd)
+        },
+        || {
+            let mut byte = 0_u8;
+            let local = libc::iovec {
+                iov_base: (&mut byte as *mut u8).cast(),
+                iov_len: 1,
+            };
+            let remote = libc::iovec {
+                iov_base: (&PROBE_BYTE as *const u8).cast_mut().cast(),
+                iov_len: 1,
+            };
+            // SAFETY: both buffers are valid for one byte in this process
+            let read = unsafe { libc::process_vm_readv(libc::getpid(), &local, 1, &remote, 1, 0) };
+            if read < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Release of the running kernel
+pub(super) fn kernel_release() -> Option<String> {
+    // SAFETY: `uname` fills the zeroed struct with NUL-terminated strings
+    unsafe {
+        let mut name: libc::utsname = std::mem::zeroed();
+        if libc::uname(&mut name) != 0 {
+            return None;
+        }
+        Some(
+            CStr::from_ptr(name.release.as_ptr())
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// Attempt `operation` without and then with the protection `protect`
+/// installs, and classify the outcome
+fn probe<F>(
+    feature: &'static str,
+    operation: &'static str,
+    protect: impl FnOnce(&mut Command) -> Result<()>,
+    attempt: F,
+) -> ProbeResult
+where
+    F: Fn() -> io::Result<()> + Clone + Send + Sync + 'static,
+{
+    let result = |status, detail: String| ProbeResult::new(feature, operation, status, detail);
+
+    if let Ok(Err(e)) = run_attempt(|_| Ok(()), attempt.clone()) {
+        return result(
+            ProbeStatus::Inconclusive,
+            format!("fails even without restrictions: {e}"),
+        );
+    }
+
+    match run_attempt(protect, attempt) {
+        Err(e) => result(ProbeStatus::Unsupported, e.to_string()),
+        Ok(Ok(())) => result(
+            ProbeStatus::Bypassed,
+            "the operation succeeded inside the sandbox".to_string(),
+        ),
+        Ok(Err(e)) if matches!(e.raw_os_error(), Some(libc::EACCES | libc::EPERM)) => {
+            result(ProbeStatus::Active, format!("blocked: {e}"))
+        }
+        Ok(Err(e)) => result(
+            ProbeStatus::Inconclusive,
+            format!("unexpected failure inside the sandbox: {e}"),
+        ),
+    }
+}
+
+/// Run `attempt` in a child process set up by `protect`
+///
+/// The outer error means the protection could not be installed; the inner
+/// one is the error the attempt failed with.
+fn run_attempt<F>(
+    protect: impl FnOnce(&mut Command) -> Result<()>,
+    attempt: F,
+) -> Result<io::Result<()>>
+where
+    F: Fn() -> io::Result<()> + Send + Sync + 'static,
+{
+    let mut cmd = Command::new("true");
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    protect(&mut cmd)?;
+    // SAFETY: attempts only make raw syscalls on data prepared before
+    // `fork`. Hooks run in order, so the restrictions are already in place.
+    unsafe {
+        cmd.pre_exec(attempt);
+    }
+    Ok(cmd.status().and_then(|status| {
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("probe exited with {status}")))
+        }
+    }))
+}
@@ -0,0 +1,161 @@
+//! Self-test of the sandbox protections available on this platform
+//!
+//! Each probe attempts a representative forbidden operation in a child
+//! process: once without restrictions, to show the operation works at all,
+//! and once under the protection it exercises. The operation runs in the
+//! child right before `exec`, after the restrictions are installed, so no
+//! external tools are involved. An operation that still succeeds under its
+//! protection means the sandbox can be escaped.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+use crate::enforcement::{effective_level, EnforcementLevel};
+use cuenv_core::Result;
+use serde::Serialize;
+use std::fmt;
+
+/// Outcome of a probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    /// The protection blocked the operation
+    Active,
+    /// The operation succeeded despite the protection
+    Bypassed,
+    /// The platform cannot apply the protection
+    Unsupported,
+    /// The operation failed even without the protection, or failed
+    /// unexpectedly under it
+    Inconclusive,
+}
+
+impl fmt::Display for ProbeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Active => "active",
+            Self::Bypassed => "BYPASSED",
+            Self::Unsupported => "unsupported",
+            Self::Inconclusive => "inconclusive",
+        })
+    }
+}
+
+/// Result of probing one protection
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    /// Protection probed
+    pub feature: &'static str,
+    /// Forbidden operation attempted
+    pub operation: &'static str,
+    pub status: ProbeStatus,
+    pub detail: String,
+}
+
+impl ProbeResult {
+    fn new(
+        feature: &'static str,
+        operation: &'static str,
+        status: ProbeStatus,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            feature,
+            operation,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Protections of this platform, as found by [`run`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    /// Operating system and architecture
+    pub platform: String,
+    /// Kernel release, where known
+    pub kernel: Option<String>,
+    /// Landlock ABI version of the running kernel
+    pub landlock_abi: Option<i64>,
+    /// Enforcement level tasks run with unless they configure their own
+    pub enforcement: EnforcementLevel,
+    pub probes: Vec<ProbeResult>,
+}
+
+impl SelfTestReport {
+    /// Probes whose forbidden operation got through
+    pub fn bypassed(&self) -> impl Iterator<Item = &ProbeResult> {
+        self.probes
+            .iter()
+            .filter(|probe| probe.status == ProbeStatus::Bypassed)
+    }
+}
+
+/// Probe every protection
+pub fn run() -> Result<SelfTestReport> {
+    Ok(SelfTestReport {
+        platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        kernel: kernel_release(),
+        landlock_abi: landlock_abi(),
+        enforcement: effective_level(EnforcementLevel::default())?,
+        probes: probes(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn probes() -> Vec<ProbeResult> {
+    vec![linux::filesystem(), linux::network(), linux::seccomp()]
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probes() -> Vec<ProbeResult> {
+    [
+        ("filesystem", "write outside the allowed paths"),
+        ("network", "connect to a port that is not allowed"),
+        ("seccomp", "call a syscall the default profile blocks"),
+    ]
+    .into_iter()
+    .map(|(feature, operation)| {
+        ProbeResult::new(
+            feature,
+            operation,
+            ProbeStatus::Unsupported,
+            "sandboxing is only available on Linux",
+        )
+    })
+    .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn kernel_release() -> Option<String> {
+    linux::kernel_release()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_release() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn landlock_abi() -> Option<i64> {
+    crate::AccessRestrictions::landlock_abi_version()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn landlock_abi() -> Option<i64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_protection_is_bypassed() {
+        let report = run().unwrap();
+        assert_eq!(report.probes.len(), 3);
+        let bypassed: Vec<_> = report.bypassed().collect();
+        assert!(bypassed.is_empty(), "sandbox escapes: {bypassed:?}");
+    }
+}
@@ -17,6 +17,74 @@ _cuenv_hook"#
             .to_string()
     }
 
+    fn async_hook(&self) -> Option<String> {
+        // As in zsh, evaluations never overlap, every result is applied and
+        // results only go to a directory the user owns
+        Some(
+            r#"function _cuenv_async_init
+  set -l dir
+  if set -q XDG_RUNTIME_DIR; and test -d "$XDG_RUNTIME_DIR"
+    set dir $XDG_RUNTIME_DIR/cuenv
+    command mkdir -p -m 700 -- $dir 2>/dev/null
+  else
+    set -l tmp /tmp
+    set -q TMPDIR; and test -n "$TMPDIR"; and set tmp $TMPDIR
+    set dir (command mktemp -d $tmp/cuenv-async-$fish_pid.XXXXXX); or return 1
+    set -g _cuenv_async_tmp $dir
+  end
+  test -d $dir; and not test -L $dir; and test -O $dir; or return 1
+  command chmod 700 -- $dir; or return 1
+  set -g _cuenv_async_file $dir/cuenv-async-$fish_pid.fish
+end
+
+function _cuenv_async_start
+  set -g _cuenv_async_dir $PWD
+  command rm -f -- $_cuenv_async_file
+  command cuenv shell hook fish --deferred $_cuenv_async_file </dev/null >/dev/null &
+  set -g _cuenv_async_pid $last_pid
+  disown $_cuenv_async_pid 2>/dev/null
+end
+
+function _cuenv_hook --on-variable PWD --description 'cuenv async hook'
+  set -q _cuenv_async_pid; or _cuenv_async_start
+end
+
+function _cuenv_async_apply --on-event fish_prompt --description 'Apply the environment loaded by cuenv'
+  set -l prev_status $status
+  set -q _cuenv_async_pid; or return $prev_status
+  if test -f $_cuenv_async_file; and test -O $_cuenv_async_file
+    source $_cuenv_async_file
+    command rm -f -- $_cuenv_async_file
+  else if kill -0 $_cuenv_async_pid 2>/dev/null
+    return $prev_status
+  end
+  set -e _cuenv_async_pid
+  test "$PWD" = "$_cuenv_async_dir"; or _cuenv_async_start
+  return $prev_status
+end
+
+function _cuenv_async_cleanup --on-event fish_exit
+  command rm -f -- $_cuenv_async_file
+  set -q _cuenv_async_tmp; and command rmdir -- $_cuenv_async_tmp 2>/dev/null
+end
+
+if _cuenv_async_init
+  # Load the initial directory
+  _cuenv_async_start
+else
+  echo "cuenv: no private directory for async hook output; loading synchronously" >&2
+  functions -e _cuenv_hook _cuenv_async_apply _cuenv_async_cleanup
+  function _cuenv_hook --on-variable PWD --description 'cuenv hook'
+    set -l prev_status $status
+    cuenv shell hook fish | source
+    return $prev_status
+  end
+  _cuenv_hook
+end"#
+                .to_string(),
+        )
+    }
+
     fn export(&self, key: &str, value: &str) -> String {
         format!("set -gx {key} {}", self.escape(value))
     }
@@ -110,4 +178,13 @@ mod tests {
         assert!(hook.contains("_cuenv_hook"));
         assert!(hook.contains("--on-variable PWD"));
     }
+
+    #[test]
+    fn test_fish_async_hook() {
+        let hook = FishShell.async_hook().unwrap();
+        assert!(hook.contains("cuenv shell hook fish --deferred"));
+        assert!(hook.contains("--on-event fish_prompt"));
+        assert!(hook.contains("test -O $_cuenv_async_file"));
+        assert!(!hook.contains("/tmp/cuenv-async-$fish_pid.fish"));
+    }
 }
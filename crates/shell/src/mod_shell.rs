@@ -19,6 +19,12 @@ pub enum ShellType {
 pub trait Shell {
     fn hook(&self) -> String;
 
+    /// Hook running `cuenv shell hook --deferred` in the background and
+    /// applying its output at a later prompt, if the shell has one
+    fn async_hook(&self) -> Option<String> {
+        None
+    }
+
     fn export(&self, key: &str, value: &str) -> String;
 
     fn unset(&self, key: &str) -> String;
@@ -1,5 +1,5 @@
 use crate::mod_shell::ShellType;
-use cuenv_core::Result;
+use cuenv_core::{Error, Result};
 
 pub struct ShellHook;
 
@@ -9,4 +9,15 @@ impl ShellHook {
         let shell_impl = shell_type.as_shell();
         Ok(shell_impl.hook())
     }
+
+    /// Hook loading the environment in the background, for shells that can
+    pub fn generate_async_hook(shell: &str) -> Result<String> {
+        let shell_type = ShellType::from_name(shell);
+        shell_type.as_shell().async_hook().ok_or_else(|| {
+            Error::configuration(format!(
+                "{} has no async hook; async hooks are available for zsh and fish",
+                shell_type.name()
+            ))
+        })
+    }
 }
@@ -16,6 +16,75 @@ fi"#
         .to_string()
     }
 
+    fn async_hook(&self) -> Option<String> {
+        // One evaluation runs at a time, each starting from the environment
+        // the previous one left, and every result is applied; the next
+        // evaluation then catches up with the directory. Results go to a
+        // directory only the user can write, or the hook loads synchronously.
+        Some(
+            r#"typeset -g _cuenv_async_fd= _cuenv_async_dir= _cuenv_async_file= _cuenv_async_tmp=
+
+_cuenv_async_init() {
+  local dir
+  if [[ -n $XDG_RUNTIME_DIR && -d $XDG_RUNTIME_DIR ]]; then
+    dir=$XDG_RUNTIME_DIR/cuenv
+    command mkdir -p -m 700 -- "$dir" 2>/dev/null
+  else
+    dir=$(command mktemp -d "${TMPDIR:-/tmp}/cuenv-async-$$.XXXXXX") || return 1
+    _cuenv_async_tmp=$dir
+  fi
+  [[ -d $dir && ! -L $dir && -O $dir ]] || return 1
+  command chmod 700 -- "$dir" || return 1
+  _cuenv_async_file=$dir/cuenv-async-$$.zsh
+}
+
+_cuenv_async_start() {
+  _cuenv_async_dir=$PWD
+  command rm -f -- "$_cuenv_async_file"
+  # Only the final print makes the descriptor readable
+  exec {_cuenv_async_fd}< <(cuenv shell hook zsh --deferred "$_cuenv_async_file" </dev/null >/dev/null; print)
+  zle -F "$_cuenv_async_fd" _cuenv_async_ready
+}
+
+_cuenv_async_ready() {
+  local fd=$1
+  zle -F "$fd"
+  exec {fd}<&-
+  _cuenv_async_fd=
+  if [[ -f $_cuenv_async_file && -O $_cuenv_async_file ]]; then
+    zle -I
+    source "$_cuenv_async_file"
+    command rm -f -- "$_cuenv_async_file"
+  fi
+  [[ $PWD == "$_cuenv_async_dir" ]] || _cuenv_async_start
+}
+
+_cuenv_hook() {
+  if [[ -z $_cuenv_async_file ]]; then
+    trap -- '' SIGINT
+    eval "$(cuenv hook zsh)"
+    trap - SIGINT
+    return
+  fi
+  [[ -n $_cuenv_async_fd ]] || _cuenv_async_start
+}
+
+_cuenv_async_cleanup() {
+  [[ -n $_cuenv_async_file ]] && command rm -f -- "$_cuenv_async_file"
+  [[ -n $_cuenv_async_tmp ]] && command rmdir -- "$_cuenv_async_tmp" 2>/dev/null
+}
+
+_cuenv_async_init || print -u2 "cuenv: no private directory for async hook output; loading synchronously"
+
+typeset -ag precmd_functions zshexit_functions
+if [[ ${precmd_functions[(ie)_cuenv_hook]} -gt ${#precmd_functions} ]]; then
+  precmd_functions+=(_cuenv_hook)
+fi
+zshexit_functions+=(_cuenv_async_cleanup)"#
+                .to_string(),
+        )
+    }
+
     fn export(&self, key: &str, value: &str) -> String {
         format!("export {}={}", key, self.escape(value))
     }
@@ -53,4 +122,16 @@ mod tests {
         assert!(hook.contains("_cuenv_hook"));
         assert!(hook.contains("precmd_functions"));
     }
+
+    #[test]
+    fn test_zsh_async_hook() {
+        let hook = ZshShell.async_hook().unwrap();
+        assert!(hook.contains("cuenv shell hook zsh --deferred"));
+        assert!(hook.contains("zle -F"));
+        assert!(hook.contains("precmd_functions+=(_cuenv_hook)"));
+        // Output is only read from a private directory the user owns
+        assert!(!hook.contains("${TMPDIR:-/tmp}/cuenv-async-$$.zsh"));
+        assert!(hook.contains("-O $_cuenv_async_file"));
+        assert!(hook.contains("chmod 700"));
+    }
 }
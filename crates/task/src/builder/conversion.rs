@@ -3,11 +3,15 @@
 //! This module handles the conversion from TaskConfig (configuration format)
 //! to TaskDefinition (runtime format) with proper validation and defaults.
 
-use cuenv_config::TaskConfig;
+use crate::script::Shebang;
+use cuenv_config::{CacheEnvConfig, ProblemMatcherConfig, PublishTargetConfig, TaskConfig};
 use cuenv_core::{
-    Error, ResolvedDependency, Result, TaskCache, TaskDefinition, TaskExecutionMode, TaskSecurity,
-    DEFAULT_TASK_TIMEOUT_SECS,
+    CacheEnvFilter, Error, ResolvedDependency, Result, Severity, TaskCache, TaskDefinition,
+    TaskExecutionMode, TaskOutputValue, TaskProblemMatcher, TaskProcess, TaskPublish,
+    TaskPublishTarget, TaskRunAs, TaskSecurity, TaskWait, TaskWaitTarget,
+    DEFAULT_TASK_TIMEOUT_SECS, DEFAULT_WAIT_TIMEOUT_SECS,
 };
+use cuenv_utils::duration::parse_duration;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -23,7 +27,19 @@ pub fn config_to_definition(config: TaskConfig) -> Result<TaskDefinition> {
     let security = convert_security_config(&config);
 
     // Convert cache config
-    let cache = convert_cache_config(&config);
+    let cache = convert_cache_config(&config)?;
+
+    let publish = convert_publish_config(&config)?;
+
+    let problem_matchers = convert_problem_matchers(&config)?;
+
+    let output_values = convert_output_values(&config);
+
+    let process = convert_process_settings(&config)?;
+
+    let wait_for = convert_wait_for(&config)?;
+
+    let run_as = convert_run_as(&config);
 
     // Build the final task definition
     let definition = TaskDefinition {
@@ -34,13 +50,23 @@ pub fn config_to_definition(config: TaskConfig) -> Result<TaskDefinition> {
         working_directory: PathBuf::from(config.working_dir.unwrap_or_else(|| ".".to_string())),
         shell: config.shell.unwrap_or_else(|| "sh".to_string()),
         inputs: config.inputs.unwrap_or_default(),
-        outputs: config.outputs.unwrap_or_default(),
+        outputs: config
+            .outputs
+            .as_ref()
+            .map(|outputs| outputs.files())
+            .unwrap_or_default(),
         security,
         cache,
         timeout: config
             .timeout
             .map(|t| Duration::from_secs(t as u64))
             .unwrap_or_else(|| Duration::from_secs(DEFAULT_TASK_TIMEOUT_SECS)),
+        run_as,
+        process,
+        wait_for,
+        publish,
+        problem_matchers,
+        output_values,
     };
 
     Ok(definition)
@@ -52,9 +78,13 @@ fn create_execution_mode(config: &TaskConfig) -> Result<TaskExecutionMode> {
         (Some(command), None) => Ok(TaskExecutionMode::Command {
             command: command.clone(),
         }),
-        (None, Some(script)) => Ok(TaskExecutionMode::Script {
-            content: script.clone(),
-        }),
+        (None, Some(script)) => {
+            // Unsupported interpreters are reported before anything runs
+            Shebang::parse(script)?;
+            Ok(TaskExecutionMode::Script {
+                content: script.clone(),
+            })
+        }
         (Some(_), Some(_)) => Err(Error::configuration(
             "Task cannot have both command and script".to_string(),
         )),
@@ -89,19 +119,179 @@ fn convert_security_config(config: &TaskConfig) -> Option<TaskSecurity> {
             .collect(),
         write_only_paths: Vec::new(), // TODO: Add when TaskConfig supports it
         allowed_hosts: sec.allowed_hosts.as_ref().unwrap_or(&Vec::new()).clone(),
+        seccomp_profile: sec.seccomp_profile.clone(),
+        seccomp_audit: sec.seccomp_audit.unwrap_or(false),
+        enforcement: sec.enforcement.unwrap_or_default(),
     })
 }
 
 /// Convert cache configuration to TaskCache
-fn convert_cache_config(config: &TaskConfig) -> TaskCache {
-    match &config.cache {
-        Some(_cache_config) => TaskCache {
-            enabled: true, // If cache config is present, enable it
+pub fn convert_cache_config(config: &TaskConfig) -> Result<TaskCache> {
+    let env_filter = convert_cache_env_config(config)?;
+    let failure_ttl = config
+        .cache_failures
+        .as_deref()
+        .map(parse_duration)
+        .transpose()?;
+    Ok(match &config.cache {
+        Some(cache_config) if cache_config.enabled() => TaskCache {
+            enabled: true,
             key: config.cache_key.clone(),
-            env_filter: None, // TODO: Convert from cache_config if needed
+            env_filter,
+            failure_ttl,
         },
-        None => TaskCache::default(),
-    }
+        _ if failure_ttl.is_some() => {
+            return Err(Error::configuration(
+                "cacheFailures needs the task to be cached; add cache: true",
+            ))
+        }
+        _ => TaskCache {
+            env_filter,
+            ..TaskCache::default()
+        },
+    })
+}
+
+/// Environment filter from `cache.env`, falling back to the deprecated `cache_env`
+fn convert_cache_env_config(config: &TaskConfig) -> Result<Option<CacheEnvFilter>> {
+    let env_config = match config.cache.as_ref().and_then(|cache| cache.env_filter()) {
+        Some(value) => Some(
+            serde_json::from_value::<CacheEnvConfig>(value.clone())
+                .map_err(|e| Error::configuration(format!("Invalid cache.env: {e}")))?,
+        ),
+        None => config.cache_env.clone(),
+    };
+
+    Ok(env_config.map(|env| CacheEnvFilter {
+        include: env.include.unwrap_or_default(),
+        exclude: env.exclude.unwrap_or_default(),
+        smart_defaults: env.use_smart_defaults.unwrap_or(true),
+        env_all: env.env_all.unwrap_or(false),
+    }))
+}
+
+/// Convert `problemMatcher` entries, checking that they compile
+pub fn convert_problem_matchers(config: &TaskConfig) -> Result<Vec<TaskProblemMatcher>> {
+    let matchers: Vec<_> = config
+        .problem_matcher
+        .iter()
+        .flatten()
+        .map(|matcher| match matcher {
+            ProblemMatcherConfig::Builtin(name) => TaskProblemMatcher::Builtin(name.clone()),
+            ProblemMatcherConfig::Pattern { pattern, severity } => TaskProblemMatcher::Pattern {
+                pattern: pattern.clone(),
+                severity: severity.unwrap_or(Severity::Error),
+            },
+        })
+        .collect();
+    crate::problem_matcher::validate_problem_matchers(&matchers)?;
+    Ok(matchers)
+}
+
+/// Convert the named values of `outputs`
+pub fn convert_output_values(config: &TaskConfig) -> Vec<TaskOutputValue> {
+    config
+        .outputs
+        .iter()
+        .flat_map(|outputs| outputs.values())
+        .map(|(name, value)| TaskOutputValue {
+            name: name.clone(),
+            from_file: value.from_file.clone(),
+            value_type: value.value_type,
+        })
+        .collect()
+}
+
+/// Convert `runAs`
+pub fn convert_run_as(config: &TaskConfig) -> Option<TaskRunAs> {
+    config.run_as.as_ref().map(|run_as| TaskRunAs {
+        user: run_as.user.clone(),
+        group: run_as.group.clone(),
+    })
+}
+
+/// Convert `umask`, `nice` and `ioPriority`
+pub fn convert_process_settings(config: &TaskConfig) -> Result<TaskProcess> {
+    Ok(TaskProcess {
+        umask: config.umask.as_deref().map(parse_umask).transpose()?,
+        nice: config.nice,
+        io_priority: config.io_priority,
+    })
+}
+
+/// Parse an octal umask such as `"027"` or `"0o027"`
+pub fn parse_umask(umask: &str) -> Result<u32> {
+    let digits = umask.strip_prefix("0o").unwrap_or(umask);
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mask| *mask <= 0o777)
+        .ok_or_else(|| {
+            Error::configuration(format!(
+                "Invalid umask '{umask}'. Must be an octal mode from 000 to 777"
+            ))
+        })
+}
+
+/// Convert `waitFor` entries, defaulting their timeout
+pub fn convert_wait_for(config: &TaskConfig) -> Result<Vec<TaskWait>> {
+    config
+        .wait_for
+        .iter()
+        .flatten()
+        .map(|wait| {
+            let target = match (&wait.tcp, &wait.http) {
+                (Some(address), None) => TaskWaitTarget::Tcp(address.clone()),
+                (None, Some(url)) => TaskWaitTarget::Http(url.clone()),
+                _ => {
+                    return Err(Error::configuration(
+                        "waitFor entries need exactly one of 'tcp' and 'http'",
+                    ))
+                }
+            };
+            let timeout = match &wait.timeout {
+                Some(timeout) => parse_duration(timeout)?,
+                None => Duration::from_secs(DEFAULT_WAIT_TIMEOUT_SECS),
+            };
+            Ok(TaskWait { target, timeout })
+        })
+        .collect()
+}
+
+/// Convert `publish` entries, checking HTTP methods up front
+pub fn convert_publish_config(config: &TaskConfig) -> Result<Vec<TaskPublish>> {
+    config
+        .publish
+        .iter()
+        .flatten()
+        .map(|entry| {
+            let target = match &entry.target {
+                PublishTargetConfig::Dir(dir) => TaskPublishTarget::Directory(PathBuf::from(dir)),
+                PublishTargetConfig::Oci(oci) => TaskPublishTarget::Oci {
+                    reference: oci.reference.clone(),
+                    media_type: oci.media_type.clone(),
+                },
+                PublishTargetConfig::Http(http) => {
+                    let method = http.method.as_deref().unwrap_or("PUT").to_uppercase();
+                    if !matches!(method.as_str(), "PUT" | "POST") {
+                        return Err(Error::configuration(format!(
+                            "Unsupported publish method '{method}' for {}. Must be PUT or POST",
+                            http.url
+                        )));
+                    }
+                    TaskPublishTarget::Http {
+                        url: http.url.clone(),
+                        method,
+                        headers: http.headers.clone(),
+                        token_env: http.token_env.clone(),
+                    }
+                }
+            };
+            Ok(TaskPublish {
+                files: entry.files.clone().unwrap_or_default(),
+                target,
+            })
+        })
+        .collect()
 }
 
 /// Validate that the conversion produces a valid task definition
@@ -154,6 +344,25 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: Some(30),
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         }
     }
 
@@ -185,6 +394,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_script_with_unsupported_interpreter() {
+        let mut config = create_basic_task_config();
+        config.command = None;
+        config.script = Some("#!/usr/bin/env php\necho 'script';".to_string());
+
+        assert!(config_to_definition(config).is_err());
+    }
+
     #[test]
     fn test_both_command_and_script_error() {
         let mut config = create_basic_task_config();
@@ -228,6 +446,9 @@ mod tests {
             deny_paths: None,
             allowed_hosts: Some(vec!["example.com".to_string()]),
             infer_from_inputs_outputs: None,
+            seccomp_profile: None,
+            seccomp_audit: None,
+            enforcement: None,
         });
 
         let definition = config_to_definition(config).unwrap();
@@ -251,6 +472,36 @@ mod tests {
         assert_eq!(definition.cache.key, Some("custom-key".to_string()));
     }
 
+    #[test]
+    fn test_cache_failures_conversion() {
+        let mut config = create_basic_task_config();
+        config.cache_failures = Some("10m".to_string());
+        assert!(config_to_definition(config.clone()).is_err());
+
+        config.cache = Some(TaskCacheConfig::Simple(true));
+        let definition = config_to_definition(config).unwrap();
+        assert_eq!(definition.cache.failure_ttl, Some(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn test_cache_env_conversion() {
+        let mut config = create_basic_task_config();
+        config.cache = Some(TaskCacheConfig::Advanced {
+            enabled: true,
+            env: Some(serde_json::json!({ "include": ["API_*"], "envAll": true })),
+        });
+
+        let filter = config_to_definition(config)
+            .unwrap()
+            .cache
+            .env_filter
+            .unwrap();
+
+        assert_eq!(filter.include, vec!["API_*"]);
+        assert!(filter.smart_defaults);
+        assert!(filter.env_all);
+    }
+
     #[test]
     fn test_default_values() {
         let config = TaskConfig {
@@ -267,6 +518,25 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: None,
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         };
 
         let definition = config_to_definition(config).unwrap();
@@ -321,4 +591,53 @@ mod tests {
         let definition = config_to_definition(config).unwrap();
         assert_eq!(definition.timeout, Duration::from_secs(120));
     }
+
+    #[test]
+    fn test_publish_entries() {
+        let mut config = create_basic_task_config();
+        config.publish = serde_json::from_value(serde_json::json!([
+            {"dir": "dist"},
+            {"files": ["app.tar"], "oci": {"ref": "ghcr.io/acme/app:1.0"}},
+            {"http": {"url": "https://example.com/up", "tokenEnv": "TOKEN"}}
+        ]))
+        .unwrap();
+
+        let publish = config_to_definition(config.clone()).unwrap().publish;
+        assert_eq!(
+            publish[0].target,
+            TaskPublishTarget::Directory(PathBuf::from("dist"))
+        );
+        assert_eq!(publish[1].files, ["app.tar"]);
+        assert!(matches!(
+            &publish[2].target,
+            TaskPublishTarget::Http { method, token_env: Some(env), .. }
+                if method == "PUT" && env == "TOKEN"
+        ));
+
+        config.publish = serde_json::from_value(serde_json::json!([
+            {"http": {"url": "https://example.com/up", "method": "DELETE"}}
+        ]))
+        .unwrap();
+        assert!(config_to_definition(config).is_err());
+    }
+
+    #[test]
+    fn test_process_settings_conversion() {
+        let mut config = create_basic_task_config();
+        config.umask = Some("0o027".to_string());
+        config.nice = Some(10);
+        config.io_priority = serde_json::from_value(serde_json::json!({"class": "idle"})).unwrap();
+
+        let process = config_to_definition(config.clone()).unwrap().process;
+        assert_eq!(process.umask, Some(0o027));
+        assert_eq!(process.nice, Some(10));
+        assert_eq!(
+            process.io_priority.unwrap().class,
+            cuenv_core::IoClass::Idle
+        );
+
+        assert_eq!(parse_umask("022").unwrap(), 0o022);
+        config.umask = Some("u=rwx".to_string());
+        assert!(config_to_definition(config).is_err());
+    }
 }
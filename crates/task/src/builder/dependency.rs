@@ -295,6 +295,25 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: Some(30),
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         }
     }
 
@@ -313,6 +332,12 @@ mod tests {
             security: None,
             cache: cuenv_core::TaskCache::default(),
             timeout: std::time::Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         }
     }
 
@@ -514,6 +539,8 @@ mod tests {
             "ci".to_string(),
             TaskNode::Group {
                 description: Some("CI tasks".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(ci_tasks),
             },
         );
@@ -585,6 +612,8 @@ mod tests {
             "quality".to_string(),
             TaskNode::Group {
                 description: Some("Quality checks".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(quality_tasks),
             },
         );
@@ -593,6 +622,8 @@ mod tests {
             "release".to_string(),
             TaskNode::Group {
                 description: Some("Release process".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(release_tasks),
             },
         );
@@ -637,6 +668,8 @@ mod tests {
             "empty_group".to_string(),
             TaskNode::Group {
                 description: Some("Empty group".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(IndexMap::new()), // Empty!
             },
         );
@@ -670,6 +703,8 @@ mod tests {
             "nested".to_string(),
             TaskNode::Group {
                 description: Some("Nested group".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel({
                     let mut inner = IndexMap::new();
                     inner.insert(
@@ -685,6 +720,8 @@ mod tests {
             "circular".to_string(),
             TaskNode::Group {
                 description: Some("Circular group".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(nested_tasks),
             },
         );
@@ -751,6 +788,8 @@ mod tests {
                 "level3".to_string(),
                 TaskNode::Group {
                     description: Some("Level 3".to_string()),
+                    setup: None,
+                    teardown: None,
                     tasks: TaskCollection::Parallel(level3_tasks),
                 },
             );
@@ -763,6 +802,8 @@ mod tests {
                 "level2".to_string(),
                 TaskNode::Group {
                     description: Some("Level 2".to_string()),
+                    setup: None,
+                    teardown: None,
                     tasks: TaskCollection::Parallel(level2_tasks),
                 },
             );
@@ -775,6 +816,8 @@ mod tests {
                 "level1".to_string(),
                 TaskNode::Group {
                     description: Some("Level 1".to_string()),
+                    setup: None,
+                    teardown: None,
                     tasks: TaskCollection::Parallel(level1_tasks),
                 },
             );
@@ -785,6 +828,8 @@ mod tests {
             "deep".to_string(),
             TaskNode::Group {
                 description: Some("Deep nesting test".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(deep_tasks),
             },
         );
@@ -855,6 +900,8 @@ mod tests {
             "test".to_string(),
             TaskNode::Group {
                 description: Some("Test tasks".to_string()),
+                setup: None,
+                teardown: None,
                 tasks: TaskCollection::Parallel(test_tasks),
             },
         );
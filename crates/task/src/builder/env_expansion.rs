@@ -1,27 +1,37 @@
 //! Environment variable expansion for task building
 //!
 //! This module handles expansion of environment variables in task commands and scripts
-//! using the ${VAR} syntax pattern.
+//! using the ${VAR} syntax pattern. Undefined variables expand to an empty string,
+//! unless strict mode (`config: strictVariables: true`) makes them an error.
 
-use cuenv_core::{Result, TaskExecutionMode};
+use cuenv_config::TaskConfig;
+use cuenv_core::{Error, Result, TaskExecutionMode};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use super::outputs::parse_reference;
 use super::BuildContext;
 
-/// Expand environment variables in task execution content and working directories
+/// Expand environment variables in task execution content
+///
+/// With `strict`, a reference to an undefined variable is an error unless
+/// the task sets `allowUndefined`.
 pub fn expand_environment_variables(
     context: &mut BuildContext,
     global_env: &HashMap<String, String>,
+    strict: bool,
 ) -> Result<()> {
-    for definition in context.task_definitions.values_mut() {
+    for (name, definition) in context.task_definitions.iter_mut() {
+        let strict = strict && !allows_undefined(&context.task_configs, name);
+
         // Expand environment variables in execution content
         match &mut definition.execution_mode {
             TaskExecutionMode::Command { command } => {
-                *command = expand_env_vars(command, global_env)?;
+                *command = expand_field(command, global_env, strict, name, "command")?;
             }
             TaskExecutionMode::Script { content } => {
-                *content = expand_env_vars(content, global_env)?;
+                *content = expand_field(content, global_env, strict, name, "script")?;
             }
         }
     }
@@ -34,11 +44,14 @@ pub fn resolve_working_directories(
     context: &mut BuildContext,
     workspace_root: &Path,
     global_env: &HashMap<String, String>,
+    strict: bool,
 ) -> Result<()> {
-    for definition in context.task_definitions.values_mut() {
+    for (name, definition) in context.task_definitions.iter_mut() {
+        let strict = strict && !allows_undefined(&context.task_configs, name);
+
         // First expand any environment variables in the working directory path
         let working_dir_str = definition.working_directory.to_string_lossy();
-        let expanded_path = expand_env_vars(&working_dir_str, global_env)?;
+        let expanded_path = expand_field(&working_dir_str, global_env, strict, name, "workingDir")?;
         definition.working_directory = PathBuf::from(expanded_path);
 
         // If working_directory is relative, make it relative to workspace_root
@@ -61,29 +74,90 @@ pub fn resolve_working_directories(
 }
 
 /// Expand environment variables in a string using ${VAR} syntax
+///
+/// Undefined variables expand to an empty string.
 pub fn expand_env_vars(input: &str, env_vars: &HashMap<String, String>) -> Result<String> {
-    let mut result = input.to_string();
-    let mut start = 0;
+    let mut result = String::with_capacity(input.len());
+    let mut last = 0;
+
+    for (range, name) in variable_references(input) {
+        result.push_str(&input[last..range.start]);
+        result.push_str(env_vars.get(name).map_or("", String::as_str));
+        last = range.end;
+    }
+    result.push_str(&input[last..]);
+
+    Ok(result)
+}
 
-    while let Some(pos) = result[start..].find("${") {
-        let abs_pos = start + pos;
+/// A `${VAR}` reference to a variable that is not defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndefinedVariable {
+    pub name: String,
+    /// 1-based line of the reference
+    pub line: usize,
+    /// 1-based column, in characters, of the `$`
+    pub column: usize,
+}
 
-        if let Some(end_pos) = result[abs_pos + 2..].find('}') {
-            let var_name = &result[abs_pos + 2..abs_pos + 2 + end_pos];
+/// The first reference in `input` to a variable missing from `env_vars`
+pub fn find_undefined_variable(
+    input: &str,
+    env_vars: &HashMap<String, String>,
+) -> Option<UndefinedVariable> {
+    let (range, name) =
+        variable_references(input).find(|(_, name)| !env_vars.contains_key(*name))?;
+    let before = &input[..range.start];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+    Some(UndefinedVariable {
+        name: name.to_string(),
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    })
+}
 
-            // Look up the variable in the environment
-            let var_value = env_vars.get(var_name).map(|s| s.as_str()).unwrap_or("");
+/// `${VAR}` references in `input`, except those to task outputs
+fn variable_references(input: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    references(input).filter(|(_, name)| parse_reference(name).is_none())
+}
 
-            // Replace ${VAR} with the value
-            result.replace_range(abs_pos..abs_pos + 3 + end_pos, var_value);
-            start = abs_pos + var_value.len();
-        } else {
-            // No closing brace found, skip this occurrence
-            start = abs_pos + 2;
+/// `${...}` references in `input`: the byte range of each and the name inside
+pub(super) fn references(input: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let open = start + input[start..].find("${")?;
+        let close = open + 2 + input[open + 2..].find('}')?;
+        start = close + 1;
+        Some((open..close + 1, &input[open + 2..close]))
+    })
+}
+
+fn allows_undefined(task_configs: &HashMap<String, TaskConfig>, name: &str) -> bool {
+    task_configs
+        .get(name)
+        .and_then(|config| config.allow_undefined)
+        .unwrap_or(false)
+}
+
+/// Expand one field of a task, rejecting undefined variables when `strict`
+fn expand_field(
+    input: &str,
+    env_vars: &HashMap<String, String>,
+    strict: bool,
+    task: &str,
+    field: &str,
+) -> Result<String> {
+    if strict {
+        if let Some(undefined) = find_undefined_variable(input, env_vars) {
+            return Err(Error::configuration(format!(
+                "Task '{task}' references undefined variable '{}' in its {field} at line {}, column {}. \
+                 Define the variable, or set `allowUndefined: true` on the task",
+                undefined.name, undefined.line, undefined.column
+            )));
         }
     }
-
-    Ok(result)
+    expand_env_vars(input, env_vars)
 }
 
 #[cfg(test)]
@@ -107,6 +181,12 @@ mod tests {
             security: None,
             cache: cuenv_core::TaskCache::default(),
             timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         }
     }
 
@@ -136,6 +216,16 @@ mod tests {
         assert_eq!(result, "echo value and ${UNCLOSED");
     }
 
+    #[test]
+    fn test_expand_env_vars_leaves_output_references() {
+        let env = HashMap::from([("TAG".to_string(), "latest".to_string())]);
+        let command = "deploy ${tasks.build.outputs.version} ${TAG}";
+
+        let result = expand_env_vars(command, &env).unwrap();
+        assert_eq!(result, "deploy ${tasks.build.outputs.version} latest");
+        assert_eq!(find_undefined_variable(command, &env), None);
+    }
+
     #[test]
     fn test_expand_env_vars_nested() {
         let mut env = HashMap::new();
@@ -163,7 +253,7 @@ mod tests {
         let mut env = HashMap::new();
         env.insert("TEST_VAR".to_string(), "expanded".to_string());
 
-        let result = expand_environment_variables(&mut context, &env);
+        let result = expand_environment_variables(&mut context, &env, false);
         assert!(result.is_ok());
 
         let definition = &context.task_definitions["test"];
@@ -190,7 +280,7 @@ mod tests {
         let mut env = HashMap::new();
         env.insert("TEST_VAR".to_string(), "script_value".to_string());
 
-        let result = expand_environment_variables(&mut context, &env);
+        let result = expand_environment_variables(&mut context, &env, false);
         assert!(result.is_ok());
 
         let definition = &context.task_definitions["test"];
@@ -234,7 +324,7 @@ mod tests {
         let sub_dir = temp_dir.path().join("subdir");
         std::fs::create_dir(&sub_dir).unwrap();
 
-        let result = resolve_working_directories(&mut context, &workspace_root, &env);
+        let result = resolve_working_directories(&mut context, &workspace_root, &env, false);
         assert!(result.is_ok());
 
         let definition = &context.task_definitions["test"];
@@ -243,4 +333,54 @@ mod tests {
             sub_dir.canonicalize().unwrap()
         );
     }
+
+    #[test]
+    fn test_find_undefined_variable_position() {
+        let env = HashMap::from([("DEFINED".to_string(), "x".to_string())]);
+        let script = "echo ${DEFINED}\n  deploy ${MISSING}";
+
+        let undefined = find_undefined_variable(script, &env).unwrap();
+        assert_eq!(
+            undefined,
+            UndefinedVariable {
+                name: "MISSING".to_string(),
+                line: 2,
+                column: 10,
+            }
+        );
+        assert_eq!(find_undefined_variable("echo ${DEFINED}", &env), None);
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_undefined_unless_allowed() {
+        let mut context = BuildContext {
+            task_configs: HashMap::new(),
+            task_nodes: indexmap::IndexMap::new(),
+            task_definitions: HashMap::new(),
+            dependency_graph: HashMap::new(),
+        };
+        context.task_definitions.insert(
+            "deploy".to_string(),
+            create_test_definition("deploy", "deploy ${TARGET}", "."),
+        );
+
+        let error = expand_environment_variables(&mut context, &HashMap::new(), true)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Task 'deploy' references undefined variable 'TARGET'"));
+        assert!(error.contains("command at line 1, column 8"));
+
+        context.task_configs.insert(
+            "deploy".to_string(),
+            TaskConfig {
+                allow_undefined: Some(true),
+                ..Default::default()
+            },
+        );
+        expand_environment_variables(&mut context, &HashMap::new(), true).unwrap();
+        assert_eq!(
+            context.task_definitions["deploy"].get_execution_content(),
+            "deploy "
+        );
+    }
 }
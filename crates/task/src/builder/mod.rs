@@ -6,6 +6,7 @@
 
 use cuenv_config::{TaskConfig, TaskNode};
 use cuenv_core::{Result, TaskDefinition};
+use cuenv_security::SecurityPolicy;
 use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::env;
@@ -15,6 +16,7 @@ use std::path::{Path, PathBuf};
 pub mod conversion;
 pub mod dependency;
 pub mod env_expansion;
+pub mod outputs;
 pub mod security;
 pub mod validation;
 
@@ -43,6 +45,8 @@ pub struct TaskBuilder {
     global_env: HashMap<String, String>,
     /// Cached dependency validation results
     dependency_cache: DependencyValidationCache,
+    /// Fail on `${VAR}` references to undefined variables
+    strict_variables: bool,
 }
 
 impl TaskBuilder {
@@ -58,9 +62,30 @@ impl TaskBuilder {
             workspace_root,
             global_env,
             dependency_cache: create_dependency_cache(),
+            strict_variables: false,
         }
     }
 
+    /// Reject `${VAR}` references to undefined variables instead of
+    /// expanding them to an empty string
+    pub fn with_strict_variables(mut self, strict: bool) -> Self {
+        self.strict_variables = strict;
+        self
+    }
+
+    /// Leave `${VAR}` references to these variables for the task's shell
+    ///
+    /// Lazy secrets are only resolved when the task runs, so there is no
+    /// value to expand at build time.
+    pub fn with_deferred_variables<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.global_env.extend(
+            names
+                .into_iter()
+                .map(|name| (name.to_string(), format!("${{{name}}}"))),
+        );
+        self
+    }
+
     /// Build task definitions from configurations
     pub fn build_tasks(
         &self,
@@ -103,18 +128,30 @@ impl TaskBuilder {
         dependency::validate_dependencies(&context, &self.dependency_cache)?;
 
         // Step 5: Expand environment variables
-        env_expansion::expand_environment_variables(&mut context, &self.global_env)?;
+        env_expansion::expand_environment_variables(
+            &mut context,
+            &self.global_env,
+            self.strict_variables,
+        )?;
+
+        // Step 6: Check references to other tasks' outputs
+        outputs::validate_output_references(&context)?;
 
-        // Step 6: Resolve working directories
+        // Step 7: Resolve working directories
         env_expansion::resolve_working_directories(
             &mut context,
             &self.workspace_root,
             &self.global_env,
+            self.strict_variables,
         )?;
 
-        // Step 7: Validate security configurations
+        // Step 8: Validate security configurations
         security::validate_security_configs(&mut context, &self.workspace_root)?;
 
+        // Step 9: Enforce the security policies of the system and workspace
+        let policy = SecurityPolicy::load(&self.workspace_root)?;
+        security::enforce_policy(&mut context, &policy)?;
+
         Ok(context.task_definitions)
     }
 
@@ -134,6 +171,7 @@ mod tests {
     use super::*;
     use cuenv_config::{SecurityConfig, TaskCacheConfig};
     use std::fs;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     fn create_test_config(command: &str) -> TaskConfig {
@@ -151,6 +189,25 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: Some(30),
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         }
     }
 
@@ -258,6 +315,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deferred_variables_are_left_for_the_shell() {
+        let temp_dir = TempDir::new().unwrap();
+        let builder = TaskBuilder::new_with_env(temp_dir.path().to_path_buf(), HashMap::new())
+            .with_strict_variables(true)
+            .with_deferred_variables(["DEPLOY_TOKEN"]);
+
+        let mut configs = HashMap::new();
+        configs.insert(
+            "deploy".to_string(),
+            create_test_config("deploy --token ${DEPLOY_TOKEN}"),
+        );
+
+        let definitions = builder.build_tasks(configs).unwrap();
+
+        assert_eq!(
+            definitions["deploy"].get_execution_content(),
+            "deploy --token ${DEPLOY_TOKEN}"
+        );
+    }
+
     #[test]
     fn test_working_directory_resolution() {
         let temp_dir = TempDir::new().unwrap();
@@ -296,6 +374,9 @@ mod tests {
             deny_paths: None,
             allowed_hosts: Some(vec!["example.com".to_string()]),
             infer_from_inputs_outputs: None,
+            seccomp_profile: None,
+            seccomp_audit: None,
+            enforcement: None,
         });
 
         configs.insert("test".to_string(), config);
@@ -326,6 +407,37 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("not allowed"));
     }
 
+    #[test]
+    fn test_security_policy_enforced() {
+        let temp_dir = TempDir::new().unwrap();
+        let policy_dir = temp_dir.path().join(".cuenv");
+        fs::create_dir(&policy_dir).unwrap();
+        fs::write(
+            policy_dir.join("policy.json"),
+            r#"{"bannedShells": ["bash"], "maxTimeout": "10m"}"#,
+        )
+        .unwrap();
+        let builder = TaskBuilder::new_with_env(temp_dir.path().to_path_buf(), HashMap::new());
+
+        let mut default_timeout = create_test_config("echo hello");
+        default_timeout.timeout = None;
+        let configs = HashMap::from([("build".to_string(), default_timeout.clone())]);
+        let definitions = builder.build_tasks(configs).unwrap();
+        assert_eq!(definitions["build"].timeout, Duration::from_secs(600));
+
+        let mut banned = create_test_config("echo hello");
+        banned.shell = Some("bash".to_string());
+        banned.timeout = Some(3600);
+        let configs = HashMap::from([
+            ("build".to_string(), default_timeout),
+            ("deploy".to_string(), banned),
+        ]);
+        let error = builder.build_tasks(configs).unwrap_err().to_string();
+        assert!(error.contains("task 'deploy' uses the banned shell 'bash'"));
+        assert!(error.contains("above the maximum of 600s"));
+        assert!(!error.contains("task 'build'"));
+    }
+
     #[test]
     fn test_invalid_command_script_combination() {
         let temp_dir = TempDir::new().unwrap();
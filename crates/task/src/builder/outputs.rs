@@ -0,0 +1,116 @@
+//! References to the values other tasks output
+//!
+//! A task declaring `outputs: { version: string @fromFile("VERSION") }`
+//! outputs `version`. Tasks depending on it, directly or through other
+//! tasks, reference the value in their command or script as
+//! `${tasks.build.outputs.version}`. References are checked when tasks are
+//! built and replaced by the executor once the value is known.
+
+use super::env_expansion::references;
+use super::BuildContext;
+use cuenv_core::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// A `${tasks.<task>.outputs.<value>}` reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputReference<'a> {
+    pub task: &'a str,
+    pub value: &'a str,
+}
+
+/// The reference named inside `${...}`, if it is one
+pub fn parse_reference(name: &str) -> Option<OutputReference<'_>> {
+    let (task, value) = name.strip_prefix("tasks.")?.rsplit_once(".outputs.")?;
+    (!task.is_empty() && !value.is_empty()).then_some(OutputReference { task, value })
+}
+
+/// Output references in `input`, with the byte range of each
+pub fn output_references(input: &str) -> impl Iterator<Item = (Range<usize>, OutputReference<'_>)> {
+    references(input).filter_map(|(range, name)| Some((range, parse_reference(name)?)))
+}
+
+/// Check that every task only references values declared by tasks it
+/// depends on
+pub fn validate_output_references(context: &BuildContext) -> Result<()> {
+    for (name, definition) in &context.task_definitions {
+        let content = definition.get_execution_content();
+        for (_, reference) in output_references(content) {
+            let Some(producer) = context.task_definitions.get(reference.task) else {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' references outputs of unknown task '{}'",
+                    reference.task
+                )));
+            };
+            if !producer
+                .output_values
+                .iter()
+                .any(|value| value.name == reference.value)
+            {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' references output '{}' that task '{}' does not declare",
+                    reference.value, reference.task
+                )));
+            }
+            if !depends_on(&context.dependency_graph, name, reference.task) {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' references outputs of '{}' without depending on it",
+                    reference.task
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `task` depends on `other`, directly or transitively
+fn depends_on(graph: &HashMap<String, Vec<String>>, task: &str, other: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut pending = vec![task];
+    while let Some(current) = pending.pop() {
+        for dependency in graph.get(current).into_iter().flatten() {
+            if dependency == other {
+                return true;
+            }
+            if seen.insert(dependency.as_str()) {
+                pending.push(dependency);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            parse_reference("tasks.build.outputs.version"),
+            Some(OutputReference {
+                task: "build",
+                value: "version"
+            })
+        );
+        assert_eq!(
+            parse_reference("tasks.ci.build.outputs.digest"),
+            Some(OutputReference {
+                task: "ci.build",
+                value: "digest"
+            })
+        );
+        assert_eq!(parse_reference("HOME"), None);
+        assert_eq!(parse_reference("tasks.build.outputs."), None);
+    }
+
+    #[test]
+    fn test_depends_on_transitively() {
+        let graph = HashMap::from([
+            ("deploy".to_string(), vec!["test".to_string()]),
+            ("test".to_string(), vec!["build".to_string()]),
+        ]);
+        assert!(depends_on(&graph, "deploy", "build"));
+        assert!(!depends_on(&graph, "build", "deploy"));
+    }
+}
@@ -4,6 +4,7 @@
 //! security paths are properly resolved and validated for task execution.
 
 use cuenv_core::{Error, Result, TaskSecurity};
+use cuenv_security::{SecurityPolicy, DEFAULT_SECCOMP_PROFILE};
 use std::path::{Path, PathBuf};
 
 use super::BuildContext;
@@ -23,6 +24,39 @@ pub fn validate_security_configs(context: &mut BuildContext, workspace_root: &Pa
     Ok(())
 }
 
+/// Check every task against the security policy, reporting all violations
+///
+/// Tasks without a timeout of their own get the policy's maximum where it
+/// is below the default.
+pub fn enforce_policy(context: &mut BuildContext, policy: &SecurityPolicy) -> Result<()> {
+    if policy.is_empty() {
+        return Ok(());
+    }
+    let mut violations = Vec::new();
+    for (task_name, definition) in &mut context.task_definitions {
+        let has_timeout = context
+            .task_configs
+            .get(task_name)
+            .is_some_and(|config| config.timeout.is_some());
+        if !has_timeout {
+            policy.apply_default_timeout(definition);
+        }
+        violations.extend(policy.check(definition));
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    violations.sort_by(|a, b| a.task.cmp(&b.task));
+    let report = violations
+        .iter()
+        .map(|violation| format!("  - {violation}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(Error::configuration(format!(
+        "Security policy violation:\n{report}"
+    )))
+}
+
 /// Resolve security paths to absolute paths and validate them
 pub fn resolve_security_paths(
     task_name: &str,
@@ -71,6 +105,13 @@ pub fn resolve_security_paths(
     resolve_paths(&mut security.read_only_paths)?;
     resolve_paths(&mut security.write_only_paths)?;
 
+    // Custom seccomp profiles are files relative to the workspace
+    if let Some(profile) = &mut security.seccomp_profile {
+        if profile != DEFAULT_SECCOMP_PROFILE && Path::new(profile.as_str()).is_relative() {
+            *profile = workspace_root.join(&*profile).to_string_lossy().to_string();
+        }
+    }
+
     Ok(())
 }
 
@@ -127,7 +168,7 @@ pub fn validate_security_path(path: &Path, workspace_root: &Path, task_name: &st
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cuenv_core::{TaskDefinition, TaskExecutionMode};
+    use cuenv_core::{EnforcementLevel, TaskDefinition, TaskExecutionMode};
     use std::fs;
     use std::time::Duration;
     use tempfile::TempDir;
@@ -150,6 +191,12 @@ mod tests {
             security,
             cache: cuenv_core::TaskCache::default(),
             timeout: Duration::from_secs(30),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         }
     }
 
@@ -161,6 +208,9 @@ mod tests {
             read_only_paths: Vec::new(),
             write_only_paths: Vec::new(),
             allowed_hosts: vec!["example.com".to_string(), "api.test.com".to_string()],
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let result = validate_security_hosts("test_task", &security);
@@ -175,6 +225,9 @@ mod tests {
             read_only_paths: Vec::new(),
             write_only_paths: Vec::new(),
             allowed_hosts: vec!["".to_string()],
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let result = validate_security_hosts("test_task", &security);
@@ -190,6 +243,9 @@ mod tests {
             read_only_paths: Vec::new(),
             write_only_paths: Vec::new(),
             allowed_hosts: vec!["invalid host.com".to_string()],
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let result = validate_security_hosts("test_task", &security);
@@ -215,6 +271,9 @@ mod tests {
             read_only_paths: vec![PathBuf::from("readonly")],
             write_only_paths: Vec::new(),
             allowed_hosts: Vec::new(),
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let result = resolve_security_paths("test_task", &mut security, &workspace_root);
@@ -239,6 +298,9 @@ mod tests {
             read_only_paths: vec![PathBuf::from("/etc/passwd")],
             write_only_paths: Vec::new(),
             allowed_hosts: Vec::new(),
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let result = resolve_security_paths("test_task", &mut security, &workspace_root);
@@ -262,6 +324,9 @@ mod tests {
             read_only_paths: vec![PathBuf::from("secure")],
             write_only_paths: Vec::new(),
             allowed_hosts: vec!["example.com".to_string()],
+            seccomp_profile: None,
+            seccomp_audit: false,
+            enforcement: EnforcementLevel::default(),
         };
 
         let mut context = BuildContext {
@@ -3,8 +3,10 @@
 //! This module provides validation functionality for task configurations,
 //! ensuring they meet the required constraints and standards.
 
+use super::conversion::parse_umask;
 use cuenv_config::TaskConfig;
-use cuenv_core::{Error, Result};
+use cuenv_core::{Error, IoClass, Result};
+use cuenv_utils::duration::parse_duration;
 use std::collections::HashMap;
 
 /// Validates basic task configurations
@@ -20,11 +22,20 @@ pub fn validate_task_configs(task_configs: &HashMap<String, TaskConfig>) -> Resu
         // Validate command/script exclusivity
         validate_command_script_exclusivity(name, config)?;
 
+        // Validate aliases
+        for alias in config.alias.iter().flatten() {
+            validate_alias(name, alias)?;
+        }
+
         // Validate shell
         if let Some(shell) = &config.shell {
             validate_shell(shell)?;
         }
 
+        validate_sharding(name, config)?;
+        validate_process_settings(name, config)?;
+        validate_wait_for(name, config)?;
+
         // Validate timeout
         if let Some(timeout) = config.timeout {
             if timeout == 0 {
@@ -51,6 +62,113 @@ fn validate_command_script_exclusivity(name: &str, config: &TaskConfig) -> Resul
     }
 }
 
+/// Validate that an alias is a single name segment
+fn validate_alias(name: &str, alias: &str) -> Result<()> {
+    if alias.is_empty() || alias.contains(['.', ':']) {
+        return Err(Error::configuration(format!(
+            "Task '{name}' alias '{alias}' must be a non-empty name without '.' or ':'"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate that a shardable task can be split: its tests are appended to
+/// its command, so it cannot be a script
+fn validate_sharding(name: &str, config: &TaskConfig) -> Result<()> {
+    if config.shardable != Some(true) {
+        return Ok(());
+    }
+    if config.test_list.is_none() {
+        return Err(Error::configuration(format!(
+            "Shardable task '{name}' must define 'testList'"
+        )));
+    }
+    if config.command.is_none() {
+        return Err(Error::configuration(format!(
+            "Shardable task '{name}' must use 'command', not 'script'"
+        )));
+    }
+    if config.shards == Some(0) {
+        return Err(Error::configuration(format!(
+            "Task '{name}' shards must be greater than 0"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate that `umask`, `nice` and `ioPriority` are in range
+fn validate_process_settings(name: &str, config: &TaskConfig) -> Result<()> {
+    if let Some(umask) = &config.umask {
+        if parse_umask(umask).is_err() {
+            return Err(Error::configuration(format!(
+                "Task '{name}' umask '{umask}' must be an octal mode from 000 to 777"
+            )));
+        }
+    }
+    if let Some(nice) = config.nice {
+        if !(-20..=19).contains(&nice) {
+            return Err(Error::configuration(format!(
+                "Task '{name}' nice must be between -20 and 19, got {nice}"
+            )));
+        }
+    }
+    if let Some(io_priority) = config.io_priority {
+        match (io_priority.class, io_priority.level) {
+            (IoClass::Idle, Some(_)) => {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' ioPriority level does not apply to the idle class"
+                )))
+            }
+            (_, Some(level)) if level > 7 => {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' ioPriority level must be between 0 and 7, got {level}"
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Validate that each `waitFor` entry names one TCP address or HTTP URL
+/// and a positive timeout
+fn validate_wait_for(name: &str, config: &TaskConfig) -> Result<()> {
+    for wait in config.wait_for.iter().flatten() {
+        match (&wait.tcp, &wait.http) {
+            (Some(address), None) => {
+                let port = address
+                    .rsplit_once(':')
+                    .map(|(_, port)| port.parse::<u16>());
+                if !matches!(port, Some(Ok(_))) {
+                    return Err(Error::configuration(format!(
+                        "Task '{name}' waitFor tcp '{address}' must be host:port"
+                    )));
+                }
+            }
+            (None, Some(url)) => {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(Error::configuration(format!(
+                        "Task '{name}' waitFor http '{url}' must be an http:// or https:// URL"
+                    )));
+                }
+            }
+            _ => {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' waitFor entries need exactly one of 'tcp' and 'http'"
+                )))
+            }
+        }
+        if let Some(timeout) = &wait.timeout {
+            if !parse_duration(timeout).is_ok_and(|timeout| !timeout.is_zero()) {
+                return Err(Error::configuration(format!(
+                    "Task '{name}' waitFor timeout '{timeout}' must be a positive duration such as '30s'"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Validate shell command
 pub fn validate_shell(shell: &str) -> Result<()> {
     const ALLOWED_SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "pwsh", "powershell"];
@@ -78,7 +196,7 @@ pub fn validate_shell(shell: &str) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cuenv_config::TaskConfig;
+    use cuenv_config::{TaskConfig, WaitForConfig};
 
     fn create_test_config(command: Option<&str>, script: Option<&str>) -> TaskConfig {
         TaskConfig {
@@ -95,6 +213,25 @@ mod tests {
             cache_key: None,
             cache_env: None,
             timeout: Some(30),
+            run_as: None,
+            allow_undefined: None,
+            publish: None,
+            alias: None,
+            shardable: None,
+            test_list: None,
+            shards: None,
+            confirm: None,
+            problem_matcher: None,
+            schedule: None,
+            deprecated: None,
+            owner: None,
+            tags: None,
+            labels: None,
+            umask: None,
+            nice: None,
+            io_priority: None,
+            wait_for: None,
+            cache_failures: None,
         }
     }
 
@@ -174,4 +311,107 @@ mod tests {
             .to_string()
             .contains("must be greater than 0"));
     }
+
+    #[test]
+    fn test_alias_with_separator() {
+        let mut configs = HashMap::new();
+        let mut config = create_test_config(Some("echo hello"), None);
+        config.alias = Some(vec!["lint.c".to_string()]);
+        configs.insert("check".to_string(), config);
+
+        let result = validate_task_configs(&configs);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("alias 'lint.c'"));
+    }
+
+    #[test]
+    fn test_shardable_task_needs_test_list_and_command() {
+        let mut config = create_test_config(None, Some("cargo test"));
+        config.shardable = Some(true);
+        let configs = HashMap::from([("test".to_string(), config.clone())]);
+        let message = validate_task_configs(&configs).unwrap_err().to_string();
+        assert!(message.contains("must define 'testList'"));
+
+        config.test_list = Some("cargo test -- --list".to_string());
+        let configs = HashMap::from([("test".to_string(), config.clone())]);
+        let message = validate_task_configs(&configs).unwrap_err().to_string();
+        assert!(message.contains("must use 'command'"));
+
+        config.command = Some("cargo test".to_string());
+        config.script = None;
+        let configs = HashMap::from([("test".to_string(), config)]);
+        assert!(validate_task_configs(&configs).is_ok());
+    }
+
+    #[test]
+    fn test_process_settings_ranges() {
+        let validate = |config: TaskConfig| {
+            validate_task_configs(&HashMap::from([("build".to_string(), config)]))
+        };
+        let mut config = create_test_config(Some("make"), None);
+        config.umask = Some("027".to_string());
+        config.nice = Some(10);
+        config.io_priority = Some(cuenv_core::TaskIoPriority {
+            class: IoClass::BestEffort,
+            level: Some(7),
+        });
+        assert!(validate(config.clone()).is_ok());
+
+        let mut bad = config.clone();
+        bad.umask = Some("0999".to_string());
+        assert!(validate(bad)
+            .unwrap_err()
+            .to_string()
+            .contains("umask '0999'"));
+
+        let mut bad = config.clone();
+        bad.nice = Some(20);
+        assert!(validate(bad).is_err());
+
+        let mut bad = config;
+        bad.io_priority = Some(cuenv_core::TaskIoPriority {
+            class: IoClass::Idle,
+            level: Some(0),
+        });
+        assert!(validate(bad).is_err());
+    }
+    #[test]
+    fn test_wait_for_entries() {
+        let validate = |wait: WaitForConfig| {
+            let mut config = create_test_config(Some("npm test"), None);
+            config.wait_for = Some(vec![wait]);
+            validate_task_configs(&HashMap::from([("test".to_string(), config)]))
+        };
+        assert!(validate(WaitForConfig {
+            tcp: Some("localhost:5432".to_string()),
+            timeout: Some("30s".to_string()),
+            ..WaitForConfig::default()
+        })
+        .is_ok());
+        assert!(validate(WaitForConfig {
+            http: Some("http://localhost:8080/health".to_string()),
+            ..WaitForConfig::default()
+        })
+        .is_ok());
+
+        let err = validate(WaitForConfig {
+            tcp: Some("localhost".to_string()),
+            ..WaitForConfig::default()
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("must be host:port"));
+        assert!(validate(WaitForConfig::default()).is_err());
+        assert!(validate(WaitForConfig {
+            http: Some("localhost:8080".to_string()),
+            ..WaitForConfig::default()
+        })
+        .is_err());
+        assert!(validate(WaitForConfig {
+            tcp: Some("localhost:5432".to_string()),
+            timeout: Some("0s".to_string()),
+            ..WaitForConfig::default()
+        })
+        .is_err());
+    }
 }
@@ -0,0 +1,233 @@
+//! Cancelling task runs from outside the executor
+//!
+//! A task process registers its run for as long as it runs, under the run
+//! id its events carry (the `runId` of task notifications). Editors stop one
+//! run with `cancelRun`, or every run of a task with `cancelTask`, through
+//! the task server or `cuenv agent`.
+//!
+//! Cancelling is graceful first: the task's process group gets SIGTERM and
+//! [`GRACE_PERIOD`] to exit before it is sent SIGKILL. Cancelling a run a
+//! second time, or with `force`, kills it right away. On Windows the process
+//! tree is always killed. A cancelled run fails with a "cancelled" error, so
+//! tasks that depend on it do not run.
+
+use dashmap::DashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Time a task has to exit after SIGTERM before it is killed
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How hard a cancellation was asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CancelMode {
+    /// Ask the task to stop, then kill it after the grace period
+    Graceful,
+    /// Kill the task now
+    Force,
+}
+
+struct ActiveRun {
+    task: String,
+    requests: watch::Sender<Option<CancelMode>>,
+}
+
+fn active() -> &'static DashMap<String, ActiveRun> {
+    static ACTIVE: OnceLock<DashMap<String, ActiveRun>> = OnceLock::new();
+    ACTIVE.get_or_init(DashMap::new)
+}
+
+/// A run that can be cancelled, registered until dropped
+pub struct CancellationToken {
+    run_id: String,
+    requests: watch::Receiver<Option<CancelMode>>,
+}
+
+/// Register the run `run_id` of `task` as cancellable
+pub fn register(run_id: &str, task: &str) -> CancellationToken {
+    let (requests, receiver) = watch::channel(None);
+    active().insert(
+        run_id.to_string(),
+        ActiveRun {
+            task: task.to_string(),
+            requests,
+        },
+    );
+    CancellationToken {
+        run_id: run_id.to_string(),
+        requests: receiver,
+    }
+}
+
+impl CancellationToken {
+    /// Whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.requests.borrow().is_some()
+    }
+
+    /// Resolves once cancellation of at least `mode` is requested
+    pub async fn requested(&mut self, mode: CancelMode) {
+        if self
+            .requests
+            .wait_for(|requested| requested.is_some_and(|requested| requested >= mode))
+            .await
+            .is_err()
+        {
+            // The run is no longer registered, so it is never cancelled
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for CancellationToken {
+    fn drop(&mut self) {
+        active().remove(&self.run_id);
+    }
+}
+
+/// Request cancellation of the run `run_id`; false when no such run is
+/// active
+pub fn cancel_run(run_id: &str, force: bool) -> bool {
+    match active().get(run_id) {
+        Some(run) => {
+            request(&run.requests, force);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Request cancellation of every active run of `task`, returning their run
+/// ids
+pub fn cancel_task(task: &str, force: bool) -> Vec<String> {
+    let mut cancelled: Vec<String> = active()
+        .iter()
+        .filter(|run| run.task == task)
+        .map(|run| {
+            request(&run.requests, force);
+            run.key().clone()
+        })
+        .collect();
+    cancelled.sort();
+    cancelled
+}
+
+/// Active runs as (run id, task) pairs, oldest first
+pub fn active_runs() -> Vec<(String, String)> {
+    let mut runs: Vec<_> = active()
+        .iter()
+        .map(|run| (run.key().clone(), run.task.clone()))
+        .collect();
+    // Run ids are ULIDs, which sort by creation time
+    runs.sort();
+    runs
+}
+
+/// A repeated request escalates to a forced one
+fn request(requests: &watch::Sender<Option<CancelMode>>, force: bool) {
+    requests.send_modify(|mode| {
+        *mode = Some(if force || mode.is_some() {
+            CancelMode::Force
+        } else {
+            CancelMode::Graceful
+        });
+    });
+}
+
+/// Stop the process group of `pid` once `token` is cancelled, gracefully
+/// first; resolves once the group was sent SIGKILL
+pub(crate) async fn terminate_when_cancelled(token: &mut CancellationToken, pid: u32) {
+    token.requested(CancelMode::Graceful).await;
+    tracing::info!(pid, run_id = %token.run_id, "Cancelling task");
+    signal(pid, CancelMode::Graceful);
+
+    tokio::select! {
+        () = token.requested(CancelMode::Force) => {}
+        () = tokio::time::sleep(GRACE_PERIOD) => {}
+    }
+    signal(pid, CancelMode::Force);
+}
+
+/// Signal the process group led by `pid`, which every task process leads
+#[cfg(unix)]
+fn signal(pid: u32, mode: CancelMode) {
+    let signal = match mode {
+        CancelMode::Graceful => libc::SIGTERM,
+        CancelMode::Force => libc::SIGKILL,
+    };
+    // Signalling group 0 would signal cuenv's own group
+    let Some(pgid) = libc::pid_t::try_from(pid).ok().filter(|pgid| *pgid > 0) else {
+        return;
+    };
+    // SAFETY: kill has no memory safety requirements
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
+}
+
+/// Kill the process tree of `pid`
+#[cfg(not(unix))]
+fn signal(pid: u32, _mode: CancelMode) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_run_escalates_to_force() {
+        let mut token = register("01TESTRUNESCALATE", "cancel-escalate");
+        assert!(!token.is_cancelled());
+
+        assert!(cancel_run("01TESTRUNESCALATE", false));
+        token.requested(CancelMode::Graceful).await;
+        assert_eq!(*token.requests.borrow(), Some(CancelMode::Graceful));
+
+        assert!(cancel_run("01TESTRUNESCALATE", false));
+        token.requested(CancelMode::Force).await;
+
+        drop(token);
+        assert!(!cancel_run("01TESTRUNESCALATE", true));
+    }
+
+    #[test]
+    fn test_cancel_task_cancels_every_run_of_it() {
+        let first = register("01TESTRUNTASKA", "cancel-task");
+        let second = register("01TESTRUNTASKB", "cancel-task");
+        let other = register("01TESTRUNTASKC", "cancel-other");
+
+        assert_eq!(
+            cancel_task("cancel-task", true),
+            ["01TESTRUNTASKA", "01TESTRUNTASKB"]
+        );
+        assert!(first.is_cancelled() && second.is_cancelled());
+        assert!(!other.is_cancelled());
+        assert!(active_runs().contains(&("01TESTRUNTASKC".to_string(), "cancel-other".to_string())));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_terminate_stops_the_process_group() {
+        use std::os::unix::process::CommandExt;
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut token = register("01TESTRUNTERMINATE", "cancel-terminate");
+        cancel_run("01TESTRUNTERMINATE", false);
+
+        let _ = tokio::time::timeout(
+            Duration::from_millis(200),
+            terminate_when_cancelled(&mut token, child.id()),
+        )
+        .await;
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+    }
+}
@@ -1,5 +1,8 @@
+use cuenv_cache::hashing::split_package_input;
 use cuenv_core::{Error, Result};
+use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 /// Represents a reference to a task, potentially in another package
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -204,6 +207,53 @@ pub fn parse_reference(input: &str) -> Result<CrossPackageReference> {
     }
 }
 
+/// Directory of `package` in the CUE module containing `working_dir`
+///
+/// Package names follow discovery: the directories from the module root
+/// joined by colons, with `root` for the module root itself.
+pub fn resolve_package_dir(working_dir: &Path, package: &str) -> Result<PathBuf> {
+    let module_root = working_dir
+        .ancestors()
+        .find(|dir| dir.join("cue.mod").is_dir())
+        .ok_or_else(|| {
+            Error::configuration(format!(
+                "Cannot resolve package '{package}': no cue.mod directory above {}",
+                working_dir.display()
+            ))
+        })?;
+    let dir = if package == "root" {
+        module_root.to_path_buf()
+    } else {
+        package
+            .split(':')
+            .fold(module_root.to_path_buf(), |dir, part| dir.join(part))
+    };
+    if !dir.join("env.cue").is_file() {
+        return Err(Error::configuration(format!(
+            "Package '{package}' not found: {} has no env.cue",
+            dir.display()
+        )));
+    }
+    Ok(dir)
+}
+
+/// Directories of the packages named by `pkg://` entries of `inputs`
+pub fn package_input_dirs(
+    working_dir: &Path,
+    inputs: &[String],
+) -> Result<HashMap<String, PathBuf>> {
+    let mut dirs = HashMap::new();
+    for (package, _) in inputs.iter().filter_map(|input| split_package_input(input)) {
+        if !dirs.contains_key(package) {
+            dirs.insert(
+                package.to_string(),
+                resolve_package_dir(working_dir, package)?,
+            );
+        }
+    }
+    Ok(dirs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +275,27 @@ mod tests {
         assert_eq!(output.output(), Some("dist"));
         assert!(output.is_cross_package());
     }
+
+    #[test]
+    fn test_package_input_dirs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("cue.mod")).unwrap();
+        let lib = root.join("projects").join("lib");
+        let app = root.join("projects").join("app");
+        for dir in [&lib, &app] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("env.cue"), "package env").unwrap();
+        }
+
+        let inputs = [
+            "src/**".to_string(),
+            "pkg://projects:lib/src/**".to_string(),
+            "pkg://projects:lib/Cargo.toml".to_string(),
+        ];
+        let dirs = package_input_dirs(&app, &inputs).unwrap();
+        assert_eq!(dirs, HashMap::from([("projects:lib".to_string(), lib)]));
+
+        assert!(resolve_package_dir(&app, "projects:missing").is_err());
+    }
 }
@@ -1,20 +1,27 @@
 mod api;
 mod builder;
 mod cache;
+mod confirm;
 mod context;
 mod dag_cache;
 mod dependency;
+mod deprecation;
 pub mod execution;
 mod graph;
 mod management;
+mod outputs;
 mod plan;
 mod runner;
 mod strategies;
+mod stubs;
 mod unified_dag;
 
 pub use context::TaskExecutionContext;
 pub use dag_cache::{DAGCache, DAGCacheConfig, DAGCacheStats};
+pub use outputs::OutputValues;
 pub use plan::TaskExecutionPlan;
+pub use runner::publish_piped_output;
+pub use stubs::{stub_tasks, TaskStubs};
 pub use unified_dag::{DAGBuilder, UnifiedTaskDAG};
 
 use crate::{MonorepoTaskRegistry, TaskBuilder};
@@ -39,8 +46,18 @@ pub struct TaskExecutor {
     pub(crate) monorepo_registry: Option<Arc<MonorepoTaskRegistry>>,
     /// Track executed tasks to avoid re-execution in cross-package scenarios
     pub(crate) executed_tasks: Arc<Mutex<HashSet<String>>>,
+    /// Tasks whose result came from the cache in this run
+    pub(crate) cached_tasks: Arc<Mutex<HashSet<String>>>,
+    /// Values output by the tasks of this run
+    pub(crate) output_values: OutputValues,
     /// DAG cache for performance optimization
     pub(crate) dag_cache: Arc<DAGCache>,
+    /// Tasks asking for confirmation that were confirmed
+    pub(crate) confirmed_tasks: Arc<Mutex<HashSet<String>>>,
+    /// Publish tasks' output as events instead of passing it to the terminal
+    pub(crate) capture_output: bool,
+    /// Commands that stubbed tasks run instead of their own
+    pub(crate) stubs: TaskStubs,
 }
 
 #[cfg(test)]
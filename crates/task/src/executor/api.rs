@@ -50,6 +50,22 @@ impl TaskExecutor {
             .unwrap_or(false)
     }
 
+    /// Whether the task's results were served from the cache
+    pub fn is_cached(&self, task_name: &str) -> bool {
+        self.cached_tasks
+            .lock()
+            .map(|guard| guard.contains(task_name))
+            .unwrap_or(false)
+    }
+
+    /// Whether every task that ran was served from the cache
+    pub fn all_cached(&self) -> bool {
+        match (self.executed_tasks.lock(), self.cached_tasks.lock()) {
+            (Ok(executed), Ok(cached)) => executed.iter().all(|task| cached.contains(task)),
+            _ => false,
+        }
+    }
+
     /// Execute multiple tasks with their dependencies
     pub async fn execute_tasks_with_dependencies(
         &self,
@@ -57,8 +73,11 @@ impl TaskExecutor {
         args: &[String],
         audit_mode: bool,
     ) -> Result<i32> {
-        self.execute_tasks_with_dependencies_internal(task_names, args, audit_mode, false)
-            .await
+        let result = self
+            .execute_tasks_with_dependencies_internal(task_names, args, audit_mode, false)
+            .await;
+        self.record_cache_history();
+        result
     }
 
     /// Execute multiple tasks with their dependencies and output capture
@@ -68,8 +87,11 @@ impl TaskExecutor {
         args: &[String],
         audit_mode: bool,
     ) -> Result<i32> {
-        self.execute_tasks_with_dependencies_internal(task_names, args, audit_mode, true)
-            .await
+        let result = self
+            .execute_tasks_with_dependencies_internal(task_names, args, audit_mode, true)
+            .await;
+        self.record_cache_history();
+        result
     }
 
     /// Execute tasks using the unified DAG system - ensures consistent ordering
@@ -80,7 +102,10 @@ impl TaskExecutor {
         args: &[String],
         audit_mode: bool,
     ) -> Result<i32> {
-        self.execute_tasks_with_unified_dag(task_names, args, audit_mode)
-            .await
+        let result = self
+            .execute_tasks_with_unified_dag(task_names, args, audit_mode)
+            .await;
+        self.record_cache_history();
+        result
     }
 }
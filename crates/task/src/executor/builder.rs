@@ -1,4 +1,4 @@
-use super::{cache, TaskExecutor};
+use super::{cache, OutputValues, TaskExecutor, TaskStubs};
 use crate::{MonorepoTaskRegistry, TaskBuilder};
 use cuenv_cache::config::CacheConfiguration;
 use cuenv_cache::CacheManager;
@@ -24,7 +24,9 @@ impl TaskExecutor {
         let action_cache = cache_manager.action_cache();
 
         // Create TaskBuilder with current working directory and environment
-        let task_builder = TaskBuilder::new(working_dir.clone());
+        let task_builder = TaskBuilder::new(working_dir.clone())
+            .with_strict_variables(env_manager.strict_variables())
+            .with_deferred_variables(env_manager.deferred_secrets().keys().map(String::as_str));
 
         // Initialize DAG cache for performance optimization
         let dag_cache = Arc::new(super::dag_cache::DAGCache::new());
@@ -38,7 +40,12 @@ impl TaskExecutor {
             task_builder,
             monorepo_registry: None,
             executed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            cached_tasks: Arc::new(Mutex::new(HashSet::new())),
+            output_values: OutputValues::default(),
             dag_cache,
+            confirmed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            capture_output: false,
+            stubs: TaskStubs::for_run()?,
         })
     }
 
@@ -71,7 +78,12 @@ impl TaskExecutor {
             task_builder,
             monorepo_registry: Some(Arc::new(registry)),
             executed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            cached_tasks: Arc::new(Mutex::new(HashSet::new())),
+            output_values: OutputValues::default(),
             dag_cache,
+            confirmed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            capture_output: false,
+            stubs: TaskStubs::for_run()?,
         })
     }
 
@@ -93,7 +105,9 @@ impl TaskExecutor {
         let action_cache = cache_manager.action_cache();
 
         // Create TaskBuilder with current working directory
-        let task_builder = TaskBuilder::new(working_dir.clone());
+        let task_builder = TaskBuilder::new(working_dir.clone())
+            .with_strict_variables(env_manager.strict_variables())
+            .with_deferred_variables(env_manager.deferred_secrets().keys().map(String::as_str));
 
         // Initialize DAG cache for performance optimization
         let dag_cache = Arc::new(super::dag_cache::DAGCache::new());
@@ -107,7 +121,22 @@ impl TaskExecutor {
             task_builder,
             monorepo_registry: None,
             executed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            cached_tasks: Arc::new(Mutex::new(HashSet::new())),
+            output_values: OutputValues::default(),
             dag_cache,
+            confirmed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            capture_output: false,
+            stubs: TaskStubs::default(),
         })
     }
+
+    /// Publish tasks' output a line at a time as `TaskOutput` and
+    /// `TaskError` events instead of passing it to the terminal
+    ///
+    /// Tasks run without a standard input while output is captured.
+    #[must_use]
+    pub fn with_captured_output(mut self, capture: bool) -> Self {
+        self.capture_output = capture;
+        self
+    }
 }
@@ -1,7 +1,18 @@
 use super::context::TaskExecutionContext;
 use super::runner;
+use crate::cross_package::package_input_dirs;
+use cuenv_cache::concurrent::action::{ActionDigest, ActionResult};
 use cuenv_cache::config::{CacheConfig, CacheConfiguration};
-use cuenv_core::{Result, TaskDefinition};
+use cuenv_cache::env_usage::{observed_variables, referenced_variables};
+use cuenv_cache::CacheMode;
+use cuenv_core::{
+    Error, ExitStatus, Result, TaskDefinition, CUENV_GIT_BRANCH_VAR, CUENV_GIT_DIRTY_VAR,
+    CUENV_GIT_SHA_VAR, CUENV_GIT_TAG_VAR,
+};
+use cuenv_security::AuditReport;
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::paths::get_audit_report_path;
+use std::collections::{BTreeSet, HashMap};
 
 /// Create cache config struct from configuration
 pub fn create_cache_config_struct(cache_config: &CacheConfiguration) -> Result<CacheConfig> {
@@ -30,84 +41,566 @@ pub fn create_cache_config_struct(cache_config: &CacheConfiguration) -> Result<C
     Ok(config)
 }
 
+/// Outcome of a task run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskRun {
+    pub status: ExitStatus,
+    /// Whether the result came from the cache instead of running the task
+    pub cached: bool,
+}
+
 /// Execute a single task with caching support
 pub async fn execute_single_task_with_cache(
     ctx: &TaskExecutionContext<'_>,
     task_name: &str,
     task_definition: &TaskDefinition,
     args: &[String],
-) -> Result<i32> {
-    // Check if caching is enabled for this task using the new configuration system
-    // TODO: Add CacheConfigResolver when moved to workspace
-    let cache_enabled = false;
-    let _unused = (&ctx.cache_config.global, &task_definition.cache, task_name);
+) -> Result<TaskRun> {
+    // Tasks opt in with `cache`, and the global setting can turn caching off
+    let global = &ctx.cache_config.global;
+    let cache_enabled =
+        global.enabled && global.mode != CacheMode::Off && task_definition.cache.enabled;
 
     if !cache_enabled {
-        // Execute without caching
-        // TODO: Add tracing when moved to workspace
-        // task_progress(task_name, None, "Executing task (cache disabled)");
-        return runner::execute_single_task(
+        tracing::debug!(task_name = %task_name, "Executing task (cache disabled)");
+        let status = run_task(ctx, task_name, task_definition, args).await?;
+        return Ok(TaskRun {
+            status,
+            cached: false,
+        });
+    }
+
+    // Generate action digest using ActionCache. Git variables are only read
+    // when the task refers to them or an include pattern of the key filter
+    // names them
+    let cue_vars = ctx.env_manager.get_cue_vars();
+    let mut env_vars: HashMap<String, String> = std::env::vars().collect();
+    env_vars.extend(
+        cue_vars
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    let git_names = keyed_git_variables(ctx, task_name, task_definition);
+    env_vars.extend(cuenv_env::git::git_variables(
+        &task_definition.working_directory,
+        git_names.iter().map(String::as_str),
+    ));
+    let env_all = task_definition
+        .cache
+        .env_filter
+        .as_ref()
+        .is_some_and(|filter| filter.env_all);
+    let declared_vars: BTreeSet<String> = if env_all {
+        cue_vars.keys().cloned().collect()
+    } else {
+        BTreeSet::new()
+    };
+    let package_dirs = package_input_dirs(ctx.working_dir, &task_definition.inputs)?;
+    let digest = ctx
+        .action_cache
+        .compute_digest(
             task_name,
             task_definition,
             ctx.working_dir,
-            args,
-            ctx.audit_mode,
-            ctx.capture_output,
+            env_vars,
+            &declared_vars,
+            &package_dirs,
         )
-        .await;
+        .await?;
+    let environment = ctx.env_manager.environment().map(str::to_string);
+    let (mut result, mut ran) = run_action(
+        ctx,
+        task_name,
+        task_definition,
+        args,
+        &digest,
+        environment.clone(),
+    )
+    .await?;
+
+    // A hit only counts once its outputs are back in place and verified; a
+    // corrupted entry is dropped and the task runs again. Outputs of cached
+    // failures are left alone
+    if ran.is_none() && result.exit_code == 0 {
+        let verify = ctx.cache_config.global.verify_mode();
+        if let Err(e) = ctx
+            .action_cache
+            .restore_output_files(&result.output_files, ctx.working_dir, verify)
+            .await
+        {
+            tracing::warn!(
+                task_name = %task_name,
+                "Not reusing cached result: {e}; running the task again"
+            );
+            ctx.action_cache.invalidate(&digest.hash);
+            (result, ran) = run_action(
+                ctx,
+                task_name,
+                task_definition,
+                args,
+                &digest,
+                environment.clone(),
+            )
+            .await?;
+        }
     }
 
-    // Generate action digest using ActionCache
-    let env_vars = std::env::vars().collect();
-    let digest = ctx
-        .action_cache
-        .compute_digest(task_name, task_definition, ctx.working_dir, env_vars)
-        .await?;
+    if ran.is_none() && result.exit_code != 0 {
+        tracing::error!(
+            task_name = %task_name,
+            exit_code = %result.exit_code,
+            "Failing fast: the task failed with the same inputs in run {} at {}; change its \
+             inputs, or run it again after {}, to retry",
+            result.run_id.as_deref().unwrap_or("(unknown)"),
+            describe_time(result.executed_at),
+            result.expires_at.map_or_else(|| "(unknown)".to_string(), describe_time),
+        );
+    } else if ran.is_none() {
+        tracing::info!(
+            task_name = %task_name,
+            "Reusing cached result recorded by run {}",
+            result.run_id.as_deref().unwrap_or("(unknown)")
+        );
+    }
+    if ran.is_none() {
+        ctx.action_cache
+            .record_reuse(&digest, &result, ctx.run.run_id());
+    }
+
+    // The key only covers variables the task is known to use, so a result
+    // from another environment may be reused; say so rather than stay silent
+    if result.environment != environment {
+        tracing::warn!(
+            task_name = %task_name,
+            "Reusing cached result recorded in environment {} while running in {}; if the task \
+             reads variables that differ between them, list them in cache.env or set envAll",
+            describe_environment(result.environment.as_deref()),
+            describe_environment(environment.as_deref()),
+        );
+    }
 
-    // Execute with ActionCache
+    // Update cache manager statistics for backward compatibility
+    if result.exit_code == 0 {
+        // TODO: Add tracing when moved to workspace
+        // task_progress(task_name, Some(100), "Task completed successfully");
+        tracing::info!(task_name = %task_name, "Task completed successfully");
+    } else {
+        tracing::error!(
+            task_name = %task_name,
+            exit_code = %result.exit_code,
+            "Task failed with exit code"
+        );
+    }
+
+    Ok(TaskRun {
+        status: ran.unwrap_or(ExitStatus::Code(result.exit_code)),
+        cached: ran.is_none(),
+    })
+}
+
+/// Names of the built-in git variables that may key the cache of a task
+fn keyed_git_variables(
+    ctx: &TaskExecutionContext<'_>,
+    task_name: &str,
+    task_definition: &TaskDefinition,
+) -> BTreeSet<String> {
+    let candidates: HashMap<String, String> = [
+        CUENV_GIT_SHA_VAR,
+        CUENV_GIT_BRANCH_VAR,
+        CUENV_GIT_DIRTY_VAR,
+        CUENV_GIT_TAG_VAR,
+    ]
+    .into_iter()
+    .map(|name| (name.to_string(), String::new()))
+    .collect();
+    ctx.action_cache
+        .filter_env_vars(task_name, &candidates)
+        .into_keys()
+        .chain(referenced_variables(
+            task_definition.get_execution_content(),
+        ))
+        .collect()
+}
+
+/// Run the action of a task through the action cache, returning its result
+/// and, when the task ran instead of being served from the cache, its status
+async fn run_action(
+    ctx: &TaskExecutionContext<'_>,
+    task_name: &str,
+    task_definition: &TaskDefinition,
+    args: &[String],
+    digest: &ActionDigest,
+    environment: Option<String>,
+) -> Result<(ActionResult, Option<ExitStatus>)> {
+    let run_id = ctx.run.run_id().to_string();
+
+    // Execute with ActionCache; the status is only set when the task runs
+    let mut ran: Option<ExitStatus> = None;
+    let ran_status = &mut ran;
     let result = ctx
         .action_cache
-        .execute_action(&digest, || async {
+        .execute_action(digest, || async move {
             // TODO: Add tracing when moved to workspace
             // cache_event(task_name, false, "task_result");
             // TODO: Add tracing when moved to workspace
             // task_progress(task_name, Some(0), "Starting task execution");
 
-            let exit_code = runner::execute_single_task(
-                task_name,
-                task_definition,
-                ctx.working_dir,
-                args,
-                ctx.audit_mode,
-                ctx.capture_output,
-            )
-            .await?;
+            let status = run_task(ctx, task_name, task_definition, args).await?;
+            *ran_status = Some(status);
+            // Failures are only reused for as long as `cacheFailures` says
+            let expires_at = task_definition
+                .cache
+                .failure_ttl
+                .filter(|_| !status.success())
+                .map(|ttl| std::time::SystemTime::now() + ttl);
+            let output_files = ctx
+                .action_cache
+                .store_output_files(&task_definition.outputs, ctx.working_dir)
+                .await?;
 
             // Create ActionResult for caching
-            // TODO: Fix when ActionResult is properly exposed
-            Ok(cuenv_cache::concurrent::action::ActionResult {
-                exit_code,
+            Ok(ActionResult {
+                exit_code: status.code(),
                 stdout_hash: None, // Not captured in current implementation
                 stderr_hash: None, // Not captured in current implementation
-                output_files: std::collections::HashMap::new(),
+                output_files,
                 executed_at: std::time::SystemTime::now(),
                 duration_ms: 0, // Not tracked in current implementation
+                environment,
+                run_id: Some(run_id),
+                expires_at,
             })
         })
         .await?;
 
-    // Update cache manager statistics for backward compatibility
-    if result.exit_code == 0 {
-        // TODO: Add tracing when moved to workspace
-        // task_progress(task_name, Some(100), "Task completed successfully");
-        tracing::info!(task_name = %task_name, "Task completed successfully");
-    } else {
-        tracing::error!(
-            task_name = %task_name,
-            exit_code = %result.exit_code,
-            "Task failed with exit code"
+    Ok((result, ran))
+}
+
+/// Run a task, resolving the lazy secrets it references just before it starts
+async fn run_task(
+    ctx: &TaskExecutionContext<'_>,
+    task_name: &str,
+    task_definition: &TaskDefinition,
+    args: &[String],
+) -> Result<ExitStatus> {
+    let referenced = referenced_variables(task_definition.get_execution_content());
+    let secrets = ctx
+        .env_manager
+        .resolve_deferred(referenced.iter().map(String::as_str))?;
+
+    let (exit_status, audit_report) = runner::execute_single_task(
+        ctx.run,
+        task_definition,
+        ctx.working_dir,
+        args,
+        &secrets,
+        ctx.audit_mode,
+        ctx.capture_output,
+    )
+    .await?;
+    if let Some(report) = &audit_report {
+        save_audit_report(ctx, task_name, report);
+    }
+    record_env_usage(ctx, task_name, audit_report.as_ref());
+    Ok(exit_status)
+}
+
+/// Keep the report of an audited run for `cuenv security allow`
+fn save_audit_report(ctx: &TaskExecutionContext<'_>, task_name: &str, report: &AuditReport) {
+    let path = get_audit_report_path(ctx.working_dir, task_name);
+    let saved = report.to_json().and_then(|json| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::file_system(dir, "create audit directory", e))?;
+        }
+        write_atomic_string(&path, &json)
+    });
+    if let Err(e) = saved {
+        tracing::warn!(task_name = %task_name, "Failed to save audit report: {e}");
+    }
+}
+
+/// Remember which environment variables an audited run was seen to use
+fn record_env_usage(
+    ctx: &TaskExecutionContext<'_>,
+    task_name: &str,
+    audit_report: Option<&AuditReport>,
+) {
+    let Some(report) = audit_report else {
+        return;
+    };
+    let accessed: Vec<String> = report
+        .accessed_files
+        .iter()
+        .chain(&report.network_connections)
+        .cloned()
+        .collect();
+    let observed = observed_variables(ctx.env_manager.get_cue_vars(), &accessed);
+    if observed.is_empty() {
+        return;
+    }
+
+    tracing::info!(
+        task_name = %task_name,
+        variables = ?observed,
+        "Audit observed environment variables; they now key the task's cache"
+    );
+    if let Err(e) = ctx
+        .action_cache
+        .record_env_usage(task_name, ctx.working_dir, &observed)
+    {
+        tracing::warn!(task_name = %task_name, "Failed to record environment usage: {e}");
+    }
+}
+
+fn describe_environment(environment: Option<&str>) -> String {
+    environment.map_or_else(|| "(default)".to_string(), |name| format!("'{name}'"))
+}
+
+fn describe_time(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_cache::concurrent::action::ActionCache;
+    use cuenv_cache::manager::RunLog;
+    use cuenv_cache::mode::VerifyMode;
+    use cuenv_cache::ContentAddressedStore;
+    use cuenv_core::events::TaskRunEvents;
+    use cuenv_core::TaskExecutionMode;
+    use cuenv_env::manager::EnvManager;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    /// What a task execution context borrows, kept in a temporary directory
+    struct Fixture {
+        dir: TempDir,
+        action_cache: ActionCache,
+        env_manager: EnvManager,
+        run: TaskRunEvents,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let dir = TempDir::new().unwrap();
+            let cas = Arc::new(ContentAddressedStore::new(dir.path().join("cas"), 4096).unwrap());
+            let action_cache = ActionCache::new(cas, 0, &dir.path().join("cache")).unwrap();
+            Self {
+                dir,
+                action_cache,
+                env_manager: EnvManager::new(),
+                run: TaskRunEvents::start("build").await,
+            }
+        }
+
+        /// Run `definition`, which must succeed, returning whether it came
+        /// from the cache
+        async fn run(
+            &self,
+            cache_config: &CacheConfiguration,
+            definition: &TaskDefinition,
+        ) -> bool {
+            let run = self.run_task(cache_config, definition).await;
+            assert!(run.status.success());
+            run.cached
+        }
+
+        async fn run_task(
+            &self,
+            cache_config: &CacheConfiguration,
+            definition: &TaskDefinition,
+        ) -> TaskRun {
+            let ctx = TaskExecutionContext {
+                cache_config,
+                working_dir: &definition.working_directory,
+                action_cache: &self.action_cache,
+                env_manager: &self.env_manager,
+                audit_mode: false,
+                capture_output: true,
+                run: &self.run,
+            };
+            execute_single_task_with_cache(&ctx, "build", definition, &[])
+                .await
+                .unwrap()
+        }
+
+        /// How often the command of [`definition`] actually ran
+        fn runs(&self) -> usize {
+            runs_in(self.dir.path())
+        }
+    }
+
+    /// How often a task writing `runs` in `dir` ran
+    fn runs_in(dir: &Path) -> usize {
+        std::fs::read_to_string(dir.join("runs"))
+            .unwrap()
+            .lines()
+            .count()
+    }
+
+    /// Run `definition` twice, returning whether each run came from the
+    /// cache and how often the command actually ran
+    async fn run_twice(
+        cache_config: &CacheConfiguration,
+        definition: impl Fn(&TempDir) -> TaskDefinition,
+    ) -> (Fixture, bool, bool, usize) {
+        let fixture = Fixture::new().await;
+        let definition = definition(&fixture.dir);
+        let first = fixture.run(cache_config, &definition).await;
+        let second = fixture.run(cache_config, &definition).await;
+        let runs = fixture.runs();
+        (fixture, first, second, runs)
+    }
+
+    fn definition(dir: &TempDir, cached: bool) -> TaskDefinition {
+        let mut definition = TaskDefinition::new(
+            "build".to_string(),
+            TaskExecutionMode::Command {
+                command: "echo ran >> runs".to_string(),
+            },
+            dir.path().to_path_buf(),
         );
+        definition.cache.enabled = cached;
+        definition
+    }
+
+    fn cached(dir: &TempDir) -> TaskDefinition {
+        definition(dir, true)
+    }
+
+    #[tokio::test]
+    async fn test_cached_task_is_reused() {
+        let config = CacheConfiguration::default();
+        let (fixture, first, second, runs) = run_twice(&config, cached).await;
+        assert_eq!((first, second, runs), (false, true, 1));
+
+        // The reuse is recorded too, so the run's manifest lists the entry
+        let entries = RunLog::new(&fixture.dir.path().join("cache"))
+            .load()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].key, entries[0].key);
+        assert_eq!(entries[1].reused_from, entries[0].run_id);
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_off_unless_task_and_global_setting_allow_it() {
+        let config = CacheConfiguration::default();
+        let (_, first, second, runs) = run_twice(&config, |dir| definition(dir, false)).await;
+        assert_eq!((first, second, runs), (false, false, 2));
+
+        let mut config = CacheConfiguration::default();
+        config.global.enabled = false;
+        let (_, first, second, runs) = run_twice(&config, cached).await;
+        assert_eq!((first, second, runs), (false, false, 2));
+    }
+
+    /// Files under `dir` holding exactly `content`
+    fn files_holding(dir: &Path, content: &[u8]) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .flat_map(|path| {
+                if path.is_dir() {
+                    files_holding(&path, content)
+                } else if std::fs::read(&path).is_ok_and(|bytes| bytes == content) {
+                    vec![path]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_restore_verified_outputs() {
+        let fixture = Fixture::new().await;
+        let (work, output) = (fixture.dir.path(), fixture.dir.path().join("out.txt"));
+        let mut definition = definition(&fixture.dir, true);
+        definition.execution_mode = TaskExecutionMode::Command {
+            command: "echo ran >> runs && echo built > out.txt".to_string(),
+        };
+        definition.outputs = vec!["out.txt".to_string()];
+        let mut config = CacheConfiguration::default();
+        config.global.verify = Some(VerifyMode::Full);
+
+        assert!(!fixture.run(&config, &definition).await);
+        std::fs::remove_file(&output).unwrap();
+        assert!(fixture.run(&config, &definition).await);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "built\n");
+        assert_eq!(fixture.runs(), 1);
+
+        // A corrupted object is quarantined and the task runs again
+        let objects = files_holding(&work.join("cas"), b"built\n");
+        assert_eq!(objects.len(), 1);
+        std::fs::write(&objects[0], "BUILT\n").unwrap();
+        std::fs::remove_file(&output).unwrap();
+        assert!(!fixture.run(&config, &definition).await);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "built\n");
+        assert_eq!(fixture.runs(), 2);
+        let quarantined = std::fs::read_dir(work.join("cas/quarantine")).unwrap();
+        assert_eq!(quarantined.count(), 1);
     }
 
-    Ok(result.exit_code)
+    #[tokio::test]
+    async fn test_package_inputs_key_the_cache() {
+        let fixture = Fixture::new().await;
+        let root = fixture.dir.path();
+        std::fs::create_dir(root.join("cue.mod")).unwrap();
+        let (lib, app) = (root.join("projects/lib"), root.join("projects/app"));
+        for dir in [&lib, &app] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("env.cue"), "package env").unwrap();
+        }
+        std::fs::create_dir(lib.join("src")).unwrap();
+        std::fs::write(lib.join("src/lib.rs"), "fn lib() {}").unwrap();
+
+        let mut definition = definition(&fixture.dir, true);
+        definition.working_directory = app.clone();
+        definition.inputs = vec!["pkg://projects:lib/src/**".to_string()];
+        let config = CacheConfiguration::default();
+
+        assert!(!fixture.run(&config, &definition).await);
+        assert!(fixture.run(&config, &definition).await);
+        std::fs::write(lib.join("src/lib.rs"), "fn lib() { changed() }").unwrap();
+        assert!(!fixture.run(&config, &definition).await);
+        assert_eq!(runs_in(&app), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cached_failures_are_reused_for_their_time() {
+        let fixture = Fixture::new().await;
+        let mut definition = definition(&fixture.dir, true);
+        definition.execution_mode = TaskExecutionMode::Command {
+            command: "echo ran >> runs; exit 3".to_string(),
+        };
+        let config = CacheConfiguration::default();
+        let failed = TaskRun {
+            status: ExitStatus::Code(3),
+            cached: false,
+        };
+
+        // Without cacheFailures a failure runs again
+        assert_eq!(fixture.run_task(&config, &definition).await, failed);
+        assert_eq!(fixture.run_task(&config, &definition).await, failed);
+        assert_eq!(fixture.runs(), 2);
+
+        definition.cache.failure_ttl = Some(std::time::Duration::from_secs(600));
+        assert_eq!(fixture.run_task(&config, &definition).await, failed);
+        assert_eq!(
+            fixture.run_task(&config, &definition).await,
+            TaskRun {
+                cached: true,
+                ..failed
+            }
+        );
+        assert_eq!(fixture.runs(), 3);
+    }
 }
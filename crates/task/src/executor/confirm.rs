@@ -0,0 +1,203 @@
+//! Tasks that ask for confirmation before they run
+//!
+//! A task declaring `confirm` in the loaded environment is refused until
+//! the caller has asked the user, or been told to assume yes, and
+//! confirmed it with [`TaskExecutor::confirm`].
+
+use super::strategies::task_config_name;
+use super::TaskExecutor;
+use cuenv_config::TaskConfig;
+use cuenv_core::{Error, Result};
+
+impl TaskExecutor {
+    /// Tasks running for `task_names`, dependencies included, that ask for
+    /// confirmation and do not have it yet, with their questions
+    pub fn pending_confirmations(&self, task_names: &[String]) -> Result<Vec<(String, String)>> {
+        let tasks: Vec<String> = if self.monorepo_registry.is_some() {
+            self.build_execution_plan(task_names)?
+                .tasks
+                .into_keys()
+                .collect()
+        } else {
+            self.build_unified_dag(task_names)?
+                .get_flattened_tasks()
+                .iter()
+                .filter(|task| !task.is_barrier)
+                .map(|task| task.id.clone())
+                .collect()
+        };
+        Ok(self.unconfirmed(tasks.iter().map(String::as_str)))
+    }
+
+    /// Let `tasks` run although they ask for confirmation
+    pub fn confirm(&self, tasks: impl IntoIterator<Item = String>) {
+        if let Ok(mut confirmed) = self.confirmed_tasks.lock() {
+            confirmed.extend(tasks);
+        }
+    }
+
+    /// Refuse to run while one of `tasks` is still unconfirmed
+    pub(crate) fn check_confirmed<'a>(
+        &self,
+        tasks: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        match self.unconfirmed(tasks).into_iter().next() {
+            Some((task, question)) => Err(Error::configuration(format!(
+                "Task '{task}' asks for confirmation: {question}\nRun it with --yes to confirm"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn unconfirmed<'a>(&self, tasks: impl IntoIterator<Item = &'a str>) -> Vec<(String, String)> {
+        let confirmed = self
+            .confirmed_tasks
+            .lock()
+            .map(|confirmed| confirmed.clone())
+            .unwrap_or_default();
+        let environment = self.env_manager.environment();
+        let mut pending: Vec<(String, String)> = tasks
+            .into_iter()
+            .filter(|task| !confirmed.contains(*task))
+            .filter_map(|task| {
+                let question = self
+                    .task_config(task)?
+                    .confirm
+                    .as_ref()?
+                    .prompt(environment)?;
+                Some((task.to_string(), question.to_string()))
+            })
+            .collect();
+        pending.sort();
+        pending
+    }
+
+    pub(super) fn task_config(&self, task: &str) -> Option<&TaskConfig> {
+        let local = self.env_manager.get_task(&task_config_name(task));
+        local.or_else(|| {
+            self.monorepo_registry
+                .as_ref()?
+                .get_task(task)
+                .map(|registered| &registered.config)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_config::{ConfirmConfig, TaskCollection, TaskNode};
+    use cuenv_env::manager::EnvManager;
+    use indexmap::IndexMap;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_unconfirmed_tasks_are_refused() {
+        let task =
+            |command: &str, dependencies: &[&str], confirm: Option<ConfirmConfig>| TaskConfig {
+                command: Some(command.to_string()),
+                dependencies: Some(dependencies.iter().map(|dep| dep.to_string()).collect()),
+                confirm,
+                ..TaskConfig::default()
+            };
+        let tasks = HashMap::from([
+            (
+                "reset".to_string(),
+                task(
+                    "true",
+                    &[],
+                    Some(ConfirmConfig::Always("Drop the database?".to_string())),
+                ),
+            ),
+            ("deploy".to_string(), task("true", &["reset"], None)),
+            (
+                "seed".to_string(),
+                task(
+                    "true",
+                    &[],
+                    Some(ConfirmConfig::Environments {
+                        message: "Seed production?".to_string(),
+                        environments: vec!["production".to_string()],
+                    }),
+                ),
+            ),
+        ]);
+        let mut manager = EnvManager::new();
+        manager.set_tasks_for_testing(tasks, Default::default(), HashMap::new());
+        let temp_dir = TempDir::new().unwrap();
+        let cache_config = cuenv_cache::CacheConfig {
+            base_dir: temp_dir.path().join(".cache"),
+            max_size: 1024 * 1024,
+            mode: cuenv_cache::CacheMode::ReadWrite,
+            inline_threshold: 4096,
+            env_filter: Default::default(),
+            task_env_filters: HashMap::new(),
+        };
+        let executor =
+            TaskExecutor::new_with_config(manager, temp_dir.path().to_path_buf(), cache_config)
+                .await
+                .unwrap();
+
+        // Dependencies ask too; seed only asks in production
+        let deploy = ["deploy".to_string()];
+        let pending = executor.pending_confirmations(&deploy).unwrap();
+        assert_eq!(
+            pending,
+            [("reset".to_string(), "Drop the database?".to_string())]
+        );
+        assert!(executor
+            .pending_confirmations(&["seed".to_string()])
+            .unwrap()
+            .is_empty());
+
+        let err = executor.execute_tasks_unified(&deploy, &[], false).await;
+        assert!(err.unwrap_err().to_string().contains("--yes"));
+
+        executor.confirm(pending.into_iter().map(|(task, _)| task));
+        assert!(executor.pending_confirmations(&deploy).unwrap().is_empty());
+        assert_eq!(
+            executor
+                .execute_tasks_unified(&deploy, &[], false)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_group_members_ask_for_confirmation() {
+        let reset = TaskConfig {
+            command: Some("true".to_string()),
+            confirm: Some(ConfirmConfig::Always("Drop the database?".to_string())),
+            ..TaskConfig::default()
+        };
+        let tasks = HashMap::from([("ci.reset".to_string(), reset.clone())]);
+        let task_nodes = IndexMap::from([(
+            "ci".to_string(),
+            TaskNode::Group {
+                tasks: TaskCollection::Parallel(IndexMap::from([(
+                    "reset".to_string(),
+                    TaskNode::Task(Box::new(reset)),
+                )])),
+                description: None,
+                setup: None,
+                teardown: None,
+            },
+        )]);
+        let mut manager = EnvManager::new();
+        manager.set_tasks_for_testing(tasks, task_nodes, HashMap::new());
+        let temp_dir = TempDir::new().unwrap();
+        let executor = TaskExecutor::new(manager, temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let ci = ["ci".to_string()];
+        assert_eq!(
+            executor.pending_confirmations(&ci).unwrap(),
+            [("ci:reset".to_string(), "Drop the database?".to_string())]
+        );
+        let err = executor.execute_tasks_unified(&ci, &[], false).await;
+        assert!(err.unwrap_err().to_string().contains("--yes"));
+    }
+}
@@ -1,5 +1,7 @@
 use cuenv_cache::concurrent::action::ActionCache;
 use cuenv_cache::config::CacheConfiguration;
+use cuenv_core::events::TaskRunEvents;
+use cuenv_env::manager::EnvManager;
 use std::path::Path;
 
 /// Context for task execution to reduce function parameter count
@@ -7,6 +9,9 @@ pub struct TaskExecutionContext<'a> {
     pub cache_config: &'a CacheConfiguration,
     pub working_dir: &'a Path,
     pub action_cache: &'a ActionCache,
+    pub env_manager: &'a EnvManager,
     pub audit_mode: bool,
     pub capture_output: bool,
+    /// The run the task's events are published for
+    pub run: &'a TaskRunEvents,
 }
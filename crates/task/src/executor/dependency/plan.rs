@@ -1,7 +1,9 @@
 use crate::executor::graph;
 use crate::executor::plan::TaskExecutionPlan;
 use crate::executor::TaskExecutor;
+use crate::resolution::similar_task_names;
 use crate::MonorepoTaskRegistry;
+use cuenv_core::suggestions::with_suggestions;
 use cuenv_core::{Error, Result};
 use std::collections::{HashMap, HashSet};
 
@@ -20,8 +22,9 @@ impl TaskExecutor {
         for task_name in task_names {
             if !all_task_configs.contains_key(task_name) && !all_task_nodes.contains_key(task_name)
             {
-                return Err(Error::configuration(format!(
-                    "Task or task group '{task_name}' not found"
+                return Err(Error::configuration(with_suggestions(
+                    format!("Task or task group '{task_name}' not found"),
+                    &similar_task_names(all_task_configs, task_name),
                 )));
             }
         }
@@ -76,9 +79,12 @@ impl TaskExecutor {
 
         // Validate and collect tasks from registry
         for task_name in task_names {
-            let _task = registry
-                .get_task(task_name)
-                .ok_or_else(|| Error::configuration(format!("Task '{task_name}' not found")))?;
+            let _task = registry.get_task(task_name).ok_or_else(|| {
+                Error::configuration(with_suggestions(
+                    format!("Task '{task_name}' not found"),
+                    &registry.similar_tasks(task_name),
+                ))
+            })?;
 
             super::monorepo::collect_monorepo_dependencies(
                 task_name,
@@ -0,0 +1,87 @@
+//! Tasks marked deprecated
+//!
+//! A task declaring `deprecated`, or annotated `@deprecated("...")` in CUE,
+//! still runs, but each run warns about it, dependencies included, and
+//! publishes a `TaskDeprecated` event so dashboards and logs show what
+//! still relies on it.
+
+use super::TaskExecutor;
+use cuenv_core::events::TaskRunEvents;
+
+impl TaskExecutor {
+    /// Warn about each of `tasks` that is deprecated
+    pub(crate) async fn warn_deprecated<'a>(&self, tasks: impl IntoIterator<Item = &'a str>) {
+        for (task, message) in self.deprecated(tasks) {
+            eprintln!("⚠ {message}");
+            TaskRunEvents::deprecated(&task, &message).await;
+        }
+    }
+
+    /// Deprecated tasks among `tasks`, with their warnings, sorted by name
+    fn deprecated<'a>(&self, tasks: impl IntoIterator<Item = &'a str>) -> Vec<(String, String)> {
+        let mut deprecated: Vec<(String, String)> = tasks
+            .into_iter()
+            .filter_map(|task| {
+                let message = self.task_config(task)?.deprecation_message(task)?;
+                Some((task.to_string(), message))
+            })
+            .collect();
+        deprecated.sort();
+        deprecated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_config::TaskConfig;
+    use cuenv_env::manager::EnvManager;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_deprecated_dependencies_are_reported() {
+        let tasks = HashMap::from([
+            (
+                "build".to_string(),
+                TaskConfig {
+                    command: Some("true".to_string()),
+                    deprecated: Some("use build2".to_string()),
+                    ..TaskConfig::default()
+                },
+            ),
+            (
+                "deploy".to_string(),
+                TaskConfig {
+                    command: Some("true".to_string()),
+                    dependencies: Some(vec!["build".to_string()]),
+                    ..TaskConfig::default()
+                },
+            ),
+        ]);
+        let mut manager = EnvManager::new();
+        manager.set_tasks_for_testing(tasks, Default::default(), HashMap::new());
+        let temp_dir = TempDir::new().unwrap();
+        let cache_config = cuenv_cache::CacheConfig {
+            base_dir: temp_dir.path().join(".cache"),
+            max_size: 1024 * 1024,
+            mode: cuenv_cache::CacheMode::ReadWrite,
+            inline_threshold: 4096,
+            env_filter: Default::default(),
+            task_env_filters: HashMap::new(),
+        };
+        let executor =
+            TaskExecutor::new_with_config(manager, temp_dir.path().to_path_buf(), cache_config)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            executor.deprecated(["deploy", "build"]),
+            [(
+                "build".to_string(),
+                "Task 'build' is deprecated: use build2".to_string()
+            )]
+        );
+        assert!(executor.deprecated(["deploy"]).is_empty());
+    }
+}
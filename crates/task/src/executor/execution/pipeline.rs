@@ -1,6 +1,10 @@
+use super::shards::ShardTracker;
 use crate::executor::TaskExecutor;
-use cuenv_core::{Error, Result};
+use crate::shard::ShardNode;
+use cuenv_core::{Error, ExitStatus, Result};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::task::JoinSet;
 
 impl TaskExecutor {
@@ -14,6 +18,10 @@ impl TaskExecutor {
     ) -> Result<i32> {
         // Build execution plan
         let plan = self.build_execution_plan(task_names)?;
+        self.check_confirmed(plan.tasks.keys().map(String::as_str))?;
+        self.warn_deprecated(plan.tasks.keys().map(String::as_str))
+            .await;
+        self.warn_unmatched_stubs(plan.tasks.keys().map(String::as_str));
 
         // Create pipeline span for the entire execution
         // TODO: Add tracing when moved to workspace
@@ -73,11 +81,14 @@ impl TaskExecutor {
                         task_args: args.to_vec(),
                         failed_tasks: Arc::clone(&failed_tasks),
                         action_cache: Arc::clone(&self.action_cache),
-                        _env_manager: self.env_manager.clone(),
+                        env_manager: self.env_manager.clone(),
                         cache_config: self.cache_config.clone(),
                         executed_tasks: Arc::clone(&self.executed_tasks),
+                        cached_tasks: Arc::clone(&self.cached_tasks),
+                        output_values: self.output_values.clone(),
                         audit_mode,
                         capture_output,
+                        stub: self.stub(task_name),
                     },
                 );
             }
@@ -89,17 +100,11 @@ impl TaskExecutor {
                 }
             }
 
-            // Check if any tasks failed
-            let failed = failed_tasks
-                .lock()
-                .map_err(|e| Error::configuration(format!("Failed to acquire lock: {e}")))?;
-            if !failed.is_empty() {
-                let failed_names: Vec<&str> =
-                    failed.iter().map(|(name, _)| name.as_str()).collect();
-                return Err(Error::configuration(format!(
-                    "Tasks failed: {}",
-                    failed_names.join(", ")
-                )));
+            // Check if any tasks failed; the first failure is the run's status
+            let mut failures = Failures::default();
+            failures.record(&failed_tasks)?;
+            if let Some(status) = failures.report() {
+                return Ok(status.code());
             }
 
             drop(level_guard);
@@ -111,6 +116,10 @@ impl TaskExecutor {
     }
 
     /// Execute tasks using the unified DAG system - this ensures consistent ordering
+    ///
+    /// Once a level fails, later levels only run group teardown nodes whose
+    /// group has started; everything else is reported as skipped and the
+    /// first failure is returned after the teardowns finish.
     pub async fn execute_tasks_with_unified_dag(
         &self,
         task_names: &[String],
@@ -119,6 +128,25 @@ impl TaskExecutor {
     ) -> Result<i32> {
         // Build unified DAG
         let dag = self.build_unified_dag(task_names)?;
+        self.check_confirmed(
+            dag.get_flattened_tasks()
+                .iter()
+                .filter(|task| !task.is_barrier)
+                .map(|task| task.id.as_str()),
+        )?;
+        self.warn_deprecated(
+            dag.get_flattened_tasks()
+                .iter()
+                .filter(|task| !task.is_barrier)
+                .map(|task| task.id.as_str()),
+        )
+        .await;
+        self.warn_unmatched_stubs(
+            dag.get_flattened_tasks()
+                .iter()
+                .filter(|task| !task.is_barrier)
+                .map(|task| task.id.as_str()),
+        );
         let levels = dag.get_execution_levels()?;
 
         tracing::info!(
@@ -128,6 +156,10 @@ impl TaskExecutor {
             "Starting unified DAG task execution"
         );
 
+        let mut started = HashSet::new();
+        let mut failures = Failures::default();
+        let mut shards = ShardTracker::default();
+
         // Execute tasks level by level using the DAG
         for (level_idx, level) in levels.iter().enumerate() {
             tracing::info!(
@@ -137,6 +169,7 @@ impl TaskExecutor {
             );
             let mut join_set = JoinSet::new();
             let failed_tasks = Arc::new(Mutex::new(Vec::with_capacity(level.len())));
+            let mut running_shards = HashMap::new();
 
             // Launch all tasks in this level concurrently
             for task_id in level {
@@ -146,6 +179,23 @@ impl TaskExecutor {
                     continue;
                 }
 
+                if !failures.is_empty() && !dag.runs_after_failure(task_id, &started) {
+                    super::task::publish_task_skipped(task_id, "an earlier task failed").await;
+                    continue;
+                }
+
+                let flattened = dag.get_flattened_task(task_id);
+                let shard = flattened.and_then(|t| t.shard.clone());
+
+                // A sharded task's own node only reports on its shards
+                if shard == Some(ShardNode::Merge) {
+                    let count = flattened.map_or(0, |t| t.dependencies.len());
+                    shards
+                        .report(task_id, count, dag.get_task_definition(task_id))
+                        .await;
+                    continue;
+                }
+
                 // Get the task definition from the DAG
                 let task_definition = match dag.get_task_definition(task_id) {
                     Some(definition) => definition.clone(),
@@ -156,6 +206,29 @@ impl TaskExecutor {
                     }
                 };
 
+                let task_args = match &shard {
+                    Some(ShardNode::Shard { task, index, count }) => {
+                        match shards.shard_args(
+                            &dag,
+                            task,
+                            (*index, *count),
+                            &task_definition,
+                            args,
+                        )? {
+                            Some(shard_args) => shard_args,
+                            None => {
+                                super::task::publish_task_skipped(
+                                    task_id,
+                                    "no tests in this shard",
+                                )
+                                .await;
+                                continue;
+                            }
+                        }
+                    }
+                    _ => args.to_vec(),
+                };
+
                 // Determine working directory based on whether this is a cross-package task
                 let working_dir = if let Some(ref registry) = self.monorepo_registry {
                     if let Some(task) = registry.get_task(task_id) {
@@ -167,48 +240,80 @@ impl TaskExecutor {
                     self.working_dir.clone()
                 };
 
-                super::task::spawn_task_execution(
+                started.insert(task_id.clone());
+                let handle = super::task::spawn_task_execution(
                     &mut join_set,
                     super::task::TaskExecutionParams {
                         task_name: task_id.clone(),
                         task_definition,
                         working_dir,
-                        task_args: args.to_vec(),
+                        task_args,
                         failed_tasks: Arc::clone(&failed_tasks),
                         action_cache: Arc::clone(&self.action_cache),
-                        _env_manager: self.env_manager.clone(),
+                        env_manager: self.env_manager.clone(),
                         cache_config: self.cache_config.clone(),
                         executed_tasks: Arc::clone(&self.executed_tasks),
+                        cached_tasks: Arc::clone(&self.cached_tasks),
+                        output_values: self.output_values.clone(),
                         audit_mode,
-                        capture_output: false, // For now, unified DAG doesn't support output capture
+                        capture_output: self.capture_output,
+                        stub: self.stub(task_id),
                     },
                 );
+                if let Some(ShardNode::Shard { task, index, .. }) = shard {
+                    running_shards.insert(handle.id(), (task, index, Instant::now()));
+                }
             }
 
-            // Wait for all tasks in this level to complete
-            while let Some(result) = join_set.join_next().await {
-                if let Err(e) = result {
-                    return Err(Error::configuration(format!("Task execution failed: {e}")));
+            // Wait for all tasks in this level to complete, timing shards
+            while let Some(result) = join_set.join_next_with_id().await {
+                let (id, code) = result
+                    .map_err(|e| Error::configuration(format!("Task execution failed: {e}")))?;
+                if let Some((task, index, start)) = running_shards.remove(&id) {
+                    shards.finish(&task, index, start.elapsed(), code == 0);
                 }
             }
 
-            // Check if any tasks failed
-            let failed = failed_tasks
-                .lock()
-                .map_err(|e| Error::configuration(format!("Failed to acquire lock: {e}")))?;
-            if !failed.is_empty() {
-                let failed_names: Vec<&str> =
-                    failed.iter().map(|(name, _)| name.as_str()).collect();
-                return Err(Error::configuration(format!(
-                    "Tasks failed: {}",
-                    failed_names.join(", ")
-                )));
-            }
+            // Failures of teardowns are reported along with the first one
+            failures.record(&failed_tasks)?;
 
             tracing::info!(level = %level_idx, "Completed execution level");
         }
 
+        if let Some(status) = failures.report() {
+            return Ok(status.code());
+        }
+
         tracing::info!("Completed unified DAG task execution");
         Ok(0)
     }
 }
+
+/// Tasks that failed during a run, in the order they finished
+#[derive(Default)]
+struct Failures(Vec<(String, ExitStatus)>);
+
+impl Failures {
+    /// Add the failures of a level
+    fn record(&mut self, failed_tasks: &Mutex<Vec<(String, ExitStatus)>>) -> Result<()> {
+        let mut failed = failed_tasks
+            .lock()
+            .map_err(|e| Error::configuration(format!("Failed to acquire lock: {e}")))?;
+        self.0.append(&mut failed);
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Log the tasks that failed and return the status of the first one,
+    /// which is the run's status
+    fn report(&self) -> Option<ExitStatus> {
+        let (_, status) = self.0.first()?;
+        let names: Vec<&str> = self.0.iter().map(|(name, _)| name.as_str()).collect();
+        let error = Error::configuration(format!("Tasks failed: {}", names.join(", ")));
+        tracing::error!(%status, "{error}");
+        Some(*status)
+    }
+}
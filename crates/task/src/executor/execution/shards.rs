@@ -0,0 +1,134 @@
+//! Running the shards of sharded tasks
+
+use crate::executor::UnifiedTaskDAG;
+use crate::shard::{list_tests, summarize, ShardHistory, ShardPlan, ShardRun};
+use cuenv_config::TaskNode;
+use cuenv_core::{Error, Result, TaskDefinition};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Test plans and shard timings of the sharded tasks of one run
+#[derive(Default)]
+pub(super) struct ShardTracker {
+    history: Option<ShardHistory>,
+    plans: HashMap<String, ShardPlan>,
+    runs: HashMap<String, Vec<ShardRun>>,
+}
+
+impl ShardTracker {
+    /// Arguments of the `index`th of `count` shards of `task`: the run's
+    /// arguments followed by the shard's tests, quoted. The task's tests are
+    /// listed and dealt out on its first shard; `None` when the shard has
+    /// no tests.
+    pub fn shard_args(
+        &mut self,
+        dag: &UnifiedTaskDAG,
+        task: &str,
+        (index, count): (usize, usize),
+        definition: &TaskDefinition,
+        args: &[String],
+    ) -> Result<Option<Vec<String>>> {
+        if !self.plans.contains_key(task) {
+            let plan = self.plan(dag, task, count, definition)?;
+            self.plans.insert(task.to_string(), plan);
+        }
+        let tests = self
+            .plans
+            .get(task)
+            .map_or(&[][..], |plan| plan.tests(index));
+        if tests.is_empty() {
+            return Ok(None);
+        }
+
+        let quoted = tests
+            .iter()
+            .map(|test| {
+                shlex::try_quote(test)
+                    .map(|quoted| quoted.into_owned())
+                    .map_err(|e| Error::configuration(format!("Invalid test name '{test}': {e}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(args.iter().cloned().chain(quoted).collect()))
+    }
+
+    fn plan(
+        &mut self,
+        dag: &UnifiedTaskDAG,
+        task: &str,
+        count: usize,
+        definition: &TaskDefinition,
+    ) -> Result<ShardPlan> {
+        let test_list = match dag.get_flattened_task(task).map(|t| &t.node) {
+            Some(TaskNode::Task(config)) => config.test_list.clone(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Error::configuration(format!("Shardable task '{task}' must define 'testList'"))
+        })?;
+        let tests = list_tests(&test_list, &definition.shell, &definition.working_directory)?;
+        let key = ShardHistory::key(&definition.working_directory, task);
+        let durations = self
+            .history
+            .get_or_insert_with(ShardHistory::default)
+            .durations(&key);
+        tracing::info!(
+            task,
+            tests = tests.len(),
+            shards = count,
+            "Planned test shards"
+        );
+        Ok(ShardPlan::new(&tests, count, &durations))
+    }
+
+    /// Record how the `index`th shard of `task` went
+    pub fn finish(&mut self, task: &str, index: usize, elapsed: Duration, success: bool) {
+        let tests = self
+            .plans
+            .get(task)
+            .map_or(0, |plan| plan.tests(index).len());
+        self.runs
+            .entry(task.to_string())
+            .or_default()
+            .push(ShardRun {
+                index,
+                tests,
+                elapsed,
+                success,
+            });
+    }
+
+    /// Report the shards of `task` as the task's own result and remember
+    /// how long they took
+    pub async fn report(&mut self, task: &str, count: usize, definition: Option<&TaskDefinition>) {
+        let mut runs = self.runs.remove(task).unwrap_or_default();
+        runs.sort_by_key(|run| run.index);
+        let summary = summarize(&runs, count);
+        let success = runs.iter().all(|run| run.success);
+        tracing::info!(task, "{summary}");
+
+        if let (Some(plan), Some(definition)) = (self.plans.get(task), definition) {
+            let history = self.history.get_or_insert_with(ShardHistory::default);
+            history.record(
+                &ShardHistory::key(&definition.working_directory, task),
+                plan,
+                &runs,
+            );
+            if let Err(e) = history.save() {
+                tracing::debug!(error = %e, "Failed to save shard history");
+            }
+        }
+
+        let run = cuenv_core::events::TaskRunEvents::start(task).await;
+        run.progress(summary.clone()).await;
+        if success {
+            let duration_ms = runs
+                .iter()
+                .map(|run| run.elapsed.as_millis() as u64)
+                .max()
+                .unwrap_or_default();
+            run.completed(duration_ms).await;
+        } else {
+            run.failed(summary, None).await;
+        }
+    }
+}
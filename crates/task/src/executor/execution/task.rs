@@ -1,14 +1,17 @@
-use crate::executor::cache;
+use crate::executor::cache::{self, TaskRun};
 use crate::executor::context::TaskExecutionContext;
+use crate::executor::outputs::OutputValues;
+use crate::publish::PublishState;
 use cuenv_cache::concurrent::action::ActionCache;
 use cuenv_cache::config::CacheConfiguration;
-use cuenv_core::TaskDefinition;
+use cuenv_core::events::TaskRunEvents;
+use cuenv_core::{ExitStatus, Result, TaskDefinition};
 use cuenv_env::manager::EnvManager;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use tokio::task::JoinSet;
+use tokio::task::{AbortHandle, JoinSet};
 use tracing::Instrument;
 
 /// Parameters for task execution
@@ -17,43 +20,63 @@ pub struct TaskExecutionParams {
     pub task_definition: TaskDefinition,
     pub working_dir: PathBuf,
     pub task_args: Vec<String>,
-    pub failed_tasks: Arc<Mutex<Vec<(String, i32)>>>,
+    pub failed_tasks: Arc<Mutex<Vec<(String, ExitStatus)>>>,
     pub action_cache: Arc<ActionCache>,
-    pub _env_manager: EnvManager,
+    pub env_manager: EnvManager,
     pub cache_config: CacheConfiguration,
     pub executed_tasks: Arc<Mutex<HashSet<String>>>,
+    pub cached_tasks: Arc<Mutex<HashSet<String>>>,
+    pub output_values: OutputValues,
     pub audit_mode: bool,
     pub capture_output: bool,
+    /// Command the task runs instead of its own, when it is stubbed
+    pub stub: Option<String>,
 }
 
 /// Spawn a task execution
-pub fn spawn_task_execution(join_set: &mut JoinSet<i32>, params: TaskExecutionParams) {
+pub fn spawn_task_execution(
+    join_set: &mut JoinSet<i32>,
+    params: TaskExecutionParams,
+) -> AbortHandle {
     // Create task span
     // TODO: Add tracing when moved to workspace
-    let task_span = tracing::info_span!("task", name = params.task_name.as_str());
+    let task_span = tracing::info_span!(
+        "task",
+        name = params.task_name.as_str(),
+        run_id = tracing::field::Empty
+    );
 
-    join_set.spawn(async move { execute_single_task_async(params).await }.instrument(task_span));
+    join_set.spawn(async move { execute_single_task_async(params).await }.instrument(task_span))
 }
 
 async fn execute_single_task_async(params: TaskExecutionParams) -> i32 {
     let TaskExecutionParams {
         task_name,
-        task_definition,
+        mut task_definition,
         working_dir,
         task_args,
         failed_tasks,
         action_cache,
-        _env_manager: _,
+        env_manager,
         cache_config,
         executed_tasks,
+        cached_tasks,
+        output_values,
         audit_mode,
         capture_output,
+        stub,
     } = params;
 
     let start_time = Instant::now();
-
-    // Publish task started event
-    publish_task_started(&task_name).await;
+    let span = cuenv_core::events::global_timeline()
+        .start(cuenv_core::events::SpanKind::Task, task_name.as_str());
+
+    let run = TaskRunEvents::start(&task_name).await;
+    tracing::Span::current().record("run_id", run.run_id());
+    if let Some(command) = &stub {
+        crate::executor::stubs::apply(&mut task_definition, command);
+        run.stubbed(command).await;
+    }
 
     // Disabled: Detailed task configuration events (not essential for now)
     // if false {
@@ -64,76 +87,105 @@ async fn execute_single_task_async(params: TaskExecutionParams) -> i32 {
         cache_config: &cache_config,
         working_dir: &working_dir,
         action_cache: &action_cache,
+        env_manager: &env_manager,
         audit_mode,
         capture_output,
+        run: &run,
+    };
+
+    let result = run_with_outputs(&ctx, &mut task_definition, &task_args, &output_values).await;
+    let result = match result {
+        Ok(outcome) if outcome.status.success() && !task_definition.publish.is_empty() => {
+            publish_artifacts(&run, &task_definition)
+                .await
+                .map(|()| outcome)
+        }
+        other => other,
     };
 
-    match cache::execute_single_task_with_cache(&ctx, &task_name, &task_definition, &task_args)
-        .await
-    {
-        Ok(status) => {
-            handle_task_success(status, &task_name, start_time, failed_tasks, executed_tasks).await
+    match result {
+        Ok(outcome) => {
+            if !outcome.status.success() {
+                span.fail(outcome.status.to_string());
+            }
+            if outcome.cached {
+                if let Ok(mut guard) = cached_tasks.lock() {
+                    guard.insert(task_name.clone());
+                }
+            }
+            handle_task_success(outcome, run, start_time, failed_tasks, executed_tasks).await
         }
-        Err(e) => handle_task_error(e, &task_name, start_time, failed_tasks).await,
+        Err(e) => {
+            span.fail(e.to_string());
+            handle_task_error(e, run, start_time, failed_tasks).await
+        }
+    }
+}
+
+/// Run the task with the output values it references filled in, recording
+/// the values it outputs if it succeeds
+async fn run_with_outputs(
+    ctx: &TaskExecutionContext<'_>,
+    task_definition: &mut TaskDefinition,
+    args: &[String],
+    output_values: &OutputValues,
+) -> Result<TaskRun> {
+    output_values.resolve(task_definition)?;
+    let outcome =
+        cache::execute_single_task_with_cache(ctx, ctx.run.task_name(), task_definition, args)
+            .await?;
+    if outcome.status.success() {
+        output_values.record(task_definition)?;
+    }
+    Ok(outcome)
+}
+
+/// Publishing phase of a task that exited successfully
+async fn publish_artifacts(run: &TaskRunEvents, task_definition: &TaskDefinition) -> Result<()> {
+    run.publishing().await;
+
+    let task_name = run.task_name();
+    let outcomes =
+        crate::publish::publish_task(task_name, task_definition, &PublishState::default()).await?;
+    for outcome in outcomes {
+        tracing::info!(task = task_name, "{}", outcome.describe());
+        run.progress(outcome.describe()).await;
     }
+    Ok(())
 }
 
-async fn publish_task_started(task_name: &str) {
-    let event_bus = cuenv_core::events::global_event_bus();
-    let _ = event_bus
-        .publish(cuenv_core::SystemEvent::Task(
-            cuenv_core::TaskEvent::TaskStarted {
-                task_name: task_name.to_string(),
-                task_id: task_name.to_string(),
-            },
-        ))
-        .await;
+/// Publish that a task was not run
+pub async fn publish_task_skipped(task_name: &str, reason: &str) {
+    TaskRunEvents::skipped(task_name, reason).await;
 }
 
 async fn handle_task_success(
-    status: i32,
-    task_name: &str,
+    outcome: TaskRun,
+    run: TaskRunEvents,
     start_time: Instant,
-    failed_tasks: Arc<Mutex<Vec<(String, i32)>>>,
+    failed_tasks: Arc<Mutex<Vec<(String, ExitStatus)>>>,
     executed_tasks: Arc<Mutex<HashSet<String>>>,
 ) -> i32 {
     let duration_ms = start_time.elapsed().as_millis() as u64;
+    let status = outcome.status;
+    let task_name = run.task_name().to_string();
 
-    if status != 0 {
+    if !status.success() {
         if let Ok(mut guard) = failed_tasks.lock() {
-            guard.push((task_name.to_string(), status));
+            guard.push((task_name, status));
         } else {
             tracing::error!("Failed to acquire lock for failed tasks tracking");
         }
 
-        // Publish task failed event
-        let event_bus = cuenv_core::events::global_event_bus();
-        let _ = event_bus
-            .publish(cuenv_core::SystemEvent::Task(
-                cuenv_core::TaskEvent::TaskFailed {
-                    task_name: task_name.to_string(),
-                    task_id: task_name.to_string(),
-                    error: format!("Task exited with code {status}"),
-                },
-            ))
+        run.failed(format!("Task exited with {status}"), Some(status))
             .await;
     } else {
         // Mark task as executed
         if let Ok(mut guard) = executed_tasks.lock() {
-            guard.insert(task_name.to_string());
+            guard.insert(task_name.clone());
         }
 
-        // Publish task completed event
-        let event_bus = cuenv_core::events::global_event_bus();
-        let _ = event_bus
-            .publish(cuenv_core::SystemEvent::Task(
-                cuenv_core::TaskEvent::TaskCompleted {
-                    task_name: task_name.to_string(),
-                    task_id: task_name.to_string(),
-                    duration_ms,
-                },
-            ))
-            .await;
+        run.completed(duration_ms).await;
 
         tracing::info!(
             task = task_name,
@@ -142,40 +194,29 @@ async fn handle_task_success(
         );
     }
 
-    status
+    status.code()
 }
 
 async fn handle_task_error(
     e: cuenv_core::Error,
-    task_name: &str,
+    run: TaskRunEvents,
     start_time: Instant,
-    failed_tasks: Arc<Mutex<Vec<(String, i32)>>>,
+    failed_tasks: Arc<Mutex<Vec<(String, ExitStatus)>>>,
 ) -> i32 {
     let _duration_ms = start_time.elapsed().as_millis() as u64;
 
     if let Ok(mut guard) = failed_tasks.lock() {
-        guard.push((task_name.to_string(), -1));
+        guard.push((run.task_name().to_string(), ExitStatus::FAILURE));
     } else {
         tracing::error!("Failed to acquire lock for failed tasks tracking");
     }
 
-    // Publish task failed event
-    let event_bus = cuenv_core::events::global_event_bus();
-    let _ = event_bus
-        .publish(cuenv_core::SystemEvent::Task(
-            cuenv_core::TaskEvent::TaskFailed {
-                task_name: task_name.to_string(),
-                task_id: task_name.to_string(),
-                error: e.to_string(),
-            },
-        ))
-        .await;
-
     tracing::error!(
-        task_name = %task_name,
+        task_name = %run.task_name(),
         error = %e,
         "Task execution failed"
     );
+    run.failed(e.to_string(), None).await;
 
-    -1
+    ExitStatus::FAILURE.code()
 }
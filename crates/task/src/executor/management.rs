@@ -3,6 +3,7 @@ use super::unified_dag::UnifiedTaskDAG;
 use super::TaskExecutor;
 use cuenv_config::{TaskCollection, TaskNode};
 use cuenv_core::Result;
+use cuenv_env::manager::EnvManager;
 use std::collections::HashMap;
 use std::time::Duration;
 
@@ -12,6 +13,39 @@ impl TaskExecutor {
         self.env_manager.list_tasks()
     }
 
+    /// Environment manager the tasks run with
+    pub fn env_manager(&self) -> &EnvManager {
+        &self.env_manager
+    }
+
+    /// Tasks that can run side by side in place of `task_name`: the
+    /// members of a parallel group, or the task itself
+    pub fn parallel_units(&self, task_name: &str) -> Vec<String> {
+        match self.find_task_node(task_name) {
+            Some(TaskNode::Group {
+                tasks: TaskCollection::Parallel(tasks),
+                ..
+            }) if !tasks.is_empty() => tasks
+                .keys()
+                .map(|member| format!("{task_name}.{member}"))
+                .collect(),
+            _ => vec![task_name.to_string()],
+        }
+    }
+
+    /// Node of a dotted task name, looked up through parallel groups
+    fn find_task_node(&self, task_name: &str) -> Option<&TaskNode> {
+        let mut segments = task_name.split('.');
+        let root = self.env_manager.get_task_nodes().get(segments.next()?)?;
+        segments.try_fold(root, |node, segment| match node {
+            TaskNode::Group {
+                tasks: TaskCollection::Parallel(tasks),
+                ..
+            } => tasks.get(segment),
+            _ => None,
+        })
+    }
+
     /// Get CUE environment variables
     pub fn get_env_vars(&self) -> &HashMap<String, String> {
         self.env_manager.get_cue_vars()
@@ -49,6 +83,15 @@ impl TaskExecutor {
         Ok(self.cache_manager.get_statistics())
     }
 
+    /// Persist this run's cache activity to the statistics history
+    ///
+    /// History is best-effort: failing to write it never fails a task run.
+    pub(crate) fn record_cache_history(&self) {
+        if let Err(e) = self.cache_manager.record_history() {
+            tracing::debug!(error = %e, "Failed to record cache statistics history");
+        }
+    }
+
     /// Print cache statistics
     pub fn print_cache_statistics(&self) -> Result<()> {
         let stats = self.cache_manager.get_statistics();
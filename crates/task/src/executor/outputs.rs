@@ -0,0 +1,171 @@
+//! Values tasks output during a run
+//!
+//! Once a task declaring output values succeeds, each value is read from its
+//! file in the task's working directory, trimmed and checked against its
+//! type. Before a task runs, its references to those values are replaced,
+//! quoted for the shell, so the values become part of its command and with
+//! it its cache key. Values are kept under the dotted name references use,
+//! `ci.build` for the group member `ci:build`.
+
+use super::strategies::task_config_name;
+use crate::builder::outputs::output_references;
+use cuenv_core::{Error, Result, TaskDefinition, TaskExecutionMode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Values output by the tasks of a run, by task and value name
+#[derive(Debug, Clone, Default)]
+pub struct OutputValues(Arc<Mutex<HashMap<String, HashMap<String, String>>>>);
+
+impl OutputValues {
+    /// Read the values `definition` outputs, after it succeeded
+    pub fn record(&self, definition: &TaskDefinition) -> Result<()> {
+        if definition.output_values.is_empty() {
+            return Ok(());
+        }
+        let values = definition
+            .output_values
+            .iter()
+            .map(|output| {
+                let path = definition.working_directory.join(&output.from_file);
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| Error::file_system(&path, "read task output", e))?;
+                let value = content.trim();
+                if !output.value_type.accepts(value) {
+                    return Err(Error::configuration(format!(
+                        "Task '{}' output '{}' must be of type {}, got '{value}'",
+                        definition.name, output.name, output.value_type
+                    )));
+                }
+                Ok((output.name.clone(), value.to_string()))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        self.lock()?
+            .insert(task_config_name(&definition.name), values);
+        Ok(())
+    }
+
+    /// Replace references to output values in the command or script of
+    /// `definition`
+    pub fn resolve(&self, definition: &mut TaskDefinition) -> Result<()> {
+        let content = match &mut definition.execution_mode {
+            TaskExecutionMode::Command { command } => command,
+            TaskExecutionMode::Script { content } => content,
+        };
+        if output_references(content).next().is_none() {
+            return Ok(());
+        }
+
+        let values = self.lock()?;
+        let mut resolved = String::with_capacity(content.len());
+        let mut last = 0;
+        for (range, reference) in output_references(content) {
+            let value = values
+                .get(reference.task)
+                .and_then(|task| task.get(reference.value))
+                .ok_or_else(|| {
+                    Error::configuration(format!(
+                        "Task '{}' needs output '{}' of '{}', which was not produced in this run",
+                        definition.name, reference.value, reference.task
+                    ))
+                })?;
+            let quoted = shlex::try_quote(value).map_err(|e| {
+                Error::configuration(format!(
+                    "Output '{}' of '{}' cannot be passed to a shell: {e}",
+                    reference.value, reference.task
+                ))
+            })?;
+            resolved.push_str(&content[last..range.start]);
+            resolved.push_str(&quoted);
+            last = range.end;
+        }
+        resolved.push_str(&content[last..]);
+        *content = resolved;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, HashMap<String, String>>>> {
+        self.0
+            .lock()
+            .map_err(|e| Error::configuration(format!("Failed to acquire lock: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cuenv_core::{TaskOutputType, TaskOutputValue};
+    use tempfile::TempDir;
+
+    fn task(name: &str, command: &str, dir: &TempDir) -> TaskDefinition {
+        TaskDefinition::new(
+            name.to_string(),
+            TaskExecutionMode::Command {
+                command: command.to_string(),
+            },
+            dir.path().to_path_buf(),
+        )
+    }
+
+    #[test]
+    fn test_recorded_values_replace_references() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("VERSION"), "1.4.0\n").unwrap();
+        std::fs::write(dir.path().join("SIZE"), "big").unwrap();
+
+        let mut build = task("build", "make", &dir);
+        build.output_values = vec![TaskOutputValue {
+            name: "version".to_string(),
+            from_file: "VERSION".to_string(),
+            value_type: TaskOutputType::String,
+        }];
+        let outputs = OutputValues::default();
+        outputs.record(&build).unwrap();
+
+        let mut deploy = task(
+            "deploy",
+            "deploy --tag ${tasks.build.outputs.version}",
+            &dir,
+        );
+        outputs.resolve(&mut deploy).unwrap();
+        assert_eq!(deploy.get_execution_content(), "deploy --tag 1.4.0");
+
+        let mut missing = task("notify", "echo ${tasks.test.outputs.report}", &dir);
+        assert!(outputs
+            .resolve(&mut missing)
+            .unwrap_err()
+            .to_string()
+            .contains("needs output 'report' of 'test'"));
+
+        build.output_values = vec![TaskOutputValue {
+            name: "size".to_string(),
+            from_file: "SIZE".to_string(),
+            value_type: TaskOutputType::Int,
+        }];
+        assert!(outputs
+            .record(&build)
+            .unwrap_err()
+            .to_string()
+            .contains("output 'size' must be of type int, got 'big'"));
+    }
+
+    #[test]
+    fn test_group_member_values_are_quoted_for_the_shell() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("TITLE"), "it's done; rm -rf /\n").unwrap();
+
+        let mut build = task("ci:build", "make", &dir);
+        build.output_values = vec![TaskOutputValue {
+            name: "title".to_string(),
+            from_file: "TITLE".to_string(),
+            value_type: TaskOutputType::String,
+        }];
+        let outputs = OutputValues::default();
+        outputs.record(&build).unwrap();
+
+        let mut notify = task("notify", "echo ${tasks.ci.build.outputs.title}", &dir);
+        outputs.resolve(&mut notify).unwrap();
+        let words = shlex::split(notify.get_execution_content()).unwrap();
+        assert_eq!(words, ["echo", "it's done; rm -rf /"]);
+    }
+}
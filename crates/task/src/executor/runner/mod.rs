@@ -1,5 +1,9 @@
 mod output;
 mod process;
+mod process_settings;
+mod run_as;
 mod security;
+mod wait;
 
+pub use output::publish_piped_output;
 pub use process::execute_single_task;
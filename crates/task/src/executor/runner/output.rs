@@ -1,164 +1,216 @@
-use cuenv_core::{Error, Result};
+use crate::cancellation::{self, terminate_when_cancelled};
+use crate::problem_matcher::DiagnosticParser;
+use cuenv_core::events::TaskRunEvents;
+use cuenv_core::output::{decode_line, policy, sanitize, OutputSink};
+use cuenv_core::redaction::redact;
+use cuenv_core::{Diagnostic, Error, ExitStatus, Result};
 use cuenv_utils::cleanup::handler::ProcessGuard;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Whether piped output is published too, for the rest of the process
+static PUBLISH_PIPED: AtomicBool = AtomicBool::new(false);
+
+/// Pipe the output of every task through cuenv and publish it as events
+/// while it is also written to the terminal, for reports of the run
+pub fn publish_piped_output(publish: bool) {
+    PUBLISH_PIPED.store(publish, Ordering::Relaxed);
+}
+
+/// Whether output that is not captured is piped and published anyway
+pub(super) fn piped_output_published() -> bool {
+    PUBLISH_PIPED.load(Ordering::Relaxed)
+}
+
+/// What happens to a task's output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Inherited from cuenv
+    Inherit,
+    /// Read a line at a time for problem matchers and passed on to cuenv's
+    /// own output, with secrets masked, and published too when
+    /// [`publish_piped_output`] asks for it
+    Tee,
+    /// Kept off the terminal, and published a line at a time as it is read
+    Capture,
+}
 
 /// Execute command with output handling
+///
+/// Diagnostics `parser` finds in piped output are published once the task
+/// exits. The run can be cancelled while it runs, see [`crate::cancellation`].
 pub async fn execute_with_output_handling(
     mut cmd: Command,
-    shell: &str,
-    script_content: String,
+    program: &str,
+    program_args: Vec<String>,
     timeout: Duration,
-    task_name: &str,
-    capture_output: bool,
-) -> Result<i32> {
+    run: &TaskRunEvents,
+    mode: OutputMode,
+    parser: Option<DiagnosticParser>,
+) -> Result<ExitStatus> {
     // Spawn the process with timeout
     let mut child = cmd.spawn().map_err(|e| {
         Error::command_execution(
-            shell,
-            vec!["-c".to_string(), script_content.clone()],
+            program,
+            program_args.clone(),
             format!("Failed to spawn task: {e}"),
             None,
         )
     })?;
 
-    // Handle output capturing if needed
-    let (stdout_handle, stderr_handle, captured_output) = if capture_output {
-        let output = Arc::new(Mutex::new(CapturedOutput::default()));
-        let task_name_clone = task_name.to_string();
-        let (stdout_h, stderr_h) =
-            handle_captured_output(&mut child, &task_name_clone, Arc::clone(&output));
-        (stdout_h, stderr_h, Some(output))
+    let pid = child.id();
+
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let (lines, mut captured_lines) = mpsc::unbounded_channel();
+    let readers = if mode == OutputMode::Inherit {
+        Vec::new()
     } else {
-        (None, None, None)
+        read_output(&mut child, mode, parser.as_ref(), &diagnostics, &lines)
     };
+    drop(lines);
 
     // Use ProcessGuard for automatic cleanup
     let mut guard = ProcessGuard::new(child, timeout);
-
-    // Wait for completion with timeout (use async version to avoid blocking the runtime)
-    let status = guard.wait_with_timeout_async().await.map_err(|e| {
-        Error::command_execution(
-            shell,
-            vec!["-c".to_string(), script_content.clone()],
-            e.to_string(),
-            None,
-        )
+    let mut cancellation = cancellation::register(run.run_id(), run.task_name());
+
+    // Publish captured lines while waiting for completion with timeout (use
+    // async version to avoid blocking the runtime)
+    let publish = async {
+        while let Some((stream, line)) = captured_lines.recv().await {
+            match stream {
+                Stream::Stdout => run.output(line).await,
+                Stream::Stderr => run.error(line).await,
+            }
+        }
+    };
+    let wait = async { tokio::join!(guard.wait_with_timeout_async(), publish).0 };
+    tokio::pin!(wait);
+    let status = tokio::select! {
+        status = &mut wait => status,
+        () = terminate_when_cancelled(&mut cancellation, pid) => wait.await,
+    };
+    let status = status.map_err(|e| {
+        Error::command_execution(program, program_args.clone(), e.to_string(), None)
     })?;
 
     // Wait for output threads to complete
-    if let Some(handle) = stdout_handle {
-        let _ = handle.join();
+    for reader in readers {
+        let _ = reader.join();
     }
-    if let Some(handle) = stderr_handle {
-        let _ = handle.join();
+
+    if cancellation.is_cancelled() {
+        return Err(Error::command_execution(
+            program,
+            program_args,
+            "Task was cancelled",
+            None,
+        ));
     }
 
-    let exit_code = status.code().unwrap_or(1);
-
-    // If the task failed and we captured output, send it through the event system
-    // This ensures TUI can display it properly without corrupting the terminal
-    if exit_code != 0 {
-        if let Some(output) = captured_output {
-            // Extract the captured output to avoid holding the lock across await
-            let (stdout_lines, stderr_lines) = {
-                if let Ok(captured) = output.lock() {
-                    (captured.stdout.clone(), captured.stderr.clone())
-                } else {
-                    (vec![], vec![])
-                }
-            };
-
-            if !stdout_lines.is_empty() || !stderr_lines.is_empty() {
-                // Send output through event system for proper TUI handling
-                let event_bus = cuenv_core::events::global_event_bus();
-
-                // Send stdout as TaskOutput events
-                if !stdout_lines.is_empty() {
-                    let combined_stdout = stdout_lines.join("\n");
-                    let _ = event_bus
-                        .publish(cuenv_core::SystemEvent::Task(
-                            cuenv_core::TaskEvent::TaskOutput {
-                                task_name: task_name.to_string(),
-                                task_id: task_name.to_string(),
-                                output: combined_stdout,
-                            },
-                        ))
-                        .await;
-                }
+    let exit_status = ExitStatus::from(status);
 
-                // Send stderr as TaskError events
-                if !stderr_lines.is_empty() {
-                    let combined_stderr = stderr_lines.join("\n");
-                    let _ = event_bus
-                        .publish(cuenv_core::SystemEvent::Task(
-                            cuenv_core::TaskEvent::TaskError {
-                                task_name: task_name.to_string(),
-                                task_id: task_name.to_string(),
-                                error: combined_stderr,
-                            },
-                        ))
-                        .await;
-                }
-            }
-        }
+    // Take the diagnostics out to avoid holding the lock across await
+    let diagnostics = diagnostics
+        .lock()
+        .map(|mut diagnostics| std::mem::take(&mut *diagnostics))
+        .unwrap_or_default();
+    for diagnostic in diagnostics {
+        run.diagnostic(diagnostic).await;
     }
 
-    Ok(exit_code)
+    Ok(exit_status)
 }
 
-#[derive(Default)]
-struct CapturedOutput {
-    stdout: Vec<String>,
-    stderr: Vec<String>,
+#[derive(Clone, Copy)]
+enum Stream {
+    Stdout,
+    Stderr,
 }
 
-fn handle_captured_output(
+/// Read the child's output a line at a time on two threads
+///
+/// Lines are published as they were written, escape sequences and all;
+/// each sink decides what it keeps of them, see [`cuenv_core::output`].
+fn read_output(
     child: &mut std::process::Child,
-    _task_name: &str,
-    captured_output: Arc<Mutex<CapturedOutput>>,
-) -> (
-    Option<std::thread::JoinHandle<()>>,
-    Option<std::thread::JoinHandle<()>>,
-) {
-    use std::io::{BufRead, BufReader};
-
-    // Take stdout and stderr from child
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-
-    // Spawn thread to read stdout
-    let stdout_handle = stdout.map(|stdout| {
-        let output_clone = Arc::clone(&captured_output);
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines().map_while(|result| result.ok()) {
-                // Store for potential error display
-                if let Ok(mut output) = output_clone.lock() {
-                    output.stdout.push(line);
-                }
-                // Note: Real-time event sending removed as it's not working reliably
-                // Events will be sent after task completion
-            }
-        })
+    mode: OutputMode,
+    parser: Option<&DiagnosticParser>,
+    diagnostics: &Arc<Mutex<Vec<Diagnostic>>>,
+    lines: &mpsc::UnboundedSender<(Stream, String)>,
+) -> Vec<std::thread::JoinHandle<()>> {
+    let stdout = child.stdout.take().map(|stdout| {
+        spawn_reader(
+            stdout,
+            Stream::Stdout,
+            mode,
+            parser.cloned(),
+            Arc::clone(diagnostics),
+            lines.clone(),
+        )
     });
+    let stderr = child.stderr.take().map(|stderr| {
+        spawn_reader(
+            stderr,
+            Stream::Stderr,
+            mode,
+            parser.cloned(),
+            Arc::clone(diagnostics),
+            lines.clone(),
+        )
+    });
+    stdout.into_iter().chain(stderr).collect()
+}
 
-    // Spawn thread to read stderr
-    let stderr_handle = stderr.map(|stderr| {
-        let output_clone = Arc::clone(&captured_output);
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines().map_while(|result| result.ok()) {
-                // Store for potential error display
-                if let Ok(mut output) = output_clone.lock() {
-                    output.stderr.push(line);
+fn spawn_reader<R: std::io::Read + Send + 'static>(
+    reader: R,
+    stream: Stream,
+    mode: OutputMode,
+    mut parser: Option<DiagnosticParser>,
+    diagnostics: Arc<Mutex<Vec<Diagnostic>>>,
+    lines: mpsc::UnboundedSender<(Stream, String)>,
+) -> std::thread::JoinHandle<()> {
+    use std::io::{BufRead, BufReader, IsTerminal, Write};
+
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut bytes = Vec::new();
+        let sink = match stream {
+            Stream::Stdout if std::io::stdout().is_terminal() => OutputSink::Terminal,
+            Stream::Stderr if std::io::stderr().is_terminal() => OutputSink::Terminal,
+            _ => OutputSink::File,
+        };
+        let policy = policy(sink);
+        // Invalid UTF-8 is replaced rather than ending the stream
+        while matches!(reader.read_until(b'\n', &mut bytes), Ok(read) if read > 0) {
+            let line = decode_line(&bytes);
+            bytes.clear();
+            let found = parser
+                .as_mut()
+                .map(|parser| parser.parse_line(&line))
+                .unwrap_or_default();
+            if let Ok(mut diagnostics) = diagnostics.lock() {
+                diagnostics.extend(found);
+            }
+            match mode {
+                OutputMode::Tee => {
+                    let redacted = redact(&line);
+                    let redacted = sanitize(&redacted, policy);
+                    let _ = match stream {
+                        Stream::Stdout => writeln!(std::io::stdout().lock(), "{redacted}"),
+                        Stream::Stderr => writeln!(std::io::stderr().lock(), "{redacted}"),
+                    };
+                    if piped_output_published() {
+                        let _ = lines.send((stream, line));
+                    }
                 }
-                // Note: Real-time event sending removed as it's not working reliably
-                // Events will be sent after task completion
+                OutputMode::Capture => {
+                    let _ = lines.send((stream, line));
+                }
+                OutputMode::Inherit => {}
             }
-        })
-    });
-
-    (stdout_handle, stderr_handle)
+        }
+    })
 }
@@ -1,17 +1,32 @@
-use cuenv_core::{Result, TaskDefinition, TaskExecutionMode};
-use std::collections::HashSet;
+use super::output::{piped_output_published, OutputMode};
+use crate::problem_matcher::DiagnosticParser;
+use crate::script::{ScriptFile, Shebang};
+use cuenv_cache::env_usage::referenced_variables;
+use cuenv_core::events::TaskRunEvents;
+use cuenv_core::redaction::global_redactor;
+use cuenv_core::{ExitStatus, Result, TaskDefinition, TaskExecutionMode};
+use cuenv_env::git::git_variables;
+use cuenv_security::AuditReport;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::process::{Command, Stdio};
 
-/// Execute a single task
+/// Execute a single task as part of `run`
+///
+/// `secrets` are lazy secrets the task references, already resolved. Tasks
+/// with problem matchers, and all tasks once there are secrets to mask or
+/// output is published for a report, have their output piped through cuenv
+/// even when it is not captured. Returns the exit status, with the audit
+/// report when the task ran audited.
 pub async fn execute_single_task(
-    task_name: &str,
+    run: &TaskRunEvents,
     task_definition: &TaskDefinition,
     _working_dir: &Path,
     args: &[String],
+    secrets: &HashMap<String, String>,
     audit_mode: bool,
     capture_output: bool,
-) -> Result<i32> {
+) -> Result<(ExitStatus, Option<AuditReport>)> {
     // Determine what to execute from TaskDefinition
     let (shell, script_content) = match &task_definition.execution_mode {
         TaskExecutionMode::Command { command } => {
@@ -25,39 +40,115 @@ pub async fn execute_single_task(
         }
         TaskExecutionMode::Script { content } => (task_definition.shell.clone(), content.clone()),
     };
+    let shebang = match &task_definition.execution_mode {
+        TaskExecutionMode::Script { content } => Shebang::parse(content)?,
+        TaskExecutionMode::Command { .. } => None,
+    };
 
     // Validate for security
-    validate_security(&shell, &script_content, args)?;
+    match &shebang {
+        Some(shebang) => validate_script_security(shebang, &script_content, args)?,
+        None => validate_security(&shell, &script_content, args)?,
+    }
+
+    super::wait::wait_for_services(run, &task_definition.wait_for).await?;
 
     // Use the working directory from task definition
     let exec_dir = task_definition.working_directory.clone();
 
+    // Git variables are only read when the task refers to them
+    let referenced = referenced_variables(&script_content);
+    let git_vars = git_variables(&exec_dir, referenced.iter().map(String::as_str));
+
+    // Scripts with a shebang run from a file, given to their interpreter
+    // with the task's arguments; the file is removed once the task is done
+    let script = shebang
+        .map(|shebang| {
+            ScriptFile::write(run.task_name(), &shebang, &script_content)
+                .map(|file| (shebang, file))
+        })
+        .transpose()?;
+    let (program, program_args) = match &script {
+        Some((shebang, file)) => {
+            let mut program_args = shebang.args.clone();
+            program_args.push(file.path().display().to_string());
+            program_args.extend(args.iter().cloned());
+            (shebang.interpreter.clone(), program_args)
+        }
+        None => (shell, vec!["-c".to_string(), script_content]),
+    };
+
     // Configure command
-    let mut cmd = Command::new(&shell);
-    cmd.arg("-c").arg(&script_content).current_dir(&exec_dir);
+    let mut cmd = Command::new(&program);
+    cmd.args(&program_args)
+        .current_dir(&exec_dir)
+        .envs(&git_vars)
+        .envs(secrets);
 
-    configure_stdio(&mut cmd, capture_output);
+    let parser = DiagnosticParser::new(run.task_name(), &task_definition.problem_matchers)?;
+    let mode = match (capture_output, &parser) {
+        (true, _) => OutputMode::Capture,
+        (false, Some(_)) => OutputMode::Tee,
+        (false, None) if !global_redactor().is_empty() || piped_output_published() => {
+            OutputMode::Tee
+        }
+        (false, None) => OutputMode::Inherit,
+    };
+    configure_stdio(&mut cmd, mode);
     configure_platform_specific(&mut cmd);
+    super::process_settings::apply_process_settings(&mut cmd, &task_definition.process)?;
+
+    // Switch to the configured user before any sandboxing is layered on
+    if let Some(run_as) = &task_definition.run_as {
+        super::run_as::apply_run_as(&mut cmd, run_as)?;
+        if let Some((_, file)) = &script {
+            super::run_as::hand_over(&[file.dir(), file.path()], run_as)?;
+        }
+    }
 
-    // Apply security restrictions if configured
+    // Apply security restrictions if configured, letting the task read its
+    // script
     if let Some(security) = &task_definition.security {
-        if let Some(exit_code) =
-            super::security::apply_security_restrictions(&mut cmd, security, audit_mode)?
+        let mut security = security.clone();
+        if let Some((_, file)) = &script {
+            security.read_only_paths.push(file.dir().to_path_buf());
+        }
+        if let Some((exit_status, report)) =
+            super::security::apply_security_restrictions(&mut cmd, &security, audit_mode)?
         {
-            return Ok(exit_code);
+            return Ok((exit_status, Some(report)));
         }
     }
 
     // Execute with output handling
     super::output::execute_with_output_handling(
         cmd,
-        &shell,
-        script_content,
+        &program,
+        program_args,
         task_definition.timeout,
-        task_name,
-        capture_output,
+        run,
+        mode,
+        parser,
     )
     .await
+    .map(|exit_status| (exit_status, None))
+}
+
+/// Scripts run by the interpreter of their shebang, which
+/// [`Shebang::parse`] only accepts from its list, with the line's arguments
+fn validate_script_security(
+    shebang: &Shebang,
+    script_content: &str,
+    args: &[String],
+) -> Result<()> {
+    cuenv_security::SecurityValidator::validate_shell_expansion(script_content)?;
+
+    let line_and_task_args: Vec<String> = shebang.args.iter().chain(args).cloned().collect();
+    if !line_and_task_args.is_empty() {
+        cuenv_security::SecurityValidator::validate_command_args(&line_and_task_args)?;
+    }
+
+    Ok(())
 }
 
 fn validate_security(shell: &str, script_content: &str, args: &[String]) -> Result<()> {
@@ -75,18 +166,23 @@ fn validate_security(shell: &str, script_content: &str, args: &[String]) -> Resu
     Ok(())
 }
 
-fn configure_stdio(cmd: &mut Command, capture_output: bool) {
-    if capture_output {
+fn configure_stdio(cmd: &mut Command, mode: OutputMode) {
+    match mode {
         // Capture output for TUI mode to prevent interference
-        cmd.stdin(Stdio::null())
+        OutputMode::Capture => cmd
+            .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-    } else {
+            .stderr(Stdio::piped()),
+        OutputMode::Tee => cmd
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
         // Normal mode - inherit stdio
-        cmd.stdin(Stdio::inherit())
+        OutputMode::Inherit => cmd
+            .stdin(Stdio::inherit())
             .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
-    }
+            .stderr(Stdio::inherit()),
+    };
 }
 
 fn configure_platform_specific(cmd: &mut Command) {
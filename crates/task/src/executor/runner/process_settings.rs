@@ -0,0 +1,158 @@
+//! umask, niceness and IO priority of task processes
+//!
+//! On Unix the settings are applied in the forked child, right before
+//! `exec`, and before any switch to another user so that privileged
+//! settings such as a negative niceness still work when cuenv runs as root.
+//! IO priority is Linux only. Windows has neither umask nor IO priority
+//! classes; niceness maps to the closest process priority class.
+
+use cuenv_core::{Result, TaskProcess};
+use std::process::Command;
+
+/// Configure `cmd` to start with the settings of `process`
+#[cfg(unix)]
+pub fn apply_process_settings(cmd: &mut Command, process: &TaskProcess) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    if *process == TaskProcess::default() {
+        return Ok(());
+    }
+
+    let umask = process.umask.map(|umask| umask as libc::mode_t);
+    let nice = process.nice;
+    #[cfg(target_os = "linux")]
+    let io_priority = process.io_priority.map(linux::io_priority_value);
+    #[cfg(not(target_os = "linux"))]
+    if process.io_priority.is_some() {
+        tracing::warn!("ioPriority is only supported on Linux, ignoring it");
+    }
+
+    tracing::debug!(?process, "Applying task process settings");
+
+    // SAFETY: only async-signal-safe libc calls are made between fork and exec
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(umask) = umask {
+                libc::umask(umask);
+            }
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(io_priority) = io_priority {
+                if libc::syscall(
+                    libc::SYS_ioprio_set,
+                    linux::IOPRIO_WHO_PROCESS,
+                    0,
+                    io_priority,
+                ) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
+/// Configure `cmd` to start with the settings of `process`
+#[cfg(not(unix))]
+pub fn apply_process_settings(cmd: &mut Command, process: &TaskProcess) -> Result<()> {
+    if process.umask.is_some() {
+        tracing::warn!("umask is only supported on Unix, ignoring it");
+    }
+    if process.io_priority.is_some() {
+        tracing::warn!("ioPriority is only supported on Linux, ignoring it");
+    }
+    #[cfg(windows)]
+    if let Some(nice) = process.nice {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(priority_class(nice));
+    }
+    #[cfg(not(windows))]
+    let _ = cmd;
+    Ok(())
+}
+
+/// Windows priority class closest to the niceness `nice`
+#[cfg(any(windows, test))]
+fn priority_class(nice: i32) -> u32 {
+    const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+    const NORMAL_PRIORITY_CLASS: u32 = 0x0000_0020;
+    const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+    const HIGH_PRIORITY_CLASS: u32 = 0x0000_0080;
+
+    match nice {
+        10.. => IDLE_PRIORITY_CLASS,
+        1..=9 => BELOW_NORMAL_PRIORITY_CLASS,
+        0 => NORMAL_PRIORITY_CLASS,
+        -9..=-1 => ABOVE_NORMAL_PRIORITY_CLASS,
+        _ => HIGH_PRIORITY_CLASS,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use cuenv_core::{IoClass, TaskIoPriority};
+
+    pub const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const DEFAULT_LEVEL: u8 = 4;
+
+    /// `ioprio_set` value of `priority`
+    pub fn io_priority_value(priority: TaskIoPriority) -> libc::c_int {
+        let (class, level) = match priority.class {
+            IoClass::Realtime => (1, priority.level.unwrap_or(DEFAULT_LEVEL)),
+            IoClass::BestEffort => (2, priority.level.unwrap_or(DEFAULT_LEVEL)),
+            IoClass::Idle => (3, 0),
+        };
+        (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_class_mapping() {
+        assert_eq!(priority_class(19), 0x40);
+        assert_eq!(priority_class(5), 0x4000);
+        assert_eq!(priority_class(0), 0x20);
+        assert_eq!(priority_class(-5), 0x8000);
+        assert_eq!(priority_class(-20), 0x80);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_io_priority_value() {
+        use cuenv_core::{IoClass, TaskIoPriority};
+
+        let value = |class, level| linux::io_priority_value(TaskIoPriority { class, level });
+        assert_eq!(value(IoClass::BestEffort, None), (2 << 13) | 4);
+        assert_eq!(value(IoClass::Realtime, Some(0)), 1 << 13);
+        assert_eq!(value(IoClass::Idle, None), 3 << 13);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_umask_and_nice_apply_to_the_child() {
+        let process = TaskProcess {
+            umask: Some(0o077),
+            nice: Some(5),
+            io_priority: None,
+        };
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("umask; nice");
+        apply_process_settings(&mut cmd, &process).unwrap();
+        let output = cmd.output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<_> = stdout.lines().collect();
+        assert_eq!(lines, ["0077", "5"]);
+    }
+}
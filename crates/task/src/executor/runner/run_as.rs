@@ -0,0 +1,167 @@
+//! Running tasks as a different user
+//!
+//! The target user and group are resolved before the process is spawned so
+//! lookup failures are reported with a proper error. The actual switch
+//! happens in the forked child, right before `exec`.
+
+use cuenv_core::{Result, TaskRunAs};
+use std::path::Path;
+use std::process::Command;
+
+/// Configure `cmd` to run as the user (and group) described by `run_as`
+#[cfg(unix)]
+pub fn apply_run_as(cmd: &mut Command, run_as: &TaskRunAs) -> Result<()> {
+    use cuenv_core::Error;
+    use std::os::unix::process::CommandExt;
+
+    let (uid, gid) = resolve(run_as)?;
+
+    let current_uid = users::get_current_uid();
+    let current_gid = users::get_current_gid();
+    if (uid, gid) == (current_uid, current_gid) {
+        return Ok(());
+    }
+
+    if users::get_effective_uid() != 0 {
+        return Err(Error::permission_denied(
+            "run task as another user",
+            format!(
+                "switching to user '{}' requires cuenv to run as root (current uid {current_uid})",
+                run_as.user
+            ),
+        ));
+    }
+
+    tracing::debug!(uid, gid, user = %run_as.user, "Running task as another user");
+
+    // SAFETY: only async-signal-safe libc calls are made between fork and exec
+    unsafe {
+        cmd.pre_exec(move || {
+            // Supplementary groups must be dropped while still privileged
+            if libc::setgroups(1, &gid) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::setgid(gid) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::setuid(uid) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
+/// Configure `cmd` to run as the user (and group) described by `run_as`
+#[cfg(not(unix))]
+pub fn apply_run_as(_cmd: &mut Command, run_as: &TaskRunAs) -> Result<()> {
+    Err(cuenv_core::Error::unsupported(
+        "runAs",
+        format!(
+            "running tasks as user '{}' is only supported on Unix",
+            run_as.user
+        ),
+    ))
+}
+
+/// Give `paths` to the user `run_as` switches to, so the task can use files
+/// cuenv wrote for it
+#[cfg(unix)]
+pub fn hand_over(paths: &[&Path], run_as: &TaskRunAs) -> Result<()> {
+    use cuenv_core::Error;
+
+    let (uid, gid) = resolve(run_as)?;
+    if users::get_effective_uid() != 0 || uid == users::get_current_uid() {
+        return Ok(());
+    }
+    for path in paths {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))
+            .map_err(|e| Error::file_system(*path, "hand over to runAs user", e))?;
+    }
+    Ok(())
+}
+
+/// Give `paths` to the user `run_as` switches to, so the task can use files
+/// cuenv wrote for it
+#[cfg(not(unix))]
+pub fn hand_over(_paths: &[&Path], _run_as: &TaskRunAs) -> Result<()> {
+    Ok(())
+}
+
+/// Resolve user and group names (or numeric ids) to a uid/gid pair
+///
+/// Without an explicit group, the user's primary group is used.
+#[cfg(unix)]
+fn resolve(run_as: &TaskRunAs) -> Result<(libc::uid_t, libc::gid_t)> {
+    use cuenv_core::Error;
+
+    let (uid, primary_gid) = match run_as.user.parse::<libc::uid_t>() {
+        Ok(uid) => (
+            uid,
+            users::get_user_by_uid(uid).map(|user| user.primary_group_id()),
+        ),
+        Err(_) => {
+            let user = users::get_user_by_name(&run_as.user).ok_or_else(|| {
+                Error::configuration(format!("runAs user '{}' does not exist", run_as.user))
+            })?;
+            (user.uid(), Some(user.primary_group_id()))
+        }
+    };
+
+    let gid = match &run_as.group {
+        Some(group) => match group.parse::<libc::gid_t>() {
+            Ok(gid) => gid,
+            Err(_) => users::get_group_by_name(group)
+                .map(|group| group.gid())
+                .ok_or_else(|| {
+                    Error::configuration(format!("runAs group '{group}' does not exist"))
+                })?,
+        },
+        None => primary_gid.ok_or_else(|| {
+            Error::configuration(format!(
+                "runAs user '{}' has no primary group; set runAs.group explicitly",
+                run_as.user
+            ))
+        })?,
+    };
+
+    Ok((uid, gid))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn run_as(user: &str, group: Option<&str>) -> TaskRunAs {
+        TaskRunAs {
+            user: user.to_string(),
+            group: group.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_resolve_numeric_ids() {
+        assert_eq!(
+            resolve(&run_as("4242", Some("4343"))).unwrap(),
+            (4242, 4343)
+        );
+        // Unknown numeric users have no primary group to fall back to
+        assert!(resolve(&run_as("4242", None)).is_err());
+    }
+
+    #[test]
+    fn test_resolve_unknown_names() {
+        assert!(resolve(&run_as("cuenv-no-such-user", None)).is_err());
+        assert!(resolve(&run_as("0", Some("cuenv-no-such-group"))).is_err());
+    }
+
+    #[test]
+    fn test_current_user_is_a_no_op() {
+        let uid = users::get_current_uid().to_string();
+        let gid = users::get_current_gid().to_string();
+        let mut cmd = Command::new("true");
+        assert!(apply_run_as(&mut cmd, &run_as(&uid, Some(&gid))).is_ok());
+    }
+}
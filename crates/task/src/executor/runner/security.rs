@@ -1,27 +1,36 @@
-use cuenv_core::{Result, TaskSecurity as TaskSecurityConfig};
+use cuenv_core::{ExitStatus, Result, TaskSecurity as TaskSecurityConfig};
+use cuenv_security::AuditReport;
 use std::process::Command;
 
 /// Apply security restrictions to a command
-/// Returns Some(exit_code) if audit mode completed, None to continue execution
+/// Returns the exit status and report if audit mode completed, None to continue execution
 pub fn apply_security_restrictions(
     cmd: &mut Command,
     security: &TaskSecurityConfig,
     audit_mode: bool,
-) -> Result<Option<i32>> {
+) -> Result<Option<(ExitStatus, AuditReport)>> {
     apply_security_restrictions_with_format(cmd, security, audit_mode, false)
 }
 
 /// Apply security restrictions to a command with output format control
-/// Returns Some(exit_code) if audit mode completed, None to continue execution
+/// Returns the exit status and report if audit mode completed, None to continue execution
 pub fn apply_security_restrictions_with_format(
     cmd: &mut Command,
     security: &TaskSecurityConfig,
     audit_mode: bool,
     json_output: bool,
-) -> Result<Option<i32>> {
-    use cuenv_security::AccessRestrictions;
+) -> Result<Option<(ExitStatus, AuditReport)>> {
+    use cuenv_security::enforcement::{effective_level, protection_unavailable};
+    use cuenv_security::{AccessRestrictions, EnforcementLevel, SeccompFilter, SeccompMode};
+
+    let enforcement = effective_level(security.enforcement)?;
+    if enforcement == EnforcementLevel::Off && !audit_mode {
+        return Ok(None);
+    }
+
     let mut restrictions =
         AccessRestrictions::new(security.restrict_disk, security.restrict_network);
+    restrictions.enforcement = enforcement;
 
     // Add allowed paths
     for path in &security.read_only_paths {
@@ -36,7 +45,7 @@ pub fn apply_security_restrictions_with_format(
         // TODO: Add tracing when moved to workspace
         // task_progress(task_name, None, "Running task in audit mode...");
 
-        let (exit_code, audit_report) = restrictions.run_with_audit(cmd)?;
+        let (exit_status, audit_report) = restrictions.run_with_audit(cmd)?;
 
         if json_output {
             match audit_report.to_json() {
@@ -50,10 +59,22 @@ pub fn apply_security_restrictions_with_format(
             audit_report.print_summary();
         }
 
-        return Ok(Some(exit_code));
+        return Ok(Some((exit_status, audit_report)));
     } else if restrictions.has_any_restrictions() {
         restrictions.apply_to_command(cmd)?;
     }
 
+    if let Some(profile) = &security.seccomp_profile {
+        let mode = if security.seccomp_audit {
+            SeccompMode::Audit
+        } else {
+            SeccompMode::Enforce
+        };
+        let filter = SeccompFilter::load(profile, mode)?;
+        if let Err(e) = filter.apply_to_command(cmd) {
+            protection_unavailable(enforcement, e)?;
+        }
+    }
+
     Ok(None)
 }
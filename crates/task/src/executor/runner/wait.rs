@@ -0,0 +1,32 @@
+//! Waiting for the services a task needs before its command starts
+
+use cuenv_core::events::TaskRunEvents;
+use cuenv_core::{Result, TaskWait, TaskWaitTarget};
+use cuenv_utils::network::wait::{wait_until_ready, WaitTarget};
+
+/// Wait for each of `waits` in order, reporting progress as events of `run`
+pub async fn wait_for_services(run: &TaskRunEvents, waits: &[TaskWait]) -> Result<()> {
+    for wait in waits {
+        let target = match &wait.target {
+            TaskWaitTarget::Tcp(address) => WaitTarget::Tcp(address.clone()),
+            TaskWaitTarget::Http(url) => WaitTarget::Http(url.clone()),
+        };
+        run.progress(format!(
+            "Waiting for {target} (up to {}s)",
+            wait.timeout.as_secs()
+        ))
+        .await;
+
+        let name = target.to_string();
+        let waited = wait_until_ready(&target, wait.timeout, move |reason| {
+            run.progress(format!("Still waiting for {name}: {reason}"))
+        })
+        .await?;
+        run.progress(format!(
+            "{target} is ready after {:.1}s",
+            waited.as_secs_f64()
+        ))
+        .await;
+    }
+    Ok(())
+}
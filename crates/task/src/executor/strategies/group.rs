@@ -1,5 +1,6 @@
 //! Group execution strategy (simple collection)
 
+use super::hooks::with_node_hooks;
 use super::{create_task_id, FlattenedTask, GroupExecutionStrategy};
 use cuenv_config::{TaskCollection, TaskNode};
 use cuenv_core::Result;
@@ -63,6 +64,8 @@ impl GroupExecutionStrategy for GroupStrategy {
                         dependencies: deps,
                         node: node.clone(),
                         is_barrier: false,
+                        hook: None,
+                        shard: None,
                     });
                 }
                 TaskNode::Group {
@@ -71,8 +74,12 @@ impl GroupExecutionStrategy for GroupStrategy {
                     // Recursively process subgroup - use GroupStrategy for nested groups
                     let strategy = GroupStrategy;
                     let subtask_path = group_path.clone();
-                    let subgroup_tasks =
-                        strategy.process_group(&task_name, subtasks, subtask_path)?;
+                    let subgroup_tasks = with_node_hooks(
+                        &group_path,
+                        &task_name,
+                        node,
+                        strategy.process_group(&task_name, subtasks, subtask_path)?,
+                    );
                     flattened.extend(subgroup_tasks);
                 }
             }
@@ -0,0 +1,102 @@
+//! Group setup and teardown hooks
+//!
+//! A group's `setup` and `teardown` tasks become synthetic nodes around its
+//! members: every member depends on the setup node and the teardown node
+//! depends on every member. Both run once per run, however many members are
+//! selected and whether or not they are cache hits.
+
+use super::{create_task_id, FlattenedTask, GroupHook};
+use cuenv_config::TaskNode;
+
+/// Name of the synthetic node running a group's setup task
+pub const SETUP_NODE: &str = "@setup";
+/// Name of the synthetic node running a group's teardown task
+pub const TEARDOWN_NODE: &str = "@teardown";
+
+/// Wrap a flattened group with its setup and teardown nodes
+pub fn with_group_hooks(
+    group_path: &[String],
+    setup: Option<&str>,
+    teardown: Option<&str>,
+    members: Vec<FlattenedTask>,
+) -> Vec<FlattenedTask> {
+    let setup_node = setup.map(|task| {
+        hook_task(
+            group_path,
+            SETUP_NODE,
+            GroupHook::Setup(task.to_string()),
+            Vec::new(),
+        )
+    });
+
+    let members: Vec<FlattenedTask> = members
+        .into_iter()
+        .map(|mut member| {
+            if let Some(setup) = &setup_node {
+                member.dependencies.push(setup.id.clone());
+            }
+            member
+        })
+        .collect();
+
+    let teardown_node = teardown.map(|task| {
+        let dependencies = setup_node
+            .iter()
+            .chain(&members)
+            .map(|t| t.id.clone())
+            .collect();
+        hook_task(
+            group_path,
+            TEARDOWN_NODE,
+            GroupHook::Teardown(task.to_string()),
+            dependencies,
+        )
+    });
+
+    setup_node
+        .into_iter()
+        .chain(members)
+        .chain(teardown_node)
+        .collect()
+}
+
+/// Wrap a nested group node, whose path is `parent_path` plus `group_name`
+pub(super) fn with_node_hooks(
+    parent_path: &[String],
+    group_name: &str,
+    node: &TaskNode,
+    members: Vec<FlattenedTask>,
+) -> Vec<FlattenedTask> {
+    match node {
+        TaskNode::Group {
+            setup, teardown, ..
+        } => {
+            let group_path: Vec<String> = parent_path
+                .iter()
+                .cloned()
+                .chain(std::iter::once(group_name.to_string()))
+                .collect();
+            with_group_hooks(&group_path, setup.as_deref(), teardown.as_deref(), members)
+        }
+        TaskNode::Task(_) => members,
+    }
+}
+
+fn hook_task(
+    group_path: &[String],
+    name: &str,
+    hook: GroupHook,
+    dependencies: Vec<String>,
+) -> FlattenedTask {
+    FlattenedTask {
+        id: create_task_id(group_path, name),
+        group_path: group_path.to_vec(),
+        name: name.to_string(),
+        dependencies,
+        // Resolved from the referenced task when the DAG is built
+        node: TaskNode::Task(Box::default()),
+        is_barrier: false,
+        hook: Some(hook),
+        shard: None,
+    }
+}
@@ -1,9 +1,11 @@
 //! Task group execution strategies
 
+use crate::shard::ShardNode;
 use cuenv_config::{TaskCollection, TaskNode};
 use cuenv_core::Result;
 
 mod group;
+mod hooks;
 mod integration;
 mod parallel;
 mod sequential;
@@ -12,6 +14,7 @@ mod sequential;
 mod tests;
 
 pub use group::GroupStrategy;
+pub use hooks::{with_group_hooks, TEARDOWN_NODE};
 pub use integration::{process_task_group, TaskGroupExecutionPlan};
 pub use sequential::SequentialStrategy;
 
@@ -41,6 +44,29 @@ pub struct FlattenedTask {
     pub node: TaskNode,
     /// Whether this is a barrier task
     pub is_barrier: bool,
+    /// Set when this node runs a group's setup or teardown task
+    pub hook: Option<GroupHook>,
+    /// Set on the nodes of a task whose tests are split into shards
+    pub shard: Option<ShardNode>,
+}
+
+/// Synthetic node running a task referenced by a group's `setup` or `teardown`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupHook {
+    /// Runs before any member of the group; if it fails, no member runs
+    Setup(String),
+    /// Runs after every member of the group, and still runs when the run
+    /// fails once anything in the group has started
+    Teardown(String),
+}
+
+impl GroupHook {
+    /// Name of the referenced task
+    pub fn task(&self) -> &str {
+        match self {
+            GroupHook::Setup(task) | GroupHook::Teardown(task) => task,
+        }
+    }
 }
 
 /// Helper function to create a barrier task
@@ -56,6 +82,8 @@ pub fn create_barrier_task(
         dependencies,
         node: TaskNode::Task(Box::default()),
         is_barrier: true,
+        hook: None,
+        shard: None,
     }
 }
 
@@ -67,3 +95,9 @@ pub fn create_task_id(path: &[String], name: &str) -> String {
         format!("{}:{}", path.join("."), name)
     }
 }
+
+/// Name of a task's configuration for its ID: group members are `ci:build`
+/// in the graph but `ci.build` among the configured tasks
+pub fn task_config_name(task_id: &str) -> String {
+    task_id.replace(':', ".")
+}
@@ -1,5 +1,6 @@
 //! Parallel execution strategy
 
+use super::hooks::with_node_hooks;
 use super::{create_barrier_task, create_task_id, FlattenedTask, GroupExecutionStrategy};
 use cuenv_config::{TaskCollection, TaskNode};
 use cuenv_core::Result;
@@ -68,6 +69,8 @@ impl GroupExecutionStrategy for ParallelStrategy {
                         dependencies: deps,
                         node: node.clone(),
                         is_barrier: false,
+                        hook: None,
+                        shard: None,
                     });
                 }
                 TaskNode::Group {
@@ -88,8 +91,12 @@ impl GroupExecutionStrategy for ParallelStrategy {
                         TaskCollection::Parallel(_) => Box::new(ParallelStrategy),
                     };
                     let subtask_path = group_path.clone();
-                    let mut subgroup_tasks =
-                        strategy.process_group(&task_name, subtasks, subtask_path)?;
+                    let mut subgroup_tasks = with_node_hooks(
+                        &group_path,
+                        &task_name,
+                        node,
+                        strategy.process_group(&task_name, subtasks, subtask_path)?,
+                    );
 
                     // Update first tasks in subgroup to depend on subgroup start
                     for task in &mut subgroup_tasks {
@@ -1,5 +1,6 @@
 //! Sequential execution strategy
 
+use super::hooks::with_node_hooks;
 use super::{create_barrier_task, create_task_id, FlattenedTask, GroupExecutionStrategy};
 use cuenv_config::{TaskCollection, TaskNode};
 use cuenv_core::Result;
@@ -52,6 +53,8 @@ impl GroupExecutionStrategy for SequentialStrategy {
                         dependencies: deps,
                         node: node.clone(),
                         is_barrier: false,
+                        hook: None,
+                        shard: None,
                     });
 
                     prev_task_id = task_id;
@@ -74,8 +77,12 @@ impl GroupExecutionStrategy for SequentialStrategy {
                         TaskCollection::Parallel(_) => Box::new(super::GroupStrategy),
                     };
                     let subtask_path = group_path.clone();
-                    let mut subgroup_tasks =
-                        strategy.process_group(&task_name, subtasks, subtask_path)?;
+                    let mut subgroup_tasks = with_node_hooks(
+                        &group_path,
+                        &task_name,
+                        node,
+                        strategy.process_group(&task_name, subtasks, subtask_path)?,
+                    );
 
                     // Update first task in subgroup to depend on subgroup start
                     if let Some(first) = subgroup_tasks.first_mut() {
@@ -19,6 +19,8 @@ fn create_test_group(tasks: TaskCollection) -> TaskNode {
     TaskNode::Group {
         tasks,
         description: None,
+        setup: None,
+        teardown: None,
     }
 }
 
@@ -280,3 +282,72 @@ fn test_flattened_task_fields() {
     assert!(!barrier.dependencies.is_empty());
     assert!(barrier.is_barrier);
 }
+
+#[test]
+fn test_group_hooks_wrap_members() {
+    let mut tasks = IndexMap::new();
+    tasks.insert("unit".to_string(), create_test_task(None));
+    tasks.insert("e2e".to_string(), create_test_task(None));
+    let collection = TaskCollection::Parallel(tasks);
+
+    let members = GroupStrategy
+        .process_group("test", &collection, vec![])
+        .unwrap();
+    let result = with_group_hooks(
+        &["test".to_string()],
+        Some("db.start"),
+        Some("db.stop"),
+        members,
+    );
+
+    let setup = result.first().unwrap();
+    assert_eq!(setup.id, "test:@setup");
+    assert_eq!(setup.hook, Some(GroupHook::Setup("db.start".to_string())));
+    assert!(setup.dependencies.is_empty());
+
+    let teardown = result.last().unwrap();
+    assert_eq!(teardown.id, "test:@teardown");
+    assert_eq!(
+        teardown.hook,
+        Some(GroupHook::Teardown("db.stop".to_string()))
+    );
+    assert_eq!(
+        teardown.dependencies,
+        vec!["test:@setup", "test:unit", "test:e2e"]
+    );
+
+    for member in &result[1..result.len() - 1] {
+        assert!(member.hook.is_none());
+        assert!(member.dependencies.contains(&"test:@setup".to_string()));
+    }
+}
+
+#[test]
+fn test_nested_group_hooks() {
+    let mut inner = IndexMap::new();
+    inner.insert("migrate".to_string(), create_test_task(None));
+    let nested = TaskNode::Group {
+        description: None,
+        setup: Some("db.start".to_string()),
+        teardown: None,
+        tasks: TaskCollection::Parallel(inner),
+    };
+    let collection = TaskCollection::Sequential(vec![create_test_task(None), nested]);
+
+    let result = SequentialStrategy
+        .process_group("ci", &collection, vec![])
+        .unwrap();
+
+    let setup = result
+        .iter()
+        .find(|t| t.hook.is_some())
+        .expect("nested group should have a setup node");
+    assert_eq!(setup.id, format!("ci.task_1:{}", super::hooks::SETUP_NODE));
+    // The nested setup waits for the previous step of the sequence
+    assert!(setup
+        .dependencies
+        .contains(&"ci:task_1:__start__".to_string()));
+
+    let migrate = result.iter().find(|t| t.name == "migrate").unwrap();
+    assert!(migrate.dependencies.contains(&setup.id));
+}
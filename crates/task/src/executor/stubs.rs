@@ -0,0 +1,151 @@
+//! Stubbed tasks, for dry runs of a pipeline
+//!
+//! `CUENV_TASK_STUBS`, or `--stub task=command` of `cuenv task`, replace
+//! the command of tasks for a run, so the orchestration around them can be
+//! tried without running real builds. A stub runs with the task's shell, in
+//! its directory and environment, and receives its arguments; it is never
+//! cached, publishes nothing and waits for no services. Each stubbed run
+//! publishes a `TaskStubbed` event right after `TaskStarted`. Group members
+//! may be named `ci:build` or `ci.build`; a stub matching no task of the run
+//! is warned about.
+
+use super::strategies::task_config_name;
+use super::TaskExecutor;
+use crate::resolution::similar_task_names;
+use cuenv_core::suggestions::with_suggestions;
+use cuenv_core::{Error, Result, TaskDefinition, TaskExecutionMode, CUENV_TASK_STUBS_VAR};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+/// Stubs given on the command line, for the rest of the process
+static FLAG_STUBS: OnceLock<TaskStubs> = OnceLock::new();
+
+/// Stub tasks for the rest of the process, each of `stubs` a `task=command`
+/// taking precedence over `CUENV_TASK_STUBS`
+pub fn stub_tasks(stubs: &[String]) -> Result<()> {
+    let mut flags = TaskStubs::default();
+    for stub in stubs {
+        flags.add(stub)?;
+    }
+    // Only the first call counts, like the rest of the command line
+    let _ = FLAG_STUBS.set(flags);
+    Ok(())
+}
+
+/// Commands replacing those of tasks, by configured task name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskStubs(HashMap<String, String>);
+
+impl TaskStubs {
+    /// Stubs of `CUENV_TASK_STUBS`, one `task=command` per line
+    pub fn from_env() -> Result<Self> {
+        let mut stubs = Self::default();
+        if let Ok(spec) = std::env::var(CUENV_TASK_STUBS_VAR) {
+            for line in spec.lines().filter(|line| !line.trim().is_empty()) {
+                stubs.add(line)?;
+            }
+        }
+        Ok(stubs)
+    }
+
+    /// Stubs of a run: those of `CUENV_TASK_STUBS`, then the command line's
+    pub(crate) fn for_run() -> Result<Self> {
+        let mut stubs = Self::from_env()?;
+        if let Some(flags) = FLAG_STUBS.get() {
+            stubs.0.extend(flags.0.clone());
+        }
+        Ok(stubs)
+    }
+
+    /// Add the stub `task=command`, replacing any earlier one of the task
+    pub fn add(&mut self, stub: &str) -> Result<()> {
+        let (task, command) = stub
+            .split_once('=')
+            .map(|(task, command)| (task.trim(), command.trim()))
+            .filter(|(task, command)| !task.is_empty() && !command.is_empty())
+            .ok_or_else(|| {
+                Error::configuration(format!(
+                    "Invalid task stub '{stub}'. Expected task=command, e.g. build=true"
+                ))
+            })?;
+        self.0.insert(task_config_name(task), command.to_string());
+        Ok(())
+    }
+
+    /// The stub of `task`, if it has one
+    pub fn get(&self, task: &str) -> Option<&str> {
+        self.0.get(&task_config_name(task)).map(String::as_str)
+    }
+
+    /// Stubbed tasks that are none of `tasks`, sorted
+    pub fn unmatched<'a>(&self, tasks: impl IntoIterator<Item = &'a str>) -> Vec<&str> {
+        let tasks: BTreeSet<String> = tasks.into_iter().map(task_config_name).collect();
+        let mut unmatched: Vec<&str> = self
+            .0
+            .keys()
+            .filter(|task| !tasks.contains(*task))
+            .map(String::as_str)
+            .collect();
+        unmatched.sort_unstable();
+        unmatched
+    }
+}
+
+/// Make `task` run `command` instead of its own command
+pub(crate) fn apply(task: &mut TaskDefinition, command: &str) {
+    task.execution_mode = TaskExecutionMode::Command {
+        command: command.to_string(),
+    };
+    task.cache.enabled = false;
+    task.publish.clear();
+    task.wait_for.clear();
+}
+
+impl TaskExecutor {
+    /// The stub `task` runs, if it is stubbed
+    pub(crate) fn stub(&self, task: &str) -> Option<String> {
+        self.stubs.get(task).map(str::to_string)
+    }
+
+    /// Warn about each stub that matches none of `tasks`, the tasks of a run
+    pub(crate) fn warn_unmatched_stubs<'a>(&self, tasks: impl IntoIterator<Item = &'a str>) {
+        for task in self.stubs.unmatched(tasks) {
+            let message = with_suggestions(
+                format!("Stub of '{task}' matches no task of this run and is ignored"),
+                &similar_task_names(self.env_manager.get_tasks(), task),
+            );
+            eprintln!("⚠ {message}");
+            tracing::warn!("{message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_stubs() {
+        let mut stubs = TaskStubs::default();
+        stubs.add("build=echo built").unwrap();
+        stubs.add(" db:migrate = exit 1 ").unwrap();
+        stubs.add("build=true").unwrap();
+        assert_eq!(stubs.get("build"), Some("true"));
+        assert_eq!(stubs.get("db:migrate"), Some("exit 1"));
+        assert_eq!(stubs.get("db.migrate"), Some("exit 1"));
+        assert_eq!(stubs.get("test"), None);
+        assert!(stubs.add("build").is_err());
+        assert!(stubs.add("=true").is_err());
+        assert!(stubs.add("build=").is_err());
+    }
+
+    #[test]
+    fn test_unmatched_stubs() {
+        let mut stubs = TaskStubs::default();
+        for stub in ["ci.build=true", "ci:test=true", "biuld=true"] {
+            stubs.add(stub).unwrap();
+        }
+        assert_eq!(stubs.unmatched(["ci:build", "ci:test"]), ["biuld"]);
+        assert_eq!(stubs.unmatched(["build"]), ["biuld", "ci.build", "ci.test"]);
+    }
+}
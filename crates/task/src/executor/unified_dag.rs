@@ -5,11 +5,17 @@
 //! It ensures proper ordering preservation and eliminates code duplication.
 
 use cuenv_config::{TaskCollection, TaskConfig, TaskNode};
+use cuenv_core::suggestions::with_suggestions;
 use cuenv_core::{Result, TaskDefinition};
 use indexmap::IndexMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use super::strategies::{FlattenedTask, GroupExecutionStrategy, GroupStrategy, SequentialStrategy};
+use super::strategies::{
+    create_barrier_task, create_task_id, task_config_name, with_group_hooks, FlattenedTask,
+    GroupExecutionStrategy, GroupHook, GroupStrategy, SequentialStrategy, TEARDOWN_NODE,
+};
+use crate::resolution::similar_task_names;
+use crate::shard::{default_shard_count, shard_node_id, ShardNode, SHARD_NODE};
 
 /// A unified DAG builder that consolidates all task execution paths
 #[derive(Debug, Clone)]
@@ -104,15 +110,17 @@ impl UnifiedTaskDAG {
                 // It's a task group - use strategy to flatten it
                 self.collect_group_dependencies(task_name, task_node, &mut all_flattened_tasks)?;
             } else {
-                return Err(cuenv_core::Error::configuration(format!(
-                    "Task or task group '{task_name}' not found"
+                return Err(cuenv_core::Error::configuration(with_suggestions(
+                    format!("Task or task group '{task_name}' not found"),
+                    &similar_task_names(&self.task_configs, task_name),
                 )));
             }
         }
 
         self.execution_graph = all_flattened_tasks;
-        self.build_dependency_map()?;
         self.build_task_definitions_for_flattened_tasks()?;
+        self.expand_shards();
+        self.build_dependency_map()?;
 
         Ok(())
     }
@@ -124,46 +132,15 @@ impl UnifiedTaskDAG {
         task_config: &TaskConfig,
         flattened_tasks: &mut Vec<FlattenedTask>,
     ) -> Result<()> {
-        // If this task has dependencies, collect them first
-        if let Some(deps) = &task_config.dependencies {
-            for dep_name in deps {
-                if let Some(dep_node) = self.task_nodes.get(dep_name) {
-                    // Dependency is a group - flatten it using appropriate strategy
-                    self.collect_group_dependencies(dep_name, dep_node, flattened_tasks)?;
-                } else if let Some(dep_config) = self.task_configs.get(dep_name) {
-                    // Dependency is a regular task - recurse
-                    self.collect_task_dependencies(dep_name, dep_config, flattened_tasks)?;
-                } else {
-                    return Err(cuenv_core::Error::configuration(format!(
-                        "Dependency '{dep_name}' not found for task '{task_name}'"
-                    )));
-                }
-            }
-        }
+        // Collect dependencies first so they precede this task
+        let resolved_dependencies =
+            self.collect_dependency_list(task_name, task_config, flattened_tasks)?;
 
         // Add this task if not already added
         if !flattened_tasks
             .iter()
             .any(|t| t.name == task_name && !t.is_barrier)
         {
-            // Resolve dependencies: if dependency is a group, point to its end barrier
-            let mut resolved_dependencies = Vec::new();
-            if let Some(deps) = &task_config.dependencies {
-                for dep_name in deps {
-                    if self.task_nodes.contains_key(dep_name) {
-                        // This dependency is a group - depend on its end barrier
-                        resolved_dependencies.push(format!("{dep_name}:__end__"));
-                    } else if self.task_configs.contains_key(dep_name) {
-                        // This dependency is a regular task
-                        resolved_dependencies.push(dep_name.clone());
-                    } else {
-                        return Err(cuenv_core::Error::configuration(format!(
-                            "Dependency '{dep_name}' not found for task '{task_name}'"
-                        )));
-                    }
-                }
-            }
-
             flattened_tasks.push(FlattenedTask {
                 id: task_name.to_string(),
                 group_path: Vec::new(),
@@ -171,12 +148,45 @@ impl UnifiedTaskDAG {
                 dependencies: resolved_dependencies,
                 node: TaskNode::Task(Box::new(task_config.clone())),
                 is_barrier: false,
+                hook: None,
+                shard: None,
             });
         }
 
         Ok(())
     }
 
+    /// Collect a task's dependencies and return the node IDs it must wait for
+    fn collect_dependency_list(
+        &self,
+        task_name: &str,
+        task_config: &TaskConfig,
+        flattened_tasks: &mut Vec<FlattenedTask>,
+    ) -> Result<Vec<String>> {
+        let Some(deps) = &task_config.dependencies else {
+            return Ok(Vec::new());
+        };
+
+        let mut resolved_dependencies = Vec::with_capacity(deps.len());
+        for dep_name in deps {
+            if let Some(dep_node) = self.task_nodes.get(dep_name) {
+                // Dependency is a group - flatten it and wait for it to finish
+                self.collect_group_dependencies(dep_name, dep_node, flattened_tasks)?;
+                resolved_dependencies.push(group_exit_id(dep_name, dep_node));
+            } else if let Some(dep_config) = self.task_configs.get(dep_name) {
+                // Dependency is a regular task - recurse
+                self.collect_task_dependencies(dep_name, dep_config, flattened_tasks)?;
+                resolved_dependencies.push(dep_name.clone());
+            } else {
+                return Err(cuenv_core::Error::configuration(format!(
+                    "Dependency '{dep_name}' not found for task '{task_name}'"
+                )));
+            }
+        }
+
+        Ok(resolved_dependencies)
+    }
+
     /// Collect dependencies for a task group using appropriate strategy
     fn collect_group_dependencies(
         &self,
@@ -185,20 +195,46 @@ impl UnifiedTaskDAG {
         flattened_tasks: &mut Vec<FlattenedTask>,
     ) -> Result<()> {
         match group_node {
-            TaskNode::Group { tasks, .. } => {
+            TaskNode::Group {
+                tasks,
+                setup,
+                teardown,
+                ..
+            } => {
                 // Use the appropriate strategy based on collection type
                 let strategy: Box<dyn GroupExecutionStrategy> = match tasks {
                     TaskCollection::Sequential(_) => Box::new(SequentialStrategy),
                     TaskCollection::Parallel(_) => Box::new(GroupStrategy),
                 };
-                let group_flattened = strategy.process_group(group_name, tasks, Vec::new())?;
+                let group_path = [group_name.to_string()];
+                let mut group_flattened = with_group_hooks(
+                    &group_path,
+                    setup.as_deref(),
+                    teardown.as_deref(),
+                    strategy.process_group(group_name, tasks, Vec::new())?,
+                );
+
+                // Parallel groups have no end barrier of their own, but
+                // dependents of the group wait for one
+                let end_id = create_task_id(&group_path, "__end__");
+                if teardown.is_none() && !group_flattened.iter().any(|t| t.id == end_id) {
+                    let members = group_flattened.iter().map(|t| t.id.clone()).collect();
+                    group_flattened.push(create_barrier_task(end_id, group_path.to_vec(), members));
+                }
 
                 // Add all tasks from this group
                 for task in group_flattened {
                     // Avoid duplicates
-                    if !flattened_tasks.iter().any(|t| t.id == task.id) {
-                        flattened_tasks.push(task);
+                    if flattened_tasks.iter().any(|t| t.id == task.id) {
+                        continue;
                     }
+                    let task = match &task.hook {
+                        Some(hook) => {
+                            self.resolve_group_hook(task.clone(), hook, flattened_tasks)?
+                        }
+                        None => task,
+                    };
+                    flattened_tasks.push(task);
                 }
             }
             TaskNode::Task(task_config) => {
@@ -210,6 +246,87 @@ impl UnifiedTaskDAG {
         Ok(())
     }
 
+    /// Point a setup or teardown node at the task it references
+    ///
+    /// The referenced task's own dependencies are collected and run before
+    /// the hook node.
+    fn resolve_group_hook(
+        &self,
+        mut task: FlattenedTask,
+        hook: &GroupHook,
+        flattened_tasks: &mut Vec<FlattenedTask>,
+    ) -> Result<FlattenedTask> {
+        let config = self.task_configs.get(hook.task()).ok_or_else(|| {
+            cuenv_core::Error::configuration(format!(
+                "Task '{}' referenced by '{}' not found",
+                hook.task(),
+                task.id
+            ))
+        })?;
+
+        let dependencies = self.collect_dependency_list(hook.task(), config, flattened_tasks)?;
+        task.dependencies.extend(dependencies);
+        task.node = TaskNode::Task(Box::new(config.clone()));
+        Ok(task)
+    }
+
+    /// Split every shardable task into shard nodes
+    ///
+    /// The shards take over the task's dependencies and its definition,
+    /// uncached since each run may deal tests out differently. The task's own
+    /// node stays, so dependents keep waiting for it, but only waits for the
+    /// shards and reports on them.
+    fn expand_shards(&mut self) {
+        let graph = std::mem::take(&mut self.execution_graph);
+        for task in graph {
+            let count = match &task.node {
+                TaskNode::Task(config)
+                    if config.shardable == Some(true)
+                        && !task.is_barrier
+                        && task.hook.is_none() =>
+                {
+                    config.shards.unwrap_or_else(default_shard_count).max(1)
+                }
+                _ => {
+                    self.execution_graph.push(task);
+                    continue;
+                }
+            };
+
+            let shards: Vec<FlattenedTask> = (1..=count)
+                .map(|index| FlattenedTask {
+                    id: shard_node_id(&task.id, index),
+                    name: format!("{SHARD_NODE}{index}"),
+                    shard: Some(ShardNode::Shard {
+                        task: task.id.clone(),
+                        index,
+                        count,
+                    }),
+                    ..task.clone()
+                })
+                .collect();
+            if let Some(definition) = self.task_definitions.get(&task.id).cloned() {
+                for shard in &shards {
+                    let shard_definition = TaskDefinition {
+                        name: shard.id.clone(),
+                        cache: cuenv_core::TaskCache::default(),
+                        ..definition.clone()
+                    };
+                    self.task_definitions
+                        .insert(shard.id.clone(), shard_definition);
+                }
+            }
+
+            let merge = FlattenedTask {
+                dependencies: shards.iter().map(|shard| shard.id.clone()).collect(),
+                shard: Some(ShardNode::Merge),
+                ..task
+            };
+            self.execution_graph.extend(shards);
+            self.execution_graph.push(merge);
+        }
+    }
+
     /// Build the dependency map from flattened tasks
     fn build_dependency_map(&mut self) -> Result<()> {
         for task in &self.execution_graph {
@@ -231,6 +348,42 @@ impl UnifiedTaskDAG {
                 continue;
             }
 
+            // Setup and teardown run on every run, so they are never cached
+            let referenced = task
+                .hook
+                .as_ref()
+                .and_then(|hook| self.task_definitions.get(hook.task()));
+            if let Some(referenced) = referenced {
+                let definition = TaskDefinition {
+                    name: task.id.clone(),
+                    dependencies: task
+                        .dependencies
+                        .iter()
+                        .map(|dep| cuenv_core::ResolvedDependency::new(dep.clone()))
+                        .collect(),
+                    cache: cuenv_core::TaskCache::default(),
+                    ..referenced.clone()
+                };
+                self.task_definitions.insert(task.id.clone(), definition);
+                continue;
+            }
+
+            // Group members run with the definition the task builder made
+            // for their configuration, which security policy was enforced on
+            if let Some(built) = self.task_definitions.get(&task_config_name(&task.id)) {
+                let definition = TaskDefinition {
+                    name: task.id.clone(),
+                    dependencies: task
+                        .dependencies
+                        .iter()
+                        .map(|dep| cuenv_core::ResolvedDependency::new(dep.clone()))
+                        .collect(),
+                    ..built.clone()
+                };
+                self.task_definitions.insert(task.id.clone(), definition);
+                continue;
+            }
+
             // Extract the underlying task config from the flattened task
             if let TaskNode::Task(task_config) = &task.node {
                 let definition = TaskDefinition {
@@ -265,10 +418,22 @@ impl UnifiedTaskDAG {
                         .clone()
                         .unwrap_or_else(|| "sh".to_string()),
                     inputs: task_config.inputs.clone().unwrap_or_default(),
-                    outputs: task_config.outputs.clone().unwrap_or_default(),
+                    outputs: task_config
+                        .outputs
+                        .as_ref()
+                        .map(|outputs| outputs.files())
+                        .unwrap_or_default(),
                     security: None, // TODO: Convert from task_config.security
-                    cache: cuenv_core::TaskCache::default(), // TODO: Convert from task_config.cache
+                    cache: crate::builder::conversion::convert_cache_config(task_config)?,
                     timeout: Duration::from_secs(300), // TODO: Extract from config if available
+                    run_as: crate::builder::conversion::convert_run_as(task_config),
+                    process: crate::builder::conversion::convert_process_settings(task_config)?,
+                    wait_for: crate::builder::conversion::convert_wait_for(task_config)?,
+                    publish: crate::builder::conversion::convert_publish_config(task_config)?,
+                    problem_matchers: crate::builder::conversion::convert_problem_matchers(
+                        task_config,
+                    )?,
+                    output_values: crate::builder::conversion::convert_output_values(task_config),
                 };
 
                 self.task_definitions.insert(task.id.clone(), definition);
@@ -314,6 +479,47 @@ impl UnifiedTaskDAG {
     pub fn get_task_dependencies(&self, task_id: &str) -> Option<&[String]> {
         self.dependencies.get(task_id).map(|deps| deps.as_slice())
     }
+
+    /// Get a flattened task by ID
+    pub fn get_flattened_task(&self, task_id: &str) -> Option<&FlattenedTask> {
+        self.execution_graph.iter().find(|t| t.id == task_id)
+    }
+
+    /// Whether a node still runs after an earlier level has failed
+    ///
+    /// Teardown nodes do once something in their group (the setup node or
+    /// any member) has been started, and the node of a sharded task does
+    /// once any of its shards has, to report on them.
+    pub fn runs_after_failure(&self, task_id: &str, started: &HashSet<String>) -> bool {
+        let Some(task) = self.get_flattened_task(task_id) else {
+            return false;
+        };
+        if task.shard == Some(ShardNode::Merge) {
+            return task
+                .dependencies
+                .iter()
+                .any(|shard| started.contains(shard));
+        }
+        if !matches!(task.hook, Some(GroupHook::Teardown(_))) {
+            return false;
+        }
+
+        self.execution_graph.iter().any(|t| {
+            t.id != task.id && started.contains(&t.id) && t.group_path.starts_with(&task.group_path)
+        })
+    }
+}
+
+/// Node a dependent of `name` waits for; a group with a teardown is only
+/// finished once the teardown has run
+fn group_exit_id(name: &str, node: &TaskNode) -> String {
+    match node {
+        TaskNode::Task(_) => name.to_string(),
+        TaskNode::Group {
+            teardown: Some(_), ..
+        } => format!("{name}:{TEARDOWN_NODE}"),
+        TaskNode::Group { .. } => format!("{name}:__end__"),
+    }
 }
 
 #[cfg(test)]
@@ -333,6 +539,8 @@ mod tests {
         TaskNode::Group {
             tasks,
             description: None,
+            setup: None,
+            teardown: None,
         }
     }
 
@@ -585,4 +793,284 @@ mod tests {
             regular_tasks.len()
         );
     }
+
+    fn create_hooked_group(setup: Option<&str>, teardown: Option<&str>) -> TaskNode {
+        let mut tasks = IndexMap::new();
+        tasks.insert(
+            "unit".to_string(),
+            TaskNode::Task(Box::new(create_test_config("echo unit", None))),
+        );
+        tasks.insert(
+            "e2e".to_string(),
+            TaskNode::Task(Box::new(create_test_config("echo e2e", None))),
+        );
+        TaskNode::Group {
+            description: None,
+            setup: setup.map(String::from),
+            teardown: teardown.map(String::from),
+            tasks: TaskCollection::Parallel(tasks),
+        }
+    }
+
+    fn level_of(levels: &[Vec<String>], task_id: &str) -> usize {
+        levels
+            .iter()
+            .position(|level| level.iter().any(|id| id == task_id))
+            .unwrap_or_else(|| panic!("{task_id} not scheduled"))
+    }
+
+    #[test]
+    fn test_group_setup_and_teardown_nodes() {
+        let mut task_configs = HashMap::new();
+        task_configs.insert("network".to_string(), create_test_config("echo net", None));
+        task_configs.insert(
+            "db_start".to_string(),
+            create_test_config("echo start", Some(vec!["network".to_string()])),
+        );
+        task_configs.insert("db_stop".to_string(), create_test_config("echo stop", None));
+        task_configs.insert(
+            "report".to_string(),
+            create_test_config("echo report", Some(vec!["test".to_string()])),
+        );
+
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "test".to_string(),
+            create_hooked_group(Some("db_start"), Some("db_stop")),
+        );
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_configs(task_configs)
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["report".to_string()])
+            .unwrap();
+        let levels = dag.get_execution_levels().unwrap();
+
+        // The setup task's own dependencies come first
+        assert!(level_of(&levels, "network") < level_of(&levels, "test:@setup"));
+        for member in ["test:unit", "test:e2e"] {
+            assert!(level_of(&levels, "test:@setup") < level_of(&levels, member));
+            assert!(level_of(&levels, member) < level_of(&levels, "test:@teardown"));
+        }
+        // Dependents of the group wait for its teardown
+        assert!(level_of(&levels, "test:@teardown") < level_of(&levels, "report"));
+
+        let setup = dag.get_task_definition("test:@setup").unwrap();
+        assert_eq!(setup.get_execution_content(), "echo start");
+        assert!(!setup.cache.enabled);
+        let teardown = dag.get_task_definition("test:@teardown").unwrap();
+        assert_eq!(teardown.get_execution_content(), "echo stop");
+    }
+
+    #[test]
+    fn test_task_depending_on_parallel_group() {
+        let mut task_configs = HashMap::new();
+        task_configs.insert(
+            "release".to_string(),
+            create_test_config("echo release", Some(vec!["ci".to_string()])),
+        );
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert("ci".to_string(), create_hooked_group(None, None));
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_configs(task_configs)
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["release".to_string()])
+            .unwrap();
+
+        let levels = dag.get_execution_levels().unwrap();
+        assert!(level_of(&levels, "release") > level_of(&levels, "ci:unit"));
+        assert!(level_of(&levels, "release") > level_of(&levels, "ci:e2e"));
+    }
+
+    #[test]
+    fn test_group_member_runs_as_configured_user() {
+        let mut tasks = IndexMap::new();
+        tasks.insert(
+            "migrate".to_string(),
+            TaskNode::Task(Box::new(TaskConfig {
+                run_as: Some(cuenv_config::RunAsConfig {
+                    user: "postgres".to_string(),
+                    group: None,
+                }),
+                ..create_test_config("psql -f migrate.sql", None)
+            })),
+        );
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "db".to_string(),
+            create_test_group(TaskCollection::Parallel(tasks)),
+        );
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["db".to_string()])
+            .unwrap();
+
+        let run_as = dag
+            .get_task_definition("db:migrate")
+            .unwrap()
+            .run_as
+            .clone();
+        assert_eq!(
+            run_as.map(|run_as| run_as.user).as_deref(),
+            Some("postgres")
+        );
+    }
+
+    #[test]
+    fn test_group_member_publishes_its_outputs() {
+        let mut tasks = IndexMap::new();
+        tasks.insert(
+            "build".to_string(),
+            TaskNode::Task(Box::new(TaskConfig {
+                publish: Some(vec![cuenv_config::PublishConfig {
+                    files: Some(vec!["dist/**".to_string()]),
+                    target: cuenv_config::PublishTargetConfig::Dir("artifacts".to_string()),
+                }]),
+                ..create_test_config("make dist", None)
+            })),
+        );
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "ci".to_string(),
+            create_test_group(TaskCollection::Parallel(tasks)),
+        );
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["ci".to_string()])
+            .unwrap();
+
+        let publish = &dag.get_task_definition("ci:build").unwrap().publish;
+        assert_eq!(publish.len(), 1);
+        assert_eq!(
+            publish[0].target,
+            cuenv_core::TaskPublishTarget::Directory("artifacts".into())
+        );
+    }
+
+    #[test]
+    fn test_group_member_runs_with_its_built_definition() {
+        let mut tasks = IndexMap::new();
+        tasks.insert(
+            "deploy".to_string(),
+            TaskNode::Task(Box::new(create_test_config("./deploy.sh", None))),
+        );
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "ci".to_string(),
+            create_test_group(TaskCollection::Parallel(tasks)),
+        );
+        // As the task builder leaves it once a policy capped its timeout
+        let mut built = TaskDefinition::new(
+            "ci.deploy".to_string(),
+            cuenv_core::TaskExecutionMode::Command {
+                command: "./deploy.sh".to_string(),
+            },
+            "/workspace".into(),
+        );
+        built.timeout = std::time::Duration::from_secs(60);
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_nodes(task_nodes)
+            .with_task_definitions(HashMap::from([("ci.deploy".to_string(), built)]))
+            .build_for_tasks(&["ci".to_string()])
+            .unwrap();
+
+        let definition = dag.get_task_definition("ci:deploy").unwrap();
+        assert_eq!(definition.name, "ci:deploy");
+        assert_eq!(definition.timeout, std::time::Duration::from_secs(60));
+        assert_eq!(
+            definition.working_directory,
+            std::path::Path::new("/workspace")
+        );
+    }
+
+    #[test]
+    fn test_group_hook_must_exist() {
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "test".to_string(),
+            create_hooked_group(Some("missing"), None),
+        );
+
+        let result = UnifiedTaskDAG::builder()
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["test".to_string()]);
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("'missing' referenced by 'test:@setup'"));
+    }
+
+    #[test]
+    fn test_teardown_runs_after_failure_once_started() {
+        let mut task_configs = HashMap::new();
+        task_configs.insert("db_start".to_string(), create_test_config("false", None));
+        task_configs.insert("db_stop".to_string(), create_test_config("echo stop", None));
+
+        let mut task_nodes = IndexMap::new();
+        task_nodes.insert(
+            "test".to_string(),
+            create_hooked_group(Some("db_start"), Some("db_stop")),
+        );
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_configs(task_configs)
+            .with_task_nodes(task_nodes)
+            .build_for_tasks(&["test".to_string()])
+            .unwrap();
+
+        let nothing_started = HashSet::new();
+        assert!(!dag.runs_after_failure("test:@teardown", &nothing_started));
+
+        let setup_started = HashSet::from(["test:@setup".to_string()]);
+        assert!(dag.runs_after_failure("test:@teardown", &setup_started));
+        assert!(!dag.runs_after_failure("test:unit", &setup_started));
+    }
+
+    #[test]
+    fn test_shardable_task_expands_into_shards() {
+        let mut task_configs = HashMap::new();
+        task_configs.insert("build".to_string(), create_test_config("echo build", None));
+        task_configs.insert(
+            "test".to_string(),
+            TaskConfig {
+                shardable: Some(true),
+                test_list: Some("printf 'a\\nb\\n'".to_string()),
+                shards: Some(2),
+                ..create_test_config("echo test", Some(vec!["build".to_string()]))
+            },
+        );
+        task_configs.insert(
+            "release".to_string(),
+            create_test_config("echo release", Some(vec!["test".to_string()])),
+        );
+
+        let dag = UnifiedTaskDAG::builder()
+            .with_task_configs(task_configs)
+            .build_for_tasks(&["release".to_string()])
+            .unwrap();
+        let levels = dag.get_execution_levels().unwrap();
+
+        for shard in ["test:@shard1", "test:@shard2"] {
+            assert!(level_of(&levels, "build") < level_of(&levels, shard));
+            assert!(level_of(&levels, shard) < level_of(&levels, "test"));
+            let definition = dag.get_task_definition(shard).unwrap();
+            assert_eq!(definition.get_execution_content(), "echo test");
+        }
+        assert!(level_of(&levels, "test") < level_of(&levels, "release"));
+        assert_eq!(
+            dag.get_flattened_task("test:@shard2").unwrap().shard,
+            Some(ShardNode::Shard {
+                task: "test".to_string(),
+                index: 2,
+                count: 2
+            })
+        );
+
+        // The task still reports once a shard has started
+        let started = HashSet::from(["test:@shard1".to_string()]);
+        assert!(dag.runs_after_failure("test", &started));
+        assert!(!dag.runs_after_failure("test", &HashSet::new()));
+    }
 }
@@ -0,0 +1,146 @@
+//! Resolved task definitions for external build systems
+//!
+//! `cuenv task export` turns the unified task DAG into a stable,
+//! self-contained description of every task: what it runs, where, what it
+//! depends on and which files it reads and writes. Group barriers are
+//! collapsed, so each dependency names a task that actually runs. The
+//! format is versioned; fields are only ever added within a version.
+
+mod starlark;
+
+use crate::executor::UnifiedTaskDAG;
+use cuenv_core::{Error, Result, TaskExecutionMode};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Version of the export format
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Every task of a package, in dependency order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskExport {
+    pub version: u32,
+    /// Directory containing `env.cue`; task working directories are relative to it
+    pub root: PathBuf,
+    /// Environment the variables were resolved for
+    pub environment: Option<String>,
+    /// Variables every task runs with; secret references are kept unresolved
+    pub env: BTreeMap<String, String>,
+    pub tasks: Vec<ExportedTask>,
+}
+
+/// A single task, fully resolved
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportedTask {
+    /// Unique name, e.g. `build` or `ci:test` for a task in a group
+    pub name: String,
+    pub description: Option<String>,
+    /// Command line, for command tasks
+    pub command: Option<String>,
+    /// Script content, for script tasks
+    pub script: Option<String>,
+    pub shell: String,
+    /// Working directory relative to the root; `.` for the root itself
+    pub working_dir: PathBuf,
+    /// Names of the tasks that must finish first
+    pub dependencies: Vec<String>,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub cache: bool,
+    pub timeout_secs: u64,
+}
+
+impl TaskExport {
+    /// Export every task in `dag`
+    pub fn from_dag(
+        dag: &UnifiedTaskDAG,
+        root: &Path,
+        environment: Option<String>,
+        env: BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let tasks = dag
+            .get_execution_levels()?
+            .into_iter()
+            .flat_map(|mut level| {
+                // Keep the output stable from one export to the next
+                level.sort();
+                level
+            })
+            .filter(|id| !is_barrier(dag, id))
+            .map(|id| export_task(dag, root, &id))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            version: EXPORT_FORMAT_VERSION,
+            root: root.to_path_buf(),
+            environment,
+            env,
+            tasks,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Json {
+            message: "Failed to serialize task export".to_string(),
+            source: e,
+        })
+    }
+
+    /// The export as a Starlark file of plain dicts, loadable from Bazel,
+    /// Buck2 or any other Starlark interpreter
+    pub fn to_starlark(&self) -> String {
+        starlark::render(self)
+    }
+}
+
+fn export_task(dag: &UnifiedTaskDAG, root: &Path, id: &str) -> Result<ExportedTask> {
+    let definition = dag
+        .get_task_definition(id)
+        .ok_or_else(|| Error::configuration(format!("No definition for task '{id}'")))?;
+    let (command, script) = match &definition.execution_mode {
+        TaskExecutionMode::Command { command } => (Some(command.clone()), None),
+        TaskExecutionMode::Script { content } => (None, Some(content.clone())),
+    };
+    let working_dir = match definition.working_directory.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => definition.working_directory.clone(),
+    };
+
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(dag, id, &mut dependencies);
+
+    Ok(ExportedTask {
+        name: id.to_string(),
+        description: definition.description.clone(),
+        command,
+        script,
+        shell: definition.shell.clone(),
+        working_dir,
+        dependencies: dependencies.into_iter().collect(),
+        inputs: definition.inputs.clone(),
+        outputs: definition.outputs.clone(),
+        cache: definition.cache.enabled,
+        timeout_secs: definition.timeout.as_secs(),
+    })
+}
+
+/// Direct dependencies of `id`, looking through barrier nodes
+fn collect_dependencies(dag: &UnifiedTaskDAG, id: &str, out: &mut BTreeSet<String>) {
+    for dependency in dag.get_task_dependencies(id).unwrap_or_default() {
+        if is_barrier(dag, dependency) {
+            collect_dependencies(dag, dependency, out);
+        } else {
+            out.insert(dependency.clone());
+        }
+    }
+}
+
+fn is_barrier(dag: &UnifiedTaskDAG, id: &str) -> bool {
+    dag.get_flattened_task(id)
+        .is_some_and(|task| task.is_barrier)
+}
+
+#[cfg(test)]
+mod tests;
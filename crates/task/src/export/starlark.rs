@@ -0,0 +1,93 @@
+//! Starlark rendering of a task export
+//!
+//! Only literals are emitted (dicts, lists, strings, ints, bools and
+//! `None`), so the file loads under any Starlark dialect and leaves it to
+//! the consuming rules to turn tasks into targets.
+
+use super::{ExportedTask, TaskExport};
+use std::fmt::Write;
+use std::path::Path;
+
+pub(super) fn render(export: &TaskExport) -> String {
+    let mut out = String::new();
+    out.push_str("# Generated by `cuenv task export --format starlark`. Do not edit.\n\n");
+    let _ = writeln!(out, "CUENV_EXPORT_VERSION = {}", export.version);
+    let _ = writeln!(out, "CUENV_ROOT = {}", path(&export.root));
+    let _ = writeln!(
+        out,
+        "CUENV_ENVIRONMENT = {}",
+        optional(export.environment.as_deref())
+    );
+
+    out.push_str("\nCUENV_ENV = {\n");
+    for (key, value) in &export.env {
+        let _ = writeln!(out, "    {}: {},", string(key), string(value));
+    }
+    out.push_str("}\n\nCUENV_TASKS = [\n");
+    for task in &export.tasks {
+        render_task(&mut out, task);
+    }
+    out.push_str("]\n");
+    out
+}
+
+fn render_task(out: &mut String, task: &ExportedTask) {
+    let fields = [
+        ("name", string(&task.name)),
+        ("description", optional(task.description.as_deref())),
+        ("command", optional(task.command.as_deref())),
+        ("script", optional(task.script.as_deref())),
+        ("shell", string(&task.shell)),
+        ("working_dir", path(&task.working_dir)),
+        ("dependencies", list(&task.dependencies)),
+        ("inputs", list(&task.inputs)),
+        ("outputs", list(&task.outputs)),
+        ("cache", boolean(task.cache)),
+        ("timeout_secs", task.timeout_secs.to_string()),
+    ];
+
+    out.push_str("    {\n");
+    for (key, value) in fields {
+        let _ = writeln!(out, "        {}: {value},", string(key));
+    }
+    out.push_str("    },\n");
+}
+
+/// A double-quoted string literal
+fn string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // Octal escapes are the form every Starlark implementation accepts
+            c if c.is_ascii_control() => {
+                let _ = write!(literal, "\\{:03o}", c as u32);
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn path(value: &Path) -> String {
+    string(&value.to_string_lossy())
+}
+
+fn optional(value: Option<&str>) -> String {
+    value.map_or_else(|| "None".to_string(), string)
+}
+
+fn list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|value| string(value)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn boolean(value: bool) -> String {
+    if value { "True" } else { "False" }.to_string()
+}
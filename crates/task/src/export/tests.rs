@@ -0,0 +1,94 @@
+use super::*;
+use crate::builder::TaskBuilder;
+use cuenv_config::{TaskCollection, TaskConfig, TaskNode};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+fn task(command: &str, deps: &[&str]) -> TaskConfig {
+    TaskConfig {
+        command: Some(command.to_string()),
+        dependencies: (!deps.is_empty()).then(|| deps.iter().map(|d| d.to_string()).collect()),
+        inputs: Some(vec!["src/**".to_string()]),
+        ..Default::default()
+    }
+}
+
+fn export(root: &Path) -> TaskExport {
+    let mut configs = HashMap::from([
+        ("build".to_string(), task("cargo build", &[])),
+        (
+            "release".to_string(),
+            task("./release.sh", &["build", "ci"]),
+        ),
+        ("ci.unit".to_string(), task("cargo test", &[])),
+        ("ci.lint".to_string(), task("cargo clippy", &[])),
+    ]);
+    configs.get_mut("ci.lint").unwrap().description = Some("Lint \"all\"\ncode".to_string());
+
+    let members = IndexMap::from([
+        (
+            "unit".to_string(),
+            TaskNode::Task(Box::new(configs["ci.unit"].clone())),
+        ),
+        (
+            "lint".to_string(),
+            TaskNode::Task(Box::new(configs["ci.lint"].clone())),
+        ),
+    ]);
+    let nodes = IndexMap::from([(
+        "ci".to_string(),
+        TaskNode::Group {
+            tasks: TaskCollection::Parallel(members),
+            description: None,
+            setup: None,
+            teardown: None,
+        },
+    )]);
+
+    let definitions = TaskBuilder::new_with_env(root.to_path_buf(), HashMap::new())
+        .build_tasks_with_nodes(configs.clone(), nodes.clone())
+        .unwrap();
+    let dag = UnifiedTaskDAG::builder()
+        .with_task_configs(configs)
+        .with_task_nodes(nodes)
+        .with_task_definitions(definitions)
+        .build_for_tasks(&["release".to_string()])
+        .unwrap();
+
+    let env = BTreeMap::from([("GREETING".to_string(), "hello".to_string())]);
+    TaskExport::from_dag(&dag, root, Some("dev".to_string()), env).unwrap()
+}
+
+#[test]
+fn test_export_resolves_dependencies_through_groups() {
+    let root = TempDir::new().unwrap();
+    let export = export(root.path());
+
+    let names: Vec<&str> = export.tasks.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names.last(), Some(&"release"));
+    assert!(!names.iter().any(|name| name.contains("__")));
+
+    let release = export.tasks.last().unwrap();
+    assert_eq!(release.dependencies, ["build", "ci:lint", "ci:unit"]);
+    assert_eq!(release.command.as_deref(), Some("./release.sh"));
+    assert_eq!(release.working_dir, PathBuf::from("."));
+    assert_eq!(release.inputs, ["src/**"]);
+
+    let json: serde_json::Value = serde_json::from_str(&export.to_json().unwrap()).unwrap();
+    assert_eq!(json["version"], EXPORT_FORMAT_VERSION);
+    assert_eq!(json["env"]["GREETING"], "hello");
+}
+
+#[test]
+fn test_starlark_output_escapes_strings() {
+    let root = TempDir::new().unwrap();
+    let starlark = export(root.path()).to_starlark();
+
+    assert!(starlark.contains("CUENV_ENVIRONMENT = \"dev\""));
+    assert!(starlark.contains("\"GREETING\": \"hello\","));
+    assert!(starlark.contains("\"description\": \"Lint \\\"all\\\"\\ncode\","));
+    assert!(starlark.contains("\"dependencies\": [\"build\", \"ci:lint\", \"ci:unit\"],"));
+    assert!(starlark.contains("\"cache\": False,") || starlark.contains("\"cache\": True,"));
+    assert!(starlark.ends_with("]\n"));
+}
@@ -4,14 +4,24 @@
 //! cross-package references, and command execution.
 
 pub mod builder;
+pub mod cancellation;
 pub mod command_executor;
 pub mod cross_package;
 pub mod executor;
+pub mod export;
 // pub mod executor_v2;  // Complex version with compilation issues
 // pub mod executor_tui;
+pub mod problem_matcher;
 pub mod protocol;
+pub mod publish;
 pub mod registry;
+pub mod resolution;
+pub mod scheduler;
+pub mod script;
+pub mod selection;
+pub mod shard;
 pub mod source;
+pub mod workspace_env;
 
 pub use builder::*;
 pub use command_executor::*;
@@ -20,4 +30,6 @@ pub use executor::*;
 // pub use executor_tui::*;
 pub use protocol::*;
 pub use registry::*;
+pub use resolution::*;
 pub use source::*;
+pub use workspace_env::*;
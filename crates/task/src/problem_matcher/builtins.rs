@@ -0,0 +1,62 @@
+//! Problem matchers cuenv ships
+//!
+//! Each matcher is a sequence of patterns, one per output line, as in VS
+//! Code: earlier patterns capture context such as a message or a file name
+//! and the last one completes the diagnostic.
+
+/// A built-in matcher's patterns, and whether its last pattern may match
+/// several lines in a row
+pub(super) struct Builtin {
+    pub patterns: &'static [&'static str],
+    pub repeat_last: bool,
+}
+
+/// Names of the built-in matchers
+pub const BUILTIN_NAMES: &[&str] = &["rustc", "eslint", "tsc", "gcc", "go"];
+
+pub(super) fn builtin(name: &str) -> Option<Builtin> {
+    let (patterns, repeat_last): (&'static [&'static str], bool) = match name {
+        // error[E0308]: mismatched types
+        //   --> src/main.rs:4:5
+        "rustc" => (
+            &[
+                r"^(?P<severity>error|warning)(?:\[(?P<code>[^\]]+)\])?: (?P<message>.+)$",
+                r"^\s*--> (?P<file>.+?):(?P<line>\d+):(?P<column>\d+)$",
+            ],
+            false,
+        ),
+        // /src/app.js
+        //   3:10  error  'x' is defined but never used  no-unused-vars
+        "eslint" => (
+            &[
+                r"^(?P<file>\S.*)$",
+                r"^\s+(?P<line>\d+):(?P<column>\d+)\s+(?P<severity>error|warning)\s+(?P<message>.+?)(?:\s{2,}(?P<code>\S+))?$",
+            ],
+            true,
+        ),
+        // src/app.ts(3,10): error TS2322: Type 'string' is not assignable
+        "tsc" => (
+            &[
+                r"^(?P<file>[^\s(].*?)\((?P<line>\d+),(?P<column>\d+)\): (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$",
+            ],
+            false,
+        ),
+        // src/main.c:3:10: error: expected ';' after expression
+        "gcc" => (
+            &[
+                r"^(?P<file>[^\s:][^:]*):(?P<line>\d+):(?:(?P<column>\d+):)? (?P<severity>fatal error|error|warning|note): (?P<message>.+)$",
+            ],
+            false,
+        ),
+        // ./main.go:10:2: undefined: x
+        "go" => (
+            &[r"^(?P<file>[^\s:][^:]*\.go):(?P<line>\d+):(?:(?P<column>\d+):)? (?P<message>.+)$"],
+            false,
+        ),
+        _ => return None,
+    };
+    Some(Builtin {
+        patterns,
+        repeat_last,
+    })
+}
@@ -0,0 +1,294 @@
+//! Diagnostics read from task output with problem matchers
+//!
+//! A task's `problemMatcher` entries are compiled into a
+//! [`DiagnosticParser`], which is fed the task's output a line at a time.
+//! Each output stream gets its own parser, as matchers spanning several
+//! lines would be confused by interleaved stdout and stderr.
+
+mod builtins;
+
+pub use builtins::BUILTIN_NAMES;
+
+use cuenv_core::output::{sanitize, AnsiPolicy};
+use cuenv_core::{Diagnostic, Error, Result, Severity, TaskProblemMatcher};
+use regex::{Captures, Regex};
+
+/// A compiled problem matcher
+#[derive(Debug, Clone)]
+struct ProblemMatcher {
+    patterns: Vec<Regex>,
+    repeat_last: bool,
+    severity: Severity,
+}
+
+impl ProblemMatcher {
+    fn compile(matcher: &TaskProblemMatcher) -> Result<Self> {
+        match matcher {
+            TaskProblemMatcher::Builtin(name) => {
+                let builtin = builtins::builtin(name).ok_or_else(|| {
+                    Error::configuration(format!(
+                        "Unknown problem matcher '{name}'. Built-in matchers: {}",
+                        BUILTIN_NAMES.join(", ")
+                    ))
+                })?;
+                Ok(Self {
+                    patterns: builtin
+                        .patterns
+                        .iter()
+                        .map(|pattern| compile_pattern(pattern))
+                        .collect::<Result<_>>()?,
+                    repeat_last: builtin.repeat_last,
+                    severity: Severity::Error,
+                })
+            }
+            TaskProblemMatcher::Pattern { pattern, severity } => {
+                let regex = compile_pattern(pattern)?;
+                if !regex.capture_names().any(|name| name == Some("message")) {
+                    return Err(Error::configuration(format!(
+                        "Problem matcher pattern '{pattern}' needs a (?P<message>...) group"
+                    )));
+                }
+                Ok(Self {
+                    patterns: vec![regex],
+                    repeat_last: false,
+                    severity: *severity,
+                })
+            }
+        }
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| {
+        Error::configuration(format!("Invalid problem matcher pattern '{pattern}': {e}"))
+    })
+}
+
+/// Check that matchers name built-ins that exist and compile
+pub fn validate_problem_matchers(matchers: &[TaskProblemMatcher]) -> Result<()> {
+    matchers
+        .iter()
+        .try_for_each(|matcher| ProblemMatcher::compile(matcher).map(drop))
+}
+
+/// Fields captured so far by a matcher's patterns
+#[derive(Debug, Clone, Default)]
+struct Captured {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    severity: Option<Severity>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl Captured {
+    fn merge(&mut self, captures: &Captures) {
+        let text = |name: &str| captures.name(name).map(|m| m.as_str().to_string());
+        let number = |name: &str| captures.name(name).and_then(|m| m.as_str().parse().ok());
+        self.file = text("file").or(self.file.take());
+        self.line = number("line").or(self.line);
+        self.column = number("column").or(self.column);
+        self.severity = text("severity")
+            .and_then(|severity| Severity::parse(&severity))
+            .or(self.severity);
+        self.code = text("code").or(self.code.take());
+        self.message = text("message").or(self.message.take());
+    }
+}
+
+/// A matcher and how far it got through its patterns
+#[derive(Debug, Clone)]
+struct MatcherState {
+    matcher: ProblemMatcher,
+    /// Index of the pattern the next line has to match
+    next: usize,
+    captured: Captured,
+}
+
+impl MatcherState {
+    fn feed(&mut self, task: &str, line: &str) -> Option<Diagnostic> {
+        if !self.matcher.patterns[self.next].is_match(line) {
+            if self.next == 0 {
+                return None;
+            }
+            // A line breaking a sequence may start the next one
+            self.reset();
+        }
+        self.advance(task, line)
+    }
+
+    fn advance(&mut self, task: &str, line: &str) -> Option<Diagnostic> {
+        let last = self.matcher.patterns.len() - 1;
+        let captures = self.matcher.patterns[self.next].captures(line)?;
+        let mut captured = self.captured.clone();
+        captured.merge(&captures);
+        if self.next < last {
+            self.captured = captured;
+            self.next += 1;
+            return None;
+        }
+        if !self.matcher.repeat_last {
+            self.reset();
+        }
+        Some(Diagnostic {
+            task: task.to_string(),
+            severity: captured.severity.unwrap_or(self.matcher.severity),
+            message: captured.message.unwrap_or_default(),
+            file: captured.file,
+            line: captured.line,
+            column: captured.column,
+            code: captured.code,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.next = 0;
+        self.captured = Captured::default();
+    }
+}
+
+/// Reads diagnostics from one output stream of a task
+#[derive(Debug, Clone)]
+pub struct DiagnosticParser {
+    task: String,
+    matchers: Vec<MatcherState>,
+}
+
+impl DiagnosticParser {
+    /// Parser for `task`'s matchers, or `None` when it has none
+    pub fn new(task: &str, matchers: &[TaskProblemMatcher]) -> Result<Option<Self>> {
+        if matchers.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            task: task.to_string(),
+            matchers: matchers
+                .iter()
+                .map(|matcher| {
+                    ProblemMatcher::compile(matcher).map(|matcher| MatcherState {
+                        matcher,
+                        next: 0,
+                        captured: Captured::default(),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        }))
+    }
+
+    /// Diagnostics completed by the next line of output, which is matched
+    /// without its escape sequences
+    pub fn parse_line(&mut self, line: &str) -> Vec<Diagnostic> {
+        let line = sanitize(line, AnsiPolicy::Strip);
+        let line = line.trim_end();
+        self.matchers
+            .iter_mut()
+            .filter_map(|state| state.feed(&self.task, line))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(matchers: &[TaskProblemMatcher], output: &str) -> Vec<Diagnostic> {
+        let mut parser = DiagnosticParser::new("build", matchers).unwrap().unwrap();
+        output
+            .lines()
+            .flat_map(|line| parser.parse_line(line))
+            .collect()
+    }
+
+    fn builtin(name: &str) -> Vec<TaskProblemMatcher> {
+        vec![TaskProblemMatcher::Builtin(name.to_string())]
+    }
+
+    #[test]
+    fn test_rustc_matcher() {
+        let output = "\
+   Compiling app v0.1.0
+\x1b[1m\x1b[31merror[E0308]\x1b[0m: mismatched types
+  --> src/main.rs:4:5
+   |
+warning: unused variable: `x`
+ --> src/lib.rs:2:9
+error: could not compile `app` (bin \"app\") due to 1 previous error
+";
+        let diagnostics = parse(&builtin("rustc"), output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "src/main.rs:4:5: error[E0308]: mismatched types"
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].file.as_deref(), Some("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_eslint_matcher_repeats_for_each_problem_of_a_file() {
+        let output = "
+/app/src/index.js
+  3:10  error    'x' is defined but never used  no-unused-vars
+  7:1   warning  Unexpected console statement   no-console
+
+/app/src/util.js
+  1:1  error  Parsing error: Unexpected token
+
+✖ 3 problems (2 errors, 1 warning)
+";
+        let diagnostics = parse(&builtin("eslint"), output);
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.file.as_deref().unwrap(),
+                    d.line.unwrap(),
+                    d.code.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/app/src/index.js", 3, Some("no-unused-vars")),
+                ("/app/src/index.js", 7, Some("no-console")),
+                ("/app/src/util.js", 1, None),
+            ]
+        );
+        assert_eq!(diagnostics[1].message, "Unexpected console statement");
+    }
+
+    #[test]
+    fn test_pattern_matcher() {
+        let matchers = [TaskProblemMatcher::Pattern {
+            pattern: r"^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.+)$".to_string(),
+            severity: Severity::Warning,
+        }];
+        let diagnostics = parse(&matchers, "docs/index.md:12: broken link\nall done\n");
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                task: "build".to_string(),
+                severity: Severity::Warning,
+                message: "broken link".to_string(),
+                file: Some("docs/index.md".to_string()),
+                line: Some(12),
+                column: None,
+                code: None,
+            }]
+        );
+
+        let invalid = [
+            TaskProblemMatcher::Builtin("javac".to_string()),
+            TaskProblemMatcher::Pattern {
+                pattern: r"^(?P<file>.+)$".to_string(),
+                severity: Severity::Error,
+            },
+        ];
+        for matcher in invalid {
+            assert!(validate_problem_matchers(&[matcher]).is_err());
+        }
+        assert!(DiagnosticParser::new("build", &[]).unwrap().is_none());
+    }
+}
@@ -5,6 +5,7 @@ use super::types::{
     RunTaskResult, TaskDefinition,
 };
 use cuenv_core::{Error, Result};
+use cuenv_utils::resilience::policy::{self, Subsystem};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::Duration;
@@ -78,10 +79,15 @@ impl TaskServerClient {
             ));
         }
 
-        // Connect to socket
-        let stream = UnixStream::connect(&self.socket_path).await.map_err(|e| {
-            Error::configuration(format!("Failed to connect to task server socket: {e}"))
-        })?;
+        // Connect to socket; it can appear before the server listens on it, so
+        // refused connections are retried under the task server retry policy
+        let socket_path = &self.socket_path;
+        let stream = policy::call(Subsystem::TaskServer, || async move {
+            UnixStream::connect(socket_path)
+                .await
+                .map_err(|e| Error::file_system(socket_path, "connect to task server socket", e))
+        })
+        .await?;
 
         self.server_process = Some(child);
         self.stream = Some(stream);
@@ -1,6 +1,8 @@
 //! Task-specific MCP handlers
 
 use super::handlers::parse_env_readonly;
+use crate::resolution::similar_task_names;
+use cuenv_core::suggestions::with_suggestions;
 
 /// Handle list_tasks tool call
 pub async fn handle_list_tasks(
@@ -110,13 +112,18 @@ pub async fn handle_get_task(
                     "id": id
                 })
             } else {
+                let candidates = similar_task_names(&parse_result.tasks, task_name);
                 serde_json::json!({
                     "jsonrpc": "2.0",
                     "result": {
                         "content": [{
                             "type": "text",
-                            "text": format!("Task '{}' not found", task_name)
-                        }]
+                            "text": with_suggestions(
+                                format!("Task '{task_name}' not found"),
+                                &candidates
+                            )
+                        }],
+                        "candidates": candidates
                     },
                     "id": id
                 })
@@ -1,7 +1,13 @@
 //! Model Context Protocol (MCP) handlers for Claude Code integration
 
+use super::notifications::{LogStream, TaskEvents, TaskNotification, TaskRunState};
+use crate::builder::conversion::convert_problem_matchers;
+use crate::cancellation::{self, terminate_when_cancelled};
+use crate::problem_matcher::DiagnosticParser;
 use cuenv_config::TaskConfig;
-use cuenv_core::{Error, Result};
+use cuenv_core::{Error, ExitStatus, Result};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 
 /// Returns the MCP tool definitions
 pub fn get_mcp_tools(allow_exec: bool) -> Vec<serde_json::Value> {
@@ -161,26 +167,141 @@ pub fn get_mcp_tools(allow_exec: bool) -> Vec<serde_json::Value> {
     tools
 }
 
-/// Simple task execution (placeholder implementation)
-pub async fn execute_task(task_config: &TaskConfig) -> Result<i32> {
+/// Simple task execution, streaming output and state to subscribers
+pub async fn execute_task(
+    task_name: &str,
+    task_config: &TaskConfig,
+    events: &TaskEvents,
+) -> Result<i32> {
+    let run_id = events.next_run_id();
+    let publish_state = |state: TaskRunState, exit_status: Option<ExitStatus>| {
+        events.publish(TaskNotification::StateChanged {
+            task: task_name.to_string(),
+            run_id: run_id.clone(),
+            state,
+            exit_status,
+        });
+    };
+    publish_state(TaskRunState::Started, None);
+
+    let result = run_command(task_name, &run_id, task_config, events).await;
+    match &result {
+        Ok(status) if status.success() => publish_state(TaskRunState::Succeeded, Some(*status)),
+        Ok(status) => publish_state(TaskRunState::Failed, Some(*status)),
+        Err(_) => publish_state(TaskRunState::Failed, None),
+    }
+    result.map(ExitStatus::code)
+}
+
+async fn run_command(
+    task_name: &str,
+    run_id: &str,
+    task_config: &TaskConfig,
+    events: &TaskEvents,
+) -> Result<ExitStatus> {
     // This is a simplified implementation
     // In practice, this would integrate with the full task executor
-    if let Some(command) = &task_config.command {
-        let mut cmd = tokio::process::Command::new("sh");
-        cmd.arg("-c").arg(command);
+    let Some(command) = &task_config.command else {
+        // No command specified, consider it successful
+        return Ok(ExitStatus::SUCCESS);
+    };
+    let parser = DiagnosticParser::new(task_name, &convert_problem_matchers(task_config)?)?;
 
-        let output = cmd.output().await.map_err(|e| {
-            Error::command_execution(
-                "sh",
-                vec!["-c".to_string(), command.clone()],
-                format!("Failed to execute task: {e}"),
-                None,
-            )
-        })?;
+    let command_error = |e: std::io::Error| {
+        Error::command_execution(
+            "sh",
+            vec!["-c".to_string(), command.clone()],
+            format!("Failed to execute task: {e}"),
+            None,
+        )
+    };
 
-        Ok(output.status.code().unwrap_or(-1))
-    } else {
-        // No command specified, consider it successful
-        Ok(0)
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Cancellation signals the task's whole process group
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let mut child = cmd.spawn().map_err(command_error)?;
+    let pid = child.id();
+    let mut cancellation = cancellation::register(run_id, task_name);
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let output = |stream| Output {
+        task_name,
+        run_id,
+        stream,
+        parser: parser.clone(),
+        events,
+    };
+    let wait = async {
+        tokio::join!(
+            child.wait(),
+            forward_lines(stdout, output(LogStream::Stdout)),
+            forward_lines(stderr, output(LogStream::Stderr)),
+        )
+        .0
+    };
+    tokio::pin!(wait);
+    let status = match pid {
+        Some(pid) => tokio::select! {
+            status = &mut wait => status,
+            () = terminate_when_cancelled(&mut cancellation, pid) => wait.await,
+        },
+        None => wait.await,
+    };
+    let status = status.map_err(command_error)?;
+
+    if cancellation.is_cancelled() {
+        return Err(Error::command_execution(
+            "sh",
+            vec!["-c".to_string(), command.clone()],
+            "Task was cancelled",
+            None,
+        ));
+    }
+    Ok(status.into())
+}
+
+/// One output stream of a task run and where its lines are published
+struct Output<'a> {
+    task_name: &'a str,
+    run_id: &'a str,
+    stream: LogStream,
+    /// Each stream has its own parser, so interleaved lines do not break
+    /// multi-line matches
+    parser: Option<DiagnosticParser>,
+    events: &'a TaskEvents,
+}
+
+/// Publish each line read from a task's output stream, and the diagnostics
+/// its problem matchers find in them
+async fn forward_lines<R: AsyncRead + Unpin>(reader: Option<R>, mut output: Output<'_>) {
+    let Some(reader) = reader else {
+        return;
+    };
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let diagnostics = output
+            .parser
+            .as_mut()
+            .map(|parser| parser.parse_line(&line))
+            .unwrap_or_default();
+        output.events.publish(TaskNotification::Log {
+            task: output.task_name.to_string(),
+            run_id: output.run_id.to_string(),
+            stream: output.stream,
+            line,
+        });
+        diagnostics.into_iter().for_each(|diagnostic| {
+            output.events.publish(TaskNotification::Diagnostic {
+                task: output.task_name.to_string(),
+                run_id: output.run_id.to_string(),
+                diagnostic,
+            })
+        });
     }
 }
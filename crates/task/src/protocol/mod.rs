@@ -9,6 +9,8 @@
 //! - cuenv discovers servers by launching executables (like `myexecutable /tmp/socket.sock`)
 //! - Communication uses JSON-RPC 2.0 with initialize and run methods
 //! - Servers stream back log output and final results
+//! - Clients may `subscribe` to live task output, state and diagnostic
+//!   notifications
 
 // Core protocol types
 mod types;
@@ -30,6 +32,14 @@ mod handlers;
 mod handlers_execution;
 mod handlers_tasks;
 
+// Live notifications for subscribed clients
+mod notifications;
+mod subscriptions;
+pub use notifications::{
+    LogStream, SubscribeParams, TaskEvents, TaskNotification, TaskRunState, DIAGNOSTIC_METHOD,
+    LOG_METHOD, STATE_CHANGED_METHOD,
+};
+
 // Provider for exposing cuenv tasks
 mod provider;
 mod provider_handlers;
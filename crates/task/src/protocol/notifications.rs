@@ -0,0 +1,264 @@
+//! Live task notifications for TSP subscribers
+//!
+//! Task output, state changes and the diagnostics a task's problem matchers
+//! find are published on a broadcast bus owned by
+//! the provider. Clients opt in with the `subscribe` method and then receive
+//! JSON-RPC notifications (requests without an `id`) on the same socket,
+//! interleaved with regular responses:
+//!
+//! ```json
+//! {"jsonrpc":"2.0","method":"task/stateChanged","params":{"subscription":1,"task":"build","runId":"01J9ZQ3K8M4T6V2W5X7Y9A1B3C","state":"started"}}
+//! {"jsonrpc":"2.0","method":"task/log","params":{"subscription":1,"task":"build","runId":"01J9ZQ3K8M4T6V2W5X7Y9A1B3C","stream":"stdout","line":"Compiling"}}
+//! {"jsonrpc":"2.0","method":"task/diagnostic","params":{"subscription":1,"task":"build","runId":"01J9ZQ3K8M4T6V2W5X7Y9A1B3C","diagnostic":{"task":"build","severity":"error","message":"mismatched types","file":"src/main.rs","line":4}}}
+//! ```
+
+use cuenv_core::{Diagnostic, ExitStatus, RunId, TaskEvent};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Notification method for a line of task output
+pub const LOG_METHOD: &str = "task/log";
+
+/// Notification method for a task state transition
+pub const STATE_CHANGED_METHOD: &str = "task/stateChanged";
+
+/// Notification method for a problem found in task output
+pub const DIAGNOSTIC_METHOD: &str = "task/diagnostic";
+
+/// Notifications buffered per subscriber before it starts lagging
+const EVENT_BUS_CAPACITY: usize = 4096;
+
+/// Output stream a log line was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// Lifecycle state of a task run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskRunState {
+    Started,
+    Succeeded,
+    Failed,
+}
+
+/// An event published while a task runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskNotification {
+    Log {
+        task: String,
+        run_id: String,
+        stream: LogStream,
+        line: String,
+    },
+    StateChanged {
+        task: String,
+        run_id: String,
+        state: TaskRunState,
+        exit_status: Option<ExitStatus>,
+    },
+    Diagnostic {
+        task: String,
+        run_id: String,
+        diagnostic: Diagnostic,
+    },
+}
+
+impl TaskNotification {
+    /// Name of the task the notification belongs to
+    pub fn task(&self) -> &str {
+        match self {
+            Self::Log { task, .. }
+            | Self::StateChanged { task, .. }
+            | Self::Diagnostic { task, .. } => task,
+        }
+    }
+
+    /// Notification for an event the task executor published, if it has one
+    pub fn from_event(event: TaskEvent) -> Option<Self> {
+        let notification = match event {
+            TaskEvent::TaskStarted { task_name, task_id } => Self::StateChanged {
+                task: task_name,
+                run_id: task_id,
+                state: TaskRunState::Started,
+                exit_status: None,
+            },
+            TaskEvent::TaskCompleted {
+                task_name, task_id, ..
+            } => Self::StateChanged {
+                task: task_name,
+                run_id: task_id,
+                state: TaskRunState::Succeeded,
+                exit_status: Some(ExitStatus::SUCCESS),
+            },
+            TaskEvent::TaskFailed {
+                task_name,
+                task_id,
+                exit_status,
+                ..
+            } => Self::StateChanged {
+                task: task_name,
+                run_id: task_id,
+                state: TaskRunState::Failed,
+                exit_status,
+            },
+            TaskEvent::TaskOutput {
+                task_name,
+                task_id,
+                output,
+            } => Self::Log {
+                task: task_name,
+                run_id: task_id,
+                stream: LogStream::Stdout,
+                line: output,
+            },
+            TaskEvent::TaskError {
+                task_name,
+                task_id,
+                error,
+            } => Self::Log {
+                task: task_name,
+                run_id: task_id,
+                stream: LogStream::Stderr,
+                line: error,
+            },
+            TaskEvent::TaskDiagnostic {
+                task_name,
+                task_id,
+                diagnostic,
+            } => Self::Diagnostic {
+                task: task_name,
+                run_id: task_id,
+                diagnostic,
+            },
+            _ => return None,
+        };
+        Some(notification)
+    }
+
+    /// Encode as a JSON-RPC notification for the given subscription
+    pub fn to_json_rpc(&self, subscription: u64) -> serde_json::Value {
+        let (method, params) = match self {
+            Self::Log {
+                task,
+                run_id,
+                stream,
+                line,
+            } => (
+                LOG_METHOD,
+                serde_json::json!({
+                    "subscription": subscription,
+                    "task": task,
+                    "runId": run_id,
+                    "stream": stream,
+                    "line": line,
+                }),
+            ),
+            Self::StateChanged {
+                task,
+                run_id,
+                state,
+                exit_status,
+            } => (
+                STATE_CHANGED_METHOD,
+                serde_json::json!({
+                    "subscription": subscription,
+                    "task": task,
+                    "runId": run_id,
+                    "state": state,
+                    "exitCode": exit_status.map(ExitStatus::code),
+                    "signal": exit_status.and_then(ExitStatus::signal),
+                }),
+            ),
+            Self::Diagnostic {
+                task,
+                run_id,
+                diagnostic,
+            } => (
+                DIAGNOSTIC_METHOD,
+                serde_json::json!({
+                    "subscription": subscription,
+                    "task": task,
+                    "runId": run_id,
+                    "diagnostic": diagnostic,
+                }),
+            ),
+        };
+
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        })
+    }
+}
+
+/// Parameters of the `subscribe` method
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscribeParams {
+    /// Only forward events for these tasks; empty means all tasks
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    /// Forward log lines in addition to state changes and diagnostics
+    #[serde(default = "default_true")]
+    pub logs: bool,
+}
+
+impl Default for SubscribeParams {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new(),
+            logs: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SubscribeParams {
+    /// Whether a notification passes this subscription's filter
+    pub fn matches(&self, notification: &TaskNotification) -> bool {
+        let wanted_kind = self.logs || !matches!(notification, TaskNotification::Log { .. });
+        let wanted_task =
+            self.tasks.is_empty() || self.tasks.iter().any(|task| task == notification.task());
+        wanted_kind && wanted_task
+    }
+}
+
+/// Broadcast bus for task notifications
+#[derive(Debug, Clone)]
+pub struct TaskEvents {
+    sender: broadcast::Sender<TaskNotification>,
+}
+
+impl Default for TaskEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Allocate a ULID identifying a run of a task
+    pub fn next_run_id(&self) -> String {
+        RunId::new().to_string()
+    }
+
+    /// Publish a notification; a no-op when nobody is subscribed
+    pub fn publish(&self, notification: TaskNotification) {
+        let _ = self.sender.send(notification);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskNotification> {
+        self.sender.subscribe()
+    }
+}
@@ -1,10 +1,14 @@
 //! Task server provider that exposes cuenv tasks to external tools (part 1)
 
+use super::notifications::TaskEvents;
+use super::subscriptions::ConnectionSubscriptions;
 use cuenv_config::Config;
 use cuenv_core::{Error, Result};
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::net::unix::OwnedWriteHalf;
 use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc;
 
 /// Task server provider that exposes cuenv tasks to external tools
 pub struct TaskServerProvider {
@@ -13,6 +17,7 @@ pub struct TaskServerProvider {
     pub(crate) config: Arc<Config>,
     pub(crate) allow_exec: bool,
     pub(crate) use_stdio: bool,
+    pub(crate) events: TaskEvents,
 }
 
 impl TaskServerProvider {
@@ -24,6 +29,7 @@ impl TaskServerProvider {
             config,
             allow_exec: false,
             use_stdio: false,
+            events: TaskEvents::new(),
         }
     }
 
@@ -35,6 +41,7 @@ impl TaskServerProvider {
             config,
             allow_exec,
             use_stdio: true,
+            events: TaskEvents::new(),
         }
     }
 
@@ -51,9 +58,17 @@ impl TaskServerProvider {
             config,
             allow_exec,
             use_stdio,
+            events: TaskEvents::new(),
         }
     }
 
+    /// Bus on which task output and state changes are published
+    ///
+    /// Socket clients receive these after calling `subscribe`.
+    pub fn events(&self) -> TaskEvents {
+        self.events.clone()
+    }
+
     /// Start the server and listen for connections
     pub async fn start(&mut self) -> Result<()> {
         if self.use_stdio {
@@ -119,8 +134,13 @@ impl TaskServerProvider {
                 .map_err(|e| Error::configuration(format!("Invalid JSON-RPC request: {e}")))?;
 
             // Handle the request
-            let response =
-                Self::handle_request(request, self.config.get_tasks(), self.allow_exec).await;
+            let response = Self::handle_request(
+                request,
+                self.config.get_tasks(),
+                self.allow_exec,
+                &self.events,
+            )
+            .await;
 
             // Send response
             let response_json = serde_json::to_string(&response)
@@ -154,8 +174,11 @@ impl TaskServerProvider {
                 Ok((stream, _)) => {
                     let config = Arc::clone(&self.config);
                     let allow_exec = self.allow_exec;
+                    let events = self.events.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = Self::handle_client(stream, config, allow_exec).await {
+                        if let Err(e) =
+                            Self::handle_client(stream, config, allow_exec, events).await
+                        {
                             tracing::error!(error = %e, "Client connection error");
                         }
                     });
@@ -169,14 +192,25 @@ impl TaskServerProvider {
     }
 
     /// Handle a single client connection
+    ///
+    /// Responses and subscription notifications share the connection, so all
+    /// output goes through a single writer task. Requests other than
+    /// subscriptions are handled on tasks of their own, so a long `run` does
+    /// not hold up requests such as `cancelTask` sent after it; responses
+    /// may therefore arrive out of order and are matched by their `id`.
     async fn handle_client(
         stream: UnixStream,
         config: Arc<Config>,
         allow_exec: bool,
+        events: TaskEvents,
     ) -> Result<()> {
-        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let (read_half, write_half) = stream.into_split();
+        let (outgoing, receiver) = mpsc::unbounded_channel::<String>();
+        let writer = tokio::spawn(write_lines(write_half, receiver));
+        let mut subscriptions = ConnectionSubscriptions::new(events.clone(), outgoing.clone());
 
-        let (read_half, mut write_half) = stream.into_split();
         let mut buf_reader = BufReader::new(read_half);
         let mut line = String::new();
 
@@ -189,22 +223,71 @@ impl TaskServerProvider {
             // Parse JSON-RPC request
             let request: serde_json::Value = serde_json::from_str(line.trim())
                 .map_err(|e| Error::configuration(format!("Invalid JSON-RPC request: {e}")))?;
+            line.clear();
 
-            // Handle the request
-            let response = Self::handle_request(request, config.get_tasks(), allow_exec).await;
+            let method = request
+                .get("method")
+                .and_then(|m| m.as_str())
+                .unwrap_or_default();
+            let params = request
+                .get("params")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            let id = request
+                .get("id")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
 
-            // Send response
-            let response_json = serde_json::to_string(&response)
-                .map_err(|e| Error::configuration(format!("Failed to serialize response: {e}")))?;
+            if let Some(response) = subscriptions.handle(method, params, id) {
+                if !send_response(&outgoing, &response) {
+                    break;
+                }
+                continue;
+            }
 
-            write_half
-                .write_all(format!("{response_json}\n").as_bytes())
-                .await
-                .map_err(|e| Error::configuration(format!("Failed to write response: {e}")))?;
+            let config = Arc::clone(&config);
+            let events = events.clone();
+            let outgoing = outgoing.clone();
+            tokio::spawn(async move {
+                let response =
+                    Self::handle_request(request, config.get_tasks(), allow_exec, &events).await;
+                send_response(&outgoing, &response);
+            });
+        }
 
-            line.clear();
+        // Stop forwarding notifications and let the writer drain
+        drop(subscriptions);
+        drop(outgoing);
+        writer
+            .await
+            .map_err(|e| Error::configuration(format!("Connection writer failed: {e}")))?
+    }
+}
+
+/// Queue a response for the connection's writer; false once it has gone
+fn send_response(outgoing: &mpsc::UnboundedSender<String>, response: &serde_json::Value) -> bool {
+    match serde_json::to_string(response) {
+        Ok(response_json) => outgoing.send(response_json).is_ok(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize response");
+            true
         }
+    }
+}
 
-        Ok(())
+/// Write newline-delimited messages to the client until all senders close
+async fn write_lines(
+    mut write_half: OwnedWriteHalf,
+    mut receiver: mpsc::UnboundedReceiver<String>,
+) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    while let Some(message) = receiver.recv().await {
+        write_half
+            .write_all(format!("{message}\n").as_bytes())
+            .await
+            .map_err(|e| Error::configuration(format!("Failed to write response: {e}")))?;
     }
+
+    Ok(())
 }
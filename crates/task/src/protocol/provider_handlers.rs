@@ -2,9 +2,13 @@
 
 use super::handlers::handle_mcp_tool_call;
 use super::mcp::{execute_task, get_mcp_tools};
+use super::notifications::TaskEvents;
 use super::provider::TaskServerProvider;
 use super::types::TaskDefinition;
+use crate::cancellation;
+use crate::resolution::similar_task_names;
 use cuenv_config::TaskConfig;
+use cuenv_core::suggestions::with_suggestions;
 use cuenv_core::{Error, Result};
 use std::collections::HashMap;
 
@@ -14,6 +18,7 @@ impl TaskServerProvider {
         request: serde_json::Value,
         tasks: &HashMap<String, TaskConfig>,
         allow_exec: bool,
+        events: &TaskEvents,
     ) -> serde_json::Value {
         let method = request
             .get("method")
@@ -62,7 +67,7 @@ impl TaskServerProvider {
                 if let Some(task_config) = tasks.get(task_name) {
                     // Execute the task (simplified for now)
                     // In a real implementation, this would use the task executor
-                    match execute_task(task_config).await {
+                    match execute_task(task_name, task_config, events).await {
                         Ok(exit_code) => serde_json::json!({
                             "jsonrpc": "2.0",
                             "result": {
@@ -81,17 +86,59 @@ impl TaskServerProvider {
                         }),
                     }
                 } else {
+                    let candidates = similar_task_names(tasks, task_name);
                     serde_json::json!({
                         "jsonrpc": "2.0",
                         "error": {
                             "code": -1,
-                            "message": format!("Task not found: {}", task_name)
+                            "message": with_suggestions(
+                                format!("Task not found: {task_name}"),
+                                &candidates
+                            ),
+                            "data": { "candidates": candidates }
                         },
                         "id": id
                     })
                 }
             }
 
+            // Stop task runs, e.g. from an editor's stop button; runs of
+            // other connections and of the task executor can be cancelled
+            "cancelRun" | "cancelTask" => {
+                let force = params
+                    .get("force")
+                    .and_then(|force| force.as_bool())
+                    .unwrap_or(false);
+                let target = if method == "cancelRun" {
+                    "runId"
+                } else {
+                    "task"
+                };
+                let Some(name) = params.get(target).and_then(|name| name.as_str()) else {
+                    return serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "error": {
+                            "code": -32602,
+                            "message": format!("{method} needs a '{target}' parameter")
+                        },
+                        "id": id
+                    });
+                };
+                let cancelled = if method == "cancelRun" {
+                    cancellation::cancel_run(name, force)
+                        .then(|| name.to_string())
+                        .into_iter()
+                        .collect()
+                } else {
+                    cancellation::cancel_task(name, force)
+                };
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "result": { "cancelled": cancelled },
+                    "id": id
+                })
+            }
+
             // MCP Methods (Claude Code integration)
             "tools/list" => {
                 // List available MCP tools
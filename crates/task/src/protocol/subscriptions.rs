@@ -0,0 +1,133 @@
+//! Per-connection notification subscriptions
+//!
+//! Each socket connection owns a single outgoing line channel. Responses
+//! and notifications from any number of subscriptions are multiplexed onto
+//! it, so a client can keep issuing requests while output streams in.
+
+use super::notifications::{SubscribeParams, TaskEvents};
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+/// Subscriptions held by one client connection
+pub(crate) struct ConnectionSubscriptions {
+    events: TaskEvents,
+    outgoing: UnboundedSender<String>,
+    active: HashMap<u64, JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl ConnectionSubscriptions {
+    pub(crate) fn new(events: TaskEvents, outgoing: UnboundedSender<String>) -> Self {
+        Self {
+            events,
+            outgoing,
+            active: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Handle `subscribe`/`unsubscribe`; returns `None` for other methods
+    pub(crate) fn handle(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        id: serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        let response = match method {
+            "subscribe" => self.subscribe(params, id),
+            "unsubscribe" => self.unsubscribe(&params, id),
+            _ => return None,
+        };
+        Some(response)
+    }
+
+    fn subscribe(&mut self, params: serde_json::Value, id: serde_json::Value) -> serde_json::Value {
+        let filter = if params.is_null() {
+            SubscribeParams::default()
+        } else {
+            match serde_json::from_value::<SubscribeParams>(params) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    return serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "error": {
+                            "code": -32602,
+                            "message": format!("Invalid subscribe parameters: {e}")
+                        },
+                        "id": id
+                    })
+                }
+            }
+        };
+
+        let subscription = self.next_id;
+        self.next_id += 1;
+
+        let mut receiver = self.events.subscribe();
+        let outgoing = self.outgoing.clone();
+        let forwarder = tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) if filter.matches(&notification) => {
+                        let line = notification.to_json_rpc(subscription).to_string();
+                        if outgoing.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(subscription, skipped, "Subscriber lagging, events dropped");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        self.active.insert(subscription, forwarder);
+
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "subscription": subscription
+            },
+            "id": id
+        })
+    }
+
+    fn unsubscribe(
+        &mut self,
+        params: &serde_json::Value,
+        id: serde_json::Value,
+    ) -> serde_json::Value {
+        let removed = params
+            .get("subscription")
+            .and_then(|s| s.as_u64())
+            .and_then(|subscription| self.active.remove(&subscription));
+
+        match removed {
+            Some(forwarder) => {
+                forwarder.abort();
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "result": {},
+                    "id": id
+                })
+            }
+            None => serde_json::json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32602,
+                    "message": "Unknown subscription"
+                },
+                "id": id
+            }),
+        }
+    }
+}
+
+impl Drop for ConnectionSubscriptions {
+    fn drop(&mut self) {
+        self.active.values().for_each(JoinHandle::abort);
+    }
+}
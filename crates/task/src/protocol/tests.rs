@@ -81,6 +81,10 @@ mod protocol_tests {
             task_nodes: indexmap::IndexMap::new(),
             hooks: HashMap::new(),
             config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
         };
         let config = Arc::new(cuenv_config::Config::new(
             temp_dir.path().to_path_buf(),
@@ -114,6 +118,10 @@ mod protocol_tests {
             task_nodes: indexmap::IndexMap::new(),
             hooks: HashMap::new(),
             config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
         };
         let config = Arc::new(cuenv_config::Config::new(
             temp_dir.path().to_path_buf(),
@@ -147,6 +155,10 @@ mod protocol_tests {
             task_nodes: indexmap::IndexMap::new(),
             hooks: HashMap::new(),
             config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
         };
         let config = Arc::new(cuenv_config::Config::new(
             temp_dir.path().to_path_buf(),
@@ -163,4 +175,287 @@ mod protocol_tests {
         assert!(json.contains("Build the project"));
         assert!(json.contains("deps"));
     }
+
+    #[test]
+    fn test_subscribe_params_filter() {
+        let log = TaskNotification::Log {
+            task: "build".to_string(),
+            run_id: "01J9ZQ3K8M4T6V2W5X7Y9A1B3C".to_string(),
+            stream: LogStream::Stdout,
+            line: "ok".to_string(),
+        };
+        let state = TaskNotification::StateChanged {
+            task: "test".to_string(),
+            run_id: "01J9ZQ3K8M4T6V2W5X7Y9A1B3D".to_string(),
+            state: TaskRunState::Started,
+            exit_status: None,
+        };
+
+        let all = SubscribeParams::default();
+        assert!(all.matches(&log) && all.matches(&state));
+
+        let states_only: SubscribeParams = serde_json::from_str(r#"{"logs": false}"#).unwrap();
+        assert!(!states_only.matches(&log));
+        assert!(states_only.matches(&state));
+
+        let build_only: SubscribeParams = serde_json::from_str(r#"{"tasks": ["build"]}"#).unwrap();
+        assert!(build_only.matches(&log));
+        assert!(!build_only.matches(&state));
+
+        let json = log.to_json_rpc(7);
+        assert_eq!(json["method"], LOG_METHOD);
+        assert_eq!(json["params"]["subscription"], 7);
+        assert!(json.get("id").is_none());
+    }
+
+    #[test]
+    fn test_notifications_from_executor_events() {
+        let run_id = "01J9ZQ3K8M4T6V2W5X7Y9A1B3C".to_string();
+        let stderr = TaskNotification::from_event(cuenv_core::TaskEvent::TaskError {
+            task_name: "build".to_string(),
+            task_id: run_id.clone(),
+            error: "warning: unused".to_string(),
+        });
+        assert_eq!(
+            stderr,
+            Some(TaskNotification::Log {
+                task: "build".to_string(),
+                run_id: run_id.clone(),
+                stream: LogStream::Stderr,
+                line: "warning: unused".to_string(),
+            })
+        );
+
+        let completed = TaskNotification::from_event(cuenv_core::TaskEvent::TaskCompleted {
+            task_name: "build".to_string(),
+            task_id: run_id.clone(),
+            duration_ms: 12,
+        })
+        .unwrap();
+        assert_eq!(completed.to_json_rpc(0)["params"]["state"], "succeeded");
+        assert_eq!(completed.to_json_rpc(0)["params"]["exitCode"], 0);
+
+        assert_eq!(
+            TaskNotification::from_event(cuenv_core::TaskEvent::TaskProgress {
+                task_name: "build".to_string(),
+                task_id: run_id,
+                message: "50%".to_string(),
+            }),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscription_streams_task_output() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::UnixStream;
+
+        let temp_dir = TempDir::new().unwrap();
+        let socket_path = temp_dir.path().join("stream.sock");
+
+        let mut tasks = HashMap::new();
+        tasks.insert(
+            "greet".to_string(),
+            cuenv_config::TaskConfig {
+                command: Some("echo hello; echo oops >&2".to_string()),
+                problem_matcher: Some(vec![cuenv_config::ProblemMatcherConfig::Pattern {
+                    pattern: "^(?P<message>oops)$".to_string(),
+                    severity: None,
+                }]),
+                ..Default::default()
+            },
+        );
+        let parse_result = cuenv_config::ParseResult {
+            variables: HashMap::new(),
+            metadata: HashMap::new(),
+            commands: HashMap::new(),
+            tasks,
+            task_nodes: indexmap::IndexMap::new(),
+            hooks: HashMap::new(),
+            config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
+        };
+        let config = Arc::new(cuenv_config::Config::new(
+            temp_dir.path().to_path_buf(),
+            None,
+            parse_result,
+            cuenv_config::RuntimeOptions::default(),
+        ));
+
+        let mut provider = TaskServerProvider::new(socket_path.clone(), config);
+        let server = tokio::spawn(async move { provider.start().await });
+
+        let mut stream = None;
+        for _ in 0..100 {
+            if let Ok(connected) = UnixStream::connect(&socket_path).await {
+                stream = Some(connected);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let (read_half, mut write_half) = stream.expect("provider did not start").into_split();
+        let mut lines = BufReader::new(read_half).lines();
+
+        write_half
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"subscribe\",\"params\":{\"tasks\":[\"greet\"]},\"id\":1}\n")
+            .await
+            .unwrap();
+        let response: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(response["result"]["subscription"], 1);
+
+        write_half
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"run\",\"params\":{\"task\":\"greet\"},\"id\":2}\n")
+            .await
+            .unwrap();
+
+        // Notifications are not ordered relative to responses, so read until
+        // both the run response and the final state change have arrived
+        let mut messages = Vec::new();
+        let mut response = None;
+        let mut finished = false;
+        while response.is_none() || !finished {
+            let line = lines.next_line().await.unwrap().unwrap();
+            let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+            if message["id"] == 2 {
+                response = Some(message);
+            } else {
+                finished |= message["params"]["state"] == "succeeded";
+                messages.push(message);
+            }
+        }
+        server.abort();
+
+        assert_eq!(response.unwrap()["result"]["exit_code"], 0);
+
+        let notifications = &messages;
+        let log_lines: Vec<(&str, &str)> = notifications
+            .iter()
+            .filter(|message| message["method"] == LOG_METHOD)
+            .map(|message| {
+                (
+                    message["params"]["stream"].as_str().unwrap(),
+                    message["params"]["line"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert!(log_lines.contains(&("stdout", "hello")));
+        assert!(log_lines.contains(&("stderr", "oops")));
+
+        let states: Vec<&str> = notifications
+            .iter()
+            .filter(|message| message["method"] == STATE_CHANGED_METHOD)
+            .map(|message| message["params"]["state"].as_str().unwrap())
+            .collect();
+        assert_eq!(states, vec!["started", "succeeded"]);
+
+        // Every notification of the run carries its ULID
+        let run_ids: std::collections::HashSet<&str> = notifications
+            .iter()
+            .map(|message| message["params"]["runId"].as_str().unwrap())
+            .collect();
+        assert_eq!(run_ids.len(), 1);
+        assert!(run_ids.iter().all(|id| id.len() == 26));
+
+        let diagnostics: Vec<&serde_json::Value> = notifications
+            .iter()
+            .filter(|message| message["method"] == DIAGNOSTIC_METHOD)
+            .map(|message| &message["params"]["diagnostic"])
+            .collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["message"], "oops");
+        assert_eq!(diagnostics[0]["severity"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_served_while_a_task_runs() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::UnixStream;
+
+        let temp_dir = TempDir::new().unwrap();
+        let socket_path = temp_dir.path().join("concurrent.sock");
+
+        let mut tasks = HashMap::new();
+        tasks.insert(
+            "provider-slow".to_string(),
+            cuenv_config::TaskConfig {
+                command: Some("sleep 30".to_string()),
+                ..Default::default()
+            },
+        );
+        let parse_result = cuenv_config::ParseResult {
+            variables: HashMap::new(),
+            metadata: HashMap::new(),
+            commands: HashMap::new(),
+            tasks,
+            task_nodes: indexmap::IndexMap::new(),
+            hooks: HashMap::new(),
+            config: None,
+            run_configs: Default::default(),
+            provenance: Default::default(),
+            imports: Vec::new(),
+            environments: Vec::new(),
+        };
+        let config = Arc::new(cuenv_config::Config::new(
+            temp_dir.path().to_path_buf(),
+            None,
+            parse_result,
+            cuenv_config::RuntimeOptions::default(),
+        ));
+
+        let mut provider = TaskServerProvider::new(socket_path.clone(), config);
+        let server = tokio::spawn(async move { provider.start().await });
+
+        let mut stream = None;
+        for _ in 0..100 {
+            if let Ok(connected) = UnixStream::connect(&socket_path).await {
+                stream = Some(connected);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let (read_half, mut write_half) = stream.expect("provider did not start").into_split();
+        let mut lines = BufReader::new(read_half).lines();
+
+        write_half
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"run\",\"params\":{\"task\":\"provider-slow\"},\"id\":1}\n")
+            .await
+            .unwrap();
+
+        // The run has to be registered before it can be cancelled
+        let mut id = 2;
+        let cancelled = loop {
+            let request = format!(
+                "{{\"jsonrpc\":\"2.0\",\"method\":\"cancelTask\",\"params\":{{\"task\":\"provider-slow\"}},\"id\":{id}}}\n"
+            );
+            write_half.write_all(request.as_bytes()).await.unwrap();
+            let response: serde_json::Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert_eq!(response["id"], id, "answered before the run finished");
+            if response["result"]["cancelled"]
+                .as_array()
+                .is_some_and(|runs| !runs.is_empty())
+            {
+                break response;
+            }
+            id += 1;
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(
+            cancelled["result"]["cancelled"].as_array().unwrap().len(),
+            1
+        );
+
+        let run: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(run["id"], 1);
+        assert!(run["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("cancelled"));
+        server.abort();
+    }
 }
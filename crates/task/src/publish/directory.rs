@@ -0,0 +1,45 @@
+//! Copy artifacts into a directory
+
+use super::{Artifact, Publisher};
+use async_trait::async_trait;
+use cuenv_core::{Error, Result};
+use std::path::PathBuf;
+
+/// Copies artifacts under a directory, keeping their relative paths
+pub struct DirectoryPublisher {
+    dir: PathBuf,
+}
+
+impl DirectoryPublisher {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+#[async_trait]
+impl Publisher for DirectoryPublisher {
+    fn destination(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    async fn is_present(&self, artifacts: &[Artifact]) -> bool {
+        artifacts
+            .iter()
+            .all(|artifact| self.dir.join(&artifact.relative).is_file())
+    }
+
+    async fn publish(&self, artifacts: &[Artifact]) -> Result<()> {
+        for artifact in artifacts {
+            let target = self.dir.join(&artifact.relative);
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| Error::file_system(parent, "create publish directory", e))?;
+            }
+            tokio::fs::copy(&artifact.path, &target)
+                .await
+                .map_err(|e| Error::file_system(&target, "publish artifact", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+//! Upload artifacts to a generic HTTP endpoint
+
+use super::{Artifact, Publisher};
+use async_trait::async_trait;
+use cuenv_core::{Error, Result};
+use cuenv_utils::credentials::CredentialLookup;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Sends each artifact as the body of a request to `<url>/<relative path>`
+pub struct HttpPublisher {
+    url: String,
+    method: String,
+    headers: BTreeMap<String, String>,
+    token_env: Option<String>,
+}
+
+impl HttpPublisher {
+    pub fn new(
+        url: String,
+        method: String,
+        headers: BTreeMap<String, String>,
+        token_env: Option<String>,
+    ) -> Self {
+        Self {
+            url,
+            method,
+            headers,
+            token_env,
+        }
+    }
+
+    fn file_url(&self, artifact: &Artifact) -> String {
+        let relative = artifact
+            .relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{}/{relative}", self.url.trim_end_matches('/'))
+    }
+
+    /// Token of `tokenEnv`, or else the credential stored for the host
+    fn token(&self) -> Result<Option<String>> {
+        let mut lookup = CredentialLookup::for_url(&self.url);
+        if let Some(name) = &self.token_env {
+            lookup = lookup.with_env_var(name);
+        }
+        match (lookup.resolve(), &self.token_env) {
+            (Some(credential), _) => Ok(Some(credential.token)),
+            (None, Some(name)) => Err(Error::environment(
+                name,
+                format!(
+                    "needed to publish to {}; set it or run `cuenv login {}`",
+                    self.url,
+                    lookup.backend()
+                ),
+            )),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for HttpPublisher {
+    fn destination(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn publish(&self, artifacts: &[Artifact]) -> Result<()> {
+        let method = reqwest::Method::from_bytes(self.method.as_bytes())
+            .map_err(|e| Error::configuration(format!("Invalid HTTP method: {e}")))?;
+        let token = self.token()?;
+        let client = reqwest::Client::builder()
+            .timeout(UPLOAD_TIMEOUT)
+            .build()
+            .map_err(|e| Error::network(&self.url, e.to_string()))?;
+
+        for artifact in artifacts {
+            let url = self.file_url(artifact);
+            let body = tokio::fs::read(&artifact.path)
+                .await
+                .map_err(|e| Error::file_system(&artifact.path, "read artifact", e))?;
+            let request = self
+                .headers
+                .iter()
+                .fold(client.request(method.clone(), &url), |request, (k, v)| {
+                    request.header(k, v)
+                })
+                .body(body);
+            let request = match &token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            };
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| Error::network(&url, e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(Error::network(
+                    &url,
+                    format!("upload failed with HTTP {}", response.status()),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
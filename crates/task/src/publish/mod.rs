@@ -0,0 +1,189 @@
+//! Publishing task artifacts after a successful run
+//!
+//! Each `publish` entry of a task names some files (the task's outputs by
+//! default) and one destination: a directory, an OCI registry or a generic
+//! HTTP endpoint. Entries run in order once the task exits successfully, as
+//! a phase of their own. An entry is skipped when its files are exactly what
+//! it last published to the same destination and the destination still
+//! has them.
+
+mod directory;
+mod http;
+mod oci;
+mod state;
+
+pub use directory::DirectoryPublisher;
+pub use http::HttpPublisher;
+pub use oci::OciPublisher;
+pub use state::PublishState;
+
+use async_trait::async_trait;
+use cuenv_cache::hashing::{expand_glob_pattern, ContentHasher};
+use cuenv_core::{Error, Result, TaskDefinition, TaskPublishTarget};
+use std::path::{Path, PathBuf};
+
+/// A file to publish
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Artifact {
+    /// Path on disk
+    pub path: PathBuf,
+    /// Path relative to the task's working directory, kept at the destination
+    pub relative: PathBuf,
+}
+
+/// A destination for task artifacts
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    /// Human-readable destination, e.g. `dist` or `oci://ghcr.io/acme/app:1.0`
+    fn destination(&self) -> String;
+
+    /// Whether previously published artifacts are still at the destination
+    ///
+    /// Destinations that cannot be inspected cheaply trust the record of the
+    /// last publish.
+    async fn is_present(&self, _artifacts: &[Artifact]) -> bool {
+        true
+    }
+
+    /// Copy or upload every artifact
+    async fn publish(&self, artifacts: &[Artifact]) -> Result<()>;
+}
+
+/// What happened to one `publish` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishOutcome {
+    Published { destination: String, files: usize },
+    UpToDate { destination: String },
+}
+
+impl PublishOutcome {
+    /// One-line summary for progress output
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Published { destination, files } => {
+                let plural = if *files == 1 { "" } else { "s" };
+                format!("published {files} file{plural} to {destination}")
+            }
+            Self::UpToDate { destination } => format!("{destination} is up to date"),
+        }
+    }
+}
+
+/// Publisher for `target`, with relative paths resolved against `working_dir`
+pub fn publisher_for(target: &TaskPublishTarget, working_dir: &Path) -> Box<dyn Publisher> {
+    match target {
+        TaskPublishTarget::Directory(dir) => {
+            Box::new(DirectoryPublisher::new(working_dir.join(dir)))
+        }
+        TaskPublishTarget::Oci {
+            reference,
+            media_type,
+        } => Box::new(OciPublisher::new(
+            reference.clone(),
+            media_type.clone(),
+            working_dir.to_path_buf(),
+        )),
+        TaskPublishTarget::Http {
+            url,
+            method,
+            headers,
+            token_env,
+        } => Box::new(HttpPublisher::new(
+            url.clone(),
+            method.clone(),
+            headers.clone(),
+            token_env.clone(),
+        )),
+    }
+}
+
+/// Files matching `patterns` under `working_dir`, sorted and deduplicated
+pub fn collect_artifacts(working_dir: &Path, patterns: &[String]) -> Result<Vec<Artifact>> {
+    let mut artifacts = patterns
+        .iter()
+        .map(|pattern| expand_glob_pattern(pattern, working_dir))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .map(|path| {
+            let relative = path
+                .strip_prefix(working_dir)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.file_name().map(PathBuf::from).unwrap_or_default());
+            Artifact { path, relative }
+        })
+        .collect::<Vec<_>>();
+    artifacts.sort();
+    artifacts.dedup_by(|a, b| a.relative == b.relative);
+    Ok(artifacts)
+}
+
+/// Run every `publish` entry of a task that just succeeded
+pub async fn publish_task(
+    task_name: &str,
+    definition: &TaskDefinition,
+    state: &PublishState,
+) -> Result<Vec<PublishOutcome>> {
+    let working_dir = &definition.working_directory;
+    let mut outcomes = Vec::with_capacity(definition.publish.len());
+
+    for entry in &definition.publish {
+        let publisher = publisher_for(&entry.target, working_dir);
+        let destination = publisher.destination();
+        let patterns = if entry.files.is_empty() {
+            &definition.outputs
+        } else {
+            &entry.files
+        };
+        if patterns.is_empty() {
+            return Err(Error::configuration(format!(
+                "Task '{task_name}' publishes to {destination} but lists no files and has no outputs"
+            )));
+        }
+
+        let artifacts = collect_artifacts(working_dir, patterns)?;
+        if artifacts.is_empty() {
+            return Err(Error::configuration(format!(
+                "Task '{task_name}' has nothing to publish to {destination}: {} matched no files",
+                patterns.join(", ")
+            )));
+        }
+
+        let key = record_key(working_dir, task_name, &destination)?;
+        let digest = artifacts_digest(&destination, &artifacts)?;
+        if state.is_current(&key, &digest) && publisher.is_present(&artifacts).await {
+            outcomes.push(PublishOutcome::UpToDate { destination });
+            continue;
+        }
+
+        publisher.publish(&artifacts).await?;
+        state.record(&key, &digest)?;
+        outcomes.push(PublishOutcome::Published {
+            destination,
+            files: artifacts.len(),
+        });
+    }
+
+    Ok(outcomes)
+}
+
+/// Identifies one destination of one task
+fn record_key(working_dir: &Path, task_name: &str, destination: &str) -> Result<String> {
+    let mut hasher = ContentHasher::new("publish-destination");
+    hasher.hash_content((working_dir, task_name, destination))?;
+    hasher.generate_hash()
+}
+
+/// Changes whenever a file, its path or the destination changes
+fn artifacts_digest(destination: &str, artifacts: &[Artifact]) -> Result<String> {
+    let mut hasher = ContentHasher::new("publish");
+    hasher.hash_content(destination)?;
+    for artifact in artifacts {
+        hasher.hash_content(&artifact.relative)?;
+        hasher.hash_file(&artifact.path)?;
+    }
+    hasher.generate_hash()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,107 @@
+//! Push artifacts to an OCI registry with the `oras` CLI
+//!
+//! Registry credentials come from `oras login` (or the Docker credential
+//! store), so cuenv never handles them.
+
+use super::{Artifact, Publisher};
+use async_trait::async_trait;
+use cuenv_core::{Error, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Pushes every artifact as a layer of one OCI artifact
+pub struct OciPublisher {
+    reference: String,
+    media_type: Option<String>,
+    working_dir: PathBuf,
+}
+
+impl OciPublisher {
+    pub fn new(reference: String, media_type: Option<String>, working_dir: PathBuf) -> Self {
+        Self {
+            reference,
+            media_type,
+            working_dir,
+        }
+    }
+
+    /// `oras push` arguments; files are relative so layer titles are too
+    fn args(&self, artifacts: &[Artifact]) -> Vec<String> {
+        let files = artifacts.iter().map(|artifact| {
+            let file = artifact.relative.display().to_string();
+            match &self.media_type {
+                Some(media_type) => format!("{file}:{media_type}"),
+                None => file,
+            }
+        });
+        ["push".to_string(), self.reference.clone()]
+            .into_iter()
+            .chain(files)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Publisher for OciPublisher {
+    fn destination(&self) -> String {
+        format!("oci://{}", self.reference)
+    }
+
+    async fn publish(&self, artifacts: &[Artifact]) -> Result<()> {
+        let args = self.args(artifacts);
+        let output = Command::new("oras")
+            .args(&args)
+            .current_dir(&self.working_dir)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| {
+                Error::command_execution(
+                    "oras",
+                    args.clone(),
+                    format!("OCI publishing needs the `oras` CLI on PATH: {e}"),
+                    None,
+                )
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::command_execution(
+                "oras",
+                args,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                output.status.code(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_args_use_relative_paths_and_media_type() {
+        let publisher = OciPublisher::new(
+            "ghcr.io/acme/app:1.0".to_string(),
+            Some("application/vnd.acme.bin".to_string()),
+            PathBuf::from("/work"),
+        );
+        let artifacts = [Artifact {
+            path: PathBuf::from("/work/target/app"),
+            relative: PathBuf::from("target/app"),
+        }];
+
+        assert_eq!(publisher.destination(), "oci://ghcr.io/acme/app:1.0");
+        assert_eq!(
+            publisher.args(&artifacts),
+            [
+                "push",
+                "ghcr.io/acme/app:1.0",
+                "target/app:application/vnd.acme.bin"
+            ]
+        );
+    }
+}
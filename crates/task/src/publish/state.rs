@@ -0,0 +1,34 @@
+//! Record of what each destination last received
+
+use cuenv_core::Result;
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::xdg::XdgPaths;
+use std::path::PathBuf;
+
+/// Digests of the last successful publish, one file per destination
+#[derive(Debug, Clone)]
+pub struct PublishState {
+    dir: PathBuf,
+}
+
+impl PublishState {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Whether `digest` is what was last published under `key`
+    pub fn is_current(&self, key: &str, digest: &str) -> bool {
+        std::fs::read_to_string(self.dir.join(key)).is_ok_and(|last| last.trim() == digest)
+    }
+
+    /// Remember that `digest` was published under `key`
+    pub fn record(&self, key: &str, digest: &str) -> Result<()> {
+        write_atomic_string(&self.dir.join(key), digest)
+    }
+}
+
+impl Default for PublishState {
+    fn default() -> Self {
+        Self::new(XdgPaths::cache_dir().join("publish"))
+    }
+}
@@ -0,0 +1,67 @@
+use super::*;
+use cuenv_core::{TaskExecutionMode, TaskPublish};
+use tempfile::TempDir;
+
+fn definition(working_dir: &Path, files: &[&str], outputs: &[&str]) -> TaskDefinition {
+    let mut definition = TaskDefinition::new(
+        "build".to_string(),
+        TaskExecutionMode::Command {
+            command: "make".to_string(),
+        },
+        working_dir.to_path_buf(),
+    );
+    definition.outputs = outputs.iter().map(|o| o.to_string()).collect();
+    definition.publish = vec![TaskPublish {
+        files: files.iter().map(|f| f.to_string()).collect(),
+        target: TaskPublishTarget::Directory(PathBuf::from("dist")),
+    }];
+    definition
+}
+
+#[tokio::test]
+async fn test_directory_publish_skips_unchanged_artifacts() {
+    let work = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+    let state = PublishState::new(state_dir.path());
+    std::fs::create_dir_all(work.path().join("out/bin")).unwrap();
+    std::fs::write(work.path().join("out/bin/app"), "v1").unwrap();
+    let definition = definition(work.path(), &[], &["out"]);
+    let dist = work.path().join("dist/out/bin/app");
+
+    let outcomes = publish_task("build", &definition, &state).await.unwrap();
+    assert!(matches!(
+        outcomes[..],
+        [PublishOutcome::Published { files: 1, .. }]
+    ));
+    assert_eq!(std::fs::read_to_string(&dist).unwrap(), "v1");
+
+    let outcomes = publish_task("build", &definition, &state).await.unwrap();
+    assert!(matches!(outcomes[..], [PublishOutcome::UpToDate { .. }]));
+
+    // A changed artifact, or one missing from the destination, goes out again
+    std::fs::write(work.path().join("out/bin/app"), "v2").unwrap();
+    let outcomes = publish_task("build", &definition, &state).await.unwrap();
+    assert!(matches!(outcomes[..], [PublishOutcome::Published { .. }]));
+    assert_eq!(std::fs::read_to_string(&dist).unwrap(), "v2");
+
+    std::fs::remove_file(&dist).unwrap();
+    let outcomes = publish_task("build", &definition, &state).await.unwrap();
+    assert!(matches!(outcomes[..], [PublishOutcome::Published { .. }]));
+    assert!(dist.is_file());
+}
+
+#[tokio::test]
+async fn test_publish_requires_matching_files() {
+    let work = TempDir::new().unwrap();
+    let state = PublishState::new(work.path().join("state"));
+
+    let error = publish_task("build", &definition(work.path(), &[], &[]), &state)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("no outputs"));
+
+    let error = publish_task("build", &definition(work.path(), &["*.tar"], &[]), &state)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("*.tar matched no files"));
+}
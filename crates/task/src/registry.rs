@@ -1,5 +1,8 @@
 use crate::cross_package::{parse_reference, CrossPackageReference};
+use crate::resolution::{resolve_task_name, similar_task_names, TaskResolution};
+use cuenv_cache::hashing::split_package_input;
 use cuenv_config::TaskConfig;
+use cuenv_core::suggestions::with_suggestions;
 use cuenv_core::{Error, Result};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -8,6 +11,8 @@ use std::path::PathBuf;
 #[derive(Debug, Clone)]
 pub struct ParseResult {
     pub tasks: HashMap<String, TaskConfig>,
+    /// Environment variables the package defines
+    pub variables: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +43,8 @@ pub struct MonorepoTaskRegistry {
     tasks: HashMap<String, RegisteredTask>,
     /// Map from package name to package path
     package_paths: HashMap<String, PathBuf>,
+    /// Map from package name to the environment variables it defines
+    package_variables: HashMap<String, HashMap<String, String>>,
     /// Cached task configs for TaskSource trait
     task_configs: HashMap<String, TaskConfig>,
 }
@@ -48,6 +55,7 @@ impl MonorepoTaskRegistry {
         Self {
             tasks: HashMap::new(),
             package_paths: HashMap::new(),
+            package_variables: HashMap::new(),
             task_configs: HashMap::new(),
         }
     }
@@ -62,6 +70,9 @@ impl MonorepoTaskRegistry {
                 registry
                     .package_paths
                     .insert(package.name.clone(), package.path.clone());
+                registry
+                    .package_variables
+                    .insert(package.name.clone(), parse_result.variables);
 
                 // Register all tasks from this package
                 for (task_name, task_config) in parse_result.tasks {
@@ -93,6 +104,16 @@ impl MonorepoTaskRegistry {
         self.tasks.get(full_name)
     }
 
+    /// Resolve a task name, alias or trailing part of a name to a full name
+    pub fn resolve_task(&self, query: &str) -> TaskResolution {
+        resolve_task_name(&self.task_configs, query)
+    }
+
+    /// Names of tasks, groups and aliases that `query` may be a typo of
+    pub fn similar_tasks(&self, query: &str) -> Vec<String> {
+        similar_task_names(&self.task_configs, query)
+    }
+
     /// Get all tasks for a specific package
     pub fn get_tasks_by_package(&self, package_name: &str) -> Vec<&RegisteredTask> {
         self.tasks
@@ -114,18 +135,27 @@ impl MonorepoTaskRegistry {
         self.package_paths.get(package_name)
     }
 
+    /// Get the environment variables a package defines
+    pub fn get_package_variables(&self, package_name: &str) -> Option<&HashMap<String, String>> {
+        self.package_variables.get(package_name)
+    }
+
     /// Resolve a task output to its filesystem path
     pub fn resolve_task_output(&self, task_ref: &str, output_name: &str) -> Result<PathBuf> {
         // Get the task
         let task = self
             .get_task(task_ref)
             .ok_or_else(|| Error::Configuration {
-                message: format!("Task '{task_ref}' not found"),
+                message: with_suggestions(
+                    format!("Task '{task_ref}' not found"),
+                    &self.similar_tasks(task_ref),
+                ),
             })?;
 
         // Check if the task declares this output
         if let Some(ref outputs) = task.config.outputs {
             if !outputs
+                .files()
                 .iter()
                 .any(|o| o == output_name || o.ends_with(output_name))
             {
@@ -201,6 +231,11 @@ impl MonorepoTaskRegistry {
             // Validate inputs reference existing outputs
             if let Some(ref inputs) = task.config.inputs {
                 for input in inputs {
+                    // Files of other packages are resolved when hashing
+                    if split_package_input(input).is_some() {
+                        continue;
+                    }
+
                     // Parse input reference
                     let input_ref = parse_reference(input)?;
 
@@ -216,7 +251,7 @@ impl MonorepoTaskRegistry {
                         // Check if we can resolve this output (don't fail if it doesn't exist yet)
                         if let Some(ref_task) = self.get_task(&task_ref) {
                             if let Some(ref outputs) = ref_task.config.outputs {
-                                if !outputs.iter().any(|o| o == output) {
+                                if !outputs.files().iter().any(|o| o == output) {
                                     return Err(Error::Configuration { message: format!(
                                         "Task '{task_name}' references non-existent output '{output}' from task '{task_ref}'"
                                     ) });
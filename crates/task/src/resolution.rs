@@ -0,0 +1,169 @@
+//! Resolving the task name given on the command line
+//!
+//! Besides full names, a task can be named by one of its aliases or by a
+//! trailing part of its name: `check` finds `lint.check` and
+//! `projects:web:lint.check` as long as no other task also ends in `check`.
+//! An alias stands in for the last segment of the task's name, so `alias:
+//! ["c"]` on `lint.check` makes `lint.c` and `c` name it too.
+
+use cuenv_config::TaskConfig;
+use cuenv_core::suggestions::similar_names;
+use std::collections::{BTreeSet, HashMap};
+
+/// Characters separating the segments of a full task name
+const SEGMENT_SEPARATORS: [char; 2] = ['.', ':'];
+
+/// Outcome of resolving a task name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskResolution {
+    /// The name refers to exactly one task
+    Found(String),
+    /// Several tasks match, sorted by name
+    Ambiguous(Vec<String>),
+    /// No task matches
+    NotFound,
+}
+
+/// Resolve `query` against `tasks`, keyed by full task name
+///
+/// An exact task name wins, then exact alias matches, then names and
+/// aliases ending in `query` at a segment boundary.
+pub fn resolve_task_name(tasks: &HashMap<String, TaskConfig>, query: &str) -> TaskResolution {
+    if tasks.contains_key(query) {
+        return TaskResolution::Found(query.to_string());
+    }
+
+    let by_alias = matching_tasks(tasks, |name| name == query);
+    if !by_alias.is_empty() {
+        return into_resolution(by_alias);
+    }
+
+    into_resolution(matching_tasks(tasks, |name| {
+        ends_with_segments(name, query)
+    }))
+}
+
+/// Names of tasks, groups and aliases that `query` may be a typo of
+pub fn similar_task_names(tasks: &HashMap<String, TaskConfig>, query: &str) -> Vec<String> {
+    let names = tasks.iter().flat_map(|(name, config)| {
+        let groups = name
+            .match_indices(SEGMENT_SEPARATORS)
+            .map(|(index, _)| name[..index].to_string());
+        let aliases = config.alias.iter().flatten().cloned();
+        full_names(name, config).chain(groups).chain(aliases)
+    });
+    similar_names(query, names.collect::<BTreeSet<_>>())
+}
+
+/// Names the task can be referred to by in full: its own and one per alias
+fn full_names<'a>(name: &'a str, config: &'a TaskConfig) -> impl Iterator<Item = String> + 'a {
+    let prefix = name
+        .rfind(SEGMENT_SEPARATORS)
+        .map_or("", |index| &name[..=index]);
+    std::iter::once(name.to_string()).chain(
+        config
+            .alias
+            .iter()
+            .flatten()
+            .map(move |alias| format!("{prefix}{alias}")),
+    )
+}
+
+fn matching_tasks(
+    tasks: &HashMap<String, TaskConfig>,
+    matches: impl Fn(&str) -> bool,
+) -> BTreeSet<String> {
+    tasks
+        .iter()
+        .filter(|(name, config)| full_names(name, config).any(|full| matches(&full)))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn ends_with_segments(name: &str, query: &str) -> bool {
+    name.len() > query.len()
+        && name.ends_with(query)
+        && name[..name.len() - query.len()].ends_with(SEGMENT_SEPARATORS)
+}
+
+fn into_resolution(candidates: BTreeSet<String>) -> TaskResolution {
+    let mut candidates: Vec<_> = candidates.into_iter().collect();
+    match candidates.len() {
+        0 => TaskResolution::NotFound,
+        1 => TaskResolution::Found(candidates.remove(0)),
+        _ => TaskResolution::Ambiguous(candidates),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(entries: &[(&str, &[&str])]) -> HashMap<String, TaskConfig> {
+        entries
+            .iter()
+            .map(|(name, aliases)| {
+                let config = TaskConfig {
+                    command: Some("true".to_string()),
+                    alias: (!aliases.is_empty())
+                        .then(|| aliases.iter().map(|a| a.to_string()).collect()),
+                    ..Default::default()
+                };
+                (name.to_string(), config)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolves_exact_names_aliases_and_suffixes() {
+        let tasks = tasks(&[
+            ("test", &["t"]),
+            ("lint.check", &["c"]),
+            ("fmt.check", &[]),
+            ("docs.build", &[]),
+        ]);
+
+        let found = |name: &str| TaskResolution::Found(name.to_string());
+        assert_eq!(resolve_task_name(&tasks, "test"), found("test"));
+        assert_eq!(resolve_task_name(&tasks, "t"), found("test"));
+        assert_eq!(resolve_task_name(&tasks, "c"), found("lint.check"));
+        assert_eq!(resolve_task_name(&tasks, "lint.c"), found("lint.check"));
+        assert_eq!(resolve_task_name(&tasks, "build"), found("docs.build"));
+        assert_eq!(
+            resolve_task_name(&tasks, "check"),
+            TaskResolution::Ambiguous(vec!["fmt.check".to_string(), "lint.check".to_string()])
+        );
+        assert_eq!(resolve_task_name(&tasks, "eck"), TaskResolution::NotFound);
+    }
+
+    #[test]
+    fn test_suggests_tasks_groups_and_aliases() {
+        let tasks = tasks(&[
+            ("test", &["t"]),
+            ("lint.check", &["chk"]),
+            ("docs.build", &[]),
+        ]);
+
+        assert_eq!(similar_task_names(&tasks, "tets"), vec!["test"]);
+        assert_eq!(
+            similar_task_names(&tasks, "lint.chek"),
+            vec!["lint.check", "lint.chk", "lint"]
+        );
+        assert_eq!(similar_task_names(&tasks, "lnt"), vec!["lint", "lint.chk"]);
+        assert!(similar_task_names(&tasks, "deploy").is_empty());
+    }
+
+    #[test]
+    fn test_resolves_across_packages() {
+        let tasks = tasks(&[("projects:web:build", &["b"]), ("root:build.docs", &[])]);
+
+        assert_eq!(
+            resolve_task_name(&tasks, "web:b"),
+            TaskResolution::Found("projects:web:build".to_string())
+        );
+        assert_eq!(
+            resolve_task_name(&tasks, "docs"),
+            TaskResolution::Found("root:build.docs".to_string())
+        );
+    }
+}
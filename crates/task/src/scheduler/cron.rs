@@ -0,0 +1,175 @@
+//! Cron expressions: `minute hour day-of-month month day-of-week`
+//!
+//! Each field is `*`, a value, a range `a-b` or a list of them, any of which
+//! can take a step, as in `*/15` or `1-5/2`. Months and days of the week can
+//! be named (`jan`, `mon`), and Sunday is both 0 and 7. As in cron, a time
+//! matches when both day fields restrict it and either matches. The macros
+//! `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted too.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use cuenv_core::{Error, Result};
+use std::str::FromStr;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead to look for a matching time, enough for `0 0 29 2 *`
+const SEARCH_YEARS: i32 = 8;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    /// Whether the day-of-month field did not start with `*`
+    day_of_month_restricted: bool,
+    /// Whether the day-of-week field did not start with `*`
+    day_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(invalid(
+                expression,
+                "expected 5 fields: minute hour day-of-month month day-of-week",
+            ));
+        };
+
+        let field = |text: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(text, min, max, names)
+                .map_err(|reason| invalid(expression, &format!("'{text}': {reason}")))
+        };
+        // Sunday is 7 as well as 0
+        let weekdays = field(day_of_week, 0, 7, &WEEKDAYS)?;
+        Ok(Self {
+            minutes: field(minute, 0, 59, &[])?,
+            hours: field(hour, 0, 23, &[])? as u32,
+            days_of_month: field(day_of_month, 1, 31, &[])? as u32,
+            months: field(month, 1, 12, &MONTHS)? as u16,
+            days_of_week: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl CronSchedule {
+    /// First matching minute strictly after `after`, in its time zone
+    ///
+    /// Local times skipped by a daylight saving change never match; those
+    /// repeated by one match the first time round.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let mut candidate = after.naive_local();
+        loop {
+            candidate = self.next_naive_after(candidate)?;
+            if let Some(time) = timezone.from_local_datetime(&candidate).earliest() {
+                if time > *after {
+                    return Some(time);
+                }
+            }
+        }
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    fn next_naive_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = after.year() + SEARCH_YEARS;
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        while time.year() <= limit {
+            if !self.matches_date(time.date()) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+fn invalid(expression: &str, reason: &str) -> Error {
+    Error::configuration(format!("Invalid cron expression '{expression}': {reason}"))
+}
+
+/// Bit set of the values a field matches
+fn parse_field(text: &str, min: u32, max: u32, names: &[&str]) -> std::result::Result<u64, String> {
+    text.split(',').try_fold(0u64, |bits, part| {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step '{step}'"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (
+                    parse_value(start, min, max, names)?,
+                    parse_value(end, min, max, names)?,
+                ),
+                // `5/10` means from 5 to the end, every 10
+                None if part.contains('/') => (parse_value(range, min, max, names)?, max),
+                None => {
+                    let value = parse_value(range, min, max, names)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range {start}-{end} is backwards"));
+        }
+        Ok((start..=end)
+            .step_by(step as usize)
+            .fold(bits, |bits, value| bits | (1 << value)))
+    })
+}
+
+fn parse_value(text: &str, min: u32, max: u32, names: &[&str]) -> std::result::Result<u32, String> {
+    let lower = text.to_ascii_lowercase();
+    let value = match names.iter().position(|name| *name == lower) {
+        // Names count from the field's minimum: `jan` is 1, `sun` is 0
+        Some(index) => index as u32 + min,
+        None => text
+            .parse()
+            .map_err(|_| format!("'{text}' is not a number"))?,
+    };
+    if value < min || value > max {
+        return Err(format!("{value} is outside {min}-{max}"));
+    }
+    Ok(value)
+}
@@ -0,0 +1,192 @@
+//! Running tasks on a schedule
+//!
+//! `cuenv scheduler run` runs each task with a `schedule` whenever its cron
+//! expression matches, in local time. A task with `jitter` starts a fixed
+//! share of it late, derived from the task's name, so tasks sharing a
+//! schedule do not all start at once but each starts at the same time every
+//! day. The last time each task was due is recorded, so that when the
+//! scheduler was not running the times it missed are caught up as the
+//! task's `catchUp` policy says: one run (the default), one run per missed
+//! time, or none.
+
+mod cron;
+mod state;
+
+#[cfg(test)]
+mod tests;
+
+pub use cron::CronSchedule;
+pub use state::ScheduleState;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use cuenv_config::{CatchUpPolicy, ScheduleConfig, TaskConfig};
+use cuenv_core::{Error, Result};
+use cuenv_utils::duration::parse_duration;
+use std::collections::HashMap;
+
+/// How late a run can start before its time counts as missed
+const MISSED_AFTER_SECS: i64 = 60;
+
+/// Most runs made for the missed times of a task with `catchUp: "all"`
+const MAX_CATCH_UP_RUNS: usize = 10;
+
+/// A task with a schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledTask {
+    pub name: String,
+    /// The cron expression, as written
+    pub expression: String,
+    pub cron: CronSchedule,
+    pub catch_up: CatchUpPolicy,
+    /// How long after each time it is due the task starts
+    pub delay: Duration,
+}
+
+impl ScheduledTask {
+    pub fn new(name: &str, schedule: &ScheduleConfig) -> Result<Self> {
+        let cron = schedule
+            .cron()
+            .parse()
+            .map_err(|e| Error::configuration(format!("Task '{name}': {e}")))?;
+        let jitter = schedule
+            .jitter()
+            .map(parse_duration)
+            .transpose()
+            .map_err(|e| Error::configuration(format!("Task '{name}': invalid jitter: {e}")))?
+            .unwrap_or_default();
+        Ok(Self {
+            name: name.to_string(),
+            expression: schedule.cron().to_string(),
+            cron,
+            catch_up: schedule.catch_up(),
+            delay: jitter_delay(name, jitter.as_secs()),
+        })
+    }
+
+    /// When the task next starts after `after`
+    pub fn next_run<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.cron
+            .next_after(&(after.clone() - self.delay))
+            .map(|due| due + self.delay)
+    }
+
+    /// Times the task was due after `after` and should have started by `now`
+    fn due_times<Tz: TimeZone>(
+        &self,
+        after: &DateTime<Tz>,
+        now: &DateTime<Tz>,
+    ) -> Vec<DateTime<Tz>> {
+        std::iter::successors(self.cron.next_after(after), |due| self.cron.next_after(due))
+            .take_while(|due| due.clone() + self.delay <= *now)
+            .collect()
+    }
+}
+
+/// Scheduled tasks of a configuration, sorted by name
+pub fn scheduled_tasks(tasks: &HashMap<String, TaskConfig>) -> Result<Vec<ScheduledTask>> {
+    let mut scheduled = tasks
+        .iter()
+        .filter_map(|(name, task)| {
+            task.schedule
+                .as_ref()
+                .map(|schedule| ScheduledTask::new(name, schedule))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    scheduled.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(scheduled)
+}
+
+/// Share of `jitter_secs` a task starts late by, the same on every run
+fn jitter_delay(name: &str, jitter_secs: u64) -> Duration {
+    if jitter_secs == 0 {
+        return Duration::zero();
+    }
+    // FNV-1a, which unlike the std hasher is the same in every build
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    Duration::seconds((hash % jitter_secs) as i64)
+}
+
+/// Runs to make of a task that is due
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueRun {
+    pub task: String,
+    /// The latest time the task was due
+    pub due: DateTime<Utc>,
+    /// Times it was due that were missed
+    pub missed: usize,
+    /// How many times to run it, which is 0 when missed runs are skipped
+    pub runs: usize,
+}
+
+/// Scheduled tasks and when they were last due
+pub struct Scheduler {
+    tasks: Vec<ScheduledTask>,
+    state: ScheduleState,
+}
+
+impl Scheduler {
+    /// Schedule `tasks`, counting those never scheduled before as last due
+    /// at `now` so that their past is not caught up
+    pub fn new(tasks: Vec<ScheduledTask>, mut state: ScheduleState, now: DateTime<Utc>) -> Self {
+        for task in &tasks {
+            if state.last_due(&task.name).is_none() {
+                state.set_last_due(&task.name, now);
+            }
+        }
+        Self { tasks, state }
+    }
+
+    pub fn tasks(&self) -> &[ScheduledTask] {
+        &self.tasks
+    }
+
+    /// Tasks that should have started by `now`, in the time zone of `now`
+    pub fn due<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Vec<DueRun> {
+        let missed_before = now.clone() - Duration::seconds(MISSED_AFTER_SECS);
+        self.tasks
+            .iter()
+            .filter_map(|task| {
+                let last = self
+                    .state
+                    .last_due(&task.name)?
+                    .with_timezone(&now.timezone());
+                let times = task.due_times(&last, now);
+                let latest = times.last()?.with_timezone(&Utc);
+                let missed = times
+                    .iter()
+                    .filter(|due| (*due).clone() + task.delay < missed_before)
+                    .count();
+                let runs = match task.catch_up {
+                    CatchUpPolicy::Once => 1,
+                    CatchUpPolicy::All => times.len().min(MAX_CATCH_UP_RUNS),
+                    CatchUpPolicy::Skip => usize::from(missed < times.len()),
+                };
+                Some(DueRun {
+                    task: task.name.clone(),
+                    due: latest,
+                    missed,
+                    runs,
+                })
+            })
+            .collect()
+    }
+
+    /// When the next task starts after `now`
+    pub fn next_run<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.tasks
+            .iter()
+            .filter_map(|task| task.next_run(now))
+            .min()
+    }
+
+    /// Record that `run` was handled, so its times are not due again
+    pub fn record(&mut self, run: &DueRun) {
+        self.state.set_last_due(&run.task, run.due);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.state.save()
+    }
+}
@@ -0,0 +1,42 @@
+//! When each scheduled task was last due
+
+use chrono::{DateTime, Utc};
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Last time each scheduled task of a project was due, whether it ran then
+/// or not
+#[derive(Debug, Clone)]
+pub struct ScheduleState {
+    path: PathBuf,
+    last_due: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl ScheduleState {
+    /// State in `path`; empty when the file does not exist or is unreadable
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let last_due = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, last_due }
+    }
+
+    pub fn last_due(&self, task: &str) -> Option<DateTime<Utc>> {
+        self.last_due.get(task).copied()
+    }
+
+    pub fn set_last_due(&mut self, task: &str, due: DateTime<Utc>) {
+        self.last_due.insert(task.to_string(), due);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.last_due).map_err(|e| {
+            Error::configuration(format!("Failed to serialize scheduler state: {e}"))
+        })?;
+        write_atomic_string(&self.path, &content)
+    }
+}
@@ -0,0 +1,185 @@
+use super::*;
+use tempfile::TempDir;
+
+fn at(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn next(expression: &str, after: &str) -> DateTime<Utc> {
+    let cron: CronSchedule = expression.parse().unwrap();
+    cron.next_after(&at(after)).unwrap()
+}
+
+fn task(expression: &str, catch_up: CatchUpPolicy) -> ScheduledTask {
+    ScheduledTask::new(
+        "deps",
+        &ScheduleConfig::Detailed {
+            cron: expression.to_string(),
+            catch_up,
+            jitter: None,
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_cron_next_after() {
+    // 2026-10-16 is a Friday
+    assert_eq!(
+        next("0 9 * * 1-5", "2026-10-16T08:30:00Z"),
+        at("2026-10-16T09:00:00Z")
+    );
+    assert_eq!(
+        next("0 9 * * 1-5", "2026-10-16T09:00:00Z"),
+        at("2026-10-19T09:00:00Z")
+    );
+    assert_eq!(
+        next("*/15 * * * *", "2026-10-16T09:07:42Z"),
+        at("2026-10-16T09:15:00Z")
+    );
+    assert_eq!(
+        next("@monthly", "2026-10-16T09:00:00Z"),
+        at("2026-11-01T00:00:00Z")
+    );
+    assert_eq!(
+        next("30 2 29 feb *", "2026-10-16T09:00:00Z"),
+        at("2028-02-29T02:30:00Z")
+    );
+    // Sunday is 0 and 7
+    assert_eq!(
+        next("0 0 * * 7", "2026-10-16T09:00:00Z"),
+        next("0 0 * * sun", "2026-10-16T09:00:00Z")
+    );
+}
+
+#[test]
+fn test_cron_day_fields_match_either_when_both_are_set() {
+    // The 13th, or any Friday
+    assert_eq!(
+        next("0 0 13 * fri", "2026-10-16T09:00:00Z"),
+        at("2026-10-23T00:00:00Z")
+    );
+    assert_eq!(
+        next("0 0 13 * fri", "2026-11-12T09:00:00Z"),
+        at("2026-11-13T00:00:00Z")
+    );
+    // A stepped day of the week still narrows the day of the month
+    assert_eq!(
+        next("0 0 1 * */7", "2026-10-16T09:00:00Z"),
+        at("2026-11-01T00:00:00Z")
+    );
+}
+
+#[test]
+fn test_cron_rejects_invalid_expressions() {
+    for expression in [
+        "",
+        "0 9 * *",
+        "60 * * * *",
+        "* * 0 * *",
+        "5-1 * * * *",
+        "*/0 * * * *",
+    ] {
+        assert!(
+            expression.parse::<CronSchedule>().is_err(),
+            "'{expression}' should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_on_time_run_is_due_once() {
+    let state = ScheduleState::load(TempDir::new().unwrap().path().join("state.json"));
+    let scheduler = Scheduler::new(
+        vec![task("0 * * * *", CatchUpPolicy::Skip)],
+        state,
+        at("2026-10-16T08:30:00Z"),
+    );
+
+    assert!(scheduler.due(&at("2026-10-16T08:59:00Z")).is_empty());
+    assert_eq!(
+        scheduler.next_run(&at("2026-10-16T08:59:00Z")),
+        Some(at("2026-10-16T09:00:00Z"))
+    );
+    assert_eq!(
+        scheduler.due(&at("2026-10-16T09:00:05Z")),
+        vec![DueRun {
+            task: "deps".to_string(),
+            due: at("2026-10-16T09:00:00Z"),
+            missed: 0,
+            runs: 1,
+        }]
+    );
+}
+
+#[test]
+fn test_catch_up_policies() {
+    let missed_three = |catch_up| {
+        let temp_dir = TempDir::new().unwrap();
+        let mut state = ScheduleState::load(temp_dir.path().join("state.json"));
+        state.set_last_due("deps", at("2026-10-16T06:00:00Z"));
+        let scheduler = Scheduler::new(vec![task("0 * * * *", catch_up)], state, Utc::now());
+        scheduler.due(&at("2026-10-16T09:30:00Z")).remove(0)
+    };
+
+    let once = missed_three(CatchUpPolicy::Once);
+    assert_eq!((once.missed, once.runs), (3, 1));
+    assert_eq!(once.due, at("2026-10-16T09:00:00Z"));
+    assert_eq!(missed_three(CatchUpPolicy::All).runs, 3);
+    assert_eq!(missed_three(CatchUpPolicy::Skip).runs, 0);
+}
+
+#[test]
+fn test_recorded_runs_are_not_due_again() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("state.json");
+    let mut scheduler = Scheduler::new(
+        vec![task("0 * * * *", CatchUpPolicy::Once)],
+        ScheduleState::load(&path),
+        at("2026-10-16T08:30:00Z"),
+    );
+    let run = scheduler.due(&at("2026-10-16T09:00:00Z")).remove(0);
+    scheduler.record(&run);
+    scheduler.save().unwrap();
+
+    let reloaded = ScheduleState::load(&path);
+    assert_eq!(reloaded.last_due("deps"), Some(at("2026-10-16T09:00:00Z")));
+    let scheduler = Scheduler::new(
+        vec![task("0 * * * *", CatchUpPolicy::Once)],
+        reloaded,
+        Utc::now(),
+    );
+    assert!(scheduler.due(&at("2026-10-16T09:30:00Z")).is_empty());
+}
+
+#[test]
+fn test_jitter_delays_runs_by_a_stable_share() {
+    let schedule = ScheduleConfig::Detailed {
+        cron: "0 9 * * *".to_string(),
+        catch_up: CatchUpPolicy::Once,
+        jitter: Some("10m".to_string()),
+    };
+    let first = ScheduledTask::new("gc", &schedule).unwrap();
+    let second = ScheduledTask::new("gc", &schedule).unwrap();
+    assert_eq!(first.delay, second.delay);
+    assert!(first.delay < Duration::minutes(10));
+
+    let start = first.next_run(&at("2026-10-16T08:00:00Z")).unwrap();
+    assert_eq!(start, at("2026-10-16T09:00:00Z") + first.delay);
+    assert!(first
+        .due_times(&at("2026-10-16T08:00:00Z"), &(start - Duration::seconds(1)))
+        .is_empty());
+    assert_eq!(
+        first.due_times(&at("2026-10-16T08:00:00Z"), &start).len(),
+        1
+    );
+
+    let invalid = ScheduleConfig::Detailed {
+        cron: "0 9 * * *".to_string(),
+        catch_up: CatchUpPolicy::Once,
+        jitter: Some("soon".to_string()),
+    };
+    assert!(ScheduledTask::new("gc", &invalid).is_err());
+}
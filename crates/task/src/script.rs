@@ -0,0 +1,174 @@
+//! Scripts of `script` tasks that name their interpreter with a shebang
+//!
+//! A script starting with `#!` is written to a file of its own and run by
+//! the interpreter it names, looked up in `PATH` by its file name, so
+//! `#!/usr/bin/python3` and `#!/usr/bin/env python3` both run the
+//! environment's `python3`. Other scripts run with the task's shell. The
+//! file keeps the script as it is, so line numbers in errors are the
+//! script's, and is named after the task; it is removed once the task ends.
+
+use cuenv_core::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Interpreters scripts can name, without a version suffix such as the
+/// `3.12` of `python3.12`
+pub const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "dash", "zsh", "fish", "pwsh", "python", "nu", "node", "ruby", "perl",
+];
+
+/// The interpreter line of a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shebang {
+    /// File name of the interpreter, looked up in `PATH`
+    pub interpreter: String,
+    /// Arguments the line passes before the script
+    pub args: Vec<String>,
+}
+
+impl Shebang {
+    /// The shebang of `script`, failing on interpreters cuenv doesn't run
+    pub fn parse(script: &str) -> Result<Option<Self>> {
+        let Some(line) = script
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("#!"))
+        else {
+            return Ok(None);
+        };
+        let mut words = line.split_whitespace();
+        let Some(mut program) = words.next() else {
+            return Err(Error::configuration(
+                "Script has a shebang without an interpreter",
+            ));
+        };
+        if file_name(program) == "env" {
+            program = words
+                .by_ref()
+                .find(|word| !word.starts_with('-'))
+                .ok_or_else(|| {
+                    Error::configuration(format!("Script shebang '#!{line}' names no interpreter"))
+                })?;
+        }
+
+        let interpreter = file_name(program).to_string();
+        let family = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if !INTERPRETERS.contains(&family) {
+            return Err(Error::configuration(format!(
+                "Script shebang names '{interpreter}', which cuenv does not run scripts with. \
+                 Supported interpreters: {}",
+                INTERPRETERS.join(", ")
+            )));
+        }
+        Ok(Some(Self {
+            interpreter,
+            args: words.map(str::to_string).collect(),
+        }))
+    }
+
+    /// Extension of script files, which some interpreters insist on
+    fn extension(&self) -> &'static str {
+        match self
+            .interpreter
+            .trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+        {
+            "python" => "py",
+            "nu" => "nu",
+            "node" => "js",
+            "ruby" => "rb",
+            "perl" => "pl",
+            "pwsh" => "ps1",
+            "fish" => "fish",
+            _ => "sh",
+        }
+    }
+}
+
+fn file_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// A script written out for its interpreter, removed when dropped
+#[derive(Debug)]
+pub struct ScriptFile {
+    dir: tempfile::TempDir,
+    path: PathBuf,
+}
+
+impl ScriptFile {
+    /// Write `script` of task `task_name` to a private temporary directory
+    pub fn write(task_name: &str, shebang: &Shebang, script: &str) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("cuenv-script-")
+            .tempdir()
+            .map_err(|e| Error::file_system(std::env::temp_dir(), "create script directory", e))?;
+        let name: String = task_name
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect();
+        let path = dir.path().join(format!("{name}.{}", shebang.extension()));
+        std::fs::write(&path, script)
+            .map_err(|e| Error::file_system(&path, "write task script", e))?;
+        Ok(Self { dir, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory of the script, for sandboxes to allow reading
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shebang(script: &str) -> Option<Shebang> {
+        Shebang::parse(script).unwrap()
+    }
+
+    #[test]
+    fn test_parse_shebang() {
+        assert_eq!(shebang("echo hi"), None);
+        let python = shebang("#!/usr/bin/env python3\nprint(1)").unwrap();
+        assert_eq!(python.interpreter, "python3");
+        assert!(python.args.is_empty());
+        let bash = shebang("#!/bin/bash -eu\nset -x").unwrap();
+        assert_eq!(bash.interpreter, "bash");
+        assert_eq!(bash.args, ["-eu"]);
+        let nu = shebang("#!/usr/bin/env -S nu --stdin\n").unwrap();
+        assert_eq!(nu.interpreter, "nu");
+        assert_eq!(nu.args, ["--stdin"]);
+        assert_eq!(
+            shebang("#!/usr/bin/python3.12\n").unwrap().interpreter,
+            "python3.12"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_shebang() {
+        let err = Shebang::parse("#!/usr/bin/env php\n").unwrap_err();
+        assert!(err.to_string().contains("'php'"));
+        assert!(Shebang::parse("#!\n").is_err());
+        assert!(Shebang::parse("#!/usr/bin/env\n").is_err());
+    }
+
+    #[test]
+    fn test_script_file_is_removed() {
+        let shebang = shebang("#!/usr/bin/env python3\n").unwrap();
+        let file = ScriptFile::write("db:migrate", &shebang, "#!/usr/bin/env python3\n").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(path.file_name().unwrap(), "db_migrate.py");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "#!/usr/bin/env python3\n"
+        );
+        drop(file);
+        assert!(!path.exists());
+    }
+}
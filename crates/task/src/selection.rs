@@ -0,0 +1,266 @@
+//! Selecting tasks by label and by what changed
+//!
+//! `cuenv task run --label integration --exclude-label flaky` runs every
+//! task labelled `integration` and not `flaky`, with their dependencies, as
+//! one DAG. Tags from `@tag` count as labels. With `--affected <ref>` the
+//! selection narrows further to tasks whose inputs changed since that git
+//! revision, and tasks depending on them. Tasks without `inputs`, or with
+//! `pkg://` inputs of other packages, always count as affected, as what
+//! they read is unknown.
+
+use cuenv_cache::hashing::split_package_input;
+use cuenv_config::TaskConfig;
+use cuenv_core::{Error, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Which tasks to run
+#[derive(Debug, Clone, Default)]
+pub struct TaskSelection {
+    /// Labels a task must all have
+    pub labels: Vec<String>,
+    /// Labels any of which leaves a task out
+    pub exclude_labels: Vec<String>,
+    /// Files changed since the base revision, relative to the project;
+    /// `None` selects tasks whether or not they are affected
+    pub changed_files: Option<Vec<PathBuf>>,
+}
+
+impl TaskSelection {
+    /// Whether the selection leaves out no task
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.exclude_labels.is_empty() && self.changed_files.is_none()
+    }
+
+    /// Whether `task` has the selected labels and none of the excluded ones
+    pub fn matches_labels(&self, task: &TaskConfig) -> bool {
+        self.labels.iter().all(|label| task.has_label(label))
+            && !self
+                .exclude_labels
+                .iter()
+                .any(|label| task.has_label(label))
+    }
+
+    /// Names of the selected tasks among `candidates`, sorted
+    pub fn select<'a>(
+        &self,
+        tasks: &HashMap<String, TaskConfig>,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<String>> {
+        let affected = match &self.changed_files {
+            Some(changed) => Some(affected_tasks(tasks, changed)?),
+            None => None,
+        };
+        let mut selected: Vec<String> = candidates
+            .into_iter()
+            .filter(|name| {
+                tasks
+                    .get(*name)
+                    .is_some_and(|task| self.matches_labels(task))
+            })
+            .filter(|name| {
+                affected
+                    .as_ref()
+                    .is_none_or(|affected| affected.contains(*name))
+            })
+            .map(str::to_string)
+            .collect();
+        selected.sort();
+        selected.dedup();
+        Ok(selected)
+    }
+}
+
+/// Files changed in `dir` since the git revision `base`, committed or not,
+/// relative to `dir`
+pub fn changed_files(dir: &Path, base: &str) -> Result<Vec<PathBuf>> {
+    let diff = git(dir, &["diff", "--name-only", "--relative", base, "--"])?;
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Tasks with an input among `changed`, or without inputs or with inputs
+/// of other packages, and the tasks depending on them
+fn affected_tasks(
+    tasks: &HashMap<String, TaskConfig>,
+    changed: &[PathBuf],
+) -> Result<HashSet<String>> {
+    let mut affected = HashSet::new();
+    for (name, task) in tasks {
+        let inputs = task.inputs.as_deref().unwrap_or_default();
+        // Changes are only known within the project, not in other packages
+        if inputs.is_empty()
+            || inputs
+                .iter()
+                .any(|input| split_package_input(input).is_some())
+        {
+            affected.insert(name.clone());
+            continue;
+        }
+        let globs = input_set(name, inputs)?;
+        if changed.iter().any(|file| is_input(&globs, inputs, file)) {
+            affected.insert(name.clone());
+        }
+    }
+
+    // A task is affected when something it depends on is
+    loop {
+        let dependents: Vec<String> = tasks
+            .iter()
+            .filter(|(name, _)| !affected.contains(*name))
+            .filter(|(_, task)| {
+                task.dependencies
+                    .iter()
+                    .flatten()
+                    .any(|dependency| affected.contains(dependency))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if dependents.is_empty() {
+            return Ok(affected);
+        }
+        affected.extend(dependents);
+    }
+}
+
+fn input_set(task: &str, inputs: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for input in inputs {
+        let glob = Glob::new(input.trim_start_matches("./")).map_err(|e| {
+            Error::configuration(format!("Task '{task}': invalid input '{input}': {e}"))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| Error::configuration(format!("Task '{task}': invalid inputs: {e}")))
+}
+
+/// Whether `file` matches one of `inputs`, or is inside an input directory
+fn is_input(globs: &GlobSet, inputs: &[String], file: &Path) -> bool {
+    globs.is_match(file)
+        || inputs
+            .iter()
+            .any(|input| file.starts_with(input.trim_start_matches("./")))
+}
+
+/// Run git in `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| {
+            Error::command_execution(
+                "git",
+                args.iter().map(|arg| arg.to_string()).collect(),
+                e.to_string(),
+                None,
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            "git",
+            args.iter().map(|arg| arg.to_string()).collect(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            output.status.code(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(labels: &[&str], inputs: &[&str], dependencies: &[&str]) -> TaskConfig {
+        let strings = |values: &[&str]| {
+            (!values.is_empty()).then(|| values.iter().map(|v| v.to_string()).collect())
+        };
+        TaskConfig {
+            command: Some("true".to_string()),
+            labels: strings(labels),
+            inputs: strings(inputs),
+            dependencies: strings(dependencies),
+            ..TaskConfig::default()
+        }
+    }
+
+    fn tasks() -> HashMap<String, TaskConfig> {
+        HashMap::from([
+            ("unit".to_string(), task(&["test"], &["src/**/*.rs"], &[])),
+            (
+                "e2e".to_string(),
+                task(&["test", "integration"], &["e2e"], &["build"]),
+            ),
+            (
+                "flaky".to_string(),
+                task(&["test", "integration", "flaky"], &["e2e"], &[]),
+            ),
+            ("build".to_string(), task(&[], &["src/**/*.rs"], &[])),
+            ("docs".to_string(), task(&["docs"], &["docs/**"], &[])),
+        ])
+    }
+
+    fn select(selection: &TaskSelection) -> Vec<String> {
+        let tasks = tasks();
+        selection
+            .select(&tasks, tasks.keys().map(String::as_str))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_select_by_labels() {
+        let selection = TaskSelection {
+            labels: vec!["integration".to_string()],
+            exclude_labels: vec!["flaky".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(&selection), ["e2e"]);
+
+        let selection = TaskSelection {
+            labels: vec!["test".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(&selection), ["e2e", "flaky", "unit"]);
+        assert!(TaskSelection::default().is_empty());
+    }
+
+    #[test]
+    fn test_tags_count_as_labels() {
+        let tagged = TaskConfig {
+            tags: Some(vec!["slow".to_string()]),
+            ..TaskConfig::default()
+        };
+        let selection = TaskSelection {
+            labels: vec!["slow".to_string()],
+            ..Default::default()
+        };
+        assert!(selection.matches_labels(&tagged));
+    }
+
+    #[test]
+    fn test_affected_follows_inputs_and_dependencies() {
+        let selection = TaskSelection {
+            changed_files: Some(vec![PathBuf::from("src/lib.rs")]),
+            ..Default::default()
+        };
+        // e2e depends on build, whose inputs changed
+        assert_eq!(select(&selection), ["build", "e2e", "unit"]);
+
+        let selection = TaskSelection {
+            labels: vec!["integration".to_string()],
+            changed_files: Some(vec![PathBuf::from("e2e/login.spec.ts")]),
+            ..Default::default()
+        };
+        assert_eq!(select(&selection), ["e2e", "flaky"]);
+    }
+}
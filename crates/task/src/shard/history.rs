@@ -0,0 +1,67 @@
+//! Durations of tests in earlier sharded runs
+
+use super::{ShardPlan, ShardRun};
+use cuenv_core::{Error, Result};
+use cuenv_utils::atomic_file::write_atomic_string;
+use cuenv_utils::xdg::XdgPaths;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Seconds each test of each sharded task took, by task key then test
+///
+/// Only whole shards are timed, so a shard's duration is shared evenly by
+/// its tests. Each run is averaged with the previous estimate, which evens
+/// out shards that were slow for reasons unrelated to their tests.
+#[derive(Debug, Clone)]
+pub struct ShardHistory {
+    path: PathBuf,
+    durations: HashMap<String, HashMap<String, f64>>,
+}
+
+impl ShardHistory {
+    /// History in `path`; empty when the file does not exist or is unreadable
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let durations = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, durations }
+    }
+
+    /// Key of the task `task_name` run in `working_dir`
+    pub fn key(working_dir: &Path, task_name: &str) -> String {
+        format!("{}#{task_name}", working_dir.display())
+    }
+
+    /// Seconds per test of a task
+    pub fn durations(&self, key: &str) -> HashMap<String, f64> {
+        self.durations.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Fold in the successful shards of a run
+    pub fn record(&mut self, key: &str, plan: &ShardPlan, runs: &[ShardRun]) {
+        let durations = self.durations.entry(key.to_string()).or_default();
+        for run in runs.iter().filter(|run| run.success && run.tests > 0) {
+            let per_test = run.elapsed.as_secs_f64() / run.tests as f64;
+            for test in plan.tests(run.index) {
+                let estimate = durations
+                    .get(test)
+                    .map_or(per_test, |previous| (previous + per_test) / 2.0);
+                durations.insert(test.clone(), estimate);
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string(&self.durations)
+            .map_err(|e| Error::configuration(format!("Failed to serialize shard history: {e}")))?;
+        write_atomic_string(&self.path, &content)
+    }
+}
+
+impl Default for ShardHistory {
+    fn default() -> Self {
+        Self::load(XdgPaths::cache_dir().join("shard-history.json"))
+    }
+}
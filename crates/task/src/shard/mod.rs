@@ -0,0 +1,173 @@
+//! Splitting a task's tests across parallel shards
+//!
+//! A task marked `shardable` runs as `shards` nodes of the DAG,
+//! `<task>:@shard1` to `<task>:@shardN`, each running the task's command with
+//! a share of the tests printed by its `testList` command appended. Tests are
+//! dealt out longest first, each to the shard with the least work so far,
+//! using how long they took in earlier runs; tests without a history count
+//! as the average of those with one. The task's own node waits for every
+//! shard, reports them together and records how long they took.
+
+mod history;
+
+#[cfg(test)]
+mod tests;
+
+pub use history::ShardHistory;
+
+use cuenv_core::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Name of the synthetic nodes running a share of a task's tests
+pub const SHARD_NODE: &str = "@shard";
+
+/// Duration assumed for every test when none has a history
+const DEFAULT_TEST_SECS: f64 = 1.0;
+
+/// Role of a DAG node in a sharded task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardNode {
+    /// Runs the `index`th (from 1) of `count` shares of `task`'s tests
+    Shard {
+        task: String,
+        index: usize,
+        count: usize,
+    },
+    /// The sharded task's own node, which reports once every shard is done
+    Merge,
+}
+
+/// ID of the node running the `index`th shard of `task`
+pub fn shard_node_id(task: &str, index: usize) -> String {
+    format!("{task}:{SHARD_NODE}{index}")
+}
+
+/// Number of shards of a task that does not set `shards`
+pub fn default_shard_count() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Tests of each shard of a task
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardPlan {
+    shards: Vec<Vec<String>>,
+}
+
+impl ShardPlan {
+    /// Deal `tests` out to `count` shards so that their expected durations,
+    /// by `durations` in seconds, are as even as possible
+    pub fn new(tests: &[String], count: usize, durations: &HashMap<String, f64>) -> Self {
+        let known: Vec<f64> = tests
+            .iter()
+            .filter_map(|test| durations.get(test).copied())
+            .collect();
+        let fallback = if known.is_empty() {
+            DEFAULT_TEST_SECS
+        } else {
+            known.iter().sum::<f64>() / known.len() as f64
+        };
+
+        let mut weighted: Vec<(&String, f64)> = tests
+            .iter()
+            .map(|test| (test, durations.get(test).copied().unwrap_or(fallback)))
+            .collect();
+        // Longest first; ties in name order so plans are reproducible
+        weighted.sort_by(|(a, a_secs), (b, b_secs)| b_secs.total_cmp(a_secs).then(a.cmp(b)));
+
+        let count = count.max(1);
+        let mut shards = vec![Vec::new(); count];
+        let mut loads = vec![0.0_f64; count];
+        for (test, secs) in weighted {
+            let lightest = (0..count)
+                .min_by(|&a, &b| loads[a].total_cmp(&loads[b]))
+                .unwrap_or(0);
+            shards[lightest].push(test.clone());
+            loads[lightest] += secs;
+        }
+        Self { shards }
+    }
+
+    /// Tests of the `index`th shard, counting from 1
+    pub fn tests(&self, index: usize) -> &[String] {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.shards.get(i))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn test_count(&self) -> usize {
+        self.shards.iter().map(Vec::len).sum()
+    }
+}
+
+/// Run a task's `testList` command in `dir`, returning one test per
+/// non-empty output line
+pub fn list_tests(command: &str, shell: &str, dir: &Path) -> Result<Vec<String>> {
+    let output = Command::new(shell)
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| {
+            Error::command_execution(
+                shell,
+                vec!["-c".to_string(), command.to_string()],
+                e.to_string(),
+                None,
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::command_execution(
+            shell,
+            vec!["-c".to_string(), command.to_string()],
+            format!(
+                "Listing tests failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            output.status.code(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|test| !test.is_empty() && seen.insert(test.to_string()))
+        .map(str::to_string)
+        .collect())
+}
+
+/// How one shard went
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardRun {
+    pub index: usize,
+    pub tests: usize,
+    pub elapsed: Duration,
+    pub success: bool,
+}
+
+/// One-line report of a sharded task's shards
+pub fn summarize(runs: &[ShardRun], count: usize) -> String {
+    let tests: usize = runs.iter().map(|run| run.tests).sum();
+    let failed: Vec<String> = runs
+        .iter()
+        .filter(|run| !run.success)
+        .map(|run| format!("{SHARD_NODE}{}", run.index))
+        .collect();
+    let slowest = runs.iter().map(|run| run.elapsed).max().unwrap_or_default();
+    let fastest = runs.iter().map(|run| run.elapsed).min().unwrap_or_default();
+    let outcome = if failed.is_empty() {
+        "all passed".to_string()
+    } else {
+        format!("failed: {}", failed.join(", "))
+    };
+    format!(
+        "{tests} tests in {} of {count} shards, {outcome} (shards took {:.1}s to {:.1}s)",
+        runs.len(),
+        fastest.as_secs_f64(),
+        slowest.as_secs_f64()
+    )
+}
@@ -0,0 +1,104 @@
+use super::*;
+use tempfile::TempDir;
+
+fn names(tests: &[&str]) -> Vec<String> {
+    tests.iter().map(|test| test.to_string()).collect()
+}
+
+#[test]
+fn test_plan_balances_by_duration() {
+    let tests = names(&["slow", "medium", "fast_a", "fast_b", "new"]);
+    let durations = HashMap::from([
+        ("slow".to_string(), 10.0),
+        ("medium".to_string(), 6.0),
+        ("fast_a".to_string(), 2.0),
+        ("fast_b".to_string(), 2.0),
+    ]);
+
+    // "new" counts as the 5s average of the known tests
+    let plan = ShardPlan::new(&tests, 2, &durations);
+    assert_eq!(plan.tests(1), names(&["slow", "fast_a"]));
+    assert_eq!(plan.tests(2), names(&["medium", "new", "fast_b"]));
+    assert_eq!(plan.test_count(), 5);
+    assert!(plan.tests(3).is_empty());
+
+    // Without any history, tests are dealt out evenly
+    let plan = ShardPlan::new(&tests, 3, &HashMap::new());
+    assert_eq!(plan.tests(1).len(), 2);
+    assert_eq!(plan.tests(2).len(), 2);
+    assert_eq!(plan.tests(3).len(), 1);
+}
+
+#[test]
+fn test_history_records_successful_shards() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("shard-history.json");
+    let key = ShardHistory::key(temp_dir.path(), "test");
+    let plan = ShardPlan::new(&names(&["a", "b", "c"]), 2, &HashMap::new());
+    let runs = [
+        ShardRun {
+            index: 1,
+            tests: 2,
+            elapsed: Duration::from_secs(8),
+            success: true,
+        },
+        ShardRun {
+            index: 2,
+            tests: 1,
+            elapsed: Duration::from_secs(30),
+            success: false,
+        },
+    ];
+
+    let mut history = ShardHistory::load(&path);
+    history.record(&key, &plan, &runs);
+    history.save().unwrap();
+
+    let mut history = ShardHistory::load(&path);
+    assert_eq!(
+        history.durations(&key),
+        HashMap::from([("a".to_string(), 4.0), ("c".to_string(), 4.0)])
+    );
+
+    // Later runs are averaged with what was recorded
+    history.record(
+        &key,
+        &plan,
+        &[ShardRun {
+            elapsed: Duration::from_secs(4),
+            ..runs[0].clone()
+        }],
+    );
+    assert_eq!(history.durations(&key).get("a"), Some(&3.0));
+    assert!(history.durations("elsewhere#test").is_empty());
+}
+
+#[test]
+fn test_list_tests_and_summary() {
+    let temp_dir = TempDir::new().unwrap();
+    let tests = list_tests("printf 'b\\n\\n a \\nb\\n'", "sh", temp_dir.path()).unwrap();
+    assert_eq!(tests, names(&["b", "a"]));
+
+    let err = list_tests("exit 3", "sh", temp_dir.path()).unwrap_err();
+    assert!(err.to_string().contains("Listing tests failed"));
+
+    let runs = [
+        ShardRun {
+            index: 1,
+            tests: 3,
+            elapsed: Duration::from_millis(1500),
+            success: true,
+        },
+        ShardRun {
+            index: 3,
+            tests: 2,
+            elapsed: Duration::from_secs(2),
+            success: false,
+        },
+    ];
+    assert_eq!(
+        summarize(&runs, 3),
+        "5 tests in 2 of 3 shards, failed: @shard3 (shards took 1.5s to 2.0s)"
+    );
+    assert_eq!(shard_node_id("ci:test", 2), "ci:test:@shard2");
+}
@@ -0,0 +1,193 @@
+//! Environment variables across the packages of a monorepo run
+//!
+//! A cross-package run loads every package its tasks belong to. Each of them
+//! may define the same variable, and when the values differ it depends on
+//! the package which one a task sees. [`WorkspaceEnv`] keeps every
+//! definition together with the package it came from, so such conflicts can
+//! be reported before anything runs.
+
+use crate::MonorepoTaskRegistry;
+use cuenv_core::{Error, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// A package's definition of a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableOrigin {
+    pub package: String,
+    pub value: String,
+}
+
+/// Variables of several packages, merged with their origins
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceEnv {
+    variables: BTreeMap<String, Vec<VariableOrigin>>,
+}
+
+impl WorkspaceEnv {
+    /// Add the variables `package` defines
+    ///
+    /// Packages added earlier take precedence in [`merged`](Self::merged).
+    pub fn add_package(&mut self, package: &str, variables: &HashMap<String, String>) {
+        for (name, value) in variables {
+            self.variables
+                .entry(name.clone())
+                .or_default()
+                .push(VariableOrigin {
+                    package: package.to_string(),
+                    value: value.clone(),
+                });
+        }
+    }
+
+    /// Every definition of `name`, in the order the packages were added
+    pub fn origins(&self, name: &str) -> &[VariableOrigin] {
+        self.variables.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Value of each variable with the package it comes from
+    pub fn merged(&self) -> BTreeMap<&str, &VariableOrigin> {
+        self.variables
+            .iter()
+            .filter_map(|(name, origins)| Some((name.as_str(), origins.first()?)))
+            .collect()
+    }
+
+    /// Variables defined with different values by different packages
+    pub fn conflicts(&self) -> Vec<EnvConflict<'_>> {
+        self.variables
+            .iter()
+            .filter(|(_, origins)| {
+                origins
+                    .iter()
+                    .any(|origin| origin.value != origins[0].value)
+            })
+            .map(|(name, origins)| EnvConflict { name, origins })
+            .collect()
+    }
+}
+
+/// A variable packages disagree on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvConflict<'a> {
+    pub name: &'a str,
+    pub origins: &'a [VariableOrigin],
+}
+
+impl fmt::Display for EnvConflict<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for origin in self.origins {
+            write!(f, "\n  {} = {:?}", origin.package, origin.value)?;
+        }
+        Ok(())
+    }
+}
+
+impl MonorepoTaskRegistry {
+    /// Variables of the packages `task_ref` and its dependencies belong to
+    ///
+    /// The package of `task_ref` comes first, followed by those of its
+    /// dependencies in the order they are reached.
+    pub fn workspace_env(&self, task_ref: &str) -> Result<WorkspaceEnv> {
+        let mut env = WorkspaceEnv::default();
+        let mut seen_packages = HashSet::new();
+        let mut seen_tasks = HashSet::new();
+        let mut pending = vec![task_ref.to_string()];
+
+        while let Some(name) = pending.pop() {
+            if !seen_tasks.insert(name.clone()) {
+                continue;
+            }
+            let task = self
+                .get_task(&name)
+                .ok_or_else(|| Error::configuration(format!("Task '{name}' not found")))?;
+
+            if seen_packages.insert(task.package_name.as_str()) {
+                if let Some(variables) = self.get_package_variables(&task.package_name) {
+                    env.add_package(&task.package_name, variables);
+                }
+            }
+
+            let dependencies = task.config.dependencies.iter().flatten();
+            pending.extend(dependencies.rev().map(|dep| {
+                if dep.contains(':') {
+                    dep.clone()
+                } else {
+                    format!("{}:{dep}", task.package_name)
+                }
+            }));
+        }
+
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiscoveredPackage, ParseResult};
+    use cuenv_config::TaskConfig;
+    use std::path::PathBuf;
+
+    fn package(
+        name: &str,
+        tasks: &[(&str, &[&str])],
+        variables: &[(&str, &str)],
+    ) -> DiscoveredPackage {
+        DiscoveredPackage {
+            name: name.to_string(),
+            path: PathBuf::from(format!("/repo/{name}")),
+            parse_result: Some(ParseResult {
+                tasks: tasks
+                    .iter()
+                    .map(|(task, deps)| {
+                        let config = TaskConfig {
+                            command: Some("true".to_string()),
+                            dependencies: Some(deps.iter().map(|d| d.to_string()).collect()),
+                            ..Default::default()
+                        };
+                        (task.to_string(), config)
+                    })
+                    .collect(),
+                variables: variables
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_conflicts_carry_package_origins() {
+        let registry = MonorepoTaskRegistry::from_packages(vec![
+            package(
+                "web",
+                &[("deploy", &["build", "api:build"]), ("build", &[])],
+                &[("REGION", "eu"), ("PORT", "3000")],
+            ),
+            package(
+                "api",
+                &[("build", &[])],
+                &[("REGION", "us"), ("PORT", "3000")],
+            ),
+            package("docs", &[("build", &[])], &[("REGION", "ap")]),
+        ])
+        .unwrap();
+
+        let env = registry.workspace_env("web:deploy").unwrap();
+        let conflicts = env.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].name, "REGION");
+        assert_eq!(
+            conflicts[0].to_string(),
+            "REGION\n  web = \"eu\"\n  api = \"us\""
+        );
+        assert_eq!(env.merged()["REGION"].package, "web");
+        assert_eq!(env.origins("PORT").len(), 2);
+
+        let env = registry.workspace_env("api:build").unwrap();
+        assert!(env.conflicts().is_empty());
+        assert!(registry.workspace_env("web:missing").is_err());
+    }
+}
@@ -3,9 +3,12 @@ use super::focus::FocusedPane;
 use super::input::InputHandler;
 use super::render::Renderer;
 use crate::{
-    components::{EnvPane, FocusPane, MiniMap},
+    components::{DependencyInspector, EnvPane, FocusPane, MiniMap, TracingPane},
     event_bus::{EventBus, EventSubscriber},
+    keymap::Keymap,
+    settings::TuiSettings,
     terminal::{InputEvent, TerminalManager},
+    theme::Theme,
 };
 use cuenv_task::executor::TaskExecutor;
 use std::collections::HashMap;
@@ -16,25 +19,42 @@ pub struct TuiApp {
     pub(super) minimap: MiniMap,
     pub(super) focus_pane: FocusPane,
     pub(super) env_pane: EnvPane,
+    pub(super) tracing_pane: TracingPane,
+    /// Show the timeline instead of the environment pane
+    pub(super) show_timeline: bool,
     pub(super) event_subscriber: EventSubscriber,
     pub(super) running: bool,
     pub(super) focused_pane: FocusedPane,
     pub(super) task_executor: TaskExecutor,
+    pub(super) theme: Theme,
+    pub(super) keymap: Keymap,
+    /// Overlay open on the dependencies of a task, taking the keys
+    pub(super) dependency_inspector: Option<DependencyInspector>,
 }
 
 impl TuiApp {
+    /// TUI with the theme and key bindings of the global config file
     pub async fn new(
         event_bus: EventBus,
         task_executor: TaskExecutor,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::with_settings(event_bus, task_executor, TuiSettings::load()?).await
+    }
+
+    pub async fn with_settings(
+        event_bus: EventBus,
+        task_executor: TaskExecutor,
+        settings: TuiSettings,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let terminal = TerminalManager::new()?;
+        let TuiSettings { theme, keymap } = settings;
 
         let registry = event_bus.registry().clone();
-        let minimap = MiniMap::new(registry.clone());
-        let focus_pane = FocusPane::new(registry);
+        let minimap = MiniMap::new(registry.clone()).with_theme(theme);
+        let focus_pane = FocusPane::new(registry).with_theme(theme);
 
         // Start with no environment variables - will be updated when a task is selected
-        let env_pane = EnvPane::new(HashMap::new());
+        let env_pane = EnvPane::new(HashMap::new()).with_theme(theme);
         let event_subscriber = event_bus.subscribe();
 
         Ok(Self {
@@ -42,10 +62,15 @@ impl TuiApp {
             minimap,
             focus_pane,
             env_pane,
+            tracing_pane: TracingPane::new(cuenv_core::events::global_timeline()).with_theme(theme),
+            show_timeline: false,
             event_subscriber,
             running: true,
             focused_pane: FocusedPane::MiniMap,
             task_executor,
+            theme,
+            keymap,
+            dependency_inspector: None,
         })
     }
 
@@ -82,6 +107,9 @@ impl TuiApp {
                     if self.focus_pane.needs_task_info_update() {
                         self.focus_pane.update_task_info().await;
                         self.render()?;
+                    } else if self.show_timeline {
+                        // Running spans grow even without new events
+                        self.render()?;
                     }
                 }
 
@@ -89,7 +117,7 @@ impl TuiApp {
                 Some(input) = self.terminal.next_event() => {
                     match input {
                         InputEvent::Key(key) => {
-                            if TerminalManager::should_quit(&key) {
+                            if TerminalManager::is_interrupt(&key) {
                                 self.running = false;
                             } else {
                                 self.handle_key_event(key).await;
@@ -119,6 +147,6 @@ impl TuiApp {
 
     pub(super) fn update_env_pane_for_task(&mut self, task_name: &str) {
         let filtered_vars = self.task_executor.get_task_env_vars(task_name);
-        self.env_pane = EnvPane::new(filtered_vars);
+        self.env_pane = EnvPane::new(filtered_vars).with_theme(self.theme);
     }
 }
@@ -21,7 +21,8 @@ impl EventHandler for TuiApp {
             }
             TaskEvent::Completed { .. }
             | TaskEvent::Failed { .. }
-            | TaskEvent::Cancelled { .. } => {
+            | TaskEvent::Cancelled { .. }
+            | TaskEvent::Publishing { .. } => {
                 // Rebuild tree when task states change
                 self.minimap.build_tree_lines().await;
 
@@ -39,7 +40,8 @@ impl EventHandler for TuiApp {
                         | TaskEvent::Progress { task_name, .. }
                         | TaskEvent::Completed { task_name, .. }
                         | TaskEvent::Failed { task_name, .. }
-                        | TaskEvent::Cancelled { task_name } => {
+                        | TaskEvent::Cancelled { task_name }
+                        | TaskEvent::Publishing { task_name } => {
                             if current_task == task_name {
                                 self.focus_pane.update_task_info().await;
                             }
@@ -51,6 +53,11 @@ impl EventHandler for TuiApp {
             _ => {}
         }
 
+        if let Some(inspector) = self.dependency_inspector.as_mut() {
+            let tasks = self.minimap.task_registry.get_all_tasks().await;
+            inspector.refresh(&tasks);
+        }
+
         // Keep focus pane synced to currently selected node
         if let Some(selected) = self.minimap.get_selected_task() {
             let selected_clone = selected.clone();
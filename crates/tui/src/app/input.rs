@@ -1,5 +1,8 @@
 use super::core::TuiApp;
 use super::focus::FocusedPane;
+use crate::components::DependencyInspector;
+use crate::events::LogStream;
+use crate::keymap::Action;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 pub trait InputHandler {
@@ -8,123 +11,191 @@ pub trait InputHandler {
 
 impl InputHandler for TuiApp {
     async fn handle_key_event(&mut self, key: KeyEvent) {
-        match key.code {
+        self.focus_pane.clear_status();
+        if self.focus_pane.is_searching() {
+            self.handle_search_key(key);
+            return;
+        }
+
+        if self.dependency_inspector.is_some() {
+            self.handle_inspector_key(key).await;
+            return;
+        }
+
+        let Some(action) = self.keymap.action(&key) else {
+            return;
+        };
+
+        match action {
+            Action::Quit => {
+                self.running = false;
+            }
+
             // Pane switching
-            KeyCode::Tab => {
+            Action::NextPane => {
                 self.focused_pane = self.focused_pane.next();
             }
 
             // Navigation
-            KeyCode::Up | KeyCode::Char('k') => match self.focused_pane {
+            Action::Up => match self.focused_pane {
                 FocusedPane::MiniMap => {
                     self.minimap.select_previous();
-                    if let Some(task) = self.minimap.get_selected_task() {
-                        let task_clone = task.clone();
-                        self.focus_pane.set_task(task_clone.clone());
-                        self.update_env_pane_for_task(&task_clone);
-                    }
+                    self.sync_selected_task();
                 }
                 FocusedPane::TaskDetails => {
                     self.focus_pane.scroll_up(1);
                 }
+                FocusedPane::Environment if self.show_timeline => {
+                    self.tracing_pane.scroll_up(1);
+                }
                 FocusedPane::Environment => {
                     self.env_pane.select_previous();
                 }
             },
-            KeyCode::Down | KeyCode::Char('j') => match self.focused_pane {
+            Action::Down => match self.focused_pane {
                 FocusedPane::MiniMap => {
                     self.minimap.select_next();
-                    if let Some(task) = self.minimap.get_selected_task() {
-                        let task_clone = task.clone();
-                        self.focus_pane.set_task(task_clone.clone());
-                        self.update_env_pane_for_task(&task_clone);
-                    }
+                    self.sync_selected_task();
                 }
                 FocusedPane::TaskDetails => {
                     self.focus_pane.scroll_down(1);
                 }
+                FocusedPane::Environment if self.show_timeline => {
+                    self.tracing_pane.scroll_down(1);
+                }
                 FocusedPane::Environment => {
                     self.env_pane.select_next();
                 }
             },
 
             // Tree expansion
-            KeyCode::Left | KeyCode::Char('h') => {
-                self.minimap.toggle_expand();
-                self.minimap.build_tree_lines().await;
-            }
-            KeyCode::Right | KeyCode::Char('l') => {
-                self.minimap.toggle_expand();
-                self.minimap.build_tree_lines().await;
-            }
-            KeyCode::Enter | KeyCode::Char(' ') => {
+            Action::Toggle => {
                 self.minimap.toggle_expand();
                 self.minimap.build_tree_lines().await;
             }
 
             // Scrolling
-            KeyCode::PageUp => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    self.focus_pane.scroll_up(10);
-                } else {
-                    self.minimap.scroll_up(10);
-                }
-            }
-            KeyCode::PageDown => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    self.focus_pane.scroll_down(10);
-                } else {
-                    self.minimap.scroll_down(10);
-                }
-            }
+            Action::PageUp => self.minimap.scroll_up(10),
+            Action::PageDown => self.minimap.scroll_down(10),
+            Action::LogsPageUp => self.focus_pane.scroll_up(10),
+            Action::LogsPageDown => self.focus_pane.scroll_down(10),
 
             // Jump commands (PRD: g/G operate on mini-map selection)
-            KeyCode::Char('g') => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    // Shift+g behaves like 'G'
-                    self.minimap.jump_to_bottom();
-                } else {
-                    self.minimap.jump_to_top();
-                }
-                if let Some(task) = self.minimap.get_selected_task() {
-                    let task_clone = task.clone();
-                    self.focus_pane.set_task(task_clone.clone());
-                    self.update_env_pane_for_task(&task_clone);
-                }
+            Action::Top => {
+                self.minimap.jump_to_top();
+                self.sync_selected_task();
             }
-            KeyCode::Char('G') => {
+            Action::Bottom => {
                 self.minimap.jump_to_bottom();
-                if let Some(task) = self.minimap.get_selected_task() {
-                    let task_clone = task.clone();
-                    self.focus_pane.set_task(task_clone.clone());
-                    self.update_env_pane_for_task(&task_clone);
-                }
+                self.sync_selected_task();
             }
-            KeyCode::Char('E') => {
+            Action::FirstError => {
                 self.minimap.jump_to_first_error();
-                if let Some(task) = self.minimap.get_selected_task() {
-                    let task_clone = task.clone();
-                    self.focus_pane.set_task(task_clone.clone());
-                    self.update_env_pane_for_task(&task_clone);
-                }
+                self.sync_selected_task();
             }
 
             // Tree manipulation
-            KeyCode::Char('*') => {
+            Action::ExpandAll => {
                 self.minimap.expand_all();
                 self.minimap.build_tree_lines().await;
             }
-            KeyCode::Char('/') => {
+            Action::CollapseAll => {
                 self.minimap.collapse_all();
                 self.minimap.build_tree_lines().await;
             }
 
             // Focus pane controls
-            KeyCode::Char('a') => {
+            Action::AutoScroll => {
                 self.focus_pane.toggle_auto_scroll();
             }
 
+            // Swap the environment pane for the hook/env/task timeline
+            Action::Timeline => {
+                self.show_timeline = !self.show_timeline;
+            }
+
+            // Log search and filters
+            Action::Search => self.focus_pane.log_view_mut().start_search(),
+            Action::NextMatch => self.focus_pane.jump_to_match(true),
+            Action::PreviousMatch => self.focus_pane.jump_to_match(false),
+            Action::ToggleStdout => self
+                .focus_pane
+                .log_view_mut()
+                .toggle_stream(LogStream::Stdout),
+            Action::ToggleStderr => self
+                .focus_pane
+                .log_view_mut()
+                .toggle_stream(LogStream::Stderr),
+            Action::ToggleSystem => self
+                .focus_pane
+                .log_view_mut()
+                .toggle_stream(LogStream::System),
+            Action::CycleLevel => self.focus_pane.log_view_mut().cycle_level(),
+            Action::Pin => self.focus_pane.toggle_pin(),
+            Action::SaveLogs => self.focus_pane.save_logs(),
+            Action::Dependencies => {
+                if let Some(task) = self.minimap.get_selected_task().cloned() {
+                    let tasks = self.minimap.task_registry.get_all_tasks().await;
+                    self.dependency_inspector =
+                        Some(DependencyInspector::new(&task, &tasks).with_theme(self.theme));
+                }
+            }
+        }
+    }
+}
+
+impl TuiApp {
+    /// Edit the search query while it is typed; keys are text, not actions
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let view = self.focus_pane.log_view_mut();
+        match key.code {
+            KeyCode::Enter => {
+                view.finish_search();
+                if view.has_query() {
+                    self.focus_pane.jump_to_match(true);
+                }
+            }
+            KeyCode::Esc => view.cancel_search(),
+            KeyCode::Backspace => view.pop_char(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => view.push_char(c),
+            _ => {}
+        }
+    }
+
+    /// Keys while the dependency inspector is open; everything else is
+    /// swallowed so the panes behind it do not move
+    async fn handle_inspector_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Esc {
+            self.dependency_inspector = None;
+            return;
+        }
+        let (Some(inspector), Some(action)) =
+            (self.dependency_inspector.as_mut(), self.keymap.action(&key))
+        else {
+            return;
+        };
+        match action {
+            Action::Up => inspector.select_previous(),
+            Action::Down => inspector.select_next(),
+            Action::Toggle => {
+                if let Some(task) = inspector.selected_task().map(str::to_string) {
+                    self.dependency_inspector = None;
+                    self.focused_pane = FocusedPane::MiniMap;
+                    self.minimap.reveal_task(&task).await;
+                    self.sync_selected_task();
+                }
+            }
+            Action::Quit | Action::Dependencies => self.dependency_inspector = None,
             _ => {}
         }
     }
+
+    /// Show the task selected in the mini-map in the other panes
+    fn sync_selected_task(&mut self) {
+        if let Some(task) = self.minimap.get_selected_task() {
+            let task_clone = task.clone();
+            self.focus_pane.set_task(task_clone.clone());
+            self.update_env_pane_for_task(&task_clone);
+        }
+    }
 }
@@ -1,9 +1,11 @@
 use super::core::TuiApp;
 use super::focus::FocusedPane;
-use crate::components::{EnvPane, FocusPane, MiniMap};
+use crate::components::{EnvPane, FocusPane, MiniMap, TracingPane};
+use crate::keymap::{Action, Keymap};
+use crate::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, Borders},
     Frame,
 };
@@ -16,22 +18,40 @@ impl Renderer for TuiApp {
     fn render(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let minimap = &mut self.minimap;
         let focus_pane = &mut self.focus_pane;
-        let env_pane = &mut self.env_pane;
+        let bottom_pane = if self.show_timeline {
+            BottomPane::Timeline(&mut self.tracing_pane)
+        } else {
+            BottomPane::Environment(&mut self.env_pane)
+        };
         let focused = self.focused_pane;
+        let help_text = help_text(&self.keymap);
+        let theme = self.theme;
+        let inspector = self.dependency_inspector.as_ref();
 
         self.terminal.terminal().draw(|f| {
-            draw_ui(f, minimap, focus_pane, env_pane, focused);
+            draw_ui(f, minimap, focus_pane, bottom_pane, focused, &theme);
+            draw_help_bar(f, &help_text, &theme);
+            if let Some(inspector) = inspector {
+                inspector.render(f, f.area());
+            }
         })?;
         Ok(())
     }
 }
 
+/// Pane shown below the task details
+enum BottomPane<'a> {
+    Environment(&'a mut EnvPane),
+    Timeline(&'a mut TracingPane),
+}
+
 fn draw_ui(
     frame: &mut Frame<'_>,
     minimap: &mut MiniMap,
     focus_pane: &mut FocusPane,
-    env_pane: &mut EnvPane,
+    bottom_pane: BottomPane<'_>,
     focused: FocusedPane,
+    theme: &Theme,
 ) {
     // Main layout: split screen horizontally
     let chunks = Layout::default()
@@ -43,14 +63,9 @@ fn draw_ui(
         .split(frame.area());
 
     // Draw mini-map with border highlight if focused
-    let minimap_border_style = if focused == FocusedPane::MiniMap {
-        Style::default().fg(Color::Cyan)
-    } else {
-        Style::default().fg(Color::DarkGray)
-    };
     let minimap_block = Block::default()
         .borders(Borders::RIGHT)
-        .border_style(minimap_border_style);
+        .border_style(theme.border_style(focused == FocusedPane::MiniMap));
     let minimap_area = minimap_block.inner(chunks[0]);
     frame.render_widget(minimap_block, chunks[0]);
     minimap.render(frame, minimap_area);
@@ -67,18 +82,47 @@ fn draw_ui(
     // Draw focus pane
     focus_pane.render(frame, right_chunks[0]);
 
-    // Draw environment pane with border highlight if focused
-    env_pane.render(frame, right_chunks[1]);
+    // Draw environment pane or timeline
+    match bottom_pane {
+        BottomPane::Environment(env_pane) => env_pane.render(frame, right_chunks[1]),
+        BottomPane::Timeline(tracing_pane) => tracing_pane.render(frame, right_chunks[1]),
+    }
+}
+
+/// Actions listed in the help bar, with the keys currently bound to them
+const HELP_ENTRIES: [(&[Action], &str); 11] = [
+    (&[Action::NextPane], "Switch Pane"),
+    (&[Action::Up, Action::Down], "Navigate"),
+    (&[Action::Toggle], "Expand"),
+    (&[Action::FirstError], "First Error"),
+    (&[Action::Top, Action::Bottom], "Top/Bottom"),
+    (&[Action::Search], "Search"),
+    (&[Action::AutoScroll], "Follow"),
+    (&[Action::Pin], "Pin"),
+    (&[Action::Dependencies], "Deps"),
+    (&[Action::Timeline], "Timeline"),
+    (&[Action::Quit], "Quit"),
+];
 
-    // Draw help bar at the bottom
-    draw_help_bar(frame);
+fn help_text(keymap: &Keymap) -> String {
+    let entries: Vec<String> = HELP_ENTRIES
+        .iter()
+        .filter_map(|(actions, label)| {
+            let keys: Vec<String> = actions
+                .iter()
+                .filter_map(|action| keymap.primary_key(*action))
+                .map(|key| key.to_string())
+                .collect();
+            (!keys.is_empty()).then(|| format!("{}: {label}", keys.join("/")))
+        })
+        .collect();
+    format!(" {} ", entries.join(" │ "))
 }
 
-fn draw_help_bar(frame: &mut Frame<'_>) {
-    let help_text = " Tab: Switch Pane │ ↑↓/jk: Navigate │ ←→/hl/Space: Expand │ E: First Error │ g/G: Top/Bottom │ a: Auto-scroll │ q: Quit ";
+fn draw_help_bar(frame: &mut Frame<'_>, help_text: &str, theme: &Theme) {
     let help_bar = Block::default()
         .title(help_text)
-        .title_style(Style::default().fg(Color::DarkGray))
+        .title_style(Style::default().fg(theme.muted))
         .borders(Borders::TOP);
 
     let help_area = Layout::default()
@@ -88,3 +132,24 @@ fn draw_help_bar(frame: &mut Frame<'_>) {
 
     frame.render_widget(help_bar, help_area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_help_text_follows_keymap() {
+        assert_eq!(
+            help_text(&Keymap::default()),
+            " Tab: Switch Pane │ ↑/↓: Navigate │ Space: Expand │ E: First Error │ g/G: Top/Bottom │ /: Search │ a: Follow │ p: Pin │ d: Deps │ t: Timeline │ q: Quit "
+        );
+
+        let overrides = HashMap::from([
+            ("quit".to_string(), vec!["esc".to_string()]),
+            ("timeline".to_string(), vec![]),
+        ]);
+        let keymap = Keymap::default().with_overrides(&overrides).unwrap();
+        assert!(help_text(&keymap).ends_with(" p: Pin │ d: Deps │ Esc: Quit "));
+    }
+}
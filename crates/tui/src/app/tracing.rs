@@ -30,7 +30,11 @@ pub fn tracing_to_tui_event(
                 .unwrap_or_else(|| "Unknown error".to_string()),
             duration_ms: visitor.duration_ms.unwrap_or(0),
         }),
-        _ => None,
+        _ => Some(TaskEvent::Trace {
+            task_name: visitor.task_name?,
+            level: *metadata.level(),
+            message: visitor.message?,
+        }),
     }
 }
 
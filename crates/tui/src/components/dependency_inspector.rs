@@ -0,0 +1,313 @@
+//! Overlay listing what a task depends on and what depends on it
+//!
+//! Opened from the task tree, it lists the direct and transitive
+//! dependencies and dependents of the selected task with their states and
+//! durations. Dependents are derived from the dependencies of every task, so
+//! they do not depend on the order in which tasks were registered.
+
+use crate::events::{TaskInfo, TaskState};
+use crate::theme::Theme;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::time::Duration;
+
+/// How a listed task relates to the inspected one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Dependency,
+    TransitiveDependency,
+    Dependent,
+    TransitiveDependent,
+}
+
+impl Relation {
+    fn is_dependency(self) -> bool {
+        matches!(self, Self::Dependency | Self::TransitiveDependency)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Dependency | Self::Dependent => "direct",
+            Self::TransitiveDependency | Self::TransitiveDependent => "transitive",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DependencyEntry {
+    pub name: String,
+    pub relation: Relation,
+    pub state: TaskState,
+    pub duration: Option<Duration>,
+}
+
+pub struct DependencyInspector {
+    task: String,
+    entries: Vec<DependencyEntry>,
+    selected: usize,
+    theme: Theme,
+}
+
+impl DependencyInspector {
+    pub fn new(task: &str, tasks: &HashMap<String, TaskInfo>) -> Self {
+        let mut inspector = Self {
+            task: task.to_string(),
+            entries: Vec::new(),
+            selected: 0,
+            theme: Theme::default(),
+        };
+        inspector.refresh(tasks);
+        inspector
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+
+    pub fn entries(&self) -> &[DependencyEntry] {
+        &self.entries
+    }
+
+    /// Recompute the entries and their states, keeping the selection
+    pub fn refresh(&mut self, tasks: &HashMap<String, TaskInfo>) {
+        let selected = self.selected_task().map(str::to_string);
+        let dependencies = |name: &str| {
+            tasks
+                .get(name)
+                .map(|task| task.dependencies.clone())
+                .unwrap_or_default()
+        };
+        let dependents = |name: &str| {
+            tasks
+                .values()
+                .filter(|task| task.dependencies.iter().any(|dep| dep == name))
+                .map(|task| task.name.clone())
+                .collect()
+        };
+
+        let entry = |name: String, relation| {
+            let info = tasks.get(&name);
+            DependencyEntry {
+                state: info.map_or(TaskState::Queued, |task| task.state.clone()),
+                duration: info.and_then(TaskInfo::duration),
+                name,
+                relation,
+            }
+        };
+        let (direct, transitive) = closure(&self.task, dependencies);
+        let (direct_dependents, transitive_dependents) = closure(&self.task, dependents);
+        self.entries = direct
+            .into_iter()
+            .map(|name| entry(name, Relation::Dependency))
+            .chain(
+                transitive
+                    .into_iter()
+                    .map(|name| entry(name, Relation::TransitiveDependency)),
+            )
+            .chain(
+                direct_dependents
+                    .into_iter()
+                    .map(|name| entry(name, Relation::Dependent)),
+            )
+            .chain(
+                transitive_dependents
+                    .into_iter()
+                    .map(|name| entry(name, Relation::TransitiveDependent)),
+            )
+            .collect();
+
+        self.selected = selected
+            .and_then(|name| self.entries.iter().position(|entry| entry.name == name))
+            .unwrap_or(0);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_task(&self) -> Option<&str> {
+        self.entries
+            .get(self.selected)
+            .map(|entry| entry.name.as_str())
+    }
+
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect) {
+        let area = centered(area, 70, 70);
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(" Dependencies of {} ", self.task))
+            .title_bottom(" ↑/↓: Select │ Enter: Jump │ Esc: Close ")
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_style(true));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.entries.is_empty() {
+            let empty = Paragraph::new("No dependencies or dependents")
+                .style(Style::default().fg(self.theme.muted));
+            frame.render_widget(empty, inner);
+            return;
+        }
+
+        let starts_group = |idx: usize| {
+            idx == 0
+                || self.entries[idx - 1].relation.is_dependency()
+                    != self.entries[idx].relation.is_dependency()
+        };
+        let rows = self.entries.iter().enumerate().flat_map(|(idx, entry)| {
+            let is_dependency = entry.relation.is_dependency();
+            let heading = starts_group(idx).then(|| {
+                let title = if is_dependency {
+                    "Depends on"
+                } else {
+                    "Needed by"
+                };
+                Row::new(vec![Cell::from(Line::from(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(self.theme.muted)
+                        .add_modifier(Modifier::BOLD),
+                )))])
+            });
+            heading
+                .into_iter()
+                .chain(std::iter::once(self.row(idx, entry)))
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(11),
+                Constraint::Length(10),
+                Constraint::Length(9),
+            ],
+        );
+        // Headings are rows too, so the table scrolls to the selection
+        let headings = (0..=self.selected).filter(|idx| starts_group(*idx)).count();
+        let mut state = TableState::default().with_selected(Some(self.selected + headings));
+        frame.render_stateful_widget(table, inner, &mut state);
+    }
+
+    fn row(&self, idx: usize, entry: &DependencyEntry) -> Row<'_> {
+        let name_style = if idx == self.selected {
+            self.theme.selected()
+        } else {
+            Style::default().fg(self.theme.text)
+        };
+        let state_style = Style::default().fg(self.theme.state_color(&entry.state));
+        let duration = entry
+            .duration
+            .map(|duration| format!("{:.2}s", duration.as_secs_f64()))
+            .unwrap_or_default();
+        Row::new(vec![
+            Cell::from(format!("  {}", entry.name)).style(name_style),
+            Cell::from(format!("{} {:?}", entry.state.icon(), entry.state)).style(state_style),
+            Cell::from(entry.relation.label()).style(Style::default().fg(self.theme.secondary)),
+            Cell::from(duration).style(Style::default().fg(self.theme.muted)),
+        ])
+    }
+}
+
+/// Tasks reachable from `start` through `next`, split into those one step
+/// away and the rest, each sorted by name
+fn closure(
+    start: &str,
+    next: impl Fn(&str) -> Vec<String>,
+) -> (BTreeSet<String>, BTreeSet<String>) {
+    let direct: BTreeSet<String> = next(start).into_iter().collect();
+    let mut seen = direct.clone();
+    let mut queue: VecDeque<String> = direct.iter().cloned().collect();
+    while let Some(name) = queue.pop_front() {
+        for reached in next(&name) {
+            if reached != start && seen.insert(reached.clone()) {
+                queue.push_back(reached);
+            }
+        }
+    }
+    let transitive = seen.difference(&direct).cloned().collect();
+    (direct, transitive)
+}
+
+/// Rectangle of the given percentages of `area`, centered in it
+fn centered(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
+    let width = area.width * width_percent / 100;
+    let height = area.height * height_percent / 100;
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(edges: &[(&str, &[&str])]) -> HashMap<String, TaskInfo> {
+        edges
+            .iter()
+            .map(|(name, deps)| {
+                let deps = deps.iter().map(|dep| dep.to_string()).collect();
+                (name.to_string(), TaskInfo::new(name.to_string(), deps))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inspector_lists_direct_and_transitive_relations() {
+        // deploy -> build -> {lint, codegen}, test -> build
+        let tasks = tasks(&[
+            ("deploy", &["build"]),
+            ("test", &["build"]),
+            ("build", &["lint", "codegen"]),
+            ("lint", &[]),
+            ("codegen", &["lint"]),
+        ]);
+
+        let inspector = DependencyInspector::new("build", &tasks);
+        let listed: Vec<(&str, Relation)> = inspector
+            .entries()
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.relation))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("codegen", Relation::Dependency),
+                ("lint", Relation::Dependency),
+                ("deploy", Relation::Dependent),
+                ("test", Relation::Dependent),
+            ]
+        );
+
+        let mut inspector = DependencyInspector::new("deploy", &tasks);
+        assert_eq!(
+            inspector.entries()[1].relation,
+            Relation::TransitiveDependency
+        );
+        inspector.select_next();
+        inspector.select_next();
+        assert_eq!(inspector.selected_task(), Some("lint"));
+        // The selection follows the task across refreshes
+        inspector.refresh(&tasks);
+        assert_eq!(inspector.selected_task(), Some("lint"));
+    }
+}
@@ -1,6 +1,8 @@
+use crate::theme::Theme;
+use cuenv_core::redaction::{redact, MASK};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{
         Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
         Table,
@@ -14,6 +16,7 @@ pub struct EnvPane {
     scroll_offset: u16,
     selected_index: Option<usize>,
     sorted_keys: Vec<String>,
+    theme: Theme,
 }
 
 impl EnvPane {
@@ -30,9 +33,15 @@ impl EnvPane {
                 Some(0)
             },
             sorted_keys,
+            theme: Theme::default(),
         }
     }
 
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
     pub fn render(&mut self, frame: &mut Frame<'_>, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -42,14 +51,14 @@ impl EnvPane {
         let block = Block::default()
             .title(" Environment Variables ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray));
+            .border_style(self.theme.border_style(false));
 
         let inner_area = block.inner(chunks[0]);
         frame.render_widget(block, chunks[0]);
 
         if self.sorted_keys.is_empty() {
             let empty_msg = Paragraph::new("No environment variables defined")
-                .style(Style::default().fg(Color::DarkGray));
+                .style(Style::default().fg(self.theme.muted));
             frame.render_widget(empty_msg, inner_area);
             return;
         }
@@ -63,34 +72,32 @@ impl EnvPane {
         for (idx, key) in self.sorted_keys[start_idx..end_idx].iter().enumerate() {
             let global_idx = start_idx + idx;
             let value = self.env_vars.get(key).map(String::as_str).unwrap_or("");
+            let value = redact(value);
 
             // Check if value looks like a secret reference
             let (value_display, value_style) = if value.starts_with("${") && value.ends_with("}") {
                 (
                     format!("🔒 {value}"),
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(self.theme.warning)
                         .add_modifier(Modifier::ITALIC),
                 )
-            } else if value.contains("***") || value.contains("REDACTED") {
+            } else if value.contains(MASK) || value.contains("REDACTED") {
                 (
                     "🔒 [REDACTED]".to_string(),
                     Style::default()
-                        .fg(Color::Red)
+                        .fg(self.theme.error)
                         .add_modifier(Modifier::ITALIC),
                 )
             } else {
-                (value.to_string(), Style::default().fg(Color::White))
+                (value.into_owned(), Style::default().fg(self.theme.text))
             };
 
             let is_selected = self.selected_index == Some(global_idx);
             let key_style = if is_selected {
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD)
-                    .add_modifier(Modifier::REVERSED)
+                self.theme.selected()
             } else {
-                Style::default().fg(Color::Cyan)
+                Style::default().fg(self.theme.accent)
             };
 
             rows.push(Row::new(vec![
@@ -1,7 +1,10 @@
-use crate::events::{LogEntry, LogStream, TaskInfo, TaskRegistry};
+use super::log_view::{highlight, log_file_name, LogView};
+use crate::events::{LogStream, TaskInfo, TaskRegistry};
+use crate::log_store::LogLine;
+use crate::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{
         Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
@@ -14,8 +17,15 @@ pub struct FocusPane {
     task_registry: TaskRegistry,
     current_task: Option<String>,
     current_task_info: Option<TaskInfo>,
-    log_scroll_offset: u16,
+    /// First visible log line shown
+    log_scroll_offset: usize,
     auto_scroll: bool,
+    log_view: LogView,
+    /// Keep showing the current task while the selection moves
+    pinned: bool,
+    /// Outcome of the last log command, shown in the logs title
+    status: Option<String>,
+    theme: Theme,
 }
 
 impl FocusPane {
@@ -26,11 +36,20 @@ impl FocusPane {
             current_task_info: None,
             log_scroll_offset: 0,
             auto_scroll: true,
+            log_view: LogView::default(),
+            pinned: false,
+            status: None,
+            theme: Theme::default(),
         }
     }
 
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
     pub fn set_task(&mut self, task_name: String) {
-        if self.current_task.as_ref() != Some(&task_name) {
+        if !self.pinned && self.current_task.as_ref() != Some(&task_name) {
             self.current_task = Some(task_name);
             self.current_task_info = None; // Clear cached info
             self.log_scroll_offset = 0;
@@ -85,7 +104,7 @@ impl FocusPane {
         let block = Block::default()
             .title(" Task Details ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray));
+            .border_style(self.theme.border_style(false));
 
         let inner_area = block.inner(area);
         frame.render_widget(block, area);
@@ -96,11 +115,11 @@ impl FocusPane {
         } else if self.current_task.is_some() {
             // Task selected but info not loaded yet
             let loading =
-                Paragraph::new("Loading task info...").style(Style::default().fg(Color::DarkGray));
+                Paragraph::new("Loading task info...").style(Style::default().fg(self.theme.muted));
             frame.render_widget(loading, inner_area);
         } else {
             let empty_msg =
-                Paragraph::new("No task selected").style(Style::default().fg(Color::DarkGray));
+                Paragraph::new("No task selected").style(Style::default().fg(self.theme.muted));
             frame.render_widget(empty_msg, inner_area);
         }
     }
@@ -110,12 +129,12 @@ impl FocusPane {
 
         // Task name and state
         rows.push(Row::new(vec![
-            Cell::from("Task:").style(Style::default().fg(Color::DarkGray)),
+            Cell::from("Task:").style(Style::default().fg(self.theme.muted)),
             Cell::from(task.name.clone()).style(Style::default().add_modifier(Modifier::BOLD)),
         ]));
 
         rows.push(Row::new(vec![
-            Cell::from("State:").style(Style::default().fg(Color::DarkGray)),
+            Cell::from("State:").style(Style::default().fg(self.theme.muted)),
             Cell::from(format!("{} {:?}", task.state.icon(), task.state))
                 .style(self.get_state_style(&task.state)),
         ]));
@@ -123,21 +142,21 @@ impl FocusPane {
         // Duration
         if let Some(duration) = task.duration() {
             rows.push(Row::new(vec![
-                Cell::from("Duration:").style(Style::default().fg(Color::DarkGray)),
+                Cell::from("Duration:").style(Style::default().fg(self.theme.muted)),
                 Cell::from(format!("{:.2}s", duration.as_secs_f64()))
-                    .style(Style::default().fg(Color::White)),
+                    .style(Style::default().fg(self.theme.text)),
             ]));
         }
 
         // Exit code
         if let Some(exit_code) = task.exit_code {
             let exit_style = if exit_code == 0 {
-                Style::default().fg(Color::Green)
+                Style::default().fg(self.theme.success)
             } else {
-                Style::default().fg(Color::Red)
+                Style::default().fg(self.theme.error)
             };
             rows.push(Row::new(vec![
-                Cell::from("Exit Code:").style(Style::default().fg(Color::DarkGray)),
+                Cell::from("Exit Code:").style(Style::default().fg(self.theme.muted)),
                 Cell::from(exit_code.to_string()).style(exit_style),
             ]));
         }
@@ -145,16 +164,17 @@ impl FocusPane {
         // Dependencies
         if !task.dependencies.is_empty() {
             rows.push(Row::new(vec![
-                Cell::from("Dependencies:").style(Style::default().fg(Color::DarkGray)),
-                Cell::from(task.dependencies.join(", ")).style(Style::default().fg(Color::Blue)),
+                Cell::from("Dependencies:").style(Style::default().fg(self.theme.muted)),
+                Cell::from(task.dependencies.join(", "))
+                    .style(Style::default().fg(self.theme.secondary)),
             ]));
         }
 
         // Current message
         if let Some(message) = &task.message {
             rows.push(Row::new(vec![
-                Cell::from("Message:").style(Style::default().fg(Color::DarkGray)),
-                Cell::from(message.clone()).style(Style::default().fg(Color::Yellow)),
+                Cell::from("Message:").style(Style::default().fg(self.theme.muted)),
+                Cell::from(message.clone()).style(Style::default().fg(self.theme.warning)),
             ]));
         }
 
@@ -167,49 +187,57 @@ impl FocusPane {
             .constraints([Constraint::Min(0), Constraint::Length(1)])
             .split(area);
 
+        let mode = if self.auto_scroll {
+            "[FOLLOW]"
+        } else {
+            "[MANUAL]"
+        };
+        let logs = self
+            .current_task_info
+            .as_ref()
+            .map(|task_info| task_info.logs.clone());
+        let dropped = logs.as_ref().map_or(0, |logs| logs.dropped());
+        let title = [
+            Some(mode.to_string()),
+            self.pinned.then(|| "[PINNED]".to_string()),
+            Some(self.log_view.summary()).filter(|summary| !summary.is_empty()),
+            (dropped > 0).then(|| format!("[{dropped} entries lost]")),
+            self.status.as_ref().map(|status| format!("─ {status}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
         let block = Block::default()
-            .title(format!(
-                " Logs {} ",
-                if self.auto_scroll {
-                    "[AUTO]"
-                } else {
-                    "[MANUAL]"
-                }
-            ))
+            .title(format!(" Logs {title} "))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray));
+            .border_style(self.theme.border_style(false));
 
         let inner_area = block.inner(chunks[0]);
         frame.render_widget(block, chunks[0]);
 
-        if let Some(task_info) = &self.current_task_info {
-            let formatted_logs = self.format_logs(&task_info.logs);
-            let total_lines = formatted_logs.1;
+        if let Some(logs) = logs {
+            let total_lines = self.log_view.line_count(&logs);
             let visible_height = inner_area.height as usize;
 
             // Auto-scroll to bottom if enabled
-            let scroll_offset = if self.auto_scroll && total_lines > visible_height {
-                (total_lines - visible_height) as u16
-            } else {
-                self.log_scroll_offset
-            };
+            if self.auto_scroll {
+                self.log_scroll_offset = total_lines.saturating_sub(visible_height);
+            }
 
-            let paragraph = Paragraph::new(formatted_logs.0)
-                .scroll((scroll_offset, 0))
-                .wrap(Wrap { trim: false });
+            // Only the lines on screen are read, which may be from disk
+            let lines = self
+                .log_view
+                .window(&logs, self.log_scroll_offset, visible_height);
+            let paragraph = Paragraph::new(self.format_lines(&lines)).wrap(Wrap { trim: false });
 
             frame.render_widget(paragraph, inner_area);
 
-            // Update scroll offset after rendering
-            if self.auto_scroll && total_lines > visible_height {
-                self.log_scroll_offset = (total_lines - visible_height) as u16;
-            }
-
             // Render scrollbar if needed
             if total_lines > visible_height {
                 let mut scrollbar_state = ScrollbarState::default()
                     .content_length(total_lines.saturating_sub(visible_height))
-                    .position(self.log_scroll_offset as usize);
+                    .position(self.log_scroll_offset);
 
                 let scrollbar = Scrollbar::default()
                     .orientation(ScrollbarOrientation::VerticalRight)
@@ -220,66 +248,59 @@ impl FocusPane {
             }
         } else {
             let empty_msg =
-                Paragraph::new("No task selected").style(Style::default().fg(Color::DarkGray));
+                Paragraph::new("No task selected").style(Style::default().fg(self.theme.muted));
             frame.render_widget(empty_msg, inner_area);
         }
     }
 
-    fn format_logs(&self, logs: &[LogEntry]) -> (Vec<Line>, usize) {
-        let mut lines = Vec::new();
-        let mut line_count = 0;
+    fn format_lines(&self, lines: &[LogLine]) -> Vec<Line<'static>> {
+        let match_style = Style::default()
+            .fg(self.theme.highlight)
+            .add_modifier(Modifier::REVERSED);
 
-        for log in logs {
-            let timestamp = format!("{:>8.2}s", log.timestamp.elapsed().as_secs_f64());
-            let stream_style = match log.stream {
-                LogStream::Stdout => Style::default().fg(Color::White),
-                LogStream::Stderr => Style::default().fg(Color::Red),
-                LogStream::System => Style::default().fg(Color::Yellow),
-            };
+        lines
+            .iter()
+            .map(|line| {
+                let timestamp = format!("{:>8.2}s", line.timestamp.elapsed().as_secs_f64());
+                let stream_style = match line.stream {
+                    LogStream::Stdout => Style::default().fg(self.theme.text),
+                    LogStream::Stderr => Style::default().fg(self.theme.error),
+                    LogStream::System => Style::default().fg(self.theme.warning),
+                };
 
-            // Split content into lines
-            for content_line in log.content.lines() {
                 let mut spans = vec![
-                    Span::styled(timestamp.clone(), Style::default().fg(Color::DarkGray)),
+                    Span::styled(timestamp, Style::default().fg(self.theme.muted)),
                     Span::raw(" "),
                 ];
 
-                match log.stream {
+                match line.stream {
                     LogStream::Stdout => spans.push(Span::raw("│ ")),
                     LogStream::Stderr => {
-                        spans.push(Span::styled("┃ ", Style::default().fg(Color::Red)))
+                        spans.push(Span::styled("┃ ", Style::default().fg(self.theme.error)))
                     }
                     LogStream::System => {
-                        spans.push(Span::styled("┊ ", Style::default().fg(Color::Yellow)))
+                        spans.push(Span::styled("┊ ", Style::default().fg(self.theme.warning)))
                     }
                 }
 
-                spans.push(Span::styled(content_line.to_string(), stream_style));
-                lines.push(Line::from(spans));
-                line_count += 1;
-            }
-        }
-
-        (lines, line_count)
+                let ranges = self.log_view.match_ranges(&line.text);
+                spans.extend(highlight(&line.text, &ranges, stream_style, match_style));
+                Line::from(spans)
+            })
+            .collect()
     }
 
     fn get_state_style(&self, state: &crate::events::TaskState) -> Style {
-        match state {
-            crate::events::TaskState::Queued => Style::default().fg(Color::DarkGray),
-            crate::events::TaskState::Running => Style::default().fg(Color::Yellow),
-            crate::events::TaskState::Completed => Style::default().fg(Color::Green),
-            crate::events::TaskState::Failed => Style::default().fg(Color::Red),
-            crate::events::TaskState::Cancelled => Style::default().fg(Color::Magenta),
-        }
+        Style::default().fg(self.theme.state_color(state))
     }
 
     pub fn scroll_up(&mut self, amount: u16) {
-        self.log_scroll_offset = self.log_scroll_offset.saturating_sub(amount);
+        self.log_scroll_offset = self.log_scroll_offset.saturating_sub(amount.into());
         self.auto_scroll = false;
     }
 
     pub fn scroll_down(&mut self, amount: u16) {
-        self.log_scroll_offset = self.log_scroll_offset.saturating_add(amount);
+        self.log_scroll_offset = self.log_scroll_offset.saturating_add(amount.into());
         // Don't disable auto-scroll when scrolling down
     }
 
@@ -295,12 +316,73 @@ impl FocusPane {
     pub fn jump_to_bottom(&mut self) {
         self.auto_scroll = true;
     }
+
+    pub fn log_view_mut(&mut self) -> &mut LogView {
+        &mut self.log_view
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.log_view.is_searching()
+    }
+
+    pub fn toggle_pin(&mut self) {
+        self.pinned = !self.pinned;
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
+    /// Scroll to the next line matching the search, wrapping around
+    pub fn jump_to_match(&mut self, forward: bool) {
+        let Some(task_info) = &self.current_task_info else {
+            return;
+        };
+        let matches = self.log_view.matching_lines(&task_info.logs);
+        let current = self.log_scroll_offset;
+        let target = if forward {
+            matches
+                .iter()
+                .find(|&&line| line > current)
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&line| line < current)
+                .or(matches.last())
+        };
+        if let Some(&line) = target {
+            self.log_scroll_offset = line;
+            self.auto_scroll = false;
+        }
+        self.status = Some(match matches.len() {
+            0 => "no matches".to_string(),
+            1 => "1 match".to_string(),
+            count => format!("{count} matches"),
+        });
+    }
+
+    /// Save the lines shown for the current task to a file in the current
+    /// directory
+    pub fn save_logs(&mut self) {
+        let Some(task_info) = &self.current_task_info else {
+            return;
+        };
+        let path = std::path::PathBuf::from(log_file_name(&task_info.name));
+        self.status = Some(match self.log_view.save(&task_info.logs, &path) {
+            Ok(count) => format!("saved {count} lines to {}", path.display()),
+            Err(e) => format!("failed to save {}: {e}", path.display()),
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::events::{LogEntry, LogStream, TaskInfo, TaskRegistry, TaskState};
+    use crate::log_store::TaskLogs;
+    use ratatui::style::Color;
     use std::time::{Duration, Instant};
 
     fn create_test_task_registry() -> TaskRegistry {
@@ -331,6 +413,7 @@ mod tests {
             timestamp: Instant::now() - Duration::from_secs(seconds_ago),
             stream,
             content: content.to_string(),
+            level: None,
         }
     }
 
@@ -442,9 +525,9 @@ mod tests {
         focus_pane.scroll_down(3);
         assert_eq!(focus_pane.log_scroll_offset, 3);
 
-        // Test scroll down with overflow protection
+        // Long logs scroll past what a u16 holds
         focus_pane.scroll_down(u16::MAX);
-        assert_eq!(focus_pane.log_scroll_offset, u16::MAX);
+        assert_eq!(focus_pane.log_scroll_offset, 3 + usize::from(u16::MAX));
     }
 
     #[tokio::test]
@@ -498,17 +581,21 @@ mod tests {
         let registry = create_test_task_registry();
         let focus_pane = FocusPane::new(registry);
 
-        let logs = vec![
+        let logs = TaskLogs::default();
+        for log in [
             create_test_log_entry("stdout message", LogStream::Stdout, 5),
             create_test_log_entry("stderr message", LogStream::Stderr, 3),
             create_test_log_entry("system message", LogStream::System, 1),
             create_test_log_entry("multiline\nmessage\nhere", LogStream::Stdout, 0),
-        ];
+        ] {
+            logs.push(log);
+        }
 
-        let (formatted_lines, line_count) = focus_pane.format_logs(&logs);
+        let lines = focus_pane.log_view.window(&logs, 0, 100);
+        let formatted_lines = focus_pane.format_lines(&lines);
 
         // Should have 6 lines total (3 single lines + 3 lines from multiline message)
-        assert_eq!(line_count, 6);
+        assert_eq!(focus_pane.log_view.line_count(&logs), 6);
         assert_eq!(formatted_lines.len(), 6);
 
         // Verify that each line has the correct structure (timestamp + separator + content)
@@ -524,9 +611,10 @@ mod tests {
         let registry = create_test_task_registry();
         let focus_pane = FocusPane::new(registry);
 
-        let (formatted_lines, line_count) = focus_pane.format_logs(&[]);
-        assert_eq!(line_count, 0);
-        assert!(formatted_lines.is_empty());
+        let logs = TaskLogs::default();
+        assert_eq!(focus_pane.log_view.line_count(&logs), 0);
+        assert!(focus_pane.log_view.window(&logs, 0, 10).is_empty());
+        assert!(focus_pane.format_lines(&[]).is_empty());
     }
 
     #[tokio::test]
@@ -612,8 +700,8 @@ mod tests {
         assert_eq!(task_info.state, TaskState::Running);
 
         // Test log formatting
-        let (formatted_lines, line_count) = focus_pane.format_logs(&task_info.logs);
-        assert_eq!(line_count, 5);
-        assert_eq!(formatted_lines.len(), 5);
+        let lines = focus_pane.log_view.window(&task_info.logs, 0, 100);
+        assert_eq!(focus_pane.log_view.line_count(&task_info.logs), 5);
+        assert_eq!(focus_pane.format_lines(&lines).len(), 5);
     }
 }
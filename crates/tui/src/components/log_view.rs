@@ -0,0 +1,322 @@
+//! Filtering, search and export of task logs
+//!
+//! The logs pane shows a task's log through a [`LogView`]: lines of hidden
+//! streams and system lines below the minimum level are left out, and the
+//! search query is highlighted, ignoring ASCII case, in what remains. The
+//! lines shown are also what gets saved to a file. The log may be mostly on
+//! disk, so the pane asks for the lines it draws, and searching and saving
+//! read it a page at a time.
+
+use crate::events::{LogEntry, LogStream};
+use crate::log_store::{LogLine, TaskLogs};
+use ratatui::{style::Style, text::Span};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use tracing::Level;
+
+pub struct LogView {
+    show_stdout: bool,
+    show_stderr: bool,
+    show_system: bool,
+    /// Least severe level of system lines shown
+    min_level: Level,
+    query: Option<String>,
+    /// Query being typed, highlighted as it is typed
+    input: Option<String>,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            show_stdout: true,
+            show_stderr: true,
+            show_system: true,
+            min_level: Level::TRACE,
+            query: None,
+            input: None,
+        }
+    }
+}
+
+impl LogView {
+    pub fn toggle_stream(&mut self, stream: LogStream) {
+        let shown = match stream {
+            LogStream::Stdout => &mut self.show_stdout,
+            LogStream::Stderr => &mut self.show_stderr,
+            LogStream::System => &mut self.show_system,
+        };
+        *shown = !*shown;
+    }
+
+    /// Hide the least severe level still shown, starting over from TRACE
+    /// after ERROR
+    pub fn cycle_level(&mut self) {
+        self.min_level = match self.min_level {
+            Level::TRACE => Level::DEBUG,
+            Level::DEBUG => Level::INFO,
+            Level::INFO => Level::WARN,
+            Level::WARN => Level::ERROR,
+            _ => Level::TRACE,
+        };
+    }
+
+    fn shows(&self, stream: &LogStream, level: Option<Level>) -> bool {
+        let stream_shown = match stream {
+            LogStream::Stdout => self.show_stdout,
+            LogStream::Stderr => self.show_stderr,
+            LogStream::System => self.show_system,
+        };
+        // More verbose levels compare greater
+        stream_shown && level.is_none_or(|level| level <= self.min_level)
+    }
+
+    /// Lines of the entries shown
+    fn visible_lines<'a>(&'a self, logs: &'a [LogEntry]) -> impl Iterator<Item = &'a str> + 'a {
+        logs.iter()
+            .filter(|entry| self.shows(&entry.stream, entry.level))
+            .flat_map(|entry| entry.content.lines())
+    }
+
+    /// How many lines are shown
+    pub fn line_count(&self, logs: &TaskLogs) -> usize {
+        logs.line_count(|stream, level| self.shows(stream, level))
+    }
+
+    /// Up to `count` of the lines shown, from the `first`
+    pub fn window(&self, logs: &TaskLogs, first: usize, count: usize) -> Vec<LogLine> {
+        logs.lines(|stream, level| self.shows(stream, level), first, count)
+    }
+
+    pub fn start_search(&mut self) {
+        self.input = Some(String::new());
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if let Some(input) = &mut self.input {
+            input.push(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+        }
+    }
+
+    /// Use the typed query; an empty one clears the search
+    pub fn finish_search(&mut self) {
+        self.query = self.input.take().filter(|input| !input.is_empty());
+    }
+
+    /// Drop the typed query and the current one
+    pub fn cancel_search(&mut self) {
+        self.input = None;
+        self.query = None;
+    }
+
+    pub fn has_query(&self) -> bool {
+        self.query.is_some()
+    }
+
+    fn active_query(&self) -> Option<&str> {
+        self.input
+            .as_deref()
+            .or(self.query.as_deref())
+            .filter(|query| !query.is_empty())
+    }
+
+    /// Byte ranges of `line` matching the query
+    pub fn match_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        let Some(query) = self.active_query() else {
+            return Vec::new();
+        };
+        let needle = query.to_ascii_lowercase();
+        line.to_ascii_lowercase()
+            .match_indices(&needle)
+            .map(|(start, found)| start..start + found.len())
+            .collect()
+    }
+
+    /// Indices among the visible lines of those matching the query
+    pub fn matching_lines(&self, logs: &TaskLogs) -> Vec<usize> {
+        let mut matches = Vec::new();
+        let mut index = 0;
+        logs.for_each_page(|page| {
+            for line in self.visible_lines(page) {
+                if !self.match_ranges(line).is_empty() {
+                    matches.push(index);
+                }
+                index += 1;
+            }
+        });
+        matches
+    }
+
+    /// Write the visible lines to `path`, returning how many were written
+    pub fn save(&self, logs: &TaskLogs, path: &Path) -> io::Result<usize> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let mut count = 0;
+        let mut result = Ok(());
+        logs.for_each_page(|page| {
+            for line in self.visible_lines(page) {
+                if result.is_ok() {
+                    result = writeln!(file, "{line}");
+                    count += 1;
+                }
+            }
+        });
+        result?;
+        file.flush()?;
+        Ok(count)
+    }
+
+    /// Filters and query in effect, for the pane title
+    pub fn summary(&self) -> String {
+        let hidden = [
+            (self.show_stdout, "-stdout"),
+            (self.show_stderr, "-stderr"),
+            (self.show_system, "-system"),
+        ]
+        .into_iter()
+        .filter(|(shown, _)| !shown)
+        .map(|(_, label)| label.to_string());
+        let level = (self.min_level != Level::TRACE).then(|| format!("≥{}", self.min_level));
+        let search = match (&self.input, &self.query) {
+            (Some(input), _) => Some(format!("/{input}_")),
+            (None, Some(query)) => Some(format!("/{query}")),
+            (None, None) => None,
+        };
+        hidden
+            .chain(level)
+            .chain(search)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Spans of `line` with `ranges` drawn in `match_style`
+pub fn highlight(
+    line: &str,
+    ranges: &[Range<usize>],
+    style: Style,
+    match_style: Style,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for range in ranges {
+        if range.start > position {
+            spans.push(Span::styled(line[position..range.start].to_string(), style));
+        }
+        spans.push(Span::styled(line[range.clone()].to_string(), match_style));
+        position = range.end;
+    }
+    if position < line.len() || spans.is_empty() {
+        spans.push(Span::styled(line[position..].to_string(), style));
+    }
+    spans
+}
+
+/// File in the current directory the logs of `task_name` are saved to
+pub fn log_file_name(task_name: &str) -> String {
+    let safe_name: String = task_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("cuenv-{safe_name}.log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn entry(stream: LogStream, level: Option<Level>, content: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Instant::now(),
+            stream,
+            content: content.to_string(),
+            level,
+        }
+    }
+
+    #[test]
+    fn test_filters_search_and_save() {
+        let logs = TaskLogs::default();
+        for log in [
+            entry(LogStream::Stdout, None, "Compiling app\nFinished build"),
+            entry(LogStream::Stderr, None, "warning: unused BUILD flag"),
+            entry(
+                LogStream::System,
+                Some(Level::DEBUG),
+                "cache miss for build",
+            ),
+            entry(LogStream::System, Some(Level::ERROR), "Task failed: exit 1"),
+        ] {
+            logs.push(log);
+        }
+        let mut view = LogView::default();
+        assert_eq!(view.line_count(&logs), 5);
+
+        view.start_search();
+        "build".chars().for_each(|c| view.push_char(c));
+        view.finish_search();
+        assert_eq!(view.matching_lines(&logs), vec![1, 2, 3]);
+        assert_eq!(
+            view.match_ranges("warning: unused BUILD flag"),
+            vec![16..21]
+        );
+
+        view.toggle_stream(LogStream::Stderr);
+        view.cycle_level();
+        view.cycle_level();
+        assert_eq!(view.matching_lines(&logs), vec![1]);
+        assert_eq!(view.summary(), "-stderr ≥INFO /build");
+        assert_eq!(view.line_count(&logs), 3);
+        let window = view.window(&logs, 1, 5);
+        assert_eq!(window.len(), 2);
+        assert_eq!(window[1].text, "Task failed: exit 1");
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(log_file_name("ci:build/linux"));
+        assert_eq!(view.save(&logs, &path).unwrap(), 3);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Compiling app\nFinished build\nTask failed: exit 1\n"
+        );
+        assert!(path.ends_with("cuenv-ci_build_linux.log"));
+    }
+
+    #[test]
+    fn test_highlight_splits_matches() {
+        let style = Style::default();
+        let match_style = Style::default().bg(ratatui::style::Color::Yellow);
+        let spans = highlight("a build of build", &[2..7, 11..16], style, match_style);
+        let parts: Vec<_> = spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style == match_style))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("a ", false),
+                ("build", true),
+                (" of ", false),
+                ("build", true)
+            ]
+        );
+    }
+}
@@ -7,6 +7,7 @@ mod state;
 pub use state::MiniMap;
 
 use crate::events::TaskRegistry;
+use crate::theme::Theme;
 use std::collections::HashSet;
 
 #[derive(Clone)]
@@ -28,9 +29,15 @@ impl MiniMap {
             visible_lines: Vec::new(),
             max_line_width: 0,
             cached_states: Vec::new(),
+            theme: Theme::default(),
         }
     }
 
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
     pub fn get_selected_task(&self) -> Option<&String> {
         self.selected_task.as_ref()
     }
@@ -15,6 +15,17 @@ impl MiniMap {
         }
     }
 
+    /// Select `task_name`, expanding its parents so it is in the tree
+    pub async fn reveal_task(&mut self, task_name: &str) {
+        let parents = task_name
+            .match_indices('.')
+            .map(|(idx, _)| task_name[..idx].to_string());
+        self.expanded_nodes.extend(parents);
+        self.selected_task = Some(task_name.to_string());
+        self.build_tree_lines().await;
+        self.ensure_visible(self.get_selected_index());
+    }
+
     pub fn select_previous(&mut self) {
         if self.visible_lines.is_empty() {
             return;
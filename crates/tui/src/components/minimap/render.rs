@@ -1,8 +1,9 @@
 use super::{MiniMap, TreeLine};
 use crate::events::TaskState;
+use crate::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
@@ -25,7 +26,7 @@ impl MiniMap {
                 }
             ))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray));
+            .border_style(self.theme.border_style(false));
 
         let inner_area = block.inner(chunks[0]);
         frame.render_widget(block, chunks[0]);
@@ -58,7 +59,8 @@ impl MiniMap {
                     .map(|(_, s)| s.clone())
                     .unwrap_or(TaskState::Queued);
 
-                let line = Self::render_tree_line_pure(tree_line, is_selected, task_state);
+                let line =
+                    Self::render_tree_line_pure(&self.theme, tree_line, is_selected, task_state);
                 let scrolled_line = self.apply_horizontal_scroll(line, visible_width);
                 lines.push(scrolled_line);
             }
@@ -83,6 +85,7 @@ impl MiniMap {
     }
 
     fn render_tree_line_pure(
+        theme: &Theme,
         tree_line: &TreeLine,
         is_selected: bool,
         task_state: TaskState,
@@ -99,24 +102,14 @@ impl MiniMap {
             } else {
                 "▶ "
             };
-            spans.push(Span::styled(
-                indicator,
-                Style::default().fg(Color::DarkGray),
-            ));
+            spans.push(Span::styled(indicator, Style::default().fg(theme.muted)));
         }
 
         // Task state icon
         let icon = task_state.icon();
-        let icon_color = match task_state {
-            TaskState::Queued => Color::DarkGray,
-            TaskState::Running => Color::Yellow,
-            TaskState::Completed => Color::Green,
-            TaskState::Failed => Color::Red,
-            TaskState::Cancelled => Color::Magenta,
-        };
         spans.push(Span::styled(
             format!("{icon} "),
-            Style::default().fg(icon_color),
+            Style::default().fg(theme.state_color(&task_state)),
         ));
 
         // Task name (show only the last part after the final dot)
@@ -127,12 +120,9 @@ impl MiniMap {
         };
 
         let name_style = if is_selected {
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::REVERSED)
+            theme.selected()
         } else {
-            Style::default().fg(Color::White)
+            Style::default().fg(theme.text)
         };
         spans.push(Span::styled(display_name, name_style));
 
@@ -1,6 +1,6 @@
 use super::MiniMap;
 use ratatui::{
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
 };
 
@@ -84,7 +84,7 @@ impl MiniMap {
         }
 
         if self.horizontal_scroll > 0 && visible_spans.is_empty() {
-            visible_spans.push(Span::styled("←", Style::default().fg(Color::DarkGray)));
+            visible_spans.push(Span::styled("←", Style::default().fg(self.theme.muted)));
         }
 
         Line::from(visible_spans)
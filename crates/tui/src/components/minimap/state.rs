@@ -1,5 +1,6 @@
 use super::TreeLine;
 use crate::events::{TaskRegistry, TaskState};
+use crate::theme::Theme;
 use std::collections::HashSet;
 
 pub struct MiniMap {
@@ -12,4 +13,5 @@ pub struct MiniMap {
     pub(crate) max_line_width: u16,
     // Cached aggregate state per task for current frame
     pub(crate) cached_states: Vec<(String, TaskState)>,
+    pub(crate) theme: Theme,
 }
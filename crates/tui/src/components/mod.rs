@@ -1,7 +1,13 @@
+pub mod dependency_inspector;
 pub mod env_pane;
 pub mod focus_pane;
+pub mod log_view;
 pub mod minimap;
+pub mod tracing_pane;
 
+pub use dependency_inspector::DependencyInspector;
 pub use env_pane::*;
 pub use focus_pane::*;
+pub use log_view::LogView;
 pub use minimap::*;
+pub use tracing_pane::*;
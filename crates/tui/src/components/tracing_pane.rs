@@ -0,0 +1,195 @@
+use crate::theme::Theme;
+use cuenv_core::events::{SpanKind, SpanStatus, Timeline, TimelineSpan};
+use cuenv_core::redaction::redact;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Width of the waterfall bar column
+const BAR_WIDTH: usize = 24;
+
+/// One line of the waterfall
+#[derive(Debug, Clone, PartialEq)]
+pub struct TracingRow {
+    pub depth: usize,
+    pub span: TimelineSpan,
+    /// Span length measured up to now for running spans
+    pub elapsed: Duration,
+}
+
+/// Waterfall of hooks, environment loading, secrets and tasks
+pub struct TracingPane {
+    timeline: Timeline,
+    scroll_offset: usize,
+    theme: Theme,
+}
+
+impl TracingPane {
+    pub fn new(timeline: Timeline) -> Self {
+        Self {
+            timeline,
+            scroll_offset: 0,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Spans in start order, each nested under its parent
+    pub fn rows(&self) -> Vec<TracingRow> {
+        let now = self.timeline.elapsed();
+        let spans = self.timeline.spans();
+        let parents: HashMap<u64, Option<u64>> =
+            spans.iter().map(|span| (span.id, span.parent)).collect();
+
+        let depth_of = |span: &TimelineSpan| {
+            std::iter::successors(span.parent, |id| parents.get(id).copied().flatten()).count()
+        };
+
+        spans
+            .iter()
+            .map(|span| TracingRow {
+                depth: depth_of(span),
+                elapsed: span.elapsed(now),
+                span: span.clone(),
+            })
+            .collect()
+    }
+
+    pub fn render(&mut self, frame: &mut Frame<'_>, area: Rect) {
+        let block = Block::default()
+            .title(" Timeline ")
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_style(false));
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+
+        let rows = self.rows();
+        if rows.is_empty() {
+            let empty_msg =
+                Paragraph::new("Nothing recorded yet").style(Style::default().fg(self.theme.muted));
+            frame.render_widget(empty_msg, inner_area);
+            return;
+        }
+
+        let total = rows
+            .iter()
+            .map(|row| row.span.start + row.elapsed)
+            .max()
+            .unwrap_or_default();
+
+        self.scroll_offset = self.scroll_offset.min(rows.len().saturating_sub(1));
+        let table_rows = rows
+            .iter()
+            .skip(self.scroll_offset)
+            .take(inner_area.height as usize)
+            .map(|row| {
+                let name = format!("{}{}", "  ".repeat(row.depth), redact(&row.span.name));
+                Row::new(vec![
+                    Cell::from(row.span.kind.label()).style(kind_style(&self.theme, row.span.kind)),
+                    Cell::from(name).style(status_style(&self.theme, row.span.status)),
+                    Cell::from(format!("{:.2}s", row.elapsed.as_secs_f64())),
+                    Cell::from(bar(row.span.start, row.elapsed, total))
+                        .style(kind_style(&self.theme, row.span.kind)),
+                ])
+            });
+
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Length(8),
+                Constraint::Min(10),
+                Constraint::Length(9),
+                Constraint::Length(BAR_WIDTH as u16),
+            ],
+        );
+        frame.render_widget(table, inner_area);
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(amount);
+    }
+
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll_offset += amount;
+    }
+}
+
+fn kind_style(theme: &Theme, kind: SpanKind) -> Style {
+    let color = match kind {
+        SpanKind::Hook => theme.span_hook,
+        SpanKind::Env => theme.span_env,
+        SpanKind::Secrets => theme.span_secrets,
+        SpanKind::Task => theme.span_task,
+    };
+    Style::default().fg(color)
+}
+
+fn status_style(theme: &Theme, status: SpanStatus) -> Style {
+    match status {
+        SpanStatus::Running => Style::default().add_modifier(Modifier::BOLD),
+        SpanStatus::Succeeded => Style::default().fg(theme.text),
+        SpanStatus::Failed => Style::default().fg(theme.error),
+    }
+}
+
+/// Position of a span within the whole timeline, drawn as a bar
+fn bar(start: Duration, elapsed: Duration, total: Duration) -> String {
+    if total.is_zero() {
+        return "█".to_string();
+    }
+    let scale = |d: Duration| (d.as_secs_f64() / total.as_secs_f64() * BAR_WIDTH as f64) as usize;
+    let offset = scale(start).min(BAR_WIDTH - 1);
+    let width = scale(elapsed).clamp(1, BAR_WIDTH - offset);
+    format!("{}{}", " ".repeat(offset), "█".repeat(width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_nested_by_parent() {
+        let timeline = Timeline::new();
+        let load = timeline.start(SpanKind::Env, "load environment");
+        let hook = load.child_of_kind(SpanKind::Hook, "nix develop");
+        drop(hook.child("eval"));
+        drop(hook);
+        drop(load);
+        drop(timeline.start(SpanKind::Task, "build"));
+
+        let pane = TracingPane::new(timeline);
+        let depths: Vec<(usize, String)> = pane
+            .rows()
+            .into_iter()
+            .map(|row| (row.depth, row.span.name))
+            .collect();
+        assert_eq!(
+            depths,
+            vec![
+                (0, "load environment".to_string()),
+                (1, "nix develop".to_string()),
+                (2, "eval".to_string()),
+                (0, "build".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bar_scaling() {
+        let total = Duration::from_secs(10);
+        assert_eq!(bar(Duration::ZERO, total, total), "█".repeat(BAR_WIDTH));
+        let half = bar(Duration::from_secs(5), Duration::from_secs(5), total);
+        assert_eq!(half.trim_start().chars().count(), BAR_WIDTH / 2);
+        assert_eq!(bar(Duration::ZERO, Duration::ZERO, Duration::ZERO), "█");
+        assert_eq!(bar(total, Duration::ZERO, total).chars().count(), BAR_WIDTH);
+    }
+}
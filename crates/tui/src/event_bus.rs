@@ -60,6 +60,15 @@ impl EventBus {
                     .add_log(task_name, stream.clone(), content.clone())
                     .await;
             }
+            TaskEvent::Trace {
+                task_name,
+                level,
+                message,
+            } => {
+                self.registry
+                    .add_system_log(task_name, *level, message.clone())
+                    .await;
+            }
             TaskEvent::Completed {
                 task_name,
                 exit_code,
@@ -77,9 +86,9 @@ impl EventBus {
                     .update_task_state(task_name, crate::events::TaskState::Failed)
                     .await;
                 self.registry
-                    .add_log(
+                    .add_system_log(
                         task_name,
-                        crate::events::LogStream::System,
+                        tracing::Level::ERROR,
                         format!("Task failed: {error}"),
                     )
                     .await;
@@ -89,6 +98,11 @@ impl EventBus {
                     .update_task_state(task_name, crate::events::TaskState::Cancelled)
                     .await;
             }
+            TaskEvent::Publishing { task_name } => {
+                self.registry
+                    .update_task_state(task_name, crate::events::TaskState::Publishing)
+                    .await;
+            }
         }
 
         // Broadcast event to all subscribers
@@ -1,12 +1,18 @@
+use crate::log_store::TaskLogs;
+use cuenv_core::output::{policy, sanitize, OutputSink};
+use cuenv_core::redaction::redact;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tracing::Level;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TaskState {
     Queued,
     Running,
+    /// Succeeded and publishing artifacts
+    Publishing,
     Completed,
     Failed,
     Cancelled,
@@ -17,6 +23,7 @@ impl TaskState {
         match self {
             Self::Queued => "◌",
             Self::Running => "▣",
+            Self::Publishing => "⇪",
             Self::Completed => "✓",
             Self::Failed => "✖",
             Self::Cancelled => "⊘",
@@ -38,7 +45,8 @@ pub struct TaskInfo {
     pub end_time: Option<Instant>,
     pub exit_code: Option<i32>,
     pub message: Option<String>,
-    pub logs: Vec<LogEntry>,
+    /// Bounded in memory, so cloning the task shares it
+    pub logs: TaskLogs,
 }
 
 impl TaskInfo {
@@ -52,7 +60,7 @@ impl TaskInfo {
             end_time: None,
             exit_code: None,
             message: None,
-            logs: Vec::new(),
+            logs: TaskLogs::default(),
         }
     }
 
@@ -70,6 +78,8 @@ pub struct LogEntry {
     pub timestamp: Instant,
     pub stream: LogStream,
     pub content: String,
+    /// Level of system lines that come from tracing events
+    pub level: Option<Level>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,6 +104,12 @@ pub enum TaskEvent {
         stream: LogStream,
         content: String,
     },
+    /// A tracing event about a task, shown as a system log line
+    Trace {
+        task_name: String,
+        level: Level,
+        message: String,
+    },
     Completed {
         task_name: String,
         exit_code: i32,
@@ -107,6 +123,9 @@ pub enum TaskEvent {
     Cancelled {
         task_name: String,
     },
+    Publishing {
+        task_name: String,
+    },
 }
 
 #[derive(Clone)]
@@ -123,7 +142,7 @@ impl TaskRegistry {
 
     pub async fn register_task(&self, name: String, dependencies: Vec<String>) {
         let mut tasks = self.tasks.write().await;
-        let task = TaskInfo::new(name.clone(), dependencies);
+        let mut task = TaskInfo::new(name.clone(), dependencies);
 
         // Update dependents for each dependency
         for dep in &task.dependencies {
@@ -131,6 +150,12 @@ impl TaskRegistry {
                 dep_task.dependents.push(name.clone());
             }
         }
+        // Tasks registered earlier may already depend on this one
+        task.dependents = tasks
+            .values()
+            .filter(|other| other.dependencies.contains(&name))
+            .map(|other| other.name.clone())
+            .collect();
 
         tasks.insert(name, task);
     }
@@ -152,12 +177,26 @@ impl TaskRegistry {
     }
 
     pub async fn add_log(&self, name: &str, stream: LogStream, content: String) {
+        self.push_log(name, stream, content, None).await;
+    }
+
+    /// Add a system line with the level of the tracing event it reports
+    pub async fn add_system_log(&self, name: &str, level: Level, content: String) {
+        self.push_log(name, LogStream::System, content, Some(level))
+            .await;
+    }
+
+    /// Secrets are masked here, so neither the panes nor saved logs show them,
+    /// and escape sequences dropped, as they would move the TUI's cursor
+    async fn push_log(&self, name: &str, stream: LogStream, content: String, level: Option<Level>) {
         let mut tasks = self.tasks.write().await;
         if let Some(task) = tasks.get_mut(name) {
+            let content = redact(&content);
             task.logs.push(LogEntry {
                 timestamp: Instant::now(),
                 stream,
-                content,
+                content: sanitize(&content, policy(OutputSink::Tui)).into_owned(),
+                level,
             });
         }
     }
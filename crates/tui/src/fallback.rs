@@ -1,8 +1,9 @@
 use crate::events::{TaskEvent, TaskRegistry, TaskState};
 use chrono::Local;
+use cuenv_core::events::timeline::ChromeTraceEvent;
+use cuenv_core::events::{global_timeline, Timeline};
 use cuenv_task::executor::TaskExecutionPlan;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 struct TaskTreeContext<'a> {
     task_infos: &'a HashMap<String, crate::events::TaskInfo>,
@@ -17,18 +18,7 @@ pub struct FallbackRenderer {
     task_registry: TaskRegistry,
     start_time: Instant,
     output_path: Option<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct ChromeTraceEvent {
-    name: String,
-    cat: String,
-    ph: String,
-    ts: u64,
-    dur: Option<u64>,
-    pid: u32,
-    tid: String,
-    args: HashMap<String, serde_json::Value>,
+    timeline: Timeline,
 }
 
 impl FallbackRenderer {
@@ -37,9 +27,16 @@ impl FallbackRenderer {
             task_registry,
             start_time: Instant::now(),
             output_path,
+            timeline: global_timeline(),
         }
     }
 
+    /// Read hook, environment and task spans from `timeline`
+    pub fn with_timeline(mut self, timeline: Timeline) -> Self {
+        self.timeline = timeline;
+        self
+    }
+
     /// Generate ASCII representation of the DAG
     pub async fn generate_ascii_dag(&self, plan: &TaskExecutionPlan) -> String {
         let mut output = String::new();
@@ -165,13 +162,35 @@ impl FallbackRenderer {
     }
 
     /// Generate Chrome Trace format JSON for visualization
+    ///
+    /// Timeline spans (hooks, environment loading, secrets, tasks) come
+    /// first; tasks only known to the registry are added from it.
     pub async fn generate_chrome_trace(&self) -> Result<String, serde_json::Error> {
-        let mut events = Vec::new();
-        let tasks = self.task_registry.get_all_tasks().await;
+        let origin = self.start_time.min(self.timeline.origin());
+        let shift = self.timeline.origin().duration_since(origin).as_micros() as u64;
+
+        let mut events: Vec<ChromeTraceEvent> = self
+            .timeline
+            .to_chrome_trace()
+            .into_iter()
+            .map(|event| ChromeTraceEvent {
+                ts: event.ts + shift,
+                ..event
+            })
+            .collect();
+        let traced_tasks: HashSet<String> = events
+            .iter()
+            .filter(|event| event.cat == "task")
+            .map(|event| event.name.clone())
+            .collect();
 
+        let tasks = self.task_registry.get_all_tasks().await;
         for (task_name, task_info) in tasks {
+            if traced_tasks.contains(&task_name) {
+                continue;
+            }
             if let Some(start_time) = task_info.start_time {
-                let start_us = start_time.duration_since(self.start_time).as_micros() as u64;
+                let start_us = start_time.duration_since(origin).as_micros() as u64;
 
                 let mut args = HashMap::new();
                 args.insert(
@@ -267,6 +286,13 @@ impl FallbackRenderer {
                     println!("{prefix} {task_name} | {line}");
                 }
             }
+            TaskEvent::Trace {
+                task_name,
+                level,
+                message,
+            } => {
+                println!("[{level}] {task_name} | {message}");
+            }
             TaskEvent::Completed {
                 task_name,
                 exit_code,
@@ -294,6 +320,9 @@ impl FallbackRenderer {
             TaskEvent::Cancelled { task_name } => {
                 println!("[CANCEL] {task_name}");
             }
+            TaskEvent::Publishing { task_name } => {
+                println!("[PUBLISH] {task_name}");
+            }
         }
     }
 }
@@ -744,6 +773,35 @@ mod tests {
         assert!(output.contains("├─ ✖ child1"));
     }
 
+    #[tokio::test]
+    async fn test_chrome_trace_includes_timeline_spans() {
+        use cuenv_core::events::SpanKind;
+
+        let timeline = Timeline::new();
+        drop(timeline.start(SpanKind::Hook, "nix develop"));
+        drop(timeline.start(SpanKind::Task, "build"));
+
+        let registry = create_test_task_registry();
+        for task in ["build", "lint"] {
+            registry.register_task(task.to_string(), vec![]).await;
+            registry.update_task_state(task, TaskState::Running).await;
+        }
+
+        let renderer = FallbackRenderer::new(registry, None).with_timeline(timeline);
+        let trace_json = renderer.generate_chrome_trace().await.unwrap();
+        let events: Vec<ChromeTraceEvent> = serde_json::from_str(&trace_json).unwrap();
+
+        let mut names: Vec<(&str, &str)> = events
+            .iter()
+            .map(|event| (event.cat.as_str(), event.name.as_str()))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![("hook", "nix develop"), ("task", "build"), ("task", "lint")]
+        );
+    }
+
     #[tokio::test]
     async fn test_chrome_trace_event_structure() {
         let registry = create_test_task_registry();
@@ -75,6 +75,7 @@ impl TaskDisplay {
             match self.state {
                 TaskState::Queued => "◌",
                 TaskState::Running => SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()],
+                TaskState::Publishing => "⇪",
                 TaskState::Completed => "✔",
                 TaskState::Failed => "✖",
                 TaskState::Cancelled => "⊘",
@@ -89,6 +90,7 @@ impl TaskDisplay {
             match self.state {
                 TaskState::Queued => Color::DarkGrey,
                 TaskState::Running => Color::Blue,
+                TaskState::Publishing => Color::Cyan,
                 TaskState::Completed => Color::Green,
                 TaskState::Failed => Color::Red,
                 TaskState::Cancelled => Color::DarkRed,
@@ -401,6 +403,12 @@ impl SpinnerFormatter {
                     task.end_time = Some(Instant::now());
                 }
             }
+            TaskEvent::Publishing { task_name } => {
+                if let Some(task) = tasks.get_mut(&task_name) {
+                    task.state = TaskState::Publishing;
+                    task.message = Some("publishing artifacts".to_string());
+                }
+            }
             _ => {}
         }
 
@@ -481,6 +489,12 @@ mod tests {
             security: None,
             cache: Default::default(),
             timeout: Duration::from_secs(60),
+            run_as: None,
+            process: Default::default(),
+            wait_for: Vec::new(),
+            publish: Vec::new(),
+            problem_matchers: Vec::new(),
+            output_values: Vec::new(),
         }
     }
 
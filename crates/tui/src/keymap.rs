@@ -0,0 +1,416 @@
+//! Key bindings of the terminal UI
+//!
+//! Keys map to [`Action`]s through a [`Keymap`]. The global config file can
+//! rebind an action by listing its keys, such as `"ctrl+n"`, `"G"` or
+//! `"shift+pageup"`; a key bound to a new action is taken away from its old
+//! one. Ctrl-C quits regardless of the keymap.
+
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Something a key can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    NextPane,
+    Up,
+    Down,
+    /// Expand or collapse the selected task
+    Toggle,
+    /// Scroll the task tree
+    PageUp,
+    PageDown,
+    /// Scroll the task logs
+    LogsPageUp,
+    LogsPageDown,
+    Top,
+    Bottom,
+    FirstError,
+    ExpandAll,
+    CollapseAll,
+    /// Follow new log lines
+    AutoScroll,
+    Timeline,
+    /// Type a query to highlight in the logs
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ToggleStdout,
+    ToggleStderr,
+    ToggleSystem,
+    /// Raise the least severe level of system lines shown
+    CycleLevel,
+    /// Keep the logs on the current task while the selection moves
+    Pin,
+    /// Save the log lines shown to a file
+    SaveLogs,
+    /// Inspect the dependencies and dependents of the selected task
+    Dependencies,
+}
+
+impl Action {
+    pub const ALL: [Self; 26] = [
+        Self::Quit,
+        Self::NextPane,
+        Self::Up,
+        Self::Down,
+        Self::Toggle,
+        Self::PageUp,
+        Self::PageDown,
+        Self::LogsPageUp,
+        Self::LogsPageDown,
+        Self::Top,
+        Self::Bottom,
+        Self::FirstError,
+        Self::ExpandAll,
+        Self::CollapseAll,
+        Self::AutoScroll,
+        Self::Timeline,
+        Self::Search,
+        Self::NextMatch,
+        Self::PreviousMatch,
+        Self::ToggleStdout,
+        Self::ToggleStderr,
+        Self::ToggleSystem,
+        Self::CycleLevel,
+        Self::Pin,
+        Self::SaveLogs,
+        Self::Dependencies,
+    ];
+
+    /// Name of the action in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::NextPane => "next_pane",
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Toggle => "toggle",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::LogsPageUp => "logs_page_up",
+            Self::LogsPageDown => "logs_page_down",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::FirstError => "first_error",
+            Self::ExpandAll => "expand_all",
+            Self::CollapseAll => "collapse_all",
+            Self::AutoScroll => "auto_scroll",
+            Self::Timeline => "timeline",
+            Self::Search => "search",
+            Self::NextMatch => "next_match",
+            Self::PreviousMatch => "previous_match",
+            Self::ToggleStdout => "toggle_stdout",
+            Self::ToggleStderr => "toggle_stderr",
+            Self::ToggleSystem => "toggle_system",
+            Self::CycleLevel => "cycle_level",
+            Self::Pin => "pin",
+            Self::SaveLogs => "save_logs",
+            Self::Dependencies => "dependencies",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Quit => &["q"],
+            Self::NextPane => &["tab"],
+            Self::Up => &["up", "k"],
+            Self::Down => &["down", "j"],
+            Self::Toggle => &["space", "enter", "left", "right", "h", "l"],
+            Self::PageUp => &["pageup"],
+            Self::PageDown => &["pagedown"],
+            Self::LogsPageUp => &["shift+pageup"],
+            Self::LogsPageDown => &["shift+pagedown"],
+            Self::Top => &["g"],
+            Self::Bottom => &["G"],
+            Self::FirstError => &["E"],
+            Self::ExpandAll => &["*"],
+            Self::CollapseAll => &["-"],
+            Self::AutoScroll => &["a"],
+            Self::Timeline => &["t"],
+            Self::Search => &["/"],
+            Self::NextMatch => &["n"],
+            Self::PreviousMatch => &["N"],
+            Self::ToggleStdout => &["1"],
+            Self::ToggleStderr => &["2"],
+            Self::ToggleSystem => &["3"],
+            Self::CycleLevel => &["L"],
+            Self::Pin => &["p"],
+            Self::SaveLogs => &["s"],
+            Self::Dependencies => &["d"],
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|action| action.name()).collect();
+                anyhow!(
+                    "Unknown TUI action '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A key together with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Binding matched by a key press
+    ///
+    /// Shift is part of the character for character keys, so `G` and
+    /// `shift+g` are the same binding however the terminal reports them.
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(binding: &str) -> Result<Self> {
+        // A trailing "+" after a separator is the plus key itself
+        let (modifier_names, key) = match binding.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => binding.rsplit_once('+').unwrap_or(("", binding)),
+        };
+
+        let modifiers = modifier_names
+            .split('+')
+            .filter(|name| !name.is_empty())
+            .try_fold(KeyModifiers::NONE, |modifiers, name| {
+                let modifier = match name.to_ascii_lowercase().as_str() {
+                    "ctrl" | "control" => KeyModifiers::CONTROL,
+                    "alt" => KeyModifiers::ALT,
+                    "shift" => KeyModifiers::SHIFT,
+                    _ => bail!("Unknown modifier '{name}' in key '{binding}'"),
+                };
+                Ok(modifiers | modifier)
+            })?;
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => named_key(&key.to_ascii_lowercase())
+                .ok_or_else(|| anyhow!("Unknown key '{binding}'"))?,
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let code = match name {
+        "tab" => KeyCode::Tab,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        _ => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+    };
+    Some(code)
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Which action each key performs
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// Bindings in the order they were defined, so the first key of an
+    /// action is the one shown in the help bar
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .flat_map(|action| {
+                action.default_keys().iter().map(move |key| {
+                    let binding = key.parse().expect("default key bindings are valid");
+                    (binding, action)
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// This keymap with the actions in `overrides` bound to the listed keys
+    /// only; an empty list unbinds the action
+    pub fn with_overrides(mut self, overrides: &HashMap<String, Vec<String>>) -> Result<Self> {
+        for (name, keys) in overrides {
+            let action: Action = name.parse()?;
+            let keys = keys
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<Vec<KeyBinding>>>()?;
+            self.bindings
+                .retain(|(binding, bound)| *bound != action && !keys.contains(binding));
+            self.bindings
+                .extend(keys.into_iter().map(|binding| (binding, action)));
+        }
+        Ok(self)
+    }
+
+    /// Action bound to a key press
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        let pressed = KeyBinding::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(binding, _)| *binding == pressed)
+            .map(|(_, action)| *action)
+    }
+
+    /// First key bound to `action`
+    pub fn primary_key(&self, action: Action) -> Option<KeyBinding> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(binding, _)| *binding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let keymap = Keymap::default();
+        let none = KeyModifiers::NONE;
+
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('j'), none)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('g'), KeyModifiers::SHIFT)),
+            Some(Action::Bottom)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Some(Action::Bottom)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::PageUp, KeyModifiers::SHIFT)),
+            Some(Action::LogsPageUp)
+        );
+        assert_eq!(keymap.action(&press(KeyCode::Char('x'), none)), None);
+        assert_eq!(
+            keymap.primary_key(Action::Toggle).unwrap().to_string(),
+            "Space"
+        );
+    }
+
+    #[test]
+    fn test_overrides_move_keys_between_actions() {
+        let overrides = HashMap::from([
+            (
+                "down".to_string(),
+                vec!["ctrl+n".to_string(), "k".to_string()],
+            ),
+            ("timeline".to_string(), vec![]),
+        ]);
+        let keymap = Keymap::default().with_overrides(&overrides).unwrap();
+
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('k'), KeyModifiers::NONE)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Down, KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Up, KeyModifiers::NONE)),
+            Some(Action::Up)
+        );
+        assert_eq!(keymap.primary_key(Action::Timeline), None);
+        assert_eq!(
+            "ctrl++".parse::<KeyBinding>().unwrap(),
+            KeyBinding::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+
+        let unknown = HashMap::from([("jump".to_string(), vec!["x".to_string()])]);
+        assert!(Keymap::default().with_overrides(&unknown).is_err());
+        let invalid = HashMap::from([("down".to_string(), vec!["hyper+x".to_string()])]);
+        assert!(Keymap::default().with_overrides(&invalid).is_err());
+    }
+}
@@ -5,6 +5,8 @@
 //! - Interactive terminal UI
 //! - Event handling
 //! - Application state management
+//! - Bounded task logs, spilled to disk
+//! - Themes and key bindings from the global config file
 
 pub mod app;
 pub mod components;
@@ -12,14 +14,22 @@ pub mod event_bus;
 pub mod events;
 pub mod fallback;
 pub mod formatters;
+pub mod keymap;
+pub mod log_store;
+pub mod settings;
 pub mod spinner;
 pub mod terminal;
+pub mod theme;
 
 pub use app::*;
 pub use components::*;
 pub use event_bus::*;
 pub use events::*;
 pub use fallback::*;
+pub use keymap::{Action, Keymap};
+pub use log_store::{LogLine, TaskLogs};
+pub use settings::TuiSettings;
 // Only export SpinnerFormatter from spinner to avoid ambiguity
 pub use spinner::SpinnerFormatter;
 pub use terminal::*;
+pub use theme::Theme;
@@ -0,0 +1,452 @@
+//! Bounded storage of a task's log
+//!
+//! A chatty task can log far more than fits in memory, so a task keeps only
+//! its most recent pages of log entries there. Older pages are spilled to an
+//! anonymous temporary file, which goes away with the process, and read back
+//! when the logs pane scrolls to them or searches or saves the log. Each page
+//! counts its lines by stream and level, so the length of the log as the
+//! pane filters it is known without reading anything back.
+
+use crate::events::{LogEntry, LogStream};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::Level;
+
+/// Entries per page
+const PAGE_ENTRIES: usize = 512;
+/// Pages of a task kept in memory, the one being filled included
+const MEMORY_PAGES: usize = 16;
+/// Spilled pages kept once read back, for scrolling through them
+const CACHED_PAGES: usize = 4;
+
+/// Streams and levels lines are counted by
+const CATEGORIES: [(LogStream, Option<Level>); 8] = [
+    (LogStream::Stdout, None),
+    (LogStream::Stderr, None),
+    (LogStream::System, None),
+    (LogStream::System, Some(Level::ERROR)),
+    (LogStream::System, Some(Level::WARN)),
+    (LogStream::System, Some(Level::INFO)),
+    (LogStream::System, Some(Level::DEBUG)),
+    (LogStream::System, Some(Level::TRACE)),
+];
+
+/// A line of a log entry, as the logs pane shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub timestamp: Instant,
+    pub stream: LogStream,
+    pub text: String,
+}
+
+/// The log of a task; clones share it
+#[derive(Clone)]
+pub struct TaskLogs {
+    store: Arc<Mutex<Store>>,
+}
+
+impl Default for TaskLogs {
+    fn default() -> Self {
+        Self::with_limits(PAGE_ENTRIES, MEMORY_PAGES)
+    }
+}
+
+impl fmt::Debug for TaskLogs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskLogs")
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+impl TaskLogs {
+    fn with_limits(page_entries: usize, memory_pages: usize) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(Store {
+                epoch: Instant::now(),
+                page_entries,
+                memory_pages,
+                pages: Vec::new(),
+                first_in_memory: 0,
+                spill: None,
+                spill_end: 0,
+                cache: VecDeque::new(),
+                entries: 0,
+                dropped: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn push(&self, entry: LogEntry) {
+        self.lock().push(entry);
+    }
+
+    /// Entries logged, spilled ones included
+    pub fn len(&self) -> usize {
+        self.lock().entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries lost because their page could not be spilled
+    pub fn dropped(&self) -> usize {
+        self.lock().dropped
+    }
+
+    /// Lines of the entries `shows` keeps
+    pub fn line_count(&self, shows: impl Fn(&LogStream, Option<Level>) -> bool) -> usize {
+        let store = self.lock();
+        store
+            .pages
+            .iter()
+            .map(|page| page.counts.visible(&shows))
+            .sum()
+    }
+
+    /// Up to `count` lines of the entries `shows` keeps, from the `first`
+    ///
+    /// Only the pages holding them are read back.
+    pub fn lines(
+        &self,
+        shows: impl Fn(&LogStream, Option<Level>) -> bool,
+        first: usize,
+        count: usize,
+    ) -> Vec<LogLine> {
+        let (mut index, mut skip) = {
+            let store = self.lock();
+            let mut skip = first;
+            let mut index = 0;
+            while let Some(page) = store.pages.get(index) {
+                let visible = page.counts.visible(&shows);
+                if skip < visible {
+                    break;
+                }
+                skip -= visible;
+                index += 1;
+            }
+            (index, skip)
+        };
+
+        let mut lines = Vec::new();
+        while lines.len() < count && index < self.page_count() {
+            let page = self.page(index).unwrap_or_default();
+            index += 1;
+            let visible = page
+                .iter()
+                .filter(|entry| shows(&entry.stream, entry.level))
+                .flat_map(|entry| entry.content.lines().map(move |text| (entry, text)));
+            for (entry, text) in visible {
+                if skip > 0 {
+                    skip -= 1;
+                } else if lines.len() < count {
+                    lines.push(LogLine {
+                        timestamp: entry.timestamp,
+                        stream: entry.stream.clone(),
+                        text: text.to_string(),
+                    });
+                }
+            }
+        }
+        lines
+    }
+
+    /// Call `visit` with the entries of every page in turn, reading spilled
+    /// ones back one at a time
+    pub fn for_each_page(&self, mut visit: impl FnMut(&[LogEntry])) {
+        let mut index = 0;
+        while index < self.page_count() {
+            if let Some(page) = self.page(index) {
+                visit(&page);
+            }
+            index += 1;
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        self.lock().pages.len()
+    }
+
+    fn page(&self, index: usize) -> Option<Arc<Vec<LogEntry>>> {
+        self.lock().read_page(index)
+    }
+}
+
+struct Store {
+    /// What timestamps of spilled entries are relative to
+    epoch: Instant,
+    page_entries: usize,
+    memory_pages: usize,
+    pages: Vec<Page>,
+    /// Pages before this one are spilled, or dropped
+    first_in_memory: usize,
+    spill: Option<File>,
+    spill_end: u64,
+    /// Spilled pages read back, most recent last
+    cache: VecDeque<(usize, Arc<Vec<LogEntry>>)>,
+    entries: usize,
+    dropped: usize,
+}
+
+struct Page {
+    counts: LineCounts,
+    data: PageData,
+}
+
+enum PageData {
+    Memory(Arc<Vec<LogEntry>>),
+    Spilled { offset: u64, len: usize },
+    Dropped,
+}
+
+impl Store {
+    fn push(&mut self, entry: LogEntry) {
+        let full = match self.pages.last() {
+            Some(Page {
+                data: PageData::Memory(entries),
+                ..
+            }) => entries.len() >= self.page_entries,
+            _ => true,
+        };
+        if full {
+            self.pages.push(Page {
+                counts: LineCounts::default(),
+                data: PageData::Memory(Arc::new(Vec::with_capacity(self.page_entries))),
+            });
+            if self.pages.len() - self.first_in_memory > self.memory_pages {
+                self.spill_oldest();
+            }
+        }
+
+        if let Some(page) = self.pages.last_mut() {
+            page.counts.add(&entry);
+            if let PageData::Memory(entries) = &mut page.data {
+                // Copies the page only while a reader still holds it
+                Arc::make_mut(entries).push(entry);
+            }
+        }
+        self.entries += 1;
+    }
+
+    fn spill_oldest(&mut self) {
+        let index = self.first_in_memory;
+        self.first_in_memory += 1;
+        let data = std::mem::replace(&mut self.pages[index].data, PageData::Dropped);
+        let PageData::Memory(entries) = data else {
+            return;
+        };
+        match self.write_page(&entries) {
+            Ok((offset, len)) => self.pages[index].data = PageData::Spilled { offset, len },
+            Err(_) => {
+                // The log must stay bounded, so the page goes
+                self.dropped += entries.len();
+                self.pages[index].counts = LineCounts::default();
+            }
+        }
+    }
+
+    fn write_page(&mut self, entries: &[LogEntry]) -> io::Result<(u64, usize)> {
+        let bytes = encode(self.epoch, entries);
+        let file = match &mut self.spill {
+            Some(file) => file,
+            spill => spill.insert(tempfile::tempfile()?),
+        };
+        file.seek(SeekFrom::Start(self.spill_end))?;
+        file.write_all(&bytes)?;
+        let offset = self.spill_end;
+        self.spill_end += bytes.len() as u64;
+        Ok((offset, bytes.len()))
+    }
+
+    fn read_page(&mut self, index: usize) -> Option<Arc<Vec<LogEntry>>> {
+        let (offset, len) = match &self.pages.get(index)?.data {
+            PageData::Memory(entries) => return Some(Arc::clone(entries)),
+            PageData::Spilled { offset, len } => (*offset, *len),
+            PageData::Dropped => return None,
+        };
+        if let Some((_, entries)) = self.cache.iter().find(|(cached, _)| *cached == index) {
+            return Some(Arc::clone(entries));
+        }
+
+        let file = self.spill.as_mut()?;
+        let mut bytes = vec![0; len];
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(&mut bytes).ok()?;
+        let entries = Arc::new(decode(self.epoch, &bytes)?);
+        if self.cache.len() == CACHED_PAGES {
+            self.cache.pop_front();
+        }
+        self.cache.push_back((index, Arc::clone(&entries)));
+        Some(entries)
+    }
+}
+
+/// Lines of a page by category of [`CATEGORIES`]
+#[derive(Debug, Default, Clone, Copy)]
+struct LineCounts([usize; CATEGORIES.len()]);
+
+impl LineCounts {
+    fn add(&mut self, entry: &LogEntry) {
+        let category = match (&entry.stream, entry.level) {
+            (LogStream::Stdout, _) => 0,
+            (LogStream::Stderr, _) => 1,
+            (LogStream::System, level) => CATEGORIES
+                .iter()
+                .position(|(stream, category)| *stream == LogStream::System && *category == level)
+                .unwrap_or(2),
+        };
+        self.0[category] += entry.content.lines().count();
+    }
+
+    fn visible(&self, shows: &impl Fn(&LogStream, Option<Level>) -> bool) -> usize {
+        CATEGORIES
+            .iter()
+            .zip(self.0)
+            .filter(|((stream, level), _)| shows(stream, *level))
+            .map(|(_, lines)| lines)
+            .sum()
+    }
+}
+
+/// Entries as timestamp, stream, level and content, one after the other
+fn encode(epoch: Instant, entries: &[LogEntry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for entry in entries {
+        let micros = entry.timestamp.saturating_duration_since(epoch).as_micros() as u64;
+        bytes.extend_from_slice(&micros.to_le_bytes());
+        bytes.push(match entry.stream {
+            LogStream::Stdout => 0,
+            LogStream::Stderr => 1,
+            LogStream::System => 2,
+        });
+        bytes.push(match entry.level {
+            None => 0,
+            Some(level) => CATEGORIES
+                .iter()
+                .position(|(_, category)| *category == Some(level))
+                .unwrap_or(0) as u8,
+        });
+        bytes.extend_from_slice(&(entry.content.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(entry.content.as_bytes());
+    }
+    bytes
+}
+
+fn decode(epoch: Instant, mut bytes: &[u8]) -> Option<Vec<LogEntry>> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = bytes.split_at_checked(len)?;
+        *bytes = rest;
+        Some(taken)
+    }
+
+    let mut entries = Vec::new();
+    while !bytes.is_empty() {
+        let micros = u64::from_le_bytes(take(&mut bytes, 8)?.try_into().ok()?);
+        let header = take(&mut bytes, 2)?;
+        let stream = match header[0] {
+            0 => LogStream::Stdout,
+            1 => LogStream::Stderr,
+            _ => LogStream::System,
+        };
+        let level = CATEGORIES
+            .get(usize::from(header[1]))
+            .and_then(|(_, level)| *level);
+        let len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().ok()?);
+        let content = String::from_utf8_lossy(take(&mut bytes, len as usize)?).into_owned();
+        entries.push(LogEntry {
+            timestamp: epoch + Duration::from_micros(micros),
+            stream,
+            content,
+            level,
+        });
+    }
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(stream: LogStream, level: Option<Level>, content: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Instant::now(),
+            stream,
+            content: content.to_string(),
+            level,
+        }
+    }
+
+    fn all(_: &LogStream, _: Option<Level>) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_spills_and_pages_back() {
+        let logs = TaskLogs::with_limits(4, 2);
+        for n in 0..40 {
+            logs.push(entry(LogStream::Stdout, None, &format!("line {n}")));
+        }
+        logs.push(entry(
+            LogStream::System,
+            Some(Level::WARN),
+            "slow\nvery slow",
+        ));
+
+        {
+            let store = logs.lock();
+            assert_eq!(store.first_in_memory, 9);
+            let in_memory: usize = store
+                .pages
+                .iter()
+                .filter_map(|page| match &page.data {
+                    PageData::Memory(entries) => Some(entries.len()),
+                    _ => None,
+                })
+                .sum();
+            assert_eq!(in_memory, 5);
+        }
+        assert_eq!(logs.len(), 41);
+        assert_eq!(logs.dropped(), 0);
+        assert_eq!(logs.line_count(all), 42);
+        assert_eq!(logs.line_count(|stream, _| *stream == LogStream::System), 2);
+
+        let texts = |lines: Vec<LogLine>| -> Vec<String> {
+            lines.into_iter().map(|line| line.text).collect()
+        };
+        assert_eq!(texts(logs.lines(all, 5, 2)), ["line 5", "line 6"]);
+        assert_eq!(
+            texts(logs.lines(all, 39, 10)),
+            ["line 39", "slow", "very slow"]
+        );
+        let warnings = logs.lines(|stream, _| *stream == LogStream::System, 1, 1);
+        assert_eq!(texts(warnings), ["very slow"]);
+
+        let mut entries = 0;
+        logs.for_each_page(|page| entries += page.len());
+        assert_eq!(entries, 41);
+    }
+
+    #[test]
+    fn test_encoding_round_trips() {
+        let epoch = Instant::now();
+        let entries = vec![
+            entry(LogStream::Stderr, None, "ünïcode\n"),
+            entry(LogStream::System, Some(Level::DEBUG), ""),
+        ];
+        let decoded = decode(epoch, &encode(epoch, &entries)).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].content, "ünïcode\n");
+        assert_eq!(decoded[0].stream, LogStream::Stderr);
+        assert_eq!(decoded[1].level, Some(Level::DEBUG));
+        assert!(decode(epoch, &[1, 2, 3]).is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! TUI settings from the global config file
+//!
+//! The `tui` section of `~/.config/cuenv/config.json` picks a theme,
+//! replaces single colors of it and rebinds keys:
+//!
+//! ```json
+//! {
+//!   "tui": {
+//!     "theme": "color-blind",
+//!     "colors": { "border_focused": "#ffffff" },
+//!     "keybindings": { "down": ["j", "ctrl+n"], "up": ["k", "ctrl+p"] }
+//!   }
+//! }
+//! ```
+
+use crate::keymap::Keymap;
+use crate::theme::Theme;
+use anyhow::{Context, Result};
+use cuenv_utils::xdg::XdgPaths;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The `tui` section of the global config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TuiConfig {
+    /// One of [`crate::theme::THEME_NAMES`]
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Colors replacing those of the theme, by color name
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+    /// Keys of each rebound action, by action name
+    #[serde(default)]
+    pub keybindings: HashMap<String, Vec<String>>,
+}
+
+/// Theme and keymap the TUI runs with
+#[derive(Debug, Clone, Default)]
+pub struct TuiSettings {
+    pub theme: Theme,
+    pub keymap: Keymap,
+}
+
+impl TuiSettings {
+    /// Settings from the global config file; defaults without one
+    pub fn load() -> Result<Self> {
+        Self::from_file(&XdgPaths::config_dir().join("config.json"))
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let config = match file.get("tui") {
+            Some(section) => TuiConfig::deserialize(section)
+                .with_context(|| format!("Invalid tui section in {}", path.display()))?,
+            None => TuiConfig::default(),
+        };
+        Self::from_config(&config)
+            .with_context(|| format!("Invalid tui section in {}", path.display()))
+    }
+
+    pub fn from_config(config: &TuiConfig) -> Result<Self> {
+        let theme = match &config.theme {
+            Some(name) => Theme::named(name)?,
+            None => Theme::default(),
+        };
+        Ok(Self {
+            theme: theme.with_overrides(&config.colors)?,
+            keymap: Keymap::default().with_overrides(&config.keybindings)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Action;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::style::Color;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_settings_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+
+        assert_eq!(
+            TuiSettings::from_file(&path).unwrap().theme,
+            Theme::default()
+        );
+
+        fs::write(
+            &path,
+            r##"{
+                "cache": { "enabled": true },
+                "tui": {
+                    "theme": "high-contrast",
+                    "colors": { "error": "#ff0000" },
+                    "keybindings": { "quit": ["esc"] }
+                }
+            }"##,
+        )
+        .unwrap();
+        let settings = TuiSettings::from_file(&path).unwrap();
+        assert_eq!(settings.theme.error, Color::Rgb(0xff, 0, 0));
+        assert_eq!(settings.theme.success, Color::LightGreen);
+        assert_eq!(
+            settings
+                .keymap
+                .action(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(Action::Quit)
+        );
+
+        fs::write(&path, r#"{ "tui": { "theme": "neon" } }"#).unwrap();
+        let err = TuiSettings::from_file(&path).unwrap_err();
+        assert!(format!("{err:#}").contains("Unknown TUI theme 'neon'"));
+    }
+}
@@ -82,6 +82,7 @@ impl TaskDisplay {
             match self.state {
                 TaskState::Queued => "◌",
                 TaskState::Running => SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()],
+                TaskState::Publishing => "⇪",
                 TaskState::Completed => "✔",
                 TaskState::Failed => "✖",
                 TaskState::Cancelled => "⊘",
@@ -96,6 +97,7 @@ impl TaskDisplay {
             match self.state {
                 TaskState::Queued => Color::DarkGrey,
                 TaskState::Running => Color::Blue,
+                TaskState::Publishing => Color::Cyan,
                 TaskState::Completed => Color::Green,
                 TaskState::Failed => Color::Red,
                 TaskState::Cancelled => Color::DarkRed,
@@ -390,6 +392,12 @@ impl SpinnerFormatter {
                     task.end_time = Some(Instant::now());
                 }
             }
+            TaskEvent::Publishing { task_name } => {
+                if let Some(task) = tasks.get_mut(&task_name) {
+                    task.state = TaskState::Publishing;
+                    task.message = Some("publishing artifacts".to_string());
+                }
+            }
             _ => {}
         }
 
@@ -97,14 +97,11 @@ impl TerminalManager {
         self.event_rx.recv().await
     }
 
-    pub fn should_quit(key: &KeyEvent) -> bool {
+    /// Ctrl-C, which quits whatever the keymap says
+    pub fn is_interrupt(key: &KeyEvent) -> bool {
         matches!(
             key,
             KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::NONE,
-                ..
-            } | KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::CONTROL,
                 ..
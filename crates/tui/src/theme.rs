@@ -0,0 +1,263 @@
+//! Colors of the terminal UI
+//!
+//! Every pane draws with the named colors of a [`Theme`] instead of fixed
+//! ones. Besides the default palette there is a high-contrast palette and a
+//! color-blind-safe palette built on the Okabe-Ito colors, which keeps
+//! success and failure apart for red-green color blindness. Single colors
+//! of a theme can be replaced by name from the global config file.
+
+use crate::events::TaskState;
+use anyhow::{anyhow, bail, Result};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::HashMap;
+
+/// Names of the built-in themes
+pub const THEME_NAMES: [&str; 3] = ["default", "high-contrast", "color-blind"];
+
+/// Names of the colors a theme defines
+pub const COLOR_NAMES: [&str; 17] = [
+    "text",
+    "muted",
+    "border",
+    "border_focused",
+    "accent",
+    "secondary",
+    "success",
+    "warning",
+    "error",
+    "running",
+    "publishing",
+    "cancelled",
+    "highlight",
+    "span_hook",
+    "span_env",
+    "span_secrets",
+    "span_task",
+];
+
+// Okabe-Ito palette
+const ORANGE: Color = Color::Rgb(0xE6, 0x9F, 0x00);
+const SKY_BLUE: Color = Color::Rgb(0x56, 0xB4, 0xE9);
+const BLUISH_GREEN: Color = Color::Rgb(0x00, 0x9E, 0x73);
+const YELLOW: Color = Color::Rgb(0xF0, 0xE4, 0x42);
+const VERMILLION: Color = Color::Rgb(0xD5, 0x5E, 0x00);
+const REDDISH_PURPLE: Color = Color::Rgb(0xCC, 0x79, 0xA7);
+
+/// Named colors used by the panes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Task names, variable values and log output
+    pub text: Color,
+    /// Labels, placeholders, timestamps and the help bar
+    pub muted: Color,
+    pub border: Color,
+    /// Border of the focused pane
+    pub border_focused: Color,
+    /// Environment variable names
+    pub accent: Color,
+    /// Task dependencies
+    pub secondary: Color,
+    pub success: Color,
+    /// System log lines, progress messages and secret references
+    pub warning: Color,
+    /// Failed tasks, stderr and redacted values
+    pub error: Color,
+    pub running: Color,
+    pub publishing: Color,
+    pub cancelled: Color,
+    /// Search matches in the logs
+    pub highlight: Color,
+    pub span_hook: Color,
+    pub span_env: Color,
+    pub span_secrets: Color,
+    pub span_task: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::DarkGray,
+            border: Color::DarkGray,
+            border_focused: Color::Cyan,
+            accent: Color::Cyan,
+            secondary: Color::Blue,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            running: Color::Yellow,
+            publishing: Color::Cyan,
+            cancelled: Color::Magenta,
+            highlight: Color::Yellow,
+            span_hook: Color::Magenta,
+            span_env: Color::Blue,
+            span_secrets: Color::Yellow,
+            span_task: Color::Cyan,
+        }
+    }
+}
+
+impl Theme {
+    /// Bright colors only, for low-contrast terminals and screens
+    pub fn high_contrast() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::Gray,
+            border: Color::Gray,
+            border_focused: Color::LightYellow,
+            accent: Color::LightCyan,
+            secondary: Color::LightBlue,
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            running: Color::LightYellow,
+            publishing: Color::LightCyan,
+            cancelled: Color::LightMagenta,
+            highlight: Color::LightYellow,
+            span_hook: Color::LightMagenta,
+            span_env: Color::LightBlue,
+            span_secrets: Color::LightYellow,
+            span_task: Color::LightCyan,
+        }
+    }
+
+    /// Colors that stay distinct with the common forms of color blindness
+    pub fn color_blind() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::DarkGray,
+            border: Color::DarkGray,
+            border_focused: SKY_BLUE,
+            accent: BLUISH_GREEN,
+            secondary: SKY_BLUE,
+            success: SKY_BLUE,
+            warning: YELLOW,
+            error: VERMILLION,
+            running: ORANGE,
+            publishing: BLUISH_GREEN,
+            cancelled: REDDISH_PURPLE,
+            highlight: YELLOW,
+            span_hook: REDDISH_PURPLE,
+            span_env: SKY_BLUE,
+            span_secrets: YELLOW,
+            span_task: ORANGE,
+        }
+    }
+
+    /// Built-in theme called `name`
+    pub fn named(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Self::default()),
+            "high-contrast" => Ok(Self::high_contrast()),
+            "color-blind" => Ok(Self::color_blind()),
+            _ => bail!(
+                "Unknown TUI theme '{name}', expected one of: {}",
+                THEME_NAMES.join(", ")
+            ),
+        }
+    }
+
+    /// This theme with the colors in `overrides` replaced
+    ///
+    /// Colors are names like `red` or `light-blue`, `#rrggbb` or an index
+    /// into the 256-color palette.
+    pub fn with_overrides(mut self, overrides: &HashMap<String, String>) -> Result<Self> {
+        for (name, value) in overrides {
+            let color = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid color '{value}' for TUI color '{name}'"))?;
+            *self.color_mut(name).ok_or_else(|| {
+                anyhow!(
+                    "Unknown TUI color '{name}', expected one of: {}",
+                    COLOR_NAMES.join(", ")
+                )
+            })? = color;
+        }
+        Ok(self)
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let color = match name {
+            "text" => &mut self.text,
+            "muted" => &mut self.muted,
+            "border" => &mut self.border,
+            "border_focused" => &mut self.border_focused,
+            "accent" => &mut self.accent,
+            "secondary" => &mut self.secondary,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "running" => &mut self.running,
+            "publishing" => &mut self.publishing,
+            "cancelled" => &mut self.cancelled,
+            "highlight" => &mut self.highlight,
+            "span_hook" => &mut self.span_hook,
+            "span_env" => &mut self.span_env,
+            "span_secrets" => &mut self.span_secrets,
+            "span_task" => &mut self.span_task,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    pub fn state_color(&self, state: &TaskState) -> Color {
+        match state {
+            TaskState::Queued => self.muted,
+            TaskState::Running => self.running,
+            TaskState::Publishing => self.publishing,
+            TaskState::Completed => self.success,
+            TaskState::Failed => self.error,
+            TaskState::Cancelled => self.cancelled,
+        }
+    }
+
+    /// Style of the selected row of a list
+    pub fn selected(&self) -> Style {
+        Style::default()
+            .fg(self.text)
+            .add_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::REVERSED)
+    }
+
+    pub fn border_style(&self, focused: bool) -> Style {
+        Style::default().fg(if focused {
+            self.border_focused
+        } else {
+            self.border
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_single_colors() {
+        let overrides = HashMap::from([
+            ("error".to_string(), "#ff5555".to_string()),
+            ("muted".to_string(), "light-black".to_string()),
+        ]);
+        let theme = Theme::named("high-contrast")
+            .unwrap()
+            .with_overrides(&overrides)
+            .unwrap();
+
+        assert_eq!(theme.error, Color::Rgb(0xff, 0x55, 0x55));
+        assert_eq!(theme.muted, Color::DarkGray);
+        assert_eq!(theme.success, Color::LightGreen);
+        assert_eq!(theme.state_color(&TaskState::Failed), theme.error);
+    }
+
+    #[test]
+    fn test_invalid_names_are_rejected() {
+        assert!(Theme::named("solarized").is_err());
+
+        let unknown = HashMap::from([("failure".to_string(), "red".to_string())]);
+        let err = Theme::default().with_overrides(&unknown).unwrap_err();
+        assert!(err.to_string().contains("Unknown TUI color 'failure'"));
+
+        let invalid = HashMap::from([("error".to_string(), "reddish".to_string())]);
+        assert!(Theme::default().with_overrides(&invalid).is_err());
+    }
+}
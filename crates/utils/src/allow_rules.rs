@@ -0,0 +1,254 @@
+//! Glob-pattern allow and deny rules for directories
+//!
+//! Rules complement the exact-directory allow list. They are stored in a
+//! versioned JSON file so the format can evolve without breaking older
+//! installations:
+//!
+//! ```json
+//! {"version": 1, "rules": [{"pattern": "~/work/**", "action": "allow"}]}
+//! ```
+//!
+//! Deny rules always win over allow rules and exact allow entries.
+
+use crate::atomic_file::write_atomic_string;
+use crate::xdg::XdgPaths;
+use cuenv_core::{Error, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Current version of the rules file format
+pub const ALLOW_RULES_VERSION: u32 = 1;
+
+/// Whether a rule grants or revokes permission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+/// A single pattern rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowRule {
+    /// Glob pattern; a leading `~` is expanded to the home directory
+    pub pattern: String,
+    pub action: RuleAction,
+}
+
+/// Outcome of evaluating rules for a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleDecision {
+    Allowed,
+    Denied,
+    /// No rule matched; fall back to the exact allow list
+    Unmatched,
+}
+
+/// The versioned set of pattern rules
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowRules {
+    pub version: u32,
+    #[serde(default)]
+    pub rules: Vec<AllowRule>,
+}
+
+impl Default for AllowRules {
+    fn default() -> Self {
+        Self {
+            version: ALLOW_RULES_VERSION,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl AllowRules {
+    /// Default location of the rules file
+    pub fn default_path() -> PathBuf {
+        XdgPaths::allow_rules_file()
+    }
+
+    /// Load rules from `path`, returning an empty set if the file is missing
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content =
+            fs::read_to_string(path).map_err(|e| Error::file_system(path, "read rules file", e))?;
+        let rules: Self = serde_json::from_str(&content).map_err(|e| Error::Json {
+            message: format!("invalid allow rules file {}", path.display()),
+            source: e,
+        })?;
+
+        if rules.version > ALLOW_RULES_VERSION {
+            return Err(Error::configuration(format!(
+                "Allow rules file {} has version {}, but this cuenv only supports version {ALLOW_RULES_VERSION}",
+                path.display(),
+                rules.version
+            )));
+        }
+
+        Ok(rules)
+    }
+
+    /// Persist the rules to `path`, upgrading the version marker
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::file_system(parent, "create rules directory", e))?;
+        }
+
+        let rules = Self {
+            version: ALLOW_RULES_VERSION,
+            rules: self.rules.clone(),
+        };
+        let content = serde_json::to_string_pretty(&rules).map_err(|e| Error::Json {
+            message: "failed to serialize allow rules".to_string(),
+            source: e,
+        })?;
+        write_atomic_string(path, &format!("{content}\n"))
+    }
+
+    /// Add a rule, replacing any existing rule for the same pattern
+    pub fn add(&mut self, pattern: &str, action: RuleAction) -> Result<()> {
+        compile(pattern)?;
+        self.rules.retain(|rule| rule.pattern != pattern);
+        self.rules.push(AllowRule {
+            pattern: pattern.to_string(),
+            action,
+        });
+        Ok(())
+    }
+
+    /// Remove the rule for `pattern`; returns whether one was removed
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|rule| rule.pattern != pattern);
+        self.rules.len() != before
+    }
+
+    /// Evaluate the rules for an (already canonical) directory
+    pub fn evaluate(&self, dir: &Path) -> RuleDecision {
+        if self.matching(dir, RuleAction::Deny).is_some() {
+            RuleDecision::Denied
+        } else if self.matching(dir, RuleAction::Allow).is_some() {
+            RuleDecision::Allowed
+        } else {
+            RuleDecision::Unmatched
+        }
+    }
+
+    /// The first rule with `action` whose pattern matches `dir`
+    pub fn matching(&self, dir: &Path, action: RuleAction) -> Option<&AllowRule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.action == action)
+            .find(|rule| compile(&rule.pattern).is_ok_and(|matcher| matcher.is_match(dir)))
+    }
+}
+
+/// Compile a rule pattern, expanding `~` to the home directory
+///
+/// `*` stays within one path component; only `**` crosses directories.
+fn compile(pattern: &str) -> Result<GlobMatcher> {
+    let expanded = expand_home(pattern);
+    GlobBuilder::new(&expanded)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| Error::configuration(format!("Invalid pattern '{pattern}': {e}")))
+}
+
+fn expand_home(pattern: &str) -> String {
+    match (pattern.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{rest}", home.display())
+        }
+        _ => pattern.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_evaluate_deny_wins() {
+        let mut rules = AllowRules::default();
+        rules.add("/work/**", RuleAction::Allow).unwrap();
+        rules.add("/work/untrusted/**", RuleAction::Deny).unwrap();
+
+        assert_eq!(
+            rules.evaluate(Path::new("/work/project")),
+            RuleDecision::Allowed
+        );
+        assert_eq!(
+            rules.evaluate(Path::new("/work/untrusted/x")),
+            RuleDecision::Denied
+        );
+        assert_eq!(
+            rules.evaluate(Path::new("/elsewhere")),
+            RuleDecision::Unmatched
+        );
+    }
+
+    #[test]
+    fn test_star_stays_within_a_component() {
+        let mut rules = AllowRules::default();
+        rules.add("/work/*", RuleAction::Allow).unwrap();
+
+        assert_eq!(
+            rules.evaluate(Path::new("/work/project")),
+            RuleDecision::Allowed
+        );
+        assert_eq!(
+            rules.evaluate(Path::new("/work/project/nested")),
+            RuleDecision::Unmatched
+        );
+    }
+
+    #[test]
+    fn test_add_replaces_and_remove() {
+        let mut rules = AllowRules::default();
+        rules.add("/a/**", RuleAction::Allow).unwrap();
+        rules.add("/a/**", RuleAction::Deny).unwrap();
+        assert_eq!(rules.rules.len(), 1);
+        assert_eq!(rules.rules[0].action, RuleAction::Deny);
+
+        assert!(rules.remove("/a/**"));
+        assert!(!rules.remove("/a/**"));
+        assert!(rules.add("/a/[", RuleAction::Allow).is_err());
+    }
+
+    #[test]
+    fn test_home_expansion() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let mut rules = AllowRules::default();
+        rules.add("~/work/**", RuleAction::Allow).unwrap();
+        assert_eq!(
+            rules.evaluate(&home.join("work").join("app")),
+            RuleDecision::Allowed
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("nested").join("allow-rules.json");
+
+        assert_eq!(AllowRules::load(&path).unwrap(), AllowRules::default());
+
+        let mut rules = AllowRules::default();
+        rules.add("/srv/**", RuleAction::Allow).unwrap();
+        rules.save(&path).unwrap();
+        assert_eq!(AllowRules::load(&path).unwrap(), rules);
+
+        fs::write(&path, r#"{"version": 99, "rules": []}"#).unwrap();
+        assert!(AllowRules::load(&path).is_err());
+    }
+}
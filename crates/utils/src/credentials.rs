@@ -0,0 +1,436 @@
+//! Credentials for the services cuenv talks to
+//!
+//! A credential belongs to a backend: the `host[:port]` of a remote cache,
+//! publish endpoint or hook URL, or the name of a secret provider. It is
+//! looked up in order in
+//!
+//! 1. the environment variable the caller names, if any
+//! 2. the credentials file in cuenv's config directory
+//! 3. the OS keychain, through `security` on macOS and `secret-tool`
+//!    (libsecret) on Linux
+//! 4. `~/.netrc`, or the file `NETRC` names, whose `password` for the
+//!    backend's host is the token
+//!
+//! `cuenv login` stores tokens in the keychain, or in the credentials file,
+//! readable by the user only, where no keychain is available. Tokens found
+//! are masked in all output.
+
+use crate::xdg::XdgPaths;
+use cuenv_core::redaction::global_redactor;
+use cuenv_core::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Keychain service the tokens of `cuenv login` are stored under
+pub const KEYCHAIN_SERVICE: &str = "cuenv";
+
+/// Environment variable naming the netrc file to read
+pub const NETRC_VAR: &str = "NETRC";
+
+/// Where a credential was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialSource {
+    Environment(String),
+    ConfigFile,
+    Keychain,
+    Netrc,
+}
+
+impl std::fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Environment(var) => write!(f, "${var}"),
+            Self::ConfigFile => write!(f, "{}", credentials_file().display()),
+            Self::Keychain => write!(f, "the OS keychain"),
+            Self::Netrc => write!(f, "netrc"),
+        }
+    }
+}
+
+/// A token and where it came from
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credential {
+    pub token: String,
+    pub source: CredentialSource,
+}
+
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credential")
+            .field("token", &"***")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// Where to look for the credential of a backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialLookup {
+    backend: String,
+    env_var: Option<String>,
+}
+
+impl CredentialLookup {
+    pub fn new(backend: impl Into<String>) -> Self {
+        Self {
+            backend: backend.into(),
+            env_var: None,
+        }
+    }
+
+    /// Lookup for the host `url` points at
+    pub fn for_url(url: &str) -> Self {
+        Self::new(backend_of_url(url))
+    }
+
+    /// Take the token from `var` first, when it is set
+    pub fn with_env_var(mut self, var: impl Into<String>) -> Self {
+        self.env_var = Some(var.into());
+        self
+    }
+
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// The first credential found for the backend
+    pub fn resolve(&self) -> Option<Credential> {
+        let credential = self.find()?;
+        global_redactor().register_secret(&credential.token);
+        Some(credential)
+    }
+
+    fn find(&self) -> Option<Credential> {
+        let found = |token: String, source| Some(Credential { token, source });
+        if let Some(var) = &self.env_var {
+            if let Some(token) = std::env::var(var).ok().filter(|t| !t.trim().is_empty()) {
+                return found(
+                    token.trim().to_string(),
+                    CredentialSource::Environment(var.clone()),
+                );
+            }
+        }
+        if let Some(token) = load_credentials_file(&credentials_file())
+            .ok()
+            .and_then(|mut tokens| tokens.remove(&self.backend))
+        {
+            return found(token, CredentialSource::ConfigFile);
+        }
+        if let Some(token) = keychain::lookup(&self.backend) {
+            return found(token, CredentialSource::Keychain);
+        }
+        let netrc = netrc_path().and_then(|path| fs::read_to_string(path).ok())?;
+        netrc_token(&netrc, host_of(&self.backend))
+            .and_then(|token| found(token, CredentialSource::Netrc))
+    }
+}
+
+/// Store the token of `backend` in the keychain, or in the credentials file
+/// when there is none or it fails, returning where it went
+pub fn store(backend: &str, token: &str) -> Result<CredentialSource> {
+    // Masked before any error could quote it
+    global_redactor().register_secret(token);
+    match keychain::store(backend, token) {
+        Ok(true) => return Ok(CredentialSource::Keychain),
+        Ok(false) => {}
+        Err(e) => {
+            tracing::warn!("Could not store in the keychain, using the credentials file: {e}")
+        }
+    }
+    store_in_credentials_file(&credentials_file(), backend, token)?;
+    Ok(CredentialSource::ConfigFile)
+}
+
+/// The backend of a URL: its host, and its port when one is given
+pub fn backend_of_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            _ => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// File holding the tokens of `cuenv login` where no keychain is available
+pub fn credentials_file() -> PathBuf {
+    XdgPaths::config_dir().join("credentials.json")
+}
+
+/// The netrc file in use: `NETRC`, or else `~/.netrc`
+pub fn netrc_path() -> Option<PathBuf> {
+    std::env::var_os(NETRC_VAR)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".netrc")))
+}
+
+fn host_of(backend: &str) -> &str {
+    backend.split_once(':').map_or(backend, |(host, _)| host)
+}
+
+fn load_credentials_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| Error::file_system(path, "read credentials file", e))?;
+    serde_json::from_str(&content).map_err(|e| Error::Json {
+        message: format!("invalid credentials file {}", path.display()),
+        source: e,
+    })
+}
+
+/// Write the token into the file, created readable by the user only
+fn store_in_credentials_file(path: &Path, backend: &str, token: &str) -> Result<()> {
+    let mut tokens = load_credentials_file(path)?;
+    tokens.insert(backend.to_string(), token.to_string());
+    let content = serde_json::to_string_pretty(&tokens).map_err(|e| Error::Json {
+        message: "failed to serialize credentials".to_string(),
+        source: e,
+    })?;
+
+    let parent = path
+        .parent()
+        .ok_or_else(|| Error::configuration("Invalid credentials file path"))?;
+    fs::create_dir_all(parent)
+        .map_err(|e| Error::file_system(parent, "create config directory", e))?;
+    let temp_path = parent.join(format!(".credentials.{}.tmp", uuid::Uuid::new_v4()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(format!("{content}\n").as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    written.map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        Error::file_system(path, "write credentials file", e)
+    })
+}
+
+/// The `password` netrc gives for `machine`, or for `default`
+fn netrc_token(netrc: &str, machine: &str) -> Option<String> {
+    #[derive(PartialEq)]
+    enum Entry {
+        Other,
+        Wanted,
+        Default,
+    }
+
+    let mut words = netrc.split_whitespace();
+    let mut entry = Entry::Other;
+    let mut default = None;
+    while let Some(word) = words.next() {
+        match word {
+            "machine" => {
+                entry = if words.next() == Some(machine) {
+                    Entry::Wanted
+                } else {
+                    Entry::Other
+                }
+            }
+            "default" => entry = Entry::Default,
+            "password" => {
+                let password = words.next()?;
+                match entry {
+                    Entry::Wanted => return Some(password.to_string()),
+                    Entry::Default => default = default.or(Some(password.to_string())),
+                    Entry::Other => {}
+                }
+            }
+            "login" | "account" => {
+                words.next();
+            }
+            // Macros run until a blank line, which splitting on whitespace
+            // loses; nothing after one is read
+            "macdef" => break,
+            _ => {}
+        }
+    }
+    default
+}
+
+/// The platform keychain, through its command line tool
+mod keychain {
+    use super::*;
+
+    /// The token stored for `backend`, if the keychain is available and has one
+    pub fn lookup(backend: &str) -> Option<String> {
+        let output = lookup_command(backend)?
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let token = String::from_utf8(output.stdout).ok()?;
+        let token = token.trim_end_matches(['\r', '\n']);
+        (output.status.success() && !token.is_empty()).then(|| token.to_string())
+    }
+
+    /// Store the token, returning false when no keychain is available
+    pub fn store(backend: &str, token: &str) -> Result<bool> {
+        let Some((program, args, input)) = store_command(backend, token) else {
+            return Ok(false);
+        };
+        let child = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(Error::command_execution(program, args, e.to_string(), None)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).map_err(|e| {
+                Error::command_execution(program, args.clone(), e.to_string(), None)
+            })?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::command_execution(program, args.clone(), e.to_string(), None))?;
+        if !output.status.success() {
+            return Err(Error::command_execution(
+                program,
+                args,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                output.status.code(),
+            ));
+        }
+        Ok(true)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn lookup_command(backend: &str) -> Option<Command> {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYCHAIN_SERVICE,
+            "-a",
+            backend,
+            "-w",
+        ]);
+        Some(command)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn lookup_command(backend: &str) -> Option<Command> {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYCHAIN_SERVICE, "backend", backend]);
+        Some(command)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn lookup_command(_backend: &str) -> Option<Command> {
+        None
+    }
+
+    /// Program, arguments and standard input storing the token
+    #[cfg(target_os = "macos")]
+    fn store_command(backend: &str, token: &str) -> Option<(&'static str, Vec<String>, String)> {
+        // `security` only reads the password from its arguments or the terminal
+        let args = [
+            "add-generic-password",
+            "-U",
+            "-s",
+            KEYCHAIN_SERVICE,
+            "-a",
+            backend,
+            "-w",
+            token,
+        ];
+        Some((
+            "security",
+            args.iter().map(|a| a.to_string()).collect(),
+            String::new(),
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn store_command(backend: &str, token: &str) -> Option<(&'static str, Vec<String>, String)> {
+        let label = format!("--label=cuenv credential for {backend}");
+        let args = [
+            label.as_str(),
+            "service",
+            KEYCHAIN_SERVICE,
+            "backend",
+            backend,
+        ];
+        Some((
+            "secret-tool",
+            std::iter::once("store")
+                .chain(args)
+                .map(str::to_string)
+                .collect(),
+            token.to_string(),
+        ))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn store_command(_backend: &str, _token: &str) -> Option<(&'static str, Vec<String>, String)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_netrc_token_by_machine_and_default() {
+        let netrc = "machine cache.example.com login ci password s3cret\n\
+                     default login anon password fallback\n";
+        assert_eq!(
+            netrc_token(netrc, "cache.example.com").as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(
+            netrc_token(netrc, "other.example.com").as_deref(),
+            Some("fallback")
+        );
+        assert_eq!(netrc_token("machine a password b", "c"), None);
+    }
+
+    #[test]
+    fn test_backend_of_url() {
+        assert_eq!(
+            backend_of_url("https://cache.example.com/cas"),
+            "cache.example.com"
+        );
+        assert_eq!(backend_of_url("http://localhost:8080"), "localhost:8080");
+        assert_eq!(backend_of_url("vault"), "vault");
+        assert_eq!(host_of("localhost:8080"), "localhost");
+    }
+
+    #[test]
+    fn test_credentials_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cuenv").join("credentials.json");
+        store_in_credentials_file(&path, "cache.example.com", "one").unwrap();
+        store_in_credentials_file(&path, "vault", "two").unwrap();
+
+        let tokens = load_credentials_file(&path).unwrap();
+        assert_eq!(tokens["cache.example.com"], "one");
+        assert_eq!(tokens["vault"], "two");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
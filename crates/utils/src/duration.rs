@@ -0,0 +1,61 @@
+//! Human-friendly duration parsing (`30s`, `15m`, `12h`, `7d`, `2w`)
+
+use cuenv_core::{Error, Result};
+use std::time::Duration;
+
+/// Parse a duration made of a positive integer followed by a unit suffix
+///
+/// Supported units are `s`, `m`, `h`, `d` and `w`. A bare number is
+/// interpreted as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| Error::configuration(format!("Invalid duration '{input}'")))?;
+
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(Error::configuration(format!(
+                "Invalid duration unit '{unit}' in '{input}'. Use one of: s, m, h, d, w"
+            )))
+        }
+    };
+
+    Ok(Duration::from_secs(value.saturating_mul(multiplier)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::from_secs(43_200));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604_800));
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_garbage() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("-1d").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Shell hook latency measurement
+//!
+//! Every hook invocation appends its duration to a small log next to the
+//! hooks status file, trimmed to the most recent invocations now and then,
+//! so `cuenv env status --hook-latency` can report how much the hook adds to
+//! each prompt.
+
+use crate::atomic_file::write_atomic_string;
+use crate::paths::get_cuenv_temp_dir;
+use cuenv_core::{Error, Result};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of invocations kept
+pub const HOOK_LATENCY_CAPACITY: usize = 200;
+
+/// p95 above which the hook is reported as slow
+pub const SLOW_HOOK_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// One hook invocation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookLatencySample {
+    /// Unix timestamp of the invocation
+    pub timestamp: u64,
+    /// Time the hook took, in microseconds
+    pub duration_us: u64,
+    /// Directory the hook ran in
+    pub directory: PathBuf,
+}
+
+impl HookLatencySample {
+    pub fn new(directory: &Path, duration: Duration) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            duration_us: u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_micros(self.duration_us)
+    }
+}
+
+/// Persistent log of recent hook invocations, one JSON sample per line
+#[derive(Debug, Clone)]
+pub struct HookLatencyLog {
+    path: PathBuf,
+    capacity: usize,
+}
+
+impl HookLatencyLog {
+    pub fn new(path: PathBuf, capacity: usize) -> Self {
+        Self { path, capacity }
+    }
+
+    /// The log shared by all shells of the current user
+    pub fn for_user() -> Self {
+        Self::new(
+            get_cuenv_temp_dir().join("hook-latency.jsonl"),
+            HOOK_LATENCY_CAPACITY,
+        )
+    }
+
+    /// Recorded samples, oldest first; unreadable lines are skipped and a
+    /// missing log is empty
+    pub fn load(&self) -> Vec<HookLatencySample> {
+        let content = fs::read_to_string(&self.path).unwrap_or_default();
+        let samples: Vec<HookLatencySample> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let skip = samples.len().saturating_sub(self.capacity);
+        samples.into_iter().skip(skip).collect()
+    }
+
+    /// Append a sample, trimming the log once it holds about twice the
+    /// capacity
+    ///
+    /// The hook must not wait on other shells, so the sample is dropped
+    /// while another one is recording.
+    pub fn record(&self, sample: HookLatencySample) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::file_system(parent, "create hook latency directory", e))?;
+        }
+        let lock_path = self.path.with_extension("lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| Error::file_system(&lock_path, "open hook latency lock", e))?;
+        if lock.try_lock_exclusive().is_err() {
+            return Ok(());
+        }
+
+        let line = serde_json::to_string(&sample).map_err(|e| Error::Json {
+            message: "Failed to serialize hook latency sample".to_string(),
+            source: e,
+        })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| Error::file_system(&self.path, "open hook latency log", e))?;
+        writeln!(file, "{line}")
+            .map_err(|e| Error::file_system(&self.path, "append to hook latency log", e))?;
+
+        let len = file
+            .metadata()
+            .map_err(|e| Error::file_system(&self.path, "read hook latency log metadata", e))?
+            .len();
+        let limit = u64::try_from(2 * self.capacity * (line.len() + 1)).unwrap_or(u64::MAX);
+        if len > limit {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the log with only the samples `load` keeps
+    fn compact(&self) -> Result<()> {
+        let mut content = String::new();
+        for sample in self.load() {
+            let line = serde_json::to_string(&sample).map_err(|e| Error::Json {
+                message: "Failed to serialize hook latency sample".to_string(),
+                source: e,
+            })?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        write_atomic_string(&self.path, &content)
+    }
+}
+
+/// Percentiles over a set of hook invocations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookLatencySummary {
+    pub count: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// Directory with the highest median latency
+    pub slowest_directory: Option<PathBuf>,
+}
+
+impl HookLatencySummary {
+    /// Summary of the last `last` samples; `None` when there are none
+    pub fn of(samples: &[HookLatencySample], last: usize) -> Option<Self> {
+        let recent = &samples[samples.len().saturating_sub(last)..];
+        let durations: Vec<Duration> = recent.iter().map(HookLatencySample::duration).collect();
+
+        let mut by_directory: HashMap<&Path, Vec<Duration>> = HashMap::new();
+        for sample in recent {
+            by_directory
+                .entry(&sample.directory)
+                .or_default()
+                .push(sample.duration());
+        }
+        let slowest_directory = by_directory
+            .into_iter()
+            .filter_map(|(dir, durations)| Some((percentile(durations, 50)?, dir)))
+            .max()
+            .map(|(_, dir)| dir.to_path_buf());
+
+        Some(Self {
+            count: durations.len(),
+            p50: percentile(durations.clone(), 50)?,
+            p95: percentile(durations.clone(), 95)?,
+            max: durations.into_iter().max()?,
+            slowest_directory,
+        })
+    }
+
+    pub fn is_slow(&self) -> bool {
+        self.p95 > SLOW_HOOK_THRESHOLD
+    }
+}
+
+/// Nearest-rank percentile
+fn percentile(mut durations: Vec<Duration>, pct: usize) -> Option<Duration> {
+    durations.sort_unstable();
+    let rank = (durations.len() * pct).div_ceil(100).max(1);
+    durations.get(rank - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample(dir: &str, ms: u64) -> HookLatencySample {
+        HookLatencySample::new(Path::new(dir), Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn test_log_keeps_the_most_recent_samples() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("latency.jsonl");
+        let log = HookLatencyLog::new(path.clone(), 3);
+        for ms in 1..=20 {
+            log.record(sample("/a", ms)).unwrap();
+        }
+
+        let durations: Vec<u64> = log.load().iter().map(|s| s.duration_us / 1000).collect();
+        assert_eq!(durations, [18, 19, 20]);
+        // The log is trimmed rather than growing with every invocation
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!((3..=7).contains(&lines), "{lines} lines");
+    }
+
+    #[test]
+    fn test_record_skips_while_another_shell_records() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("latency.jsonl");
+        let log = HookLatencyLog::new(path.clone(), 3);
+        let held = fs::File::create(path.with_extension("lock")).unwrap();
+        held.lock_exclusive().unwrap();
+
+        log.record(sample("/a", 1)).unwrap();
+        assert!(log.load().is_empty());
+    }
+
+    #[test]
+    fn test_summary_percentiles_and_slowest_directory() {
+        let samples: Vec<_> = (1..=100)
+            .map(|ms| sample(if ms % 10 == 0 { "/slow" } else { "/fast" }, ms))
+            .collect();
+
+        let summary = HookLatencySummary::of(&samples, 100).unwrap();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50, Duration::from_millis(50));
+        assert_eq!(summary.p95, Duration::from_millis(95));
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert_eq!(summary.slowest_directory, Some(PathBuf::from("/slow")));
+        assert!(!summary.is_slow());
+
+        let last = HookLatencySummary::of(&samples, 10).unwrap();
+        assert_eq!(last.p50, Duration::from_millis(95));
+        assert!(HookLatencySummary::of(&[], 10).is_none());
+    }
+}
@@ -4,6 +4,7 @@ use crate::paths::{
     ensure_state_dir_exists, ensure_status_dir_exists, get_hooks_status_file_path,
     get_hooks_status_file_path_for_dir,
 };
+use crate::state_prune::process_alive;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -101,6 +102,19 @@ impl HooksStatus {
         }
     }
 
+    /// Whether the status claims hooks are running though neither they nor
+    /// the supervisor that owns them are
+    pub fn is_orphaned(&self) -> bool {
+        let mut unfinished = self
+            .hooks
+            .values()
+            .filter(|h| matches!(h.status, HookState::Running | HookState::Pending))
+            .peekable();
+        unfinished.peek().is_some()
+            && self.supervisor_pid.is_none_or(|pid| !process_alive(pid))
+            && unfinished.all(|h| h.pid.is_none_or(|pid| !process_alive(pid)))
+    }
+
     /// Check if there are actually running hooks
     pub fn has_actually_running_hooks(&self) -> bool {
         self.hooks.values().any(|h| {
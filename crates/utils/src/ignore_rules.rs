@@ -0,0 +1,155 @@
+//! `.gitignore`-style rules for skipping paths when walking a project
+//!
+//! Rules come from a `.cuenvignore` file at the root being walked, on top of
+//! defaults that skip `.git`, `node_modules` and `target`. The syntax is the
+//! subset of `.gitignore` that matters for skipping trees:
+//!
+//! ```text
+//! # Comments and blank lines are ignored
+//! dist/          # a trailing slash only matches directories
+//! /build         # a leading or inner slash anchors to the root
+//! *.generated    # otherwise the pattern matches at any depth
+//! !target/       # negation re-includes what an earlier rule ignored
+//! ```
+//!
+//! The last matching rule wins, and nothing below an ignored directory can
+//! be re-included.
+
+use cuenv_core::{Error, Result, CUENV_IGNORE_FILENAME};
+use globset::{GlobBuilder, GlobMatcher};
+use std::fs;
+use std::path::Path;
+
+/// Directories skipped unless a `.cuenvignore` re-includes them
+pub const DEFAULT_IGNORES: &[&str] = &[".git/", "node_modules/", "target/"];
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Ordered ignore rules, evaluated against paths relative to their root
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self::parse(&DEFAULT_IGNORES.join("\n")).unwrap_or(Self { rules: Vec::new() })
+    }
+}
+
+impl IgnoreRules {
+    /// Defaults followed by the rules of `root/.cuenvignore`, if present
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CUENV_IGNORE_FILENAME);
+        let mut rules = Self::default();
+        if path.is_file() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| Error::file_system(&path, "read ignore file", e))?;
+            rules.rules.extend(Self::parse(&content)?.rules);
+        }
+        Ok(rules)
+    }
+
+    /// Parse rules in `.gitignore` syntax, without the defaults
+    pub fn parse(content: &str) -> Result<Self> {
+        let rules = content
+            .lines()
+            .filter_map(parse_line)
+            .map(|(pattern, negated, dir_only)| {
+                let matcher = GlobBuilder::new(&pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        Error::configuration(format!("Invalid ignore pattern '{pattern}': {e}"))
+                    })?
+                    .compile_matcher();
+                Ok(IgnoreRule {
+                    matcher,
+                    negated,
+                    dir_only,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether `path`, relative to the root, is ignored, either itself or
+    /// through one of its parent directories
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        path.ancestors()
+            .skip(1)
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .any(|parent| self.matches(parent, true))
+            || self.matches(path, is_dir)
+    }
+
+    /// Whether the last rule matching `path` itself ignores it
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// Split a line into its glob, whether it is negated and whether it only
+/// matches directories
+fn parse_line(line: &str) -> Option<(String, bool, bool)> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (line, negated) = match line.strip_prefix('!') {
+        Some(rest) => (rest, true),
+        None => (line.strip_prefix('\\').unwrap_or(line), false),
+    };
+    let (line, dir_only) = match line.strip_suffix('/') {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+    if line.is_empty() {
+        return None;
+    }
+    let pattern = if line.contains('/') {
+        line.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{line}")
+    };
+    Some((pattern, negated, dir_only))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_rules() {
+        let mut rules = IgnoreRules::default();
+        rules.rules.extend(
+            IgnoreRules::parse("# vendored\n/build\ndist/\n*.log\n!target/\n")
+                .unwrap()
+                .rules,
+        );
+        let ignored = |path: &str, is_dir| rules.is_ignored(Path::new(path), is_dir);
+
+        assert!(ignored("node_modules", true));
+        assert!(ignored("web/node_modules/pkg/env.cue", false));
+        assert!(ignored(".git", true));
+        // Re-included by negation
+        assert!(!ignored("target", true));
+        // Anchored to the root
+        assert!(ignored("build", true));
+        assert!(!ignored("web/build", true));
+        // Directory-only
+        assert!(ignored("web/dist", true));
+        assert!(!ignored("dist", false));
+        assert!(ignored("logs/app.log", false));
+        assert!(!ignored("services/api/env.cue", false));
+    }
+}
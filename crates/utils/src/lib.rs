@@ -4,29 +4,40 @@
 //! the cuenv workspace. All functions here are designed to be pure and
 //! side-effect free where possible.
 
+pub mod allow_rules;
 pub mod atomic_file;
 pub mod cleanup;
 pub mod compression;
+pub mod credentials;
 pub mod directory;
 pub mod directory_lock;
+pub mod duration;
 pub mod file_times;
+pub mod hook_latency;
 pub mod hooks_status;
+pub mod ignore_rules;
 pub mod limits;
 pub mod memory;
 pub mod network;
 pub mod paths;
+pub mod portable_path;
 pub mod resilience;
+pub mod state_bundle;
+pub mod state_prune;
 pub mod sync;
 pub mod tracing;
 pub mod xdg;
 
+pub use allow_rules::{AllowRule, AllowRules, RuleAction, RuleDecision};
 pub use atomic_file::*;
 pub use cleanup::*;
 pub use compression::*;
 pub use directory::*;
 pub use directory_lock::DirectoryLock;
+pub use duration::parse_duration;
 pub use file_times::*;
 pub use hooks_status::*;
+pub use ignore_rules::{IgnoreRules, DEFAULT_IGNORES};
 pub use limits::*;
 pub use memory::*;
 // Re-export specific network items to avoid conflicts with resilience module
@@ -36,6 +47,9 @@ pub use network::retry::{
     RetryConfig as NetworkRetryConfig,
 };
 pub use paths::*;
+pub use portable_path::{
+    normalize_portable_path, path_lists_equivalent, split_path_list, PathCasePolicy,
+};
 pub use resilience::*;
 pub use sync::*;
 pub use tracing::*;
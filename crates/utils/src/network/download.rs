@@ -0,0 +1,116 @@
+//! Resumable HTTP downloads
+//!
+//! A download is written to `<dest>.part` first. When a transfer is cut off,
+//! the next attempt asks the server for the remaining bytes only and appends
+//! them; a server that ignores the range request sends the whole file again.
+//! The partial file is renamed to `dest` once complete.
+
+use crate::resilience::policy::{self, Subsystem};
+use cuenv_core::{Error, Result};
+use reqwest::{header, Client, Proxy, StatusCode};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Download `url` to `dest`, resuming an earlier partial download and
+/// retrying transient failures under the retry policy of `subsystem`
+///
+/// Without an explicit `proxy`, the `HTTP_PROXY`, `HTTPS_PROXY` and
+/// `NO_PROXY` variables apply.
+pub async fn download_resumable(
+    url: &str,
+    dest: &Path,
+    proxy: Option<&str>,
+    subsystem: Subsystem,
+) -> Result<()> {
+    let client = build_client(url, proxy)?;
+    let partial = partial_path(dest);
+    policy::call(subsystem, || download_once(&client, url, &partial)).await?;
+    fs::rename(&partial, dest)
+        .await
+        .map_err(|e| Error::file_system(dest, "rename download", e))
+}
+
+/// Where the download to `dest` is kept until it completes
+pub fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+fn build_client(url: &str, proxy: Option<&str>) -> Result<Client> {
+    let builder = Client::builder().connect_timeout(CONNECT_TIMEOUT);
+    let builder = match proxy {
+        Some(proxy) => builder.proxy(
+            Proxy::all(proxy)
+                .map_err(|e| Error::configuration(format!("Invalid proxy '{proxy}': {e}")))?,
+        ),
+        None => builder,
+    };
+    builder
+        .build()
+        .map_err(|e| Error::network(url, e.to_string()))
+}
+
+async fn download_once(client: &Client, url: &str, partial: &Path) -> Result<()> {
+    let offset = fs::metadata(partial)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let request = client.get(url);
+    let request = if offset > 0 {
+        request.header(header::RANGE, format!("bytes={offset}-"))
+    } else {
+        request
+    };
+
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| Error::network(url, e.to_string()))?;
+    let append = match response.status() {
+        StatusCode::PARTIAL_CONTENT => true,
+        // The partial file already holds the whole download
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(()),
+        status if status.is_success() => false,
+        status
+            if status.is_client_error()
+                && status != StatusCode::TOO_MANY_REQUESTS
+                && status != StatusCode::REQUEST_TIMEOUT =>
+        {
+            return Err(Error::configuration(format!(
+                "Download of {url} failed with HTTP {status}"
+            )));
+        }
+        status => {
+            return Err(Error::network(
+                url,
+                format!("download failed with HTTP {status}"),
+            ))
+        }
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(partial)
+        .await
+        .map_err(|e| Error::file_system(partial, "open download", e))?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| Error::network(url, e.to_string()))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| Error::file_system(partial, "write download", e))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| Error::file_system(partial, "write download", e))
+}
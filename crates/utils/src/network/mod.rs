@@ -6,10 +6,15 @@
 //!
 //! ## Key Components
 //!
+//! - **`download`**: Resumable HTTP downloads that pick up where an
+//!   interrupted transfer stopped.
 //! - **`rate_limit`**: Implements various rate-limiting strategies, such as
 //!   token bucket and sliding window, to control the frequency of operations.
 //! - **`retry`**: Offers flexible, exponential backoff retry mechanisms to
 //!   robustly handle temporary errors.
+//! - **`wait`**: Polls TCP and HTTP services until they are up.
 
+pub mod download;
 pub mod rate_limit;
 pub mod retry;
+pub mod wait;
@@ -99,7 +99,7 @@ impl RetryableError for Error {
             // File system errors might be transient
             Error::FileSystem { source, .. } => source.is_retryable(),
             // Network/secret resolution errors are often transient
-            Error::SecretResolution { .. } => true,
+            Error::SecretResolution { .. } | Error::Network { .. } | Error::Timeout { .. } => true,
             // Other errors are not retryable by default
             _ => false,
         }
@@ -0,0 +1,162 @@
+//! Waiting for services to come up
+//!
+//! A TCP target is ready once a connection to it succeeds, an HTTP target
+//! once a GET request returns a success status. Targets are polled until
+//! they are ready or the timeout runs out; the error then carries the reason
+//! the last attempt failed.
+
+use cuenv_core::{Error, Result};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// Time between two attempts
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a target that is not ready yet is reported
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest a single attempt may take
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A service to wait for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitTarget {
+    /// `host:port` accepting connections
+    Tcp(String),
+    /// URL answering with a success status
+    Http(String),
+}
+
+impl fmt::Display for WaitTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "tcp {address}"),
+            Self::Http(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+/// Wait until `target` is ready, for at most `timeout`
+///
+/// `report` is called with the reason the target is not ready yet after the
+/// first failed attempt and then every [`REPORT_INTERVAL`]. Returns the time
+/// it took.
+pub async fn wait_until_ready<F, Fut>(
+    target: &WaitTarget,
+    timeout: Duration,
+    mut report: F,
+) -> Result<Duration>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let client = match target {
+        WaitTarget::Http(url) => Some(
+            reqwest::Client::builder()
+                .timeout(ATTEMPT_TIMEOUT)
+                .build()
+                .map_err(|e| Error::network(url, e.to_string()))?,
+        ),
+        WaitTarget::Tcp(_) => None,
+    };
+
+    let started = Instant::now();
+    let mut attempts = 0u32;
+    let mut last_report: Option<Instant> = None;
+    loop {
+        attempts += 1;
+        let limit = timeout
+            .saturating_sub(started.elapsed())
+            .min(ATTEMPT_TIMEOUT);
+        let reason = match attempt(target, client.as_ref(), limit).await {
+            Ok(()) => return Ok(started.elapsed()),
+            Err(reason) => reason,
+        };
+
+        if started.elapsed() + POLL_INTERVAL >= timeout {
+            return Err(Error::network(
+                target.to_string(),
+                format!(
+                    "not ready after {}s ({attempts} attempts): {reason}",
+                    timeout.as_secs()
+                ),
+            ));
+        }
+        if last_report.is_none_or(|at| at.elapsed() >= REPORT_INTERVAL) {
+            last_report = Some(Instant::now());
+            report(reason).await;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// One attempt at reaching `target`, failing with the reason it is not ready
+async fn attempt(
+    target: &WaitTarget,
+    client: Option<&reqwest::Client>,
+    limit: Duration,
+) -> std::result::Result<(), String> {
+    match (target, client) {
+        (WaitTarget::Tcp(address), _) => {
+            match tokio::time::timeout(limit, TcpStream::connect(address.as_str())).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("connection timed out".to_string()),
+            }
+        }
+        (WaitTarget::Http(url), Some(client)) => {
+            match tokio::time::timeout(limit, client.get(url).send()).await {
+                Ok(Ok(response)) if response.status().is_success() => Ok(()),
+                Ok(Ok(response)) => Err(format!("HTTP {}", response.status())),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("request timed out".to_string()),
+            }
+        }
+        (WaitTarget::Http(_), None) => Err("no HTTP client".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_target_is_ready_once_listening() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = WaitTarget::Tcp(listener.local_addr().unwrap().to_string());
+
+        let waited = wait_until_ready(&target, Duration::from_secs(5), |_| async {})
+            .await
+            .unwrap();
+        assert!(waited < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_target_fails_with_last_reason() {
+        // Bind then drop a listener to find a port nothing listens on
+        let address = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let target = WaitTarget::Tcp(address.clone());
+
+        let mut reports = Vec::new();
+        let err = wait_until_ready(&target, Duration::from_secs(1), |reason| {
+            reports.push(reason);
+            async {}
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains(&address), "unexpected error: {err}");
+        assert!(
+            err.contains("not ready after 1s"),
+            "unexpected error: {err}"
+        );
+        assert_eq!(reports.len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 //! Path utilities for cuenv-specific file locations
 
+use crate::xdg::XdgPaths;
 use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use std::{env, fs};
@@ -84,12 +85,18 @@ pub fn get_directory_hash(dir: &Path) -> String {
     full_hash.chars().take(16).collect()
 }
 
+/// Get the directory holding the per-directory state directories
+///
+/// This is the relocated state directory when `CUENV_STATE_DIR` or the
+/// project's `stateDir` setting moves it, and otherwise lives under the
+/// temporary directory.
+pub fn get_state_root() -> PathBuf {
+    XdgPaths::state_dir_override().unwrap_or_else(|| get_cuenv_temp_dir().join("state"))
+}
+
 /// Get the state directory for a specific directory
 pub fn get_state_dir(directory: &Path) -> PathBuf {
-    let base_dir = get_cuenv_temp_dir();
-    let dir_hash = get_directory_hash(directory);
-
-    base_dir.join("state").join(dir_hash)
+    get_state_root().join(get_directory_hash(directory))
 }
 
 /// Get the hooks status file path for a specific directory
@@ -107,17 +114,69 @@ pub fn get_captured_env_path(directory: &Path) -> PathBuf {
     get_state_dir(directory).join("captured_env.json")
 }
 
+/// Get the environment history file path for a specific directory
+pub fn get_env_history_path(directory: &Path) -> PathBuf {
+    get_state_dir(directory).join("env_history.json")
+}
+
+/// Get the file holding the report of the last audited run of `task`
+pub fn get_audit_report_path(directory: &Path, task: &str) -> PathBuf {
+    // Task names may hold `:` and `/`; anything else is spelled out in hex
+    let name: String = task
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_string()
+            } else {
+                format!("%{:x}", u32::from(c))
+            }
+        })
+        .collect();
+    get_state_dir(directory)
+        .join("audit")
+        .join(format!("{name}.json"))
+}
+
+/// Get the file holding the environment `cuenv env use` chose for a shell session
+pub fn get_session_environment_path(session: &str) -> PathBuf {
+    get_state_root().join("sessions").join(session)
+}
+
+/// Get the project-local cache of archives downloaded by fetch hooks
+pub fn get_fetch_cache_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(".cuenv").join("fetch")
+}
+
+/// Get the file recording when each scheduled task of a project was last due
+///
+/// Unlike the per-directory state, this is kept in the XDG state directory
+/// so that runs missed while the machine was off can be caught up.
+pub fn get_scheduler_state_path(project_dir: &Path) -> PathBuf {
+    XdgPaths::state_dir()
+        .join("scheduler")
+        .join(format!("{}.json", get_directory_hash(project_dir)))
+}
+
 /// Ensure the state directory exists for a specific directory
+///
+/// The directory it belongs to is recorded in it, so state of directories
+/// that were deleted can be pruned.
 pub fn ensure_state_dir_exists(directory: &Path) -> std::io::Result<()> {
     let state_dir = get_state_dir(directory);
-    fs::create_dir_all(state_dir)?;
+    fs::create_dir_all(&state_dir)?;
+    let origin = state_dir.join(crate::state_prune::STATE_ORIGIN_FILE);
+    if !origin.exists() {
+        let canonical = directory
+            .canonicalize()
+            .unwrap_or_else(|_| directory.to_path_buf());
+        fs::write(origin, canonical.to_string_lossy().as_bytes())?;
+    }
     Ok(())
 }
 
 /// Get all active state directories (directories with hook status)
 pub fn get_all_state_dirs() -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
-    let base_dir = get_cuenv_temp_dir();
-    let state_root = base_dir.join("state");
+    let state_root = get_state_root();
 
     let mut result = Vec::new();
 
@@ -156,6 +215,12 @@ mod tests {
         assert!(ensure_status_dir_exists().is_ok());
     }
 
+    #[test]
+    fn test_audit_report_path() {
+        let path = get_audit_report_path(Path::new("/project"), "db:migrate/up");
+        assert!(path.ends_with("audit/db%3amigrate%2fup.json"));
+    }
+
     #[test]
     fn test_cuenv_temp_dir() {
         let path = get_cuenv_temp_dir();
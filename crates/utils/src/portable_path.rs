@@ -0,0 +1,266 @@
+//! Platform-independent path normalization
+//!
+//! Cache keys and environment diffs must not depend on which operating
+//! system produced a path. The helpers here turn Windows and Unix spellings
+//! of a path into one canonical, forward-slash form without touching the
+//! filesystem:
+//!
+//! - `C:\work\app` and `C:/work/app` become `/c/work/app`
+//! - `\\server\share\app` becomes `//server/share/app`
+//! - verbatim prefixes (`\\?\C:\app`, `\\?\UNC\server\share`) are stripped
+//! - `.` and `..` components are resolved lexically
+
+/// How letter case is treated when normalizing paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathCasePolicy {
+    /// Keep the original case of every component
+    Preserve,
+    /// Lowercase paths that are Windows-style (drive letter or UNC), since
+    /// those filesystems are case-insensitive. Unix paths are left untouched.
+    #[default]
+    FoldWindows,
+    /// Lowercase every path
+    FoldAll,
+}
+
+/// The root a normalized path hangs off
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Root {
+    /// No root: a relative path
+    Relative,
+    /// `/` on Unix-like systems
+    Unix,
+    /// A Windows drive letter, stored lowercased
+    Drive(char),
+    /// A UNC `\\server\share` prefix
+    Unc { server: String, share: String },
+}
+
+impl Root {
+    fn is_windows(&self) -> bool {
+        matches!(self, Self::Drive(_) | Self::Unc { .. })
+    }
+}
+
+/// Normalize a path string into a platform-independent, forward-slash form
+///
+/// Relative paths stay relative; leading `..` components that cannot be
+/// resolved are kept. An empty input normalizes to `.`.
+pub fn normalize_portable_path(path: &str, policy: PathCasePolicy) -> String {
+    let unified = path.replace('\\', "/");
+    let (root, rest) = split_root(&unified);
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => match components.last() {
+                Some(&last) if last != ".." => {
+                    components.pop();
+                }
+                _ if root == Root::Relative => components.push(component),
+                // `..` above an absolute root is ignored
+                _ => {}
+            },
+            _ => components.push(component),
+        }
+    }
+
+    let joined = components.join("/");
+    let rendered = match &root {
+        Root::Relative if joined.is_empty() => ".".to_string(),
+        Root::Relative => joined,
+        Root::Unix => format!("/{joined}"),
+        Root::Drive(letter) if joined.is_empty() => format!("/{letter}"),
+        Root::Drive(letter) => format!("/{letter}/{joined}"),
+        Root::Unc { server, share } if joined.is_empty() => format!("//{server}/{share}"),
+        Root::Unc { server, share } => format!("//{server}/{share}/{joined}"),
+    };
+
+    let fold = match policy {
+        PathCasePolicy::Preserve => false,
+        PathCasePolicy::FoldWindows => root.is_windows(),
+        PathCasePolicy::FoldAll => true,
+    };
+
+    if fold {
+        rendered.to_lowercase()
+    } else {
+        rendered
+    }
+}
+
+/// Split a `PATH`-style list into its entries
+///
+/// Lists containing `;`, or whose first entry starts with a drive letter,
+/// are treated as Windows lists; everything else is split on `:`. Empty
+/// entries are dropped.
+pub fn split_path_list(value: &str) -> Vec<&str> {
+    let separator = if value.contains(';') || drive_letter(value).is_some() {
+        ';'
+    } else {
+        ':'
+    };
+
+    value
+        .split(separator)
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Check whether two `PATH`-style lists name the same directories in order
+pub fn path_lists_equivalent(a: &str, b: &str, policy: PathCasePolicy) -> bool {
+    let left = split_path_list(a);
+    let right = split_path_list(b);
+
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(&right)
+            .all(|(l, r)| normalize_portable_path(l, policy) == normalize_portable_path(r, policy))
+}
+
+/// Separate the root from the remainder of a forward-slash path
+fn split_root(path: &str) -> (Root, &str) {
+    // Verbatim prefixes: `//?/UNC/server/share` and `//?/C:/...`
+    let path = if let Some(rest) = path.strip_prefix("//?/") {
+        match rest.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("UNC/") => {
+                return split_unc(&rest[4..]);
+            }
+            _ => rest,
+        }
+    } else {
+        path
+    };
+
+    if let Some(letter) = drive_letter(path) {
+        return (Root::Drive(letter), &path[2..]);
+    }
+
+    if let Some(rest) = path.strip_prefix("//") {
+        if !rest.starts_with('/') {
+            return split_unc(rest);
+        }
+    }
+
+    if let Some(rest) = path.strip_prefix('/') {
+        (Root::Unix, rest)
+    } else {
+        (Root::Relative, path)
+    }
+}
+
+fn split_unc(rest: &str) -> (Root, &str) {
+    let mut parts = rest.splitn(3, '/');
+    let server = parts.next().unwrap_or_default();
+    let share = parts.next().unwrap_or_default();
+    let remainder = parts.next().unwrap_or_default();
+
+    if server.is_empty() {
+        return (Root::Unix, rest);
+    }
+
+    (
+        Root::Unc {
+            server: server.to_string(),
+            share: share.to_string(),
+        },
+        remainder,
+    )
+}
+
+fn drive_letter(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    let letter = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    match chars.next() {
+        None | Some('/') | Some('\\') => Some(letter.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norm(path: &str) -> String {
+        normalize_portable_path(path, PathCasePolicy::FoldWindows)
+    }
+
+    #[test]
+    fn test_unix_paths() {
+        assert_eq!(norm("/project"), "/project");
+        assert_eq!(norm("/project/./src/"), "/project/src");
+        assert_eq!(norm("/tmp/../project"), "/project");
+        assert_eq!(norm("/../.."), "/");
+        assert_eq!(norm("/Project"), "/Project");
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        assert_eq!(norm("src/../lib"), "lib");
+        assert_eq!(norm("../lib"), "../lib");
+        assert_eq!(norm(""), ".");
+        assert_eq!(norm("."), ".");
+    }
+
+    #[test]
+    fn test_drive_letters() {
+        assert_eq!(norm(r"C:\Work\App"), "/c/work/app");
+        assert_eq!(norm("c:/work/app/"), "/c/work/app");
+        assert_eq!(norm(r"D:\"), "/d");
+        assert_eq!(norm(r"C:\work\..\other"), "/c/other");
+    }
+
+    #[test]
+    fn test_unc_paths() {
+        assert_eq!(norm(r"\\Server\Share\Dir"), "//server/share/dir");
+        assert_eq!(norm(r"\\server\share"), "//server/share");
+        assert_ne!(norm(r"\\server\share\dir"), norm("/server/share/dir"));
+    }
+
+    #[test]
+    fn test_verbatim_prefixes() {
+        assert_eq!(norm(r"\\?\C:\Work"), norm(r"C:\work"));
+        assert_eq!(
+            norm(r"\\?\UNC\server\share\dir"),
+            norm(r"\\server\share\dir")
+        );
+    }
+
+    #[test]
+    fn test_case_policies() {
+        assert_eq!(
+            normalize_portable_path(r"C:\Work", PathCasePolicy::Preserve),
+            "/c/Work"
+        );
+        assert_eq!(
+            normalize_portable_path("/Work", PathCasePolicy::FoldAll),
+            "/work"
+        );
+    }
+
+    #[test]
+    fn test_path_lists() {
+        assert_eq!(split_path_list("/usr/bin:/bin"), vec!["/usr/bin", "/bin"]);
+        assert_eq!(
+            split_path_list(r"C:\bin;D:\tools;"),
+            vec![r"C:\bin", r"D:\tools"]
+        );
+        assert_eq!(split_path_list(r"C:\bin"), vec![r"C:\bin"]);
+
+        assert!(path_lists_equivalent(
+            r"C:\Bin;C:\Tools",
+            "c:/bin;c:/tools/",
+            PathCasePolicy::FoldWindows
+        ));
+        assert!(!path_lists_equivalent(
+            "/usr/bin:/bin",
+            "/bin:/usr/bin",
+            PathCasePolicy::FoldWindows
+        ));
+    }
+}
@@ -1,11 +1,14 @@
 //! Core types and enums for circuit breaker functionality.
 
 use cuenv_core::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::sync::Arc;
 use std::time::Instant;
 
 /// Circuit breaker states
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CircuitState {
     /// Circuit is closed - requests pass through normally
     Closed,
@@ -15,6 +18,16 @@ pub enum CircuitState {
     HalfOpen,
 }
 
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        })
+    }
+}
+
 /// Which errors should trigger a retry
 #[derive(Clone)]
 pub enum RetryOn {
@@ -7,10 +7,14 @@
 //!
 //! - **`circuit`**: Implements the circuit breaker pattern to prevent
 //!   repeatedly calling a service that is known to be failing.
+//! - **`policy`**: The retry and circuit breaker policy of each
+//!   network-facing subsystem, configurable in the global config file.
 
 pub mod circuit;
+pub mod policy;
 
 pub use circuit::{
     retry, retry_with_circuit_breaker, suggest_recovery, CircuitBreaker, CircuitBreakerConfig,
     CircuitBreakerStats, CircuitState, RetryConfig, RetryOn,
 };
+pub use policy::{Policy, Subsystem};
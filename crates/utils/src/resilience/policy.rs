@@ -0,0 +1,369 @@
+//! Retry and circuit breaker policy of each network-facing subsystem
+//!
+//! Secret providers, the remote cache, hooks that download, task server
+//! connections and self-update all retry through [`call`], each with its own
+//! [`Policy`] and circuit breaker. The `retry` section of the global config file
+//! (`~/.config/cuenv/config.json`) overrides the defaults per subsystem:
+//!
+//! ```json
+//! {
+//!   "retry": {
+//!     "remote_cache": { "max_retries": 8, "max_delay_ms": 60000 },
+//!     "secrets": { "failure_threshold": 3, "break_duration_secs": 120 }
+//!   }
+//! }
+//! ```
+//!
+//! Circuits that open or close are recorded in the state directory, so
+//! `cuenv doctor` can show them from another process.
+
+use super::circuit::{
+    retry_with_circuit_breaker, CircuitBreaker, CircuitBreakerConfig, CircuitState, RetryConfig,
+    RetryOn,
+};
+use crate::atomic_file::write_atomic_string;
+use crate::network::retry::RetryableError;
+use crate::xdg::XdgPaths;
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A subsystem that talks to something outside cuenv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Subsystem {
+    /// Secret providers and bulk secret imports
+    Secrets,
+    /// The remote cache
+    RemoteCache,
+    /// Fetch hooks and hooks run from a URL
+    Hooks,
+    /// Connections to external task servers
+    TaskServer,
+    /// Release downloads of `cuenv self-update`
+    Updates,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Secrets,
+        Subsystem::RemoteCache,
+        Subsystem::Hooks,
+        Subsystem::TaskServer,
+        Subsystem::Updates,
+    ];
+
+    /// Name of the subsystem in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::Secrets => "secrets",
+            Subsystem::RemoteCache => "remote_cache",
+            Subsystem::Hooks => "hooks",
+            Subsystem::TaskServer => "task_server",
+            Subsystem::Updates => "updates",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|subsystem| subsystem.name() == name)
+    }
+
+    /// Policy the subsystem runs with unless the config file overrides it
+    pub fn default_policy(self) -> Policy {
+        let retry = match self {
+            Subsystem::Secrets => RetryConfig {
+                max_retries: 4,
+                base_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(30),
+                jitter_factor: 0.2,
+                retry_on: retryable(),
+            },
+            Subsystem::RemoteCache | Subsystem::Hooks | Subsystem::Updates => RetryConfig {
+                retry_on: retryable(),
+                ..RetryConfig::for_network()
+            },
+            Subsystem::TaskServer => RetryConfig {
+                max_retries: 3,
+                base_delay: Duration::from_millis(100),
+                max_delay: Duration::from_secs(2),
+                jitter_factor: 0.1,
+                retry_on: retryable(),
+            },
+        };
+        let circuit = match self {
+            Subsystem::Secrets | Subsystem::Hooks => CircuitBreakerConfig {
+                failure_threshold: 5,
+                success_threshold: 2,
+                timeout: Duration::from_secs(300),
+                break_duration: Duration::from_secs(60),
+                half_open_max_calls: 3,
+            },
+            Subsystem::RemoteCache | Subsystem::TaskServer | Subsystem::Updates => {
+                CircuitBreakerConfig::default()
+            }
+        };
+        Policy { retry, circuit }
+    }
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Retry the errors [`RetryableError`] considers transient
+fn retryable() -> RetryOn {
+    RetryOn::Custom(Arc::new(|error: &Error| error.is_retryable()))
+}
+
+/// How a subsystem retries and when its circuit opens
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub retry: RetryConfig,
+    pub circuit: CircuitBreakerConfig,
+}
+
+/// A subsystem's entry in the `retry` section of the config file
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyOverrides {
+    pub max_retries: Option<usize>,
+    pub base_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    /// Failures that open the circuit
+    pub failure_threshold: Option<usize>,
+    /// How long an open circuit rejects calls
+    pub break_duration_secs: Option<u64>,
+}
+
+impl PolicyOverrides {
+    pub fn apply(&self, mut policy: Policy) -> Policy {
+        if let Some(max_retries) = self.max_retries {
+            policy.retry.max_retries = max_retries;
+        }
+        if let Some(ms) = self.base_delay_ms {
+            policy.retry.base_delay = Duration::from_millis(ms);
+        }
+        if let Some(ms) = self.max_delay_ms {
+            policy.retry.max_delay = Duration::from_millis(ms);
+        }
+        if let Some(threshold) = self.failure_threshold {
+            policy.circuit.failure_threshold = threshold;
+        }
+        if let Some(secs) = self.break_duration_secs {
+            policy.circuit.break_duration = Duration::from_secs(secs);
+        }
+        policy
+    }
+}
+
+/// The global config file holding the `retry` section
+pub fn config_file() -> PathBuf {
+    XdgPaths::config_dir().join("config.json")
+}
+
+/// Policy of every subsystem, with the overrides of the config file at
+/// `path`; defaults without one
+pub fn load_policies(path: &Path) -> Result<BTreeMap<Subsystem, Policy>> {
+    let mut policies: BTreeMap<Subsystem, Policy> = Subsystem::ALL
+        .into_iter()
+        .map(|subsystem| (subsystem, subsystem.default_policy()))
+        .collect();
+    if !path.exists() {
+        return Ok(policies);
+    }
+
+    let content =
+        fs::read_to_string(path).map_err(|e| Error::file_system(path, "read config file", e))?;
+    let file: serde_json::Value = serde_json::from_str(&content).map_err(|e| Error::Json {
+        message: format!("Failed to parse {}", path.display()),
+        source: e,
+    })?;
+    let Some(section) = file.get("retry") else {
+        return Ok(policies);
+    };
+    let overrides: BTreeMap<String, PolicyOverrides> = serde_json::from_value(section.clone())
+        .map_err(|e| Error::Json {
+            message: format!("Invalid retry section in {}", path.display()),
+            source: e,
+        })?;
+
+    for (name, overrides) in overrides {
+        let subsystem = Subsystem::from_name(&name).ok_or_else(|| {
+            let known: Vec<_> = Subsystem::ALL.iter().map(|s| s.name()).collect();
+            Error::configuration(format!(
+                "Unknown subsystem '{name}' in the retry section of {}; expected one of: {}",
+                path.display(),
+                known.join(", ")
+            ))
+        })?;
+        let policy = subsystem.default_policy();
+        policies.insert(subsystem, overrides.apply(policy));
+    }
+    Ok(policies)
+}
+
+/// Policies and circuit breakers of this process
+struct Registry {
+    policies: BTreeMap<Subsystem, Policy>,
+    breakers: BTreeMap<Subsystem, CircuitBreaker>,
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let policies = load_policies(&config_file()).unwrap_or_else(|e| {
+            log::warn!("Using the default retry policies: {e}");
+            Subsystem::ALL
+                .into_iter()
+                .map(|subsystem| (subsystem, subsystem.default_policy()))
+                .collect()
+        });
+        let breakers = policies
+            .iter()
+            .map(|(subsystem, policy)| (*subsystem, CircuitBreaker::new(policy.circuit.clone())))
+            .collect();
+        Registry { policies, breakers }
+    })
+}
+
+/// Policy `subsystem` runs with in this process
+pub fn policy_for(subsystem: Subsystem) -> Policy {
+    registry().policies[&subsystem].clone()
+}
+
+/// Run `operation`, retrying it under the policy of `subsystem` behind the
+/// subsystem's circuit breaker
+pub async fn call<F, Fut, T>(subsystem: Subsystem, operation: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let registry = registry();
+    let breaker = &registry.breakers[&subsystem];
+    let before = breaker.state().await;
+    let result =
+        retry_with_circuit_breaker(&registry.policies[&subsystem].retry, breaker, operation).await;
+
+    let after = breaker.state().await;
+    if after != before {
+        if let Err(e) = record_state(&circuits_file(), subsystem, after) {
+            log::warn!("Failed to record the {subsystem} circuit state: {e}");
+        }
+    }
+    result
+}
+
+/// Circuit state a cuenv process recorded for a subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedCircuit {
+    pub state: CircuitState,
+    /// Unix time of the change, in seconds
+    pub changed_at: u64,
+}
+
+/// File circuit state changes are recorded in
+pub fn circuits_file() -> PathBuf {
+    XdgPaths::state_dir().join("circuits.json")
+}
+
+/// Record in `path` that the circuit of `subsystem` is now `state`
+pub fn record_state(path: &Path, subsystem: Subsystem, state: CircuitState) -> Result<()> {
+    let mut circuits = recorded_states(path);
+    let changed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    circuits.insert(
+        subsystem.name().to_string(),
+        RecordedCircuit { state, changed_at },
+    );
+    let content = serde_json::to_string_pretty(&circuits).map_err(|e| Error::Json {
+        message: "Failed to serialize circuit states".to_string(),
+        source: e,
+    })?;
+    write_atomic_string(path, &content)
+}
+
+/// Last circuit state recorded in `path`, by subsystem name
+pub fn recorded_states(path: &Path) -> BTreeMap<String, RecordedCircuit> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_file_overrides_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        fs::write(
+            &path,
+            r#"{"tui": {}, "retry": {"remote_cache": {"max_retries": 8, "break_duration_secs": 5}}}"#,
+        )
+        .unwrap();
+
+        let policies = load_policies(&path).unwrap();
+        let remote = &policies[&Subsystem::RemoteCache];
+        assert_eq!(remote.retry.max_retries, 8);
+        assert_eq!(remote.circuit.break_duration, Duration::from_secs(5));
+        assert_eq!(
+            policies[&Subsystem::Secrets].retry.max_retries,
+            Subsystem::Secrets.default_policy().retry.max_retries
+        );
+
+        fs::write(&path, r#"{"retry": {"ftp": {"max_retries": 1}}}"#).unwrap();
+        let error = load_policies(&path).unwrap_err().to_string();
+        assert!(error.contains("Unknown subsystem 'ftp'"), "{error}");
+    }
+
+    #[test]
+    fn test_recorded_states_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state").join("circuits.json");
+        assert!(recorded_states(&path).is_empty());
+
+        record_state(&path, Subsystem::Hooks, CircuitState::Open).unwrap();
+        record_state(&path, Subsystem::Secrets, CircuitState::Closed).unwrap();
+        let states = recorded_states(&path);
+        assert_eq!(states["hooks"].state, CircuitState::Open);
+        assert_eq!(states["secrets"].state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_call_retries_transient_errors_only() {
+        let attempts = AtomicUsize::new(0);
+        let result = call(Subsystem::TaskServer, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(Error::network("task-server", "connection refused"))
+            } else {
+                Ok("connected")
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let attempts = AtomicUsize::new(0);
+        let result: Result<()> = call(Subsystem::TaskServer, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Error::configuration("bad request"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,492 @@
+//! Bundles of cuenv's state, to start containers warm
+//!
+//! A bundle is a zstd-compressed tar archive of the state a fresh machine
+//! would otherwise have to build up: directory approvals (`approvals/`), the
+//! user's global configuration (`overrides/`) and the cache (`cache/`), after
+//! a `manifest.json` describing it. Credentials, values cached from secret
+//! stores and the state of shells are never bundled, and a bundle holding
+//! any file but these is refused. Importing merges approvals with those
+//! already there and replaces the other files.
+
+use crate::allow_rules::AllowRules;
+use crate::xdg::XdgPaths;
+use cuenv_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Version of the bundle layout this build writes and reads
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const ALLOWED: &str = "allow";
+const DENIED: &str = "deny";
+const ALLOW_RULES: &str = "allow-rules.json";
+const GLOBAL_CONFIG: &str = "config.json";
+/// Directory of the cache holding values imported from secret stores
+const SECRET_IMPORTS: &str = "imports";
+
+/// A part of the state a bundle can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    /// Allowed and denied directories and the allow rules
+    Approvals,
+    /// The global configuration file
+    Overrides,
+    /// Evaluated configurations, hook environments and task results
+    Cache,
+}
+
+impl Section {
+    pub const ALL: [Section; 3] = [Section::Approvals, Section::Overrides, Section::Cache];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Approvals => "approvals",
+            Section::Overrides => "overrides",
+            Section::Cache => "cache",
+        }
+    }
+
+    /// The section called `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|section| section.name() == name)
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What a bundle holds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format_version: u32,
+    pub cuenv_version: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub sections: Vec<Section>,
+    pub files: usize,
+}
+
+/// The directories the state lives in
+#[derive(Debug, Clone)]
+pub struct StateDirs {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+}
+
+impl StateDirs {
+    /// The directories of this machine, with any relocation applied
+    pub fn current() -> Self {
+        Self {
+            config_dir: XdgPaths::config_dir(),
+            data_dir: XdgPaths::data_dir(),
+            cache_dir: XdgPaths::cache_dir(),
+        }
+    }
+
+    /// Whether a bundle may hold the file named `name` in `section`
+    fn bundles(section: Section, name: &str) -> bool {
+        match section {
+            Section::Approvals => [ALLOWED, DENIED, ALLOW_RULES].contains(&name),
+            Section::Overrides => name == GLOBAL_CONFIG,
+            Section::Cache => name.split('/').next() != Some(SECRET_IMPORTS),
+        }
+    }
+
+    /// Where the file named `name` in `section` of a bundle lives
+    fn path(&self, section: Section, name: &str) -> PathBuf {
+        match (section, name) {
+            (Section::Approvals, ALLOW_RULES) => self.config_dir.join(name),
+            (Section::Approvals, _) => self.data_dir.join(name),
+            (Section::Overrides, _) => self.config_dir.join(name),
+            (Section::Cache, _) => self.cache_dir.join(name),
+        }
+    }
+
+    /// The files of `section` on this machine, by their name in a bundle
+    fn files(&self, section: Section) -> Vec<(String, PathBuf)> {
+        let names: &[&str] = match section {
+            Section::Approvals => &[ALLOWED, DENIED, ALLOW_RULES],
+            Section::Overrides => &[GLOBAL_CONFIG],
+            Section::Cache => return self.cache_files(),
+        };
+        names
+            .iter()
+            .map(|name| (name.to_string(), self.path(section, name)))
+            .filter(|(_, path)| path.is_file())
+            .collect()
+    }
+
+    /// Every file of the cache but locks, files being written and secret
+    /// store values
+    fn cache_files(&self) -> Vec<(String, PathBuf)> {
+        let mut files: Vec<(String, PathBuf)> = WalkDir::new(&self.cache_dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                !name.ends_with(".lock") && !name.ends_with(".tmp")
+            })
+            .filter_map(|entry| {
+                let name = entry.path().strip_prefix(&self.cache_dir).ok()?;
+                let name = name.to_str()?.replace(std::path::MAIN_SEPARATOR, "/");
+                Some((name, entry.into_path()))
+            })
+            .filter(|(name, _)| Self::bundles(Section::Cache, name))
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// Write and read bundles of the state in some directories
+#[derive(Debug, Clone)]
+pub struct StateBundle {
+    dirs: StateDirs,
+}
+
+impl StateBundle {
+    pub fn new(dirs: StateDirs) -> Self {
+        Self { dirs }
+    }
+
+    /// Bundle `sections` of the state into `bundle`
+    pub fn export(&self, bundle: &Path, sections: &[Section]) -> Result<Manifest> {
+        let mut sections = sections.to_vec();
+        sections.sort();
+        sections.dedup();
+        let files: Vec<(Section, String, PathBuf)> = sections
+            .iter()
+            .flat_map(|&section| {
+                self.dirs
+                    .files(section)
+                    .into_iter()
+                    .map(move |(name, path)| (section, name, path))
+            })
+            .collect();
+        let manifest = Manifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            cuenv_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            sections,
+            files: files.len(),
+        };
+
+        let dir = match bundle.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let temp = tempfile::NamedTempFile::new_in(dir)
+            .map_err(|e| Error::file_system(dir, "create bundle", e))?;
+        let write = |temp: &File| -> io::Result<()> {
+            let encoder = zstd::Encoder::new(temp, 0)?;
+            let mut archive = tar::Builder::new(encoder);
+            let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(manifest_json.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(manifest.created_at);
+            header.set_cksum();
+            archive.append_data(&mut header, MANIFEST, manifest_json.as_slice())?;
+            for (section, name, path) in &files {
+                archive.append_path_with_name(path, format!("{section}/{name}"))?;
+            }
+            archive.into_inner()?.finish()?;
+            Ok(())
+        };
+        write(temp.as_file()).map_err(|e| Error::file_system(bundle, "write bundle", e))?;
+        temp.persist(bundle)
+            .map_err(|e| Error::file_system(bundle, "write bundle", e.error))?;
+        Ok(manifest)
+    }
+
+    /// Unpack `bundle` into the state directories
+    ///
+    /// Every file is checked before any is unpacked, so a bundle holding a
+    /// file it should not changes nothing.
+    pub fn import(&self, bundle: &Path) -> Result<Manifest> {
+        let manifest = self.unpack(bundle, false)?;
+        self.unpack(bundle, true)?;
+        Ok(manifest)
+    }
+
+    /// Read the manifest and check the files of `bundle`, unpacking them
+    /// with `write`
+    fn unpack(&self, bundle: &Path, write: bool) -> Result<Manifest> {
+        let file = File::open(bundle).map_err(|e| Error::file_system(bundle, "open bundle", e))?;
+        let decoder =
+            zstd::Decoder::new(file).map_err(|e| Error::file_system(bundle, "read bundle", e))?;
+        let mut archive = tar::Archive::new(decoder);
+        let mut entries = archive
+            .entries()
+            .map_err(|e| Error::file_system(bundle, "read bundle", e))?;
+
+        let manifest = entries
+            .next()
+            .transpose()
+            .map_err(|e| Error::file_system(bundle, "read bundle", e))?
+            .filter(|entry| entry.path().is_ok_and(|path| path == Path::new(MANIFEST)))
+            .ok_or_else(|| invalid(bundle, "it has no manifest"))?;
+        let manifest: Manifest = serde_json::from_reader(manifest).map_err(|e| Error::Json {
+            message: format!("invalid manifest in bundle {}", bundle.display()),
+            source: e,
+        })?;
+        if manifest.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(invalid(
+                bundle,
+                &format!(
+                    "it has format version {}, but this cuenv only reads version {BUNDLE_FORMAT_VERSION}",
+                    manifest.format_version
+                ),
+            ));
+        }
+
+        for entry in entries {
+            let mut entry = entry.map_err(|e| Error::file_system(bundle, "read bundle", e))?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry
+                .path()
+                .map_err(|e| Error::file_system(bundle, "read bundle", e))?
+                .into_owned();
+            let (section, name) = split_entry(&path)
+                .filter(|(section, name)| StateDirs::bundles(*section, name))
+                .ok_or_else(|| {
+                    invalid(
+                        bundle,
+                        &format!("it holds {}, which is never bundled", path.display()),
+                    )
+                })?;
+            if !write {
+                continue;
+            }
+            let target = self.dirs.path(section, &name);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| Error::file_system(parent, "create state directory", e))?;
+            }
+            match (section, name.as_str()) {
+                (Section::Approvals, ALLOWED | DENIED) => {
+                    merge_lines(&target, &read_text(&mut entry, bundle)?)?
+                }
+                (Section::Approvals, _) => merge_rules(&target, &read_text(&mut entry, bundle)?)?,
+                _ => {
+                    entry
+                        .unpack(&target)
+                        .map_err(|e| Error::file_system(&target, "unpack bundle file", e))?;
+                }
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn invalid(bundle: &Path, reason: &str) -> Error {
+    Error::configuration(format!(
+        "{} is not a valid cuenv state bundle: {reason}",
+        bundle.display()
+    ))
+}
+
+/// The section and name of a bundle entry, refusing names that would
+/// escape the section's directory
+fn split_entry(path: &Path) -> Option<(Section, String)> {
+    let mut components = path.components();
+    let section = match components.next()? {
+        Component::Normal(section) => Section::from_name(section.to_str()?)?,
+        _ => return None,
+    };
+    let parts: Vec<&str> = components
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    if parts.is_empty() {
+        return None;
+    }
+    Some((section, parts.join("/")))
+}
+
+fn read_text(entry: &mut impl Read, bundle: &Path) -> Result<String> {
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .map_err(|e| Error::file_system(bundle, "read bundle", e))?;
+    Ok(text)
+}
+
+/// Add the lines of `imported` that `path` does not have yet
+fn merge_lines(path: &Path, imported: &str) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::file_system(path, "read approvals", e)),
+    };
+    let mut lines: Vec<&str> = existing.lines().filter(|line| !line.is_empty()).collect();
+    for line in imported.lines().filter(|line| !line.is_empty()) {
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    crate::atomic_file::write_atomic_string(path, &merged)
+}
+
+/// Add the rules of `imported` for patterns `path` has no rule for yet
+fn merge_rules(path: &Path, imported: &str) -> Result<()> {
+    let imported: AllowRules = serde_json::from_str(imported).map_err(|e| Error::Json {
+        message: "invalid allow rules in bundle".to_string(),
+        source: e,
+    })?;
+    let mut rules = AllowRules::load(path)?;
+    for rule in imported.rules {
+        if !rules
+            .rules
+            .iter()
+            .any(|existing| existing.pattern == rule.pattern)
+        {
+            rules.add(&rule.pattern, rule.action)?;
+        }
+    }
+    rules.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allow_rules::RuleAction;
+    use tempfile::TempDir;
+
+    fn dirs(root: &Path) -> StateDirs {
+        StateDirs {
+            config_dir: root.join("config"),
+            data_dir: root.join("data"),
+            cache_dir: root.join("cache"),
+        }
+    }
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let source = TempDir::new().unwrap();
+        let source_dirs = dirs(source.path());
+        fs::create_dir_all(source_dirs.cache_dir.join("environments")).unwrap();
+        fs::create_dir_all(&source_dirs.data_dir).unwrap();
+        fs::write(source_dirs.data_dir.join(ALLOWED), "/work/app:abc\n").unwrap();
+        fs::write(source_dirs.cache_dir.join("environments/app.json"), "{}").unwrap();
+        fs::write(source_dirs.cache_dir.join("index.lock"), "").unwrap();
+        fs::create_dir_all(source_dirs.cache_dir.join(SECRET_IMPORTS)).unwrap();
+        fs::write(source_dirs.cache_dir.join("imports/ssm.json"), "{}").unwrap();
+        let mut rules = AllowRules::default();
+        rules.add("/work/**", RuleAction::Allow).unwrap();
+        rules
+            .save(&source_dirs.config_dir.join(ALLOW_RULES))
+            .unwrap();
+
+        let bundle = source.path().join("state.tar.zst");
+        let exported = StateBundle::new(source_dirs)
+            .export(&bundle, &Section::ALL)
+            .unwrap();
+        assert_eq!(exported.files, 3);
+
+        let target = TempDir::new().unwrap();
+        let target_dirs = dirs(target.path());
+        fs::create_dir_all(&target_dirs.data_dir).unwrap();
+        fs::write(target_dirs.data_dir.join(ALLOWED), "/home/me/dotfiles\n").unwrap();
+        let imported = StateBundle::new(target_dirs.clone())
+            .import(&bundle)
+            .unwrap();
+
+        assert_eq!(imported, exported);
+        assert_eq!(
+            fs::read_to_string(target_dirs.data_dir.join(ALLOWED)).unwrap(),
+            "/home/me/dotfiles\n/work/app:abc\n"
+        );
+        assert!(target_dirs
+            .cache_dir
+            .join("environments/app.json")
+            .is_file());
+        assert!(!target_dirs.cache_dir.join("index.lock").exists());
+        assert!(!target_dirs.cache_dir.join(SECRET_IMPORTS).exists());
+        let rules = AllowRules::load(&target_dirs.config_dir.join(ALLOW_RULES)).unwrap();
+        assert_eq!(rules.rules.len(), 1);
+    }
+
+    /// A bundle holding `files`, by their names in it
+    fn bundle_of(path: &Path, files: &[(&str, &str)]) {
+        let encoder = zstd::Encoder::new(File::create(path).unwrap(), 0).unwrap();
+        let mut archive = tar::Builder::new(encoder);
+        let manifest = Manifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            cuenv_version: "0.0.0".to_string(),
+            created_at: 0,
+            sections: Section::ALL.to_vec(),
+            files: files.len(),
+        };
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+        for (name, content) in [(MANIFEST, manifest.as_slice())].into_iter().chain(
+            files
+                .iter()
+                .map(|(name, content)| (*name, content.as_bytes())),
+        ) {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive.append_data(&mut header, name, content).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_import_refuses_files_never_bundled() {
+        let root = TempDir::new().unwrap();
+        let dirs = dirs(root.path());
+        let bundle = root.path().join("state.tar.zst");
+
+        for name in [
+            "overrides/credentials.json",
+            "approvals/allow-rules.json.bak",
+            "approvals/credentials.json",
+            "cache/imports/ssm.json",
+        ] {
+            bundle_of(
+                &bundle,
+                &[("overrides/config.json", "{}"), (name, "{\"token\":\"x\"}")],
+            );
+            let err = StateBundle::new(dirs.clone()).import(&bundle).unwrap_err();
+            assert!(err.to_string().contains("never bundled"), "{name}: {err}");
+        }
+        // Nothing is unpacked from a refused bundle
+        assert!(!dirs.config_dir.exists());
+        assert!(!dirs.data_dir.exists());
+    }
+
+    #[test]
+    fn test_split_entry_refuses_escapes() {
+        assert_eq!(
+            split_entry(Path::new("cache/environments/app.json")),
+            Some((Section::Cache, "environments/app.json".to_string()))
+        );
+        assert_eq!(split_entry(Path::new("cache/../../etc/passwd")), None);
+        assert_eq!(split_entry(Path::new("credentials/token")), None);
+        assert_eq!(split_entry(Path::new("cache")), None);
+    }
+}
@@ -0,0 +1,467 @@
+//! Detection and removal of stale cuenv state
+//!
+//! State goes stale in a few ways: the per-directory state of a directory
+//! that was deleted or has not been visited for a while, the output files of
+//! async shell hooks whose shell died without cleaning up, and hook status
+//! records still claiming hooks run after their supervisor is gone. A
+//! [`PrunePolicy`] picks which of these count; [`StatePruner`] finds and
+//! removes them. State whose supervisor lock is held is never removed.
+
+use crate::hooks_status::HooksStatus;
+use crate::paths::{get_hooks_status_file_path, get_state_root};
+use cuenv_core::{Error, Result};
+use fs2::FileExt;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default age after which state of a directory not visited is stale
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Default minimum time between two automatic prunes
+pub const DEFAULT_AUTO_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// File in a state directory naming the directory it belongs to
+pub const STATE_ORIGIN_FILE: &str = "directory";
+
+/// Lock held by a directory's hook supervisor while it runs
+const SUPERVISOR_LOCK: &str = "supervisor.lock";
+/// File in the state root recording when state was last pruned automatically
+const AUTO_PRUNE_STAMP: &str = ".last-prune";
+/// Time a status record without a supervisor is left alone after its last
+/// update, so a supervisor that is just starting is not mistaken for a dead one
+const ORPHAN_GRACE: Duration = Duration::from_secs(60);
+
+/// Which kinds of state count as stale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunePolicy {
+    /// State of directories that no longer exist
+    pub deleted_directories: bool,
+    /// State not written to for this long; `None` keeps it however old
+    pub max_age: Option<Duration>,
+    /// Async hook output left by shells that are no longer running
+    pub dead_sessions: bool,
+    /// Hook status records whose hooks and supervisor are gone
+    pub orphaned_hooks: bool,
+}
+
+impl Default for PrunePolicy {
+    fn default() -> Self {
+        Self {
+            deleted_directories: true,
+            max_age: Some(DEFAULT_MAX_AGE),
+            dead_sessions: true,
+            orphaned_hooks: true,
+        }
+    }
+}
+
+/// Why a piece of state is stale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleReason {
+    /// The directory the state belongs to was deleted
+    DeletedDirectory(PathBuf),
+    /// The state was last written this long ago
+    Unused(Duration),
+    /// The shell with this PID is no longer running
+    DeadSession(u32),
+    /// The status claims hooks run, but neither they nor the supervisor do
+    OrphanedHooks,
+}
+
+/// One piece of stale state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleState {
+    /// File or state directory to remove
+    pub path: PathBuf,
+    pub reason: StaleReason,
+}
+
+impl fmt::Display for StaleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match &self.reason {
+            StaleReason::DeletedDirectory(dir) => {
+                write!(f, "{path}: state of deleted directory {}", dir.display())
+            }
+            StaleReason::Unused(age) => {
+                write!(f, "{path}: unused for {} days", age.as_secs() / 86_400)
+            }
+            StaleReason::DeadSession(pid) => write!(f, "{path}: shell {pid} exited"),
+            StaleReason::OrphanedHooks => write!(f, "{path}: hooks no longer running"),
+        }
+    }
+}
+
+/// Finds and removes stale state
+#[derive(Debug, Clone)]
+pub struct StatePruner {
+    state_root: PathBuf,
+    session_dirs: Vec<PathBuf>,
+    legacy_status_file: PathBuf,
+    policy: PrunePolicy,
+}
+
+impl StatePruner {
+    pub fn new(
+        state_root: PathBuf,
+        session_dirs: Vec<PathBuf>,
+        legacy_status_file: PathBuf,
+        policy: PrunePolicy,
+    ) -> Self {
+        Self {
+            state_root,
+            session_dirs,
+            legacy_status_file,
+            policy,
+        }
+    }
+
+    /// The state of the current user
+    ///
+    /// Async hooks write to `$XDG_RUNTIME_DIR/cuenv`, or to a directory of
+    /// their own in the temporary directory.
+    pub fn for_user(policy: PrunePolicy) -> Self {
+        let temp_dir = std::env::var_os("TMPDIR")
+            .filter(|dir| !dir.is_empty())
+            .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| PathBuf::from(dir).join("cuenv"));
+        Self::new(
+            get_state_root(),
+            runtime_dir.into_iter().chain([temp_dir]).collect(),
+            get_hooks_status_file_path(),
+            policy,
+        )
+    }
+
+    pub fn state_root(&self) -> &Path {
+        &self.state_root
+    }
+
+    /// Everything stale under the policy, in path order
+    pub fn find(&self) -> Vec<StaleState> {
+        let mut stale = self.stale_state_dirs();
+        if self.policy.orphaned_hooks && orphaned_status(&self.legacy_status_file) {
+            stale.push(StaleState {
+                path: self.legacy_status_file.clone(),
+                reason: StaleReason::OrphanedHooks,
+            });
+        }
+        if self.policy.dead_sessions {
+            stale.extend(self.dead_sessions());
+        }
+        stale.sort_by(|a, b| a.path.cmp(&b.path));
+        stale
+    }
+
+    /// Remove `stale`, returning how many were removed
+    ///
+    /// State that disappeared in the meantime or whose supervisor lock is
+    /// held is skipped.
+    pub fn prune(&self, stale: &[StaleState]) -> Result<usize> {
+        self.prune_with(stale, |_| {})
+    }
+
+    /// [`StatePruner::prune`], calling `before_remove` on each state
+    /// directory about to go while its supervisor lock is held
+    pub fn prune_with(
+        &self,
+        stale: &[StaleState],
+        mut before_remove: impl FnMut(&StaleState),
+    ) -> Result<usize> {
+        let mut removed = 0;
+        for state in stale {
+            let done = if state.path.is_dir() {
+                remove_unless_locked(&state.path, || before_remove(state))?
+            } else {
+                remove_file(&state.path)?
+            };
+            removed += usize::from(done);
+        }
+        Ok(removed)
+    }
+
+    /// Claim the next automatic prune if `interval` has passed since the last
+    ///
+    /// Returns `true` at most once per interval, however many shells ask at
+    /// the same time.
+    pub fn claim_auto_prune(&self, interval: Duration) -> Result<bool> {
+        fs::create_dir_all(&self.state_root)
+            .map_err(|e| Error::file_system(&self.state_root, "create state directory", e))?;
+        let path = self.state_root.join(AUTO_PRUNE_STAMP);
+        let mut stamp = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| Error::file_system(&path, "open prune stamp", e))?;
+        if stamp.try_lock_exclusive().is_err() {
+            return Ok(false);
+        }
+
+        let mut content = String::new();
+        stamp
+            .read_to_string(&mut content)
+            .map_err(|e| Error::file_system(&path, "read prune stamp", e))?;
+        let now = now_secs();
+        let due = content
+            .trim()
+            .parse::<u64>()
+            .map_or(true, |last| now.saturating_sub(last) >= interval.as_secs());
+        if due {
+            stamp
+                .set_len(0)
+                .and_then(|()| stamp.rewind())
+                .and_then(|()| write!(stamp, "{now}"))
+                .map_err(|e| Error::file_system(&path, "write prune stamp", e))?;
+        }
+        Ok(due)
+    }
+
+    fn stale_state_dirs(&self) -> Vec<StaleState> {
+        let Ok(entries) = fs::read_dir(&self.state_root) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|dir| dir.is_dir())
+            .filter_map(|dir| self.classify_state_dir(dir))
+            .collect()
+    }
+
+    fn classify_state_dir(&self, dir: PathBuf) -> Option<StaleState> {
+        if self.policy.deleted_directories {
+            if let Some(origin) = state_origin(&dir).filter(|origin| !origin.exists()) {
+                return Some(StaleState {
+                    path: dir,
+                    reason: StaleReason::DeletedDirectory(origin),
+                });
+            }
+        }
+        if let Some(max_age) = self.policy.max_age {
+            let age = last_modified(&dir).and_then(|time| time.elapsed().ok());
+            if let Some(age) = age.filter(|age| *age >= max_age) {
+                return Some(StaleState {
+                    path: dir,
+                    reason: StaleReason::Unused(age),
+                });
+            }
+        }
+        let status_file = dir.join("hooks_status.json");
+        (self.policy.orphaned_hooks && orphaned_status(&status_file)).then_some(StaleState {
+            path: status_file,
+            reason: StaleReason::OrphanedHooks,
+        })
+    }
+
+    /// Output files and directories of the async zsh and fish hooks, named
+    /// `cuenv-async-<pid>.<suffix>` after their shell
+    fn dead_sessions(&self) -> Vec<StaleState> {
+        self.session_dirs
+            .iter()
+            .filter_map(|dir| Some((dir, fs::read_dir(dir).ok()?)))
+            .flat_map(|(dir, entries)| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                    .filter_map(move |name| {
+                        let (pid, _) = name.strip_prefix("cuenv-async-")?.split_once('.')?;
+                        let pid = pid.parse::<u32>().ok()?;
+                        (!process_alive(pid)).then(|| StaleState {
+                            path: dir.join(&name),
+                            reason: StaleReason::DeadSession(pid),
+                        })
+                    })
+            })
+            .collect()
+    }
+}
+
+/// The directory a state directory belongs to, if recorded
+fn state_origin(state_dir: &Path) -> Option<PathBuf> {
+    if let Ok(origin) = fs::read_to_string(state_dir.join(STATE_ORIGIN_FILE)) {
+        return Some(PathBuf::from(origin.trim_end_matches('\n')));
+    }
+    read_status(&state_dir.join("hooks_status.json"))?
+        .directory
+        .map(PathBuf::from)
+}
+
+fn read_status(path: &Path) -> Option<HooksStatus> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn orphaned_status(path: &Path) -> bool {
+    read_status(path).is_some_and(|status| {
+        status.is_orphaned()
+            && crate::hooks_status::calculate_elapsed(status.last_update) >= ORPHAN_GRACE
+    })
+}
+
+/// Newest modification time of the directory and the files in it
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    let own = fs::metadata(dir).ok()?.modified().ok()?;
+    let files = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok());
+    Some(files.fold(own, SystemTime::max))
+}
+
+/// Remove `dir` while holding its supervisor lock; `false` when it is in use
+fn remove_unless_locked(dir: &Path, before_remove: impl FnOnce()) -> Result<bool> {
+    let lock_path = dir.join(SUPERVISOR_LOCK);
+    let lock = match OpenOptions::new().write(true).open(&lock_path) {
+        Ok(lock) => Some(lock),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(Error::file_system(lock_path, "open supervisor lock", e)),
+    };
+    if lock
+        .as_ref()
+        .is_some_and(|lock| lock.try_lock_exclusive().is_err())
+    {
+        return Ok(false);
+    }
+
+    before_remove();
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(Error::file_system(dir, "remove stale state directory", e)),
+    }
+}
+
+fn remove_file(path: &Path) -> Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(Error::file_system(path, "remove stale state", e)),
+    }
+}
+
+/// Whether a process with `pid` exists
+///
+/// Processes of other users count as running. Where this cannot be checked
+/// every process is assumed to run, so nothing is pruned for being dead.
+pub(crate) fn process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        use nix::errno::Errno;
+        use nix::sys::signal::kill;
+        use nix::unistd::Pid;
+
+        let Ok(pid) = i32::try_from(pid) else {
+            return false;
+        };
+        matches!(kill(Pid::from_raw(pid), None), Ok(()) | Err(Errno::EPERM))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks_status::{HookState, HookStatus};
+    use tempfile::TempDir;
+
+    fn pruner(root: &TempDir) -> StatePruner {
+        StatePruner::new(
+            root.path().join("state"),
+            vec![root.path().join("tmp")],
+            root.path().join("hooks-status.json"),
+            PrunePolicy::default(),
+        )
+    }
+
+    /// PID of a process that has exited
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_finds_deleted_directories_dead_sessions_and_orphans() {
+        let root = TempDir::new().unwrap();
+        let pruner = pruner(&root);
+
+        let deleted = pruner.state_root().join("deleted");
+        fs::create_dir_all(&deleted).unwrap();
+        fs::write(deleted.join(STATE_ORIGIN_FILE), "/nonexistent/project").unwrap();
+        let live = pruner.state_root().join("live");
+        fs::create_dir_all(&live).unwrap();
+        fs::write(live.join(STATE_ORIGIN_FILE), root.path().to_str().unwrap()).unwrap();
+
+        let pid = dead_pid();
+        let mut status = HooksStatus {
+            supervisor_pid: Some(pid),
+            last_update: 0,
+            ..HooksStatus::default()
+        };
+        status.hooks.insert(
+            "install".to_string(),
+            HookStatus {
+                name: "install".to_string(),
+                pid: Some(pid),
+                start_time: 0,
+                status: HookState::Running,
+                duration: None,
+                error: None,
+            },
+        );
+        let status_file = live.join("hooks_status.json");
+        fs::write(&status_file, serde_json::to_string(&status).unwrap()).unwrap();
+
+        let sessions = root.path().join("tmp");
+        fs::create_dir_all(&sessions).unwrap();
+        fs::write(sessions.join(format!("cuenv-async-{pid}.zsh")), "").unwrap();
+        // The directory a shell without a runtime directory made for itself
+        let private = sessions.join(format!("cuenv-async-{pid}.Ab12Cd"));
+        fs::create_dir_all(&private).unwrap();
+        fs::write(private.join(format!("cuenv-async-{pid}.fish")), "").unwrap();
+        let own = format!("cuenv-async-{}.fish", std::process::id());
+        fs::write(sessions.join(&own), "").unwrap();
+
+        let stale = pruner.find();
+        let reasons: Vec<_> = stale.iter().map(|s| s.reason.clone()).collect();
+        assert_eq!(
+            reasons,
+            [
+                StaleReason::DeletedDirectory(PathBuf::from("/nonexistent/project")),
+                StaleReason::OrphanedHooks,
+                StaleReason::DeadSession(pid),
+                StaleReason::DeadSession(pid),
+            ]
+        );
+
+        assert_eq!(pruner.prune(&stale).unwrap(), 4);
+        assert!(!deleted.exists() && !status_file.exists() && !private.exists());
+        assert!(live.exists() && sessions.join(own).exists());
+    }
+
+    #[test]
+    fn test_auto_prune_is_claimed_once_per_interval() {
+        let root = TempDir::new().unwrap();
+        let pruner = pruner(&root);
+
+        assert!(pruner.claim_auto_prune(Duration::from_secs(60)).unwrap());
+        assert!(!pruner.claim_auto_prune(Duration::from_secs(60)).unwrap());
+        assert!(pruner.claim_auto_prune(Duration::ZERO).unwrap());
+    }
+}
@@ -80,6 +80,60 @@ impl SyncEnv {
     }
 }
 
+/// Environment changes that are undone when the guard is dropped
+///
+/// This and [`SyncEnv`] are the only places allowed to mutate the process
+/// environment; a test fails on direct `std::env` mutation anywhere else.
+/// Changes go through [`SyncEnv`], so they are serialized with every other
+/// access made by cuenv. Variables meant for a child process should be set
+/// on its `Command` instead.
+#[derive(Debug, Default)]
+pub struct ScopedEnv {
+    originals: Vec<(String, Option<String>)>,
+}
+
+impl ScopedEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` until the guard is dropped
+    pub fn set<K: AsRef<str>, V: AsRef<str>>(&mut self, key: K, value: V) -> Result<&mut Self> {
+        self.remember(key.as_ref())?;
+        SyncEnv::set_var(key, value)?;
+        Ok(self)
+    }
+
+    /// Remove `key` until the guard is dropped
+    pub fn remove<K: AsRef<str>>(&mut self, key: K) -> Result<&mut Self> {
+        self.remember(key.as_ref())?;
+        SyncEnv::remove_var(key)?;
+        Ok(self)
+    }
+
+    /// Record the value to restore, the first time `key` is changed
+    fn remember(&mut self, key: &str) -> Result<()> {
+        if !self.originals.iter().any(|(known, _)| known == key) {
+            self.originals.push((key.to_string(), SyncEnv::var(key)?));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ScopedEnv {
+    fn drop(&mut self) {
+        for (key, original) in self.originals.drain(..).rev() {
+            let restored = match original {
+                Some(value) => SyncEnv::set_var(&key, value),
+                None => SyncEnv::remove_var(&key),
+            };
+            if let Err(e) = restored {
+                tracing::warn!("Failed to restore {key}: {e}");
+            }
+        }
+    }
+}
+
 /// File-based lock for concurrent cuenv instances
 pub struct InstanceLock {
     file: Option<File>,
@@ -162,6 +216,7 @@ impl Drop for InstanceLock {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
     use std::thread;
     use std::time::Duration;
 
@@ -189,6 +244,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scoped_env_restores() -> Result<()> {
+        let set_key = format!("TEST_SCOPED_SET_{}", uuid::Uuid::new_v4());
+        let removed_key = format!("TEST_SCOPED_REMOVED_{}", uuid::Uuid::new_v4());
+        SyncEnv::set_var(&removed_key, "kept")?;
+
+        {
+            let mut scope = ScopedEnv::new();
+            scope.set(&set_key, "first")?.set(&set_key, "second")?;
+            scope.remove(&removed_key)?;
+            assert_eq!(SyncEnv::var(&set_key)?, Some("second".to_string()));
+            assert_eq!(SyncEnv::var(&removed_key)?, None);
+        }
+
+        assert_eq!(SyncEnv::var(&set_key)?, None);
+        assert_eq!(SyncEnv::var(&removed_key)?, Some("kept".to_string()));
+        SyncEnv::remove_var(&removed_key)
+    }
+
+    /// Mutating the process environment is unsound while other threads may
+    /// read it, so all mutation has to go through this module
+    #[test]
+    fn test_no_direct_env_mutation() {
+        let crates = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mutation = regex::Regex::new(r"env::(set_var|remove_var)\(").unwrap();
+
+        let offenders: Vec<String> = walkdir::WalkDir::new(&crates)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != "target")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "rs"))
+            .filter(|entry| !entry.path().ends_with("utils/src/sync/env.rs"))
+            .flat_map(|entry| {
+                let text = fs::read_to_string(entry.path()).unwrap_or_default();
+                text.lines()
+                    .enumerate()
+                    .filter(|(_, line)| mutation.is_match(line))
+                    .map(|(index, _)| format!("{}:{}", entry.path().display(), index + 1))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert!(
+            offenders.is_empty(),
+            "Use SyncEnv or ScopedEnv instead of mutating the environment directly:\n{}",
+            offenders.join("\n")
+        );
+    }
+
     #[test]
     fn test_instance_lock() -> Result<()> {
         // First lock should succeed
@@ -7,8 +7,9 @@
 //! ## Key Components
 //!
 //! - **`env`**: Provides thread-safe and process-safe mechanisms for
-//!   manipulating environment variables.
+//!   manipulating environment variables, and `ScopedEnv` for changes that
+//!   are undone at the end of a scope.
 
 pub mod env;
 
-pub use env::SyncEnv;
+pub use env::{ScopedEnv, SyncEnv};
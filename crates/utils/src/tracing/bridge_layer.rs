@@ -132,6 +132,7 @@ impl EventBridgeLayer {
             task_name,
             task_id,
             error,
+            exit_status: None,
         }))
     }
 
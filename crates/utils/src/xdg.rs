@@ -1,10 +1,60 @@
+use cuenv_core::{CUENV_CACHE_DIR_VAR, CUENV_STATE_DIR_VAR};
 use std::env;
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Directories a project relocates cuenv's cache and state to, set from the
+/// `cacheDir` and `stateDir` config settings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirOverrides {
+    pub cache_dir: Option<PathBuf>,
+    pub state_dir: Option<PathBuf>,
+}
+
+static PROJECT_OVERRIDES: RwLock<DirOverrides> = RwLock::new(DirOverrides {
+    cache_dir: None,
+    state_dir: None,
+});
+
+/// The variable, when set and non-empty, wins over the project's setting
+fn pick_relocation(var: Option<OsString>, configured: Option<PathBuf>) -> Option<PathBuf> {
+    var.filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or(configured)
+}
 
 /// XDG Base Directory paths for cuenv
+///
+/// The cache and state directories can be relocated, by the
+/// `CUENV_CACHE_DIR` and `CUENV_STATE_DIR` variables or else by the
+/// project's configuration.
 pub struct XdgPaths;
 
 impl XdgPaths {
+    /// Relocate the cache and state directories for the rest of the process
+    pub fn set_project_overrides(overrides: DirOverrides) {
+        if let Ok(mut current) = PROJECT_OVERRIDES.write() {
+            *current = overrides;
+        }
+    }
+
+    /// Directory `var` or else the project's configuration relocates to
+    fn relocated(var: &str, project: fn(&DirOverrides) -> Option<PathBuf>) -> Option<PathBuf> {
+        let configured = PROJECT_OVERRIDES.read().ok().and_then(|o| project(&o));
+        pick_relocation(env::var_os(var), configured)
+    }
+
+    /// Relocated cache directory, if any
+    pub fn cache_dir_override() -> Option<PathBuf> {
+        Self::relocated(CUENV_CACHE_DIR_VAR, |o| o.cache_dir.clone())
+    }
+
+    /// Relocated state directory, if any
+    pub fn state_dir_override() -> Option<PathBuf> {
+        Self::relocated(CUENV_STATE_DIR_VAR, |o| o.state_dir.clone())
+    }
+
     /// Get XDG_CONFIG_HOME/cuenv or fallback
     pub fn config_dir() -> PathBuf {
         env::var("XDG_CONFIG_HOME")
@@ -29,8 +79,13 @@ impl XdgPaths {
             .join("cuenv")
     }
 
-    /// Get XDG_STATE_HOME/cuenv or fallback
+    /// Get the relocated state directory, or XDG_STATE_HOME/cuenv or fallback
     pub fn state_dir() -> PathBuf {
+        Self::state_dir_override().unwrap_or_else(Self::default_state_dir)
+    }
+
+    /// Get XDG_STATE_HOME/cuenv or fallback, ignoring any relocation
+    pub fn default_state_dir() -> PathBuf {
         env::var("XDG_STATE_HOME")
             .map(PathBuf::from)
             .unwrap_or_else(|_| {
@@ -41,8 +96,13 @@ impl XdgPaths {
             .join("cuenv")
     }
 
-    /// Get XDG_CACHE_HOME/cuenv or fallback
+    /// Get the relocated cache directory, or XDG_CACHE_HOME/cuenv or fallback
     pub fn cache_dir() -> PathBuf {
+        Self::cache_dir_override().unwrap_or_else(Self::default_cache_dir)
+    }
+
+    /// Get XDG_CACHE_HOME/cuenv or fallback, ignoring any relocation
+    pub fn default_cache_dir() -> PathBuf {
         env::var("XDG_CACHE_HOME")
             .map(PathBuf::from)
             .unwrap_or_else(|_| {
@@ -58,6 +118,11 @@ impl XdgPaths {
         Self::data_dir().join("allow")
     }
 
+    /// Get the glob-pattern allow/deny rules file path
+    pub fn allow_rules_file() -> PathBuf {
+        Self::config_dir().join("allow-rules.json")
+    }
+
     /// Get the denied directories file path
     pub fn denied_file() -> PathBuf {
         Self::data_dir().join("deny")
@@ -79,8 +144,7 @@ impl XdgPaths {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sync::env::SyncEnv;
-    use std::env;
+    use crate::sync::env::{ScopedEnv, SyncEnv};
 
     #[test]
     fn test_xdg_paths_with_env() {
@@ -128,9 +192,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_relocation_precedence() {
+        let configured = Some(PathBuf::from("/project/.cuenv/cache"));
+        assert_eq!(pick_relocation(None, configured.clone()), configured);
+        assert_eq!(
+            pick_relocation(Some("/scratch/cuenv".into()), configured.clone()),
+            Some(PathBuf::from("/scratch/cuenv"))
+        );
+        // An empty variable counts as unset
+        assert_eq!(
+            pick_relocation(Some("".into()), configured.clone()),
+            configured
+        );
+        assert_eq!(pick_relocation(None, None), None);
+    }
+
     #[test]
     fn test_specific_paths() {
-        env::set_var("XDG_DATA_HOME", "/tmp/data");
+        let mut scope = ScopedEnv::new();
+        scope.set("XDG_DATA_HOME", "/tmp/data").unwrap();
 
         assert_eq!(
             XdgPaths::allowed_file(),
@@ -140,7 +221,5 @@ mod tests {
             XdgPaths::denied_file(),
             PathBuf::from("/tmp/data/cuenv/deny")
         );
-
-        env::remove_var("XDG_DATA_HOME");
     }
 }
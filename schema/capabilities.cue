@@ -2,4 +2,7 @@ package schema
 
 #Capability: {
 	commands?: [...string]
+
+	// Access granted to these commands under `cuenv exec --restrict`
+	security?: #Security
 }
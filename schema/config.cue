@@ -2,7 +2,15 @@ package schema
 
 #Config: {
 	// Task output format
-	outputFormat?: "tui" | "spinner" | "simple" | "tree"
+	outputFormat?: "tui" | "spinner" | "simple" | "tree" | "tmux" | "template" | "json"
+
+	// Format strings of the "template" output format, by event type
+	// (e.g. taskOutput: "[{task_name}] {output}")
+	outputTemplates?: [string]: string
+
+	// Fields of "json" output lines and the event paths they come from
+	// (e.g. message: ".output")
+	outputJson?: [string]: =~"^\\."
 	
 	// Cache configuration
 	cacheMode?: "off" | "read" | "read-write" | "write"
@@ -15,4 +23,37 @@ package schema
 	// Default environment settings
 	defaultEnvironment?: string
 	defaultCapabilities?: [...string]
+
+	// Keep exporting renamed variables under their old name
+	exportDeprecatedAliases?: bool | *true
+
+	// Fail task builds on ${VAR} references to undefined variables
+	strictVariables?: bool | *false
+
+	// Warn about or fail on drift from cuenv.lock
+	lockDrift?: "warn" | "fail" | *"warn"
+
+	// Most onEnter hooks running at once (default: one per CPU)
+	hookParallelism?: int & >=1
+
+	// Regular expressions of secrets to mask in all output
+	redactPatterns?: [...string]
+
+	// Security defaults for `cuenv exec --restrict`
+	security?: #Security
+
+	// Relocate cuenv's cache and per-directory state, relative to this file
+	cacheDir?: string
+	stateDir?: string
+
+	// Oldest and newest CUE versions the project supports, both inclusive;
+	// evaluating with another version fails (e.g. min: "v0.9.0", max: "v0.11")
+	cueVersion?: {
+		min?: =~"^v?[0-9]+(\\.[0-9]+){0,2}$"
+		max?: =~"^v?[0-9]+(\\.[0-9]+){0,2}$"
+	}
+
+	// Fail loading when env.cue has fields this schema doesn't declare,
+	// likely typos such as `dependecies`; needs the CUE bridge
+	strictSchema?: bool
 }
\ No newline at end of file
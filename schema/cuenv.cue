@@ -6,4 +6,15 @@ package schema
 	env?: #Env
 	hooks?: #Hooks
 	tasks: [string]: #Tasks | *{}
+	runConfigs?: [string]: #RunConfig
+}
+
+// A named bundle of environment, capabilities and tasks, run with
+// `cuenv run-config <name>`
+#RunConfig: {
+	description?: string
+	environment?: string
+	capabilities?: [...string]
+	// Run one after the other, stopping at the first failure
+	tasks: [string, ...string]
 }
@@ -1,17 +1,73 @@
 package schema
 
 #Environment: {
-	[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret
+	[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret | #Lazy | *#Deprecated
 }
 
 
 // #Env defines the structure for environment variable configuration
 #Env: {
 	// Environment variables - keys must be valid environment variable names
-	[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret
+	[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret | #Lazy | *#Deprecated
+
+	// Groups of variables, e.g. `aws: REGION: ...` for AWS_REGION
+	[=~"^[a-z][a-z0-9_]*$" & !="environment" & !="capabilities"]: #Group
+
+	// Variables imported from AWS SSM Parameter Store or Azure App Configuration
+	valueFrom?: #Import | [...#Import]
 
 	// Environment-specific overrides
 	environment?: [string]: {
-		[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret
+		// Environment whose variables and imports this one starts from;
+		// this environment's own values take precedence
+		inherits?: string
+		// Imported after the imports of every environment
+		valueFrom?: #Import | [...#Import]
+		[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret | #Lazy | *#Deprecated
+		[=~"^[a-z][a-z0-9_]*$" & !="inherits"]: #Group
 	}
 }
+
+// #Group nests variables that share a prefix. Members flatten to
+// <prefix><separator><NAME>; nested groups append their prefix and inherit
+// the settings of the groups around them.
+#Group: {
+	// Defaults to the group's name in upper case
+	prefix?: =~"^[A-Z][A-Z0-9_]*$"
+	// Joins prefixes and names, "_" by default
+	separator?: string
+	// Only load the members with this capability
+	capability?: string
+	// Secret provider of members declared as {ref: "..."}
+	provider?: string
+
+	[=~"^[A-Z][A-Z0-9_]*$"]: string | #Secret | #Lazy | #GroupRef | *#Deprecated
+	[=~"^[a-z][a-z0-9_]*$" & !="prefix" & !="separator" & !="capability" & !="provider"]: #Group
+}
+
+// #GroupRef is a secret of the group's provider, resolved through
+// `cuenv-secret-<provider>` like a #ProviderRef
+#GroupRef: {
+	ref!:  string
+	lazy?: bool
+}
+
+// #Deprecated marks a variable as deprecated or renamed, and may record its
+// owner and tags as the @deprecated, @owner and @tag attributes do. Renamed
+// variables without a value mirror the variable they were renamed to. It is
+// the default disjunct so a bare `{renamedTo: ...}` is not ambiguous with the open #Secret.
+#Deprecated: {
+	value?:      string | #Secret
+	deprecated?: string
+	renamedTo?:  =~"^[A-Z][A-Z0-9_]*$"
+	owner?:      string
+	tags?: [...string]
+}
+
+// #Lazy is a secret reference kept out of the shell, resolved only for tasks
+// that reference it and commands run through cuenv. A #Secret can also set
+// `lazy: true` directly.
+#Lazy: {
+	value!: string
+	lazy!:  true
+}
@@ -5,17 +5,44 @@ package schema
 	onExit?: #Hook | [...#Hook]
 }
 
-#Hook: #ExecHook
+#Hook: #ExecHook | #FetchHook | #ComposeHook
 
 #ExecHook: {
+	// Referred to by other hooks' dependsOn
+	name?: string
+	// Hooks that must finish before this one starts
+	dependsOn?: [...string]
 	command!: string
 	args?: [...string]
 	dir?: string | *"."
 	inputs?: [...string]
 	source?: bool
 	preload?: bool | *false
+	fetch?: _|_
+	compose?: _|_
 
 	// To be extended
 	...
 }
 
+#FetchHook: {
+	fetch!: {
+		url!: string
+		sha256!: =~"^[0-9a-fA-F]{64}$"
+		extractTo!: string
+		proxy?: string
+	}
+	preload?: false
+}
+
+#ComposeHook: {
+	compose!: {
+		// Compose file; the runtime's default lookup when unset
+		file?: string
+		// Services to manage, all of the file's services when empty
+		services?: [...string]
+		upOnEnter?: bool | *true
+		downOnExit?: bool | *false
+	}
+	preload?: false
+}
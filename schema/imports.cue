@@ -0,0 +1,25 @@
+package schema
+
+// #Import pulls every parameter or key of a store into the environment when
+// it loads. Names are taken relative to the path or prefix, upper-cased, with
+// separators as "_": /myapp/dev/db/password imported from /myapp/dev/ is
+// DB_PASSWORD. Variables declared in CUE take precedence.
+#Import: #AwsSsmImport | #AzureAppConfigImport
+
+#AwsSsmImport: {
+	// Parameter Store path, read recursively with decryption
+	awsSsmPath: =~"^/"
+	region?:    string
+	// Seconds to cache the values for, 300 by default; 0 disables caching
+	ttl?: int & >=0
+}
+
+#AzureAppConfigImport: {
+	azureAppConfig: {
+		store: string
+		// Only keys starting with this, which is removed from their names
+		prefix?: string
+		label?:  string
+	}
+	ttl?: int & >=0
+}
@@ -0,0 +1,19 @@
+package schema
+
+// Rules of /etc/cuenv/policy.cue and .cuenv/policy.cue (package policy)
+// that every task must follow
+#Policy: {
+	// Hosts, and their subdomains, no task may list in allowedHosts
+	forbiddenHosts?: [...string]
+
+	// Directories whose tasks must set restrictDisk; relative to the
+	// project root
+	restrictDisk?: [...string]
+
+	// Shells tasks may not use, by name or path
+	bannedShells?: [...string]
+
+	// Longest timeout a task may set, e.g. "30m"; the timeout of tasks
+	// that set none
+	maxTimeout?: string
+}
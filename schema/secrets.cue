@@ -2,6 +2,8 @@ package schema
 
 #Secret: {
 	resolver: #ExecResolver
+	// Resolve only when a task or command needs it, never on shell load
+	lazy?: bool
 	...
 }
 
@@ -9,3 +11,10 @@ package schema
 	command: string
 	args: [...string]
 }
+
+// #ProviderRef is served by an external `cuenv-secret-<provider>` executable on PATH
+#ProviderRef: {
+	provider: string
+	ref:      string
+	value:    "cuenv-provider://\(provider)/\(ref)"
+}
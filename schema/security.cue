@@ -0,0 +1,20 @@
+package schema
+
+#Security: {
+	restrictDisk?:    bool
+	restrictNetwork?: bool
+	readOnlyPaths?: [...string]
+	readWritePaths?: [...string]
+	denyPaths?: [...string]
+	allowedHosts?: [...string]
+	inferFromInputsOutputs?: bool
+
+	// Syscall filtering (Linux only): built-in profile or path to a JSON profile
+	seccompProfile?: "default" | string
+	// Log blocked syscalls to the kernel audit log instead of failing them
+	seccompAudit?: bool
+
+	// What happens when the platform can't apply a restriction: fail the
+	// task, warn and run it anyway, or skip restrictions altogether
+	enforcement?: "off" | "warn" | "enforce"
+}
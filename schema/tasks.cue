@@ -11,14 +11,161 @@ package schema
 	args?: [...string]
 
 	dependencies?: [...string]
+	// Files the task reads; `pkg://projects:lib/src/**` names files of
+	// another package of the module
 	inputs?: [...string]
-	outputs?: [...string]
+	// Files the task writes, or values read from them that dependent tasks
+	// reference as ${tasks.<task>.outputs.<value>}, e.g.
+	// `outputs: version: string @fromFile("VERSION")`
+	outputs?: [...string] | {[string]: #OutputValue}
+
+	security?: #Security
+
+	cache?: bool | {
+		enabled: bool
+		env?:    #CacheEnv
+	}
+	// Reuse a failed run with the same cache key for this long, e.g. "10m";
+	// only successes are cached without it. Needs cache
+	cacheFailures?: string
+
+	// Expand undefined ${VAR} references to "" even with strictVariables
+	allowUndefined?: bool
+
+	// Run the task as another user (Unix only, requires root)
+	runAs?: {
+		user!:  string
+		group?: string
+	}
+
+	// File mode creation mask in octal, e.g. "027" (Unix only)
+	umask?: =~"^(0o)?[0-7]{1,4}$"
+	// Niceness from -20 (favoured) to 19; a priority class on Windows
+	nice?: int & >=-20 & <=19
+	// IO scheduling of the task process (Linux only); the idle class has
+	// no levels
+	ioPriority?: {
+		class!: "realtime" | "best-effort" | "idle"
+		level?: int & >=0 & <=7
+	}
+	// Services that must accept connections before the task starts, waited
+	// for in order
+	waitFor?: [...#WaitFor]
+
+	// Publish artifacts after a successful run, in order
+	publish?: [...#Publish]
+
+	// Other names to run the task by, e.g. `cuenv task t`
+	alias?: [...=~"^[^.:]+$"]
+
+	// Split the tests printed by testList, one per line, across parallel
+	// shards; each shard runs command with its tests appended
+	shardable?: bool
+	testList?:  string
+	shards?:    int & >0
+
+	// Ask before running, e.g. "This drops the production database. Continue?".
+	// Without a terminal the task only runs with --yes. List environments to
+	// ask only when one of them is loaded.
+	confirm?: string | {
+		message!: string
+		environments?: [...string]
+	}
+
+	// Read errors and warnings from the task's output
+	problemMatcher?: #ProblemMatcher | [...#ProblemMatcher]
+
+	// Run the task from `cuenv scheduler run` whenever the cron expression
+	// matches, in local time, e.g. "0 9 * * 1-5". catchUp decides how runs
+	// missed while the scheduler was stopped are made up; jitter delays each
+	// run by a fixed share of it.
+	schedule?: string | {
+		cron!:    string
+		catchUp?: *"once" | "all" | "skip"
+		jitter?:  string
+	}
+
+	// Metadata, also set by the @deprecated("..."), @owner("...") and
+	// @tag(...) attributes. A deprecated task still runs, with a warning.
+	deprecated?: string
+	owner?:      string
+	tags?: [...string]
+
+	// Labels to select tasks by with `cuenv task run --label <label>`
+	labels?: [...string]
+}
+
+// ProblemMatcher is a built-in matcher or a regular expression with a
+// `message` group and optional `file`, `line`, `column`, `severity` and
+// `code` groups
+#ProblemMatcher: "rustc" | "eslint" | "tsc" | "gcc" | "go" | {
+	pattern!:  string
+	severity?: *"error" | "warning" | "note"
+}
+
+// OutputValue is the type of a value a task outputs, read from the file
+// named by its @fromFile attribute
+#OutputValue: string | number | bool | {
+	fromFile!: string
+	type?:     *"string" | "int" | "number" | "bool"
 }
 
+// CacheEnv selects the environment variables that key a task's cache.
+// Variables the command references, and those an audited run was seen to
+// use, are always included.
+#CacheEnv: {
+	include?: [...string]
+	exclude?: [...string]
+	useSmartDefaults?: bool | *true
+	// Key on every variable of the loaded environment
+	envAll?: bool | *false
+}
+
+// Publish copies files (the task's outputs by default) to one destination
+#Publish: {
+	files?: [...string]
+} & ({
+	// Directory, relative to the task's working directory
+	dir!: string
+} | {
+	// OCI artifact pushed with the `oras` CLI
+	oci!: {
+		ref!:       string
+		mediaType?: string
+	}
+} | {
+	// Each file is sent to <url>/<relative path>
+	http!: {
+		url!:    string
+		method?: *"PUT" | "POST"
+		headers?: [string]: string
+		// Environment variable holding a bearer token
+		tokenEnv?: string
+	}
+})
+
+// WaitFor names one service that must be ready
+#WaitFor: {
+	// Defaults to "30s"
+	timeout?: string
+} & ({
+	// host:port accepting connections
+	tcp!: =~"^.+:[0-9]+$"
+} | {
+	// URL answering with a success status
+	http!: =~"^https?://"
+})
+
 // TaskGroup uses structure to determine execution mode:
 // - Array of tasks: Sequential execution (order preserved)
 // - Object of named tasks: Parallel execution with dependencies
 #TaskGroup: {
+	// Task run once before any member, and once after all members.
+	// Members are skipped if setup fails; teardown runs even when a member
+	// fails, as long as the group has started.
+	setup?:    string
+	teardown?: string
+
 	// Sequential: array of tasks executed in order
 	tasks: [...#Tasks] |
 	// Parallel with dependencies: object of named tasks  